@@ -0,0 +1,95 @@
+// Single vs batched agent output inserts
+//
+// Writes RECORDS agent outputs to a fresh on-disk database three ways:
+// one INSERT per record, explicit batches of EVENT_BUFFER_MAX_RECORDS, and
+// through the event buffer used by the stream handlers.
+//
+// Run with: cargo bench --bench event_inserts
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use claude_commander_lib::agent_runs_db::{
+    AgentOutputRecord, AgentRunsDB, BufferedEvent, EventQueryFilters, EVENT_BUFFER_MAX_RECORDS,
+};
+
+const RECORDS: usize = 5_000;
+
+fn records() -> Vec<AgentOutputRecord> {
+    (0..RECORDS)
+        .map(|i| AgentOutputRecord {
+            id: None,
+            agent_id: "bench-agent".to_string(),
+            pipeline_id: Some("bench-pipeline".to_string()),
+            output_type: "text".to_string(),
+            content: format!("output line {} {}", i, "x".repeat(120)),
+            metadata: None,
+            timestamp: i as i64,
+            turn_index: None,
+        })
+        .collect()
+}
+
+fn fresh_db(dir: &tempfile::TempDir, name: &str) -> Arc<AgentRunsDB> {
+    Arc::new(AgentRunsDB::new(dir.path().join(format!("{}.db", name))).unwrap())
+}
+
+async fn stored_count(db: &AgentRunsDB) -> usize {
+    db.query_agent_outputs(EventQueryFilters::default())
+        .await
+        .unwrap()
+        .len()
+}
+
+fn report(name: &str, elapsed: Duration, baseline: Duration) {
+    println!(
+        "{:<28} {:>10.1} ms {:>10.1} µs/record {:>8.1}x",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_secs_f64() * 1_000_000.0 / RECORDS as f64,
+        baseline.as_secs_f64() / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let records = records();
+
+    runtime.block_on(async {
+        let db = fresh_db(&dir, "single");
+        let start = Instant::now();
+        for record in &records {
+            db.insert_agent_output(record).await.unwrap();
+        }
+        let single = start.elapsed();
+        assert_eq!(stored_count(&db).await, RECORDS);
+
+        let db = fresh_db(&dir, "batched");
+        let start = Instant::now();
+        for chunk in records.chunks(EVENT_BUFFER_MAX_RECORDS) {
+            db.insert_agent_outputs(chunk).await.unwrap();
+        }
+        let batched = start.elapsed();
+        assert_eq!(stored_count(&db).await, RECORDS);
+
+        let db = fresh_db(&dir, "buffered");
+        let start = Instant::now();
+        for record in &records {
+            db.buffer_event(BufferedEvent::AgentOutput(record.clone()))
+                .await;
+        }
+        db.flush_event_buffer().await.unwrap();
+        let buffered = start.elapsed();
+        assert_eq!(stored_count(&db).await, RECORDS);
+
+        println!("{} agent output inserts", RECORDS);
+        report("single INSERT per record", single, single);
+        report(
+            &format!("batches of {}", EVENT_BUFFER_MAX_RECORDS),
+            batched,
+            single,
+        );
+        report("event buffer", buffered, single);
+    });
+}
@@ -0,0 +1,155 @@
+// Inter-agent messaging - Message bus for agents talking to each other
+//
+// Agents can't reach each other directly; the meta-agent or a pipeline's
+// orchestrator relays a message into the target agent's prompt queue on the
+// sender's behalf. Messages are counted per conversation (the pipeline both
+// agents belong to, or the agent pair for standalone agents) and capped so two
+// agents can't keep each other busy forever.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::commands::env_registry;
+use crate::types::AgentInfo;
+
+/// Messages allowed per conversation when AGENT_MESSAGE_LIMIT is unset or invalid
+pub const DEFAULT_AGENT_MESSAGE_LIMIT: u32 = 10;
+
+/// Messages allowed per pipeline (or per agent pair outside pipelines)
+pub fn agent_message_limit() -> u32 {
+    env_registry::AGENT_MESSAGE_LIMIT
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_AGENT_MESSAGE_LIMIT)
+}
+
+/// The pipeline a message between two agents belongs to.
+/// Agents in different pipelines can't message each other.
+pub fn message_pipeline(
+    from_pipeline: Option<&str>,
+    to_pipeline: Option<&str>,
+) -> Result<Option<String>, String> {
+    match (from_pipeline, to_pipeline) {
+        (Some(from), Some(to)) if from != to => {
+            Err("Agents belong to different pipelines and cannot message each other".to_string())
+        }
+        (Some(id), _) | (None, Some(id)) => Ok(Some(id.to_string())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Key the message cap is counted under
+fn conversation_key(pipeline_id: Option<&str>, from: &str, to: &str) -> String {
+    match pipeline_id {
+        Some(id) => format!("pipeline:{}", id),
+        None if from < to => format!("agents:{}:{}", from, to),
+        None => format!("agents:{}:{}", to, from),
+    }
+}
+
+/// How the sender is named in the relayed prompt
+pub fn sender_name(info: &AgentInfo) -> String {
+    let short_id: String = info.id.chars().take(8).collect();
+    match info.title.as_ref().or(info.label.as_ref()) {
+        Some(name) => format!("{} ({})", name, short_id),
+        None => format!("agent {}", short_id),
+    }
+}
+
+/// Prompt delivered to the target agent
+pub fn format_agent_message(sender: &str, text: &str) -> String {
+    format!(
+        "[Message from another agent: {}]\n\n{}\n\n(This message was relayed by the commander. Answer it in your response; your reply is not sent back automatically.)",
+        sender, text
+    )
+}
+
+/// Per-conversation message counts
+pub struct MessageBus {
+    counts: Mutex<HashMap<String, u32>>,
+}
+
+impl MessageBus {
+    pub fn new() -> Self {
+        Self {
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Claim a slot for a message, failing once the conversation hit `limit`.
+    /// Returns the conversation key for `release`.
+    pub fn reserve(
+        &self,
+        pipeline_id: Option<&str>,
+        from: &str,
+        to: &str,
+        limit: u32,
+    ) -> Result<String, String> {
+        let key = conversation_key(pipeline_id, from, to);
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        let count = counts.entry(key.clone()).or_default();
+        if *count >= limit {
+            return Err(format!(
+                "Message limit reached ({} messages{}); stop relaying and resolve the question yourself",
+                limit,
+                if pipeline_id.is_some() {
+                    " in this pipeline"
+                } else {
+                    " between these agents"
+                }
+            ));
+        }
+        *count += 1;
+        Ok(key)
+    }
+
+    /// Give back a slot for a message that couldn't be delivered
+    pub fn release(&self, key: &str) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = counts.get_mut(key) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+impl Default for MessageBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_is_per_pipeline() {
+        let bus = MessageBus::new();
+        bus.reserve(Some("p1"), "verifier", "builder", 2).unwrap();
+        bus.reserve(Some("p1"), "builder", "verifier", 2).unwrap();
+        let err = bus
+            .reserve(Some("p1"), "verifier", "builder", 2)
+            .unwrap_err();
+        assert!(err.contains("limit reached"));
+
+        // Other pipelines and standalone pairs have their own budget
+        bus.reserve(Some("p2"), "verifier", "builder", 2).unwrap();
+        let key = bus.reserve(None, "a", "b", 1).unwrap();
+        assert!(bus.reserve(None, "b", "a", 1).is_err());
+
+        // Undelivered messages don't count
+        bus.release(&key);
+        bus.reserve(None, "b", "a", 1).unwrap();
+    }
+
+    #[test]
+    fn test_agents_in_different_pipelines_cannot_talk() {
+        assert_eq!(
+            message_pipeline(Some("p1"), None).unwrap(),
+            Some("p1".to_string())
+        );
+        assert_eq!(message_pipeline(None, None).unwrap(), None);
+        assert!(message_pipeline(Some("p1"), Some("p2")).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+// ANSI escape codes in agent output
+//
+// Compilers and test runners run by agents color their output, and the
+// escape codes end up in tool results. The output buffer keeps each output as
+// it arrived (its metadata says whether it contained escape codes); what is
+// stored in the database and sent to the UI depends on AGENT_OUTPUT_ANSI:
+// - html: stored without escape codes; the `agent:output` payload carries the
+//   plain content plus `content_html`, with colors as `ansi-*` classes
+// - strip: stored and sent without escape codes
+// - keep: stored and sent as they arrived
+
+use std::borrow::Cow;
+
+use crate::commands::env_registry;
+use crate::types::AgentOutputEvent;
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// What happens to escape codes in stored and displayed output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiMode {
+    Html,
+    Strip,
+    Keep,
+}
+
+impl AnsiMode {
+    /// Read AGENT_OUTPUT_ANSI, defaulting to html
+    pub fn from_env() -> Self {
+        match env_registry::AGENT_OUTPUT_ANSI
+            .var()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "strip" => Self::Strip,
+            "keep" => Self::Keep,
+            _ => Self::Html,
+        }
+    }
+}
+
+pub fn contains_ansi(text: &str) -> bool {
+    text.contains(ESC)
+}
+
+/// A piece of text: plain characters or an escape sequence
+enum Segment<'a> {
+    Text(&'a str),
+    /// Parameters of a Select Graphic Rendition (color/style) sequence
+    Sgr(&'a str),
+    /// Any other escape sequence (cursor movement, titles, ...)
+    Other,
+}
+
+/// Split text into plain runs and escape sequences
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while let Some(esc) = rest.find(ESC) {
+        if esc > 0 {
+            out.push(Segment::Text(&rest[..esc]));
+        }
+        let after = &rest[esc + 1..];
+        let mut chars = after.char_indices();
+        let consumed = match chars.next() {
+            // CSI: parameters, then a final byte in @..~
+            Some((_, '[')) => match chars.find(|(_, c)| ('@'..='~').contains(c)) {
+                Some((end, final_byte)) => {
+                    if final_byte == 'm' {
+                        out.push(Segment::Sgr(&after[1..end]));
+                    } else {
+                        out.push(Segment::Other);
+                    }
+                    end + 1
+                }
+                None => after.len(),
+            },
+            // OSC: runs to BEL or ESC \
+            Some((_, ']')) => {
+                out.push(Segment::Other);
+                match after.find([BEL, ESC]) {
+                    Some(end) if after[end + 1..].starts_with('\\') => end + 2,
+                    Some(end) => end + 1,
+                    None => after.len(),
+                }
+            }
+            // Intermediate bytes (e.g. the `(` of a charset designation),
+            // then a final byte
+            Some((_, ' '..='/')) => match chars.find(|(_, c)| !(' '..='/').contains(c)) {
+                Some((end, c)) => {
+                    out.push(Segment::Other);
+                    end + c.len_utf8()
+                }
+                None => after.len(),
+            },
+            Some((_, c)) => {
+                out.push(Segment::Other);
+                c.len_utf8()
+            }
+            None => 0,
+        };
+        rest = &after[consumed..];
+    }
+    if !rest.is_empty() {
+        out.push(Segment::Text(rest));
+    }
+    out
+}
+
+/// Remove every escape sequence
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+    if !contains_ansi(text) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    for segment in segments(text) {
+        if let Segment::Text(t) = segment {
+            out.push_str(t);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Text style built up from SGR sequences
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+fn color_name(index: u16) -> Option<String> {
+    match index {
+        0..=7 => Some(COLORS[index as usize].to_string()),
+        8..=15 => Some(format!("bright-{}", COLORS[index as usize - 8])),
+        _ => None,
+    }
+}
+
+impl Style {
+    fn apply(&mut self, params: &str) {
+        let codes: Vec<u16> = if params.is_empty() {
+            vec![0]
+        } else {
+            params.split(';').map(|p| p.parse().unwrap_or(0)).collect()
+        };
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                code @ 30..=37 => self.fg = color_name(code - 30),
+                39 => self.fg = None,
+                code @ 40..=47 => self.bg = color_name(code - 40),
+                49 => self.bg = None,
+                code @ 90..=97 => self.fg = color_name(code - 90 + 8),
+                code @ 100..=107 => self.bg = color_name(code - 100 + 8),
+                // Extended colors: 5;n picks from the 256-color palette (only
+                // the 16 named ones are kept), 2;r;g;b is dropped
+                code @ (38 | 48) => {
+                    let color = match codes.get(i + 1) {
+                        Some(5) => {
+                            i += 2;
+                            codes.get(i).and_then(|n| color_name(*n))
+                        }
+                        Some(2) => {
+                            i += 4;
+                            None
+                        }
+                        _ => None,
+                    };
+                    if code == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn classes(&self) -> Vec<String> {
+        let mut classes = Vec::new();
+        for (on, name) in [
+            (self.bold, "ansi-bold"),
+            (self.dim, "ansi-dim"),
+            (self.italic, "ansi-italic"),
+            (self.underline, "ansi-underline"),
+        ] {
+            if on {
+                classes.push(name.to_string());
+            }
+        }
+        if let Some(fg) = &self.fg {
+            classes.push(format!("ansi-{}", fg));
+        }
+        if let Some(bg) = &self.bg {
+            classes.push(format!("ansi-bg-{}", bg));
+        }
+        classes
+    }
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// HTML-escaped text with colors and styles as `<span class="ansi-...">`
+pub fn ansi_to_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 4);
+    let mut style = Style::default();
+    let mut span_open = false;
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(t) => {
+                if !span_open {
+                    let classes = style.classes();
+                    if !classes.is_empty() {
+                        out.push_str(&format!("<span class=\"{}\">", classes.join(" ")));
+                        span_open = true;
+                    }
+                }
+                push_escaped(&mut out, t);
+            }
+            Segment::Sgr(params) => {
+                let before = style.clone();
+                style.apply(params);
+                if style != before && span_open {
+                    out.push_str("</span>");
+                    span_open = false;
+                }
+            }
+            Segment::Other => {}
+        }
+    }
+    if span_open {
+        out.push_str("</span>");
+    }
+    out
+}
+
+/// Content as written to the database
+pub fn for_storage(content: &str) -> Cow<'_, str> {
+    match AnsiMode::from_env() {
+        AnsiMode::Keep => Cow::Borrowed(content),
+        AnsiMode::Html | AnsiMode::Strip => strip_ansi(content),
+    }
+}
+
+/// An output with its escape codes removed (unless AGENT_OUTPUT_ANSI=keep)
+pub fn cleaned(mut event: AgentOutputEvent) -> AgentOutputEvent {
+    if AnsiMode::from_env() != AnsiMode::Keep && contains_ansi(&event.content) {
+        event.content = strip_ansi(&event.content).into_owned();
+    }
+    event
+}
+
+/// The `agent:output` payload for an output
+pub fn ui_payload(event: &AgentOutputEvent) -> serde_json::Value {
+    let mode = AnsiMode::from_env();
+    let mut payload = serde_json::to_value(event).unwrap_or(serde_json::Value::Null);
+    if mode == AnsiMode::Keep || !contains_ansi(&event.content) {
+        return payload;
+    }
+    if let Some(fields) = payload.as_object_mut() {
+        fields.insert(
+            "content".to_string(),
+            strip_ansi(&event.content).into_owned().into(),
+        );
+        if mode == AnsiMode::Html {
+            fields.insert(
+                "content_html".to_string(),
+                ansi_to_html(&event.content).into(),
+            );
+        }
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_removes_every_kind_of_sequence() {
+        let text = "\x1b[1;31merror\x1b[0m: build failed\x1b[K\n\x1b]0;cargo\x07done\x1b(B";
+        assert_eq!(strip_ansi(text), "error: build failed\ndone");
+        assert!(matches!(strip_ansi("plain"), Cow::Borrowed("plain")));
+        // A sequence cut off at the end of a chunk is dropped too
+        assert_eq!(strip_ansi("ok\x1b[3"), "ok");
+    }
+
+    #[test]
+    fn test_html_classes_and_escaping() {
+        assert_eq!(
+            ansi_to_html("\x1b[1;31merror\x1b[0m: <T> & \x1b[92mok\x1b[39m!"),
+            "<span class=\"ansi-bold ansi-red\">error</span>: &lt;T&gt; &amp; \
+             <span class=\"ansi-bright-green\">ok</span>!"
+        );
+        assert_eq!(
+            ansi_to_html("\x1b[38;5;4;48;2;1;2;3mx\x1b[m"),
+            "<span class=\"ansi-blue\">x</span>"
+        );
+        assert_eq!(ansi_to_html("no codes"), "no codes");
+    }
+}
@@ -0,0 +1,376 @@
+// Auto follow-up
+//
+// Agents often end a turn with "Shall I proceed?" and then sit waiting for a
+// trivial "yes". For agents opted in with set_agent_auto_follow_up, the idle
+// transition hands such turns to a background worker instead of notifying the
+// user right away: one light-model call reads the last message and the
+// original task and decides whether to reply "continue" or escalate to the
+// user. Every decision is pinned to the run as a note, and auto-replies start
+// with AUTO_REPLY_MARKER so they stand out in the prompt history. The global
+// kill switch (AUTO_FOLLOW_UP_DISABLED, or set_auto_follow_up_kill_switch at
+// runtime) turns the policy off for every agent.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::ai_client::{AIClient, Message};
+use crate::commands::env_registry;
+use crate::events::AppEventEmitter;
+use crate::types::NoteAuthor;
+use crate::utils::generator::{extract_json_from_response, extract_text_from_content_blocks};
+
+use super::idle_debounce::notify_input_required;
+use super::types::AgentProcess;
+use super::AgentManager;
+
+/// Prefix of every prompt sent by the auto follow-up policy
+pub const AUTO_REPLY_MARKER: &str = "[Auto follow-up from Commander]";
+
+/// Text sent after the marker when the agent is told to carry on
+const AUTO_REPLY_TEXT: &str = "Yes, continue.";
+
+/// Auto-replies per agent before every further question goes to the user
+pub const MAX_AUTO_REPLIES: u32 = 5;
+
+/// Only the end of the last message is checked for a "shall I proceed?" question
+const QUESTION_TAIL_CHARS: usize = 400;
+
+/// Maximum characters of the task and last message sent to the light model
+const MAX_CONTEXT_CHARS: usize = 4_000;
+
+/// Phrases that ask for permission to carry on
+const PROCEED_PATTERNS: &[&str] = &[
+    "shall i proceed",
+    "should i proceed",
+    "shall i continue",
+    "should i continue",
+    "shall i go ahead",
+    "should i go ahead",
+    "can i proceed",
+    "may i proceed",
+    "would you like me to proceed",
+    "would you like me to continue",
+    "would you like me to go ahead",
+    "do you want me to proceed",
+    "do you want me to continue",
+    "do you want me to go ahead",
+    "want me to proceed",
+    "want me to continue",
+    "ready to proceed",
+    "proceed with",
+    "continue with",
+];
+
+const DECISION_PROMPT: &str = r#"You supervise coding agents on behalf of a user. An agent finished its turn by asking whether it should proceed. Decide whether to let it continue without involving the user.
+
+Answer "continue" only when the agent asks for permission to carry on with work that is clearly part of the original task and continuing is low-risk and reversible.
+
+Answer "escalate" when the agent:
+- asks the user to choose between options or approaches
+- needs information only the user has
+- proposes work outside the original task
+- wants to do something destructive or hard to undo (deleting data, force-pushing, deploying, spending money)
+- reports a problem instead of asking to continue
+
+When in doubt, escalate.
+
+Respond with JSON only:
+{"decision": "continue" or "escalate", "reasoning": "<one sentence>"}"#;
+
+static KILL_SWITCH: OnceLock<AtomicBool> = OnceLock::new();
+
+fn kill_switch() -> &'static AtomicBool {
+    KILL_SWITCH.get_or_init(|| {
+        let engaged = env_registry::AUTO_FOLLOW_UP_DISABLED.flag();
+        AtomicBool::new(engaged)
+    })
+}
+
+/// Whether the global kill switch currently disables auto follow-ups
+pub fn kill_switch_engaged() -> bool {
+    kill_switch().load(Ordering::Relaxed)
+}
+
+/// Engage or release the global kill switch
+pub fn set_kill_switch(engaged: bool) {
+    kill_switch().store(engaged, Ordering::Relaxed);
+}
+
+/// An agent that went idle on a "shall I proceed?" question
+#[derive(Debug, Clone)]
+pub struct FollowUpRequest {
+    pub agent_id: String,
+    pub last_output: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FollowUpAction {
+    Continue,
+    Escalate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FollowUpDecision {
+    pub action: FollowUpAction,
+    pub reasoning: String,
+}
+
+impl FollowUpDecision {
+    fn escalate(reasoning: impl Into<String>) -> Self {
+        Self {
+            action: FollowUpAction::Escalate,
+            reasoning: reasoning.into(),
+        }
+    }
+
+    /// Text of the note pinned to the run
+    fn note_text(&self) -> String {
+        match self.action {
+            FollowUpAction::Continue => {
+                format!("Auto follow-up: replied \"continue\". {}", self.reasoning)
+            }
+            FollowUpAction::Escalate => {
+                format!("Auto follow-up: escalated to the user. {}", self.reasoning)
+            }
+        }
+    }
+}
+
+/// Whether the end of an agent's last message asks for permission to proceed
+pub(crate) fn asks_to_proceed(last_output: &str) -> bool {
+    let tail: String = {
+        let chars: Vec<char> = last_output.trim_end().chars().collect();
+        let start = chars.len().saturating_sub(QUESTION_TAIL_CHARS);
+        chars[start..].iter().collect::<String>().to_lowercase()
+    };
+    tail.contains('?') && PROCEED_PATTERNS.iter().any(|p| tail.contains(p))
+}
+
+/// Parse the light model's reply; anything unclear is an escalation
+fn parse_decision(text: &str) -> FollowUpDecision {
+    let Some(value) = extract_json_from_response(text)
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+    else {
+        return FollowUpDecision::escalate("The model's answer could not be read.");
+    };
+
+    let reasoning = value
+        .get("reasoning")
+        .and_then(|r| r.as_str())
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| "No reasoning given.".to_string());
+    let action = match value.get("decision").and_then(|d| d.as_str()) {
+        Some(d) if d.trim().eq_ignore_ascii_case("continue") => FollowUpAction::Continue,
+        _ => FollowUpAction::Escalate,
+    };
+    FollowUpDecision { action, reasoning }
+}
+
+/// Ask the light model whether to reply "continue" or escalate
+async fn decide(task: &str, last_output: &str) -> FollowUpDecision {
+    let client = match AIClient::light_from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            return FollowUpDecision::escalate(format!("Light model unavailable: {}", e));
+        }
+    };
+
+    let task: String = task.chars().take(MAX_CONTEXT_CHARS).collect();
+    let tail: String = {
+        let chars: Vec<char> = last_output.chars().collect();
+        chars[chars.len().saturating_sub(MAX_CONTEXT_CHARS)..]
+            .iter()
+            .collect()
+    };
+    let request = vec![Message {
+        role: "user".to_string(),
+        content: format!(
+            "Original task:\n{}\n\nAgent's last message:\n{}",
+            if task.trim().is_empty() {
+                "(unknown)"
+            } else {
+                task.as_str()
+            },
+            tail
+        ),
+    }];
+
+    match client
+        .send_message_with_system_and_tools(DECISION_PROMPT, request, vec![])
+        .await
+    {
+        Ok(response) => parse_decision(&extract_text_from_content_blocks(&response.content)),
+        Err(e) => FollowUpDecision::escalate(format!("Light model call failed: {}", e)),
+    }
+}
+
+/// Whether the agent is still waiting for input and still opted in, or None
+/// if it's gone
+async fn follow_up_state(
+    agents: &Arc<Mutex<HashMap<String, AgentProcess>>>,
+    agent_id: &str,
+) -> Option<(bool, bool)> {
+    let (pending_input, opted_in) = {
+        let agents = agents.lock().await;
+        let agent = agents.get(agent_id)?;
+        (agent.pending_input.clone(), agent.auto_follow_up)
+    };
+    let pending = *pending_input.lock().await;
+    Some((pending, opted_in))
+}
+
+/// Decide on one request and either reply or hand the question to the user
+async fn handle_request(
+    manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+    request: FollowUpRequest,
+) {
+    let FollowUpRequest {
+        agent_id,
+        last_output,
+    } = request;
+    let (agents, runs_db, agent_wake_tx) = {
+        let manager = manager.lock().await;
+        (
+            manager.agents.clone(),
+            manager.runs_db.clone(),
+            manager.agent_wake_tx.clone(),
+        )
+    };
+    let escalate =
+        || notify_input_required(&app_handle, &agent_wake_tx, &agent_id, last_output.clone());
+
+    let replies = match agents.lock().await.get(&agent_id) {
+        Some(agent) => agent.auto_follow_ups,
+        None => return,
+    };
+    let decision = if kill_switch_engaged() {
+        escalate().await;
+        return;
+    } else if replies >= MAX_AUTO_REPLIES {
+        FollowUpDecision::escalate(format!(
+            "Already auto-replied {} times to this agent.",
+            replies
+        ))
+    } else {
+        let task = match &runs_db {
+            Some(db) => db
+                .get_run(&agent_id)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|run| run.initial_prompt)
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        decide(&task, &last_output).await
+    };
+
+    // The user may have answered, or switched the policy off, in the meantime
+    match follow_up_state(&agents, &agent_id).await {
+        Some((true, true)) if !kill_switch_engaged() => {}
+        Some((true, _)) => {
+            escalate().await;
+            return;
+        }
+        _ => return,
+    }
+
+    eprintln!(
+        "[AutoFollowUp] Agent {}: {:?} ({})",
+        agent_id, decision.action, decision.reasoning
+    );
+    {
+        let manager = manager.lock().await;
+        match manager
+            .add_agent_note(&agent_id, NoteAuthor::Meta, &decision.note_text())
+            .await
+        {
+            Ok(note) => {
+                if let Ok(payload) = serde_json::to_value(&note) {
+                    let _ = app_handle.emit("agent:note", payload);
+                }
+            }
+            Err(e) => eprintln!(
+                "[AutoFollowUp] Failed to log decision for {}: {}",
+                agent_id, e
+            ),
+        }
+    }
+
+    if decision.action == FollowUpAction::Escalate {
+        escalate().await;
+        return;
+    }
+
+    if let Some(agent) = agents.lock().await.get_mut(&agent_id) {
+        agent.auto_follow_ups += 1;
+    }
+    let prompt = format!("{} {}", AUTO_REPLY_MARKER, AUTO_REPLY_TEXT);
+    let sent = manager
+        .lock()
+        .await
+        .send_prompt(&agent_id, &prompt, Some(app_handle.clone()), None)
+        .await;
+    if let Err(e) = sent {
+        eprintln!("[AutoFollowUp] Failed to auto-reply to {}: {}", agent_id, e);
+        escalate().await;
+    }
+}
+
+/// Handle follow-up requests from idle agents until the sender is dropped
+pub async fn run_auto_follow_up_worker(
+    manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+    mut requests: mpsc::Receiver<FollowUpRequest>,
+) {
+    while let Some(request) = requests.recv().await {
+        tokio::spawn(handle_request(manager.clone(), app_handle.clone(), request));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asks_to_proceed() {
+        assert!(asks_to_proceed(
+            "I've outlined the migration plan above.\n\nShall I proceed with step 1?"
+        ));
+        assert!(asks_to_proceed(
+            "Tests pass. Would you like me to continue?  \n"
+        ));
+        // A statement isn't a question
+        assert!(!asks_to_proceed("I will proceed with the refactor next."));
+        // Open questions need the user
+        assert!(!asks_to_proceed(
+            "Which database should I use, Postgres or SQLite?"
+        ));
+        // Only the end of the message counts
+        let buried = format!("Should I continue? {}", "Done. ".repeat(100));
+        assert!(!asks_to_proceed(&buried));
+    }
+
+    #[test]
+    fn test_parse_decision() {
+        let decision = parse_decision(
+            "```json\n{\"decision\": \"continue\", \"reasoning\": \"Next step of the plan.\"}\n```",
+        );
+        assert_eq!(decision.action, FollowUpAction::Continue);
+        assert_eq!(decision.reasoning, "Next step of the plan.");
+
+        let decision = parse_decision("{\"decision\": \"ESCALATE\"}");
+        assert_eq!(decision.action, FollowUpAction::Escalate);
+        assert_eq!(decision.reasoning, "No reasoning given.");
+
+        assert_eq!(
+            parse_decision("Sure, let it continue").action,
+            FollowUpAction::Escalate
+        );
+    }
+}
@@ -2,63 +2,239 @@
 //
 // This module handles finding the Claude CLI binary in various installation
 // locations and managing the elevation-bin path for sudo wrapper functionality.
+//
+// The CLI is resolved in order: CLAUDE_PATH, the global bin directories of npm,
+// pnpm, yarn and nvm, the directories on PATH, node_modules/.bin in the agent's
+// working directory, and finally `npx @anthropic-ai/claude-code`. On Windows a
+// .cmd script can't always be spawned directly, so it is run with `cmd /C`.
 
-/// Attempt to find the Claude CLI in common installation locations
-pub fn find_claude_cli() -> Result<String, std::env::VarError> {
-    #[cfg(windows)]
-    {
-        // Check npm global install location on Windows
-        if let Ok(appdata) = std::env::var("APPDATA") {
-            let npm_path = std::path::PathBuf::from(&appdata)
-                .join("npm")
-                .join("claude.cmd");
-            if npm_path.exists() {
-                return Ok(npm_path.to_string_lossy().to_string());
-            }
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::commands::env_registry;
+
+/// The npm package npx runs when no claude CLI is installed
+pub const NPX_PACKAGE: &str = "@anthropic-ai/claude-code";
+
+/// Where the claude CLI was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeCliSource {
+    /// CLAUDE_PATH
+    EnvVar,
+    /// A global bin directory of npm, pnpm, yarn or nvm
+    KnownLocation,
+    /// A directory on PATH
+    Path,
+    /// node_modules/.bin in the working directory
+    LocalNodeModules,
+    /// Nothing was found: run through npx
+    Npx,
+}
+
+/// How the claude CLI is run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClaudeCli {
+    /// The claude executable or script, or npx
+    pub path: String,
+    /// Arguments before claude's own (npx's package)
+    pub prefix_args: Vec<String>,
+    pub source: ClaudeCliSource,
+}
+
+impl ClaudeCli {
+    /// A command running the CLI with `args`
+    pub fn command(&self, args: &[String]) -> Command {
+        let args: Vec<String> = self.prefix_args.iter().chain(args).cloned().collect();
+        #[cfg(windows)]
+        if is_batch_script(&self.path) {
+            use std::os::windows::process::CommandExt;
+            let mut cmd = std::process::Command::new("cmd.exe");
+            cmd.raw_arg(cmd_line(&self.path, &args));
+            return Command::from(cmd);
         }
-        // Check Program Files
-        let program_files =
-            std::env::var("ProgramFiles").unwrap_or_else(|_| "C:\\Program Files".to_string());
-        let pf_path = std::path::PathBuf::from(&program_files)
-            .join("nodejs")
-            .join("claude.cmd");
-        if pf_path.exists() {
-            return Ok(pf_path.to_string_lossy().to_string());
+        let mut cmd = Command::new(&self.path);
+        cmd.args(&args);
+        cmd
+    }
+}
+
+/// The environment the CLI is looked up in
+struct Lookup<'a> {
+    var: Box<dyn Fn(&str) -> Option<String> + 'a>,
+    windows: bool,
+    /// /usr/local/bin outside Windows
+    system_bin: Option<PathBuf>,
+}
+
+impl Lookup<'_> {
+    /// A directory named by an env var
+    fn dir(&self, name: &str) -> Option<PathBuf> {
+        (self.var)(name)
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// File names the CLI may have in a bin directory
+    fn names(&self) -> &'static [&'static str] {
+        if self.windows {
+            &["claude.cmd", "claude.exe"]
+        } else {
+            &["claude"]
         }
     }
 
-    #[cfg(not(windows))]
-    {
-        // Check common Unix locations
-        if let Ok(home) = std::env::var("HOME") {
-            // Check ~/.local/bin (common for user installs)
-            let local_bin = std::path::PathBuf::from(&home).join(".local/bin/claude");
-            if local_bin.exists() {
-                return Ok(local_bin.to_string_lossy().to_string());
-            }
+    fn in_dirs(&self, dirs: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+        dirs.into_iter()
+            .flat_map(|dir| self.names().iter().map(move |name| dir.join(name)))
+            .collect()
+    }
 
-            // Check nvm locations (try to find any node version)
-            let nvm_dir = std::path::PathBuf::from(&home).join(".nvm/versions/node");
-            if nvm_dir.exists() {
-                if let Ok(entries) = std::fs::read_dir(&nvm_dir) {
-                    for entry in entries.flatten() {
-                        let claude_path = entry.path().join("bin/claude");
-                        if claude_path.exists() {
-                            return Ok(claude_path.to_string_lossy().to_string());
-                        }
-                    }
+    /// The global bin directories of npm, pnpm, yarn and nvm
+    fn known_locations(&self) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if self.windows {
+            if let Some(appdata) = self.dir("APPDATA") {
+                dirs.push(appdata.join("npm"));
+            }
+            let program_files = self
+                .dir("ProgramFiles")
+                .unwrap_or_else(|| PathBuf::from("C:\\Program Files"));
+            dirs.push(program_files.join("nodejs"));
+            dirs.extend(self.dir("PNPM_HOME"));
+            if let Some(local) = self.dir("LOCALAPPDATA") {
+                dirs.push(local.join("pnpm"));
+                dirs.push(local.join("Yarn").join("bin"));
+            }
+        } else {
+            if let Some(home) = self.dir("HOME") {
+                dirs.push(home.join(".local/bin"));
+                // Any node version nvm installed
+                if let Ok(entries) = std::fs::read_dir(home.join(".nvm/versions/node")) {
+                    dirs.extend(entries.flatten().map(|entry| entry.path().join("bin")));
                 }
             }
+            dirs.extend(self.system_bin.clone());
+            dirs.extend(self.dir("PNPM_HOME"));
+            if let Some(home) = self.dir("HOME") {
+                dirs.push(home.join(".local/share/pnpm"));
+                dirs.push(home.join("Library/pnpm"));
+                dirs.push(home.join(".yarn/bin"));
+                dirs.push(home.join(".config/yarn/global/node_modules/.bin"));
+            }
         }
+        self.in_dirs(dirs)
+    }
+
+    /// The directories on PATH
+    fn path_dirs(&self) -> Vec<PathBuf> {
+        let separator = if self.windows { ';' } else { ':' };
+        let path = (self.var)("PATH").unwrap_or_default();
+        let dirs = path
+            .split(separator)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        self.in_dirs(dirs)
+    }
+
+    fn local_node_modules(&self, working_dir: Option<&Path>) -> Vec<PathBuf> {
+        self.in_dirs(working_dir.map(|dir| dir.join("node_modules").join(".bin")))
+    }
+
+    /// Every place the CLI is looked for, in order, with where it is
+    fn candidates(&self, working_dir: Option<&Path>) -> Vec<(PathBuf, ClaudeCliSource)> {
+        let tag = |paths: Vec<PathBuf>, source| paths.into_iter().map(move |p| (p, source));
+        tag(self.known_locations(), ClaudeCliSource::KnownLocation)
+            .chain(tag(self.path_dirs(), ClaudeCliSource::Path))
+            .chain(tag(
+                self.local_node_modules(working_dir),
+                ClaudeCliSource::LocalNodeModules,
+            ))
+            .collect()
+    }
+
+    fn find(&self, working_dir: Option<&Path>) -> Option<ClaudeCli> {
+        self.candidates(working_dir)
+            .into_iter()
+            .find(|(path, _)| path.is_file())
+            .map(|(path, source)| ClaudeCli {
+                path: path.to_string_lossy().to_string(),
+                prefix_args: Vec::new(),
+                source,
+            })
+    }
 
-        // Check /usr/local/bin
-        let usr_local = std::path::PathBuf::from("/usr/local/bin/claude");
-        if usr_local.exists() {
-            return Ok(usr_local.to_string_lossy().to_string());
+    fn resolve(&self, claude_path: Option<String>, working_dir: Option<&Path>) -> ClaudeCli {
+        if let Some(path) = claude_path.filter(|p| !p.trim().is_empty()) {
+            return ClaudeCli {
+                path: path.trim().to_string(),
+                prefix_args: Vec::new(),
+                source: ClaudeCliSource::EnvVar,
+            };
         }
+        self.find(working_dir).unwrap_or_else(|| ClaudeCli {
+            path: if self.windows { "npx.cmd" } else { "npx" }.to_string(),
+            prefix_args: vec!["--yes".to_string(), NPX_PACKAGE.to_string()],
+            source: ClaudeCliSource::Npx,
+        })
     }
+}
 
-    Err(std::env::VarError::NotPresent)
+fn system_lookup() -> Lookup<'static> {
+    Lookup {
+        var: Box::new(|name: &str| std::env::var(name).ok()),
+        windows: cfg!(windows),
+        system_bin: (!cfg!(windows)).then(|| PathBuf::from("/usr/local/bin")),
+    }
+}
+
+/// The CLI to run agents in `working_dir` with: CLAUDE_PATH, else the first
+/// one found, else npx
+pub fn resolve_claude_cli(working_dir: Option<&Path>) -> ClaudeCli {
+    system_lookup().resolve(env_registry::CLAUDE_PATH.var().ok(), working_dir)
+}
+
+/// The places the CLI is looked for, in order
+pub fn claude_cli_search_paths(working_dir: Option<&Path>) -> Vec<String> {
+    system_lookup()
+        .candidates(working_dir)
+        .into_iter()
+        .map(|(path, _)| path.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Whether a path is a batch script, which Windows runs through cmd.exe
+#[cfg_attr(not(windows), allow(dead_code))]
+fn is_batch_script(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    path.ends_with(".cmd") || path.ends_with(".bat")
+}
+
+/// The arguments of `cmd.exe` running a batch script. /S makes cmd strip
+/// exactly the outer quotes, so the script and arguments keep their own.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn cmd_line(script: &str, args: &[String]) -> String {
+    let line: Vec<String> = std::iter::once(script)
+        .chain(args.iter().map(String::as_str))
+        .map(quote_cmd_arg)
+        .collect();
+    format!("/D /S /C \"{}\"", line.join(" "))
+}
+
+/// Quote an argument for cmd.exe if it has spaces or characters cmd treats
+/// specially
+#[cfg_attr(not(windows), allow(dead_code))]
+fn quote_cmd_arg(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && !arg
+            .chars()
+            .any(|c| c.is_whitespace() || "\"&|<>^(),;=%!".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    }
 }
 
 /// Get the path to the elevation-bin directory for the current platform
@@ -107,3 +283,108 @@ pub(crate) fn get_elevation_bin_path() -> Option<std::path::PathBuf> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup<'a>(vars: &'a HashMap<&str, String>, windows: bool) -> Lookup<'a> {
+        Lookup {
+            var: Box::new(move |name: &str| vars.get(name).cloned()),
+            windows,
+            system_bin: None,
+        }
+    }
+
+    fn touch(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn test_windows_finds_pnpm_and_yarn_installs() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("Local");
+        let vars = HashMap::from([
+            ("APPDATA", dir.path().join("Roaming").display().to_string()),
+            ("ProgramFiles", dir.path().join("PF").display().to_string()),
+            ("LOCALAPPDATA", local.display().to_string()),
+        ]);
+        let windows = lookup(&vars, true);
+        assert_eq!(windows.find(None), None);
+
+        let yarn = local.join("Yarn").join("bin").join("claude.cmd");
+        touch(&yarn);
+        let found = windows.find(None).unwrap();
+        assert_eq!(found.path, yarn.to_string_lossy());
+        assert_eq!(found.source, ClaudeCliSource::KnownLocation);
+
+        // pnpm's directory comes before yarn's
+        let pnpm = local.join("pnpm").join("claude.cmd");
+        touch(&pnpm);
+        assert_eq!(windows.find(None).unwrap().path, pnpm.to_string_lossy());
+    }
+
+    #[test]
+    fn test_path_then_local_node_modules_then_npx() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        let project = dir.path().join("project");
+        let vars = HashMap::from([
+            ("HOME", dir.path().join("home").display().to_string()),
+            ("PATH", format!("/nonexistent:{}", bin.display())),
+        ]);
+        let unix = lookup(&vars, false);
+
+        let npx = unix.resolve(None, Some(&project));
+        assert_eq!(npx.source, ClaudeCliSource::Npx);
+        assert_eq!(npx.path, "npx");
+        assert_eq!(npx.prefix_args, ["--yes", NPX_PACKAGE]);
+
+        let local = project.join("node_modules/.bin/claude");
+        touch(&local);
+        let found = unix.resolve(None, Some(&project));
+        assert_eq!(found.source, ClaudeCliSource::LocalNodeModules);
+        assert_eq!(found.path, local.to_string_lossy());
+
+        touch(&bin.join("claude"));
+        assert_eq!(
+            unix.resolve(None, Some(&project)).source,
+            ClaudeCliSource::Path
+        );
+
+        let configured = unix.resolve(Some(" /opt/claude ".to_string()), Some(&project));
+        assert_eq!(configured.path, "/opt/claude");
+        assert_eq!(configured.source, ClaudeCliSource::EnvVar);
+    }
+
+    #[test]
+    fn test_windows_path_uses_semicolons_and_npx_cmd() {
+        let dir = tempfile::tempdir().unwrap();
+        let vars = HashMap::from([("PATH", format!("C:\\nowhere;{}", dir.path().display()))]);
+        let windows = lookup(&vars, true);
+        assert_eq!(windows.resolve(None, None).path, "npx.cmd");
+
+        touch(&dir.path().join("claude.exe"));
+        let found = windows.resolve(None, None);
+        assert_eq!(found.source, ClaudeCliSource::Path);
+        assert!(found.path.ends_with("claude.exe"));
+    }
+
+    #[test]
+    fn test_cmd_line_quotes_what_cmd_would_split() {
+        assert!(is_batch_script("C:\\npm\\CLAUDE.CMD"));
+        assert!(!is_batch_script("C:\\npm\\claude.exe"));
+        let args = vec![
+            "-p".to_string(),
+            "C:\\Users\\Jo Smith\\hooks.json".to_string(),
+            "Bash(git diff:*)".to_string(),
+            String::new(),
+        ];
+        assert_eq!(
+            cmd_line("C:\\Program Files\\nodejs\\claude.cmd", &args),
+            "/D /S /C \"\"C:\\Program Files\\nodejs\\claude.cmd\" -p \"C:\\Users\\Jo Smith\\hooks.json\" \"Bash(git diff:*)\" \"\"\""
+        );
+    }
+}
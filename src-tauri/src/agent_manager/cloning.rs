@@ -0,0 +1,202 @@
+// Cloning agents
+//
+// An agent that got into a bad state can be replaced by a clone: a fresh agent
+// with its working directory, GitHub URL, skills (generated from its
+// instruction files), model, permission profile, env vars, MCP servers, tool
+// lists. Unlike a hand-off the original keeps running, so while it does it
+// keeps its generated skills and worktree: the clone gets a worktree of its
+// own and stopping it removes neither. A stopped original's kept worktree is
+// carried on in by the clone, or replaced by a new one if it is gone. With
+// include_context the original's output, formatted
+// the way process_agent_results formats it for the commander, is sent as the
+// clone's first prompt. The clone's run records the original in cloned_from.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::events::AppEventEmitter;
+use crate::meta_agent::{format_agent_results, DEFAULT_RESULT_MAX_CHARS};
+use crate::types::AgentStatus;
+
+use super::handoff::Replacement;
+use super::worktrees::WorktreeSetup;
+use super::AgentManager;
+
+/// First prompt of a clone started with the original's context
+fn clone_prompt(context: &str) -> String {
+    format!(
+        "# Context from a previous agent\n\
+         You are a fresh copy of an agent that worked in this directory. A summary of \
+         its output follows. Check the current state of the files before relying on \
+         it.\n\n{}\n\n---\nPlease continue the task from where it left off.",
+        context.trim()
+    )
+}
+
+/// A worktree that no longer exists is replaced by a new one
+fn recreate_missing_worktree(config: &mut Replacement) {
+    if let WorktreeSetup::Existing(worktree) = &config.worktree {
+        if !Path::new(&worktree.path).exists() {
+            use_new_worktree(config);
+        }
+    }
+}
+
+/// Give the agent a new worktree of its own instead of an existing one, in
+/// the directory of the repository matching the one it worked in
+fn use_new_worktree(config: &mut Replacement) {
+    let WorktreeSetup::Existing(worktree) = &config.worktree else {
+        return;
+    };
+    let relative = Path::new(&config.working_dir)
+        .strip_prefix(&worktree.path)
+        .unwrap_or(Path::new(""));
+    config.working_dir = Path::new(&worktree.repo_path)
+        .join(relative)
+        .to_string_lossy()
+        .to_string();
+    config.worktree = WorktreeSetup::Create;
+    config.keep_worktree = false;
+}
+
+impl AgentManager {
+    /// Spawn a fresh agent with the same configuration as `agent_id`,
+    /// seeded with a summary of its output if `include_context`. Returns the
+    /// clone's ID.
+    pub async fn clone_agent(
+        &self,
+        agent_id: &str,
+        include_context: bool,
+        app_handle: Arc<dyn AppEventEmitter>,
+    ) -> Result<String, String> {
+        let (mut config, stopped) = {
+            let mut agents = self.agents.lock().await;
+            let agent = agents
+                .get_mut(agent_id)
+                .ok_or_else(|| "Agent not found".to_string())?;
+            let stopped = matches!(agent.info.status, AgentStatus::Stopped);
+            let config = if stopped {
+                Replacement::take_from(agent)
+            } else {
+                Replacement::copy_from(agent)
+            };
+            (config, stopped)
+        };
+        let (skills, keep_worktree) = (config.generated_skill_names.clone(), config.keep_worktree);
+        // A stopped original's skills were removed when it stopped; a running
+        // one's stay its own, so stopping the clone doesn't remove them
+        config.generated_skill_names.clear();
+        if stopped {
+            recreate_missing_worktree(&mut config);
+        } else {
+            // Two agents editing one worktree is what worktrees are there to avoid
+            use_new_worktree(&mut config);
+        }
+        config.title = config.title.map(|title| format!("{} (clone)", title));
+        let working_dir = config.working_dir.clone();
+
+        let context = if include_context {
+            let outputs = self.get_agent_outputs(agent_id, 0, None).await?;
+            (!outputs.is_empty()).then(|| {
+                format_agent_results(&outputs, &working_dir, false, DEFAULT_RESULT_MAX_CHARS)
+            })
+        } else {
+            None
+        };
+
+        let new_agent_id = match self.spawn_with(config, app_handle.clone()).await {
+            Ok(id) => id,
+            Err(e) => {
+                // A stopped original keeps what it had
+                if stopped {
+                    if let Some(agent) = self.agents.lock().await.get_mut(agent_id) {
+                        agent.generated_skill_names = skills;
+                        agent.keep_worktree = keep_worktree;
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(db) = &self.runs_db {
+            if let Err(e) = db.set_run_cloned_from(&new_agent_id, agent_id).await {
+                eprintln!(
+                    "[Clone] Failed to link run {} to {}: {}",
+                    new_agent_id, agent_id, e
+                );
+            }
+        }
+
+        if let Some(context) = context {
+            self.send_prompt(
+                &new_agent_id,
+                &clone_prompt(&context),
+                Some(app_handle),
+                None,
+            )
+            .await?;
+        }
+        Ok(new_agent_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AgentSource, AgentWorktree, ToolRestrictions};
+    use std::collections::HashMap;
+
+    fn config(working_dir: &str, worktree: WorktreeSetup) -> Replacement {
+        Replacement {
+            working_dir: working_dir.to_string(),
+            github_url: None,
+            source: AgentSource::UI,
+            pipeline_id: None,
+            title: None,
+            complexity: None,
+            generated_skill_names: Vec::new(),
+            inject_conventions: false,
+            auto_follow_up: false,
+            permission_profile: None,
+            model: None,
+            extra_env: HashMap::new(),
+            mcp_servers: None,
+            tools: ToolRestrictions::default(),
+            worktree,
+            keep_worktree: false,
+        }
+    }
+
+    #[test]
+    fn test_missing_worktree_is_recreated_in_the_same_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().to_string_lossy().to_string();
+        let worktree = |path: &str| AgentWorktree {
+            repo_path: "/repo".to_string(),
+            path: path.to_string(),
+            branch: "grove/agent-1".to_string(),
+        };
+
+        let mut kept = config(&existing, WorktreeSetup::Existing(worktree(&existing)));
+        recreate_missing_worktree(&mut kept);
+        assert!(matches!(kept.worktree, WorktreeSetup::Existing(_)));
+        assert_eq!(kept.working_dir, existing);
+
+        let gone = "/repo/.grove/worktrees/agent-1";
+        let mut removed = config(
+            &format!("{}/crates/app", gone),
+            WorktreeSetup::Existing(worktree(gone)),
+        );
+        recreate_missing_worktree(&mut removed);
+        assert!(matches!(removed.worktree, WorktreeSetup::Create));
+        assert_eq!(removed.working_dir, "/repo/crates/app");
+
+        // A running original's worktree is never shared
+        let mut shared = config(&existing, WorktreeSetup::Existing(worktree(&existing)));
+        shared.keep_worktree = true;
+        use_new_worktree(&mut shared);
+        assert!(matches!(shared.worktree, WorktreeSetup::Create));
+        assert_eq!(shared.working_dir, "/repo");
+        assert!(!shared.keep_worktree);
+    }
+}
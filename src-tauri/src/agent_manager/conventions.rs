@@ -0,0 +1,262 @@
+// Repository conventions injection
+//
+// When an agent's working directory documents its conventions (CONTRIBUTING.md,
+// CODESTYLE.md, .editorconfig), a compact digest of them is prepended to the
+// agent's first prompt. Digests are generated by the light model once per set
+// of file contents and cached on disk keyed by a hash of those contents.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai_client::{AIClient, Message};
+use crate::commands::config_loader::get_config_dir;
+use crate::utils::generator::extract_text_from_content_blocks;
+
+/// Files in the working directory that describe repository conventions
+const CONVENTION_FILES: [&str; 3] = ["CONTRIBUTING.md", "CODESTYLE.md", ".editorconfig"];
+
+/// Maximum characters of each convention file sent to the light model
+const MAX_SOURCE_CHARS: usize = 16_000;
+
+/// Maximum characters of the digest prepended to a prompt
+const MAX_DIGEST_CHARS: usize = 2_000;
+
+/// How long the first prompt waits for a digest that is not cached yet
+const DIGEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CONVENTIONS_CACHE_DIR: &str = "conventions_cache";
+
+const DIGEST_PROMPT: &str = r#"You condense a repository's contribution guidelines into rules for a coding agent.
+
+The user will provide the contents of the repository's convention files. Reply with a compact bullet list of the concrete rules a contributor must follow when changing code: formatting, naming, testing, commit messages, branching, documentation and anything the files mark as required or forbidden.
+
+Important:
+- Output ONLY the bullet list, at most 20 bullets
+- One short imperative sentence per bullet
+- Skip community, licensing and code of conduct sections"#;
+
+/// A convention file found in the working directory
+#[derive(Debug, Clone)]
+struct ConventionFile {
+    name: String,
+    content: String,
+}
+
+/// Digest cached on disk, keyed by the hash of the files it was built from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedDigest {
+    content_hash: u64,
+    working_dir: String,
+    sources: Vec<String>,
+    digest: String,
+}
+
+/// Read the non-empty convention files present in the working directory
+fn read_convention_files(working_dir: &Path) -> Vec<ConventionFile> {
+    CONVENTION_FILES
+        .iter()
+        .filter_map(|name| {
+            let content = std::fs::read_to_string(working_dir.join(name)).ok()?;
+            if content.trim().is_empty() {
+                return None;
+            }
+            Some(ConventionFile {
+                name: name.to_string(),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// Hash of the convention files, used as the digest cache key
+fn content_hash(files: &[ConventionFile]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        file.name.hash(&mut hasher);
+        file.content.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn cache_path(content_hash: u64) -> Option<PathBuf> {
+    get_config_dir().ok().map(|d| {
+        d.join(CONVENTIONS_CACHE_DIR)
+            .join(format!("{:016x}.json", content_hash))
+    })
+}
+
+fn load_cached_digest(content_hash: u64) -> Option<String> {
+    let content = std::fs::read_to_string(cache_path(content_hash)?).ok()?;
+    let cached: CachedDigest = serde_json::from_str(&content).ok()?;
+    (cached.content_hash == content_hash).then_some(cached.digest)
+}
+
+fn save_cached_digest(cached: &CachedDigest) -> Result<(), String> {
+    let path = cache_path(cached.content_hash)
+        .ok_or_else(|| "Could not determine config directory for conventions cache".to_string())?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create conventions cache directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(cached)
+        .map_err(|e| format!("Failed to serialize conventions digest: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write conventions digest: {}", e))
+}
+
+/// Truncate to at most `max_chars` characters on a char boundary
+fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => &text[..idx],
+        None => text,
+    }
+}
+
+/// Trim the digest to MAX_DIGEST_CHARS, cutting at a line break where possible
+fn cap_digest(digest: &str) -> String {
+    let digest = digest.trim();
+    if digest.chars().count() <= MAX_DIGEST_CHARS {
+        return digest.to_string();
+    }
+
+    let truncated = truncate_chars(digest, MAX_DIGEST_CHARS);
+    let cut = truncated.rfind('\n').unwrap_or(truncated.len());
+    truncated[..cut].trim_end().to_string()
+}
+
+/// Ask the light model for a digest of the convention files
+async fn generate_digest(files: &[ConventionFile]) -> Result<String, String> {
+    let client =
+        AIClient::light_from_env().map_err(|e| format!("Failed to create light client: {}", e))?;
+
+    let content = files
+        .iter()
+        .map(|f| {
+            format!(
+                "=== {} ===\n{}",
+                f.name,
+                truncate_chars(&f.content, MAX_SOURCE_CHARS)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let response = client
+        .send_message_with_system_and_tools(
+            DIGEST_PROMPT,
+            vec![Message {
+                role: "user".to_string(),
+                content,
+            }],
+            vec![],
+        )
+        .await
+        .map_err(|e| format!("Light model call failed: {}", e))?;
+
+    let digest = cap_digest(&extract_text_from_content_blocks(&response.content));
+    if digest.is_empty() {
+        return Err("Model returned an empty conventions digest".to_string());
+    }
+    Ok(digest)
+}
+
+/// Get the conventions digest for a working directory.
+///
+/// Returns None when the directory has no convention files or the digest
+/// could not be generated in time; the prompt is then sent unchanged.
+pub(crate) async fn conventions_digest(working_dir: &str) -> Option<String> {
+    let files = read_convention_files(Path::new(working_dir));
+    if files.is_empty() {
+        return None;
+    }
+
+    let hash = content_hash(&files);
+    if let Some(digest) = load_cached_digest(hash) {
+        return Some(cap_digest(&digest));
+    }
+
+    let digest = match tokio::time::timeout(DIGEST_TIMEOUT, generate_digest(&files)).await {
+        Ok(Ok(digest)) => digest,
+        Ok(Err(e)) => {
+            eprintln!(
+                "[Conventions] Failed to generate digest for {}: {}",
+                working_dir, e
+            );
+            return None;
+        }
+        Err(_) => {
+            eprintln!(
+                "[Conventions] Timed out generating digest for {}",
+                working_dir
+            );
+            return None;
+        }
+    };
+
+    let cached = CachedDigest {
+        content_hash: hash,
+        working_dir: working_dir.to_string(),
+        sources: files.iter().map(|f| f.name.clone()).collect(),
+        digest: digest.clone(),
+    };
+    if let Err(e) = save_cached_digest(&cached) {
+        eprintln!("[Conventions] {}", e);
+    }
+
+    Some(digest)
+}
+
+/// Prepend the conventions digest to an agent's first prompt
+pub(crate) fn prepend_conventions(digest: &str, prompt: &str) -> String {
+    format!(
+        "## Repository Conventions\nThis repository documents its conventions. Follow these rules in every change:\n\n{}\n\n---\n\n{}",
+        digest, prompt
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_convention_files_skips_missing_and_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("CONTRIBUTING.md"), "Run cargo fmt.").unwrap();
+        std::fs::write(dir.path().join(".editorconfig"), "  \n").unwrap();
+
+        let files = read_convention_files(dir.path());
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "CONTRIBUTING.md");
+    }
+
+    #[test]
+    fn test_content_hash_changes_with_content() {
+        let file = |content: &str| ConventionFile {
+            name: "CODESTYLE.md".to_string(),
+            content: content.to_string(),
+        };
+        assert_eq!(content_hash(&[file("a")]), content_hash(&[file("a")]));
+        assert_ne!(content_hash(&[file("a")]), content_hash(&[file("b")]));
+    }
+
+    #[test]
+    fn test_cap_digest_cuts_at_line_break() {
+        let digest = "- Use tabs\n".repeat(MAX_DIGEST_CHARS / 5);
+        let capped = cap_digest(&digest);
+        assert!(capped.chars().count() <= MAX_DIGEST_CHARS);
+        assert!(capped.ends_with("- Use tabs"));
+
+        assert_eq!(cap_digest("  - Short\n"), "- Short");
+    }
+
+    #[test]
+    fn test_truncate_chars_respects_char_boundaries() {
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+        assert_eq!(truncate_chars("hi", 10), "hi");
+    }
+}
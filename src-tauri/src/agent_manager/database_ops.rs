@@ -4,7 +4,7 @@
 
 use std::sync::Arc;
 
-use crate::agent_runs_db::{AgentRun, AgentRunsDB, RunStatus};
+use crate::agent_runs_db::{AgentRun, AgentRunsDB, DeadLetterWrite, RunStatus};
 use crate::logger::Logger;
 
 /// Record a new agent run in the database
@@ -18,9 +18,16 @@ pub(crate) async fn record_run_in_db(
     github_context: &Option<crate::types::GitHubContext>,
     source: &crate::types::AgentSource,
     pipeline_id: Option<String>,
+    permission_profile: Option<String>,
+    model: Option<String>,
+    title: Option<String>,
+    mut env_keys: Vec<String>,
+    tools: crate::types::ToolRestrictions,
+    worktree: Option<&crate::types::AgentWorktree>,
     now: i64,
 ) {
     if let Some(ref runs_db) = runs_db {
+        env_keys.sort();
         let github_context_json = github_context
             .as_ref()
             .and_then(|gc| serde_json::to_string(gc).ok());
@@ -48,9 +55,30 @@ pub(crate) async fn record_run_in_db(
             model_usage: None,
             can_resume: true,
             resume_data: None,
+            conventions_injected: false,
+            label: None,
+            workspace_id: crate::workspace::active_workspace_id(),
+            continued_from: None,
+            app_version: None,
+            git_commit: None,
+            schema_version: None,
+            stop_mode: None,
+            permission_profile,
+            model,
+            title,
+            env_keys,
+            allowed_tools: tools.allowed_tools,
+            disallowed_tools: tools.disallowed_tools,
+            repo_path: worktree.map(|w| w.repo_path.clone()),
+            worktree_path: worktree.map(|w| w.path.clone()),
+            tool_timings: None,
+            cloned_from: None,
         };
 
-        if let Err(e) = runs_db.create_run(&run).await {
+        if let Err(e) = runs_db
+            .write_or_dead_letter(DeadLetterWrite::CreateRun { run })
+            .await
+        {
             if let Some(ref logger) = logger {
                 let _ = logger
                     .error(
@@ -64,3 +92,35 @@ pub(crate) async fn record_run_in_db(
         }
     }
 }
+
+/// Put a resumed run back to running
+pub(crate) async fn reopen_run_in_db(
+    runs_db: &Option<Arc<AgentRunsDB>>,
+    logger: &Option<Arc<Logger>>,
+    mut run: AgentRun,
+    now: i64,
+) {
+    if let Some(ref runs_db) = runs_db {
+        let agent_id = run.agent_id.clone();
+        run.status = RunStatus::Running;
+        run.ended_at = None;
+        run.error_message = None;
+        run.last_activity = now;
+
+        if let Err(e) = runs_db
+            .write_or_dead_letter(DeadLetterWrite::UpdateRun { run })
+            .await
+        {
+            if let Some(ref logger) = logger {
+                let _ = logger
+                    .error(
+                        "agent_manager",
+                        &format!("Failed to reopen resumed run in database: {}", e),
+                        Some(agent_id),
+                        None,
+                    )
+                    .await;
+            }
+        }
+    }
+}
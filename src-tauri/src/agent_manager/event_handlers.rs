@@ -11,8 +11,15 @@ use tokio::time::Instant;
 use tokio::sync::mpsc;
 
 use crate::agent_runs_db::AgentRunsDB;
-use crate::types::{AgentOutputEvent, AgentStatistics, AgentWakeEvent};
-
+use crate::fault_injection::SharedFaultHook;
+use crate::meta_agent::SharedResultQueue;
+use crate::types::{AgentStatistics, AgentWakeEvent};
+
+use super::auto_follow_up::FollowUpRequest;
+use super::idle_debounce::IdleDebounce;
+use super::output_buffer::OutputBuffer;
+use super::session_finalizer::SessionFinalizer;
+use super::stderr_buffer::StderrBuffer;
 use super::types::AgentProcess;
 
 // Re-export handlers from sub-modules
@@ -54,7 +61,10 @@ pub struct StreamContext {
     pub stats: Arc<Mutex<AgentStatistics>>,
 
     /// Buffer of recent output events
-    pub output_buffer: Arc<Mutex<Vec<AgentOutputEvent>>>,
+    pub output_buffer: Arc<Mutex<OutputBuffer>>,
+
+    /// Recent stderr lines, attached to the run's error message on crash
+    pub stderr_buffer: Arc<Mutex<StderrBuffer>>,
 
     /// Database for persisting agent runs
     pub runs_db: Option<Arc<AgentRunsDB>>,
@@ -64,4 +74,19 @@ pub struct StreamContext {
 
     /// Sender for waking the meta-agent when agent reaches terminal state
     pub agent_wake_tx: Arc<Mutex<Option<mpsc::Sender<AgentWakeEvent>>>>,
+
+    /// Fault hook for chaos tests (None in normal operation)
+    pub fault_hook: SharedFaultHook,
+
+    /// Defers the idle transition after a turn ends
+    pub idle_debounce: Arc<IdleDebounce>,
+
+    /// Sender for handing "shall I proceed?" turns to the auto follow-up worker
+    pub follow_up_tx: Option<mpsc::Sender<FollowUpRequest>>,
+
+    /// Records the session's end once, shared with stop_agent
+    pub session_finalizer: Arc<SessionFinalizer>,
+
+    /// The meta-agent's result queue, where a crash is reported
+    pub result_queue: Option<SharedResultQueue>,
 }
@@ -0,0 +1,88 @@
+// Background GitHub context refresh
+//
+// Agents spawn with the cached GitHub context for their working directory.
+// When the cache is empty or stale the context is rebuilt off the async
+// runtime, stored on the agent, persisted to the run record and pushed to the
+// frontend via `agent:status`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::agent_runs_db::AgentRunsDB;
+use crate::events::AppEventEmitter;
+use crate::github;
+use crate::types::{AgentStatusEvent, GitHubContext};
+
+use super::types::AgentProcess;
+
+/// Rebuild the GitHub context for an agent and apply it
+pub(crate) async fn refresh_agent_github_context(
+    agents: Arc<Mutex<HashMap<String, AgentProcess>>>,
+    runs_db: Option<Arc<AgentRunsDB>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+    agent_id: String,
+    working_dir: String,
+    github_url: Option<String>,
+) -> Result<Option<GitHubContext>, String> {
+    let context = tokio::task::spawn_blocking(move || {
+        github::refresh_github_context(&working_dir, github_url.as_deref())
+    })
+    .await
+    .map_err(|e| format!("GitHub context refresh failed: {}", e))?;
+
+    let info = {
+        let mut agents = agents.lock().await;
+        let agent = agents
+            .get_mut(&agent_id)
+            .ok_or_else(|| format!("Agent {} not found", agent_id))?;
+        agent.info.github_context = context.clone();
+        agent.info.clone()
+    };
+
+    if let Some(db) = &runs_db {
+        let json = context.as_ref().and_then(|c| serde_json::to_string(c).ok());
+        if let Err(e) = db.update_github_context(&agent_id, json).await {
+            eprintln!(
+                "[AgentManager] Failed to persist GitHub context for {}: {}",
+                agent_id, e
+            );
+        }
+    }
+
+    if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+        agent_id: agent_id.clone(),
+        status: info.status.clone(),
+        info: Some(info),
+        reason: None,
+    }) {
+        let _ = app_handle.emit("agent:status", status_event);
+    }
+
+    Ok(context)
+}
+
+/// Refresh the GitHub context in the background, logging failures
+pub(crate) fn spawn_github_context_refresh(
+    agents: Arc<Mutex<HashMap<String, AgentProcess>>>,
+    runs_db: Option<Arc<AgentRunsDB>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+    agent_id: String,
+    working_dir: String,
+    github_url: Option<String>,
+) {
+    tokio::spawn(async move {
+        if let Err(e) = refresh_agent_github_context(
+            agents,
+            runs_db,
+            app_handle,
+            agent_id,
+            working_dir,
+            github_url,
+        )
+        .await
+        {
+            eprintln!("[AgentManager] {}", e);
+        }
+    });
+}
@@ -0,0 +1,100 @@
+// Agent groups - batch operations across related agents
+//
+// Every pipeline gets a group holding its agents, created along with its first
+// agent; other groups are named by the user with create_agent_group. Groups can
+// be stopped, prompted and totalled as a whole. Membership is stored in the run
+// database and mirrored into each live agent's AgentInfo.group_ids so the agent
+// list can be grouped.
+
+use crate::agent_runs_db::AgentRun;
+use crate::types::{AgentGroup, AgentStatistics, GroupStatistics};
+
+/// Group names are at most this many characters
+pub const GROUP_NAME_CHAR_LIMIT: usize = 80;
+
+/// ID of the group holding a pipeline's agents
+pub fn pipeline_group_id(pipeline_id: &str) -> String {
+    format!("pipeline:{}", pipeline_id)
+}
+
+/// The group for a pipeline, holding one of its agents
+pub fn pipeline_group(pipeline_id: &str, agent_id: &str, now: i64) -> AgentGroup {
+    let short_id: String = pipeline_id.chars().take(8).collect();
+    AgentGroup {
+        id: pipeline_group_id(pipeline_id),
+        name: format!("Pipeline {}", short_id),
+        pipeline_id: Some(pipeline_id.to_string()),
+        agent_ids: vec![agent_id.to_string()],
+        created_at: now,
+    }
+}
+
+/// Trim a user-supplied group name and check it fits
+pub fn validate_group_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Group name cannot be empty".to_string());
+    }
+    if name.chars().count() > GROUP_NAME_CHAR_LIMIT {
+        return Err(format!(
+            "Group name must be at most {} characters",
+            GROUP_NAME_CHAR_LIMIT
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Agent IDs with blanks and repeats removed, in the order given
+pub fn dedup_agent_ids(agent_ids: &[String]) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for id in agent_ids.iter().map(|id| id.trim()) {
+        if !id.is_empty() && !unique.iter().any(|u| u == id) {
+            unique.push(id.to_string());
+        }
+    }
+    unique
+}
+
+/// Add a live member's statistics to the group totals
+pub fn add_live_stats(totals: &mut GroupStatistics, stats: &AgentStatistics) {
+    totals.total_prompts += stats.total_prompts;
+    totals.total_tool_calls += stats.total_tool_calls;
+    totals.total_tokens_used += stats.total_tokens_used.unwrap_or(0) as u64;
+    totals.total_cost_usd += stats.total_cost_usd.unwrap_or(0.0);
+}
+
+/// Add a finished member's recorded run to the group totals
+pub fn add_run_stats(totals: &mut GroupStatistics, run: &AgentRun) {
+    totals.total_prompts += run.total_prompts;
+    totals.total_tool_calls += run.total_tool_calls;
+    totals.total_tokens_used += run.total_tokens_used.unwrap_or(0) as u64;
+    totals.total_cost_usd += run.total_cost_usd.unwrap_or(0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_group_and_name_validation() {
+        let group = pipeline_group("0123456789abcdef", "a1", 5);
+        assert_eq!(group.id, "pipeline:0123456789abcdef");
+        assert_eq!(group.name, "Pipeline 01234567");
+        assert_eq!(group.agent_ids, vec!["a1"]);
+
+        assert_eq!(
+            validate_group_name("  Experiments ").unwrap(),
+            "Experiments"
+        );
+        assert!(validate_group_name("   ").is_err());
+        assert!(validate_group_name(&"x".repeat(GROUP_NAME_CHAR_LIMIT + 1)).is_err());
+
+        let ids = dedup_agent_ids(&[
+            "a2".to_string(),
+            " a1 ".to_string(),
+            "a2".to_string(),
+            "".to_string(),
+        ]);
+        assert_eq!(ids, vec!["a2", "a1"]);
+    }
+}
@@ -0,0 +1,533 @@
+// Context hand-off
+//
+// Once a worker's context window is nearly full its work gets noticeably
+// worse. A hand-off replaces it with a fresh agent: the light model condenses
+// the worker's task state (original request, todo list, files it changed and
+// its latest reply) into a summary, the old agent is stopped, a new one is
+// spawned in the same directory with the same options, and the summary is sent
+// as its first prompt. The new run's continued_from points at the old run.
+// Each stage is emitted as an agent:handoff event so the UI can follow along.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::ai_client::{AIClient, Message};
+use crate::events::AppEventEmitter;
+use crate::hook_server::{self, AgentTodoItem};
+use crate::types::{AgentOutputEvent, AgentStatus, ToolRestrictions};
+use crate::utils::generator::extract_text_from_content_blocks;
+use crate::utils::string::truncate_with_ellipsis;
+
+use super::worktrees::WorktreeSetup;
+use super::{AgentManager, AgentProcess, AgentSpawnOptions};
+
+/// Tools whose file_path input is a file the agent changed
+const FILE_CHANGING_TOOLS: [&str; 4] = ["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Bytes of the original request and latest reply passed to the light model
+const MAX_SECTION_BYTES: usize = 6_000;
+
+const HANDOFF_SUMMARY_PROMPT: &str = r#"You write hand-off notes for a coding agent that is being replaced by a fresh agent because its context window is full.
+
+The user will provide the old agent's task state. Write a concise hand-off in Markdown with exactly these sections:
+## Task
+## Done so far
+## Files changed
+## Open issues and next steps
+
+Important:
+- Keep every concrete detail the new agent needs: file paths, commands, decisions, error messages
+- Leave out anything already finished that doesn't affect what's left
+- Output ONLY the hand-off"#;
+
+/// Stages of a hand-off, in order
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandoffStage {
+    Summarizing,
+    Stopping,
+    Spawning,
+    Prompting,
+    Completed,
+    Failed,
+}
+
+/// Payload of the agent:handoff event
+#[derive(Debug, Clone, Serialize)]
+pub struct HandoffEvent {
+    /// The agent being replaced
+    pub agent_id: String,
+    pub stage: HandoffStage,
+    /// The replacement, once it has been spawned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HandoffResult {
+    pub previous_agent_id: String,
+    pub agent_id: String,
+    /// The summary sent to the new agent
+    pub summary: String,
+}
+
+/// What the old agent was doing, collected before it is stopped
+#[derive(Debug, Clone, Default)]
+pub struct HandoffState {
+    pub working_dir: String,
+    pub original_prompt: Option<String>,
+    pub todos: Vec<AgentTodoItem>,
+    pub files_changed: Vec<String>,
+    pub latest_reply: Option<String>,
+}
+
+/// Files the agent edited or wrote, in the order first touched
+pub fn files_changed(events: &[AgentOutputEvent]) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for event in events.iter().filter(|e| e.output_type == "tool_use") {
+        let tool = event
+            .content
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("Using tool: "))
+            .unwrap_or("");
+        if !FILE_CHANGING_TOOLS.contains(&tool) {
+            continue;
+        }
+        let path = event.parsed_json.as_ref().and_then(|input| {
+            input
+                .get("file_path")
+                .or_else(|| input.get("notebook_path"))
+                .and_then(|p| p.as_str())
+        });
+        if let Some(path) = path {
+            if !files.iter().any(|f| f == path) {
+                files.push(path.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// The task state as Markdown: what the light model summarizes, and the
+/// hand-off itself when the light model is unavailable
+pub fn state_report(state: &HandoffState) -> String {
+    let mut parts = vec![format!("Working directory: {}", state.working_dir)];
+
+    parts.push("\n## Original request".to_string());
+    parts.push(match &state.original_prompt {
+        Some(prompt) => truncate_with_ellipsis(prompt, MAX_SECTION_BYTES),
+        None => "(not recorded)".to_string(),
+    });
+
+    if !state.todos.is_empty() {
+        parts.push("\n## Todo list".to_string());
+        for todo in &state.todos {
+            let mark = if todo.status == "completed" { "x" } else { " " };
+            parts.push(format!("- [{}] {} ({})", mark, todo.content, todo.status));
+        }
+    }
+
+    parts.push("\n## Files changed".to_string());
+    if state.files_changed.is_empty() {
+        parts.push("(none)".to_string());
+    } else {
+        parts.extend(state.files_changed.iter().map(|f| format!("- {}", f)));
+    }
+
+    if let Some(reply) = &state.latest_reply {
+        parts.push("\n## Latest reply".to_string());
+        parts.push(truncate_with_ellipsis(reply, MAX_SECTION_BYTES));
+    }
+
+    parts.join("\n")
+}
+
+/// First prompt of the replacement agent
+pub fn handoff_prompt(summary: &str) -> String {
+    format!(
+        "# Continuing a previous session\n\
+         You are taking over this task from an agent whose context window filled up. \
+         Its hand-off notes follow. Check the current state of the files listed before \
+         changing them.\n\n{}\n\n---\nPlease continue from where the previous agent left off.",
+        summary.trim()
+    )
+}
+
+/// Ask the light model to condense the task state
+async fn summarize(report: &str) -> Result<String, String> {
+    let client =
+        AIClient::light_from_env().map_err(|e| format!("Failed to create light client: {}", e))?;
+
+    let request = vec![Message {
+        role: "user".to_string(),
+        content: report.to_string(),
+    }];
+
+    let response = client
+        .send_message_with_system_and_tools(HANDOFF_SUMMARY_PROMPT, request, vec![])
+        .await
+        .map_err(|e| format!("Light model call failed: {}", e))?;
+
+    let summary = extract_text_from_content_blocks(&response.content);
+    if summary.trim().is_empty() {
+        return Err("Model returned an empty summary".to_string());
+    }
+    Ok(summary)
+}
+
+fn emit_stage(
+    app_handle: &Arc<dyn AppEventEmitter>,
+    agent_id: &str,
+    stage: HandoffStage,
+    new_agent_id: Option<&str>,
+    error: Option<&str>,
+) {
+    let event = HandoffEvent {
+        agent_id: agent_id.to_string(),
+        stage,
+        new_agent_id: new_agent_id.map(str::to_string),
+        error: error.map(str::to_string),
+    };
+    if let Ok(value) = serde_json::to_value(&event) {
+        let _ = app_handle.emit("agent:handoff", value);
+    }
+}
+
+/// Replace an agent with a fresh one that continues its task
+///
+/// The manager is only locked around each stage, not while the light model
+/// writes the summary.
+pub async fn handoff_agent(
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    agent_id: &str,
+    app_handle: Arc<dyn AppEventEmitter>,
+) -> Result<HandoffResult, String> {
+    let result = run_handoff(agent_manager, agent_id, &app_handle).await;
+    match &result {
+        Ok(done) => emit_stage(
+            &app_handle,
+            agent_id,
+            HandoffStage::Completed,
+            Some(done.agent_id.as_str()),
+            None,
+        ),
+        Err(e) => emit_stage(
+            &app_handle,
+            agent_id,
+            HandoffStage::Failed,
+            None,
+            Some(e.as_str()),
+        ),
+    }
+    result
+}
+
+async fn run_handoff(
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    agent_id: &str,
+    app_handle: &Arc<dyn AppEventEmitter>,
+) -> Result<HandoffResult, String> {
+    emit_stage(app_handle, agent_id, HandoffStage::Summarizing, None, None);
+    let state = agent_manager.lock().await.handoff_state(agent_id).await?;
+    let report = state_report(&state);
+    let summary = summarize(&report).await.unwrap_or_else(|e| {
+        eprintln!("[Handoff] {}, handing off the raw task state", e);
+        report
+    });
+
+    let manager = agent_manager.lock().await;
+
+    emit_stage(app_handle, agent_id, HandoffStage::Stopping, None, None);
+    let replacement = manager.stop_for_handoff(agent_id).await?;
+
+    emit_stage(app_handle, agent_id, HandoffStage::Spawning, None, None);
+    let new_agent_id = manager
+        .spawn_replacement(agent_id, replacement, app_handle.clone())
+        .await?;
+
+    emit_stage(
+        app_handle,
+        agent_id,
+        HandoffStage::Prompting,
+        Some(new_agent_id.as_str()),
+        None,
+    );
+    manager
+        .send_prompt(
+            &new_agent_id,
+            &handoff_prompt(&summary),
+            Some(app_handle.clone()),
+            None,
+        )
+        .await?;
+
+    Ok(HandoffResult {
+        previous_agent_id: agent_id.to_string(),
+        agent_id: new_agent_id,
+        summary,
+    })
+}
+
+/// Options the replacement agent is spawned with
+pub(crate) struct Replacement {
+    pub working_dir: String,
+    pub github_url: Option<String>,
+    pub source: crate::types::AgentSource,
+    pub pipeline_id: Option<String>,
+    pub title: Option<String>,
+    pub complexity: Option<String>,
+    /// Skills generated for the old agent, kept for the new one
+    pub generated_skill_names: Vec<String>,
+    pub inject_conventions: bool,
+    pub auto_follow_up: bool,
+    /// Permission profile the old agent was spawned under
+    pub permission_profile: Option<String>,
+    /// Model the old agent was started with
+    pub model: Option<String>,
+    /// Extra env vars the old agent was spawned with
+    pub extra_env: HashMap<String, String>,
+    /// MCP servers the old agent was given at spawn
+    pub mcp_servers: Option<serde_json::Value>,
+    /// Tool lists the old agent was given at spawn
+    pub tools: ToolRestrictions,
+    /// Git worktree the old agent ran in, which the replacement carries on in
+    pub worktree: WorktreeSetup,
+    /// Whether that worktree stays when the replacement stops
+    pub keep_worktree: bool,
+}
+
+impl Replacement {
+    /// The options `agent` was spawned with. Its generated skills and its
+    /// worktree are handed over: stopping it no longer removes them.
+    pub(crate) fn take_from(agent: &mut AgentProcess) -> Self {
+        let replacement = Self::copy_from(agent);
+        agent.generated_skill_names.clear();
+        agent.keep_worktree = true;
+        replacement
+    }
+
+    /// The options `agent` was spawned with, leaving it in charge of its
+    /// generated skills and worktree
+    pub(crate) fn copy_from(agent: &AgentProcess) -> Self {
+        Replacement {
+            working_dir: agent.info.working_dir.clone(),
+            github_url: agent.github_url.clone(),
+            source: agent.info.source.clone(),
+            pipeline_id: agent.pipeline_id.clone(),
+            title: agent.info.title.clone(),
+            complexity: agent.info.complexity.clone(),
+            generated_skill_names: agent.generated_skill_names.clone(),
+            inject_conventions: agent.inject_conventions,
+            auto_follow_up: agent.auto_follow_up,
+            permission_profile: agent
+                .info
+                .permission_profile
+                .as_ref()
+                .map(|p| p.name.clone()),
+            model: agent.info.model.clone(),
+            extra_env: agent.extra_env.clone(),
+            mcp_servers: agent.mcp_servers.clone(),
+            tools: agent.tools.clone(),
+            worktree: match agent.info.worktree.clone() {
+                Some(worktree) => WorktreeSetup::Existing(worktree),
+                None => WorktreeSetup::None,
+            },
+            keep_worktree: agent.keep_worktree,
+        }
+    }
+}
+
+impl AgentManager {
+    /// Collect what an agent was working on
+    pub(crate) async fn handoff_state(&self, agent_id: &str) -> Result<HandoffState, String> {
+        let (working_dir, output_buffer) = {
+            let agents = self.agents.lock().await;
+            let agent = agents
+                .get(agent_id)
+                .ok_or_else(|| "Agent not found".to_string())?;
+            if agent.info.status == AgentStatus::Stopped {
+                return Err("Agent is already stopped".to_string());
+            }
+            (agent.info.working_dir.clone(), agent.output_buffer.clone())
+        };
+
+        let (files_changed, latest_reply) = {
+            let buffer = output_buffer.lock().await;
+            let events = buffer.events();
+            let latest_reply = events
+                .iter()
+                .rev()
+                .find(|e| e.output_type == "text" && !e.content.trim().is_empty())
+                .map(|e| e.content.clone());
+            (files_changed(events), latest_reply)
+        };
+
+        let original_prompt = match &self.runs_db {
+            Some(db) => db
+                .get_prompts(agent_id)
+                .await
+                .ok()
+                .and_then(|prompts| prompts.into_iter().next())
+                .map(|(prompt, _)| prompt),
+            None => None,
+        };
+
+        Ok(HandoffState {
+            working_dir,
+            original_prompt,
+            todos: hook_server::get_agent_todos(agent_id)
+                .await
+                .unwrap_or_default(),
+            files_changed,
+            latest_reply,
+        })
+    }
+
+    /// Stop an agent that is being replaced, keeping what the replacement needs
+    pub(crate) async fn stop_for_handoff(&self, agent_id: &str) -> Result<Replacement, String> {
+        let replacement = {
+            let mut agents = self.agents.lock().await;
+            let agent = agents
+                .get_mut(agent_id)
+                .ok_or_else(|| "Agent not found".to_string())?;
+            // Taken so stopping doesn't remove the skills and worktree the
+            // replacement uses
+            Replacement::take_from(agent)
+        };
+        self.stop_agent(agent_id).await?;
+        Ok(replacement)
+    }
+
+    /// Spawn the replacement and link its run to the old one
+    pub(crate) async fn spawn_replacement(
+        &self,
+        previous_agent_id: &str,
+        replacement: Replacement,
+        app_handle: Arc<dyn AppEventEmitter>,
+    ) -> Result<String, String> {
+        let new_agent_id = self.spawn_with(replacement, app_handle).await?;
+
+        if let Some(db) = &self.runs_db {
+            if let Err(e) = db
+                .set_run_continued_from(&new_agent_id, previous_agent_id)
+                .await
+            {
+                eprintln!(
+                    "[Handoff] Failed to link run {} to {}: {}",
+                    new_agent_id, previous_agent_id, e
+                );
+            }
+        }
+        Ok(new_agent_id)
+    }
+
+    /// Spawn an agent with another agent's options
+    pub(crate) async fn spawn_with(
+        &self,
+        replacement: Replacement,
+        app_handle: Arc<dyn AppEventEmitter>,
+    ) -> Result<String, String> {
+        let new_agent_id = self
+            .spawn_agent(
+                replacement.working_dir,
+                replacement.github_url,
+                replacement.generated_skill_names,
+                replacement.source,
+                app_handle,
+                AgentSpawnOptions {
+                    pipeline_id: replacement.pipeline_id,
+                    title: replacement.title,
+                    model: replacement.model,
+                    complexity: replacement.complexity,
+                    permission_profile: replacement.permission_profile,
+                    extra_env: replacement.extra_env,
+                    mcp_servers: replacement.mcp_servers,
+                    tools: replacement.tools,
+                    ..Default::default()
+                },
+                replacement.worktree,
+                None,
+            )
+            .await?;
+
+        if let Some(agent) = self.agents.lock().await.get_mut(&new_agent_id) {
+            agent.inject_conventions = replacement.inject_conventions;
+            agent.auto_follow_up = replacement.auto_follow_up;
+            agent.keep_worktree = replacement.keep_worktree;
+        }
+        Ok(new_agent_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_use(tool: &str, input: serde_json::Value) -> AgentOutputEvent {
+        AgentOutputEvent {
+            agent_id: "agent-1".to_string(),
+            output_type: "tool_use".to_string(),
+            content: format!("Using tool: {}\nInput:\n{}", tool, input),
+            parsed_json: Some(input),
+            metadata: None,
+            session_id: None,
+            uuid: None,
+            parent_tool_use_id: None,
+            subtype: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_files_changed_skips_reads_and_duplicates() {
+        let events = vec![
+            tool_use("Read", serde_json::json!({"file_path": "src/lib.rs"})),
+            tool_use("Edit", serde_json::json!({"file_path": "src/main.rs"})),
+            tool_use("Write", serde_json::json!({"file_path": "src/new.rs"})),
+            tool_use("Edit", serde_json::json!({"file_path": "src/main.rs"})),
+            tool_use(
+                "NotebookEdit",
+                serde_json::json!({"notebook_path": "a.ipynb"}),
+            ),
+        ];
+        assert_eq!(
+            files_changed(&events),
+            vec!["src/main.rs", "src/new.rs", "a.ipynb"]
+        );
+    }
+
+    #[test]
+    fn test_state_report_lists_todos_and_files() {
+        let state = HandoffState {
+            working_dir: "/work/app".to_string(),
+            original_prompt: Some("Add retries to the sync job".to_string()),
+            todos: vec![
+                AgentTodoItem {
+                    content: "Add backoff".to_string(),
+                    status: "completed".to_string(),
+                    active_form: None,
+                },
+                AgentTodoItem {
+                    content: "Write tests".to_string(),
+                    status: "pending".to_string(),
+                    active_form: None,
+                },
+            ],
+            files_changed: vec!["src/sync.rs".to_string()],
+            latest_reply: None,
+        };
+        let report = state_report(&state);
+        assert!(report.contains("Add retries to the sync job"));
+        assert!(report.contains("- [x] Add backoff (completed)"));
+        assert!(report.contains("- [ ] Write tests (pending)"));
+        assert!(report.contains("- src/sync.rs"));
+        assert!(!report.contains("## Latest reply"));
+
+        assert!(handoff_prompt(&report).contains("## Original request"));
+    }
+}
@@ -0,0 +1,195 @@
+// Merging of user hooks with the commander's tracking hooks
+//
+// Claude Code only reads one `--settings` file per process, so writing a file
+// with just the tracking hooks clobbers any hooks the user relies on
+// (formatters, linters, ...). The user's settings - the project's
+// `.claude/settings.json` and/or an explicitly provided hooks file - are used
+// as the base and the tracking hooks are merged into them.
+
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Read a settings file and check it is a JSON object
+pub(crate) fn load_settings_file(path: &Path) -> Result<Value, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read settings file {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid JSON in settings file {}: {}", path.display(), e))?;
+    if !value.is_object() {
+        return Err(format!(
+            "Settings file {} must contain a JSON object",
+            path.display()
+        ));
+    }
+    Ok(value)
+}
+
+/// Deep-merge `overlay` into `base`
+///
+/// Objects are merged key by key. Under `hooks`, event arrays are combined:
+/// an overlay entry whose matcher already exists has its hook commands appended
+/// after the existing ones, and entries with new matchers are appended.
+/// Any other value in `overlay` replaces the one in `base`.
+pub(crate) fn merge_settings(base: &mut Value, overlay: &Value) {
+    merge_object(base, overlay, false);
+}
+
+fn merge_object(base: &mut Value, overlay: &Value, in_hooks: bool) {
+    let (Some(base_obj), Some(overlay_obj)) = (base.as_object_mut(), overlay.as_object()) else {
+        *base = overlay.clone();
+        return;
+    };
+
+    for (key, value) in overlay_obj {
+        match base_obj.get_mut(key) {
+            Some(existing) if in_hooks => merge_hook_entries(existing, value),
+            Some(existing) if existing.is_object() && value.is_object() => {
+                merge_object(existing, value, key == "hooks")
+            }
+            _ => {
+                base_obj.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Combine the matcher entries of one hook event, keeping both sides in order
+fn merge_hook_entries(existing: &mut Value, overlay: &Value) {
+    let (Some(entries), Some(new_entries)) = (existing.as_array_mut(), overlay.as_array()) else {
+        *existing = overlay.clone();
+        return;
+    };
+
+    for new_entry in new_entries {
+        let matcher = new_entry.get("matcher");
+        let same_matcher = entries
+            .iter_mut()
+            .find(|e| e.get("matcher") == matcher && e["hooks"].is_array());
+        match (same_matcher, new_entry["hooks"].as_array()) {
+            (Some(entry), Some(new_hooks)) => {
+                if let Some(hooks) = entry["hooks"].as_array_mut() {
+                    hooks.extend(new_hooks.iter().cloned());
+                }
+            }
+            _ => entries.push(new_entry.clone()),
+        }
+    }
+}
+
+/// Check the `hooks` section has the shape the Claude CLI accepts
+pub(crate) fn validate_settings(settings: &Value) -> Result<(), String> {
+    let obj = settings
+        .as_object()
+        .ok_or("Settings must be a JSON object")?;
+    let Some(hooks) = obj.get("hooks") else {
+        return Ok(());
+    };
+    let hooks: &Map<String, Value> = hooks
+        .as_object()
+        .ok_or("\"hooks\" must be an object of event names")?;
+
+    for (event, entries) in hooks {
+        let entries = entries
+            .as_array()
+            .ok_or_else(|| format!("hooks.{} must be an array", event))?;
+        for (i, entry) in entries.iter().enumerate() {
+            if let Some(matcher) = entry.get("matcher") {
+                if !matcher.is_string() {
+                    return Err(format!("hooks.{}[{}].matcher must be a string", event, i));
+                }
+            }
+            let commands = entry["hooks"]
+                .as_array()
+                .ok_or_else(|| format!("hooks.{}[{}].hooks must be an array", event, i))?;
+            for (j, hook) in commands.iter().enumerate() {
+                let kind = hook["type"].as_str().ok_or_else(|| {
+                    format!("hooks.{}[{}].hooks[{}].type must be a string", event, i, j)
+                })?;
+                if kind == "command" && !hook["command"].is_string() {
+                    return Err(format!(
+                        "hooks.{}[{}].hooks[{}].command must be a string",
+                        event, i, j
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn command(cmd: &str) -> Value {
+        json!({ "type": "command", "command": cmd })
+    }
+
+    #[test]
+    fn test_same_matcher_keeps_both_commands_in_order() {
+        let mut base = json!({
+            "permissions": { "allow": ["Bash(ls)"] },
+            "hooks": {
+                "PostToolUse": [{ "matcher": "*", "hooks": [command("prettier")] }]
+            }
+        });
+        let tracking = json!({
+            "hooks": {
+                "PostToolUse": [{ "matcher": "*", "hooks": [command("track")] }],
+                "Stop": [{ "hooks": [command("track")] }]
+            }
+        });
+
+        merge_settings(&mut base, &tracking);
+
+        assert_eq!(
+            base["hooks"]["PostToolUse"],
+            json!([{ "matcher": "*", "hooks": [command("prettier"), command("track")] }])
+        );
+        assert_eq!(base["hooks"]["Stop"][0]["hooks"][0], command("track"));
+        assert_eq!(base["permissions"]["allow"][0], json!("Bash(ls)"));
+        assert!(validate_settings(&base).is_ok());
+    }
+
+    #[test]
+    fn test_different_matchers_are_appended() {
+        let mut base = json!({
+            "hooks": {
+                "PreToolUse": [{ "matcher": "Edit", "hooks": [command("lint")] }]
+            }
+        });
+        let tracking = json!({
+            "hooks": {
+                "PreToolUse": [{ "matcher": "*", "hooks": [command("track")] }]
+            }
+        });
+
+        merge_settings(&mut base, &tracking);
+
+        let entries = base["hooks"]["PreToolUse"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["matcher"], json!("Edit"));
+        assert_eq!(entries[1]["matcher"], json!("*"));
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_hooks() {
+        assert!(validate_settings(&json!({ "hooks": { "Stop": {} } })).is_err());
+        assert!(validate_settings(&json!({
+            "hooks": { "Stop": [{ "hooks": [{ "type": "command" }] }] }
+        }))
+        .is_err());
+        assert!(validate_settings(&json!({ "model": "sonnet" })).is_ok());
+    }
+
+    #[test]
+    fn test_load_settings_file_requires_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(load_settings_file(&path).is_err());
+        std::fs::write(&path, "{\"hooks\": {}}").unwrap();
+        assert!(load_settings_file(&path).is_ok());
+    }
+}
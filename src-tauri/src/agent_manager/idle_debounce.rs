@@ -0,0 +1,261 @@
+// Idle debounce
+//
+// The end of a Claude turn (an end_turn message, a success result or the Stop
+// hook) doesn't always mean the agent is idle: it sometimes starts another turn
+// straight away. The switch to WaitingForInput is therefore deferred by a grace
+// period and cancelled if output or a PreToolUse hook arrives in the meantime.
+// All end-of-turn signals for one turn collapse into a single transition, so the
+// meta-agent is woken once per completion instead of once per signal. Turns that
+// end on a "shall I proceed?" question from an agent opted into auto follow-ups
+// go to the auto follow-up worker instead, which decides whether to notify.
+// The time the turn ended is recorded on its prompt in the runs database. An
+// agent with queued prompts isn't marked idle: the next one is sent instead.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::agent_runs_db::AgentRunsDB;
+use crate::commands::env_registry;
+use crate::events::AppEventEmitter;
+use crate::types::{AgentInputRequiredEvent, AgentStatus, AgentWakeEvent, AgentWakeReason};
+use crate::utils::time::now_millis;
+
+use super::auto_follow_up::{asks_to_proceed, kill_switch_engaged, FollowUpRequest};
+use super::event_handlers::StreamContext;
+use super::prompt_queue;
+use super::result_handlers::send_wake;
+use super::types::AgentProcess;
+
+/// Grace period used when AGENT_STOP_GRACE_MS is unset or invalid
+pub const DEFAULT_STOP_GRACE_MS: u64 = 2_000;
+
+/// Grace period after a turn ends before the agent is marked idle
+pub fn stop_grace_period() -> Duration {
+    let ms = env_registry::AGENT_STOP_GRACE_MS
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_STOP_GRACE_MS);
+    Duration::from_millis(ms)
+}
+
+#[derive(Default)]
+struct DebounceState {
+    /// Bumped by every activity and every scheduled transition; a timer only
+    /// fires if its generation is still current when the grace period ends
+    generation: u64,
+    /// Whether the agent was already marked idle since its last activity
+    idle: bool,
+    /// Last text output of the turn, sent with the input_required event
+    last_output: String,
+}
+
+/// Per-agent state for deferring the idle transition
+#[derive(Default)]
+pub struct IdleDebounce {
+    state: std::sync::Mutex<DebounceState>,
+}
+
+impl IdleDebounce {
+    /// Record activity, cancelling any pending idle transition
+    pub fn activity(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.idle = false;
+        state.last_output.clear();
+    }
+
+    /// Start a grace period and return its token, or None if already idle
+    fn begin(&self, last_output: &str) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.idle {
+            return None;
+        }
+        if !last_output.is_empty() {
+            state.last_output = last_output.to_string();
+        }
+        state.generation += 1;
+        Some(state.generation)
+    }
+
+    /// End the grace period for `token`, marking the agent idle if nothing
+    /// happened since. Returns the turn's last output when it did.
+    fn complete(&self, token: u64) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        if state.generation != token || state.idle {
+            return None;
+        }
+        state.idle = true;
+        Some(state.last_output.clone())
+    }
+}
+
+/// Everything needed to mark an agent idle once its grace period ends
+pub(crate) struct IdleTransition {
+    pub agent_id: String,
+    pub agents: Arc<Mutex<HashMap<String, AgentProcess>>>,
+    pub app_handle: Arc<dyn AppEventEmitter>,
+    pub is_processing: Arc<Mutex<bool>>,
+    pub pending_input: Arc<Mutex<bool>>,
+    pub agent_wake_tx: Arc<Mutex<Option<mpsc::Sender<AgentWakeEvent>>>>,
+    pub follow_up_tx: Option<mpsc::Sender<FollowUpRequest>>,
+    pub debounce: Arc<IdleDebounce>,
+    pub runs_db: Option<Arc<AgentRunsDB>>,
+}
+
+impl IdleTransition {
+    pub fn from_stream(ctx: &StreamContext) -> Self {
+        Self {
+            agent_id: ctx.agent_id.clone(),
+            agents: ctx.agents.clone(),
+            app_handle: ctx.app_handle.clone(),
+            is_processing: ctx.is_processing.clone(),
+            pending_input: ctx.pending_input.clone(),
+            agent_wake_tx: ctx.agent_wake_tx.clone(),
+            follow_up_tx: ctx.follow_up_tx.clone(),
+            debounce: ctx.idle_debounce.clone(),
+            runs_db: ctx.runs_db.clone(),
+        }
+    }
+
+    /// Mark the agent idle after the grace period unless it becomes active again
+    pub fn schedule(self, last_output: &str) {
+        let Some(token) = self.debounce.begin(last_output) else {
+            return;
+        };
+        let grace = stop_grace_period();
+        let ended_at = now_millis();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+            if let Some(last_output) = self.debounce.complete(token) {
+                self.mark_idle(last_output, ended_at).await;
+            }
+        });
+    }
+
+    async fn mark_idle(&self, last_output: String, ended_at: i64) {
+        // An agent stopped during the grace period stays stopped, and a
+        // rate-limited one was already handled when its limit was detected
+        let mut turn_recorded = false;
+        let auto_follow_up = {
+            let mut agents = self.agents.lock().await;
+            match agents.get_mut(&self.agent_id) {
+                Some(agent)
+                    if agent.info.status != AgentStatus::Stopped
+                        && agent.info.status != AgentStatus::RateLimited =>
+                {
+                    if !agent.prompt_queue.is_empty() {
+                        self.record_turn_end(ended_at).await;
+                        turn_recorded = true;
+                        if prompt_queue::send_next(
+                            agent,
+                            &self.agent_id,
+                            self.runs_db.as_ref(),
+                            self.app_handle.as_ref(),
+                        )
+                        .await
+                        {
+                            return;
+                        }
+                    }
+                    agent.info.status = AgentStatus::WaitingForInput;
+                    agent.info.pending_input = true;
+                    agent.auto_follow_up
+                }
+                _ => return,
+            }
+        };
+
+        *self.pending_input.lock().await = true;
+        *self.is_processing.lock().await = false;
+        if !turn_recorded {
+            self.record_turn_end(ended_at).await;
+        }
+
+        if auto_follow_up && !kill_switch_engaged() && asks_to_proceed(&last_output) {
+            if let Some(tx) = &self.follow_up_tx {
+                let request = FollowUpRequest {
+                    agent_id: self.agent_id.clone(),
+                    last_output: last_output.clone(),
+                };
+                if tx.send(request).await.is_ok() {
+                    return;
+                }
+            }
+        }
+
+        notify_input_required(
+            &self.app_handle,
+            &self.agent_wake_tx,
+            &self.agent_id,
+            last_output,
+        )
+        .await;
+    }
+
+    /// Record the end of the agent's turn on its latest prompt
+    async fn record_turn_end(&self, ended_at: i64) {
+        if let Some(ref runs_db) = self.runs_db {
+            if let Err(e) = runs_db.mark_turn_ended(&self.agent_id, ended_at).await {
+                eprintln!(
+                    "[IdleDebounce] Failed to record turn end for agent {}: {}",
+                    self.agent_id, e
+                );
+            }
+        }
+    }
+}
+
+/// Tell the frontend an agent needs input and wake the meta-agent
+pub(crate) async fn notify_input_required(
+    app_handle: &Arc<dyn AppEventEmitter>,
+    agent_wake_tx: &Mutex<Option<mpsc::Sender<AgentWakeEvent>>>,
+    agent_id: &str,
+    last_output: String,
+) {
+    let _ = app_handle.emit(
+        "agent:input_required",
+        serde_json::to_value(AgentInputRequiredEvent {
+            agent_id: agent_id.to_string(),
+            last_output,
+        })
+        .unwrap(),
+    );
+
+    // Send wake event to meta-agent if it's sleeping
+    send_wake(agent_wake_tx, agent_id, AgentWakeReason::WaitingForInput).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signals_for_one_turn_fire_once() {
+        let debounce = IdleDebounce::default();
+        let first = debounce.begin("Done.").unwrap();
+        let second = debounce.begin("").unwrap();
+
+        // Only the latest grace period can complete, keeping the turn's output
+        assert_eq!(debounce.complete(first), None);
+        assert_eq!(debounce.complete(second), Some("Done.".to_string()));
+
+        // A Stop hook arriving after the agent went idle is ignored
+        assert_eq!(debounce.begin(""), None);
+    }
+
+    #[test]
+    fn test_activity_cancels_pending_transition() {
+        let debounce = IdleDebounce::default();
+        let token = debounce.begin("Let me also check the tests").unwrap();
+        debounce.activity();
+        assert_eq!(debounce.complete(token), None);
+
+        // The next turn can go idle again
+        let token = debounce.begin("").unwrap();
+        assert_eq!(debounce.complete(token), Some(String::new()));
+    }
+}
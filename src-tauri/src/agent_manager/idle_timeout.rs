@@ -0,0 +1,262 @@
+// Idle timeout
+//
+// An agent that finished its work but was never stopped keeps its claude
+// process alive, and its cost is only recorded when it stops. The watchdog
+// checks every CHECK_INTERVAL for agents waiting for input (pending_input) with
+// no output for longer than the idle timeout of their source, and stops them the
+// way stop_agent does, which finalizes the run and records its cost. They drain
+// at once, without the manager locked. Each stop is logged and emitted as
+// agent:status with reason "idle_timeout".
+//
+// AGENT_IDLE_TIMEOUT_MINUTES sets the timeout (0 never stops idle agents) and
+// AGENT_IDLE_TIMEOUT_BY_SOURCE overrides it per source, e.g. "pipeline=10,ui=0".
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+use crate::commands::env_registry;
+use crate::events::AppEventEmitter;
+use crate::types::{AgentSource, AgentStatus, AgentStatusEvent, StopReason};
+
+use super::stopping::{finish_stops, PendingStop};
+use super::{summaries, AgentManager};
+
+/// Minutes an agent may wait for input when AGENT_IDLE_TIMEOUT_MINUTES is unset
+pub const DEFAULT_IDLE_TIMEOUT_MINUTES: u64 = 30;
+
+/// How often the watchdog looks for idle agents
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long agents of each source may wait for input (None never stops them)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdleTimeouts {
+    pub default: Option<Duration>,
+    pub by_source: HashMap<AgentSource, Option<Duration>>,
+}
+
+fn from_minutes(minutes: u64) -> Option<Duration> {
+    (minutes > 0).then(|| Duration::from_secs(minutes * 60))
+}
+
+impl Default for IdleTimeouts {
+    fn default() -> Self {
+        Self {
+            default: from_minutes(DEFAULT_IDLE_TIMEOUT_MINUTES),
+            by_source: HashMap::new(),
+        }
+    }
+}
+
+impl IdleTimeouts {
+    pub fn from_env() -> Self {
+        let default = env_registry::AGENT_IDLE_TIMEOUT_MINUTES
+            .var()
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_IDLE_TIMEOUT_MINUTES);
+        let overrides = env_registry::AGENT_IDLE_TIMEOUT_BY_SOURCE
+            .var()
+            .unwrap_or_default();
+        Self {
+            default: from_minutes(default),
+            by_source: parse_overrides(&overrides),
+        }
+    }
+
+    /// The idle timeout for agents of a source
+    pub fn for_source(&self, source: &AgentSource) -> Option<Duration> {
+        match self.by_source.get(source) {
+            Some(timeout) => *timeout,
+            None => self.default,
+        }
+    }
+}
+
+/// Parse "source=minutes" pairs, comma-separated. Unknown sources and bad
+/// numbers are skipped with a warning.
+pub fn parse_overrides(value: &str) -> HashMap<AgentSource, Option<Duration>> {
+    let mut overrides = HashMap::new();
+    for pair in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parsed = pair.split_once('=').and_then(|(source, mins)| {
+            Some((
+                AgentSource::parse(&source.trim().to_lowercase())?,
+                mins.trim().parse::<u64>().ok()?,
+            ))
+        });
+        match parsed {
+            Some((source, mins)) => {
+                overrides.insert(source, from_minutes(mins));
+            }
+            None => eprintln!(
+                "[IdleTimeout] Ignoring AGENT_IDLE_TIMEOUT_BY_SOURCE entry {:?}",
+                pair
+            ),
+        }
+    }
+    overrides
+}
+
+impl AgentManager {
+    /// Change how long agents of a source may wait for input (None never
+    /// stops them)
+    pub fn set_idle_timeout(&mut self, source: AgentSource, timeout: Option<Duration>) {
+        self.idle_timeouts.by_source.insert(source, timeout);
+    }
+
+    /// Stop agents that have waited for input longer than their source's idle
+    /// timeout, draining them at once. Returns their IDs.
+    pub async fn stop_idle_agents(&self, app_handle: &dyn AppEventEmitter) -> Vec<String> {
+        let stopped = self.begin_idle_stops().await.finish().await;
+        self.report_idle_stops(stopped, app_handle).await
+    }
+
+    /// Agents waiting for input longer than their source's idle timeout, with
+    /// how long they have waited
+    async fn idle_agents(&self) -> Vec<(String, Duration)> {
+        let agents = self.agents.lock().await;
+        let mut idle = Vec::new();
+        for (id, agent) in agents.iter() {
+            if matches!(agent.info.status, AgentStatus::Stopped | AgentStatus::Error)
+                || !*agent.pending_input.lock().await
+                || *agent.is_processing.lock().await
+                || !agent.prompt_queue.is_empty()
+            {
+                continue;
+            }
+            let Some(timeout) = self.idle_timeouts.for_source(&agent.info.source) else {
+                continue;
+            };
+            let idle_for = Instant::now().duration_since(*agent.last_activity.lock().await);
+            if idle_for > timeout {
+                idle.push((id.clone(), idle_for));
+            }
+        }
+        idle
+    }
+
+    /// Start stopping the idle agents
+    async fn begin_idle_stops(&self) -> IdleStops {
+        let idle = self.idle_agents().await;
+        let mut begun = Vec::with_capacity(idle.len());
+        for (agent_id, _) in &idle {
+            begun.push(self.begin_stop(agent_id, false, None).await);
+        }
+        IdleStops { idle, begun }
+    }
+
+    /// Log and emit the idle agents that were stopped. Returns their IDs.
+    async fn report_idle_stops(
+        &self,
+        stopped: Vec<(String, Duration)>,
+        app_handle: &dyn AppEventEmitter,
+    ) -> Vec<String> {
+        let mut ids = Vec::with_capacity(stopped.len());
+        for (agent_id, idle_for) in stopped {
+            let message = format!(
+                "Stopped agent after {} minutes without input",
+                idle_for.as_secs() / 60
+            );
+            eprintln!("[IdleTimeout] {}: {}", agent_id, message);
+            if let Some(ref logger) = self.logger {
+                let _ = logger
+                    .info("agent_manager", &message, Some(agent_id.clone()), None)
+                    .await;
+            }
+
+            let info = {
+                let agents = self.agents.lock().await;
+                match agents.get(&agent_id) {
+                    Some(agent) => Some(summaries::current_info(agent).await),
+                    None => None,
+                }
+            };
+            if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+                agent_id: agent_id.clone(),
+                status: AgentStatus::Stopped,
+                info,
+                reason: Some(StopReason::IdleTimeout),
+            }) {
+                let _ = app_handle.emit("agent:status", status_event);
+            }
+            if let Some(notice) = self.take_worktree_notice(&agent_id).await {
+                super::worktrees::notify_kept_worktree(app_handle, &notice);
+            }
+            ids.push(agent_id);
+        }
+        ids
+    }
+}
+
+/// Stops begun for idle agents, with how long each had waited
+struct IdleStops {
+    idle: Vec<(String, Duration)>,
+    begun: Vec<Result<PendingStop, String>>,
+}
+
+impl IdleStops {
+    /// Drain the agents concurrently. Returns those stopped, with how long
+    /// each had waited.
+    async fn finish(self) -> Vec<(String, Duration)> {
+        let ids: Vec<String> = self.idle.iter().map(|(id, _)| id.clone()).collect();
+        let outcomes = finish_stops(&ids, self.begun).await;
+        self.idle
+            .into_iter()
+            .zip(outcomes)
+            .filter_map(|((agent_id, idle_for), (_, result))| match result {
+                Ok(_) => Some((agent_id, idle_for)),
+                Err(e) => {
+                    eprintln!(
+                        "[IdleTimeout] Failed to stop idle agent {}: {}",
+                        agent_id, e
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Stop idle agents every CHECK_INTERVAL
+pub async fn run_idle_watchdog(
+    manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+) {
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        // The manager isn't locked while the agents drain
+        let pending = manager.lock().await.begin_idle_stops().await;
+        let stopped = pending.finish().await;
+        if !stopped.is_empty() {
+            manager
+                .lock()
+                .await
+                .report_idle_stops(stopped, app_handle.as_ref())
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_replace_the_default_per_source() {
+        let timeouts = IdleTimeouts {
+            default: from_minutes(30),
+            by_source: parse_overrides("pipeline=10, UI=0,bogus=5,pool=soon"),
+        };
+        assert_eq!(timeouts.by_source.len(), 2);
+        assert_eq!(
+            timeouts.for_source(&AgentSource::Pipeline),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(timeouts.for_source(&AgentSource::UI), None);
+        assert_eq!(
+            timeouts.for_source(&AgentSource::Pool),
+            Some(Duration::from_secs(1800))
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// MCP servers for worker agents
+//
+// Agents get MCP servers through the claude CLI's --mcp-config, a JSON file in
+// Claude's format: {"mcpServers": {"<name>": server}} where a server is
+// {"command", "args", "env"} for a local (stdio) server or {"type": "http" or
+// "sse", "url", "headers"} for a remote one. WORKER_MCP_CONFIG names a file with
+// the servers every agent gets; servers given when an agent is created are
+// added to those, replacing any of the same name. The merged config is checked
+// before the agent is spawned, written next to its hooks settings and removed
+// when the agent stops.
+
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+use crate::commands::env_registry;
+
+/// Check that a value is an MCP config claude will accept
+pub(crate) fn validate_mcp_config(config: &Value) -> Result<(), String> {
+    let servers = config
+        .get("mcpServers")
+        .and_then(Value::as_object)
+        .ok_or("MCP config must be an object with an \"mcpServers\" object")?;
+
+    for (name, server) in servers {
+        let fail = |problem: &str| Err(format!("MCP server '{}': {}", name, problem));
+        let Some(server) = server.as_object() else {
+            return fail("must be an object");
+        };
+        let string_field = |field: &str| server.get(field).and_then(Value::as_str);
+
+        let remote = match server.get("type") {
+            None => !server.contains_key("command") && server.contains_key("url"),
+            Some(Value::String(t)) if t == "stdio" => false,
+            Some(Value::String(t)) if t == "http" || t == "sse" => true,
+            Some(_) => return fail("type must be \"stdio\", \"http\" or \"sse\""),
+        };
+
+        if remote {
+            if !string_field("url").is_some_and(|url| !url.trim().is_empty()) {
+                return fail("url must be a non-empty string");
+            }
+            if !is_string_map(server.get("headers")) {
+                return fail("headers must map names to strings");
+            }
+        } else {
+            if !string_field("command").is_some_and(|c| !c.trim().is_empty()) {
+                return fail("command must be a non-empty string");
+            }
+            let args_ok = match server.get("args") {
+                None => true,
+                Some(args) => args
+                    .as_array()
+                    .is_some_and(|args| args.iter().all(Value::is_string)),
+            };
+            if !args_ok {
+                return fail("args must be an array of strings");
+            }
+            if !is_string_map(server.get("env")) {
+                return fail("env must map names to strings");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Absent, or an object whose values are all strings
+fn is_string_map(value: Option<&Value>) -> bool {
+    match value {
+        None => true,
+        Some(value) => value
+            .as_object()
+            .is_some_and(|map| map.values().all(Value::is_string)),
+    }
+}
+
+/// The servers from the file WORKER_MCP_CONFIG names, if it is set
+fn default_mcp_servers() -> Result<Map<String, Value>, String> {
+    let Some(path) = env_registry::WORKER_MCP_CONFIG
+        .var()
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+    else {
+        return Ok(Map::new());
+    };
+    let path = path.trim();
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read WORKER_MCP_CONFIG {}: {}", path, e))?;
+    let config: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("WORKER_MCP_CONFIG {} is not valid JSON: {}", path, e))?;
+    validate_mcp_config(&config).map_err(|e| format!("WORKER_MCP_CONFIG {}: {}", path, e))?;
+    Ok(servers(config))
+}
+
+fn servers(config: Value) -> Map<String, Value> {
+    match config {
+        Value::Object(mut config) => match config.remove("mcpServers") {
+            Some(Value::Object(servers)) => servers,
+            _ => Map::new(),
+        },
+        _ => Map::new(),
+    }
+}
+
+/// The default servers merged with an agent's own. None when there are no
+/// servers at all.
+pub(crate) fn resolve_mcp_config(mcp_servers: Option<&Value>) -> Result<Option<Value>, String> {
+    let mut merged = default_mcp_servers()?;
+    if let Some(config) = mcp_servers {
+        validate_mcp_config(config)?;
+        merged.extend(servers(config.clone()));
+    }
+    if merged.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::json!({ "mcpServers": merged })))
+}
+
+/// Write an agent's MCP config next to its hooks settings
+pub(crate) fn write_mcp_config(agent_id: &str, config: &Value) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("claude_mcp_{}.json", agent_id));
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize MCP config: {}", e))?;
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write MCP config file {:?}: {}", path, e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_mcp_config() {
+        let valid = json!({
+            "mcpServers": {
+                "internal": { "command": "npx", "args": ["-y", "internal-mcp"], "env": { "TOKEN": "x" } },
+                "docs": { "type": "http", "url": "https://mcp.example.com", "headers": { "Authorization": "Bearer x" } },
+                "events": { "type": "sse", "url": "https://mcp.example.com/sse" }
+            }
+        });
+        assert_eq!(validate_mcp_config(&valid), Ok(()));
+
+        let invalid = [
+            (json!({ "servers": {} }), "\"mcpServers\""),
+            (json!({ "mcpServers": { "a": "npx" } }), "must be an object"),
+            (json!({ "mcpServers": { "a": {} } }), "command"),
+            (
+                json!({ "mcpServers": { "a": { "command": "npx", "args": "-y" } } }),
+                "args",
+            ),
+            (
+                json!({ "mcpServers": { "a": { "command": "npx", "env": { "N": 1 } } } }),
+                "env",
+            ),
+            (json!({ "mcpServers": { "a": { "type": "http" } } }), "url"),
+            (
+                json!({ "mcpServers": { "a": { "type": "ws", "url": "x" } } }),
+                "type",
+            ),
+        ];
+        for (config, problem) in invalid {
+            let err = validate_mcp_config(&config).unwrap_err();
+            assert!(err.contains(problem), "{} should mention {}", err, problem);
+        }
+    }
+}
@@ -4,9 +4,9 @@
 // system messages, assistant responses (text and tool use), and
 // user messages (tool results).
 
-use crate::types::{AgentInputRequiredEvent, AgentStatus};
-
+use super::ansi;
 use super::event_handlers::StreamContext;
+use super::idle_debounce::IdleTransition;
 use super::output_builder::{extract_common_fields, OutputEventBuilder};
 use super::statistics::{increment_tool_calls, update_output_bytes};
 use super::stream_parser::{persist_output, store_in_buffer};
@@ -31,7 +31,7 @@ pub(crate) async fn handle_system_message(ctx: &StreamContext, json: &serde_json
 
     let _ = ctx
         .app_handle
-        .emit("agent:output", serde_json::to_value(output_event).unwrap());
+        .emit("agent:output", ansi::ui_payload(&output_event));
 
     // Persist to database
     persist_output(
@@ -40,6 +40,7 @@ pub(crate) async fn handle_system_message(ctx: &StreamContext, json: &serde_json
         &ctx.pipeline_id,
         "system",
         &content,
+        output_event.timestamp,
     )
     .await;
 }
@@ -76,6 +77,9 @@ pub(crate) async fn handle_assistant_message(ctx: &StreamContext, json: &serde_j
     let mut has_tool_use = false;
     let mut last_text_output = String::new();
 
+    // New output means the agent is still working
+    ctx.idle_debounce.activity();
+
     if let Some(message) = json.get("message") {
         if let Some(content_array) = message.get("content").and_then(|v| v.as_array()) {
             for content_block in content_array {
@@ -147,10 +151,18 @@ async fn handle_text_block(
         store_in_buffer(output_event.clone(), ctx.output_buffer.clone()).await;
         let _ = ctx
             .app_handle
-            .emit("agent:output", serde_json::to_value(output_event).unwrap());
+            .emit("agent:output", ansi::ui_payload(&output_event));
 
         // Persist to database
-        persist_output(&ctx.runs_db, &ctx.agent_id, &ctx.pipeline_id, "text", text).await;
+        persist_output(
+            &ctx.runs_db,
+            &ctx.agent_id,
+            &ctx.pipeline_id,
+            "text",
+            text,
+            output_event.timestamp,
+        )
+        .await;
     }
 }
 
@@ -196,7 +208,7 @@ async fn handle_tool_use_block(
     store_in_buffer(output_event.clone(), ctx.output_buffer.clone()).await;
     let _ = ctx
         .app_handle
-        .emit("agent:output", serde_json::to_value(output_event).unwrap());
+        .emit("agent:output", ansi::ui_payload(&output_event));
 
     // Persist to database
     persist_output(
@@ -205,6 +217,7 @@ async fn handle_tool_use_block(
         &ctx.pipeline_id,
         "tool_use",
         &content,
+        output_event.timestamp,
     )
     .await;
 }
@@ -223,28 +236,9 @@ async fn update_assistant_state(
         .unwrap_or("");
 
     if stop_reason == "end_turn" && !has_tool_use {
-        // Agent finished its turn without tool use - waiting for user input
-        *ctx.pending_input.lock().await = true;
-        *ctx.is_processing.lock().await = false;
-
-        // Update agent status to WaitingForInput
-        {
-            let mut agents = ctx.agents.lock().await;
-            if let Some(agent) = agents.get_mut(&ctx.agent_id) {
-                agent.info.status = AgentStatus::WaitingForInput;
-                agent.info.pending_input = true;
-            }
-        }
-
-        // Emit input required event
-        let _ = ctx.app_handle.emit(
-            "agent:input_required",
-            serde_json::to_value(AgentInputRequiredEvent {
-                agent_id: ctx.agent_id.clone(),
-                last_output: last_text_output,
-            })
-            .unwrap(),
-        );
+        // Agent finished its turn without tool use - waiting for user input,
+        // unless it starts another turn within the grace period
+        IdleTransition::from_stream(ctx).schedule(&last_text_output);
     } else if has_tool_use {
         *ctx.pending_input.lock().await = false;
     } else {
@@ -256,6 +250,7 @@ async fn update_assistant_state(
 /// Handle user messages (tool results)
 pub(crate) async fn handle_user_message(ctx: &StreamContext, json: &serde_json::Value) {
     let (session_id, uuid, parent_tool_use_id, subtype) = extract_common_fields(json);
+    ctx.idle_debounce.activity();
 
     if let Some(message) = json.get("message") {
         if let Some(content_array) = message.get("content").and_then(|v| v.as_array()) {
@@ -322,7 +317,7 @@ async fn handle_tool_result_block(
         store_in_buffer(output_event.clone(), ctx.output_buffer.clone()).await;
         let _ = ctx
             .app_handle
-            .emit("agent:output", serde_json::to_value(output_event).unwrap());
+            .emit("agent:output", ansi::ui_payload(&output_event));
 
         // Persist to database
         persist_output(
@@ -331,6 +326,7 @@ async fn handle_tool_result_block(
             &ctx.pipeline_id,
             output_type,
             &result_text,
+            output_event.timestamp,
         )
         .await;
     }
@@ -2,19 +2,43 @@
 //
 // Handles spawning, managing, and communicating with Claude CLI agent processes.
 
+mod agent_messages;
+mod ansi;
+pub mod auto_follow_up;
 pub mod claude_cli;
+mod cloning;
+mod conventions;
 mod database_ops;
 mod event_handlers;
+mod github_context;
+mod groups;
+pub mod handoff;
+mod hooks_config;
+mod idle_debounce;
+pub mod idle_timeout;
+pub mod mcp_config;
 mod message_handlers;
+mod output_buffer;
 mod output_builder;
+pub mod output_search;
+pub mod process_health;
 mod process_spawner;
+pub mod prompt_files;
+pub mod prompt_queue;
+pub mod rate_limit;
 mod result_handlers;
+mod run_labels;
+pub mod session_finalizer;
 mod statistics;
+mod stderr_buffer;
+pub mod stopping;
 mod stream_handler;
 mod stream_parser;
+mod summaries;
 mod types;
+mod worktrees;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
@@ -22,22 +46,65 @@ use tokio::time::Instant;
 
 use tokio::sync::mpsc;
 
-use crate::agent_runs_db::{AgentRunsDB, RunStatus};
+use crate::agent_runs_db::{AgentRun, AgentRunsDB, EventQueryFilters};
+use crate::directory_locks::{self, LockedAction};
+use crate::fault_injection::{check_fault, FaultPoint, SharedFaultHook};
 use crate::github;
 use crate::logger::Logger;
+use crate::memory_footprint::AgentBufferFootprint;
+use crate::meta_agent::SharedResultQueue;
+use crate::permission_profiles;
 use crate::security_monitor::SecurityMonitor;
 use crate::types::{
-    AgentActivityEvent, AgentInfo, AgentOutputEvent, AgentStatistics, AgentStatus,
-    AgentStatusEvent, AgentWakeEvent,
+    AgentGroup, AgentInfo, AgentMessage, AgentNote, AgentOutputEvent, AgentStatistics, AgentStatus,
+    AgentStatusEvent, AgentStopResult, AgentSummary, AgentWakeEvent, AppliedProfile, GitHubContext,
+    GroupMemberResult, GroupStatistics, NoteAuthor, StopOutcome, ToolRestrictions,
 };
 use crate::utils::time::now_millis;
 
-use database_ops::record_run_in_db;
-use process_spawner::{create_hooks_config, spawn_claude_process};
+use agent_messages::{
+    agent_message_limit, format_agent_message, message_pipeline, sender_name, MessageBus,
+};
+use auto_follow_up::FollowUpRequest;
+use database_ops::{record_run_in_db, reopen_run_in_db};
+use github_context::{refresh_agent_github_context, spawn_github_context_refresh};
+use idle_debounce::{IdleDebounce, IdleTransition};
+use idle_timeout::IdleTimeouts;
+use output_buffer::OutputBuffer;
+use process_spawner::{
+    api_keys_blocked, create_hooks_config, filter_extra_env, resolve_model, spawn_claude_process,
+};
+use prompt_files::PromptFileOptions;
+use prompt_queue::{PromptSendResult, QueuedPrompt};
+use session_finalizer::SessionFinalizer;
 use statistics::create_initial_stats;
+use stderr_buffer::StderrBuffer;
 use stream_handler::{spawn_stderr_handler, spawn_stdout_handler, StreamContext};
+use worktrees::WorktreeSetup;
+
+pub use output_buffer::OutputTypeFilter;
+pub use types::{AgentProcess, AgentSpawnOptions};
+
+/// Titles set with set_agent_title are kept shorter than this many characters
+pub const TITLE_CHAR_LIMIT: usize = 80;
+
+/// Collapse whitespace in a new agent title and check it fits
+fn validate_title(title: &str) -> Result<String, String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return Err("Title cannot be empty".to_string());
+    }
+    if title.chars().count() >= TITLE_CHAR_LIMIT {
+        return Err(format!(
+            "Title must be shorter than {} characters",
+            TITLE_CHAR_LIMIT
+        ));
+    }
+    Ok(title)
+}
 
-pub use types::AgentProcess;
+/// Called with a newly created agent's info once it is registered
+pub type AgentCreatedCallback = Arc<dyn Fn(&AgentInfo) + Send + Sync>;
 
 pub struct AgentManager {
     pub agents: Arc<Mutex<HashMap<String, AgentProcess>>>,
@@ -45,9 +112,22 @@ pub struct AgentManager {
     pub hook_port: u16,
     pub logger: Option<Arc<Logger>>,
     pub runs_db: Option<Arc<AgentRunsDB>>,
-    pub on_agent_created: Option<Arc<dyn Fn(String, crate::types::AgentSource) + Send + Sync>>,
+    /// Subscribers told about each new agent, in the order they were added
+    pub on_agent_created: Vec<AgentCreatedCallback>,
     /// Sender for waking meta-agent when agents reach terminal states
     pub agent_wake_tx: Arc<Mutex<Option<mpsc::Sender<AgentWakeEvent>>>>,
+    /// Fault hook for chaos tests (None in normal operation)
+    pub fault_hook: SharedFaultHook,
+    /// Caps messages relayed between agents
+    pub message_bus: Arc<MessageBus>,
+    /// Sender for the auto follow-up worker (None until the worker is started)
+    pub follow_up_tx: Option<mpsc::Sender<FollowUpRequest>>,
+    /// Records each agent's session end once
+    pub session_finalizer: Arc<SessionFinalizer>,
+    /// How long agents of each source may wait for input before being stopped
+    pub idle_timeouts: IdleTimeouts,
+    /// The meta-agent's result queue, where crashed agents are reported
+    pub result_queue: Option<SharedResultQueue>,
 }
 
 impl AgentManager {
@@ -58,8 +138,14 @@ impl AgentManager {
             hook_port,
             logger: None,
             runs_db: None,
-            on_agent_created: None,
+            on_agent_created: Vec::new(),
             agent_wake_tx: Arc::new(Mutex::new(None)),
+            fault_hook: None,
+            message_bus: Arc::new(MessageBus::new()),
+            follow_up_tx: None,
+            session_finalizer: Arc::new(SessionFinalizer::new()),
+            idle_timeouts: IdleTimeouts::from_env(),
+            result_queue: None,
         }
     }
 
@@ -70,8 +156,14 @@ impl AgentManager {
             hook_port,
             logger: Some(logger),
             runs_db: None,
-            on_agent_created: None,
+            on_agent_created: Vec::new(),
             agent_wake_tx: Arc::new(Mutex::new(None)),
+            fault_hook: None,
+            message_bus: Arc::new(MessageBus::new()),
+            follow_up_tx: None,
+            session_finalizer: Arc::new(SessionFinalizer::new()),
+            idle_timeouts: IdleTimeouts::from_env(),
+            result_queue: None,
         }
     }
 
@@ -86,22 +178,39 @@ impl AgentManager {
             hook_port,
             logger: Some(logger),
             runs_db: Some(runs_db),
-            on_agent_created: None,
+            on_agent_created: Vec::new(),
             agent_wake_tx: Arc::new(Mutex::new(None)),
+            fault_hook: None,
+            message_bus: Arc::new(MessageBus::new()),
+            follow_up_tx: None,
+            session_finalizer: Arc::new(SessionFinalizer::new()),
+            idle_timeouts: IdleTimeouts::from_env(),
+            result_queue: None,
         }
     }
 
+    /// Set the sender used to hand idle agents to the auto follow-up worker
+    pub fn set_follow_up_tx(&mut self, tx: mpsc::Sender<FollowUpRequest>) {
+        self.follow_up_tx = Some(tx);
+    }
+
     /// Set the agent wake sender (for waking meta-agent when agents reach terminal states)
     pub fn set_agent_wake_tx(&mut self, tx: Arc<Mutex<Option<mpsc::Sender<AgentWakeEvent>>>>) {
         self.agent_wake_tx = tx;
     }
 
-    /// Set callback to be invoked when an agent is created
-    pub fn set_on_agent_created<F>(&mut self, callback: F)
+    /// Install a fault hook so tests can inject failures into the agent lifecycle
+    #[cfg(feature = "fault-injection")]
+    pub fn set_fault_hook(&mut self, hook: Arc<dyn crate::fault_injection::FaultHook>) {
+        self.fault_hook = Some(hook);
+    }
+
+    /// Add a callback to be invoked when an agent is created
+    pub fn add_on_agent_created<F>(&mut self, callback: F)
     where
-        F: Fn(String, crate::types::AgentSource) + Send + Sync + 'static,
+        F: Fn(&AgentInfo) + Send + Sync + 'static,
     {
-        self.on_agent_created = Some(Arc::new(callback));
+        self.on_agent_created.push(Arc::new(callback));
     }
 
     pub async fn create_agent(
@@ -143,10 +252,11 @@ impl AgentManager {
             Vec::new(),
             source,
             app_handle,
-            None,
-            None,
-            model,
-            complexity,
+            AgentSpawnOptions {
+                model,
+                complexity,
+                ..Default::default()
+            },
         )
         .await
     }
@@ -159,6 +269,7 @@ impl AgentManager {
         generated_skill_names: Vec<String>,
         source: crate::types::AgentSource,
         app_handle: Arc<dyn crate::events::AppEventEmitter>,
+        options: AgentSpawnOptions,
     ) -> Result<String, String> {
         self.create_agent_with_pipeline(
             working_dir,
@@ -167,15 +278,13 @@ impl AgentManager {
             generated_skill_names,
             source,
             app_handle,
-            None,
-            None,
-            None,
-            None, // No complexity
+            options,
         )
         .await
     }
 
-    /// Create a new agent with optional pipeline linkage and title
+    /// Create a new agent with the given options. The permission profile
+    /// bound to `source` applies unless the options name another.
     #[allow(clippy::too_many_arguments)]
     pub async fn create_agent_with_pipeline(
         &self,
@@ -185,18 +294,165 @@ impl AgentManager {
         generated_skill_names: Vec<String>,
         source: crate::types::AgentSource,
         app_handle: Arc<dyn crate::events::AppEventEmitter>,
-        pipeline_id: Option<String>,
-        title: Option<String>,
-        model: Option<String>,
-        complexity: Option<String>,
+        options: AgentSpawnOptions,
+    ) -> Result<String, String> {
+        let worktree = WorktreeSetup::requested(options.use_worktree);
+        self.spawn_agent(
+            working_dir,
+            github_url,
+            generated_skill_names,
+            source,
+            app_handle,
+            options,
+            worktree,
+            None,
+        )
+        .await
+    }
+
+    /// Spawn an agent's CLI process and wire up its handlers: a new agent, or
+    /// with `resume` a crashed one continuing its run's Claude session.
+    /// `worktree` decides where it runs (options.use_worktree isn't read).
+    #[allow(clippy::too_many_arguments)]
+    async fn spawn_agent(
+        &self,
+        working_dir: String,
+        github_url: Option<String>,
+        generated_skill_names: Vec<String>,
+        source: crate::types::AgentSource,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+        options: AgentSpawnOptions,
+        worktree: WorktreeSetup,
+        resume: Option<AgentRun>,
     ) -> Result<String, String> {
-        let agent_id = uuid::Uuid::new_v4().to_string();
+        let AgentSpawnOptions {
+            pipeline_id,
+            title,
+            model,
+            complexity,
+            hooks_file,
+            permission_profile,
+            extra_env,
+            mcp_servers,
+            tools,
+            use_worktree: _,
+        } = options;
+        // Warn about (or refuse) working in a directory another pipeline has locked
+        directory_locks::check_agent_access(
+            &working_dir,
+            pipeline_id.as_deref(),
+            LockedAction::CreateAgent,
+            None,
+            Some(app_handle.as_ref()),
+        )?;
+
+        let agent_id = match &resume {
+            Some(run) => run.agent_id.clone(),
+            None => uuid::Uuid::new_v4().to_string(),
+        };
+        let resume_session = resume.as_ref().and_then(|run| run.session_id.clone());
+        let resuming = resume.is_some();
+
+        // A resumed agent keeps the profile its run was spawned under
+        let requested_profile = match &resume {
+            Some(run) => run.permission_profile.clone(),
+            None => permission_profile,
+        };
+        let profile = permission_profiles::resolve(
+            self.runs_db.as_deref(),
+            &source,
+            requested_profile.as_deref(),
+        )
+        .await?;
+
+        // A resumed agent keeps the model its run was started with
+        let model = resolve_model(match &resume {
+            Some(run) => run.model.clone(),
+            None => model,
+        });
+
+        // A resumed agent keeps the tool lists its run was spawned with
+        let tools = permission_profiles::validate_tool_restrictions(&match &resume {
+            Some(run) => ToolRestrictions {
+                allowed_tools: run.allowed_tools.clone(),
+                disallowed_tools: run.disallowed_tools.clone(),
+            },
+            None => tools,
+        })?;
+
+        let extra_env = filter_extra_env(extra_env, api_keys_blocked())?;
+        let mcp_config = mcp_config::resolve_mcp_config(mcp_servers.as_ref())?;
+
+        // A resumed agent keeps its title unless it is given a new one
+        let title = title.or_else(|| resume.as_ref().and_then(|run| run.title.clone()));
 
-        // Create hooks config
-        let settings_path = create_hooks_config(self.hook_port, &agent_id)?;
+        // A resumed agent carries on in its run's worktree
+        let worktree = match resume.as_ref().and_then(worktrees::run_worktree) {
+            Some(existing) => WorktreeSetup::Existing(existing),
+            None => worktree,
+        };
+        let new_worktree = matches!(worktree, WorktreeSetup::Create);
 
-        // Spawn claude process
-        let mut child = spawn_claude_process(&settings_path, &working_dir, &agent_id, model)?;
+        // Create hooks config, merged with the user's own hooks
+        let settings_path = create_hooks_config(
+            self.hook_port,
+            &agent_id,
+            &working_dir,
+            hooks_file.as_deref(),
+        )?;
+
+        let mcp_config_path = match mcp_config
+            .as_ref()
+            .map(|config| mcp_config::write_mcp_config(&agent_id, config))
+            .transpose()
+        {
+            Ok(path) => path,
+            Err(e) => {
+                let _ = std::fs::remove_file(&settings_path);
+                return Err(e);
+            }
+        };
+        let remove_config_files = || {
+            let _ = std::fs::remove_file(&settings_path);
+            if let Some(ref path) = mcp_config_path {
+                let _ = std::fs::remove_file(path);
+            }
+        };
+
+        // Add the agent's worktree, which becomes its working directory
+        let (worktree, working_dir) =
+            match worktrees::prepare_worktree(worktree, &working_dir, &agent_id).await {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    remove_config_files();
+                    return Err(e);
+                }
+            };
+
+        // Spawn claude process (removing the config files and a new worktree
+        // again if that fails)
+        let mut child = match check_fault(&self.fault_hook, FaultPoint::AgentSpawn).and_then(|_| {
+            spawn_claude_process(
+                &settings_path,
+                &working_dir,
+                &agent_id,
+                model.clone(),
+                profile.as_ref(),
+                &tools,
+                resume_session.as_deref(),
+                mcp_config_path.as_deref(),
+                &extra_env,
+            )
+        }) {
+            Ok(child) => child,
+            Err(e) => {
+                remove_config_files();
+                if let Some(worktree) = worktree.clone().filter(|_| new_worktree) {
+                    worktrees::remove_agent_worktree(worktree).await;
+                }
+                return Err(e);
+            }
+        };
 
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
         let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
@@ -223,14 +479,16 @@ impl AgentManager {
 
         let now = now_millis();
 
-        // Build GitHub context if available
-        let github_context = github::build_github_context(&working_dir, github_url.clone());
+        // Use the cached GitHub context so spawning never waits on git
+        let cached_github = github::github_context_cache().get(&working_dir, github_url.as_deref());
+        let needs_github_refresh = !cached_github.as_ref().is_some_and(|c| c.is_fresh);
+        let github_context = cached_github.and_then(|c| c.context);
 
         let agent_info = AgentInfo {
             id: agent_id.clone(),
             working_dir: working_dir.clone(),
             status: AgentStatus::Running,
-            session_id: None,
+            session_id: resume_session.clone(),
             last_activity: Some(now),
             is_processing: false,
             pending_input: false,
@@ -239,6 +497,17 @@ impl AgentManager {
             pooled: None,
             title,
             complexity,
+            latest_note: None,
+            label: None,
+            group_ids: pipeline_id
+                .as_deref()
+                .map(groups::pipeline_group_id)
+                .into_iter()
+                .collect(),
+            hooks_missing: false,
+            permission_profile: profile.as_ref().map(permission_profiles::applied),
+            model: model.clone(),
+            worktree,
         };
 
         // Store agent
@@ -246,27 +515,51 @@ impl AgentManager {
         let is_processing = Arc::new(Mutex::new(false));
         let pending_input = Arc::new(Mutex::new(false));
         let stats = Arc::new(Mutex::new(create_initial_stats(agent_id.clone())));
-        let output_buffer = Arc::new(Mutex::new(Vec::new()));
-
-        // Record run in database
-        record_run_in_db(
-            &self.runs_db,
-            &self.logger,
-            &agent_id,
-            &working_dir,
-            &github_url,
-            &github_context,
-            &source,
-            pipeline_id.clone(),
-            now,
-        )
-        .await;
+        let output_buffer = Arc::new(Mutex::new(OutputBuffer::new()));
+        let stderr_buffer = Arc::new(Mutex::new(StderrBuffer::default()));
+        let idle_debounce = Arc::new(IdleDebounce::default());
+
+        // Record the run in the database, or put the resumed one back to running
+        match resume {
+            Some(run) => {
+                if let Some(ref session_id) = resume_session {
+                    self.session_to_agent
+                        .lock()
+                        .await
+                        .insert(session_id.clone(), agent_id.clone());
+                }
+                reopen_run_in_db(&self.runs_db, &self.logger, run, now).await;
+            }
+            None => {
+                let effective_tools =
+                    permission_profiles::effective_tools(profile.as_ref(), &tools);
+                record_run_in_db(
+                    &self.runs_db,
+                    &self.logger,
+                    &agent_id,
+                    &working_dir,
+                    &github_url,
+                    &github_context,
+                    &source,
+                    pipeline_id.clone(),
+                    profile.map(|p| p.name),
+                    model,
+                    agent_info.title.clone(),
+                    extra_env.keys().cloned().collect(),
+                    effective_tools,
+                    agent_info.worktree.as_ref(),
+                    now,
+                )
+                .await;
+            }
+        }
 
         // Emit event to notify frontend about new agent
         if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
             agent_id: agent_id.clone(),
             status: AgentStatus::Running,
             info: Some(agent_info.clone()),
+            reason: None,
         }) {
             let _ = app_handle.emit("agent:status", status_event);
         }
@@ -282,9 +575,15 @@ impl AgentManager {
             pending_input: pending_input.clone(),
             stats: stats.clone(),
             output_buffer: output_buffer.clone(),
+            stderr_buffer: stderr_buffer.clone(),
             runs_db: self.runs_db.clone(),
             pipeline_id: pipeline_id.clone(),
             agent_wake_tx: self.agent_wake_tx.clone(),
+            fault_hook: self.fault_hook.clone(),
+            idle_debounce: idle_debounce.clone(),
+            follow_up_tx: self.follow_up_tx.clone(),
+            session_finalizer: self.session_finalizer.clone(),
+            result_queue: self.result_queue.clone(),
         };
 
         // Spawn stream handlers (capture JoinHandles for proper cleanup)
@@ -292,11 +591,47 @@ impl AgentManager {
         let stderr_handle = spawn_stderr_handler(
             stderr,
             agent_id.clone(),
-            app_handle,
+            app_handle.clone(),
             self.runs_db.clone(),
-            pipeline_id,
+            pipeline_id.clone(),
+            stats.clone(),
+            stderr_buffer.clone(),
         );
 
+        // Add the agent to its pipeline's group (a resumed one already is)
+        if let (Some(runs_db), Some(pipeline_id)) = (&self.runs_db, &pipeline_id) {
+            if !resuming {
+                let group = groups::pipeline_group(pipeline_id, &agent_id, now);
+                if let Err(e) = runs_db.save_agent_group(&group).await {
+                    eprintln!("Warning: Failed to add agent to pipeline group: {}", e);
+                }
+            }
+        }
+
+        // Keep the skills generated for this agent; stop_agent marks them removed
+        if let Some(ref runs_db) = self.runs_db {
+            for skill_name in &generated_skill_names {
+                let skill_dir = std::path::Path::new(&working_dir)
+                    .join(".claude")
+                    .join("skills")
+                    .join(skill_name);
+                match crate::generated_files::snapshot_artifact(
+                    crate::generated_files::GeneratedFileKind::Skill,
+                    skill_name,
+                    &skill_dir,
+                ) {
+                    Ok(mut record) => {
+                        record.agent_id = Some(agent_id.clone());
+                        record.pipeline_id = pipeline_id.clone();
+                        if let Err(e) = runs_db.insert_generated_artifact(&record).await {
+                            eprintln!("Warning: Failed to persist skill {}: {}", skill_name, e);
+                        }
+                    }
+                    Err(e) => eprintln!("Warning: Failed to snapshot skill {}: {}", skill_name, e),
+                }
+            }
+        }
+
         // Store agent with all handles for cleanup
         {
             let mut agents = self.agents.lock().await;
@@ -311,31 +646,145 @@ impl AgentManager {
                     pending_input,
                     stats,
                     output_buffer,
+                    stderr_buffer,
                     generated_skill_names,
+                    github_url: github_url.clone(),
                     settings_path: Some(settings_path),
+                    mcp_config_path,
+                    mcp_servers,
                     stdin_handle: Some(stdin_handle),
                     stdout_handle: Some(stdout_handle),
                     stderr_handle: Some(stderr_handle),
                     stopped_at: None,
+                    inject_conventions: true,
+                    idle_debounce,
+                    pipeline_id,
+                    extra_env,
+                    tools,
+                    keep_worktree: false,
+                    worktree_notice: None,
+                    auto_follow_up: false,
+                    auto_follow_ups: 0,
+                    prompt_files: Vec::new(),
+                    prompt_queue: Default::default(),
                 },
             );
         }
 
-        // Call the on_agent_created callback if set
-        if let Some(callback) = &self.on_agent_created {
-            callback(agent_id.clone(), source.clone());
+        if needs_github_refresh {
+            spawn_github_context_refresh(
+                self.agents.clone(),
+                self.runs_db.clone(),
+                app_handle,
+                agent_id.clone(),
+                working_dir,
+                github_url,
+            );
+        }
+
+        // Tell the subscribers about the new agent
+        for callback in &self.on_agent_created {
+            callback(&agent_info);
         }
 
         Ok(agent_id)
     }
 
+    /// Bring a crashed agent back under the same id: the CLI is respawned in
+    /// the run's working directory with `--resume` on the Claude session
+    /// stored with the run, and the run goes back to running
+    pub async fn resume_agent(
+        &self,
+        agent_id: &str,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) -> Result<String, String> {
+        let runs_db = self
+            .runs_db
+            .as_ref()
+            .ok_or_else(|| "Run history is not available".to_string())?;
+        let run = runs_db
+            .get_run(agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No run found for agent {}", agent_id))?;
+
+        if !run.can_resume {
+            return Err("This run cannot be resumed".to_string());
+        }
+        if !run.session_id.as_deref().is_some_and(|s| !s.is_empty()) {
+            return Err(format!(
+                "Run for agent {} has no Claude session to resume",
+                agent_id
+            ));
+        }
+        if !std::path::Path::new(&run.working_dir).is_dir() {
+            return Err(format!(
+                "Working directory {} no longer exists",
+                run.working_dir
+            ));
+        }
+
+        // A crashed agent may still be listed; its process is gone, so the
+        // entry is replaced
+        {
+            let mut agents = self.agents.lock().await;
+            if let Some(agent) = agents.get(agent_id) {
+                if !matches!(agent.info.status, AgentStatus::Stopped | AgentStatus::Error) {
+                    return Err(format!("Agent {} is still running", agent_id));
+                }
+                agents.remove(agent_id);
+            }
+        }
+        // Lets the resumed session be finalized when it ends
+        self.session_finalizer.forget(agent_id);
+
+        let source = crate::types::AgentSource::parse(&run.source)
+            .unwrap_or(crate::types::AgentSource::Manual);
+        // The profile, model, title and tool lists are restored from the run.
+        // Only the names of extra env vars are stored, so they can't be.
+        self.spawn_agent(
+            run.working_dir.clone(),
+            run.github_url.clone(),
+            Vec::new(),
+            source,
+            app_handle,
+            AgentSpawnOptions {
+                pipeline_id: run.pipeline_id.clone(),
+                ..Default::default()
+            },
+            WorktreeSetup::None, // The run's worktree, if it had one
+            Some(run),
+        )
+        .await
+    }
+
     pub async fn send_prompt(
         &self,
         agent_id: &str,
         prompt: &str,
         app_handle: Option<Arc<dyn crate::events::AppEventEmitter>>,
         security_monitor: Option<Arc<SecurityMonitor>>,
-    ) -> Result<(), String> {
+    ) -> Result<PromptSendResult, String> {
+        self.send_prompt_with_options(
+            agent_id,
+            prompt,
+            PromptFileOptions::default(),
+            app_handle,
+            security_monitor,
+        )
+        .await
+    }
+
+    /// Send a prompt, overriding when it is handed over in a file. A prompt
+    /// for an agent that is still processing is queued until its turn ends.
+    pub async fn send_prompt_with_options(
+        &self,
+        agent_id: &str,
+        prompt: &str,
+        file_options: PromptFileOptions,
+        app_handle: Option<Arc<dyn crate::events::AppEventEmitter>>,
+        security_monitor: Option<Arc<SecurityMonitor>>,
+    ) -> Result<PromptSendResult, String> {
         // Log prompt sending
         if let Some(ref logger) = self.logger {
             let _ = logger
@@ -348,6 +797,23 @@ impl AgentManager {
                 .await;
         }
 
+        // Warn about (or refuse) prompting into a directory another pipeline has locked
+        let lock_target = {
+            let agents = self.agents.lock().await;
+            agents
+                .get(agent_id)
+                .map(|agent| (agent.info.working_dir.clone(), agent.pipeline_id.clone()))
+        };
+        if let Some((working_dir, pipeline_id)) = lock_target {
+            directory_locks::check_agent_access(
+                &working_dir,
+                pipeline_id.as_deref(),
+                LockedAction::SendPrompt,
+                Some(agent_id),
+                app_handle.as_deref(),
+            )?;
+        }
+
         // Seed security expectations from the prompt (if security monitor is available)
         // This should be done BEFORE processing so the first tool call can be checked
         if let Some(ref monitor) = security_monitor {
@@ -359,35 +825,125 @@ impl AgentManager {
             }
         }
 
-        let agents = self.agents.lock().await;
+        // Look up the conventions digest for the agent's first prompt (unless opted out).
+        // The agents lock is not held while the digest is generated.
+        let conventions_dir = {
+            let agents = self.agents.lock().await;
+            match agents.get(agent_id) {
+                Some(agent) if agent.inject_conventions => {
+                    let is_first_prompt = agent.stats.lock().await.total_prompts == 0;
+                    is_first_prompt.then(|| agent.info.working_dir.clone())
+                }
+                _ => None,
+            }
+        };
+        let conventions_digest = match conventions_dir {
+            Some(dir) => conventions::conventions_digest(&dir).await,
+            None => None,
+        };
+        if conventions_digest.is_some() {
+            if let Some(ref logger) = self.logger {
+                let _ = logger
+                    .info(
+                        "agent_manager",
+                        "Injecting repository conventions into first prompt",
+                        Some(agent_id.to_string()),
+                        None,
+                    )
+                    .await;
+            }
+        }
+
+        let mut agents = self.agents.lock().await;
         let agent = agents
-            .get(agent_id)
+            .get_mut(agent_id)
             .ok_or_else(|| "Agent not found".to_string())?;
 
+        // A new prompt is the retry of a rate-limited turn
+        if agent.info.status == AgentStatus::RateLimited {
+            agent.info.status = AgentStatus::Running;
+        }
+
+        // Very large prompts are handed over in a file; only a preview is recorded
+        let prompt_file = if file_options.use_file(prompt.len(), prompt_files::threshold_bytes()) {
+            let path = prompt_files::write_prompt_file(&agent.info.working_dir, agent_id, prompt)?;
+            agent.prompt_files.push(path.clone());
+            Some(path)
+        } else {
+            None
+        };
+        let recorded_prompt = match &prompt_file {
+            Some(path) => prompt_files::recorded_preview(prompt, path),
+            None => prompt.to_string(),
+        };
+
+        let sent_prompt = match &prompt_file {
+            Some(path) => prompt_files::file_prompt(path, prompt.len()),
+            None => prompt.to_string(),
+        };
+        let agent_prompt = match conventions_digest.as_deref() {
+            Some(digest) => conventions::prepend_conventions(digest, &sent_prompt),
+            None => sent_prompt,
+        };
+        let json_line = prompt_queue::prompt_line(&agent_prompt)?;
+
+        // Writing into a turn in progress would interleave the prompts
+        let busy = *agent.is_processing.lock().await
+            && !matches!(agent.info.status, AgentStatus::Stopped | AgentStatus::Error);
+        if busy {
+            let queue_depth = agent.prompt_queue.push(QueuedPrompt {
+                id: uuid::Uuid::new_v4().to_string(),
+                prompt: recorded_prompt,
+                queued_at: now_millis(),
+                line: json_line,
+                conventions_injected: conventions_digest.is_some(),
+            });
+            if let Some(app_handle) = app_handle.as_ref() {
+                prompt_queue::emit_activity(
+                    app_handle.as_ref(),
+                    agent_id,
+                    true,
+                    false,
+                    queue_depth,
+                );
+            }
+            return Ok(PromptSendResult {
+                queued: true,
+                queue_depth,
+            });
+        }
+
         // Clear pending input flag and set processing
+        agent.idle_debounce.activity();
         *agent.pending_input.lock().await = false;
         *agent.is_processing.lock().await = true;
 
         // Emit activity event to update UI
         if let Some(app_handle) = app_handle.as_ref() {
-            if let Ok(activity_event) = serde_json::to_value(AgentActivityEvent {
-                agent_id: agent_id.to_string(),
-                is_processing: true,
-                pending_input: false,
-                last_activity: now_millis(),
-            }) {
-                let _ = app_handle.emit("agent:activity", activity_event);
-            }
+            prompt_queue::emit_activity(
+                app_handle.as_ref(),
+                agent_id,
+                true,
+                false,
+                agent.prompt_queue.len(),
+            );
         }
 
         // Increment prompt counter
+        let is_first_prompt = agent.stats.lock().await.total_prompts == 0;
         statistics::increment_prompts(&agent.stats).await;
 
         // Record prompt in database
         if let Some(ref runs_db) = self.runs_db {
-            let timestamp = now_millis();
-
-            if let Err(e) = runs_db.record_prompt(agent_id, prompt, timestamp).await {
+            if let Err(e) = prompt_queue::record_sent_prompt(
+                runs_db,
+                agent_id,
+                &recorded_prompt,
+                now_millis(),
+                conventions_digest.is_some(),
+            )
+            .await
+            {
                 if let Some(ref logger) = self.logger {
                     let _ = logger
                         .error(
@@ -399,16 +955,16 @@ impl AgentManager {
                         .await;
                 }
             }
+        }
 
-            // Update run in database
-            if let Ok(Some(mut run)) = runs_db.get_run(agent_id).await {
-                run.last_activity = timestamp;
-                run.total_prompts += 1;
-                if run.total_prompts == 1 {
-                    run.initial_prompt = Some(prompt.to_string());
-                }
-                let _ = runs_db.update_run(&run).await;
-            }
+        if is_first_prompt && agent.info.label.is_none() {
+            run_labels::spawn_run_labeling(
+                self.agents.clone(),
+                self.runs_db.clone(),
+                app_handle.clone(),
+                agent_id.to_string(),
+                recorded_prompt,
+            );
         }
 
         let stdin_tx = agent
@@ -416,21 +972,6 @@ impl AgentManager {
             .as_ref()
             .ok_or_else(|| "Agent stdin not available".to_string())?;
 
-        // Format as stream-json message (JSONL format)
-        let message = serde_json::json!({
-            "type": "user",
-            "message": {
-                "role": "user",
-                "content": [{
-                    "type": "text",
-                    "text": prompt
-                }]
-            }
-        });
-
-        let json_line = serde_json::to_string(&message)
-            .map_err(|e| format!("Failed to serialize message: {}", e))?;
-
         stdin_tx.send(json_line).await.map_err(|e| {
             if let Some(ref logger) = self.logger {
                 let _ = tokio::task::block_in_place(|| {
@@ -449,125 +990,759 @@ impl AgentManager {
             format!("Failed to send prompt: {}", e)
         })?;
 
+        Ok(PromptSendResult {
+            queued: false,
+            queue_depth: agent.prompt_queue.len(),
+        })
+    }
+
+    /// Prompts waiting for an agent's current turn to end, oldest first
+    pub async fn get_queued_prompts(&self, agent_id: &str) -> Result<Vec<QueuedPrompt>, String> {
+        let agents = self.agents.lock().await;
+        let agent = agents
+            .get(agent_id)
+            .ok_or_else(|| format!("Agent {} not found", agent_id))?;
+        Ok(agent.prompt_queue.list())
+    }
+
+    /// Drop a queued prompt before it is sent. Returns the prompts still queued.
+    pub async fn cancel_queued_prompt(
+        &self,
+        agent_id: &str,
+        prompt_id: &str,
+        app_handle: Option<Arc<dyn crate::events::AppEventEmitter>>,
+    ) -> Result<Vec<QueuedPrompt>, String> {
+        let mut agents = self.agents.lock().await;
+        let agent = agents
+            .get_mut(agent_id)
+            .ok_or_else(|| format!("Agent {} not found", agent_id))?;
+        agent
+            .prompt_queue
+            .cancel(prompt_id)
+            .ok_or_else(|| format!("No queued prompt {} for agent {}", prompt_id, agent_id))?;
+
+        if let Some(app_handle) = app_handle.as_ref() {
+            prompt_queue::emit_activity(
+                app_handle.as_ref(),
+                agent_id,
+                *agent.is_processing.lock().await,
+                *agent.pending_input.lock().await,
+                agent.prompt_queue.len(),
+            );
+        }
+        Ok(agent.prompt_queue.list())
+    }
+
+    /// Enable or disable repository conventions injection for an agent's first prompt
+    pub async fn set_agent_conventions_injection(
+        &self,
+        agent_id: &str,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let mut agents = self.agents.lock().await;
+        let agent = agents
+            .get_mut(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+        agent.inject_conventions = enabled;
         Ok(())
     }
 
-    pub async fn stop_agent(&self, agent_id: &str) -> Result<(), String> {
-        // Get final stats and extract handles before stopping
-        let (final_stats, stdin_handle, stdout_handle, stderr_handle, settings_path, session_id) = {
-            let mut agents = self.agents.lock().await;
-            let agent = agents
-                .get_mut(agent_id)
-                .ok_or_else(|| "Agent not found".to_string())?;
+    /// Opt an agent in or out of auto follow-ups on "shall I proceed?" questions
+    pub async fn set_agent_auto_follow_up(
+        &self,
+        agent_id: &str,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let mut agents = self.agents.lock().await;
+        let agent = agents
+            .get_mut(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+        agent.auto_follow_up = enabled;
+        Ok(())
+    }
+
+    /// Keep an agent's git worktree when it stops (to inspect its changes) or
+    /// remove it
+    pub async fn set_agent_keep_worktree(&self, agent_id: &str, keep: bool) -> Result<(), String> {
+        let mut agents = self.agents.lock().await;
+        let agent = agents
+            .get_mut(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+        agent.keep_worktree = keep;
+        Ok(())
+    }
+
+    /// Take the notice left when stopping an agent kept its worktree because
+    /// of uncommitted changes
+    pub async fn take_worktree_notice(&self, agent_id: &str) -> Option<String> {
+        let mut agents = self.agents.lock().await;
+        agents.get_mut(agent_id)?.worktree_notice.take()
+    }
+
+    /// Suspend or resume an agent's process (SIGSTOP/SIGCONT)
+    pub async fn set_agent_paused(&self, agent_id: &str, paused: bool) -> Result<(), String> {
+        let agents = self.agents.lock().await;
+        let agent = agents
+            .get(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+        let pid = agent
+            .child
+            .as_ref()
+            .and_then(|c| c.id())
+            .ok_or_else(|| "Agent process is not running".to_string())?;
+
+        #[cfg(unix)]
+        {
+            let signal = if paused { "-STOP" } else { "-CONT" };
+            let status = std::process::Command::new("kill")
+                .arg(signal)
+                .arg(pid.to_string())
+                .status()
+                .map_err(|e| format!("Failed to signal agent process: {}", e))?;
+            if !status.success() {
+                return Err(format!("Failed to signal agent process {}", pid));
+            }
+            Ok(())
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (pid, paused);
+            Err("Pausing agents is not supported on this platform".to_string())
+        }
+    }
+
+    pub async fn list_agents(&self) -> Vec<AgentInfo> {
+        let agents = self.agents.lock().await;
+        let mut infos = Vec::with_capacity(agents.len());
+        for agent in agents.values() {
+            infos.push(summaries::current_info(agent).await);
+        }
+        infos
+    }
+
+    /// Every agent with its card details: counters, latest reply snippet, todo
+    /// progress and whether it is in `alerted_agents`
+    pub async fn list_agents_detailed(
+        &self,
+        alerted_agents: &HashSet<String>,
+    ) -> Vec<AgentSummary> {
+        let handles: Vec<_> = {
+            let agents = self.agents.lock().await;
+            agents
+                .values()
+                .map(|a| (a.info.clone(), a.stats.clone(), a.output_buffer.clone()))
+                .collect()
+        };
+        let todos = crate::hook_server::get_all_agent_todos().await;
+        let todos = &todos;
+
+        futures::future::join_all(handles.into_iter().map(
+            |(mut info, stats, output_buffer)| async move {
+                let stats = {
+                    let stats = stats.lock().await;
+                    info.hooks_missing = summaries::hooks_missing(&info.id, &stats);
+                    summaries::stats_summary(&stats)
+                };
+                let last_message =
+                    summaries::last_message_snippet(output_buffer.lock().await.events());
+                let todo_progress = todos
+                    .get(&info.id)
+                    .and_then(|items| summaries::todo_progress(items));
+                let security_alert = alerted_agents.contains(&info.id);
+                AgentSummary {
+                    info,
+                    stats,
+                    last_message,
+                    todo_progress,
+                    security_alert,
+                }
+            },
+        ))
+        .await
+    }
+
+    pub async fn get_agent_by_session(&self, session_id: &str) -> Option<String> {
+        let map = self.session_to_agent.lock().await;
+        map.get(session_id).cloned()
+    }
+
+    /// Handle an agent's Stop hook: mark it idle once the grace period passes
+    pub async fn schedule_idle_after_stop(
+        &self,
+        agent_id: &str,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) {
+        let transition = {
+            let agents = self.agents.lock().await;
+            agents.get(agent_id).map(|agent| IdleTransition {
+                agent_id: agent_id.to_string(),
+                agents: self.agents.clone(),
+                app_handle,
+                is_processing: agent.is_processing.clone(),
+                pending_input: agent.pending_input.clone(),
+                agent_wake_tx: self.agent_wake_tx.clone(),
+                follow_up_tx: self.follow_up_tx.clone(),
+                debounce: agent.idle_debounce.clone(),
+                runs_db: self.runs_db.clone(),
+            })
+        };
+        if let Some(transition) = transition {
+            transition.schedule("");
+        }
+    }
+
+    /// Record hook activity (e.g. PreToolUse), cancelling a pending idle transition
+    pub async fn note_agent_activity(&self, agent_id: &str) {
+        if let Some(agent) = self.agents.lock().await.get(agent_id) {
+            agent.idle_debounce.activity();
+        }
+    }
+
+    /// Add a tool call's duration (PreToolUse to PostToolUse) to the agent's statistics
+    pub async fn record_tool_timing(&self, agent_id: &str, tool_name: &str, ms: u64) {
+        if let Some(agent) = self.agents.lock().await.get(agent_id) {
+            statistics::record_tool_timing(&agent.stats, tool_name, ms).await;
+        }
+    }
+
+    /// Get info for a specific agent
+    pub async fn get_agent_info(&self, agent_id: &str) -> Option<AgentInfo> {
+        let agents = self.agents.lock().await;
+        match agents.get(agent_id) {
+            Some(agent) => Some(summaries::current_info(agent).await),
+            None => None,
+        }
+    }
+
+    /// The permission profile an agent was spawned under, if any
+    pub async fn applied_profile(&self, agent_id: &str) -> Option<AppliedProfile> {
+        let agents = self.agents.lock().await;
+        agents.get(agent_id)?.info.permission_profile.clone()
+    }
+
+    /// Pin a note to an agent and make it the agent's latest note
+    ///
+    /// Works for live agents and for runs that only exist in the database.
+    pub async fn add_agent_note(
+        &self,
+        agent_id: &str,
+        author: NoteAuthor,
+        text: &str,
+    ) -> Result<AgentNote, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Note text cannot be empty".to_string());
+        }
+
+        if !self.agent_exists(agent_id).await? {
+            return Err(format!("Agent {} not found", agent_id));
+        }
 
-            // Step 1: Drop stdin_tx first to signal stdin handler to exit
-            agent.stdin_tx = None;
+        let mut note = AgentNote {
+            id: None,
+            agent_id: agent_id.to_string(),
+            author,
+            text: text.to_string(),
+            timestamp: now_millis(),
+        };
+        if let Some(db) = &self.runs_db {
+            let id = db
+                .add_agent_note(&note)
+                .await
+                .map_err(|e| format!("Failed to save note: {}", e))?;
+            note.id = Some(id);
+        }
+
+        if let Some(agent) = self.agents.lock().await.get_mut(agent_id) {
+            agent.info.latest_note = Some(note.clone());
+        }
+
+        Ok(note)
+    }
+
+    /// Relay a message from one agent into another agent's prompt queue
+    ///
+    /// The message is prefixed as coming from another agent, counted against the
+    /// conversation's message cap, recorded and emitted as `agent:message`.
+    pub async fn send_agent_message(
+        &self,
+        from_agent_id: &str,
+        to_agent_id: &str,
+        text: &str,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) -> Result<AgentMessage, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Message text cannot be empty".to_string());
+        }
+        if from_agent_id == to_agent_id {
+            return Err("An agent cannot message itself".to_string());
+        }
 
+        let (pipeline_id, sender) = {
+            let agents = self.agents.lock().await;
+            let from = agents
+                .get(from_agent_id)
+                .ok_or_else(|| format!("Agent {} not found", from_agent_id))?;
+            let to = agents
+                .get(to_agent_id)
+                .ok_or_else(|| format!("Agent {} not found", to_agent_id))?;
+            if to.info.status == AgentStatus::Stopped {
+                return Err(format!("Agent {} has stopped", to_agent_id));
+            }
             (
-                Some(agent.stats.clone()),
-                agent.stdin_handle.take(),
-                agent.stdout_handle.take(),
-                agent.stderr_handle.take(),
-                agent.settings_path.take(),
-                agent.info.session_id.clone(),
+                message_pipeline(from.pipeline_id.as_deref(), to.pipeline_id.as_deref())?,
+                sender_name(&from.info),
             )
         };
 
-        // Step 2: Abort all handler tasks
-        if let Some(handle) = stdin_handle {
-            handle.abort();
+        let key = self.message_bus.reserve(
+            pipeline_id.as_deref(),
+            from_agent_id,
+            to_agent_id,
+            agent_message_limit(),
+        )?;
+        // Note: No security_monitor for relayed messages
+        if let Err(e) = self
+            .send_prompt(
+                to_agent_id,
+                &format_agent_message(&sender, text),
+                Some(app_handle.clone()),
+                None,
+            )
+            .await
+        {
+            self.message_bus.release(&key);
+            return Err(e);
+        }
+
+        let mut message = AgentMessage {
+            id: None,
+            pipeline_id,
+            from_agent_id: from_agent_id.to_string(),
+            to_agent_id: to_agent_id.to_string(),
+            text: text.to_string(),
+            timestamp: now_millis(),
+        };
+        if let Some(db) = &self.runs_db {
+            // The message was delivered, so a failed write is only logged
+            match db.add_agent_message(&message).await {
+                Ok(id) => message.id = Some(id),
+                Err(e) => {
+                    if let Some(ref logger) = self.logger {
+                        let _ = logger
+                            .error(
+                                "agent_manager",
+                                &format!("Failed to record agent message: {}", e),
+                                Some(to_agent_id.to_string()),
+                                None,
+                            )
+                            .await;
+                    }
+                }
+            }
         }
-        if let Some(handle) = stdout_handle {
-            handle.abort();
+
+        if let Ok(payload) = serde_json::to_value(&message) {
+            let _ = app_handle.emit("agent:message", payload);
         }
-        if let Some(handle) = stderr_handle {
-            handle.abort();
+        Ok(message)
+    }
+
+    /// Messages relayed to or from an agent, oldest first
+    pub async fn get_agent_messages(&self, agent_id: &str) -> Result<Vec<AgentMessage>, String> {
+        match &self.runs_db {
+            Some(db) => db
+                .get_agent_messages(agent_id)
+                .await
+                .map_err(|e| format!("Failed to load agent messages: {}", e)),
+            None => Ok(Vec::new()),
         }
+    }
 
-        // Step 2.5: Clean up session_to_agent map
-        if let Some(sid) = session_id {
-            let mut session_map = self.session_to_agent.lock().await;
-            session_map.remove(&sid);
+    /// Get all notes for an agent, oldest first
+    pub async fn get_agent_notes(&self, agent_id: &str) -> Result<Vec<AgentNote>, String> {
+        match &self.runs_db {
+            Some(db) => db
+                .get_agent_notes(agent_id)
+                .await
+                .map_err(|e| format!("Failed to load notes: {}", e)),
+            // Without a database only the latest note is kept
+            None => Ok(self
+                .get_agent_info(agent_id)
+                .await
+                .and_then(|info| info.latest_note)
+                .into_iter()
+                .collect()),
         }
+    }
 
-        // Step 3: Give stream handlers a moment to notice abort before killing child
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    /// Whether an agent is live or has a run in the database
+    async fn agent_exists(&self, agent_id: &str) -> Result<bool, String> {
+        if self.agents.lock().await.contains_key(agent_id) {
+            return Ok(true);
+        }
+        match &self.runs_db {
+            Some(db) => Ok(db
+                .get_run(agent_id)
+                .await
+                .map_err(|e| format!("Failed to look up run: {}", e))?
+                .is_some()),
+            None => Ok(false),
+        }
+    }
 
-        // Step 4: Now kill the child process and update state
-        let mut agents = self.agents.lock().await;
-        let agent = agents
-            .get_mut(agent_id)
-            .ok_or_else(|| "Agent not found".to_string())?;
+    /// Create a named group of agents (live or historical)
+    pub async fn create_agent_group(
+        &self,
+        name: &str,
+        agent_ids: &[String],
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) -> Result<AgentGroup, String> {
+        let name = groups::validate_group_name(name)?;
+        let agent_ids = groups::dedup_agent_ids(agent_ids);
+        if agent_ids.is_empty() {
+            return Err("A group needs at least one agent".to_string());
+        }
+        for agent_id in &agent_ids {
+            if !self.agent_exists(agent_id).await? {
+                return Err(format!("Agent {} not found", agent_id));
+            }
+        }
 
-        if let Some(mut child) = agent.child.take() {
-            let _ = child.kill().await;
+        let group = AgentGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            pipeline_id: None,
+            agent_ids,
+            created_at: now_millis(),
+        };
+        if let Some(db) = &self.runs_db {
+            db.save_agent_group(&group)
+                .await
+                .map_err(|e| format!("Failed to save group: {}", e))?;
         }
 
-        // Clean up generated skills
-        if !agent.generated_skill_names.is_empty() {
-            let working_dir = agent.info.working_dir.clone();
-            let skill_names = agent.generated_skill_names.clone();
-            if let Err(e) =
-                crate::skill_generator::cleanup_generated_skills(&working_dir, &skill_names)
-            {
-                eprintln!("Warning: Failed to cleanup skills: {}", e);
+        // Mirror membership into live agents so the agent list can group them
+        let updated: Vec<AgentInfo> = {
+            let mut agents = self.agents.lock().await;
+            group
+                .agent_ids
+                .iter()
+                .filter_map(|id| agents.get_mut(id))
+                .map(|agent| {
+                    agent.info.group_ids.push(group.id.clone());
+                    agent.info.clone()
+                })
+                .collect()
+        };
+        for info in updated {
+            if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+                agent_id: info.id.clone(),
+                status: info.status.clone(),
+                info: Some(info),
+                reason: None,
+            }) {
+                let _ = app_handle.emit("agent:status", status_event);
             }
         }
 
-        agent.info.status = AgentStatus::Stopped;
-        agent.stopped_at = Some(Instant::now());
+        Ok(group)
+    }
+
+    /// Get a group with its members
+    pub async fn get_agent_group(&self, group_id: &str) -> Result<AgentGroup, String> {
+        self.list_agent_groups()
+            .await?
+            .into_iter()
+            .find(|g| g.id == group_id)
+            .ok_or_else(|| format!("Agent group {} not found", group_id))
+    }
 
-        // Step 5: Clean up hooks config file
-        if let Some(path) = settings_path {
-            if let Err(e) = std::fs::remove_file(&path) {
-                eprintln!("Warning: Failed to cleanup hooks config {:?}: {}", path, e);
+    /// Get all agent groups, oldest first
+    pub async fn list_agent_groups(&self) -> Result<Vec<AgentGroup>, String> {
+        if let Some(db) = &self.runs_db {
+            return db
+                .list_agent_groups()
+                .await
+                .map_err(|e| format!("Failed to load agent groups: {}", e));
+        }
+
+        // Without a database only live agents' memberships are known
+        let agents = self.agents.lock().await;
+        let mut found: Vec<AgentGroup> = Vec::new();
+        for agent in agents.values() {
+            for group_id in &agent.info.group_ids {
+                match found.iter_mut().find(|g| &g.id == group_id) {
+                    Some(group) => group.agent_ids.push(agent.info.id.clone()),
+                    None => found.push(AgentGroup {
+                        id: group_id.clone(),
+                        name: group_id.clone(),
+                        pipeline_id: agent
+                            .pipeline_id
+                            .clone()
+                            .filter(|p| groups::pipeline_group_id(p) == *group_id),
+                        agent_ids: vec![agent.info.id.clone()],
+                        created_at: 0,
+                    }),
+                }
             }
         }
+        Ok(found)
+    }
 
-        // Update run in database
-        if let Some(ref runs_db) = self.runs_db {
-            if let Ok(Some(mut run)) = runs_db.get_run(agent_id).await {
-                let now = now_millis();
-
-                run.status = RunStatus::Stopped;
-                run.ended_at = Some(now);
-                run.last_activity = now;
-
-                // Update stats if available
-                if let Some(stats) = final_stats {
-                    let stats_lock = stats.lock().await;
-                    run.total_prompts = stats_lock.total_prompts;
-                    run.total_tool_calls = stats_lock.total_tool_calls;
-                    run.total_output_bytes = stats_lock.total_output_bytes;
-                    run.total_tokens_used = stats_lock.total_tokens_used;
-                    run.total_cost_usd = stats_lock.total_cost_usd;
-
-                    if let Some(ref model_usage) = stats_lock.model_usage {
-                        run.model_usage = serde_json::to_string(&model_usage).ok();
+    /// Stop every live member of a group at once, returning a result per
+    /// stopped agent
+    pub async fn stop_agent_group(&self, group_id: &str) -> Result<Vec<GroupMemberResult>, String> {
+        let group = self.get_agent_group(group_id).await?;
+        let running: Vec<String> = {
+            let agents = self.agents.lock().await;
+            group
+                .agent_ids
+                .into_iter()
+                .filter(|id| {
+                    agents
+                        .get(id)
+                        .is_some_and(|a| a.info.status != AgentStatus::Stopped)
+                })
+                .collect()
+        };
+
+        Ok(self
+            .stop_agents(&running, false)
+            .await
+            .into_iter()
+            .map(|(agent_id, result)| GroupMemberResult {
+                agent_id,
+                success: result.is_ok(),
+                error: result.err(),
+            })
+            .collect())
+    }
+
+    /// Stop every agent, or only those from `source` and/or working in
+    /// `working_dir` (or below it), returning an outcome per agent instead of
+    /// giving up on the first error. Running agents are killed, all at once,
+    /// and each is emitted as agent:status.
+    pub async fn stop_all(
+        &self,
+        source: Option<&crate::types::AgentSource>,
+        working_dir: Option<&str>,
+        app_handle: &dyn crate::events::AppEventEmitter,
+    ) -> Vec<AgentStopResult> {
+        let matching: Vec<(String, AgentStatus)> = {
+            let agents = self.agents.lock().await;
+            let mut matching: Vec<_> = agents
+                .values()
+                .filter(|a| source.is_none() || source == Some(&a.info.source))
+                .filter(|a| match working_dir {
+                    Some(dir) => std::path::Path::new(&a.info.working_dir).starts_with(dir),
+                    None => true,
+                })
+                .map(|a| (a.info.id.clone(), a.info.status.clone()))
+                .collect();
+            matching.sort_by(|a, b| a.0.cmp(&b.0));
+            matching
+        };
+
+        let (stopped, running): (Vec<_>, Vec<_>) = matching
+            .into_iter()
+            .partition(|(_, status)| *status == AgentStatus::Stopped);
+        let mut results: Vec<AgentStopResult> = stopped
+            .into_iter()
+            .map(|(agent_id, _)| AgentStopResult {
+                agent_id,
+                outcome: StopOutcome::AlreadyStopped,
+                error: None,
+            })
+            .collect();
+
+        // Killed rather than drained: stopping everything shouldn't wait on
+        // each agent to finish writing
+        let running: Vec<String> = running.into_iter().map(|(id, _)| id).collect();
+        for (agent_id, result) in self.stop_agents(&running, true).await {
+            match result {
+                Ok(_) => {
+                    if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+                        agent_id: agent_id.clone(),
+                        status: AgentStatus::Stopped,
+                        info: self.get_agent_info(&agent_id).await,
+                        reason: None,
+                    }) {
+                        let _ = app_handle.emit("agent:status", status_event);
+                    }
+                    if let Some(notice) = self.take_worktree_notice(&agent_id).await {
+                        worktrees::notify_kept_worktree(app_handle, &notice);
                     }
+                    results.push(AgentStopResult {
+                        agent_id,
+                        outcome: StopOutcome::Stopped,
+                        error: None,
+                    });
                 }
-
-                let _ = runs_db.update_run(&run).await;
+                Err(e) => results.push(AgentStopResult {
+                    agent_id,
+                    outcome: StopOutcome::Failed,
+                    error: Some(e),
+                }),
             }
         }
+        results.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+        results
+    }
 
-        Ok(())
+    /// Send the same prompt to every member of a group, returning a result per member
+    pub async fn send_prompt_to_group(
+        &self,
+        group_id: &str,
+        prompt: &str,
+        app_handle: Option<Arc<dyn crate::events::AppEventEmitter>>,
+        security_monitor: Option<Arc<SecurityMonitor>>,
+    ) -> Result<Vec<GroupMemberResult>, String> {
+        if prompt.trim().is_empty() {
+            return Err("Prompt cannot be empty".to_string());
+        }
+        let group = self.get_agent_group(group_id).await?;
+
+        let mut results = Vec::with_capacity(group.agent_ids.len());
+        for agent_id in group.agent_ids {
+            let status = self.get_agent_info(&agent_id).await.map(|info| info.status);
+            let result = match status {
+                None => Err("Agent is no longer running".to_string()),
+                Some(AgentStatus::Stopped) => Err("Agent has stopped".to_string()),
+                Some(_) => self
+                    .send_prompt(
+                        &agent_id,
+                        prompt,
+                        app_handle.clone(),
+                        security_monitor.clone(),
+                    )
+                    .await
+                    .map(|_| ()),
+            };
+            results.push(GroupMemberResult {
+                agent_id,
+                success: result.is_ok(),
+                error: result.err(),
+            });
+        }
+        Ok(results)
     }
 
-    pub async fn list_agents(&self) -> Vec<AgentInfo> {
-        let agents = self.agents.lock().await;
-        agents.values().map(|a| a.info.clone()).collect()
+    /// Cost, tokens and tool calls summed over a group's members
+    ///
+    /// Live members count their current statistics; others their recorded run.
+    pub async fn get_group_statistics(&self, group_id: &str) -> Result<GroupStatistics, String> {
+        let group = self.get_agent_group(group_id).await?;
+        let mut totals = GroupStatistics {
+            group_id: group.id,
+            agent_count: group.agent_ids.len() as u32,
+            ..Default::default()
+        };
+
+        for agent_id in &group.agent_ids {
+            let live = {
+                let agents = self.agents.lock().await;
+                agents
+                    .get(agent_id)
+                    .map(|a| (a.info.status.clone(), a.stats.clone()))
+            };
+            match live {
+                Some((status, stats)) => {
+                    if !matches!(status, AgentStatus::Stopped | AgentStatus::Error) {
+                        totals.active_count += 1;
+                    }
+                    groups::add_live_stats(&mut totals, &*stats.lock().await);
+                }
+                None => {
+                    if let Some(db) = &self.runs_db {
+                        if let Ok(Some(run)) = db.get_run(agent_id).await {
+                            groups::add_run_stats(&mut totals, &run);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(totals)
     }
 
-    pub async fn get_agent_by_session(&self, session_id: &str) -> Option<String> {
-        let map = self.session_to_agent.lock().await;
-        map.get(session_id).cloned()
+    /// Replace the label of a live agent or a historical run
+    pub async fn relabel_run(
+        &self,
+        agent_id: &str,
+        label: &str,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) -> Result<String, String> {
+        let label = run_labels::validate_label(label)?;
+        run_labels::apply_run_label(
+            &self.agents,
+            &self.runs_db,
+            Some(&app_handle),
+            agent_id,
+            &label,
+        )
+        .await?;
+        Ok(label)
     }
 
-    /// Get info for a specific agent
-    pub async fn get_agent_info(&self, agent_id: &str) -> Option<AgentInfo> {
-        let agents = self.agents.lock().await;
-        agents.get(agent_id).map(|a| a.info.clone())
+    /// Rename a live agent, updating its info, its run record and the UI
+    pub async fn set_agent_title(
+        &self,
+        agent_id: &str,
+        title: &str,
+        app_handle: &dyn crate::events::AppEventEmitter,
+    ) -> Result<AgentInfo, String> {
+        let title = validate_title(title)?;
+        let info = {
+            let mut agents = self.agents.lock().await;
+            let agent = agents
+                .get_mut(agent_id)
+                .ok_or_else(|| format!("Agent {} not found", agent_id))?;
+            agent.info.title = Some(title.clone());
+            summaries::current_info(agent).await
+        };
+
+        if let Some(ref db) = self.runs_db {
+            if let Err(e) = db.update_run_title(agent_id, &title).await {
+                eprintln!("[AgentManager] Failed to save title of {}: {}", agent_id, e);
+            }
+        }
+
+        if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+            agent_id: agent_id.to_string(),
+            status: info.status.clone(),
+            info: Some(info.clone()),
+            reason: None,
+        }) {
+            let _ = app_handle.emit("agent:status", status_event);
+        }
+        Ok(info)
+    }
+
+    /// Rebuild an agent's GitHub context now, bypassing the cache TTL
+    pub async fn refresh_github_context(
+        &self,
+        agent_id: &str,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) -> Result<Option<GitHubContext>, String> {
+        let (working_dir, github_url) = {
+            let agents = self.agents.lock().await;
+            let agent = agents
+                .get(agent_id)
+                .ok_or_else(|| format!("Agent {} not found", agent_id))?;
+            (agent.info.working_dir.clone(), agent.github_url.clone())
+        };
+
+        refresh_agent_github_context(
+            self.agents.clone(),
+            self.runs_db.clone(),
+            app_handle,
+            agent_id.to_string(),
+            working_dir,
+            github_url,
+        )
+        .await
     }
 
     pub async fn get_agent_statistics(&self, agent_id: &str) -> Result<AgentStatistics, String> {
@@ -580,10 +1755,26 @@ impl AgentManager {
         Ok(stats)
     }
 
+    /// An agent's last `last_n` buffered outputs (all when 0), optionally
+    /// only of one kind, with ANSI escape codes removed (see ansi.rs)
     pub async fn get_agent_outputs(
         &self,
         agent_id: &str,
         last_n: usize,
+        output_type: Option<OutputTypeFilter>,
+    ) -> Result<Vec<AgentOutputEvent>, String> {
+        let outputs = self
+            .get_raw_agent_outputs(agent_id, last_n, output_type)
+            .await?;
+        Ok(outputs.into_iter().map(ansi::cleaned).collect())
+    }
+
+    /// Like get_agent_outputs, but with content exactly as the agent sent it
+    pub async fn get_raw_agent_outputs(
+        &self,
+        agent_id: &str,
+        last_n: usize,
+        output_type: Option<OutputTypeFilter>,
     ) -> Result<Vec<AgentOutputEvent>, String> {
         let agents = self.agents.lock().await;
         let agent = agents
@@ -591,12 +1782,113 @@ impl AgentManager {
             .ok_or_else(|| "Agent not found".to_string())?;
 
         let buffer = agent.output_buffer.lock().await;
-        let outputs = if last_n == 0 || last_n >= buffer.len() {
-            buffer.clone()
-        } else {
-            buffer[buffer.len() - last_n..].to_vec()
+        Ok(buffer.latest(last_n, output_type))
+    }
+
+    /// An agent's outputs stamped after `since_timestamp` (ms, all when None),
+    /// the last `last_n` of them (all when 0). When the buffer has evicted
+    /// outputs that were asked for, they are read from the runs database.
+    /// `raw` keeps ANSI escape codes.
+    pub async fn get_agent_output_history(
+        &self,
+        agent_id: &str,
+        last_n: usize,
+        output_type: Option<OutputTypeFilter>,
+        since_timestamp: Option<i64>,
+        raw: bool,
+    ) -> Result<Vec<AgentOutputEvent>, String> {
+        let (mut outputs, oldest, evicted) = {
+            let agents = self.agents.lock().await;
+            let agent = agents
+                .get(agent_id)
+                .ok_or_else(|| "Agent not found".to_string())?;
+            let buffer = agent.output_buffer.lock().await;
+            (
+                buffer.latest_since(0, output_type, since_timestamp),
+                buffer.oldest_timestamp(),
+                buffer.evicted() > 0,
+            )
         };
-        Ok(outputs)
+
+        // The buffer answers on its own unless outputs asked for were evicted
+        let enough = last_n > 0 && outputs.len() >= last_n;
+        let reaches_back =
+            matches!((since_timestamp, oldest), (Some(since), Some(oldest)) if oldest <= since);
+        let runs_db = self
+            .runs_db
+            .as_ref()
+            .filter(|_| evicted && !enough && !reaches_back);
+        if let Some(runs_db) = runs_db {
+            let filters = EventQueryFilters {
+                agent_id: Some(agent_id.to_string()),
+                since_timestamp: since_timestamp.map(|t| t + 1),
+                until_timestamp: oldest.map(|t| t - 1),
+                limit: last_n
+                    .checked_sub(outputs.len())
+                    .filter(|n| *n > 0 && output_type.is_none()),
+                ..Default::default()
+            };
+            let records = runs_db
+                .query_agent_outputs(filters)
+                .await
+                .map_err(|e| format!("Failed to load agent outputs: {}", e))?;
+            // Newest first from the database; stderr never enters the buffer
+            let mut older: Vec<AgentOutputEvent> = records
+                .into_iter()
+                .rev()
+                .filter(|r| r.output_type != "stderr")
+                .filter(|r| match output_type {
+                    Some(filter) => filter.matches(&r.output_type),
+                    None => true,
+                })
+                .map(output_buffer::from_record)
+                .collect();
+            older.append(&mut outputs);
+            outputs = older;
+        }
+
+        if last_n > 0 && last_n < outputs.len() {
+            outputs.drain(..outputs.len() - last_n);
+        }
+        if raw {
+            Ok(outputs)
+        } else {
+            Ok(outputs.into_iter().map(ansi::cleaned).collect())
+        }
+    }
+
+    /// Change how many outputs an agent keeps in memory, evicting the oldest
+    /// if it now holds too many. Returns the new cap.
+    pub async fn set_output_buffer_size(
+        &self,
+        agent_id: &str,
+        max_outputs: usize,
+    ) -> Result<usize, String> {
+        if max_outputs == 0 {
+            return Err("Output buffer size must be at least 1".to_string());
+        }
+        let agents = self.agents.lock().await;
+        let agent = agents
+            .get(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+        let mut buffer = agent.output_buffer.lock().await;
+        buffer.set_max_events(max_outputs);
+        Ok(buffer.max_events())
+    }
+
+    /// Approximate bytes held by each agent's output buffer
+    pub async fn output_buffer_footprints(&self) -> Vec<AgentBufferFootprint> {
+        let agents = self.agents.lock().await;
+        let mut footprints = Vec::with_capacity(agents.len());
+        for (agent_id, agent) in agents.iter() {
+            let buffer = agent.output_buffer.lock().await;
+            footprints.push(AgentBufferFootprint {
+                agent_id: agent_id.clone(),
+                outputs: buffer.len(),
+                bytes: buffer.bytes(),
+            });
+        }
+        footprints
     }
 
     /// Remove an agent from memory (data is already persisted to DB)
@@ -630,6 +1922,7 @@ impl AgentManager {
         for id in &to_remove {
             eprintln!("[AgentManager] Cleanup: removing stopped agent {}", id);
             agents.remove(id);
+            self.session_finalizer.forget(id);
         }
 
         to_remove
@@ -0,0 +1,368 @@
+// Output buffer - An agent's recent outputs, capped by count and by bytes
+//
+// Agents keep their latest outputs in memory for GetAgentOutput and the UI
+// (everything is also persisted to the runs database). The buffer holds at
+// most AGENT_OUTPUT_BUFFER_SIZE outputs, which can be changed per agent. Some
+// outputs - file dumps, long tool results - are huge, so the buffer is also
+// capped at AGENT_OUTPUT_BUFFER_MB: the oldest outputs are evicted until it
+// fits, and a single output larger than the whole cap is kept with its
+// content cut short. Sizes are reported to crate::memory_footprint.
+//
+// Evicted outputs are read back from the database when more history is asked
+// for than the buffer holds (AgentManager::get_agent_output_history).
+
+use serde::Deserialize;
+
+use crate::agent_runs_db::AgentOutputRecord;
+use crate::commands::env_registry;
+use crate::memory_footprint;
+use crate::types::AgentOutputEvent;
+use crate::utils::string::truncate_utf8;
+
+/// Default maximum outputs kept per agent (AGENT_OUTPUT_BUFFER_SIZE)
+pub const DEFAULT_MAX_OUTPUTS: usize = 100;
+
+/// Rough fixed size of an output event (ids, type, metadata)
+const EVENT_OVERHEAD_BYTES: usize = 256;
+
+/// Appended to an output cut short to fit the cap
+const TRUNCATED_MARKER: &str = "\n... (output truncated to fit the agent's output buffer)";
+
+/// Kind of output to return from the buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputTypeFilter {
+    Text,
+    ToolResults,
+    Errors,
+}
+
+impl OutputTypeFilter {
+    pub fn matches(&self, output_type: &str) -> bool {
+        output_type
+            == match self {
+                Self::Text => "text",
+                Self::ToolResults => "tool_result",
+                Self::Errors => "error",
+            }
+    }
+}
+
+/// Outputs kept per agent unless overridden for the agent
+pub fn max_outputs_from_env() -> usize {
+    env_registry::AGENT_OUTPUT_BUFFER_SIZE
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_OUTPUTS)
+}
+
+pub struct OutputBuffer {
+    events: Vec<AgentOutputEvent>,
+    /// Approximate size of each event, index-aligned with `events`
+    sizes: Vec<usize>,
+    bytes: usize,
+    max_events: usize,
+    max_bytes: usize,
+    /// Outputs evicted so far
+    evicted: usize,
+}
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::with_caps(
+            max_outputs_from_env(),
+            memory_footprint::output_buffer_cap_bytes(),
+        )
+    }
+
+    pub fn with_caps(max_events: usize, max_bytes: usize) -> Self {
+        Self {
+            events: Vec::new(),
+            sizes: Vec::new(),
+            bytes: 0,
+            max_events,
+            max_bytes,
+            evicted: 0,
+        }
+    }
+
+    pub fn max_events(&self) -> usize {
+        self.max_events
+    }
+
+    /// Change how many outputs are kept, evicting the oldest if over
+    pub fn set_max_events(&mut self, max_events: usize) {
+        self.max_events = max_events.max(1);
+        self.evict_to_caps();
+    }
+
+    /// Add an output, evicting the oldest ones to stay under both caps
+    pub fn push(&mut self, mut event: AgentOutputEvent) {
+        let mut size = approx_event_bytes(&event);
+        if size > self.max_bytes {
+            let keep = self
+                .max_bytes
+                .saturating_sub(EVENT_OVERHEAD_BYTES + TRUNCATED_MARKER.len());
+            event.content = format!(
+                "{}{}",
+                truncate_utf8(&event.content, keep),
+                TRUNCATED_MARKER
+            );
+            // The raw JSON holds the same content; the buffer can't afford both
+            event.parsed_json = None;
+            size = approx_event_bytes(&event);
+        }
+
+        self.events.push(event);
+        self.sizes.push(size);
+        self.bytes += size;
+        memory_footprint::add_agent_output(size);
+        self.evict_to_caps();
+    }
+
+    /// Evict the oldest outputs (never the newest) until both caps hold
+    fn evict_to_caps(&mut self) {
+        let mut evict = 0;
+        let mut evicted_bytes = 0;
+        while self.events.len() - evict > 1
+            && (self.events.len() - evict > self.max_events
+                || self.bytes - evicted_bytes > self.max_bytes)
+        {
+            evicted_bytes += self.sizes[evict];
+            evict += 1;
+        }
+        if evict > 0 {
+            self.events.drain(..evict);
+            self.sizes.drain(..evict);
+            self.bytes -= evicted_bytes;
+            self.evicted += evict;
+            memory_footprint::release_agent_output(evicted_bytes);
+        }
+    }
+
+    pub fn events(&self) -> &[AgentOutputEvent] {
+        &self.events
+    }
+
+    /// The last `last_n` outputs (all when 0), only of one kind if filtered
+    pub fn latest(
+        &self,
+        last_n: usize,
+        output_type: Option<OutputTypeFilter>,
+    ) -> Vec<AgentOutputEvent> {
+        self.latest_since(last_n, output_type, None)
+    }
+
+    /// Like `latest`, but only outputs stamped after `since` (ms)
+    pub fn latest_since(
+        &self,
+        last_n: usize,
+        output_type: Option<OutputTypeFilter>,
+        since: Option<i64>,
+    ) -> Vec<AgentOutputEvent> {
+        let mut matching: Vec<&AgentOutputEvent> = self
+            .events
+            .iter()
+            .filter(|e| match output_type {
+                Some(filter) => filter.matches(&e.output_type),
+                None => true,
+            })
+            .filter(|e| match since {
+                Some(since) => e.timestamp.is_some_and(|t| t > since),
+                None => true,
+            })
+            .collect();
+        if last_n > 0 && last_n < matching.len() {
+            matching.drain(..matching.len() - last_n);
+        }
+        matching.into_iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Approximate bytes held
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Outputs evicted so far (only in the database now)
+    pub fn evicted(&self) -> usize {
+        self.evicted
+    }
+
+    /// Timestamp of the oldest output still held
+    pub fn oldest_timestamp(&self) -> Option<i64> {
+        self.events.first().and_then(|e| e.timestamp)
+    }
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for OutputBuffer {
+    fn drop(&mut self) {
+        memory_footprint::release_agent_output(self.bytes);
+    }
+}
+
+/// Approximate in-memory size of an output event
+fn approx_event_bytes(event: &AgentOutputEvent) -> usize {
+    EVENT_OVERHEAD_BYTES
+        + event.content.len()
+        + event
+            .parsed_json
+            .as_ref()
+            .map(memory_footprint::approx_json_bytes)
+            .unwrap_or(0)
+}
+
+/// An output read back from the runs database
+pub(crate) fn from_record(record: AgentOutputRecord) -> AgentOutputEvent {
+    AgentOutputEvent {
+        agent_id: record.agent_id,
+        output_type: record.output_type,
+        content: record.content,
+        parsed_json: None,
+        metadata: record
+            .metadata
+            .as_deref()
+            .and_then(|m| serde_json::from_str(m).ok()),
+        session_id: None,
+        uuid: None,
+        parent_tool_use_id: None,
+        subtype: None,
+        timestamp: Some(record.timestamp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(content: String) -> AgentOutputEvent {
+        AgentOutputEvent {
+            agent_id: "agent-1".to_string(),
+            output_type: "text".to_string(),
+            parsed_json: Some(serde_json::json!({ "text": content })),
+            content,
+            metadata: None,
+            session_id: None,
+            uuid: None,
+            parent_tool_use_id: None,
+            subtype: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_oversized_outputs_are_evicted_to_the_byte_cap() {
+        let cap = 100_000;
+        let mut buffer = OutputBuffer::with_caps(500, cap);
+
+        for i in 0..20 {
+            buffer.push(output(format!("{}{}", i, "x".repeat(20_000))));
+            assert!(
+                buffer.bytes() <= cap,
+                "{} bytes after push {}",
+                buffer.bytes(),
+                i
+            );
+        }
+        // Only the newest outputs that fit are left, in order
+        assert!(buffer.len() < 20 && buffer.len() >= 2);
+        assert!(buffer.events().last().unwrap().content.starts_with("19"));
+        assert_eq!(
+            buffer.bytes(),
+            buffer
+                .events()
+                .iter()
+                .map(approx_event_bytes)
+                .sum::<usize>()
+        );
+
+        // One output bigger than the whole cap is kept, cut short
+        buffer.push(output("y".repeat(cap * 3)));
+        assert_eq!(buffer.len(), 1);
+        assert!(buffer.bytes() <= cap);
+        let kept = &buffer.events()[0];
+        assert!(kept.content.ends_with(TRUNCATED_MARKER));
+        assert!(kept.parsed_json.is_none());
+    }
+
+    #[test]
+    fn test_entry_cap_still_applies() {
+        let mut buffer = OutputBuffer::with_caps(3, usize::MAX);
+        for i in 0..5 {
+            buffer.push(output(i.to_string()));
+        }
+        let contents: Vec<&str> = buffer.events().iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(contents, vec!["2", "3", "4"]);
+        assert_eq!(buffer.evicted(), 2);
+
+        // Lowering the cap evicts right away
+        buffer.set_max_events(1);
+        assert_eq!(buffer.events()[0].content, "4");
+        assert_eq!(buffer.evicted(), 4);
+        assert_eq!(buffer.max_events(), 1);
+    }
+
+    #[test]
+    fn test_latest_filters_by_type_before_taking_the_last_n() {
+        let mut buffer = OutputBuffer::with_caps(500, usize::MAX);
+        for (i, output_type) in ["text", "tool_result", "error", "tool_result", "text"]
+            .iter()
+            .enumerate()
+        {
+            let mut event = output(i.to_string());
+            event.output_type = output_type.to_string();
+            buffer.push(event);
+        }
+
+        let contents = |events: Vec<AgentOutputEvent>| -> Vec<String> {
+            events.into_iter().map(|e| e.content).collect()
+        };
+        assert_eq!(contents(buffer.latest(2, None)), vec!["3", "4"]);
+        assert_eq!(
+            contents(buffer.latest(0, Some(OutputTypeFilter::ToolResults))),
+            vec!["1", "3"]
+        );
+        assert_eq!(
+            contents(buffer.latest(1, Some(OutputTypeFilter::Text))),
+            vec!["4"]
+        );
+        assert_eq!(
+            contents(buffer.latest(5, Some(OutputTypeFilter::Errors))),
+            vec!["2"]
+        );
+    }
+
+    #[test]
+    fn test_latest_since_skips_older_outputs() {
+        let mut buffer = OutputBuffer::with_caps(500, usize::MAX);
+        for i in 0..4 {
+            let mut event = output(i.to_string());
+            event.timestamp = Some(100 + i);
+            buffer.push(event);
+        }
+
+        let contents: Vec<String> = buffer
+            .latest_since(0, None, Some(101))
+            .into_iter()
+            .map(|e| e.content)
+            .collect();
+        assert_eq!(contents, vec!["2", "3"]);
+        assert_eq!(buffer.latest_since(1, None, Some(101))[0].content, "3");
+        assert_eq!(buffer.oldest_timestamp(), Some(100));
+    }
+}
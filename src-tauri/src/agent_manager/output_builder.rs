@@ -1,5 +1,6 @@
 // Output event builder for consistent event creation
 
+use super::ansi;
 use crate::types::{AgentOutputEvent, OutputMetadata};
 
 /// Builder for creating AgentOutputEvent instances
@@ -91,6 +92,7 @@ impl OutputEventBuilder {
                 line_count: self.line_count,
                 byte_size: self.byte_size,
                 is_truncated: self.is_truncated,
+                contains_ansi: ansi::contains_ansi(&self.content),
             }),
             session_id: self.session_id,
             uuid: self.uuid,
@@ -0,0 +1,283 @@
+// Searching agent output
+//
+// search_agent_outputs finds the agents whose output mentions a file name or
+// error string. The output buffers of agents still in memory are scanned
+// directly, and the agent_outputs table is searched through its trigram
+// full-text index (agent_outputs_fts, kept up to date by triggers), which
+// also covers agents from earlier sessions and outputs the buffers evicted.
+// Both match a case-insensitive substring of at least MIN_SEARCH_CHARS
+// characters. An output found in both places (same agent, type, timestamp and
+// text) is listed once, as live. Each
+// match comes with a one-line snippet around the first occurrence, which is
+// wrapped in `**`.
+
+use std::collections::HashSet;
+
+use crate::agent_runs_db::{AgentRunsDB, MIN_SEARCH_CHARS};
+use crate::types::OutputSearchMatch;
+
+use super::ansi;
+use super::AgentManager;
+
+/// Matches returned when no limit is given
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Upper bound for the limit
+pub const MAX_SEARCH_LIMIT: usize = 500;
+
+/// Characters of context kept on each side of a match
+const SNIPPET_CONTEXT_CHARS: usize = 60;
+
+/// Replace each run of whitespace with a single space
+fn collapse_whitespace(chars: &[char]) -> String {
+    let mut out = String::with_capacity(chars.len());
+    let mut in_space = false;
+    for &c in chars {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// Index of the first case-insensitive occurrence of `query` in `text`
+fn find_ignore_case(text: &[char], query: &[char]) -> Option<usize> {
+    if query.is_empty() || query.len() > text.len() {
+        return None;
+    }
+    (0..=text.len() - query.len()).find(|&i| {
+        text[i..i + query.len()]
+            .iter()
+            .zip(query)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+    })
+}
+
+/// One line of `content` around the first case-insensitive occurrence of
+/// `query`, with the occurrence wrapped in `**` and ellipses where the line
+/// was cut. Without an occurrence the start of the content is used.
+pub fn highlight_snippet(content: &str, query: &str) -> String {
+    let text: Vec<char> = ansi::strip_ansi(content).chars().collect();
+    let query: Vec<char> = query.chars().collect();
+
+    let Some(at) = find_ignore_case(&text, &query) else {
+        let end = text.len().min(2 * SNIPPET_CONTEXT_CHARS);
+        let line = collapse_whitespace(&text[..end]);
+        let ellipsis = if end < text.len() { "..." } else { "" };
+        return format!("{}{}", line.trim(), ellipsis);
+    };
+
+    let start = at.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let match_end = at + query.len();
+    let end = (match_end + SNIPPET_CONTEXT_CHARS).min(text.len());
+    format!(
+        "{}{}**{}**{}{}",
+        if start > 0 { "..." } else { "" },
+        collapse_whitespace(&text[start..at]).trim_start(),
+        collapse_whitespace(&text[at..match_end]),
+        collapse_whitespace(&text[match_end..end]).trim_end(),
+        if end < text.len() { "..." } else { "" }
+    )
+}
+
+/// A query the search accepts, trimmed
+pub(crate) fn search_query(query: &str) -> Result<&str, String> {
+    let query = query.trim();
+    if query.chars().count() < MIN_SEARCH_CHARS {
+        return Err(format!(
+            "Search query must be at least {} characters",
+            MIN_SEARCH_CHARS
+        ));
+    }
+    Ok(query)
+}
+
+/// Persisted outputs containing `query`, newest first
+pub(crate) async fn search_persisted_outputs(
+    runs_db: &AgentRunsDB,
+    query: &str,
+    agent_id: Option<&str>,
+    limit: usize,
+) -> Result<Vec<OutputSearchMatch>, String> {
+    let records = runs_db
+        .search_agent_outputs(query, agent_id, limit)
+        .await
+        .map_err(|e| format!("Failed to search agent outputs: {}", e))?;
+    Ok(records
+        .into_iter()
+        .map(|record| OutputSearchMatch {
+            snippet: highlight_snippet(&record.content, query),
+            agent_id: record.agent_id,
+            output_type: record.output_type,
+            timestamp: record.timestamp,
+            live: false,
+        })
+        .collect())
+}
+
+/// Drop outputs listed twice (live and persisted: same agent, type,
+/// timestamp and snippet), keeping the first, then keep the newest `limit`.
+/// The same text output at different times is listed each time.
+fn merge_matches(matches: Vec<OutputSearchMatch>, limit: usize) -> Vec<OutputSearchMatch> {
+    let mut seen = HashSet::new();
+    let mut merged: Vec<OutputSearchMatch> = matches
+        .into_iter()
+        .filter(|m| {
+            seen.insert((
+                m.agent_id.clone(),
+                m.output_type.clone(),
+                m.timestamp,
+                m.snippet.clone(),
+            ))
+        })
+        .collect();
+    merged.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    merged.truncate(limit);
+    merged
+}
+
+impl AgentManager {
+    /// Outputs in the buffers of agents in memory (only `agent_id`'s if
+    /// given) that contain `query`, the newest `limit` of each agent
+    async fn search_live_outputs(
+        &self,
+        query: &str,
+        agent_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<OutputSearchMatch> {
+        let needle = query.to_lowercase();
+        let agents = self.agents.lock().await;
+        let mut matches = Vec::new();
+        for (id, agent) in agents.iter() {
+            if agent_id.is_some_and(|wanted| wanted != id.as_str()) {
+                continue;
+            }
+            let buffer = agent.output_buffer.lock().await;
+            let found = buffer
+                .events()
+                .iter()
+                .rev()
+                .filter(|event| {
+                    ansi::strip_ansi(&event.content)
+                        .to_lowercase()
+                        .contains(&needle)
+                })
+                .take(limit)
+                .map(|event| OutputSearchMatch {
+                    agent_id: id.clone(),
+                    output_type: event.output_type.clone(),
+                    snippet: highlight_snippet(&event.content, query),
+                    timestamp: event.timestamp.unwrap_or_default(),
+                    live: true,
+                });
+            matches.extend(found);
+        }
+        matches
+    }
+
+    /// Outputs of every agent (only `agent_id`'s if given), live or
+    /// persisted, that contain `query`, newest first
+    pub async fn search_agent_outputs(
+        &self,
+        query: &str,
+        agent_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<OutputSearchMatch>, String> {
+        let query = search_query(query)?;
+        let limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+
+        let mut matches = self.search_live_outputs(query, agent_id, limit).await;
+        if let Some(db) = &self.runs_db {
+            matches.extend(search_persisted_outputs(db, query, agent_id, limit).await?);
+        }
+        Ok(merge_matches(matches, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(agent_id: &str, snippet: &str, timestamp: i64, live: bool) -> OutputSearchMatch {
+        OutputSearchMatch {
+            agent_id: agent_id.to_string(),
+            output_type: "text".to_string(),
+            snippet: snippet.to_string(),
+            timestamp,
+            live,
+        }
+    }
+
+    #[test]
+    fn test_snippet_highlights_the_first_match() {
+        assert_eq!(
+            highlight_snippet(
+                "Edited src/Payments.rs\n\nand ran   the tests",
+                "payments.rs"
+            ),
+            "Edited src/**Payments.rs** and ran the tests"
+        );
+
+        let long = format!("{} payments.rs {}", "a".repeat(100), "b".repeat(100));
+        let snippet = highlight_snippet(&long, "payments.rs");
+        assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+        assert!(snippet.contains(" **payments.rs** "));
+
+        assert_eq!(
+            highlight_snippet("no match here", "payments"),
+            "no match here"
+        );
+    }
+
+    #[test]
+    fn test_merge_prefers_live_matches_and_keeps_the_newest() {
+        let merged = merge_matches(
+            vec![
+                found("a1", "**error**", 30, true),
+                found("a1", "**error**", 30, false),
+                found("a2", "**error** again", 10, false),
+                found("a3", "an **error**", 20, false),
+            ],
+            2,
+        );
+        assert_eq!(
+            merged,
+            vec![
+                found("a1", "**error**", 30, true),
+                found("a3", "an **error**", 20, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_repeated_output_at_different_times() {
+        let merged = merge_matches(
+            vec![
+                found("a1", "build **failed**", 20, true),
+                found("a1", "build **failed**", 10, true),
+                found("a1", "build **failed**", 20, false),
+                found("a1", "build **failed**", 10, false),
+            ],
+            10,
+        );
+        assert_eq!(
+            merged,
+            vec![
+                found("a1", "build **failed**", 20, true),
+                found("a1", "build **failed**", 10, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_short_queries_are_rejected() {
+        assert!(search_query("  rs ").is_err());
+        assert_eq!(search_query(" .rs "), Ok(".rs"));
+    }
+}
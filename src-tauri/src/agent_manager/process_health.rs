@@ -0,0 +1,243 @@
+// Process health check
+//
+// A crash is normally noticed when the agent's stdout reaches its end. That
+// never happens if the claude process dies (OOM, kill, crash) while something
+// it started still holds the pipe open, and the agent would be listed as
+// running forever. Every HEALTH_CHECK_INTERVAL each live agent's process is
+// polled with try_wait; one that has exited without being stopped, and that
+// the stdout handler hasn't reported within EXIT_GRACE, is marked crashed:
+// status Error, the run Crashed (resumable) with the exit code and the tail of
+// stderr, its prompt files deleted, an agent:status event, and a queued
+// result plus a wake for the meta-agent so it doesn't wait on an agent that
+// will never answer.
+
+use std::process::ExitStatus;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+use crate::events::AppEventEmitter;
+use crate::meta_agent::SharedResultQueue;
+use crate::types::{
+    AgentResultStatus, AgentStatus, AgentStatusEvent, AgentWakeReason, QueuedAgentResult,
+    ResultQueueUpdatedEvent,
+};
+use crate::utils::time::now_millis;
+
+use super::result_handlers::send_wake;
+use super::session_finalizer::SessionEnd;
+use super::stderr_buffer::{format_crash_message, CRASH_STDERR_LINES};
+use super::{prompt_files, AgentManager};
+
+/// How often agent processes are polled
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time the stdout handler gets to report an exit itself
+const EXIT_GRACE: Duration = Duration::from_secs(2);
+
+/// "Process terminated unexpectedly", with the exit code or signal if known
+pub(crate) fn crash_message(exit_status: Option<ExitStatus>) -> String {
+    const MESSAGE: &str = "Process terminated unexpectedly";
+    let Some(status) = exit_status else {
+        return MESSAGE.to_string();
+    };
+    if let Some(code) = status.code() {
+        return format!("{} (exit code {})", MESSAGE, code);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("{} (killed by signal {})", MESSAGE, signal);
+        }
+    }
+    MESSAGE.to_string()
+}
+
+/// Queue a crashed agent's error for the meta-agent
+pub(crate) fn queue_crash_result(
+    result_queue: Option<&SharedResultQueue>,
+    app_handle: &dyn AppEventEmitter,
+    agent_id: &str,
+    working_dir: &str,
+    error_message: &str,
+) {
+    let Some(result_queue) = result_queue else {
+        return;
+    };
+    let mut queue = result_queue.lock().unwrap();
+    queue.push(QueuedAgentResult {
+        agent_id: agent_id.to_string(),
+        working_dir: working_dir.to_string(),
+        output: error_message.to_string(),
+        timestamp: now_millis(),
+        status: AgentResultStatus::Pending,
+    });
+    if let Ok(payload) = serde_json::to_value(ResultQueueUpdatedEvent {
+        queue_status: queue.status(),
+    }) {
+        let _ = app_handle.emit("result-queue:updated", payload);
+    }
+}
+
+impl AgentManager {
+    /// Set the meta-agent's result queue, where crashed agents are reported
+    pub fn set_result_queue(&mut self, result_queue: SharedResultQueue) {
+        self.result_queue = Some(result_queue);
+    }
+
+    /// Mark agents whose process exited without being stopped as crashed.
+    /// Returns their IDs.
+    pub async fn check_agent_processes(&self, app_handle: &dyn AppEventEmitter) -> Vec<String> {
+        let exited = self.exited_agents().await;
+        if exited.is_empty() {
+            return Vec::new();
+        }
+
+        // Usually the stdout handler reaches the end of the output and
+        // reports the exit itself
+        tokio::time::sleep(EXIT_GRACE).await;
+        self.mark_crashed(exited, app_handle).await
+    }
+
+    /// Live agents whose process has exited, with its exit status
+    async fn exited_agents(&self) -> Vec<(String, ExitStatus)> {
+        let mut agents = self.agents.lock().await;
+        agents
+            .iter_mut()
+            .filter(|(_, agent)| {
+                !matches!(agent.info.status, AgentStatus::Stopped | AgentStatus::Error)
+            })
+            .filter_map(|(id, agent)| {
+                let status = agent.child.as_mut()?.try_wait().ok().flatten()?;
+                Some((id.clone(), status))
+            })
+            .collect()
+    }
+
+    /// Mark the exited agents the stdout handler hasn't reported in the
+    /// meantime as crashed. Returns their IDs.
+    async fn mark_crashed(
+        &self,
+        exited: Vec<(String, ExitStatus)>,
+        app_handle: &dyn AppEventEmitter,
+    ) -> Vec<String> {
+        let mut crashed = Vec::new();
+        for (agent_id, exit_status) in exited {
+            let (info, stats, stderr_buffer, prompt_files) = {
+                let mut agents = self.agents.lock().await;
+                let Some(agent) = agents.get_mut(&agent_id) else {
+                    continue;
+                };
+                if matches!(agent.info.status, AgentStatus::Stopped | AgentStatus::Error) {
+                    continue;
+                }
+                agent.info.status = AgentStatus::Error;
+                agent.stdin_tx = None;
+                agent.child = None;
+                for handle in [
+                    agent.stdin_handle.take(),
+                    agent.stdout_handle.take(),
+                    agent.stderr_handle.take(),
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    handle.abort();
+                }
+                (
+                    agent.info.clone(),
+                    agent.stats.clone(),
+                    agent.stderr_buffer.clone(),
+                    std::mem::take(&mut agent.prompt_files),
+                )
+            };
+            prompt_files::remove_prompt_files(&info.working_dir, &prompt_files);
+
+            let message = crash_message(Some(exit_status));
+            eprintln!("[HealthCheck] Agent {}: {}", agent_id, message);
+            if let Some(ref logger) = self.logger {
+                let _ = logger
+                    .error("agent_manager", &message, Some(agent_id.clone()), None)
+                    .await;
+            }
+
+            let stderr_tail = stderr_buffer.lock().await.tail(CRASH_STDERR_LINES);
+            let error_message = format_crash_message(&message, &stderr_tail);
+            self.session_finalizer
+                .finalize_agent_session(
+                    self.runs_db.as_ref(),
+                    &agent_id,
+                    Some(stats),
+                    SessionEnd::Crashed {
+                        error_message: error_message.clone(),
+                    },
+                )
+                .await;
+
+            if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+                agent_id: agent_id.clone(),
+                status: AgentStatus::Error,
+                info: Some(info.clone()),
+                reason: None,
+            }) {
+                let _ = app_handle.emit("agent:status", status_event);
+            }
+            queue_crash_result(
+                self.result_queue.as_ref(),
+                app_handle,
+                &agent_id,
+                &info.working_dir,
+                &error_message,
+            );
+            send_wake(
+                &self.agent_wake_tx,
+                &agent_id,
+                AgentWakeReason::Error(message),
+            )
+            .await;
+            crashed.push(agent_id);
+        }
+        crashed
+    }
+}
+
+/// Check agent processes every HEALTH_CHECK_INTERVAL
+pub async fn run_health_check(
+    manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+) {
+    loop {
+        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+        let exited = manager.lock().await.exited_agents().await;
+        if exited.is_empty() {
+            continue;
+        }
+        // The grace period is waited out without the manager locked
+        tokio::time::sleep(EXIT_GRACE).await;
+        manager
+            .lock()
+            .await
+            .mark_crashed(exited, app_handle.as_ref())
+            .await;
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_crash_message_names_exit_code_or_signal() {
+        assert_eq!(crash_message(None), "Process terminated unexpectedly");
+        assert_eq!(
+            crash_message(Some(ExitStatus::from_raw(1 << 8))),
+            "Process terminated unexpectedly (exit code 1)"
+        );
+        assert_eq!(
+            crash_message(Some(ExitStatus::from_raw(9))),
+            "Process terminated unexpectedly (killed by signal 9)"
+        );
+    }
+}
@@ -5,19 +5,66 @@
 
 use std::collections::HashMap;
 use std::process::Stdio;
-use tokio::process::Command;
 
-use super::claude_cli::{find_claude_cli, get_elevation_bin_path};
+use crate::agent_runs_db::PermissionProfile;
+use crate::commands::env_registry;
+use crate::permission_profiles;
+use crate::types::ToolRestrictions;
+
+use super::claude_cli::{get_elevation_bin_path, resolve_claude_cli};
+use super::hooks_config::{load_settings_file, merge_settings, validate_settings};
 
 /// Environment variables to exclude from Claude Code child processes
 /// when CLAUDE_CODE_API_KEY_MODE is set to "blocked".
 /// This allows meta agents to use the Anthropic API while Claude Code uses OAuth.
 pub(crate) const SENSITIVE_ENV_VARS: &[&str] = &["ANTHROPIC_API_KEY"];
 
+/// Set for every agent by spawn_claude_process, so extra env vars can't override them
+const RESERVED_ENV_VARS: &[&str] = &["CLAUDE_AGENT_ID"];
+
+/// Whether CLAUDE_CODE_API_KEY_MODE keeps API keys from agents (the default)
+pub(crate) fn api_keys_blocked() -> bool {
+    env_registry::CLAUDE_CODE_API_KEY_MODE
+        .var()
+        .map(|mode| mode.eq_ignore_ascii_case("blocked"))
+        .unwrap_or(true)
+}
+
+/// Check the extra env vars given for an agent at spawn. Sensitive keys are
+/// dropped with a warning when API keys are blocked.
+pub(crate) fn filter_extra_env(
+    extra_env: HashMap<String, String>,
+    blocked: bool,
+) -> Result<HashMap<String, String>, String> {
+    let mut allowed = HashMap::new();
+    for (key, value) in extra_env {
+        if key.is_empty() || key.contains('=') || key.contains('\0') || value.contains('\0') {
+            return Err(format!("Invalid environment variable: {:?}", key));
+        }
+        if RESERVED_ENV_VARS.contains(&key.as_str()) {
+            return Err(format!("{} is set by Claude Commander", key));
+        }
+        if blocked && SENSITIVE_ENV_VARS.contains(&key.as_str()) {
+            eprintln!(
+                "[spawn] Not passing {} to the agent: CLAUDE_CODE_API_KEY_MODE is blocked",
+                key
+            );
+            continue;
+        }
+        allowed.insert(key, value);
+    }
+    Ok(allowed)
+}
+
 /// Create hooks configuration file for the agent
+///
+/// The tracking hooks are merged into the project's `.claude/settings.json`
+/// and the optional user-provided `hooks_file`, so the user's own hooks still run.
 pub(crate) fn create_hooks_config(
     hook_port: u16,
     agent_id: &str,
+    working_dir: &str,
+    hooks_file: Option<&str>,
 ) -> Result<std::path::PathBuf, String> {
     let settings_path = std::env::temp_dir().join(format!("claude_hooks_{}.json", agent_id));
     // Include agent_id in hook URL to avoid race condition where hooks arrive
@@ -47,45 +94,74 @@ pub(crate) fn create_hooks_config(
         }
     });
 
+    let mut settings = serde_json::json!({});
+
+    let project_settings = std::path::Path::new(working_dir)
+        .join(".claude")
+        .join("settings.json");
+    if project_settings.exists() {
+        match load_settings_file(&project_settings) {
+            Ok(project) => merge_settings(&mut settings, &project),
+            // The CLI reports a broken project file itself; don't block the spawn on it
+            Err(e) => eprintln!("[hooks] Ignoring project settings: {}", e),
+        }
+    }
+
+    if let Some(path) = hooks_file {
+        let user = load_settings_file(std::path::Path::new(path))?;
+        merge_settings(&mut settings, &user);
+    }
+
+    merge_settings(&mut settings, &hooks_config);
+    validate_settings(&settings).map_err(|e| format!("Invalid merged hooks settings: {}", e))?;
+
     std::fs::write(
         &settings_path,
-        serde_json::to_string_pretty(&hooks_config).unwrap(),
+        serde_json::to_string_pretty(&settings).unwrap(),
     )
     .map_err(|e| format!("Failed to create settings file: {}", e))?;
 
     Ok(settings_path)
 }
 
-/// Spawn the Claude CLI process with appropriate configuration
+/// The model to start an agent with: the requested one, or CLAUDE_CODE_MODEL
+/// unless that is unset or "auto" (None leaves it to the CLI)
+pub(crate) fn resolve_model(model: Option<String>) -> Option<String> {
+    model.or_else(|| {
+        env_registry::CLAUDE_CODE_MODEL
+            .var()
+            .ok()
+            .filter(|m| {
+                let m = m.trim().to_lowercase();
+                !m.is_empty() && m != "auto"
+            })
+            .map(|m| m.trim().to_string())
+    })
+}
+
+/// Spawn the Claude CLI process with appropriate configuration: with `model`
+/// as --model, under the permission profile if given and limited to `tools`
+/// on top of it, continuing
+/// `resume_session` if given, with the MCP servers in `mcp_config_path` if
+/// given and with `extra_env` set on top of the inherited environment
 pub(crate) fn spawn_claude_process(
     settings_path: &std::path::Path,
     working_dir: &str,
     agent_id: &str,
     model: Option<String>,
+    profile: Option<&PermissionProfile>,
+    tools: &ToolRestrictions,
+    resume_session: Option<&str>,
+    mcp_config_path: Option<&std::path::Path>,
+    extra_env: &HashMap<String, String>,
 ) -> Result<tokio::process::Child, String> {
-    let claude_path = std::env::var("CLAUDE_PATH")
-        .or_else(|_| find_claude_cli())
-        .unwrap_or_else(|_| {
-            if cfg!(windows) {
-                "claude.cmd".to_string()
-            } else {
-                "claude".to_string()
-            }
-        });
-
-    // Check if we should block API keys from Claude Code
-    // Default to "blocked" if not set (Claude Code uses OAuth)
-    let api_key_mode =
-        std::env::var("CLAUDE_CODE_API_KEY_MODE").unwrap_or_else(|_| "blocked".to_string());
-
-    let mut cmd = Command::new(&claude_path);
+    // CLAUDE_PATH, an installed CLI or npx; .cmd scripts run through cmd /C
+    let claude_cli = resolve_claude_cli(Some(std::path::Path::new(working_dir)));
 
     // Build base args
     let mut args = vec![
         "-p",
         "--verbose",
-        "--permission-mode",
-        "bypassPermissions",
         "--input-format",
         "stream-json",
         "--output-format",
@@ -94,24 +170,28 @@ pub(crate) fn spawn_claude_process(
         settings_path.to_str().unwrap(),
     ];
 
-    // Determine model: use passed model parameter, or fall back to CLAUDE_CODE_MODEL env var
-    let model_arg: Option<String> = model.or_else(|| {
-        std::env::var("CLAUDE_CODE_MODEL")
-            .ok()
-            .filter(|m| {
-                let m = m.trim().to_lowercase();
-                !m.is_empty() && m != "auto"
-            })
-            .map(|m| m.trim().to_string())
-    });
+    // Permission mode from the profile, tool lists from it and the agent
+    let permission_args = permission_profiles::cli_args(profile, tools);
+    args.extend(permission_args.iter().map(String::as_str));
 
-    if let Some(ref model) = model_arg {
+    if let Some(ref model) = model {
         args.push("--model");
         args.push(model);
     }
 
-    cmd.args(&args)
-        .current_dir(working_dir)
+    if let Some(session_id) = resume_session {
+        args.push("--resume");
+        args.push(session_id);
+    }
+
+    if let Some(path) = mcp_config_path {
+        args.push("--mcp-config");
+        args.push(path.to_str().ok_or("MCP config path is not valid UTF-8")?);
+    }
+
+    let args: Vec<String> = args.into_iter().map(str::to_string).collect();
+    let mut cmd = claude_cli.command(&args);
+    cmd.current_dir(working_dir)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -120,9 +200,12 @@ pub(crate) fn spawn_claude_process(
     let elevation_bin_path = get_elevation_bin_path();
 
     // Apply environment filtering based on mode
-    if api_key_mode.to_lowercase() == "blocked" {
-        // Filter out sensitive API keys so Claude Code uses OAuth authentication
+    // Default to "blocked" if not set (Claude Code uses OAuth)
+    if api_keys_blocked() {
+        // Filter out sensitive API keys so Claude Code uses OAuth authentication,
+        // including any passed in as extra env vars
         let mut filtered_env: HashMap<String, String> = std::env::vars()
+            .chain(extra_env.clone())
             .filter(|(key, _)| !SENSITIVE_ENV_VARS.contains(&key.as_str()))
             .collect();
 
@@ -139,14 +222,49 @@ pub(crate) fn spawn_claude_process(
         cmd.env_clear().envs(&filtered_env);
     } else {
         // "passthrough" mode: inherit all env vars but still inject elevation
+        cmd.envs(extra_env);
         if let Some(elevation_path) = &elevation_bin_path {
-            let existing_path = std::env::var("PATH").unwrap_or_default();
+            let existing_path = extra_env
+                .get("PATH")
+                .cloned()
+                .unwrap_or_else(|| std::env::var("PATH").unwrap_or_default());
             let new_path = format!("{}:{}", elevation_path.display(), existing_path);
             cmd.env("PATH", new_path);
         }
         cmd.env("CLAUDE_AGENT_ID", agent_id);
     }
+    // Agents can reach the hook server; the token keeps them off its
+    // authenticated routes
+    cmd.env_remove(env_registry::HOOK_SERVER_TOKEN.name);
 
     cmd.spawn()
-        .map_err(|e| format!("Failed to spawn claude: {}", e))
+        .map_err(|e| format!("Failed to spawn claude ({}): {}", claude_cli.path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_extra_env_cannot_carry_blocked_api_keys() {
+        let extra = env(&[
+            ("DATABASE_URL", "postgres://staging"),
+            ("ANTHROPIC_API_KEY", "sk-ant-test"),
+        ]);
+
+        let blocked = filter_extra_env(extra.clone(), true).unwrap();
+        assert_eq!(blocked, env(&[("DATABASE_URL", "postgres://staging")]));
+        assert_eq!(filter_extra_env(extra.clone(), false).unwrap(), extra);
+
+        assert!(filter_extra_env(env(&[("CLAUDE_AGENT_ID", "other")]), false).is_err());
+        assert!(filter_extra_env(env(&[("A=B", "x")]), false).is_err());
+        assert!(filter_extra_env(env(&[("", "x")]), false).is_err());
+    }
 }
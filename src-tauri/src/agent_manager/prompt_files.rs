@@ -0,0 +1,222 @@
+// Large prompts handed to agents in a file
+//
+// A prompt with a pasted log or file dump becomes one enormous stream-json
+// line on the agent's stdin and an equally large row in agent_prompts. Past
+// PROMPT_FILE_THRESHOLD_KB the prompt is written to
+// `<working_dir>/.commander/prompt-files/` instead, the agent is asked to read
+// it, and only a preview and the path are recorded. Each file is listed in
+// the generated file manifest, so GIT_EXCLUDE_GENERATED_FILES keeps it out of
+// `git status`. The files are deleted when the agent stops or crashes; those
+// left by agents that were running when the app exited are deleted at the
+// next startup. Each send can force inline or file delivery, or use its own
+// threshold.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::commands::env_registry;
+use crate::generated_files::{self, GeneratedFileKind, COMMANDER_DIR};
+
+/// Default size past which prompts go in a file, in KB (PROMPT_FILE_THRESHOLD_KB)
+const DEFAULT_THRESHOLD_KB: usize = 64;
+
+/// Characters of a file prompt kept in the prompt history
+const PREVIEW_CHARS: usize = 2_000;
+
+const PROMPT_FILES_DIR: &str = "prompt-files";
+
+const KB: usize = 1024;
+
+/// How a prompt reaches the agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptDelivery {
+    /// In a file when larger than the threshold
+    #[default]
+    Auto,
+    /// Always on stdin
+    Inline,
+    /// Always in a file
+    File,
+}
+
+/// Per-send override of the file delivery settings
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PromptFileOptions {
+    #[serde(default)]
+    pub delivery: PromptDelivery,
+    /// Replaces PROMPT_FILE_THRESHOLD_KB for this send
+    #[serde(default)]
+    pub threshold_kb: Option<usize>,
+}
+
+/// Size past which prompts go in a file, in bytes (0 keeps every prompt inline)
+pub fn threshold_bytes() -> usize {
+    env_registry::PROMPT_FILE_THRESHOLD_KB
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_THRESHOLD_KB)
+        * KB
+}
+
+impl PromptFileOptions {
+    /// Whether a prompt of this many bytes goes in a file
+    pub fn use_file(&self, prompt_bytes: usize, default_threshold_bytes: usize) -> bool {
+        match self.delivery {
+            PromptDelivery::Inline => false,
+            PromptDelivery::File => true,
+            PromptDelivery::Auto => {
+                let threshold = self
+                    .threshold_kb
+                    .map(|kb| kb * KB)
+                    .unwrap_or(default_threshold_bytes);
+                threshold > 0 && prompt_bytes > threshold
+            }
+        }
+    }
+}
+
+/// Write a prompt to a new file in the agent's working directory
+pub fn write_prompt_file(
+    working_dir: &str,
+    agent_id: &str,
+    prompt: &str,
+) -> Result<PathBuf, String> {
+    let dir = Path::new(working_dir)
+        .join(COMMANDER_DIR)
+        .join(PROMPT_FILES_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let short_id: String = agent_id.chars().take(8).collect();
+    let suffix = uuid::Uuid::new_v4().to_string();
+    let path = dir.join(format!("prompt-{}-{}.md", short_id, &suffix[..8]));
+    std::fs::write(&path, prompt)
+        .map_err(|e| format!("Failed to write prompt file {}: {}", path.display(), e))?;
+    generated_files::record(
+        working_dir,
+        &path,
+        GeneratedFileKind::PromptFile,
+        true,
+        None,
+    );
+    Ok(path)
+}
+
+/// The short prompt sent in place of one written to a file
+pub fn file_prompt(path: &Path, prompt_bytes: usize) -> String {
+    format!(
+        "My message is too long to send directly ({} KB), so it is in {}. \
+         Read that whole file and treat its contents as my message.",
+        (prompt_bytes + KB / 2) / KB,
+        path.display()
+    )
+}
+
+/// What the prompt history keeps of a prompt written to a file
+pub fn recorded_preview(prompt: &str, path: &Path) -> String {
+    let preview: String = prompt.chars().take(PREVIEW_CHARS).collect();
+    format!(
+        "{}\n\n[... {} bytes in total; full prompt sent in {}]",
+        preview.trim_end(),
+        prompt.len(),
+        path.display()
+    )
+}
+
+/// Delete an agent's prompt files from its working directory
+pub fn remove_prompt_files(working_dir: &str, paths: &[PathBuf]) {
+    if paths.is_empty() {
+        return;
+    }
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!(
+                    "Warning: Failed to remove prompt file {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+    generated_files::forget(working_dir, paths);
+}
+
+/// Delete every prompt file in a working directory, e.g. those left by agents
+/// that were running when the app exited
+pub fn remove_all_prompt_files(working_dir: &str) {
+    let dir = Path::new(working_dir)
+        .join(COMMANDER_DIR)
+        .join(PROMPT_FILES_DIR);
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+    let paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    remove_prompt_files(working_dir, &paths);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delivery_and_threshold_overrides() {
+        let auto = PromptFileOptions::default();
+        assert!(!auto.use_file(64 * KB, 64 * KB));
+        assert!(auto.use_file(64 * KB + 1, 64 * KB));
+        assert!(!auto.use_file(10 * 1024 * KB, 0));
+
+        let lower = PromptFileOptions {
+            threshold_kb: Some(1),
+            ..Default::default()
+        };
+        assert!(lower.use_file(2 * KB, 64 * KB));
+
+        let inline = PromptFileOptions {
+            delivery: PromptDelivery::Inline,
+            threshold_kb: Some(1),
+        };
+        assert!(!inline.use_file(300 * KB, 64 * KB));
+        let file = PromptFileOptions {
+            delivery: PromptDelivery::File,
+            threshold_kb: None,
+        };
+        assert!(file.use_file(10, 64 * KB));
+    }
+
+    #[test]
+    fn test_prompt_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let prompt = "log line\n".repeat(40_000);
+
+        let working_dir = dir.path().to_str().unwrap();
+        let path = write_prompt_file(working_dir, "agent-123456789", &prompt).unwrap();
+        assert!(path.starts_with(dir.path().join(".commander/prompt-files")));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), prompt);
+        let listed = generated_files::list_generated_files(working_dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].kind, GeneratedFileKind::PromptFile);
+
+        let sent = file_prompt(&path, prompt.len());
+        assert!(sent.contains("(352 KB)"));
+        assert!(sent.contains(&path.display().to_string()));
+
+        let recorded = recorded_preview(&prompt, &path);
+        assert!(recorded.len() < PREVIEW_CHARS + 200);
+        assert!(recorded.ends_with(&format!("full prompt sent in {}]", path.display())));
+
+        remove_prompt_files(working_dir, &[path.clone(), path.clone()]);
+        assert!(!path.exists());
+        assert!(!dir.path().join(COMMANDER_DIR).exists());
+
+        // Files left behind (e.g. by an agent running when the app exited)
+        let left = write_prompt_file(working_dir, "agent-2", "left behind").unwrap();
+        remove_all_prompt_files(working_dir);
+        assert!(!left.exists());
+        assert!(generated_files::list_generated_files(working_dir)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+// Prompt queue
+//
+// A prompt written to an agent's stdin while it is still working on a turn gets
+// interleaved into that turn and confuses the session. send_prompt therefore
+// queues prompts for a busy agent, already formatted, and the next one is sent
+// when the turn ends (where the agent would otherwise be marked idle). A queued
+// prompt is recorded in agent_prompts when it is actually sent, and every
+// agent:activity event carries the queue depth.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent_runs_db::{AgentRunsDB, DeadLetterWrite};
+use crate::events::AppEventEmitter;
+use crate::types::AgentActivityEvent;
+use crate::utils::time::now_millis;
+
+use super::statistics;
+use super::types::AgentProcess;
+
+/// What send_prompt did with a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptSendResult {
+    /// Whether the prompt waits for the agent's current turn to end
+    pub queued: bool,
+    /// Prompts waiting for the agent afterwards
+    pub queue_depth: usize,
+}
+
+/// A prompt waiting for its agent's current turn to end
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedPrompt {
+    pub id: String,
+    /// The prompt as it will be recorded (a preview if handed over in a file)
+    pub prompt: String,
+    pub queued_at: i64,
+    /// The stream-json line written to the agent's stdin
+    #[serde(skip)]
+    pub line: String,
+    /// Whether the line starts with the repository conventions digest
+    #[serde(skip)]
+    pub conventions_injected: bool,
+}
+
+/// An agent's queued prompts, oldest first
+#[derive(Debug, Default)]
+pub struct PromptQueue {
+    prompts: VecDeque<QueuedPrompt>,
+}
+
+impl PromptQueue {
+    /// Queue a prompt, returning the queue depth
+    pub fn push(&mut self, prompt: QueuedPrompt) -> usize {
+        self.prompts.push_back(prompt);
+        self.prompts.len()
+    }
+
+    /// Take the next prompt to send
+    pub fn pop(&mut self) -> Option<QueuedPrompt> {
+        self.prompts.pop_front()
+    }
+
+    /// Put back a prompt taken with pop that couldn't be sent
+    pub fn push_front(&mut self, prompt: QueuedPrompt) {
+        self.prompts.push_front(prompt);
+    }
+
+    /// Remove a queued prompt by ID
+    pub fn cancel(&mut self, id: &str) -> Option<QueuedPrompt> {
+        let index = self.prompts.iter().position(|p| p.id == id)?;
+        self.prompts.remove(index)
+    }
+
+    pub fn list(&self) -> Vec<QueuedPrompt> {
+        self.prompts.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.prompts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty()
+    }
+}
+
+/// A prompt as the stream-json line the Claude CLI reads from stdin
+pub(crate) fn prompt_line(text: &str) -> Result<String, String> {
+    let message = serde_json::json!({
+        "type": "user",
+        "message": {
+            "role": "user",
+            "content": [{
+                "type": "text",
+                "text": text
+            }]
+        }
+    });
+    serde_json::to_string(&message).map_err(|e| format!("Failed to serialize message: {}", e))
+}
+
+/// Tell the UI about an agent's activity and queue depth
+pub(crate) fn emit_activity(
+    app_handle: &dyn AppEventEmitter,
+    agent_id: &str,
+    is_processing: bool,
+    pending_input: bool,
+    queued_prompts: usize,
+) {
+    if let Ok(activity_event) = serde_json::to_value(AgentActivityEvent {
+        agent_id: agent_id.to_string(),
+        is_processing,
+        pending_input,
+        last_activity: now_millis(),
+        queued_prompts,
+    }) {
+        let _ = app_handle.emit("agent:activity", activity_event);
+    }
+}
+
+/// Record a prompt sent at `timestamp` in agent_prompts and on its run
+pub(crate) async fn record_sent_prompt(
+    runs_db: &AgentRunsDB,
+    agent_id: &str,
+    prompt: &str,
+    timestamp: i64,
+    conventions_injected: bool,
+) -> Result<(), String> {
+    let write = DeadLetterWrite::RecordPrompt {
+        agent_id: agent_id.to_string(),
+        prompt: prompt.to_string(),
+        timestamp,
+    };
+    let recorded = runs_db.write_or_dead_letter(write).await;
+
+    if let Ok(Some(mut run)) = runs_db.get_run(agent_id).await {
+        run.last_activity = timestamp;
+        run.total_prompts += 1;
+        if run.total_prompts == 1 {
+            run.initial_prompt = Some(prompt.to_string());
+        }
+        if conventions_injected {
+            run.conventions_injected = true;
+        }
+        let _ = runs_db
+            .write_or_dead_letter(DeadLetterWrite::UpdateRun { run })
+            .await;
+    }
+    recorded
+}
+
+/// Send an agent's next queued prompt as its turn ends. Returns false when
+/// nothing was sent, so the agent should go idle. A prompt that can't be
+/// written to stdin stays first in the queue.
+pub(crate) async fn send_next(
+    agent: &mut AgentProcess,
+    agent_id: &str,
+    runs_db: Option<&Arc<AgentRunsDB>>,
+    app_handle: &dyn AppEventEmitter,
+) -> bool {
+    let Some(mut next) = agent.prompt_queue.pop() else {
+        return false;
+    };
+    let Some(stdin_tx) = agent.stdin_tx.clone() else {
+        agent.prompt_queue = PromptQueue::default();
+        return false;
+    };
+
+    agent.idle_debounce.activity();
+    *agent.pending_input.lock().await = false;
+    *agent.is_processing.lock().await = true;
+    let line = std::mem::take(&mut next.line);
+    if let Err(e) = stdin_tx.send(line).await {
+        eprintln!(
+            "[PromptQueue] Failed to send queued prompt to agent {}, keeping it queued: {}",
+            agent_id, e
+        );
+        next.line = e.0;
+        agent.prompt_queue.push_front(next);
+        *agent.is_processing.lock().await = false;
+        emit_activity(app_handle, agent_id, false, true, agent.prompt_queue.len());
+        return false;
+    }
+
+    emit_activity(app_handle, agent_id, true, false, agent.prompt_queue.len());
+    statistics::increment_prompts(&agent.stats).await;
+    if let Some(runs_db) = runs_db {
+        if let Err(e) = record_sent_prompt(
+            runs_db,
+            agent_id,
+            &next.prompt,
+            now_millis(),
+            next.conventions_injected,
+        )
+        .await
+        {
+            eprintln!(
+                "[PromptQueue] Failed to record queued prompt for agent {}: {}",
+                agent_id, e
+            );
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: &str) -> QueuedPrompt {
+        QueuedPrompt {
+            id: id.to_string(),
+            prompt: format!("prompt {}", id),
+            queued_at: 0,
+            line: prompt_line(&format!("prompt {}", id)).unwrap(),
+            conventions_injected: false,
+        }
+    }
+
+    #[test]
+    fn test_queue_is_first_in_first_out() {
+        let mut queue = PromptQueue::default();
+        assert_eq!(queue.push(queued("a")), 1);
+        assert_eq!(queue.push(queued("b")), 2);
+        assert_eq!(queue.push(queued("c")), 3);
+
+        assert_eq!(
+            queue.cancel("b").map(|p| p.prompt),
+            Some("prompt b".to_string())
+        );
+        assert!(queue.cancel("b").is_none());
+        let ids: Vec<_> = queue.list().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+
+        let next = queue.pop().unwrap();
+        assert_eq!(next.id, "a");
+        let line: serde_json::Value = serde_json::from_str(&next.line).unwrap();
+        assert_eq!(line["message"]["content"][0]["text"], "prompt a");
+        assert_eq!(queue.len(), 1);
+
+        // A prompt that couldn't be sent goes back to the front
+        queue.push_front(next);
+        let ids: Vec<_> = queue.list().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+    }
+}
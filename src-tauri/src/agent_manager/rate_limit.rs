@@ -0,0 +1,231 @@
+// Claude usage limits - detection and the shared back-off window
+//
+// When the Claude CLI is rate-limited, overloaded, or the account hits its
+// usage cap, the turn ends with an error result instead of an answer, e.g.
+//   "Claude AI usage limit reached|1735689600"
+//   "API Error: 429 {... "type":"rate_limit_error" ...}"
+//   "API Error: 529 {... "type":"overloaded_error" ...}"
+// Such results mark the agent RateLimited and emit `agent:rate-limited`. The
+// limit applies to the whole account, so it also opens a shared window during
+// which pipelines hold new prompts for Claude agents (see
+// `wait_for_window`) instead of burning retries on failures.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use crate::utils::time::now_millis;
+
+/// Back-off when a rate limit gives no retry time
+const DEFAULT_RATE_LIMIT_SECS: u64 = 60;
+
+/// Back-off when the API is overloaded
+const DEFAULT_OVERLOADED_SECS: u64 = 30;
+
+/// Back-off when the usage cap gives no reset time; checked again afterwards
+const DEFAULT_USAGE_LIMIT_SECS: u64 = 15 * 60;
+
+/// End of the current window in Unix milliseconds (0 when none was set)
+static LIMITED_UNTIL: AtomicI64 = AtomicI64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKind {
+    /// The account's usage cap was reached
+    UsageLimit,
+    /// Requests are being rate-limited (HTTP 429)
+    RateLimit,
+    /// The API is overloaded (HTTP 529)
+    Overloaded,
+}
+
+impl RateLimitKind {
+    fn default_secs(&self) -> u64 {
+        match self {
+            RateLimitKind::UsageLimit => DEFAULT_USAGE_LIMIT_SECS,
+            RateLimitKind::RateLimit => DEFAULT_RATE_LIMIT_SECS,
+            RateLimitKind::Overloaded => DEFAULT_OVERLOADED_SECS,
+        }
+    }
+}
+
+/// A limit reported by a result message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RateLimitInfo {
+    pub kind: RateLimitKind,
+    pub message: String,
+    /// Seconds until retrying, when the error says
+    pub retry_after_secs: Option<u64>,
+}
+
+impl RateLimitInfo {
+    /// When work can resume, in Unix milliseconds
+    pub fn resume_at(&self, now_ms: i64) -> i64 {
+        let secs = self
+            .retry_after_secs
+            .unwrap_or_else(|| self.kind.default_secs());
+        now_ms + secs as i64 * 1000
+    }
+}
+
+/// Payload of `agent:rate-limited`
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentRateLimitedEvent {
+    pub agent_id: String,
+    pub kind: RateLimitKind,
+    pub message: String,
+    pub retry_after_secs: Option<u64>,
+    /// When dispatching to Claude agents resumes, in Unix milliseconds
+    pub resume_at: i64,
+}
+
+/// The limit an error result reports, if any
+pub fn detect(json: &Value) -> Option<RateLimitInfo> {
+    let is_error = json.get("is_error").and_then(|v| v.as_bool()) == Some(true)
+        || json
+            .get("subtype")
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| s != "success");
+    if !is_error {
+        return None;
+    }
+
+    let text = json
+        .get("result")
+        .and_then(|v| v.as_str())
+        .or_else(|| json.get("error").and_then(|v| v.as_str()))?;
+    let lower = text.to_lowercase();
+
+    let kind = if lower.contains("usage limit")
+        || lower.contains("hit your limit")
+        || lower.contains("-hour limit")
+        || lower.contains("weekly limit")
+    {
+        RateLimitKind::UsageLimit
+    } else if lower.contains("rate_limit_error")
+        || lower.contains("rate limit")
+        || lower.contains("api error: 429")
+    {
+        RateLimitKind::RateLimit
+    } else if lower.contains("overloaded") || lower.contains("api error: 529") {
+        RateLimitKind::Overloaded
+    } else {
+        return None;
+    };
+
+    Some(RateLimitInfo {
+        kind,
+        message: text.split('|').next().unwrap_or(text).trim().to_string(),
+        retry_after_secs: retry_after_secs(text, now_millis()),
+    })
+}
+
+/// Retry time from a "|<unix seconds>" reset suffix or a "retry after N" hint
+fn retry_after_secs(text: &str, now_ms: i64) -> Option<u64> {
+    if let Some(reset) = text
+        .rsplit_once('|')
+        .and_then(|(_, ts)| ts.trim().parse::<i64>().ok())
+    {
+        return Some(((reset * 1000 - now_ms).max(0) / 1000) as u64);
+    }
+
+    let lower = text.to_lowercase();
+    let start = lower
+        .find("retry-after")
+        .or_else(|| lower.find("retry after"))?;
+    lower[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|part| !part.is_empty())
+        .and_then(|secs| secs.parse().ok())
+}
+
+/// Open (or extend) the shared window and return when it ends
+pub fn note_limit(info: &RateLimitInfo) -> i64 {
+    let until = info.resume_at(now_millis());
+    LIMITED_UNTIL.fetch_max(until, Ordering::Relaxed);
+    LIMITED_UNTIL.load(Ordering::Relaxed)
+}
+
+/// End of the current window, if Claude agents are limited right now
+pub fn limited_until() -> Option<i64> {
+    let until = LIMITED_UNTIL.load(Ordering::Relaxed);
+    (until > now_millis()).then_some(until)
+}
+
+/// Wait until the window passes. Returns whether there was one to wait for.
+pub async fn wait_for_window() -> bool {
+    let mut waited = false;
+    // Re-check after sleeping: another agent may have extended the window
+    while let Some(until) = limited_until() {
+        if !waited {
+            eprintln!(
+                "[RateLimit] Claude agents are limited; holding new work for {}s",
+                (until - now_millis()) / 1000
+            );
+        }
+        waited = true;
+        let wait_ms = (until - now_millis()).max(0) as u64;
+        tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+    }
+    waited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detects_limit_shapes() {
+        let usage = detect(&json!({
+            "type": "result",
+            "subtype": "success",
+            "is_error": true,
+            "result": format!("Claude AI usage limit reached|{}", now_millis() / 1000 + 600)
+        }))
+        .unwrap();
+        assert_eq!(usage.kind, RateLimitKind::UsageLimit);
+        assert_eq!(usage.message, "Claude AI usage limit reached");
+        let secs = usage.retry_after_secs.unwrap();
+        assert!((598..=600).contains(&secs), "{}", secs);
+
+        let rate = detect(&json!({
+            "subtype": "error_during_execution",
+            "is_error": true,
+            "result": "API Error: 429 {\"type\":\"error\",\"error\":{\"type\":\"rate_limit_error\",\"message\":\"Number of requests exceeded, retry after 42 seconds\"}}"
+        }))
+        .unwrap();
+        assert_eq!(rate.kind, RateLimitKind::RateLimit);
+        assert_eq!(rate.retry_after_secs, Some(42));
+
+        let overloaded = detect(&json!({
+            "is_error": true,
+            "result": "API Error: 529 {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}"
+        }))
+        .unwrap();
+        assert_eq!(overloaded.kind, RateLimitKind::Overloaded);
+        assert_eq!(overloaded.retry_after_secs, None);
+        assert_eq!(overloaded.resume_at(0), 30_000);
+    }
+
+    #[test]
+    fn test_ignores_successes_and_other_errors() {
+        // A successful answer that talks about rate limits is not a limit
+        assert_eq!(
+            detect(&json!({
+                "subtype": "success",
+                "is_error": false,
+                "result": "Added a rate limit to the API client"
+            })),
+            None
+        );
+        assert_eq!(
+            detect(&json!({
+                "subtype": "error_max_turns",
+                "is_error": true,
+                "result": "Reached maximum number of turns"
+            })),
+            None
+        );
+    }
+}
@@ -3,16 +3,24 @@
 // This module handles result messages (task completion), stream events,
 // unknown message types, plain text output, and process end handling.
 
-use crate::agent_runs_db::RunStatus;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::agent_runs_db::DeadLetterWrite;
 use crate::types::{
-    AgentInputRequiredEvent, AgentStatistics, AgentStatsEvent, AgentStatus, AgentStatusEvent,
-    AgentWakeEvent, AgentWakeReason,
+    AgentStatistics, AgentStatsEvent, AgentStatus, AgentStatusEvent, AgentWakeEvent,
+    AgentWakeReason,
 };
 use crate::utils::time::now_millis;
 
+use super::ansi;
 use super::event_handlers::StreamContext;
+use super::idle_debounce::IdleTransition;
 use super::output_builder::{extract_common_fields, OutputEventBuilder};
-use super::statistics::{update_from_result, update_output_bytes};
+use super::process_health::{crash_message, queue_crash_result};
+use super::rate_limit::{self, AgentRateLimitedEvent, RateLimitInfo};
+use super::session_finalizer::SessionEnd;
+use super::statistics::{update_from_result, update_output_bytes, usage_delta};
+use super::stderr_buffer::{format_crash_message, CRASH_STDERR_LINES};
 use super::stream_parser::{persist_output, store_in_buffer};
 
 /// Handle result messages (task completion)
@@ -28,6 +36,7 @@ pub(crate) async fn handle_result_message(ctx: &StreamContext, json: &serde_json
     let byte_size = content.len();
 
     // Update statistics from result message
+    let stats_before = ctx.stats.lock().await.clone();
     update_from_result(&ctx.stats, json, byte_size).await;
 
     // Emit updated stats
@@ -52,7 +61,7 @@ pub(crate) async fn handle_result_message(ctx: &StreamContext, json: &serde_json
     store_in_buffer(output_event.clone(), ctx.output_buffer.clone()).await;
     let _ = ctx
         .app_handle
-        .emit("agent:output", serde_json::to_value(output_event).unwrap());
+        .emit("agent:output", ansi::ui_payload(&output_event));
 
     // Persist output to database
     persist_output(
@@ -61,18 +70,79 @@ pub(crate) async fn handle_result_message(ctx: &StreamContext, json: &serde_json
         &ctx.pipeline_id,
         "result",
         &content,
+        output_event.timestamp,
     )
     .await;
 
     // Update costs in database incrementally (don't wait until agent stops)
     update_costs_in_database(ctx, &stats_snapshot).await;
+    record_prompt_usage(ctx, &stats_before, &stats_snapshot);
 
-    // Handle successful completion - update state
-    if is_success {
-        handle_success_completion(ctx).await;
+    // A usage or rate limit is not a completion: hold the agent until retried
+    if let Some(limit) = rate_limit::detect(json) {
+        handle_rate_limit(ctx, limit).await;
+    } else if is_success {
+        handle_success_completion(ctx);
     }
 }
 
+/// Mark the agent RateLimited, open the shared back-off window and tell the
+/// frontend and the meta-agent
+async fn handle_rate_limit(ctx: &StreamContext, limit: RateLimitInfo) {
+    // Cancel idle transitions scheduled by this turn's end_turn message
+    ctx.idle_debounce.activity();
+    {
+        let mut agents = ctx.agents.lock().await;
+        match agents.get_mut(&ctx.agent_id) {
+            Some(agent) if agent.info.status != AgentStatus::Stopped => {
+                agent.info.status = AgentStatus::RateLimited;
+                agent.info.pending_input = true;
+            }
+            _ => return,
+        }
+    }
+    *ctx.pending_input.lock().await = true;
+    *ctx.is_processing.lock().await = false;
+
+    let resume_at = rate_limit::note_limit(&limit);
+    eprintln!(
+        "[StreamContext] Agent {} hit a Claude limit ({:?}): {}",
+        ctx.agent_id, limit.kind, limit.message
+    );
+
+    let _ = ctx.app_handle.emit(
+        "agent:status",
+        serde_json::to_value(AgentStatusEvent {
+            agent_id: ctx.agent_id.clone(),
+            status: AgentStatus::RateLimited,
+            info: None,
+            reason: None,
+        })
+        .unwrap(),
+    );
+    let _ = ctx.app_handle.emit(
+        "agent:rate-limited",
+        serde_json::to_value(AgentRateLimitedEvent {
+            agent_id: ctx.agent_id.clone(),
+            kind: limit.kind,
+            message: limit.message.clone(),
+            retry_after_secs: limit.retry_after_secs,
+            resume_at,
+        })
+        .unwrap(),
+    );
+
+    send_wake_event(
+        ctx,
+        AgentWakeReason::Error(format!(
+            "rate-limited ({}), retry in {}s",
+            limit.message,
+            (resume_at - now_millis()).max(0) / 1000
+        )),
+    )
+    .await;
+}
+
 /// Extract human-readable content from result JSON
 fn extract_result_content(json: &serde_json::Value, subtype: &Option<String>) -> String {
     if let Some(result) = json.get("result") {
@@ -111,51 +181,39 @@ fn extract_result_content(json: &serde_json::Value, subtype: &Option<String>) ->
     }
 }
 
-/// Handle successful task completion - update state and emit events
-async fn handle_success_completion(ctx: &StreamContext) {
-    *ctx.pending_input.lock().await = true;
-    *ctx.is_processing.lock().await = false;
-
-    // Update agent status to WaitingForInput
-    {
-        let mut agents = ctx.agents.lock().await;
-        if let Some(agent) = agents.get_mut(&ctx.agent_id) {
-            agent.info.status = AgentStatus::WaitingForInput;
-            agent.info.pending_input = true;
-        }
-    }
-
-    // Emit input required event
-    let _ = ctx.app_handle.emit(
-        "agent:input_required",
-        serde_json::to_value(AgentInputRequiredEvent {
-            agent_id: ctx.agent_id.clone(),
-            last_output: String::new(),
-        })
-        .unwrap(),
-    );
-
-    // Send wake event to meta-agent if it's sleeping
-    send_wake_event(ctx, AgentWakeReason::WaitingForInput).await;
+/// Handle successful task completion - mark the agent idle once the grace period passes
+fn handle_success_completion(ctx: &StreamContext) {
+    IdleTransition::from_stream(ctx).schedule("");
 }
 
 /// Send a wake event to the meta-agent if it's sleeping
 async fn send_wake_event(ctx: &StreamContext, reason: AgentWakeReason) {
-    let wake_tx_guard = ctx.agent_wake_tx.lock().await;
+    send_wake(&ctx.agent_wake_tx, &ctx.agent_id, reason).await;
+}
+
+/// Send a wake event for an agent through the shared wake sender, if set
+pub(crate) async fn send_wake(
+    agent_wake_tx: &Mutex<Option<mpsc::Sender<AgentWakeEvent>>>,
+    agent_id: &str,
+    reason: AgentWakeReason,
+) {
+    let wake_tx_guard = agent_wake_tx.lock().await;
     if let Some(ref tx) = *wake_tx_guard {
+        let description = reason.to_string();
         let event = AgentWakeEvent {
-            agent_id: ctx.agent_id.clone(),
+            agent_id: agent_id.to_string(),
             reason,
         };
         if let Err(e) = tx.send(event).await {
             eprintln!(
                 "[StreamContext] Failed to send wake event for agent {}: {}",
-                ctx.agent_id, e
+                agent_id, e
             );
         } else {
             eprintln!(
-                "[StreamContext] Sent wake event for agent {} (waiting for input)",
-                &ctx.agent_id[..8.min(ctx.agent_id.len())]
+                "[StreamContext] Sent wake event for agent {} ({})",
+                &agent_id[..8.min(agent_id.len())],
+                description
             );
         }
     }
@@ -184,7 +242,29 @@ async fn update_costs_in_database(ctx: &StreamContext, stats: &AgentStatistics)
                     run.model_usage = serde_json::to_string(&model_usage).ok();
                 }
 
-                let _ = runs_db.update_run(&run).await;
+                let _ = runs_db
+                    .write_or_dead_letter(DeadLetterWrite::UpdateRun { run })
+                    .await;
+            }
+        });
+    }
+}
+
+/// Attribute the usage of the cycle this result ends to the prompt that started it
+fn record_prompt_usage(ctx: &StreamContext, before: &AgentStatistics, after: &AgentStatistics) {
+    let usage = usage_delta(before, after);
+    if usage.is_empty() {
+        return;
+    }
+    if let Some(runs_db) = ctx.runs_db.clone() {
+        let agent_id = ctx.agent_id.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = runs_db.record_prompt_usage(&agent_id, &usage).await {
+                eprintln!(
+                    "[StreamContext] Failed to record prompt usage for agent {}: {}",
+                    agent_id, e
+                );
             }
         });
     }
@@ -211,7 +291,7 @@ pub(crate) async fn handle_stream_event(ctx: &StreamContext, json: &serde_json::
 
     let _ = ctx
         .app_handle
-        .emit("agent:output", serde_json::to_value(output_event).unwrap());
+        .emit("agent:output", ansi::ui_payload(&output_event));
 }
 
 /// Extract content from stream event JSON
@@ -254,7 +334,7 @@ pub(crate) async fn handle_unknown_message(
 
     let _ = ctx
         .app_handle
-        .emit("agent:output", serde_json::to_value(output_event).unwrap());
+        .emit("agent:output", ansi::ui_payload(&output_event));
 }
 
 /// Handle plain text (non-JSON) output
@@ -271,31 +351,53 @@ pub(crate) async fn handle_plain_text(ctx: &StreamContext, line: &str) {
 
     let _ = ctx
         .app_handle
-        .emit("agent:output", serde_json::to_value(output_event).unwrap());
+        .emit("agent:output", ansi::ui_payload(&output_event));
 
     // Persist to database
-    persist_output(&ctx.runs_db, &ctx.agent_id, &ctx.pipeline_id, "text", line).await;
+    persist_output(
+        &ctx.runs_db,
+        &ctx.agent_id,
+        &ctx.pipeline_id,
+        "text",
+        line,
+        output_event.timestamp,
+    )
+    .await;
 }
 
 /// Handle process end (completion or crash)
 pub(crate) async fn handle_process_end(ctx: &StreamContext) {
     // Process ended - check if it was expected or a crash
-    let (was_stopped, final_stats) = {
+    let (was_stopped, final_stats, exit_status, working_dir) = {
         let mut agents = ctx.agents.lock().await;
         if let Some(agent) = agents.get_mut(&ctx.agent_id) {
+            // Already reported by the health check
+            if agent.info.status == AgentStatus::Error {
+                return;
+            }
             let was_stopped = agent.info.status == AgentStatus::Stopped;
             let stats = agent.stats.clone();
+            let exit_status = agent
+                .child
+                .as_mut()
+                .and_then(|child| child.try_wait().ok().flatten());
             if !was_stopped {
                 agent.info.status = AgentStatus::Error;
             }
-            (was_stopped, Some(stats))
+            (
+                was_stopped,
+                Some(stats),
+                exit_status,
+                agent.info.working_dir.clone(),
+            )
         } else {
-            (false, None)
+            (false, None, None, String::new())
         }
     };
+    let message = crash_message(exit_status);
 
     // Update database - mark as crashed if not explicitly stopped
-    update_database_on_end(ctx, was_stopped, final_stats).await;
+    update_database_on_end(ctx, was_stopped, final_stats, &message, &working_dir).await;
 
     // Emit status event
     let status = if was_stopped {
@@ -310,6 +412,7 @@ pub(crate) async fn handle_process_end(ctx: &StreamContext) {
             agent_id: ctx.agent_id.clone(),
             status: status.clone(),
             info: None,
+            reason: None,
         })
         .unwrap(),
     );
@@ -318,48 +421,45 @@ pub(crate) async fn handle_process_end(ctx: &StreamContext) {
     let wake_reason = if was_stopped {
         AgentWakeReason::Stopped
     } else {
-        AgentWakeReason::Error("Process terminated unexpectedly".to_string())
+        AgentWakeReason::Error(message)
     };
     send_wake_event(ctx, wake_reason).await;
 }
 
-/// Update database when process ends
+/// Update database when process ends, and queue a crash for the meta-agent
 async fn update_database_on_end(
     ctx: &StreamContext,
     was_stopped: bool,
     final_stats: Option<std::sync::Arc<tokio::sync::Mutex<crate::types::AgentStatistics>>>,
+    message: &str,
+    working_dir: &str,
 ) {
-    if let Some(runs_db) = ctx.runs_db.clone() {
-        let agent_id = ctx.agent_id.clone();
-        tokio::spawn(async move {
-            if let Ok(Some(mut run)) = runs_db.get_run(&agent_id).await {
-                let now = now_millis();
-
-                if !was_stopped {
-                    run.status = RunStatus::Crashed;
-                    run.error_message = Some("Process terminated unexpectedly".to_string());
-                    run.can_resume = true;
-                }
-
-                run.ended_at = Some(now);
-                run.last_activity = now;
-
-                // Update final stats if available
-                if let Some(stats) = final_stats {
-                    let stats_lock = stats.lock().await;
-                    run.total_prompts = stats_lock.total_prompts;
-                    run.total_tool_calls = stats_lock.total_tool_calls;
-                    run.total_output_bytes = stats_lock.total_output_bytes;
-                    run.total_tokens_used = stats_lock.total_tokens_used;
-                    run.total_cost_usd = stats_lock.total_cost_usd;
-
-                    if let Some(ref model_usage) = stats_lock.model_usage {
-                        run.model_usage = serde_json::to_string(&model_usage).ok();
-                    }
-                }
-
-                let _ = runs_db.update_run(&run).await;
-            }
-        });
-    }
+    let runs_db = ctx.runs_db.clone();
+    let finalizer = ctx.session_finalizer.clone();
+    let agent_id = ctx.agent_id.clone();
+    let stderr_buffer = ctx.stderr_buffer.clone();
+    let result_queue = ctx.result_queue.clone();
+    let app_handle = ctx.app_handle.clone();
+    let message = message.to_string();
+    let working_dir = working_dir.to_string();
+    tokio::spawn(async move {
+        let end = if was_stopped {
+            SessionEnd::Stopped
+        } else {
+            // Attach recent stderr so a crash can be triaged without a repro
+            let stderr_tail = stderr_buffer.lock().await.tail(CRASH_STDERR_LINES);
+            let error_message = format_crash_message(&message, &stderr_tail);
+            queue_crash_result(
+                result_queue.as_ref(),
+                app_handle.as_ref(),
+                &agent_id,
+                &working_dir,
+                &error_message,
+            );
+            SessionEnd::Crashed { error_message }
+        };
+        finalizer
+            .finalize_agent_session(runs_db.as_ref(), &agent_id, final_stats, end)
+            .await;
+    });
 }
@@ -0,0 +1,258 @@
+// Run labels
+//
+// A run's label is a short name for it, shown in the agent list, run history
+// and cost breakdowns instead of the full initial prompt. When the first prompt
+// is sent, prompts that already fit are used as the label directly; longer ones
+// are condensed by the light model in the background. The relabel_run command
+// replaces the label by hand.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::agent_runs_db::AgentRunsDB;
+use crate::ai_client::{AIClient, Message};
+use crate::events::AppEventEmitter;
+use crate::types::AgentStatusEvent;
+use crate::utils::generator::extract_text_from_content_blocks;
+
+use super::types::AgentProcess;
+
+/// Labels are kept shorter than this many characters
+pub const LABEL_CHAR_LIMIT: usize = 60;
+
+/// Maximum characters of the prompt sent to the light model
+const MAX_PROMPT_CHARS: usize = 4_000;
+
+const LABEL_PROMPT: &str = r#"You write short labels for tasks given to a coding agent.
+
+The user will provide the task. Reply with a label of at most 8 words that says what the task is about, e.g. "Fix flaky retry test in pipeline runner".
+
+Important:
+- Output ONLY the label
+- No quotes, no trailing punctuation, no "Label:" prefix"#;
+
+/// The prompt itself, with whitespace collapsed, when it is short enough to be the label
+fn prompt_as_label(prompt: &str) -> Option<String> {
+    let collapsed = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!collapsed.is_empty() && collapsed.chars().count() < LABEL_CHAR_LIMIT).then_some(collapsed)
+}
+
+/// Cut a label down to fit the limit, at a word boundary where possible
+fn truncate_label(label: &str) -> String {
+    if label.chars().count() < LABEL_CHAR_LIMIT {
+        return label.to_string();
+    }
+
+    // Leave room for the ellipsis
+    let cut: String = label.chars().take(LABEL_CHAR_LIMIT - 2).collect();
+    let cut = match cut.rfind(' ') {
+        Some(i) if i > 0 => &cut[..i],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([',', '.', ';', ':']))
+}
+
+/// Normalize a model-generated label: first line only, no prefix, quotes or
+/// trailing punctuation, and shorter than LABEL_CHAR_LIMIT
+fn clean_label(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .strip_prefix("Label:")
+        .or_else(|| line.strip_prefix("label:"))
+        .unwrap_or(line);
+    let line = line
+        .trim()
+        .trim_matches(|c: char| c == '"' || c == '\'' || c == '*' || c == '#' || c == '`')
+        .trim_end_matches(['.', '!', ':', ';', ','])
+        .trim();
+
+    let label = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if label.is_empty() {
+        None
+    } else {
+        Some(truncate_label(&label))
+    }
+}
+
+/// Validate a label entered by the user
+pub(crate) fn validate_label(label: &str) -> Result<String, String> {
+    let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+    if label.is_empty() {
+        return Err("Label cannot be empty".to_string());
+    }
+    if label.chars().count() >= LABEL_CHAR_LIMIT {
+        return Err(format!(
+            "Label must be shorter than {} characters",
+            LABEL_CHAR_LIMIT
+        ));
+    }
+    Ok(label)
+}
+
+/// Ask the light model for a label describing the prompt
+async fn generate_label(prompt: &str) -> Result<String, String> {
+    let client =
+        AIClient::light_from_env().map_err(|e| format!("Failed to create light client: {}", e))?;
+
+    let request = vec![Message {
+        role: "user".to_string(),
+        content: prompt.chars().take(MAX_PROMPT_CHARS).collect(),
+    }];
+
+    let response = client
+        .send_message_with_system_and_tools(LABEL_PROMPT, request, vec![])
+        .await
+        .map_err(|e| format!("Light model call failed: {}", e))?;
+
+    clean_label(&extract_text_from_content_blocks(&response.content))
+        .ok_or_else(|| "Model returned an empty label".to_string())
+}
+
+/// Derive a label for a run from its initial prompt
+///
+/// Falls back to the truncated prompt when the light model is unavailable.
+pub(crate) async fn label_for_prompt(prompt: &str) -> Option<String> {
+    if let Some(label) = prompt_as_label(prompt) {
+        return Some(label);
+    }
+
+    match generate_label(prompt).await {
+        Ok(label) => Some(label),
+        Err(e) => {
+            eprintln!("[RunLabels] {}, using the prompt instead", e);
+            let collapsed = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!collapsed.is_empty()).then(|| truncate_label(&collapsed))
+        }
+    }
+}
+
+/// Store a label on the live agent (if any) and the run record
+///
+/// Fails if the agent is neither running nor recorded in the database.
+pub(crate) async fn apply_run_label(
+    agents: &Arc<Mutex<HashMap<String, AgentProcess>>>,
+    runs_db: &Option<Arc<AgentRunsDB>>,
+    app_handle: Option<&Arc<dyn AppEventEmitter>>,
+    agent_id: &str,
+    label: &str,
+) -> Result<(), String> {
+    let info = agents.lock().await.get_mut(agent_id).map(|agent| {
+        agent.info.label = Some(label.to_string());
+        agent.info.clone()
+    });
+
+    let in_history = match runs_db {
+        Some(db) => db
+            .update_run_label(agent_id, label)
+            .await
+            .map_err(|e| format!("Failed to save run label: {}", e))?,
+        None => false,
+    };
+    if info.is_none() && !in_history {
+        return Err(format!("Agent {} not found", agent_id));
+    }
+
+    if let (Some(info), Some(app_handle)) = (info, app_handle) {
+        if let Ok(status_event) = serde_json::to_value(AgentStatusEvent {
+            agent_id: agent_id.to_string(),
+            status: info.status.clone(),
+            info: Some(info),
+            reason: None,
+        }) {
+            let _ = app_handle.emit("agent:status", status_event);
+        }
+    }
+
+    Ok(())
+}
+
+/// Label a run from its initial prompt in the background
+///
+/// A label set by hand while the model is still working is kept.
+pub(crate) fn spawn_run_labeling(
+    agents: Arc<Mutex<HashMap<String, AgentProcess>>>,
+    runs_db: Option<Arc<AgentRunsDB>>,
+    app_handle: Option<Arc<dyn AppEventEmitter>>,
+    agent_id: String,
+    prompt: String,
+) {
+    tokio::spawn(async move {
+        let Some(label) = label_for_prompt(&prompt).await else {
+            return;
+        };
+
+        let live_label = agents
+            .lock()
+            .await
+            .get(&agent_id)
+            .map(|agent| agent.info.label.is_some());
+        let already_labeled = match (live_label, &runs_db) {
+            (Some(labeled), _) => labeled,
+            (None, Some(db)) => db
+                .get_run(&agent_id)
+                .await
+                .ok()
+                .flatten()
+                .is_some_and(|run| run.label.is_some()),
+            (None, None) => false,
+        };
+        if already_labeled {
+            return;
+        }
+
+        if let Err(e) =
+            apply_run_label(&agents, &runs_db, app_handle.as_ref(), &agent_id, &label).await
+        {
+            eprintln!("[RunLabels] Failed to label run {}: {}", agent_id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_prompt_is_used_as_label() {
+        assert_eq!(
+            prompt_as_label("  Fix the\n  login   bug "),
+            Some("Fix the login bug".to_string())
+        );
+        assert_eq!(prompt_as_label("   "), None);
+        assert_eq!(prompt_as_label(&"word ".repeat(20)), None);
+    }
+
+    #[test]
+    fn test_truncate_label_stays_under_limit() {
+        let long = "Refactor the pipeline orchestrator so that retries are scheduled with backoff";
+        let label = truncate_label(long);
+        assert!(label.chars().count() < LABEL_CHAR_LIMIT);
+        assert_eq!(
+            label,
+            "Refactor the pipeline orchestrator so that retries are…"
+        );
+
+        let unbroken = "x".repeat(100);
+        assert!(truncate_label(&unbroken).chars().count() < LABEL_CHAR_LIMIT);
+    }
+
+    #[test]
+    fn test_clean_label_strips_decoration() {
+        assert_eq!(
+            clean_label("\nLabel: \"Fix flaky retry test.\"\nBecause..."),
+            Some("Fix flaky retry test".to_string())
+        );
+        assert_eq!(clean_label("  \n"), None);
+    }
+
+    #[test]
+    fn test_validate_label() {
+        assert_eq!(
+            validate_label("  Nightly  cleanup "),
+            Ok("Nightly cleanup".to_string())
+        );
+        assert!(validate_label(" ").is_err());
+        assert!(validate_label(&"a".repeat(LABEL_CHAR_LIMIT)).is_err());
+    }
+}
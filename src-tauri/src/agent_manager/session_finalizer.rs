@@ -0,0 +1,257 @@
+// Session finalizer - what happens once when an agent's session ends
+//
+// However a session ends (stopped by the user, a pipeline or a stop phrase, or
+// the process exiting on its own) the run record gets its end status and final
+// statistics, which the cost summaries are built from, and the security
+// monitor forgets the agent. A stop and the process-end handler can race, so
+// each agent is finalized at most once: whichever path gets there first wins.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock, Weak};
+use tokio::sync::Mutex;
+
+use crate::agent_runs_db::{AgentRun, AgentRunsDB, DeadLetterWrite, RunStatus};
+use crate::security_monitor::SecurityMonitor;
+use crate::types::AgentStatistics;
+use crate::utils::time::now_millis;
+
+/// How an agent session ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEnd {
+    Stopped,
+    /// The process exited without being stopped
+    Crashed {
+        error_message: String,
+    },
+}
+
+/// Tracks which agents have been finalized
+#[derive(Default)]
+pub struct SessionFinalizer {
+    finalized: StdMutex<HashSet<String>>,
+    /// Weak because the monitor holds the agent manager
+    security_monitor: OnceLock<Weak<SecurityMonitor>>,
+}
+
+impl SessionFinalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear agents' security expectations when they are finalized
+    pub fn set_security_monitor(&self, monitor: &Arc<SecurityMonitor>) {
+        let _ = self.security_monitor.set(Arc::downgrade(monitor));
+    }
+
+    /// Mark an agent finalized; false if it already was
+    fn claim(&self, agent_id: &str) -> bool {
+        self.finalized
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(agent_id.to_string())
+    }
+
+    /// Drop an agent from the finalized set once it's removed from memory
+    pub fn forget(&self, agent_id: &str) {
+        self.finalized
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(agent_id);
+    }
+
+    /// Record the end of an agent's session. Returns false without doing
+    /// anything if another path already finalized it.
+    pub async fn finalize_agent_session(
+        &self,
+        runs_db: Option<&Arc<AgentRunsDB>>,
+        agent_id: &str,
+        stats: Option<Arc<Mutex<AgentStatistics>>>,
+        end: SessionEnd,
+    ) -> bool {
+        if !self.claim(agent_id) {
+            return false;
+        }
+
+        if let Some(runs_db) = runs_db {
+            match runs_db.get_run(agent_id).await {
+                Ok(Some(mut run)) => {
+                    apply_session_end(&mut run, end, now_millis());
+                    if let Some(stats) = stats {
+                        apply_final_stats(&mut run, &stats.lock().await);
+                    }
+                    let _ = runs_db
+                        .write_or_dead_letter(DeadLetterWrite::UpdateRun { run })
+                        .await;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!(
+                    "[SessionFinalizer] Failed to load run for {}: {}",
+                    agent_id, e
+                ),
+            }
+        }
+
+        if let Some(monitor) = self.security_monitor.get().and_then(Weak::upgrade) {
+            monitor.remove_agent_expectations(agent_id).await;
+        }
+        true
+    }
+}
+
+fn apply_session_end(run: &mut AgentRun, end: SessionEnd, now: i64) {
+    match end {
+        SessionEnd::Stopped => run.status = RunStatus::Stopped,
+        SessionEnd::Crashed { error_message } => {
+            run.status = RunStatus::Crashed;
+            run.error_message = Some(error_message);
+            run.can_resume = true;
+        }
+    }
+    run.ended_at = Some(now);
+    run.last_activity = now;
+}
+
+fn apply_final_stats(run: &mut AgentRun, stats: &AgentStatistics) {
+    run.total_prompts = stats.total_prompts;
+    run.total_tool_calls = stats.total_tool_calls;
+    run.total_output_bytes = stats.total_output_bytes;
+    run.total_tokens_used = stats.total_tokens_used;
+    run.total_cost_usd = stats.total_cost_usd;
+    if let Some(ref model_usage) = stats.model_usage {
+        run.model_usage = serde_json::to_string(model_usage).ok();
+    }
+    if !stats.tool_timings.is_empty() {
+        run.tool_timings = serde_json::to_string(&stats.tool_timings).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(agent_id: &str) -> AgentRun {
+        AgentRun {
+            id: None,
+            agent_id: agent_id.to_string(),
+            session_id: None,
+            working_dir: "/work/project".to_string(),
+            github_url: None,
+            github_context: None,
+            source: "ui".to_string(),
+            status: RunStatus::Running,
+            started_at: 1_000,
+            ended_at: None,
+            last_activity: 1_000,
+            initial_prompt: None,
+            error_message: None,
+            pipeline_id: None,
+            total_prompts: 0,
+            total_tool_calls: 0,
+            total_output_bytes: 0,
+            total_tokens_used: None,
+            total_cost_usd: None,
+            model_usage: None,
+            can_resume: false,
+            resume_data: None,
+            conventions_injected: false,
+            label: None,
+            workspace_id: crate::workspace::DEFAULT_WORKSPACE_ID.to_string(),
+            continued_from: None,
+            app_version: None,
+            git_commit: None,
+            schema_version: None,
+            stop_mode: None,
+            permission_profile: None,
+            model: None,
+            title: None,
+            env_keys: Vec::new(),
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+            repo_path: None,
+            worktree_path: None,
+            tool_timings: None,
+            cloned_from: None,
+        }
+    }
+
+    fn stats(cost: f64) -> Arc<Mutex<AgentStatistics>> {
+        let mut stats = super::super::statistics::create_initial_stats("agent-1".to_string());
+        stats.total_prompts = 3;
+        stats.total_cost_usd = Some(cost);
+        Arc::new(Mutex::new(stats))
+    }
+
+    #[tokio::test]
+    async fn test_racing_paths_finalize_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(AgentRunsDB::new(dir.path().join("runs.db")).unwrap());
+        db.create_run(&run("agent-1")).await.unwrap();
+        let finalizer = SessionFinalizer::new();
+
+        let (stopped, crashed) = tokio::join!(
+            finalizer.finalize_agent_session(
+                Some(&db),
+                "agent-1",
+                Some(stats(1.5)),
+                SessionEnd::Stopped
+            ),
+            finalizer.finalize_agent_session(
+                Some(&db),
+                "agent-1",
+                Some(stats(9.0)),
+                SessionEnd::Crashed {
+                    error_message: "exited".to_string()
+                }
+            ),
+        );
+        assert!(stopped ^ crashed, "exactly one path finalizes");
+
+        let saved = db.get_run("agent-1").await.unwrap().unwrap();
+        let expected_cost = if stopped { 1.5 } else { 9.0 };
+        assert_eq!(saved.total_cost_usd, Some(expected_cost));
+        assert_eq!(saved.total_prompts, 3);
+        assert_eq!(saved.working_dir, "/work/project");
+        assert!(saved.ended_at.is_some());
+
+        // A later stop doesn't overwrite the recorded end
+        assert!(
+            !finalizer
+                .finalize_agent_session(Some(&db), "agent-1", None, SessionEnd::Stopped)
+                .await
+        );
+        let again = db.get_run("agent-1").await.unwrap().unwrap();
+        assert_eq!(again.status, saved.status);
+    }
+
+    #[test]
+    fn test_crash_end_is_resumable() {
+        let mut run = run("agent-2");
+        apply_session_end(
+            &mut run,
+            SessionEnd::Crashed {
+                error_message: "boom".to_string(),
+            },
+            5_000,
+        );
+        assert_eq!(run.status, RunStatus::Crashed);
+        assert_eq!(run.error_message.as_deref(), Some("boom"));
+        assert!(run.can_resume);
+        assert_eq!(run.ended_at, Some(5_000));
+    }
+
+    #[tokio::test]
+    async fn test_tool_timings_are_stored_as_json() {
+        let mut run = run("agent-3");
+        let stats = stats(0.5);
+        super::super::statistics::record_tool_timing(&stats, "Bash", 120).await;
+        super::super::statistics::record_tool_timing(&stats, "Bash", 30).await;
+        apply_final_stats(&mut run, &*stats.lock().await);
+
+        let timings: serde_json::Value =
+            serde_json::from_str(run.tool_timings.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            timings,
+            serde_json::json!({ "Bash": { "calls": 2, "total_ms": 150, "max_ms": 120 } })
+        );
+    }
+}
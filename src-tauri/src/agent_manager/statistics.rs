@@ -3,6 +3,7 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::agent_runs_db::{ModelCostBreakdown, PromptUsage};
 use crate::types::{AgentStatistics, ModelUsageStats};
 
 /// Update statistics with output bytes
@@ -19,6 +20,15 @@ pub async fn increment_tool_calls(stats: &Arc<Mutex<AgentStatistics>>) {
     stats.last_activity = chrono::Utc::now().to_rfc3339();
 }
 
+/// Add a finished tool call's duration to the per-tool timings
+pub async fn record_tool_timing(stats: &Arc<Mutex<AgentStatistics>>, tool_name: &str, ms: u64) {
+    let mut stats = stats.lock().await;
+    let timing = stats.tool_timings.entry(tool_name.to_string()).or_default();
+    timing.calls += 1;
+    timing.total_ms += ms;
+    timing.max_ms = timing.max_ms.max(ms);
+}
+
 /// Increment prompt counter
 pub async fn increment_prompts(stats: &Arc<Mutex<AgentStatistics>>) {
     let mut stats = stats.lock().await;
@@ -126,6 +136,42 @@ pub async fn update_from_result(
     stats.last_activity = chrono::Utc::now().to_rfc3339();
 }
 
+/// Usage added between two snapshots of an agent's cumulative statistics
+///
+/// Taken around a result message, this is the usage of the prompt-response
+/// cycle that the result ends.
+pub fn usage_delta(before: &AgentStatistics, after: &AgentStatistics) -> PromptUsage {
+    let tokens = |s: &AgentStatistics| s.total_tokens_used.unwrap_or(0) as u64;
+    let cost = |s: &AgentStatistics| s.total_cost_usd.unwrap_or(0.0);
+
+    let mut model_usage = std::collections::HashMap::new();
+    for (model, now) in after.model_usage.iter().flatten() {
+        let prev = before.model_usage.as_ref().and_then(|m| m.get(model));
+        let diff = |field: fn(&ModelUsageStats) -> Option<u64>| {
+            field(now)
+                .unwrap_or(0)
+                .saturating_sub(prev.and_then(field).unwrap_or(0))
+        };
+        let breakdown = ModelCostBreakdown {
+            input_tokens: diff(|m| m.input_tokens),
+            output_tokens: diff(|m| m.output_tokens),
+            cache_creation_input_tokens: diff(|m| m.cache_creation_input_tokens),
+            cache_read_input_tokens: diff(|m| m.cache_read_input_tokens),
+            cost_usd: (now.cost_usd.unwrap_or(0.0) - prev.and_then(|m| m.cost_usd).unwrap_or(0.0))
+                .max(0.0),
+        };
+        if breakdown != ModelCostBreakdown::default() {
+            model_usage.insert(model.clone(), breakdown);
+        }
+    }
+
+    PromptUsage {
+        total_tokens: tokens(after).saturating_sub(tokens(before)),
+        cost_usd: (cost(after) - cost(before)).max(0.0),
+        model_usage,
+    }
+}
+
 /// Create initial statistics for a new agent
 pub fn create_initial_stats(agent_id: String) -> AgentStatistics {
     let session_start = chrono::Utc::now().to_rfc3339();
@@ -142,5 +188,52 @@ pub fn create_initial_stats(agent_id: String) -> AgentStatistics {
         duration_api_ms: None,
         duration_ms: None,
         num_turns: None,
+        stderr_lines: 0,
+        last_stderr_error: None,
+        tool_timings: std::collections::HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_usage_delta_covers_one_result() {
+        let stats = Arc::new(Mutex::new(create_initial_stats("a".to_string())));
+        let result = |cost: f64, input: u64| {
+            serde_json::json!({
+                "total_cost_usd": cost,
+                "usage": {"input_tokens": input, "output_tokens": 10},
+                "modelUsage": {"claude-sonnet": {"inputTokens": input, "outputTokens": 10, "costUSD": cost}}
+            })
+        };
+
+        update_from_result(&stats, &result(0.5, 100), 0).await;
+        let before = stats.lock().await.clone();
+        update_from_result(&stats, &result(0.25, 40), 0).await;
+        let after = stats.lock().await.clone();
+
+        let delta = usage_delta(&before, &after);
+        assert_eq!(delta.total_tokens, 50);
+        assert!((delta.cost_usd - 0.25).abs() < 1e-9);
+        let model = &delta.model_usage["claude-sonnet"];
+        assert_eq!(model.input_tokens, 40);
+        assert_eq!(model.output_tokens, 10);
+
+        assert!(usage_delta(&after, &after).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tool_timings_accumulate_per_tool() {
+        let stats = Arc::new(Mutex::new(create_initial_stats("a".to_string())));
+        record_tool_timing(&stats, "Bash", 1200).await;
+        record_tool_timing(&stats, "Bash", 300).await;
+        record_tool_timing(&stats, "Read", 5).await;
+
+        let stats = stats.lock().await;
+        let bash = &stats.tool_timings["Bash"];
+        assert_eq!((bash.calls, bash.total_ms, bash.max_ms), (2, 1500, 1200));
+        assert_eq!(stats.tool_timings["Read"].calls, 1);
     }
 }
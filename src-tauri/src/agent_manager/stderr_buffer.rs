@@ -0,0 +1,160 @@
+// Stderr buffering for agent processes
+//
+// Keeps the most recent stderr lines per agent so they can be surfaced as
+// diagnostics (statistics, crash reports) after the fact, and batches lines
+// into rate-limited agent:stderr events.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Maximum stderr lines kept in memory per agent
+const MAX_STDERR_LINES: usize = 200;
+
+/// Number of stderr lines attached to a crashed run's error message
+pub(crate) const CRASH_STDERR_LINES: usize = 50;
+
+/// Most stderr attached to a crashed run's error message, in bytes
+pub(crate) const CRASH_STDERR_BYTES: usize = 2048;
+
+/// Minimum interval between agent:stderr events for one agent
+pub(crate) const STDERR_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Markers that make a stderr line look like an error rather than noise
+const ERROR_MARKERS: [&str; 8] = [
+    "error",
+    "fatal",
+    "panic",
+    "exception",
+    "traceback",
+    "failed",
+    "denied",
+    "unhandled",
+];
+
+/// Ring buffer of recent stderr lines for one agent
+#[derive(Debug, Default)]
+pub struct StderrBuffer {
+    lines: VecDeque<String>,
+    total_lines: u32,
+}
+
+impl StderrBuffer {
+    /// Record a stderr line, dropping the oldest once the buffer is full
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == MAX_STDERR_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+        self.total_lines = self.total_lines.saturating_add(1);
+    }
+
+    /// Total stderr lines seen (including lines dropped from the buffer)
+    pub fn total_lines(&self) -> u32 {
+        self.total_lines
+    }
+
+    /// The last `n` buffered lines, oldest first
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let skip = self.lines.len().saturating_sub(n);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Batched stderr event emitted to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentStderrEvent {
+    pub agent_id: String,
+    pub lines: Vec<String>,
+    pub total_lines: u32,
+}
+
+/// Whether a stderr line looks like an error worth surfacing in statistics
+pub(crate) fn is_error_line(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    ERROR_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+/// Append the tail of stderr (at most CRASH_STDERR_BYTES) to a crash error message
+pub(crate) fn format_crash_message(message: &str, stderr_tail: &[String]) -> String {
+    if stderr_tail.is_empty() {
+        return message.to_string();
+    }
+    // The most recent lines that fit, and at least the end of the last one
+    let mut bytes = 0;
+    let fitting = stderr_tail
+        .iter()
+        .rev()
+        .take_while(|line| {
+            bytes += line.len() + 1;
+            bytes <= CRASH_STDERR_BYTES
+        })
+        .count();
+    let mut lines: Vec<&str> = stderr_tail[stderr_tail.len() - fitting.max(1)..]
+        .iter()
+        .map(String::as_str)
+        .collect();
+    if fitting == 0 {
+        let last = lines[0];
+        let mut start = last.len() - CRASH_STDERR_BYTES;
+        while !last.is_char_boundary(start) {
+            start += 1;
+        }
+        lines[0] = &last[start..];
+    }
+    format!(
+        "{}\n\nLast {} stderr line(s):\n{}",
+        message,
+        lines.len(),
+        lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_keeps_recent_lines() {
+        let mut buffer = StderrBuffer::default();
+        for i in 0..MAX_STDERR_LINES + 10 {
+            buffer.push(format!("line {}", i));
+        }
+
+        assert_eq!(buffer.total_lines(), (MAX_STDERR_LINES + 10) as u32);
+        let tail = buffer.tail(2);
+        assert_eq!(
+            tail,
+            vec![
+                format!("line {}", MAX_STDERR_LINES + 8),
+                format!("line {}", MAX_STDERR_LINES + 9)
+            ]
+        );
+        assert_eq!(buffer.tail(usize::MAX).len(), MAX_STDERR_LINES);
+    }
+
+    #[test]
+    fn test_is_error_line() {
+        assert!(is_error_line("Error: ENOENT: no such file or directory"));
+        assert!(is_error_line("thread 'main' panicked at src/main.rs"));
+        assert!(!is_error_line("Debugger listening on ws://127.0.0.1"));
+    }
+
+    #[test]
+    fn test_format_crash_message() {
+        assert_eq!(format_crash_message("crashed", &[]), "crashed");
+
+        let message = format_crash_message("crashed", &["a".to_string(), "b".to_string()]);
+        assert!(message.starts_with("crashed\n\nLast 2 stderr line(s):"));
+        assert!(message.ends_with("a\nb"));
+
+        // Only the most recent stderr is kept
+        let long = vec!["x".repeat(1500), "y".repeat(1000), "z".repeat(900)];
+        let message = format_crash_message("crashed", &long);
+        assert!(message.starts_with("crashed\n\nLast 2 stderr line(s):"));
+        assert!(message.ends_with(&"z".repeat(900)));
+        let message = format_crash_message("crashed", &["é".repeat(2000)]);
+        assert!(message.len() <= "crashed\n\nLast 1 stderr line(s):\n".len() + CRASH_STDERR_BYTES);
+    }
+}
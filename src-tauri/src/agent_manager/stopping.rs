@@ -0,0 +1,315 @@
+// Stopping agents
+//
+// A stop is taken in two parts so callers don't hold the AgentManager lock
+// while an agent drains. begin_stop marks the agent stopped and takes its
+// process and stream handlers out of it, which is quick. PendingStop::finish
+// then waits for the process to exit (or kills it) and cleans up, using only
+// the shared state it was given, so it can run after the lock is released and
+// alongside other stops. stop_agents stops several agents at once this way.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+use crate::agent_runs_db::{AgentRunsDB, StopMode};
+use crate::commands::env_registry;
+use crate::logger::Logger;
+use crate::types::{AgentStatistics, AgentStatus};
+use crate::utils::time::now_millis;
+
+use super::session_finalizer::{SessionEnd, SessionFinalizer};
+use super::{prompt_files, worktrees, AgentManager, AgentProcess};
+
+/// Default time a stopped agent gets to exit on its own (AGENT_STOP_TIMEOUT_SECS)
+const DEFAULT_STOP_TIMEOUT_SECS: u64 = 10;
+
+/// Time the stream handlers of an agent that exited on its own get to read
+/// its last output
+const HANDLER_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn stop_timeout_secs() -> u64 {
+    env_registry::AGENT_STOP_TIMEOUT_SECS
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_STOP_TIMEOUT_SECS)
+}
+
+/// An agent marked stopped whose process hasn't been drained yet
+pub struct PendingStop {
+    agent_id: String,
+    drain_timeout: Duration,
+    final_stats: Option<Arc<Mutex<AgentStatistics>>>,
+    child: Option<tokio::process::Child>,
+    stdin_handle: Option<JoinHandle<()>>,
+    stdout_handle: Option<JoinHandle<()>>,
+    stderr_handle: Option<JoinHandle<()>>,
+    settings_path: Option<std::path::PathBuf>,
+    session_id: Option<String>,
+    agents: Arc<Mutex<HashMap<String, AgentProcess>>>,
+    session_to_agent: Arc<Mutex<HashMap<String, String>>>,
+    runs_db: Option<Arc<AgentRunsDB>>,
+    logger: Option<Arc<Logger>>,
+    session_finalizer: Arc<SessionFinalizer>,
+}
+
+impl PendingStop {
+    /// Wait for the process to exit on its own (killing it after the drain
+    /// timeout), then clean up after the agent and record the stop on its run
+    pub async fn finish(self) -> Result<StopMode, String> {
+        let PendingStop {
+            agent_id,
+            drain_timeout,
+            final_stats,
+            child,
+            stdin_handle,
+            stdout_handle,
+            stderr_handle,
+            settings_path,
+            session_id,
+            agents,
+            session_to_agent,
+            runs_db,
+            logger,
+            session_finalizer,
+        } = self;
+        let agent_id = agent_id.as_str();
+
+        // Step 2: Wait for the process to exit on its own, then kill it
+        let stop_mode = match child {
+            Some(mut child) => {
+                let exited = !drain_timeout.is_zero()
+                    && matches!(
+                        tokio::time::timeout(drain_timeout, child.wait()).await,
+                        Ok(Ok(_))
+                    );
+                if exited {
+                    // Let the stream handlers read the last of the output
+                    for mut handle in [stdout_handle, stderr_handle].into_iter().flatten() {
+                        if tokio::time::timeout(HANDLER_DRAIN_TIMEOUT, &mut handle)
+                            .await
+                            .is_err()
+                        {
+                            handle.abort();
+                        }
+                    }
+                    if let Some(handle) = stdin_handle {
+                        handle.abort();
+                    }
+                    StopMode::Graceful
+                } else {
+                    for handle in [stdin_handle, stdout_handle, stderr_handle]
+                        .into_iter()
+                        .flatten()
+                    {
+                        handle.abort();
+                    }
+                    // Give stream handlers a moment to notice abort before killing child
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    let _ = child.kill().await;
+                    StopMode::Forced
+                }
+            }
+            // Already exited
+            None => {
+                for handle in [stdin_handle, stdout_handle, stderr_handle]
+                    .into_iter()
+                    .flatten()
+                {
+                    handle.abort();
+                }
+                StopMode::Graceful
+            }
+        };
+
+        // Step 3: Clean up session_to_agent map
+        if let Some(sid) = session_id {
+            let mut session_map = session_to_agent.lock().await;
+            session_map.remove(&sid);
+        }
+
+        // Step 4: Update state
+        let mut agents_guard = agents.lock().await;
+        let agent = agents_guard
+            .get_mut(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+
+        // Clean up generated skills
+        if !agent.generated_skill_names.is_empty() {
+            let working_dir = agent.info.working_dir.clone();
+            let skill_names = agent.generated_skill_names.clone();
+            if let Err(e) =
+                crate::skill_generator::cleanup_generated_skills(&working_dir, &skill_names)
+            {
+                eprintln!("Warning: Failed to cleanup skills: {}", e);
+            }
+            if let Some(ref runs_db) = runs_db {
+                if let Err(e) = runs_db
+                    .mark_agent_artifacts_removed(agent_id, now_millis())
+                    .await
+                {
+                    eprintln!("Warning: Failed to mark skills removed: {}", e);
+                }
+            }
+        }
+
+        agent.info.status = AgentStatus::Stopped;
+        agent.stopped_at = Some(Instant::now());
+
+        // Delete the files large prompts were handed over in
+        prompt_files::remove_prompt_files(
+            &agent.info.working_dir,
+            &std::mem::take(&mut agent.prompt_files),
+        );
+
+        // Step 5: Clean up hooks and MCP config files
+        if let Some(path) = settings_path {
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("Warning: Failed to cleanup hooks config {:?}: {}", path, e);
+            }
+        }
+        if let Some(path) = agent.mcp_config_path.take() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("Warning: Failed to cleanup MCP config {:?}: {}", path, e);
+            }
+        }
+        let worktree = agent.info.worktree.clone().filter(|_| !agent.keep_worktree);
+
+        drop(agents_guard);
+
+        // Step 6: Remove the agent's worktree unless it is to be kept (or
+        // has uncommitted changes, which keeps it for the user to look at)
+        if let Some(worktree) = worktree {
+            if let Some(notice) = worktrees::remove_agent_worktree(worktree).await {
+                if let Some(ref logger) = logger {
+                    let _ = logger
+                        .warning("worktree", &notice, Some(agent_id.to_string()), None)
+                        .await;
+                }
+                if let Some(agent) = agents.lock().await.get_mut(agent_id) {
+                    agent.keep_worktree = true;
+                    agent.worktree_notice = Some(notice);
+                }
+            }
+        }
+
+        // Record the end of the session in the run (unless the process-end handler already did)
+        session_finalizer
+            .finalize_agent_session(runs_db.as_ref(), agent_id, final_stats, SessionEnd::Stopped)
+            .await;
+        if let Some(ref runs_db) = runs_db {
+            if let Err(e) = runs_db.set_stop_mode(agent_id, stop_mode).await {
+                eprintln!(
+                    "Warning: Failed to record how agent {} stopped: {}",
+                    agent_id, e
+                );
+            }
+        }
+
+        Ok(stop_mode)
+    }
+}
+
+impl AgentManager {
+    /// Stop an agent, giving it AGENT_STOP_TIMEOUT_SECS to exit on its own
+    pub async fn stop_agent(&self, agent_id: &str) -> Result<(), String> {
+        self.stop_agent_with(agent_id, false, None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Stop an agent. Unless `force`, its stdin is closed first so it can
+    /// finish what it is writing and exit; it is killed only if it is still
+    /// running after `drain_timeout_secs` (AGENT_STOP_TIMEOUT_SECS by default).
+    /// The run records which of the two happened.
+    pub async fn stop_agent_with(
+        &self,
+        agent_id: &str,
+        force: bool,
+        drain_timeout_secs: Option<u64>,
+    ) -> Result<StopMode, String> {
+        self.begin_stop(agent_id, force, drain_timeout_secs)
+            .await?
+            .finish()
+            .await
+    }
+
+    /// Start stopping an agent: mark it stopped, close its stdin and take its
+    /// process out of it. Finishing the returned stop (which may take the
+    /// drain timeout) doesn't need the manager, so callers can release its
+    /// lock first.
+    pub async fn begin_stop(
+        &self,
+        agent_id: &str,
+        force: bool,
+        drain_timeout_secs: Option<u64>,
+    ) -> Result<PendingStop, String> {
+        let drain_timeout = if force {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(drain_timeout_secs.unwrap_or_else(stop_timeout_secs))
+        };
+
+        let mut agents = self.agents.lock().await;
+        let agent = agents
+            .get_mut(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+
+        // Marked stopped up front so the process exiting during the drain
+        // isn't taken for a crash
+        agent.info.status = AgentStatus::Stopped;
+
+        // Step 1: Drop stdin_tx to signal the stdin handler to exit, which
+        // closes the process's stdin
+        agent.stdin_tx = None;
+
+        Ok(PendingStop {
+            agent_id: agent_id.to_string(),
+            drain_timeout,
+            final_stats: Some(agent.stats.clone()),
+            child: agent.child.take(),
+            stdin_handle: agent.stdin_handle.take(),
+            stdout_handle: agent.stdout_handle.take(),
+            stderr_handle: agent.stderr_handle.take(),
+            settings_path: agent.settings_path.take(),
+            session_id: agent.info.session_id.clone(),
+            agents: self.agents.clone(),
+            session_to_agent: self.session_to_agent.clone(),
+            runs_db: self.runs_db.clone(),
+            logger: self.logger.clone(),
+            session_finalizer: self.session_finalizer.clone(),
+        })
+    }
+
+    /// Stop several agents at once, draining them concurrently. Returns each
+    /// agent's outcome in the order given.
+    pub async fn stop_agents(
+        &self,
+        agent_ids: &[String],
+        force: bool,
+    ) -> Vec<(String, Result<StopMode, String>)> {
+        let mut begun = Vec::with_capacity(agent_ids.len());
+        for agent_id in agent_ids {
+            begun.push(self.begin_stop(agent_id, force, None).await);
+        }
+        finish_stops(agent_ids, begun).await
+    }
+}
+
+/// Finish stops begun for `agent_ids` concurrently, pairing each agent with
+/// its outcome (or the error beginning its stop)
+pub async fn finish_stops(
+    agent_ids: &[String],
+    begun: Vec<Result<PendingStop, String>>,
+) -> Vec<(String, Result<StopMode, String>)> {
+    let outcomes = futures::future::join_all(begun.into_iter().map(|pending| async move {
+        match pending {
+            Ok(pending) => pending.finish().await,
+            Err(e) => Err(e),
+        }
+    }))
+    .await;
+    agent_ids.iter().cloned().zip(outcomes).collect()
+}
@@ -6,16 +6,19 @@
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::ChildStdout;
+use tokio::sync::Mutex;
 use tokio::time::Instant;
 
-use crate::agent_runs_db::{AgentOutputRecord, AgentRunsDB};
+use crate::agent_runs_db::{AgentOutputRecord, AgentRunsDB, BufferedEvent};
+use crate::fault_injection::{check_fault, FaultPoint};
+use crate::types::AgentStatistics;
 use crate::utils::time::now_millis;
 
 use super::event_handlers::{
     handle_assistant_message, handle_plain_text, handle_process_end, handle_result_message,
     handle_stream_event, handle_system_message, handle_unknown_message, handle_user_message,
 };
-use super::output_builder::OutputEventBuilder;
+use super::stderr_buffer::{is_error_line, AgentStderrEvent, StderrBuffer, STDERR_EMIT_INTERVAL};
 
 // Re-export StreamContext for use by mod.rs
 pub use super::event_handlers::StreamContext;
@@ -39,6 +42,17 @@ async fn handle_stdout_stream(stdout: ChildStdout, ctx: StreamContext) {
         // Update activity timestamp
         *ctx.last_activity.lock().await = Instant::now();
 
+        // Injected fault: the stream ends halfway through this message
+        if let Err(e) = check_fault(&ctx.fault_hook, FaultPoint::StdoutEof) {
+            eprintln!("[AgentManager] {} for agent {}", e, ctx.agent_id);
+            let mut cut = line.len() / 2;
+            while !line.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            handle_plain_text(&ctx, &line[..cut]).await;
+            break;
+        }
+
         // Try to parse as JSON
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
             // Extract session_id if present
@@ -46,9 +60,27 @@ async fn handle_stdout_stream(stdout: ChildStdout, ctx: StreamContext) {
                 let mut map = ctx.session_map.lock().await;
                 map.insert(session_id.to_string(), ctx.agent_id.clone());
 
-                let mut agents = ctx.agents.lock().await;
-                if let Some(agent) = agents.get_mut(&ctx.agent_id) {
-                    agent.info.session_id = Some(session_id.to_string());
+                let is_new = {
+                    let mut agents = ctx.agents.lock().await;
+                    match agents.get_mut(&ctx.agent_id) {
+                        Some(agent) if agent.info.session_id.as_deref() != Some(session_id) => {
+                            agent.info.session_id = Some(session_id.to_string());
+                            true
+                        }
+                        _ => false,
+                    }
+                };
+
+                // Kept on the run so a crashed agent can be resumed
+                if is_new {
+                    if let Some(ref db) = ctx.runs_db {
+                        if let Err(e) = db.set_run_session_id(&ctx.agent_id, session_id).await {
+                            eprintln!(
+                                "[AgentManager] Failed to record session for {}: {}",
+                                ctx.agent_id, e
+                            );
+                        }
+                    }
                 }
             }
 
@@ -99,39 +131,91 @@ async fn dispatch_message(
 }
 
 /// Spawn the stderr stream handler task, returns JoinHandle for cleanup
+///
+/// Each line is buffered for crash diagnostics, counted in the agent's
+/// statistics and persisted with output_type "stderr". Lines are batched
+/// into agent:stderr events at most once per STDERR_EMIT_INTERVAL.
 pub fn spawn_stderr_handler(
     stderr: tokio::process::ChildStderr,
     agent_id: String,
     app_handle: Arc<dyn crate::events::AppEventEmitter>,
     runs_db: Option<Arc<AgentRunsDB>>,
     pipeline_id: Option<String>,
+    stats: Arc<Mutex<AgentStatistics>>,
+    stderr_buffer: Arc<Mutex<StderrBuffer>>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let reader = BufReader::new(stderr);
         let mut lines = reader.lines();
+        let mut pending: Vec<String> = Vec::new();
+        let mut last_emit: Option<Instant> = None;
+
+        loop {
+            // Wake up after the emit interval so a trailing batch is not held back
+            let line = match tokio::time::timeout(STDERR_EMIT_INTERVAL, lines.next_line()).await {
+                Ok(Ok(Some(line))) => line,
+                Ok(_) => break,
+                Err(_) => {
+                    emit_stderr_batch(&app_handle, &agent_id, &stderr_buffer, &mut pending).await;
+                    continue;
+                }
+            };
+
+            let total_lines = {
+                let mut buffer = stderr_buffer.lock().await;
+                buffer.push(line.clone());
+                buffer.total_lines()
+            };
+
+            {
+                let mut stats = stats.lock().await;
+                stats.stderr_lines = total_lines;
+                if is_error_line(&line) {
+                    stats.last_stderr_error = Some(line.clone());
+                }
+            }
 
-        while let Ok(Some(line)) = lines.next_line().await {
-            let output_event = OutputEventBuilder::new(agent_id.clone())
-                .output_type("error")
-                .content(&line)
-                .with_size_from_content()
-                .build();
-
-            let _ = app_handle.emit("agent:output", serde_json::to_value(output_event).unwrap());
-
-            // Persist error to database (await instead of spawning detached task)
+            // Persist to database (buffered, written in batches)
             if let Some(ref db) = runs_db {
                 let record = AgentOutputRecord {
                     id: None,
                     agent_id: agent_id.clone(),
                     pipeline_id: pipeline_id.clone(),
-                    output_type: "error".to_string(),
+                    output_type: "stderr".to_string(),
                     content: line.clone(),
                     metadata: None,
                     timestamp: now_millis(),
+                    turn_index: None,
                 };
-                let _ = db.insert_agent_output(&record).await;
+                db.buffer_event(BufferedEvent::AgentOutput(record)).await;
+            }
+
+            pending.push(line);
+            if !matches!(last_emit, Some(t) if t.elapsed() < STDERR_EMIT_INTERVAL) {
+                emit_stderr_batch(&app_handle, &agent_id, &stderr_buffer, &mut pending).await;
+                last_emit = Some(Instant::now());
             }
         }
+
+        emit_stderr_batch(&app_handle, &agent_id, &stderr_buffer, &mut pending).await;
     })
 }
+
+/// Emit the pending stderr lines as a single agent:stderr event
+async fn emit_stderr_batch(
+    app_handle: &Arc<dyn crate::events::AppEventEmitter>,
+    agent_id: &str,
+    stderr_buffer: &Arc<Mutex<StderrBuffer>>,
+    pending: &mut Vec<String>,
+) {
+    if pending.is_empty() {
+        return;
+    }
+
+    let event = AgentStderrEvent {
+        agent_id: agent_id.to_string(),
+        lines: std::mem::take(pending),
+        total_lines: stderr_buffer.lock().await.total_lines(),
+    };
+    let _ = app_handle.emit("agent:stderr", serde_json::to_value(event).unwrap());
+}
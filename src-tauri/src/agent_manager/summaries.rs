@@ -0,0 +1,132 @@
+// Agent summaries - what the agent cards show, built in one call
+//
+// list_agents_detailed returns each agent's info with its counters, the start
+// of its latest reply, its todo progress and whether a security review is
+// waiting on it, so the sidebar doesn't need a statistics and an outputs call
+// per agent.
+//
+// Agent info is also flagged when the agent's output shows tool calls but no
+// hook event has arrived for it, which usually means its hooks aren't set up.
+
+use crate::hook_server::{has_hook_events, AgentTodoItem};
+use crate::types::{AgentInfo, AgentOutputEvent, AgentStatistics, AgentStatsSummary, TodoProgress};
+use crate::utils::string::truncate_with_ellipsis;
+
+/// Reply snippets are cut to this many bytes
+pub const SNIPPET_BYTE_LIMIT: usize = 200;
+
+/// The counters an agent card shows
+pub fn stats_summary(stats: &AgentStatistics) -> AgentStatsSummary {
+    AgentStatsSummary {
+        total_prompts: stats.total_prompts,
+        total_tool_calls: stats.total_tool_calls,
+        total_tokens_used: stats.total_tokens_used,
+        total_cost_usd: stats.total_cost_usd,
+        last_activity: stats.last_activity.clone(),
+    }
+}
+
+/// Start of the latest assistant text in the output buffer, on one line
+pub fn last_message_snippet(events: &[AgentOutputEvent]) -> Option<String> {
+    let text = events
+        .iter()
+        .rev()
+        .filter(|e| e.output_type == "text")
+        .map(|e| e.content.trim())
+        .find(|content| !content.is_empty())?;
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(truncate_with_ellipsis(&line, SNIPPET_BYTE_LIMIT))
+}
+
+/// Completed and total items of a todo list (None for an empty list)
+pub fn todo_progress(todos: &[AgentTodoItem]) -> Option<TodoProgress> {
+    if todos.is_empty() {
+        return None;
+    }
+    Some(TodoProgress {
+        completed: todos.iter().filter(|t| t.status == "completed").count() as u32,
+        total: todos.len() as u32,
+    })
+}
+
+/// Whether the agent has made tool calls without any hook event arriving
+pub fn hooks_missing(agent_id: &str, stats: &AgentStatistics) -> bool {
+    stats.total_tool_calls > 0 && !has_hook_events(agent_id)
+}
+
+/// An agent's info with its hooks-missing flag brought up to date
+pub async fn current_info(agent: &super::AgentProcess) -> AgentInfo {
+    let mut info = agent.info.clone();
+    info.hooks_missing = hooks_missing(&info.id, &*agent.stats.lock().await);
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(output_type: &str, content: &str) -> AgentOutputEvent {
+        AgentOutputEvent {
+            agent_id: "agent-1".to_string(),
+            output_type: output_type.to_string(),
+            content: content.to_string(),
+            parsed_json: None,
+            metadata: None,
+            session_id: None,
+            uuid: None,
+            parent_tool_use_id: None,
+            subtype: None,
+            timestamp: None,
+        }
+    }
+
+    fn todo(status: &str) -> AgentTodoItem {
+        AgentTodoItem {
+            content: "step".to_string(),
+            status: status.to_string(),
+            active_form: None,
+        }
+    }
+
+    #[test]
+    fn test_snippet_is_latest_text_on_one_line_and_truncated() {
+        let events = vec![
+            output("text", "First reply"),
+            output("text", "Done.\n\nAll  tests pass."),
+            output("tool_use", "Bash"),
+            output("text", "   "),
+        ];
+        assert_eq!(
+            last_message_snippet(&events).as_deref(),
+            Some("Done. All tests pass.")
+        );
+
+        let long = output("text", &"word ".repeat(100));
+        let snippet = last_message_snippet(&[long]).unwrap();
+        assert_eq!(snippet.len(), SNIPPET_BYTE_LIMIT + 3);
+        assert!(snippet.ends_with("..."));
+
+        assert_eq!(last_message_snippet(&[output("tool_use", "Read")]), None);
+    }
+
+    #[test]
+    fn test_hooks_missing_needs_tool_calls() {
+        let mut stats = super::super::statistics::create_initial_stats("quiet-agent".to_string());
+        assert!(!hooks_missing("quiet-agent", &stats));
+        stats.total_tool_calls = 2;
+        assert!(hooks_missing("quiet-agent", &stats));
+    }
+
+    #[test]
+    fn test_todo_progress() {
+        assert_eq!(todo_progress(&[]), None);
+        let todos = vec![todo("completed"), todo("in_progress"), todo("pending")];
+        assert_eq!(
+            todo_progress(&todos),
+            Some(TodoProgress {
+                completed: 1,
+                total: 3
+            })
+        );
+    }
+}
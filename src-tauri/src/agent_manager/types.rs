@@ -1,5 +1,6 @@
 // Agent manager types
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::process::Child;
@@ -7,7 +8,12 @@ use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
 
-use crate::types::{AgentInfo, AgentOutputEvent, AgentStatistics};
+use crate::types::{AgentInfo, AgentStatistics, ToolRestrictions};
+
+use super::idle_debounce::IdleDebounce;
+use super::output_buffer::OutputBuffer;
+use super::prompt_queue::PromptQueue;
+use super::stderr_buffer::StderrBuffer;
 
 /// Represents a running agent process with its associated state
 pub struct AgentProcess {
@@ -18,10 +24,18 @@ pub struct AgentProcess {
     pub is_processing: Arc<Mutex<bool>>,
     pub pending_input: Arc<Mutex<bool>>,
     pub stats: Arc<Mutex<AgentStatistics>>,
-    pub output_buffer: Arc<Mutex<Vec<AgentOutputEvent>>>,
+    pub output_buffer: Arc<Mutex<OutputBuffer>>,
+    /// Recent stderr lines (shared with the stderr handler)
+    pub stderr_buffer: Arc<Mutex<StderrBuffer>>,
     pub generated_skill_names: Vec<String>,
+    /// GitHub URL given at spawn (used when refreshing the GitHub context)
+    pub github_url: Option<String>,
     /// Path to the hooks config file (for cleanup)
     pub settings_path: Option<PathBuf>,
+    /// Path to the MCP config file, if the agent has MCP servers (for cleanup)
+    pub mcp_config_path: Option<PathBuf>,
+    /// MCP servers given at spawn, on top of WORKER_MCP_CONFIG (kept for hand-offs)
+    pub mcp_servers: Option<serde_json::Value>,
     /// JoinHandle for stdin handler task (for cleanup)
     pub stdin_handle: Option<JoinHandle<()>>,
     /// JoinHandle for stdout stream handler task (for cleanup)
@@ -30,4 +44,54 @@ pub struct AgentProcess {
     pub stderr_handle: Option<JoinHandle<()>>,
     /// Timestamp when agent was stopped (for cleanup timer)
     pub stopped_at: Option<Instant>,
+    /// Whether the repository conventions digest is prepended to the first prompt
+    pub inject_conventions: bool,
+    /// Defers the idle transition after a turn ends (shared with the stream handler)
+    pub idle_debounce: Arc<IdleDebounce>,
+    /// Pipeline that spawned the agent, if any
+    pub pipeline_id: Option<String>,
+    /// Extra env vars given at spawn (kept for hand-offs)
+    pub extra_env: HashMap<String, String>,
+    /// Tool lists given at spawn, on top of the profile's (kept for hand-offs)
+    pub tools: ToolRestrictions,
+    /// Whether the agent's git worktree is left in place when it stops
+    pub keep_worktree: bool,
+    /// Why the worktree was kept when the agent stopped (uncommitted
+    /// changes), until it is shown to the user
+    pub worktree_notice: Option<String>,
+    /// Whether "shall I proceed?" questions go to the auto follow-up policy
+    pub auto_follow_up: bool,
+    /// Auto follow-up replies sent to this agent so far
+    pub auto_follow_ups: u32,
+    /// Files large prompts were handed over in (deleted when the agent stops)
+    pub prompt_files: Vec<PathBuf>,
+    /// Prompts sent while the agent was busy, sent when its turn ends
+    pub prompt_queue: PromptQueue,
+}
+
+/// Optional settings for a new agent. The defaults spawn it in its working
+/// directory under its source's permission profile, with CLAUDE_CODE_MODEL
+/// and only WORKER_MCP_CONFIG's MCP servers.
+#[derive(Debug, Clone, Default)]
+pub struct AgentSpawnOptions {
+    /// Pipeline the agent works for
+    pub pipeline_id: Option<String>,
+    pub title: Option<String>,
+    /// Model to start it with instead of CLAUDE_CODE_MODEL
+    pub model: Option<String>,
+    /// Complexity level shown in the UI
+    pub complexity: Option<String>,
+    /// Settings file whose hooks are merged with the tracking hooks
+    pub hooks_file: Option<String>,
+    /// Permission profile to use instead of the source's
+    pub permission_profile: Option<String>,
+    /// Set on top of the inherited environment, except for API keys while
+    /// CLAUDE_CODE_API_KEY_MODE is blocked
+    pub extra_env: HashMap<String, String>,
+    /// MCP config in claude's format, merged over WORKER_MCP_CONFIG's servers
+    pub mcp_servers: Option<serde_json::Value>,
+    /// Tools allowed and disallowed on top of the profile's lists
+    pub tools: ToolRestrictions,
+    /// Run in a new git worktree of the repository
+    pub use_worktree: bool,
 }
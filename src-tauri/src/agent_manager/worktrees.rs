@@ -0,0 +1,134 @@
+// Git worktrees for agents
+//
+// Agents working in the same repository edit the same files. An agent created
+// with use_worktree gets a worktree of its own instead: `git worktree add
+// .grove/worktrees/<agent_id> -b grove/<agent_id>` under the repository root.
+// It runs in the worktree (in the subdirectory matching the one it was given),
+// and the repository and worktree are recorded on AgentInfo and the run.
+// Stopping the agent removes the worktree unless keep_worktree is set; the
+// branch is kept if it has commits HEAD doesn't. A worktree with uncommitted
+// changes is never removed: it is kept with its branch and the user is told
+// where it is. Hand-offs and resumed agents carry on in the same worktree.
+
+use std::path::Path;
+
+use crate::agent_runs_db::AgentRun;
+use crate::events::AppEventEmitter;
+use crate::github::{self, WorktreeRemoval};
+use crate::types::AgentWorktree;
+use crate::utils::validation::strip_verbatim_prefix;
+
+/// Which worktree a spawned agent runs in
+pub(crate) enum WorktreeSetup {
+    /// None: the agent works in its working directory
+    None,
+    /// A new one, added for the agent
+    Create,
+    /// One another agent worked in (hand-offs and resumes)
+    Existing(AgentWorktree),
+}
+
+impl WorktreeSetup {
+    pub(crate) fn requested(use_worktree: bool) -> Self {
+        if use_worktree {
+            WorktreeSetup::Create
+        } else {
+            WorktreeSetup::None
+        }
+    }
+}
+
+/// The worktree a run's agent worked in, if it had one
+pub(crate) fn run_worktree(run: &AgentRun) -> Option<AgentWorktree> {
+    Some(AgentWorktree {
+        repo_path: run.repo_path.clone()?,
+        path: run.worktree_path.clone()?,
+        branch: github::worktree_branch(&run.agent_id),
+    })
+}
+
+/// Set up an agent's worktree. Returns it with the agent's working directory,
+/// which for a new worktree is inside it.
+pub(crate) async fn prepare_worktree(
+    setup: WorktreeSetup,
+    working_dir: &str,
+    agent_id: &str,
+) -> Result<(Option<AgentWorktree>, String), String> {
+    let worktree = match setup {
+        WorktreeSetup::None => return Ok((None, working_dir.to_string())),
+        WorktreeSetup::Existing(worktree) => return Ok((Some(worktree), working_dir.to_string())),
+        WorktreeSetup::Create => {
+            let (dir, id) = (working_dir.to_string(), agent_id.to_string());
+            tokio::task::spawn_blocking(move || github::create_worktree(&dir, &id))
+                .await
+                .map_err(|e| format!("Failed to create worktree: {}", e))??
+        }
+    };
+    let working_dir = working_dir_in(&worktree, working_dir);
+    Ok((Some(worktree), working_dir))
+}
+
+/// The directory in the worktree matching `working_dir` in the repository
+fn working_dir_in(worktree: &AgentWorktree, working_dir: &str) -> String {
+    std::fs::canonicalize(working_dir)
+        .ok()
+        .and_then(|dir| dir.to_str().map(strip_verbatim_prefix))
+        .and_then(|dir| {
+            let relative = Path::new(&dir).strip_prefix(&worktree.repo_path).ok()?;
+            Some(Path::new(&worktree.path).join(relative))
+        })
+        .filter(|dir| dir.is_dir())
+        .and_then(|dir| dir.to_str().map(str::to_string))
+        .unwrap_or_else(|| worktree.path.clone())
+}
+
+/// Remove an agent's worktree, logging instead of failing. Returns a notice
+/// for the user when it was kept because it has uncommitted changes.
+pub(crate) async fn remove_agent_worktree(worktree: AgentWorktree) -> Option<String> {
+    if !Path::new(&worktree.path).exists() {
+        return None;
+    }
+    let (path, branch) = (worktree.path.clone(), worktree.branch.clone());
+    match tokio::task::spawn_blocking(move || github::remove_worktree(&worktree)).await {
+        Ok(Ok(WorktreeRemoval::Removed {
+            branch_deleted: true,
+        })) => None,
+        Ok(Ok(WorktreeRemoval::Removed {
+            branch_deleted: false,
+        })) => {
+            eprintln!(
+                "[Worktree] Kept branch {}, which has commits HEAD doesn't",
+                branch
+            );
+            None
+        }
+        Ok(Ok(WorktreeRemoval::KeptDirty(changed))) => {
+            let notice = format!(
+                "Kept worktree {} (branch {}): it has uncommitted changes in {} files",
+                path, branch, changed
+            );
+            eprintln!("[Worktree] {}", notice);
+            Some(notice)
+        }
+        Ok(Err(e)) => {
+            eprintln!("Warning: {}", e);
+            None
+        }
+        Err(e) => {
+            eprintln!("Warning: Failed to remove worktree: {}", e);
+            None
+        }
+    }
+}
+
+/// Tell the user about a worktree kept because of uncommitted changes
+pub(crate) fn notify_kept_worktree(app_handle: &dyn AppEventEmitter, notice: &str) {
+    let _ = app_handle.emit(
+        "toast",
+        serde_json::json!({
+            "type": "warning",
+            "message": notice,
+            "duration": 8000,
+        }),
+    );
+}
@@ -0,0 +1,142 @@
+// Inter-agent message persistence
+//
+// Every message the commander relays from one agent to another is recorded so
+// the cross-talk shows up in pipeline history after the agents are gone.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+use crate::types::AgentMessage;
+
+const MESSAGE_COLUMNS: &str = "id, pipeline_id, from_agent_id, to_agent_id, text, timestamp";
+
+/// Helper to convert a row to AgentMessage
+fn row_to_message(row: &rusqlite::Row) -> SqliteResult<AgentMessage> {
+    Ok(AgentMessage {
+        id: Some(row.get(0)?),
+        pipeline_id: row.get(1)?,
+        from_agent_id: row.get(2)?,
+        to_agent_id: row.get(3)?,
+        text: row.get(4)?,
+        timestamp: row.get(5)?,
+    })
+}
+
+/// Operations for inter-agent messages
+pub struct AgentMessageOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> AgentMessageOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert a message and return its row ID
+    pub async fn add_message(&self, message: &AgentMessage) -> SqliteResult<i64> {
+        let message = message.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO agent_messages (pipeline_id, from_agent_id, to_agent_id, text, timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        message.pipeline_id,
+                        message.from_agent_id,
+                        message.to_agent_id,
+                        message.text,
+                        message.timestamp
+                    ],
+                )?;
+                Ok(db.last_insert_rowid())
+            })
+            .await
+    }
+
+    /// Messages exchanged within a pipeline, oldest first
+    pub async fn get_pipeline_messages(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Vec<AgentMessage>> {
+        let pipeline_id = pipeline_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {} FROM agent_messages WHERE pipeline_id = ?1
+                     ORDER BY timestamp ASC, id ASC",
+                    MESSAGE_COLUMNS
+                ))?;
+                let messages = stmt.query_map(params![pipeline_id], row_to_message)?;
+                messages.collect()
+            })
+            .await
+    }
+
+    /// Messages sent or received by an agent, oldest first
+    pub async fn get_agent_messages(&self, agent_id: &str) -> SqliteResult<Vec<AgentMessage>> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {} FROM agent_messages WHERE from_agent_id = ?1 OR to_agent_id = ?1
+                     ORDER BY timestamp ASC, id ASC",
+                    MESSAGE_COLUMNS
+                ))?;
+                let messages = stmt.query_map(params![agent_id], row_to_message)?;
+                messages.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(pipeline_id: Option<&str>, from: &str, to: &str, timestamp: i64) -> AgentMessage {
+        AgentMessage {
+            id: None,
+            pipeline_id: pipeline_id.map(String::from),
+            from_agent_id: from.to_string(),
+            to_agent_id: to.to_string(),
+            text: format!("{} -> {}", from, to),
+            timestamp,
+        }
+    }
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_agent_messages_table(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_messages_by_pipeline_and_agent() {
+        let db = test_db();
+        let ops = AgentMessageOps::new(&db);
+        ops.add_message(&message(Some("p1"), "verifier", "builder", 20))
+            .await
+            .unwrap();
+        ops.add_message(&message(Some("p1"), "builder", "verifier", 30))
+            .await
+            .unwrap();
+        ops.add_message(&message(None, "a1", "a2", 10))
+            .await
+            .unwrap();
+
+        let pipeline = ops.get_pipeline_messages("p1").await.unwrap();
+        assert_eq!(pipeline.len(), 2);
+        assert_eq!(pipeline[0].from_agent_id, "verifier");
+        assert!(pipeline[0].id.is_some());
+
+        let agent = ops.get_agent_messages("a2").await.unwrap();
+        assert_eq!(agent.len(), 1);
+        assert_eq!(agent[0].pipeline_id, None);
+        assert_eq!(ops.get_agent_messages("builder").await.unwrap().len(), 2);
+    }
+}
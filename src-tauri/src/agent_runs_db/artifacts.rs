@@ -0,0 +1,212 @@
+// Generated artifact persistence
+//
+// Skills, subagents and CLAUDE.md files are written into the working
+// directory, where they can be rewritten or cleaned up later. Their content is
+// stored here as it was generated, so a pipeline's artifacts can be inspected
+// after the fact. Cleanup marks records removed instead of deleting them.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::GeneratedArtifactRecord;
+
+/// Helper to convert a row (without content) to GeneratedArtifactRecord
+fn row_to_artifact(row: &rusqlite::Row) -> SqliteResult<GeneratedArtifactRecord> {
+    Ok(GeneratedArtifactRecord {
+        id: Some(row.get(0)?),
+        pipeline_id: row.get(1)?,
+        agent_id: row.get(2)?,
+        kind: row.get(3)?,
+        name: row.get(4)?,
+        path: row.get(5)?,
+        content: None,
+        content_hash: row.get(6)?,
+        size_bytes: row.get(7)?,
+        pipeline_state: row.get(8)?,
+        created_at: row.get(9)?,
+        removed_at: row.get(10)?,
+    })
+}
+
+/// Operations for generated artifacts
+pub struct ArtifactOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> ArtifactOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert an artifact and return its row ID
+    pub async fn insert_artifact(&self, record: &GeneratedArtifactRecord) -> SqliteResult<i64> {
+        let record = record.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO generated_artifacts
+                     (pipeline_id, agent_id, kind, name, path, content, content_hash,
+                      size_bytes, pipeline_state, created_at, removed_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        record.pipeline_id,
+                        record.agent_id,
+                        record.kind,
+                        record.name,
+                        record.path,
+                        record.content.unwrap_or_default(),
+                        record.content_hash,
+                        record.size_bytes,
+                        record.pipeline_state,
+                        record.created_at,
+                        record.removed_at
+                    ],
+                )?;
+                Ok(db.last_insert_rowid())
+            })
+            .await
+    }
+
+    /// Get a pipeline's artifacts (without content), oldest first
+    pub async fn list_pipeline_artifacts(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Vec<GeneratedArtifactRecord>> {
+        let pipeline_id = pipeline_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT id, pipeline_id, agent_id, kind, name, path, content_hash,
+                            size_bytes, pipeline_state, created_at, removed_at
+                     FROM generated_artifacts
+                     WHERE pipeline_id = ?1 ORDER BY created_at ASC, id ASC",
+                )?;
+                let artifacts = stmt.query_map(params![pipeline_id], row_to_artifact)?;
+                artifacts.collect()
+            })
+            .await
+    }
+
+    /// Get an artifact's content as generated
+    pub async fn get_artifact_content(&self, id: i64) -> SqliteResult<Option<String>> {
+        self.db
+            .with_db(move |db| {
+                let mut stmt =
+                    db.prepare("SELECT content FROM generated_artifacts WHERE id = ?1")?;
+                let mut rows = stmt.query(params![id])?;
+
+                if let Some(row) = rows.next()? {
+                    Ok(Some(row.get(0)?))
+                } else {
+                    Ok(None)
+                }
+            })
+            .await
+    }
+
+    /// Mark an agent's artifacts removed; returns how many were marked
+    pub async fn mark_agent_artifacts_removed(
+        &self,
+        agent_id: &str,
+        removed_at: i64,
+    ) -> SqliteResult<usize> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "UPDATE generated_artifacts SET removed_at = ?2
+                     WHERE agent_id = ?1 AND removed_at IS NULL",
+                    params![agent_id, removed_at],
+                )
+            })
+            .await
+    }
+
+    /// Mark a pipeline's artifacts removed; returns how many were marked
+    pub async fn mark_pipeline_artifacts_removed(
+        &self,
+        pipeline_id: &str,
+        removed_at: i64,
+    ) -> SqliteResult<usize> {
+        let pipeline_id = pipeline_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "UPDATE generated_artifacts SET removed_at = ?2
+                     WHERE pipeline_id = ?1 AND removed_at IS NULL",
+                    params![pipeline_id, removed_at],
+                )
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(
+        pipeline_id: Option<&str>,
+        agent_id: Option<&str>,
+        name: &str,
+    ) -> GeneratedArtifactRecord {
+        GeneratedArtifactRecord {
+            id: None,
+            pipeline_id: pipeline_id.map(String::from),
+            agent_id: agent_id.map(String::from),
+            kind: "skill".to_string(),
+            name: name.to_string(),
+            path: format!("/repo/.claude/skills/{}", name),
+            content: Some(format!("# {}", name)),
+            content_hash: "0123456789abcdef".to_string(),
+            size_bytes: 3 + name.len() as i64,
+            pipeline_state: Some("SkillSynthesis".to_string()),
+            created_at: 10,
+            removed_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_are_listed_and_marked_removed() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_generated_artifacts_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = ArtifactOps::new(&db);
+
+        let id = ops
+            .insert_artifact(&artifact(Some("p1"), None, "testing"))
+            .await
+            .unwrap();
+        ops.insert_artifact(&artifact(None, Some("a1"), "linting"))
+            .await
+            .unwrap();
+
+        let listed = ops.list_pipeline_artifacts("p1").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "testing");
+        assert_eq!(listed[0].content, None);
+        assert_eq!(
+            ops.get_artifact_content(id).await.unwrap().as_deref(),
+            Some("# testing")
+        );
+        assert_eq!(ops.get_artifact_content(999).await.unwrap(), None);
+
+        assert_eq!(ops.mark_agent_artifacts_removed("a1", 50).await.unwrap(), 1);
+        assert_eq!(ops.mark_agent_artifacts_removed("a1", 60).await.unwrap(), 0);
+        assert_eq!(
+            ops.mark_pipeline_artifacts_removed("p1", 70).await.unwrap(),
+            1
+        );
+        let listed = ops.list_pipeline_artifacts("p1").await.unwrap();
+        assert_eq!(listed[0].removed_at, Some(70));
+        // Removed artifacts keep their content
+        assert!(ops.get_artifact_content(id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,230 @@
+// Commander conversation compactions and message pins
+//
+// Each compaction of the commander's history is stored with the summary and
+// the range of messages it replaced, so the user can compare the two. Pins
+// record which messages the user wants kept verbatim through compaction (or
+// explicitly doesn't, for messages that would be kept automatically).
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::{CompactionRecord, MessagePin};
+
+fn row_to_compaction(row: &rusqlite::Row) -> SqliteResult<CompactionRecord> {
+    let emergency: i32 = row.get(7)?;
+    let preserved: Option<String> = row.get(8)?;
+    Ok(CompactionRecord {
+        id: Some(row.get(0)?),
+        conversation_id: row.get(1)?,
+        summary: row.get(2)?,
+        first_message_index: row.get(3)?,
+        last_message_index: row.get(4)?,
+        tokens_before: row.get(5)?,
+        tokens_after: row.get(6)?,
+        emergency: emergency != 0,
+        preserved_message_indexes: preserved
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        created_at: row.get(9)?,
+    })
+}
+
+/// Operations for compactions and message pins
+pub struct CompactionOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> CompactionOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Store a compaction and return its row ID
+    pub async fn record_compaction(&self, compaction: &CompactionRecord) -> SqliteResult<i64> {
+        let compaction = compaction.clone();
+        let preserved = serde_json::to_string(&compaction.preserved_message_indexes)
+            .unwrap_or_else(|_| "[]".to_string());
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO meta_compactions (
+                        conversation_id, summary, first_message_index, last_message_index,
+                        tokens_before, tokens_after, emergency, preserved_message_indexes, created_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        compaction.conversation_id,
+                        compaction.summary,
+                        compaction.first_message_index,
+                        compaction.last_message_index,
+                        compaction.tokens_before,
+                        compaction.tokens_after,
+                        if compaction.emergency { 1 } else { 0 },
+                        preserved,
+                        compaction.created_at,
+                    ],
+                )?;
+                Ok(db.last_insert_rowid())
+            })
+            .await
+    }
+
+    /// The most recent compaction of a conversation
+    pub async fn get_last_compaction(
+        &self,
+        conversation_id: &str,
+    ) -> SqliteResult<Option<CompactionRecord>> {
+        let conversation_id = conversation_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT id, conversation_id, summary, first_message_index, last_message_index,
+                            tokens_before, tokens_after, emergency, preserved_message_indexes, created_at
+                     FROM meta_compactions WHERE conversation_id = ?1
+                     ORDER BY created_at DESC, id DESC LIMIT 1",
+                )?;
+                let mut rows = stmt.query_map(params![conversation_id], row_to_compaction)?;
+                rows.next().transpose()
+            })
+            .await
+    }
+
+    /// Pin or unpin a message; false if the conversation has no message at that index
+    pub async fn set_message_pin(
+        &self,
+        conversation_id: &str,
+        message_index: u32,
+        pinned: bool,
+    ) -> SqliteResult<bool> {
+        let conversation_id = conversation_id.to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db(move |db| {
+                let exists: bool = db.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM meta_messages
+                     WHERE conversation_id = ?1 AND message_index = ?2)",
+                    params![conversation_id, message_index],
+                    |row| row.get(0),
+                )?;
+                if !exists {
+                    return Ok(false);
+                }
+                db.execute(
+                    "INSERT INTO meta_message_pins (conversation_id, message_index, pinned, updated_at)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(conversation_id, message_index) DO UPDATE SET
+                        pinned = excluded.pinned,
+                        updated_at = excluded.updated_at",
+                    params![conversation_id, message_index, pinned, now],
+                )?;
+                Ok(true)
+            })
+            .await
+    }
+
+    /// Pins of a conversation with the pinned messages, oldest message first
+    pub async fn get_message_pins(&self, conversation_id: &str) -> SqliteResult<Vec<MessagePin>> {
+        let conversation_id = conversation_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT p.message_index, p.pinned, m.role, m.content
+                     FROM meta_message_pins p
+                     JOIN meta_messages m
+                       ON m.conversation_id = p.conversation_id AND m.message_index = p.message_index
+                     WHERE p.conversation_id = ?1
+                     GROUP BY p.message_index
+                     ORDER BY p.message_index ASC",
+                )?;
+                let pins = stmt.query_map(params![conversation_id], |row| {
+                    Ok(MessagePin {
+                        message_index: row.get(0)?,
+                        pinned: row.get(1)?,
+                        role: row.get(2)?,
+                        content: row.get(3)?,
+                    })
+                })?;
+                pins.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::models::MetaMessageRecord;
+    use crate::agent_runs_db::AgentRunsDB;
+
+    fn message(index: u32, role: &str, content: &str) -> MetaMessageRecord {
+        MetaMessageRecord {
+            id: None,
+            conversation_id: "conv".to_string(),
+            message_index: index,
+            role: role.to_string(),
+            content: content.to_string(),
+            image_data: None,
+            tool_calls: None,
+            timestamp: index as i64,
+            model: None,
+            personality_hash: None,
+            rating: None,
+            rating_note: None,
+            rated_at: None,
+        }
+    }
+
+    fn compaction(summary: &str, created_at: i64) -> CompactionRecord {
+        CompactionRecord {
+            id: None,
+            conversation_id: "conv".to_string(),
+            summary: summary.to_string(),
+            first_message_index: 1,
+            last_message_index: 4,
+            tokens_before: 900,
+            tokens_after: 200,
+            emergency: false,
+            preserved_message_indexes: vec![1],
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compactions_and_pins_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentRunsDB::new(dir.path().join("runs.db")).unwrap();
+        for (i, role) in ["user", "assistant"].iter().enumerate() {
+            db.insert_meta_message(&message(i as u32 + 1, role, &format!("message {}", i + 1)))
+                .await
+                .unwrap();
+        }
+
+        assert!(db.get_last_meta_compaction("conv").await.unwrap().is_none());
+        db.record_meta_compaction(&compaction("first", 10))
+            .await
+            .unwrap();
+        db.record_meta_compaction(&compaction("second", 20))
+            .await
+            .unwrap();
+        let last = db.get_last_meta_compaction("conv").await.unwrap().unwrap();
+        assert_eq!(last.summary, "second");
+        assert_eq!(last.preserved_message_indexes, vec![1]);
+
+        assert!(db.set_meta_message_pin("conv", 1, true).await.unwrap());
+        assert!(db.set_meta_message_pin("conv", 2, true).await.unwrap());
+        assert!(db.set_meta_message_pin("conv", 2, false).await.unwrap());
+        assert!(!db.set_meta_message_pin("conv", 9, true).await.unwrap());
+
+        let pins = db.get_meta_message_pins("conv").await.unwrap();
+        assert_eq!(pins.len(), 2);
+        assert_eq!((pins[0].message_index, pins[0].pinned), (1, true));
+        assert_eq!(pins[0].content, "message 1");
+        assert_eq!((pins[1].message_index, pins[1].pinned), (2, false));
+    }
+}
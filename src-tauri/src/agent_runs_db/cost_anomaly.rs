@@ -0,0 +1,395 @@
+// Cost anomaly detection
+//
+// A background check compares the spend of the last hour and the last 24 hours
+// with what is normal for this install: the median spend of the same hour on
+// each of the previous 14 days, and the median daily cost over those days.
+// Spend above COST_ANOMALY_MULTIPLIER times the baseline (and above a minimum,
+// so a few cents over a near-zero baseline don't count) emits `cost:anomaly`
+// with the agents that spent the most, and optionally an OS notification.
+// Until a few days of history exist only the minimums apply.
+
+use chrono::{Duration as ChronoDuration, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::commands::env_registry::{self, EnvVar};
+
+use super::models::{DailyCost, PromptSpend};
+
+/// How often spend is checked
+pub const COST_ANOMALY_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Days of history the baselines are taken from
+pub const BASELINE_DAYS: i64 = 14;
+
+/// Days of history needed before baselines are trusted
+pub const MIN_HISTORY_DAYS: usize = 3;
+
+/// Agents listed as top contributors
+const TOP_AGENTS_LIMIT: usize = 5;
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// Sensitivity of the check, from the environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostAnomalyConfig {
+    /// Spend must exceed this multiple of the baseline (0 disables the check)
+    pub multiplier: f64,
+    /// Spend in the last hour below this is never an anomaly
+    pub min_hourly_usd: f64,
+    /// Spend in the last 24 hours below this is never an anomaly
+    pub min_daily_usd: f64,
+    /// Also show an OS notification
+    pub notify: bool,
+}
+
+fn env_f64(var: &EnvVar, default: f64) -> f64 {
+    var.var()
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .unwrap_or(default)
+}
+
+impl CostAnomalyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            multiplier: env_f64(&env_registry::COST_ANOMALY_MULTIPLIER, 3.0),
+            min_hourly_usd: env_f64(&env_registry::COST_ANOMALY_MIN_HOURLY_USD, 2.0),
+            min_daily_usd: env_f64(&env_registry::COST_ANOMALY_MIN_DAILY_USD, 10.0),
+            notify: env_registry::COST_ANOMALY_NOTIFY.flag(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.multiplier > 0.0
+    }
+}
+
+/// Rolling window a check covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum SpendWindow {
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "24h")]
+    Day,
+}
+
+impl SpendWindow {
+    pub fn length_ms(&self) -> i64 {
+        match self {
+            SpendWindow::Hour => HOUR_MS,
+            SpendWindow::Day => DAY_MS,
+        }
+    }
+}
+
+/// An agent's share of the spend in the window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentSpend {
+    pub agent_id: String,
+    pub label: Option<String>,
+    pub spend_usd: f64,
+}
+
+/// Payload of `cost:anomaly`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostAnomalyEvent {
+    pub window: SpendWindow,
+    pub spend_usd: f64,
+    /// Normal spend for the window (None until there is enough history)
+    pub baseline_usd: Option<f64>,
+    /// Spend above this was reported
+    pub threshold_usd: f64,
+    pub multiplier: f64,
+    /// Days of history the baseline was taken from
+    pub history_days: usize,
+    pub top_agents: Vec<AgentSpend>,
+    pub detected_at: i64,
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+fn spend_between(spend: &[PromptSpend], start: i64, end: i64) -> f64 {
+    spend
+        .iter()
+        .filter(|s| s.timestamp >= start && s.timestamp < end)
+        .map(|s| s.cost_usd)
+        .sum()
+}
+
+fn top_agents(spend: &[PromptSpend], start: i64) -> Vec<AgentSpend> {
+    let mut by_agent: HashMap<&str, AgentSpend> = HashMap::new();
+    for s in spend.iter().filter(|s| s.timestamp >= start) {
+        by_agent
+            .entry(s.agent_id.as_str())
+            .or_insert_with(|| AgentSpend {
+                agent_id: s.agent_id.clone(),
+                label: s.label.clone(),
+                spend_usd: 0.0,
+            })
+            .spend_usd += s.cost_usd;
+    }
+    let mut agents: Vec<AgentSpend> = by_agent.into_values().collect();
+    agents.sort_by(|a, b| b.spend_usd.total_cmp(&a.spend_usd));
+    agents.truncate(TOP_AGENTS_LIMIT);
+    agents
+}
+
+/// Start of a "YYYY-MM-DD" day in Unix milliseconds (UTC)
+fn day_start_ms(date: &str) -> Option<i64> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some(
+        Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0)?)
+            .timestamp_millis(),
+    )
+}
+
+/// Compare recent spend with the baselines and return the windows that are abnormal
+///
+/// `spend` covers at least the last BASELINE_DAYS + 1 days and `daily` the
+/// daily costs over the same period.
+pub fn detect_anomalies(
+    now: i64,
+    spend: &[PromptSpend],
+    daily: &[DailyCost],
+    config: &CostAnomalyConfig,
+) -> Vec<CostAnomalyEvent> {
+    if !config.enabled() {
+        return Vec::new();
+    }
+
+    // History starts with the earliest spend on record; days before it don't count
+    let first_spend = spend
+        .iter()
+        .map(|s| s.timestamp)
+        .chain(daily.iter().filter_map(|d| day_start_ms(&d.date)))
+        .min();
+    let in_history = |start: i64| first_spend.is_some_and(|first| first <= start);
+
+    // Same hour on each previous day
+    let hourly: Vec<f64> = (1..=BASELINE_DAYS)
+        .map(|d| now - d * DAY_MS - HOUR_MS)
+        .filter(|start| in_history(*start))
+        .map(|start| spend_between(spend, start, start + HOUR_MS))
+        .collect();
+
+    // Previous full days; days without runs cost nothing
+    let Some(today) = Utc
+        .timestamp_millis_opt(now)
+        .single()
+        .map(|t| t.date_naive())
+    else {
+        return Vec::new();
+    };
+    let by_date: HashMap<&str, f64> = daily
+        .iter()
+        .map(|d| (d.date.as_str(), d.cost_usd))
+        .collect();
+    let daily_costs: Vec<f64> = (1..=BASELINE_DAYS)
+        .map(|d| {
+            (today - ChronoDuration::days(d))
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .filter(|date| day_start_ms(date).is_some_and(|start| in_history(start + DAY_MS - 1)))
+        .map(|date| by_date.get(date.as_str()).copied().unwrap_or(0.0))
+        .collect();
+
+    [
+        (SpendWindow::Hour, hourly, config.min_hourly_usd),
+        (SpendWindow::Day, daily_costs, config.min_daily_usd),
+    ]
+    .into_iter()
+    .filter_map(|(window, history, minimum)| {
+        let history_days = history.len();
+        let baseline = if history_days >= MIN_HISTORY_DAYS {
+            median(history)
+        } else {
+            None
+        };
+        let threshold = baseline
+            .map(|b| b * config.multiplier)
+            .unwrap_or(0.0)
+            .max(minimum);
+        let start = now - window.length_ms();
+        let spend_usd = spend_between(spend, start, now + 1);
+        (spend_usd > threshold).then(|| CostAnomalyEvent {
+            window,
+            spend_usd,
+            baseline_usd: baseline,
+            threshold_usd: threshold,
+            multiplier: config.multiplier,
+            history_days,
+            top_agents: top_agents(spend, start),
+            detected_at: now,
+        })
+    })
+    .collect()
+}
+
+/// One-line description for logs and notifications
+pub fn describe(event: &CostAnomalyEvent) -> String {
+    let window = match event.window {
+        SpendWindow::Hour => "last hour",
+        SpendWindow::Day => "last 24 hours",
+    };
+    let baseline = match event.baseline_usd {
+        Some(b) => format!("usual ${:.2}", b),
+        None => "not enough history for a baseline".to_string(),
+    };
+    let top = event
+        .top_agents
+        .first()
+        .map(|a| {
+            format!(
+                "; top: {} (${:.2})",
+                a.label.as_deref().unwrap_or(&a.agent_id),
+                a.spend_usd
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "${:.2} spent in the {} ({}){}",
+        event.spend_usd, window, baseline, top
+    )
+}
+
+/// Windows an alert was last raised for, so each is reported once per window length
+#[derive(Debug, Default)]
+pub struct AlertLog {
+    last_alert: HashMap<SpendWindow, i64>,
+}
+
+impl AlertLog {
+    /// Whether to report this anomaly, recording it if so
+    pub fn should_alert(&mut self, event: &CostAnomalyEvent) -> bool {
+        let due = match self.last_alert.get(&event.window) {
+            Some(last) => event.detected_at - last >= event.window.length_ms(),
+            None => true,
+        };
+        if due {
+            self.last_alert.insert(event.window, event.detected_at);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_767_268_800_000; // 2026-01-01 12:00 UTC
+
+    fn spent(agent_id: &str, timestamp: i64, cost_usd: f64) -> PromptSpend {
+        PromptSpend {
+            agent_id: agent_id.to_string(),
+            label: None,
+            timestamp,
+            cost_usd,
+        }
+    }
+
+    fn config() -> CostAnomalyConfig {
+        CostAnomalyConfig {
+            multiplier: 3.0,
+            min_hourly_usd: 2.0,
+            min_daily_usd: 10.0,
+            notify: false,
+        }
+    }
+
+    /// $1 at this hour and $5 per day for each of the previous 14 days
+    fn steady_history() -> (Vec<PromptSpend>, Vec<DailyCost>) {
+        let mut spend = Vec::new();
+        let mut daily = Vec::new();
+        for d in 1..=BASELINE_DAYS {
+            spend.push(spent("old", NOW - d * DAY_MS - HOUR_MS / 2, 1.0));
+            daily.push(DailyCost {
+                date: (Utc.timestamp_millis_opt(NOW).unwrap().date_naive()
+                    - ChronoDuration::days(d))
+                .format("%Y-%m-%d")
+                .to_string(),
+                cost_usd: 5.0,
+                session_count: 1,
+            });
+        }
+        (spend, daily)
+    }
+
+    #[test]
+    fn test_spend_above_baseline_multiple_is_reported_with_top_agents() {
+        let (mut spend, daily) = steady_history();
+        spend.push(spent("runaway", NOW - 10 * 60 * 1000, 20.0));
+        spend.push(spent("quiet", NOW - 5 * 60 * 1000, 0.5));
+
+        let events = detect_anomalies(NOW, &spend, &daily, &config());
+        assert_eq!(events.len(), 2);
+
+        let hour = &events[0];
+        assert_eq!(hour.window, SpendWindow::Hour);
+        assert_eq!(hour.baseline_usd, Some(1.0));
+        assert_eq!(hour.threshold_usd, 3.0);
+        assert_eq!(hour.history_days, 14);
+        assert_eq!(hour.top_agents[0].agent_id, "runaway");
+        assert_eq!(hour.top_agents.len(), 2);
+
+        let day = &events[1];
+        assert_eq!(day.window, SpendWindow::Day);
+        assert_eq!(day.baseline_usd, Some(5.0));
+        assert_eq!(day.threshold_usd, 15.0);
+        assert!(describe(day).contains("top: runaway ($20.00)"));
+    }
+
+    #[test]
+    fn test_normal_spend_and_sparse_history() {
+        let (mut spend, daily) = steady_history();
+        spend.push(spent("a1", NOW - 10 * 60 * 1000, 2.5));
+        assert!(detect_anomalies(NOW, &spend, &daily, &config()).is_empty());
+
+        // A fresh install has no baseline; only the minimums apply
+        let fresh = vec![spent("a1", NOW - 10 * 60 * 1000, 2.5)];
+        let events = detect_anomalies(NOW, &fresh, &[], &config());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].baseline_usd, None);
+        assert_eq!(events[0].history_days, 0);
+        assert_eq!(events[0].threshold_usd, 2.0);
+
+        let disabled = CostAnomalyConfig {
+            multiplier: 0.0,
+            ..config()
+        };
+        assert!(detect_anomalies(NOW, &fresh, &[], &disabled).is_empty());
+    }
+
+    #[test]
+    fn test_alerts_once_per_window() {
+        let fresh = vec![spent("a1", NOW - 10 * 60 * 1000, 2.5)];
+        let event = detect_anomalies(NOW, &fresh, &[], &config()).remove(0);
+        let mut log = AlertLog::default();
+        assert!(log.should_alert(&event));
+        let later = CostAnomalyEvent {
+            detected_at: NOW + 30 * 60 * 1000,
+            ..event.clone()
+        };
+        assert!(!log.should_alert(&later));
+        let next_hour = CostAnomalyEvent {
+            detected_at: NOW + HOUR_MS,
+            ..event
+        };
+        assert!(log.should_alert(&next_hour));
+    }
+}
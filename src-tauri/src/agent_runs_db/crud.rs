@@ -7,13 +7,32 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::db_utils::{columns, DatabaseOps, QueryBuilder};
+use crate::version_info::{APP_VERSION, GIT_COMMIT};
 
-use super::models::{AgentRun, RunQueryFilters, RunStatus};
+use super::models::{
+    AgentRun, Page, PageCursor, PromptCostRecord, PromptMatch, PromptUsage, RunQueryFilters,
+    RunStatus, StopMode,
+};
+use super::schema::SCHEMA_VERSION;
+
+/// Helper to convert a row of (agent_id, prompt, timestamp, usage, working_dir)
+/// to a PromptCostRecord
+pub fn row_to_prompt_cost(row: &rusqlite::Row) -> SqliteResult<PromptCostRecord> {
+    let usage: Option<String> = row.get(3)?;
+    Ok(PromptCostRecord {
+        agent_id: row.get(0)?,
+        prompt: row.get(1)?,
+        timestamp: row.get(2)?,
+        usage: usage.and_then(|s| serde_json::from_str(&s).ok()),
+        working_dir: row.get(4)?,
+    })
+}
 
 /// Helper to convert a row to AgentRun
 pub fn row_to_run(row: &rusqlite::Row) -> SqliteResult<AgentRun> {
     let status_str: String = row.get(7)?;
     let can_resume_int: i32 = row.get(20)?;
+    let conventions_injected_int: i32 = row.get(22)?;
 
     Ok(AgentRun {
         id: Some(row.get(0)?),
@@ -38,9 +57,45 @@ pub fn row_to_run(row: &rusqlite::Row) -> SqliteResult<AgentRun> {
         model_usage: row.get(19)?,
         can_resume: can_resume_int != 0,
         resume_data: row.get(21)?,
+        conventions_injected: conventions_injected_int != 0,
+        label: row.get(23)?,
+        workspace_id: row.get(24)?,
+        continued_from: row.get(25)?,
+        app_version: row.get(26)?,
+        git_commit: row.get(27)?,
+        schema_version: row.get(28)?,
+        stop_mode: row
+            .get::<_, Option<String>>(29)?
+            .as_deref()
+            .and_then(StopMode::parse),
+        permission_profile: row.get(30)?,
+        model: row.get(31)?,
+        title: row.get(32)?,
+        env_keys: string_list(row, 33)?,
+        allowed_tools: string_list(row, 34)?,
+        disallowed_tools: string_list(row, 35)?,
+        repo_path: row.get(36)?,
+        worktree_path: row.get(37)?,
+        tool_timings: row.get(38)?,
+        cloned_from: row.get(39)?,
     })
 }
 
+/// A column holding a JSON array of strings (NULL for none)
+fn string_list(row: &rusqlite::Row, idx: usize) -> SqliteResult<Vec<String>> {
+    Ok(row
+        .get::<_, Option<String>>(idx)?
+        .and_then(|list| serde_json::from_str(&list).ok())
+        .unwrap_or_default())
+}
+
+/// JSON for a string list column (NULL for an empty list)
+fn string_list_json(list: &[String]) -> Option<String> {
+    (!list.is_empty())
+        .then(|| serde_json::to_string(list).ok())
+        .flatten()
+}
+
 /// CRUD operations for agent runs
 pub struct CrudOperations<'a> {
     db: &'a Arc<Mutex<Connection>>,
@@ -51,8 +106,12 @@ impl<'a> CrudOperations<'a> {
         Self { db }
     }
 
-    /// Create a new run record when an agent starts
+    /// Create a new run record when an agent starts, stamped with the current build
     pub async fn create_run(&self, run: &AgentRun) -> SqliteResult<i64> {
+        let env_keys = string_list_json(&run.env_keys);
+        let allowed_tools = string_list_json(&run.allowed_tools);
+        let disallowed_tools = string_list_json(&run.disallowed_tools);
+
         self.db
             .with_db(|db| {
                 db.execute(
@@ -61,8 +120,10 @@ impl<'a> CrudOperations<'a> {
                         source, status, started_at, ended_at, last_activity,
                         initial_prompt, error_message, pipeline_id, total_prompts, total_tool_calls,
                         total_output_bytes, total_tokens_used, total_cost_usd, model_usage,
-                        can_resume, resume_data
-                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+                        can_resume, resume_data, conventions_injected, label, workspace_id, continued_from,
+                        app_version, git_commit, schema_version, permission_profile, model, title, env_keys,
+                        allowed_tools, disallowed_tools, repo_path, worktree_path
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36)",
                     params![
                         run.agent_id,
                         run.session_id,
@@ -85,6 +146,21 @@ impl<'a> CrudOperations<'a> {
                         run.model_usage,
                         if run.can_resume { 1 } else { 0 },
                         run.resume_data,
+                        if run.conventions_injected { 1 } else { 0 },
+                        run.label,
+                        run.workspace_id,
+                        run.continued_from,
+                        APP_VERSION,
+                        GIT_COMMIT,
+                        SCHEMA_VERSION,
+                        run.permission_profile,
+                        run.model,
+                        run.title,
+                        env_keys,
+                        allowed_tools,
+                        disallowed_tools,
+                        run.repo_path,
+                        run.worktree_path,
                     ],
                 )?;
 
@@ -112,7 +188,9 @@ impl<'a> CrudOperations<'a> {
                         total_cost_usd = ?12,
                         model_usage = ?13,
                         can_resume = ?14,
-                        resume_data = ?15
+                        resume_data = ?15,
+                        conventions_injected = ?16,
+                        tool_timings = ?17
                     WHERE agent_id = ?1",
                     params![
                         run.agent_id,
@@ -130,6 +208,8 @@ impl<'a> CrudOperations<'a> {
                         run.model_usage,
                         if run.can_resume { 1 } else { 0 },
                         run.resume_data,
+                        if run.conventions_injected { 1 } else { 0 },
+                        run.tool_timings,
                     ],
                 )?;
 
@@ -138,6 +218,124 @@ impl<'a> CrudOperations<'a> {
             .await
     }
 
+    /// Replace the stored GitHub context (JSON serialized) for a run
+    pub async fn update_github_context(
+        &self,
+        agent_id: &str,
+        github_context: Option<String>,
+    ) -> SqliteResult<()> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "UPDATE agent_runs SET github_context = ?2 WHERE agent_id = ?1",
+                    params![agent_id, github_context],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Set a run's label. Returns false if no run exists for the agent.
+    pub async fn update_run_label(&self, agent_id: &str, label: &str) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+        let label = label.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_runs SET label = ?2 WHERE agent_id = ?1",
+                    params![agent_id, label],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    /// Set a run's title. Returns false if no run exists for the agent.
+    pub async fn update_run_title(&self, agent_id: &str, title: &str) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+        let title = title.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_runs SET title = ?2 WHERE agent_id = ?1",
+                    params![agent_id, title],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    /// Link a run to the run it continues. Returns false if no run exists
+    /// for the agent.
+    pub async fn set_continued_from(&self, agent_id: &str, previous: &str) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+        let previous = previous.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_runs SET continued_from = ?2 WHERE agent_id = ?1",
+                    params![agent_id, previous],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    /// Link a run to the agent it was cloned from. Returns false if no run
+    /// exists for the agent.
+    pub async fn set_cloned_from(&self, agent_id: &str, original: &str) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+        let original = original.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_runs SET cloned_from = ?2 WHERE agent_id = ?1",
+                    params![agent_id, original],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    /// Record the Claude session a run belongs to, which resume_agent resumes.
+    /// Returns false if no run exists for the agent.
+    pub async fn set_run_session_id(&self, agent_id: &str, session_id: &str) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+        let session_id = session_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_runs SET session_id = ?2 WHERE agent_id = ?1",
+                    params![agent_id, session_id],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    /// Record how stop_agent ended a run's process. Returns false if no run
+    /// exists for the agent.
+    pub async fn set_stop_mode(&self, agent_id: &str, mode: StopMode) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_runs SET stop_mode = ?2 WHERE agent_id = ?1",
+                    params![agent_id, mode.to_str()],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
     /// Get a specific run by agent_id
     pub async fn get_run(&self, agent_id: &str) -> SqliteResult<Option<AgentRun>> {
         let agent_id = agent_id.to_string();
@@ -163,8 +361,10 @@ impl<'a> CrudOperations<'a> {
     pub async fn query_runs(&self, filters: RunQueryFilters) -> SqliteResult<Vec<AgentRun>> {
         self.db
             .with_db(move |db| {
-                let base_query =
-                    format!("SELECT {} FROM agent_runs WHERE 1=1", columns::AGENT_RUNS);
+                let base_query = format!(
+                    "SELECT {} FROM agent_runs WHERE deleted_at IS NULL",
+                    columns::AGENT_RUNS
+                );
                 let mut builder = QueryBuilder::new(&base_query);
 
                 if let Some(status) = filters.status {
@@ -175,10 +375,21 @@ impl<'a> CrudOperations<'a> {
                     builder.add_condition("working_dir = ?", working_dir);
                 }
 
+                if let Some(prefix) = filters.working_dir_prefix {
+                    builder.add_condition_with_params(
+                        "substr(working_dir, 1, length(?)) = ?",
+                        vec![Box::new(prefix.clone()), Box::new(prefix)],
+                    );
+                }
+
                 if let Some(source) = filters.source {
                     builder.add_condition("source = ?", source.as_str().to_string());
                 }
 
+                if let Some(workspace_id) = filters.workspace_id {
+                    builder.add_condition("workspace_id = ?", workspace_id);
+                }
+
                 if let Some(date_from) = filters.date_from {
                     builder.add_condition("started_at >= ?", date_from.timestamp_millis());
                 }
@@ -187,12 +398,25 @@ impl<'a> CrudOperations<'a> {
                     builder.add_condition("started_at <= ?", date_to.timestamp_millis());
                 }
 
-                builder.add_order_by("started_at DESC");
+                if let Some(cursor) = filters.before {
+                    builder.add_condition_with_params(
+                        "(started_at < ? OR (started_at = ? AND id < ?))",
+                        vec![
+                            Box::new(cursor.timestamp),
+                            Box::new(cursor.timestamp),
+                            Box::new(cursor.id),
+                        ],
+                    );
+                }
+
+                // id breaks ties so pages never overlap or skip rows
+                builder.add_order_by("started_at DESC, id DESC");
 
                 if let Some(limit) = filters.limit {
                     builder.add_limit(limit);
                 }
 
+                // Deprecated offset path, kept for existing callers
                 if let Some(offset) = filters.offset {
                     builder.add_offset(offset);
                 }
@@ -208,6 +432,16 @@ impl<'a> CrudOperations<'a> {
             .await
     }
 
+    /// Query one page of runs, returning the cursor for the next page
+    pub async fn query_runs_page(&self, filters: RunQueryFilters) -> SqliteResult<Page<AgentRun>> {
+        let limit = filters.limit;
+        let runs = self.query_runs(filters).await?;
+        Ok(Page::from_items(runs, limit, |run| PageCursor {
+            timestamp: run.started_at,
+            id: run.id.unwrap_or_default(),
+        }))
+    }
+
     /// Get all crashed runs that can be resumed
     pub async fn get_resumable_runs(&self) -> SqliteResult<Vec<AgentRun>> {
         let runs = self
@@ -220,7 +454,7 @@ impl<'a> CrudOperations<'a> {
         Ok(runs.into_iter().filter(|r| r.can_resume).collect())
     }
 
-    /// Record a prompt sent to an agent
+    /// Record a prompt sent to an agent, starting its next turn
     pub async fn record_prompt(
         &self,
         agent_id: &str,
@@ -233,7 +467,8 @@ impl<'a> CrudOperations<'a> {
         self.db
             .with_db(move |db| {
                 db.execute(
-                    "INSERT INTO agent_prompts (agent_id, timestamp, prompt) VALUES (?1, ?2, ?3)",
+                    "INSERT INTO agent_prompts (agent_id, timestamp, prompt, turn_index)
+                     VALUES (?1, ?2, ?3, (SELECT COUNT(*) FROM agent_prompts WHERE agent_id = ?1))",
                     params![agent_id, timestamp, prompt],
                 )?;
                 Ok(())
@@ -241,6 +476,27 @@ impl<'a> CrudOperations<'a> {
             .await
     }
 
+    /// Record when the agent's current turn ended (end_turn / Stop)
+    ///
+    /// A turn that resumes without a new prompt and ends again keeps the later
+    /// end. Returns false when the agent has no recorded prompt.
+    pub async fn mark_turn_ended(&self, agent_id: &str, ended_at: i64) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE agent_prompts SET ended_at = ?2 WHERE id = (
+                         SELECT id FROM agent_prompts WHERE agent_id = ?1
+                         ORDER BY timestamp DESC, id DESC LIMIT 1
+                     )",
+                    params![agent_id, ended_at],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
     /// Get prompt history for an agent
     pub async fn get_prompts(&self, agent_id: &str) -> SqliteResult<Vec<(String, i64)>> {
         let agent_id = agent_id.to_string();
@@ -258,6 +514,205 @@ impl<'a> CrudOperations<'a> {
             .await
     }
 
+    /// Attribute usage to the agent's most recent prompt
+    ///
+    /// Usage already recorded on the prompt is added to rather than replaced,
+    /// so a prompt answered by several result messages is counted in full.
+    /// Returns false when the agent has no recorded prompt.
+    pub async fn record_prompt_usage(
+        &self,
+        agent_id: &str,
+        usage: &PromptUsage,
+    ) -> SqliteResult<bool> {
+        let agent_id = agent_id.to_string();
+        let usage = usage.clone();
+
+        self.db
+            .with_db(move |db| {
+                let latest = {
+                    let mut stmt = db.prepare(
+                        "SELECT id, usage FROM agent_prompts WHERE agent_id = ?1
+                         ORDER BY timestamp DESC, id DESC LIMIT 1",
+                    )?;
+                    let mut rows = stmt.query(params![agent_id])?;
+                    match rows.next()? {
+                        Some(row) => {
+                            Some((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+                        }
+                        None => None,
+                    }
+                };
+                let Some((id, existing)) = latest else {
+                    return Ok(false);
+                };
+
+                let mut total = existing
+                    .and_then(|s| serde_json::from_str::<PromptUsage>(&s).ok())
+                    .unwrap_or_default();
+                total.merge(&usage);
+                let json = serde_json::to_string(&total)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+                db.execute(
+                    "UPDATE agent_prompts SET usage = ?1 WHERE id = ?2",
+                    params![json, id],
+                )?;
+                Ok(true)
+            })
+            .await
+    }
+
+    /// Get an agent's prompts with the tokens and cost of each response
+    pub async fn get_prompt_costs(&self, agent_id: &str) -> SqliteResult<Vec<PromptCostRecord>> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT p.agent_id, p.prompt, p.timestamp, p.usage, r.working_dir
+                     FROM agent_prompts p
+                     LEFT JOIN agent_runs r ON r.agent_id = p.agent_id
+                     WHERE p.agent_id = ?1
+                     ORDER BY p.timestamp ASC, p.id ASC",
+                )?;
+
+                let prompts = stmt.query_map(params![agent_id], row_to_prompt_cost)?;
+                prompts.collect()
+            })
+            .await
+    }
+
+    /// Search prompts sent to agents, newest first
+    ///
+    /// Source, working directory and date filters apply to the run the prompt
+    /// was sent to; the date range is matched against the prompt timestamp.
+    pub async fn search_prompts(
+        &self,
+        keyword: &str,
+        filters: RunQueryFilters,
+    ) -> SqliteResult<Vec<PromptMatch>> {
+        let pattern = format!("%{}%", keyword.to_lowercase());
+
+        self.db
+            .with_db(move |db| {
+                let mut builder = QueryBuilder::new(
+                    "SELECT p.agent_id, p.prompt, p.timestamp, r.working_dir
+                     FROM agent_prompts p JOIN agent_runs r ON r.agent_id = p.agent_id
+                     WHERE r.deleted_at IS NULL",
+                );
+                builder.add_condition("LOWER(p.prompt) LIKE ?", pattern);
+
+                if let Some(source) = filters.source {
+                    builder.add_condition("r.source = ?", source.as_str().to_string());
+                }
+
+                if let Some(workspace_id) = filters.workspace_id {
+                    builder.add_condition("r.workspace_id = ?", workspace_id);
+                }
+
+                if let Some(prefix) = filters.working_dir_prefix {
+                    builder.add_condition_with_params(
+                        "substr(r.working_dir, 1, length(?)) = ?",
+                        vec![Box::new(prefix.clone()), Box::new(prefix)],
+                    );
+                }
+
+                if let Some(date_from) = filters.date_from {
+                    builder.add_condition("p.timestamp >= ?", date_from.timestamp_millis());
+                }
+
+                if let Some(date_to) = filters.date_to {
+                    builder.add_condition("p.timestamp <= ?", date_to.timestamp_millis());
+                }
+
+                builder.add_order_by("p.timestamp DESC, p.id DESC");
+
+                if let Some(limit) = filters.limit {
+                    builder.add_limit(limit);
+                }
+
+                let (query, params) = builder.build();
+                let mut stmt = db.prepare(&query)?;
+                let param_refs = QueryBuilder::params_as_refs(&params);
+
+                let prompts = stmt.query_map(param_refs.as_slice(), |row| {
+                    Ok(PromptMatch {
+                        agent_id: row.get(0)?,
+                        prompt: row.get(1)?,
+                        timestamp: row.get(2)?,
+                        working_dir: row.get(3)?,
+                    })
+                })?;
+
+                prompts.collect()
+            })
+            .await
+    }
+
+    /// Distinct working directories of the most recent runs, newest first
+    pub async fn get_recent_working_dirs(&self, limit: usize) -> SqliteResult<Vec<String>> {
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT working_dir FROM agent_runs
+                     GROUP BY working_dir
+                     ORDER BY MAX(started_at) DESC
+                     LIMIT ?1",
+                )?;
+
+                let dirs = stmt.query_map(params![limit as i64], |row| row.get(0))?;
+
+                dirs.collect()
+            })
+            .await
+    }
+
+    /// Distinct working directories of the most recent runs with when each was
+    /// last used, newest first
+    pub async fn get_recent_working_dir_usage(
+        &self,
+        limit: usize,
+    ) -> SqliteResult<Vec<(String, i64)>> {
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT working_dir, MAX(started_at) AS last_used FROM agent_runs
+                     GROUP BY working_dir
+                     ORDER BY last_used DESC
+                     LIMIT ?1",
+                )?;
+
+                let dirs =
+                    stmt.query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+                dirs.collect()
+            })
+            .await
+    }
+
+    /// When each working directory at or below `parent` was last used
+    pub async fn get_working_dir_usage_under(
+        &self,
+        parent: &str,
+    ) -> SqliteResult<Vec<(String, i64)>> {
+        let parent = parent.trim_end_matches('/').to_string();
+
+        self.db
+            .with_db(move |db| {
+                // substr rather than LIKE so '%' and '_' in paths aren't wildcards
+                let mut stmt = db.prepare(
+                    "SELECT working_dir, MAX(started_at) FROM agent_runs
+                     WHERE working_dir = ?1 OR substr(working_dir, 1, length(?1) + 1) = ?1 || '/'
+                     GROUP BY working_dir",
+                )?;
+
+                let dirs = stmt.query_map(params![parent], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+                dirs.collect()
+            })
+            .await
+    }
+
     /// Clean up old runs (older than days_to_keep)
     pub async fn cleanup_old_runs(&self, days_to_keep: i64) -> SqliteResult<usize> {
         self.db
@@ -272,6 +727,12 @@ impl<'a> CrudOperations<'a> {
                     )",
                     params![cutoff_timestamp],
                 )?;
+                db.execute(
+                    "DELETE FROM agent_notes WHERE agent_id IN (
+                        SELECT agent_id FROM agent_runs WHERE started_at < ?1
+                    )",
+                    params![cutoff_timestamp],
+                )?;
 
                 // Delete old runs
                 db.execute(
@@ -283,12 +744,22 @@ impl<'a> CrudOperations<'a> {
     }
 
     /// Mark all "running" or "waiting_input" agents as "crashed" - called on app startup
-    /// This handles orphaned runs from previous sessions that didn't terminate cleanly
-    pub async fn reconcile_stale_runs(&self) -> SqliteResult<usize> {
+    /// This handles orphaned runs from previous sessions that didn't terminate cleanly.
+    /// Returns the agent ids of the runs marked.
+    pub async fn reconcile_stale_runs(&self) -> SqliteResult<Vec<String>> {
         self.db
             .with_db(|db| {
                 let now = chrono::Utc::now().timestamp_millis();
 
+                let agent_ids = {
+                    let mut stmt = db.prepare(
+                        "SELECT agent_id FROM agent_runs
+                         WHERE status = 'running' OR status = 'waiting_input'",
+                    )?;
+                    let ids = stmt.query_map([], |row| row.get(0))?;
+                    ids.collect::<SqliteResult<Vec<String>>>()?
+                };
+
                 db.execute(
                     "UPDATE agent_runs SET
                         status = 'crashed',
@@ -298,8 +769,458 @@ impl<'a> CrudOperations<'a> {
                         can_resume = 1
                      WHERE status = 'running' OR status = 'waiting_input'",
                     params![now],
-                )
+                )?;
+
+                Ok(agent_ids)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::schema;
+    use crate::types::AgentSource;
+
+    fn run(agent_id: &str, started_at: i64) -> AgentRun {
+        AgentRun {
+            id: None,
+            agent_id: agent_id.to_string(),
+            session_id: None,
+            working_dir: "/work".to_string(),
+            github_url: None,
+            github_context: None,
+            source: "ui".to_string(),
+            status: RunStatus::Completed,
+            started_at,
+            ended_at: None,
+            last_activity: started_at,
+            initial_prompt: None,
+            error_message: None,
+            pipeline_id: None,
+            total_prompts: 0,
+            total_tool_calls: 0,
+            total_output_bytes: 0,
+            total_tokens_used: None,
+            total_cost_usd: None,
+            model_usage: None,
+            can_resume: false,
+            resume_data: None,
+            conventions_injected: false,
+            label: None,
+            workspace_id: crate::workspace::DEFAULT_WORKSPACE_ID.to_string(),
+            continued_from: None,
+            app_version: None,
+            git_commit: None,
+            schema_version: None,
+            stop_mode: None,
+            permission_profile: None,
+            model: None,
+            title: None,
+            env_keys: Vec::new(),
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+            repo_path: None,
+            worktree_path: None,
+            tool_timings: None,
+            cloned_from: None,
+        }
+    }
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_schema(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    async fn page(
+        crud: &CrudOperations<'_>,
+        before: Option<PageCursor>,
+    ) -> (Vec<String>, Option<PageCursor>) {
+        let page = crud
+            .query_runs_page(RunQueryFilters {
+                limit: Some(2),
+                before,
+                ..Default::default()
             })
             .await
+            .unwrap();
+        let ids = page.items.into_iter().map(|r| r.agent_id).collect();
+        (ids, page.next_cursor)
+    }
+
+    #[tokio::test]
+    async fn test_keyset_pages_are_stable_under_inserts() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        // Ties on started_at must still be ordered deterministically
+        for (id, ts) in [("a", 100), ("b", 200), ("c", 200), ("d", 300), ("e", 400)] {
+            crud.create_run(&run(id, ts)).await.unwrap();
+        }
+
+        let (first, cursor) = page(&crud, None).await;
+        assert_eq!(first, vec!["e", "d"]);
+
+        // New runs start while the user is paging
+        crud.create_run(&run("f", 500)).await.unwrap();
+        crud.create_run(&run("g", 200)).await.unwrap();
+
+        let (second, cursor) = page(&crud, cursor).await;
+        assert_eq!(second, vec!["g", "c"]);
+        let (third, cursor) = page(&crud, cursor).await;
+        assert_eq!(third, vec!["b", "a"]);
+        let (last, cursor) = page(&crud, cursor).await;
+        assert!(last.is_empty());
+        assert!(cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stale_runs_keep_their_session_and_are_reported() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        for (id, status) in [
+            ("a", RunStatus::Running),
+            ("b", RunStatus::Completed),
+            ("c", RunStatus::WaitingInput),
+        ] {
+            let mut r = run(id, 100);
+            r.status = status;
+            crud.create_run(&r).await.unwrap();
+        }
+        assert!(crud.set_run_session_id("a", "session-a").await.unwrap());
+        assert!(!crud.set_run_session_id("missing", "x").await.unwrap());
+
+        let mut reconciled = crud.reconcile_stale_runs().await.unwrap();
+        reconciled.sort();
+        assert_eq!(reconciled, vec!["a", "c"]);
+
+        let resumable = crud.get_resumable_runs().await.unwrap();
+        let a = resumable.iter().find(|r| r.agent_id == "a").unwrap();
+        assert_eq!(a.session_id.as_deref(), Some("session-a"));
+        assert_eq!(resumable.len(), 2);
+        assert!(crud.reconcile_stale_runs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stop_mode_is_recorded() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("a", 100)).await.unwrap();
+        assert_eq!(crud.get_run("a").await.unwrap().unwrap().stop_mode, None);
+
+        assert!(crud.set_stop_mode("a", StopMode::Forced).await.unwrap());
+        assert!(!crud
+            .set_stop_mode("missing", StopMode::Graceful)
+            .await
+            .unwrap());
+        assert_eq!(
+            crud.get_run("a").await.unwrap().unwrap().stop_mode,
+            Some(StopMode::Forced)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_prompts_applies_run_filters() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        for (id, dir, source) in [
+            ("a", "/work/app", "ui"),
+            ("b", "/work/lib", "pipeline"),
+            ("c", "/workshop", "ui"),
+        ] {
+            let mut r = run(id, 100);
+            r.working_dir = dir.to_string();
+            r.source = source.to_string();
+            crud.create_run(&r).await.unwrap();
+            crud.record_prompt(id, "Fix the Login bug", 1_000)
+                .await
+                .unwrap();
+        }
+        crud.record_prompt("a", "Fix login again", 5_000)
+            .await
+            .unwrap();
+        crud.record_prompt("a", "Update docs", 6_000).await.unwrap();
+
+        let ids = |matches: Vec<PromptMatch>| -> Vec<(String, i64)> {
+            matches
+                .into_iter()
+                .map(|m| (m.agent_id, m.timestamp))
+                .collect()
+        };
+
+        let all = crud
+            .search_prompts("LOGIN", RunQueryFilters::default())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].prompt, "Fix login again");
+
+        let under_work = crud
+            .search_prompts(
+                "login",
+                RunQueryFilters {
+                    working_dir_prefix: Some("/work/".to_string()),
+                    source: Some(AgentSource::UI),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            ids(under_work),
+            vec![("a".to_string(), 5_000), ("a".to_string(), 1_000)]
+        );
+
+        let recent = crud
+            .search_prompts(
+                "login",
+                RunQueryFilters {
+                    date_from: chrono::DateTime::from_timestamp_millis(2_000),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(recent), vec![("a".to_string(), 5_000)]);
+    }
+
+    #[tokio::test]
+    async fn test_recent_working_dirs_are_distinct_and_newest_first() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        for (id, dir, ts) in [("a", "/old", 100), ("b", "/new", 300), ("c", "/old", 200)] {
+            let mut r = run(id, ts);
+            r.working_dir = dir.to_string();
+            crud.create_run(&r).await.unwrap();
+        }
+
+        let dirs = crud.get_recent_working_dirs(10).await.unwrap();
+        assert_eq!(dirs, vec!["/new", "/old"]);
+        assert_eq!(crud.get_recent_working_dirs(1).await.unwrap(), vec!["/new"]);
+    }
+
+    #[tokio::test]
+    async fn test_working_dir_usage_under_parent() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        for (id, dir, ts) in [
+            ("a", "/work/app", 100),
+            ("b", "/work/app", 300),
+            ("c", "/work/lib", 200),
+            ("d", "/workshop", 400),
+        ] {
+            let mut r = run(id, ts);
+            r.working_dir = dir.to_string();
+            crud.create_run(&r).await.unwrap();
+        }
+
+        let mut usage = crud.get_working_dir_usage_under("/work/").await.unwrap();
+        usage.sort();
+        assert_eq!(
+            usage,
+            vec![
+                ("/work/app".to_string(), 300),
+                ("/work/lib".to_string(), 200)
+            ]
+        );
+
+        let recent = crud.get_recent_working_dir_usage(2).await.unwrap();
+        assert_eq!(
+            recent,
+            vec![
+                ("/workshop".to_string(), 400),
+                ("/work/app".to_string(), 300)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_run_label_survives_run_updates() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("a", 100)).await.unwrap();
+
+        assert!(crud.update_run_label("a", "Fix login flow").await.unwrap());
+        assert!(!crud.update_run_label("missing", "x").await.unwrap());
+
+        // Stats updates carry a stale run and must not clobber the label
+        crud.update_run(&run("a", 100)).await.unwrap();
+        let stored = crud.get_run("a").await.unwrap().unwrap();
+        assert_eq!(stored.label.as_deref(), Some("Fix login flow"));
+    }
+
+    #[tokio::test]
+    async fn test_title_is_stored_and_renamed() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        let mut spawned = run("a", 100);
+        spawned.title = Some("Stage 1: plan".to_string());
+        crud.create_run(&spawned).await.unwrap();
+        assert_eq!(
+            crud.get_run("a").await.unwrap().unwrap().title.as_deref(),
+            Some("Stage 1: plan")
+        );
+
+        assert!(crud.update_run_title("a", "auth-refactor").await.unwrap());
+        assert!(!crud.update_run_title("missing", "x").await.unwrap());
+        let stored = crud.get_run("a").await.unwrap().unwrap();
+        assert_eq!(stored.title.as_deref(), Some("auth-refactor"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_lists_are_stored() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        let mut spawned = run("a", 100);
+        spawned.allowed_tools = vec!["Read".to_string(), "Grep".to_string()];
+        spawned.disallowed_tools = vec!["Edit".to_string()];
+        crud.create_run(&spawned).await.unwrap();
+        crud.create_run(&run("b", 200)).await.unwrap();
+
+        // Updates don't touch the lists
+        crud.update_run(&run("a", 100)).await.unwrap();
+        let stored = crud.get_run("a").await.unwrap().unwrap();
+        assert_eq!(stored.allowed_tools, vec!["Read", "Grep"]);
+        assert_eq!(stored.disallowed_tools, vec!["Edit"]);
+        let unrestricted = crud.get_run("b").await.unwrap().unwrap();
+        assert!(unrestricted.allowed_tools.is_empty());
+        assert!(unrestricted.disallowed_tools.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_continued_from_links_runs() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("old", 100)).await.unwrap();
+        crud.create_run(&run("new", 200)).await.unwrap();
+
+        assert!(crud.set_continued_from("new", "old").await.unwrap());
+        crud.update_run(&run("new", 200)).await.unwrap();
+        let stored = crud.get_run("new").await.unwrap().unwrap();
+        assert_eq!(stored.continued_from.as_deref(), Some("old"));
+        assert_eq!(
+            crud.get_run("old").await.unwrap().unwrap().continued_from,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cloned_from_links_runs() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("original", 100)).await.unwrap();
+        crud.create_run(&run("clone", 200)).await.unwrap();
+
+        assert!(crud.set_cloned_from("clone", "original").await.unwrap());
+        assert!(!crud.set_cloned_from("missing", "original").await.unwrap());
+        crud.update_run(&run("clone", 200)).await.unwrap();
+        let stored = crud.get_run("clone").await.unwrap().unwrap();
+        assert_eq!(stored.cloned_from.as_deref(), Some("original"));
+        assert_eq!(stored.continued_from, None);
+    }
+
+    #[tokio::test]
+    async fn test_create_run_stamps_build() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("a", 100)).await.unwrap();
+        crud.update_run(&run("a", 100)).await.unwrap();
+
+        let stored = crud.get_run("a").await.unwrap().unwrap();
+        assert_eq!(stored.app_version.as_deref(), Some(APP_VERSION));
+        assert_eq!(stored.git_commit.as_deref(), Some(GIT_COMMIT));
+        assert_eq!(stored.schema_version, Some(SCHEMA_VERSION));
+
+        // Opening the database again migrates nothing and keeps the version
+        let conn = db.lock().await;
+        schema::initialize_schema(&conn).unwrap();
+        let user_version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(user_version, SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_usage_attributed_to_latest_prompt() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("a", 100)).await.unwrap();
+        let usage = |cost: f64| PromptUsage {
+            total_tokens: 100,
+            cost_usd: cost,
+            ..Default::default()
+        };
+
+        assert!(!crud.record_prompt_usage("a", &usage(1.0)).await.unwrap());
+
+        crud.record_prompt("a", "cheap", 200).await.unwrap();
+        crud.record_prompt_usage("a", &usage(0.1)).await.unwrap();
+        crud.record_prompt("a", "pricey", 300).await.unwrap();
+        crud.record_prompt_usage("a", &usage(0.5)).await.unwrap();
+        crud.record_prompt_usage("a", &usage(0.25)).await.unwrap();
+        crud.record_prompt("a", "pending", 400).await.unwrap();
+
+        let prompts = crud.get_prompt_costs("a").await.unwrap();
+        let costs: Vec<Option<f64>> = prompts
+            .iter()
+            .map(|p| p.usage.as_ref().map(|u| u.cost_usd))
+            .collect();
+        assert_eq!(costs, vec![Some(0.1), Some(0.75), None]);
+        assert_eq!(prompts[1].usage.as_ref().unwrap().total_tokens, 200);
+        assert_eq!(prompts[0].working_dir.as_deref(), Some("/work"));
+
+        let top = db
+            .with_db(|conn| super::super::cost::query_top_prompts(conn, None, 10))
+            .await
+            .unwrap();
+        let order: Vec<&str> = top.iter().map(|p| p.prompt.as_str()).collect();
+        assert_eq!(order, vec!["pricey", "cheap"]);
+    }
+
+    #[tokio::test]
+    async fn test_trashed_runs_hidden_from_lists() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("kept", 100)).await.unwrap();
+        crud.create_run(&run("trashed", 200)).await.unwrap();
+        crud.record_prompt("kept", "fix the tests", 150)
+            .await
+            .unwrap();
+        crud.record_prompt("trashed", "fix the build", 250)
+            .await
+            .unwrap();
+        db.lock()
+            .await
+            .execute(
+                "UPDATE agent_runs SET deleted_at = 300 WHERE agent_id = 'trashed'",
+                [],
+            )
+            .unwrap();
+
+        let runs = crud.query_runs(RunQueryFilters::default()).await.unwrap();
+        let ids: Vec<&str> = runs.iter().map(|r| r.agent_id.as_str()).collect();
+        assert_eq!(ids, vec!["kept"]);
+
+        let matches = crud
+            .search_prompts("fix", RunQueryFilters::default())
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].agent_id, "kept");
+
+        // Looked up directly so it can still be restored or resumed from the trash
+        assert!(crud.get_run("trashed").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_short_page_has_no_next_cursor() {
+        let db = test_db();
+        let crud = CrudOperations::new(&db);
+        crud.create_run(&run("a", 100)).await.unwrap();
+
+        let (ids, cursor) = page(&crud, None).await;
+        assert_eq!(ids, vec!["a"]);
+        assert!(cursor.is_none());
     }
 }
@@ -0,0 +1,323 @@
+// Dead-letter queue for failed database writes
+//
+// Run/cost/prompt writes that fail (disk full, lock contention, ...) are
+// serialized to an append-only JSONL file next to the database instead of
+// being dropped. A background task replays them with backoff, and
+// `flush_dead_letters` forces a replay on demand. Lines that can no longer be
+// parsed are moved to a separate `.corrupt.jsonl` file so they never block
+// the rest of the queue.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::models::AgentRun;
+
+/// Initial delay between background retry attempts
+pub const DEAD_LETTER_BASE_BACKOFF: Duration = Duration::from_secs(30);
+/// Upper bound for the background retry delay
+pub const DEAD_LETTER_MAX_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+/// A database write that can be replayed later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeadLetterWrite {
+    CreateRun {
+        run: AgentRun,
+    },
+    UpdateRun {
+        run: AgentRun,
+    },
+    RecordPrompt {
+        agent_id: String,
+        prompt: String,
+        timestamp: i64,
+    },
+}
+
+impl DeadLetterWrite {
+    /// Agent the write belongs to
+    pub fn agent_id(&self) -> &str {
+        match self {
+            DeadLetterWrite::CreateRun { run } | DeadLetterWrite::UpdateRun { run } => {
+                &run.agent_id
+            }
+            DeadLetterWrite::RecordPrompt { agent_id, .. } => agent_id,
+        }
+    }
+
+    /// Short name of the write kind for reporting
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeadLetterWrite::CreateRun { .. } => "create_run",
+            DeadLetterWrite::UpdateRun { .. } => "update_run",
+            DeadLetterWrite::RecordPrompt { .. } => "record_prompt",
+        }
+    }
+}
+
+/// A failed write persisted in the dead-letter file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterEntry {
+    pub id: String,
+    pub created_at: i64,
+    pub attempts: u32,
+    pub last_error: String,
+    pub write: DeadLetterWrite,
+}
+
+/// Outcome of replaying a single dead-letter entry
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterStatus {
+    /// Write applied successfully and removed from the queue
+    Written,
+    /// A newer write already landed, so the entry was dropped
+    Superseded,
+    /// Write failed again and stays queued
+    Failed,
+    /// Entry could not be parsed and was moved to the corrupt file
+    Corrupt,
+}
+
+/// Per-entry result returned by a flush
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetterOutcome {
+    pub id: Option<String>,
+    pub kind: Option<String>,
+    pub agent_id: Option<String>,
+    pub status: DeadLetterStatus,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// Append-only JSONL store for failed writes
+pub struct DeadLetterQueue {
+    path: PathBuf,
+    corrupt_path: PathBuf,
+    /// Serializes appends against the read-rewrite done by a flush
+    lock: Mutex<()>,
+}
+
+impl DeadLetterQueue {
+    /// Create a queue stored next to the given database file
+    pub fn for_db(db_path: &Path) -> Self {
+        Self {
+            path: db_path.with_file_name("dead_letters.jsonl"),
+            corrupt_path: db_path.with_file_name("dead_letters.corrupt.jsonl"),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Append a failed write to the queue
+    pub async fn push(&self, write: DeadLetterWrite, error: String) -> Result<(), String> {
+        let entry = DeadLetterEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: crate::utils::time::now_millis(),
+            attempts: 0,
+            last_error: error,
+            write,
+        };
+        let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+
+        let _guard = self.lock.lock().await;
+        append_line(&self.path, &line)
+    }
+
+    /// Number of entries waiting to be replayed
+    pub async fn count(&self) -> usize {
+        let _guard = self.lock.lock().await;
+        read_lines(&self.path).len()
+    }
+
+    /// Replay every queued entry with `apply`, keeping the ones that fail again
+    ///
+    /// `apply` returns `Ok(true)` when the write landed, `Ok(false)` when it was
+    /// superseded by newer data, or an error to keep the entry queued.
+    pub async fn flush<F, Fut>(&self, mut apply: F) -> Vec<DeadLetterOutcome>
+    where
+        F: FnMut(DeadLetterWrite) -> Fut,
+        Fut: std::future::Future<Output = Result<bool, String>>,
+    {
+        let _guard = self.lock.lock().await;
+        let mut outcomes = Vec::new();
+        let mut remaining = Vec::new();
+
+        for line in read_lines(&self.path) {
+            let mut entry: DeadLetterEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // Move aside rather than blocking the queue
+                    if let Err(err) = append_line(&self.corrupt_path, &line) {
+                        eprintln!("[dead_letter] Failed to move corrupt entry: {}", err);
+                        remaining.push(line);
+                    }
+                    outcomes.push(DeadLetterOutcome {
+                        id: None,
+                        kind: None,
+                        agent_id: None,
+                        status: DeadLetterStatus::Corrupt,
+                        attempts: 0,
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            entry.attempts += 1;
+            let (status, error) = match apply(entry.write.clone()).await {
+                Ok(true) => (DeadLetterStatus::Written, None),
+                Ok(false) => (DeadLetterStatus::Superseded, None),
+                Err(e) => (DeadLetterStatus::Failed, Some(e)),
+            };
+
+            outcomes.push(DeadLetterOutcome {
+                id: Some(entry.id.clone()),
+                kind: Some(entry.write.kind().to_string()),
+                agent_id: Some(entry.write.agent_id().to_string()),
+                status: status.clone(),
+                attempts: entry.attempts,
+                error: error.clone(),
+            });
+
+            if let Some(e) = error {
+                entry.last_error = e;
+                match serde_json::to_string(&entry) {
+                    Ok(updated) => remaining.push(updated),
+                    Err(_) => remaining.push(line),
+                }
+            }
+        }
+
+        if let Err(e) = rewrite(&self.path, &remaining) {
+            eprintln!("[dead_letter] Failed to rewrite dead-letter file: {}", e);
+        }
+
+        outcomes
+    }
+}
+
+/// Next background retry delay: reset after progress, otherwise double up to the cap
+pub fn next_backoff(current: Duration, made_progress: bool) -> Duration {
+    if made_progress {
+        DEAD_LETTER_BASE_BACKOFF
+    } else {
+        (current * 2).min(DEAD_LETTER_MAX_BACKOFF)
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Replace the queue file atomically (write temp file, then rename)
+fn rewrite(path: &Path, lines: &[String]) -> Result<(), String> {
+    if lines.is_empty() {
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt_write(agent_id: &str) -> DeadLetterWrite {
+        DeadLetterWrite::RecordPrompt {
+            agent_id: agent_id.to_string(),
+            prompt: "hello".to_string(),
+            timestamp: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flush_keeps_failed_and_drops_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = DeadLetterQueue::for_db(&dir.path().join("runs.db"));
+
+        queue
+            .push(prompt_write("ok"), "disk full".into())
+            .await
+            .unwrap();
+        queue
+            .push(prompt_write("bad"), "locked".into())
+            .await
+            .unwrap();
+        assert_eq!(queue.count().await, 2);
+
+        let outcomes = queue
+            .flush(|w| async move {
+                if w.agent_id() == "ok" {
+                    Ok(true)
+                } else {
+                    Err("still locked".to_string())
+                }
+            })
+            .await;
+
+        assert_eq!(outcomes[0].status, DeadLetterStatus::Written);
+        assert_eq!(outcomes[1].status, DeadLetterStatus::Failed);
+        assert_eq!(outcomes[1].attempts, 1);
+        assert_eq!(queue.count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_entries_are_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("runs.db");
+        let queue = DeadLetterQueue::for_db(&db_path);
+
+        append_line(&db_path.with_file_name("dead_letters.jsonl"), "{not json").unwrap();
+        queue
+            .push(prompt_write("a"), "locked".into())
+            .await
+            .unwrap();
+
+        let outcomes = queue.flush(|_| async { Ok(true) }).await;
+
+        assert_eq!(outcomes[0].status, DeadLetterStatus::Corrupt);
+        assert_eq!(outcomes[1].status, DeadLetterStatus::Written);
+        assert_eq!(queue.count().await, 0);
+        let corrupt = read_lines(&db_path.with_file_name("dead_letters.corrupt.jsonl"));
+        assert_eq!(corrupt, vec!["{not json".to_string()]);
+    }
+
+    #[test]
+    fn test_next_backoff() {
+        let doubled = next_backoff(DEAD_LETTER_BASE_BACKOFF, false);
+        assert_eq!(doubled, DEAD_LETTER_BASE_BACKOFF * 2);
+        assert_eq!(
+            next_backoff(DEAD_LETTER_MAX_BACKOFF, false),
+            DEAD_LETTER_MAX_BACKOFF
+        );
+        assert_eq!(next_backoff(doubled, true), DEAD_LETTER_BASE_BACKOFF);
+    }
+}
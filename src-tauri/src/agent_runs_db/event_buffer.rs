@@ -0,0 +1,206 @@
+// Write buffer for agent outputs and orchestrator events
+//
+// These records arrive one at a time from the stream handlers and the
+// orchestrator. Inserting each one on its own takes the database lock per
+// record, which contends with query paths under heavy pipelines. Records are
+// buffered here instead and written in a single transaction when
+// EVENT_BUFFER_MAX_RECORDS have accumulated, every EVENT_FLUSH_INTERVAL, before
+// any event query and on shutdown.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::models::{
+    AgentOutputRecord, OrchestratorDecisionRecord, OrchestratorStateChangeRecord,
+    OrchestratorToolCallRecord,
+};
+
+/// How often the background task flushes buffered records
+pub const EVENT_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Number of buffered records that triggers an immediate flush
+pub const EVENT_BUFFER_MAX_RECORDS: usize = 50;
+
+/// Records kept for retry while the database keeps failing; older ones are dropped
+const MAX_RETAINED_RECORDS: usize = 10_000;
+
+/// A record waiting to be written
+#[derive(Debug, Clone)]
+pub enum BufferedEvent {
+    ToolCall(OrchestratorToolCallRecord),
+    StateChange(OrchestratorStateChangeRecord),
+    Decision(OrchestratorDecisionRecord),
+    AgentOutput(AgentOutputRecord),
+}
+
+/// Buffered records grouped by table, in arrival order
+#[derive(Debug, Default)]
+pub struct PendingEvents {
+    pub tool_calls: Vec<OrchestratorToolCallRecord>,
+    pub state_changes: Vec<OrchestratorStateChangeRecord>,
+    pub decisions: Vec<OrchestratorDecisionRecord>,
+    pub agent_outputs: Vec<AgentOutputRecord>,
+}
+
+impl PendingEvents {
+    pub fn len(&self) -> usize {
+        self.tool_calls.len()
+            + self.state_changes.len()
+            + self.decisions.len()
+            + self.agent_outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(&mut self, event: BufferedEvent) {
+        match event {
+            BufferedEvent::ToolCall(r) => self.tool_calls.push(r),
+            BufferedEvent::StateChange(r) => self.state_changes.push(r),
+            BufferedEvent::Decision(r) => self.decisions.push(r),
+            BufferedEvent::AgentOutput(r) => self.agent_outputs.push(r),
+        }
+    }
+
+    /// Put `earlier` in front of the records buffered since
+    fn prepend(&mut self, mut earlier: PendingEvents) {
+        earlier.tool_calls.append(&mut self.tool_calls);
+        earlier.state_changes.append(&mut self.state_changes);
+        earlier.decisions.append(&mut self.decisions);
+        earlier.agent_outputs.append(&mut self.agent_outputs);
+        *self = earlier;
+    }
+
+    /// Drop the oldest records beyond `max` in total, trimming the largest
+    /// table first so one noisy agent can't evict everything else
+    fn truncate_oldest(&mut self, max: usize) -> usize {
+        let mut dropped = 0;
+        while self.len() > max {
+            let excess = self.len() - max;
+            let lens = [
+                self.agent_outputs.len(),
+                self.tool_calls.len(),
+                self.state_changes.len(),
+                self.decisions.len(),
+            ];
+            let (largest, len) = lens
+                .into_iter()
+                .enumerate()
+                .max_by_key(|(_, len)| *len)
+                .unwrap_or_default();
+            let n = len.min(excess);
+            match largest {
+                0 => self.agent_outputs = self.agent_outputs.split_off(n),
+                1 => self.tool_calls = self.tool_calls.split_off(n),
+                2 => self.state_changes = self.state_changes.split_off(n),
+                _ => self.decisions = self.decisions.split_off(n),
+            }
+            dropped += n;
+        }
+        dropped
+    }
+}
+
+/// In-memory buffer shared by every writer of event records
+#[derive(Default)]
+pub struct EventBuffer {
+    pending: Mutex<PendingEvents>,
+    /// Serializes flushes so batches are written in arrival order
+    pub(super) flush_lock: tokio::sync::Mutex<()>,
+}
+
+impl EventBuffer {
+    /// Buffer a record. Returns true once the buffer should be flushed.
+    pub fn push(&self, event: BufferedEvent) -> bool {
+        let mut pending = self.lock();
+        pending.push(event);
+        pending.len() >= EVENT_BUFFER_MAX_RECORDS
+    }
+
+    /// Take everything buffered so far
+    pub fn take(&self) -> PendingEvents {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Return records that failed to write, ahead of anything buffered since
+    pub fn restore(&self, failed: PendingEvents) {
+        let mut pending = self.lock();
+        pending.prepend(failed);
+        let dropped = pending.truncate_oldest(MAX_RETAINED_RECORDS);
+        if dropped > 0 {
+            eprintln!(
+                "[EventBuffer] Dropped {} buffered records after repeated write failures",
+                dropped
+            );
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PendingEvents> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(content: &str) -> BufferedEvent {
+        BufferedEvent::AgentOutput(AgentOutputRecord {
+            id: None,
+            agent_id: "a1".to_string(),
+            pipeline_id: None,
+            output_type: "text".to_string(),
+            content: content.to_string(),
+            metadata: None,
+            timestamp: 0,
+            turn_index: None,
+        })
+    }
+
+    #[test]
+    fn test_push_reports_full_buffer() {
+        let buffer = EventBuffer::default();
+        for i in 1..EVENT_BUFFER_MAX_RECORDS {
+            assert!(!buffer.push(output(&i.to_string())));
+        }
+        assert!(buffer.push(output("last")));
+
+        assert_eq!(buffer.take().agent_outputs.len(), EVENT_BUFFER_MAX_RECORDS);
+        assert!(buffer.take().is_empty());
+    }
+
+    #[test]
+    fn test_restore_keeps_failed_records_first() {
+        let buffer = EventBuffer::default();
+        buffer.push(output("old"));
+        let failed = buffer.take();
+        buffer.push(output("new"));
+
+        buffer.restore(failed);
+
+        let contents: Vec<_> = buffer
+            .take()
+            .agent_outputs
+            .into_iter()
+            .map(|r| r.content)
+            .collect();
+        assert_eq!(contents, vec!["old", "new"]);
+    }
+
+    #[test]
+    fn test_truncate_oldest_drops_from_largest_table() {
+        let mut pending = PendingEvents::default();
+        for i in 0..5 {
+            pending.push(output(&i.to_string()));
+        }
+
+        assert_eq!(pending.truncate_oldest(3), 2);
+        let contents: Vec<_> = pending
+            .agent_outputs
+            .iter()
+            .map(|r| r.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["2", "3", "4"]);
+    }
+}
@@ -0,0 +1,146 @@
+// Agent group persistence
+//
+// Groups are sets of agents operated on together. Each pipeline gets one for
+// its agents; others are named by the user. A group is created the first time
+// it is saved, and saving it again only adds members.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+use crate::types::AgentGroup;
+use crate::version_info::{APP_VERSION, GIT_COMMIT};
+
+use super::schema::SCHEMA_VERSION;
+
+/// Load groups with their members (one group when `group_id` is set), oldest first
+fn query_groups(db: &Connection, group_id: Option<&str>) -> SqliteResult<Vec<AgentGroup>> {
+    let mut stmt = db.prepare(
+        "SELECT g.id, g.name, g.pipeline_id, g.created_at, m.agent_id
+         FROM agent_groups g
+         LEFT JOIN agent_group_members m ON m.group_id = g.id
+         WHERE ?1 IS NULL OR g.id = ?1
+         ORDER BY g.created_at ASC, g.id ASC, m.added_at ASC, m.agent_id ASC",
+    )?;
+    let mut rows = stmt.query(params![group_id])?;
+
+    let mut groups: Vec<AgentGroup> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let member: Option<String> = row.get(4)?;
+        if groups.last().map(|g| g.id.as_str()) != Some(id.as_str()) {
+            groups.push(AgentGroup {
+                id,
+                name: row.get(1)?,
+                pipeline_id: row.get(2)?,
+                agent_ids: Vec::new(),
+                created_at: row.get(3)?,
+            });
+        }
+        if let (Some(group), Some(agent_id)) = (groups.last_mut(), member) {
+            group.agent_ids.push(agent_id);
+        }
+    }
+    Ok(groups)
+}
+
+/// Operations for agent groups
+pub struct AgentGroupOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> AgentGroupOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Create the group if it doesn't exist, stamped with the current build,
+    /// and add its agents as members
+    pub async fn save_group(&self, group: &AgentGroup) -> SqliteResult<()> {
+        let group = group.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT OR IGNORE INTO agent_groups (
+                        id, name, pipeline_id, created_at, app_version, git_commit, schema_version
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        group.id,
+                        group.name,
+                        group.pipeline_id,
+                        group.created_at,
+                        APP_VERSION,
+                        GIT_COMMIT,
+                        SCHEMA_VERSION,
+                    ],
+                )?;
+                for agent_id in &group.agent_ids {
+                    db.execute(
+                        "INSERT OR IGNORE INTO agent_group_members (group_id, agent_id, added_at)
+                         VALUES (?1, ?2, ?3)",
+                        params![group.id, agent_id, group.created_at],
+                    )?;
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    /// Get a group with its members
+    pub async fn get_group(&self, group_id: &str) -> SqliteResult<Option<AgentGroup>> {
+        let group_id = group_id.to_string();
+
+        self.db
+            .with_db(move |db| Ok(query_groups(db, Some(&group_id))?.into_iter().next()))
+            .await
+    }
+
+    /// Get all groups with their members, oldest first
+    pub async fn list_groups(&self) -> SqliteResult<Vec<AgentGroup>> {
+        self.db.with_db(|db| query_groups(db, None)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(id: &str, agent_ids: &[&str], created_at: i64) -> AgentGroup {
+        AgentGroup {
+            id: id.to_string(),
+            name: format!("Group {}", id),
+            pipeline_id: None,
+            agent_ids: agent_ids.iter().map(|a| a.to_string()).collect(),
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_groups_round_trip_and_only_gain_members() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_agent_group_tables(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = AgentGroupOps::new(&db);
+
+        ops.save_group(&group("g2", &["a3"], 20)).await.unwrap();
+        ops.save_group(&group("g1", &["a1"], 10)).await.unwrap();
+        // Saving again adds members and keeps the original name
+        let mut again = group("g1", &["a1", "a2"], 30);
+        again.name = "Renamed".to_string();
+        ops.save_group(&again).await.unwrap();
+        ops.save_group(&group("empty", &[], 40)).await.unwrap();
+
+        let groups = ops.list_groups().await.unwrap();
+        let ids: Vec<_> = groups.iter().map(|g| g.id.as_str()).collect();
+        assert_eq!(ids, vec!["g1", "g2", "empty"]);
+        assert_eq!(groups[0].name, "Group g1");
+        assert_eq!(groups[0].agent_ids, vec!["a1", "a2"]);
+        assert!(groups[2].agent_ids.is_empty());
+
+        let g2 = ops.get_group("g2").await.unwrap().unwrap();
+        assert_eq!(g2.agent_ids, vec!["a3"]);
+        assert!(ops.get_group("missing").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,197 @@
+// Hook event log persistence
+//
+// One compact row per hook event an agent's Claude CLI posts to the hook
+// server, matched or not, so "did hooks fire for this agent" can be answered
+// after the fact. The log keeps the newest MAX_HOOK_EVENTS rows.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::{HookEventRecord, HookEventTotals};
+
+/// Rows kept in the hook event log
+pub const MAX_HOOK_EVENTS: i64 = 20_000;
+
+/// Operations for the hook event log
+pub struct HookEventOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> HookEventOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert an event, trim the log to MAX_HOOK_EVENTS and return the row ID
+    pub async fn insert_hook_event(&self, event: &HookEventRecord) -> SqliteResult<i64> {
+        let event = event.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO hook_events
+                        (agent_id, session_id, event_name, tool_name, matched_by, latency_ms, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        event.agent_id,
+                        event.session_id,
+                        event.event_name,
+                        event.tool_name,
+                        event.matched_by,
+                        event.latency_ms,
+                        event.created_at
+                    ],
+                )?;
+                let id = db.last_insert_rowid();
+                db.execute(
+                    "DELETE FROM hook_events WHERE id <= ?1",
+                    params![id - MAX_HOOK_EVENTS],
+                )?;
+                Ok(id)
+            })
+            .await
+    }
+
+    /// An agent's most recent events, newest first
+    pub async fn get_agent_hook_events(
+        &self,
+        agent_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<HookEventRecord>> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT id, agent_id, session_id, event_name, tool_name, matched_by,
+                            latency_ms, created_at
+                     FROM hook_events
+                     WHERE agent_id = ?1
+                     ORDER BY id DESC
+                     LIMIT ?2",
+                )?;
+                let rows = stmt.query_map(params![agent_id, limit as i64], |row| {
+                    Ok(HookEventRecord {
+                        id: Some(row.get(0)?),
+                        agent_id: row.get(1)?,
+                        session_id: row.get(2)?,
+                        event_name: row.get(3)?,
+                        tool_name: row.get(4)?,
+                        matched_by: row.get(5)?,
+                        latency_ms: row.get(6)?,
+                        created_at: row.get(7)?,
+                    })
+                })?;
+                rows.collect()
+            })
+            .await
+    }
+
+    /// Event counts over the retained log
+    pub async fn get_totals(&self) -> SqliteResult<HookEventTotals> {
+        self.db
+            .with_db(|db| {
+                db.query_row(
+                    "SELECT COUNT(*), COALESCE(SUM(agent_id IS NULL), 0), MAX(created_at)
+                     FROM hook_events",
+                    [],
+                    |row| {
+                        Ok(HookEventTotals {
+                            events_received: row.get(0)?,
+                            unmatched_session_events: row.get(1)?,
+                            last_event_at: row.get(2)?,
+                        })
+                    },
+                )
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(agent_id: Option<&str>, event_name: &str, created_at: i64) -> HookEventRecord {
+        HookEventRecord {
+            id: None,
+            agent_id: agent_id.map(str::to_string),
+            session_id: "session-1".to_string(),
+            event_name: event_name.to_string(),
+            tool_name: Some("Bash".to_string()),
+            matched_by: if agent_id.is_some() {
+                "session"
+            } else {
+                "unmatched"
+            }
+            .to_string(),
+            latency_ms: 2,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_events_newest_first_and_totals() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_hook_events_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = HookEventOps::new(&db);
+
+        ops.insert_hook_event(&event(Some("agent-1"), "PreToolUse", 1_000))
+            .await
+            .unwrap();
+        ops.insert_hook_event(&event(Some("agent-1"), "PostToolUse", 2_000))
+            .await
+            .unwrap();
+        ops.insert_hook_event(&event(Some("agent-2"), "Stop", 3_000))
+            .await
+            .unwrap();
+        ops.insert_hook_event(&event(None, "PreToolUse", 4_000))
+            .await
+            .unwrap();
+
+        let events = ops.get_agent_hook_events("agent-1", 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_name, "PostToolUse");
+        assert_eq!(
+            ops.get_agent_hook_events("agent-1", 1).await.unwrap().len(),
+            1
+        );
+
+        let totals = ops.get_totals().await.unwrap();
+        assert_eq!(totals.events_received, 4);
+        assert_eq!(totals.unmatched_session_events, 1);
+        assert_eq!(totals.last_event_at, Some(4_000));
+    }
+
+    #[tokio::test]
+    async fn test_log_is_capped() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_hook_events_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = HookEventOps::new(&db);
+
+        let first = ops
+            .insert_hook_event(&event(Some("agent-1"), "PreToolUse", 1_000))
+            .await
+            .unwrap();
+        // Jump the row IDs ahead instead of inserting MAX_HOOK_EVENTS rows
+        db.lock()
+            .await
+            .execute(
+                "UPDATE sqlite_sequence SET seq = ?1 WHERE name = 'hook_events'",
+                params![first + MAX_HOOK_EVENTS - 1],
+            )
+            .unwrap();
+        ops.insert_hook_event(&event(Some("agent-1"), "PostToolUse", 2_000))
+            .await
+            .unwrap();
+
+        let events = ops.get_agent_hook_events("agent-1", 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_name, "PostToolUse");
+    }
+}
@@ -0,0 +1,125 @@
+// Pipelines waiting on the user
+//
+// When the orchestrator asks the user a question its pipeline pauses. The
+// pipeline and the orchestrator's state are saved here until the answer
+// arrives, so a pipeline that is waiting can be resumed after a restart.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::PipelineHumanRequestRecord;
+
+/// Operations for paused pipelines
+pub struct HumanRequestOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> HumanRequestOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Save a paused pipeline, replacing an earlier request for it
+    pub async fn save_request(&self, record: &PipelineHumanRequestRecord) -> SqliteResult<()> {
+        let record = record.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT OR REPLACE INTO pipeline_human_requests
+                     (pipeline_id, question, pipeline, orchestrator, requested_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        record.pipeline_id,
+                        record.question,
+                        record.pipeline,
+                        record.orchestrator,
+                        record.requested_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// All paused pipelines, oldest request first
+    pub async fn list_requests(&self) -> SqliteResult<Vec<PipelineHumanRequestRecord>> {
+        self.db
+            .with_db(|db| {
+                let mut stmt = db.prepare(
+                    "SELECT pipeline_id, question, pipeline, orchestrator, requested_at
+                     FROM pipeline_human_requests ORDER BY requested_at ASC",
+                )?;
+                let requests = stmt.query_map([], |row| {
+                    Ok(PipelineHumanRequestRecord {
+                        pipeline_id: row.get(0)?,
+                        question: row.get(1)?,
+                        pipeline: row.get(2)?,
+                        orchestrator: row.get(3)?,
+                        requested_at: row.get(4)?,
+                    })
+                })?;
+                requests.collect()
+            })
+            .await
+    }
+
+    /// Forget a pipeline's request once answered or cancelled. Returns false if there was none.
+    pub async fn delete_request(&self, pipeline_id: &str) -> SqliteResult<bool> {
+        let pipeline_id = pipeline_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let deleted = db.execute(
+                    "DELETE FROM pipeline_human_requests WHERE pipeline_id = ?1",
+                    params![pipeline_id],
+                )?;
+                Ok(deleted > 0)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(pipeline_id: &str, question: &str, requested_at: i64) -> PipelineHumanRequestRecord {
+        PipelineHumanRequestRecord {
+            pipeline_id: pipeline_id.to_string(),
+            question: question.to_string(),
+            pipeline: "{}".to_string(),
+            orchestrator: "{}".to_string(),
+            requested_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_replaced_listed_and_deleted() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_pipeline_human_requests_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = HumanRequestOps::new(&db);
+
+        ops.save_request(&request("p2", "Which database?", 20))
+            .await
+            .unwrap();
+        ops.save_request(&request("p1", "Keep the old API?", 10))
+            .await
+            .unwrap();
+        ops.save_request(&request("p2", "Postgres or SQLite?", 30))
+            .await
+            .unwrap();
+
+        let requests = ops.list_requests().await.unwrap();
+        let questions: Vec<_> = requests.iter().map(|r| r.question.as_str()).collect();
+        assert_eq!(questions, vec!["Keep the old API?", "Postgres or SQLite?"]);
+
+        assert!(ops.delete_request("p1").await.unwrap());
+        assert!(!ops.delete_request("p1").await.unwrap());
+        assert_eq!(ops.list_requests().await.unwrap().len(), 1);
+    }
+}
@@ -5,12 +5,79 @@
 // - Inserting and retrieving messages
 // - Listing conversations with filters
 // - Deleting conversations
+// - Generated titles and summaries
+// - Forking a conversation at a message
+// - Ratings on commander responses and feedback summaries
+// - Message text search and the conversations the memory worker scans
 
 use rusqlite::{params, Connection, Result as SqliteResult};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::models::{ConversationQueryFilters, MetaConversationRecord, MetaMessageRecord};
+use crate::db_utils::run_blocking;
+
+use super::models::{
+    ConversationQueryFilters, FeedbackGroup, FeedbackSummary, MessageRating,
+    MetaConversationRecord, MetaMessageMatch, MetaMessageRecord, RatedMessageContext,
+};
+
+const CONVERSATION_COLUMNS: &str = "id, conversation_id, title, created_at, updated_at, message_count, is_archived, preview_text, title_locked, summary, forked_from, forked_at_message, workspace_id";
+
+const MESSAGE_COLUMNS: &str = "id, conversation_id, message_index, role, content, image_data, tool_calls, timestamp, model, personality_hash, rating, rating_note, rated_at";
+
+/// Helper to convert a row selected with MESSAGE_COLUMNS to MetaMessageRecord
+fn row_to_message(row: &rusqlite::Row) -> SqliteResult<MetaMessageRecord> {
+    let rating: Option<String> = row.get(10)?;
+    Ok(MetaMessageRecord {
+        id: Some(row.get(0)?),
+        conversation_id: row.get(1)?,
+        message_index: row.get(2)?,
+        role: row.get(3)?,
+        content: row.get(4)?,
+        image_data: row.get(5)?,
+        tool_calls: row.get(6)?,
+        timestamp: row.get(7)?,
+        model: row.get(8)?,
+        personality_hash: row.get(9)?,
+        rating: rating.as_deref().and_then(MessageRating::parse),
+        rating_note: row.get(11)?,
+        rated_at: row.get(12)?,
+    })
+}
+
+/// Helper to convert a row selected with CONVERSATION_COLUMNS to MetaConversationRecord
+fn row_to_conversation(row: &rusqlite::Row) -> SqliteResult<MetaConversationRecord> {
+    let is_archived_int: i32 = row.get(6)?;
+    Ok(MetaConversationRecord {
+        id: Some(row.get(0)?),
+        conversation_id: row.get(1)?,
+        title: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        message_count: row.get(5)?,
+        is_archived: is_archived_int != 0,
+        preview_text: row.get(7)?,
+        title_locked: row.get::<_, Option<i32>>(8)?.unwrap_or(0) != 0,
+        summary: row.get(9)?,
+        forked_from: row.get(10)?,
+        forked_at_message: row.get(11)?,
+        workspace_id: row.get(12)?,
+    })
+}
+
+/// Names of the tools called in a message's persisted tool blocks
+fn tool_names(tool_calls: Option<&str>) -> Vec<String> {
+    let Some(blocks) =
+        tool_calls.and_then(|json| serde_json::from_str::<Vec<serde_json::Value>>(json).ok())
+    else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter(|block| block["type"] == "tool_use")
+        .filter_map(|block| block["name"].as_str().map(|name| name.to_string()))
+        .collect()
+}
 
 /// Operations for meta agent conversation persistence
 pub struct MetaConversationOps<'a> {
@@ -25,48 +92,59 @@ impl<'a> MetaConversationOps<'a> {
     /// Create a new conversation record
     pub async fn create_conversation(&self, record: &MetaConversationRecord) -> SqliteResult<i64> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            db.execute(
+                "INSERT INTO meta_conversations
+                 (conversation_id, title, created_at, updated_at, message_count, is_archived, preview_text, title_locked, summary, forked_from, forked_at_message, workspace_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    record.conversation_id,
+                    record.title,
+                    record.created_at,
+                    record.updated_at,
+                    record.message_count,
+                    if record.is_archived { 1 } else { 0 },
+                    record.preview_text,
+                    if record.title_locked { 1 } else { 0 },
+                    record.summary,
+                    record.forked_from,
+                    record.forked_at_message,
+                    record.workspace_id
+                ],
+            )?;
 
-        db.execute(
-            "INSERT INTO meta_conversations
-             (conversation_id, title, created_at, updated_at, message_count, is_archived, preview_text)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                record.conversation_id,
-                record.title,
-                record.created_at,
-                record.updated_at,
-                record.message_count,
-                if record.is_archived { 1 } else { 0 },
-                record.preview_text
-            ],
-        )?;
-
-        Ok(db.last_insert_rowid())
+            Ok(db.last_insert_rowid())
+        })
     }
 
     /// Update an existing conversation's metadata
     pub async fn update_conversation(&self, record: &MetaConversationRecord) -> SqliteResult<()> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            db.execute(
+                "UPDATE meta_conversations SET
+                 title = ?1,
+                 updated_at = ?2,
+                 message_count = ?3,
+                 is_archived = ?4,
+                 preview_text = ?5,
+                 title_locked = ?6,
+                 summary = ?7
+                 WHERE conversation_id = ?8",
+                params![
+                    record.title,
+                    record.updated_at,
+                    record.message_count,
+                    if record.is_archived { 1 } else { 0 },
+                    record.preview_text,
+                    if record.title_locked { 1 } else { 0 },
+                    record.summary,
+                    record.conversation_id
+                ],
+            )?;
 
-        db.execute(
-            "UPDATE meta_conversations SET
-             title = ?1,
-             updated_at = ?2,
-             message_count = ?3,
-             is_archived = ?4,
-             preview_text = ?5
-             WHERE conversation_id = ?6",
-            params![
-                record.title,
-                record.updated_at,
-                record.message_count,
-                if record.is_archived { 1 } else { 0 },
-                record.preview_text,
-                record.conversation_id
-            ],
-        )?;
-
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Get a conversation by ID
@@ -75,30 +153,19 @@ impl<'a> MetaConversationOps<'a> {
         conversation_id: &str,
     ) -> SqliteResult<Option<MetaConversationRecord>> {
         let db = self.db.lock().await;
-
-        let mut stmt = db.prepare(
-            "SELECT id, conversation_id, title, created_at, updated_at, message_count, is_archived, preview_text
-             FROM meta_conversations
-             WHERE conversation_id = ?1",
-        )?;
-
-        let mut rows = stmt.query(params![conversation_id])?;
-
-        if let Some(row) = rows.next()? {
-            let is_archived_int: i32 = row.get(6)?;
-            Ok(Some(MetaConversationRecord {
-                id: Some(row.get(0)?),
-                conversation_id: row.get(1)?,
-                title: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-                message_count: row.get(5)?,
-                is_archived: is_archived_int != 0,
-                preview_text: row.get(7)?,
-            }))
-        } else {
-            Ok(None)
-        }
+        run_blocking(|| {
+            let mut stmt = db.prepare(&format!(
+                "SELECT {} FROM meta_conversations WHERE conversation_id = ?1",
+                CONVERSATION_COLUMNS
+            ))?;
+
+            let mut rows = stmt.query(params![conversation_id])?;
+
+            match rows.next()? {
+                Some(row) => Ok(Some(row_to_conversation(row)?)),
+                None => Ok(None),
+            }
+        })
     }
 
     /// List conversations with optional filters
@@ -107,99 +174,223 @@ impl<'a> MetaConversationOps<'a> {
         filters: ConversationQueryFilters,
     ) -> SqliteResult<Vec<MetaConversationRecord>> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut query = format!(
+                "SELECT {} FROM meta_conversations WHERE deleted_at IS NULL",
+                CONVERSATION_COLUMNS
+            );
+            let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if !filters.include_archived {
+                query.push_str(" AND is_archived = 0");
+            }
+
+            if let Some(ref workspace_id) = filters.workspace_id {
+                query.push_str(" AND workspace_id = ?");
+                params.push(Box::new(workspace_id.clone()));
+            }
+
+            if let Some(ref search) = filters.search_text {
+                query.push_str(" AND (title LIKE ? OR preview_text LIKE ?)");
+                let search_pattern = format!("%{}%", search);
+                params.push(Box::new(search_pattern.clone()));
+                params.push(Box::new(search_pattern));
+            }
+
+            query.push_str(" ORDER BY updated_at DESC");
+
+            if let Some(limit) = filters.limit {
+                query.push_str(&format!(" LIMIT {}", limit));
+            }
+            if let Some(offset) = filters.offset {
+                query.push_str(&format!(" OFFSET {}", offset));
+            }
+
+            let mut stmt = db.prepare(&query)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+            let rows = stmt.query_map(param_refs.as_slice(), row_to_conversation)?;
+
+            rows.collect()
+        })
+    }
 
-        let mut query = "SELECT id, conversation_id, title, created_at, updated_at, message_count, is_archived, preview_text
-                         FROM meta_conversations WHERE 1=1"
-            .to_string();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if !filters.include_archived {
-            query.push_str(" AND is_archived = 0");
-        }
+    /// Conversations in a workspace last updated after `after` and no later
+    /// than `until`, oldest first. Trashed conversations are left out.
+    pub async fn list_conversations_updated_between(
+        &self,
+        workspace_id: &str,
+        after: i64,
+        until: i64,
+    ) -> SqliteResult<Vec<MetaConversationRecord>> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut stmt = db.prepare(&format!(
+                "SELECT {} FROM meta_conversations
+                 WHERE deleted_at IS NULL AND workspace_id = ?1
+                   AND updated_at > ?2 AND updated_at <= ?3
+                 ORDER BY updated_at ASC",
+                CONVERSATION_COLUMNS
+            ))?;
+
+            let rows = stmt.query_map(params![workspace_id, after, until], row_to_conversation)?;
+
+            rows.collect()
+        })
+    }
 
-        if let Some(ref search) = filters.search_text {
-            query.push_str(" AND (title LIKE ? OR preview_text LIKE ?)");
-            let search_pattern = format!("%{}%", search);
-            params.push(Box::new(search_pattern.clone()));
-            params.push(Box::new(search_pattern));
-        }
+    /// User and assistant messages in a workspace's conversations whose text
+    /// contains `keyword` (case-insensitive), newest first
+    pub async fn search_messages(
+        &self,
+        keyword: &str,
+        workspace_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<MetaMessageMatch>> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut stmt = db.prepare(
+                "SELECT m.conversation_id, c.title, m.message_index, m.role, m.content, m.timestamp
+                 FROM meta_messages m
+                 JOIN meta_conversations c ON c.conversation_id = m.conversation_id
+                 WHERE c.deleted_at IS NULL AND c.workspace_id = ?1
+                   AND m.role IN ('user', 'assistant') AND m.content LIKE ?2
+                 ORDER BY m.timestamp DESC
+                 LIMIT ?3",
+            )?;
 
-        query.push_str(" ORDER BY updated_at DESC");
+            let pattern = format!("%{}%", keyword);
+            let rows = stmt.query_map(params![workspace_id, pattern, limit as i64], |row| {
+                Ok(MetaMessageMatch {
+                    conversation_id: row.get(0)?,
+                    conversation_title: row.get(1)?,
+                    message_index: row.get(2)?,
+                    role: row.get(3)?,
+                    content: row.get(4)?,
+                    timestamp: row.get(5)?,
+                })
+            })?;
+
+            rows.collect()
+        })
+    }
 
-        if let Some(limit) = filters.limit {
-            query.push_str(&format!(" LIMIT {}", limit));
-        }
-        if let Some(offset) = filters.offset {
-            query.push_str(&format!(" OFFSET {}", offset));
-        }
+    /// Rename a conversation (a manual rename locks the title)
+    pub async fn rename_conversation(
+        &self,
+        conversation_id: &str,
+        new_title: &str,
+    ) -> SqliteResult<()> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let now = chrono::Utc::now().timestamp_millis();
 
-        let mut stmt = db.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            let is_archived_int: i32 = row.get(6)?;
-            Ok(MetaConversationRecord {
-                id: Some(row.get(0)?),
-                conversation_id: row.get(1)?,
-                title: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-                message_count: row.get(5)?,
-                is_archived: is_archived_int != 0,
-                preview_text: row.get(7)?,
-            })
-        })?;
+            db.execute(
+                "UPDATE meta_conversations SET title = ?1, updated_at = ?2, title_locked = 1 WHERE conversation_id = ?3",
+                params![new_title, now, conversation_id],
+            )?;
 
-        rows.collect()
+            Ok(())
+        })
     }
 
-    /// Delete a conversation and all its messages
-    pub async fn delete_conversation(&self, conversation_id: &str) -> SqliteResult<()> {
+    /// Replace the title with a generated one unless the user has locked it.
+    ///
+    /// Returns true if the title was updated.
+    pub async fn set_generated_title(
+        &self,
+        conversation_id: &str,
+        title: &str,
+    ) -> SqliteResult<bool> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            let updated = db.execute(
+                "UPDATE meta_conversations SET title = ?1
+                 WHERE conversation_id = ?2 AND COALESCE(title_locked, 0) = 0",
+                params![title, conversation_id],
+            )?;
 
-        // Delete messages first (foreign key)
-        db.execute(
-            "DELETE FROM meta_messages WHERE conversation_id = ?1",
-            params![conversation_id],
-        )?;
+            Ok(updated > 0)
+        })
+    }
 
-        // Delete conversation
-        db.execute(
-            "DELETE FROM meta_conversations WHERE conversation_id = ?1",
-            params![conversation_id],
-        )?;
+    /// Store a generated summary on the conversation
+    pub async fn set_summary(&self, conversation_id: &str, summary: &str) -> SqliteResult<()> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            db.execute(
+                "UPDATE meta_conversations SET summary = ?1 WHERE conversation_id = ?2",
+                params![summary, conversation_id],
+            )?;
 
-        Ok(())
+            Ok(())
+        })
     }
 
-    /// Rename a conversation
-    pub async fn rename_conversation(
+    /// Create `fork` with a copy of `messages` in one transaction.
+    ///
+    /// The copies are re-indexed from 1 so new messages continue the sequence.
+    pub async fn fork_conversation(
         &self,
-        conversation_id: &str,
-        new_title: &str,
+        fork: &MetaConversationRecord,
+        messages: &[MetaMessageRecord],
     ) -> SqliteResult<()> {
-        let db = self.db.lock().await;
-
-        let now = chrono::Utc::now().timestamp_millis();
-
-        db.execute(
-            "UPDATE meta_conversations SET title = ?1, updated_at = ?2 WHERE conversation_id = ?3",
-            params![new_title, now, conversation_id],
-        )?;
+        let mut db = self.db.lock().await;
+        run_blocking(|| {
+            let tx = db.transaction()?;
+
+            tx.execute(
+                "INSERT INTO meta_conversations
+                 (conversation_id, title, created_at, updated_at, message_count, is_archived, preview_text, title_locked, summary, forked_from, forked_at_message, workspace_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, NULL, ?8, ?9, ?10)",
+                params![
+                    fork.conversation_id,
+                    fork.title,
+                    fork.created_at,
+                    fork.updated_at,
+                    fork.message_count,
+                    fork.preview_text,
+                    if fork.title_locked { 1 } else { 0 },
+                    fork.forked_from,
+                    fork.forked_at_message,
+                    fork.workspace_id
+                ],
+            )?;
 
-        Ok(())
+            for (position, message) in messages.iter().enumerate() {
+                tx.execute(
+                    "INSERT INTO meta_messages
+                     (conversation_id, message_index, role, content, image_data, tool_calls, timestamp, model, personality_hash)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        fork.conversation_id,
+                        position as u32 + 1,
+                        message.role,
+                        message.content,
+                        message.image_data,
+                        message.tool_calls,
+                        message.timestamp,
+                        message.model,
+                        message.personality_hash
+                    ],
+                )?;
+            }
+
+            tx.commit()
+        })
     }
 
     /// Archive/unarchive a conversation
     pub async fn set_archived(&self, conversation_id: &str, archived: bool) -> SqliteResult<()> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            db.execute(
+                "UPDATE meta_conversations SET is_archived = ?1 WHERE conversation_id = ?2",
+                params![if archived { 1 } else { 0 }, conversation_id],
+            )?;
 
-        db.execute(
-            "UPDATE meta_conversations SET is_archived = ?1 WHERE conversation_id = ?2",
-            params![if archived { 1 } else { 0 }, conversation_id],
-        )?;
-
-        Ok(())
+            Ok(())
+        })
     }
 
     // ========================================================================
@@ -209,23 +400,26 @@ impl<'a> MetaConversationOps<'a> {
     /// Insert a new message into a conversation
     pub async fn insert_message(&self, record: &MetaMessageRecord) -> SqliteResult<i64> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            db.execute(
+                "INSERT INTO meta_messages
+                 (conversation_id, message_index, role, content, image_data, tool_calls, timestamp, model, personality_hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    record.conversation_id,
+                    record.message_index,
+                    record.role,
+                    record.content,
+                    record.image_data,
+                    record.tool_calls,
+                    record.timestamp,
+                    record.model,
+                    record.personality_hash
+                ],
+            )?;
 
-        db.execute(
-            "INSERT INTO meta_messages
-             (conversation_id, message_index, role, content, image_data, tool_calls, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                record.conversation_id,
-                record.message_index,
-                record.role,
-                record.content,
-                record.image_data,
-                record.tool_calls,
-                record.timestamp
-            ],
-        )?;
-
-        Ok(db.last_insert_rowid())
+            Ok(db.last_insert_rowid())
+        })
     }
 
     /// Get all messages for a conversation, ordered by message_index
@@ -234,42 +428,164 @@ impl<'a> MetaConversationOps<'a> {
         conversation_id: &str,
     ) -> SqliteResult<Vec<MetaMessageRecord>> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut stmt = db.prepare(&format!(
+                "SELECT {} FROM meta_messages
+                 WHERE conversation_id = ?1
+                 ORDER BY message_index ASC",
+                MESSAGE_COLUMNS
+            ))?;
+
+            let rows = stmt.query_map(params![conversation_id], row_to_message)?;
+
+            rows.collect()
+        })
+    }
 
-        let mut stmt = db.prepare(
-            "SELECT id, conversation_id, message_index, role, content, image_data, tool_calls, timestamp
-             FROM meta_messages
-             WHERE conversation_id = ?1
-             ORDER BY message_index ASC",
-        )?;
-
-        let rows = stmt.query_map(params![conversation_id], |row| {
-            Ok(MetaMessageRecord {
-                id: Some(row.get(0)?),
-                conversation_id: row.get(1)?,
-                message_index: row.get(2)?,
-                role: row.get(3)?,
-                content: row.get(4)?,
-                image_data: row.get(5)?,
-                tool_calls: row.get(6)?,
-                timestamp: row.get(7)?,
+    /// Rate an assistant message, or clear its rating with `None`.
+    ///
+    /// Returns false when the conversation has no assistant message at that index.
+    pub async fn rate_message(
+        &self,
+        conversation_id: &str,
+        message_index: u32,
+        rating: Option<MessageRating>,
+        note: Option<&str>,
+    ) -> SqliteResult<bool> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let rated_at = rating.map(|_| chrono::Utc::now().timestamp_millis());
+            let note = rating.and(note.map(str::trim).filter(|n| !n.is_empty()));
+
+            let updated = db.execute(
+                "UPDATE meta_messages SET rating = ?1, rating_note = ?2, rated_at = ?3
+                 WHERE conversation_id = ?4 AND message_index = ?5 AND role = 'assistant'",
+                params![
+                    rating.map(|r| r.as_str()),
+                    note,
+                    rated_at,
+                    conversation_id,
+                    message_index
+                ],
+            )?;
+
+            Ok(updated > 0)
+        })
+    }
+
+    /// Count ratings given in the last `days` days by personality and model
+    pub async fn get_feedback_summary(&self, days: i64) -> SqliteResult<FeedbackSummary> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let cutoff = chrono::Utc::now().timestamp_millis() - days * 24 * 60 * 60 * 1000;
+
+            let mut stmt = db.prepare(
+                "SELECT personality_hash, model,
+                        SUM(CASE WHEN rating = 'up' THEN 1 ELSE 0 END),
+                        SUM(CASE WHEN rating = 'down' THEN 1 ELSE 0 END)
+                 FROM meta_messages
+                 WHERE rating IS NOT NULL AND rated_at >= ?1
+                 GROUP BY personality_hash, model
+                 ORDER BY SUM(CASE WHEN rating = 'down' THEN 1 ELSE 0 END) DESC, COUNT(*) DESC",
+            )?;
+            let groups = stmt
+                .query_map(params![cutoff], |row| {
+                    Ok(FeedbackGroup {
+                        personality_hash: row.get(0)?,
+                        model: row.get(1)?,
+                        up: row.get(2)?,
+                        down: row.get(3)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(FeedbackSummary {
+                days,
+                total_up: groups.iter().map(|g| g.up).sum(),
+                total_down: groups.iter().map(|g| g.down).sum(),
+                groups,
             })
-        })?;
+        })
+    }
 
-        rows.collect()
+    /// Get messages given `rating` in the last `days` days, newest first, each
+    /// with the user message it answered and the tools called in between
+    pub async fn get_rated_messages(
+        &self,
+        rating: MessageRating,
+        days: i64,
+        limit: usize,
+    ) -> SqliteResult<Vec<RatedMessageContext>> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let cutoff = chrono::Utc::now().timestamp_millis() - days * 24 * 60 * 60 * 1000;
+
+            let rated = {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {} FROM meta_messages
+                     WHERE rating = ?1 AND rated_at >= ?2
+                     ORDER BY rated_at DESC, id DESC LIMIT ?3",
+                    MESSAGE_COLUMNS
+                ))?;
+                let rows = stmt.query_map(
+                    params![rating.as_str(), cutoff, limit as i64],
+                    row_to_message,
+                )?;
+                rows.collect::<SqliteResult<Vec<_>>>()?
+            };
+
+            let mut preceding = db.prepare(&format!(
+                "SELECT {} FROM meta_messages
+                 WHERE conversation_id = ?1 AND message_index < ?2
+                 ORDER BY message_index DESC",
+                MESSAGE_COLUMNS
+            ))?;
+            let mut contexts = Vec::with_capacity(rated.len());
+            for message in rated {
+                // Walk back through the tool loop to the user's own message; tool
+                // results are persisted as user messages that carry tool blocks
+                let mut previous_user_message = None;
+                let mut tools_used = Vec::new();
+                let earlier = preceding.query_map(
+                    params![message.conversation_id, message.message_index],
+                    row_to_message,
+                )?;
+                for earlier in earlier {
+                    let earlier = earlier?;
+                    if earlier.role == "user" && earlier.tool_calls.is_none() {
+                        previous_user_message = Some(earlier.content);
+                        break;
+                    }
+                    if earlier.role == "assistant" {
+                        tools_used.splice(0..0, tool_names(earlier.tool_calls.as_deref()));
+                    }
+                }
+                tools_used.extend(tool_names(message.tool_calls.as_deref()));
+
+                contexts.push(RatedMessageContext {
+                    message,
+                    previous_user_message,
+                    tools_used,
+                });
+            }
+
+            Ok(contexts)
+        })
     }
 
     /// Get the count of messages in a conversation
     #[allow(dead_code)]
     pub async fn get_message_count(&self, conversation_id: &str) -> SqliteResult<u32> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            let count: i64 = db.query_row(
+                "SELECT COUNT(*) FROM meta_messages WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )?;
 
-        let count: i64 = db.query_row(
-            "SELECT COUNT(*) FROM meta_messages WHERE conversation_id = ?1",
-            params![conversation_id],
-            |row| row.get(0),
-        )?;
-
-        Ok(count as u32)
+            Ok(count as u32)
+        })
     }
 
     /// Update conversation metadata after adding a message
@@ -280,77 +596,339 @@ impl<'a> MetaConversationOps<'a> {
         title: Option<&str>,
     ) -> SqliteResult<()> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            let now = chrono::Utc::now().timestamp_millis();
+
+            // Get current message count
+            let count: i64 = db.query_row(
+                "SELECT COUNT(*) FROM meta_messages WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )?;
 
-        let now = chrono::Utc::now().timestamp_millis();
-
-        // Get current message count
-        let count: i64 = db.query_row(
-            "SELECT COUNT(*) FROM meta_messages WHERE conversation_id = ?1",
-            params![conversation_id],
-            |row| row.get(0),
-        )?;
+            // Build dynamic update
+            let mut updates: Vec<String> = vec![
+                "updated_at = ?1".to_string(),
+                "message_count = ?2".to_string(),
+            ];
+            let mut params_list: Vec<Box<dyn rusqlite::ToSql>> =
+                vec![Box::new(now), Box::new(count as u32)];
+
+            if let Some(preview) = preview_text {
+                updates.push("preview_text = ?3".to_string());
+                params_list.push(Box::new(preview.to_string()));
+            }
+
+            if let Some(t) = title {
+                let idx = params_list.len() + 1;
+                updates.push(format!("title = ?{}", idx));
+                params_list.push(Box::new(t.to_string()));
+            }
+
+            let query = format!(
+                "UPDATE meta_conversations SET {} WHERE conversation_id = ?{}",
+                updates.join(", "),
+                params_list.len() + 1
+            );
+            params_list.push(Box::new(conversation_id.to_string()));
+
+            let param_refs: Vec<&dyn rusqlite::ToSql> =
+                params_list.iter().map(|p| p.as_ref()).collect();
+            db.execute(&query, param_refs.as_slice())?;
+
+            Ok(())
+        })
+    }
 
-        // Build dynamic update
-        let mut updates: Vec<String> = vec![
-            "updated_at = ?1".to_string(),
-            "message_count = ?2".to_string(),
-        ];
-        let mut params_list: Vec<Box<dyn rusqlite::ToSql>> =
-            vec![Box::new(now), Box::new(count as u32)];
+    /// Cleanup old conversations (older than days_to_keep)
+    pub async fn cleanup_old_conversations(&self, days_to_keep: i64) -> SqliteResult<usize> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let cutoff =
+                chrono::Utc::now().timestamp_millis() - (days_to_keep * 24 * 60 * 60 * 1000);
 
-        if let Some(preview) = preview_text {
-            updates.push("preview_text = ?3".to_string());
-            params_list.push(Box::new(preview.to_string()));
-        }
+            // Get IDs of old conversations
+            let mut stmt = db.prepare(
+                "SELECT conversation_id FROM meta_conversations WHERE updated_at < ?1 AND is_archived = 0",
+            )?;
+            let old_ids: Vec<String> = stmt
+                .query_map(params![cutoff], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            // Delete messages and conversations
+            for id in &old_ids {
+                db.execute(
+                    "DELETE FROM meta_messages WHERE conversation_id = ?1",
+                    params![id],
+                )?;
+                db.execute(
+                    "DELETE FROM meta_compactions WHERE conversation_id = ?1",
+                    params![id],
+                )?;
+                db.execute(
+                    "DELETE FROM meta_message_pins WHERE conversation_id = ?1",
+                    params![id],
+                )?;
+            }
+
+            let deleted = db.execute(
+                "DELETE FROM meta_conversations WHERE updated_at < ?1 AND is_archived = 0",
+                params![cutoff],
+            )?;
 
-        if let Some(t) = title {
-            let idx = params_list.len() + 1;
-            updates.push(format!("title = ?{}", idx));
-            params_list.push(Box::new(t.to_string()));
-        }
+            Ok(deleted)
+        })
+    }
+}
 
-        let query = format!(
-            "UPDATE meta_conversations SET {} WHERE conversation_id = ?{}",
-            updates.join(", "),
-            params_list.len() + 1
-        );
-        params_list.push(Box::new(conversation_id.to_string()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::DEFAULT_WORKSPACE_ID;
 
-        let param_refs: Vec<&dyn rusqlite::ToSql> =
-            params_list.iter().map(|p| p.as_ref()).collect();
-        db.execute(&query, param_refs.as_slice())?;
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_meta_conversation_tables(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
 
-        Ok(())
+    fn conversation(conversation_id: &str) -> MetaConversationRecord {
+        MetaConversationRecord {
+            id: None,
+            conversation_id: conversation_id.to_string(),
+            title: Some("Original".to_string()),
+            created_at: 1,
+            updated_at: 1,
+            message_count: 0,
+            is_archived: false,
+            preview_text: None,
+            title_locked: false,
+            summary: None,
+            forked_from: None,
+            forked_at_message: None,
+            workspace_id: DEFAULT_WORKSPACE_ID.to_string(),
+        }
     }
 
-    /// Cleanup old conversations (older than days_to_keep)
-    pub async fn cleanup_old_conversations(&self, days_to_keep: i64) -> SqliteResult<usize> {
-        let db = self.db.lock().await;
+    fn message(conversation_id: &str, message_index: u32) -> MetaMessageRecord {
+        MetaMessageRecord {
+            id: None,
+            conversation_id: conversation_id.to_string(),
+            message_index,
+            role: "user".to_string(),
+            content: format!("message {}", message_index),
+            image_data: None,
+            tool_calls: None,
+            timestamp: message_index as i64,
+            model: None,
+            personality_hash: None,
+            rating: None,
+            rating_note: None,
+            rated_at: None,
+        }
+    }
 
-        let cutoff = chrono::Utc::now().timestamp_millis() - (days_to_keep * 24 * 60 * 60 * 1000);
+    #[tokio::test]
+    async fn test_fork_copies_messages_and_links_original() {
+        let db = test_db();
+        let ops = MetaConversationOps::new(&db);
+        ops.create_conversation(&conversation("orig"))
+            .await
+            .unwrap();
+        for index in 1..=4 {
+            ops.insert_message(&message("orig", index)).await.unwrap();
+        }
 
-        // Get IDs of old conversations
-        let mut stmt = db.prepare(
-            "SELECT conversation_id FROM meta_conversations WHERE updated_at < ?1 AND is_archived = 0",
-        )?;
-        let old_ids: Vec<String> = stmt
-            .query_map(params![cutoff], |row| row.get(0))?
-            .filter_map(|r| r.ok())
-            .collect();
+        let originals = ops.get_messages("orig").await.unwrap();
+        let fork = MetaConversationRecord {
+            message_count: 2,
+            forked_from: Some("orig".to_string()),
+            forked_at_message: Some(1),
+            ..conversation("fork")
+        };
+        ops.fork_conversation(&fork, &originals[..2]).await.unwrap();
+
+        let copied = ops.get_messages("fork").await.unwrap();
+        assert_eq!(copied.len(), 2);
+        assert_eq!(copied[1].message_index, 2);
+        assert_eq!(copied[1].content, "message 2");
+        assert_eq!(ops.get_messages("orig").await.unwrap().len(), 4);
+
+        let stored = ops.get_conversation("fork").await.unwrap().unwrap();
+        assert_eq!(stored.forked_from.as_deref(), Some("orig"));
+        assert_eq!(stored.forked_at_message, Some(1));
+        assert_eq!(stored.message_count, 2);
+    }
 
-        // Delete messages and conversations
-        for id in &old_ids {
-            db.execute(
-                "DELETE FROM meta_messages WHERE conversation_id = ?1",
-                params![id],
-            )?;
+    #[tokio::test]
+    async fn test_ratings_are_summarized_with_their_context() {
+        let db = test_db();
+        let ops = MetaConversationOps::new(&db);
+        ops.create_conversation(&conversation("conv"))
+            .await
+            .unwrap();
+
+        let tool_use = r#"[{"type":"tool_use","id":"t1","name":"ListAgents","input":{}}]"#;
+        let tool_result = r#"[{"type":"tool_result","tool_use_id":"t1","content":"[]"}]"#;
+        let messages = [
+            ("user", "how many agents?", None),
+            ("assistant", "Checking.", Some(tool_use)),
+            ("user", "[]", Some(tool_result)),
+            ("assistant", "There are none.", None),
+            ("user", "thanks", None),
+            ("assistant", "Anytime.", None),
+        ];
+        for (i, (role, content, tool_calls)) in messages.iter().enumerate() {
+            ops.insert_message(&MetaMessageRecord {
+                role: role.to_string(),
+                content: content.to_string(),
+                tool_calls: tool_calls.map(|t| t.to_string()),
+                model: Some("claude-test".to_string()),
+                personality_hash: Some("abc".to_string()),
+                ..message("conv", i as u32 + 1)
+            })
+            .await
+            .unwrap();
         }
 
-        let deleted = db.execute(
-            "DELETE FROM meta_conversations WHERE updated_at < ?1 AND is_archived = 0",
-            params![cutoff],
-        )?;
+        // Only assistant messages can be rated
+        assert!(!ops
+            .rate_message("conv", 1, Some(MessageRating::Up), None)
+            .await
+            .unwrap());
+        assert!(ops
+            .rate_message("conv", 4, Some(MessageRating::Down), Some(" wrong count "))
+            .await
+            .unwrap());
+        assert!(ops
+            .rate_message("conv", 6, Some(MessageRating::Up), None)
+            .await
+            .unwrap());
+
+        let summary = ops.get_feedback_summary(7).await.unwrap();
+        assert_eq!((summary.total_up, summary.total_down), (1, 1));
+        assert_eq!(summary.groups.len(), 1);
+        assert_eq!(summary.groups[0].model.as_deref(), Some("claude-test"));
+
+        let low = ops
+            .get_rated_messages(MessageRating::Down, 7, 10)
+            .await
+            .unwrap();
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].message.content, "There are none.");
+        assert_eq!(low[0].message.rating_note.as_deref(), Some("wrong count"));
+        assert_eq!(
+            low[0].previous_user_message.as_deref(),
+            Some("how many agents?")
+        );
+        assert_eq!(low[0].tools_used, vec!["ListAgents".to_string()]);
+
+        // Clearing a rating drops it from the summary
+        ops.rate_message("conv", 4, None, Some("ignored"))
+            .await
+            .unwrap();
+        let stored = ops.get_messages("conv").await.unwrap();
+        assert_eq!(stored[3].rating, None);
+        assert_eq!(stored[3].rating_note, None);
+        assert_eq!(ops.get_feedback_summary(7).await.unwrap().total_down, 0);
+    }
 
-        Ok(deleted)
+    #[tokio::test]
+    async fn test_list_conversations_scoped_to_workspace() {
+        let db = test_db();
+        let ops = MetaConversationOps::new(&db);
+        ops.create_conversation(&conversation("default-conv"))
+            .await
+            .unwrap();
+        ops.create_conversation(&MetaConversationRecord {
+            workspace_id: "acme".to_string(),
+            ..conversation("acme-conv")
+        })
+        .await
+        .unwrap();
+
+        let scoped = |workspace_id: &str| ConversationQueryFilters {
+            workspace_id: Some(workspace_id.to_string()),
+            ..Default::default()
+        };
+        let acme = ops.list_conversations(scoped("acme")).await.unwrap();
+        assert_eq!(acme.len(), 1);
+        assert_eq!(acme[0].conversation_id, "acme-conv");
+        assert_eq!(acme[0].workspace_id, "acme");
+        assert_eq!(
+            ops.list_conversations(scoped(DEFAULT_WORKSPACE_ID))
+                .await
+                .unwrap()[0]
+                .conversation_id,
+            "default-conv"
+        );
+        assert_eq!(
+            ops.list_conversations(ConversationQueryFilters::default())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_search_and_updated_window() {
+        let db = test_db();
+        let ops = MetaConversationOps::new(&db);
+        for (conversation_id, updated_at, workspace_id) in [
+            ("early", 10, DEFAULT_WORKSPACE_ID),
+            ("late", 20, DEFAULT_WORKSPACE_ID),
+            ("other", 15, "acme"),
+        ] {
+            ops.create_conversation(&MetaConversationRecord {
+                updated_at,
+                workspace_id: workspace_id.to_string(),
+                ..conversation(conversation_id)
+            })
+            .await
+            .unwrap();
+        }
+        ops.insert_message(&MetaMessageRecord {
+            content: "Deploys go through the Staging cluster".to_string(),
+            ..message("early", 1)
+        })
+        .await
+        .unwrap();
+        ops.insert_message(&MetaMessageRecord {
+            role: "tool".to_string(),
+            content: "staging".to_string(),
+            ..message("late", 1)
+        })
+        .await
+        .unwrap();
+        ops.insert_message(&MetaMessageRecord {
+            content: "staging is down".to_string(),
+            ..message("other", 1)
+        })
+        .await
+        .unwrap();
+
+        let found = ops
+            .search_messages("staging", DEFAULT_WORKSPACE_ID, 10)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].conversation_id, "early");
+        assert_eq!(found[0].conversation_title.as_deref(), Some("Original"));
+
+        let window: Vec<_> = ops
+            .list_conversations_updated_between(DEFAULT_WORKSPACE_ID, 0, 20)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.conversation_id)
+            .collect();
+        assert_eq!(window, vec!["early", "late"]);
+        assert!(ops
+            .list_conversations_updated_between(DEFAULT_WORKSPACE_ID, 10, 19)
+            .await
+            .unwrap()
+            .is_empty());
     }
 }
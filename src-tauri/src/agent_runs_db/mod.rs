@@ -4,40 +4,116 @@
 // cost tracking, and prompt history.
 //
 // Submodules:
+// - agent_messages.rs: Messages relayed between agents by the commander
+// - artifacts.rs: Skills, subagents and CLAUDE.md files as generated
 // - crud.rs: Create/read/update/delete operations for runs and prompts
 // - queries.rs: Complex queries and statistics
 // - cost.rs: Cost aggregation and reporting
+// - cost_anomaly.rs: Alerts when recent spend is far above its usual level
+// - dead_letter.rs: Persistent retry queue for failed writes
+// - event_buffer.rs: Batches agent output and orchestrator event inserts
+// - groups.rs: Agent groups (per pipeline or user-defined) and their members
+// - hook_events.rs: Capped log of hook events received from agents
+// - human_requests.rs: Pipelines paused on a question for the user
+// - notes.rs: Notes pinned to runs by the user or meta agent
+// - offloads.rs: Work offloaded to worker agents to save API spend
 // - orchestrator_events.rs: Orchestrator event persistence
+// - pipeline_snapshots.rs: Finished pipelines evicted from memory
 // - meta_conversations.rs: Meta agent conversation persistence
+// - trash.rs: Soft-deleted runs and conversations, restore and purge
+// - trends.rs: Historical usage trends bucketed by day/week
+// - turns.rs: Prompts grouped with the outputs produced in response
+// - workspaces.rs: Workspace records and their per-workspace rollups
 // - models.rs: Data structures
 // - schema.rs: Database schema and migrations
+//
+// Concurrency: there is one connection behind an async mutex. Callers queue
+// on the mutex without blocking a thread, and the SQLite work itself runs
+// through db_utils::run_blocking, so the current runtime worker hands its
+// other tasks to another thread while a query or a batched write runs.
+// Writes are serialized by the mutex. A batched write takes the lock once
+// and commits one transaction, so readers see all of it or none of it.
 
+mod agent_messages;
+mod artifacts;
+mod compactions;
 mod cost;
+mod cost_anomaly;
 mod crud;
+mod dead_letter;
+mod event_buffer;
+mod groups;
+mod hook_events;
+mod human_requests;
 mod meta_conversations;
 mod models;
+mod notes;
+mod offloads;
 mod orchestrator_events;
+mod permission_profiles;
+mod pipeline_snapshots;
+mod prompt_library;
 mod queries;
 mod schema;
+mod scratchpads;
+mod trash;
+mod trends;
+mod turns;
+mod workspaces;
 
 use rusqlite::{Connection, Result as SqliteResult};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::events::AppEventEmitter;
+use crate::fault_injection::{check_fault, FaultPoint, SharedFaultHook};
+
 pub use models::{
-    AgentOutputRecord, AgentRun, ConversationQueryFilters, CostSummary, DailyCost, DatabaseStats,
-    DateRangeCostSummary, EventQueryFilters, MetaConversationRecord, MetaMessageRecord,
-    ModelCostBreakdown, OrchestratorDecisionRecord, OrchestratorStateChangeRecord,
-    OrchestratorToolCallRecord, PipelineHistoryBundle, RunQueryFilters, RunStats, RunStatus,
-    SessionCostRecord,
+    AgentOutputRecord, AgentRun, AgentTurn, CompactionRecord, ConversationQueryFilters,
+    CostSummary, DailyCost, DailyOffloads, DatabaseStats, DateRangeCostSummary,
+    ElevatedCommandPolicy, EventQueryFilters, FeedbackGroup, FeedbackSummary,
+    GeneratedArtifactRecord, HookEventRecord, HookEventTotals, MessagePin, MessageRating,
+    MetaConversationRecord, MetaMessageMatch, MetaMessageRecord, ModelCostBreakdown,
+    OrchestratorDecisionRecord, OrchestratorStateChangeRecord, OrchestratorToolCallRecord, Page,
+    PageCursor, PermissionMode, PermissionProfile, PipelineHistoryBundle,
+    PipelineHumanRequestRecord, PipelineSnapshotRecord, PromptCostRecord, PromptMatch, PromptUsage,
+    RatedMessageContext, RunQueryFilters, RunStats, RunStatus, SavedPromptTemplate,
+    ScratchpadVersionRecord, SessionCostRecord, StopMode, TaskOffloadRecord, UsageTrendBucket,
+    UsageTrends, WorkspaceInfo,
 };
+pub use schema::SCHEMA_VERSION;
+pub use trends::TrendBucket;
+
+pub use cost_anomaly::{AgentSpend, CostAnomalyConfig, CostAnomalyEvent, SpendWindow};
+
+pub use dead_letter::{DeadLetterOutcome, DeadLetterStatus, DeadLetterWrite};
+pub use event_buffer::{BufferedEvent, EVENT_BUFFER_MAX_RECORDS, EVENT_FLUSH_INTERVAL};
+pub use orchestrator_events::MIN_SEARCH_CHARS;
 
+use agent_messages::AgentMessageOps;
+use artifacts::ArtifactOps;
+use compactions::CompactionOps;
 use cost::CostOperations;
 use crud::CrudOperations;
+use dead_letter::DeadLetterQueue;
+use event_buffer::EventBuffer;
+use groups::AgentGroupOps;
+use hook_events::HookEventOps;
+use human_requests::HumanRequestOps;
 use meta_conversations::MetaConversationOps;
+use notes::AgentNoteOps;
+use offloads::OffloadOps;
 use orchestrator_events::OrchestratorEventOps;
+use permission_profiles::PermissionProfileOps;
+use pipeline_snapshots::PipelineSnapshotOps;
+use prompt_library::PromptLibraryOps;
 use queries::QueryOperations;
+use scratchpads::ScratchpadOps;
+use trash::TrashOperations;
+use trends::TrendOperations;
+use turns::TurnOperations;
+use workspaces::WorkspaceOperations;
 
 /// Main database interface for agent runs
 ///
@@ -46,6 +122,9 @@ use queries::QueryOperations;
 pub struct AgentRunsDB {
     db: Arc<Mutex<Connection>>,
     db_path: PathBuf,
+    dead_letters: DeadLetterQueue,
+    event_buffer: EventBuffer,
+    fault_hook: SharedFaultHook,
 }
 
 impl AgentRunsDB {
@@ -58,10 +137,20 @@ impl AgentRunsDB {
 
         Ok(Self {
             db: Arc::new(Mutex::new(conn)),
+            dead_letters: DeadLetterQueue::for_db(&db_path),
+            event_buffer: EventBuffer::default(),
             db_path,
+            fault_hook: None,
         })
     }
 
+    /// Install a fault hook so tests can make writes fail with lock errors
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_hook(mut self, hook: Arc<dyn crate::fault_injection::FaultHook>) -> Self {
+        self.fault_hook = Some(hook);
+        self
+    }
+
     // ========================================================================
     // CRUD Operations - delegated to CrudOperations
     // ========================================================================
@@ -76,6 +165,63 @@ impl AgentRunsDB {
         CrudOperations::new(&self.db).update_run(run).await
     }
 
+    /// Replace the stored GitHub context for a run
+    pub async fn update_github_context(
+        &self,
+        agent_id: &str,
+        github_context: Option<String>,
+    ) -> SqliteResult<()> {
+        CrudOperations::new(&self.db)
+            .update_github_context(agent_id, github_context)
+            .await
+    }
+
+    /// Set the label shown for a run
+    pub async fn update_run_label(&self, agent_id: &str, label: &str) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .update_run_label(agent_id, label)
+            .await
+    }
+
+    /// Set the title of the agent behind a run
+    pub async fn update_run_title(&self, agent_id: &str, title: &str) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .update_run_title(agent_id, title)
+            .await
+    }
+
+    /// Record that a run continues an earlier run after a context hand-off
+    pub async fn set_run_continued_from(
+        &self,
+        agent_id: &str,
+        previous: &str,
+    ) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .set_continued_from(agent_id, previous)
+            .await
+    }
+
+    /// Record that a run's agent was cloned from another agent
+    pub async fn set_run_cloned_from(&self, agent_id: &str, original: &str) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .set_cloned_from(agent_id, original)
+            .await
+    }
+
+    /// Record the Claude session a run belongs to
+    pub async fn set_run_session_id(&self, agent_id: &str, session_id: &str) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .set_run_session_id(agent_id, session_id)
+            .await
+    }
+
+    /// Record how stop_agent ended a run's process
+    pub async fn set_stop_mode(&self, agent_id: &str, mode: StopMode) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .set_stop_mode(agent_id, mode)
+            .await
+    }
+
     /// Get a specific run by agent_id
     pub async fn get_run(&self, agent_id: &str) -> SqliteResult<Option<AgentRun>> {
         CrudOperations::new(&self.db).get_run(agent_id).await
@@ -86,6 +232,11 @@ impl AgentRunsDB {
         CrudOperations::new(&self.db).query_runs(filters).await
     }
 
+    /// Query one page of runs with keyset pagination
+    pub async fn query_runs_page(&self, filters: RunQueryFilters) -> SqliteResult<Page<AgentRun>> {
+        CrudOperations::new(&self.db).query_runs_page(filters).await
+    }
+
     /// Get all crashed runs that can be resumed
     pub async fn get_resumable_runs(&self) -> SqliteResult<Vec<AgentRun>> {
         CrudOperations::new(&self.db).get_resumable_runs().await
@@ -108,6 +259,77 @@ impl AgentRunsDB {
         CrudOperations::new(&self.db).get_prompts(agent_id).await
     }
 
+    /// Record when the agent's current turn ended (end_turn / Stop)
+    pub async fn mark_turn_ended(&self, agent_id: &str, ended_at: i64) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .mark_turn_ended(agent_id, ended_at)
+            .await
+    }
+
+    /// Get an agent's prompts grouped with the outputs produced in response
+    pub async fn get_agent_turns(&self, agent_id: &str) -> SqliteResult<Vec<AgentTurn>> {
+        self.flush_before_query().await;
+        TurnOperations::new(&self.db)
+            .get_agent_turns(agent_id)
+            .await
+    }
+
+    /// Attribute usage to the agent's most recent prompt
+    pub async fn record_prompt_usage(
+        &self,
+        agent_id: &str,
+        usage: &PromptUsage,
+    ) -> SqliteResult<bool> {
+        CrudOperations::new(&self.db)
+            .record_prompt_usage(agent_id, usage)
+            .await
+    }
+
+    /// Get an agent's prompts with the tokens and cost of each response
+    pub async fn get_prompt_costs(&self, agent_id: &str) -> SqliteResult<Vec<PromptCostRecord>> {
+        CrudOperations::new(&self.db)
+            .get_prompt_costs(agent_id)
+            .await
+    }
+
+    /// Search prompts sent to agents, newest first
+    pub async fn search_prompts(
+        &self,
+        keyword: &str,
+        filters: RunQueryFilters,
+    ) -> SqliteResult<Vec<PromptMatch>> {
+        CrudOperations::new(&self.db)
+            .search_prompts(keyword, filters)
+            .await
+    }
+
+    /// Distinct working directories of the most recent runs, newest first
+    pub async fn get_recent_working_dirs(&self, limit: usize) -> SqliteResult<Vec<String>> {
+        CrudOperations::new(&self.db)
+            .get_recent_working_dirs(limit)
+            .await
+    }
+
+    /// Distinct working directories of recent runs with when each was last used
+    pub async fn get_recent_working_dir_usage(
+        &self,
+        limit: usize,
+    ) -> SqliteResult<Vec<(String, i64)>> {
+        CrudOperations::new(&self.db)
+            .get_recent_working_dir_usage(limit)
+            .await
+    }
+
+    /// When each working directory at or below `parent` was last used
+    pub async fn get_working_dir_usage_under(
+        &self,
+        parent: &str,
+    ) -> SqliteResult<Vec<(String, i64)>> {
+        CrudOperations::new(&self.db)
+            .get_working_dir_usage_under(parent)
+            .await
+    }
+
     /// Clean up old runs (older than days_to_keep)
     pub async fn cleanup_old_runs(&self, days_to_keep: i64) -> SqliteResult<usize> {
         CrudOperations::new(&self.db)
@@ -116,10 +338,181 @@ impl AgentRunsDB {
     }
 
     /// Mark all "running" or "waiting_input" agents as "crashed" - called on app startup
-    pub async fn reconcile_stale_runs(&self) -> SqliteResult<usize> {
+    pub async fn reconcile_stale_runs(&self) -> SqliteResult<Vec<String>> {
         CrudOperations::new(&self.db).reconcile_stale_runs().await
     }
 
+    // ========================================================================
+    // Agent Notes - delegated to AgentNoteOps
+    // ========================================================================
+
+    /// Pin a note to an agent run, returning its row ID
+    pub async fn add_agent_note(&self, note: &crate::types::AgentNote) -> SqliteResult<i64> {
+        AgentNoteOps::new(&self.db).add_note(note).await
+    }
+
+    /// Get all notes for an agent, oldest first
+    pub async fn get_agent_notes(
+        &self,
+        agent_id: &str,
+    ) -> SqliteResult<Vec<crate::types::AgentNote>> {
+        AgentNoteOps::new(&self.db).get_notes(agent_id).await
+    }
+
+    /// Search notes across all runs by keyword
+    pub async fn search_agent_notes(
+        &self,
+        keyword: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<crate::types::AgentNote>> {
+        AgentNoteOps::new(&self.db)
+            .search_notes(keyword, limit)
+            .await
+    }
+
+    // ========================================================================
+    // Agent Groups - delegated to AgentGroupOps
+    // ========================================================================
+
+    /// Create an agent group if needed and add its agents as members
+    pub async fn save_agent_group(&self, group: &crate::types::AgentGroup) -> SqliteResult<()> {
+        AgentGroupOps::new(&self.db).save_group(group).await
+    }
+
+    /// Get an agent group with its members
+    pub async fn get_agent_group(
+        &self,
+        group_id: &str,
+    ) -> SqliteResult<Option<crate::types::AgentGroup>> {
+        AgentGroupOps::new(&self.db).get_group(group_id).await
+    }
+
+    /// Get all agent groups with their members, oldest first
+    pub async fn list_agent_groups(&self) -> SqliteResult<Vec<crate::types::AgentGroup>> {
+        AgentGroupOps::new(&self.db).list_groups().await
+    }
+
+    // ========================================================================
+    // Prompt Library - delegated to PromptLibraryOps
+    // ========================================================================
+
+    /// Save a prompt template, replacing the content of one with the same name
+    pub async fn save_prompt_template(
+        &self,
+        name: &str,
+        content: &str,
+    ) -> SqliteResult<SavedPromptTemplate> {
+        PromptLibraryOps::new(&self.db)
+            .save_template(name, content)
+            .await
+    }
+
+    /// Get a prompt template by name
+    pub async fn get_prompt_template(
+        &self,
+        name: &str,
+    ) -> SqliteResult<Option<SavedPromptTemplate>> {
+        PromptLibraryOps::new(&self.db).get_template(name).await
+    }
+
+    /// List prompt templates, most used first, optionally only those whose
+    /// name or content contains a keyword
+    pub async fn list_prompt_templates(
+        &self,
+        keyword: Option<&str>,
+    ) -> SqliteResult<Vec<SavedPromptTemplate>> {
+        PromptLibraryOps::new(&self.db)
+            .list_templates(keyword)
+            .await
+    }
+
+    /// Delete a prompt template; false if there was none with that name
+    pub async fn delete_prompt_template(&self, name: &str) -> SqliteResult<bool> {
+        PromptLibraryOps::new(&self.db).delete_template(name).await
+    }
+
+    /// Count a prompt sent from a template
+    pub async fn record_prompt_template_use(&self, name: &str) -> SqliteResult<bool> {
+        PromptLibraryOps::new(&self.db).record_use(name).await
+    }
+
+    // ========================================================================
+    // Permission Profiles - delegated to PermissionProfileOps
+    // ========================================================================
+
+    /// Save a permission profile, replacing one with the same name and taking
+    /// its sources from any other profile bound to them
+    pub async fn save_permission_profile(
+        &self,
+        profile: &PermissionProfile,
+    ) -> SqliteResult<PermissionProfile> {
+        PermissionProfileOps::new(&self.db)
+            .save_profile(profile)
+            .await
+    }
+
+    /// Get a permission profile by name
+    pub async fn get_permission_profile(
+        &self,
+        name: &str,
+    ) -> SqliteResult<Option<PermissionProfile>> {
+        PermissionProfileOps::new(&self.db).get_profile(name).await
+    }
+
+    /// List permission profiles by name
+    pub async fn list_permission_profiles(&self) -> SqliteResult<Vec<PermissionProfile>> {
+        PermissionProfileOps::new(&self.db).list_profiles().await
+    }
+
+    /// The permission profile bound to an agent source, if any
+    pub async fn permission_profile_for_source(
+        &self,
+        source: &crate::types::AgentSource,
+    ) -> SqliteResult<Option<PermissionProfile>> {
+        PermissionProfileOps::new(&self.db)
+            .profile_for_source(source)
+            .await
+    }
+
+    /// Delete a permission profile; false if there was none with that name
+    pub async fn delete_permission_profile(&self, name: &str) -> SqliteResult<bool> {
+        PermissionProfileOps::new(&self.db)
+            .delete_profile(name)
+            .await
+    }
+
+    // ========================================================================
+    // Agent Messages - delegated to AgentMessageOps
+    // ========================================================================
+
+    /// Record a message relayed between agents, returning its row ID
+    pub async fn add_agent_message(
+        &self,
+        message: &crate::types::AgentMessage,
+    ) -> SqliteResult<i64> {
+        AgentMessageOps::new(&self.db).add_message(message).await
+    }
+
+    /// Messages exchanged within a pipeline, oldest first
+    pub async fn get_pipeline_agent_messages(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Vec<crate::types::AgentMessage>> {
+        AgentMessageOps::new(&self.db)
+            .get_pipeline_messages(pipeline_id)
+            .await
+    }
+
+    /// Messages sent or received by an agent, oldest first
+    pub async fn get_agent_messages(
+        &self,
+        agent_id: &str,
+    ) -> SqliteResult<Vec<crate::types::AgentMessage>> {
+        AgentMessageOps::new(&self.db)
+            .get_agent_messages(agent_id)
+            .await
+    }
+
     // ========================================================================
     // Query/Statistics Operations - delegated to QueryOperations
     // ========================================================================
@@ -133,11 +526,211 @@ impl AgentRunsDB {
 
     /// Get database statistics including size and record counts
     pub async fn get_database_stats(&self) -> SqliteResult<DatabaseStats> {
-        QueryOperations::new(&self.db, &self.db_path)
+        let mut stats = QueryOperations::new(&self.db, &self.db_path)
             .get_database_stats()
+            .await?;
+        stats.dead_letter_count = self.dead_letters.count().await;
+        Ok(stats)
+    }
+
+    /// Get usage trends (runs, cost, tokens, pipeline outcomes) per day or week
+    pub async fn get_usage_trends(
+        &self,
+        days: i64,
+        bucket: TrendBucket,
+    ) -> SqliteResult<UsageTrends> {
+        TrendOperations::new(&self.db)
+            .get_usage_trends(days, bucket)
+            .await
+    }
+
+    // ========================================================================
+    // Dead-Letter Queue - failed writes are persisted and retried
+    // ========================================================================
+
+    /// Apply a write, queueing it for retry if it fails
+    ///
+    /// The original error is still returned so callers can log it.
+    pub async fn write_or_dead_letter(&self, write: DeadLetterWrite) -> Result<(), String> {
+        if let Err(e) = self.apply_write(write.clone()).await {
+            eprintln!(
+                "[agent_runs_db] {} for {} failed, queued for retry: {}",
+                write.kind(),
+                write.agent_id(),
+                e
+            );
+            if let Err(dl_err) = self.dead_letters.push(write, e.clone()).await {
+                eprintln!("[agent_runs_db] Failed to persist dead letter: {}", dl_err);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Retry all queued writes now and report per-entry outcomes
+    pub async fn flush_dead_letters(&self) -> Vec<DeadLetterOutcome> {
+        self.dead_letters
+            .flush(|write| self.apply_write(write))
+            .await
+    }
+
+    // ========================================================================
+    // Trash - delegated to TrashOperations
+    // ========================================================================
+
+    /// Move a finished run to the trash
+    pub async fn delete_run(&self, agent_id: &str) -> SqliteResult<bool> {
+        TrashOperations::new(&self.db)
+            .move_to_trash(TrashKind::Run, agent_id)
+            .await
+    }
+
+    /// List trashed items, optionally of one kind
+    pub async fn list_trash(&self, kind: Option<TrashKind>) -> SqliteResult<Vec<TrashItem>> {
+        TrashOperations::new(&self.db).list_trash(kind).await
+    }
+
+    /// Restore a trashed item
+    pub async fn restore_from_trash(&self, kind: TrashKind, id: &str) -> SqliteResult<bool> {
+        TrashOperations::new(&self.db).restore(kind, id).await
+    }
+
+    /// Permanently delete items trashed more than `older_than_days` days ago
+    pub async fn empty_trash(&self, older_than_days: i64) -> SqliteResult<TrashPurgeResult> {
+        TrashOperations::new(&self.db)
+            .empty_trash(older_than_days)
+            .await
+    }
+
+    /// Spawn the background task that purges trash older than TRASH_RETENTION_DAYS
+    pub fn start_trash_purge(self: &Arc<Self>) {
+        let retention_days = trash_retention_days();
+        if retention_days == 0 {
+            return;
+        }
+
+        let db = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match db.empty_trash(retention_days).await {
+                    Ok(purged) if purged.runs + purged.conversations > 0 => eprintln!(
+                        "[Trash] Purged {} runs and {} conversations older than {} days",
+                        purged.runs, purged.conversations, retention_days
+                    ),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("[Trash] Failed to purge trash: {}", e),
+                }
+                tokio::time::sleep(trash::TRASH_PURGE_INTERVAL).await;
+            }
+        });
+    }
+
+    // ========================================================================
+    // Workspaces - delegated to WorkspaceOperations
+    // ========================================================================
+
+    /// Create a workspace. Returns false if the ID is taken.
+    pub async fn create_workspace(&self, workspace_id: &str, name: &str) -> SqliteResult<bool> {
+        WorkspaceOperations::new(&self.db)
+            .create_workspace(workspace_id, name)
+            .await
+    }
+
+    /// List workspaces with their rollups, flagging the active one
+    pub async fn list_workspaces(&self, active_id: &str) -> SqliteResult<Vec<WorkspaceInfo>> {
+        WorkspaceOperations::new(&self.db)
+            .list_workspaces(active_id)
+            .await
+    }
+
+    /// Record a switch to a workspace. Returns false if it doesn't exist.
+    pub async fn touch_workspace(&self, workspace_id: &str) -> SqliteResult<bool> {
+        WorkspaceOperations::new(&self.db)
+            .touch_workspace(workspace_id)
             .await
     }
 
+    /// Every workspace with the time its conversations were last scanned for memory
+    pub async fn workspace_history_scan_marks(&self) -> SqliteResult<Vec<(String, Option<i64>)>> {
+        WorkspaceOperations::new(&self.db)
+            .history_scan_marks()
+            .await
+    }
+
+    /// Record how far a workspace's conversations have been scanned for memory
+    pub async fn set_workspace_history_scanned_at(
+        &self,
+        workspace_id: &str,
+        scanned_at: i64,
+    ) -> SqliteResult<()> {
+        WorkspaceOperations::new(&self.db)
+            .set_history_scanned_at(workspace_id, scanned_at)
+            .await
+    }
+
+    /// The most recently switched-to workspace
+    pub async fn last_active_workspace(&self) -> SqliteResult<Option<String>> {
+        WorkspaceOperations::new(&self.db)
+            .last_active_workspace()
+            .await
+    }
+
+    /// Spawn the background task that retries queued writes with backoff
+    pub fn start_dead_letter_retry(self: &Arc<Self>) {
+        let db = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut backoff = dead_letter::DEAD_LETTER_BASE_BACKOFF;
+            loop {
+                tokio::time::sleep(backoff).await;
+                if db.dead_letters.count().await == 0 {
+                    backoff = dead_letter::DEAD_LETTER_BASE_BACKOFF;
+                    continue;
+                }
+
+                let outcomes = db.flush_dead_letters().await;
+                let made_progress = outcomes
+                    .iter()
+                    .any(|o| o.status != DeadLetterStatus::Failed);
+                backoff = dead_letter::next_backoff(backoff, made_progress);
+            }
+        });
+    }
+
+    /// Apply a single write. Returns `Ok(false)` when newer data already exists.
+    async fn apply_write(&self, write: DeadLetterWrite) -> Result<bool, String> {
+        check_fault(&self.fault_hook, FaultPoint::DbLocked)?;
+        let crud = CrudOperations::new(&self.db);
+        match write {
+            DeadLetterWrite::CreateRun { run } => {
+                if crud.get_run(&run.agent_id).await.ok().flatten().is_some() {
+                    return Ok(false);
+                }
+                crud.create_run(&run).await.map_err(|e| e.to_string())?;
+            }
+            DeadLetterWrite::UpdateRun { run } => {
+                // Don't let a stale snapshot overwrite a newer successful update
+                let current = crud
+                    .get_run(&run.agent_id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if current.is_some_and(|c| c.last_activity > run.last_activity) {
+                    return Ok(false);
+                }
+                crud.update_run(&run).await.map_err(|e| e.to_string())?;
+            }
+            DeadLetterWrite::RecordPrompt {
+                agent_id,
+                prompt,
+                timestamp,
+            } => {
+                crud.record_prompt(&agent_id, &prompt, timestamp)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(true)
+    }
+
     // ========================================================================
     // Cost Operations - delegated to CostOperations
     // ========================================================================
@@ -154,6 +747,37 @@ impl AgentRunsDB {
         CostOperations::new(&self.db).get_daily_costs(days).await
     }
 
+    /// Record work that was handed to a worker agent instead of the API
+    pub async fn record_task_offload(&self, record: &TaskOffloadRecord) -> SqliteResult<i64> {
+        OffloadOps::new(&self.db).insert_offload(record).await
+    }
+
+    /// Offloaded tasks and the API spend they avoided, per day
+    pub async fn get_daily_offloads(&self, days: i64) -> SqliteResult<Vec<DailyOffloads>> {
+        OffloadOps::new(&self.db).get_daily_offloads(days).await
+    }
+
+    /// Log a hook event, dropping the oldest once the log is full
+    pub async fn record_hook_event(&self, event: &HookEventRecord) -> SqliteResult<i64> {
+        HookEventOps::new(&self.db).insert_hook_event(event).await
+    }
+
+    /// An agent's most recent hook events, newest first
+    pub async fn get_agent_hook_events(
+        &self,
+        agent_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<HookEventRecord>> {
+        HookEventOps::new(&self.db)
+            .get_agent_hook_events(agent_id, limit)
+            .await
+    }
+
+    /// Event counts over the retained hook event log
+    pub async fn get_hook_event_totals(&self) -> SqliteResult<HookEventTotals> {
+        HookEventOps::new(&self.db).get_totals().await
+    }
+
     /// Get total cost for current month
     pub async fn get_current_month_cost(&self) -> Result<f64, String> {
         CostOperations::new(&self.db).get_current_month_cost().await
@@ -185,6 +809,60 @@ impl AgentRunsDB {
         CostOperations::new(&self.db).clear_cost_history().await
     }
 
+    /// Periodically compare recent spend with its baselines, emitting `cost:anomaly`
+    /// (and an OS notification if enabled) when it is abnormally high
+    pub fn start_cost_anomaly_check(self: &Arc<Self>, emitter: Arc<dyn AppEventEmitter>) {
+        let config = cost_anomaly::CostAnomalyConfig::from_env();
+        if !config.enabled() {
+            return;
+        }
+
+        let db = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut alerts = cost_anomaly::AlertLog::default();
+            loop {
+                tokio::time::sleep(cost_anomaly::COST_ANOMALY_CHECK_INTERVAL).await;
+
+                let now = crate::utils::time::now_millis();
+                let since = now - (cost_anomaly::BASELINE_DAYS + 1) * 24 * 60 * 60 * 1000;
+                let ops = CostOperations::new(&db.db);
+                let (spend, daily) = match (
+                    ops.get_prompt_spend_since(since).await,
+                    ops.get_daily_costs(cost_anomaly::BASELINE_DAYS + 1).await,
+                ) {
+                    (Ok(spend), Ok(daily)) => (spend, daily),
+                    (Err(e), _) | (_, Err(e)) => {
+                        eprintln!("[CostAnomaly] Failed to load spend history: {}", e);
+                        continue;
+                    }
+                };
+
+                for event in cost_anomaly::detect_anomalies(now, &spend, &daily, &config) {
+                    if !alerts.should_alert(&event) {
+                        continue;
+                    }
+                    let summary = cost_anomaly::describe(&event);
+                    eprintln!("[CostAnomaly] {}", summary);
+                    if config.notify {
+                        if let Err(e) =
+                            crate::notifications::notify_os("Unusual API spend", &summary)
+                        {
+                            eprintln!("[CostAnomaly] {}", e);
+                        }
+                    }
+                    match serde_json::to_value(&event) {
+                        Ok(payload) => {
+                            if let Err(e) = emitter.emit("cost:anomaly", payload) {
+                                eprintln!("[CostAnomaly] Failed to emit event: {}", e);
+                            }
+                        }
+                        Err(e) => eprintln!("[CostAnomaly] Failed to serialize event: {}", e),
+                    }
+                }
+            }
+        });
+    }
+
     // ========================================================================
     // Orchestrator Event Persistence - delegated to OrchestratorEventOps
     // ========================================================================
@@ -220,11 +898,102 @@ impl AgentRunsDB {
             .await
     }
 
+    /// Insert orchestrator tool calls in a single transaction
+    pub async fn insert_tool_calls(
+        &self,
+        records: &[OrchestratorToolCallRecord],
+    ) -> SqliteResult<usize> {
+        OrchestratorEventOps::new(&self.db)
+            .insert_tool_calls(records)
+            .await
+    }
+
+    /// Insert orchestrator state changes in a single transaction
+    pub async fn insert_state_changes(
+        &self,
+        records: &[OrchestratorStateChangeRecord],
+    ) -> SqliteResult<usize> {
+        OrchestratorEventOps::new(&self.db)
+            .insert_state_changes(records)
+            .await
+    }
+
+    /// Insert orchestrator decisions in a single transaction
+    pub async fn insert_decisions(
+        &self,
+        records: &[OrchestratorDecisionRecord],
+    ) -> SqliteResult<usize> {
+        OrchestratorEventOps::new(&self.db)
+            .insert_decisions(records)
+            .await
+    }
+
+    /// Insert agent outputs in a single transaction
+    pub async fn insert_agent_outputs(&self, records: &[AgentOutputRecord]) -> SqliteResult<usize> {
+        OrchestratorEventOps::new(&self.db)
+            .insert_agent_outputs(records)
+            .await
+    }
+
+    /// Queue an agent output or orchestrator event for a batched write.
+    ///
+    /// The record is written by the next flush: when the buffer fills up, on
+    /// the background interval, before any event query, or on shutdown.
+    pub async fn buffer_event(&self, event: BufferedEvent) {
+        if self.event_buffer.push(event) {
+            if let Err(e) = self.flush_event_buffer().await {
+                eprintln!("[AgentRunsDB] Failed to flush event buffer: {}", e);
+            }
+        }
+    }
+
+    /// Write all buffered events now. Records that fail to write stay buffered.
+    pub async fn flush_event_buffer(&self) -> SqliteResult<usize> {
+        let _flushing = self.event_buffer.flush_lock.lock().await;
+        let pending = self.event_buffer.take();
+
+        match OrchestratorEventOps::new(&self.db)
+            .insert_pending_events(&pending)
+            .await
+        {
+            Ok(written) => Ok(written),
+            Err(e) => {
+                self.event_buffer.restore(pending);
+                Err(e)
+            }
+        }
+    }
+
+    /// Spawn the background task that flushes buffered events periodically
+    pub fn start_event_flusher(self: &Arc<Self>) {
+        let db = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut interval = tokio::time::interval(EVENT_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = db.flush_event_buffer().await {
+                    eprintln!("[AgentRunsDB] Failed to flush event buffer: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Flush buffered events before reading so queries see every write
+    async fn flush_before_query(&self) {
+        if let Err(e) = self.flush_event_buffer().await {
+            eprintln!(
+                "[AgentRunsDB] Failed to flush event buffer before query: {}",
+                e
+            );
+        }
+    }
+
     /// Query orchestrator tool calls with filters
     pub async fn query_tool_calls(
         &self,
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<OrchestratorToolCallRecord>> {
+        self.flush_before_query().await;
         OrchestratorEventOps::new(&self.db)
             .query_tool_calls(filters)
             .await
@@ -235,6 +1004,7 @@ impl AgentRunsDB {
         &self,
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<OrchestratorStateChangeRecord>> {
+        self.flush_before_query().await;
         OrchestratorEventOps::new(&self.db)
             .query_state_changes(filters)
             .await
@@ -245,6 +1015,7 @@ impl AgentRunsDB {
         &self,
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<OrchestratorDecisionRecord>> {
+        self.flush_before_query().await;
         OrchestratorEventOps::new(&self.db)
             .query_decisions(filters)
             .await
@@ -255,23 +1026,145 @@ impl AgentRunsDB {
         &self,
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<AgentOutputRecord>> {
+        self.flush_before_query().await;
         OrchestratorEventOps::new(&self.db)
             .query_agent_outputs(filters)
             .await
     }
 
+    /// Agent outputs containing `query` (case-insensitive, at least
+    /// MIN_SEARCH_CHARS characters), newest first
+    pub async fn search_agent_outputs(
+        &self,
+        query: &str,
+        agent_id: Option<&str>,
+        limit: usize,
+    ) -> SqliteResult<Vec<AgentOutputRecord>> {
+        self.flush_before_query().await;
+        OrchestratorEventOps::new(&self.db)
+            .search_agent_outputs(query, agent_id, limit)
+            .await
+    }
+
     /// Get all history for a pipeline (for restoring UI state after reload)
     pub async fn get_pipeline_history(
         &self,
         pipeline_id: &str,
     ) -> SqliteResult<PipelineHistoryBundle> {
+        self.flush_before_query().await;
         OrchestratorEventOps::new(&self.db)
             .get_pipeline_history(pipeline_id)
             .await
     }
 
+    /// Record a snapshot of a pipeline's scratchpad
+    pub async fn insert_scratchpad_version(
+        &self,
+        record: &ScratchpadVersionRecord,
+    ) -> SqliteResult<i64> {
+        ScratchpadOps::new(&self.db).insert_version(record).await
+    }
+
+    /// Save a pipeline paused on a question for the user
+    pub async fn save_pipeline_human_request(
+        &self,
+        record: &PipelineHumanRequestRecord,
+    ) -> SqliteResult<()> {
+        HumanRequestOps::new(&self.db).save_request(record).await
+    }
+
+    /// All pipelines paused on a question for the user
+    pub async fn list_pipeline_human_requests(
+        &self,
+    ) -> SqliteResult<Vec<PipelineHumanRequestRecord>> {
+        HumanRequestOps::new(&self.db).list_requests().await
+    }
+
+    /// Forget a pipeline's question once answered or cancelled
+    pub async fn delete_pipeline_human_request(&self, pipeline_id: &str) -> SqliteResult<bool> {
+        HumanRequestOps::new(&self.db)
+            .delete_request(pipeline_id)
+            .await
+    }
+
+    /// Save a finished pipeline evicted from memory
+    pub async fn save_pipeline_snapshot(
+        &self,
+        record: &PipelineSnapshotRecord,
+    ) -> SqliteResult<()> {
+        PipelineSnapshotOps::new(&self.db)
+            .save_snapshot(record)
+            .await
+    }
+
+    /// An evicted pipeline, if it was evicted
+    pub async fn get_pipeline_snapshot(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Option<PipelineSnapshotRecord>> {
+        PipelineSnapshotOps::new(&self.db)
+            .get_snapshot(pipeline_id)
+            .await
+    }
+
+    /// Evicted pipelines created at or after `since` (all if None), newest first
+    pub async fn list_pipeline_snapshots(
+        &self,
+        since: Option<i64>,
+    ) -> SqliteResult<Vec<PipelineSnapshotRecord>> {
+        PipelineSnapshotOps::new(&self.db)
+            .list_snapshots(since)
+            .await
+    }
+
+    /// Record a generated skill, subagent or CLAUDE.md
+    pub async fn insert_generated_artifact(
+        &self,
+        record: &GeneratedArtifactRecord,
+    ) -> SqliteResult<i64> {
+        ArtifactOps::new(&self.db).insert_artifact(record).await
+    }
+
+    /// Get a pipeline's generated artifacts (without content), oldest first
+    pub async fn list_pipeline_artifacts(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Vec<GeneratedArtifactRecord>> {
+        ArtifactOps::new(&self.db)
+            .list_pipeline_artifacts(pipeline_id)
+            .await
+    }
+
+    /// Get a generated artifact's content
+    pub async fn get_artifact_content(&self, id: i64) -> SqliteResult<Option<String>> {
+        ArtifactOps::new(&self.db).get_artifact_content(id).await
+    }
+
+    /// Mark the artifacts generated for an agent as removed
+    pub async fn mark_agent_artifacts_removed(
+        &self,
+        agent_id: &str,
+        removed_at: i64,
+    ) -> SqliteResult<usize> {
+        ArtifactOps::new(&self.db)
+            .mark_agent_artifacts_removed(agent_id, removed_at)
+            .await
+    }
+
+    /// Mark the artifacts generated for a pipeline as removed
+    pub async fn mark_pipeline_artifacts_removed(
+        &self,
+        pipeline_id: &str,
+        removed_at: i64,
+    ) -> SqliteResult<usize> {
+        ArtifactOps::new(&self.db)
+            .mark_pipeline_artifacts_removed(pipeline_id, removed_at)
+            .await
+    }
+
     /// Clear all orchestrator events for a pipeline
     pub async fn clear_pipeline_events(&self, pipeline_id: &str) -> SqliteResult<()> {
+        self.flush_before_query().await;
         OrchestratorEventOps::new(&self.db)
             .clear_pipeline_events(pipeline_id)
             .await
@@ -321,10 +1214,35 @@ impl AgentRunsDB {
             .await
     }
 
-    /// Delete a meta agent conversation and all its messages
-    pub async fn delete_meta_conversation(&self, conversation_id: &str) -> SqliteResult<()> {
+    /// Meta agent conversations in a workspace last updated after `after` and
+    /// no later than `until`, oldest first
+    pub async fn list_meta_conversations_updated_between(
+        &self,
+        workspace_id: &str,
+        after: i64,
+        until: i64,
+    ) -> SqliteResult<Vec<MetaConversationRecord>> {
         MetaConversationOps::new(&self.db)
-            .delete_conversation(conversation_id)
+            .list_conversations_updated_between(workspace_id, after, until)
+            .await
+    }
+
+    /// Search the text of user and assistant messages in a workspace's conversations
+    pub async fn search_meta_messages(
+        &self,
+        keyword: &str,
+        workspace_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<MetaMessageMatch>> {
+        MetaConversationOps::new(&self.db)
+            .search_messages(keyword, workspace_id, limit)
+            .await
+    }
+
+    /// Move a meta agent conversation to the trash
+    pub async fn delete_meta_conversation(&self, conversation_id: &str) -> SqliteResult<bool> {
+        TrashOperations::new(&self.db)
+            .move_to_trash(TrashKind::Conversation, conversation_id)
             .await
     }
 
@@ -339,6 +1257,28 @@ impl AgentRunsDB {
             .await
     }
 
+    /// Set a generated title unless the conversation's title is locked
+    pub async fn set_meta_conversation_generated_title(
+        &self,
+        conversation_id: &str,
+        title: &str,
+    ) -> SqliteResult<bool> {
+        MetaConversationOps::new(&self.db)
+            .set_generated_title(conversation_id, title)
+            .await
+    }
+
+    /// Store a generated summary on a meta agent conversation
+    pub async fn set_meta_conversation_summary(
+        &self,
+        conversation_id: &str,
+        summary: &str,
+    ) -> SqliteResult<()> {
+        MetaConversationOps::new(&self.db)
+            .set_summary(conversation_id, summary)
+            .await
+    }
+
     /// Archive/unarchive a meta agent conversation
     pub async fn set_meta_conversation_archived(
         &self,
@@ -350,6 +1290,17 @@ impl AgentRunsDB {
             .await
     }
 
+    /// Create a forked meta agent conversation with a copy of the given messages
+    pub async fn fork_meta_conversation(
+        &self,
+        fork: &MetaConversationRecord,
+        messages: &[MetaMessageRecord],
+    ) -> SqliteResult<()> {
+        MetaConversationOps::new(&self.db)
+            .fork_conversation(fork, messages)
+            .await
+    }
+
     /// Insert a message into a meta agent conversation
     pub async fn insert_meta_message(&self, record: &MetaMessageRecord) -> SqliteResult<i64> {
         MetaConversationOps::new(&self.db)
@@ -367,6 +1318,38 @@ impl AgentRunsDB {
             .await
     }
 
+    /// Rate an assistant message in a meta agent conversation (None clears it)
+    pub async fn rate_meta_message(
+        &self,
+        conversation_id: &str,
+        message_index: u32,
+        rating: Option<MessageRating>,
+        note: Option<&str>,
+    ) -> SqliteResult<bool> {
+        MetaConversationOps::new(&self.db)
+            .rate_message(conversation_id, message_index, rating, note)
+            .await
+    }
+
+    /// Count ratings from the last `days` days by personality and model
+    pub async fn get_feedback_summary(&self, days: i64) -> SqliteResult<FeedbackSummary> {
+        MetaConversationOps::new(&self.db)
+            .get_feedback_summary(days)
+            .await
+    }
+
+    /// Get rated messages with the user message and tools that led to them
+    pub async fn get_rated_meta_messages(
+        &self,
+        rating: MessageRating,
+        days: i64,
+        limit: usize,
+    ) -> SqliteResult<Vec<RatedMessageContext>> {
+        MetaConversationOps::new(&self.db)
+            .get_rated_messages(rating, days, limit)
+            .await
+    }
+
     /// Update conversation metadata after adding a message
     pub async fn update_meta_conversation_after_message(
         &self,
@@ -385,4 +1368,106 @@ impl AgentRunsDB {
             .cleanup_old_conversations(days_to_keep)
             .await
     }
+
+    // ========================================================================
+    // Compactions and Message Pins - delegated to CompactionOps
+    // ========================================================================
+
+    /// Store a compaction of a meta agent conversation
+    pub async fn record_meta_compaction(&self, compaction: &CompactionRecord) -> SqliteResult<i64> {
+        CompactionOps::new(&self.db)
+            .record_compaction(compaction)
+            .await
+    }
+
+    /// Get the most recent compaction of a meta agent conversation
+    pub async fn get_last_meta_compaction(
+        &self,
+        conversation_id: &str,
+    ) -> SqliteResult<Option<CompactionRecord>> {
+        CompactionOps::new(&self.db)
+            .get_last_compaction(conversation_id)
+            .await
+    }
+
+    /// Pin or unpin a meta agent message; false if there is no such message
+    pub async fn set_meta_message_pin(
+        &self,
+        conversation_id: &str,
+        message_index: u32,
+        pinned: bool,
+    ) -> SqliteResult<bool> {
+        CompactionOps::new(&self.db)
+            .set_message_pin(conversation_id, message_index, pinned)
+            .await
+    }
+
+    /// Get the pins of a meta agent conversation
+    pub async fn get_meta_message_pins(
+        &self,
+        conversation_id: &str,
+    ) -> SqliteResult<Vec<MessagePin>> {
+        CompactionOps::new(&self.db)
+            .get_message_pins(conversation_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(i: usize) -> AgentOutputRecord {
+        AgentOutputRecord {
+            id: None,
+            agent_id: "writer".to_string(),
+            pipeline_id: None,
+            output_type: "text".to_string(),
+            content: format!("line {}", i),
+            metadata: None,
+            timestamp: i as i64,
+            turn_index: Some(0),
+        }
+    }
+
+    fn writer_filter() -> EventQueryFilters {
+        EventQueryFilters {
+            agent_id: Some("writer".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_reads_during_a_large_batched_write() {
+        const RECORDS: usize = 5_000;
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(AgentRunsDB::new(dir.path().join("runs.db")).unwrap());
+
+        let records: Vec<AgentOutputRecord> = (0..RECORDS).map(output).collect();
+        let writer = {
+            let db = db.clone();
+            tokio::spawn(async move { db.insert_agent_outputs(&records).await })
+        };
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    for _ in 0..10 {
+                        let seen = db.query_agent_outputs(writer_filter()).await.unwrap().len();
+                        // The batch is one transaction: none of it or all of it
+                        assert!(seen == 0 || seen == RECORDS, "saw {} rows", seen);
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+
+        assert_eq!(writer.await.unwrap().unwrap(), RECORDS);
+        for reader in readers {
+            reader.await.unwrap();
+        }
+        let written = db.query_agent_outputs(writer_filter()).await.unwrap();
+        assert_eq!(written.len(), RECORDS);
+    }
 }
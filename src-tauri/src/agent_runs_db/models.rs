@@ -4,7 +4,14 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::trash::TrashKind;
 use crate::types::AgentSource;
+use crate::workspace::DEFAULT_WORKSPACE_ID;
+
+/// Serde default for records written before workspaces existed
+fn default_workspace_id() -> String {
+    DEFAULT_WORKSPACE_ID.to_string()
+}
 
 /// Run status - tracks the lifecycle of an agent run
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -40,6 +47,33 @@ impl RunStatus {
     }
 }
 
+/// How stop_agent ended an agent's process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopMode {
+    /// Exited on its own after stdin was closed
+    Graceful,
+    /// Killed, at once or after the drain timeout
+    Forced,
+}
+
+impl StopMode {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            StopMode::Graceful => "graceful",
+            StopMode::Forced => "forced",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "graceful" => Some(StopMode::Graceful),
+            "forced" => Some(StopMode::Forced),
+            _ => None,
+        }
+    }
+}
+
 /// A record of an agent run - stored in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentRun {
@@ -71,6 +105,105 @@ pub struct AgentRun {
     // Recovery information
     pub can_resume: bool,
     pub resume_data: Option<String>, // JSON serialized state for recovery
+
+    // Whether a repository conventions digest was prepended to the first prompt
+    #[serde(default)]
+    pub conventions_injected: bool,
+
+    // Short name derived from the initial prompt, or set by the user
+    #[serde(default)]
+    pub label: Option<String>,
+
+    // Workspace that was active when the run started
+    #[serde(default = "default_workspace_id")]
+    pub workspace_id: String,
+
+    // Run this one took over from in a context hand-off
+    #[serde(default)]
+    pub continued_from: Option<String>,
+
+    // Build that created the run; set by create_run, None for runs from
+    // before it was recorded
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub git_commit: Option<String>,
+    #[serde(default)]
+    pub schema_version: Option<i64>,
+
+    // How a stopped agent's process ended; set by stop_agent
+    #[serde(default)]
+    pub stop_mode: Option<StopMode>,
+
+    // Permission profile the agent was spawned under, if any
+    #[serde(default)]
+    pub permission_profile: Option<String>,
+
+    // Model passed to the claude CLI (--model); None for the CLI's default
+    #[serde(default)]
+    pub model: Option<String>,
+
+    // Display title of the agent, e.g. its pipeline stage; set by rename_agent
+    #[serde(default)]
+    pub title: Option<String>,
+
+    // Names of the extra env vars the agent was spawned with; values are not stored
+    #[serde(default)]
+    pub env_keys: Vec<String>,
+
+    // Tools the agent could use without asking and tools it could not use:
+    // its permission profile's lists combined with those it was spawned with
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+
+    // Repository and git worktree the agent ran in when it had a worktree of its own
+    #[serde(default)]
+    pub repo_path: Option<String>,
+    #[serde(default)]
+    pub worktree_path: Option<String>,
+
+    // Time spent per tool (JSON: tool name -> calls, total_ms, max_ms), stored on stop
+    #[serde(default)]
+    pub tool_timings: Option<String>,
+
+    // Agent this one was cloned from (clone_agent)
+    #[serde(default)]
+    pub cloned_from: Option<String>,
+}
+
+/// Keyset pagination cursor: the `(timestamp, id)` of the last row already seen
+///
+/// Rows are ordered by `(timestamp DESC, id DESC)`, so a page starting after
+/// the cursor is stable even when new rows are inserted while paging.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub timestamp: i64,
+    pub id: i64,
+}
+
+/// One page of results plus the cursor for the next page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// None when this is the last page
+    pub next_cursor: Option<PageCursor>,
+}
+
+impl<T> Page<T> {
+    /// Build a page, deriving the next cursor from the last item when the page is full
+    pub fn from_items(
+        items: Vec<T>,
+        limit: Option<usize>,
+        cursor_of: impl Fn(&T) -> PageCursor,
+    ) -> Self {
+        let next_cursor = match (limit, items.last()) {
+            (Some(limit), Some(last)) if items.len() >= limit => Some(cursor_of(last)),
+            _ => None,
+        };
+        Self { items, next_cursor }
+    }
 }
 
 /// Query filters for searching runs
@@ -78,15 +211,31 @@ pub struct AgentRun {
 pub struct RunQueryFilters {
     pub status: Option<RunStatus>,
     pub working_dir: Option<String>,
+    /// Only runs whose working directory starts with this path
+    pub working_dir_prefix: Option<String>,
     pub source: Option<AgentSource>,
+    /// Only runs started in this workspace (all workspaces when None)
+    pub workspace_id: Option<String>,
     pub date_from: Option<DateTime<Utc>>,
     pub date_to: Option<DateTime<Utc>>,
     pub limit: Option<usize>,
+    /// Deprecated: offsets skip or repeat runs inserted between pages, use `before`
     pub offset: Option<usize>,
+    /// Only return runs ordered after this `(started_at, id)` cursor
+    pub before: Option<PageCursor>,
 }
 
-/// Model cost breakdown - detailed token usage per model
+/// A prompt matched by `search_prompts`, with the run's working directory
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMatch {
+    pub agent_id: String,
+    pub prompt: String,
+    pub timestamp: i64,
+    pub working_dir: String,
+}
+
+/// Model cost breakdown - detailed token usage per model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ModelCostBreakdown {
     pub input_tokens: u64,
     pub output_tokens: u64,
@@ -95,6 +244,64 @@ pub struct ModelCostBreakdown {
     pub cost_usd: f64,
 }
 
+/// Tokens and cost attributed to a single prompt-response cycle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptUsage {
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    #[serde(default)]
+    pub model_usage: HashMap<String, ModelCostBreakdown>,
+}
+
+impl PromptUsage {
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens == 0 && self.cost_usd == 0.0 && self.model_usage.is_empty()
+    }
+
+    /// Add another cycle's usage (a prompt answered by several result messages)
+    pub fn merge(&mut self, other: &PromptUsage) {
+        self.total_tokens += other.total_tokens;
+        self.cost_usd += other.cost_usd;
+        for (model, usage) in &other.model_usage {
+            let entry = self.model_usage.entry(model.clone()).or_default();
+            entry.input_tokens += usage.input_tokens;
+            entry.output_tokens += usage.output_tokens;
+            entry.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+            entry.cache_read_input_tokens += usage.cache_read_input_tokens;
+            entry.cost_usd += usage.cost_usd;
+        }
+    }
+}
+
+/// A prompt with the usage of the response to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptCostRecord {
+    pub agent_id: String,
+    pub prompt: String,
+    pub timestamp: i64,
+    /// None for prompts recorded before usage was tracked, or still in flight
+    pub usage: Option<PromptUsage>,
+    /// Working directory of the run, when the run is still recorded
+    #[serde(default)]
+    pub working_dir: Option<String>,
+}
+
+/// One prompt-response cycle of an agent: the prompt and everything it produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTurn {
+    pub turn_index: i64,
+    pub prompt: String,
+    pub started_at: i64,
+    /// When the turn ended (end_turn / Stop), None while in flight or for
+    /// turns recorded before turn ends were tracked
+    pub ended_at: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub outputs: Vec<AgentOutputRecord>,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub usage: Option<PromptUsage>,
+}
+
 /// Cost summary across all agent runs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CostSummary {
@@ -106,6 +313,11 @@ pub struct CostSummary {
     pub session_records: Vec<SessionCostRecord>,
     pub cost_by_model: HashMap<String, f64>,
     pub cost_by_working_dir: HashMap<String, f64>,
+    #[serde(default)]
+    pub cost_by_workspace: HashMap<String, f64>,
+    /// Most expensive individual prompts, highest cost first
+    #[serde(default)]
+    pub top_prompts: Vec<PromptCostRecord>,
 }
 
 /// Individual session cost record for display
@@ -121,6 +333,13 @@ pub struct SessionCostRecord {
     pub total_prompts: u32,
     pub total_tool_calls: u32,
     pub model_usage: Option<HashMap<String, ModelCostBreakdown>>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Run is in the trash (still counted, since the cost was incurred)
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default = "default_workspace_id")]
+    pub workspace_id: String,
 }
 
 /// Date range cost summary
@@ -141,6 +360,224 @@ pub struct DailyCost {
     pub session_count: usize,
 }
 
+/// Work handed to a worker agent instead of a metered API call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOffloadRecord {
+    pub id: Option<i64>,
+    /// What was offloaded, e.g. "agent_output" or "final_summary"
+    pub kind: String,
+    /// Estimated API tokens that weren't sent
+    pub tokens_avoided: i64,
+    pub usd_avoided: f64,
+    pub created_at: i64,
+}
+
+/// Offloaded tasks per day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyOffloads {
+    pub date: String,
+    pub task_count: usize,
+    pub tokens_avoided: i64,
+    pub usd_avoided: f64,
+}
+
+/// A hook event received from an agent's Claude CLI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookEventRecord {
+    pub id: Option<i64>,
+    /// None when the event couldn't be matched to an agent
+    pub agent_id: Option<String>,
+    pub session_id: String,
+    /// "PreToolUse", "PostToolUse", "Stop", ...
+    pub event_name: String,
+    pub tool_name: Option<String>,
+    /// How the agent was found: "session" (session map), "agent_id" (query
+    /// parameter, before the session was mapped) or "unmatched"
+    pub matched_by: String,
+    /// Time the hook server spent handling the event
+    pub latency_ms: i64,
+    pub created_at: i64,
+}
+
+/// Totals over the retained hook event log
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HookEventTotals {
+    pub events_received: usize,
+    pub unmatched_session_events: usize,
+    pub last_event_at: Option<i64>,
+}
+
+/// Cost of one prompt, at the time its turn ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptSpend {
+    pub agent_id: String,
+    pub label: Option<String>,
+    pub timestamp: i64,
+    pub cost_usd: f64,
+}
+
+/// Activity aggregated over one day or week
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UsageTrendBucket {
+    /// First day of the bucket (YYYY-MM-DD, UTC)
+    pub bucket_start: String,
+    pub total_runs: i64,
+    pub runs_by_status: Vec<(String, i64)>,
+    /// Completed runs / finished runs (None when nothing finished)
+    pub success_rate: Option<f64>,
+    pub total_cost_usd: f64,
+    pub total_tokens: i64,
+    pub pipelines_completed: i64,
+    pub pipelines_failed: i64,
+    pub pipelines_gave_up: i64,
+    pub avg_pipeline_iterations: Option<f64>,
+}
+
+/// Usage trends over a date range, one entry per bucket (including empty ones)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageTrends {
+    pub bucket: String,
+    pub days: i64,
+    pub buckets: Vec<UsageTrendBucket>,
+    /// Most expensive individual prompts in the range, highest cost first
+    #[serde(default)]
+    pub top_prompts: Vec<PromptCostRecord>,
+}
+
+/// A run or conversation in the trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: String,
+    pub title: String,
+    pub deleted_at: i64,
+    /// Cost of a trashed run (None for conversations)
+    pub cost_usd: Option<f64>,
+}
+
+/// Rows permanently removed when the trash is emptied
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrashPurgeResult {
+    pub runs: usize,
+    pub conversations: usize,
+}
+
+/// A workspace with rollups of what it owns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceInfo {
+    pub workspace_id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub last_active_at: i64,
+    pub conversation_count: u32,
+    pub run_count: u32,
+    pub total_cost_usd: f64,
+    /// Whether this is the active workspace
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// A saved prompt from the prompt library
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPromptTemplate {
+    pub name: String,
+    pub content: String,
+    /// `{{variable}}` placeholders in the content, in order of first use
+    pub variables: Vec<String>,
+    /// How many prompts have been sent from it
+    pub use_count: u32,
+    pub last_used_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// The Claude CLI's `--permission-mode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    Default,
+    AcceptEdits,
+    Plan,
+    #[default]
+    BypassPermissions,
+}
+
+impl PermissionMode {
+    /// The value passed to `--permission-mode`
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::Plan => "plan",
+            PermissionMode::BypassPermissions => "bypassPermissions",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "default" => Some(PermissionMode::Default),
+            "acceptEdits" => Some(PermissionMode::AcceptEdits),
+            "plan" => Some(PermissionMode::Plan),
+            "bypassPermissions" => Some(PermissionMode::BypassPermissions),
+            _ => None,
+        }
+    }
+}
+
+/// What happens when an agent runs a command through the sudo wrapper
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevatedCommandPolicy {
+    /// Ask the user to approve each request
+    #[default]
+    Ask,
+    /// Deny every request without asking
+    Deny,
+}
+
+impl ElevatedCommandPolicy {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ElevatedCommandPolicy::Ask => "ask",
+            ElevatedCommandPolicy::Deny => "deny",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ask" => Some(ElevatedCommandPolicy::Ask),
+            "deny" => Some(ElevatedCommandPolicy::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// A named permission policy, applied to agents spawned from its sources
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PermissionProfile {
+    pub name: String,
+    /// Agent sources spawned under this profile; a source has at most one
+    #[serde(default)]
+    pub sources: Vec<AgentSource>,
+    /// Tools the agent may use (`--allowedTools`); empty allows any
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Tools the agent may not use (`--disallowedTools`)
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+    #[serde(default)]
+    pub permission_mode: PermissionMode,
+    #[serde(default)]
+    pub elevated_commands: ElevatedCommandPolicy,
+    /// Whether the security monitor should expect network access from the start
+    #[serde(default)]
+    pub network_expected: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
 /// Statistics about all runs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RunStats {
@@ -161,6 +598,8 @@ pub struct DatabaseStats {
     pub runs_by_status: Vec<(String, i64)>,
     pub runs_by_source: Vec<(String, i64)>,
     pub total_cost_usd: f64,
+    /// Failed writes waiting in the dead-letter queue
+    pub dead_letter_count: usize,
 }
 
 /// Format bytes into human readable size
@@ -236,6 +675,9 @@ pub struct AgentOutputRecord {
     pub content: String,
     pub metadata: Option<String>, // JSON serialized
     pub timestamp: i64,
+    /// Turn of the prompt this output answers; stamped on insert when None
+    #[serde(default)]
+    pub turn_index: Option<i64>,
 }
 
 /// Query filters for orchestrator events
@@ -246,7 +688,66 @@ pub struct EventQueryFilters {
     pub since_timestamp: Option<i64>,
     pub until_timestamp: Option<i64>,
     pub limit: Option<usize>,
+    /// Deprecated: offsets skip or repeat events inserted between pages, use `before`
     pub offset: Option<usize>,
+    /// Only return events ordered after this `(timestamp, id)` cursor
+    pub before: Option<PageCursor>,
+}
+
+/// Snapshot of a pipeline's shared scratchpad, taken after a step finished
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchpadVersionRecord {
+    pub id: Option<i64>,
+    pub pipeline_id: String,
+    pub iteration: u32,
+    pub step_number: Option<u32>, // 1=Planning, 2=Building, 3=Verifying
+    pub content: String,
+    pub timestamp: i64,
+}
+
+/// A pipeline paused on a question for the user, saved so it survives a restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineHumanRequestRecord {
+    pub pipeline_id: String,
+    pub question: String,
+    /// The pipeline as JSON, at the time it paused
+    pub pipeline: String,
+    /// The orchestrator's conversation and progress as JSON
+    pub orchestrator: String,
+    pub requested_at: i64,
+}
+
+/// A finished pipeline evicted from memory, kept so it can still be listed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineSnapshotRecord {
+    pub pipeline_id: String,
+    pub status: String,
+    /// The pipeline as JSON, as it was when evicted
+    pub pipeline: String,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub evicted_at: i64,
+}
+
+/// A skill, subagent or CLAUDE.md as it was generated for a pipeline or agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedArtifactRecord {
+    pub id: Option<i64>,
+    pub pipeline_id: Option<String>,
+    pub agent_id: Option<String>,
+    pub kind: String, // "skill", "subagent" or "claude_md"
+    pub name: String,
+    pub path: String,
+    /// Left out of listings; fetched with get_artifact_content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    pub content_hash: String,
+    pub size_bytes: i64,
+    /// Pipeline state the artifact was generated in
+    pub pipeline_state: Option<String>,
+    pub created_at: i64,
+    /// Set when cleanup removed the files; the record itself is kept
+    pub removed_at: Option<i64>,
 }
 
 /// Bundle of all pipeline history for restoring UI state
@@ -255,6 +756,9 @@ pub struct PipelineHistoryBundle {
     pub tool_calls: Vec<OrchestratorToolCallRecord>,
     pub state_changes: Vec<OrchestratorStateChangeRecord>,
     pub decisions: Vec<OrchestratorDecisionRecord>,
+    /// Scratchpad snapshots, oldest first
+    #[serde(default)]
+    pub scratchpad_versions: Vec<ScratchpadVersionRecord>,
 }
 
 // ============================================================================
@@ -272,6 +776,20 @@ pub struct MetaConversationRecord {
     pub message_count: u32,
     pub is_archived: bool,
     pub preview_text: Option<String>, // First ~100 chars for list view
+    /// Set when the user renamed the conversation; generated titles never replace it
+    #[serde(default)]
+    pub title_locked: bool,
+    /// Generated paragraph summary (see summarize_conversation)
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Conversation this one was forked from
+    #[serde(default)]
+    pub forked_from: Option<String>,
+    /// Position (0-based) of the last message copied from `forked_from`
+    #[serde(default)]
+    pub forked_at_message: Option<u32>,
+    #[serde(default = "default_workspace_id")]
+    pub workspace_id: String,
 }
 
 /// Record of a single message in a meta agent conversation
@@ -285,6 +803,115 @@ pub struct MetaMessageRecord {
     pub image_data: Option<String>, // JSON if has image
     pub tool_calls: Option<String>, // JSON array
     pub timestamp: i64,             // Unix timestamp in milliseconds
+    /// Model that produced an assistant message
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Commander personality settings hash ("default" without a personality)
+    /// in effect for an assistant message
+    #[serde(default)]
+    pub personality_hash: Option<String>,
+    /// User feedback on an assistant message (see rate_message)
+    #[serde(default)]
+    pub rating: Option<MessageRating>,
+    #[serde(default)]
+    pub rating_note: Option<String>,
+    #[serde(default)]
+    pub rated_at: Option<i64>,
+}
+
+/// Thumbs up/down given to a commander response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRating {
+    Up,
+    Down,
+}
+
+impl MessageRating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageRating::Up => "up",
+            MessageRating::Down => "down",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "up" => Some(MessageRating::Up),
+            "down" => Some(MessageRating::Down),
+            _ => None,
+        }
+    }
+}
+
+/// Ratings given to responses from one personality and model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackGroup {
+    /// None for responses persisted before ratings were introduced
+    pub personality_hash: Option<String>,
+    pub model: Option<String>,
+    pub up: u32,
+    pub down: u32,
+}
+
+/// Ratings over the last `days` days, grouped by personality and model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackSummary {
+    pub days: i64,
+    pub total_up: u32,
+    pub total_down: u32,
+    pub groups: Vec<FeedbackGroup>,
+}
+
+/// A rated response with what led up to it, for studying failure patterns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatedMessageContext {
+    pub message: MetaMessageRecord,
+    /// The user message this response answered
+    pub previous_user_message: Option<String>,
+    /// Tools the commander called between that user message and the response
+    pub tools_used: Vec<String>,
+}
+
+/// A commander conversation message whose text matched a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaMessageMatch {
+    pub conversation_id: String,
+    pub conversation_title: Option<String>,
+    pub message_index: u32,
+    pub role: String,
+    pub content: String,
+    pub timestamp: i64,
+}
+
+/// A compaction of a commander conversation: the summary that replaced a
+/// range of messages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactionRecord {
+    pub id: Option<i64>,
+    pub conversation_id: String,
+    pub summary: String,
+    /// First and last replaced message (message_index, inclusive)
+    pub first_message_index: u32,
+    pub last_message_index: u32,
+    /// Estimated context tokens before and after
+    pub tokens_before: u32,
+    pub tokens_after: u32,
+    pub emergency: bool,
+    /// Replaced messages carried over verbatim because they were pinned or
+    /// looked like a constraint
+    pub preserved_message_indexes: Vec<u32>,
+    pub created_at: i64,
+}
+
+/// A user's pin on a commander conversation message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessagePin {
+    pub message_index: u32,
+    /// False when the user unpinned a message that would otherwise be kept
+    pub pinned: bool,
+    pub role: String,
+    pub content: String,
 }
 
 /// Query filters for listing conversations
@@ -292,6 +919,8 @@ pub struct MetaMessageRecord {
 pub struct ConversationQueryFilters {
     pub include_archived: bool,
     pub search_text: Option<String>,
+    /// Only conversations in this workspace (all workspaces when None)
+    pub workspace_id: Option<String>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
 }
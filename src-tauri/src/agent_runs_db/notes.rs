@@ -0,0 +1,146 @@
+// Agent note persistence
+//
+// Notes are short free-form annotations ("exploring approach B") pinned to a
+// run by the user or the meta agent. They outlive the in-memory agent so they
+// show up in history, exports and search.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+use crate::types::{AgentNote, NoteAuthor};
+
+/// Helper to convert a row to AgentNote
+fn row_to_note(row: &rusqlite::Row) -> SqliteResult<AgentNote> {
+    let author: String = row.get(2)?;
+    Ok(AgentNote {
+        id: Some(row.get(0)?),
+        agent_id: row.get(1)?,
+        author: NoteAuthor::parse(&author),
+        text: row.get(3)?,
+        timestamp: row.get(4)?,
+    })
+}
+
+/// Operations for agent notes
+pub struct AgentNoteOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> AgentNoteOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert a note and return its row ID
+    pub async fn add_note(&self, note: &AgentNote) -> SqliteResult<i64> {
+        let note = note.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO agent_notes (agent_id, author, text, timestamp) VALUES (?1, ?2, ?3, ?4)",
+                    params![note.agent_id, note.author.as_str(), note.text, note.timestamp],
+                )?;
+                Ok(db.last_insert_rowid())
+            })
+            .await
+    }
+
+    /// Get all notes for an agent, oldest first
+    pub async fn get_notes(&self, agent_id: &str) -> SqliteResult<Vec<AgentNote>> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT id, agent_id, author, text, timestamp FROM agent_notes
+                     WHERE agent_id = ?1 ORDER BY timestamp ASC, id ASC",
+                )?;
+                let notes = stmt.query_map(params![agent_id], row_to_note)?;
+                notes.collect()
+            })
+            .await
+    }
+
+    /// Find notes containing a keyword (case-insensitive), newest first
+    pub async fn search_notes(&self, keyword: &str, limit: usize) -> SqliteResult<Vec<AgentNote>> {
+        let pattern = format!("%{}%", keyword.to_lowercase());
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT id, agent_id, author, text, timestamp FROM agent_notes
+                     WHERE LOWER(text) LIKE ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2",
+                )?;
+                let notes = stmt.query_map(params![pattern, limit as i64], row_to_note)?;
+                notes.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(agent_id: &str, author: NoteAuthor, text: &str, timestamp: i64) -> AgentNote {
+        AgentNote {
+            id: None,
+            agent_id: agent_id.to_string(),
+            author,
+            text: text.to_string(),
+            timestamp,
+        }
+    }
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_agent_notes_table(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_notes_round_trip_in_order() {
+        let db = test_db();
+        let ops = AgentNoteOps::new(&db);
+        ops.add_note(&note("a1", NoteAuthor::User, "exploring approach B", 20))
+            .await
+            .unwrap();
+        ops.add_note(&note("a1", NoteAuthor::Meta, "blocked on tests", 10))
+            .await
+            .unwrap();
+        ops.add_note(&note("a2", NoteAuthor::User, "other agent", 5))
+            .await
+            .unwrap();
+
+        let notes = ops.get_notes("a1").await.unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].author, NoteAuthor::Meta);
+        assert_eq!(notes[1].text, "exploring approach B");
+        assert!(notes[1].id.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_search_notes_is_case_insensitive() {
+        let db = test_db();
+        let ops = AgentNoteOps::new(&db);
+        ops.add_note(&note(
+            "a1",
+            NoteAuthor::User,
+            "Approach B looks promising",
+            1,
+        ))
+        .await
+        .unwrap();
+        ops.add_note(&note("a2", NoteAuthor::Meta, "approach A abandoned", 2))
+            .await
+            .unwrap();
+
+        let found = ops.search_notes("APPROACH", 10).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].agent_id, "a2");
+        assert_eq!(ops.search_notes("approach b", 10).await.unwrap().len(), 1);
+    }
+}
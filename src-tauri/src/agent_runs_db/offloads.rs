@@ -0,0 +1,117 @@
+// Task offload persistence
+//
+// One row per task that was handed to a worker agent (running on the Claude
+// subscription) instead of being done with a metered API call, with an
+// estimate of what the API call would have cost. See cost_preference.rs.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::{DailyOffloads, TaskOffloadRecord};
+
+/// Operations for offloaded tasks
+pub struct OffloadOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> OffloadOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert an offloaded task and return its row ID
+    pub async fn insert_offload(&self, record: &TaskOffloadRecord) -> SqliteResult<i64> {
+        let record = record.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO task_offloads (kind, tokens_avoided, usd_avoided, created_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        record.kind,
+                        record.tokens_avoided,
+                        record.usd_avoided,
+                        record.created_at
+                    ],
+                )?;
+                Ok(db.last_insert_rowid())
+            })
+            .await
+    }
+
+    /// Offload totals per day over the last `days` days, newest first
+    pub async fn get_daily_offloads(&self, days: i64) -> SqliteResult<Vec<DailyOffloads>> {
+        let cutoff = chrono::Utc::now().timestamp_millis() - days * 24 * 60 * 60 * 1000;
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT DATE(created_at / 1000, 'unixepoch') AS date,
+                            COUNT(*), SUM(tokens_avoided), SUM(usd_avoided)
+                     FROM task_offloads
+                     WHERE created_at >= ?1
+                     GROUP BY date
+                     ORDER BY date DESC",
+                )?;
+                let rows = stmt.query_map(params![cutoff], |row| {
+                    Ok(DailyOffloads {
+                        date: row.get(0)?,
+                        task_count: row.get(1)?,
+                        tokens_avoided: row.get(2)?,
+                        usd_avoided: row.get(3)?,
+                    })
+                })?;
+                rows.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offload(kind: &str, tokens: i64, created_at: i64) -> TaskOffloadRecord {
+        TaskOffloadRecord {
+            id: None,
+            kind: kind.to_string(),
+            tokens_avoided: tokens,
+            usd_avoided: tokens as f64 * 3.0 / 1_000_000.0,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_daily_offloads_group_by_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_task_offloads_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = OffloadOps::new(&db);
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let day_ms = 24 * 60 * 60 * 1000;
+        ops.insert_offload(&offload("agent_output", 1_000_000, now))
+            .await
+            .unwrap();
+        ops.insert_offload(&offload("final_summary", 500_000, now))
+            .await
+            .unwrap();
+        ops.insert_offload(&offload("agent_output", 10, now - 2 * day_ms))
+            .await
+            .unwrap();
+        ops.insert_offload(&offload("agent_output", 10, now - 30 * day_ms))
+            .await
+            .unwrap();
+
+        let days = ops.get_daily_offloads(7).await.unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].task_count, 2);
+        assert_eq!(days[0].tokens_avoided, 1_500_000);
+        assert!((days[0].usd_avoided - 4.5).abs() < 1e-9);
+        assert_eq!(days[1].task_count, 1);
+    }
+}
@@ -5,15 +5,155 @@
 // - State changes
 // - Decisions
 // - Agent outputs
+//
+// Each kind has a single-record insert and a batched variant that writes a
+// slice of records in one transaction (used by the event buffer).
 
 use rusqlite::{params, Connection, Result as SqliteResult};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::db_utils::{run_blocking, with_transaction, DatabaseOps};
+
+use super::event_buffer::PendingEvents;
 use super::models::{
     AgentOutputRecord, EventQueryFilters, OrchestratorDecisionRecord,
     OrchestratorStateChangeRecord, OrchestratorToolCallRecord, PipelineHistoryBundle,
 };
+use super::scratchpads::ScratchpadOps;
+
+/// Append the `before` cursor condition, newest-first ordering and limit/offset
+///
+/// `id` breaks timestamp ties so keyset pages never overlap or skip rows.
+fn push_paging(
+    query: &mut String,
+    params: &mut Vec<Box<dyn rusqlite::ToSql>>,
+    filters: &EventQueryFilters,
+) {
+    if let Some(cursor) = filters.before {
+        query.push_str(" AND (timestamp < ? OR (timestamp = ? AND id < ?))");
+        params.push(Box::new(cursor.timestamp));
+        params.push(Box::new(cursor.timestamp));
+        params.push(Box::new(cursor.id));
+    }
+
+    query.push_str(" ORDER BY timestamp DESC, id DESC");
+
+    if let Some(limit) = filters.limit {
+        query.push_str(&format!(" LIMIT {}", limit));
+    }
+    // Deprecated offset path, kept for existing callers
+    if let Some(offset) = filters.offset {
+        query.push_str(&format!(" OFFSET {}", offset));
+    }
+}
+
+fn insert_tool_call_row(db: &Connection, record: &OrchestratorToolCallRecord) -> SqliteResult<()> {
+    db.prepare_cached(
+        "INSERT INTO orchestrator_tool_calls
+         (pipeline_id, agent_id, tool_name, tool_input, is_error, summary,
+          current_state, iteration, step_number, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )?
+    .execute(params![
+        record.pipeline_id,
+        record.agent_id,
+        record.tool_name,
+        record.tool_input,
+        if record.is_error { 1 } else { 0 },
+        record.summary,
+        record.current_state,
+        record.iteration,
+        record.step_number,
+        record.timestamp
+    ])?;
+    Ok(())
+}
+
+fn insert_state_change_row(
+    db: &Connection,
+    record: &OrchestratorStateChangeRecord,
+) -> SqliteResult<()> {
+    db.prepare_cached(
+        "INSERT INTO orchestrator_state_changes
+         (pipeline_id, old_state, new_state, iteration, generated_skills,
+          generated_subagents, claudemd_generated, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?
+    .execute(params![
+        record.pipeline_id,
+        record.old_state,
+        record.new_state,
+        record.iteration,
+        record.generated_skills,
+        record.generated_subagents,
+        if record.claudemd_generated { 1 } else { 0 },
+        record.timestamp
+    ])?;
+    Ok(())
+}
+
+fn insert_decision_row(db: &Connection, record: &OrchestratorDecisionRecord) -> SqliteResult<()> {
+    let issues_json = serde_json::to_string(&record.issues).unwrap_or_else(|_| "[]".to_string());
+    let suggestions_json =
+        serde_json::to_string(&record.suggestions).unwrap_or_else(|_| "[]".to_string());
+
+    db.prepare_cached(
+        "INSERT INTO orchestrator_decisions
+         (pipeline_id, decision, reasoning, issues, suggestions, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?
+    .execute(params![
+        record.pipeline_id,
+        record.decision,
+        record.reasoning,
+        issues_json,
+        suggestions_json,
+        record.timestamp
+    ])?;
+    Ok(())
+}
+
+fn insert_agent_output_row(db: &Connection, record: &AgentOutputRecord) -> SqliteResult<()> {
+    db.prepare_cached(
+        "INSERT INTO agent_outputs
+         (agent_id, pipeline_id, output_type, content, metadata, timestamp, turn_index)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, COALESCE(?7,
+             (SELECT MAX(turn_index) FROM agent_prompts WHERE agent_id = ?1 AND timestamp <= ?6)))",
+    )?
+    .execute(params![
+        record.agent_id,
+        record.pipeline_id,
+        record.output_type,
+        record.content,
+        record.metadata,
+        record.timestamp,
+        record.turn_index
+    ])?;
+    Ok(())
+}
+
+/// Shortest query the trigram index can match
+pub const MIN_SEARCH_CHARS: usize = 3;
+
+/// `query` as a single FTS5 phrase, so operators and punctuation in it are
+/// matched literally
+fn fts_phrase(query: &str) -> String {
+    format!("\"{}\"", query.replace('"', "\"\""))
+}
+
+fn row_to_agent_output(row: &rusqlite::Row) -> SqliteResult<AgentOutputRecord> {
+    Ok(AgentOutputRecord {
+        id: Some(row.get(0)?),
+        agent_id: row.get(1)?,
+        pipeline_id: row.get(2)?,
+        output_type: row.get(3)?,
+        content: row.get(4)?,
+        metadata: row.get(5)?,
+        timestamp: row.get(6)?,
+        turn_index: row.get(7)?,
+    })
+}
 
 /// Operations for orchestrator event persistence
 pub struct OrchestratorEventOps<'a> {
@@ -28,27 +168,10 @@ impl<'a> OrchestratorEventOps<'a> {
     /// Insert a single orchestrator tool call record
     pub async fn insert_tool_call(&self, record: &OrchestratorToolCallRecord) -> SqliteResult<i64> {
         let db = self.db.lock().await;
-
-        db.execute(
-            "INSERT INTO orchestrator_tool_calls
-             (pipeline_id, agent_id, tool_name, tool_input, is_error, summary,
-              current_state, iteration, step_number, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                record.pipeline_id,
-                record.agent_id,
-                record.tool_name,
-                record.tool_input,
-                if record.is_error { 1 } else { 0 },
-                record.summary,
-                record.current_state,
-                record.iteration,
-                record.step_number,
-                record.timestamp
-            ],
-        )?;
-
-        Ok(db.last_insert_rowid())
+        run_blocking(|| {
+            insert_tool_call_row(&db, record)?;
+            Ok(db.last_insert_rowid())
+        })
     }
 
     /// Insert a single orchestrator state change record
@@ -57,134 +180,162 @@ impl<'a> OrchestratorEventOps<'a> {
         record: &OrchestratorStateChangeRecord,
     ) -> SqliteResult<i64> {
         let db = self.db.lock().await;
-
-        db.execute(
-            "INSERT INTO orchestrator_state_changes
-             (pipeline_id, old_state, new_state, iteration, generated_skills,
-              generated_subagents, claudemd_generated, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                record.pipeline_id,
-                record.old_state,
-                record.new_state,
-                record.iteration,
-                record.generated_skills,
-                record.generated_subagents,
-                if record.claudemd_generated { 1 } else { 0 },
-                record.timestamp
-            ],
-        )?;
-
-        Ok(db.last_insert_rowid())
+        run_blocking(|| {
+            insert_state_change_row(&db, record)?;
+            Ok(db.last_insert_rowid())
+        })
     }
 
     /// Insert a single orchestrator decision record
     pub async fn insert_decision(&self, record: &OrchestratorDecisionRecord) -> SqliteResult<i64> {
         let db = self.db.lock().await;
-
-        let issues_json =
-            serde_json::to_string(&record.issues).unwrap_or_else(|_| "[]".to_string());
-        let suggestions_json =
-            serde_json::to_string(&record.suggestions).unwrap_or_else(|_| "[]".to_string());
-
-        db.execute(
-            "INSERT INTO orchestrator_decisions
-             (pipeline_id, decision, reasoning, issues, suggestions, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                record.pipeline_id,
-                record.decision,
-                record.reasoning,
-                issues_json,
-                suggestions_json,
-                record.timestamp
-            ],
-        )?;
-
-        Ok(db.last_insert_rowid())
+        run_blocking(|| {
+            insert_decision_row(&db, record)?;
+            Ok(db.last_insert_rowid())
+        })
     }
 
     /// Insert a single agent output record
     pub async fn insert_agent_output(&self, record: &AgentOutputRecord) -> SqliteResult<i64> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            insert_agent_output_row(&db, record)?;
+            Ok(db.last_insert_rowid())
+        })
+    }
 
-        db.execute(
-            "INSERT INTO agent_outputs
-             (agent_id, pipeline_id, output_type, content, metadata, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                record.agent_id,
-                record.pipeline_id,
-                record.output_type,
-                record.content,
-                record.metadata,
-                record.timestamp
-            ],
-        )?;
-
-        Ok(db.last_insert_rowid())
+    /// Insert orchestrator tool calls in a single transaction
+    pub async fn insert_tool_calls(
+        &self,
+        records: &[OrchestratorToolCallRecord],
+    ) -> SqliteResult<usize> {
+        self.insert_batch(records, insert_tool_call_row).await
     }
 
-    /// Query orchestrator tool calls with filters
-    pub async fn query_tool_calls(
+    /// Insert orchestrator state changes in a single transaction
+    pub async fn insert_state_changes(
         &self,
-        filters: EventQueryFilters,
-    ) -> SqliteResult<Vec<OrchestratorToolCallRecord>> {
-        let db = self.db.lock().await;
+        records: &[OrchestratorStateChangeRecord],
+    ) -> SqliteResult<usize> {
+        self.insert_batch(records, insert_state_change_row).await
+    }
 
-        let mut query = "SELECT id, pipeline_id, agent_id, tool_name, tool_input, is_error,
-                                summary, current_state, iteration, step_number, timestamp
-                         FROM orchestrator_tool_calls WHERE 1=1"
-            .to_string();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    /// Insert orchestrator decisions in a single transaction
+    pub async fn insert_decisions(
+        &self,
+        records: &[OrchestratorDecisionRecord],
+    ) -> SqliteResult<usize> {
+        self.insert_batch(records, insert_decision_row).await
+    }
 
-        if let Some(ref pipeline_id) = filters.pipeline_id {
-            query.push_str(" AND pipeline_id = ?");
-            params.push(Box::new(pipeline_id.clone()));
-        }
-        if let Some(ref agent_id) = filters.agent_id {
-            query.push_str(" AND agent_id = ?");
-            params.push(Box::new(agent_id.clone()));
-        }
-        if let Some(since) = filters.since_timestamp {
-            query.push_str(" AND timestamp >= ?");
-            params.push(Box::new(since));
-        }
-        if let Some(until) = filters.until_timestamp {
-            query.push_str(" AND timestamp <= ?");
-            params.push(Box::new(until));
+    /// Insert agent outputs in a single transaction
+    pub async fn insert_agent_outputs(&self, records: &[AgentOutputRecord]) -> SqliteResult<usize> {
+        self.insert_batch(records, insert_agent_output_row).await
+    }
+
+    /// Write buffered records of every kind in a single transaction
+    pub async fn insert_pending_events(&self, events: &PendingEvents) -> SqliteResult<usize> {
+        if events.is_empty() {
+            return Ok(0);
         }
 
-        query.push_str(" ORDER BY timestamp DESC");
+        self.db
+            .with_db_mut(|conn| {
+                with_transaction(conn, |tx| {
+                    for record in &events.tool_calls {
+                        insert_tool_call_row(tx, record)?;
+                    }
+                    for record in &events.state_changes {
+                        insert_state_change_row(tx, record)?;
+                    }
+                    for record in &events.decisions {
+                        insert_decision_row(tx, record)?;
+                    }
+                    for record in &events.agent_outputs {
+                        insert_agent_output_row(tx, record)?;
+                    }
+                    Ok(events.len())
+                })
+            })
+            .await
+    }
 
-        if let Some(limit) = filters.limit {
-            query.push_str(&format!(" LIMIT {}", limit));
-        }
-        if let Some(offset) = filters.offset {
-            query.push_str(&format!(" OFFSET {}", offset));
+    /// Take the lock once and insert every record in one transaction
+    async fn insert_batch<T: Sync>(
+        &self,
+        records: &[T],
+        insert_row: fn(&Connection, &T) -> SqliteResult<()>,
+    ) -> SqliteResult<usize> {
+        if records.is_empty() {
+            return Ok(0);
         }
 
-        let mut stmt = db.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            let is_error_int: i32 = row.get(5)?;
-            Ok(OrchestratorToolCallRecord {
-                id: Some(row.get(0)?),
-                pipeline_id: row.get(1)?,
-                agent_id: row.get(2)?,
-                tool_name: row.get(3)?,
-                tool_input: row.get(4)?,
-                is_error: is_error_int != 0,
-                summary: row.get(6)?,
-                current_state: row.get(7)?,
-                iteration: row.get(8)?,
-                step_number: row.get(9)?,
-                timestamp: row.get(10)?,
+        self.db
+            .with_db_mut(|conn| {
+                with_transaction(conn, |tx| {
+                    for record in records {
+                        insert_row(tx, record)?;
+                    }
+                    Ok(records.len())
+                })
             })
-        })?;
+            .await
+    }
 
-        rows.collect()
+    /// Query orchestrator tool calls with filters
+    pub async fn query_tool_calls(
+        &self,
+        filters: EventQueryFilters,
+    ) -> SqliteResult<Vec<OrchestratorToolCallRecord>> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut query = "SELECT id, pipeline_id, agent_id, tool_name, tool_input, is_error,
+                                    summary, current_state, iteration, step_number, timestamp
+                             FROM orchestrator_tool_calls WHERE 1=1"
+                .to_string();
+            let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if let Some(ref pipeline_id) = filters.pipeline_id {
+                query.push_str(" AND pipeline_id = ?");
+                params.push(Box::new(pipeline_id.clone()));
+            }
+            if let Some(ref agent_id) = filters.agent_id {
+                query.push_str(" AND agent_id = ?");
+                params.push(Box::new(agent_id.clone()));
+            }
+            if let Some(since) = filters.since_timestamp {
+                query.push_str(" AND timestamp >= ?");
+                params.push(Box::new(since));
+            }
+            if let Some(until) = filters.until_timestamp {
+                query.push_str(" AND timestamp <= ?");
+                params.push(Box::new(until));
+            }
+
+            push_paging(&mut query, &mut params, &filters);
+
+            let mut stmt = db.prepare(&query)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+            let rows = stmt.query_map(param_refs.as_slice(), |row| {
+                let is_error_int: i32 = row.get(5)?;
+                Ok(OrchestratorToolCallRecord {
+                    id: Some(row.get(0)?),
+                    pipeline_id: row.get(1)?,
+                    agent_id: row.get(2)?,
+                    tool_name: row.get(3)?,
+                    tool_input: row.get(4)?,
+                    is_error: is_error_int != 0,
+                    summary: row.get(6)?,
+                    current_state: row.get(7)?,
+                    iteration: row.get(8)?,
+                    step_number: row.get(9)?,
+                    timestamp: row.get(10)?,
+                })
+            })?;
+
+            rows.collect()
+        })
     }
 
     /// Query orchestrator state changes with filters
@@ -193,54 +344,48 @@ impl<'a> OrchestratorEventOps<'a> {
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<OrchestratorStateChangeRecord>> {
         let db = self.db.lock().await;
-
-        let mut query = "SELECT id, pipeline_id, old_state, new_state, iteration,
-                                generated_skills, generated_subagents, claudemd_generated, timestamp
-                         FROM orchestrator_state_changes WHERE 1=1"
-            .to_string();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if let Some(ref pipeline_id) = filters.pipeline_id {
-            query.push_str(" AND pipeline_id = ?");
-            params.push(Box::new(pipeline_id.clone()));
-        }
-        if let Some(since) = filters.since_timestamp {
-            query.push_str(" AND timestamp >= ?");
-            params.push(Box::new(since));
-        }
-        if let Some(until) = filters.until_timestamp {
-            query.push_str(" AND timestamp <= ?");
-            params.push(Box::new(until));
-        }
-
-        query.push_str(" ORDER BY timestamp DESC");
-
-        if let Some(limit) = filters.limit {
-            query.push_str(&format!(" LIMIT {}", limit));
-        }
-        if let Some(offset) = filters.offset {
-            query.push_str(&format!(" OFFSET {}", offset));
-        }
-
-        let mut stmt = db.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            let claudemd_int: i32 = row.get(7)?;
-            Ok(OrchestratorStateChangeRecord {
-                id: Some(row.get(0)?),
-                pipeline_id: row.get(1)?,
-                old_state: row.get(2)?,
-                new_state: row.get(3)?,
-                iteration: row.get(4)?,
-                generated_skills: row.get(5)?,
-                generated_subagents: row.get(6)?,
-                claudemd_generated: claudemd_int != 0,
-                timestamp: row.get(8)?,
-            })
-        })?;
-
-        rows.collect()
+        run_blocking(|| {
+            let mut query = "SELECT id, pipeline_id, old_state, new_state, iteration,
+                                    generated_skills, generated_subagents, claudemd_generated, timestamp
+                             FROM orchestrator_state_changes WHERE 1=1"
+                .to_string();
+            let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if let Some(ref pipeline_id) = filters.pipeline_id {
+                query.push_str(" AND pipeline_id = ?");
+                params.push(Box::new(pipeline_id.clone()));
+            }
+            if let Some(since) = filters.since_timestamp {
+                query.push_str(" AND timestamp >= ?");
+                params.push(Box::new(since));
+            }
+            if let Some(until) = filters.until_timestamp {
+                query.push_str(" AND timestamp <= ?");
+                params.push(Box::new(until));
+            }
+
+            push_paging(&mut query, &mut params, &filters);
+
+            let mut stmt = db.prepare(&query)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+            let rows = stmt.query_map(param_refs.as_slice(), |row| {
+                let claudemd_int: i32 = row.get(7)?;
+                Ok(OrchestratorStateChangeRecord {
+                    id: Some(row.get(0)?),
+                    pipeline_id: row.get(1)?,
+                    old_state: row.get(2)?,
+                    new_state: row.get(3)?,
+                    iteration: row.get(4)?,
+                    generated_skills: row.get(5)?,
+                    generated_subagents: row.get(6)?,
+                    claudemd_generated: claudemd_int != 0,
+                    timestamp: row.get(8)?,
+                })
+            })?;
+
+            rows.collect()
+        })
     }
 
     /// Query orchestrator decisions with filters
@@ -249,57 +394,51 @@ impl<'a> OrchestratorEventOps<'a> {
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<OrchestratorDecisionRecord>> {
         let db = self.db.lock().await;
-
-        let mut query =
-            "SELECT id, pipeline_id, decision, reasoning, issues, suggestions, timestamp
-                         FROM orchestrator_decisions WHERE 1=1"
-                .to_string();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if let Some(ref pipeline_id) = filters.pipeline_id {
-            query.push_str(" AND pipeline_id = ?");
-            params.push(Box::new(pipeline_id.clone()));
-        }
-        if let Some(since) = filters.since_timestamp {
-            query.push_str(" AND timestamp >= ?");
-            params.push(Box::new(since));
-        }
-        if let Some(until) = filters.until_timestamp {
-            query.push_str(" AND timestamp <= ?");
-            params.push(Box::new(until));
-        }
-
-        query.push_str(" ORDER BY timestamp DESC");
-
-        if let Some(limit) = filters.limit {
-            query.push_str(&format!(" LIMIT {}", limit));
-        }
-        if let Some(offset) = filters.offset {
-            query.push_str(&format!(" OFFSET {}", offset));
-        }
-
-        let mut stmt = db.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            let issues_json: String = row.get(4)?;
-            let suggestions_json: String = row.get(5)?;
-            let issues: Vec<String> = serde_json::from_str(&issues_json).unwrap_or_default();
-            let suggestions: Vec<String> =
-                serde_json::from_str(&suggestions_json).unwrap_or_default();
-
-            Ok(OrchestratorDecisionRecord {
-                id: Some(row.get(0)?),
-                pipeline_id: row.get(1)?,
-                decision: row.get(2)?,
-                reasoning: row.get(3)?,
-                issues,
-                suggestions,
-                timestamp: row.get(6)?,
-            })
-        })?;
-
-        rows.collect()
+        run_blocking(|| {
+            let mut query =
+                "SELECT id, pipeline_id, decision, reasoning, issues, suggestions, timestamp
+                             FROM orchestrator_decisions WHERE 1=1"
+                    .to_string();
+            let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if let Some(ref pipeline_id) = filters.pipeline_id {
+                query.push_str(" AND pipeline_id = ?");
+                params.push(Box::new(pipeline_id.clone()));
+            }
+            if let Some(since) = filters.since_timestamp {
+                query.push_str(" AND timestamp >= ?");
+                params.push(Box::new(since));
+            }
+            if let Some(until) = filters.until_timestamp {
+                query.push_str(" AND timestamp <= ?");
+                params.push(Box::new(until));
+            }
+
+            push_paging(&mut query, &mut params, &filters);
+
+            let mut stmt = db.prepare(&query)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+            let rows = stmt.query_map(param_refs.as_slice(), |row| {
+                let issues_json: String = row.get(4)?;
+                let suggestions_json: String = row.get(5)?;
+                let issues: Vec<String> = serde_json::from_str(&issues_json).unwrap_or_default();
+                let suggestions: Vec<String> =
+                    serde_json::from_str(&suggestions_json).unwrap_or_default();
+
+                Ok(OrchestratorDecisionRecord {
+                    id: Some(row.get(0)?),
+                    pipeline_id: row.get(1)?,
+                    decision: row.get(2)?,
+                    reasoning: row.get(3)?,
+                    issues,
+                    suggestions,
+                    timestamp: row.get(6)?,
+                })
+            })?;
+
+            rows.collect()
+        })
     }
 
     /// Query agent outputs with filters
@@ -308,55 +447,73 @@ impl<'a> OrchestratorEventOps<'a> {
         filters: EventQueryFilters,
     ) -> SqliteResult<Vec<AgentOutputRecord>> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut query =
+                "SELECT id, agent_id, pipeline_id, output_type, content, metadata, timestamp, turn_index
+                             FROM agent_outputs WHERE 1=1"
+                    .to_string();
+            let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+            if let Some(ref pipeline_id) = filters.pipeline_id {
+                query.push_str(" AND pipeline_id = ?");
+                params.push(Box::new(pipeline_id.clone()));
+            }
+            if let Some(ref agent_id) = filters.agent_id {
+                query.push_str(" AND agent_id = ?");
+                params.push(Box::new(agent_id.clone()));
+            }
+            if let Some(since) = filters.since_timestamp {
+                query.push_str(" AND timestamp >= ?");
+                params.push(Box::new(since));
+            }
+            if let Some(until) = filters.until_timestamp {
+                query.push_str(" AND timestamp <= ?");
+                params.push(Box::new(until));
+            }
+
+            push_paging(&mut query, &mut params, &filters);
+
+            let mut stmt = db.prepare(&query)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+            let rows = stmt.query_map(param_refs.as_slice(), row_to_agent_output)?;
+
+            rows.collect()
+        })
+    }
 
-        let mut query =
-            "SELECT id, agent_id, pipeline_id, output_type, content, metadata, timestamp
-                         FROM agent_outputs WHERE 1=1"
-                .to_string();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if let Some(ref pipeline_id) = filters.pipeline_id {
-            query.push_str(" AND pipeline_id = ?");
-            params.push(Box::new(pipeline_id.clone()));
-        }
-        if let Some(ref agent_id) = filters.agent_id {
-            query.push_str(" AND agent_id = ?");
-            params.push(Box::new(agent_id.clone()));
-        }
-        if let Some(since) = filters.since_timestamp {
-            query.push_str(" AND timestamp >= ?");
-            params.push(Box::new(since));
-        }
-        if let Some(until) = filters.until_timestamp {
-            query.push_str(" AND timestamp <= ?");
-            params.push(Box::new(until));
-        }
-
-        query.push_str(" ORDER BY timestamp DESC");
-
-        if let Some(limit) = filters.limit {
-            query.push_str(&format!(" LIMIT {}", limit));
-        }
-        if let Some(offset) = filters.offset {
-            query.push_str(&format!(" OFFSET {}", offset));
-        }
-
-        let mut stmt = db.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            Ok(AgentOutputRecord {
-                id: Some(row.get(0)?),
-                agent_id: row.get(1)?,
-                pipeline_id: row.get(2)?,
-                output_type: row.get(3)?,
-                content: row.get(4)?,
-                metadata: row.get(5)?,
-                timestamp: row.get(6)?,
-            })
-        })?;
-
-        rows.collect()
+    /// Agent outputs containing `query` (case-insensitive, at least
+    /// MIN_SEARCH_CHARS characters), newest first, through the full-text index
+    pub async fn search_agent_outputs(
+        &self,
+        query: &str,
+        agent_id: Option<&str>,
+        limit: usize,
+    ) -> SqliteResult<Vec<AgentOutputRecord>> {
+        let db = self.db.lock().await;
+        run_blocking(|| {
+            let mut sql =
+                "SELECT o.id, o.agent_id, o.pipeline_id, o.output_type, o.content, o.metadata,
+                        o.timestamp, o.turn_index
+                 FROM agent_outputs_fts JOIN agent_outputs o ON o.id = agent_outputs_fts.rowid
+                 WHERE agent_outputs_fts MATCH ?"
+                    .to_string();
+            let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(fts_phrase(query))];
+
+            if let Some(agent_id) = agent_id {
+                sql.push_str(" AND o.agent_id = ?");
+                params.push(Box::new(agent_id.to_string()));
+            }
+            sql.push_str(&format!(
+                " ORDER BY o.timestamp DESC, o.id DESC LIMIT {}",
+                limit
+            ));
+
+            let mut stmt = db.prepare(&sql)?;
+            let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+            let rows = stmt.query_map(param_refs.as_slice(), row_to_agent_output)?;
+            rows.collect()
+        })
     }
 
     /// Get all history for a pipeline (for restoring UI state after reload)
@@ -372,35 +529,240 @@ impl<'a> OrchestratorEventOps<'a> {
         let tool_calls = self.query_tool_calls(filters.clone()).await?;
         let state_changes = self.query_state_changes(filters.clone()).await?;
         let decisions = self.query_decisions(filters).await?;
+        let scratchpad_versions = ScratchpadOps::new(self.db)
+            .get_versions(pipeline_id)
+            .await?;
 
         Ok(PipelineHistoryBundle {
             tool_calls,
             state_changes,
             decisions,
+            scratchpad_versions,
         })
     }
 
     /// Clear all orchestrator events for a pipeline
     pub async fn clear_pipeline_events(&self, pipeline_id: &str) -> SqliteResult<()> {
         let db = self.db.lock().await;
+        run_blocking(|| {
+            db.execute(
+                "DELETE FROM orchestrator_tool_calls WHERE pipeline_id = ?1",
+                params![pipeline_id],
+            )?;
+            db.execute(
+                "DELETE FROM orchestrator_state_changes WHERE pipeline_id = ?1",
+                params![pipeline_id],
+            )?;
+            db.execute(
+                "DELETE FROM orchestrator_decisions WHERE pipeline_id = ?1",
+                params![pipeline_id],
+            )?;
+            db.execute(
+                "DELETE FROM agent_outputs WHERE pipeline_id = ?1",
+                params![pipeline_id],
+            )?;
+            db.execute(
+                "DELETE FROM pipeline_scratchpad_versions WHERE pipeline_id = ?1",
+                params![pipeline_id],
+            )?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::models::PageCursor;
+    use crate::agent_runs_db::schema;
+
+    fn tool_call(tool_name: &str, timestamp: i64) -> OrchestratorToolCallRecord {
+        OrchestratorToolCallRecord {
+            id: None,
+            pipeline_id: "p1".to_string(),
+            agent_id: None,
+            tool_name: tool_name.to_string(),
+            tool_input: None,
+            is_error: false,
+            summary: None,
+            current_state: "Building".to_string(),
+            iteration: 1,
+            step_number: None,
+            timestamp,
+        }
+    }
+
+    fn agent_output(agent_id: &str, content: &str, timestamp: i64) -> AgentOutputRecord {
+        AgentOutputRecord {
+            id: None,
+            agent_id: agent_id.to_string(),
+            pipeline_id: Some("p1".to_string()),
+            output_type: "text".to_string(),
+            content: content.to_string(),
+            metadata: None,
+            timestamp,
+            turn_index: None,
+        }
+    }
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_schema(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_batched_inserts_write_every_record() {
+        let db = test_db();
+        let ops = OrchestratorEventOps::new(&db);
+
+        let outputs: Vec<_> = (0..5)
+            .map(|i| agent_output("a1", &i.to_string(), i))
+            .collect();
+        assert_eq!(ops.insert_agent_outputs(&outputs).await.unwrap(), 5);
+        assert_eq!(ops.insert_agent_outputs(&[]).await.unwrap(), 0);
+
+        let pending = PendingEvents {
+            tool_calls: vec![tool_call("a", 10), tool_call("b", 20)],
+            agent_outputs: vec![agent_output("a2", "x", 30)],
+            ..Default::default()
+        };
+        assert_eq!(ops.insert_pending_events(&pending).await.unwrap(), 3);
+
+        let filters = EventQueryFilters {
+            pipeline_id: Some("p1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ops.query_agent_outputs(filters.clone())
+                .await
+                .unwrap()
+                .len(),
+            6
+        );
+        assert_eq!(ops.query_tool_calls(filters).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_writes_nothing() {
+        let db = test_db();
+        let ops = OrchestratorEventOps::new(&db);
+        db.lock()
+            .await
+            .execute_batch(
+                "CREATE TRIGGER reject_bad BEFORE INSERT ON agent_outputs
+                 WHEN NEW.content = 'bad' BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+            )
+            .unwrap();
+
+        let outputs = vec![agent_output("a1", "good", 1), agent_output("a1", "bad", 2)];
+        assert!(ops.insert_agent_outputs(&outputs).await.is_err());
+
+        let stored = ops
+            .query_agent_outputs(EventQueryFilters::default())
+            .await
+            .unwrap();
+        assert!(stored.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_finds_outputs_by_substring() {
+        let db = test_db();
+        let ops = OrchestratorEventOps::new(&db);
+        let outputs = vec![
+            agent_output("a1", "Edited src/Payments.rs", 10),
+            agent_output("a2", "Read payments.rs and \"billing\" notes", 20),
+            agent_output("a2", "Ran cargo test", 30),
+        ];
+        ops.insert_agent_outputs(&outputs).await.unwrap();
+
+        let found = ops
+            .search_agent_outputs("payments.rs", None, 10)
+            .await
+            .unwrap();
+        let agents: Vec<_> = found.iter().map(|o| o.agent_id.as_str()).collect();
+        assert_eq!(agents, vec!["a2", "a1"]);
+
+        let found = ops
+            .search_agent_outputs("payments", Some("a1"), 10)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].timestamp, 10);
+
+        // Quotes and FTS operators are matched literally
+        let found = ops
+            .search_agent_outputs("\"billing\" OR", None, 10)
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+        let found = ops
+            .search_agent_outputs("\"billing\"", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+
+        // Deleted outputs leave the index
+        ops.clear_pipeline_events("p1").await.unwrap();
+        let found = ops.search_agent_outputs("cargo", None, 10).await.unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_outputs_written_before_the_index_are_indexed() {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_schema(&conn).unwrap();
+        // As a database from before the index
+        conn.execute_batch(
+            "DROP TRIGGER agent_outputs_fts_insert;
+             DROP TRIGGER agent_outputs_fts_delete;
+             DROP TRIGGER agent_outputs_fts_update;
+             DROP TABLE agent_outputs_fts;",
+        )
+        .unwrap();
+        insert_agent_output_row(&conn, &agent_output("a1", "panicked at lib.rs", 1)).unwrap();
+
+        schema::initialize_schema(&conn).unwrap();
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM agent_outputs_fts WHERE agent_outputs_fts MATCH '\"panicked\"'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
 
-        db.execute(
-            "DELETE FROM orchestrator_tool_calls WHERE pipeline_id = ?1",
-            params![pipeline_id],
-        )?;
-        db.execute(
-            "DELETE FROM orchestrator_state_changes WHERE pipeline_id = ?1",
-            params![pipeline_id],
-        )?;
-        db.execute(
-            "DELETE FROM orchestrator_decisions WHERE pipeline_id = ?1",
-            params![pipeline_id],
-        )?;
-        db.execute(
-            "DELETE FROM agent_outputs WHERE pipeline_id = ?1",
-            params![pipeline_id],
-        )?;
-
-        Ok(())
+    #[tokio::test]
+    async fn test_tool_call_keyset_pagination_survives_inserts() {
+        let db = test_db();
+        let ops = OrchestratorEventOps::new(&db);
+        for (name, ts) in [("a", 10), ("b", 20), ("c", 20), ("d", 30)] {
+            ops.insert_tool_call(&tool_call(name, ts)).await.unwrap();
+        }
+
+        let filters = |before: Option<PageCursor>| EventQueryFilters {
+            pipeline_id: Some("p1".to_string()),
+            limit: Some(2),
+            before,
+            ..Default::default()
+        };
+
+        let first = ops.query_tool_calls(filters(None)).await.unwrap();
+        let names: Vec<_> = first.iter().map(|t| t.tool_name.as_str()).collect();
+        assert_eq!(names, vec!["d", "c"]);
+
+        // A newer event arrives between pages
+        ops.insert_tool_call(&tool_call("e", 40)).await.unwrap();
+
+        let last = first.last().unwrap();
+        let cursor = PageCursor {
+            timestamp: last.timestamp,
+            id: last.id.unwrap(),
+        };
+        let second = ops.query_tool_calls(filters(Some(cursor))).await.unwrap();
+        let names: Vec<_> = second.iter().map(|t| t.tool_name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a"]);
     }
 }
@@ -0,0 +1,254 @@
+// Permission profile persistence
+//
+// Named tool and command policies, keyed by name (case insensitive). Sources
+// and tool lists are stored as JSON arrays. A source is bound to at most one
+// profile: saving a profile takes its sources away from any other profile.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+use crate::types::AgentSource;
+
+use super::models::{ElevatedCommandPolicy, PermissionMode, PermissionProfile};
+
+const COLUMNS: &str = "name, sources, allowed_tools, disallowed_tools, permission_mode, \
+                       elevated_commands, network_expected, created_at, updated_at";
+
+fn json_list<T: serde::de::DeserializeOwned>(json: &str) -> Vec<T> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn row_to_profile(row: &rusqlite::Row) -> SqliteResult<PermissionProfile> {
+    let sources: String = row.get(1)?;
+    let allowed_tools: String = row.get(2)?;
+    let disallowed_tools: String = row.get(3)?;
+    let permission_mode: String = row.get(4)?;
+    let elevated_commands: String = row.get(5)?;
+    let network_expected: i32 = row.get(6)?;
+    Ok(PermissionProfile {
+        name: row.get(0)?,
+        sources: json_list(&sources),
+        allowed_tools: json_list(&allowed_tools),
+        disallowed_tools: json_list(&disallowed_tools),
+        permission_mode: PermissionMode::parse(&permission_mode).unwrap_or_default(),
+        elevated_commands: ElevatedCommandPolicy::parse(&elevated_commands).unwrap_or_default(),
+        network_expected: network_expected != 0,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn query_profile(db: &Connection, name: &str) -> SqliteResult<Option<PermissionProfile>> {
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM permission_profiles WHERE name = ?1",
+        COLUMNS
+    ))?;
+    let mut rows = stmt.query_map(params![name], row_to_profile)?;
+    rows.next().transpose()
+}
+
+fn query_profiles(db: &Connection) -> SqliteResult<Vec<PermissionProfile>> {
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM permission_profiles ORDER BY name ASC",
+        COLUMNS
+    ))?;
+    let profiles = stmt.query_map([], row_to_profile)?;
+    profiles.collect()
+}
+
+/// Operations for permission profiles
+pub struct PermissionProfileOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> PermissionProfileOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert a profile or replace the one with this name, unbinding its
+    /// sources from every other profile
+    pub async fn save_profile(
+        &self,
+        profile: &PermissionProfile,
+    ) -> SqliteResult<PermissionProfile> {
+        let profile = profile.clone();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db_mut(move |db| {
+                let tx = db.transaction()?;
+                for other in query_profiles(&tx)? {
+                    if other.name.eq_ignore_ascii_case(&profile.name) {
+                        continue;
+                    }
+                    let kept: Vec<&AgentSource> = other
+                        .sources
+                        .iter()
+                        .filter(|source| !profile.sources.contains(source))
+                        .collect();
+                    if kept.len() != other.sources.len() {
+                        tx.execute(
+                            "UPDATE permission_profiles SET sources = ?2, updated_at = ?3
+                             WHERE name = ?1",
+                            params![other.name, to_json(&kept), now],
+                        )?;
+                    }
+                }
+
+                tx.execute(
+                    "INSERT INTO permission_profiles (
+                        name, sources, allowed_tools, disallowed_tools, permission_mode,
+                        elevated_commands, network_expected, created_at, updated_at
+                     ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+                     ON CONFLICT(name) DO UPDATE SET
+                        sources = excluded.sources,
+                        allowed_tools = excluded.allowed_tools,
+                        disallowed_tools = excluded.disallowed_tools,
+                        permission_mode = excluded.permission_mode,
+                        elevated_commands = excluded.elevated_commands,
+                        network_expected = excluded.network_expected,
+                        updated_at = excluded.updated_at",
+                    params![
+                        profile.name,
+                        to_json(&profile.sources),
+                        to_json(&profile.allowed_tools),
+                        to_json(&profile.disallowed_tools),
+                        profile.permission_mode.to_str(),
+                        profile.elevated_commands.to_str(),
+                        if profile.network_expected { 1 } else { 0 },
+                        now,
+                    ],
+                )?;
+                let saved = query_profile(&tx, &profile.name)?
+                    .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+                tx.commit()?;
+                Ok(saved)
+            })
+            .await
+    }
+
+    pub async fn get_profile(&self, name: &str) -> SqliteResult<Option<PermissionProfile>> {
+        let name = name.to_string();
+
+        self.db.with_db(move |db| query_profile(db, &name)).await
+    }
+
+    pub async fn list_profiles(&self) -> SqliteResult<Vec<PermissionProfile>> {
+        self.db.with_db(query_profiles).await
+    }
+
+    /// The profile bound to an agent source, if any
+    pub async fn profile_for_source(
+        &self,
+        source: &AgentSource,
+    ) -> SqliteResult<Option<PermissionProfile>> {
+        let source = source.clone();
+
+        self.db
+            .with_db(move |db| {
+                Ok(query_profiles(db)?
+                    .into_iter()
+                    .find(|profile| profile.sources.contains(&source)))
+            })
+            .await
+    }
+
+    pub async fn delete_profile(&self, name: &str) -> SqliteResult<bool> {
+        let name = name.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let deleted = db.execute(
+                    "DELETE FROM permission_profiles WHERE name = ?1",
+                    params![name],
+                )?;
+                Ok(deleted > 0)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_permission_profiles_table(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    fn profile(name: &str, sources: Vec<AgentSource>) -> PermissionProfile {
+        PermissionProfile {
+            name: name.to_string(),
+            sources,
+            allowed_tools: vec!["Read".to_string(), "Bash(git:*)".to_string()],
+            disallowed_tools: vec!["WebFetch".to_string()],
+            permission_mode: PermissionMode::AcceptEdits,
+            elevated_commands: ElevatedCommandPolicy::Deny,
+            network_expected: false,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_binds_each_source_once() {
+        let db = test_db();
+        let ops = PermissionProfileOps::new(&db);
+        let workers = ops
+            .save_profile(&profile(
+                "workers",
+                vec![AgentSource::Pool, AgentSource::Pipeline],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(workers.allowed_tools, vec!["Read", "Bash(git:*)"]);
+        assert_eq!(workers.permission_mode, PermissionMode::AcceptEdits);
+        assert_eq!(workers.elevated_commands, ElevatedCommandPolicy::Deny);
+
+        // Binding pipeline agents elsewhere takes them from "workers"
+        ops.save_profile(&profile("pipeline", vec![AgentSource::Pipeline]))
+            .await
+            .unwrap();
+        let workers = ops.get_profile("WORKERS").await.unwrap().unwrap();
+        assert_eq!(workers.sources, vec![AgentSource::Pool]);
+        let bound = ops
+            .profile_for_source(&AgentSource::Pipeline)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bound.name, "pipeline");
+        assert!(ops
+            .profile_for_source(&AgentSource::Meta)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Editing keeps the name's case and creation time
+        let mut edited = profile("Workers", vec![AgentSource::Pool]);
+        edited.network_expected = true;
+        let edited = ops.save_profile(&edited).await.unwrap();
+        assert_eq!(edited.name, "workers");
+        assert_eq!(edited.created_at, workers.created_at);
+        assert!(edited.network_expected);
+
+        let names: Vec<_> = ops
+            .list_profiles()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["pipeline", "workers"]);
+        assert!(ops.delete_profile("pipeline").await.unwrap());
+        assert!(!ops.delete_profile("pipeline").await.unwrap());
+    }
+}
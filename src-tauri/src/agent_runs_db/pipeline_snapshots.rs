@@ -0,0 +1,148 @@
+// Finished pipelines evicted from memory
+//
+// The auto-pipeline manager drops finished pipelines from memory once they are
+// older than the retention period (see auto_pipeline/retention.rs). Each is
+// saved here first, so it can still be listed and opened.
+
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::PipelineSnapshotRecord;
+
+const COLUMNS: &str = "pipeline_id, status, pipeline, created_at, completed_at, evicted_at";
+
+fn row_to_snapshot(row: &Row) -> SqliteResult<PipelineSnapshotRecord> {
+    Ok(PipelineSnapshotRecord {
+        pipeline_id: row.get(0)?,
+        status: row.get(1)?,
+        pipeline: row.get(2)?,
+        created_at: row.get(3)?,
+        completed_at: row.get(4)?,
+        evicted_at: row.get(5)?,
+    })
+}
+
+/// Operations for evicted pipelines
+pub struct PipelineSnapshotOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> PipelineSnapshotOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Save an evicted pipeline, replacing an earlier snapshot of it
+    pub async fn save_snapshot(&self, record: &PipelineSnapshotRecord) -> SqliteResult<()> {
+        let record = record.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO pipeline_snapshots ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        COLUMNS
+                    ),
+                    params![
+                        record.pipeline_id,
+                        record.status,
+                        record.pipeline,
+                        record.created_at,
+                        record.completed_at,
+                        record.evicted_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// An evicted pipeline, if it was evicted
+    pub async fn get_snapshot(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Option<PipelineSnapshotRecord>> {
+        let pipeline_id = pipeline_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {} FROM pipeline_snapshots WHERE pipeline_id = ?1",
+                    COLUMNS
+                ))?;
+                let mut rows = stmt.query_map(params![pipeline_id], row_to_snapshot)?;
+                rows.next().transpose()
+            })
+            .await
+    }
+
+    /// Evicted pipelines created at or after `since` (all if None), newest first
+    pub async fn list_snapshots(
+        &self,
+        since: Option<i64>,
+    ) -> SqliteResult<Vec<PipelineSnapshotRecord>> {
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {} FROM pipeline_snapshots WHERE created_at >= ?1
+                     ORDER BY created_at DESC",
+                    COLUMNS
+                ))?;
+                let snapshots = stmt.query_map(params![since.unwrap_or(0)], row_to_snapshot)?;
+                snapshots.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(pipeline_id: &str, status: &str, created_at: i64) -> PipelineSnapshotRecord {
+        PipelineSnapshotRecord {
+            pipeline_id: pipeline_id.to_string(),
+            status: status.to_string(),
+            pipeline: "{}".to_string(),
+            created_at,
+            completed_at: Some(created_at + 5),
+            evicted_at: created_at + 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_are_replaced_and_listed_since() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_pipeline_snapshots_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = PipelineSnapshotOps::new(&db);
+
+        ops.save_snapshot(&snapshot("p1", "completed", 10))
+            .await
+            .unwrap();
+        ops.save_snapshot(&snapshot("p2", "failed", 20))
+            .await
+            .unwrap();
+        ops.save_snapshot(&snapshot("p1", "cancelled", 10))
+            .await
+            .unwrap();
+
+        let all = ops.list_snapshots(None).await.unwrap();
+        let ids: Vec<_> = all.iter().map(|s| s.pipeline_id.as_str()).collect();
+        assert_eq!(ids, vec!["p2", "p1"]);
+        assert_eq!(all[1].status, "cancelled");
+
+        let recent = ops.list_snapshots(Some(15)).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].pipeline_id, "p2");
+
+        assert_eq!(
+            ops.get_snapshot("p2").await.unwrap(),
+            Some(snapshot("p2", "failed", 20))
+        );
+        assert!(ops.get_snapshot("p3").await.unwrap().is_none());
+    }
+}
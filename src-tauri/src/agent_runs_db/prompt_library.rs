@@ -0,0 +1,181 @@
+// Prompt library persistence
+//
+// Saved prompts with `{{variable}}` placeholders, keyed by name (case
+// insensitive). Saving under an existing name replaces the content but keeps
+// the use count, so editing a template doesn't make it look unused.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+use crate::prompt_library::template_variables;
+
+use super::models::SavedPromptTemplate;
+
+const COLUMNS: &str = "name, content, use_count, last_used_at, created_at, updated_at";
+
+fn row_to_template(row: &rusqlite::Row) -> SqliteResult<SavedPromptTemplate> {
+    let content: String = row.get(1)?;
+    Ok(SavedPromptTemplate {
+        name: row.get(0)?,
+        variables: template_variables(&content)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        content,
+        use_count: row.get(2)?,
+        last_used_at: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+fn query_template(db: &Connection, name: &str) -> SqliteResult<Option<SavedPromptTemplate>> {
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM prompt_library WHERE name = ?1",
+        COLUMNS
+    ))?;
+    let mut rows = stmt.query_map(params![name], row_to_template)?;
+    rows.next().transpose()
+}
+
+/// Operations for the prompt library
+pub struct PromptLibraryOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> PromptLibraryOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert a template or replace the content of the one with this name
+    pub async fn save_template(
+        &self,
+        name: &str,
+        content: &str,
+    ) -> SqliteResult<SavedPromptTemplate> {
+        let name = name.to_string();
+        let content = content.to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO prompt_library (name, content, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?3)
+                     ON CONFLICT(name) DO UPDATE SET
+                        content = excluded.content,
+                        updated_at = excluded.updated_at",
+                    params![name, content, now],
+                )?;
+                query_template(db, &name)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+            })
+            .await
+    }
+
+    pub async fn get_template(&self, name: &str) -> SqliteResult<Option<SavedPromptTemplate>> {
+        let name = name.to_string();
+
+        self.db.with_db(move |db| query_template(db, &name)).await
+    }
+
+    /// Templates whose name or content contains the keyword (all when None),
+    /// most used first
+    pub async fn list_templates(
+        &self,
+        keyword: Option<&str>,
+    ) -> SqliteResult<Vec<SavedPromptTemplate>> {
+        let pattern = keyword.map(|k| format!("%{}%", k.to_lowercase()));
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {} FROM prompt_library
+                     WHERE ?1 IS NULL OR LOWER(name) LIKE ?1 OR LOWER(content) LIKE ?1
+                     ORDER BY use_count DESC, name ASC",
+                    COLUMNS
+                ))?;
+                let templates = stmt.query_map(params![pattern], row_to_template)?;
+                templates.collect()
+            })
+            .await
+    }
+
+    pub async fn delete_template(&self, name: &str) -> SqliteResult<bool> {
+        let name = name.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let deleted =
+                    db.execute("DELETE FROM prompt_library WHERE name = ?1", params![name])?;
+                Ok(deleted > 0)
+            })
+            .await
+    }
+
+    /// Count a prompt sent from the template; false if it doesn't exist
+    pub async fn record_use(&self, name: &str) -> SqliteResult<bool> {
+        let name = name.to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db(move |db| {
+                let updated = db.execute(
+                    "UPDATE prompt_library SET use_count = use_count + 1, last_used_at = ?2
+                     WHERE name = ?1",
+                    params![name, now],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_prompt_library_table(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_save_list_use_and_delete() {
+        let db = test_db();
+        let ops = PromptLibraryOps::new(&db);
+        ops.save_template("review", "Review {{path}} for {{focus}}")
+            .await
+            .unwrap();
+        ops.save_template("refactor", "Refactor {{path}}")
+            .await
+            .unwrap();
+
+        assert!(ops.record_use("Review").await.unwrap());
+        assert!(!ops.record_use("missing").await.unwrap());
+
+        // Editing keeps the use count and creation time
+        let before = ops.get_template("review").await.unwrap().unwrap();
+        let edited = ops
+            .save_template("REVIEW", "Review {{path}} carefully")
+            .await
+            .unwrap();
+        assert_eq!(edited.name, "review");
+        assert_eq!(edited.use_count, 1);
+        assert_eq!(edited.created_at, before.created_at);
+        assert_eq!(edited.variables, vec!["path"]);
+
+        let all = ops.list_templates(None).await.unwrap();
+        let names: Vec<_> = all.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["review", "refactor"]);
+        let found = ops.list_templates(Some("CAREFULLY")).await.unwrap();
+        assert_eq!(found.len(), 1);
+
+        assert!(ops.delete_template("refactor").await.unwrap());
+        assert!(!ops.delete_template("refactor").await.unwrap());
+        assert!(ops.get_template("refactor").await.unwrap().is_none());
+    }
+}
@@ -114,6 +114,7 @@ impl<'a> QueryOperations<'a> {
                     runs_by_status,
                     runs_by_source,
                     total_cost_usd,
+                    dead_letter_count: 0,
                 })
             })
             .await
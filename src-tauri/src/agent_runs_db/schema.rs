@@ -5,6 +5,11 @@
 
 use rusqlite::{Connection, Result as SqliteResult};
 
+/// Version of the schema this build creates, stamped on runs and pipeline
+/// groups and stored as the database's user_version. Bump it whenever a
+/// table, column or index is added.
+pub const SCHEMA_VERSION: i64 = 15;
+
 /// Create the main agent_runs table
 pub fn create_agent_runs_table(conn: &Connection) -> SqliteResult<()> {
     conn.execute(
@@ -29,7 +34,26 @@ pub fn create_agent_runs_table(conn: &Connection) -> SqliteResult<()> {
             total_cost_usd REAL,
             model_usage TEXT,
             can_resume INTEGER DEFAULT 0,
-            resume_data TEXT
+            resume_data TEXT,
+            conventions_injected INTEGER DEFAULT 0,
+            label TEXT,
+            deleted_at INTEGER,
+            workspace_id TEXT NOT NULL DEFAULT 'default',
+            continued_from TEXT,
+            stop_mode TEXT,
+            permission_profile TEXT,
+            model TEXT,
+            app_version TEXT,
+            git_commit TEXT,
+            schema_version INTEGER,
+            title TEXT,
+            env_keys TEXT,
+            allowed_tools TEXT,
+            disallowed_tools TEXT,
+            repo_path TEXT,
+            worktree_path TEXT,
+            tool_timings TEXT,
+            cloned_from TEXT
         )",
         [],
     )?;
@@ -70,6 +94,9 @@ pub fn create_prompts_table(conn: &Connection) -> SqliteResult<()> {
             timestamp INTEGER NOT NULL,
             prompt TEXT NOT NULL,
             response_summary TEXT,
+            usage TEXT,
+            turn_index INTEGER,
+            ended_at INTEGER,
             FOREIGN KEY (agent_id) REFERENCES agent_runs(agent_id)
         )",
         [],
@@ -83,6 +110,262 @@ pub fn create_prompts_table(conn: &Connection) -> SqliteResult<()> {
     Ok(())
 }
 
+/// Create the agent_notes table for notes pinned to runs by the user or meta agent
+pub fn create_agent_notes_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id TEXT NOT NULL,
+            author TEXT NOT NULL,
+            text TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_notes_agent ON agent_notes(agent_id, timestamp)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the agent_messages table for messages relayed between agents
+pub fn create_agent_messages_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_id TEXT,
+            from_agent_id TEXT NOT NULL,
+            to_agent_id TEXT NOT NULL,
+            text TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_agent_messages_pipeline ON agent_messages(pipeline_id, timestamp)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding per-step snapshots of pipeline scratchpads
+pub fn create_scratchpad_versions_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipeline_scratchpad_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_id TEXT NOT NULL,
+            iteration INTEGER NOT NULL,
+            step_number INTEGER,
+            content TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_scratchpad_pipeline ON pipeline_scratchpad_versions(pipeline_id, timestamp)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the table recording work offloaded to worker agents
+pub fn create_task_offloads_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS task_offloads (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            tokens_avoided INTEGER NOT NULL,
+            usd_avoided REAL NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_task_offloads_created ON task_offloads(created_at)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the capped log of hook events received from agents
+pub fn create_hook_events_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hook_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id TEXT,
+            session_id TEXT NOT NULL,
+            event_name TEXT NOT NULL,
+            tool_name TEXT,
+            matched_by TEXT NOT NULL,
+            latency_ms INTEGER NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_hook_events_agent ON hook_events(agent_id, id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding pipelines paused on a question for the user
+pub fn create_pipeline_human_requests_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipeline_human_requests (
+            pipeline_id TEXT PRIMARY KEY,
+            question TEXT NOT NULL,
+            pipeline TEXT NOT NULL,
+            orchestrator TEXT NOT NULL,
+            requested_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding finished pipelines evicted from memory
+pub fn create_pipeline_snapshots_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipeline_snapshots (
+            pipeline_id TEXT PRIMARY KEY,
+            status TEXT NOT NULL,
+            pipeline TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            completed_at INTEGER,
+            evicted_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_pipeline_snapshots_created ON pipeline_snapshots(created_at)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding skills, subagents and CLAUDE.md files as generated
+pub fn create_generated_artifacts_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS generated_artifacts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_id TEXT,
+            agent_id TEXT,
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            path TEXT NOT NULL,
+            content TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            pipeline_state TEXT,
+            created_at INTEGER NOT NULL,
+            removed_at INTEGER
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_artifacts_pipeline ON generated_artifacts(pipeline_id, created_at)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_artifacts_agent ON generated_artifacts(agent_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the agent group tables: groups and their members
+pub fn create_agent_group_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_groups (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            pipeline_id TEXT,
+            created_at INTEGER NOT NULL,
+            app_version TEXT,
+            git_commit TEXT,
+            schema_version INTEGER
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_group_members (
+            group_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            added_at INTEGER NOT NULL,
+            PRIMARY KEY (group_id, agent_id)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_group_members_agent ON agent_group_members(agent_id)",
+        [],
+    )?;
+
+    // Migration: Add the build that created the group
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(agent_groups)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    add_version_columns(conn, "agent_groups", &columns)?;
+
+    Ok(())
+}
+
+/// Create the prompt library table: named prompts with `{{variable}}` placeholders
+pub fn create_prompt_library_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS prompt_library (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            content TEXT NOT NULL,
+            use_count INTEGER NOT NULL DEFAULT 0,
+            last_used_at INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Create the permission profiles table: named tool and command policies,
+/// each bound to the agent sources it applies to
+pub fn create_permission_profiles_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS permission_profiles (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            sources TEXT NOT NULL DEFAULT '[]',
+            allowed_tools TEXT NOT NULL DEFAULT '[]',
+            disallowed_tools TEXT NOT NULL DEFAULT '[]',
+            permission_mode TEXT NOT NULL,
+            elevated_commands TEXT NOT NULL,
+            network_expected INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
 /// Create orchestrator event tables for hybrid persistence
 pub fn create_orchestrator_tables(conn: &Connection) -> SqliteResult<()> {
     // Create orchestrator_tool_calls table
@@ -157,10 +440,12 @@ pub fn create_orchestrator_tables(conn: &Connection) -> SqliteResult<()> {
             output_type TEXT NOT NULL,
             content TEXT NOT NULL,
             metadata TEXT,
-            timestamp INTEGER NOT NULL
+            timestamp INTEGER NOT NULL,
+            turn_index INTEGER
         )",
         [],
     )?;
+    migrate_agent_outputs(conn)?;
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_outputs_agent ON agent_outputs(agent_id, timestamp DESC)",
@@ -172,6 +457,48 @@ pub fn create_orchestrator_tables(conn: &Connection) -> SqliteResult<()> {
         [],
     )?;
 
+    create_agent_outputs_fts(conn)?;
+
+    Ok(())
+}
+
+/// Full-text index over agent_outputs.content for search_agent_outputs
+///
+/// The trigram tokenizer matches any substring of three or more characters,
+/// case-insensitively, so file names and error strings are found wherever
+/// they appear. Triggers keep the index in step with the table; outputs
+/// written before the index existed are indexed when it is created.
+fn create_agent_outputs_fts(conn: &Connection) -> SqliteResult<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'agent_outputs_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS agent_outputs_fts USING fts5(
+            content, content='agent_outputs', content_rowid='id', tokenize='trigram'
+        );
+        CREATE TRIGGER IF NOT EXISTS agent_outputs_fts_insert AFTER INSERT ON agent_outputs BEGIN
+            INSERT INTO agent_outputs_fts(rowid, content) VALUES (new.id, new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS agent_outputs_fts_delete AFTER DELETE ON agent_outputs BEGIN
+            INSERT INTO agent_outputs_fts(agent_outputs_fts, rowid, content)
+            VALUES ('delete', old.id, old.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS agent_outputs_fts_update AFTER UPDATE OF content ON agent_outputs BEGIN
+            INSERT INTO agent_outputs_fts(agent_outputs_fts, rowid, content)
+            VALUES ('delete', old.id, old.content);
+            INSERT INTO agent_outputs_fts(rowid, content) VALUES (new.id, new.content);
+        END;",
+    )?;
+
+    if !exists {
+        conn.execute(
+            "INSERT INTO agent_outputs_fts(agent_outputs_fts) VALUES ('rebuild')",
+            [],
+        )?;
+    }
     Ok(())
 }
 
@@ -196,6 +523,244 @@ pub fn run_migrations(conn: &Connection) -> SqliteResult<()> {
         )?;
     }
 
+    // Migration: Add conventions_injected to attribute behavior to injected repo conventions
+    if !columns.contains(&"conventions_injected".to_string()) {
+        conn.execute(
+            "ALTER TABLE agent_runs ADD COLUMN conventions_injected INTEGER DEFAULT 0",
+            [],
+        )?;
+    }
+
+    // Migration: Add label for short run names derived from the initial prompt
+    if !columns.contains(&"label".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN label TEXT", [])?;
+    }
+
+    // Migration: Add deleted_at for runs moved to the trash
+    if !columns.contains(&"deleted_at".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN deleted_at INTEGER", [])?;
+    }
+
+    // Migration: Add workspace_id; existing runs belong to the default workspace
+    if !columns.contains(&"workspace_id".to_string()) {
+        conn.execute(
+            "ALTER TABLE agent_runs ADD COLUMN workspace_id TEXT NOT NULL DEFAULT 'default'",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_runs_workspace ON agent_runs(workspace_id)",
+        [],
+    )?;
+
+    // Migration: Add continued_from, the run a handed-off agent took over from
+    if !columns.contains(&"continued_from".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN continued_from TEXT", [])?;
+    }
+
+    // Migration: Add stop_mode, whether a stopped agent exited on its own or was killed
+    if !columns.contains(&"stop_mode".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN stop_mode TEXT", [])?;
+    }
+
+    // Migration: Add permission_profile, the profile the agent was spawned under
+    if !columns.contains(&"permission_profile".to_string()) {
+        conn.execute(
+            "ALTER TABLE agent_runs ADD COLUMN permission_profile TEXT",
+            [],
+        )?;
+    }
+
+    // Migration: Add model, the model the agent's CLI was started with
+    if !columns.contains(&"model".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN model TEXT", [])?;
+    }
+
+    // Migration: Add the build that created the run, for forensic debugging
+    add_version_columns(conn, "agent_runs", &columns)?;
+
+    // Migration: Add title, the agent's display title (set at spawn or by rename_agent)
+    if !columns.contains(&"title".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN title TEXT", [])?;
+    }
+
+    // Migration: Add env_keys, the names of the extra env vars the agent was spawned with
+    if !columns.contains(&"env_keys".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN env_keys TEXT", [])?;
+    }
+
+    // Migration: Add allowed_tools and disallowed_tools, the tool lists the agent ran with
+    // Migration: Add repo_path and worktree_path, where an agent with its own worktree ran
+    for column in [
+        "allowed_tools",
+        "disallowed_tools",
+        "repo_path",
+        "worktree_path",
+    ] {
+        if !columns.contains(&column.to_string()) {
+            conn.execute(
+                &format!("ALTER TABLE agent_runs ADD COLUMN {} TEXT", column),
+                [],
+            )?;
+        }
+    }
+
+    // Migration: Add tool_timings, the time spent per tool, stored when the agent stops
+    if !columns.contains(&"tool_timings".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN tool_timings TEXT", [])?;
+    }
+
+    // Migration: Add cloned_from, the agent a cloned agent was copied from
+    if !columns.contains(&"cloned_from".to_string()) {
+        conn.execute("ALTER TABLE agent_runs ADD COLUMN cloned_from TEXT", [])?;
+    }
+
+    // Migration: Add usage for per-prompt token and cost attribution
+    let prompt_columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(agent_prompts)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if !prompt_columns.contains(&"usage".to_string()) {
+        conn.execute("ALTER TABLE agent_prompts ADD COLUMN usage TEXT", [])?;
+    }
+
+    // Migration: Add turn_index and ended_at to link prompts to the turn they started
+    if !prompt_columns.contains(&"turn_index".to_string()) {
+        conn.execute(
+            "ALTER TABLE agent_prompts ADD COLUMN turn_index INTEGER",
+            [],
+        )?;
+    }
+    if !prompt_columns.contains(&"ended_at".to_string()) {
+        conn.execute("ALTER TABLE agent_prompts ADD COLUMN ended_at INTEGER", [])?;
+    }
+
+    Ok(())
+}
+
+/// Add the app_version, git_commit and schema_version columns to a table that lacks them
+fn add_version_columns(conn: &Connection, table: &str, columns: &[String]) -> SqliteResult<()> {
+    for (column, sql_type) in [
+        ("app_version", "TEXT"),
+        ("git_commit", "TEXT"),
+        ("schema_version", "INTEGER"),
+    ] {
+        if !columns.iter().any(|c| c == column) {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, sql_type),
+                [],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Add columns introduced after agent_outputs was first created
+fn migrate_agent_outputs(conn: &Connection) -> SqliteResult<()> {
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(agent_outputs)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Migration: Add turn_index so outputs can be grouped under the prompt they answer
+    if !columns.contains(&"turn_index".to_string()) {
+        conn.execute(
+            "ALTER TABLE agent_outputs ADD COLUMN turn_index INTEGER",
+            [],
+        )?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_outputs_turn ON agent_outputs(agent_id, turn_index)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Add columns introduced after meta_conversations was first created
+fn migrate_meta_conversations(conn: &Connection) -> SqliteResult<()> {
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(meta_conversations)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Migration: Add title_locked so generated titles never overwrite a manual rename
+    if !columns.contains(&"title_locked".to_string()) {
+        conn.execute(
+            "ALTER TABLE meta_conversations ADD COLUMN title_locked INTEGER DEFAULT 0",
+            [],
+        )?;
+    }
+
+    // Migration: Add summary column for generated conversation summaries
+    if !columns.contains(&"summary".to_string()) {
+        conn.execute("ALTER TABLE meta_conversations ADD COLUMN summary TEXT", [])?;
+    }
+
+    // Migration: Add fork links for conversations branched from another one
+    if !columns.contains(&"forked_from".to_string()) {
+        conn.execute(
+            "ALTER TABLE meta_conversations ADD COLUMN forked_from TEXT",
+            [],
+        )?;
+    }
+    if !columns.contains(&"forked_at_message".to_string()) {
+        conn.execute(
+            "ALTER TABLE meta_conversations ADD COLUMN forked_at_message INTEGER",
+            [],
+        )?;
+    }
+
+    // Migration: Add deleted_at for conversations moved to the trash
+    if !columns.contains(&"deleted_at".to_string()) {
+        conn.execute(
+            "ALTER TABLE meta_conversations ADD COLUMN deleted_at INTEGER",
+            [],
+        )?;
+    }
+
+    // Migration: Add workspace_id; existing conversations belong to the default workspace
+    if !columns.contains(&"workspace_id".to_string()) {
+        conn.execute(
+            "ALTER TABLE meta_conversations ADD COLUMN workspace_id TEXT NOT NULL DEFAULT 'default'",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Add columns introduced after meta_messages was first created
+fn migrate_meta_messages(conn: &Connection) -> SqliteResult<()> {
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(meta_messages)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Migration: Add the model and personality behind each response, and user ratings
+    for (column, sql_type) in [
+        ("model", "TEXT"),
+        ("personality_hash", "TEXT"),
+        ("rating", "TEXT"),
+        ("rating_note", "TEXT"),
+        ("rated_at", "INTEGER"),
+    ] {
+        if !columns.contains(&column.to_string()) {
+            conn.execute(
+                &format!(
+                    "ALTER TABLE meta_messages ADD COLUMN {} {}",
+                    column, sql_type
+                ),
+                [],
+            )?;
+        }
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_meta_msg_rated ON meta_messages(rated_at)",
+        [],
+    )?;
+
     Ok(())
 }
 
@@ -211,10 +776,17 @@ pub fn create_meta_conversation_tables(conn: &Connection) -> SqliteResult<()> {
             updated_at INTEGER NOT NULL,
             message_count INTEGER DEFAULT 0,
             is_archived INTEGER DEFAULT 0,
-            preview_text TEXT
+            preview_text TEXT,
+            title_locked INTEGER DEFAULT 0,
+            summary TEXT,
+            forked_from TEXT,
+            forked_at_message INTEGER,
+            deleted_at INTEGER,
+            workspace_id TEXT NOT NULL DEFAULT 'default'
         )",
         [],
     )?;
+    migrate_meta_conversations(conn)?;
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_meta_conv_id ON meta_conversations(conversation_id)",
@@ -236,16 +808,90 @@ pub fn create_meta_conversation_tables(conn: &Connection) -> SqliteResult<()> {
             image_data TEXT,
             tool_calls TEXT,
             timestamp INTEGER NOT NULL,
+            model TEXT,
+            personality_hash TEXT,
+            rating TEXT,
+            rating_note TEXT,
+            rated_at INTEGER,
             FOREIGN KEY (conversation_id) REFERENCES meta_conversations(conversation_id)
         )",
         [],
     )?;
+    migrate_meta_messages(conn)?;
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_meta_msg_conv ON meta_messages(conversation_id, message_index)",
         [],
     )?;
 
+    // Compactions of a conversation's history: the summary that replaced a
+    // range of messages, for reviewing what was lost
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS meta_compactions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            conversation_id TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            first_message_index INTEGER NOT NULL,
+            last_message_index INTEGER NOT NULL,
+            tokens_before INTEGER NOT NULL,
+            tokens_after INTEGER NOT NULL,
+            emergency INTEGER NOT NULL DEFAULT 0,
+            preserved_message_indexes TEXT,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_meta_compactions_conv ON meta_compactions(conversation_id, created_at)",
+        [],
+    )?;
+
+    // User pins: 1 keeps a message verbatim through compaction, 0 lets it be
+    // summarized even if it looks like a constraint
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS meta_message_pins (
+            conversation_id TEXT NOT NULL,
+            message_index INTEGER NOT NULL,
+            pinned INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (conversation_id, message_index)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Create the workspaces table, with the default workspace that existing data belongs to
+pub fn create_workspaces_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS workspaces (
+            workspace_id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_active_at INTEGER NOT NULL,
+            history_scanned_at INTEGER
+        )",
+        [],
+    )?;
+
+    // Migration: Add history_scanned_at, the memory worker's conversation scan mark
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(workspaces)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if !columns.contains(&"history_scanned_at".to_string()) {
+        conn.execute(
+            "ALTER TABLE workspaces ADD COLUMN history_scanned_at INTEGER",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO workspaces (workspace_id, name, created_at, last_active_at)
+         VALUES ('default', 'Default', 0, 0)",
+        [],
+    )?;
     Ok(())
 }
 
@@ -254,8 +900,21 @@ pub fn initialize_schema(conn: &Connection) -> SqliteResult<()> {
     create_agent_runs_table(conn)?;
     create_agent_runs_indexes(conn)?;
     create_prompts_table(conn)?;
+    create_agent_notes_table(conn)?;
+    create_agent_messages_table(conn)?;
     run_migrations(conn)?;
     create_orchestrator_tables(conn)?;
+    create_scratchpad_versions_table(conn)?;
+    create_pipeline_human_requests_table(conn)?;
+    create_pipeline_snapshots_table(conn)?;
+    create_task_offloads_table(conn)?;
+    create_hook_events_table(conn)?;
+    create_generated_artifacts_table(conn)?;
+    create_agent_group_tables(conn)?;
+    create_prompt_library_table(conn)?;
+    create_permission_profiles_table(conn)?;
     create_meta_conversation_tables(conn)?;
+    create_workspaces_table(conn)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
     Ok(())
 }
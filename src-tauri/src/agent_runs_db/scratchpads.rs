@@ -0,0 +1,124 @@
+// Pipeline scratchpad version persistence
+//
+// The scratchpad itself is a file in the pipeline's working directory; these
+// snapshots of it, one per finished step, keep its history after the file has
+// been edited or deleted.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::ScratchpadVersionRecord;
+
+/// Helper to convert a row to ScratchpadVersionRecord
+fn row_to_version(row: &rusqlite::Row) -> SqliteResult<ScratchpadVersionRecord> {
+    Ok(ScratchpadVersionRecord {
+        id: Some(row.get(0)?),
+        pipeline_id: row.get(1)?,
+        iteration: row.get(2)?,
+        step_number: row.get(3)?,
+        content: row.get(4)?,
+        timestamp: row.get(5)?,
+    })
+}
+
+/// Operations for scratchpad versions
+pub struct ScratchpadOps<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> ScratchpadOps<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Insert a snapshot and return its row ID
+    pub async fn insert_version(&self, record: &ScratchpadVersionRecord) -> SqliteResult<i64> {
+        let record = record.clone();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "INSERT INTO pipeline_scratchpad_versions
+                     (pipeline_id, iteration, step_number, content, timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        record.pipeline_id,
+                        record.iteration,
+                        record.step_number,
+                        record.content,
+                        record.timestamp
+                    ],
+                )?;
+                Ok(db.last_insert_rowid())
+            })
+            .await
+    }
+
+    /// Get all snapshots for a pipeline, oldest first
+    pub async fn get_versions(
+        &self,
+        pipeline_id: &str,
+    ) -> SqliteResult<Vec<ScratchpadVersionRecord>> {
+        let pipeline_id = pipeline_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT id, pipeline_id, iteration, step_number, content, timestamp
+                     FROM pipeline_scratchpad_versions
+                     WHERE pipeline_id = ?1 ORDER BY timestamp ASC, id ASC",
+                )?;
+                let versions = stmt.query_map(params![pipeline_id], row_to_version)?;
+                versions.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(
+        pipeline_id: &str,
+        iteration: u32,
+        content: &str,
+        timestamp: i64,
+    ) -> ScratchpadVersionRecord {
+        ScratchpadVersionRecord {
+            id: None,
+            pipeline_id: pipeline_id.to_string(),
+            iteration,
+            step_number: Some(1),
+            content: content.to_string(),
+            timestamp,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_versions_round_trip_in_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::schema::create_scratchpad_versions_table(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let ops = ScratchpadOps::new(&db);
+
+        ops.insert_version(&version("p1", 2, "second", 20))
+            .await
+            .unwrap();
+        ops.insert_version(&version("p1", 1, "first", 10))
+            .await
+            .unwrap();
+        ops.insert_version(&version("p2", 1, "other", 5))
+            .await
+            .unwrap();
+
+        let versions = ops.get_versions("p1").await.unwrap();
+        let contents: Vec<_> = versions.iter().map(|v| v.content.as_str()).collect();
+        assert_eq!(contents, vec!["first", "second"]);
+        assert_eq!(versions[1].iteration, 2);
+        assert!(versions[0].id.is_some());
+    }
+}
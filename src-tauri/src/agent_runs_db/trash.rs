@@ -0,0 +1,316 @@
+// Trash for soft-deleted runs and meta agent conversations
+//
+// Deleting a run or conversation sets its `deleted_at` timestamp instead of
+// removing the row. Default list queries skip trashed rows, but cost
+// summaries and trends still count trashed runs since the money was spent.
+// Trashed items can be restored until the trash is emptied, either by hand
+// or by the scheduled purge once they are older than TRASH_RETENTION_DAYS.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::commands::env_registry;
+use crate::db_utils::DatabaseOps;
+
+use super::models::{TrashItem, TrashPurgeResult};
+
+/// Days a trashed item is kept before the scheduled purge removes it
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// How often the scheduled purge runs
+pub const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Retention for the scheduled purge from TRASH_RETENTION_DAYS (0 disables the purge)
+pub fn trash_retention_days() -> i64 {
+    env_registry::TRASH_RETENTION_DAYS
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+}
+
+/// Kind of item that can be moved to the trash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashKind {
+    Run,
+    Conversation,
+}
+
+impl TrashKind {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "run" => Ok(TrashKind::Run),
+            "conversation" => Ok(TrashKind::Conversation),
+            "pipeline" => Err("Pipelines are not persisted, so they have no trash".to_string()),
+            other => Err(format!(
+                "Unknown trash kind '{}': expected \"run\" or \"conversation\"",
+                other
+            )),
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            TrashKind::Run => "agent_runs",
+            TrashKind::Conversation => "meta_conversations",
+        }
+    }
+
+    fn id_column(&self) -> &'static str {
+        match self {
+            TrashKind::Run => "agent_id",
+            TrashKind::Conversation => "conversation_id",
+        }
+    }
+}
+
+/// Trash operations extension for AgentRunsDB
+pub struct TrashOperations<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> TrashOperations<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Move an item to the trash. Returns false if it doesn't exist or is already trashed.
+    pub async fn move_to_trash(&self, kind: TrashKind, id: &str) -> SqliteResult<bool> {
+        let id = id.to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db(move |db| {
+                let sql = format!(
+                    "UPDATE {} SET deleted_at = ?1 WHERE {} = ?2 AND deleted_at IS NULL",
+                    kind.table(),
+                    kind.id_column()
+                );
+                Ok(db.execute(&sql, params![now, id])? > 0)
+            })
+            .await
+    }
+
+    /// Restore a trashed item. Returns false if it isn't in the trash.
+    pub async fn restore(&self, kind: TrashKind, id: &str) -> SqliteResult<bool> {
+        let id = id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let sql = format!(
+                    "UPDATE {} SET deleted_at = NULL WHERE {} = ?1 AND deleted_at IS NOT NULL",
+                    kind.table(),
+                    kind.id_column()
+                );
+                Ok(db.execute(&sql, params![id])? > 0)
+            })
+            .await
+    }
+
+    /// List trashed items, most recently deleted first
+    pub async fn list_trash(&self, kind: Option<TrashKind>) -> SqliteResult<Vec<TrashItem>> {
+        self.db
+            .with_db(move |db| {
+                let mut items = Vec::new();
+
+                if kind.is_none() || kind == Some(TrashKind::Run) {
+                    let mut stmt = db.prepare(
+                        "SELECT agent_id, COALESCE(label, substr(initial_prompt, 1, 100), working_dir),
+                                deleted_at, total_cost_usd
+                         FROM agent_runs WHERE deleted_at IS NOT NULL",
+                    )?;
+                    let rows = stmt.query_map([], |row| {
+                        Ok(TrashItem {
+                            kind: TrashKind::Run,
+                            id: row.get(0)?,
+                            title: row.get(1)?,
+                            deleted_at: row.get(2)?,
+                            cost_usd: row.get(3)?,
+                        })
+                    })?;
+                    for row in rows {
+                        items.push(row?);
+                    }
+                }
+
+                if kind.is_none() || kind == Some(TrashKind::Conversation) {
+                    let mut stmt = db.prepare(
+                        "SELECT conversation_id, COALESCE(title, 'Untitled conversation'), deleted_at
+                         FROM meta_conversations WHERE deleted_at IS NOT NULL",
+                    )?;
+                    let rows = stmt.query_map([], |row| {
+                        Ok(TrashItem {
+                            kind: TrashKind::Conversation,
+                            id: row.get(0)?,
+                            title: row.get(1)?,
+                            deleted_at: row.get(2)?,
+                            cost_usd: None,
+                        })
+                    })?;
+                    for row in rows {
+                        items.push(row?);
+                    }
+                }
+
+                items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+                Ok(items)
+            })
+            .await
+    }
+
+    /// Permanently delete items trashed more than `older_than_days` days ago
+    /// (0 empties the whole trash)
+    pub async fn empty_trash(&self, older_than_days: i64) -> SqliteResult<TrashPurgeResult> {
+        let cutoff = chrono::Utc::now().timestamp_millis() - older_than_days.max(0) * DAY_MS;
+
+        self.db
+            .with_db_mut(move |db| {
+                let tx = db.transaction()?;
+
+                tx.execute(
+                    "DELETE FROM agent_prompts WHERE agent_id IN (
+                        SELECT agent_id FROM agent_runs WHERE deleted_at <= ?1
+                    )",
+                    params![cutoff],
+                )?;
+                tx.execute(
+                    "DELETE FROM agent_notes WHERE agent_id IN (
+                        SELECT agent_id FROM agent_runs WHERE deleted_at <= ?1
+                    )",
+                    params![cutoff],
+                )?;
+                let runs = tx.execute(
+                    "DELETE FROM agent_runs WHERE deleted_at <= ?1",
+                    params![cutoff],
+                )?;
+
+                tx.execute(
+                    "DELETE FROM meta_messages WHERE conversation_id IN (
+                        SELECT conversation_id FROM meta_conversations WHERE deleted_at <= ?1
+                    )",
+                    params![cutoff],
+                )?;
+                tx.execute(
+                    "DELETE FROM meta_compactions WHERE conversation_id IN (
+                        SELECT conversation_id FROM meta_conversations WHERE deleted_at <= ?1
+                    )",
+                    params![cutoff],
+                )?;
+                tx.execute(
+                    "DELETE FROM meta_message_pins WHERE conversation_id IN (
+                        SELECT conversation_id FROM meta_conversations WHERE deleted_at <= ?1
+                    )",
+                    params![cutoff],
+                )?;
+                let conversations = tx.execute(
+                    "DELETE FROM meta_conversations WHERE deleted_at <= ?1",
+                    params![cutoff],
+                )?;
+
+                tx.commit()?;
+                Ok(TrashPurgeResult {
+                    runs,
+                    conversations,
+                })
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::schema;
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO agent_runs (agent_id, working_dir, source, status, started_at, last_activity, total_cost_usd)
+             VALUES ('run-1', '/work', 'ui', 'completed', 1, 1, 2.5)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO agent_prompts (agent_id, timestamp, prompt) VALUES ('run-1', 1, 'hi')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO meta_conversations (conversation_id, title, created_at, updated_at)
+             VALUES ('conv-1', 'Planning', 1, 1)",
+            [],
+        )
+        .unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_trash_restore_round_trip() {
+        let db = test_db();
+        let trash = TrashOperations::new(&db);
+
+        assert!(trash.move_to_trash(TrashKind::Run, "run-1").await.unwrap());
+        assert!(!trash.move_to_trash(TrashKind::Run, "run-1").await.unwrap());
+        assert!(trash
+            .move_to_trash(TrashKind::Conversation, "conv-1")
+            .await
+            .unwrap());
+
+        let items = trash.list_trash(None).await.unwrap();
+        assert_eq!(items.len(), 2);
+        let runs = trash.list_trash(Some(TrashKind::Run)).await.unwrap();
+        assert_eq!(runs[0].title, "/work");
+        assert_eq!(runs[0].cost_usd, Some(2.5));
+
+        assert!(trash.restore(TrashKind::Run, "run-1").await.unwrap());
+        assert!(!trash.restore(TrashKind::Run, "run-1").await.unwrap());
+        assert!(trash
+            .list_trash(Some(TrashKind::Run))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_trash_honors_age_and_removes_children() {
+        let db = test_db();
+        let trash = TrashOperations::new(&db);
+        trash.move_to_trash(TrashKind::Run, "run-1").await.unwrap();
+        trash
+            .move_to_trash(TrashKind::Conversation, "conv-1")
+            .await
+            .unwrap();
+
+        // Just trashed, so a 30 day retention keeps everything
+        let kept = trash.empty_trash(30).await.unwrap();
+        assert_eq!((kept.runs, kept.conversations), (0, 0));
+
+        let purged = trash.empty_trash(0).await.unwrap();
+        assert_eq!((purged.runs, purged.conversations), (1, 1));
+        let prompts: i64 = db
+            .lock()
+            .await
+            .query_row("SELECT COUNT(*) FROM agent_prompts", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(prompts, 0);
+    }
+
+    #[test]
+    fn test_parse_kind() {
+        assert_eq!(TrashKind::parse("Run").unwrap(), TrashKind::Run);
+        assert_eq!(
+            TrashKind::parse("conversation").unwrap(),
+            TrashKind::Conversation
+        );
+        assert!(TrashKind::parse("pipeline").is_err());
+        assert!(TrashKind::parse("agent").is_err());
+    }
+}
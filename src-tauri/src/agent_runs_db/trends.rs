@@ -0,0 +1,313 @@
+// Historical usage trends for agent runs and pipelines
+//
+// Aggregates runs, cost, tokens and pipeline outcomes into day or week
+// buckets. Buckets without activity are filled in so charts get a
+// continuous axis.
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::cost::{query_top_prompts, TOP_PROMPTS_LIMIT};
+use super::models::{UsageTrendBucket, UsageTrends};
+
+/// Longest range a trend query may cover
+pub const MAX_TREND_DAYS: i64 = 365;
+
+/// Bucket size for trend aggregation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrendBucket {
+    Day,
+    Week,
+}
+
+impl TrendBucket {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "day" => Ok(TrendBucket::Day),
+            "week" => Ok(TrendBucket::Week),
+            other => Err(format!(
+                "Invalid bucket '{}': expected \"day\" or \"week\"",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrendBucket::Day => "day",
+            TrendBucket::Week => "week",
+        }
+    }
+
+    /// SQL expression mapping a millisecond timestamp column to its bucket start date
+    fn sql_expr(&self, column: &str) -> String {
+        match self {
+            TrendBucket::Day => format!("DATE({} / 1000, 'unixepoch')", column),
+            // Back up six days then forward to the next Monday = Monday of that week
+            TrendBucket::Week => format!(
+                "DATE({} / 1000, 'unixepoch', '-6 days', 'weekday 1')",
+                column
+            ),
+        }
+    }
+
+    /// First day of the bucket containing `date`
+    pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TrendBucket::Day => date,
+            TrendBucket::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+
+    /// Every bucket start between `from` and `to` (inclusive)
+    pub fn keys(&self, from: NaiveDate, to: NaiveDate) -> Vec<String> {
+        let step = match self {
+            TrendBucket::Day => Duration::days(1),
+            TrendBucket::Week => Duration::weeks(1),
+        };
+        let mut keys = Vec::new();
+        let mut current = self.start_of(from);
+        while current <= to {
+            keys.push(current.format("%Y-%m-%d").to_string());
+            current += step;
+        }
+        keys
+    }
+}
+
+/// Trend operations extension for AgentRunsDB
+pub struct TrendOperations<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> TrendOperations<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Get usage trends for the last `days` days grouped by `bucket`
+    ///
+    /// The range is capped at `MAX_TREND_DAYS` and at the oldest retained run,
+    /// so cleaned-up history doesn't show up as a long run of empty buckets.
+    pub async fn get_usage_trends(
+        &self,
+        days: i64,
+        bucket: TrendBucket,
+    ) -> SqliteResult<UsageTrends> {
+        let today = Utc::now().date_naive();
+        let requested_days = days.clamp(1, MAX_TREND_DAYS);
+
+        self.db
+            .with_db(move |db| {
+                let oldest: Option<i64> =
+                    db.query_row("SELECT MIN(started_at) FROM agent_runs", [], |row| {
+                        row.get(0)
+                    })?;
+
+                let mut from = today - Duration::days(requested_days - 1);
+                if let Some(oldest_day) = oldest
+                    .and_then(chrono::DateTime::from_timestamp_millis)
+                    .map(|dt| dt.date_naive())
+                {
+                    from = from.max(oldest_day.min(today));
+                }
+                let from = bucket.start_of(from);
+                let cutoff = from
+                    .and_hms_opt(0, 0, 0)
+                    .map(|dt| dt.and_utc().timestamp_millis())
+                    .unwrap_or(0);
+
+                let mut buckets: BTreeMap<String, UsageTrendBucket> = bucket
+                    .keys(from, today)
+                    .into_iter()
+                    .map(|key| {
+                        let entry = UsageTrendBucket {
+                            bucket_start: key.clone(),
+                            ..Default::default()
+                        };
+                        (key, entry)
+                    })
+                    .collect();
+
+                collect_runs(db, bucket, cutoff, &mut buckets)?;
+                collect_pipelines(db, bucket, cutoff, &mut buckets)?;
+
+                for entry in buckets.values_mut() {
+                    entry.success_rate = success_rate(&entry.runs_by_status);
+                }
+
+                Ok(UsageTrends {
+                    bucket: bucket.as_str().to_string(),
+                    days: (today - from).num_days() + 1,
+                    buckets: buckets.into_values().collect(),
+                    top_prompts: query_top_prompts(db, Some(cutoff), TOP_PROMPTS_LIMIT)?,
+                })
+            })
+            .await
+    }
+}
+
+/// Aggregate run counts, cost and tokens per bucket and status
+fn collect_runs(
+    db: &Connection,
+    bucket: TrendBucket,
+    cutoff: i64,
+    buckets: &mut BTreeMap<String, UsageTrendBucket>,
+) -> SqliteResult<()> {
+    let sql = format!(
+        "SELECT {} AS bucket, status, COUNT(*),
+                COALESCE(SUM(total_cost_usd), 0.0), COALESCE(SUM(total_tokens_used), 0)
+         FROM agent_runs
+         WHERE started_at >= ?1
+         GROUP BY bucket, status",
+        bucket.sql_expr("started_at")
+    );
+    let mut stmt = db.prepare(&sql)?;
+    let rows = stmt.query_map(params![cutoff], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, f64>(3)?,
+            row.get::<_, i64>(4)?,
+        ))
+    })?;
+
+    for row in rows {
+        let (key, status, count, cost, tokens) = row?;
+        if let Some(entry) = buckets.get_mut(&key) {
+            entry.total_runs += count;
+            entry.total_cost_usd += cost;
+            entry.total_tokens += tokens;
+            entry.runs_by_status.push((status, count));
+        }
+    }
+    Ok(())
+}
+
+/// Aggregate pipeline outcomes from terminal orchestrator state changes
+fn collect_pipelines(
+    db: &Connection,
+    bucket: TrendBucket,
+    cutoff: i64,
+    buckets: &mut BTreeMap<String, UsageTrendBucket>,
+) -> SqliteResult<()> {
+    let sql = format!(
+        "SELECT {} AS bucket, new_state, COUNT(*), COALESCE(SUM(iteration), 0)
+         FROM orchestrator_state_changes
+         WHERE new_state IN ('Completed', 'Failed', 'GaveUp') AND timestamp >= ?1
+         GROUP BY bucket, new_state",
+        bucket.sql_expr("timestamp")
+    );
+    let mut stmt = db.prepare(&sql)?;
+    let rows = stmt.query_map(params![cutoff], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+
+    // (pipelines, total iterations) per bucket for the average
+    let mut iterations: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for row in rows {
+        let (key, state, count, iteration_sum) = row?;
+        let Some(entry) = buckets.get_mut(&key) else {
+            continue;
+        };
+        match state.as_str() {
+            "Completed" => entry.pipelines_completed += count,
+            "Failed" => entry.pipelines_failed += count,
+            _ => entry.pipelines_gave_up += count,
+        }
+        let totals = iterations.entry(key).or_default();
+        totals.0 += count;
+        totals.1 += iteration_sum;
+    }
+
+    for (key, (count, sum)) in iterations {
+        if let Some(entry) = buckets.get_mut(&key) {
+            entry.avg_pipeline_iterations = (count > 0).then(|| sum as f64 / count as f64);
+        }
+    }
+    Ok(())
+}
+
+/// Share of finished runs that completed successfully
+fn success_rate(runs_by_status: &[(String, i64)]) -> Option<f64> {
+    let mut completed = 0;
+    let mut finished = 0;
+    for (status, count) in runs_by_status {
+        match status.as_str() {
+            "completed" => {
+                completed += count;
+                finished += count;
+            }
+            "stopped" | "crashed" => finished += count,
+            _ => {}
+        }
+    }
+    (finished > 0).then(|| completed as f64 / finished as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_week_keys_start_on_monday() {
+        // 2025-01-01 is a Wednesday
+        let keys = TrendBucket::Week.keys(date("2025-01-01"), date("2025-01-14"));
+        assert_eq!(keys, vec!["2024-12-30", "2025-01-06", "2025-01-13"]);
+    }
+
+    #[test]
+    fn test_day_keys_include_empty_days() {
+        let keys = TrendBucket::Day.keys(date("2025-03-30"), date("2025-04-02"));
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys[0], "2025-03-30");
+        assert_eq!(keys[3], "2025-04-02");
+    }
+
+    #[test]
+    fn test_sql_week_matches_rust_bucketing() {
+        let conn = Connection::open_in_memory().unwrap();
+        for day in ["2025-01-05", "2025-01-06", "2025-01-08", "2025-01-12"] {
+            let ts = date(day)
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+                .and_utc()
+                .timestamp_millis();
+            let sql = format!("SELECT {}", TrendBucket::Week.sql_expr("?1"));
+            let from_sql: String = conn.query_row(&sql, params![ts], |r| r.get(0)).unwrap();
+            let from_rust = TrendBucket::Week
+                .start_of(date(day))
+                .format("%Y-%m-%d")
+                .to_string();
+            assert_eq!(from_sql, from_rust, "bucket mismatch for {}", day);
+        }
+    }
+
+    #[test]
+    fn test_success_rate_ignores_running() {
+        let statuses = vec![
+            ("completed".to_string(), 3),
+            ("crashed".to_string(), 1),
+            ("running".to_string(), 5),
+        ];
+        assert_eq!(success_rate(&statuses), Some(0.75));
+        assert_eq!(success_rate(&[("running".to_string(), 2)]), None);
+    }
+}
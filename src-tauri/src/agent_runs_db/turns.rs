@@ -0,0 +1,224 @@
+// Agent turns - prompts grouped with the outputs produced in response
+//
+// Each prompt sent to an agent starts a turn. `record_prompt` numbers prompts
+// per agent (turn_index 0, 1, ...), outputs are stamped on insert with the
+// turn of the latest prompt before them, and the end of the turn (end_turn /
+// Stop, after the idle grace period) is recorded as the prompt's ended_at.
+// Rows written before turns were tracked have no turn_index; their prompts
+// are numbered by position and their outputs placed by timestamp.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::{AgentOutputRecord, AgentTurn, PromptUsage};
+
+/// A prompt row as stored, before outputs are attached
+struct PromptRow {
+    turn_index: Option<i64>,
+    prompt: String,
+    timestamp: i64,
+    ended_at: Option<i64>,
+    usage: Option<PromptUsage>,
+}
+
+/// Operations for reading an agent's history as turns
+pub struct TurnOperations<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> TurnOperations<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Get an agent's turns in order, each with its prompt, outputs and usage
+    pub async fn get_agent_turns(&self, agent_id: &str) -> SqliteResult<Vec<AgentTurn>> {
+        let agent_id = agent_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT turn_index, prompt, timestamp, ended_at, usage FROM agent_prompts
+                     WHERE agent_id = ?1 ORDER BY timestamp ASC, id ASC",
+                )?;
+                let prompts = stmt
+                    .query_map(params![agent_id], |row| {
+                        let usage: Option<String> = row.get(4)?;
+                        Ok(PromptRow {
+                            turn_index: row.get(0)?,
+                            prompt: row.get(1)?,
+                            timestamp: row.get(2)?,
+                            ended_at: row.get(3)?,
+                            usage: usage.and_then(|s| serde_json::from_str(&s).ok()),
+                        })
+                    })?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+
+                let mut stmt = db.prepare(
+                    "SELECT id, agent_id, pipeline_id, output_type, content, metadata, timestamp, turn_index
+                     FROM agent_outputs WHERE agent_id = ?1 ORDER BY timestamp ASC, id ASC",
+                )?;
+                let outputs = stmt
+                    .query_map(params![agent_id], |row| {
+                        Ok(AgentOutputRecord {
+                            id: Some(row.get(0)?),
+                            agent_id: row.get(1)?,
+                            pipeline_id: row.get(2)?,
+                            output_type: row.get(3)?,
+                            content: row.get(4)?,
+                            metadata: row.get(5)?,
+                            timestamp: row.get(6)?,
+                            turn_index: row.get(7)?,
+                        })
+                    })?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+
+                Ok(group_turns(prompts, outputs))
+            })
+            .await
+    }
+}
+
+/// Attach outputs to the turns they belong to
+///
+/// Outputs go to the turn they were stamped with, or else to the latest turn
+/// started before them. Outputs from before the first prompt (session setup)
+/// go to the first turn.
+fn group_turns(prompts: Vec<PromptRow>, outputs: Vec<AgentOutputRecord>) -> Vec<AgentTurn> {
+    let mut turns: Vec<AgentTurn> = prompts
+        .into_iter()
+        .enumerate()
+        .map(|(position, row)| {
+            let (total_tokens, cost_usd) = row
+                .usage
+                .as_ref()
+                .map(|u| (u.total_tokens, u.cost_usd))
+                .unwrap_or_default();
+            AgentTurn {
+                turn_index: row.turn_index.unwrap_or(position as i64),
+                prompt: row.prompt,
+                started_at: row.timestamp,
+                ended_at: row.ended_at,
+                duration_ms: row.ended_at.map(|end| (end - row.timestamp).max(0)),
+                outputs: Vec::new(),
+                total_tokens,
+                cost_usd,
+                usage: row.usage,
+            }
+        })
+        .collect();
+    if turns.is_empty() {
+        return turns;
+    }
+
+    for output in outputs {
+        let slot = output
+            .turn_index
+            .and_then(|index| turns.iter().position(|t| t.turn_index == index))
+            .unwrap_or_else(|| {
+                turns
+                    .iter()
+                    .rposition(|t| t.started_at <= output.timestamp)
+                    .unwrap_or(0)
+            });
+        turns[slot].outputs.push(output);
+    }
+    turns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::crud::CrudOperations;
+    use crate::agent_runs_db::orchestrator_events::OrchestratorEventOps;
+    use crate::agent_runs_db::schema;
+
+    fn output(content: &str, timestamp: i64) -> AgentOutputRecord {
+        AgentOutputRecord {
+            id: None,
+            agent_id: "a".to_string(),
+            pipeline_id: None,
+            output_type: "text".to_string(),
+            content: content.to_string(),
+            metadata: None,
+            timestamp,
+            turn_index: None,
+        }
+    }
+
+    fn contents(turn: &AgentTurn) -> Vec<&str> {
+        turn.outputs.iter().map(|o| o.content.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_outputs_are_grouped_under_the_prompt_they_answer() {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_schema(&conn).unwrap();
+        let db = Arc::new(Mutex::new(conn));
+        let crud = CrudOperations::new(&db);
+        let events = OrchestratorEventOps::new(&db);
+
+        crud.record_prompt("a", "fix the build", 100).await.unwrap();
+        events
+            .insert_agent_outputs(&[output("looking", 110), output("fixed", 150)])
+            .await
+            .unwrap();
+        crud.mark_turn_ended("a", 160).await.unwrap();
+        crud.record_prompt_usage(
+            "a",
+            &PromptUsage {
+                total_tokens: 1_000,
+                cost_usd: 0.02,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        crud.record_prompt("a", "now add a test", 200)
+            .await
+            .unwrap();
+        events
+            .insert_agent_outputs(&[output("writing test", 210)])
+            .await
+            .unwrap();
+
+        let turns = TurnOperations::new(&db).get_agent_turns("a").await.unwrap();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].turn_index, 0);
+        assert_eq!(turns[0].prompt, "fix the build");
+        assert_eq!(contents(&turns[0]), vec!["looking", "fixed"]);
+        assert_eq!(turns[0].duration_ms, Some(60));
+        assert_eq!(turns[0].total_tokens, 1_000);
+        assert!(turns[0].outputs.iter().all(|o| o.turn_index == Some(0)));
+
+        // The follow-up is still in flight
+        assert_eq!(turns[1].turn_index, 1);
+        assert_eq!(contents(&turns[1]), vec!["writing test"]);
+        assert_eq!(turns[1].duration_ms, None);
+        assert_eq!(turns[1].usage, None);
+    }
+
+    #[test]
+    fn test_unstamped_rows_are_placed_by_position_and_time() {
+        let prompt = |text: &str, timestamp: i64| PromptRow {
+            turn_index: None,
+            prompt: text.to_string(),
+            timestamp,
+            ended_at: None,
+            usage: None,
+        };
+        let turns = group_turns(
+            vec![prompt("first", 100), prompt("second", 200)],
+            vec![output("init", 50), output("one", 150), output("two", 250)],
+        );
+
+        assert_eq!(turns[1].turn_index, 1);
+        assert_eq!(contents(&turns[0]), vec!["init", "one"]);
+        assert_eq!(contents(&turns[1]), vec!["two"]);
+        assert!(group_turns(Vec::new(), vec![output("orphan", 1)]).is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+// Workspace records
+//
+// Named scopes for the meta-agent's working context (see crate::workspace).
+// Conversations and runs point at their workspace through a `workspace_id`
+// column; this module manages the workspace rows themselves and rolls up
+// what each one owns.
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_utils::DatabaseOps;
+
+use super::models::WorkspaceInfo;
+
+/// Workspace operations extension for AgentRunsDB
+pub struct WorkspaceOperations<'a> {
+    db: &'a Arc<Mutex<Connection>>,
+}
+
+impl<'a> WorkspaceOperations<'a> {
+    pub fn new(db: &'a Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Create a workspace. Returns false if the ID is taken.
+    pub async fn create_workspace(&self, workspace_id: &str, name: &str) -> SqliteResult<bool> {
+        let workspace_id = workspace_id.to_string();
+        let name = name.trim().to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db(move |db| {
+                let inserted = db.execute(
+                    "INSERT OR IGNORE INTO workspaces (workspace_id, name, created_at, last_active_at)
+                     VALUES (?1, ?2, ?3, ?3)",
+                    params![workspace_id, name, now],
+                )?;
+                Ok(inserted > 0)
+            })
+            .await
+    }
+
+    /// Record a switch to the workspace. Returns false if it doesn't exist.
+    pub async fn touch_workspace(&self, workspace_id: &str) -> SqliteResult<bool> {
+        let workspace_id = workspace_id.to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        self.db
+            .with_db(move |db| {
+                Ok(db.execute(
+                    "UPDATE workspaces SET last_active_at = ?1 WHERE workspace_id = ?2",
+                    params![now, workspace_id],
+                )? > 0)
+            })
+            .await
+    }
+
+    /// The most recently switched-to workspace, restored at startup
+    pub async fn last_active_workspace(&self) -> SqliteResult<Option<String>> {
+        self.db
+            .with_db(|db| {
+                let mut stmt = db.prepare(
+                    "SELECT workspace_id FROM workspaces ORDER BY last_active_at DESC LIMIT 1",
+                )?;
+                let mut rows = stmt.query([])?;
+                match rows.next()? {
+                    Some(row) => Ok(Some(row.get(0)?)),
+                    None => Ok(None),
+                }
+            })
+            .await
+    }
+
+    /// Every workspace with the time its conversations were last scanned for
+    /// memory (None before the first scan)
+    pub async fn history_scan_marks(&self) -> SqliteResult<Vec<(String, Option<i64>)>> {
+        self.db
+            .with_db(|db| {
+                let mut stmt = db.prepare(
+                    "SELECT workspace_id, history_scanned_at FROM workspaces ORDER BY workspace_id",
+                )?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect()
+            })
+            .await
+    }
+
+    /// Record how far a workspace's conversations have been scanned for memory
+    pub async fn set_history_scanned_at(
+        &self,
+        workspace_id: &str,
+        scanned_at: i64,
+    ) -> SqliteResult<()> {
+        let workspace_id = workspace_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                db.execute(
+                    "UPDATE workspaces SET history_scanned_at = ?1 WHERE workspace_id = ?2",
+                    params![scanned_at, workspace_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// All workspaces with their conversation, run and cost rollups, by name
+    pub async fn list_workspaces(&self, active_id: &str) -> SqliteResult<Vec<WorkspaceInfo>> {
+        let active_id = active_id.to_string();
+
+        self.db
+            .with_db(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT w.workspace_id, w.name, w.created_at, w.last_active_at,
+                            (SELECT COUNT(*) FROM meta_conversations c
+                             WHERE c.workspace_id = w.workspace_id AND c.deleted_at IS NULL),
+                            (SELECT COUNT(*) FROM agent_runs r
+                             WHERE r.workspace_id = w.workspace_id AND r.deleted_at IS NULL),
+                            (SELECT COALESCE(SUM(r.total_cost_usd), 0) FROM agent_runs r
+                             WHERE r.workspace_id = w.workspace_id)
+                     FROM workspaces w
+                     ORDER BY w.name COLLATE NOCASE",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    let workspace_id: String = row.get(0)?;
+                    Ok(WorkspaceInfo {
+                        active: workspace_id == active_id,
+                        workspace_id,
+                        name: row.get(1)?,
+                        created_at: row.get(2)?,
+                        last_active_at: row.get(3)?,
+                        conversation_count: row.get(4)?,
+                        run_count: row.get(5)?,
+                        total_cost_usd: row.get(6)?,
+                    })
+                })?;
+                rows.collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::schema;
+
+    fn test_db() -> Arc<Mutex<Connection>> {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO agent_runs (agent_id, working_dir, source, status, started_at, last_activity, total_cost_usd, workspace_id)
+             VALUES ('run-1', '/acme', 'ui', 'completed', 1, 1, 1.5, 'acme'),
+                    ('run-2', '/old', 'ui', 'completed', 1, 1, 0.5, 'default')",
+            [],
+        )
+        .unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_workspace_rollups_and_last_active() {
+        let db = test_db();
+        let ops = WorkspaceOperations::new(&db);
+
+        assert!(ops.create_workspace("acme", "Acme").await.unwrap());
+        assert!(!ops.create_workspace("acme", "Acme again").await.unwrap());
+        assert_eq!(
+            ops.last_active_workspace().await.unwrap().as_deref(),
+            Some("acme")
+        );
+
+        let workspaces = ops.list_workspaces("acme").await.unwrap();
+        assert_eq!(workspaces.len(), 2);
+        let acme = &workspaces[0];
+        assert_eq!((acme.name.as_str(), acme.run_count), ("Acme", 1));
+        assert_eq!(acme.total_cost_usd, 1.5);
+        assert!(acme.active);
+        assert_eq!(workspaces[1].workspace_id, "default");
+        assert_eq!(workspaces[1].total_cost_usd, 0.5);
+
+        assert!(ops.touch_workspace("default").await.unwrap());
+        assert!(!ops.touch_workspace("missing").await.unwrap());
+        assert_eq!(
+            ops.last_active_workspace().await.unwrap().as_deref(),
+            Some("default")
+        );
+
+        ops.set_history_scanned_at("acme", 42).await.unwrap();
+        assert_eq!(
+            ops.history_scan_marks().await.unwrap(),
+            vec![
+                ("acme".to_string(), Some(42)),
+                ("default".to_string(), None)
+            ]
+        );
+    }
+}
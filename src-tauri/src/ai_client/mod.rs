@@ -16,6 +16,9 @@ pub use types::{
 };
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::commands::env_registry;
 
 /// Main AI client that wraps provider-specific implementations
 pub struct AIClient {
@@ -33,21 +36,35 @@ impl AIClient {
         Self { provider }
     }
 
+    /// Create an AIClient around an existing provider implementation
+    pub fn with_provider(provider: Arc<dyn AIProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// Use a different request timeout than the shared HTTP client's default
+    pub fn with_request_timeout(self, timeout: Duration) -> Self {
+        self.provider.set_request_timeout(timeout);
+        self
+    }
+
     /// Create an AIClient from environment variables
     ///
     /// Provider is inferred from the PRIMARY_MODEL setting:
     /// - If model starts with "gpt-" or "o1-" or "o3-" → use OpenAI
     /// - Otherwise → use Anthropic (claude-* or aliases like sonnet/opus/haiku)
     pub fn from_env() -> Result<Self, AIError> {
-        let anthropic_key = std::env::var("ANTHROPIC_API_KEY")
+        let anthropic_key = env_registry::ANTHROPIC_API_KEY
+            .var()
             .ok()
             .filter(|k| !k.is_empty());
-        let openai_key = std::env::var("OPENAI_API_KEY")
+        let openai_key = env_registry::OPENAI_API_KEY
+            .var()
             .ok()
             .filter(|k| !k.is_empty());
 
         // Get the primary model setting
-        let model = std::env::var("PRIMARY_MODEL")
+        let model = env_registry::PRIMARY_MODEL
+            .var()
             .ok()
             .filter(|m| !m.is_empty());
 
@@ -96,16 +113,16 @@ impl AIClient {
     }
 
     /// Check if a model name indicates OpenAI
-    fn is_openai_model(model: &str) -> bool {
+    pub fn is_openai_model(model: &str) -> bool {
         let lower = model.to_lowercase();
         lower.starts_with("gpt-") || lower.starts_with("o1-") || lower.starts_with("o3-")
     }
 
     /// Create an OpenAI-based client (preferred for orchestration)
     pub fn openai_from_env() -> Result<Self, AIError> {
-        if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
-            let model = std::env::var("OPENAI_ORCHESTRATOR_MODEL")
-                .or_else(|_| std::env::var("OPENAI_MODEL"))
+        if let Ok(api_key) = env_registry::OPENAI_API_KEY.var() {
+            let model = env_registry::OPENAI_ORCHESTRATOR_MODEL
+                .var()
                 .unwrap_or_else(|_| "gpt-4o".to_string());
             return Ok(Self::new(Provider::OpenAI { api_key, model }));
         }
@@ -119,14 +136,17 @@ impl AIClient {
     /// Uses LIGHT_TASK_MODEL env var (provider inferred from model name).
     /// Defaults to claude-haiku-4-5 for Claude, gpt-4o-mini for OpenAI.
     pub fn light_from_env() -> Result<Self, AIError> {
-        let anthropic_key = std::env::var("ANTHROPIC_API_KEY")
+        let anthropic_key = env_registry::ANTHROPIC_API_KEY
+            .var()
             .ok()
             .filter(|k| !k.is_empty());
-        let openai_key = std::env::var("OPENAI_API_KEY")
+        let openai_key = env_registry::OPENAI_API_KEY
+            .var()
             .ok()
             .filter(|k| !k.is_empty());
 
-        let model = std::env::var("LIGHT_TASK_MODEL")
+        let model = env_registry::LIGHT_TASK_MODEL
+            .var()
             .ok()
             .filter(|m| !m.is_empty());
 
@@ -178,14 +198,17 @@ impl AIClient {
     /// Uses SECURITY_MODEL env var (provider inferred from model name).
     /// Defaults to the main Claude model for Claude, gpt-4o for OpenAI.
     pub fn security_from_env() -> Result<Self, AIError> {
-        let anthropic_key = std::env::var("ANTHROPIC_API_KEY")
+        let anthropic_key = env_registry::ANTHROPIC_API_KEY
+            .var()
             .ok()
             .filter(|k| !k.is_empty());
-        let openai_key = std::env::var("OPENAI_API_KEY")
+        let openai_key = env_registry::OPENAI_API_KEY
+            .var()
             .ok()
             .filter(|k| !k.is_empty());
 
-        let model = std::env::var("SECURITY_MODEL")
+        let model = env_registry::SECURITY_MODEL
+            .var()
             .ok()
             .filter(|m| !m.is_empty());
 
@@ -232,13 +255,25 @@ impl AIClient {
         ))
     }
 
+    /// Record a finished request in the application metrics
+    fn record_request<T>(&self, started: Instant, result: &Result<T, AIError>) {
+        crate::metrics::global().record_ai_request(
+            self.provider.name(),
+            started.elapsed(),
+            result.is_ok(),
+        );
+    }
+
     /// Send messages with optional tool definitions
     pub async fn send_message_with_tools(
         &self,
         messages: Vec<Message>,
         tools: Vec<Tool>,
     ) -> Result<AIResponse, AIError> {
-        self.provider.send_message(messages, Some(tools)).await
+        let started = Instant::now();
+        let result = self.provider.send_message(messages, Some(tools)).await;
+        self.record_request(started, &result);
+        result
     }
 
     /// Send messages with a system prompt and tools
@@ -248,14 +283,21 @@ impl AIClient {
         messages: Vec<Message>,
         tools: Vec<Tool>,
     ) -> Result<AIResponse, AIError> {
-        self.provider
+        let started = Instant::now();
+        let result = self
+            .provider
             .send_message_with_system(system_prompt, messages, Some(tools))
-            .await
+            .await;
+        self.record_request(started, &result);
+        result
     }
 
     /// Send simple messages without tools
     pub async fn send_message(&self, messages: Vec<Message>) -> Result<AIResponse, AIError> {
-        self.provider.send_message(messages, None).await
+        let started = Instant::now();
+        let result = self.provider.send_message(messages, None).await;
+        self.record_request(started, &result);
+        result
     }
 
     /// Send rich messages with structured content blocks (for multi-turn tool conversations)
@@ -264,38 +306,26 @@ impl AIClient {
         messages: Vec<RichMessage>,
         tools: Vec<Tool>,
     ) -> Result<AIResponse, AIError> {
-        self.provider.send_rich_message(messages, Some(tools)).await
+        let started = Instant::now();
+        let result = self.provider.send_rich_message(messages, Some(tools)).await;
+        self.record_request(started, &result);
+        result
     }
 
     /// Send rich messages with a system prompt and tools
-    ///
-    /// The system prompt is prepended as a user/assistant exchange since the
-    /// rich message API doesn't have a dedicated system prompt parameter.
     pub async fn send_rich_message_with_system_and_tools(
         &self,
         system_prompt: &str,
         messages: Vec<RichMessage>,
         tools: Vec<Tool>,
     ) -> Result<AIResponse, AIError> {
-        let mut full_messages = vec![
-            RichMessage {
-                role: "user".to_string(),
-                content: RichMessageContent::Text(format!(
-                    "System instructions:\n\n{}",
-                    system_prompt
-                )),
-            },
-            RichMessage {
-                role: "assistant".to_string(),
-                content: RichMessageContent::Text(
-                    "I understand and will follow these instructions.".to_string(),
-                ),
-            },
-        ];
-        full_messages.extend(messages);
-        self.provider
-            .send_rich_message(full_messages, Some(tools))
-            .await
+        let started = Instant::now();
+        let result = self
+            .provider
+            .send_rich_message_with_system(system_prompt, messages, Some(tools))
+            .await;
+        self.record_request(started, &result);
+        result
     }
 
     /// Get the provider name (e.g., "Claude", "OpenAI")
@@ -1,7 +1,7 @@
-use reqwest::Client;
 use serde::Deserialize;
 
 use crate::ai_client::error::{check_response_status, AIError};
+use crate::http_client;
 
 /// Returns the latest recommended Claude model
 /// Priority: claude-sonnet-4-5 > claude-sonnet-4 > claude-3-5-sonnet
@@ -32,12 +32,10 @@ pub async fn list_claude_models(_api_key: &str) -> Result<Vec<String>, AIError>
 
 /// List available OpenAI models (fetches from API)
 pub async fn list_openai_models(api_key: &str) -> Result<Vec<String>, AIError> {
-    let http_client = Client::new();
-    let response = http_client
+    let request = http_client::shared()
         .get("https://api.openai.com/v1/models")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .send()
-        .await?;
+        .header("Authorization", format!("Bearer {}", api_key));
+    let response = http_client::send(request).await?;
 
     let response = check_response_status(response, "OpenAI").await?;
 
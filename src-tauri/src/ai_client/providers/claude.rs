@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -9,17 +10,41 @@ use crate::ai_client::error::{check_response_status, AIError};
 use crate::ai_client::providers::AIProvider;
 use crate::ai_client::types::{
     AIResponse, ContentBlock, Message, RichContentBlock, RichMessage, RichMessageContent, Tool,
-    Usage,
+    Usage, MEMORY_SECTION_HEADING,
 };
+use crate::commands::env_registry;
+use crate::http_client;
 
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const CLAUDE_API_VERSION: &str = "2023-06-01";
 
+/// Whether ANTHROPIC_PROMPT_CACHING allows cache_control hints (default: on)
+fn prompt_caching_enabled() -> bool {
+    env_registry::ANTHROPIC_PROMPT_CACHING
+        .var()
+        .map(|v| {
+            !matches!(
+                v.trim().to_lowercase().as_str(),
+                "0" | "false" | "no" | "off"
+            )
+        })
+        .unwrap_or(true)
+}
+
+/// Marks the end of a cacheable prefix
+fn ephemeral() -> Value {
+    json!({ "type": "ephemeral" })
+}
+
 /// Claude (Anthropic) provider implementation
 pub struct ClaudeProvider {
     api_key: String,
     model: String,
     http_client: Client,
+    /// Send prompt caching hints; turned off if the model rejects them
+    prompt_caching: AtomicBool,
+    /// Per-request timeout in milliseconds
+    request_timeout_ms: AtomicU64,
 }
 
 impl ClaudeProvider {
@@ -27,10 +52,96 @@ impl ClaudeProvider {
         Self {
             api_key,
             model,
-            http_client: Client::builder()
-                .timeout(Duration::from_secs(180))
-                .build()
-                .expect("Failed to build HTTP client"),
+            http_client: http_client::shared().clone(),
+            prompt_caching: AtomicBool::new(prompt_caching_enabled()),
+            request_timeout_ms: AtomicU64::new(
+                http_client::config().request_timeout.as_millis() as u64
+            ),
+        }
+    }
+
+    /// System prompt for the request body. With caching, the prompt and its
+    /// memory section become separate text blocks, each ending a cached prefix.
+    fn system_json(system_prompt: &str, cache: bool) -> Value {
+        if !cache {
+            return json!(system_prompt);
+        }
+        let (base, memory) = match system_prompt.find(MEMORY_SECTION_HEADING) {
+            Some(idx) => system_prompt.split_at(idx),
+            None => (system_prompt, ""),
+        };
+        let blocks: Vec<Value> = [base, memory]
+            .iter()
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| {
+                json!({
+                    "type": "text",
+                    "text": text,
+                    "cache_control": ephemeral()
+                })
+            })
+            .collect();
+        json!(blocks)
+    }
+
+    /// Tool definitions for the request body; with caching the last one ends a
+    /// cached prefix covering all of them
+    fn tools_json(tools: &[Tool], cache: bool) -> Value {
+        let mut tools = json!(tools);
+        if cache {
+            if let Some(last) = tools.as_array_mut().and_then(|t| t.last_mut()) {
+                last["cache_control"] = ephemeral();
+            }
+        }
+        tools
+    }
+
+    /// Build the request body
+    fn build_body(
+        &self,
+        messages: &Value,
+        system_prompt: Option<&str>,
+        tools: Option<&[Tool]>,
+        cache: bool,
+    ) -> Value {
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": 4096,
+            "messages": messages,
+        });
+
+        if let Some(system_prompt) = system_prompt {
+            body["system"] = Self::system_json(system_prompt, cache);
+        }
+        if let Some(tools) = tools {
+            body["tools"] = Self::tools_json(tools, cache);
+        }
+        body
+    }
+
+    /// Send a request, with prompt caching hints unless they are off. A model
+    /// that rejects them gets the request again without, and no hints after.
+    async fn send(
+        &self,
+        messages: Value,
+        system_prompt: Option<&str>,
+        tools: Option<Vec<Tool>>,
+    ) -> Result<AIResponse, AIError> {
+        let tools = tools.as_deref();
+        let cache = self.prompt_caching.load(Ordering::Relaxed);
+        let body = self.build_body(&messages, system_prompt, tools, cache);
+
+        match self.send_request(body).await {
+            Err(AIError::ApiError(e)) if cache && e.contains("cache_control") => {
+                eprintln!(
+                    "[LLM][Claude][{}] Prompt caching rejected, retrying without: {}",
+                    self.model, e
+                );
+                self.prompt_caching.store(false, Ordering::Relaxed);
+                let body = self.build_body(&messages, system_prompt, tools, false);
+                self.send_request(body).await
+            }
+            result => result,
         }
     }
 
@@ -102,6 +213,8 @@ impl ClaudeProvider {
             usage: Usage {
                 input_tokens: response.usage.input_tokens,
                 output_tokens: response.usage.output_tokens,
+                cache_creation_input_tokens: response.usage.cache_creation_input_tokens,
+                cache_read_input_tokens: response.usage.cache_read_input_tokens,
             },
         }
     }
@@ -109,15 +222,17 @@ impl ClaudeProvider {
     /// Build and send request to Claude API
     async fn send_request(&self, body: Value) -> Result<AIResponse, AIError> {
         eprintln!("[LLM][Claude][{}] Sending API request", self.model);
-        let response = self
+        let request = self
             .http_client
             .post(CLAUDE_API_URL)
+            .timeout(Duration::from_millis(
+                self.request_timeout_ms.load(Ordering::Relaxed),
+            ))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", CLAUDE_API_VERSION)
             .header("content-type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .json(&body);
+        let response = http_client::send(request).await?;
 
         let response = check_response_status(response, "Claude").await?;
 
@@ -126,9 +241,14 @@ impl ClaudeProvider {
             .await
             .map_err(|e| AIError::ParseError(format!("Failed to parse Claude response: {}", e)))?;
 
+        let usage = &claude_response.usage;
         eprintln!(
-            "[LLM][Claude][{}] Response received - tokens: in={}, out={}",
-            self.model, claude_response.usage.input_tokens, claude_response.usage.output_tokens
+            "[LLM][Claude][{}] Response received - tokens: in={}, out={}, cache_write={}, cache_read={}",
+            self.model,
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens
         );
         Ok(Self::convert_response(claude_response))
     }
@@ -141,17 +261,7 @@ impl AIProvider for ClaudeProvider {
         messages: Vec<Message>,
         tools: Option<Vec<Tool>>,
     ) -> Result<AIResponse, AIError> {
-        let mut body = json!({
-            "model": self.model,
-            "max_tokens": 4096,
-            "messages": messages,
-        });
-
-        if let Some(tools) = tools {
-            body["tools"] = json!(tools);
-        }
-
-        self.send_request(body).await
+        self.send(json!(messages), None, tools).await
     }
 
     async fn send_message_with_system(
@@ -160,18 +270,7 @@ impl AIProvider for ClaudeProvider {
         messages: Vec<Message>,
         tools: Option<Vec<Tool>>,
     ) -> Result<AIResponse, AIError> {
-        let mut body = json!({
-            "model": self.model,
-            "max_tokens": 4096,
-            "system": system_prompt,
-            "messages": messages,
-        });
-
-        if let Some(tools) = tools {
-            body["tools"] = json!(tools);
-        }
-
-        self.send_request(body).await
+        self.send(json!(messages), Some(system_prompt), tools).await
     }
 
     async fn send_rich_message(
@@ -180,18 +279,18 @@ impl AIProvider for ClaudeProvider {
         tools: Option<Vec<Tool>>,
     ) -> Result<AIResponse, AIError> {
         let claude_messages = Self::convert_rich_messages(&messages);
+        self.send(json!(claude_messages), None, tools).await
+    }
 
-        let mut body = json!({
-            "model": self.model,
-            "max_tokens": 4096,
-            "messages": claude_messages,
-        });
-
-        if let Some(tools) = tools {
-            body["tools"] = json!(tools);
-        }
-
-        self.send_request(body).await
+    async fn send_rich_message_with_system(
+        &self,
+        system_prompt: &str,
+        messages: Vec<RichMessage>,
+        tools: Option<Vec<Tool>>,
+    ) -> Result<AIResponse, AIError> {
+        let claude_messages = Self::convert_rich_messages(&messages);
+        self.send(json!(claude_messages), Some(system_prompt), tools)
+            .await
     }
 
     fn name(&self) -> &str {
@@ -201,6 +300,11 @@ impl AIProvider for ClaudeProvider {
     fn model(&self) -> &str {
         &self.model
     }
+
+    fn set_request_timeout(&self, timeout: Duration) {
+        self.request_timeout_ms
+            .store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
 }
 
 // Internal response structures for Claude API
@@ -221,4 +325,66 @@ struct ClaudeAPIResponse {
 struct ClaudeUsage {
     input_tokens: u32,
     output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: u32,
+    #[serde(default)]
+    cache_read_input_tokens: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: format!("{} tool", name),
+            input_schema: json!({ "type": "object" }),
+        }
+    }
+
+    #[test]
+    fn test_cache_breakpoints_on_system_memory_and_tools() {
+        let provider = ClaudeProvider::new("key".to_string(), "claude-test".to_string());
+        let system = format!(
+            "You are the commander.\n\n{}\nPrefers tabs",
+            MEMORY_SECTION_HEADING
+        );
+        let tools = vec![tool("ListAgents"), tool("Sleep")];
+        let messages = json!([{ "role": "user", "content": "hi" }]);
+
+        let body = provider.build_body(&messages, Some(system.as_str()), Some(&tools), true);
+        let blocks = body["system"].as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["text"], "You are the commander.\n\n");
+        assert!(blocks[1]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with(MEMORY_SECTION_HEADING));
+        assert!(blocks.iter().all(|b| b["cache_control"] == ephemeral()));
+        assert!(body["tools"][0].get("cache_control").is_none());
+        assert_eq!(body["tools"][1]["cache_control"], ephemeral());
+
+        // Without memory there is one block; without caching the body is unchanged
+        let body = provider.build_body(&messages, Some("Base"), None, true);
+        assert_eq!(body["system"].as_array().unwrap().len(), 1);
+        let body = provider.build_body(&messages, Some(system.as_str()), Some(&tools), false);
+        assert_eq!(body["system"], json!(system));
+        assert_eq!(body["tools"], json!(tools));
+    }
+
+    #[test]
+    fn test_usage_without_cache_fields() {
+        let usage: ClaudeUsage =
+            serde_json::from_value(json!({ "input_tokens": 12, "output_tokens": 3 })).unwrap();
+        assert_eq!(usage.cache_read_input_tokens, 0);
+        let usage: ClaudeUsage = serde_json::from_value(json!({
+            "input_tokens": 12,
+            "output_tokens": 3,
+            "cache_creation_input_tokens": 0,
+            "cache_read_input_tokens": 4000
+        }))
+        .unwrap();
+        assert_eq!(usage.cache_read_input_tokens, 4000);
+    }
 }
@@ -0,0 +1,315 @@
+// Mock provider - Scripted responses for tests and offline development
+//
+// Replays a queue of AIResponses in order, so the meta-agent's tool loop can
+// be driven through tool calls without network access or an API key. Each
+// request is checked the way the real APIs check it: every tool_use in an
+// assistant message must be answered by a tool_result in the next user
+// message, and every tool_result must answer one of those tool_uses. Once the
+// script runs out the provider replies with plain text, which ends the loop.
+//
+// Compiled for tests and with the `mock-provider` feature only.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::ai_client::error::AIError;
+use crate::ai_client::providers::AIProvider;
+use crate::ai_client::types::{
+    AIResponse, ContentBlock, Message, RichContentBlock, RichMessage, RichMessageContent, Tool,
+    Usage,
+};
+use crate::utils::string::truncate_with_ellipsis;
+
+const MOCK_MODEL: &str = "mock";
+
+/// Reply once the script is used up
+const SCRIPT_EXHAUSTED_REPLY: &str = "Mock script finished.";
+
+/// Characters of text kept per transcript line
+const TRANSCRIPT_TEXT_CHARS: usize = 60;
+
+/// Provider that returns scripted responses instead of calling an API
+pub struct MockProvider {
+    script: Mutex<VecDeque<AIResponse>>,
+    requests: Mutex<Vec<Vec<RichMessage>>>,
+}
+
+impl MockProvider {
+    pub fn new(script: Vec<AIResponse>) -> Self {
+        Self {
+            script: Mutex::new(script.into()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// A response with the given content blocks
+    pub fn response(content: Vec<ContentBlock>) -> AIResponse {
+        let uses_tools = content
+            .iter()
+            .any(|b| matches!(b, ContentBlock::ToolUse { .. }));
+        AIResponse {
+            id: format!("msg_mock_{}", uuid::Uuid::new_v4().simple()),
+            role: "assistant".to_string(),
+            content,
+            model: MOCK_MODEL.to_string(),
+            stop_reason: Some(if uses_tools { "tool_use" } else { "end_turn" }.to_string()),
+            usage: Usage {
+                input_tokens: 10,
+                output_tokens: 5,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// A plain text response
+    pub fn text(text: &str) -> AIResponse {
+        Self::response(vec![ContentBlock::Text {
+            text: text.to_string(),
+        }])
+    }
+
+    /// A response making a single tool call
+    pub fn tool_use(id: &str, name: &str, input: Value) -> AIResponse {
+        Self::response(vec![ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input,
+        }])
+    }
+
+    /// Scripted responses not yet returned
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap().len()
+    }
+
+    /// Every request received so far, in order
+    pub fn requests(&self) -> Vec<Vec<RichMessage>> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// One line per content block, for comparing conversations in tests:
+    /// `user: "Hi"`, `assistant: tool_use UpdateUser#toolu_1`,
+    /// `user: tool_result toolu_1`
+    pub fn transcript(messages: &[RichMessage]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for message in messages {
+            match &message.content {
+                RichMessageContent::Text(text) => {
+                    lines.push(format!("{}: {:?}", message.role, transcript_text(text)));
+                }
+                RichMessageContent::Blocks(blocks) => {
+                    for block in blocks {
+                        let line = match block {
+                            RichContentBlock::Text { text } => {
+                                format!("{:?}", transcript_text(text))
+                            }
+                            RichContentBlock::Image { .. } => "image".to_string(),
+                            RichContentBlock::ToolUse { id, name, .. } => {
+                                format!("tool_use {}#{}", name, id)
+                            }
+                            RichContentBlock::ToolResult {
+                                tool_use_id,
+                                is_error,
+                                ..
+                            } => match is_error {
+                                Some(true) => format!("tool_result {} (error)", tool_use_id),
+                                _ => format!("tool_result {}", tool_use_id),
+                            },
+                        };
+                        lines.push(format!("{}: {}", message.role, line));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    fn next_response(&self, messages: Vec<RichMessage>) -> Result<AIResponse, AIError> {
+        check_tool_pairs(&messages).map_err(AIError::ApiError)?;
+        self.requests.lock().unwrap().push(messages);
+        Ok(self
+            .script
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Self::text(SCRIPT_EXHAUSTED_REPLY)))
+    }
+}
+
+fn transcript_text(text: &str) -> String {
+    truncate_with_ellipsis(
+        text.lines().next().unwrap_or_default(),
+        TRANSCRIPT_TEXT_CHARS,
+    )
+}
+
+fn to_rich(messages: Vec<Message>) -> Vec<RichMessage> {
+    messages
+        .into_iter()
+        .map(|m| RichMessage {
+            role: m.role,
+            content: RichMessageContent::Text(m.content),
+        })
+        .collect()
+}
+
+/// Reject conversations the real APIs would reject for tool pairing
+fn check_tool_pairs(messages: &[RichMessage]) -> Result<(), String> {
+    let mut open: HashSet<&str> = HashSet::new();
+
+    for message in messages {
+        let blocks: &[RichContentBlock] = match &message.content {
+            RichMessageContent::Blocks(blocks) => blocks,
+            RichMessageContent::Text(_) => &[],
+        };
+
+        if message.role == "user" {
+            for block in blocks {
+                if let RichContentBlock::ToolResult { tool_use_id, .. } = block {
+                    if !open.remove(tool_use_id.as_str()) {
+                        return Err(format!(
+                            "tool_result {} does not answer a tool_use in the previous message",
+                            tool_use_id
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(id) = open.iter().next() {
+            return Err(format!("tool_use {} was not answered by a tool_result", id));
+        }
+
+        if message.role == "assistant" {
+            for block in blocks {
+                if let RichContentBlock::ToolUse { id, .. } = block {
+                    open.insert(id);
+                }
+            }
+        }
+    }
+
+    match open.iter().next() {
+        Some(id) => Err(format!("tool_use {} was not answered by a tool_result", id)),
+        None => Ok(()),
+    }
+}
+
+#[async_trait]
+impl AIProvider for MockProvider {
+    async fn send_message(
+        &self,
+        messages: Vec<Message>,
+        _tools: Option<Vec<Tool>>,
+    ) -> Result<AIResponse, AIError> {
+        self.next_response(to_rich(messages))
+    }
+
+    async fn send_message_with_system(
+        &self,
+        _system_prompt: &str,
+        messages: Vec<Message>,
+        _tools: Option<Vec<Tool>>,
+    ) -> Result<AIResponse, AIError> {
+        self.next_response(to_rich(messages))
+    }
+
+    async fn send_rich_message(
+        &self,
+        messages: Vec<RichMessage>,
+        _tools: Option<Vec<Tool>>,
+    ) -> Result<AIResponse, AIError> {
+        self.next_response(messages)
+    }
+
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn model(&self) -> &str {
+        MOCK_MODEL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn blocks(role: &str, blocks: Vec<RichContentBlock>) -> RichMessage {
+        RichMessage {
+            role: role.to_string(),
+            content: RichMessageContent::Blocks(blocks),
+        }
+    }
+
+    fn tool_use(id: &str) -> RichContentBlock {
+        RichContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "UpdateUser".to_string(),
+            input: json!({}),
+        }
+    }
+
+    fn tool_result(id: &str) -> RichContentBlock {
+        RichContentBlock::ToolResult {
+            tool_use_id: id.to_string(),
+            content: "{}".to_string(),
+            is_error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_script_then_falls_back_to_text() {
+        let provider = MockProvider::new(vec![MockProvider::tool_use(
+            "toolu_1",
+            "UpdateUser",
+            json!({"message": "hi"}),
+        )]);
+
+        let first = provider.send_rich_message(Vec::new(), None).await.unwrap();
+        assert_eq!(first.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(provider.remaining(), 0);
+
+        let second = provider.send_rich_message(Vec::new(), None).await.unwrap();
+        assert!(matches!(
+            &second.content[0],
+            ContentBlock::Text { text } if text == SCRIPT_EXHAUSTED_REPLY
+        ));
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unmatched_tool_pairs() {
+        let provider = MockProvider::new(Vec::new());
+
+        let answered = vec![
+            blocks("assistant", vec![tool_use("toolu_1"), tool_use("toolu_2")]),
+            blocks("user", vec![tool_result("toolu_1"), tool_result("toolu_2")]),
+        ];
+        assert!(provider.send_rich_message(answered, None).await.is_ok());
+
+        let unanswered = vec![
+            blocks("assistant", vec![tool_use("toolu_1"), tool_use("toolu_2")]),
+            blocks("user", vec![tool_result("toolu_1")]),
+        ];
+        let err = provider
+            .send_rich_message(unanswered, None)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("tool_use toolu_2 was not answered"));
+
+        let stray = vec![blocks("user", vec![tool_result("toolu_9")])];
+        let err = provider.send_rich_message(stray, None).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("tool_result toolu_9 does not answer"));
+
+        // Rejected requests aren't recorded
+        assert_eq!(provider.requests().len(), 1);
+    }
+}
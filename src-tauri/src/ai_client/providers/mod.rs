@@ -1,12 +1,17 @@
 pub mod claude;
+#[cfg(any(test, feature = "mock-provider"))]
+pub mod mock;
 pub mod openai;
 
 pub use claude::ClaudeProvider;
+#[cfg(any(test, feature = "mock-provider"))]
+pub use mock::MockProvider;
 pub use openai::OpenAIProvider;
 
 use crate::ai_client::error::AIError;
-use crate::ai_client::types::{AIResponse, Message, RichMessage, Tool};
+use crate::ai_client::types::{AIResponse, Message, RichMessage, RichMessageContent, Tool};
 use async_trait::async_trait;
+use std::time::Duration;
 
 /// Trait for AI provider implementations
 #[async_trait]
@@ -33,9 +38,40 @@ pub trait AIProvider: Send + Sync {
         tools: Option<Vec<Tool>>,
     ) -> Result<AIResponse, AIError>;
 
+    /// Send rich messages with a system prompt
+    ///
+    /// By default the system prompt is prepended as a user/assistant exchange.
+    async fn send_rich_message_with_system(
+        &self,
+        system_prompt: &str,
+        messages: Vec<RichMessage>,
+        tools: Option<Vec<Tool>>,
+    ) -> Result<AIResponse, AIError> {
+        let mut full_messages = vec![
+            RichMessage {
+                role: "user".to_string(),
+                content: RichMessageContent::Text(format!(
+                    "System instructions:\n\n{}",
+                    system_prompt
+                )),
+            },
+            RichMessage {
+                role: "assistant".to_string(),
+                content: RichMessageContent::Text(
+                    "I understand and will follow these instructions.".to_string(),
+                ),
+            },
+        ];
+        full_messages.extend(messages);
+        self.send_rich_message(full_messages, tools).await
+    }
+
     /// Get the provider name (e.g., "Claude", "OpenAI")
     fn name(&self) -> &str;
 
     /// Get the model being used
     fn model(&self) -> &str;
+
+    /// Override the request timeout for later calls (e.g. long orchestrator turns)
+    fn set_request_timeout(&self, _timeout: Duration) {}
 }
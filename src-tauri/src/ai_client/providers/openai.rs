@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -12,6 +13,7 @@ use crate::ai_client::types::{
     AIResponse, ContentBlock, Message, RichContentBlock, RichMessage, RichMessageContent, Tool,
     Usage,
 };
+use crate::http_client;
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
 
@@ -20,6 +22,8 @@ pub struct OpenAIProvider {
     api_key: String,
     model: String,
     http_client: Client,
+    /// Per-request timeout in milliseconds
+    request_timeout_ms: AtomicU64,
 }
 
 impl OpenAIProvider {
@@ -27,10 +31,10 @@ impl OpenAIProvider {
         Self {
             api_key,
             model,
-            http_client: Client::builder()
-                .timeout(Duration::from_secs(180))
-                .build()
-                .expect("Failed to build HTTP client"),
+            http_client: http_client::shared().clone(),
+            request_timeout_ms: AtomicU64::new(
+                http_client::config().request_timeout.as_millis() as u64
+            ),
         }
     }
 
@@ -236,6 +240,7 @@ impl OpenAIProvider {
             usage: Usage {
                 input_tokens: response.usage.prompt_tokens,
                 output_tokens: response.usage.completion_tokens,
+                ..Default::default()
             },
         })
     }
@@ -248,13 +253,16 @@ impl OpenAIProvider {
             self.model,
             body_str.len()
         );
-        let response = self
+        let request = self
             .http_client
             .post(OPENAI_API_URL)
+            .timeout(Duration::from_millis(
+                self.request_timeout_ms.load(Ordering::Relaxed),
+            ))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("content-type", "application/json")
-            .body(body_str)
-            .send()
+            .body(body_str);
+        let response = http_client::send(request)
             .await
             .map_err(|e| {
                 eprintln!(
@@ -372,6 +380,11 @@ impl AIProvider for OpenAIProvider {
     fn model(&self) -> &str {
         &self.model
     }
+
+    fn set_request_timeout(&self, timeout: Duration) {
+        self.request_timeout_ms
+            .store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
 }
 
 // Internal response structures for OpenAI API
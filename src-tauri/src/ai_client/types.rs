@@ -89,11 +89,37 @@ pub struct AIResponse {
     pub usage: Usage,
 }
 
+/// Heading of the memory section appended to the meta-agent's system prompt.
+/// Claude caches the prompt before it separately, since memory changes more often.
+pub const MEMORY_SECTION_HEADING: &str = "## Your Persistent Memory";
+
 /// Token usage statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
+    /// Uncached input tokens
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Input tokens written to the prompt cache (Claude only)
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    /// Input tokens read from the prompt cache (Claude only)
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
+}
+
+impl Usage {
+    /// All input tokens, cached or not
+    pub fn total_input_tokens(&self) -> u32 {
+        self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+
+    /// Add another response's usage to this one
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
 }
 
 /// Provider configuration
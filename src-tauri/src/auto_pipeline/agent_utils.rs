@@ -3,7 +3,9 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::agent_manager::rate_limit;
 use crate::agent_manager::AgentManager;
+use crate::events::AppEventEmitter;
 use crate::types::AgentStatus;
 
 use super::types::StepOutput;
@@ -37,7 +39,51 @@ pub fn extract_json_from_markdown(text: &str) -> String {
     text.to_string()
 }
 
-/// Wait for an agent to complete (reach WaitingForInput, Stopped, or Error status)
+/// Times a prompt is re-sent after its turn hit a Claude usage or rate limit
+const MAX_RATE_LIMIT_RESENDS: usize = 5;
+
+/// Send a prompt and wait for the agent to complete it.
+///
+/// While Claude agents are rate-limited the prompt is held until the window
+/// passes, and a turn that ends on a limit is re-sent afterwards instead of
+/// failing the step.
+pub async fn send_prompt_and_wait(
+    agent_id: &str,
+    prompt: &str,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn AppEventEmitter>,
+) -> Result<(), String> {
+    for _ in 0..=MAX_RATE_LIMIT_RESENDS {
+        rate_limit::wait_for_window().await;
+
+        // Note: No security_monitor for pipeline automated prompts
+        {
+            let manager = agent_manager.lock().await;
+            manager
+                .send_prompt(agent_id, prompt, Some(app_handle.clone()), None)
+                .await?;
+        }
+
+        wait_for_agent_completion(agent_id, agent_manager.clone()).await?;
+
+        let rate_limited = agent_manager
+            .lock()
+            .await
+            .get_agent_info(agent_id)
+            .await
+            .is_some_and(|info| info.status == AgentStatus::RateLimited);
+        if !rate_limited {
+            return Ok(());
+        }
+    }
+
+    Err(format!(
+        "Agent was still rate-limited after {} retries",
+        MAX_RATE_LIMIT_RESENDS
+    ))
+}
+
+/// Wait for an agent to complete (reach WaitingForInput, RateLimited, Stopped, or Error status)
 pub async fn wait_for_agent_completion(
     agent_id: &str,
     agent_manager: Arc<Mutex<AgentManager>>,
@@ -51,6 +97,7 @@ pub async fn wait_for_agent_completion(
         if let Some(agent) = agents_list.iter().find(|a| a.id == agent_id) {
             match agent.status {
                 AgentStatus::WaitingForInput => break,
+                AgentStatus::RateLimited => break,
                 AgentStatus::Stopped => break,
                 AgentStatus::Error => {
                     return Err("Agent encountered an error".to_string());
@@ -65,13 +112,43 @@ pub async fn wait_for_agent_completion(
     Ok(())
 }
 
+/// Wait for an agent that was already idle to finish the turn started by a new prompt.
+///
+/// Its status stays WaitingForInput across turns, so this waits for the
+/// pending-input flag (cleared by send_prompt) to be set again instead.
+pub async fn wait_for_agent_reply(
+    agent_id: &str,
+    agent_manager: Arc<Mutex<AgentManager>>,
+) -> Result<(), String> {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+        let manager = agent_manager.lock().await;
+        let agents = manager.agents.lock().await;
+        let agent = agents
+            .get(agent_id)
+            .ok_or_else(|| "Agent not found".to_string())?;
+        match agent.info.status {
+            AgentStatus::Stopped => return Err("Agent stopped".to_string()),
+            AgentStatus::Error => return Err("Agent encountered an error".to_string()),
+            AgentStatus::RateLimited => {
+                return Err("Agent hit a Claude usage or rate limit".to_string())
+            }
+            _ => {}
+        }
+        if *agent.pending_input.lock().await {
+            return Ok(());
+        }
+    }
+}
+
 /// Extract output from an agent's result
 pub async fn extract_agent_output(
     agent_id: &str,
     agent_manager: Arc<Mutex<AgentManager>>,
 ) -> Result<StepOutput, String> {
     let manager = agent_manager.lock().await;
-    let outputs = manager.get_agent_outputs(agent_id, 100).await?;
+    let outputs = manager.get_agent_outputs(agent_id, 100, None).await?;
 
     // Try to find a result message first
     if let Some(result_output) = outputs.iter().rev().find(|o| o.output_type == "result") {
@@ -0,0 +1,270 @@
+// Build diff - What the build agent changed, for the verification agent
+//
+// The build agent's output is mostly tool chatter and build logs; what the
+// verifier needs is the code change. After each build the working directory
+// is diffed against HEAD at pipeline start (tracked changes, commits made by
+// the agent, and new untracked files), capped per file so one huge file can't
+// crowd out the rest, and handed to the verifier with a short build summary.
+//
+// Working directories that aren't git repositories fall back to the raw build
+// output, as does every pipeline when PIPELINE_VERIFY_BUILD_OUTPUT is set.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::Command;
+
+use crate::commands::env_registry;
+use crate::generated_files::{self, COMMANDER_DIR};
+use crate::github::{detect_git_repo, get_current_commit};
+use crate::utils::string::truncate_utf8;
+
+/// Maximum characters of diff given to the verifier
+const MAX_DIFF_CHARS: usize = 60_000;
+
+/// Maximum characters of a single file's diff
+const MAX_FILE_DIFF_CHARS: usize = 15_000;
+
+/// Characters of build output kept as the summary (taken from the end, where
+/// the build agent reports what it did)
+const BUILD_SUMMARY_CHARS: usize = 3_000;
+
+/// What the verification agent was given as the implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationInputMode {
+    /// Diff against the pipeline's base commit plus a short build summary
+    Diff,
+    /// The build agent's raw output (non-git directories, or forced by the flag)
+    BuildOutput,
+}
+
+/// Whether PIPELINE_VERIFY_BUILD_OUTPUT forces the old behavior
+fn build_output_forced() -> bool {
+    env_registry::PIPELINE_VERIFY_BUILD_OUTPUT.flag()
+}
+
+/// Commit to diff builds against: HEAD when the pipeline starts.
+///
+/// None when the directory isn't a git repository (or has no commits yet).
+pub fn base_commit(working_dir: &str) -> Option<String> {
+    if !detect_git_repo(working_dir) {
+        return None;
+    }
+    get_current_commit(working_dir)
+}
+
+/// Build the implementation section of the verification prompt
+pub fn verification_input(
+    working_dir: &str,
+    base_commit: Option<&str>,
+    build_output: &str,
+) -> (VerificationInputMode, String) {
+    let diff = match base_commit {
+        Some(base) if !build_output_forced() => collect_diff(working_dir, base),
+        _ => None,
+    };
+    let Some(diff) = diff else {
+        return (VerificationInputMode::BuildOutput, build_output.to_string());
+    };
+
+    let base = base_commit.unwrap_or_default();
+    let short_base = truncate_utf8(base, 12);
+    let diff_section = if diff.trim().is_empty() {
+        format!(
+            "The working directory has no changes against {} - the build agent changed nothing.",
+            short_base
+        )
+    } else {
+        format!("```diff\n{}\n```", cap_diff(&diff, MAX_DIFF_CHARS))
+    };
+
+    let text = format!(
+        "### Build summary\n{}\n\n### Changes since {}\n{}\n\nRead the changed files in full where the diff isn't enough context.",
+        build_summary(build_output),
+        short_base,
+        diff_section
+    );
+    (VerificationInputMode::Diff, text)
+}
+
+/// Diff of the working directory against `base`, including untracked files.
+///
+/// Files the pipeline itself generated (skills, scratchpads, `.commander/`)
+/// are left out. None if git fails.
+fn collect_diff(working_dir: &str, base: &str) -> Option<String> {
+    let mut diff = run_git(working_dir, &["diff", "--no-color", base])?;
+
+    let generated: HashSet<String> = generated_files::list_generated_files(working_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    let untracked = run_git(working_dir, &["ls-files", "--others", "--exclude-standard"])?;
+    for path in untracked.lines() {
+        if generated.contains(path) || path.starts_with(&format!("{}/", COMMANDER_DIR)) {
+            continue;
+        }
+        // --no-index exits 1 when the files differ, which is always the case here
+        let output = Command::new("git")
+            .args(["diff", "--no-color", "--no-index", "--", "/dev/null", path])
+            .current_dir(working_dir)
+            .output()
+            .ok()?;
+        diff.push_str(&String::from_utf8_lossy(&output.stdout));
+    }
+
+    Some(diff)
+}
+
+fn run_git(working_dir: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(working_dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Cap a diff at `max_chars`, keeping whole files where possible.
+///
+/// The diff is split into one chunk per file. A chunk over MAX_FILE_DIFF_CHARS
+/// is cut short; files that no longer fit are listed by name so the verifier
+/// knows to read them.
+fn cap_diff(diff: &str, max_chars: usize) -> String {
+    let mut kept = String::new();
+    let mut omitted = Vec::new();
+
+    for chunk in split_file_chunks(diff) {
+        let chunk = if chunk.len() > MAX_FILE_DIFF_CHARS {
+            format!(
+                "{}\n... (diff of this file truncated)\n",
+                truncate_utf8(chunk, MAX_FILE_DIFF_CHARS)
+            )
+        } else {
+            chunk.to_string()
+        };
+        if kept.len() + chunk.len() > max_chars {
+            omitted.push(chunk_file_name(&chunk));
+            continue;
+        }
+        kept.push_str(&chunk);
+    }
+
+    let mut capped = kept.trim_end().to_string();
+    if !omitted.is_empty() {
+        capped.push_str(&format!(
+            "\n\n... diff too large; {} more changed file(s) not shown: {}",
+            omitted.len(),
+            omitted.join(", ")
+        ));
+    }
+    capped
+}
+
+/// Split a diff into its per-file sections ("diff --git ..." up to the next one)
+fn split_file_chunks(diff: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = diff
+        .match_indices("diff --git ")
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || diff.as_bytes()[i - 1] == b'\n')
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).copied().unwrap_or(diff.len());
+            &diff[start..end]
+        })
+        .filter(|chunk| !chunk.trim().is_empty())
+        .collect()
+}
+
+/// File name from a chunk's "diff --git a/<path> b/<path>" header
+fn chunk_file_name(chunk: &str) -> String {
+    let header = chunk.lines().next().unwrap_or_default();
+    header
+        .rsplit(" b/")
+        .next()
+        .filter(|_| header.starts_with("diff --git "))
+        .unwrap_or("(unknown file)")
+        .to_string()
+}
+
+/// Last BUILD_SUMMARY_CHARS of the build output
+fn build_summary(build_output: &str) -> String {
+    let trimmed = build_output.trim();
+    if trimmed.len() <= BUILD_SUMMARY_CHARS {
+        return trimmed.to_string();
+    }
+    let cut = trimmed.len() - BUILD_SUMMARY_CHARS;
+    let start = (cut..trimmed.len())
+        .find(|&i| trimmed.is_char_boundary(i))
+        .unwrap_or(trimmed.len());
+    format!("...{}", &trimmed[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_diff(path: &str, body_len: usize) -> String {
+        format!(
+            "diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n@@ -1 +1 @@\n+{1}\n",
+            path,
+            "x".repeat(body_len)
+        )
+    }
+
+    #[test]
+    fn test_cap_diff_keeps_whole_files_and_lists_the_rest() {
+        let diff = format!(
+            "{}{}{}",
+            file_diff("src/a.rs", 100),
+            file_diff("src/b.rs", 500),
+            file_diff("src/c.rs", 50)
+        );
+
+        assert_eq!(cap_diff(&diff, 10_000), diff.trim_end());
+
+        let capped = cap_diff(&diff, 400);
+        assert!(capped.contains("a/src/a.rs"));
+        assert!(!capped.contains("a/src/b.rs"));
+        assert!(capped.contains("a/src/c.rs"));
+        assert!(capped.ends_with("1 more changed file(s) not shown: src/b.rs"));
+    }
+
+    #[test]
+    fn test_cap_diff_truncates_huge_files() {
+        let diff = file_diff("big.json", MAX_FILE_DIFF_CHARS * 2);
+        let capped = cap_diff(&diff, MAX_DIFF_CHARS);
+        assert!(capped.len() < MAX_FILE_DIFF_CHARS + 100);
+        assert!(capped.ends_with("(diff of this file truncated)"));
+    }
+
+    #[test]
+    fn test_build_output_fallback_without_git() {
+        let dir = tempfile::tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        assert_eq!(base_commit(working_dir), None);
+
+        let (mode, text) = verification_input(working_dir, None, "cargo build ok");
+        assert_eq!(mode, VerificationInputMode::BuildOutput);
+        assert_eq!(text, "cargo build ok");
+    }
+
+    #[test]
+    fn test_build_summary_keeps_the_end() {
+        let output = format!("{}Done: added the column.", "log line\n".repeat(1000));
+        let summary = build_summary(&output);
+        assert!(summary.starts_with("..."));
+        assert!(summary.ends_with("Done: added the column."));
+        assert!(summary.len() <= BUILD_SUMMARY_CHARS + 3);
+    }
+}
@@ -0,0 +1,93 @@
+// Human requests - questions the orchestrator asks the user
+//
+// Instead of giving up, the orchestrator can call request_human_help. The
+// pipeline then pauses in AwaitingHuman, `auto_pipeline:needs-human` is
+// emitted with the question and recent agent output, and the orchestrator
+// waits until answer_pipeline_human_request delivers the answer.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::types::AgentOutputEvent;
+use crate::utils::string::truncate_with_ellipsis;
+
+/// Agent outputs included with a question
+const EXCERPT_LIMIT: usize = 3;
+
+/// Each excerpt is cut to this many bytes
+const EXCERPT_BYTE_LIMIT: usize = 1000;
+
+/// A question the orchestrator is waiting on the user to answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HumanRequest {
+    pub pipeline_id: String,
+    pub question: String,
+    /// What the orchestrator tried and why it needs the user
+    pub context: String,
+    /// Latest output of the agent in the current phase
+    pub excerpts: Vec<String>,
+    pub requested_at: i64,
+}
+
+/// Answer channels of the pipelines currently waiting, by pipeline ID
+pub type HumanAnswers = Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>;
+
+/// The last few text outputs, oldest first
+pub fn output_excerpts(outputs: &[AgentOutputEvent]) -> Vec<String> {
+    let mut excerpts: Vec<String> = outputs
+        .iter()
+        .rev()
+        .filter(|o| o.output_type == "text" && !o.content.trim().is_empty())
+        .take(EXCERPT_LIMIT)
+        .map(|o| truncate_with_ellipsis(o.content.trim(), EXCERPT_BYTE_LIMIT))
+        .collect();
+    excerpts.reverse();
+    excerpts
+}
+
+/// The message that hands the user's answer to the orchestrator
+pub fn answer_message(question: &str, answer: &str) -> String {
+    format!(
+        "The user answered your question.\n\nQuestion: {}\n\nAnswer: {}\n\nContinue the pipeline from where you left off.",
+        question, answer
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(output_type: &str, content: &str) -> AgentOutputEvent {
+        AgentOutputEvent {
+            agent_id: "agent-1".to_string(),
+            output_type: output_type.to_string(),
+            content: content.to_string(),
+            parsed_json: None,
+            metadata: None,
+            session_id: None,
+            uuid: None,
+            parent_tool_use_id: None,
+            subtype: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_excerpts_are_latest_text_outputs_in_order() {
+        let outputs = vec![
+            output("text", "one"),
+            output("text", "two"),
+            output("tool_use", "Bash"),
+            output("text", "three"),
+            output("text", "  "),
+            output("text", &"x".repeat(2000)),
+        ];
+        let excerpts = output_excerpts(&outputs);
+        assert_eq!(excerpts.len(), 3);
+        assert_eq!(excerpts[0], "two");
+        assert_eq!(excerpts[1], "three");
+        assert_eq!(excerpts[2].len(), EXCERPT_BYTE_LIMIT + 3);
+    }
+}
@@ -6,8 +6,11 @@
 // 3. Building - Implements the solution based on the plan and Q&A
 // 4. Verifying - Reviews implementation and generates verification report
 // 5. Orchestrator decides: complete | iterate (back to build) | replan (back to plan)
+//    or pauses to ask the user a question (request_human_help)
 
 mod agent_utils;
+pub mod build_diff;
+pub mod human_requests;
 mod orchestrator;
 pub mod prompts;
 pub mod steps;
@@ -16,10 +19,15 @@ mod types;
 // Skill synthesis and enhanced pipeline modules
 pub mod orchestrator_agent;
 pub mod orchestrator_tools;
+pub mod prompt_templates;
 pub mod replay;
+pub mod retention;
+pub mod scratchpad;
 pub mod skill_matcher;
 pub mod state_machine;
+pub mod step_progress;
 pub mod task_analyzer;
+pub mod tool_health;
 
 pub use orchestrator::{DecisionResult, Orchestrator, OrchestratorDecision, RefinedTask};
 pub use orchestrator_agent::{OrchestratorAction, OrchestratorAgent};
@@ -28,17 +36,26 @@ pub use replay::{
     ChangeType, ExecutionLogEntry, ExecutionLogEntryType, FailureAnalysis, FailureCategory,
     RecommendedChange, ReplayFile, VerificationIssue, VerificationReport, VerificationStatus,
 };
+pub use retention::PipelineListFilter;
 pub use skill_matcher::{match_instructions, match_instructions_simple, MatchResult};
 pub use state_machine::{is_valid_transition, PipelineState, StateTransition};
 pub use task_analyzer::{analyze_task, TaskAnalysis};
+pub use tool_health::{HealthChange, PipelineHealth};
 pub use types::{
-    AutoPipeline, AutoPipelineStep, EnhancedAutoPipeline, IterationRecord, StepOutput, StepRole,
-    StepStatus,
+    ArtifactCounts, AutoPipeline, AutoPipelineStep, EnhancedAutoPipeline, IterationRecord,
+    PromptTemplateUsage, StepOutput, StepRetryRecord, StepRole, StepStatus,
+    DEFAULT_STEP_MAX_RETRIES, MAX_STEP_RETRIES,
 };
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::agent_manager::AgentManager;
+use crate::agent_runs_db::{AgentRunsDB, PipelineHumanRequestRecord};
+use crate::directory_locks;
+use crate::events::AppEventEmitter;
 
 use steps::StepExecutionContext;
 
@@ -62,6 +79,10 @@ impl AutoPipelineManager {
                 pipelines: Arc::new(Mutex::new(HashMap::new())),
                 orchestrator,
                 orchestrator_agents: Arc::new(Mutex::new(HashMap::new())),
+                running: Arc::new(Mutex::new(HashMap::new())),
+                fault_hook: None,
+                human_answers: Arc::new(Mutex::new(HashMap::new())),
+                restored_requests: Arc::new(Mutex::new(HashMap::new())),
             }),
         })
     }
@@ -78,10 +99,26 @@ impl AutoPipelineManager {
                 pipelines: Arc::new(Mutex::new(HashMap::new())),
                 orchestrator,
                 orchestrator_agents: Arc::new(Mutex::new(HashMap::new())),
+                running: Arc::new(Mutex::new(HashMap::new())),
+                fault_hook: None,
+                human_answers: Arc::new(Mutex::new(HashMap::new())),
+                restored_requests: Arc::new(Mutex::new(HashMap::new())),
             }),
         })
     }
 
+    /// Install a fault hook so tests can inject failures into pipeline execution
+    ///
+    /// Must be called before the execution context is shared (i.e. before any
+    /// pipeline is started).
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_hook(mut self, hook: Arc<dyn crate::fault_injection::FaultHook>) -> Self {
+        if let Some(ctx) = Arc::get_mut(&mut self.ctx) {
+            ctx.fault_hook = Some(hook);
+        }
+        self
+    }
+
     /// Get a clone of the execution context for concurrent pipeline execution
     /// This allows running multiple pipelines without holding the manager lock
     pub fn get_ctx(&self) -> Arc<StepExecutionContext> {
@@ -89,19 +126,27 @@ impl AutoPipelineManager {
     }
 
     /// Create a new pipeline
+    ///
+    /// `max_retries` overrides how many times each failed step is retried
+    /// (default DEFAULT_STEP_MAX_RETRIES, capped at MAX_STEP_RETRIES).
     pub async fn create_pipeline(
         &self,
         user_request: String,
         working_dir: String,
+        max_retries: Option<u8>,
     ) -> Result<String, String> {
+        let working_dir = crate::utils::validation::validate_working_dir(&working_dir)?;
         let pipeline_id = uuid::Uuid::new_v4().to_string();
         let max_iterations = self.ctx.orchestrator.max_iterations();
-        let pipeline = AutoPipeline::new(
+        let mut pipeline = AutoPipeline::new(
             pipeline_id.clone(),
             user_request,
             working_dir,
             max_iterations,
         );
+        if let Some(max_retries) = max_retries {
+            pipeline.set_step_max_retries(max_retries);
+        }
 
         let mut pipelines = self.ctx.pipelines.lock().await;
         pipelines.insert(pipeline_id.clone(), pipeline);
@@ -114,4 +159,256 @@ impl AutoPipelineManager {
         let pipelines = self.ctx.pipelines.lock().await;
         pipelines.get(pipeline_id).cloned()
     }
+
+    /// List all pipelines known to this manager
+    pub async fn list_pipelines(&self) -> Vec<AutoPipeline> {
+        let pipelines = self.ctx.pipelines.lock().await;
+        pipelines.values().cloned().collect()
+    }
+
+    /// A pipeline by ID, read back from the database if it was evicted
+    pub async fn find_pipeline(
+        &self,
+        pipeline_id: &str,
+        runs_db: &AgentRunsDB,
+    ) -> Result<Option<AutoPipeline>, String> {
+        if let Some(pipeline) = self.get_pipeline(pipeline_id).await {
+            return Ok(Some(pipeline));
+        }
+        runs_db
+            .get_pipeline_snapshot(pipeline_id)
+            .await
+            .map_err(|e| format!("Failed to load evicted pipeline: {}", e))?
+            .map(|record| retention::from_snapshot(&record))
+            .transpose()
+    }
+
+    /// Pipelines matching `filter`, newest first. Finished pipelines evicted
+    /// from memory are read from the database when the filter includes them.
+    pub async fn list_pipelines_filtered(
+        &self,
+        filter: PipelineListFilter,
+        runs_db: &AgentRunsDB,
+    ) -> Result<Vec<AutoPipeline>, String> {
+        let mut pipelines: Vec<AutoPipeline> = self
+            .list_pipelines()
+            .await
+            .into_iter()
+            .filter(|p| filter.matches(p))
+            .collect();
+
+        if filter.include_finished {
+            let snapshots = runs_db
+                .list_pipeline_snapshots(filter.since)
+                .await
+                .map_err(|e| format!("Failed to load evicted pipelines: {}", e))?;
+            for record in snapshots {
+                if pipelines.iter().any(|p| p.id == record.pipeline_id) {
+                    continue;
+                }
+                match retention::from_snapshot(&record) {
+                    Ok(pipeline) => pipelines.push(pipeline),
+                    Err(e) => eprintln!("[auto_pipeline] {}", e),
+                }
+            }
+        }
+
+        pipelines.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(pipelines)
+    }
+
+    /// Check whether a pipeline is currently executing in the background
+    pub async fn is_running(&self, pipeline_id: &str) -> bool {
+        self.ctx.running.lock().await.contains_key(pipeline_id)
+    }
+
+    /// Spawn background execution of a pipeline
+    ///
+    /// The returned handle resolves once the pipeline finishes. The abort handle is
+    /// tracked so `cancel_pipeline` can stop execution.
+    pub async fn start_pipeline(
+        &self,
+        pipeline_id: &str,
+        agent_manager: Arc<Mutex<AgentManager>>,
+        app_handle: Arc<dyn AppEventEmitter>,
+    ) -> Result<JoinHandle<Result<(), String>>, String> {
+        if self.get_pipeline(pipeline_id).await.is_none() {
+            return Err(format!("Pipeline {} not found", pipeline_id));
+        }
+        if self
+            .ctx
+            .restored_requests
+            .lock()
+            .await
+            .contains_key(pipeline_id)
+        {
+            return Err(format!(
+                "Pipeline {} is waiting for an answer; use answer_pipeline_human_request",
+                pipeline_id
+            ));
+        }
+
+        self.spawn_execution(pipeline_id, app_handle.clone(), move |ctx, id| async move {
+            ctx.execute_pipeline(id, agent_manager, app_handle).await
+        })
+        .await
+    }
+
+    /// Run a pipeline's execution on its own task, tracking its abort handle.
+    /// The pipeline's working directory is locked while it runs.
+    async fn spawn_execution<F, Fut>(
+        &self,
+        pipeline_id: &str,
+        app_handle: Arc<dyn AppEventEmitter>,
+        execute: F,
+    ) -> Result<JoinHandle<Result<(), String>>, String>
+    where
+        F: FnOnce(Arc<StepExecutionContext>, String) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<(), String>> + Send + 'static,
+    {
+        // Hold the running map while spawning so the task can't remove its entry
+        // before it has been inserted
+        let mut running = self.ctx.running.lock().await;
+        if running.contains_key(pipeline_id) {
+            return Err(format!("Pipeline {} is already running", pipeline_id));
+        }
+        if let Some(pipeline) = self.get_pipeline(pipeline_id).await {
+            directory_locks::lock_for_pipeline(
+                &pipeline.working_dir,
+                pipeline_id,
+                app_handle.as_ref(),
+            )?;
+        }
+
+        let ctx = self.get_ctx();
+        let id = pipeline_id.to_string();
+        let handle = tokio::spawn(async move {
+            let result = execute(ctx.clone(), id.clone()).await;
+            ctx.running.lock().await.remove(&id);
+            directory_locks::unlock_pipeline(&id, app_handle.as_ref());
+            result
+        });
+
+        running.insert(pipeline_id.to_string(), handle.abort_handle());
+        Ok(handle)
+    }
+
+    /// Answer the question a pipeline's orchestrator is waiting on.
+    ///
+    /// A pipeline restored after a restart is started again from its saved
+    /// state, with the answer added to the orchestrator's conversation.
+    pub async fn answer_human_request(
+        &self,
+        pipeline_id: &str,
+        answer: &str,
+        agent_manager: Arc<Mutex<AgentManager>>,
+        app_handle: Arc<dyn AppEventEmitter>,
+    ) -> Result<(), String> {
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Err("Answer cannot be empty".to_string());
+        }
+
+        let waiting = self.ctx.human_answers.lock().await.remove(pipeline_id);
+        if let Some(answer_tx) = waiting {
+            return answer_tx
+                .send(answer.to_string())
+                .map_err(|_| format!("Pipeline {} is no longer running", pipeline_id));
+        }
+
+        let snapshot = self.ctx.restored_requests.lock().await.remove(pipeline_id);
+        let Some(snapshot) = snapshot else {
+            return Err(format!(
+                "Pipeline {} is not waiting for an answer",
+                pipeline_id
+            ));
+        };
+        let answer = answer.to_string();
+        self.spawn_execution(pipeline_id, app_handle.clone(), move |ctx, id| async move {
+            ctx.resume_pipeline(id, snapshot, answer, agent_manager, app_handle)
+                .await
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Bring back pipelines that were waiting on the user when the app last closed,
+    /// so they are listed and can be answered. Returns how many were restored.
+    pub async fn restore_human_requests(&self, records: Vec<PipelineHumanRequestRecord>) -> usize {
+        let mut restored = 0;
+        for record in records {
+            let parsed = serde_json::from_str::<AutoPipeline>(&record.pipeline).and_then(|p| {
+                serde_json::from_str::<orchestrator_agent::OrchestratorSnapshot>(
+                    &record.orchestrator,
+                )
+                .map(|snapshot| (p, snapshot))
+            });
+            let (pipeline, snapshot) = match parsed {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    eprintln!(
+                        "[auto_pipeline] Failed to restore pipeline {} waiting on the user: {}",
+                        record.pipeline_id, e
+                    );
+                    continue;
+                }
+            };
+            self.ctx
+                .pipelines
+                .lock()
+                .await
+                .insert(record.pipeline_id.clone(), pipeline);
+            self.ctx
+                .restored_requests
+                .lock()
+                .await
+                .insert(record.pipeline_id, snapshot);
+            restored += 1;
+        }
+        restored
+    }
+
+    /// Cancel a running pipeline, stopping its agents and marking it cancelled
+    pub async fn cancel_pipeline(
+        &self,
+        pipeline_id: &str,
+        agent_manager: &Arc<Mutex<AgentManager>>,
+        app_handle: &Arc<dyn AppEventEmitter>,
+    ) -> Result<(), String> {
+        let handle = self.ctx.running.lock().await.remove(pipeline_id);
+        let restored = self.ctx.restored_requests.lock().await.remove(pipeline_id);
+        match handle {
+            Some(handle) => handle.abort(),
+            None if restored.is_some() => {}
+            None => return Err(format!("Pipeline {} is not running", pipeline_id)),
+        }
+
+        // Drop any question it was waiting on
+        self.ctx.human_answers.lock().await.remove(pipeline_id);
+        let runs_db = agent_manager.lock().await.runs_db.clone();
+        if let Some(db) = runs_db {
+            if let Err(e) = db.delete_pipeline_human_request(pipeline_id).await {
+                eprintln!(
+                    "[auto_pipeline] Failed to clear question of cancelled pipeline {}: {}",
+                    pipeline_id, e
+                );
+            }
+        }
+
+        self.ctx
+            .stop_all_pipeline_agents(pipeline_id, agent_manager)
+            .await;
+        directory_locks::unlock_pipeline(pipeline_id, app_handle.as_ref());
+
+        {
+            let mut pipelines = self.ctx.pipelines.lock().await;
+            if let Some(pipeline) = pipelines.get_mut(pipeline_id) {
+                pipeline.mark_cancelled();
+            }
+        }
+
+        steps::emit_pipeline_completed(app_handle, pipeline_id, "cancelled", "cancelled", None);
+        eprintln!("[auto_pipeline] Pipeline {} cancelled", pipeline_id);
+        Ok(())
+    }
 }
@@ -0,0 +1,273 @@
+// Human Help
+//
+// The request_human_help tool and the pause that follows it: the pipeline waits
+// in AwaitingHuman until the user answers, then the answer is added to the
+// conversation and the orchestrator carries on from the phase it was in.
+// While waiting, the orchestrator's state is saved to the run database so the
+// pipeline can be resumed after a restart.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+use crate::agent_runs_db::{AgentRunsDB, PipelineHumanRequestRecord};
+use crate::auto_pipeline::human_requests::{
+    answer_message, output_excerpts, HumanAnswers, HumanRequest,
+};
+use crate::auto_pipeline::orchestrator_tools::{RequestHumanHelpInput, ToolResult};
+use crate::auto_pipeline::state_machine::PipelineState;
+use crate::auto_pipeline::types::AutoPipeline;
+use crate::utils::time::now_millis;
+
+use super::types::ConversationMessage;
+use super::OrchestratorAgent;
+
+/// What the orchestrator needs to pick up a pipeline that was waiting on the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrchestratorSnapshot {
+    /// The question being waited on
+    pub question: String,
+    /// State to return to once answered
+    pub resume_state: PipelineState,
+    messages: Vec<ConversationMessage>,
+    current_iteration: u8,
+    generated_skills: Vec<String>,
+    generated_subagents: Vec<String>,
+    claudemd_generated: bool,
+    current_plan: String,
+    current_qna: String,
+    current_implementation: String,
+    base_commit: Option<String>,
+    planning_replan_count: u8,
+}
+
+impl OrchestratorAgent {
+    /// Attach the answer channels so the orchestrator can ask the user questions
+    pub fn set_human_answers(&mut self, human_answers: HumanAnswers) {
+        self.human_answers = Some(human_answers);
+    }
+
+    /// request_human_help tool implementation: checks the question can be asked.
+    /// The pause itself happens in run_to_completion.
+    pub(crate) fn tool_request_human_help(&self, input: &Value) -> ToolResult {
+        let input: RequestHumanHelpInput = match serde_json::from_value(input.clone()) {
+            Ok(i) => i,
+            Err(e) => return ToolResult::error("".to_string(), format!("Invalid input: {}", e)),
+        };
+        if input.question.trim().is_empty() {
+            return ToolResult::error("".to_string(), "question cannot be empty".to_string());
+        }
+        if self.human_answers.is_none() {
+            return ToolResult::error(
+                "".to_string(),
+                "No user is available to answer in this pipeline. Decide with the other tools."
+                    .to_string(),
+            );
+        }
+        ToolResult::success(
+            "".to_string(),
+            "Question sent to the user. The pipeline is paused until they answer.".to_string(),
+        )
+    }
+
+    /// Pause the pipeline until the user answers, then hand the answer to the orchestrator
+    pub(crate) async fn wait_for_human(
+        &mut self,
+        question: String,
+        context: String,
+    ) -> Result<(), String> {
+        let Some(human_answers) = self.human_answers.clone() else {
+            return Err("No user is available to answer the orchestrator".to_string());
+        };
+
+        let resume_state = self.current_state.clone();
+        let phase_outputs = match self.get_current_step_number() {
+            1 => &self.planning_agent_outputs,
+            2 => &self.building_agent_outputs,
+            _ => &self.verification_agent_outputs,
+        };
+        let request = HumanRequest {
+            pipeline_id: self.pipeline_id.clone(),
+            question,
+            context,
+            excerpts: output_excerpts(phase_outputs),
+            requested_at: now_millis(),
+        };
+
+        let (answer_tx, answer_rx) = oneshot::channel();
+        human_answers
+            .lock()
+            .await
+            .insert(self.pipeline_id.clone(), answer_tx);
+
+        let snapshot = self.snapshot(&request.question, resume_state.clone());
+        self.set_state(PipelineState::AwaitingHuman);
+        let pipeline = self
+            .update_pipeline(|p| p.mark_awaiting_human(request.clone()))
+            .await;
+        if let Some(pipeline) = pipeline {
+            self.save_human_request(&request, &pipeline, &snapshot)
+                .await;
+        }
+
+        if let Some(ref emitter) = self.event_emitter {
+            let _ = emitter.emit("auto_pipeline:needs-human", json!(request));
+        }
+
+        let answer = answer_rx
+            .await
+            .map_err(|_| "Pipeline stopped while waiting for the user's answer".to_string())?;
+        self.resume_with_answer(&request.question, resume_state, &answer)
+            .await;
+        Ok(())
+    }
+
+    /// Restore a pipeline saved while it waited on the user and hand it the answer
+    pub async fn resume_from_snapshot(&mut self, snapshot: OrchestratorSnapshot, answer: &str) {
+        self.messages = snapshot.messages;
+        self.current_iteration = snapshot.current_iteration;
+        self.generated_skills = snapshot.generated_skills;
+        self.generated_subagents = snapshot.generated_subagents;
+        self.claudemd_generated = snapshot.claudemd_generated;
+        self.current_plan = snapshot.current_plan;
+        self.current_qna = snapshot.current_qna;
+        self.current_implementation = snapshot.current_implementation;
+        self.base_commit = snapshot.base_commit;
+        self.planning_replan_count = snapshot.planning_replan_count;
+
+        self.resume_with_answer(&snapshot.question, snapshot.resume_state, answer)
+            .await;
+    }
+
+    /// Add the answer to the conversation and return to the phase that asked
+    async fn resume_with_answer(
+        &mut self,
+        question: &str,
+        resume_state: PipelineState,
+        answer: &str,
+    ) {
+        self.add_context("user", &answer_message(question, answer));
+        self.set_state(resume_state);
+        self.update_pipeline(|p| p.mark_resumed()).await;
+
+        if let Some(db) = self.runs_db().await {
+            if let Err(e) = db.delete_pipeline_human_request(&self.pipeline_id).await {
+                eprintln!("[ORCHESTRATOR] Failed to clear answered question: {}", e);
+            }
+        }
+
+        if let Some(ref emitter) = self.event_emitter {
+            let _ = emitter.emit(
+                "auto_pipeline:human-answered",
+                json!({
+                    "pipeline_id": self.pipeline_id,
+                    "question": question,
+                    "answer": answer,
+                }),
+            );
+        }
+    }
+
+    fn snapshot(&self, question: &str, resume_state: PipelineState) -> OrchestratorSnapshot {
+        OrchestratorSnapshot {
+            question: question.to_string(),
+            resume_state,
+            messages: self.messages.clone(),
+            current_iteration: self.current_iteration,
+            generated_skills: self.generated_skills.clone(),
+            generated_subagents: self.generated_subagents.clone(),
+            claudemd_generated: self.claudemd_generated,
+            current_plan: self.current_plan.clone(),
+            current_qna: self.current_qna.clone(),
+            current_implementation: self.current_implementation.clone(),
+            base_commit: self.base_commit.clone(),
+            planning_replan_count: self.planning_replan_count,
+        }
+    }
+
+    /// Save the paused pipeline so it survives a restart
+    async fn save_human_request(
+        &self,
+        request: &HumanRequest,
+        pipeline: &AutoPipeline,
+        snapshot: &OrchestratorSnapshot,
+    ) {
+        let Some(db) = self.runs_db().await else {
+            return;
+        };
+        let record = match (
+            serde_json::to_string(pipeline),
+            serde_json::to_string(snapshot),
+        ) {
+            (Ok(pipeline), Ok(orchestrator)) => PipelineHumanRequestRecord {
+                pipeline_id: self.pipeline_id.clone(),
+                question: request.question.clone(),
+                pipeline,
+                orchestrator,
+                requested_at: request.requested_at,
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("[ORCHESTRATOR] Failed to serialize paused pipeline: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = db.save_pipeline_human_request(&record).await {
+            eprintln!("[ORCHESTRATOR] Failed to persist paused pipeline: {}", e);
+        }
+    }
+
+    /// Apply a change to this pipeline in the shared store, returning the updated pipeline
+    pub(super) async fn update_pipeline(
+        &self,
+        f: impl FnOnce(&mut AutoPipeline),
+    ) -> Option<AutoPipeline> {
+        let pipelines = self.pipelines.as_ref()?;
+        let mut pipelines = pipelines.lock().await;
+        let pipeline = pipelines.get_mut(&self.pipeline_id)?;
+        f(pipeline);
+        Some(pipeline.clone())
+    }
+
+    pub(super) async fn runs_db(&self) -> Option<Arc<AgentRunsDB>> {
+        match &self.agent_manager {
+            Some(am) => am.lock().await.runs_db.clone(),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_pipeline::orchestrator_agent::types::ConversationContent;
+
+    #[test]
+    fn test_snapshot_round_trips_through_json() {
+        let snapshot = OrchestratorSnapshot {
+            question: "Postgres or SQLite?".to_string(),
+            resume_state: PipelineState::PlanReady,
+            messages: vec![ConversationMessage {
+                role: "user".to_string(),
+                content: ConversationContent::Text("Build the API".to_string()),
+            }],
+            current_iteration: 2,
+            generated_skills: vec!["api-guidelines".to_string()],
+            generated_subagents: Vec::new(),
+            claudemd_generated: true,
+            current_plan: "1. Add routes".to_string(),
+            current_qna: String::new(),
+            current_implementation: String::new(),
+            base_commit: Some("abc123".to_string()),
+            planning_replan_count: 1,
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: OrchestratorSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.resume_state, PipelineState::PlanReady);
+        assert_eq!(restored.question, "Postgres or SQLite?");
+        assert_eq!(restored.messages.len(), 1);
+        assert_eq!(restored.current_iteration, 2);
+        assert_eq!(restored.base_commit.as_deref(), Some("abc123"));
+    }
+}
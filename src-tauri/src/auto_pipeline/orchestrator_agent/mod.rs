@@ -6,26 +6,36 @@
 // - Read and create skills from instruction files
 // - Spawn planning, build, and verification agents
 // - Make final decisions (complete, iterate, replan, give_up)
+// - Ask the user for help when blocked (request_human_help)
 
 mod context_builders;
+mod human_help;
 mod tool_loop;
 mod tools;
 mod types;
 
+pub use human_help::OrchestratorSnapshot;
 pub use types::OrchestratorAction;
 
 use context_builders::build_system_context;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
 use crate::ai_client::{AIClient, Tool};
 use crate::events::AppEventEmitter;
+use crate::fault_injection::SharedFaultHook;
+use crate::http_client;
 use crate::instruction_manager::{list_instruction_files, InstructionFileInfo};
 
+use super::build_diff::{self, VerificationInputMode};
+use super::human_requests::HumanAnswers;
 use super::orchestrator_tools::get_tools_for_state;
 use super::prompts::build_initial_prompt;
+use super::scratchpad::Scratchpad;
 use super::state_machine::PipelineState;
+use super::types::AutoPipeline;
 
 use types::{ConversationContent, ConversationMessage};
 
@@ -67,6 +77,12 @@ pub struct OrchestratorAgent {
     pub(crate) current_qna: String,
     /// Current implementation output (stored for verification phase)
     pub(crate) current_implementation: String,
+    /// HEAD when the pipeline started, which builds are diffed against (None outside git)
+    pub(crate) base_commit: Option<String>,
+    /// Implementation section for the verification prompt, prepared after each build
+    pub(crate) verification_input: Option<(VerificationInputMode, String)>,
+    /// What the last verification agent was given, recorded with the next decision
+    pub(crate) verified_with: Option<VerificationInputMode>,
     /// Agent outputs from the planning phase
     pub(crate) planning_agent_outputs: Vec<crate::types::AgentOutputEvent>,
     /// Agent outputs from the building phase
@@ -81,6 +97,16 @@ pub struct OrchestratorAgent {
     pub(crate) pipeline_id: String,
     /// Spawned agent IDs for tracking: [planning, building, verification]
     pub(crate) spawned_agents: [Option<String>; 3],
+    /// Shared pipeline store, used to record step retries (None when not run by the manager)
+    pub(crate) pipelines: Option<Arc<Mutex<HashMap<String, AutoPipeline>>>>,
+    /// Fault hook for chaos tests (None in normal operation)
+    pub(crate) fault_hook: SharedFaultHook,
+    /// Shared notes file for the orchestrator and every spawned agent
+    pub(crate) scratchpad: Scratchpad,
+    /// Scratchpad contents at the last persisted snapshot
+    pub(crate) last_scratchpad_snapshot: Option<String>,
+    /// Where the user's answers arrive (None when no user can be asked)
+    pub(crate) human_answers: Option<HumanAnswers>,
 }
 
 impl OrchestratorAgent {
@@ -136,7 +162,8 @@ impl OrchestratorAgent {
         // Prefer OpenAI for orchestration (falls back to any available provider)
         let ai_client = AIClient::openai_from_env()
             .or_else(|_| AIClient::from_env())
-            .map_err(|e| format!("Failed to create AI client: {}", e))?;
+            .map_err(|e| format!("Failed to create AI client: {}", e))?
+            .with_request_timeout(http_client::config().orchestrator_timeout);
 
         // Load available instruction files
         let instruction_files = list_instruction_files(&working_dir).unwrap_or_default();
@@ -188,6 +215,10 @@ impl OrchestratorAgent {
             content: ConversationContent::Text(initial_prompt),
         }];
 
+        let pipeline_id = pipeline_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let base_commit = build_diff::base_commit(&working_dir);
+        let scratchpad = Scratchpad::for_pipeline(&working_dir, &pipeline_id);
+
         Ok(Self {
             ai_client,
             messages,
@@ -207,16 +238,34 @@ impl OrchestratorAgent {
             current_plan: String::new(),
             current_qna: String::new(),
             current_implementation: String::new(),
+            base_commit,
+            verification_input: None,
+            verified_with: None,
             planning_agent_outputs: Vec::new(),
             building_agent_outputs: Vec::new(),
             verification_agent_outputs: Vec::new(),
             planning_replan_count: 0,
             max_planning_replans: 1, // Default: allow 1 replan during planning
-            pipeline_id: pipeline_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            pipeline_id,
             spawned_agents: [None, None, None],
+            pipelines: None,
+            fault_hook: None,
+            scratchpad,
+            last_scratchpad_snapshot: None,
+            human_answers: None,
         })
     }
 
+    /// Attach the manager's pipeline store so step retries are recorded on the pipeline
+    pub fn set_pipeline_store(&mut self, pipelines: Arc<Mutex<HashMap<String, AutoPipeline>>>) {
+        self.pipelines = Some(pipelines);
+    }
+
+    /// Attach the fault hook used to inject AI provider failures in tests
+    pub fn set_fault_hook(&mut self, hook: SharedFaultHook) {
+        self.fault_hook = hook;
+    }
+
     /// Get the current state
     pub fn current_state(&self) -> &PipelineState {
         &self.current_state
@@ -237,6 +286,14 @@ impl OrchestratorAgent {
         self.current_iteration
     }
 
+    /// Approximate bytes held by the conversation history
+    pub fn conversation_bytes(&self) -> usize {
+        self.messages
+            .iter()
+            .map(|m| serde_json::to_vec(m).map_or(0, |v| v.len()))
+            .sum()
+    }
+
     /// Increment iteration counter
     pub fn increment_iteration(&mut self) {
         self.current_iteration += 1;
@@ -261,7 +318,10 @@ impl OrchestratorAgent {
             PipelineState::Verifying
             | PipelineState::VerificationPassed
             | PipelineState::VerificationFailed => 3,
-            PipelineState::Completed | PipelineState::Failed | PipelineState::GaveUp => 0,
+            PipelineState::AwaitingHuman
+            | PipelineState::Completed
+            | PipelineState::Failed
+            | PipelineState::GaveUp => 0,
         }
     }
 
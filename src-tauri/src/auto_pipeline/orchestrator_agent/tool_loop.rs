@@ -5,8 +5,11 @@
 use serde_json::{json, Value};
 
 use crate::auto_pipeline::orchestrator_tools::{
-    CompleteInput, GiveUpInput, IterateInput, ReplanInput, ToolResult,
+    CompleteInput, GiveUpInput, IterateInput, ReplanInput, RequestHumanHelpInput, ToolResult,
 };
+use crate::auto_pipeline::{HealthChange, IterationRecord};
+use crate::cost_preference::{self, OffloadKind};
+use crate::fault_injection::{check_fault, FaultPoint};
 use crate::utils::string::truncate_with_ellipsis;
 
 use super::context_builders::send_to_ai;
@@ -20,6 +23,7 @@ impl OrchestratorAgent {
     pub async fn run_until_action(&mut self) -> Result<OrchestratorAction, String> {
         loop {
             // Send message to AI
+            check_fault(&self.fault_hook, FaultPoint::AiProviderTimeout)?;
             let response = send_to_ai(&self.ai_client, &self.messages, &self.tools).await?;
 
             // Check for tool calls
@@ -126,6 +130,16 @@ impl OrchestratorAgent {
                                 });
                             }
                         }
+                        "request_human_help" => {
+                            if let Ok(input) =
+                                serde_json::from_value::<RequestHumanHelpInput>(tool_input)
+                            {
+                                action = Some(OrchestratorAction::NeedsHuman {
+                                    question: input.question,
+                                    context: input.context,
+                                });
+                            }
+                        }
                         _ => {
                             // Other tools (start_planning, start_execution, start_verification, etc.)
                             // are handled internally and continue the loop
@@ -133,11 +147,17 @@ impl OrchestratorAgent {
                     }
                 }
 
+                let error = result.is_error.then_some(result.content.as_str());
+                let degraded_note = self.record_tool_health(&tool_name, error).await;
+
                 tool_results.push(ContentBlockValue::ToolResult {
                     tool_use_id: tool_id,
                     content: result.content,
                     is_error: if result.is_error { Some(true) } else { None },
                 });
+                if let Some(note) = degraded_note {
+                    tool_results.push(ContentBlockValue::Text { text: note });
+                }
             }
 
             // Add tool results to messages
@@ -148,8 +168,13 @@ impl OrchestratorAgent {
 
             // If we got a terminal action (decision), get final summary and return
             if let Some(act) = action {
-                // For Complete action, do one more AI turn to get a final summary
+                // For Complete action, do one more AI turn to get a final summary,
+                // unless the completion summary is kept to save API cost
                 if matches!(act, OrchestratorAction::Complete { .. }) {
+                    if cost_preference::offload_to_workers() {
+                        self.record_skipped_final_summary().await;
+                        return Ok(act);
+                    }
                     let final_summary = self.get_final_summary().await;
                     if let OrchestratorAction::Complete { summary } = act {
                         return Ok(OrchestratorAction::Complete {
@@ -168,6 +193,49 @@ impl OrchestratorAgent {
         }
     }
 
+    /// Count a tool call towards the pipeline's health. When this call makes
+    /// the pipeline degraded, emits auto_pipeline:degraded and returns the note
+    /// for the orchestrator.
+    async fn record_tool_health(&self, tool_name: &str, error: Option<&str>) -> Option<String> {
+        let mut change = HealthChange::Unchanged;
+        let pipeline = self
+            .update_pipeline(|p| change = p.tool_health.record(tool_name, error))
+            .await?;
+        if change != HealthChange::Degraded {
+            return None;
+        }
+
+        eprintln!(
+            "[ORCHESTRATOR] Pipeline {} degraded: {} of the last {} tool calls failed",
+            pipeline.id, pipeline.tool_health.recent_errors, pipeline.tool_health.recent_calls
+        );
+        if let Some(ref emitter) = self.event_emitter {
+            let _ = emitter.emit(
+                "auto_pipeline:degraded",
+                json!({
+                    "pipeline_id": pipeline.id,
+                    "health": pipeline.tool_health,
+                }),
+            );
+        }
+        Some(pipeline.tool_health.degraded_note())
+    }
+
+    /// Record the final-summary turn that wasn't sent, estimated at the size
+    /// of the conversation it would have carried
+    async fn record_skipped_final_summary(&self) {
+        let chars = serde_json::to_string(&self.messages)
+            .map(|json| json.len())
+            .unwrap_or(0);
+        let runs_db = self.runs_db().await;
+        cost_preference::record_offload(
+            runs_db.as_ref(),
+            OffloadKind::FinalSummary,
+            cost_preference::estimate_tokens(chars),
+        )
+        .await;
+    }
+
     /// Get a final summary from the orchestrator after completion
     async fn get_final_summary(&mut self) -> String {
         // Emit event that we're generating final summary
@@ -212,15 +280,47 @@ impl OrchestratorAgent {
         }
     }
 
+    /// Add the orchestrator's decision to the pipeline's iteration history
+    async fn record_iteration(&mut self, action: &OrchestratorAction) {
+        let (decision, reasoning, issues) = match action {
+            OrchestratorAction::Complete { summary } => ("complete", summary.clone(), Vec::new()),
+            OrchestratorAction::Iterate {
+                issues,
+                suggestions,
+            } => ("iterate", suggestions.join("\n"), issues.clone()),
+            OrchestratorAction::Replan { reason, issues, .. } => {
+                ("replan", reason.clone(), issues.clone())
+            }
+            OrchestratorAction::GiveUp { reason } => ("give_up", reason.clone(), Vec::new()),
+            _ => return,
+        };
+        let verification_input = self.verified_with.take();
+
+        let Some(ref pipelines) = self.pipelines else {
+            return;
+        };
+        if let Some(pipeline) = pipelines.lock().await.get_mut(&self.pipeline_id) {
+            pipeline.iteration_history.push(IterationRecord {
+                iteration: self.current_iteration,
+                decision: decision.to_string(),
+                reasoning,
+                issues,
+                verification_input,
+            });
+        }
+    }
+
     /// Run the orchestrator through its complete workflow until final completion or give up
     /// Handles planning, execution, verification, iteration, and replanning internally
     pub async fn run_to_completion(&mut self) -> Result<OrchestratorAction, String> {
         let max_iterations = self.max_iterations;
-        let mut iteration_count = 0;
+        // Counted from the iteration so a pipeline resumed after a restart keeps its budget
+        let mut iteration_count = self.current_iteration.saturating_sub(1);
 
         loop {
             // Run until we get a decision
             let action = self.run_until_action().await?;
+            self.record_iteration(&action).await;
 
             match action {
                 // Terminal success
@@ -238,6 +338,16 @@ impl OrchestratorAgent {
                     return Ok(OrchestratorAction::GiveUp { reason });
                 }
 
+                // Question for the user - pause until answered, then carry on
+                OrchestratorAction::NeedsHuman { question, context } => {
+                    eprintln!(
+                        "[ORCHESTRATOR] run_to_completion: Waiting for the user: {}",
+                        question
+                    );
+                    self.wait_for_human(question, context).await?;
+                    continue;
+                }
+
                 // Iteration - reset and continue
                 OrchestratorAction::Iterate {
                     issues: _,
@@ -318,7 +428,11 @@ impl OrchestratorAgent {
             "complete" => self.tool_complete().await,
             "iterate" => self.tool_iterate().await,
             "replan" => self.tool_replan().await,
+            "read_scratchpad" => self.tool_read_scratchpad().await,
+            "append_scratchpad" => self.tool_append_scratchpad(input).await,
+            "send_message_to_agent" => self.tool_send_message_to_agent(input).await,
             "give_up" => ToolResult::success("".to_string(), "Pipeline abandoned.".to_string()),
+            "request_human_help" => self.tool_request_human_help(input),
             _ => ToolResult::error("".to_string(), format!("Unknown tool: {}", tool_name)),
         }
     }
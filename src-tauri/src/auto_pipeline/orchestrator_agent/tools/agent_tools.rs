@@ -1,29 +1,58 @@
 // Agent-Spawning Tools
 //
-// Tools that spawn Claude Code agents: start_planning, start_execution, start_verification.
+// Tools that spawn Claude Code agents: start_planning, start_execution, start_verification,
+// plus send_message_to_agent for relaying questions between the spawned agents.
+// Spawning, waiting and retries are handled by the step runner.
 
 use serde_json::Value;
 
-use crate::auto_pipeline::agent_utils::{extract_agent_output, wait_for_agent_completion};
+use crate::agent_manager::mcp_config::validate_mcp_config;
+use crate::auto_pipeline::agent_utils::{extract_agent_output, wait_for_agent_reply};
+use crate::auto_pipeline::build_diff::{self, VerificationInputMode};
 use crate::auto_pipeline::orchestrator_tools::{
-    StartExecutionInput, StartPlanningInput, StartVerificationInput, ToolResult,
+    SendMessageToAgentInput, StartExecutionInput, StartPlanningInput, StartVerificationInput,
+    ToolResult,
 };
+use crate::auto_pipeline::prompt_templates::{load_template, PromptTemplateName};
 use crate::auto_pipeline::state_machine::PipelineState;
-use crate::types::AgentSource;
+use crate::permission_profiles;
+use crate::types::ToolRestrictions;
+use crate::utils::validation::validate_model;
 
 use super::super::context_builders::{build_full_skills_section, build_full_subagents_section};
 use super::super::OrchestratorAgent;
+use super::step_runner::StepAgentSpec;
 use crate::auto_pipeline::prompts::{
     build_builder_prompt, build_planning_prompt, build_verification_prompt,
 };
 
 impl OrchestratorAgent {
+    /// Load the workspace's kickoff template and record which version is used
+    async fn resolve_prompt_template(&self, name: PromptTemplateName) -> Result<String, String> {
+        let template = load_template(&self.working_dir, name)?;
+        if let Some(ref pipelines) = self.pipelines {
+            if let Some(pipeline) = pipelines.lock().await.get_mut(&self.pipeline_id) {
+                pipeline.record_prompt_template(template.usage(name));
+            }
+        }
+        Ok(template.content)
+    }
+
     /// Start planning tool - spawns a Claude Code planning agent
     pub(crate) async fn tool_start_planning(&mut self, input: &Value) -> ToolResult {
         let parsed: StartPlanningInput = match serde_json::from_value(input.clone()) {
             Ok(p) => p,
             Err(e) => return ToolResult::error("".to_string(), format!("Invalid input: {}", e)),
         };
+        let model = match validate_model(parsed.model.as_deref()) {
+            Ok(model) => model,
+            Err(e) => return ToolResult::error("".to_string(), e),
+        };
+        if let Some(ref mcp_servers) = parsed.mcp_servers {
+            if let Err(e) = validate_mcp_config(mcp_servers) {
+                return ToolResult::error("".to_string(), e);
+            }
+        }
 
         self.set_state(PipelineState::Planning);
 
@@ -47,78 +76,35 @@ impl OrchestratorAgent {
         let subagents_section =
             build_full_subagents_section(&self.generated_subagents, &self.working_dir);
 
+        let template = match self
+            .resolve_prompt_template(PromptTemplateName::Planning)
+            .await
+        {
+            Ok(t) => t,
+            Err(e) => return ToolResult::error("".to_string(), e),
+        };
         let planning_prompt = build_planning_prompt(
+            &template,
             &self.user_request,
             &self.working_dir,
             &skills_section,
             &subagents_section,
         );
 
-        // Spawn the planning agent (linked to this pipeline for historical queries)
-        let agent_id = {
-            let manager = agent_manager.lock().await;
-            match manager
-                .create_agent_with_pipeline(
-                    self.working_dir.clone(),
-                    None,
-                    None,
-                    Vec::new(), // No pre-generated skills for pipeline agents
-                    AgentSource::Pipeline,
-                    event_emitter.clone(),
-                    Some(self.pipeline_id.clone()),
-                    Some("Planning".to_string()),
-                    None, // No model override
-                    None, // No complexity
-                )
-                .await
-            {
-                Ok(id) => id,
-                Err(e) => {
-                    return ToolResult::error(
-                        "".to_string(),
-                        format!("Failed to create planning agent: {}", e),
-                    )
-                }
-            }
+        let spec = StepAgentSpec {
+            index: 0,
+            step_name: "Planning",
+            label: "planning",
+            model,
+            mcp_servers: parsed.mcp_servers,
+            tools: ToolRestrictions::default(),
         };
-
-        // Track the spawned agent for later cleanup
-        self.spawned_agents[0] = Some(agent_id.clone());
-
-        // Send the prompt
-        // Note: No security_monitor for pipeline automated prompts
+        let output = match self
+            .run_step_agent_with_retries(&spec, &planning_prompt, agent_manager, event_emitter)
+            .await
         {
-            let manager = agent_manager.lock().await;
-            if let Err(e) = manager
-                .send_prompt(
-                    &agent_id,
-                    &planning_prompt,
-                    Some(event_emitter.clone()),
-                    None,
-                )
-                .await
-            {
-                return ToolResult::error(
-                    "".to_string(),
-                    format!("Failed to send planning prompt: {}", e),
-                );
-            }
-        }
-
-        // Wait for completion
-        if let Err(e) = wait_for_agent_completion(&agent_id, agent_manager.clone()).await {
-            return ToolResult::error("".to_string(), format!("Planning agent failed: {}", e));
-        }
-
-        // Extract output
-        let output = match extract_agent_output(&agent_id, agent_manager.clone()).await {
             Ok(o) => o,
-            Err(e) => {
-                return ToolResult::error(
-                    "".to_string(),
-                    format!("Failed to get planning output: {}", e),
-                )
-            }
+            Err(e) => return ToolResult::error("".to_string(), e),
         };
 
         // Store the plan and agent outputs
@@ -145,7 +131,20 @@ impl OrchestratorAgent {
         }
 
         let parsed: StartExecutionInput =
-            serde_json::from_value(input.clone()).unwrap_or(StartExecutionInput { notes: None });
+            serde_json::from_value(input.clone()).unwrap_or(StartExecutionInput {
+                notes: None,
+                model: None,
+                mcp_servers: None,
+            });
+        let model = match validate_model(parsed.model.as_deref()) {
+            Ok(model) => model,
+            Err(e) => return ToolResult::error("".to_string(), e),
+        };
+        if let Some(ref mcp_servers) = parsed.mcp_servers {
+            if let Err(e) = validate_mcp_config(mcp_servers) {
+                return ToolResult::error("".to_string(), e);
+            }
+        }
 
         self.set_state(PipelineState::Executing);
 
@@ -170,7 +169,15 @@ impl OrchestratorAgent {
             .map(|n| format!("\n## ADDITIONAL NOTES\n{}\n", n))
             .unwrap_or_default();
 
+        let template = match self
+            .resolve_prompt_template(PromptTemplateName::Builder)
+            .await
+        {
+            Ok(t) => t,
+            Err(e) => return ToolResult::error("".to_string(), e),
+        };
         let builder_prompt = build_builder_prompt(
+            &template,
             &self.user_request,
             &self.working_dir,
             &self.current_plan,
@@ -180,77 +187,39 @@ impl OrchestratorAgent {
             &notes_section,
         );
 
-        // Spawn the build agent (linked to this pipeline for historical queries)
-        let agent_id = {
-            let manager = agent_manager.lock().await;
-            match manager
-                .create_agent_with_pipeline(
-                    self.working_dir.clone(),
-                    None,
-                    None,
-                    Vec::new(), // No pre-generated skills for pipeline agents
-                    AgentSource::Pipeline,
-                    event_emitter.clone(),
-                    Some(self.pipeline_id.clone()),
-                    Some("Building".to_string()),
-                    None, // No model override
-                    None, // No complexity
-                )
-                .await
-            {
-                Ok(id) => id,
-                Err(e) => {
-                    return ToolResult::error(
-                        "".to_string(),
-                        format!("Failed to create build agent: {}", e),
-                    )
-                }
-            }
+        let spec = StepAgentSpec {
+            index: 1,
+            step_name: "Building",
+            label: "build",
+            model,
+            mcp_servers: parsed.mcp_servers,
+            tools: ToolRestrictions::default(),
         };
-
-        // Track the spawned agent for later cleanup
-        self.spawned_agents[1] = Some(agent_id.clone());
-
-        // Send the prompt
-        // Note: No security_monitor for pipeline automated prompts
+        let output = match self
+            .run_step_agent_with_retries(&spec, &builder_prompt, agent_manager, event_emitter)
+            .await
         {
-            let manager = agent_manager.lock().await;
-            if let Err(e) = manager
-                .send_prompt(
-                    &agent_id,
-                    &builder_prompt,
-                    Some(event_emitter.clone()),
-                    None,
-                )
-                .await
-            {
-                return ToolResult::error(
-                    "".to_string(),
-                    format!("Failed to send build prompt: {}", e),
-                );
-            }
-        }
-
-        // Wait for completion
-        if let Err(e) = wait_for_agent_completion(&agent_id, agent_manager.clone()).await {
-            return ToolResult::error("".to_string(), format!("Build agent failed: {}", e));
-        }
-
-        // Extract output
-        let output = match extract_agent_output(&agent_id, agent_manager.clone()).await {
             Ok(o) => o,
-            Err(e) => {
-                return ToolResult::error(
-                    "".to_string(),
-                    format!("Failed to get build output: {}", e),
-                )
-            }
+            Err(e) => return ToolResult::error("".to_string(), e),
         };
 
         // Store the implementation and agent outputs
         self.current_implementation = output.raw_text.clone();
         self.building_agent_outputs = output.agent_outputs.clone();
 
+        // Diff what the build changed for the verifier (raw output outside git)
+        let (mode, verification_input) = build_diff::verification_input(
+            &self.working_dir,
+            self.base_commit.as_deref(),
+            &output.raw_text,
+        );
+        eprintln!(
+            "[ORCHESTRATOR] Verification input prepared as {:?} ({} chars)",
+            mode,
+            verification_input.len()
+        );
+        self.verification_input = Some((mode, verification_input));
+
         ToolResult::success(
             "".to_string(),
             format!(
@@ -273,7 +242,18 @@ impl OrchestratorAgent {
         let parsed: StartVerificationInput =
             serde_json::from_value(input.clone()).unwrap_or(StartVerificationInput {
                 focus_areas: Vec::new(),
+                model: None,
+                mcp_servers: None,
             });
+        let model = match validate_model(parsed.model.as_deref()) {
+            Ok(model) => model,
+            Err(e) => return ToolResult::error("".to_string(), e),
+        };
+        if let Some(ref mcp_servers) = parsed.mcp_servers {
+            if let Err(e) = validate_mcp_config(mcp_servers) {
+                return ToolResult::error("".to_string(), e);
+            }
+        }
 
         self.set_state(PipelineState::Verifying);
 
@@ -307,80 +287,45 @@ impl OrchestratorAgent {
         let subagents_section =
             build_full_subagents_section(&self.generated_subagents, &self.working_dir);
 
+        let template = match self
+            .resolve_prompt_template(PromptTemplateName::Verifier)
+            .await
+        {
+            Ok(t) => t,
+            Err(e) => return ToolResult::error("".to_string(), e),
+        };
+        let (mode, implementation) = self.verification_input.clone().unwrap_or((
+            VerificationInputMode::BuildOutput,
+            self.current_implementation.clone(),
+        ));
+        self.verified_with = Some(mode);
         let verification_prompt = build_verification_prompt(
+            &template,
             &self.user_request,
+            &self.working_dir,
             &self.current_plan,
-            &self.current_implementation,
+            &self.current_qna,
+            &implementation,
             &focus_section,
             &skills_section,
             &subagents_section,
         );
 
-        // Spawn the verification agent (linked to this pipeline for historical queries)
-        let agent_id = {
-            let manager = agent_manager.lock().await;
-            match manager
-                .create_agent_with_pipeline(
-                    self.working_dir.clone(),
-                    None,
-                    None,
-                    Vec::new(), // No pre-generated skills for pipeline agents
-                    AgentSource::Pipeline,
-                    event_emitter.clone(),
-                    Some(self.pipeline_id.clone()),
-                    Some("Verification".to_string()),
-                    None, // No model override
-                    None, // No complexity
-                )
-                .await
-            {
-                Ok(id) => id,
-                Err(e) => {
-                    return ToolResult::error(
-                        "".to_string(),
-                        format!("Failed to create verification agent: {}", e),
-                    )
-                }
-            }
+        let spec = StepAgentSpec {
+            index: 2,
+            step_name: "Verification",
+            label: "verification",
+            model,
+            mcp_servers: parsed.mcp_servers,
+            // Verification reviews the build; it doesn't change it
+            tools: permission_profiles::read_only_tools(),
         };
-
-        // Track the spawned agent for later cleanup
-        self.spawned_agents[2] = Some(agent_id.clone());
-
-        // Send the prompt
-        // Note: No security_monitor for pipeline automated prompts
+        let output = match self
+            .run_step_agent_with_retries(&spec, &verification_prompt, agent_manager, event_emitter)
+            .await
         {
-            let manager = agent_manager.lock().await;
-            if let Err(e) = manager
-                .send_prompt(
-                    &agent_id,
-                    &verification_prompt,
-                    Some(event_emitter.clone()),
-                    None,
-                )
-                .await
-            {
-                return ToolResult::error(
-                    "".to_string(),
-                    format!("Failed to send verification prompt: {}", e),
-                );
-            }
-        }
-
-        // Wait for completion
-        if let Err(e) = wait_for_agent_completion(&agent_id, agent_manager.clone()).await {
-            return ToolResult::error("".to_string(), format!("Verification agent failed: {}", e));
-        }
-
-        // Extract output
-        let output = match extract_agent_output(&agent_id, agent_manager.clone()).await {
             Ok(o) => o,
-            Err(e) => {
-                return ToolResult::error(
-                    "".to_string(),
-                    format!("Failed to get verification output: {}", e),
-                )
-            }
+            Err(e) => return ToolResult::error("".to_string(), e),
         };
 
         // Store agent outputs
@@ -394,4 +339,65 @@ impl OrchestratorAgent {
             ),
         )
     }
+
+    /// Send message tool - relays a message between step agents and waits for the reply
+    pub(crate) async fn tool_send_message_to_agent(&mut self, input: &Value) -> ToolResult {
+        let parsed: SendMessageToAgentInput = match serde_json::from_value(input.clone()) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error("".to_string(), format!("Invalid input: {}", e)),
+        };
+
+        let (agent_manager, event_emitter) = match (&self.agent_manager, &self.event_emitter) {
+            (Some(am), Some(ee)) => (am.clone(), ee.clone()),
+            _ => {
+                return ToolResult::error(
+                    "".to_string(),
+                    "Agent manager not configured - no step agents to message.".to_string(),
+                );
+            }
+        };
+
+        let step_agent = |step: &str| -> Result<String, String> {
+            let index = match step {
+                "planning" => 0,
+                "building" => 1,
+                "verification" => 2,
+                other => return Err(format!("Unknown step '{}'", other)),
+            };
+            self.spawned_agents[index]
+                .clone()
+                .ok_or_else(|| format!("The {} agent has not been started", step))
+        };
+        let (from_id, to_id) = match (step_agent(&parsed.from), step_agent(&parsed.to)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(e), _) | (_, Err(e)) => return ToolResult::error("".to_string(), e),
+        };
+
+        let sent = {
+            let manager = agent_manager.lock().await;
+            manager
+                .send_agent_message(&from_id, &to_id, &parsed.message, event_emitter)
+                .await
+        };
+        if let Err(e) = sent {
+            return ToolResult::error("".to_string(), e);
+        }
+
+        if let Err(e) = wait_for_agent_reply(&to_id, agent_manager.clone()).await {
+            return ToolResult::error(
+                "".to_string(),
+                format!("The {} agent failed to reply: {}", parsed.to, e),
+            );
+        }
+        match extract_agent_output(&to_id, agent_manager).await {
+            Ok(output) => ToolResult::success(
+                "".to_string(),
+                format!(
+                    "The {} agent replied:\n\n{}\n\nRelay the answer to the {} agent with send_message_to_agent if it needs it.",
+                    parsed.to, output.raw_text, parsed.from
+                ),
+            ),
+            Err(e) => ToolResult::error("".to_string(), format!("Failed to read reply: {}", e)),
+        }
+    }
 }
@@ -1,9 +1,13 @@
 // Tool Implementations
 //
-// Organized by category: state transitions, agent spawning, and resource management.
+// Organized by category: state transitions, agent spawning, resource management
+// and the shared scratchpad.
+// The step runner backs agent spawning with per-step retries.
 
 mod agent_tools;
 mod resource_tools;
+mod scratchpad_tools;
 mod state_tools;
+mod step_runner;
 
 // Re-exports removed - these modules are used internally only
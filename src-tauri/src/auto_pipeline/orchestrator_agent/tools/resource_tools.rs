@@ -1,15 +1,18 @@
 // Resource Tools
 //
 // Tools for reading and creating resources: read_instruction_file, create_skill,
-// create_subagent, generate_claudemd.
+// create_subagent, generate_claudemd. What they generate is also stored in the
+// run database as pipeline artifacts.
 
 use serde_json::Value;
+use std::path::Path;
 
 use crate::auto_pipeline::orchestrator_tools::{
     CreateSkillInput, CreateSubagentInput, GenerateClaudeMdInput, ReadInstructionFileInput,
     ToolResult,
 };
 use crate::claudemd_generator::generate_claudemd_from_instructions;
+use crate::generated_files::{self, GeneratedFileKind};
 use crate::instruction_manager::get_instruction_file_content;
 use crate::skill_generator::generate_skill_from_instruction;
 use crate::subagent_generator::generate_subagent_from_instruction;
@@ -85,6 +88,17 @@ impl OrchestratorAgent {
         {
             Ok(skill) => {
                 self.generated_skills.push(skill.skill_name.clone());
+                generated_files::assign_to_pipeline(
+                    &self.working_dir,
+                    Path::new(&skill.skill_path),
+                    &self.pipeline_id,
+                );
+                self.record_artifact(
+                    GeneratedFileKind::Skill,
+                    &skill.skill_name,
+                    &skill.skill_path,
+                )
+                .await;
                 ToolResult::success(
                     "".to_string(),
                     format!(
@@ -137,6 +151,17 @@ impl OrchestratorAgent {
         {
             Ok(subagent) => {
                 self.generated_subagents.push(subagent.agent_name.clone());
+                generated_files::assign_to_pipeline(
+                    &self.working_dir,
+                    Path::new(&subagent.agent_path),
+                    &self.pipeline_id,
+                );
+                self.record_artifact(
+                    GeneratedFileKind::Subagent,
+                    &subagent.agent_name,
+                    &subagent.agent_path,
+                )
+                .await;
                 ToolResult::success(
                     "".to_string(),
                     format!(
@@ -203,6 +228,13 @@ impl OrchestratorAgent {
         {
             Ok(result) => {
                 self.claudemd_generated = true;
+                generated_files::assign_to_pipeline(
+                    &self.working_dir,
+                    Path::new(&result.file_path),
+                    &self.pipeline_id,
+                );
+                self.record_artifact(GeneratedFileKind::ClaudeMd, "CLAUDE.md", &result.file_path)
+                    .await;
                 ToolResult::success(
                     "".to_string(),
                     format!(
@@ -219,4 +251,41 @@ impl OrchestratorAgent {
             ),
         }
     }
+
+    /// Store a generated artifact's content and count it on the pipeline
+    async fn record_artifact(&self, kind: GeneratedFileKind, name: &str, path: &str) {
+        let mut record = match generated_files::snapshot_artifact(kind, name, Path::new(path)) {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!(
+                    "[ORCHESTRATOR] Failed to snapshot {} '{}': {}",
+                    kind.as_str(),
+                    name,
+                    e
+                );
+                return;
+            }
+        };
+        record.pipeline_id = Some(self.pipeline_id.clone());
+        record.pipeline_state = Some(format!("{:?}", self.current_state));
+
+        let runs_db = match &self.agent_manager {
+            Some(am) => am.lock().await.runs_db.clone(),
+            None => None,
+        };
+        if let Some(db) = runs_db {
+            if let Err(e) = db.insert_generated_artifact(&record).await {
+                eprintln!(
+                    "[ORCHESTRATOR] Failed to persist artifact '{}': {}",
+                    name, e
+                );
+            }
+        }
+
+        if let Some(ref pipelines) = self.pipelines {
+            if let Some(pipeline) = pipelines.lock().await.get_mut(&self.pipeline_id) {
+                pipeline.artifact_counts.add(kind);
+            }
+        }
+    }
 }
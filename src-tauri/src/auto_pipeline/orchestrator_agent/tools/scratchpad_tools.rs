@@ -0,0 +1,91 @@
+// Scratchpad Tools
+//
+// Tools for the pipeline's shared scratchpad: read_scratchpad, append_scratchpad.
+// Also snapshots the scratchpad after each step so its history is persisted.
+
+use serde_json::{json, Value};
+
+use crate::agent_runs_db::ScratchpadVersionRecord;
+use crate::auto_pipeline::orchestrator_tools::{AppendScratchpadInput, ToolResult};
+use crate::utils::time::now_millis;
+
+use super::super::OrchestratorAgent;
+
+impl OrchestratorAgent {
+    /// Read scratchpad tool implementation
+    pub(crate) async fn tool_read_scratchpad(&self) -> ToolResult {
+        match self.scratchpad.read_for_orchestrator() {
+            Ok(content) => ToolResult::success("".to_string(), content),
+            Err(e) => ToolResult::error("".to_string(), e),
+        }
+    }
+
+    /// Append scratchpad tool implementation
+    pub(crate) async fn tool_append_scratchpad(&self, input: &Value) -> ToolResult {
+        let parsed: AppendScratchpadInput = match serde_json::from_value(input.clone()) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error("".to_string(), format!("Invalid input: {}", e)),
+        };
+
+        if parsed.content.trim().is_empty() {
+            return ToolResult::error(
+                "".to_string(),
+                "Scratchpad content cannot be empty.".to_string(),
+            );
+        }
+
+        match self
+            .scratchpad
+            .append(&self.user_request, &parsed.heading, &parsed.content)
+        {
+            Ok(()) => ToolResult::success(
+                "".to_string(),
+                format!(
+                    "Added section '{}' to the scratchpad.",
+                    parsed.heading.trim()
+                ),
+            ),
+            Err(e) => ToolResult::error("".to_string(), e),
+        }
+    }
+
+    /// Persist a snapshot of the scratchpad if it changed since the last one
+    pub(crate) async fn snapshot_scratchpad(&mut self, step_number: u32) {
+        let content = match self.scratchpad.read() {
+            Ok(content) if !content.trim().is_empty() => content,
+            Ok(_) => return,
+            Err(e) => {
+                eprintln!("[ORCHESTRATOR] Failed to snapshot scratchpad: {}", e);
+                return;
+            }
+        };
+        if self.last_scratchpad_snapshot.as_deref() == Some(content.as_str()) {
+            return;
+        }
+
+        let record = ScratchpadVersionRecord {
+            id: None,
+            pipeline_id: self.pipeline_id.clone(),
+            iteration: self.current_iteration as u32,
+            step_number: Some(step_number),
+            content: content.clone(),
+            timestamp: now_millis(),
+        };
+
+        let runs_db = match &self.agent_manager {
+            Some(am) => am.lock().await.runs_db.clone(),
+            None => None,
+        };
+        if let Some(db) = runs_db {
+            if let Err(e) = db.insert_scratchpad_version(&record).await {
+                eprintln!("[ORCHESTRATOR] Failed to persist scratchpad version: {}", e);
+            }
+        }
+
+        if let Some(ref emitter) = self.event_emitter {
+            let _ = emitter.emit("orchestrator:scratchpad_updated", json!(record));
+        }
+
+        self.last_scratchpad_snapshot = Some(content);
+    }
+}
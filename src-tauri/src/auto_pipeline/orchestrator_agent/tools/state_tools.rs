@@ -49,6 +49,7 @@ impl OrchestratorAgent {
         }
         // Clear implementation but keep plan
         self.current_implementation.clear();
+        self.verification_input = None;
 
         // Go back to ReadyForExecution state
         self.current_state = PipelineState::ReadyForExecution;
@@ -91,6 +92,7 @@ impl OrchestratorAgent {
         // Clear current plan and implementation, but keep skills
         self.current_plan.clear();
         self.current_implementation.clear();
+        self.verification_input = None;
 
         // Go back to Planning state (not ReceivedTask - we want to keep generated skills)
         self.set_state(PipelineState::Planning);
@@ -0,0 +1,294 @@
+// Step Agent Runner
+//
+// Spawns the Claude Code agent for a pipeline step and retries failed attempts
+// according to the step's retry policy. While the agent runs, its todo list is
+// followed to keep the step's progress current.
+
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::agent_manager::{AgentManager, AgentSpawnOptions};
+use crate::auto_pipeline::agent_utils::{extract_agent_output, send_prompt_and_wait};
+use crate::auto_pipeline::step_progress::{compute_progress, parse_plan_tasks};
+use crate::auto_pipeline::types::{StepOutput, StepStatus};
+use crate::events::AppEventEmitter;
+use crate::hook_server::subscribe_agent_todos;
+use crate::types::{AgentSource, ToolRestrictions};
+
+use super::super::OrchestratorAgent;
+
+/// Which pipeline step an agent is being run for
+pub(crate) struct StepAgentSpec {
+    /// Index into the pipeline's steps (0 = planning, 1 = building, 2 = verification)
+    pub index: usize,
+    /// Step name attached to the spawned agent
+    pub step_name: &'static str,
+    /// Short label used in error messages ("planning", "build", ...)
+    pub label: &'static str,
+    /// Model requested by the orchestrator (None for the configured default)
+    pub model: Option<String>,
+    /// MCP servers requested by the orchestrator, on top of WORKER_MCP_CONFIG's
+    pub mcp_servers: Option<serde_json::Value>,
+    /// Tools the agent is limited to, on top of the pipeline profile's
+    pub tools: ToolRestrictions,
+}
+
+/// Outcome of recording a failed attempt on the pipeline step
+struct RetryDecision {
+    attempt: u8,
+    max_retries: u8,
+}
+
+/// Build the prompt section telling a retried agent what went wrong last time
+fn build_retry_section(error: &str, attempt: u8, max_retries: u8) -> String {
+    format!(
+        "\n\n## PREVIOUS ATTEMPT FAILED (retry {} of {})\nThe previous attempt at this step failed with:\n{}\n\nThe failure may have been transient (flaky test, network). Check the current state of the working directory before redoing work, and avoid repeating whatever caused the failure.\n",
+        attempt, max_retries, error
+    )
+}
+
+impl OrchestratorAgent {
+    /// Run a step agent, retrying failed attempts while the step allows it.
+    ///
+    /// Each retry stops the failed agent, records the failure on the pipeline
+    /// step and re-runs the step with the failure appended to the prompt.
+    /// Once retries are exhausted the last error is returned.
+    pub(crate) async fn run_step_agent_with_retries(
+        &mut self,
+        spec: &StepAgentSpec,
+        prompt: &str,
+        agent_manager: Arc<Mutex<AgentManager>>,
+        event_emitter: Arc<dyn AppEventEmitter>,
+    ) -> Result<StepOutput, String> {
+        if let Err(e) = self.scratchpad.ensure_created(&self.user_request) {
+            eprintln!("[ORCHESTRATOR] {}", e);
+        }
+        let prompt = format!("{}{}", prompt, self.scratchpad.prompt_section());
+        let mut attempt_prompt = prompt.clone();
+
+        loop {
+            let error = match self
+                .run_step_agent(
+                    spec,
+                    &attempt_prompt,
+                    agent_manager.clone(),
+                    event_emitter.clone(),
+                )
+                .await
+            {
+                Ok(output) => {
+                    self.snapshot_scratchpad(spec.index as u32 + 1).await;
+                    return Ok(output);
+                }
+                Err(e) => e,
+            };
+
+            let Some(decision) = self.record_step_failure(spec.index, &error).await else {
+                return Err(error);
+            };
+
+            eprintln!(
+                "[ORCHESTRATOR] {} step failed, retrying ({}/{}): {}",
+                spec.step_name, decision.attempt, decision.max_retries, error
+            );
+
+            if let Some(failed_agent_id) = self.spawned_agents[spec.index].take() {
+                // Drained after releasing the lock
+                let begun = agent_manager
+                    .lock()
+                    .await
+                    .begin_stop(&failed_agent_id, false, None)
+                    .await;
+                if let Ok(pending) = begun {
+                    let _ = pending.finish().await;
+                }
+            }
+
+            let _ = event_emitter.emit(
+                "auto_pipeline:step_retry",
+                json!({
+                    "pipeline_id": self.pipeline_id,
+                    "step_number": spec.index + 1,
+                    "attempt": decision.attempt,
+                    "max_retries": decision.max_retries,
+                    "error": error,
+                }),
+            );
+
+            attempt_prompt = format!(
+                "{}{}",
+                prompt,
+                build_retry_section(&error, decision.attempt, decision.max_retries)
+            );
+        }
+    }
+
+    /// Record a failed attempt on the pipeline step.
+    ///
+    /// Returns the retry decision when the step was reset for another attempt,
+    /// or None when retries are exhausted (or there is no pipeline to track them on).
+    async fn record_step_failure(&self, index: usize, error: &str) -> Option<RetryDecision> {
+        let pipelines = self.pipelines.as_ref()?;
+        let mut pipelines = pipelines.lock().await;
+        let step = pipelines.get_mut(&self.pipeline_id)?.steps.get_mut(index)?;
+
+        if !step.record_failure(error) {
+            return None;
+        }
+
+        // The step goes straight back to running for the retry
+        step.status = StepStatus::Running;
+        step.started_at = Some(chrono::Utc::now().to_rfc3339());
+        Some(RetryDecision {
+            attempt: step.retry_count,
+            max_retries: step.max_retries,
+        })
+    }
+
+    /// Spawn a step agent, send it the prompt and wait for its output
+    async fn run_step_agent(
+        &mut self,
+        spec: &StepAgentSpec,
+        prompt: &str,
+        agent_manager: Arc<Mutex<AgentManager>>,
+        event_emitter: Arc<dyn AppEventEmitter>,
+    ) -> Result<StepOutput, String> {
+        // Spawn the agent (linked to this pipeline for historical queries)
+        let agent_id = {
+            let manager = agent_manager.lock().await;
+            manager
+                .create_agent_with_pipeline(
+                    self.working_dir.clone(),
+                    None,
+                    None,
+                    Vec::new(), // No pre-generated skills for pipeline agents
+                    AgentSource::Pipeline,
+                    event_emitter.clone(),
+                    AgentSpawnOptions {
+                        pipeline_id: Some(self.pipeline_id.clone()),
+                        title: Some(spec.step_name.to_string()),
+                        model: spec.model.clone(),
+                        mcp_servers: spec.mcp_servers.clone(),
+                        tools: spec.tools.clone(),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| format!("Failed to create {} agent: {}", spec.label, e))?
+        };
+
+        // Track the spawned agent for later cleanup
+        self.spawned_agents[spec.index] = Some(agent_id.clone());
+
+        // Subscribe before sending the prompt so no todo update is missed
+        let progress_watcher = self.spawn_progress_watcher(spec, &agent_id, event_emitter.clone());
+
+        let result = async {
+            // Send the prompt and wait for completion, holding it while
+            // Claude agents are rate-limited
+            send_prompt_and_wait(
+                &agent_id,
+                prompt,
+                agent_manager.clone(),
+                event_emitter.clone(),
+            )
+            .await
+            .map_err(|e| format!("{} agent failed: {}", spec.step_name, e))?;
+
+            // Extract output
+            extract_agent_output(&agent_id, agent_manager)
+                .await
+                .map_err(|e| format!("Failed to get {} output: {}", spec.label, e))
+        }
+        .await;
+
+        progress_watcher.abort();
+        result
+    }
+
+    /// Follow the step agent's todo list until aborted.
+    ///
+    /// Each TodoWrite updates the step's progress and emits
+    /// `auto_pipeline:step-progress`. Steps after planning also match the
+    /// todo items against the current plan's tasks.
+    fn spawn_progress_watcher(
+        &self,
+        spec: &StepAgentSpec,
+        agent_id: &str,
+        event_emitter: Arc<dyn AppEventEmitter>,
+    ) -> JoinHandle<()> {
+        let mut updates = subscribe_agent_todos();
+        let pipelines = self.pipelines.clone();
+        let pipeline_id = self.pipeline_id.clone();
+        let agent_id = agent_id.to_string();
+        let index = spec.index;
+        let plan_tasks = if index == 0 {
+            Vec::new()
+        } else {
+            parse_plan_tasks(&self.current_plan)
+        };
+
+        tokio::spawn(async move {
+            // Progress from a previous attempt doesn't apply to this agent
+            if let Some(ref pipelines) = pipelines {
+                if let Some(step) = pipelines
+                    .lock()
+                    .await
+                    .get_mut(&pipeline_id)
+                    .and_then(|p| p.steps.get_mut(index))
+                {
+                    step.progress = None;
+                }
+            }
+
+            loop {
+                let todos = match updates.recv().await {
+                    Ok((id, todos)) if id == agent_id => todos,
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                let progress = compute_progress(&todos, &plan_tasks);
+
+                if let Some(ref pipelines) = pipelines {
+                    if let Some(step) = pipelines
+                        .lock()
+                        .await
+                        .get_mut(&pipeline_id)
+                        .and_then(|p| p.steps.get_mut(index))
+                    {
+                        step.progress = Some(progress.clone());
+                    }
+                }
+
+                let _ = event_emitter.emit(
+                    "auto_pipeline:step-progress",
+                    json!({
+                        "pipeline_id": pipeline_id,
+                        "step_number": index + 1,
+                        "agent_id": agent_id,
+                        "percent": progress.percent,
+                        "completed": progress.completed,
+                        "total": progress.total,
+                        "in_progress": progress.in_progress,
+                        "plan_tasks": progress.plan_tasks,
+                    }),
+                );
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_retry_section_includes_error() {
+        let section = build_retry_section("cargo test timed out", 1, 2);
+        assert!(section.contains("retry 1 of 2"));
+        assert!(section.contains("cargo test timed out"));
+    }
+}
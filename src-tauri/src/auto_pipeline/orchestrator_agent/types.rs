@@ -33,6 +33,8 @@ pub enum OrchestratorAction {
     },
     /// Give up on the pipeline
     GiveUp { reason: String },
+    /// Pause until the user answers a question
+    NeedsHuman { question: String, context: String },
 }
 
 /// Message in the orchestrator's conversation
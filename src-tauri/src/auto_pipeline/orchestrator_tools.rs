@@ -59,13 +59,29 @@ pub fn get_orchestrator_tools() -> Vec<ToolDefinition> {
         tool_iterate(),
         tool_replan(),
         tool_give_up(),
+        tool_request_human_help(),
+        tool_send_message_to_agent(),
+        // Shared scratchpad (all phases)
+        tool_read_scratchpad(),
+        tool_append_scratchpad(),
     ]
 }
 
 /// Get tools available for a specific phase of the pipeline
 /// This enforces the workflow by only providing tools valid for the current state
-/// Note: give_up is intentionally NOT available in most phases to prevent the LLM from bailing out
+/// Note: give_up is intentionally NOT available in most phases to prevent the LLM from bailing out;
+/// request_human_help is offered wherever a decision is made instead
+/// The scratchpad tools are available in every non-terminal state
 pub fn get_tools_for_state(state: &PipelineState) -> Vec<ToolDefinition> {
+    let mut tools = phase_tools_for_state(state);
+    if !tools.is_empty() {
+        tools.push(tool_read_scratchpad());
+        tools.push(tool_append_scratchpad());
+    }
+    tools
+}
+
+fn phase_tools_for_state(state: &PipelineState) -> Vec<ToolDefinition> {
     match state {
         // Phase A: Skill & Subagent Synthesis - read instructions, create skills/subagents/CLAUDE.md, then start planning
         PipelineState::ReceivedTask
@@ -87,7 +103,12 @@ pub fn get_tools_for_state(state: &PipelineState) -> Vec<ToolDefinition> {
         PipelineState::Planning
         | PipelineState::PlanReady
         | PipelineState::PlanRevisionRequired => {
-            vec![tool_start_planning(), tool_approve_plan(), tool_replan()]
+            vec![
+                tool_start_planning(),
+                tool_approve_plan(),
+                tool_replan(),
+                tool_request_human_help(),
+            ]
         }
 
         // Phase C: Ready for Execution - must start execution
@@ -101,22 +122,43 @@ pub fn get_tools_for_state(state: &PipelineState) -> Vec<ToolDefinition> {
         }
 
         // Phase D: Verification - decide outcome
+        // Step agents can be asked follow-up questions before deciding
         PipelineState::Verifying => {
-            vec![tool_complete(), tool_iterate(), tool_replan()]
+            vec![
+                tool_complete(),
+                tool_iterate(),
+                tool_replan(),
+                tool_request_human_help(),
+                tool_send_message_to_agent(),
+            ]
         }
 
         // Verification passed - complete or iterate
         PipelineState::VerificationPassed => {
-            vec![tool_complete(), tool_iterate()]
+            vec![
+                tool_complete(),
+                tool_iterate(),
+                tool_request_human_help(),
+                tool_send_message_to_agent(),
+            ]
         }
 
         // Verification failed - iterate or replan (give_up only here after repeated failures)
         PipelineState::VerificationFailed => {
-            vec![tool_iterate(), tool_replan(), tool_give_up()]
+            vec![
+                tool_iterate(),
+                tool_replan(),
+                tool_give_up(),
+                tool_request_human_help(),
+                tool_send_message_to_agent(),
+            ]
         }
 
-        // Terminal states - no tools
-        PipelineState::Completed | PipelineState::Failed | PipelineState::GaveUp => {
+        // Waiting for the user, and terminal states - no tools
+        PipelineState::AwaitingHuman
+        | PipelineState::Completed
+        | PipelineState::Failed
+        | PipelineState::GaveUp => {
             vec![]
         }
     }
@@ -207,10 +249,32 @@ fn tool_generate_claudemd() -> ToolDefinition {
     }
 }
 
+/// Schema of the optional `mcp_servers` input of the agent-spawning tools
+fn mcp_servers_property(agent: &str) -> Value {
+    json!({
+        "type": "object",
+        "description": format!(
+            "MCP servers for the {} agent, in claude's --mcp-config format: {{\"mcpServers\": {{\"<name>\": {{\"command\": ..., \"args\": [...]}}}}}}. Added to the servers every agent gets from WORKER_MCP_CONFIG. Omit unless the task needs them.",
+            agent
+        )
+    })
+}
+
 // ============================================================================
 // Phase B: Planning Tools
 // ============================================================================
 
+/// Schema of the optional `model` input of the agent-spawning tools
+fn model_property(agent: &str) -> Value {
+    json!({
+        "type": "string",
+        "description": format!(
+            "Claude model for the {} agent: an alias (haiku, sonnet, opus) or a full model name like claude-sonnet-4-5. Omit to use the configured model.",
+            agent
+        )
+    })
+}
+
 fn tool_start_planning() -> ToolDefinition {
     ToolDefinition {
         name: "start_planning".to_string(),
@@ -221,7 +285,9 @@ fn tool_start_planning() -> ToolDefinition {
                 "summary": {
                     "type": "string",
                     "description": "Brief summary of the skills created and why they were chosen"
-                }
+                },
+                "model": model_property("planning"),
+                "mcp_servers": mcp_servers_property("planning")
             },
             "required": ["summary"]
         }),
@@ -260,7 +326,9 @@ fn tool_start_execution() -> ToolDefinition {
                 "notes": {
                     "type": "string",
                     "description": "Any notes or guidance for the build agent"
-                }
+                },
+                "model": model_property("build"),
+                "mcp_servers": mcp_servers_property("build")
             },
             "required": []
         }),
@@ -275,7 +343,7 @@ fn tool_start_verification() -> ToolDefinition {
     ToolDefinition {
         name: "start_verification".to_string(),
         description:
-            "Start the verification phase. A verification agent will review the implementation."
+            "Start the verification phase. A verification agent will review the implementation. It can read and search files, build, run tests and inspect git (diff, status, log, show); file-editing tools and common file-changing commands such as rm, sed -i and git commit are blocked."
                 .to_string(),
         input_schema: json!({
             "type": "object",
@@ -284,7 +352,9 @@ fn tool_start_verification() -> ToolDefinition {
                     "type": "array",
                     "items": { "type": "string" },
                     "description": "Specific areas to focus verification on"
-                }
+                },
+                "model": model_property("verification"),
+                "mcp_servers": mcp_servers_property("verification")
             },
             "required": []
         }),
@@ -375,6 +445,91 @@ fn tool_give_up() -> ToolDefinition {
     }
 }
 
+fn tool_request_human_help() -> ToolDefinition {
+    ToolDefinition {
+        name: "request_human_help".to_string(),
+        description: "Pause the pipeline and ask the user a question. Use when you are blocked on something only the user can decide or provide (an ambiguous requirement, a credential, a trade-off between valid approaches) rather than giving up. Progress is kept; the user's answer is added to this conversation and you continue from the current phase.".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "question": {
+                    "type": "string",
+                    "description": "The question for the user, answerable on its own"
+                },
+                "context": {
+                    "type": "string",
+                    "description": "What you have tried and why you need the user's input"
+                }
+            },
+            "required": ["question"]
+        }),
+    }
+}
+
+fn tool_send_message_to_agent() -> ToolDefinition {
+    ToolDefinition {
+        name: "send_message_to_agent".to_string(),
+        description: "Relay a message from one step agent to another and wait for the reply, e.g. when the verification agent has a clarifying question for the build agent. The message is marked as coming from the sending agent. Messages per pipeline are capped, so only relay questions that affect your decision.".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "from": {
+                    "type": "string",
+                    "enum": ["planning", "building", "verification"],
+                    "description": "The step agent the message comes from"
+                },
+                "to": {
+                    "type": "string",
+                    "enum": ["planning", "building", "verification"],
+                    "description": "The step agent to deliver the message to"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "The message text"
+                }
+            },
+            "required": ["from", "to", "message"]
+        }),
+    }
+}
+
+// ============================================================================
+// Shared Scratchpad Tools
+// ============================================================================
+
+fn tool_read_scratchpad() -> ToolDefinition {
+    ToolDefinition {
+        name: "read_scratchpad".to_string(),
+        description: "Read the pipeline's shared scratchpad: notes left by you and by the planning, build and verification agents (decisions, assumptions, gotchas, unfinished work).".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {},
+            "required": []
+        }),
+    }
+}
+
+fn tool_append_scratchpad() -> ToolDefinition {
+    ToolDefinition {
+        name: "append_scratchpad".to_string(),
+        description: "Append a section to the pipeline's shared scratchpad. Every agent spawned by this pipeline is told to read it, so use it for context the next agent needs rather than pasting it into prompts.".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "heading": {
+                    "type": "string",
+                    "description": "Section heading, e.g. 'Orchestrator: iteration 2 priorities'"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Markdown content of the section"
+                }
+            },
+            "required": ["heading", "content"]
+        }),
+    }
+}
+
 // ============================================================================
 // Tool Input Parsing
 // ============================================================================
@@ -410,6 +565,10 @@ pub struct GenerateClaudeMdInput {
 #[derive(Debug, Deserialize)]
 pub struct StartPlanningInput {
     pub summary: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub mcp_servers: Option<Value>,
 }
 
 /// Parsed input for approve_plan tool
@@ -423,6 +582,10 @@ pub struct ApprovePlanInput {
 pub struct StartExecutionInput {
     #[serde(default)]
     pub notes: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub mcp_servers: Option<Value>,
 }
 
 /// Parsed input for start_verification tool
@@ -430,6 +593,10 @@ pub struct StartExecutionInput {
 pub struct StartVerificationInput {
     #[serde(default)]
     pub focus_areas: Vec<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub mcp_servers: Option<Value>,
 }
 
 /// Parsed input for complete tool
@@ -455,12 +622,35 @@ pub struct ReplanInput {
     pub suggestions: Vec<String>,
 }
 
+/// Parsed input for append_scratchpad tool
+#[derive(Debug, Deserialize)]
+pub struct AppendScratchpadInput {
+    pub heading: String,
+    pub content: String,
+}
+
+/// Parsed input for send_message_to_agent tool
+#[derive(Debug, Deserialize)]
+pub struct SendMessageToAgentInput {
+    pub from: String,
+    pub to: String,
+    pub message: String,
+}
+
 /// Parsed input for give_up tool
 #[derive(Debug, Deserialize)]
 pub struct GiveUpInput {
     pub reason: String,
 }
 
+/// Parsed input for request_human_help tool
+#[derive(Debug, Deserialize)]
+pub struct RequestHumanHelpInput {
+    pub question: String,
+    #[serde(default)]
+    pub context: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -478,6 +668,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_orchestrator_cannot_reach_pipeline_control_tools() {
+        use crate::meta_agent::tools::pipeline_tools::PIPELINE_CONTROL_TOOLS;
+
+        // A pipeline's orchestrator must never be able to start or cancel pipelines
+        for tool in get_orchestrator_tools() {
+            assert!(!PIPELINE_CONTROL_TOOLS.contains(&tool.name.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_scratchpad_tools_available_until_terminal() {
+        for state in [
+            PipelineState::ReceivedTask,
+            PipelineState::Planning,
+            PipelineState::Executing,
+            PipelineState::VerificationFailed,
+        ] {
+            let names: Vec<_> = get_tools_for_state(&state)
+                .into_iter()
+                .map(|t| t.name)
+                .collect();
+            assert!(names.contains(&"read_scratchpad".to_string()));
+            assert!(names.contains(&"append_scratchpad".to_string()));
+        }
+        assert!(get_tools_for_state(&PipelineState::Completed).is_empty());
+    }
+
+    #[test]
+    fn test_agent_messaging_only_after_verification_started() {
+        let has_messaging = |state: PipelineState| {
+            get_tools_for_state(&state)
+                .iter()
+                .any(|t| t.name == "send_message_to_agent")
+        };
+        assert!(!has_messaging(PipelineState::Planning));
+        assert!(!has_messaging(PipelineState::Executing));
+        assert!(has_messaging(PipelineState::Verifying));
+        assert!(has_messaging(PipelineState::VerificationFailed));
+    }
+
+    #[test]
+    fn test_human_help_offered_where_decisions_are_made() {
+        let has_help = |state: PipelineState| {
+            get_tools_for_state(&state)
+                .iter()
+                .any(|t| t.name == "request_human_help")
+        };
+        assert!(has_help(PipelineState::PlanReady));
+        assert!(has_help(PipelineState::Verifying));
+        assert!(has_help(PipelineState::VerificationFailed));
+        assert!(!has_help(PipelineState::Executing));
+        assert!(get_tools_for_state(&PipelineState::AwaitingHuman).is_empty());
+    }
+
     #[test]
     fn test_parse_read_instruction_file_input() {
         let input: ReadInstructionFileInput =
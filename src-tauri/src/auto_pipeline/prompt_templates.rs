@@ -0,0 +1,335 @@
+// Prompt templates - Per-workspace overrides for worker agent kickoff prompts
+//
+// The planning, builder and verifier agents are started with prompts rendered
+// from templates. A workspace can replace any of them with its own template,
+// stored as `.commander/prompts/<name>.md` in the working directory. Templates
+// are validated when saved and again when loaded, so a broken template (one
+// missing a required placeholder, or hand-edited to use an unknown one) fails
+// the step with a list of the offending placeholders instead of silently
+// sending a degraded prompt.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::prompts::{
+    template_placeholders, BUILDER_AGENT_TEMPLATE, PLANNING_AGENT_TEMPLATE, VERIFIER_AGENT_TEMPLATE,
+};
+use super::types::PromptTemplateUsage;
+use crate::generated_files::COMMANDER_DIR;
+
+const PROMPTS_DIR: &str = "prompts";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptTemplateName {
+    Planning,
+    Builder,
+    Verifier,
+}
+
+impl PromptTemplateName {
+    pub const ALL: [PromptTemplateName; 3] = [Self::Planning, Self::Builder, Self::Verifier];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Planning => "planning",
+            Self::Builder => "builder",
+            Self::Verifier => "verifier",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_lowercase().as_str() {
+            "planning" => Ok(Self::Planning),
+            "builder" => Ok(Self::Builder),
+            "verifier" => Ok(Self::Verifier),
+            other => Err(format!(
+                "Unknown prompt template '{}' (expected planning, builder or verifier)",
+                other
+            )),
+        }
+    }
+
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            Self::Planning => PLANNING_AGENT_TEMPLATE,
+            Self::Builder => BUILDER_AGENT_TEMPLATE,
+            Self::Verifier => VERIFIER_AGENT_TEMPLATE,
+        }
+    }
+
+    /// Placeholders a template must contain
+    pub fn required_placeholders(&self) -> &'static [&'static str] {
+        match self {
+            Self::Planning => &["user_request"],
+            Self::Builder => &["user_request", "plan", "qna"],
+            Self::Verifier => &["user_request", "plan", "implementation"],
+        }
+    }
+
+    /// Every placeholder the prompt builder fills in
+    pub fn allowed_placeholders(&self) -> &'static [&'static str] {
+        match self {
+            Self::Planning => &["user_request", "working_dir", "skills", "subagents"],
+            Self::Builder => &[
+                "user_request",
+                "working_dir",
+                "plan",
+                "qna",
+                "skills",
+                "subagents",
+                "notes",
+            ],
+            Self::Verifier => &[
+                "user_request",
+                "working_dir",
+                "plan",
+                "qna",
+                "implementation",
+                "focus",
+                "skills",
+                "subagents",
+            ],
+        }
+    }
+}
+
+/// A template ready to render, with where it came from
+#[derive(Debug, Clone)]
+pub struct ResolvedTemplate {
+    pub content: String,
+    pub custom: bool,
+    pub hash: String,
+}
+
+impl ResolvedTemplate {
+    pub fn usage(&self, name: PromptTemplateName) -> PromptTemplateUsage {
+        PromptTemplateUsage {
+            name: name.as_str().to_string(),
+            hash: self.hash.clone(),
+            custom: self.custom,
+        }
+    }
+}
+
+/// Template state returned by get_prompt_templates
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptTemplateInfo {
+    pub name: String,
+    /// The template in effect (the override, or the default)
+    pub content: String,
+    pub default_content: String,
+    pub is_custom: bool,
+    pub hash: String,
+    pub required_placeholders: Vec<String>,
+    pub allowed_placeholders: Vec<String>,
+}
+
+/// Content hash recorded on pipelines. FNV-1a rather than DefaultHasher so the
+/// value stays comparable across builds and Rust versions.
+pub fn template_hash(content: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Check a template's placeholders, listing every missing and unknown one
+pub fn validate_template(name: PromptTemplateName, content: &str) -> Result<(), String> {
+    let used = template_placeholders(content);
+    let missing: Vec<&str> = name
+        .required_placeholders()
+        .iter()
+        .copied()
+        .filter(|p| !used.contains(p))
+        .collect();
+    let unknown: Vec<&str> = used
+        .iter()
+        .copied()
+        .filter(|p| !name.allowed_placeholders().contains(p))
+        .collect();
+
+    if missing.is_empty() && unknown.is_empty() {
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!(
+            "missing required placeholders: {}",
+            missing
+                .iter()
+                .map(|p| format!("{{{}}}", p))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if !unknown.is_empty() {
+        problems.push(format!(
+            "unknown placeholders: {}",
+            unknown
+                .iter()
+                .map(|p| format!("{{{}}}", p))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    Err(format!(
+        "Invalid {} prompt template: {}",
+        name.as_str(),
+        problems.join("; ")
+    ))
+}
+
+fn template_path(working_dir: &str, name: PromptTemplateName) -> PathBuf {
+    Path::new(working_dir)
+        .join(COMMANDER_DIR)
+        .join(PROMPTS_DIR)
+        .join(format!("{}.md", name.as_str()))
+}
+
+fn read_override(working_dir: &str, name: PromptTemplateName) -> Result<Option<String>, String> {
+    let path = template_path(working_dir, name);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// The template to use for a worker agent in this workspace
+pub fn load_template(
+    working_dir: &str,
+    name: PromptTemplateName,
+) -> Result<ResolvedTemplate, String> {
+    match read_override(working_dir, name)? {
+        Some(content) => {
+            validate_template(name, &content)?;
+            Ok(ResolvedTemplate {
+                hash: template_hash(&content),
+                content,
+                custom: true,
+            })
+        }
+        None => Ok(ResolvedTemplate {
+            content: name.default_template().to_string(),
+            custom: false,
+            hash: template_hash(name.default_template()),
+        }),
+    }
+}
+
+/// Validate and store a workspace override
+pub fn set_template(
+    working_dir: &str,
+    name: PromptTemplateName,
+    content: &str,
+) -> Result<(), String> {
+    validate_template(name, content)?;
+
+    let path = template_path(working_dir, name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Remove a workspace override so the default template is used again
+pub fn reset_template(working_dir: &str, name: PromptTemplateName) -> Result<(), String> {
+    let path = template_path(working_dir, name);
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+/// All templates for a workspace. Overrides are returned even when invalid so
+/// they can be fixed in the editor.
+pub fn list_templates(working_dir: &str) -> Result<Vec<PromptTemplateInfo>, String> {
+    PromptTemplateName::ALL
+        .iter()
+        .map(|name| {
+            let custom = read_override(working_dir, *name)?;
+            let is_custom = custom.is_some();
+            let content = custom.unwrap_or_else(|| name.default_template().to_string());
+            Ok(PromptTemplateInfo {
+                name: name.as_str().to_string(),
+                hash: template_hash(&content),
+                content,
+                default_content: name.default_template().to_string(),
+                is_custom,
+                required_placeholders: to_strings(name.required_placeholders()),
+                allowed_placeholders: to_strings(name.allowed_placeholders()),
+            })
+        })
+        .collect()
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_templates_are_valid() {
+        for name in PromptTemplateName::ALL {
+            validate_template(name, name.default_template()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_validation_lists_missing_and_unknown_placeholders() {
+        let err = validate_template(PromptTemplateName::Builder, "Do {user_request} per {plam}")
+            .unwrap_err();
+        assert!(
+            err.contains("missing required placeholders: {plan}, {qna}"),
+            "{}",
+            err
+        );
+        assert!(err.contains("unknown placeholders: {plam}"), "{}", err);
+
+        // JSON examples in a template aren't placeholders
+        validate_template(
+            PromptTemplateName::Planning,
+            "{user_request}\nReply with {\"plan\": \"...\"}",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_override_set_load_and_reset() {
+        let dir = TempDir::new().unwrap();
+        let wd = dir.path().to_str().unwrap();
+        let name = PromptTemplateName::Verifier;
+
+        let default = load_template(wd, name).unwrap();
+        assert!(!default.custom);
+
+        assert!(set_template(wd, name, "Check {user_request}").is_err());
+        set_template(
+            wd,
+            name,
+            "Check {implementation} against {plan} for {user_request}",
+        )
+        .unwrap();
+        let custom = load_template(wd, name).unwrap();
+        assert!(custom.custom);
+        assert_ne!(custom.hash, default.hash);
+
+        // A hand-edited broken override fails fast instead of falling back
+        std::fs::write(template_path(wd, name), "Check {user_request}").unwrap();
+        assert!(load_template(wd, name).is_err());
+        assert!(list_templates(wd).unwrap()[2].is_custom);
+
+        reset_template(wd, name).unwrap();
+        assert_eq!(load_template(wd, name).unwrap().hash, default.hash);
+        reset_template(wd, name).unwrap();
+    }
+}
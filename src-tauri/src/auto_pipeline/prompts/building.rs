@@ -2,6 +2,8 @@
 //
 // Prompts used during the building/execution phase of the automated development pipeline.
 
+use super::templates::render_template;
+
 /// Builder step prompt template (legacy mode)
 /// Placeholders: {user_request}, {plan}, {qna}, {working_dir}
 pub const BUILDER_PROMPT_TEMPLATE: &str = r#"You are an expert builder agent in a 3-step automated development pipeline.
@@ -65,20 +67,10 @@ Important:
 - Write code that works, not code that's perfect
 - If something in the plan doesn't fit the codebase, use your judgment"#;
 
-/// Build the builder/execution agent prompt (v2 mode - OrchestratorAgent)
-///
-/// Used when spawning a Claude Code build agent via the orchestrator.
-pub fn build_builder_prompt(
-    user_request: &str,
-    working_dir: &str,
-    current_plan: &str,
-    current_qna: &str,
-    skills_section: &str,
-    subagents_section: &str,
-    notes_section: &str,
-) -> String {
-    format!(
-        r#"You are implementing a solution based on the plan below.
+/// Builder agent prompt template (v2 mode - OrchestratorAgent)
+/// Placeholders: {user_request}, {working_dir}, {plan}, {qna}, {skills}, {subagents}, {notes}
+/// Can be overridden per workspace (see prompt_templates.rs)
+pub const BUILDER_AGENT_TEMPLATE: &str = r#"You are implementing a solution based on the plan below.
 
 ## USER REQUEST
 {user_request}
@@ -87,12 +79,12 @@ pub fn build_builder_prompt(
 {working_dir}
 
 ## IMPLEMENTATION PLAN
-{current_plan}
+{plan}
 
 ## Q&A
-{current_qna}
-{skills_section}
-{subagents_section}{notes_section}
+{qna}
+{skills}
+{subagents}{notes}
 
 ## YOUR TASK
 Implement the plan step by step. Create all necessary files and make the solution work.
@@ -103,6 +95,32 @@ Implement the plan step by step. Create all necessary files and make the solutio
 - Test as you go when possible
 - All files must be within {working_dir}
 
-When done, provide a summary of what was implemented."#
+When done, provide a summary of what was implemented."#;
+
+/// Build the builder/execution agent prompt (v2 mode - OrchestratorAgent)
+///
+/// Used when spawning a Claude Code build agent via the orchestrator.
+#[allow(clippy::too_many_arguments)]
+pub fn build_builder_prompt(
+    template: &str,
+    user_request: &str,
+    working_dir: &str,
+    current_plan: &str,
+    current_qna: &str,
+    skills_section: &str,
+    subagents_section: &str,
+    notes_section: &str,
+) -> String {
+    render_template(
+        template,
+        &[
+            ("user_request", user_request),
+            ("working_dir", working_dir),
+            ("plan", current_plan),
+            ("qna", current_qna),
+            ("skills", skills_section),
+            ("subagents", subagents_section),
+            ("notes", notes_section),
+        ],
     )
 }
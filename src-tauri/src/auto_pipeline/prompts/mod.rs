@@ -13,17 +13,21 @@ mod verification;
 
 // Re-export from templates module
 pub use templates::{
-    build_initial_prompt, DEFAULT_CUSTOM_INSTRUCTIONS, JSON_FORMAT_INSTRUCTION,
-    QNA_GENERATION_PROMPT, TASK_REFINEMENT_PROMPT, WORKING_DIR_CONSTRAINT,
+    build_initial_prompt, render_template, template_placeholders, DEFAULT_CUSTOM_INSTRUCTIONS,
+    JSON_FORMAT_INSTRUCTION, QNA_GENERATION_PROMPT, TASK_REFINEMENT_PROMPT, WORKING_DIR_CONSTRAINT,
 };
 
 // Re-export from planning module
-pub use planning::{build_planning_prompt, PLANNING_PROMPT_TEMPLATE, REPLAN_PROMPT_TEMPLATE};
+pub use planning::{
+    build_planning_prompt, PLANNING_AGENT_TEMPLATE, PLANNING_PROMPT_TEMPLATE,
+    REPLAN_PROMPT_TEMPLATE,
+};
 
 // Re-export from building module
-pub use building::{build_builder_prompt, BUILDER_PROMPT_TEMPLATE};
+pub use building::{build_builder_prompt, BUILDER_AGENT_TEMPLATE, BUILDER_PROMPT_TEMPLATE};
 
 // Re-export from verification module
 pub use verification::{
-    build_verification_prompt, VERIFICATION_DECISION_PROMPT, VERIFIER_PROMPT_TEMPLATE,
+    build_verification_prompt, VERIFICATION_DECISION_PROMPT, VERIFIER_AGENT_TEMPLATE,
+    VERIFIER_PROMPT_TEMPLATE,
 };
@@ -2,6 +2,8 @@
 //
 // Prompts used during the planning phase of the automated development pipeline.
 
+use super::templates::render_template;
+
 /// Planning step prompt template (legacy mode)
 /// Placeholders: {user_request}, {working_dir}
 pub const PLANNING_PROMPT_TEMPLATE: &str = r#"You are coordinating a 3-step automated development pipeline.
@@ -92,25 +94,18 @@ After the Plan agent completes, format its output as JSON:
   "changes_from_previous": ["What's different: ..."]
 }}"#;
 
-/// Build the planning agent prompt (v2 mode - OrchestratorAgent)
-///
-/// Used when spawning a Claude Code planning agent via the orchestrator.
-pub fn build_planning_prompt(
-    user_request: &str,
-    working_dir: &str,
-    skills_section: &str,
-    subagents_section: &str,
-) -> String {
-    format!(
-        r#"You are planning an implementation for the following task.
+/// Planning agent prompt template (v2 mode - OrchestratorAgent)
+/// Placeholders: {user_request}, {working_dir}, {skills}, {subagents}
+/// Can be overridden per workspace (see prompt_templates.rs)
+pub const PLANNING_AGENT_TEMPLATE: &str = r#"You are planning an implementation for the following task.
 
 ## USER REQUEST
 {user_request}
 
 ## WORKING DIRECTORY
 {working_dir}
-{skills_section}
-{subagents_section}
+{skills}
+{subagents}
 
 ## YOUR TASK
 Create a detailed, actionable implementation plan. The plan must include:
@@ -124,7 +119,7 @@ Create a detailed, actionable implementation plan. The plan must include:
 
 ## OUTPUT FORMAT
 Respond with valid JSON:
-{{
+{
   "plan": [
     "Step 1: Create directory structure...",
     "Step 2: Install dependencies...",
@@ -137,8 +132,27 @@ Respond with valid JSON:
     "Clarifying question 1?",
     "Clarifying question 2?"
   ]
-}}
+}
+
+Be specific and actionable - each step should be clear enough that another agent can implement it."#;
 
-Be specific and actionable - each step should be clear enough that another agent can implement it."#
+/// Build the planning agent prompt (v2 mode - OrchestratorAgent)
+///
+/// Used when spawning a Claude Code planning agent via the orchestrator.
+pub fn build_planning_prompt(
+    template: &str,
+    user_request: &str,
+    working_dir: &str,
+    skills_section: &str,
+    subagents_section: &str,
+) -> String {
+    render_template(
+        template,
+        &[
+            ("user_request", user_request),
+            ("working_dir", working_dir),
+            ("skills", skills_section),
+            ("subagents", subagents_section),
+        ],
     )
 }
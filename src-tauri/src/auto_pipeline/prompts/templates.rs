@@ -90,6 +90,11 @@ A verification agent will review the implementation.
 - Call `replan` if the approach needs to change fundamentally and be rethought
 - Call `give_up` only if the task is truly impossible after multiple attempts
 
+## Shared Scratchpad
+Every agent you spawn is told to read a shared scratchpad file in the working directory and to append notes for the agents after it. These tools are available in every phase:
+- `read_scratchpad` - see what the agents have recorded (decisions, assumptions, unfinished work)
+- `append_scratchpad` - input: `heading`, `content`; leave guidance for the next agent instead of repeating it in every prompt
+
 ## Available Instruction Files
 {instruction_list}
 {custom_section}
@@ -163,3 +168,50 @@ For each question, provide a concise, direct answer that:
 
 Respond with a JSON array of strings, one answer per question in the same order:
 ["Answer to Q1", "Answer to Q2", ...]"#;
+
+/// Placeholder names (`{name}`, lowercase letters and underscores) used in a template
+pub fn template_placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_lowercase() || c == '_'))
+            .unwrap_or(rest.len());
+        if len > 0 && rest[len..].starts_with('}') && !names.contains(&&rest[..len]) {
+            names.push(&rest[..len]);
+        }
+    }
+    names
+}
+
+/// Fill `{name}` placeholders in a single pass, so placeholder-like text in
+/// the values (a plan that mentions `{qna}`) is never expanded. Placeholders
+/// without a value are left as they are.
+pub fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let name = &after[..close];
+            values
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| (close, *value))
+        });
+        match value {
+            Some((close, value)) => {
+                out.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
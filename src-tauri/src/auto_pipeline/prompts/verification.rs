@@ -2,6 +2,8 @@
 //
 // Prompts used during the verification phase and decision-making of the automated development pipeline.
 
+use super::templates::render_template;
+
 /// Verifier step prompt template (legacy mode)
 /// Placeholders: {user_request}, {plan}, {qna}, {implementation}
 pub const VERIFIER_PROMPT_TEMPLATE: &str = r#"You are an expert verification agent in a 3-step automated development pipeline.
@@ -146,31 +148,23 @@ Respond with valid JSON:
   "suggestions": ["Suggestion for next iteration", "Another suggestion"]
 }}"#;
 
-/// Build the verification agent prompt (v2 mode - OrchestratorAgent)
-///
-/// Used when spawning a Claude Code verification agent via the orchestrator.
-pub fn build_verification_prompt(
-    user_request: &str,
-    current_plan: &str,
-    current_implementation: &str,
-    focus_section: &str,
-    skills_section: &str,
-    subagents_section: &str,
-) -> String {
-    format!(
-        r#"You are verifying an implementation.
+/// Verifier agent prompt template (v2 mode - OrchestratorAgent)
+/// Placeholders: {user_request}, {working_dir}, {plan}, {qna}, {implementation}, {focus},
+///               {skills}, {subagents}
+/// Can be overridden per workspace (see prompt_templates.rs)
+pub const VERIFIER_AGENT_TEMPLATE: &str = r#"You are verifying an implementation.
 
 ## USER REQUEST
 {user_request}
 
 ## PLAN
-{current_plan}
+{plan}
 
-## IMPLEMENTATION SUMMARY
-{current_implementation}
-{focus_section}
-{skills_section}
-{subagents_section}
+## IMPLEMENTATION
+{implementation}
+{focus}
+{skills}
+{subagents}
 
 ## YOUR TASK
 Review the implementation and verify it works:
@@ -183,19 +177,47 @@ Review the implementation and verify it works:
 
 ## OUTPUT FORMAT
 Respond with valid JSON:
-{{
+{
   "overall_status": "success" | "partial" | "failed",
   "files_reviewed": ["file1.py", "file2.py"],
   "tests_performed": ["test 1 result", "test 2 result"],
   "issues_found": [
-    {{
+    {
       "severity": "critical" | "major" | "minor",
       "description": "Issue description",
       "location": "file:line"
-    }}
+    }
   ],
   "recommendations": ["recommendation 1", "recommendation 2"],
   "summary": "Overall assessment"
-}}"#
+}"#;
+
+/// Build the verification agent prompt (v2 mode - OrchestratorAgent)
+///
+/// Used when spawning a Claude Code verification agent via the orchestrator.
+#[allow(clippy::too_many_arguments)]
+pub fn build_verification_prompt(
+    template: &str,
+    user_request: &str,
+    working_dir: &str,
+    current_plan: &str,
+    current_qna: &str,
+    current_implementation: &str,
+    focus_section: &str,
+    skills_section: &str,
+    subagents_section: &str,
+) -> String {
+    render_template(
+        template,
+        &[
+            ("user_request", user_request),
+            ("working_dir", working_dir),
+            ("plan", current_plan),
+            ("qna", current_qna),
+            ("implementation", current_implementation),
+            ("focus", focus_section),
+            ("skills", skills_section),
+            ("subagents", subagents_section),
+        ],
     )
 }
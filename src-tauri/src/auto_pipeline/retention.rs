@@ -0,0 +1,212 @@
+// Pipeline retention
+//
+// The manager keeps every pipeline in memory, so a long session collects
+// hundreds of finished ones. Every GC_INTERVAL, finished pipelines (completed,
+// failed or cancelled) that ended more than PIPELINE_RETENTION_MINUTES ago are
+// saved to the pipeline_snapshots table and dropped from memory, along with
+// their orchestrator. list_auto_pipelines and get_auto_pipeline read evicted
+// pipelines back from the database.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::agent_runs_db::{AgentRunsDB, PipelineSnapshotRecord};
+use crate::commands::env_registry;
+use crate::memory_footprint;
+use crate::utils::time::now_millis;
+
+use super::steps::StepExecutionContext;
+use super::{AutoPipeline, AutoPipelineManager};
+
+/// Minutes a finished pipeline stays in memory (PIPELINE_RETENTION_MINUTES)
+pub const DEFAULT_RETENTION_MINUTES: u64 = 60;
+
+/// How often finished pipelines are checked for eviction
+pub const GC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How long finished pipelines stay in memory; None keeps them
+pub fn retention_from_env() -> Option<Duration> {
+    let minutes = env_registry::PIPELINE_RETENTION_MINUTES
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RETENTION_MINUTES);
+    (minutes > 0).then(|| Duration::from_secs(minutes * 60))
+}
+
+/// Whether a pipeline has reached a final status
+pub fn is_finished(pipeline: &AutoPipeline) -> bool {
+    matches!(
+        pipeline.status.as_str(),
+        "completed" | "failed" | "cancelled"
+    )
+}
+
+fn rfc3339_millis(timestamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.timestamp_millis())
+}
+
+/// Which pipelines list_auto_pipelines returns
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PipelineListFilter {
+    /// Include finished pipelines, evicted ones too
+    pub include_finished: bool,
+    /// Only pipelines created at or after this time (ms)
+    pub since: Option<i64>,
+}
+
+impl PipelineListFilter {
+    pub fn matches(&self, pipeline: &AutoPipeline) -> bool {
+        if !self.include_finished && is_finished(pipeline) {
+            return false;
+        }
+        match self.since {
+            Some(since) => rfc3339_millis(&pipeline.created_at).is_some_and(|t| t >= since),
+            None => true,
+        }
+    }
+}
+
+/// A finished pipeline as saved when evicted
+pub fn to_snapshot(
+    pipeline: &AutoPipeline,
+    evicted_at: i64,
+) -> Result<PipelineSnapshotRecord, String> {
+    Ok(PipelineSnapshotRecord {
+        pipeline_id: pipeline.id.clone(),
+        status: pipeline.status.clone(),
+        pipeline: serde_json::to_string(pipeline)
+            .map_err(|e| format!("Failed to serialize pipeline: {}", e))?,
+        created_at: rfc3339_millis(&pipeline.created_at).unwrap_or(evicted_at),
+        completed_at: pipeline.completed_at.as_deref().and_then(rfc3339_millis),
+        evicted_at,
+    })
+}
+
+pub fn from_snapshot(record: &PipelineSnapshotRecord) -> Result<AutoPipeline, String> {
+    serde_json::from_str(&record.pipeline).map_err(|e| {
+        format!(
+            "Failed to read evicted pipeline {}: {}",
+            record.pipeline_id, e
+        )
+    })
+}
+
+/// Save finished pipelines that ended more than `retention` ago and drop them
+/// from memory. Returns how many were evicted.
+pub async fn evict_finished(
+    ctx: &StepExecutionContext,
+    runs_db: &AgentRunsDB,
+    retention: Duration,
+) -> Result<usize, String> {
+    let now = now_millis();
+    let cutoff = now - retention.as_millis() as i64;
+    let candidates: Vec<AutoPipeline> = {
+        let running = ctx.running.lock().await;
+        let pipelines = ctx.pipelines.lock().await;
+        pipelines
+            .values()
+            .filter(|p| is_finished(p) && !running.contains_key(&p.id))
+            .filter(|p| {
+                p.completed_at
+                    .as_deref()
+                    .and_then(rfc3339_millis)
+                    .is_some_and(|t| t < cutoff)
+            })
+            .cloned()
+            .collect()
+    };
+
+    let mut evicted = 0;
+    for pipeline in candidates {
+        let snapshot = to_snapshot(&pipeline, now)?;
+        runs_db
+            .save_pipeline_snapshot(&snapshot)
+            .await
+            .map_err(|e| format!("Failed to save pipeline {}: {}", pipeline.id, e))?;
+        ctx.pipelines.lock().await.remove(&pipeline.id);
+        if ctx
+            .orchestrator_agents
+            .lock()
+            .await
+            .remove(&pipeline.id)
+            .is_some()
+        {
+            memory_footprint::forget_conversation(&format!("orchestrator:{}", pipeline.id));
+        }
+        evicted += 1;
+    }
+    Ok(evicted)
+}
+
+/// Evict finished pipelines every GC_INTERVAL (unless retention is disabled)
+pub fn start_gc(manager: Arc<Mutex<AutoPipelineManager>>, runs_db: Arc<AgentRunsDB>) {
+    let Some(retention) = retention_from_env() else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(GC_INTERVAL).await;
+            let ctx = manager.lock().await.get_ctx();
+            match evict_finished(&ctx, &runs_db, retention).await {
+                Ok(evicted) => eprintln!(
+                    "[pipeline_gc] Evicted {} finished pipeline(s), {} left in memory",
+                    evicted,
+                    ctx.pipelines.lock().await.len()
+                ),
+                Err(e) => eprintln!("[pipeline_gc] Failed to evict finished pipelines: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(id: &str, created_at: &str) -> AutoPipeline {
+        let mut pipeline = AutoPipeline::new(
+            id.to_string(),
+            "Add a health check".to_string(),
+            "/tmp/repo".to_string(),
+            3,
+        );
+        pipeline.created_at = created_at.to_string();
+        pipeline
+    }
+
+    #[test]
+    fn test_filter_and_snapshot_round_trip() {
+        let mut finished = pipeline("p1", "2026-01-01T10:00:00+00:00");
+        finished.mark_completed("complete");
+        let running = pipeline("p2", "2026-01-02T10:00:00+00:00");
+
+        let active_only = PipelineListFilter::default();
+        assert!(!active_only.matches(&finished));
+        assert!(active_only.matches(&running));
+
+        let since = PipelineListFilter {
+            include_finished: true,
+            since: rfc3339_millis("2026-01-02T00:00:00+00:00"),
+        };
+        assert!(!since.matches(&finished));
+        assert!(since.matches(&running));
+
+        let snapshot = to_snapshot(&finished, 42).unwrap();
+        assert_eq!(snapshot.status, "completed");
+        assert_eq!(
+            snapshot.created_at,
+            rfc3339_millis("2026-01-01T10:00:00+00:00").unwrap()
+        );
+        assert!(snapshot.completed_at.is_some());
+        let restored = from_snapshot(&snapshot).unwrap();
+        assert_eq!(restored.id, "p1");
+        assert_eq!(restored.final_decision.as_deref(), Some("complete"));
+    }
+}
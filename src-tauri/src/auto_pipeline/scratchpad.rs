@@ -0,0 +1,158 @@
+// Pipeline Scratchpad
+//
+// A Markdown file shared by the orchestrator and every agent a pipeline spawns.
+// Planning, build and verification agents otherwise only see what the
+// orchestrator pastes into their prompts; the scratchpad lets them leave notes
+// (decisions, gotchas, open questions) for the agents that come after them.
+//
+// The file lives in the pipeline's working directory so workers can read and
+// edit it directly. The orchestrator uses the read_scratchpad and
+// append_scratchpad tools. A copy is persisted after each step so the
+// pipeline history shows how it evolved.
+
+use std::path::{Path, PathBuf};
+
+use crate::generated_files::{self, GeneratedFileKind};
+
+/// Directory (relative to the working directory) holding pipeline scratchpads
+const SCRATCHPAD_DIR: &str = ".claude/scratchpads";
+
+/// Maximum characters of the scratchpad returned to the orchestrator
+const MAX_READ_CHARS: usize = 20_000;
+
+/// The scratchpad file for one pipeline
+#[derive(Debug, Clone)]
+pub struct Scratchpad {
+    path: PathBuf,
+    working_dir: String,
+    pipeline_id: String,
+}
+
+impl Scratchpad {
+    pub fn for_pipeline(working_dir: &str, pipeline_id: &str) -> Self {
+        Self {
+            path: Path::new(working_dir)
+                .join(SCRATCHPAD_DIR)
+                .join(format!("{}.md", pipeline_id)),
+            working_dir: working_dir.to_string(),
+            pipeline_id: pipeline_id.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create the file with a header describing the task, if it doesn't exist yet
+    pub fn ensure_created(&self, user_request: &str) -> Result<(), String> {
+        if self.path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create scratchpad directory: {}", e))?;
+        }
+        let header = format!(
+            "# Pipeline Scratchpad\n\nShared notes for every agent working on this task. Append what the next agent needs to know; don't delete other agents' notes.\n\n## Task\n{}\n",
+            user_request.trim()
+        );
+        std::fs::write(&self.path, header)
+            .map_err(|e| format!("Failed to create scratchpad: {}", e))?;
+        generated_files::record(
+            &self.working_dir,
+            &self.path,
+            GeneratedFileKind::Scratchpad,
+            true,
+            Some(&self.pipeline_id),
+        );
+        Ok(())
+    }
+
+    /// Current contents, or an empty string if nothing has been written yet
+    pub fn read(&self) -> Result<String, String> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(format!("Failed to read scratchpad: {}", e)),
+        }
+    }
+
+    /// Contents for the orchestrator, keeping the most recent notes within budget
+    pub fn read_for_orchestrator(&self) -> Result<String, String> {
+        let content = self.read()?;
+        if content.trim().is_empty() {
+            return Ok("The scratchpad is empty.".to_string());
+        }
+
+        let total = content.chars().count();
+        if total <= MAX_READ_CHARS {
+            return Ok(content);
+        }
+        let tail: String = content.chars().skip(total - MAX_READ_CHARS).collect();
+        Ok(format!(
+            "[Earlier notes omitted, showing the last {} characters]\n{}",
+            MAX_READ_CHARS, tail
+        ))
+    }
+
+    /// Append a section under the given heading
+    pub fn append(&self, user_request: &str, heading: &str, content: &str) -> Result<(), String> {
+        use std::io::Write;
+
+        self.ensure_created(user_request)?;
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open scratchpad: {}", e))?;
+        write!(file, "\n## {}\n{}\n", heading.trim(), content.trim())
+            .map_err(|e| format!("Failed to write scratchpad: {}", e))
+    }
+
+    /// Prompt section telling a spawned agent where the scratchpad is
+    pub fn prompt_section(&self) -> String {
+        format!(
+            "\n\n## SHARED SCRATCHPAD\nAgents in this pipeline share notes in `{}`. Read it before you start. Before you finish, append a short section (heading with your role) with anything the next agent needs to know: decisions made, assumptions, gotchas, unfinished work. Do not remove other agents' notes.\n",
+            self.path.display()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_creates_file_with_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let pad = Scratchpad::for_pipeline(dir.path().to_str().unwrap(), "p1");
+        assert_eq!(pad.read().unwrap(), "");
+        assert_eq!(
+            pad.read_for_orchestrator().unwrap(),
+            "The scratchpad is empty."
+        );
+
+        pad.append("Add a CLI flag", "Planning", "Use clap derive.\n")
+            .unwrap();
+        pad.append("Add a CLI flag", "Build", "Flag added in main.rs")
+            .unwrap();
+
+        let content = pad.read().unwrap();
+        assert!(content.starts_with("# Pipeline Scratchpad"));
+        assert!(content.contains("## Task\nAdd a CLI flag\n"));
+        assert!(content.contains("\n## Planning\nUse clap derive.\n"));
+        assert!(content.ends_with("\n## Build\nFlag added in main.rs\n"));
+    }
+
+    #[test]
+    fn test_read_for_orchestrator_keeps_latest_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let pad = Scratchpad::for_pipeline(dir.path().to_str().unwrap(), "p1");
+        pad.append("task", "Old", &"a".repeat(MAX_READ_CHARS))
+            .unwrap();
+        pad.append("task", "Latest", "keep me").unwrap();
+
+        let read = pad.read_for_orchestrator().unwrap();
+        assert!(read.starts_with("[Earlier notes omitted"));
+        assert!(read.ends_with("## Latest\nkeep me\n"));
+    }
+}
@@ -40,6 +40,9 @@ pub enum PipelineState {
     /// Verification failed
     VerificationFailed,
 
+    /// Paused until the user answers the orchestrator's question
+    AwaitingHuman,
+
     // Terminal states
     /// Pipeline completed successfully
     Completed,
@@ -83,6 +86,7 @@ impl PipelineState {
             PipelineState::Verifying
             | PipelineState::VerificationPassed
             | PipelineState::VerificationFailed => "Verification",
+            PipelineState::AwaitingHuman => "Awaiting Human",
             PipelineState::Completed | PipelineState::Failed | PipelineState::GaveUp => "Terminal",
         }
     }
@@ -103,6 +107,7 @@ impl std::fmt::Display for PipelineState {
             PipelineState::Verifying => write!(f, "Verifying"),
             PipelineState::VerificationPassed => write!(f, "Verification Passed"),
             PipelineState::VerificationFailed => write!(f, "Verification Failed"),
+            PipelineState::AwaitingHuman => write!(f, "Awaiting Human"),
             PipelineState::Completed => write!(f, "Completed"),
             PipelineState::Failed => write!(f, "Failed"),
             PipelineState::GaveUp => write!(f, "Gave Up"),
@@ -138,6 +143,14 @@ impl StateTransition {
 pub fn is_valid_transition(from: &PipelineState, to: &PipelineState) -> bool {
     use PipelineState::*;
 
+    // Asking the user pauses any phase, and the answer resumes it
+    if *to == AwaitingHuman {
+        return !from.is_terminal() && *from != AwaitingHuman;
+    }
+    if *from == AwaitingHuman {
+        return !to.is_terminal();
+    }
+
     matches!(
         (from, to),
         // Skill synthesis flow
@@ -215,6 +228,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_awaiting_human_pauses_and_resumes_phases() {
+        assert!(is_valid_transition(
+            &PipelineState::Verifying,
+            &PipelineState::AwaitingHuman
+        ));
+        assert!(is_valid_transition(
+            &PipelineState::AwaitingHuman,
+            &PipelineState::Verifying
+        ));
+        assert!(!is_valid_transition(
+            &PipelineState::Completed,
+            &PipelineState::AwaitingHuman
+        ));
+        assert!(!is_valid_transition(
+            &PipelineState::AwaitingHuman,
+            &PipelineState::Completed
+        ));
+        assert!(!PipelineState::AwaitingHuman.is_terminal());
+    }
+
     #[test]
     fn test_phase_names() {
         assert_eq!(PipelineState::AnalyzingTask.phase_name(), "Skill Synthesis");
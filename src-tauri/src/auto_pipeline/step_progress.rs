@@ -0,0 +1,232 @@
+// Step progress - Completion tracking for pipeline steps from agent todo lists
+//
+// Step agents keep a todo list through Claude's TodoWrite tool. Each update is
+// turned into a completion percentage for the step. When the plan lists its
+// steps explicitly, todo items are matched to plan tasks by fuzzy title match
+// so the plan view can show which planned items are done.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::agent_utils::extract_json_from_markdown;
+use crate::hook_server::AgentTodoItem;
+
+/// Minimum word overlap for a todo item to count as a plan task
+const TASK_MATCH_THRESHOLD: f64 = 0.5;
+
+/// Words too common to say anything about which task an item refers to
+const STOP_WORDS: [&str; 16] = [
+    "a", "an", "and", "the", "to", "of", "for", "in", "on", "with", "into", "from", "by", "is",
+    "it", "step",
+];
+
+/// Progress of one planned task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanTaskProgress {
+    pub task: String,
+    pub done: bool,
+    /// The todo item matched to this task, if any
+    pub matched_todo: Option<String>,
+}
+
+/// Progress of a step, computed from its agent's latest todo list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepProgress {
+    /// Completed items as a percentage of all items (0-100)
+    pub percent: u8,
+    pub completed: usize,
+    pub total: usize,
+    /// Text of the item the agent is working on
+    pub in_progress: Option<String>,
+    /// Plan tasks with their matched todo items (empty when the plan has no task list)
+    #[serde(default)]
+    pub plan_tasks: Vec<PlanTaskProgress>,
+}
+
+/// Extract the task list from a plan.
+///
+/// Planning agents answer with `{"plan": ["Step 1: ...", ...]}`; plans that
+/// aren't JSON fall back to their numbered or bulleted lines.
+pub fn parse_plan_tasks(plan: &str) -> Vec<String> {
+    let json_text = extract_json_from_markdown(plan);
+    let json = serde_json::from_str::<serde_json::Value>(&json_text)
+        .ok()
+        .or_else(|| {
+            let start = json_text.find('{')?;
+            let end = json_text.rfind('}')?;
+            serde_json::from_str(json_text.get(start..=end)?).ok()
+        });
+
+    if let Some(steps) = json
+        .as_ref()
+        .and_then(|v| v.get("plan"))
+        .and_then(|p| p.as_array())
+    {
+        return steps
+            .iter()
+            .filter_map(|s| s.as_str())
+            .map(strip_step_prefix)
+            .filter(|s| !s.is_empty())
+            .collect();
+    }
+
+    plan.lines()
+        .filter_map(list_item_text)
+        .map(strip_step_prefix)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Text of a numbered ("1." / "2)") or bulleted ("-" / "*") line
+fn list_item_text(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some(rest);
+    }
+    let digits = line.find(|c: char| !c.is_ascii_digit())?;
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+}
+
+/// Drop a leading "Step 3:" so the task title is what gets matched
+fn strip_step_prefix(task: &str) -> String {
+    let task = task.trim();
+    let lower = task.to_lowercase();
+    if let Some(rest) = lower.strip_prefix("step ") {
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && rest[digits..].starts_with(':') {
+            return task["step ".len() + digits + 1..].trim().to_string();
+        }
+    }
+    task.to_string()
+}
+
+fn significant_words(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1 && !STOP_WORDS.contains(w))
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// Word overlap between two titles, relative to the shorter one (0.0-1.0)
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let a = significant_words(a);
+    let b = significant_words(b);
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / smaller as f64
+}
+
+/// Compute step progress from a todo list, matching items to plan tasks
+pub fn compute_progress(todos: &[AgentTodoItem], plan_tasks: &[String]) -> StepProgress {
+    let total = todos.len();
+    let completed = todos.iter().filter(|t| t.status == "completed").count();
+    let percent = if total == 0 {
+        0
+    } else {
+        (completed * 100 / total) as u8
+    };
+    let in_progress = todos
+        .iter()
+        .find(|t| t.status == "in_progress")
+        .map(|t| t.active_form.clone().unwrap_or_else(|| t.content.clone()));
+
+    let plan_tasks = plan_tasks
+        .iter()
+        .map(|task| {
+            let best = todos
+                .iter()
+                .map(|todo| (todo, title_similarity(task, &todo.content)))
+                .filter(|(_, score)| *score >= TASK_MATCH_THRESHOLD)
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+            PlanTaskProgress {
+                task: task.clone(),
+                done: best.is_some_and(|(todo, _)| todo.status == "completed"),
+                matched_todo: best.map(|(todo, _)| todo.content.clone()),
+            }
+        })
+        .collect();
+
+    StepProgress {
+        percent,
+        completed,
+        total,
+        in_progress,
+        plan_tasks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(content: &str, status: &str) -> AgentTodoItem {
+        AgentTodoItem {
+            content: content.to_string(),
+            status: status.to_string(),
+            active_form: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_plan_tasks_from_json_and_lists() {
+        let plan = "```json\n{\"plan\": [\"Step 1: Add the schema column\", \"Step 2: Wire up the command\"], \"questions\": []}\n```";
+        assert_eq!(
+            parse_plan_tasks(plan),
+            vec!["Add the schema column", "Wire up the command"]
+        );
+
+        let plan = "Here is the plan:\n1. Add the schema column\n2) Wire up the command\n- Write tests\nDone.";
+        assert_eq!(
+            parse_plan_tasks(plan),
+            vec![
+                "Add the schema column",
+                "Wire up the command",
+                "Write tests"
+            ]
+        );
+
+        assert!(parse_plan_tasks("Just do it").is_empty());
+    }
+
+    #[test]
+    fn test_compute_progress_matches_plan_tasks() {
+        let mut todos = vec![
+            todo("Add usage column to schema", "completed"),
+            todo("Wire up the get_prompt_costs command", "in_progress"),
+            todo("Run the test suite", "pending"),
+        ];
+        todos[1].active_form = Some("Wiring up the command".to_string());
+        let tasks = vec![
+            "Add the schema column for usage".to_string(),
+            "Wire up the get_prompt_costs command".to_string(),
+            "Update the documentation".to_string(),
+        ];
+
+        let progress = compute_progress(&todos, &tasks);
+        assert_eq!((progress.completed, progress.total), (1, 3));
+        assert_eq!(progress.percent, 33);
+        assert_eq!(
+            progress.in_progress.as_deref(),
+            Some("Wiring up the command")
+        );
+        assert!(progress.plan_tasks[0].done);
+        assert!(!progress.plan_tasks[1].done);
+        assert!(progress.plan_tasks[1].matched_todo.is_some());
+        assert_eq!(progress.plan_tasks[2].matched_todo, None);
+    }
+
+    #[test]
+    fn test_empty_todo_list() {
+        let progress = compute_progress(&[], &["Do it".to_string()]);
+        assert_eq!(progress.percent, 0);
+        assert!(!progress.plan_tasks[0].done);
+    }
+}
@@ -115,6 +115,11 @@ pub async fn execute_building_step(
                 continue;
             }
 
+            OrchestratorAction::NeedsHuman { question, context } => {
+                orchestrator_agent.wait_for_human(question, context).await?;
+                continue;
+            }
+
             OrchestratorAction::Continue => {
                 continue;
             }
@@ -5,9 +5,11 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::agent_manager::stopping::finish_stops;
 use crate::agent_manager::AgentManager;
 use crate::auto_pipeline::orchestrator_agent::OrchestratorAgent;
 use crate::auto_pipeline::types::{AutoPipeline, StepStatus};
+use crate::memory_footprint;
 
 /// Emit a step-related event
 pub fn emit_step_event(
@@ -138,6 +140,10 @@ pub async fn store_orchestrator_agent(
     pipeline_id: &str,
     agent: OrchestratorAgent,
 ) {
+    memory_footprint::record_conversation(
+        &format!("orchestrator:{}", pipeline_id),
+        agent.conversation_bytes(),
+    );
     let mut agents_lock = agents.lock().await;
     agents_lock.insert(pipeline_id.to_string(), agent);
 }
@@ -145,8 +151,15 @@ pub async fn store_orchestrator_agent(
 /// Stop an agent by ID
 pub async fn stop_agent(agent_manager: &Arc<Mutex<AgentManager>>, agent_id: &str) {
     eprintln!("[auto_pipeline] Stopping agent {}", agent_id);
-    let manager = agent_manager.lock().await;
-    let _ = manager.stop_agent(agent_id).await;
+    // Drained after releasing the lock, which can take AGENT_STOP_TIMEOUT_SECS
+    let begun = agent_manager
+        .lock()
+        .await
+        .begin_stop(agent_id, false, None)
+        .await;
+    if let Ok(pending) = begun {
+        let _ = pending.finish().await;
+    }
     eprintln!("[auto_pipeline] Agent {} stopped", agent_id);
 }
 
@@ -202,11 +215,20 @@ pub async fn stop_all_pipeline_agents(
         agent_ids
     );
 
-    let manager = agent_manager.lock().await;
-    for agent_id in agent_ids {
-        eprintln!("[auto_pipeline] Stopping agent {}", agent_id);
-        let _ = manager.stop_agent(&agent_id).await;
-        eprintln!("[auto_pipeline] Agent {} stopped", agent_id);
+    // Begun under the lock, then drained together after releasing it
+    let begun = {
+        let manager = agent_manager.lock().await;
+        let mut begun = Vec::with_capacity(agent_ids.len());
+        for agent_id in &agent_ids {
+            begun.push(manager.begin_stop(agent_id, false, None).await);
+        }
+        begun
+    };
+    for (agent_id, outcome) in finish_stops(&agent_ids, begun).await {
+        match outcome {
+            Ok(_) => eprintln!("[auto_pipeline] Agent {} stopped", agent_id),
+            Err(e) => eprintln!("[auto_pipeline] Failed to stop agent {}: {}", agent_id, e),
+        }
     }
 
     eprintln!("[auto_pipeline] stop_all_pipeline_agents completed");
@@ -12,12 +12,14 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
+use crate::fault_injection::SharedFaultHook;
 
+use super::human_requests::HumanAnswers;
 use super::orchestrator::{DecisionResult, Orchestrator};
-use super::orchestrator_agent::OrchestratorAgent;
+use super::orchestrator_agent::{OrchestratorAgent, OrchestratorSnapshot};
 use super::types::{AutoPipeline, StepStatus};
 
-pub use helpers::stop_all_pipeline_agents;
+pub use helpers::{emit_pipeline_completed, stop_all_pipeline_agents};
 
 /// Context needed for step execution
 pub struct StepExecutionContext {
@@ -25,6 +27,14 @@ pub struct StepExecutionContext {
     pub orchestrator: Orchestrator,
     /// Persistent orchestrator agents per pipeline (for new mode)
     pub orchestrator_agents: Arc<Mutex<HashMap<String, OrchestratorAgent>>>,
+    /// Abort handles for pipelines currently executing in the background
+    pub running: Arc<Mutex<HashMap<String, tokio::task::AbortHandle>>>,
+    /// Fault hook for chaos tests (None in normal operation)
+    pub fault_hook: SharedFaultHook,
+    /// Answer channels of running pipelines waiting on the user
+    pub human_answers: HumanAnswers,
+    /// Pipelines that were waiting on the user when the app last closed
+    pub restored_requests: Arc<Mutex<HashMap<String, OrchestratorSnapshot>>>,
 }
 
 impl StepExecutionContext {
@@ -157,6 +167,31 @@ impl StepExecutionContext {
             &self.orchestrator,
             agent_manager,
             app_handle,
+            self.fault_hook.clone(),
+            self.human_answers.clone(),
+        )
+        .await
+    }
+
+    /// Continue a restored pipeline from its saved state with the user's answer
+    pub async fn resume_pipeline(
+        &self,
+        pipeline_id: String,
+        snapshot: OrchestratorSnapshot,
+        answer: String,
+        agent_manager: Arc<Mutex<AgentManager>>,
+        app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    ) -> Result<(), String> {
+        pipeline_loop::resume_pipeline(
+            pipeline_id,
+            self.pipelines.clone(),
+            &self.orchestrator,
+            agent_manager,
+            app_handle,
+            self.fault_hook.clone(),
+            self.human_answers.clone(),
+            snapshot,
+            answer,
         )
         .await
     }
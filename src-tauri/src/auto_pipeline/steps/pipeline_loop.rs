@@ -6,9 +6,15 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
+use crate::auto_pipeline::human_requests::HumanAnswers;
 use crate::auto_pipeline::orchestrator::Orchestrator;
-use crate::auto_pipeline::orchestrator_agent::{OrchestratorAction, OrchestratorAgent};
+use crate::auto_pipeline::orchestrator_agent::{
+    OrchestratorAction, OrchestratorAgent, OrchestratorSnapshot,
+};
 use crate::auto_pipeline::types::AutoPipeline;
+use crate::fault_injection::SharedFaultHook;
+use crate::generated_files::{self, CompletionAction};
+use crate::utils::time::now_millis;
 
 use super::helpers::{
     emit_pipeline_completed, stop_all_pipeline_agents, with_pipeline, with_pipeline_mut,
@@ -20,6 +26,7 @@ use super::helpers::{
 /// 1. Create OrchestratorAgent
 /// 2. Hand off to orchestrator - it manages planning, execution, verification, iteration, and replanning
 /// 3. Wait for final result (Complete or GiveUp)
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_pipeline(
     pipeline_id: String,
     pipelines: Arc<Mutex<HashMap<String, AutoPipeline>>>,
@@ -27,8 +34,86 @@ pub async fn execute_pipeline(
     orchestrator: &Orchestrator,
     agent_manager: Arc<Mutex<AgentManager>>,
     app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    fault_hook: SharedFaultHook,
+    human_answers: HumanAnswers,
+) -> Result<(), String> {
+    let (orchestrator_agent, working_dir) = create_orchestrator_agent(
+        &pipeline_id,
+        &pipelines,
+        orchestrator,
+        &agent_manager,
+        &app_handle,
+        fault_hook,
+        human_answers,
+    )
+    .await?;
+
+    eprintln!("[auto_pipeline] Handing off to OrchestratorAgent for complete workflow execution");
+    run_orchestrator(
+        orchestrator_agent,
+        pipeline_id,
+        working_dir,
+        pipelines,
+        agent_manager,
+        app_handle,
+    )
+    .await
+}
+
+/// Continue a pipeline that was waiting on the user when the app last closed,
+/// starting from the saved orchestrator state with the user's answer
+#[allow(clippy::too_many_arguments)]
+pub async fn resume_pipeline(
+    pipeline_id: String,
+    pipelines: Arc<Mutex<HashMap<String, AutoPipeline>>>,
+    orchestrator: &Orchestrator,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn crate::events::AppEventEmitter>,
+    fault_hook: SharedFaultHook,
+    human_answers: HumanAnswers,
+    snapshot: OrchestratorSnapshot,
+    answer: String,
 ) -> Result<(), String> {
-    let (user_request, working_dir) = with_pipeline(&pipelines, &pipeline_id, |p| {
+    let (mut orchestrator_agent, working_dir) = create_orchestrator_agent(
+        &pipeline_id,
+        &pipelines,
+        orchestrator,
+        &agent_manager,
+        &app_handle,
+        fault_hook,
+        human_answers,
+    )
+    .await?;
+    orchestrator_agent
+        .resume_from_snapshot(snapshot, &answer)
+        .await;
+
+    eprintln!(
+        "[auto_pipeline] Resuming pipeline {} with the user's answer",
+        pipeline_id
+    );
+    run_orchestrator(
+        orchestrator_agent,
+        pipeline_id,
+        working_dir,
+        pipelines,
+        agent_manager,
+        app_handle,
+    )
+    .await
+}
+
+/// Create the orchestrator for a pipeline in the store. Returns it with the working directory.
+async fn create_orchestrator_agent(
+    pipeline_id: &str,
+    pipelines: &Arc<Mutex<HashMap<String, AutoPipeline>>>,
+    orchestrator: &Orchestrator,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    app_handle: &Arc<dyn crate::events::AppEventEmitter>,
+    fault_hook: SharedFaultHook,
+    human_answers: HumanAnswers,
+) -> Result<(OrchestratorAgent, String), String> {
+    let (user_request, working_dir) = with_pipeline(pipelines, pipeline_id, |p| {
         (p.user_request.clone(), p.working_dir.clone())
     })
     .await?;
@@ -36,25 +121,60 @@ pub async fn execute_pipeline(
     let custom_instructions = orchestrator.custom_instructions().map(String::from);
     let max_iterations = orchestrator.max_iterations();
 
-    // Create the OrchestratorAgent
     let mut orchestrator_agent = OrchestratorAgent::with_agent_manager(
-        working_dir,
+        working_dir.clone(),
         user_request,
         custom_instructions,
         max_iterations,
         agent_manager.clone(),
         app_handle.clone(),
-        pipeline_id.clone(),
+        pipeline_id.to_string(),
     )?;
+    orchestrator_agent.set_pipeline_store(pipelines.clone());
+    orchestrator_agent.set_fault_hook(fault_hook);
+    orchestrator_agent.set_human_answers(human_answers);
+    Ok((orchestrator_agent, working_dir))
+}
 
-    eprintln!("[auto_pipeline] Handing off to OrchestratorAgent for complete workflow execution");
-
+/// Run the orchestrator to its final decision and finish the pipeline accordingly
+async fn run_orchestrator(
+    mut orchestrator_agent: OrchestratorAgent,
+    pipeline_id: String,
+    working_dir: String,
+    pipelines: Arc<Mutex<HashMap<String, AutoPipeline>>>,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: Arc<dyn crate::events::AppEventEmitter>,
+) -> Result<(), String> {
     // Hand off to orchestrator - it handles EVERYTHING internally
     // (planning, execution, verification, iteration, replanning, decisions)
-    let result = orchestrator_agent.run_to_completion().await?;
+    let result = match orchestrator_agent.run_to_completion().await {
+        Ok(result) => result,
+        Err(error) => {
+            // The orchestrator itself failed (e.g. the AI provider is unreachable):
+            // don't leave the pipeline running or its agents behind
+            stop_all_pipeline_agents(&pipelines, &pipeline_id, &agent_manager).await;
+            tidy_generated_files(&working_dir, &pipeline_id, &pipelines, &agent_manager).await;
+            with_pipeline_mut(&pipelines, &pipeline_id, |pipeline| {
+                pipeline.mark_failed("error");
+            })
+            .await?;
 
-    // Clean up agents
+            emit_pipeline_completed(
+                &app_handle,
+                &pipeline_id,
+                "failed",
+                "error",
+                Some(json!({"error": error})),
+            );
+
+            eprintln!("[auto_pipeline] Pipeline {} failed: {}", pipeline_id, error);
+            return Err(error);
+        }
+    };
+
+    // Clean up agents, then the files they and the orchestrator left behind
     stop_all_pipeline_agents(&pipelines, &pipeline_id, &agent_manager).await;
+    tidy_generated_files(&working_dir, &pipeline_id, &pipelines, &agent_manager).await;
 
     // Handle final result
     match result {
@@ -107,3 +227,51 @@ pub async fn execute_pipeline(
         }
     }
 }
+
+/// Clean up or archive the pipeline's generated files, as configured.
+/// Cleaned up artifacts stay in the run database, marked removed.
+async fn tidy_generated_files(
+    working_dir: &str,
+    pipeline_id: &str,
+    pipelines: &Arc<Mutex<HashMap<String, AutoPipeline>>>,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+) {
+    let action = CompletionAction::from_env();
+    match generated_files::finish_pipeline(working_dir, pipeline_id, action) {
+        Ok(0) => return,
+        Ok(count) => eprintln!(
+            "[auto_pipeline] {:?}: {} generated file(s) of pipeline {}",
+            action, count, pipeline_id
+        ),
+        Err(e) => {
+            eprintln!(
+                "[auto_pipeline] Failed to tidy generated files of pipeline {}: {}",
+                pipeline_id, e
+            );
+            return;
+        }
+    }
+    if action != CompletionAction::Cleanup {
+        return;
+    }
+
+    let runs_db = agent_manager.lock().await.runs_db.clone();
+    let Some(db) = runs_db else {
+        return;
+    };
+    match db
+        .mark_pipeline_artifacts_removed(pipeline_id, now_millis())
+        .await
+    {
+        Ok(removed) => {
+            let _ = with_pipeline_mut(pipelines, pipeline_id, |pipeline| {
+                pipeline.artifact_counts.removed += removed as u32;
+            })
+            .await;
+        }
+        Err(e) => eprintln!(
+            "[auto_pipeline] Failed to mark artifacts of pipeline {} removed: {}",
+            pipeline_id, e
+        ),
+    }
+}
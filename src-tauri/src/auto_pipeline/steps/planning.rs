@@ -197,6 +197,11 @@ pub async fn execute_planning_step(
                 break;
             }
 
+            OrchestratorAction::NeedsHuman { question, context } => {
+                orchestrator_agent.wait_for_human(question, context).await?;
+                continue;
+            }
+
             OrchestratorAction::Continue => {
                 continue;
             }
@@ -5,8 +5,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::agent_manager::AgentManager;
-use crate::auto_pipeline::agent_utils::{extract_agent_output, wait_for_agent_completion};
+use crate::agent_manager::{AgentManager, AgentSpawnOptions};
+use crate::auto_pipeline::agent_utils::{extract_agent_output, send_prompt_and_wait};
 use crate::auto_pipeline::orchestrator::{DecisionResult, Orchestrator};
 use crate::auto_pipeline::orchestrator_agent::{OrchestratorAction, OrchestratorAgent};
 use crate::auto_pipeline::prompts::REPLAN_PROMPT_TEMPLATE;
@@ -95,10 +95,11 @@ pub async fn execute_replan_step(
                 Vec::new(), // No pre-generated skills for pipeline agents
                 AgentSource::Pipeline,
                 app_handle.clone(),
-                Some(pipeline_id.to_string()),
-                Some("Planning".to_string()),
-                None, // No model override
-                None, // No complexity
+                AgentSpawnOptions {
+                    pipeline_id: Some(pipeline_id.to_string()),
+                    title: Some("Planning".to_string()),
+                    ..Default::default()
+                },
             )
             .await?
     };
@@ -109,15 +110,13 @@ pub async fn execute_replan_step(
     })
     .await?;
 
-    // Note: No security_monitor for pipeline automated prompts
-    {
-        let manager = agent_manager.lock().await;
-        manager
-            .send_prompt(&agent_id, &replan_prompt, Some(app_handle.clone()), None)
-            .await?;
-    }
-
-    wait_for_agent_completion(&agent_id, agent_manager.clone()).await?;
+    send_prompt_and_wait(
+        &agent_id,
+        &replan_prompt,
+        agent_manager.clone(),
+        app_handle.clone(),
+    )
+    .await?;
 
     let output = extract_agent_output(&agent_id, agent_manager.clone()).await?;
 
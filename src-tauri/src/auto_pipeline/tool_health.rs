@@ -0,0 +1,251 @@
+// Pipeline tool health
+//
+// Orchestrator tool calls fail now and then (a decision tool used too early, a
+// missing instruction file), but a run of failures usually means the
+// environment is broken and the orchestrator is thrashing. Each pipeline keeps
+// the outcomes of its last PIPELINE_TOOL_ERROR_WINDOW tool calls. When the
+// share of failures reaches PIPELINE_TOOL_ERROR_THRESHOLD the pipeline is
+// degraded: auto_pipeline:degraded is emitted and the orchestrator is told, so
+// it can surface the problem instead of retrying. It recovers once the rate
+// falls below half the threshold.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::agent_runs_db::OrchestratorToolCallRecord;
+use crate::commands::env_registry;
+use crate::utils::string::truncate_with_ellipsis;
+
+/// Tool calls the error rate is taken over (PIPELINE_TOOL_ERROR_WINDOW)
+pub const DEFAULT_WINDOW: usize = 20;
+
+/// Error rate at which a pipeline is degraded (PIPELINE_TOOL_ERROR_THRESHOLD)
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+
+/// Calls needed before the rate is judged, so one early failure doesn't count
+const MIN_CALLS: usize = 5;
+
+fn window_from_env() -> usize {
+    env_registry::PIPELINE_TOOL_ERROR_WINDOW
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_WINDOW)
+}
+
+fn threshold_from_env() -> f64 {
+    env_registry::PIPELINE_TOOL_ERROR_THRESHOLD
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| (0.0..=1.0).contains(v))
+        .unwrap_or(DEFAULT_THRESHOLD)
+}
+
+/// How a recorded tool call changed a pipeline's health
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthChange {
+    Unchanged,
+    Degraded,
+    Recovered,
+}
+
+/// Rolling error rate of a pipeline's orchestrator tool calls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineHealth {
+    pub window: usize,
+    /// 0 disables the check
+    pub threshold: f64,
+    /// Outcomes of the last `window` calls, oldest first (true = error)
+    recent: VecDeque<bool>,
+    pub recent_calls: usize,
+    pub recent_errors: usize,
+    /// recent_errors / recent_calls (0 before any call)
+    pub error_rate: f64,
+    pub total_calls: u64,
+    pub total_errors: u64,
+    pub degraded: bool,
+    pub degraded_since: Option<String>,
+    /// Tool name and message of the latest failure
+    pub last_error: Option<String>,
+}
+
+impl Default for PipelineHealth {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW, DEFAULT_THRESHOLD)
+    }
+}
+
+impl PipelineHealth {
+    pub fn new(window: usize, threshold: f64) -> Self {
+        Self {
+            window: window.max(1),
+            threshold,
+            recent: VecDeque::new(),
+            recent_calls: 0,
+            recent_errors: 0,
+            error_rate: 0.0,
+            total_calls: 0,
+            total_errors: 0,
+            degraded: false,
+            degraded_since: None,
+            last_error: None,
+        }
+    }
+
+    /// Health with the window and threshold from the environment
+    pub fn from_env() -> Self {
+        Self::new(window_from_env(), threshold_from_env())
+    }
+
+    /// Record a tool call's outcome; `error` is the failure message
+    pub fn record(&mut self, tool_name: &str, error: Option<&str>) -> HealthChange {
+        let is_error = error.is_some();
+        self.total_calls += 1;
+        if let Some(message) = error {
+            self.total_errors += 1;
+            self.last_error = Some(format!(
+                "{}: {}",
+                tool_name,
+                truncate_with_ellipsis(message, 200)
+            ));
+        }
+
+        self.recent.push_back(is_error);
+        while self.recent.len() > self.window {
+            self.recent.pop_front();
+        }
+        self.recent_calls = self.recent.len();
+        self.recent_errors = self.recent.iter().filter(|e| **e).count();
+        self.error_rate = self.recent_errors as f64 / self.recent_calls as f64;
+
+        if self.threshold <= 0.0 || self.recent_calls < MIN_CALLS.min(self.window) {
+            return HealthChange::Unchanged;
+        }
+        if !self.degraded && self.error_rate >= self.threshold {
+            self.degraded = true;
+            self.degraded_since = Some(chrono::Utc::now().to_rfc3339());
+            HealthChange::Degraded
+        } else if self.degraded && self.error_rate < self.threshold / 2.0 {
+            self.degraded = false;
+            self.degraded_since = None;
+            HealthChange::Recovered
+        } else {
+            HealthChange::Unchanged
+        }
+    }
+
+    /// Health rebuilt from a pipeline's persisted tool calls (oldest first),
+    /// for pipelines that are no longer loaded
+    pub fn from_tool_calls(calls: &[OrchestratorToolCallRecord]) -> Self {
+        let mut health = Self::from_env();
+        for call in calls {
+            let error = call
+                .is_error
+                .then(|| call.summary.as_deref().unwrap_or("tool call failed"));
+            if health.record(&call.tool_name, error) == HealthChange::Degraded {
+                health.degraded_since =
+                    chrono::DateTime::<chrono::Utc>::from_timestamp_millis(call.timestamp)
+                        .map(|t| t.to_rfc3339());
+            }
+        }
+        health
+    }
+
+    /// Note for the orchestrator when its pipeline becomes degraded
+    pub fn degraded_note(&self) -> String {
+        format!(
+            "Note: {} of your last {} tool calls failed ({:.0}%). Tool failures at this rate \
+             often mean the environment is broken (missing tools or credentials, a bad working \
+             directory, an unavailable service) rather than a problem with your approach. \
+             Latest failure: {}. Rather than retrying, consider request_human_help to surface \
+             the problem, or give_up if it can't be fixed from here.",
+            self.recent_errors,
+            self.recent_calls,
+            self.error_rate * 100.0,
+            self.last_error.as_deref().unwrap_or("unknown"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degrades_at_threshold_and_recovers_below_half() {
+        let mut health = PipelineHealth::new(10, 0.5);
+        for _ in 0..3 {
+            assert_eq!(
+                health.record("start_execution", None),
+                HealthChange::Unchanged
+            );
+        }
+        // Too few calls to judge yet, then still below half
+        assert_eq!(
+            health.record("start_execution", Some("Agent failed to start")),
+            HealthChange::Unchanged
+        );
+        assert_eq!(
+            health.record("start_execution", Some("Agent failed to start")),
+            HealthChange::Unchanged
+        );
+        assert_eq!(
+            health.record("start_execution", Some("spawn claude: No such file")),
+            HealthChange::Degraded
+        );
+        assert_eq!(health.recent_errors, 3);
+        assert!(health.degraded);
+        assert!(health
+            .degraded_note()
+            .contains("3 of your last 6 tool calls failed (50%)"));
+        assert_eq!(
+            health.last_error.as_deref(),
+            Some("start_execution: spawn claude: No such file")
+        );
+
+        // Still at or above a quarter: stays degraded
+        for _ in 0..4 {
+            assert_eq!(
+                health.record("read_scratchpad", None),
+                HealthChange::Unchanged
+            );
+        }
+        assert_eq!(health.recent_calls, 10);
+        // The window drops the oldest calls, failures included
+        assert_eq!(
+            health.record("read_scratchpad", None),
+            HealthChange::Unchanged
+        );
+        assert_eq!(
+            health.record("read_scratchpad", None),
+            HealthChange::Unchanged
+        );
+        assert_eq!(
+            health.record("read_scratchpad", None),
+            HealthChange::Unchanged
+        );
+        assert_eq!(
+            health.record("read_scratchpad", None),
+            HealthChange::Recovered
+        );
+        assert_eq!(health.recent_errors, 2);
+        assert_eq!(health.total_calls, 14);
+        assert_eq!(health.total_errors, 3);
+        assert!(health.degraded_since.is_none());
+    }
+
+    #[test]
+    fn test_zero_threshold_never_degrades() {
+        let mut health = PipelineHealth::new(5, 0.0);
+        for _ in 0..10 {
+            assert_eq!(
+                health.record("iterate", Some("failed")),
+                HealthChange::Unchanged
+            );
+        }
+        assert_eq!(health.error_rate, 1.0);
+        assert!(!health.degraded);
+    }
+}
@@ -2,10 +2,16 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::generated_files::GeneratedFileKind;
+
+use super::build_diff::VerificationInputMode;
+use super::human_requests::HumanRequest;
 use super::replay::ReplayFile;
 use super::skill_matcher::MatchResult;
 use super::state_machine::{PipelineState, StateTransition};
+use super::step_progress::StepProgress;
 use super::task_analyzer::TaskAnalysis;
+use super::tool_health::PipelineHealth;
 
 /// Role of a pipeline step
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +39,21 @@ pub struct StepOutput {
     pub agent_outputs: Vec<crate::types::AgentOutputEvent>,
 }
 
+/// Default number of automatic retries for a failed step
+pub const DEFAULT_STEP_MAX_RETRIES: u8 = 1;
+
+/// Upper bound on per-step retries, however the pipeline is configured
+pub const MAX_STEP_RETRIES: u8 = 5;
+
+/// Record of a failed step attempt that was retried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRetryRecord {
+    /// The attempt that failed (1 = first attempt)
+    pub attempt: u8,
+    pub error: String,
+    pub failed_at: String,
+}
+
 /// A single step in the auto-pipeline
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoPipelineStep {
@@ -46,6 +67,18 @@ pub struct AutoPipelineStep {
     /// Count of tools used in this step (tracked in real-time via frontend)
     #[serde(default)]
     pub tool_count: u32,
+    /// How many times a failed attempt may be retried automatically
+    #[serde(default)]
+    pub max_retries: u8,
+    /// Retries used so far (kept across iterations so the cap holds for the whole run)
+    #[serde(default)]
+    pub retry_count: u8,
+    /// Failed attempts that were retried
+    #[serde(default)]
+    pub retry_history: Vec<StepRetryRecord>,
+    /// Progress from the step agent's todo list, once it has written one
+    #[serde(default)]
+    pub progress: Option<StepProgress>,
 }
 
 impl AutoPipelineStep {
@@ -60,6 +93,10 @@ impl AutoPipelineStep {
             started_at: None,
             completed_at: None,
             tool_count: 0,
+            max_retries: DEFAULT_STEP_MAX_RETRIES,
+            retry_count: 0,
+            retry_history: Vec::new(),
+            progress: None,
         }
     }
 
@@ -71,6 +108,33 @@ impl AutoPipelineStep {
         self.started_at = None;
         self.completed_at = None;
         self.tool_count = 0;
+        self.progress = None;
+    }
+
+    /// Whether another automatic retry is allowed
+    pub fn can_retry(&self) -> bool {
+        self.retry_count < self.max_retries.min(MAX_STEP_RETRIES)
+    }
+
+    /// Record a failed attempt.
+    ///
+    /// With retries remaining the step is reset to pending and `true` is
+    /// returned; otherwise the step is marked failed and `false` is returned.
+    pub fn record_failure(&mut self, error: &str) -> bool {
+        if !self.can_retry() {
+            self.status = StepStatus::Failed;
+            self.completed_at = Some(chrono::Utc::now().to_rfc3339());
+            return false;
+        }
+
+        self.retry_history.push(StepRetryRecord {
+            attempt: self.retry_count + 1,
+            error: error.to_string(),
+            failed_at: chrono::Utc::now().to_rfc3339(),
+        });
+        self.retry_count += 1;
+        self.reset();
+        true
     }
 }
 
@@ -81,6 +145,42 @@ pub struct IterationRecord {
     pub decision: String,
     pub reasoning: String,
     pub issues: Vec<String>,
+    /// What the verification agent was given (None if verification didn't run)
+    #[serde(default)]
+    pub verification_input: Option<VerificationInputMode>,
+}
+
+/// A kickoff prompt template used by a pipeline, for tracing prompt regressions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptTemplateUsage {
+    /// planning, builder or verifier
+    pub name: String,
+    /// Content hash of the template (see prompt_templates::template_hash)
+    pub hash: String,
+    /// Whether it was a workspace override rather than the built-in default
+    pub custom: bool,
+}
+
+/// How many skills, subagents and CLAUDE.md files a pipeline generated
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactCounts {
+    pub skills: u32,
+    pub subagents: u32,
+    pub claude_md: u32,
+    /// Artifacts whose files were cleaned up
+    pub removed: u32,
+}
+
+impl ArtifactCounts {
+    /// Count a newly generated artifact
+    pub fn add(&mut self, kind: GeneratedFileKind) {
+        match kind {
+            GeneratedFileKind::Skill => self.skills += 1,
+            GeneratedFileKind::Subagent => self.subagents += 1,
+            GeneratedFileKind::ClaudeMd => self.claude_md += 1,
+            GeneratedFileKind::Scratchpad | GeneratedFileKind::PromptFile => {}
+        }
+    }
 }
 
 /// The main auto-pipeline state
@@ -101,6 +201,18 @@ pub struct AutoPipeline {
     pub max_iterations: u8,
     pub iteration_history: Vec<IterationRecord>,
     pub final_decision: Option<String>,
+    /// Prompt templates the worker agents were started with
+    #[serde(default)]
+    pub prompt_templates: Vec<PromptTemplateUsage>,
+    /// Generated artifacts, listed with list_pipeline_artifacts
+    #[serde(default)]
+    pub artifact_counts: ArtifactCounts,
+    /// The orchestrator's question while the pipeline awaits an answer
+    #[serde(default)]
+    pub human_request: Option<HumanRequest>,
+    /// Rolling error rate of the orchestrator's tool calls
+    #[serde(default)]
+    pub tool_health: PipelineHealth,
 }
 
 impl AutoPipeline {
@@ -125,6 +237,17 @@ impl AutoPipeline {
             max_iterations,
             iteration_history: Vec::new(),
             final_decision: None,
+            prompt_templates: Vec::new(),
+            artifact_counts: ArtifactCounts::default(),
+            human_request: None,
+            tool_health: PipelineHealth::from_env(),
+        }
+    }
+
+    /// Record a template used to start a worker agent (each version once)
+    pub fn record_prompt_template(&mut self, usage: PromptTemplateUsage) {
+        if !self.prompt_templates.contains(&usage) {
+            self.prompt_templates.push(usage);
         }
     }
 
@@ -143,6 +266,13 @@ impl AutoPipeline {
         }
     }
 
+    /// Set the retry cap for every step (capped at MAX_STEP_RETRIES)
+    pub fn set_step_max_retries(&mut self, max_retries: u8) {
+        for step in &mut self.steps {
+            step.max_retries = max_retries.min(MAX_STEP_RETRIES);
+        }
+    }
+
     /// Mark the pipeline as completed with a final decision
     pub fn mark_completed(&mut self, decision: &str) {
         self.status = "completed".to_string();
@@ -157,6 +287,26 @@ impl AutoPipeline {
         self.completed_at = Some(chrono::Utc::now().to_rfc3339());
     }
 
+    /// Mark the pipeline as cancelled by the user or commander
+    pub fn mark_cancelled(&mut self) {
+        self.status = "cancelled".to_string();
+        self.final_decision = Some("cancelled".to_string());
+        self.completed_at = Some(chrono::Utc::now().to_rfc3339());
+        self.human_request = None;
+    }
+
+    /// Pause the pipeline until the user answers the orchestrator's question
+    pub fn mark_awaiting_human(&mut self, request: HumanRequest) {
+        self.status = "awaiting_human".to_string();
+        self.human_request = Some(request);
+    }
+
+    /// Resume the pipeline once the question is answered
+    pub fn mark_resumed(&mut self) {
+        self.status = "running".to_string();
+        self.human_request = None;
+    }
+
     /// Check if the pipeline has reached max iterations
     /// Note: max_iterations of 0 means unlimited iterations (never returns true)
     pub fn at_max_iterations(&self) -> bool {
@@ -378,6 +528,54 @@ impl EnhancedAutoPipeline {
             max_iterations: self.max_iterations,
             iteration_history: self.iteration_history.clone(),
             final_decision: self.final_decision.clone(),
+            prompt_templates: Vec::new(),
+            artifact_counts: ArtifactCounts::default(),
+            human_request: None,
+            tool_health: PipelineHealth::from_env(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_failure_retries_until_exhausted() {
+        let mut step = AutoPipelineStep::new(2, StepRole::Building);
+        step.max_retries = 2;
+        step.status = StepStatus::Running;
+        step.agent_id = Some("agent-1".to_string());
+
+        assert!(step.record_failure("flaky test"));
+        assert!(matches!(step.status, StepStatus::Pending));
+        assert!(step.agent_id.is_none());
+        assert_eq!(step.retry_count, 1);
+
+        assert!(step.record_failure("network timeout"));
+        assert_eq!(step.retry_count, 2);
+
+        assert!(!step.record_failure("still failing"));
+        assert!(matches!(step.status, StepStatus::Failed));
+        assert_eq!(step.retry_history.len(), 2);
+        assert_eq!(step.retry_history[0].attempt, 1);
+        assert_eq!(step.retry_history[1].error, "network timeout");
+    }
+
+    #[test]
+    fn test_retry_history_survives_iteration_reset() {
+        let mut pipeline = AutoPipeline::new(
+            "p1".to_string(),
+            "request".to_string(),
+            "/tmp".to_string(),
+            3,
+        );
+        pipeline.set_step_max_retries(u8::MAX);
+        assert_eq!(pipeline.steps[1].max_retries, MAX_STEP_RETRIES);
+
+        assert!(pipeline.steps[1].record_failure("flaky test"));
+        pipeline.reset_for_iteration();
+        assert_eq!(pipeline.steps[1].retry_count, 1);
+        assert_eq!(pipeline.steps[1].retry_history.len(), 1);
+    }
+}
@@ -4,6 +4,8 @@ use serde_json::{json, Value};
 use std::error::Error;
 use std::fmt;
 
+use crate::http_client;
+
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const CLAUDE_API_VERSION: &str = "2023-06-01";
 const MODEL: &str = "claude-sonnet-4-20250514";
@@ -86,7 +88,7 @@ impl ClaudeClient {
     pub fn new(api_key: String) -> Self {
         Self {
             api_key,
-            http_client: Client::new(),
+            http_client: http_client::shared().clone(),
         }
     }
 
@@ -101,15 +103,14 @@ impl ClaudeClient {
             "tools": tools,
         });
 
-        let response = self
+        let request = self
             .http_client
             .post(CLAUDE_API_URL)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", CLAUDE_API_VERSION)
             .header("content-type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .json(&body);
+        let response = http_client::send(request).await?;
 
         if !response.status().is_success() {
             let error_text = response
@@ -139,15 +140,14 @@ impl ClaudeClient {
             "messages": messages,
         });
 
-        let response = self
+        let request = self
             .http_client
             .post(CLAUDE_API_URL)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", CLAUDE_API_VERSION)
             .header("content-type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .json(&body);
+        let response = http_client::send(request).await?;
 
         if !response.status().is_success() {
             let error_text = response
@@ -9,6 +9,7 @@
 // - User-level: `~/.claude/CLAUDE.md` for global instructions across all projects
 
 use crate::ai_client::AIClient;
+use crate::generated_files::{self, GeneratedFileKind};
 use crate::utils::generator::{extract_json_from_response, extract_text_from_content_blocks};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -198,10 +199,18 @@ fn write_claudemd_file(working_dir: &str, content: &ClaudeMdContent) -> Result<S
         .map_err(|e| format!("Failed to create .claude directory: {}", e))?;
 
     let file_path = claude_dir.join("CLAUDE.md");
+    let existed = file_path.exists();
 
     // Write the content
     fs::write(&file_path, &content.content)
         .map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+    generated_files::record(
+        working_dir,
+        &file_path,
+        GeneratedFileKind::ClaudeMd,
+        !existed,
+        None,
+    );
 
     Ok(file_path.to_string_lossy().to_string())
 }
@@ -258,6 +267,13 @@ pub fn append_to_claudemd(working_dir: &str, new_content: &str) -> Result<String
             let combined = format!("{}\n\n{}", existing.trim(), new_content);
 
             fs::write(&p, combined).map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+            generated_files::record(
+                working_dir,
+                Path::new(&p),
+                GeneratedFileKind::ClaudeMd,
+                false,
+                None,
+            );
 
             p
         }
@@ -270,6 +286,13 @@ pub fn append_to_claudemd(working_dir: &str, new_content: &str) -> Result<String
             let file_path = claude_dir.join("CLAUDE.md");
             fs::write(&file_path, new_content)
                 .map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+            generated_files::record(
+                working_dir,
+                &file_path,
+                GeneratedFileKind::ClaudeMd,
+                true,
+                None,
+            );
 
             file_path.to_string_lossy().to_string()
         }
@@ -1,10 +1,24 @@
 // Agent-related Tauri commands
 
-use crate::agent_runs_db::{AgentRun, EventQueryFilters};
+use crate::agent_manager::auto_follow_up;
+use crate::agent_manager::handoff::{self, HandoffResult};
+use crate::agent_manager::prompt_files::PromptFileOptions;
+use crate::agent_manager::prompt_queue::{PromptSendResult, QueuedPrompt};
+use crate::agent_manager::{output_search, AgentSpawnOptions, OutputTypeFilter};
+use crate::agent_runs_db::{AgentRun, EventQueryFilters, HookEventRecord, StopMode};
+use crate::hook_server::{self, HookHealth};
+use crate::memory_footprint::{self, MemoryFootprint};
+use crate::prompt_library::{self, PromptTemplateRef};
 use crate::skill_generator;
-use crate::types::{AgentInfo, AgentSource, AgentStatistics};
+use crate::types::{
+    AgentGroup, AgentInfo, AgentMessage, AgentNote, AgentOutputEvent, AgentSource, AgentStatistics,
+    AgentStopResult, AgentSummary, GitHubContext, GroupMemberResult, GroupStatistics, NoteAuthor,
+    OutputSearchMatch, ToolRestrictions,
+};
+use crate::utils::validation::{validate_model, validate_working_dir};
 use crate::AppState;
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// Result returned when resuming a crashed run
@@ -25,11 +39,25 @@ pub async fn create_agent(
     working_dir: String,
     github_url: Option<String>,
     selected_instruction_files: Option<Vec<String>>,
+    hooks_file: Option<String>,
+    inject_conventions: Option<bool>,
+    auto_follow_up: Option<bool>,
+    permission_profile: Option<String>,
+    model: Option<String>,
+    env: Option<HashMap<String, String>>,
+    mcp_servers: Option<serde_json::Value>,
+    allowed_tools: Option<Vec<String>>,
+    disallowed_tools: Option<Vec<String>>,
+    use_worktree: Option<bool>,
+    keep_worktree: Option<bool>,
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<String, String> {
     use tauri::Emitter;
 
+    let working_dir = validate_working_dir(&working_dir)?;
+    let model = validate_model(model.as_deref())?;
+
     // Generate skills BEFORE creating the agent (synchronously)
     // This ensures skills are available in .claude/skills/ when the agent starts
     let mut generated_skill_names: Vec<String> = Vec::new();
@@ -239,15 +267,41 @@ pub async fn create_agent(
     // Create the agent with the generated skill names for cleanup tracking
     let agent_id = {
         let manager = state.agent_manager.lock().await;
-        manager
+        let agent_id = manager
             .create_agent_with_skills(
                 working_dir.clone(),
                 github_url,
                 generated_skill_names,
                 AgentSource::UI,
                 Arc::new(app_handle.clone()),
+                AgentSpawnOptions {
+                    model,
+                    hooks_file,
+                    permission_profile,
+                    extra_env: env.unwrap_or_default(),
+                    mcp_servers,
+                    tools: ToolRestrictions {
+                        allowed_tools: allowed_tools.unwrap_or_default(),
+                        disallowed_tools: disallowed_tools.unwrap_or_default(),
+                    },
+                    use_worktree: use_worktree.unwrap_or(false),
+                    ..Default::default()
+                },
             )
-            .await?
+            .await?;
+
+        if inject_conventions == Some(false) {
+            manager
+                .set_agent_conventions_injection(&agent_id, false)
+                .await?;
+        }
+        if auto_follow_up == Some(true) {
+            manager.set_agent_auto_follow_up(&agent_id, true).await?;
+        }
+        if keep_worktree == Some(true) {
+            manager.set_agent_keep_worktree(&agent_id, true).await?;
+        }
+        agent_id
     };
 
     Ok(agent_id)
@@ -257,38 +311,103 @@ pub async fn create_agent(
 pub async fn send_prompt(
     agent_id: String,
     prompt: String,
+    template: Option<PromptTemplateRef>,
+    prompt_file: Option<PromptFileOptions>,
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<PromptSendResult, String> {
+    // A prompt library template replaces the prompt, which must then be empty
+    let (prompt, saved_template) = match template {
+        Some(_) if !prompt.trim().is_empty() => {
+            return Err("Send either a prompt or a template, not both".to_string());
+        }
+        Some(template) => {
+            let (saved, rendered) =
+                prompt_library::render_saved(&state.agent_runs_db, &template).await?;
+            (rendered, Some(saved))
+        }
+        None => (prompt, None),
+    };
+
     let manager = state.agent_manager.lock().await;
-    manager
-        .send_prompt(
+    let delivery = manager
+        .send_prompt_with_options(
             &agent_id,
             &prompt,
+            prompt_file.unwrap_or_default(),
             Some(Arc::new(app_handle)),
             state.security_monitor.clone(),
         )
-        .await
+        .await?;
+    drop(manager);
+
+    if let Some(saved) = saved_template {
+        prompt_library::record_use(&state.agent_runs_db, &saved).await;
+    }
+    Ok(delivery)
 }
 
+/// Prompts waiting for an agent's current turn to end, oldest first
 #[tauri::command]
-pub async fn stop_agent(agent_id: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
-    // Get agent stats before stopping
-    let _stats = {
-        let manager = state.agent_manager.lock().await;
-        manager.get_agent_statistics(&agent_id).await.ok()
-    };
+pub async fn get_queued_prompts(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<QueuedPrompt>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.get_queued_prompts(&agent_id).await
+}
 
-    // Stop the agent
-    {
-        let manager = state.agent_manager.lock().await;
-        manager.stop_agent(&agent_id).await?;
-    }
+/// Drop a queued prompt before it is sent; returns the prompts still queued
+#[tauri::command]
+pub async fn cancel_queued_prompt(
+    agent_id: String,
+    prompt_id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<QueuedPrompt>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .cancel_queued_prompt(&agent_id, &prompt_id, Some(Arc::new(app_handle)))
+        .await
+}
 
-    // Cost data is now automatically tracked in agent_runs_db
-    // via the AgentManager's integration with the database
+/// Stop an agent: by default it gets AGENT_STOP_TIMEOUT_SECS to exit on its
+/// own after its input is closed; `force` kills it at once
+#[tauri::command]
+pub async fn stop_agent(
+    agent_id: String,
+    force: Option<bool>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<StopMode, String> {
+    use tauri::Emitter;
 
-    Ok(())
+    // Final statistics and cost are recorded on the run by the session
+    // finalizer. The manager isn't locked while the agent drains.
+    let pending = state
+        .agent_manager
+        .lock()
+        .await
+        .begin_stop(&agent_id, force.unwrap_or(false), None)
+        .await?;
+    let stop_mode = pending.finish().await?;
+    let notice = state
+        .agent_manager
+        .lock()
+        .await
+        .take_worktree_notice(&agent_id)
+        .await;
+    if let Some(notice) = notice {
+        let _ = app_handle.emit(
+            "toast",
+            serde_json::json!({
+                "type": "warning",
+                "message": notice,
+                "duration": 8000,
+            }),
+        );
+    }
+    Ok(stop_mode)
 }
 
 #[tauri::command]
@@ -306,6 +425,347 @@ pub async fn get_agent_statistics(
     manager.get_agent_statistics(&agent_id).await
 }
 
+/// An agent's last `last_n` outputs (all when 0), optionally only text, tool
+/// results or errors, and only those after `since_timestamp` (ms) so the UI
+/// can fetch incrementally. Outputs evicted from the agent's buffer are read
+/// from the runs database. `raw` keeps ANSI escape codes.
+#[tauri::command]
+pub async fn get_agent_outputs(
+    agent_id: String,
+    last_n: Option<usize>,
+    output_type: Option<OutputTypeFilter>,
+    since_timestamp: Option<i64>,
+    raw: Option<bool>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentOutputEvent>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .get_agent_output_history(
+            &agent_id,
+            last_n.unwrap_or(0),
+            output_type,
+            since_timestamp,
+            raw.unwrap_or(false),
+        )
+        .await
+}
+
+/// Outputs of live and past agents (only `agent_id`'s if given) containing
+/// `query`, newest first, at most `limit` (default 50)
+#[tauri::command]
+pub async fn search_agent_outputs(
+    query: String,
+    agent_id: Option<String>,
+    limit: Option<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<OutputSearchMatch>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .search_agent_outputs(
+            &query,
+            agent_id.as_deref(),
+            limit.unwrap_or(output_search::DEFAULT_SEARCH_LIMIT),
+        )
+        .await
+}
+
+/// Change how many outputs an agent keeps in memory (AGENT_OUTPUT_BUFFER_SIZE
+/// for the others). Returns the new size.
+#[tauri::command]
+pub async fn set_agent_output_buffer_size(
+    agent_id: String,
+    max_outputs: usize,
+    state: tauri::State<'_, AppState>,
+) -> Result<usize, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.set_output_buffer_size(&agent_id, max_outputs).await
+}
+
+/// Every agent with its counters, latest reply snippet, todo progress and
+/// security alert state, for the agent cards in one call
+#[tauri::command]
+pub async fn list_agents_detailed(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentSummary>, String> {
+    let alerted_agents: HashSet<String> = match &state.security_monitor {
+        Some(monitor) => monitor
+            .get_response_handler()
+            .get_pending_reviews()
+            .await
+            .into_iter()
+            .filter_map(|review| review.agent_id)
+            .collect(),
+        None => HashSet::new(),
+    };
+    let manager = state.agent_manager.lock().await;
+    Ok(manager.list_agents_detailed(&alerted_agents).await)
+}
+
+/// Group agents under a name so they can be stopped, prompted and totalled together
+#[tauri::command]
+pub async fn create_agent_group(
+    name: String,
+    agent_ids: Vec<String>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<AgentGroup, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .create_agent_group(&name, &agent_ids, Arc::new(app_handle))
+        .await
+}
+
+/// All agent groups: one per pipeline plus the user's own
+#[tauri::command]
+pub async fn list_agent_groups(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentGroup>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.list_agent_groups().await
+}
+
+/// Stop every agent, or only those from `source` and/or working in
+/// `working_dir`, with an outcome per agent
+#[tauri::command]
+pub async fn stop_all_agents(
+    source: Option<AgentSource>,
+    working_dir: Option<String>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<AgentStopResult>, String> {
+    let manager = state.agent_manager.lock().await;
+    Ok(manager
+        .stop_all(source.as_ref(), working_dir.as_deref(), &app_handle)
+        .await)
+}
+
+/// Stop every running member of a group
+#[tauri::command]
+pub async fn stop_agent_group(
+    group_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<GroupMemberResult>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.stop_agent_group(&group_id).await
+}
+
+/// Send the same prompt to every member of a group
+#[tauri::command]
+pub async fn send_prompt_to_group(
+    group_id: String,
+    prompt: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<GroupMemberResult>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .send_prompt_to_group(
+            &group_id,
+            &prompt,
+            Some(Arc::new(app_handle)),
+            state.security_monitor.clone(),
+        )
+        .await
+}
+
+/// Cost, tokens and tool calls summed over a group's members
+#[tauri::command]
+pub async fn get_group_statistics(
+    group_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<GroupStatistics, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.get_group_statistics(&group_id).await
+}
+
+/// Approximate bytes held by agent output buffers and conversations
+#[tauri::command]
+pub async fn get_memory_footprint(
+    state: tauri::State<'_, AppState>,
+) -> Result<MemoryFootprint, String> {
+    let buffers = {
+        let manager = state.agent_manager.lock().await;
+        manager.output_buffer_footprints().await
+    };
+    Ok(memory_footprint::snapshot(buffers))
+}
+
+#[tauri::command]
+pub async fn add_agent_note(
+    agent_id: String,
+    text: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<AgentNote, String> {
+    use tauri::Emitter;
+
+    let note = {
+        let manager = state.agent_manager.lock().await;
+        manager
+            .add_agent_note(&agent_id, NoteAuthor::User, &text)
+            .await?
+    };
+    let _ = app_handle.emit("agent:note", &note);
+    Ok(note)
+}
+
+#[tauri::command]
+pub async fn get_agent_notes(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentNote>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.get_agent_notes(&agent_id).await
+}
+
+/// Messages relayed to or from an agent by the commander
+#[tauri::command]
+pub async fn get_agent_messages(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentMessage>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.get_agent_messages(&agent_id).await
+}
+
+/// An agent's most recent hook events, newest first (default 100)
+#[tauri::command]
+pub async fn get_agent_hook_events(
+    agent_id: String,
+    limit: Option<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<HookEventRecord>, String> {
+    state
+        .agent_runs_db
+        .get_agent_hook_events(&agent_id, limit.unwrap_or(100))
+        .await
+        .map_err(|e| format!("Failed to load hook events: {}", e))
+}
+
+/// Whether hook events are reaching the hook server at all
+#[tauri::command]
+pub async fn get_hook_health(state: tauri::State<'_, AppState>) -> Result<HookHealth, String> {
+    hook_server::hook_health(&state.agent_runs_db).await
+}
+
+/// Replace an agent whose context is nearly full with a fresh agent that
+/// continues its task from a summary
+#[tauri::command]
+pub async fn handoff_agent(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<HandoffResult, String> {
+    handoff::handoff_agent(&state.agent_manager, &agent_id, Arc::new(app_handle)).await
+}
+
+/// Start a fresh agent with the same configuration as another, seeded with a
+/// summary of its output if `include_context`. Returns the new agent's ID.
+#[tauri::command]
+pub async fn clone_agent(
+    agent_id: String,
+    include_context: Option<bool>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .clone_agent(
+            &agent_id,
+            include_context.unwrap_or(false),
+            Arc::new(app_handle),
+        )
+        .await
+}
+
+/// Rebuild an agent's GitHub context, e.g. after switching branches
+#[tauri::command]
+pub async fn refresh_github_context(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<GitHubContext>, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .refresh_github_context(&agent_id, Arc::new(app_handle))
+        .await
+}
+
+/// Rename a run, replacing the label derived from its initial prompt
+#[tauri::command]
+pub async fn relabel_run(
+    agent_id: String,
+    label: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .relabel_run(&agent_id, &label, Arc::new(app_handle))
+        .await
+}
+
+/// Change a live agent's title, e.g. to name what it's working on
+#[tauri::command]
+pub async fn rename_agent(
+    agent_id: String,
+    title: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<AgentInfo, String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .set_agent_title(&agent_id, &title, &app_handle)
+        .await
+}
+
+/// Opt an agent in or out of repository conventions injection on its first prompt
+#[tauri::command]
+pub async fn set_agent_conventions_injection(
+    agent_id: String,
+    enabled: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = state.agent_manager.lock().await;
+    manager
+        .set_agent_conventions_injection(&agent_id, enabled)
+        .await
+}
+
+/// Opt an agent in or out of auto follow-ups on "shall I proceed?" questions
+#[tauri::command]
+pub async fn set_agent_auto_follow_up(
+    agent_id: String,
+    enabled: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = state.agent_manager.lock().await;
+    manager.set_agent_auto_follow_up(&agent_id, enabled).await
+}
+
+/// Keep an agent's git worktree when it stops, or remove it
+#[tauri::command]
+pub async fn set_agent_keep_worktree(
+    agent_id: String,
+    keep: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = state.agent_manager.lock().await;
+    manager.set_agent_keep_worktree(&agent_id, keep).await
+}
+
+/// Engage or release the global auto follow-up kill switch
+#[tauri::command]
+pub async fn set_auto_follow_up_kill_switch(engaged: bool) -> Result<(), String> {
+    auto_follow_up::set_kill_switch(engaged);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_auto_follow_up_kill_switch() -> Result<bool, String> {
+    Ok(auto_follow_up::kill_switch_engaged())
+}
+
 #[tauri::command]
 pub async fn list_github_repos() -> Result<Vec<serde_json::Value>, String> {
     use std::process::Command;
@@ -428,12 +888,14 @@ pub async fn resume_crashed_run(
     // Recreate the agent with the same working directory
     let manager = state.agent_manager.lock().await;
     let new_agent_id = manager
-        .create_agent(
+        .create_agent_with_model(
             run.working_dir,
             run.github_url,
             None, // instruction files were already copied
             AgentSource::Manual,
             Arc::new(app_handle),
+            run.model,
+            None, // No complexity
         )
         .await?;
 
@@ -455,3 +917,15 @@ pub async fn resume_crashed_run(
         })
     }
 }
+
+/// Resume a crashed agent in place, continuing its Claude session with
+/// `--resume` (unlike resume_crashed_run, which starts a new agent)
+#[tauri::command]
+pub async fn resume_agent(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let manager = state.agent_manager.lock().await;
+    manager.resume_agent(&agent_id, Arc::new(app_handle)).await
+}
@@ -4,6 +4,7 @@
 // (Anthropic, OpenAI, GitHub) by making lightweight API calls.
 
 use crate::error::{ApiError, AppError};
+use crate::http_client;
 use reqwest::Client;
 use serde::Serialize;
 
@@ -72,12 +73,12 @@ pub async fn validate_api_key(
         return Ok(ApiKeyValidationResult::invalid("API key is empty"));
     }
 
-    let http_client = Client::new();
+    let http_client = http_client::shared();
 
     match provider {
-        Provider::Anthropic => validate_anthropic_key(&http_client, api_key).await,
-        Provider::OpenAI => validate_openai_key(&http_client, api_key).await,
-        Provider::GitHub => validate_github_token(&http_client, api_key).await,
+        Provider::Anthropic => validate_anthropic_key(http_client, api_key).await,
+        Provider::OpenAI => validate_openai_key(http_client, api_key).await,
+        Provider::GitHub => validate_github_token(http_client, api_key).await,
     }
 }
 
@@ -88,13 +89,13 @@ async fn validate_anthropic_key(
 ) -> Result<ApiKeyValidationResult, AppError> {
     // Make a minimal messages request - the key validity is determined
     // by the response status, not whether the request succeeds
-    let response = client
+    let request = client
         .post("https://api.anthropic.com/v1/messages")
         .header("x-api-key", api_key)
         .header("anthropic-version", "2023-06-01")
         .header("content-type", "application/json")
-        .body(r#"{"model":"claude-3-haiku-20240307","max_tokens":1,"messages":[{"role":"user","content":"Hi"}]}"#)
-        .send()
+        .body(r#"{"model":"claude-3-haiku-20240307","max_tokens":1,"messages":[{"role":"user","content":"Hi"}]}"#);
+    let response = http_client::send(request)
         .await
         .map_err(|e| AppError::Api(ApiError::Network(format!("Network error: {}", e))))?;
 
@@ -122,10 +123,10 @@ async fn validate_openai_key(
     client: &Client,
     api_key: &str,
 ) -> Result<ApiKeyValidationResult, AppError> {
-    let response = client
+    let request = client
         .get("https://api.openai.com/v1/models")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .send()
+        .header("Authorization", format!("Bearer {}", api_key));
+    let response = http_client::send(request)
         .await
         .map_err(|e| AppError::Api(ApiError::Network(format!("Network error: {}", e))))?;
 
@@ -149,11 +150,11 @@ async fn validate_github_token(
     client: &Client,
     api_key: &str,
 ) -> Result<ApiKeyValidationResult, AppError> {
-    let response = client
+    let request = client
         .get("https://api.github.com/user")
         .header("Authorization", format!("Bearer {}", api_key))
-        .header("User-Agent", "Claude-Commander")
-        .send()
+        .header("User-Agent", "Claude-Commander");
+    let response = http_client::send(request)
         .await
         .map_err(|e| AppError::Api(ApiError::Network(format!("Network error: {}", e))))?;
 
@@ -1,6 +1,8 @@
 // Auto-pipeline related Tauri commands
 
-use crate::auto_pipeline::AutoPipeline;
+use crate::agent_runs_db::EventQueryFilters;
+use crate::auto_pipeline::{AutoPipeline, PipelineHealth, PipelineListFilter};
+use crate::directory_locks::{self, DirectoryLock, DirectoryLocks};
 use crate::AppState;
 use std::sync::Arc;
 
@@ -8,12 +10,15 @@ use std::sync::Arc;
 pub async fn create_auto_pipeline(
     user_request: String,
     working_dir: String,
+    max_retries: Option<u8>,
     state: tauri::State<'_, AppState>,
 ) -> Result<String, String> {
     let manager = state.auto_pipeline_manager.as_ref()
         .ok_or_else(|| "Auto-pipeline unavailable: No API key configured. Set OPENAI_API_KEY or ANTHROPIC_API_KEY in .env".to_string())?;
     let manager = manager.lock().await;
-    manager.create_pipeline(user_request, working_dir).await
+    manager
+        .create_pipeline(user_request, working_dir, max_retries)
+        .await
 }
 
 #[tauri::command]
@@ -29,40 +34,117 @@ pub async fn start_auto_pipeline(
 
     let agent_manager = state.agent_manager.clone();
 
-    // Clone for spawned task
-    let pipeline_id_clone = pipeline_id.clone();
-
-    // Get the execution context and emit started event, then release the lock
+    // Emit started event and spawn execution, then release the lock
     // This allows multiple pipelines to run concurrently
-    let ctx = {
-        let mgr = manager.lock().await;
-
-        // Emit pipeline started event so the UI can switch to view it
-        if let Some(pipeline) = mgr.get_pipeline(&pipeline_id).await {
-            let _ = app_handle.emit("auto_pipeline:started", pipeline);
-        } else {
-            // Fallback if somehow pipeline is missing (shouldn't happen)
-            let _ = app_handle.emit(
-                "auto_pipeline:started",
-                serde_json::json!({
-                    "pipeline_id": pipeline_id,
-                }),
-            );
+    let mgr = manager.lock().await;
+
+    // Emit pipeline started event so the UI can switch to view it
+    if let Some(pipeline) = mgr.get_pipeline(&pipeline_id).await {
+        let _ = app_handle.emit("auto_pipeline:started", pipeline);
+    } else {
+        // Fallback if somehow pipeline is missing (shouldn't happen)
+        let _ = app_handle.emit(
+            "auto_pipeline:started",
+            serde_json::json!({
+                "pipeline_id": pipeline_id,
+            }),
+        );
+    }
+
+    // Execution runs on its own task; the manager only tracks its abort handle
+    mgr.start_pipeline(&pipeline_id, agent_manager, Arc::new(app_handle))
+        .await?;
+
+    Ok(())
+}
+
+/// Answer the question a pipeline's orchestrator paused on, resuming the pipeline
+#[tauri::command]
+pub async fn answer_pipeline_human_request(
+    pipeline_id: String,
+    answer: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let manager = state.auto_pipeline_manager.as_ref()
+        .ok_or_else(|| "Auto-pipeline unavailable: No API key configured. Set OPENAI_API_KEY or ANTHROPIC_API_KEY in .env".to_string())?;
+    let manager = manager.lock().await;
+    manager
+        .answer_human_request(
+            &pipeline_id,
+            &answer,
+            state.agent_manager.clone(),
+            Arc::new(app_handle),
+        )
+        .await
+}
+
+/// Rolling error rate of a pipeline's orchestrator tool calls. Pipelines that
+/// are no longer loaded get it rebuilt from their persisted tool calls.
+#[tauri::command]
+pub async fn get_pipeline_health(
+    pipeline_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<PipelineHealth, String> {
+    if let Some(manager) = state.auto_pipeline_manager.as_ref() {
+        if let Some(pipeline) = manager.lock().await.get_pipeline(&pipeline_id).await {
+            return Ok(pipeline.tool_health);
         }
+    }
 
-        // Get the shared context - this is Arc-wrapped so we can use it after dropping the lock
-        mgr.get_ctx()
+    let filters = EventQueryFilters {
+        pipeline_id: Some(pipeline_id),
+        ..Default::default()
     };
-    // Lock is now released - other pipelines can start
+    let mut calls = state
+        .agent_runs_db
+        .query_tool_calls(filters)
+        .await
+        .map_err(|e| e.to_string())?;
+    if calls.is_empty() {
+        return Err("Pipeline not found".to_string());
+    }
+    // Queried newest first
+    calls.reverse();
+    Ok(PipelineHealth::from_tool_calls(&calls))
+}
 
-    // Spawn async execution using the context directly
-    tokio::spawn(async move {
-        let _ = ctx
-            .execute_pipeline(pipeline_id_clone, agent_manager, Arc::new(app_handle))
-            .await;
-    });
+/// Working directories locked by running pipelines
+#[tauri::command]
+pub async fn list_directory_locks() -> Result<Vec<DirectoryLock>, String> {
+    Ok(DirectoryLocks::global().list())
+}
 
-    Ok(())
+/// Release a directory's lock whichever pipeline holds it
+#[tauri::command]
+pub async fn force_release_lock(
+    dir: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<DirectoryLock, String> {
+    directory_locks::force_release(&dir, state.app_handle.as_ref())
+        .ok_or_else(|| format!("{} is not locked", dir))
+}
+
+/// Pipelines newest first. Finished ones are left out unless
+/// `include_finished` is set, which also reads those evicted from memory.
+/// `since` (ms) keeps pipelines created at or after it.
+#[tauri::command]
+pub async fn list_auto_pipelines(
+    include_finished: Option<bool>,
+    since: Option<i64>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AutoPipeline>, String> {
+    let manager = state.auto_pipeline_manager.as_ref()
+        .ok_or_else(|| "Auto-pipeline unavailable: No API key configured. Set OPENAI_API_KEY or ANTHROPIC_API_KEY in .env".to_string())?;
+    let filter = PipelineListFilter {
+        include_finished: include_finished.unwrap_or(false),
+        since,
+    };
+    manager
+        .lock()
+        .await
+        .list_pipelines_filtered(filter, &state.agent_runs_db)
+        .await
 }
 
 #[tauri::command]
@@ -72,14 +154,12 @@ pub async fn get_auto_pipeline(
 ) -> Result<AutoPipeline, String> {
     eprintln!("[get_auto_pipeline] Called for pipeline_id={}", pipeline_id);
 
-    let manager = state
-        .auto_pipeline_manager
-        .as_ref()
-        .ok_or_else(|| "Auto-pipeline unavailable: No API key configured".to_string())?;
+    let manager = state.auto_pipeline_manager.as_ref()
+        .ok_or_else(|| "Auto-pipeline unavailable: No API key configured. Set OPENAI_API_KEY or ANTHROPIC_API_KEY in .env".to_string())?;
     let manager = manager.lock().await;
     let mut pipeline = manager
-        .get_pipeline(&pipeline_id)
-        .await
+        .find_pipeline(&pipeline_id, &state.agent_runs_db)
+        .await?
         .ok_or_else(|| "Pipeline not found".to_string())?;
 
     eprintln!(
@@ -90,7 +170,6 @@ pub async fn get_auto_pipeline(
 
     // Fetch ALL outputs for this pipeline from the database
     {
-        use crate::agent_runs_db::EventQueryFilters;
         let runs_db = &state.agent_runs_db;
 
         // Query all outputs for this pipeline (not filtered by agent_id)
@@ -101,6 +180,7 @@ pub async fn get_auto_pipeline(
             until_timestamp: None,
             limit: None,
             offset: None,
+            before: None,
         };
 
         match runs_db.query_agent_outputs(filters).await {
@@ -2,25 +2,70 @@
 
 use serde::Serialize;
 
-use crate::agent_runs_db::{ConversationQueryFilters, MetaConversationRecord};
-use crate::meta_agent::CommanderPersonality;
-use crate::types::{ChatMessage, ChatResponse, ImageAttachment};
+use crate::agent_runs_db::{
+    CompactionRecord, ConversationQueryFilters, FeedbackSummary, MessageRating,
+    MetaConversationRecord, MetaMessageRecord, RatedMessageContext,
+};
+use crate::ai_client::{AIClient, AIResponse, Message};
+use crate::meta_agent::directory_approval::DirectoryApprovalRequest;
+use crate::meta_agent::tools::{answer_pending_question, AnswerSource, PendingQuestionInfo};
+use crate::meta_agent::{
+    conversation_titles, format_agent_results, BusyMode, ChatError, ChatQueueStatus,
+    CommanderPersonality, LoopActivitySnapshot, SystemPromptPreview, DEFAULT_RESULT_MAX_CHARS,
+};
+use crate::stopword::{self, HaltTargets, StopSource};
+use crate::subsystems;
+use crate::types::{ChatMessage, ChatResponse, ChatUsage, ImageAttachment};
 use crate::utils::string::truncate_with_ellipsis;
+use crate::workspace::active_workspace_id;
 use crate::AppState;
 
+/// Window used by the feedback commands when no `days` is given
+const DEFAULT_FEEDBACK_DAYS: i64 = 30;
+
 #[derive(Debug, Serialize)]
 pub struct SystemPromptResponse {
     pub prompt: String,
     pub source: String,
 }
 
+/// The last compaction of a conversation with the messages it replaced
+#[derive(Debug, Serialize)]
+pub struct CompactionReview {
+    pub compaction: CompactionRecord,
+    pub replaced_messages: Vec<MetaMessageRecord>,
+}
+
 #[tauri::command]
 pub async fn send_chat_message(
     message: String,
     image: Option<ImageAttachment>,
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
-) -> Result<ChatResponse, String> {
+) -> Result<ChatResponse, ChatError> {
+    // Stop phrases halt everything and never reach the meta-agent
+    if let Some(phrase) = stopword::detect(&message) {
+        let report =
+            stopword::halt_all(&HaltTargets::from_state(&state), phrase, StopSource::Chat).await;
+        return Ok(ChatResponse {
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content: report.confirmation(),
+                image: None,
+                tool_calls: None,
+                timestamp: report.timestamp,
+            },
+            usage: ChatUsage::default(),
+        });
+    }
+
+    // Without a working AI client, say why rather than fail on the API call
+    if let Some(message) = state.subsystems.unavailable(subsystems::META_AGENT) {
+        return Err(ChatError::Failed { message });
+    }
+
+    let mut mode = BusyMode::from_env();
+
     // CRITICAL: Interrupt sleep BEFORE acquiring meta_agent lock
     // This allows user messages to wake up a sleeping meta-agent
     {
@@ -29,16 +74,29 @@ pub async fn send_chat_message(
             if let Some(cancel_tx) = sleep_state.cancel_tx.take() {
                 eprintln!("[send_chat_message] Interrupting sleep with user message");
                 let _ = cancel_tx.send(message.clone());
-                // Don't return - the meta-agent will process the interrupted message
+                // Don't return - the meta-agent will process the interrupted message.
+                // The woken turn expects it, so it is queued even in reject mode.
+                mode = BusyMode::Queue;
             }
         }
     }
 
+    // Wait for (or reject on) a running turn before touching the meta-agent
+    let _turn = state.chat_turns.acquire(&message, mode).await?;
+
     let mut meta_agent = state.meta_agent.lock().await;
     meta_agent
         .process_user_message_with_image(message, image, state.agent_manager.clone(), app_handle)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| ChatError::from(e.to_string()))
+}
+
+/// The running chat turn and the messages queued behind it
+#[tauri::command]
+pub async fn get_chat_queue_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<ChatQueueStatus, String> {
+    Ok(state.chat_turns.status())
 }
 
 #[tauri::command]
@@ -60,6 +118,7 @@ pub async fn clear_chat_history(state: tauri::State<'_, AppState>) -> Result<(),
 pub async fn process_agent_results(
     agent_id: String,
     results_only: Option<bool>,
+    max_chars: Option<usize>,
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<ChatResponse, String> {
@@ -67,7 +126,7 @@ pub async fn process_agent_results(
 
     // Get agent outputs
     let manager = state.agent_manager.lock().await;
-    let outputs = manager.get_agent_outputs(&agent_id, 0).await?;
+    let outputs = manager.get_agent_outputs(&agent_id, 0, None).await?;
 
     // Get agent info
     let agents = manager.list_agents().await;
@@ -76,69 +135,22 @@ pub async fn process_agent_results(
         .map(|a| a.working_dir.clone())
         .unwrap_or_else(|| agent_id.clone());
 
-    // Format outputs
-    let mut formatted_output = if results_only {
-        format!("Final results from agent in {}:\n\n", agent_name)
-    } else {
-        format!("Full output from agent in {}:\n\n", agent_name)
-    };
-
-    for output in outputs.iter() {
-        match output.output_type.as_str() {
-            "text" => {
-                formatted_output.push_str(&format!("Assistant: {}\n\n", output.content));
-            }
-            "tool_use" => {
-                // Skip tool uses if results_only mode
-                if results_only {
-                    continue;
-                }
-                // Extract tool name from content
-                let tool_name = if output.content.contains("Using tool:") {
-                    output.content.lines().next().unwrap_or("Unknown tool")
-                } else {
-                    "Using tool"
-                };
-                formatted_output.push_str(&format!("{}\n", tool_name));
-            }
-            "tool_result" => {
-                // Skip tool results if results_only mode
-                if results_only {
-                    continue;
-                }
-                // Truncate long tool results (safely handling UTF-8 boundaries)
-                let truncated = truncate_with_ellipsis(&output.content, 500);
-                formatted_output.push_str(&format!("Result: {}\n\n", truncated));
-            }
-            "result" => {
-                formatted_output.push_str("\n--- Final Results ---\n");
-                if let Some(parsed) = &output.parsed_json {
-                    if let Some(cost) = parsed.get("total_cost_usd").and_then(|v| v.as_f64()) {
-                        formatted_output.push_str(&format!("Cost: ${:.4}\n", cost));
-                    }
-                    if let Some(usage) = parsed.get("usage") {
-                        if let Some(input_tokens) =
-                            usage.get("input_tokens").and_then(|v| v.as_u64())
-                        {
-                            if let Some(output_tokens) =
-                                usage.get("output_tokens").and_then(|v| v.as_u64())
-                            {
-                                formatted_output.push_str(&format!(
-                                    "Tokens: {} input, {} output\n",
-                                    input_tokens, output_tokens
-                                ));
-                            }
-                        }
-                    }
-                }
-                formatted_output.push('\n');
-            }
-            _ => {}
-        }
-    }
+    let formatted_output = format_agent_results(
+        &outputs,
+        &agent_name,
+        results_only,
+        max_chars.unwrap_or(DEFAULT_RESULT_MAX_CHARS),
+    );
 
     drop(manager);
 
+    // Runs as a chat turn, after any messages already queued
+    let _turn = state
+        .chat_turns
+        .acquire(&format!("Results of {}", agent_name), BusyMode::Queue)
+        .await
+        .map_err(|e| e.to_string())?;
+
     // Process the formatted output as a user message through the meta agent
     let mut meta_agent = state.meta_agent.lock().await;
     let response = meta_agent
@@ -163,6 +175,18 @@ pub async fn set_commander_personality(
     meta_agent.set_personality(personality).await
 }
 
+/// Phrases that halt all agent activity when sent as a chat or voice message
+#[tauri::command]
+pub async fn get_stop_phrases() -> Result<Vec<String>, String> {
+    Ok(stopword::stop_phrases())
+}
+
+/// Replace the stop phrases; returns the saved list (trimmed, without duplicates)
+#[tauri::command]
+pub async fn set_stop_phrases(phrases: Vec<String>) -> Result<Vec<String>, String> {
+    stopword::set_stop_phrases(phrases)
+}
+
 #[tauri::command]
 pub async fn get_commander_system_prompt(
     state: tauri::State<'_, AppState>,
@@ -174,12 +198,43 @@ pub async fn get_commander_system_prompt(
         prompt,
         source: if personalized {
             "personalized".to_string()
+        } else if meta_agent.has_base_prompt_override() {
+            "override".to_string()
         } else {
             "base".to_string()
         },
     })
 }
 
+/// The system prompt as sent next turn (with memory), by section, with token estimates
+#[tauri::command]
+pub async fn preview_effective_system_prompt(
+    state: tauri::State<'_, AppState>,
+) -> Result<SystemPromptPreview, String> {
+    let meta_agent = state.meta_agent.lock().await;
+    Ok(meta_agent.preview_effective_system_prompt())
+}
+
+/// Replace the built-in commander prompt (advanced). `{max_iterations}` is filled in.
+#[tauri::command]
+pub async fn set_base_system_prompt_override(
+    text: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<SystemPromptPreview, String> {
+    let mut meta_agent = state.meta_agent.lock().await;
+    meta_agent.set_base_prompt_override(text).await?;
+    Ok(meta_agent.preview_effective_system_prompt())
+}
+
+/// Go back to the built-in commander prompt
+#[tauri::command]
+pub async fn reset_base_system_prompt_override(
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let mut meta_agent = state.meta_agent.lock().await;
+    meta_agent.reset_base_prompt_override().await
+}
+
 #[tauri::command]
 pub async fn reset_commander_personality(state: tauri::State<'_, AppState>) -> Result<(), String> {
     eprintln!("[reset_commander_personality] Clearing personality and cached prompt");
@@ -202,25 +257,65 @@ pub async fn answer_meta_agent_question(
 
     // Use the shared pending_meta_question directly from AppState
     // This avoids deadlock since we don't need to lock meta_agent
-    let mut pending = state.pending_meta_question.lock().await;
-    if let Some(pq) = pending.take() {
-        if pq.question_id == question_id {
-            // Send the answer through the oneshot channel
-            pq.response_tx
-                .send(answer)
-                .map_err(|_| "Failed to send answer: channel closed".to_string())?;
-            Ok(())
-        } else {
-            // Put it back if question_id doesn't match
-            *pending = Some(pq);
-            Err(format!(
-                "Question ID mismatch: expected current pending question, got {}",
-                question_id
-            ))
-        }
-    } else {
-        Err("No pending question to answer".to_string())
-    }
+    answer_pending_question(
+        &state.pending_meta_question,
+        Some(&question_id),
+        answer,
+        AnswerSource::Ui,
+    )
+    .await
+}
+
+/// Get the question the meta-agent is currently waiting on, if any.
+///
+/// Reads the shared pending question so it works while the tool loop holds
+/// the MetaAgent lock.
+#[tauri::command]
+pub async fn get_pending_meta_question(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<PendingQuestionInfo>, String> {
+    let pending = state.pending_meta_question.lock().await;
+    Ok(pending.as_ref().map(|pq| pq.info.clone()))
+}
+
+/// Approve or deny a working directory the meta-agent wants to create a worker in.
+///
+/// With `remember`, an approved directory is added to the workspace allowlist
+/// so later agents there (or in its subdirectories) start without asking.
+#[tauri::command]
+pub async fn approve_directory(
+    nonce: String,
+    approved: bool,
+    remember: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .directory_approvals
+        .resolve(&nonce, approved, remember)
+        .await
+}
+
+/// Get the working directories waiting for the user's approval
+#[tauri::command]
+pub async fn get_pending_directory_approvals(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DirectoryApprovalRequest>, String> {
+    Ok(state.directory_approvals.pending_requests().await)
+}
+
+/// Get what the meta-agent's tool loop is currently doing.
+///
+/// Reads the shared loop activity rather than the MetaAgent, which stays
+/// locked for as long as a loop (including Sleep and AskUserQuestion) runs.
+#[tauri::command]
+pub async fn get_meta_agent_activity(
+    state: tauri::State<'_, AppState>,
+) -> Result<LoopActivitySnapshot, String> {
+    let activity = state
+        .meta_loop_activity
+        .lock()
+        .map_err(|e| format!("Failed to read meta-agent activity: {}", e))?;
+    Ok(activity.snapshot())
 }
 
 // =========================================================================
@@ -237,6 +332,7 @@ pub async fn list_conversations(
     let filters = ConversationQueryFilters {
         include_archived: include_archived.unwrap_or(false),
         search_text,
+        workspace_id: Some(active_workspace_id()),
         limit,
         offset: None,
     };
@@ -263,6 +359,22 @@ pub async fn new_conversation(state: tauri::State<'_, AppState>) -> Result<Strin
     meta_agent.start_new_conversation().await
 }
 
+/// Fork a conversation at a message (0-based, inclusive) and switch to the fork
+#[tauri::command]
+pub async fn fork_conversation(
+    conversation_id: String,
+    at_message_index: u32,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let mut meta_agent = state.meta_agent.lock().await;
+    let fork_id = meta_agent
+        .fork_conversation(&conversation_id, at_message_index)
+        .await?;
+    meta_agent.emit_context_info(&app_handle);
+    Ok(fork_id)
+}
+
 #[tauri::command]
 pub async fn delete_conversation(
     conversation_id: String,
@@ -282,6 +394,7 @@ pub async fn delete_conversation(
         .agent_runs_db
         .delete_meta_conversation(&conversation_id)
         .await
+        .map(|_| ())
         .map_err(|e| format!("Failed to delete conversation: {}", e))
 }
 
@@ -298,6 +411,209 @@ pub async fn rename_conversation(
         .map_err(|e| format!("Failed to rename conversation: {}", e))
 }
 
+/// Generate a paragraph summary of a conversation and store it on the record
+#[tauri::command]
+pub async fn summarize_conversation(
+    conversation_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let records = state
+        .agent_runs_db
+        .get_meta_messages(&conversation_id)
+        .await
+        .map_err(|e| format!("Failed to get messages: {}", e))?;
+
+    if records.is_empty() {
+        return Err("Conversation has no messages to summarize".to_string());
+    }
+
+    let messages: Vec<Message> = records
+        .into_iter()
+        .map(|r| Message {
+            role: r.role,
+            content: r.content,
+        })
+        .collect();
+
+    let client =
+        AIClient::light_from_env().map_err(|e| format!("Failed to create light client: {}", e))?;
+    let summary = conversation_titles::generate_summary(&client, &messages).await?;
+
+    state
+        .agent_runs_db
+        .set_meta_conversation_summary(&conversation_id, &summary)
+        .await
+        .map_err(|e| format!("Failed to store summary: {}", e))?;
+
+    Ok(summary)
+}
+
+/// Rate a commander response (None clears the rating). Ratings are only
+/// stored for review; they are never fed back into the live prompt.
+#[tauri::command]
+pub async fn rate_message(
+    conversation_id: String,
+    message_index: u32,
+    rating: Option<MessageRating>,
+    note: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let rated = state
+        .agent_runs_db
+        .rate_meta_message(&conversation_id, message_index, rating, note.as_deref())
+        .await
+        .map_err(|e| format!("Failed to rate message: {}", e))?;
+    if !rated {
+        return Err(format!(
+            "No commander response at index {} in conversation {}",
+            message_index, conversation_id
+        ));
+    }
+    Ok(())
+}
+
+/// Ratings from the last `days` days (default 30) by personality and model
+#[tauri::command]
+pub async fn get_feedback_summary(
+    days: Option<i64>,
+    state: tauri::State<'_, AppState>,
+) -> Result<FeedbackSummary, String> {
+    state
+        .agent_runs_db
+        .get_feedback_summary(days.unwrap_or(DEFAULT_FEEDBACK_DAYS))
+        .await
+        .map_err(|e| format!("Failed to summarize feedback: {}", e))
+}
+
+/// Rated responses with the user message and tools that led to them
+#[tauri::command]
+pub async fn get_rated_messages(
+    rating: MessageRating,
+    days: Option<i64>,
+    limit: Option<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<RatedMessageContext>, String> {
+    state
+        .agent_runs_db
+        .get_rated_meta_messages(
+            rating,
+            days.unwrap_or(DEFAULT_FEEDBACK_DAYS),
+            limit.unwrap_or(100),
+        )
+        .await
+        .map_err(|e| format!("Failed to get rated messages: {}", e))
+}
+
+/// The last compaction of a conversation (the current one by default) and
+/// the messages it replaced, to check the summary against them
+#[tauri::command]
+pub async fn review_last_compaction(
+    conversation_id: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<CompactionReview>, String> {
+    let conversation_id = match conversation_id {
+        Some(id) => id,
+        None => {
+            let meta_agent = state.meta_agent.lock().await;
+            match meta_agent.get_current_conversation_id() {
+                Some(id) => id.to_string(),
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let Some(compaction) = state
+        .agent_runs_db
+        .get_last_meta_compaction(&conversation_id)
+        .await
+        .map_err(|e| format!("Failed to get compaction: {}", e))?
+    else {
+        return Ok(None);
+    };
+    let replaced_messages = state
+        .agent_runs_db
+        .get_meta_messages(&conversation_id)
+        .await
+        .map_err(|e| format!("Failed to get messages: {}", e))?
+        .into_iter()
+        .filter(|m| {
+            (compaction.first_message_index..=compaction.last_message_index)
+                .contains(&m.message_index)
+        })
+        .collect();
+
+    Ok(Some(CompactionReview {
+        compaction,
+        replaced_messages,
+    }))
+}
+
+/// Pin a message so compaction keeps it word for word, or unpin it (also
+/// for messages kept automatically because they look like a constraint)
+#[tauri::command]
+pub async fn pin_message(
+    conversation_id: String,
+    message_index: u32,
+    pinned: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let found = state
+        .agent_runs_db
+        .set_meta_message_pin(&conversation_id, message_index, pinned)
+        .await
+        .map_err(|e| format!("Failed to pin message: {}", e))?;
+    if !found {
+        return Err(format!(
+            "No message at index {} in conversation {}",
+            message_index, conversation_id
+        ));
+    }
+    Ok(())
+}
+
+/// Dev mode: run the meta-agent against the scripted mock provider so the
+/// frontend can be worked on offline. `script` is returned response by
+/// response; without one the mock sends a status update and then replies.
+#[tauri::command]
+pub async fn use_mock_ai_provider(
+    script: Option<Vec<AIResponse>>,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    #[cfg(feature = "mock-provider")]
+    {
+        use crate::ai_client::providers::MockProvider;
+
+        let script = script.unwrap_or_else(|| {
+            vec![
+                MockProvider::tool_use(
+                    "toolu_mock_1",
+                    "UpdateUser",
+                    serde_json::json!({"message": "Mock provider: checking on the agents"}),
+                ),
+                MockProvider::text("This reply came from the mock AI provider."),
+            ]
+        });
+        let count = script.len();
+        let mut meta_agent = state.meta_agent.lock().await;
+        meta_agent.set_ai_client(AIClient::with_provider(std::sync::Arc::new(
+            MockProvider::new(script),
+        )));
+        state.subsystems.set_ok(subsystems::META_AGENT);
+        Ok(format!(
+            "Meta-agent is using the mock provider ({} scripted responses)",
+            count
+        ))
+    }
+    #[cfg(not(feature = "mock-provider"))]
+    {
+        let _ = (script, state);
+        Err(
+            "The mock AI provider isn't compiled in; rebuild with --features mock-provider"
+                .to_string(),
+        )
+    }
+}
+
 #[tauri::command]
 pub async fn get_current_conversation_id(
     state: tauri::State<'_, AppState>,
@@ -3,15 +3,25 @@
 // This module provides the Tauri command entry points for configuration
 // management. Business logic is delegated to submodules:
 // - config_loader: Environment variable loading, parsing, and file operations
+// - env_registry: Every environment variable the app reads, the .env template
+//   and configuration validation
 // - api_validator: API key validation for various providers
 
+use crate::agent_manager::claude_cli::{self, ClaudeCli, ClaudeCliSource};
 use crate::ai_client::models;
 use crate::commands::api_validator::{self, ApiKeyValidationResult};
 use crate::commands::config_loader::{
     self, env_keys, mask_api_key, AVAILABLE_CLAUDE_MODELS, CLAUDE_CODE_MODEL_OPTIONS,
     CLAUDE_MODEL_ALIASES, FALLBACK_OPENAI_MODELS,
 };
+use crate::commands::env_registry::{self, ConfigIssue, IssueSeverity};
+use crate::observer_mode::{self, ObserverModeStatus};
+use crate::subsystems::SubsystemStatus;
+use crate::version_info::{self, VersionInfo};
+use crate::AppState;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 // ============================================================================
 // Data Types
@@ -27,6 +37,22 @@ pub struct ClaudeCodeStatus {
     pub subscription_type: Option<String>,
 }
 
+/// Which claude CLI agents are started with, for debugging the installation
+#[derive(Debug, Serialize)]
+pub struct ClaudeCliDiagnosis {
+    /// The CLI as agents run it
+    #[serde(flatten)]
+    pub cli: ClaudeCli,
+    /// CLAUDE_PATH, if set
+    pub claude_path_env: Option<String>,
+    /// Output of `--version`
+    pub version: Option<String>,
+    /// Why `--version` failed
+    pub error: Option<String>,
+    /// Every place the CLI is looked for when CLAUDE_PATH is unset, in order
+    pub searched: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ModelConfig {
     pub name: String,
@@ -55,6 +81,16 @@ pub struct ConfigStatus {
     pub is_first_run: bool,
 }
 
+/// Result of checking the loaded environment against the registry
+#[derive(Debug, Serialize)]
+pub struct ConfigValidation {
+    /// False when any issue is an error
+    pub valid: bool,
+    pub issues: Vec<ConfigIssue>,
+    /// The .env files whose variable names were checked
+    pub env_files: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ConfigUpdate {
     pub key: String,
@@ -74,15 +110,14 @@ pub struct ConfigUpdateResult {
 
 #[tauri::command]
 pub async fn check_claude_code_installed() -> Result<ClaudeCodeStatus, String> {
-    use crate::agent_manager::claude_cli::find_claude_cli;
-
-    // Check installation
-    let (installed, path, version) = match find_claude_cli() {
-        Ok(p) => {
-            let v = get_claude_version(&p).await.ok();
-            (true, Some(p), v)
+    // Check installation (running it through npx doesn't count)
+    let cli = claude_cli::resolve_claude_cli(None);
+    let (installed, path, version) = match cli.source {
+        ClaudeCliSource::Npx => (false, None, None),
+        _ => {
+            let v = get_claude_version(&cli).await.ok();
+            (true, Some(cli.path), v)
         }
-        Err(_) => (false, None, None),
     };
 
     // Check authentication by reading ~/.claude/.credentials.json
@@ -144,18 +179,54 @@ fn check_claude_auth() -> (bool, Option<String>, Option<String>) {
 }
 
 /// Get the Claude CLI version by running `claude --version`
-async fn get_claude_version(cli_path: &str) -> Result<String, String> {
-    let output = tokio::process::Command::new(cli_path)
-        .arg("--version")
-        .output()
+async fn get_claude_version(cli: &ClaudeCli) -> Result<String, String> {
+    // npx may have to download the CLI first
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+    let output = cli
+        .command(&["--version".to_string()])
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(TIMEOUT, output)
         .await
-        .map_err(|e| e.to_string())?;
-
+        .map_err(|_| format!("{} --version timed out", cli.path))?
+        .map_err(|e| format!("Failed to run {}: {}", cli.path, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{} --version failed ({}): {}",
+            cli.path,
+            output.status,
+            stderr.trim()
+        ));
+    }
     String::from_utf8(output.stdout)
         .map(|s| s.trim().to_string())
         .map_err(|e| e.to_string())
 }
 
+/// Report which claude CLI agents in `working_dir` would be started with,
+/// where it was found and its version
+#[tauri::command]
+pub async fn diagnose_claude_cli(
+    working_dir: Option<String>,
+) -> Result<ClaudeCliDiagnosis, String> {
+    let working_dir = working_dir.map(PathBuf::from);
+    let cli = claude_cli::resolve_claude_cli(working_dir.as_deref());
+    let (version, error) = match get_claude_version(&cli).await {
+        Ok(version) => (Some(version), None),
+        Err(e) => (None, Some(e)),
+    };
+    Ok(ClaudeCliDiagnosis {
+        claude_path_env: env_registry::CLAUDE_PATH.var().ok(),
+        searched: claude_cli::claude_cli_search_paths(working_dir.as_deref()),
+        cli,
+        version,
+        error,
+    })
+}
+
 #[tauri::command]
 pub async fn get_config_status() -> Result<ConfigStatus, String> {
     // Determine active provider
@@ -285,6 +356,102 @@ pub async fn validate_api_key(
     api_validator::validate_api_key_by_name(&provider, &api_key).await
 }
 
+// ============================================================================
+// Tauri Commands - Environment Template & Validation
+// ============================================================================
+
+/// Write a commented .env template listing every variable the app reads.
+/// `path` may be a directory, in which case `.env.example` is written into it.
+/// Returns the path written.
+#[tauri::command]
+pub async fn generate_env_template(path: String) -> Result<String, String> {
+    let mut path = PathBuf::from(path.trim());
+    if path.as_os_str().is_empty() {
+        return Err("path cannot be empty".to_string());
+    }
+    if path.is_dir() {
+        path = path.join(".env.example");
+    }
+    // Never replace live settings with the template
+    if path.exists() && path.file_name().is_some_and(|name| name == ".env") {
+        return Err(format!(
+            "{} already exists; write the template to another file",
+            path.display()
+        ));
+    }
+
+    std::fs::write(&path, env_registry::env_template())
+        .map_err(|e| format!("Failed to write env template: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Check the loaded environment for unknown or misspelled variables,
+/// deprecated names, invalid values and settings that contradict each other
+#[tauri::command]
+pub async fn validate_configuration() -> Result<ConfigValidation, String> {
+    // The same files loaded at startup: the config directory's, then the cwd's
+    let candidates = [
+        config_loader::get_env_file_path().ok(),
+        std::env::current_dir().ok().map(|dir| dir.join(".env")),
+    ];
+    let mut env_files = Vec::new();
+    let mut file_keys = Vec::new();
+    for path in candidates.into_iter().flatten() {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        file_keys.extend(
+            config_loader::parse_env_content(&content)
+                .into_iter()
+                .filter_map(|(key, _)| key)
+                .map(|key| key.trim_start_matches("export ").trim().to_string()),
+        );
+        env_files.push(path.to_string_lossy().to_string());
+    }
+
+    let env: HashMap<String, String> = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect();
+    let issues = env_registry::validate(&env, &file_keys);
+
+    Ok(ConfigValidation {
+        valid: !issues
+            .iter()
+            .any(|issue| issue.severity == IssueSeverity::Error),
+        issues,
+        env_files,
+    })
+}
+
+/// Turn read-only observer mode on or off
+#[tauri::command]
+pub async fn set_observer_mode(
+    enabled: bool,
+    app_handle: tauri::AppHandle,
+) -> Result<ObserverModeStatus, String> {
+    Ok(observer_mode::set_enabled(enabled, &app_handle))
+}
+
+#[tauri::command]
+pub async fn get_observer_mode() -> Result<ObserverModeStatus, String> {
+    Ok(observer_mode::status())
+}
+
+/// How each subsystem came up at startup (ok, degraded or failed, with the
+/// reason and how to fix it)
+#[tauri::command]
+pub async fn get_subsystem_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SubsystemStatus>, String> {
+    Ok(state.subsystems.list())
+}
+
+/// App version, git commit, schema version and compiled-in features of this build
+#[tauri::command]
+pub async fn get_version_info() -> Result<VersionInfo, String> {
+    Ok(version_info::current())
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -299,7 +466,7 @@ fn build_api_key_statuses() -> Vec<ApiKeyStatus> {
     providers
         .iter()
         .map(|(name, key)| {
-            if let Ok(api_key) = std::env::var(key) {
+            if let Ok(api_key) = env_registry::read(key) {
                 ApiKeyStatus {
                     provider: name.to_string(),
                     is_configured: !api_key.is_empty(),
@@ -330,7 +497,7 @@ fn build_model_configs() -> Vec<ModelConfig> {
     model_keys
         .iter()
         .map(|&key| {
-            let value = std::env::var(key).ok();
+            let value = env_registry::read(key).ok();
             let is_default = value.is_none();
             let default_value = get_default_for_model_key(key);
             eprintln!(
@@ -360,7 +527,7 @@ fn get_default_for_model_key(key: &str) -> Option<String> {
 
 /// Fetch available OpenAI models, falling back to defaults on error
 async fn fetch_openai_models() -> Vec<String> {
-    if let Ok(api_key) = std::env::var(env_keys::OPENAI_API_KEY) {
+    if let Ok(api_key) = env_registry::OPENAI_API_KEY.var() {
         if !api_key.is_empty() {
             match models::list_openai_models(&api_key).await {
                 Ok(models) => return models,
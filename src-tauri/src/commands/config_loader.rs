@@ -3,6 +3,7 @@
 // This module handles environment variable loading, parsing, and caching
 // to reduce redundant lookups and centralize configuration access.
 
+use crate::commands::env_registry;
 use crate::error::{AppError, ConfigError};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -11,16 +12,18 @@ use std::sync::OnceLock;
 /// Cached environment variables for frequently accessed config keys
 static ENV_CACHE: OnceLock<EnvCache> = OnceLock::new();
 
-/// Environment variable names used throughout the application
+/// Names of the variables edited in settings (declared in env_registry)
 pub mod env_keys {
-    pub const ANTHROPIC_API_KEY: &str = "ANTHROPIC_API_KEY";
-    pub const OPENAI_API_KEY: &str = "OPENAI_API_KEY";
-    pub const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
-    pub const PRIMARY_MODEL: &str = "PRIMARY_MODEL";
-    pub const SECURITY_MODEL: &str = "SECURITY_MODEL";
-    pub const LIGHT_TASK_MODEL: &str = "LIGHT_TASK_MODEL";
-    pub const CLAUDE_CODE_API_KEY_MODE: &str = "CLAUDE_CODE_API_KEY_MODE";
-    pub const CLAUDE_CODE_MODEL: &str = "CLAUDE_CODE_MODEL";
+    use crate::commands::env_registry;
+
+    pub const ANTHROPIC_API_KEY: &str = env_registry::ANTHROPIC_API_KEY.name;
+    pub const OPENAI_API_KEY: &str = env_registry::OPENAI_API_KEY.name;
+    pub const GITHUB_TOKEN: &str = env_registry::GITHUB_TOKEN.name;
+    pub const PRIMARY_MODEL: &str = env_registry::PRIMARY_MODEL.name;
+    pub const SECURITY_MODEL: &str = env_registry::SECURITY_MODEL.name;
+    pub const LIGHT_TASK_MODEL: &str = env_registry::LIGHT_TASK_MODEL.name;
+    pub const CLAUDE_CODE_API_KEY_MODE: &str = env_registry::CLAUDE_CODE_API_KEY_MODE.name;
+    pub const CLAUDE_CODE_MODEL: &str = env_registry::CLAUDE_CODE_MODEL.name;
 }
 
 /// Allowlist of editable configuration keys
@@ -87,7 +90,7 @@ impl EnvCache {
         let mut values = HashMap::new();
 
         for &key in ALLOWED_CONFIG_KEYS {
-            values.insert(key.to_string(), std::env::var(key).ok());
+            values.insert(key.to_string(), env_registry::read(key).ok());
         }
 
         Self { values }
@@ -110,7 +113,7 @@ impl EnvCache {
 
 /// Get or initialize the environment cache
 /// Note: This cache is a snapshot at initialization time.
-/// For runtime updates, use `refresh_env_cache()` or direct `std::env::var()` calls.
+/// For runtime updates, read the variable through `env_registry` instead.
 pub fn get_env_cache() -> &'static EnvCache {
     ENV_CACHE.get_or_init(EnvCache::new)
 }
@@ -118,28 +121,31 @@ pub fn get_env_cache() -> &'static EnvCache {
 /// Batch load multiple environment variables efficiently
 pub fn load_env_vars(keys: &[&str]) -> HashMap<String, Option<String>> {
     keys.iter()
-        .map(|&key| (key.to_string(), std::env::var(key).ok()))
+        .map(|&key| (key.to_string(), env_registry::read(key).ok()))
         .collect()
 }
 
 /// Load a single environment variable with error context
 pub fn load_env_var(key: &str) -> Result<String, AppError> {
-    std::env::var(key).map_err(|_| AppError::Config(ConfigError::MissingEnvVar(key.to_string())))
+    env_registry::read(key)
+        .map_err(|_| AppError::Config(ConfigError::MissingEnvVar(key.to_string())))
 }
 
 /// Load an optional environment variable
 pub fn load_env_var_opt(key: &str) -> Option<String> {
-    std::env::var(key).ok().filter(|s| !s.is_empty())
+    env_registry::read(key).ok().filter(|s| !s.is_empty())
 }
 
 /// Determine the active AI provider based on configured API keys
 pub fn determine_active_provider() -> String {
-    if std::env::var(env_keys::ANTHROPIC_API_KEY)
+    if env_registry::ANTHROPIC_API_KEY
+        .var()
         .map(|k| !k.is_empty())
         .unwrap_or(false)
     {
         "Anthropic".to_string()
-    } else if std::env::var(env_keys::OPENAI_API_KEY)
+    } else if env_registry::OPENAI_API_KEY
+        .var()
         .map(|k| !k.is_empty())
         .unwrap_or(false)
     {
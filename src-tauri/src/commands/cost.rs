@@ -1,6 +1,7 @@
 // Cost tracking related Tauri commands
 
 use crate::agent_runs_db::{CostSummary, DailyCost, DateRangeCostSummary};
+use crate::cost_preference::{self, OffloadStats};
 use crate::AppState;
 
 #[tauri::command]
@@ -82,3 +83,11 @@ pub async fn get_runs_today_cost(state: tauri::State<'_, AppState>) -> Result<f6
         .await
         .map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+pub async fn get_offload_stats(
+    days: i64,
+    state: tauri::State<'_, AppState>,
+) -> Result<OffloadStats, String> {
+    cost_preference::offload_stats(&state.agent_runs_db, days).await
+}
@@ -1,6 +1,10 @@
 // Database/Runs related Tauri commands
 
-use crate::agent_runs_db::{AgentRun, DatabaseStats, RunQueryFilters, RunStats, RunStatus};
+use crate::agent_runs_db::{
+    AgentRun, AgentTurn, DatabaseStats, DeadLetterOutcome, Page, PageCursor, PromptCostRecord,
+    RunQueryFilters, RunStats, RunStatus, TrashItem, TrashKind, TrashPurgeResult, TrendBucket,
+    UsageTrends,
+};
 use crate::types::AgentSource;
 use crate::AppState;
 
@@ -26,6 +30,27 @@ pub async fn get_cost_database_stats(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_usage_trends(
+    days: i64,
+    bucket: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<UsageTrends, String> {
+    let bucket = TrendBucket::parse(bucket.as_deref().unwrap_or("day"))?;
+    state
+        .agent_runs_db
+        .get_usage_trends(days, bucket)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn flush_dead_letters(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DeadLetterOutcome>, String> {
+    Ok(state.agent_runs_db.flush_dead_letters().await)
+}
+
 #[tauri::command]
 pub async fn get_all_runs(state: tauri::State<'_, AppState>) -> Result<Vec<AgentRun>, String> {
     state
@@ -47,25 +72,19 @@ pub async fn get_run_by_id(
         .map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub async fn query_runs(
-    status: Option<String>,
-    working_dir: Option<String>,
-    source: Option<String>,
-    limit: Option<usize>,
-    offset: Option<usize>,
-    state: tauri::State<'_, AppState>,
-) -> Result<Vec<AgentRun>, String> {
-    let run_status = status.and_then(|s| match s.as_str() {
+fn parse_run_status(s: &str) -> Option<RunStatus> {
+    match s {
         "running" => Some(RunStatus::Running),
         "completed" => Some(RunStatus::Completed),
         "stopped" => Some(RunStatus::Stopped),
         "crashed" => Some(RunStatus::Crashed),
         "waiting_input" => Some(RunStatus::WaitingInput),
         _ => None,
-    });
+    }
+}
 
-    let agent_source = source.and_then(|s| match s.as_str() {
+fn parse_agent_source(s: &str) -> Option<AgentSource> {
+    match s {
         "ui" => Some(AgentSource::UI),
         "meta" => Some(AgentSource::Meta),
         "pipeline" => Some(AgentSource::Pipeline),
@@ -73,16 +92,37 @@ pub async fn query_runs(
         "manual" => Some(AgentSource::Manual),
         "testwizard" => Some(AgentSource::TestWizard),
         _ => None,
-    });
+    }
+}
+
+/// Query runs with limit/offset paging
+///
+/// Deprecated: offsets skip or repeat runs that start while paging.
+/// Use `query_runs_page` instead.
+#[tauri::command]
+pub async fn query_runs(
+    status: Option<String>,
+    working_dir: Option<String>,
+    source: Option<String>,
+    workspace_id: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentRun>, String> {
+    let run_status = status.as_deref().and_then(parse_run_status);
+    let agent_source = source.as_deref().and_then(parse_agent_source);
 
     let filters = RunQueryFilters {
         status: run_status,
         working_dir,
+        working_dir_prefix: None,
         source: agent_source,
+        workspace_id,
         date_from: None,
         date_to: None,
         limit,
         offset,
+        before: None,
     };
 
     state
@@ -92,6 +132,40 @@ pub async fn query_runs(
         .map_err(|e| e.to_string())
 }
 
+/// Query one page of runs with keyset pagination
+///
+/// Pass the returned `next_cursor` as `before` to fetch the following page.
+/// Unlike `query_runs` with `offset`, pages stay stable while new runs start.
+#[tauri::command]
+pub async fn query_runs_page(
+    status: Option<String>,
+    working_dir: Option<String>,
+    source: Option<String>,
+    workspace_id: Option<String>,
+    limit: Option<usize>,
+    before: Option<PageCursor>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Page<AgentRun>, String> {
+    let filters = RunQueryFilters {
+        status: status.as_deref().and_then(parse_run_status),
+        working_dir,
+        working_dir_prefix: None,
+        source: source.as_deref().and_then(parse_agent_source),
+        workspace_id,
+        date_from: None,
+        date_to: None,
+        limit: limit.or(Some(50)),
+        offset: None,
+        before,
+    };
+
+    state
+        .agent_runs_db
+        .query_runs_page(filters)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_resumable_runs(
     state: tauri::State<'_, AppState>,
@@ -115,6 +189,30 @@ pub async fn get_run_prompts(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_prompt_costs(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PromptCostRecord>, String> {
+    state
+        .agent_runs_db
+        .get_prompt_costs(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_agent_turns(
+    agent_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentTurn>, String> {
+    state
+        .agent_runs_db
+        .get_agent_turns(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_run_stats(state: tauri::State<'_, AppState>) -> Result<RunStats, String> {
     state
@@ -136,6 +234,73 @@ pub async fn cleanup_old_runs(
         .map_err(|e| e.to_string())
 }
 
+/// Move a finished run to the trash
+#[tauri::command]
+pub async fn delete_run(agent_id: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let run = state
+        .agent_runs_db
+        .get_run(&agent_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+
+    if matches!(run.status, RunStatus::Running | RunStatus::WaitingInput) {
+        return Err("Stop the agent before deleting its run".to_string());
+    }
+
+    state
+        .agent_runs_db
+        .delete_run(&agent_id)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_trash(
+    kind: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TrashItem>, String> {
+    let kind = kind.as_deref().map(TrashKind::parse).transpose()?;
+    state
+        .agent_runs_db
+        .list_trash(kind)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_from_trash(
+    kind: String,
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let kind = TrashKind::parse(&kind)?;
+    let restored = state
+        .agent_runs_db
+        .restore_from_trash(kind, &id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if restored {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not in the trash", id))
+    }
+}
+
+/// Permanently delete trashed items; without `older_than_days` the whole trash is emptied
+#[tauri::command]
+pub async fn empty_trash(
+    older_than_days: Option<i64>,
+    state: tauri::State<'_, AppState>,
+) -> Result<TrashPurgeResult, String> {
+    state
+        .agent_runs_db
+        .empty_trash(older_than_days.unwrap_or(0))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Force reconciliation of stale runs (manual cleanup)
 /// Marks any "running" or "waiting_input" agents as "crashed"
 #[tauri::command]
@@ -144,5 +309,6 @@ pub async fn reconcile_stale_runs(state: tauri::State<'_, AppState>) -> Result<u
         .agent_runs_db
         .reconcile_stale_runs()
         .await
+        .map(|agent_ids| agent_ids.len())
         .map_err(|e| e.to_string())
 }
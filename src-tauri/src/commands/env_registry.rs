@@ -0,0 +1,1077 @@
+// Environment variable registry
+//
+// Every environment variable the app reads is declared here with its group,
+// default and description, and code reads it through that declaration
+// (`env_registry::PRIMARY_MODEL.var()`) instead of calling std::env::var with
+// a name. The list is therefore complete by construction: .env.example is
+// generated from it (`env_template`), `validate` checks a loaded environment
+// against it, and a test fails if a direct read by name is added elsewhere.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env::VarError;
+
+use crate::ai_client::AIClient;
+
+/// What values a variable takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvKind {
+    Text,
+    /// API keys and tokens; never echoed back
+    Secret,
+    /// A model name; the provider is inferred from it
+    Model,
+    /// true/false (also 1/0, yes/no)
+    Flag,
+    /// A whole number, zero or more
+    Integer,
+    /// A decimal number, zero or more
+    Number,
+    /// One of a fixed set of values, case-insensitive
+    Choice(&'static [&'static str]),
+}
+
+/// A declared environment variable
+#[derive(Debug, Clone, Copy)]
+pub struct EnvVar {
+    pub name: &'static str,
+    /// Heading the variable is listed under in the template
+    pub group: &'static str,
+    pub kind: EnvKind,
+    /// Value used when unset, as shown in the template
+    pub default: Option<&'static str>,
+    /// Template comment; may span several lines
+    pub description: &'static str,
+    /// Other names read for the same setting, in order
+    pub aliases: &'static [&'static str],
+    /// Old names still read, but reported by validation
+    pub deprecated_names: &'static [&'static str],
+    /// Written uncommented with this value in the template
+    pub template_value: Option<&'static str>,
+}
+
+const fn var(
+    name: &'static str,
+    group: &'static str,
+    kind: EnvKind,
+    default: Option<&'static str>,
+    description: &'static str,
+) -> EnvVar {
+    EnvVar {
+        name,
+        group,
+        kind,
+        default,
+        description,
+        aliases: &[],
+        deprecated_names: &[],
+        template_value: None,
+    }
+}
+
+impl EnvVar {
+    const fn with_aliases(self, aliases: &'static [&'static str]) -> Self {
+        let mut var = self;
+        var.aliases = aliases;
+        var
+    }
+
+    const fn with_deprecated_names(self, names: &'static [&'static str]) -> Self {
+        let mut var = self;
+        var.deprecated_names = names;
+        var
+    }
+
+    const fn in_template(self, value: &'static str) -> Self {
+        let mut var = self;
+        var.template_value = Some(value);
+        var
+    }
+
+    /// Read the variable, falling back to its aliases and deprecated names
+    pub fn var(&self) -> Result<String, VarError> {
+        let mut result = std::env::var(self.name);
+        for name in self.aliases.iter().chain(self.deprecated_names) {
+            if result.is_ok() {
+                break;
+            }
+            result = std::env::var(name);
+        }
+        result
+    }
+
+    /// Whether the variable is set to 1, true or yes
+    pub fn flag(&self) -> bool {
+        self.var().map(|v| is_truthy(&v)).unwrap_or(false)
+    }
+
+    /// Every name this variable is read under
+    fn names(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name)
+            .chain(self.aliases.iter().copied())
+            .chain(self.deprecated_names.iter().copied())
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes")
+}
+
+fn is_falsy(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "0" | "false" | "no" | "off"
+    )
+}
+
+const API_KEYS: &str = "API keys";
+const MODELS: &str = "Models";
+const WORKERS: &str = "Claude Code workers";
+const REQUESTS: &str = "AI requests";
+const COMMANDER: &str = "Commander";
+const PIPELINES: &str = "Pipelines";
+const COST: &str = "Cost";
+const HISTORY: &str = "History";
+const METRICS: &str = "Metrics";
+
+pub const ANTHROPIC_API_KEY: EnvVar = var(
+    "ANTHROPIC_API_KEY",
+    API_KEYS,
+    EnvKind::Secret,
+    None,
+    "Anthropic API key for the commander, pipelines and light tasks.\n\
+     Set this, OPENAI_API_KEY, or both.",
+)
+.in_template("your_anthropic_api_key_here");
+
+pub const OPENAI_API_KEY: EnvVar = var(
+    "OPENAI_API_KEY",
+    API_KEYS,
+    EnvKind::Secret,
+    None,
+    "OpenAI API key, needed for gpt-*/o1-*/o3-* models and voice",
+);
+
+pub const GITHUB_TOKEN: EnvVar = var(
+    "GITHUB_TOKEN",
+    API_KEYS,
+    EnvKind::Secret,
+    None,
+    "GitHub token for repository context (optional)",
+);
+
+pub const PRIMARY_MODEL: EnvVar = var(
+    "PRIMARY_MODEL",
+    MODELS,
+    EnvKind::Model,
+    Some("claude-sonnet-4-5-20250929"),
+    "Model for the commander. The provider is inferred from the name:\n\
+     gpt-*, o1-*, o3-* use OpenAI; claude-* and aliases (sonnet, opus, haiku) use Anthropic.",
+)
+.in_template("claude-sonnet-4-5-20250929");
+
+pub const SECURITY_MODEL: EnvVar = var(
+    "SECURITY_MODEL",
+    MODELS,
+    EnvKind::Model,
+    None,
+    "Model for security analysis (defaults to the main Claude model)",
+);
+
+pub const LIGHT_TASK_MODEL: EnvVar = var(
+    "LIGHT_TASK_MODEL",
+    MODELS,
+    EnvKind::Model,
+    Some("claude-haiku-4-5"),
+    "Model for light tasks such as titles, labels and summaries",
+);
+
+pub const OPENAI_ORCHESTRATOR_MODEL: EnvVar = var(
+    "OPENAI_ORCHESTRATOR_MODEL",
+    MODELS,
+    EnvKind::Model,
+    Some("gpt-4o"),
+    "OpenAI model for the pipeline orchestrator, which uses OpenAI whenever\n\
+     OPENAI_API_KEY is set",
+)
+.with_deprecated_names(&["OPENAI_MODEL"]);
+
+pub const OPENAI_REALTIME_MODEL: EnvVar = var(
+    "OPENAI_REALTIME_MODEL",
+    MODELS,
+    EnvKind::Model,
+    None,
+    "OpenAI realtime model for voice (the default depends on the voice mode)",
+);
+
+pub const CLAUDE_CODE_MODEL: EnvVar = var(
+    "CLAUDE_CODE_MODEL",
+    WORKERS,
+    EnvKind::Model,
+    Some("auto"),
+    "Model for Claude Code workers: auto (Claude Code's default), sonnet, opus, haiku\n\
+     or a full model name",
+);
+
+pub const CLAUDE_CODE_API_KEY_MODE: EnvVar = var(
+    "CLAUDE_CODE_API_KEY_MODE",
+    WORKERS,
+    EnvKind::Choice(&["blocked", "passthrough"]),
+    Some("blocked"),
+    "Whether API keys are passed to Claude Code workers:\n\
+     - blocked = workers use OAuth (your Claude subscription)\n\
+     - passthrough = workers use the API key",
+)
+.in_template("blocked");
+
+pub const CLAUDE_PATH: EnvVar = var(
+    "CLAUDE_PATH",
+    WORKERS,
+    EnvKind::Text,
+    None,
+    "Path to the claude CLI, if it isn't found automatically",
+);
+
+pub const WORKER_MCP_CONFIG: EnvVar = var(
+    "WORKER_MCP_CONFIG",
+    WORKERS,
+    EnvKind::Text,
+    None,
+    "Path to an MCP config file (claude --mcp-config format) with servers every\n\
+     worker agent gets, in addition to any given when it is created",
+);
+
+pub const AGENT_STOP_GRACE_MS: EnvVar = var(
+    "AGENT_STOP_GRACE_MS",
+    WORKERS,
+    EnvKind::Integer,
+    Some("2000"),
+    "Grace period (ms) after an agent's turn ends before it is marked as waiting for input.\n\
+     Output or a new tool call within this window cancels the transition.",
+);
+
+pub const AUTO_FOLLOW_UP_DISABLED: EnvVar = var(
+    "AUTO_FOLLOW_UP_DISABLED",
+    WORKERS,
+    EnvKind::Flag,
+    Some("false"),
+    "Kill switch for auto follow-ups (the commander answering \"shall I proceed?\"\n\
+     for agents opted in at creation)",
+);
+
+pub const AGENT_MESSAGE_LIMIT: EnvVar = var(
+    "AGENT_MESSAGE_LIMIT",
+    WORKERS,
+    EnvKind::Integer,
+    Some("10"),
+    "Messages agents may relay to each other (SendMessageToAgent) per pipeline,\n\
+     or per agent pair outside pipelines",
+);
+
+pub const AGENT_OUTPUT_BUFFER_MB: EnvVar = var(
+    "AGENT_OUTPUT_BUFFER_MB",
+    WORKERS,
+    EnvKind::Integer,
+    Some("8"),
+    "Output kept in memory per agent, in MB (everything stays in the runs database)",
+);
+
+pub const AGENT_OUTPUT_BUFFER_SIZE: EnvVar = var(
+    "AGENT_OUTPUT_BUFFER_SIZE",
+    WORKERS,
+    EnvKind::Integer,
+    Some("100"),
+    "Outputs kept in memory per agent (older ones are read from the runs database)",
+);
+
+pub const AGENT_STOP_TIMEOUT_SECS: EnvVar = var(
+    "AGENT_STOP_TIMEOUT_SECS",
+    WORKERS,
+    EnvKind::Integer,
+    Some("10"),
+    "Seconds a stopped agent gets to finish writing and exit after its input is\n\
+     closed, before it is killed (0 kills at once)",
+);
+
+pub const AGENT_IDLE_TIMEOUT_MINUTES: EnvVar = var(
+    "AGENT_IDLE_TIMEOUT_MINUTES",
+    WORKERS,
+    EnvKind::Integer,
+    Some("30"),
+    "Minutes an agent may wait for input before it is stopped and its cost\n\
+     recorded (0 never stops it)",
+);
+
+pub const AGENT_IDLE_TIMEOUT_BY_SOURCE: EnvVar = var(
+    "AGENT_IDLE_TIMEOUT_BY_SOURCE",
+    WORKERS,
+    EnvKind::Text,
+    None,
+    "Idle timeouts in minutes for agents by source, overriding the above,\n\
+     comma-separated, e.g. pipeline=10,pool=60,ui=0",
+);
+
+pub const AGENT_OUTPUT_ANSI: EnvVar = var(
+    "AGENT_OUTPUT_ANSI",
+    WORKERS,
+    EnvKind::Choice(&["html", "strip", "keep"]),
+    Some("html"),
+    "ANSI color codes in agent output (compiler errors, test runners):\n\
+     - html = removed from stored output; the UI shows the colors\n\
+     - strip = removed everywhere\n\
+     - keep = stored and shown as they arrive",
+);
+
+pub const PROMPT_FILE_THRESHOLD_KB: EnvVar = var(
+    "PROMPT_FILE_THRESHOLD_KB",
+    WORKERS,
+    EnvKind::Integer,
+    Some("64"),
+    "Prompts larger than this many KB are written to a file in the agent's working\n\
+     directory and the agent is asked to read it (0 always sends them directly)",
+);
+
+pub const MEMORY_WARN_MB: EnvVar = var(
+    "MEMORY_WARN_MB",
+    WORKERS,
+    EnvKind::Integer,
+    Some("512"),
+    "Log a warning when output buffers and conversations together pass this many MB",
+);
+
+pub const ANTHROPIC_PROMPT_CACHING: EnvVar = var(
+    "ANTHROPIC_PROMPT_CACHING",
+    REQUESTS,
+    EnvKind::Flag,
+    Some("true"),
+    "Mark the system prompt, memory and tool definitions as prompt cache points\n\
+     on Claude requests (ignored for OpenAI)",
+);
+
+pub const HTTP_CONNECT_TIMEOUT_SECS: EnvVar = var(
+    "HTTP_CONNECT_TIMEOUT_SECS",
+    REQUESTS,
+    EnvKind::Integer,
+    Some("10"),
+    "Seconds allowed to connect, for the HTTP client shared by the AI providers",
+);
+
+pub const HTTP_REQUEST_TIMEOUT_SECS: EnvVar = var(
+    "HTTP_REQUEST_TIMEOUT_SECS",
+    REQUESTS,
+    EnvKind::Integer,
+    Some("180"),
+    "Seconds allowed for a whole request",
+);
+
+pub const ORCHESTRATOR_REQUEST_TIMEOUT_SECS: EnvVar = var(
+    "ORCHESTRATOR_REQUEST_TIMEOUT_SECS",
+    REQUESTS,
+    EnvKind::Integer,
+    Some("600"),
+    "Request timeout for pipeline orchestrator turns, which can take longer",
+);
+
+pub const HTTPS_PROXY: EnvVar = var(
+    "HTTPS_PROXY",
+    REQUESTS,
+    EnvKind::Text,
+    None,
+    "Proxy for HTTPS requests (https_proxy is read too)",
+)
+.with_aliases(&["https_proxy"]);
+
+pub const CHAT_BUSY_MODE: EnvVar = var(
+    "CHAT_BUSY_MODE",
+    COMMANDER,
+    EnvKind::Choice(&["queue", "reject"]),
+    Some("queue"),
+    "Chat messages sent while the commander is still answering:\n\
+     - queue = answer them in order once the current turn ends\n\
+     - reject = fail them with a turn_in_progress error",
+);
+
+pub const NOTIFY_MUTE: EnvVar = var(
+    "NOTIFY_MUTE",
+    COMMANDER,
+    EnvKind::Text,
+    None,
+    "OS notifications to leave out, comma-separated (or all):\n\
+     pipeline_review, question, elevated_command",
+);
+
+pub const NOTIFY_QUIET_HOURS: EnvVar = var(
+    "NOTIFY_QUIET_HOURS",
+    COMMANDER,
+    EnvKind::Text,
+    None,
+    "Local time span without OS notifications, e.g. 22:00-07:00",
+);
+
+pub const PIPELINE_VERIFY_BUILD_OUTPUT: EnvVar = var(
+    "PIPELINE_VERIFY_BUILD_OUTPUT",
+    PIPELINES,
+    EnvKind::Flag,
+    Some("false"),
+    "Give verification agents the raw build output instead of the git diff",
+);
+
+pub const PIPELINE_TOOL_ERROR_WINDOW: EnvVar = var(
+    "PIPELINE_TOOL_ERROR_WINDOW",
+    PIPELINES,
+    EnvKind::Integer,
+    Some("20"),
+    "Orchestrator tool calls the pipeline health error rate is taken over",
+);
+
+pub const PIPELINE_TOOL_ERROR_THRESHOLD: EnvVar = var(
+    "PIPELINE_TOOL_ERROR_THRESHOLD",
+    PIPELINES,
+    EnvKind::Number,
+    Some("0.5"),
+    "Share of failed orchestrator tool calls (0-1) at which a pipeline is marked\n\
+     degraded and its orchestrator is told (0 disables the check)",
+);
+
+pub const PIPELINE_RETENTION_MINUTES: EnvVar = var(
+    "PIPELINE_RETENTION_MINUTES",
+    PIPELINES,
+    EnvKind::Integer,
+    Some("60"),
+    "Minutes a finished pipeline stays in memory before it is saved to the database\n\
+     and evicted (0 keeps finished pipelines in memory)",
+);
+
+pub const GIT_EXCLUDE_GENERATED_FILES: EnvVar = var(
+    "GIT_EXCLUDE_GENERATED_FILES",
+    PIPELINES,
+    EnvKind::Flag,
+    Some("false"),
+    "Files the commander writes into working directories (skills, subagents, CLAUDE.md,\n\
+     scratchpads) are tracked in <working_dir>/.commander/manifest.json.\n\
+     Set to true to add them to .git/info/exclude so they stay out of git status.",
+);
+
+pub const GENERATED_FILES_ON_COMPLETE: EnvVar = var(
+    "GENERATED_FILES_ON_COMPLETE",
+    PIPELINES,
+    EnvKind::Choice(&["keep", "cleanup", "archive"]),
+    Some("keep"),
+    "What to do with a pipeline's generated files when it finishes:\n\
+     - keep = leave them in place\n\
+     - cleanup = delete them\n\
+     - archive = move them into .commander/<pipeline_id>/",
+);
+
+pub const DIRECTORY_LOCK_MODE: EnvVar = var(
+    "DIRECTORY_LOCK_MODE",
+    PIPELINES,
+    EnvKind::Choice(&["warn", "block"]),
+    Some("warn"),
+    "Agents created or prompted in a directory a running pipeline has locked:\n\
+     - warn = go ahead with a directory_lock:conflict event\n\
+     - block = fail, naming the pipeline",
+);
+
+pub const COST_ANOMALY_MULTIPLIER: EnvVar = var(
+    "COST_ANOMALY_MULTIPLIER",
+    COST,
+    EnvKind::Number,
+    Some("3"),
+    "Alert (cost:anomaly event) when spend in the last hour or 24 hours exceeds this\n\
+     multiple of its usual level (0 disables the check)",
+);
+
+pub const COST_ANOMALY_MIN_HOURLY_USD: EnvVar = var(
+    "COST_ANOMALY_MIN_HOURLY_USD",
+    COST,
+    EnvKind::Number,
+    Some("2"),
+    "Spend in the last hour below this never alerts",
+);
+
+pub const COST_ANOMALY_MIN_DAILY_USD: EnvVar = var(
+    "COST_ANOMALY_MIN_DAILY_USD",
+    COST,
+    EnvKind::Number,
+    Some("10"),
+    "Spend in the last 24 hours below this never alerts",
+);
+
+pub const COST_ANOMALY_NOTIFY: EnvVar = var(
+    "COST_ANOMALY_NOTIFY",
+    COST,
+    EnvKind::Flag,
+    Some("false"),
+    "Also show an OS notification for cost anomalies",
+);
+
+pub const COST_PREFERENCE: EnvVar = var(
+    "COST_PREFERENCE",
+    COST,
+    EnvKind::Choice(&["subscription_first", "latency_first"]),
+    Some("latency_first"),
+    "Where work goes when a worker agent or the API could do it: subscription_first\n\
+     leaves summaries and file inspection to workers (on the Claude subscription)\n\
+     while no usage limit is hit; latency_first always does it directly",
+);
+
+pub const API_USD_PER_MILLION_TOKENS: EnvVar = var(
+    "API_USD_PER_MILLION_TOKENS",
+    COST,
+    EnvKind::Number,
+    Some("3"),
+    "API input price used to estimate the spend avoided by offloading",
+);
+
+pub const TRASH_RETENTION_DAYS: EnvVar = var(
+    "TRASH_RETENTION_DAYS",
+    HISTORY,
+    EnvKind::Integer,
+    Some("30"),
+    "Days deleted runs and conversations stay in the trash before being purged\n\
+     (0 keeps them until the trash is emptied by hand)",
+);
+
+pub const MEMORY_HISTORY_SCAN: EnvVar = var(
+    "MEMORY_HISTORY_SCAN",
+    HISTORY,
+    EnvKind::Flag,
+    Some("true"),
+    "Have the memory worker read conversations that have gone quiet and store\n\
+     durable facts and preferences from them (light model, every 30 minutes)",
+);
+
+pub const MEMORY_HISTORY_SCAN_MAX_CHARS: EnvVar = var(
+    "MEMORY_HISTORY_SCAN_MAX_CHARS",
+    HISTORY,
+    EnvKind::Integer,
+    Some("20000"),
+    "Characters of conversation text the memory scan reads per cycle; the rest\n\
+     waits for the next cycle",
+);
+
+pub const SEARCH_CONVERSATION_MESSAGES: EnvVar = var(
+    "SEARCH_CONVERSATION_MESSAGES",
+    HISTORY,
+    EnvKind::Flag,
+    Some("true"),
+    "Let the Search tool match the messages of past conversations, not just their\n\
+     titles and previews",
+);
+
+pub const METRICS_ENABLED: EnvVar = var(
+    "METRICS_ENABLED",
+    METRICS,
+    EnvKind::Flag,
+    Some("false"),
+    "Serve Prometheus metrics at http://127.0.0.1:19832/metrics",
+);
+
+pub const HOOK_SERVER_TOKEN: EnvVar = var(
+    "HOOK_SERVER_TOKEN",
+    METRICS,
+    EnvKind::Secret,
+    None,
+    "Required as \"Authorization: Bearer <token>\" by /metrics (when set) and the\n\
+     /meta/question routes (refused while unset)",
+);
+
+/// Every variable the app reads, in template order
+pub const ALL: &[EnvVar] = &[
+    ANTHROPIC_API_KEY,
+    OPENAI_API_KEY,
+    GITHUB_TOKEN,
+    PRIMARY_MODEL,
+    SECURITY_MODEL,
+    LIGHT_TASK_MODEL,
+    OPENAI_ORCHESTRATOR_MODEL,
+    OPENAI_REALTIME_MODEL,
+    CLAUDE_CODE_MODEL,
+    CLAUDE_CODE_API_KEY_MODE,
+    CLAUDE_PATH,
+    WORKER_MCP_CONFIG,
+    AGENT_STOP_GRACE_MS,
+    AUTO_FOLLOW_UP_DISABLED,
+    AGENT_MESSAGE_LIMIT,
+    AGENT_OUTPUT_BUFFER_MB,
+    AGENT_OUTPUT_BUFFER_SIZE,
+    AGENT_STOP_TIMEOUT_SECS,
+    AGENT_IDLE_TIMEOUT_MINUTES,
+    AGENT_IDLE_TIMEOUT_BY_SOURCE,
+    AGENT_OUTPUT_ANSI,
+    PROMPT_FILE_THRESHOLD_KB,
+    MEMORY_WARN_MB,
+    ANTHROPIC_PROMPT_CACHING,
+    HTTP_CONNECT_TIMEOUT_SECS,
+    HTTP_REQUEST_TIMEOUT_SECS,
+    ORCHESTRATOR_REQUEST_TIMEOUT_SECS,
+    HTTPS_PROXY,
+    CHAT_BUSY_MODE,
+    NOTIFY_MUTE,
+    NOTIFY_QUIET_HOURS,
+    PIPELINE_VERIFY_BUILD_OUTPUT,
+    PIPELINE_TOOL_ERROR_WINDOW,
+    PIPELINE_TOOL_ERROR_THRESHOLD,
+    PIPELINE_RETENTION_MINUTES,
+    GIT_EXCLUDE_GENERATED_FILES,
+    GENERATED_FILES_ON_COMPLETE,
+    DIRECTORY_LOCK_MODE,
+    COST_ANOMALY_MULTIPLIER,
+    COST_ANOMALY_MIN_HOURLY_USD,
+    COST_ANOMALY_MIN_DAILY_USD,
+    COST_ANOMALY_NOTIFY,
+    COST_PREFERENCE,
+    API_USD_PER_MILLION_TOKENS,
+    TRASH_RETENTION_DAYS,
+    MEMORY_HISTORY_SCAN,
+    MEMORY_HISTORY_SCAN_MAX_CHARS,
+    SEARCH_CONVERSATION_MESSAGES,
+    METRICS_ENABLED,
+    HOOK_SERVER_TOKEN,
+];
+
+/// OS variables read directly; they aren't configuration
+pub const PLATFORM_VARS: &[&str] = &["PATH", "HOME", "APPDATA", "ProgramFiles"];
+
+/// Common variables close enough to a registered name to look like typos
+const NOT_MISSPELLINGS: &[&str] = &["HTTP_PROXY", "NO_PROXY", "ALL_PROXY"];
+
+/// Look up a registered variable by name
+pub fn find(name: &str) -> Option<&'static EnvVar> {
+    ALL.iter().find(|v| v.name == name)
+}
+
+/// Read a registered variable by name. Unregistered names read as unset.
+pub fn read(name: &str) -> Result<String, VarError> {
+    match find(name) {
+        Some(var) => var.var(),
+        None => {
+            eprintln!("[Config] {} is not in the environment registry", name);
+            Err(VarError::NotPresent)
+        }
+    }
+}
+
+/// A commented .env file listing every variable with its default
+pub fn env_template() -> String {
+    let mut out = String::from(
+        "# Claude Commander configuration\n\
+         #\n\
+         # Generated from the variables the app reads. Commented lines show the\n\
+         # default; uncomment and edit the ones you want to change.\n",
+    );
+    let mut group = "";
+    for var in ALL {
+        if var.group != group {
+            group = var.group;
+            out.push_str(&format!("\n# ==== {} ====\n", group));
+        }
+        out.push('\n');
+        for line in var.description.lines() {
+            out.push_str(&format!("# {}\n", line.trim()));
+        }
+        match var.template_value {
+            Some(value) => out.push_str(&format!("{}={}\n", var.name, value)),
+            None => out.push_str(&format!("# {}={}\n", var.name, var.default.unwrap_or(""))),
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// The setting is ignored or the app can't work as configured
+    Error,
+    Warning,
+}
+
+/// A problem found in the configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigIssue {
+    pub severity: IssueSeverity,
+    pub variable: String,
+    pub message: String,
+}
+
+fn issue(severity: IssueSeverity, variable: &str, message: String) -> ConfigIssue {
+    ConfigIssue {
+        severity,
+        variable: variable.to_string(),
+        message,
+    }
+}
+
+/// Value of a registered variable in `env`, following aliases and deprecated
+/// names like `EnvVar::var` (empty values count as unset)
+fn lookup<'a>(env: &'a HashMap<String, String>, var: &EnvVar) -> Option<&'a str> {
+    var.names()
+        .filter_map(|name| env.get(name))
+        .map(|v| v.trim())
+        .find(|v| !v.is_empty())
+}
+
+/// Check an environment against the registry
+///
+/// `env` is the loaded environment; `file_keys` are the names set in the .env
+/// files, which are all expected to be registered.
+pub fn validate(env: &HashMap<String, String>, file_keys: &[String]) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    check_unknown_names(env, file_keys, &mut issues);
+    for var in ALL {
+        check_deprecated_names(env, var, &mut issues);
+        if let Some(value) = lookup(env, var) {
+            if let Some(message) = invalid_value(var, value) {
+                issues.push(issue(IssueSeverity::Error, var.name, message));
+            }
+        }
+    }
+    check_consistency(env, &mut issues);
+    issues
+}
+
+fn check_unknown_names(
+    env: &HashMap<String, String>,
+    file_keys: &[String],
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let known = |name: &str| {
+        ALL.iter().any(|v| v.names().any(|n| n == name)) || PLATFORM_VARS.contains(&name)
+    };
+
+    let mut reported: Vec<&str> = Vec::new();
+    for name in file_keys {
+        if known(name) || reported.contains(&name.as_str()) {
+            continue;
+        }
+        let message = match closest_name(name) {
+            Some(close) => format!("{} is not a known setting. Did you mean {}?", name, close),
+            None => format!("{} is not a setting Claude Commander reads", name),
+        };
+        issues.push(issue(IssueSeverity::Warning, name, message));
+        reported.push(name.as_str());
+    }
+
+    // Other variables come from the shell, so only likely typos are reported
+    let mut names: Vec<&String> = env.keys().collect();
+    names.sort();
+    for name in names {
+        if known(name) || reported.contains(&name.as_str()) {
+            continue;
+        }
+        if NOT_MISSPELLINGS
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+        if let Some(close) = closest_name(name) {
+            issues.push(issue(
+                IssueSeverity::Warning,
+                name,
+                format!(
+                    "{} looks like a misspelling of {}, which isn't set",
+                    name, close
+                ),
+            ));
+        }
+    }
+}
+
+/// The registered name within two edits of `name` (ignoring case), if any
+fn closest_name(name: &str) -> Option<&'static str> {
+    let upper = name.to_uppercase();
+    ALL.iter()
+        .map(|v| (v.name, edit_distance(&upper, v.name)))
+        .filter(|(_, distance)| *distance <= 2)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(name, _)| name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(current)
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+fn check_deprecated_names(
+    env: &HashMap<String, String>,
+    var: &EnvVar,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    for old in var.deprecated_names {
+        if !env.contains_key(*old) {
+            continue;
+        }
+        let message = if env.contains_key(var.name) {
+            format!(
+                "{} is deprecated and ignored because {} is set; remove it",
+                old, var.name
+            )
+        } else {
+            format!("{} is deprecated; rename it to {}", old, var.name)
+        };
+        issues.push(issue(IssueSeverity::Warning, old, message));
+    }
+}
+
+/// Why a value isn't accepted, if it isn't
+fn invalid_value(var: &EnvVar, value: &str) -> Option<String> {
+    let default = var
+        .default
+        .map(|d| format!(", so the default ({}) is used", d))
+        .unwrap_or_default();
+    match var.kind {
+        EnvKind::Flag if !is_truthy(value) && !is_falsy(value) => Some(format!(
+            "{}={} is not true or false{}",
+            var.name, value, default
+        )),
+        EnvKind::Integer if value.parse::<u64>().is_err() => Some(format!(
+            "{}={} is not a whole number{}",
+            var.name, value, default
+        )),
+        EnvKind::Number
+            if !value
+                .parse::<f64>()
+                .is_ok_and(|n| n.is_finite() && n >= 0.0) =>
+        {
+            Some(format!("{}={} is not a number{}", var.name, value, default))
+        }
+        EnvKind::Choice(choices) if !choices.contains(&value.to_lowercase().as_str()) => {
+            Some(format!(
+                "{}={} is not one of {}{}",
+                var.name,
+                value,
+                choices.join(", "),
+                default
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn check_consistency(env: &HashMap<String, String>, issues: &mut Vec<ConfigIssue>) {
+    let has_anthropic = lookup(env, &ANTHROPIC_API_KEY).is_some();
+    let has_openai = lookup(env, &OPENAI_API_KEY).is_some();
+
+    if !has_anthropic && !has_openai {
+        issues.push(issue(
+            IssueSeverity::Error,
+            ANTHROPIC_API_KEY.name,
+            "No API key is set. The commander needs ANTHROPIC_API_KEY or OPENAI_API_KEY."
+                .to_string(),
+        ));
+    } else {
+        for var in [&PRIMARY_MODEL, &SECURITY_MODEL, &LIGHT_TASK_MODEL] {
+            let Some(model) = lookup(env, var) else {
+                continue;
+            };
+            if AIClient::is_openai_model(model) && !has_openai {
+                issues.push(issue(
+                    IssueSeverity::Error,
+                    var.name,
+                    format!(
+                        "{}={} is an OpenAI model but OPENAI_API_KEY is not set",
+                        var.name, model
+                    ),
+                ));
+            } else if !AIClient::is_openai_model(model) && !has_anthropic {
+                issues.push(issue(
+                    IssueSeverity::Warning,
+                    var.name,
+                    format!(
+                        "{}={} is a Claude model but ANTHROPIC_API_KEY is not set; \
+                         OpenAI is used instead",
+                        var.name, model
+                    ),
+                ));
+            }
+        }
+    }
+
+    for var in [&OPENAI_ORCHESTRATOR_MODEL, &OPENAI_REALTIME_MODEL] {
+        if lookup(env, var).is_some() && !has_openai {
+            issues.push(issue(
+                IssueSeverity::Warning,
+                var.name,
+                format!("{} has no effect without OPENAI_API_KEY", var.name),
+            ));
+        }
+    }
+
+    let passthrough = lookup(env, &CLAUDE_CODE_API_KEY_MODE)
+        .is_some_and(|mode| mode.eq_ignore_ascii_case("passthrough"));
+    if passthrough && !has_anthropic {
+        issues.push(issue(
+            IssueSeverity::Warning,
+            CLAUDE_CODE_API_KEY_MODE.name,
+            "CLAUDE_CODE_API_KEY_MODE=passthrough but ANTHROPIC_API_KEY is not set, \
+             so workers have no key to use"
+                .to_string(),
+        ));
+    }
+    let subscription_first = lookup(env, &COST_PREFERENCE)
+        .is_some_and(|pref| pref.eq_ignore_ascii_case("subscription_first"));
+    if subscription_first && passthrough {
+        issues.push(issue(
+            IssueSeverity::Warning,
+            COST_PREFERENCE.name,
+            "COST_PREFERENCE=subscription_first offloads work to workers, but with \
+             CLAUDE_CODE_API_KEY_MODE=passthrough workers are billed to the API too"
+                .to_string(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn flagged(issues: &[ConfigIssue]) -> Vec<&str> {
+        issues.iter().map(|i| i.variable.as_str()).collect()
+    }
+
+    #[test]
+    fn test_env_example_matches_registry() {
+        let example = include_str!("../../../.env.example");
+        assert_eq!(
+            example,
+            env_template(),
+            ".env.example is out of date; regenerate it with generate_env_template"
+        );
+    }
+
+    #[test]
+    fn test_template_lists_every_variable_once() {
+        let template = env_template();
+        for var in ALL {
+            let lines = template
+                .lines()
+                .filter(|l| {
+                    l.trim_start_matches("# ")
+                        .starts_with(&format!("{}=", var.name))
+                })
+                .count();
+            assert_eq!(lines, 1, "{} should appear once", var.name);
+        }
+    }
+
+    #[test]
+    fn test_env_is_only_read_through_the_registry() {
+        fn visit(dir: &std::path::Path, offenders: &mut Vec<String>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(&path, offenders);
+                    continue;
+                }
+                if path.extension().and_then(|e| e.to_str()) != Some("rs")
+                    || path.ends_with("env_registry.rs")
+                {
+                    continue;
+                }
+                let source = std::fs::read_to_string(&path).unwrap();
+                for call in ["env::var(", "env::var_os("] {
+                    for (pos, _) in source.match_indices(call) {
+                        let arg = &source[pos + call.len()..];
+                        let platform = PLATFORM_VARS
+                            .iter()
+                            .any(|name| arg.starts_with(&format!("\"{}\"", name)));
+                        if !platform {
+                            let line = source[..pos].lines().count();
+                            offenders.push(format!("{}:{}", path.display(), line));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut offenders = Vec::new();
+        visit(
+            &std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut offenders,
+        );
+        assert!(
+            offenders.is_empty(),
+            "read these through env_registry instead: {:?}",
+            offenders
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_misspelled_names() {
+        let loaded = env(&[
+            ("ANTHROPIC_API_KEY", "sk-ant"),
+            ("PRIMARY_MODLE", "claude-opus-4-5"),
+            ("MY_OWN_SETTING", "1"),
+            ("COST_PREFERANCE", "subscription_first"),
+            ("HTTP_PROXY", "http://proxy"),
+            ("TERM", "xterm"),
+        ]);
+        let file_keys = vec!["PRIMARY_MODLE".to_string(), "MY_OWN_SETTING".to_string()];
+        let issues = validate(&loaded, &file_keys);
+
+        assert_eq!(
+            flagged(&issues),
+            vec!["PRIMARY_MODLE", "MY_OWN_SETTING", "COST_PREFERANCE"]
+        );
+        assert!(issues[0].message.contains("Did you mean PRIMARY_MODEL?"));
+        assert!(issues[2].message.contains("COST_PREFERENCE"));
+    }
+
+    #[test]
+    fn test_inconsistent_and_invalid_settings() {
+        let issues = validate(
+            &env(&[
+                ("ANTHROPIC_API_KEY", "sk-ant"),
+                ("PRIMARY_MODEL", "gpt-4o"),
+                ("CHAT_BUSY_MODE", "drop"),
+                ("AGENT_MESSAGE_LIMIT", "ten"),
+                ("METRICS_ENABLED", "TRUE"),
+            ]),
+            &[],
+        );
+        assert_eq!(
+            flagged(&issues),
+            vec!["AGENT_MESSAGE_LIMIT", "CHAT_BUSY_MODE", "PRIMARY_MODEL"]
+        );
+        assert!(issues.iter().all(|i| i.severity == IssueSeverity::Error));
+        assert!(issues[2].message.contains("OPENAI_API_KEY"));
+
+        let no_keys = validate(&env(&[]), &[]);
+        assert_eq!(flagged(&no_keys), vec!["ANTHROPIC_API_KEY"]);
+    }
+
+    #[test]
+    fn test_deprecated_name_is_read_and_reported() {
+        let issues = validate(
+            &env(&[("OPENAI_API_KEY", "sk"), ("OPENAI_MODEL", "gpt-4o-mini")]),
+            &["OPENAI_MODEL".to_string()],
+        );
+        assert_eq!(flagged(&issues), vec!["OPENAI_MODEL"]);
+        assert!(issues[0]
+            .message
+            .contains("rename it to OPENAI_ORCHESTRATOR_MODEL"));
+    }
+}
@@ -4,61 +4,66 @@
 // state changes, decisions) and agent outputs for the hybrid persistence model.
 
 use crate::agent_runs_db::{
-    AgentOutputRecord, EventQueryFilters, OrchestratorDecisionRecord,
-    OrchestratorStateChangeRecord, OrchestratorToolCallRecord, PipelineHistoryBundle,
+    AgentOutputRecord, BufferedEvent, EventQueryFilters, GeneratedArtifactRecord,
+    OrchestratorDecisionRecord, OrchestratorStateChangeRecord, OrchestratorToolCallRecord, Page,
+    PageCursor, PipelineHistoryBundle,
 };
+use crate::types::AgentMessage;
 use crate::AppState;
 
 // ============================================================================
 // Persistence Commands (fire-and-forget from frontend)
 // ============================================================================
+//
+// Records are buffered and written in batches by AgentRunsDB; the query
+// commands below flush the buffer before reading.
 
 #[tauri::command]
 pub async fn persist_tool_call(
     tool_call: OrchestratorToolCallRecord,
     state: tauri::State<'_, AppState>,
-) -> Result<i64, String> {
+) -> Result<(), String> {
     state
         .agent_runs_db
-        .insert_tool_call(&tool_call)
-        .await
-        .map_err(|e| e.to_string())
+        .buffer_event(BufferedEvent::ToolCall(tool_call))
+        .await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn persist_state_change(
     state_change: OrchestratorStateChangeRecord,
     state: tauri::State<'_, AppState>,
-) -> Result<i64, String> {
+) -> Result<(), String> {
     state
         .agent_runs_db
-        .insert_state_change(&state_change)
-        .await
-        .map_err(|e| e.to_string())
+        .buffer_event(BufferedEvent::StateChange(state_change))
+        .await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn persist_decision(
     decision: OrchestratorDecisionRecord,
     state: tauri::State<'_, AppState>,
-) -> Result<i64, String> {
+) -> Result<(), String> {
     state
         .agent_runs_db
-        .insert_decision(&decision)
-        .await
-        .map_err(|e| e.to_string())
+        .buffer_event(BufferedEvent::Decision(decision))
+        .await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn persist_agent_output(
     output: AgentOutputRecord,
     state: tauri::State<'_, AppState>,
-) -> Result<i64, String> {
+) -> Result<(), String> {
     state
         .agent_runs_db
-        .insert_agent_output(&output)
-        .await
-        .map_err(|e| e.to_string())
+        .buffer_event(BufferedEvent::AgentOutput(output))
+        .await;
+    Ok(())
 }
 
 // ============================================================================
@@ -72,8 +77,9 @@ pub async fn get_orchestrator_tool_calls(
     since_timestamp: Option<i64>,
     limit: Option<usize>,
     offset: Option<usize>,
+    before: Option<PageCursor>,
     state: tauri::State<'_, AppState>,
-) -> Result<Vec<OrchestratorToolCallRecord>, String> {
+) -> Result<Page<OrchestratorToolCallRecord>, String> {
     let filters = EventQueryFilters {
         pipeline_id,
         agent_id,
@@ -81,13 +87,19 @@ pub async fn get_orchestrator_tool_calls(
         until_timestamp: None,
         limit: limit.or(Some(200)),
         offset,
+        before,
     };
 
-    state
+    let limit = filters.limit;
+    let records = state
         .agent_runs_db
         .query_tool_calls(filters)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    Ok(Page::from_items(records, limit, |record| PageCursor {
+        timestamp: record.timestamp,
+        id: record.id.unwrap_or_default(),
+    }))
 }
 
 #[tauri::command]
@@ -96,8 +108,9 @@ pub async fn get_orchestrator_state_changes(
     since_timestamp: Option<i64>,
     limit: Option<usize>,
     offset: Option<usize>,
+    before: Option<PageCursor>,
     state: tauri::State<'_, AppState>,
-) -> Result<Vec<OrchestratorStateChangeRecord>, String> {
+) -> Result<Page<OrchestratorStateChangeRecord>, String> {
     let filters = EventQueryFilters {
         pipeline_id,
         agent_id: None,
@@ -105,13 +118,19 @@ pub async fn get_orchestrator_state_changes(
         until_timestamp: None,
         limit: limit.or(Some(100)),
         offset,
+        before,
     };
 
-    state
+    let limit = filters.limit;
+    let records = state
         .agent_runs_db
         .query_state_changes(filters)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    Ok(Page::from_items(records, limit, |record| PageCursor {
+        timestamp: record.timestamp,
+        id: record.id.unwrap_or_default(),
+    }))
 }
 
 #[tauri::command]
@@ -120,8 +139,9 @@ pub async fn get_orchestrator_decisions(
     since_timestamp: Option<i64>,
     limit: Option<usize>,
     offset: Option<usize>,
+    before: Option<PageCursor>,
     state: tauri::State<'_, AppState>,
-) -> Result<Vec<OrchestratorDecisionRecord>, String> {
+) -> Result<Page<OrchestratorDecisionRecord>, String> {
     let filters = EventQueryFilters {
         pipeline_id,
         agent_id: None,
@@ -129,13 +149,19 @@ pub async fn get_orchestrator_decisions(
         until_timestamp: None,
         limit: limit.or(Some(50)),
         offset,
+        before,
     };
 
-    state
+    let limit = filters.limit;
+    let records = state
         .agent_runs_db
         .query_decisions(filters)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    Ok(Page::from_items(records, limit, |record| PageCursor {
+        timestamp: record.timestamp,
+        id: record.id.unwrap_or_default(),
+    }))
 }
 
 #[tauri::command]
@@ -145,6 +171,7 @@ pub async fn get_agent_output_history(
     since_timestamp: Option<i64>,
     limit: Option<usize>,
     offset: Option<usize>,
+    before: Option<PageCursor>,
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<AgentOutputRecord>, String> {
     let filters = EventQueryFilters {
@@ -154,6 +181,7 @@ pub async fn get_agent_output_history(
         until_timestamp: None,
         limit: limit.or(Some(500)),
         offset,
+        before,
     };
 
     state
@@ -176,6 +204,46 @@ pub async fn get_pipeline_history(
         .map_err(|e| e.to_string())
 }
 
+/// Messages the pipeline's agents exchanged, oldest first
+#[tauri::command]
+pub async fn get_pipeline_agent_messages(
+    pipeline_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AgentMessage>, String> {
+    state
+        .agent_runs_db
+        .get_pipeline_agent_messages(&pipeline_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Skills, subagents and CLAUDE.md files a pipeline generated (without content)
+#[tauri::command]
+pub async fn list_pipeline_artifacts(
+    pipeline_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<GeneratedArtifactRecord>, String> {
+    state
+        .agent_runs_db
+        .list_pipeline_artifacts(&pipeline_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Content of a generated artifact as it was written
+#[tauri::command]
+pub async fn get_artifact_content(
+    id: i64,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    state
+        .agent_runs_db
+        .get_artifact_content(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Artifact {} not found", id))
+}
+
 /// Clear all events for a pipeline (useful for restarting)
 #[tauri::command]
 pub async fn clear_pipeline_events(
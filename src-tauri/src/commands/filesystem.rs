@@ -0,0 +1,278 @@
+// Filesystem browsing Tauri commands
+//
+// Backs the working directory picker in the new-agent dialog. Each entry
+// carries what the picker shows next to it (git repo, CLAUDE.md, when the
+// commander last ran an agent there), so a listing is a single IPC call.
+//
+// When the workspace allowlist is in use, paths outside the user's home and
+// the allowlisted directories are marked restricted: the meta-agent would ask
+// before creating a worker there.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::AppState;
+
+/// Default number of directories returned by get_recent_working_dirs
+const DEFAULT_RECENT_DIRS_LIMIT: usize = 10;
+
+/// A file or directory in a browse listing
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub is_git_repo: bool,
+    pub has_claude_md: bool,
+    /// When an agent last ran in this directory (ms since epoch)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<i64>,
+    pub restricted: bool,
+}
+
+/// Contents of a browsed directory
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryListing {
+    /// The directory itself
+    pub directory: DirectoryEntry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Subdirectories first, then files, each sorted by name. Hidden entries are omitted.
+    pub entries: Vec<DirectoryEntry>,
+}
+
+/// Paths the user may pick without the directory being flagged
+struct Allowed {
+    home: Option<PathBuf>,
+    /// Empty when the allowlist is not in use, in which case nothing is restricted
+    roots: Vec<PathBuf>,
+}
+
+impl Allowed {
+    fn is_restricted(&self, path: &Path) -> bool {
+        if self.roots.is_empty() {
+            return false;
+        }
+        let inside_home = self
+            .home
+            .as_ref()
+            .is_some_and(|home| path.starts_with(home));
+        !inside_home && !self.roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+/// Resolve the requested path: empty means home, and a leading ~ is expanded
+fn resolve_path(path: Option<&str>) -> Result<PathBuf, String> {
+    let path = path.map(str::trim).unwrap_or_default();
+    let home = || dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string());
+
+    let resolved = if path.is_empty() || path == "~" {
+        home()?
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        home()?.join(rest)
+    } else {
+        PathBuf::from(path)
+    };
+
+    resolved
+        .canonicalize()
+        .map_err(|e| format!("Cannot open '{}': {}", resolved.display(), e))
+}
+
+/// Map keys for last-used lookups, ignoring trailing slashes
+fn usage_key(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn describe(
+    path: &Path,
+    is_dir: bool,
+    last_used: &HashMap<String, i64>,
+    allowed: &Allowed,
+) -> DirectoryEntry {
+    let path_str = path.to_string_lossy().to_string();
+    DirectoryEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path_str.clone()),
+        // .git is a file in worktrees and submodules, so don't require a directory
+        is_git_repo: is_dir && path.join(".git").exists(),
+        has_claude_md: is_dir
+            && (path.join("CLAUDE.md").is_file() || path.join(".claude/CLAUDE.md").is_file()),
+        last_used_at: last_used.get(&usage_key(&path_str)).copied(),
+        restricted: allowed.is_restricted(path),
+        path: path_str,
+        is_dir,
+    }
+}
+
+fn list_directory(
+    dir: &Path,
+    last_used: &HashMap<String, i64>,
+    allowed: &Allowed,
+) -> Result<DirectoryListing, String> {
+    let read =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read '{}': {}", dir.display(), e))?;
+
+    let mut entries: Vec<DirectoryEntry> = read
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| {
+            let path = entry.path();
+            // Follow symlinks so linked project directories can be browsed into
+            let is_dir = path.is_dir();
+            describe(&path, is_dir, last_used, allowed)
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    Ok(DirectoryListing {
+        directory: describe(dir, true, last_used, allowed),
+        parent: dir.parent().map(|p| p.to_string_lossy().to_string()),
+        entries,
+    })
+}
+
+/// Run a filesystem walk on the blocking pool: listings stat every entry,
+/// which can take a while on network mounts and shouldn't stall the executor
+async fn off_executor<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Directory listing failed: {}", e))?
+}
+
+async fn allowed_paths(state: &AppState) -> Allowed {
+    Allowed {
+        home: dirs::home_dir().and_then(|h| h.canonicalize().ok()),
+        roots: state.directory_approvals.allowlisted_roots().await,
+    }
+}
+
+/// List a directory for the working directory picker.
+///
+/// An empty path lists the home directory.
+#[tauri::command]
+pub async fn browse_directory(
+    path: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<DirectoryListing, String> {
+    let dir = off_executor(move || {
+        let dir = resolve_path(path.as_deref())?;
+        if !dir.is_dir() {
+            return Err(format!("'{}' is not a directory", dir.display()));
+        }
+        Ok(dir)
+    })
+    .await?;
+
+    let last_used: HashMap<String, i64> = state
+        .agent_runs_db
+        .get_working_dir_usage_under(&dir.to_string_lossy())
+        .await
+        .map_err(|e| format!("Failed to load run history: {}", e))?
+        .into_iter()
+        .map(|(dir, ts)| (usage_key(&dir), ts))
+        .collect();
+    let allowed = allowed_paths(&state).await;
+
+    off_executor(move || list_directory(&dir, &last_used, &allowed)).await
+}
+
+/// Working directories of recent runs, most recently used first.
+///
+/// Directories that no longer exist are skipped.
+#[tauri::command]
+pub async fn get_recent_working_dirs(
+    limit: Option<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DirectoryEntry>, String> {
+    let usage = state
+        .agent_runs_db
+        .get_recent_working_dir_usage(limit.unwrap_or(DEFAULT_RECENT_DIRS_LIMIT))
+        .await
+        .map_err(|e| format!("Failed to load run history: {}", e))?;
+    let allowed = allowed_paths(&state).await;
+
+    off_executor(move || {
+        Ok(usage
+            .into_iter()
+            .filter(|(dir, _)| Path::new(dir).is_dir())
+            .map(|(dir, ts)| {
+                let last_used = HashMap::from([(usage_key(&dir), ts)]);
+                describe(Path::new(&dir), true, &last_used, &allowed)
+            })
+            .collect())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing_flags_repos_and_claude_md() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("app/.git")).unwrap();
+        std::fs::write(root.join("app/CLAUDE.md"), "# App").unwrap();
+        std::fs::create_dir(root.join("Notes")).unwrap();
+        std::fs::create_dir(root.join(".cache")).unwrap();
+        std::fs::write(root.join("readme.txt"), "hi").unwrap();
+
+        let app_path = root.join("app").to_string_lossy().to_string();
+        let last_used = HashMap::from([(app_path, 42)]);
+        let allowed = Allowed {
+            home: None,
+            roots: Vec::new(),
+        };
+
+        let listing = list_directory(&root, &last_used, &allowed).unwrap();
+        let names: Vec<_> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "Notes", "readme.txt"]);
+
+        let app = &listing.entries[0];
+        assert!(app.is_dir && app.is_git_repo && app.has_claude_md);
+        assert_eq!(app.last_used_at, Some(42));
+        assert!(!listing.entries[1].is_git_repo);
+        assert!(!listing.entries[2].is_dir);
+        assert!(listing.entries.iter().all(|e| !e.restricted));
+    }
+
+    #[test]
+    fn test_restricted_only_when_allowlist_in_use() {
+        let allowed = Allowed {
+            home: Some(PathBuf::from("/home/dev")),
+            roots: vec![PathBuf::from("/srv/work")],
+        };
+        assert!(!allowed.is_restricted(Path::new("/home/dev/project")));
+        assert!(!allowed.is_restricted(Path::new("/srv/work/api")));
+        assert!(allowed.is_restricted(Path::new("/etc")));
+        assert!(allowed.is_restricted(Path::new("/srv/workshop")));
+
+        let open = Allowed {
+            home: Some(PathBuf::from("/home/dev")),
+            roots: Vec::new(),
+        };
+        assert!(!open.is_restricted(Path::new("/etc")));
+    }
+
+    #[test]
+    fn test_usage_key_ignores_trailing_slash() {
+        assert_eq!(usage_key("/work/app/"), "/work/app");
+        assert_eq!(usage_key("/"), "/");
+    }
+}
@@ -0,0 +1,9 @@
+// Generated file manifest Tauri commands
+
+use crate::generated_files::{self, GeneratedFile};
+
+/// Files and directories the commander has written into a working directory
+#[tauri::command]
+pub async fn list_generated_files(working_dir: String) -> Result<Vec<GeneratedFile>, String> {
+    generated_files::list_generated_files(&working_dir)
+}
@@ -12,6 +12,7 @@
 // - findings_analyzer.rs: Output analysis and recommendations
 // - instruction_generator.rs: Prompt building and parsing
 
+use crate::agent_manager::AgentSpawnOptions;
 use crate::ai_client::{ContentBlock, Message};
 use crate::types::AgentSource;
 use crate::utils::string::truncate_with_ellipsis;
@@ -152,10 +153,10 @@ pub async fn create_test_agent(
                 Vec::new(), // No pre-generated skills
                 AgentSource::TestWizard,
                 Arc::new(app_handle.clone()),
-                None,                                        // No pipeline ID
-                Some(format!("Test: {}", &session_id[..8])), // Title
-                None,                                        // No model override
-                None,                                        // No complexity
+                AgentSpawnOptions {
+                    title: Some(format!("Test: {}", &session_id[..8])),
+                    ..Default::default()
+                },
             )
             .await
             .map_err(|e| format!("Failed to create test agent: {}", e))?
@@ -197,7 +198,7 @@ pub async fn analyze_test_results(
     let outputs = {
         let manager = state.agent_manager.lock().await;
         manager
-            .get_agent_outputs(&agent_id, 0)
+            .get_agent_outputs(&agent_id, 0, None)
             .await
             .unwrap_or_default()
     };
@@ -240,14 +241,18 @@ pub async fn stop_test_agent(
 ) -> Result<(), String> {
     use std::fs;
 
-    // Stop the agent
-    {
-        let manager = state.agent_manager.lock().await;
-        manager
-            .stop_agent(&agent_id)
-            .await
-            .map_err(|e| format!("Failed to stop agent: {}", e))?;
-    }
+    // Stop the agent, draining it after releasing the lock
+    let pending = state
+        .agent_manager
+        .lock()
+        .await
+        .begin_stop(&agent_id, false, None)
+        .await
+        .map_err(|e| format!("Failed to stop agent: {}", e))?;
+    pending
+        .finish()
+        .await
+        .map_err(|e| format!("Failed to stop agent: {}", e))?;
 
     // Clean up temp file
     if let Some(ref temp_path) = temp_instruction_file {
@@ -272,7 +277,7 @@ pub async fn enhance_instruction_from_test(
     let outputs = {
         let manager = state.agent_manager.lock().await;
         manager
-            .get_agent_outputs(&agent_id, 0)
+            .get_agent_outputs(&agent_id, 0, None)
             .await
             .unwrap_or_default()
     };
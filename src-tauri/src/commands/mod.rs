@@ -9,12 +9,19 @@ pub mod config;
 pub mod config_loader;
 pub mod cost;
 pub mod database;
+pub mod env_registry;
 pub mod events;
+pub mod filesystem;
+pub mod generated_files;
 pub mod instruction;
 pub mod instruction_analysis;
 pub mod logging;
+pub mod permission_profiles;
+pub mod prompt_library;
+pub mod prompt_templates;
 pub mod security;
 pub mod skill;
+pub mod workspace;
 
 // Instruction wizard and its extracted submodules
 pub mod findings_analyzer;
@@ -31,9 +38,15 @@ pub use config::*;
 pub use cost::*;
 pub use database::*;
 pub use events::*;
+pub use filesystem::*;
+pub use generated_files::*;
 pub use instruction::*;
 pub use instruction_analysis::*;
 pub use instruction_wizard::*;
 pub use logging::*;
+pub use permission_profiles::*;
+pub use prompt_library::*;
+pub use prompt_templates::*;
 pub use security::*;
 pub use skill::*;
+pub use workspace::*;
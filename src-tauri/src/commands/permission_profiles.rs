@@ -0,0 +1,54 @@
+// Permission profile Tauri commands
+//
+// Listing, saving and deleting the named permission policies that new agents
+// get according to their source (see permission_profiles.rs). Changes apply
+// to agents spawned afterwards; running agents keep the profile they started
+// under.
+
+use crate::agent_runs_db::PermissionProfile;
+use crate::permission_profiles;
+use crate::AppState;
+
+/// All permission profiles, by name
+#[tauri::command]
+pub async fn get_permission_profiles(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PermissionProfile>, String> {
+    state
+        .agent_runs_db
+        .list_permission_profiles()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save a profile, replacing the one with the same name. Its sources are
+/// taken from any other profile bound to them.
+#[tauri::command]
+pub async fn set_permission_profile(
+    profile: PermissionProfile,
+    state: tauri::State<'_, AppState>,
+) -> Result<PermissionProfile, String> {
+    let profile = permission_profiles::validate_profile(&profile)?;
+    state
+        .agent_runs_db
+        .save_permission_profile(&profile)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_permission_profile(
+    name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let name = permission_profiles::validate_name(&name)?;
+    let deleted = state
+        .agent_runs_db
+        .delete_permission_profile(name)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !deleted {
+        return Err(format!("No permission profile named '{}'", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+// Prompt library Tauri commands
+//
+// Saving, listing, rendering and deleting the user's named prompts with
+// `{{variable}}` placeholders. These are separate from the pipeline's
+// planning, builder and verifier templates in prompt_templates.rs.
+
+use std::collections::HashMap;
+
+use crate::agent_runs_db::SavedPromptTemplate;
+use crate::prompt_library::{self, PromptTemplateRef};
+use crate::AppState;
+
+/// Save a prompt under `name`, replacing the content of an existing one
+#[tauri::command]
+pub async fn save_prompt_template(
+    name: String,
+    content: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<SavedPromptTemplate, String> {
+    let name = prompt_library::validate_name(&name)?;
+    if content.trim().is_empty() {
+        return Err("Prompt template content is required".to_string());
+    }
+    state
+        .agent_runs_db
+        .save_prompt_template(name, &content)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Saved prompts, most used first; `search` matches the name or content
+#[tauri::command]
+pub async fn list_prompt_templates(
+    search: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SavedPromptTemplate>, String> {
+    let search = search.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    state
+        .agent_runs_db
+        .list_prompt_templates(search)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Fill in a saved prompt's variables; every variable must be supplied.
+/// A preview, so it doesn't count as a use.
+#[tauri::command]
+pub async fn render_prompt_template(
+    name: String,
+    variables: HashMap<String, String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let template = PromptTemplateRef { name, variables };
+    let (_, prompt) = prompt_library::render_saved(&state.agent_runs_db, &template).await?;
+    Ok(prompt)
+}
+
+#[tauri::command]
+pub async fn delete_prompt_template(
+    name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let name = prompt_library::validate_name(&name)?;
+    let deleted = state
+        .agent_runs_db
+        .delete_prompt_template(name)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !deleted {
+        return Err(format!("No prompt template named '{}'", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+// Worker agent prompt template Tauri commands
+
+use crate::auto_pipeline::prompt_templates::{self, PromptTemplateInfo, PromptTemplateName};
+
+/// Planning, builder and verifier templates in effect for a working directory
+#[tauri::command]
+pub async fn get_prompt_templates(working_dir: String) -> Result<Vec<PromptTemplateInfo>, String> {
+    prompt_templates::list_templates(&working_dir)
+}
+
+/// Override a template for a working directory; rejected if placeholders are missing or unknown
+#[tauri::command]
+pub async fn set_prompt_template(
+    working_dir: String,
+    name: String,
+    content: String,
+) -> Result<(), String> {
+    let name = PromptTemplateName::parse(&name)?;
+    prompt_templates::set_template(&working_dir, name, &content)
+}
+
+/// Drop a working directory's override and go back to the default template
+#[tauri::command]
+pub async fn reset_prompt_template(working_dir: String, name: String) -> Result<(), String> {
+    let name = PromptTemplateName::parse(&name)?;
+    prompt_templates::reset_template(&working_dir, name)
+}
@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::security_monitor::{BatchIntervalStats, PrefilterStats};
 use crate::types::{ElevatedCommandStatus, ElevatedCommandStatusEvent, PendingElevatedCommand};
 use crate::AppState;
 
@@ -14,6 +15,10 @@ pub struct SecurityStatus {
     pub config: SecurityConfigResponse,
     pub rule_count: usize,
     pub categories: Vec<String>,
+    /// Batch risk estimate needed for LLM analysis
+    pub llm_risk_threshold: f32,
+    /// Batches sent to or kept from LLM analysis
+    pub prefilter: PrefilterStats,
 }
 
 /// Security configuration response
@@ -54,6 +59,35 @@ pub async fn get_security_status(state: State<'_, AppState>) -> Result<SecurityS
                 "DataExfiltration".to_string(),
                 "PrivilegeEscalation".to_string(),
             ],
+            llm_risk_threshold: monitor.llm_risk_threshold().await,
+            prefilter: monitor.prefilter_stats().await,
+        })
+    } else {
+        Err("Security monitor not initialized".to_string())
+    }
+}
+
+/// Background analysis loop statistics
+#[derive(Debug, Serialize)]
+pub struct SecurityMonitorStats {
+    /// Events waiting for the next batch
+    pub pending_events: usize,
+    /// Current adaptive interval and flush counts
+    pub batch_interval: BatchIntervalStats,
+    /// Batches sent to or kept from LLM analysis
+    pub prefilter: PrefilterStats,
+}
+
+/// Get the analysis loop's current interval and flush counts
+#[tauri::command]
+pub async fn get_security_monitor_stats(
+    state: State<'_, AppState>,
+) -> Result<SecurityMonitorStats, String> {
+    if let Some(monitor) = &state.security_monitor {
+        Ok(SecurityMonitorStats {
+            pending_events: monitor.pending_event_count().await,
+            batch_interval: monitor.batch_interval_stats().await,
+            prefilter: monitor.prefilter_stats().await,
         })
     } else {
         Err("Security monitor not initialized".to_string())
@@ -71,6 +105,19 @@ pub async fn set_security_enabled(state: State<'_, AppState>, enabled: bool) ->
     }
 }
 
+/// Set the batch risk estimate needed for LLM analysis (0.0 - 1.0)
+#[tauri::command]
+pub async fn set_security_llm_threshold(
+    state: State<'_, AppState>,
+    threshold: f32,
+) -> Result<(), String> {
+    if let Some(monitor) = &state.security_monitor {
+        monitor.set_llm_risk_threshold(threshold).await
+    } else {
+        Err("Security monitor not initialized".to_string())
+    }
+}
+
 /// Get pending security reviews
 #[tauri::command]
 pub async fn get_pending_security_reviews(
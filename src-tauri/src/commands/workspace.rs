@@ -0,0 +1,67 @@
+// Workspace Tauri commands
+//
+// Creating, listing and switching the workspaces that scope the meta-agent's
+// conversations, memory, personality and directory allowlist.
+
+use tauri::Emitter;
+
+use crate::agent_runs_db::WorkspaceInfo;
+use crate::workspace::{active_workspace_id, workspace_id_from_name};
+use crate::AppState;
+
+/// Create a workspace named `name`; returns its ID
+#[tauri::command]
+pub async fn create_workspace(
+    name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let workspace_id = workspace_id_from_name(&name)?;
+    let created = state
+        .agent_runs_db
+        .create_workspace(&workspace_id, &name)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !created {
+        return Err(format!("Workspace '{}' already exists", workspace_id));
+    }
+    Ok(workspace_id)
+}
+
+/// Make a workspace active, swapping the meta agent's context in place
+#[tauri::command]
+pub async fn switch_workspace(
+    workspace_id: String,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let exists = state
+        .agent_runs_db
+        .touch_workspace(&workspace_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Workspace '{}' not found", workspace_id));
+    }
+
+    let mut meta_agent = state.meta_agent.lock().await;
+    meta_agent.switch_workspace(&workspace_id).await;
+    meta_agent.emit_context_info(&app_handle);
+    drop(meta_agent);
+
+    let _ = app_handle.emit(
+        "workspace:switched",
+        serde_json::json!({ "workspace_id": workspace_id }),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_workspaces(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<WorkspaceInfo>, String> {
+    state
+        .agent_runs_db
+        .list_workspaces(&active_workspace_id())
+        .await
+        .map_err(|e| e.to_string())
+}
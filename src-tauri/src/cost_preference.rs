@@ -0,0 +1,205 @@
+// Cost preference - who does work that a worker agent or the API could do
+//
+// Claude Code workers run on the user's subscription, so their work has no
+// marginal cost, while the commander's and the orchestrator's own turns are
+// billed per token. With COST_PREFERENCE=subscription_first, and while the
+// subscription has capacity (no usage-limit window open, see
+// agent_manager::rate_limit), such work is left to the workers:
+// - the commander is told to delegate file inspection and summaries to workers
+// - GetAgentOutput returns only the end of long output and suggests asking the
+//   agent for a summary instead
+// - a completed pipeline keeps the orchestrator's completion summary instead
+//   of asking for a separate final summary
+// Each offloaded task is recorded with the API spend it is estimated to have
+// avoided. latency_first (the default) always does the work directly.
+
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::agent_manager::rate_limit;
+use crate::agent_runs_db::{AgentRunsDB, DailyOffloads, TaskOffloadRecord};
+use crate::commands::env_registry;
+use crate::utils::time::now_millis;
+
+/// Rough characters per token for estimating what a call would have cost
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// GetAgentOutput keeps this many characters of long output when offloading
+pub const OFFLOAD_OUTPUT_CHARS: usize = 4_000;
+
+/// Where work goes when either a worker or the API could do it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostPreference {
+    /// Prefer workers while the subscription has capacity
+    SubscriptionFirst,
+    /// Do the work directly, whatever it costs
+    LatencyFirst,
+}
+
+impl CostPreference {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "subscription_first" => Some(Self::SubscriptionFirst),
+            "latency_first" => Some(Self::LatencyFirst),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        match env_registry::COST_PREFERENCE.var() {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|| {
+                eprintln!(
+                    "[CostPreference] Unknown COST_PREFERENCE '{}', using latency_first",
+                    value
+                );
+                Self::LatencyFirst
+            }),
+            Err(_) => Self::LatencyFirst,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SubscriptionFirst => "subscription_first",
+            Self::LatencyFirst => "latency_first",
+        }
+    }
+
+    /// Whether work should go to workers given the end of the current
+    /// usage-limit window, if one is open
+    pub fn prefers_workers(&self, limited_until: Option<i64>) -> bool {
+        *self == Self::SubscriptionFirst && limited_until.is_none()
+    }
+}
+
+/// Whether to hand work to workers right now
+pub fn offload_to_workers() -> bool {
+    CostPreference::from_env().prefers_workers(rate_limit::limited_until())
+}
+
+/// What an offloaded task was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffloadKind {
+    /// Long agent output left with the agent instead of read by the commander
+    AgentOutput,
+    /// A pipeline's separate final-summary turn skipped
+    FinalSummary,
+}
+
+impl OffloadKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AgentOutput => "agent_output",
+            Self::FinalSummary => "final_summary",
+        }
+    }
+}
+
+/// API price used for estimates, in USD per million input tokens
+/// (API_USD_PER_MILLION_TOKENS, default 3)
+pub fn usd_per_million_tokens() -> f64 {
+    env_registry::API_USD_PER_MILLION_TOKENS
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .unwrap_or(3.0)
+}
+
+pub fn estimate_tokens(chars: usize) -> i64 {
+    (chars / CHARS_PER_TOKEN) as i64
+}
+
+/// Record an offloaded task. Failures are logged, not returned: the task was
+/// offloaded either way.
+pub async fn record_offload(
+    runs_db: Option<&Arc<AgentRunsDB>>,
+    kind: OffloadKind,
+    tokens_avoided: i64,
+) {
+    let Some(runs_db) = runs_db else {
+        return;
+    };
+    let record = TaskOffloadRecord {
+        id: None,
+        kind: kind.as_str().to_string(),
+        tokens_avoided,
+        usd_avoided: tokens_avoided as f64 * usd_per_million_tokens() / 1_000_000.0,
+        created_at: now_millis(),
+    };
+    if let Err(e) = runs_db.record_task_offload(&record).await {
+        eprintln!("[CostPreference] Failed to record offload: {}", e);
+    }
+}
+
+/// The end of long output, and how many characters were left out;
+/// None when the output is short enough to return as is
+pub fn shorten_output(output: &str, max_chars: usize) -> Option<(String, usize)> {
+    let total = output.chars().count();
+    if total <= max_chars {
+        return None;
+    }
+    let omitted = total - max_chars;
+    Some((output.chars().skip(omitted).collect(), omitted))
+}
+
+/// Added to the commander's system prompt while work is offloaded
+pub fn prompt_note() -> &'static str {
+    if !offload_to_workers() {
+        return "";
+    }
+    r#"
+
+## Cost Preference
+Worker agents run on the user's Claude subscription at no extra cost, while your own turns are billed per token. When a worker could do it - reading or inspecting files, summarizing long output - ask a worker and have it reply briefly, rather than pulling large content into this conversation."#
+}
+
+/// Offload activity for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct OffloadStats {
+    pub preference: String,
+    /// Whether work is being offloaded right now
+    pub offloading: bool,
+    /// End of the current usage-limit window, if one is open
+    pub limited_until: Option<i64>,
+    pub usd_per_million_tokens: f64,
+    /// Newest first
+    pub daily: Vec<DailyOffloads>,
+}
+
+pub async fn offload_stats(runs_db: &AgentRunsDB, days: i64) -> Result<OffloadStats, String> {
+    let preference = CostPreference::from_env();
+    let limited_until = rate_limit::limited_until();
+    Ok(OffloadStats {
+        preference: preference.as_str().to_string(),
+        offloading: preference.prefers_workers(limited_until),
+        limited_until,
+        usd_per_million_tokens: usd_per_million_tokens(),
+        daily: runs_db
+            .get_daily_offloads(days)
+            .await
+            .map_err(|e| e.to_string())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workers_preferred_only_with_capacity() {
+        let pref = CostPreference::parse(" Subscription_First ").unwrap();
+        assert!(pref.prefers_workers(None));
+        assert!(!pref.prefers_workers(Some(now_millis() + 60_000)));
+        assert!(!CostPreference::LatencyFirst.prefers_workers(None));
+        assert_eq!(CostPreference::parse("cheapest"), None);
+    }
+
+    #[test]
+    fn test_shorten_output_keeps_the_end() {
+        assert_eq!(shorten_output("short", 10), None);
+        let (tail, omitted) = shorten_output("héllo wörld", 5).unwrap();
+        assert_eq!(tail, "wörld");
+        assert_eq!(omitted, 6);
+    }
+}
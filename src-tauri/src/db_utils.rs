@@ -5,6 +5,7 @@
 
 use rusqlite::{Connection, Result as SqliteResult, ToSql};
 use std::sync::Arc;
+use tokio::runtime::{Handle, RuntimeFlavor};
 use tokio::sync::Mutex;
 
 /// Helper trait for executing database operations with automatic lock management.
@@ -45,6 +46,22 @@ pub trait DatabaseOps {
         T: Send;
 }
 
+/// Run blocking SQLite work without stalling the async runtime.
+///
+/// On the multi-threaded runtime the current worker hands its queued tasks
+/// to another thread while `f` runs (`block_in_place`), so a long write does
+/// not hold up unrelated tasks. Unlike `spawn_blocking` this lets `f` borrow
+/// the connection guard and the caller's arguments. On the current-thread
+/// runtime (`#[tokio::test]`) or outside a runtime `f` runs inline.
+pub fn run_blocking<T>(f: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
 /// Implementation of DatabaseOps for Arc<Mutex<Connection>>
 #[async_trait::async_trait]
 impl DatabaseOps for Arc<Mutex<Connection>> {
@@ -54,7 +71,7 @@ impl DatabaseOps for Arc<Mutex<Connection>> {
         T: Send,
     {
         let db = self.lock().await;
-        f(&db)
+        run_blocking(|| f(&db))
     }
 
     async fn with_db_mut<F, T>(&self, f: F) -> SqliteResult<T>
@@ -63,7 +80,7 @@ impl DatabaseOps for Arc<Mutex<Connection>> {
         T: Send,
     {
         let mut db = self.lock().await;
-        f(&mut db)
+        run_blocking(|| f(&mut db))
     }
 }
 
@@ -113,6 +130,16 @@ impl QueryBuilder {
         self.params.push(Box::new(param));
     }
 
+    /// Add a condition that binds several parameters, in placeholder order.
+    pub fn add_condition_with_params(
+        &mut self,
+        condition: &str,
+        params: Vec<Box<dyn ToSql + Send>>,
+    ) {
+        self.conditions.push(condition.to_string());
+        self.params.extend(params);
+    }
+
     /// Add a condition without a parameter (e.g., "status IS NOT NULL").
     pub fn add_raw_condition(&mut self, condition: &str) {
         self.conditions.push(condition.to_string());
@@ -173,7 +200,9 @@ pub mod columns {
         source, status, started_at, ended_at, last_activity,
         initial_prompt, error_message, pipeline_id, total_prompts, total_tool_calls,
         total_output_bytes, total_tokens_used, total_cost_usd, model_usage,
-        can_resume, resume_data";
+        can_resume, resume_data, conventions_injected, label, workspace_id, continued_from,
+        app_version, git_commit, schema_version, stop_mode, permission_profile, model, title, env_keys,
+        allowed_tools, disallowed_tools, repo_path, worktree_path, tool_timings, cloned_from";
 
     /// Column list for agent_prompts table queries.
     pub const AGENT_PROMPTS: &str = "id, agent_id, timestamp, prompt";
@@ -235,6 +264,21 @@ mod tests {
         assert!(params.is_empty());
     }
 
+    #[test]
+    fn test_query_builder_multi_param_condition() {
+        let mut builder = QueryBuilder::new("SELECT * FROM items WHERE 1=1");
+        builder.add_condition("kind = ?", "a".to_string());
+        builder.add_condition_with_params(
+            "(ts < ? OR (ts = ? AND id < ?))",
+            vec![Box::new(10i64), Box::new(10i64), Box::new(3i64)],
+        );
+
+        let (query, params) = builder.build();
+
+        assert!(query.ends_with("AND kind = ? AND (ts < ? OR (ts = ? AND id < ?))"));
+        assert_eq!(params.len(), 4);
+    }
+
     #[test]
     fn test_query_builder_with_offset() {
         let mut builder = QueryBuilder::new("SELECT * FROM items WHERE 1=1");
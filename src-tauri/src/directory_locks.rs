@@ -0,0 +1,424 @@
+// Directory locks - keep other agents out of a running pipeline's repository
+//
+// A pipeline holds an advisory lock on its working directory from the moment
+// its execution starts until it completes, fails or is cancelled. Creating or
+// prompting an agent that isn't part of that pipeline in the directory (or
+// anywhere below it) then either goes ahead with a `directory_lock:conflict`
+// event or fails naming the owning pipeline (DIRECTORY_LOCK_MODE = warn |
+// block). A second pipeline in a locked directory is treated the same way.
+//
+// Locks are saved to directory_locks.json in the app data directory.
+// Pipelines don't keep running across a restart, so at startup the locks of
+// an earlier run (one that crashed, say) are released, except those of
+// pipelines restored waiting on the user, which resume in the same
+// directory. `force_release_lock` frees a directory by hand. Every lock taken
+// or released is emitted as `directory_lock:acquired` / `directory_lock:released`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::commands::env_registry;
+use crate::events::AppEventEmitter;
+use crate::utils::time::now_millis;
+
+const LOCKS_FILE: &str = "directory_locks.json";
+
+static LOCKS: OnceLock<DirectoryLocks> = OnceLock::new();
+
+/// A pipeline's lock on its working directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryLock {
+    /// Canonical working directory
+    pub working_dir: String,
+    pub pipeline_id: String,
+    pub acquired_at: i64,
+}
+
+/// What happens when something else works in a locked directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Go ahead, emitting `directory_lock:conflict`
+    Warn,
+    /// Fail with an error naming the owning pipeline
+    Block,
+}
+
+impl LockMode {
+    /// Read DIRECTORY_LOCK_MODE, defaulting to warn
+    pub fn from_env() -> Self {
+        match env_registry::DIRECTORY_LOCK_MODE
+            .var()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "block" => Self::Block,
+            _ => Self::Warn,
+        }
+    }
+}
+
+/// What ran into a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockedAction {
+    CreateAgent,
+    SendPrompt,
+    StartPipeline,
+}
+
+impl LockedAction {
+    fn describe(&self) -> &'static str {
+        match self {
+            LockedAction::CreateAgent => "Creating an agent",
+            LockedAction::SendPrompt => "Prompting an agent",
+            LockedAction::StartPipeline => "Starting a pipeline",
+        }
+    }
+}
+
+/// Work in a directory locked by another pipeline, emitted as `directory_lock:conflict`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LockConflict {
+    pub working_dir: String,
+    pub lock: DirectoryLock,
+    pub action: LockedAction,
+    /// The agent prompted, or the pipeline started
+    pub subject_id: Option<String>,
+    /// False when the work went ahead anyway (warn mode)
+    pub blocked: bool,
+}
+
+impl LockConflict {
+    pub fn message(&self) -> String {
+        format!(
+            "{} in {} while pipeline {} has locked {}",
+            self.action.describe(),
+            self.working_dir,
+            self.lock.pipeline_id,
+            self.lock.working_dir
+        )
+    }
+}
+
+/// Why a lock was released, sent with `directory_lock:released`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseReason {
+    /// The pipeline finished, failed or was cancelled
+    Finished,
+    /// Released by the user with force_release_lock
+    Forced,
+    /// Left by a pipeline of an earlier run
+    Stale,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LockReleasedEvent<'a> {
+    lock: &'a DirectoryLock,
+    reason: ReleaseReason,
+}
+
+/// Canonical form of a directory, or the path as given if it can't be resolved
+fn canonical(dir: &str) -> String {
+    Path::new(dir)
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.into_os_string().into_string().ok())
+        .unwrap_or_else(|| dir.to_string())
+}
+
+/// The innermost lock on a (canonical) directory or one of its parents
+fn covering_lock(locks: &HashMap<String, DirectoryLock>, dir: &str) -> Option<DirectoryLock> {
+    locks
+        .values()
+        .filter(|lock| Path::new(dir).starts_with(&lock.working_dir))
+        .max_by_key(|lock| lock.working_dir.len())
+        .cloned()
+}
+
+/// Held locks by working directory
+pub struct DirectoryLocks {
+    path: Option<PathBuf>,
+    locks: Mutex<HashMap<String, DirectoryLock>>,
+}
+
+impl DirectoryLocks {
+    fn with_path(path: Option<PathBuf>) -> Self {
+        let saved: Vec<DirectoryLock> = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            locks: Mutex::new(
+                saved
+                    .into_iter()
+                    .map(|lock| (lock.working_dir.clone(), lock))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The app's lock registry
+    pub fn global() -> &'static DirectoryLocks {
+        LOCKS.get_or_init(|| {
+            Self::with_path(
+                dirs::data_local_dir().map(|d| d.join("claude-commander").join(LOCKS_FILE)),
+            )
+        })
+    }
+
+    fn lock_map(&self) -> std::sync::MutexGuard<'_, HashMap<String, DirectoryLock>> {
+        self.locks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, locks: &HashMap<String, DirectoryLock>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut list: Vec<&DirectoryLock> = locks.values().collect();
+        list.sort_by(|a, b| a.working_dir.cmp(&b.working_dir));
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                let content = serde_json::to_string_pretty(&list).unwrap_or_default();
+                std::fs::write(path, content)
+            });
+        if let Err(e) = result {
+            eprintln!("[DirectoryLocks] Failed to save locks: {}", e);
+        }
+    }
+
+    /// Held locks, by working directory
+    pub fn list(&self) -> Vec<DirectoryLock> {
+        let mut list: Vec<DirectoryLock> = self.lock_map().values().cloned().collect();
+        list.sort_by(|a, b| a.working_dir.cmp(&b.working_dir));
+        list
+    }
+
+    /// The lock covering a directory: on the directory itself or a parent
+    pub fn holder(&self, working_dir: &str) -> Option<DirectoryLock> {
+        covering_lock(&self.lock_map(), &canonical(working_dir))
+    }
+
+    /// Lock a directory for a pipeline. Fails with the lock of another
+    /// pipeline covering the directory.
+    pub fn acquire(
+        &self,
+        working_dir: &str,
+        pipeline_id: &str,
+    ) -> Result<DirectoryLock, DirectoryLock> {
+        let dir = canonical(working_dir);
+        let mut locks = self.lock_map();
+        if let Some(held) = covering_lock(&locks, &dir) {
+            if held.pipeline_id != pipeline_id {
+                return Err(held);
+            }
+            if held.working_dir == dir {
+                return Ok(held);
+            }
+        }
+
+        let lock = DirectoryLock {
+            working_dir: dir,
+            pipeline_id: pipeline_id.to_string(),
+            acquired_at: now_millis(),
+        };
+        locks.insert(lock.working_dir.clone(), lock.clone());
+        self.save(&locks);
+        Ok(lock)
+    }
+
+    /// Release the locks matching `release`, returning them
+    fn release_where(&self, release: impl Fn(&DirectoryLock) -> bool) -> Vec<DirectoryLock> {
+        let mut locks = self.lock_map();
+        let released: Vec<DirectoryLock> = locks.values().filter(|l| release(l)).cloned().collect();
+        if !released.is_empty() {
+            locks.retain(|_, lock| !release(lock));
+            self.save(&locks);
+        }
+        released
+    }
+
+    /// Release every lock a pipeline holds
+    pub fn release_pipeline(&self, pipeline_id: &str) -> Vec<DirectoryLock> {
+        self.release_where(|lock| lock.pipeline_id == pipeline_id)
+    }
+
+    /// Release a directory's lock whoever holds it
+    pub fn force_release(&self, working_dir: &str) -> Option<DirectoryLock> {
+        let dir = canonical(working_dir);
+        self.release_where(|lock| lock.working_dir == dir || lock.working_dir == working_dir)
+            .pop()
+    }
+
+    /// Release the locks of pipelines other than `keep`
+    pub fn release_stale(&self, keep: &HashSet<String>) -> Vec<DirectoryLock> {
+        self.release_where(|lock| !keep.contains(&lock.pipeline_id))
+    }
+}
+
+fn emit_released(app_handle: &dyn AppEventEmitter, lock: &DirectoryLock, reason: ReleaseReason) {
+    if let Ok(payload) = serde_json::to_value(LockReleasedEvent { lock, reason }) {
+        let _ = app_handle.emit("directory_lock:released", payload);
+    }
+}
+
+/// Warn about or refuse a conflict, depending on the lock mode
+fn resolve_conflict(
+    conflict: LockConflict,
+    app_handle: Option<&dyn AppEventEmitter>,
+) -> Result<(), String> {
+    eprintln!("[DirectoryLocks] {}", conflict.message());
+    if let Some(app_handle) = app_handle {
+        if let Ok(payload) = serde_json::to_value(&conflict) {
+            let _ = app_handle.emit("directory_lock:conflict", payload);
+        }
+    }
+    if conflict.blocked {
+        Err(format!(
+            "{}. Wait for the pipeline to finish or release the lock with force_release_lock.",
+            conflict.message()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Lock a pipeline's working directory as its execution starts
+pub fn lock_for_pipeline(
+    working_dir: &str,
+    pipeline_id: &str,
+    app_handle: &dyn AppEventEmitter,
+) -> Result<(), String> {
+    match DirectoryLocks::global().acquire(working_dir, pipeline_id) {
+        Ok(lock) => {
+            if let Ok(payload) = serde_json::to_value(&lock) {
+                let _ = app_handle.emit("directory_lock:acquired", payload);
+            }
+            Ok(())
+        }
+        Err(held) => resolve_conflict(
+            LockConflict {
+                working_dir: working_dir.to_string(),
+                lock: held,
+                action: LockedAction::StartPipeline,
+                subject_id: Some(pipeline_id.to_string()),
+                blocked: LockMode::from_env() == LockMode::Block,
+            },
+            Some(app_handle),
+        ),
+    }
+}
+
+/// Release a pipeline's locks once it has finished
+pub fn unlock_pipeline(pipeline_id: &str, app_handle: &dyn AppEventEmitter) {
+    for lock in DirectoryLocks::global().release_pipeline(pipeline_id) {
+        emit_released(app_handle, &lock, ReleaseReason::Finished);
+    }
+}
+
+/// Release a directory's lock by hand
+pub fn force_release(working_dir: &str, app_handle: &dyn AppEventEmitter) -> Option<DirectoryLock> {
+    let lock = DirectoryLocks::global().force_release(working_dir)?;
+    emit_released(app_handle, &lock, ReleaseReason::Forced);
+    Some(lock)
+}
+
+/// Release the locks left by an earlier run, keeping those of the given
+/// (restored) pipelines
+pub fn release_stale(keep: &HashSet<String>, app_handle: &dyn AppEventEmitter) -> usize {
+    let released = DirectoryLocks::global().release_stale(keep);
+    for lock in &released {
+        emit_released(app_handle, lock, ReleaseReason::Stale);
+    }
+    released.len()
+}
+
+/// Check that an agent may work in a directory. Agents of the pipeline
+/// holding the lock always may; others are warned about or refused.
+pub fn check_agent_access(
+    working_dir: &str,
+    pipeline_id: Option<&str>,
+    action: LockedAction,
+    agent_id: Option<&str>,
+    app_handle: Option<&dyn AppEventEmitter>,
+) -> Result<(), String> {
+    let Some(lock) = DirectoryLocks::global().holder(working_dir) else {
+        return Ok(());
+    };
+    if pipeline_id == Some(lock.pipeline_id.as_str()) {
+        return Ok(());
+    }
+    resolve_conflict(
+        LockConflict {
+            working_dir: working_dir.to_string(),
+            lock,
+            action,
+            subject_id: agent_id.map(str::to_string),
+            blocked: LockMode::from_env() == LockMode::Block,
+        },
+        app_handle,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire_covers_subdirectories_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        let repo_str = repo.to_str().unwrap();
+        let src_str = repo.join("src");
+        let src_str = src_str.to_str().unwrap();
+        let file = dir.path().join(LOCKS_FILE);
+
+        let locks = DirectoryLocks::with_path(Some(file.clone()));
+        let lock = locks.acquire(repo_str, "p1").unwrap();
+        assert_eq!(locks.holder(src_str), Some(lock.clone()));
+        assert!(locks.acquire(repo_str, "p1").is_ok());
+        assert_eq!(locks.acquire(src_str, "p2").unwrap_err().pipeline_id, "p1");
+        assert!(locks.holder(dir.path().to_str().unwrap()).is_none());
+
+        // A restart reads the saved locks back
+        let reloaded = DirectoryLocks::with_path(Some(file));
+        assert_eq!(reloaded.list(), vec![lock.clone()]);
+        assert!(reloaded
+            .release_stale(&HashSet::from(["p1".to_string()]))
+            .is_empty());
+        assert_eq!(reloaded.release_stale(&HashSet::new()), vec![lock]);
+        assert!(reloaded.list().is_empty());
+    }
+
+    #[test]
+    fn test_release_pipeline_and_force_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        std::fs::create_dir_all(&a).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+
+        let locks = DirectoryLocks::with_path(None);
+        locks.acquire(a.to_str().unwrap(), "p1").unwrap();
+        locks.acquire(b.to_str().unwrap(), "p2").unwrap();
+
+        assert_eq!(locks.release_pipeline("p1").len(), 1);
+        assert!(locks.holder(a.to_str().unwrap()).is_none());
+        assert_eq!(
+            locks
+                .force_release(b.to_str().unwrap())
+                .unwrap()
+                .pipeline_id,
+            "p2"
+        );
+        assert!(locks.force_release(b.to_str().unwrap()).is_none());
+    }
+}
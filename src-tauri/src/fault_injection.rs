@@ -0,0 +1,184 @@
+// Fault injection hooks for chaos testing the agent lifecycle
+//
+// Production code calls `check_fault` at a handful of points where real
+// failures happen (spawning, reading stdout, serving hooks, writing to the
+// database, calling the AI provider). Without a hook installed the check is a
+// no-op. The `fault-injection` feature adds `FaultInjector`, which tests
+// install on AgentManager, AgentRunsDB and AutoPipelineManager to make those
+// points fail on demand.
+
+use std::sync::Arc;
+
+/// Places in the agent lifecycle where a fault can be injected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultPoint {
+    /// Spawning the Claude CLI process fails
+    AgentSpawn,
+    /// The agent's stdout ends in the middle of a message
+    StdoutEof,
+    /// The hook server answers a hook with 500
+    HookServerError,
+    /// A database write fails with a lock error
+    DbLocked,
+    /// A request to the AI provider times out
+    AiProviderTimeout,
+}
+
+impl FaultPoint {
+    /// Error message reported when the fault fires
+    pub fn error_message(&self) -> &'static str {
+        match self {
+            FaultPoint::AgentSpawn => "Failed to spawn claude process (injected fault)",
+            FaultPoint::StdoutEof => "Agent stdout closed mid-message (injected fault)",
+            FaultPoint::HookServerError => "Hook server error (injected fault)",
+            FaultPoint::DbLocked => "database is locked (injected fault)",
+            FaultPoint::AiProviderTimeout => "AI provider request timed out (injected fault)",
+        }
+    }
+}
+
+/// Decides whether a fault point should fail
+pub trait FaultHook: Send + Sync {
+    /// Returns true when the operation at `point` should fail now
+    fn should_fail(&self, point: FaultPoint) -> bool;
+}
+
+/// Shared, optional fault hook as stored on the components that support injection
+pub type SharedFaultHook = Option<Arc<dyn FaultHook>>;
+
+/// Fail with the fault point's error message if the hook says so
+pub fn check_fault(hook: &SharedFaultHook, point: FaultPoint) -> Result<(), String> {
+    match hook {
+        Some(hook) if hook.should_fail(point) => Err(point.error_message().to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+pub use injector::FaultInjector;
+
+#[cfg(feature = "fault-injection")]
+mod injector {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::{FaultHook, FaultPoint};
+
+    /// How many more times an armed fault point fails (None = until disarmed)
+    type Remaining = Option<u32>;
+
+    /// Fault hook driven by tests: fault points are armed explicitly and fire
+    /// a fixed number of times (or until disarmed)
+    #[derive(Debug, Default)]
+    pub struct FaultInjector {
+        armed: Mutex<HashMap<FaultPoint, Remaining>>,
+        fired: Mutex<HashMap<FaultPoint, u32>>,
+    }
+
+    impl FaultInjector {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Fail the next `times` operations at `point`
+        pub fn arm(&self, point: FaultPoint, times: u32) {
+            if times > 0 {
+                self.armed_map().insert(point, Some(times));
+            }
+        }
+
+        /// Fail every operation at `point` until it is disarmed
+        pub fn arm_always(&self, point: FaultPoint) {
+            self.armed_map().insert(point, None);
+        }
+
+        /// Stop failing operations at `point`
+        pub fn disarm(&self, point: FaultPoint) {
+            self.armed_map().remove(&point);
+        }
+
+        /// Number of times `point` has failed so far
+        pub fn fired(&self, point: FaultPoint) -> u32 {
+            self.fired_map().get(&point).copied().unwrap_or(0)
+        }
+
+        // A panicking test must not wedge the injector for the others
+        fn armed_map(&self) -> std::sync::MutexGuard<'_, HashMap<FaultPoint, Remaining>> {
+            self.armed.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        fn fired_map(&self) -> std::sync::MutexGuard<'_, HashMap<FaultPoint, u32>> {
+            self.fired.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    impl FaultHook for FaultInjector {
+        fn should_fail(&self, point: FaultPoint) -> bool {
+            let fire = {
+                let mut armed = self.armed_map();
+                match armed.get_mut(&point) {
+                    None => false,
+                    Some(None) => true,
+                    Some(Some(remaining)) => {
+                        *remaining -= 1;
+                        if *remaining == 0 {
+                            armed.remove(&point);
+                        }
+                        true
+                    }
+                }
+            };
+
+            if fire {
+                *self.fired_map().entry(point).or_insert(0) += 1;
+            }
+            fire
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FailDbWrites;
+
+    impl FaultHook for FailDbWrites {
+        fn should_fail(&self, point: FaultPoint) -> bool {
+            point == FaultPoint::DbLocked
+        }
+    }
+
+    #[test]
+    fn test_check_fault_without_hook_passes() {
+        assert!(check_fault(&None, FaultPoint::AgentSpawn).is_ok());
+    }
+
+    #[test]
+    fn test_check_fault_reports_point_error() {
+        let hook: SharedFaultHook = Some(Arc::new(FailDbWrites));
+        assert!(check_fault(&hook, FaultPoint::AgentSpawn).is_ok());
+        assert_eq!(
+            check_fault(&hook, FaultPoint::DbLocked).unwrap_err(),
+            FaultPoint::DbLocked.error_message()
+        );
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[test]
+    fn test_injector_fires_armed_number_of_times() {
+        let injector = FaultInjector::new();
+        injector.arm(FaultPoint::StdoutEof, 2);
+
+        assert!(injector.should_fail(FaultPoint::StdoutEof));
+        assert!(injector.should_fail(FaultPoint::StdoutEof));
+        assert!(!injector.should_fail(FaultPoint::StdoutEof));
+        assert!(!injector.should_fail(FaultPoint::AgentSpawn));
+        assert_eq!(injector.fired(FaultPoint::StdoutEof), 2);
+
+        injector.arm_always(FaultPoint::AgentSpawn);
+        assert!(injector.should_fail(FaultPoint::AgentSpawn));
+        injector.disarm(FaultPoint::AgentSpawn);
+        assert!(!injector.should_fail(FaultPoint::AgentSpawn));
+    }
+}
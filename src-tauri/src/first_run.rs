@@ -7,8 +7,9 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 
+use crate::commands::env_registry;
+
 // Embed files at compile time
-const ENV_EXAMPLE: &str = include_str!("../../.env.example");
 const PLAYWRIGHT_SKILL: &str = include_str!("../../.instructions/PLAYWRIGHT_MCP_SKILL.md");
 const GMAIL_INTEGRATION: &str = include_str!("../../.instructions/GMAIL_INTEGRATION.md");
 const GOOGLE_DRIVE_INTEGRATION: &str =
@@ -70,7 +71,7 @@ pub fn initialize(config: &FirstRunConfig) -> Result<(), String> {
     // Copy env.example to config directory
     let env_example_path = config.config_dir.join("env.example");
     if !env_example_path.exists() {
-        fs::write(&env_example_path, ENV_EXAMPLE)
+        fs::write(&env_example_path, env_registry::env_template())
             .map_err(|e| format!("Failed to write env.example: {}", e))?;
         files_copied.push(env_example_path.to_string_lossy().to_string());
         println!("  ✓ Copied env.example to {:?}", env_example_path);
@@ -0,0 +1,662 @@
+// Generated file manifest
+//
+// Skills, subagents, CLAUDE.md files, pipeline scratchpads and prompt files
+// handed to agents are written into
+// the user's working directory, where they show up in `git status` and are easy
+// to commit by accident. Every such write is recorded in a manifest kept in
+// `<working_dir>/.commander/manifest.json`, so the commander can always say
+// which files it owns in a repo.
+//
+// Two settings act on the manifest:
+// - GIT_EXCLUDE_GENERATED_FILES: list owned files in `.git/info/exclude`
+//   (never the tracked .gitignore)
+// - GENERATED_FILES_ON_COMPLETE: when a pipeline finishes, `keep` its files,
+//   `cleanup` (delete) them or `archive` them under `.commander/<pipeline_id>/`
+//
+// Files the commander only modified (e.g. appending to an existing CLAUDE.md)
+// are listed but never excluded, deleted or moved.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::agent_runs_db::GeneratedArtifactRecord;
+use crate::auto_pipeline::prompt_templates::template_hash;
+use crate::commands::env_registry;
+use crate::utils::time::now_millis;
+
+/// Directory (relative to the working directory) owned by the commander
+pub const COMMANDER_DIR: &str = ".commander";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+const EXCLUDE_BEGIN: &str = "# >>> claude-commander generated files";
+const EXCLUDE_END: &str = "# <<< claude-commander generated files";
+
+/// Serializes manifest read-modify-write cycles within the process
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratedFileKind {
+    Skill,
+    Subagent,
+    ClaudeMd,
+    Scratchpad,
+    PromptFile,
+}
+
+impl GeneratedFileKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeneratedFileKind::Skill => "skill",
+            GeneratedFileKind::Subagent => "subagent",
+            GeneratedFileKind::ClaudeMd => "claude_md",
+            GeneratedFileKind::Scratchpad => "scratchpad",
+            GeneratedFileKind::PromptFile => "prompt_file",
+        }
+    }
+}
+
+/// A file or directory the commander wrote into a working directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedFile {
+    /// Path relative to the working directory
+    pub path: String,
+    pub kind: GeneratedFileKind,
+    /// False when the commander modified a file that already existed
+    pub created: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_id: Option<String>,
+    /// When the file was last written (ms since epoch)
+    pub recorded_at: i64,
+}
+
+/// What happens to a pipeline's generated files when it finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionAction {
+    Keep,
+    Cleanup,
+    Archive,
+}
+
+impl CompletionAction {
+    /// Read GENERATED_FILES_ON_COMPLETE, defaulting to keep
+    pub fn from_env() -> Self {
+        match env_registry::GENERATED_FILES_ON_COMPLETE
+            .var()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "cleanup" => Self::Cleanup,
+            "archive" => Self::Archive,
+            _ => Self::Keep,
+        }
+    }
+}
+
+/// Whether GIT_EXCLUDE_GENERATED_FILES is enabled
+fn git_exclude_enabled() -> bool {
+    env_registry::GIT_EXCLUDE_GENERATED_FILES.flag()
+}
+
+fn manifest_path(working_dir: &Path) -> PathBuf {
+    working_dir.join(COMMANDER_DIR).join(MANIFEST_FILE)
+}
+
+fn load_manifest(working_dir: &Path) -> Result<Vec<GeneratedFile>, String> {
+    match fs::read_to_string(manifest_path(working_dir)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse generated file manifest: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read generated file manifest: {}", e)),
+    }
+}
+
+fn save_manifest(working_dir: &Path, files: &[GeneratedFile]) -> Result<(), String> {
+    let path = manifest_path(working_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {} directory: {}", COMMANDER_DIR, e))?;
+    }
+    let json = serde_json::to_string_pretty(files)
+        .map_err(|e| format!("Failed to serialize generated file manifest: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write generated file manifest: {}", e))
+}
+
+/// Path relative to the working directory, with forward slashes
+fn relative_path(working_dir: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(working_dir).ok()?;
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Load, change and save the manifest, then refresh the git exclude block
+fn update_manifest<T>(
+    working_dir: &Path,
+    change: impl FnOnce(&mut Vec<GeneratedFile>) -> T,
+) -> Result<T, String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut files = load_manifest(working_dir)?;
+    let result = change(&mut files);
+    save_manifest(working_dir, &files)?;
+
+    if git_exclude_enabled() {
+        sync_git_exclude(working_dir, &files)?;
+    }
+    Ok(result)
+}
+
+fn add_entry(
+    files: &mut Vec<GeneratedFile>,
+    path: String,
+    kind: GeneratedFileKind,
+    created: bool,
+    pipeline_id: Option<&str>,
+) {
+    let now = now_millis();
+    match files.iter_mut().find(|f| f.path == path) {
+        Some(existing) => {
+            // A file the commander created stays owned when it's rewritten
+            existing.created |= created;
+            existing.kind = kind;
+            if pipeline_id.is_some() {
+                existing.pipeline_id = pipeline_id.map(String::from);
+            }
+            existing.recorded_at = now;
+        }
+        None => files.push(GeneratedFile {
+            path,
+            kind,
+            created,
+            pipeline_id: pipeline_id.map(String::from),
+            recorded_at: now,
+        }),
+    }
+}
+
+/// Record a file or directory written into `working_dir`.
+///
+/// `created` is false when the path existed before the write. Failures are
+/// logged rather than returned: the manifest must never block generation.
+pub fn record(
+    working_dir: &str,
+    path: &Path,
+    kind: GeneratedFileKind,
+    created: bool,
+    pipeline_id: Option<&str>,
+) {
+    let working_dir = Path::new(working_dir);
+    let Some(rel) = relative_path(working_dir, path) else {
+        eprintln!(
+            "[generated_files] Not recording {} (outside {})",
+            path.display(),
+            working_dir.display()
+        );
+        return;
+    };
+
+    if let Err(e) = update_manifest(working_dir, |files| {
+        add_entry(files, rel, kind, created, pipeline_id)
+    }) {
+        eprintln!("[generated_files] Warning: {}", e);
+    }
+}
+
+/// Attribute an already recorded path (and anything under it) to a pipeline
+pub fn assign_to_pipeline(working_dir: &str, path: &Path, pipeline_id: &str) {
+    let working_dir = Path::new(working_dir);
+    let Some(rel) = relative_path(working_dir, path) else {
+        return;
+    };
+    let prefix = format!("{}/", rel);
+
+    if let Err(e) = update_manifest(working_dir, |files| {
+        for file in files
+            .iter_mut()
+            .filter(|f| f.path == rel || f.path.starts_with(&prefix))
+        {
+            file.pipeline_id = Some(pipeline_id.to_string());
+        }
+    }) {
+        eprintln!("[generated_files] Warning: {}", e);
+    }
+}
+
+/// Drop paths the commander has deleted (and anything under them) from the
+/// manifest, removing the directories they leave empty. A manifest left with
+/// no entries is deleted too, so `.commander/` doesn't outlive its files.
+pub fn forget(working_dir: &str, paths: &[PathBuf]) {
+    let dir = Path::new(working_dir);
+    for path in paths {
+        remove_empty_parents(dir, path);
+    }
+    let manifest = manifest_path(dir);
+    if !manifest.exists() {
+        return;
+    }
+    let rels: Vec<String> = paths.iter().filter_map(|p| relative_path(dir, p)).collect();
+
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let result = load_manifest(dir).and_then(|mut files| {
+        files.retain(|f| {
+            !rels
+                .iter()
+                .any(|rel| f.path == *rel || f.path.starts_with(&format!("{}/", rel)))
+        });
+        if files.is_empty() {
+            fs::remove_file(&manifest)
+                .map_err(|e| format!("Failed to remove generated file manifest: {}", e))?;
+            remove_empty_parents(dir, &manifest);
+        } else {
+            save_manifest(dir, &files)?;
+        }
+        if git_exclude_enabled() {
+            sync_git_exclude(dir, &files)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("[generated_files] Warning: {}", e);
+    }
+}
+
+/// Everything the commander currently owns in `working_dir`.
+///
+/// Entries whose files were deleted since are dropped from the manifest.
+pub fn list_generated_files(working_dir: &str) -> Result<Vec<GeneratedFile>, String> {
+    let dir = Path::new(working_dir);
+    if !manifest_path(dir).exists() {
+        return Ok(Vec::new());
+    }
+
+    update_manifest(dir, |files| {
+        files.retain(|f| dir.join(&f.path).exists());
+        let mut listed = files.clone();
+        listed.sort_by_key(|f| f.recorded_at);
+        listed
+    })
+}
+
+/// Remove directories left empty between `path` and `working_dir`
+fn remove_empty_parents(working_dir: &Path, path: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == working_dir || !dir.starts_with(working_dir) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+fn cleanup_file(working_dir: &Path, file: &GeneratedFile) -> Result<(), String> {
+    let path = working_dir.join(&file.path);
+    let result = if path.is_dir() {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to remove {}: {}", file.path, e)),
+    }
+    remove_empty_parents(working_dir, &path);
+    Ok(())
+}
+
+/// Move a file under `.commander/<pipeline_id>/`, returning its new relative path
+fn archive_file(
+    working_dir: &Path,
+    file: &GeneratedFile,
+    pipeline_id: &str,
+) -> Result<String, String> {
+    let archived = format!("{}/{}/{}", COMMANDER_DIR, pipeline_id, file.path);
+    let from = working_dir.join(&file.path);
+    let to = working_dir.join(&archived);
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create archive directory: {}", e))?;
+    }
+    fs::rename(&from, &to).map_err(|e| format!("Failed to archive {}: {}", file.path, e))?;
+    remove_empty_parents(working_dir, &from);
+    Ok(archived)
+}
+
+/// Apply `action` to the files a pipeline created. Returns how many were handled.
+pub fn finish_pipeline(
+    working_dir: &str,
+    pipeline_id: &str,
+    action: CompletionAction,
+) -> Result<usize, String> {
+    let dir = Path::new(working_dir);
+    if action == CompletionAction::Keep || !manifest_path(dir).exists() {
+        return Ok(0);
+    }
+    let archive_prefix = format!("{}/", COMMANDER_DIR);
+
+    update_manifest(dir, |files| {
+        let mut handled = 0;
+        let mut kept = Vec::with_capacity(files.len());
+
+        for mut file in files.drain(..) {
+            let owned = file.created
+                && file.pipeline_id.as_deref() == Some(pipeline_id)
+                && !file.path.starts_with(&archive_prefix)
+                && dir.join(&file.path).exists();
+            if !owned {
+                kept.push(file);
+                continue;
+            }
+
+            let result = if action == CompletionAction::Cleanup {
+                cleanup_file(dir, &file).map(|_| None)
+            } else {
+                archive_file(dir, &file, pipeline_id).map(Some)
+            };
+            match result {
+                Ok(None) => handled += 1,
+                Ok(Some(new_path)) => {
+                    handled += 1;
+                    file.path = new_path;
+                    kept.push(file);
+                }
+                Err(e) => {
+                    eprintln!("[generated_files] Warning: {}", e);
+                    kept.push(file);
+                }
+            }
+        }
+
+        *files = kept;
+        handled
+    })
+}
+
+/// Every file under `dir`, at any depth
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// A file's content, or for a directory every file in it under a
+/// `--- <relative path> ---` header, in path order
+fn read_content(path: &Path) -> Result<String, String> {
+    if !path.is_dir() {
+        return fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    }
+
+    let mut files = Vec::new();
+    collect_files(path, &mut files)?;
+    files.sort();
+
+    let mut content = String::new();
+    for file in files {
+        let text = fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&format!(
+            "--- {} ---\n{}",
+            relative_path(path, &file).unwrap_or_default(),
+            text
+        ));
+    }
+    Ok(content)
+}
+
+/// Snapshot a generated file or directory as an artifact record for the run
+/// database. The caller fills in the pipeline or agent it belongs to.
+pub fn snapshot_artifact(
+    kind: GeneratedFileKind,
+    name: &str,
+    path: &Path,
+) -> Result<GeneratedArtifactRecord, String> {
+    let content = read_content(path)?;
+    Ok(GeneratedArtifactRecord {
+        id: None,
+        pipeline_id: None,
+        agent_id: None,
+        kind: kind.as_str().to_string(),
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        content_hash: template_hash(&content),
+        size_bytes: content.len() as i64,
+        content: Some(content),
+        pipeline_state: None,
+        created_at: now_millis(),
+        removed_at: None,
+    })
+}
+
+/// The repository root containing `working_dir`, if it has a `.git` directory
+fn find_git_root(working_dir: &Path) -> Option<&Path> {
+    working_dir
+        .ancestors()
+        .find(|dir| dir.join(".git").is_dir())
+}
+
+/// Exclude patterns for the owned files, relative to the repository root
+fn exclude_patterns(working_dir: &Path, git_root: &Path, files: &[GeneratedFile]) -> Vec<String> {
+    let prefix = relative_path(git_root, working_dir)
+        .map(|p| format!("{}/", p))
+        .unwrap_or_default();
+
+    let mut patterns = vec![format!("/{}{}/", prefix, COMMANDER_DIR)];
+    for file in files.iter().filter(|f| f.created) {
+        let suffix = if working_dir.join(&file.path).is_dir() {
+            "/"
+        } else {
+            ""
+        };
+        let pattern = format!("/{}{}{}", prefix, file.path, suffix);
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    patterns
+}
+
+/// Replace the commander's block in `.git/info/exclude`, keeping the user's lines
+fn sync_git_exclude(working_dir: &Path, files: &[GeneratedFile]) -> Result<(), String> {
+    let Some(git_root) = find_git_root(working_dir) else {
+        return Ok(());
+    };
+    let exclude_path = git_root.join(".git").join("info").join("exclude");
+
+    let existing = match fs::read_to_string(&exclude_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read git exclude file: {}", e)),
+    };
+
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_block = false;
+    for line in existing.lines() {
+        match line.trim() {
+            EXCLUDE_BEGIN => in_block = true,
+            EXCLUDE_END => in_block = false,
+            _ if !in_block => lines.push(line),
+            _ => {}
+        }
+    }
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let patterns = exclude_patterns(working_dir, git_root, files);
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push_str("\n\n");
+    }
+    content.push_str(EXCLUDE_BEGIN);
+    content.push('\n');
+    for pattern in &patterns {
+        content.push_str(pattern);
+        content.push('\n');
+    }
+    content.push_str(EXCLUDE_END);
+    content.push('\n');
+
+    if let Some(parent) = exclude_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create git info directory: {}", e))?;
+    }
+    fs::write(&exclude_path, content)
+        .map_err(|e| format!("Failed to write git exclude file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, rel: &str) -> PathBuf {
+        let path = dir.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "generated").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_finish_pipeline_archives_only_created_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let wd = dir.to_str().unwrap();
+
+        let pad = write(dir, ".claude/scratchpads/p1.md");
+        record(wd, &pad, GeneratedFileKind::Scratchpad, true, Some("p1"));
+        let claude_md = write(dir, "CLAUDE.md");
+        record(
+            wd,
+            &claude_md,
+            GeneratedFileKind::ClaudeMd,
+            false,
+            Some("p1"),
+        );
+        let other = write(dir, ".claude/scratchpads/p2.md");
+        record(wd, &other, GeneratedFileKind::Scratchpad, true, Some("p2"));
+
+        let handled = finish_pipeline(wd, "p1", CompletionAction::Archive).unwrap();
+        assert_eq!(handled, 1);
+        assert!(!pad.exists());
+        assert!(dir.join(".commander/p1/.claude/scratchpads/p1.md").exists());
+        assert!(claude_md.exists());
+        assert!(other.exists());
+
+        let paths: Vec<String> = list_generated_files(wd)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert!(paths.contains(&".commander/p1/.claude/scratchpads/p1.md".to_string()));
+        assert!(paths.contains(&"CLAUDE.md".to_string()));
+    }
+
+    #[test]
+    fn test_cleanup_removes_files_and_empty_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let wd = dir.to_str().unwrap();
+
+        let skill_dir = dir.join(".claude/skills/api-guidelines");
+        write(dir, ".claude/skills/api-guidelines/SKILL.md");
+        record(wd, &skill_dir, GeneratedFileKind::Skill, true, None);
+        assign_to_pipeline(wd, &skill_dir, "p1");
+
+        assert_eq!(
+            finish_pipeline(wd, "p1", CompletionAction::Cleanup).unwrap(),
+            1
+        );
+        assert!(!dir.join(".claude").exists());
+        assert!(list_generated_files(wd).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_forget_drops_entries_and_an_empty_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let wd = dir.to_str().unwrap();
+
+        let first = write(dir, ".commander/prompt-files/a.md");
+        let second = write(dir, ".commander/prompt-files/b.md");
+        record(wd, &first, GeneratedFileKind::PromptFile, true, None);
+        record(wd, &second, GeneratedFileKind::PromptFile, true, None);
+
+        fs::remove_file(&first).unwrap();
+        forget(wd, &[first]);
+        let paths: Vec<String> = list_generated_files(wd)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(paths, vec![".commander/prompt-files/b.md"]);
+
+        fs::remove_file(&second).unwrap();
+        forget(wd, &[second]);
+        assert!(!dir.join(COMMANDER_DIR).exists());
+    }
+
+    #[test]
+    fn test_exclude_block_is_replaced_not_duplicated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "*.log\n").unwrap();
+        let wd = root.join("app");
+        fs::create_dir_all(&wd).unwrap();
+
+        let files = vec![GeneratedFile {
+            path: ".claude/agents/reviewer.md".to_string(),
+            kind: GeneratedFileKind::Subagent,
+            created: true,
+            pipeline_id: None,
+            recorded_at: 0,
+        }];
+        sync_git_exclude(&wd, &files).unwrap();
+        sync_git_exclude(&wd, &files).unwrap();
+
+        let content = fs::read_to_string(root.join(".git/info/exclude")).unwrap();
+        assert!(content.starts_with("*.log\n"));
+        assert_eq!(content.matches(EXCLUDE_BEGIN).count(), 1);
+        assert!(content.contains("/app/.commander/\n"));
+        assert!(content.contains("/app/.claude/agents/reviewer.md\n"));
+    }
+
+    #[test]
+    fn test_snapshot_artifact_reads_every_file_of_a_skill() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        write(dir, ".claude/skills/testing/SKILL.md");
+        write(dir, ".claude/skills/testing/scripts/run.sh");
+
+        let skill_dir = dir.join(".claude/skills/testing");
+        let artifact = snapshot_artifact(GeneratedFileKind::Skill, "testing", &skill_dir).unwrap();
+        let content = artifact.content.unwrap();
+        assert_eq!(
+            content,
+            "--- SKILL.md ---\ngenerated\n--- scripts/run.sh ---\ngenerated"
+        );
+        assert_eq!(artifact.size_bytes, content.len() as i64);
+        assert_eq!(artifact.content_hash, template_hash(&content));
+        assert_eq!(artifact.kind, "skill");
+        assert!(snapshot_artifact(GeneratedFileKind::Skill, "gone", &dir.join("gone")).is_err());
+    }
+}
@@ -1,6 +1,13 @@
-use crate::types::GitHubContext;
+use crate::types::{AgentWorktree, GitHubContext};
 use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a cached GitHub context is served before a refresh is scheduled
+pub const GITHUB_CONTEXT_TTL: Duration = Duration::from_secs(60);
 
 /// Parse a GitHub URL to extract owner and repo
 /// Supports formats:
@@ -88,6 +95,122 @@ pub fn get_remote_url(path: &str) -> Option<String> {
     }
 }
 
+/// Directory, relative to the repository root, agent worktrees are added under
+pub const WORKTREE_DIR: &str = ".grove/worktrees";
+
+/// Branch checked out in an agent's worktree
+pub fn worktree_branch(agent_id: &str) -> String {
+    format!("grove/{}", agent_id)
+}
+
+/// Run git in a directory, returning its stdout or its error output
+fn run_git(path: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Keep the worktrees directory out of the repository's `git status`
+fn exclude_worktree_dir(repo_path: &str) -> Result<(), String> {
+    let git_dir = run_git(repo_path, &["rev-parse", "--git-common-dir"])?;
+    let exclude = Path::new(repo_path)
+        .join(git_dir.trim())
+        .join("info")
+        .join("exclude");
+    let current = std::fs::read_to_string(&exclude).unwrap_or_default();
+    if current.lines().any(|line| line.trim() == "/.grove/") {
+        return Ok(());
+    }
+
+    if let Some(dir) = exclude.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let separator = if current.is_empty() || current.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    std::fs::write(&exclude, format!("{}{}/.grove/\n", current, separator))
+        .map_err(|e| format!("Failed to update {:?}: {}", exclude, e))
+}
+
+/// Add a worktree for an agent under the root of the repository `path` is in,
+/// on a new branch from HEAD. Refused when `path` isn't in a git repository
+/// or the repository has uncommitted changes to tracked files, which the
+/// worktree wouldn't get (untracked files don't matter).
+pub fn create_worktree(path: &str, agent_id: &str) -> Result<AgentWorktree, String> {
+    if !detect_git_repo(path) {
+        return Err(format!("{} is not a git repository", path));
+    }
+    let repo_path = run_git(path, &["rev-parse", "--show-toplevel"])?
+        .trim()
+        .to_string();
+    exclude_worktree_dir(&repo_path)?;
+
+    let changed = run_git(&repo_path, &["status", "--porcelain"])?
+        .lines()
+        .filter(|line| !line.starts_with("??"))
+        .count();
+    if changed > 0 {
+        return Err(format!(
+            "{} has uncommitted changes in {} files, which a worktree would not include. Commit or stash them first.",
+            repo_path, changed
+        ));
+    }
+
+    let worktree_path = Path::new(&repo_path).join(WORKTREE_DIR).join(agent_id);
+    let worktree_path = worktree_path
+        .to_str()
+        .ok_or("Worktree path is not valid UTF-8")?
+        .to_string();
+    let branch = worktree_branch(agent_id);
+    run_git(
+        &repo_path,
+        &["worktree", "add", &worktree_path, "-b", &branch],
+    )
+    .map_err(|e| format!("Failed to create worktree {}: {}", worktree_path, e))?;
+
+    Ok(AgentWorktree {
+        repo_path,
+        path: worktree_path,
+        branch,
+    })
+}
+
+/// What remove_worktree did with an agent's worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorktreeRemoval {
+    /// Removed, with its branch unless that has commits HEAD doesn't
+    Removed { branch_deleted: bool },
+    /// Left in place with its branch: it has uncommitted changes in this many files
+    KeptDirty(usize),
+}
+
+/// Remove an agent's worktree and delete its branch unless it has commits
+/// HEAD doesn't. A worktree with uncommitted changes (untracked files
+/// included) is kept, so the agent's work isn't lost.
+pub fn remove_worktree(worktree: &AgentWorktree) -> Result<WorktreeRemoval, String> {
+    let changed = run_git(&worktree.path, &["status", "--porcelain"])?
+        .lines()
+        .count();
+    if changed > 0 {
+        return Ok(WorktreeRemoval::KeptDirty(changed));
+    }
+    run_git(&worktree.repo_path, &["worktree", "remove", &worktree.path])
+        .map_err(|e| format!("Failed to remove worktree {}: {}", worktree.path, e))?;
+    Ok(WorktreeRemoval::Removed {
+        branch_deleted: run_git(&worktree.repo_path, &["branch", "-d", &worktree.branch]).is_ok(),
+    })
+}
+
 /// Build GitHub context from a directory
 /// First checks for a git repo, then extracts GitHub info
 pub fn build_github_context(path: &str, provided_url: Option<String>) -> Option<GitHubContext> {
@@ -125,6 +248,71 @@ pub fn build_github_context(path: &str, provided_url: Option<String>) -> Option<
     })
 }
 
+/// A GitHub context looked up from the cache
+#[derive(Debug, Clone)]
+pub struct CachedGitHubContext {
+    pub context: Option<GitHubContext>,
+    /// False once the entry is older than the TTL and should be refreshed
+    pub is_fresh: bool,
+}
+
+/// Cache key: working directory plus the explicitly provided URL, if any
+type CacheKey = (String, Option<String>);
+
+/// Per-directory cache of GitHub contexts
+///
+/// Building a context shells out to git several times, which is slow in
+/// large repos. Agents spawn with whatever is cached and refresh it in the
+/// background.
+pub struct GitHubContextCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<CacheKey, (Option<GitHubContext>, Instant)>>,
+}
+
+impl GitHubContextCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the cached context for a directory, stale or not
+    pub fn get(&self, path: &str, provided_url: Option<&str>) -> Option<CachedGitHubContext> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&(path.to_string(), provided_url.map(String::from)))
+            .map(|(context, fetched_at)| CachedGitHubContext {
+                context: context.clone(),
+                is_fresh: fetched_at.elapsed() < self.ttl,
+            })
+    }
+
+    /// Store a freshly built context
+    pub fn insert(&self, path: &str, provided_url: Option<&str>, context: Option<GitHubContext>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            (path.to_string(), provided_url.map(String::from)),
+            (context, Instant::now()),
+        );
+    }
+}
+
+/// Global cache shared by all agent spawns
+pub fn github_context_cache() -> &'static GitHubContextCache {
+    static CACHE: OnceLock<GitHubContextCache> = OnceLock::new();
+    CACHE.get_or_init(|| GitHubContextCache::new(GITHUB_CONTEXT_TTL))
+}
+
+/// Build the GitHub context for a directory and update the cache
+///
+/// This runs git commands synchronously; call it from a blocking task.
+pub fn refresh_github_context(path: &str, provided_url: Option<&str>) -> Option<GitHubContext> {
+    let context = build_github_context(path, provided_url.map(String::from));
+    github_context_cache().insert(path, provided_url, context.clone());
+    context
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,4 +345,99 @@ mod tests {
     fn test_parse_github_url_invalid() {
         assert!(parse_github_url("not a github url").is_none());
     }
+
+    #[test]
+    fn test_context_cache_expires_after_ttl() {
+        let cache = GitHubContextCache::new(Duration::ZERO);
+        assert!(cache.get("/repo", None).is_none());
+
+        cache.insert("/repo", None, None);
+        let cached = cache.get("/repo", None).unwrap();
+        assert!(cached.context.is_none());
+        assert!(!cached.is_fresh);
+
+        let cache = GitHubContextCache::new(Duration::from_secs(60));
+        cache.insert("/repo", Some("https://github.com/o/r"), None);
+        assert!(
+            cache
+                .get("/repo", Some("https://github.com/o/r"))
+                .unwrap()
+                .is_fresh
+        );
+        assert!(cache.get("/repo", None).is_none());
+    }
+
+    #[test]
+    fn test_worktree_is_created_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = std::fs::canonicalize(dir.path()).unwrap();
+        let repo = repo.to_str().unwrap();
+        let git = |args: &[&str]| {
+            let mut all = vec!["-c", "user.name=test", "-c", "user.email=test@example.com"];
+            all.extend_from_slice(args);
+            run_git(repo, &all).unwrap();
+        };
+
+        assert_eq!(
+            create_worktree(repo, "a1").unwrap_err(),
+            format!("{} is not a git repository", repo)
+        );
+
+        git(&["init", "-q"]);
+        std::fs::write(dir.path().join("README.md"), "hello").unwrap();
+        git(&["add", "README.md"]);
+        git(&["commit", "-q", "-m", "init"]);
+
+        std::fs::write(dir.path().join("README.md"), "edited").unwrap();
+        let err = create_worktree(repo, "a1").unwrap_err();
+        assert!(err.contains("uncommitted changes"), "{}", err);
+        git(&["checkout", "README.md"]);
+
+        // Untracked files don't block a worktree
+        std::fs::write(dir.path().join("notes.txt"), "scratch").unwrap();
+
+        let worktree = create_worktree(repo, "a1").unwrap();
+        assert_eq!(worktree.repo_path, repo);
+        assert_eq!(worktree.branch, "grove/a1");
+        assert!(Path::new(&worktree.path).join("README.md").exists());
+
+        // Other agents can still get worktrees: the first one doesn't dirty the repo
+        let second = create_worktree(repo, "a2").unwrap();
+
+        // Uncommitted changes keep the worktree and its branch
+        std::fs::write(Path::new(&worktree.path).join("README.md"), "work").unwrap();
+        assert_eq!(
+            remove_worktree(&worktree).unwrap(),
+            WorktreeRemoval::KeptDirty(1)
+        );
+        assert!(Path::new(&worktree.path).join("README.md").exists());
+        assert!(run_git(repo, &["rev-parse", "--verify", "grove/a1"]).is_ok());
+
+        run_git(&worktree.path, &["checkout", "README.md"]).unwrap();
+        assert_eq!(
+            remove_worktree(&worktree).unwrap(),
+            WorktreeRemoval::Removed {
+                branch_deleted: true
+            }
+        );
+        assert!(!Path::new(&worktree.path).exists());
+        assert!(run_git(repo, &["rev-parse", "--verify", "grove/a1"]).is_err());
+
+        // A branch with commits of its own is kept
+        std::fs::write(Path::new(&second.path).join("NEW.md"), "new").unwrap();
+        let in_second = |args: &[&str]| {
+            let mut all = vec!["-c", "user.name=test", "-c", "user.email=test@example.com"];
+            all.extend_from_slice(args);
+            run_git(&second.path, &all).unwrap();
+        };
+        in_second(&["add", "NEW.md"]);
+        in_second(&["commit", "-q", "-m", "new"]);
+        assert_eq!(
+            remove_worktree(&second).unwrap(),
+            WorktreeRemoval::Removed {
+                branch_deleted: false
+            }
+        );
+        assert!(run_git(repo, &["rev-parse", "--verify", "grove/a2"]).is_ok());
+    }
 }
@@ -0,0 +1,108 @@
+//! Bearer token checks for hook server routes
+//!
+//! HOOK_SERVER_TOKEN is sent as `Authorization: Bearer <token>`. /metrics is
+//! open when no token is configured; routes that read or change commander
+//! state (the question API) are refused unless a token is configured and
+//! sent. Agents don't get the token in their environment, so a worker can't
+//! use these routes even though it knows the port.
+
+use axum::http::{header, HeaderMap, StatusCode};
+
+use crate::commands::env_registry;
+
+/// Token required by authenticated hook server routes, if configured
+pub(crate) fn auth_token() -> Option<String> {
+    env_registry::HOOK_SERVER_TOKEN
+        .var()
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+}
+
+/// Compare two tokens in time that depends only on their length, so the
+/// comparison doesn't reveal how much of a guess was right
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Check the bearer token against the configured one (anything passes when
+/// none is configured)
+pub(crate) fn is_authorized(headers: &HeaderMap, expected: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| tokens_match(token.trim().as_bytes(), expected.as_bytes()))
+}
+
+/// Check a route that must not be open: 403 when no token is configured,
+/// 401 when the request doesn't carry it
+pub(crate) fn require_token(headers: &HeaderMap, expected: Option<&str>) -> Result<(), StatusCode> {
+    let Some(expected) = expected else {
+        return Err(StatusCode::FORBIDDEN);
+    };
+    if !is_authorized(headers, Some(expected)) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_bearer_token_check() {
+        let mut headers = HeaderMap::new();
+        assert!(is_authorized(&headers, None));
+        assert!(!is_authorized(&headers, Some("secret")));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer wrong"),
+        );
+        assert!(!is_authorized(&headers, Some("secret")));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        assert!(is_authorized(&headers, Some("secret")));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("secret"));
+        assert!(!is_authorized(&headers, Some("secret")));
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match(b"secret", b"secret"));
+        assert!(!tokens_match(b"secreT", b"secret"));
+        assert!(!tokens_match(b"secret1", b"secret"));
+        assert!(!tokens_match(b"", b"secret"));
+    }
+
+    #[test]
+    fn test_required_token_refuses_without_configuration() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(require_token(&headers, None), Err(StatusCode::FORBIDDEN));
+        assert_eq!(
+            require_token(&headers, Some("secret")),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        assert_eq!(require_token(&headers, Some("secret")), Ok(()));
+        assert_eq!(require_token(&headers, None), Err(StatusCode::FORBIDDEN));
+    }
+}
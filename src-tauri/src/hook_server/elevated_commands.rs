@@ -10,6 +10,7 @@ use axum::{
 };
 use std::sync::Arc;
 
+use crate::agent_runs_db::ElevatedCommandPolicy;
 use crate::elevation::{
     classify_risk_level, extract_inner_command, generate_warnings, parse_compound_command,
 };
@@ -36,6 +37,27 @@ pub(crate) async fn handle_elevated_request(
     let now = chrono::Utc::now().timestamp_millis();
     let request_id = uuid::Uuid::new_v4().to_string();
 
+    // The agent's permission profile may deny elevated commands outright
+    let profile = state
+        .agent_manager
+        .lock()
+        .await
+        .applied_profile(&request.agent_id)
+        .await;
+    if let Some(profile) = profile.filter(|p| p.elevated_commands == ElevatedCommandPolicy::Deny) {
+        eprintln!(
+            "[Elevated] Denied '{}' for agent {}: permission profile '{}' denies elevated commands",
+            request.command, request.agent_id, profile.name
+        );
+        return (
+            StatusCode::OK,
+            Json(ElevatedCommandRequestResponse {
+                request_id,
+                status: "denied".to_string(),
+            }),
+        );
+    }
+
     // Rate limiting: check how many pending requests this agent has
     {
         let pending = state.pending_elevated.lock().await;
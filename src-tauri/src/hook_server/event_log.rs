@@ -0,0 +1,111 @@
+//! Hook event log
+//!
+//! Each event posted to /hook is logged to the runs database with the agent it
+//! was matched to and how long it took to handle, so whether hooks fired for
+//! an agent can be checked. Agents that have sent at least one event are also
+//! remembered in memory, which is what the hooks-missing flag on AgentInfo is
+//! based on.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+
+use crate::agent_runs_db::{AgentRunsDB, HookEventRecord};
+use crate::utils::time::now_millis;
+
+/// Agents that have sent a hook event since startup
+static AGENTS_WITH_HOOK_EVENTS: OnceLock<StdMutex<HashSet<String>>> = OnceLock::new();
+
+fn agents_with_hook_events() -> &'static StdMutex<HashSet<String>> {
+    AGENTS_WITH_HOOK_EVENTS.get_or_init(|| StdMutex::new(HashSet::new()))
+}
+
+/// Whether any hook event has been matched to this agent since startup
+pub fn has_hook_events(agent_id: &str) -> bool {
+    agents_with_hook_events()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(agent_id)
+}
+
+/// How a hook event was matched to an agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HookMatch {
+    /// The session was in the session map
+    Session,
+    /// The session wasn't mapped yet; the agent_id query parameter was used
+    AgentIdParam,
+    Unmatched,
+}
+
+impl HookMatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookMatch::Session => "session",
+            HookMatch::AgentIdParam => "agent_id",
+            HookMatch::Unmatched => "unmatched",
+        }
+    }
+}
+
+/// Log one handled hook event. The write happens in the background so the
+/// agent's CLI isn't kept waiting on the database.
+pub(crate) fn record_hook_event(
+    runs_db: Option<Arc<AgentRunsDB>>,
+    agent_id: Option<&str>,
+    session_id: &str,
+    event_name: &str,
+    tool_name: Option<&str>,
+    matched_by: HookMatch,
+    latency_ms: i64,
+) {
+    if let Some(agent_id) = agent_id {
+        agents_with_hook_events()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(agent_id.to_string());
+    }
+
+    let Some(runs_db) = runs_db else {
+        return;
+    };
+    let record = HookEventRecord {
+        id: None,
+        agent_id: agent_id.map(str::to_string),
+        session_id: session_id.to_string(),
+        event_name: event_name.to_string(),
+        tool_name: tool_name.map(str::to_string),
+        matched_by: matched_by.as_str().to_string(),
+        latency_ms,
+        created_at: now_millis(),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = runs_db.record_hook_event(&record).await {
+            eprintln!("[hook_server] Failed to log hook event: {}", e);
+        }
+    });
+}
+
+/// Whether hooks are reaching the hook server
+#[derive(Debug, Clone, Serialize)]
+pub struct HookHealth {
+    /// Events in the retained log
+    pub events_received: usize,
+    /// Events whose session wasn't mapped to any agent
+    pub unmatched_session_events: usize,
+    pub last_event_at: Option<i64>,
+    pub last_event_age_ms: Option<i64>,
+}
+
+pub async fn hook_health(runs_db: &AgentRunsDB) -> Result<HookHealth, String> {
+    let totals = runs_db
+        .get_hook_event_totals()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(HookHealth {
+        events_received: totals.events_received,
+        unmatched_session_events: totals.unmatched_session_events,
+        last_event_at: totals.last_event_at,
+        last_event_age_ms: totals.last_event_at.map(|at| now_millis() - at),
+    })
+}
@@ -0,0 +1,92 @@
+//! Prometheus metrics endpoint for the hook server
+//!
+//! Served at GET /metrics when METRICS_ENABLED is set. When HOOK_SERVER_TOKEN
+//! is set, scrapers must send it as `Authorization: Bearer <token>`.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::commands::env_registry;
+use crate::memory_footprint;
+use crate::metrics::{self, GaugeSnapshot};
+
+use super::auth::{auth_token, is_authorized};
+use super::HookServerState;
+
+/// Whether METRICS_ENABLED is set
+pub(crate) fn metrics_enabled() -> bool {
+    env_registry::METRICS_ENABLED.flag()
+}
+
+/// Read the gauges from the agent manager, pipelines and runs database
+async fn collect_gauges(state: &HookServerState) -> GaugeSnapshot {
+    let mut gauges = GaugeSnapshot::default();
+
+    let (agents, runs_db, buffers) = {
+        let manager = state.agent_manager.lock().await;
+        (
+            manager.agents.clone(),
+            manager.runs_db.clone(),
+            manager.output_buffer_footprints().await,
+        )
+    };
+    let footprint = memory_footprint::snapshot(buffers);
+    gauges.memory_bytes.insert(
+        "agent_outputs".to_string(),
+        footprint.agent_output_bytes as u64,
+    );
+    gauges.memory_bytes.insert(
+        "conversations".to_string(),
+        footprint.conversation_bytes as u64,
+    );
+    for process in agents.lock().await.values() {
+        let info = &process.info;
+        *gauges
+            .agents
+            .entry((
+                info.status.as_str().to_string(),
+                info.source.as_str().to_string(),
+            ))
+            .or_default() += 1;
+    }
+
+    if let Some(ref manager) = state.auto_pipeline_manager {
+        for pipeline in manager.lock().await.list_pipelines().await {
+            *gauges.pipelines.entry(pipeline.status).or_default() += 1;
+        }
+    }
+
+    if let Some(db) = runs_db {
+        match db.get_today_cost().await {
+            Ok(cost) => gauges.cost_today_usd = Some(cost),
+            Err(e) => eprintln!(
+                "[hook_server] Failed to read today's cost for metrics: {}",
+                e
+            ),
+        }
+    }
+
+    gauges
+}
+
+/// Application metrics in the Prometheus text format
+/// GET /metrics
+pub(crate) async fn handle_metrics(
+    State(state): State<Arc<HookServerState>>,
+    headers: HeaderMap,
+) -> Response {
+    if !is_authorized(&headers, auth_token().as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let gauges = collect_gauges(&state).await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::global().render(&gauges),
+    )
+        .into_response()
+}
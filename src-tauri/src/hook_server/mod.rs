@@ -1,4 +1,8 @@
+mod auth;
 mod elevated_commands;
+mod event_log;
+mod metrics_api;
+mod question_api;
 mod tool_tracking;
 
 use axum::{
@@ -10,6 +14,8 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
+use crate::auto_pipeline::AutoPipelineManager;
+use crate::meta_agent::tools::PendingQuestion;
 use crate::security_monitor::SecurityMonitor;
 use crate::types::PendingElevatedCommand;
 
@@ -17,6 +23,7 @@ use crate::types::PendingElevatedCommand;
 pub use elevated_commands::{
     approve_elevated_request, deny_elevated_request, get_pending_elevated_commands,
 };
+pub use event_log::{has_hook_events, hook_health, HookHealth};
 
 /// Type alias for agent todo storage
 type AgentTodoStorage = Arc<Mutex<HashMap<String, Vec<AgentTodoItem>>>>;
@@ -45,8 +52,27 @@ pub async fn get_all_agent_todos() -> HashMap<String, Vec<AgentTodoItem>> {
     }
 }
 
+/// Capacity of the todo update channel; slow subscribers skip to newer lists
+const TODO_UPDATES_CAPACITY: usize = 64;
+
+/// Todo lists broadcast as agents write them (agent_id, todo items)
+static AGENT_TODO_UPDATES: std::sync::OnceLock<
+    tokio::sync::broadcast::Sender<(String, Vec<AgentTodoItem>)>,
+> = std::sync::OnceLock::new();
+
+fn todo_updates() -> &'static tokio::sync::broadcast::Sender<(String, Vec<AgentTodoItem>)> {
+    AGENT_TODO_UPDATES.get_or_init(|| tokio::sync::broadcast::channel(TODO_UPDATES_CAPACITY).0)
+}
+
+/// Subscribe to todo list updates for all agents
+pub fn subscribe_agent_todos() -> tokio::sync::broadcast::Receiver<(String, Vec<AgentTodoItem>)> {
+    todo_updates().subscribe()
+}
+
 // Re-export the handler functions for internal router use
 use elevated_commands::{handle_elevated_request, handle_elevated_status, handle_scope_check};
+use metrics_api::{handle_metrics, metrics_enabled};
+use question_api::{handle_answer_question, handle_get_question};
 use tool_tracking::handle_hook;
 
 /// A single task/todo item from an agent's TodoWrite call
@@ -69,6 +95,10 @@ pub struct HookServerState {
     pub(crate) approved_scopes: Arc<Mutex<HashMap<String, i64>>>,
     /// Latest todo list per agent (agent_id -> todo items)
     pub(crate) agent_todos: Arc<Mutex<HashMap<String, Vec<AgentTodoItem>>>>,
+    /// Question the meta-agent is blocked on (shared with AppState)
+    pub(crate) pending_question: Arc<Mutex<Option<PendingQuestion>>>,
+    /// Auto-pipeline manager, for the pipeline gauges on /metrics
+    pub(crate) auto_pipeline_manager: Option<Arc<Mutex<AutoPipelineManager>>>,
 }
 
 /// Start the hook server on the specified port
@@ -76,6 +106,9 @@ pub struct HookServerState {
 /// This server handles:
 /// - Tool use hooks from Claude agents (PreToolUse, PostToolUse)
 /// - Elevated command approval requests from wrapper scripts
+/// - Reading and answering the meta-agent's pending question (REST companion
+///   API, requires HOOK_SERVER_TOKEN)
+/// - Prometheus metrics at /metrics when METRICS_ENABLED is set
 pub async fn start_hook_server(
     agent_manager: Arc<Mutex<AgentManager>>,
     app_handle: Arc<dyn crate::events::AppEventEmitter>,
@@ -83,6 +116,8 @@ pub async fn start_hook_server(
     security_monitor: Option<Arc<SecurityMonitor>>,
     pending_elevated: Arc<Mutex<HashMap<String, PendingElevatedCommand>>>,
     approved_scopes: Arc<Mutex<HashMap<String, i64>>>,
+    pending_question: Arc<Mutex<Option<PendingQuestion>>>,
+    auto_pipeline_manager: Option<Arc<Mutex<AutoPipelineManager>>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let agent_todos = Arc::new(Mutex::new(HashMap::new()));
 
@@ -97,16 +132,26 @@ pub async fn start_hook_server(
         pending_elevated,
         approved_scopes,
         agent_todos,
+        pending_question,
+        auto_pipeline_manager,
     });
 
-    let app = Router::new()
+    let mut app = Router::new()
         // Tool tracking endpoint
         .route("/hook", post(handle_hook))
         // Elevation API endpoints for wrapper scripts
         .route("/elevated/request", post(handle_elevated_request))
         .route("/elevated/status/:id", get(handle_elevated_status))
         .route("/elevated/check-scope/:hash", get(handle_scope_check))
-        .with_state(state);
+        // Meta-agent question API for companion clients
+        .route("/meta/question", get(handle_get_question))
+        .route("/meta/question/answer", post(handle_answer_question));
+
+    // Application metrics for Prometheus scrapers (opt-in)
+    if metrics_enabled() {
+        app = app.route("/metrics", get(handle_metrics));
+    }
+    let app = app.with_state(state);
 
     let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
     println!("Hook server listening on port {}", port);
@@ -0,0 +1,102 @@
+//! Meta-agent question API for the hook server
+//!
+//! Lets companion clients (scripts, phones on a tunnel, other tools) see the
+//! question the commander is blocked on and answer it without the desktop UI.
+//! Both routes require HOOK_SERVER_TOKEN and are refused when it isn't set.
+//! Answers are refused (403) while observer mode is on.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::meta_agent::tools::{answer_pending_question, AnswerSource, PendingQuestionInfo};
+use crate::observer_mode;
+
+use super::auth::{auth_token, require_token};
+use super::HookServerState;
+
+/// Body for POST /meta/question/answer
+#[derive(Debug, Deserialize)]
+pub(crate) struct AnswerQuestionRequest {
+    /// Must match the pending question when given
+    pub question_id: Option<String>,
+    pub answer: String,
+}
+
+/// Response for POST /meta/question/answer
+#[derive(Debug, Serialize)]
+pub(crate) struct AnswerQuestionResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Get the pending question, if any
+/// GET /meta/question
+pub(crate) async fn handle_get_question(
+    State(state): State<Arc<HookServerState>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = require_token(&headers, auth_token().as_deref()) {
+        return status.into_response();
+    }
+    let pending = state.pending_question.lock().await;
+    let info: Option<PendingQuestionInfo> = pending.as_ref().map(|pq| pq.info.clone());
+    Json(info).into_response()
+}
+
+/// Answer the pending question
+/// POST /meta/question/answer
+pub(crate) async fn handle_answer_question(
+    State(state): State<Arc<HookServerState>>,
+    headers: HeaderMap,
+    Json(request): Json<AnswerQuestionRequest>,
+) -> Response {
+    if let Err(status) = require_token(&headers, auth_token().as_deref()) {
+        return status.into_response();
+    }
+    if request.answer.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(AnswerQuestionResponse {
+                success: false,
+                error: Some("answer is required".to_string()),
+            }),
+        )
+            .into_response();
+    }
+
+    match answer_pending_question(
+        &state.pending_question,
+        request.question_id.as_deref(),
+        request.answer,
+        AnswerSource::Rest,
+    )
+    .await
+    {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(AnswerQuestionResponse {
+                success: true,
+                error: None,
+            }),
+        ),
+        Err(e) => (
+            if e == observer_mode::OBSERVER_MODE_MESSAGE {
+                StatusCode::FORBIDDEN
+            } else {
+                StatusCode::CONFLICT
+            },
+            Json(AnswerQuestionResponse {
+                success: false,
+                error: Some(e),
+            }),
+        ),
+    }
+    .into_response()
+}
@@ -1,7 +1,10 @@
 //! Tool tracking for Claude agent hooks
 //!
 //! Handles PreToolUse and PostToolUse events from Claude agents,
-//! tracking tool call duration and emitting events to the frontend.
+//! tracking tool call duration (added to the agent's per-tool timings) and
+//! emitting events to the frontend.
+//! Stop events start the agent's idle grace period. Every event is logged,
+//! see event_log.rs.
 
 use axum::{
     extract::{Query, State},
@@ -10,11 +13,14 @@ use axum::{
 };
 use serde::Deserialize;
 use std::sync::Arc;
+use std::time::Instant;
 
+use crate::fault_injection::{check_fault, FaultPoint};
 use crate::security_monitor::{SecurityEvent, SecurityEventMetadata, SecurityEventType};
 use crate::types::{AgentActivityDetailEvent, HookInput, ToolEventPayload};
 use crate::utils::string::truncate_with_ellipsis;
 
+use super::event_log::{record_hook_event, HookMatch};
 use super::{AgentTodoItem, HookServerState};
 
 /// Query parameters for the hook endpoint
@@ -41,11 +47,19 @@ pub(crate) async fn handle_hook(
     Query(params): Query<HookQueryParams>,
     Json(input): Json<HookInput>,
 ) -> StatusCode {
+    crate::metrics::global().record_hook_event(&input.hook_event_name);
+    let started = Instant::now();
+
     // Find agent by session_id, with fallback to agent_id from query params
     // This handles the race condition where hooks arrive before session is mapped
     let agent_manager = state.agent_manager.lock().await;
-    let agent_id = match agent_manager.get_agent_by_session(&input.session_id).await {
-        Some(id) => id,
+    if let Err(e) = check_fault(&agent_manager.fault_hook, FaultPoint::HookServerError) {
+        eprintln!("[hook_server] {}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    let runs_db = agent_manager.runs_db.clone();
+    let (agent_id, matched_by) = match agent_manager.get_agent_by_session(&input.session_id).await {
+        Some(id) => (id, HookMatch::Session),
         None => {
             // Session not mapped yet, try using agent_id from query params
             match params.agent_id {
@@ -53,10 +67,19 @@ pub(crate) async fn handle_hook(
                     // Also register this session mapping for future lookups
                     let mut session_map = agent_manager.session_to_agent.lock().await;
                     session_map.insert(input.session_id.clone(), id.clone());
-                    id
+                    (id, HookMatch::AgentIdParam)
                 }
                 None => {
-                    // No agent_id available, just acknowledge
+                    // No agent_id available, just log and acknowledge
+                    record_hook_event(
+                        runs_db,
+                        None,
+                        &input.session_id,
+                        &input.hook_event_name,
+                        input.tool_name.as_deref(),
+                        HookMatch::Unmatched,
+                        started.elapsed().as_millis() as i64,
+                    );
                     return StatusCode::OK;
                 }
             }
@@ -64,6 +87,27 @@ pub(crate) async fn handle_hook(
     };
     drop(agent_manager); // Release lock early
 
+    // Stop starts the idle grace period; PreToolUse means another turn is underway
+    match input.hook_event_name.as_str() {
+        "Stop" => {
+            state
+                .agent_manager
+                .lock()
+                .await
+                .schedule_idle_after_stop(&agent_id, state.app_handle.clone())
+                .await
+        }
+        "PreToolUse" => {
+            state
+                .agent_manager
+                .lock()
+                .await
+                .note_agent_activity(&agent_id)
+                .await
+        }
+        _ => {}
+    }
+
     // Only emit tool events for PreToolUse and PostToolUse
     if let Some(tool_name) = &input.tool_name {
         let now = chrono::Utc::now().timestamp_millis();
@@ -83,6 +127,15 @@ pub(crate) async fn handle_hook(
         forward_to_security_monitor(&state, monitor, &agent_id, &input, tool_name).await;
     }
 
+    record_hook_event(
+        runs_db,
+        Some(&agent_id),
+        &input.session_id,
+        &input.hook_event_name,
+        input.tool_name.as_deref(),
+        matched_by,
+        started.elapsed().as_millis() as i64,
+    );
     StatusCode::OK
 }
 
@@ -135,7 +188,9 @@ async fn handle_pre_tool_use(
                     })
                     .collect();
 
-                // Store the todo list for this agent
+                // Store the todo list for this agent and notify subscribers
+                // (no subscribers is not an error)
+                let _ = super::todo_updates().send((agent_id.to_string(), todo_items.clone()));
                 let mut agent_todos = state.agent_todos.lock().await;
                 agent_todos.insert(agent_id.to_string(), todo_items);
             }
@@ -234,6 +289,16 @@ async fn handle_post_tool_use(
     };
     drop(pending);
 
+    // Time spent per tool goes into the agent's statistics
+    if let Some(ms) = execution_time_ms {
+        state
+            .agent_manager
+            .lock()
+            .await
+            .record_tool_timing(agent_id, tool_name, ms)
+            .await;
+    }
+
     // Determine status from response
     let (status, error_message) = if let Some(response) = &input.tool_response {
         // Check if response indicates an error
@@ -0,0 +1,130 @@
+// Shared HTTP client
+//
+// One lazily built reqwest client for the AI providers and the other REST
+// calls, so connections (and their TLS sessions) are pooled and reused across
+// requests, HTTP/2 is negotiated where the server offers it, and every call
+// gets the same connect and request timeouts. Requests sent through send() are
+// timed per host for the /metrics endpoint.
+
+use reqwest::{Client, RequestBuilder, Response};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::commands::env_registry::{self, EnvVar};
+use crate::metrics;
+
+/// Idle pooled connections are closed after this long
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Idle connections kept per host
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// Timeouts and proxy for the shared client
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpClientConfig {
+    /// Time allowed to establish a connection
+    pub connect_timeout: Duration,
+    /// Time allowed for a whole request unless the call sets its own
+    pub request_timeout: Duration,
+    /// Request timeout for orchestrator turns, which can run much longer
+    pub orchestrator_timeout: Duration,
+    /// Proxy for HTTPS requests
+    pub https_proxy: Option<String>,
+}
+
+fn env_secs(var: &EnvVar, default: u64) -> Duration {
+    let secs = var
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(default);
+    Duration::from_secs(secs)
+}
+
+impl HttpClientConfig {
+    pub fn from_env() -> Self {
+        Self {
+            connect_timeout: env_secs(&env_registry::HTTP_CONNECT_TIMEOUT_SECS, 10),
+            request_timeout: env_secs(&env_registry::HTTP_REQUEST_TIMEOUT_SECS, 180),
+            orchestrator_timeout: env_secs(&env_registry::ORCHESTRATOR_REQUEST_TIMEOUT_SECS, 600),
+            https_proxy: env_registry::HTTPS_PROXY
+                .var()
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+        }
+    }
+}
+
+static CONFIG: OnceLock<HttpClientConfig> = OnceLock::new();
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Settings the shared client was built with (read from the environment once)
+pub fn config() -> &'static HttpClientConfig {
+    CONFIG.get_or_init(HttpClientConfig::from_env)
+}
+
+/// The shared client. Cloning it is cheap and shares the connection pool.
+pub fn shared() -> &'static Client {
+    CLIENT.get_or_init(|| build(config()))
+}
+
+/// Build a client with the given settings
+pub fn build(config: &HttpClientConfig) -> Client {
+    let mut builder = Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.request_timeout)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(TCP_KEEPALIVE)
+        .http2_adaptive_window(true);
+
+    if let Some(ref url) = config.https_proxy {
+        match reqwest::Proxy::https(url) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => eprintln!("[HTTP] Ignoring invalid HTTPS_PROXY '{}': {}", url, e),
+        }
+    }
+
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("[HTTP] Failed to build HTTP client, using defaults: {}", e);
+        Client::new()
+    })
+}
+
+/// Send a request, recording its latency under the host it went to
+///
+/// A timeout set on the builder (`RequestBuilder::timeout`) overrides the
+/// client's request timeout for this call.
+pub async fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let host = request.url().host_str().unwrap_or("unknown").to_string();
+
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    let success = matches!(&result, Ok(response) if response.status().is_success());
+    metrics::global().record_http_request(&host, started.elapsed(), success);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_ignores_invalid_proxy() {
+        let config = HttpClientConfig {
+            connect_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_secs(2),
+            orchestrator_timeout: Duration::from_secs(3),
+            https_proxy: Some("not a url".to_string()),
+        };
+        // Falls back to a client without the proxy instead of panicking
+        let client = build(&config);
+        assert!(client.get("https://example.com").build().is_ok());
+    }
+}
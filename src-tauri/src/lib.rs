@@ -5,25 +5,42 @@ pub mod auto_pipeline;
 pub mod claude_client;
 pub mod claudemd_generator;
 pub mod commands;
+pub mod cost_preference;
 pub mod db_utils;
+pub mod directory_locks;
 pub mod elevation;
 pub mod error;
 pub mod events;
+pub mod fault_injection;
 pub mod first_run;
+pub mod generated_files;
 pub mod github;
 pub mod hook_server;
+pub mod http_client;
 pub mod instruction_manager;
 pub mod logger;
+pub mod memory_footprint;
 pub mod meta_agent;
+pub mod metrics;
+pub mod notifications;
+pub mod observer_mode;
+pub mod permission_profiles;
+pub mod prompt_library;
 pub mod security_monitor;
 pub mod skill_generator;
+pub mod stopword;
 pub mod subagent_generator;
+pub mod subsystems;
 pub mod tool_registry;
+#[cfg(desktop)]
+pub mod tray;
 pub mod types;
 pub mod utils;
+pub mod version_info;
 pub mod voice;
+pub mod workspace;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tauri::Manager;
 use tokio::sync::Mutex;
@@ -31,10 +48,13 @@ use tokio::sync::Mutex;
 use agent_manager::AgentManager;
 use agent_runs_db::AgentRunsDB;
 use auto_pipeline::AutoPipelineManager;
+use commands::env_registry;
 use logger::Logger;
+use meta_agent::directory_approval::{DirectoryApprovals, SharedDirectoryApprovals};
 use meta_agent::tools::{AgentWakeSender, PendingQuestion, SleepState};
-use meta_agent::MetaAgent;
+use meta_agent::{ChatTurns, MetaAgent, SharedChatTurns, SharedLoopActivity};
 use security_monitor::{ResponseConfig, SecurityConfig, SecurityMonitor};
+use subsystems::SubsystemStatuses;
 use types::PendingElevatedCommand;
 
 // Make AppState public so commands module can access it
@@ -52,8 +72,15 @@ pub struct AppState {
     // Meta-agent interaction state (accessible without locking meta_agent)
     pub pending_meta_question: Arc<Mutex<Option<PendingQuestion>>>,
     pub meta_sleep_state: Arc<Mutex<SleepState>>,
+    pub directory_approvals: SharedDirectoryApprovals,
     // Wake sender for agents to wake meta-agent from sleep
     pub agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
+    // Meta-agent tool loop state (readable while the loop holds the meta_agent lock)
+    pub meta_loop_activity: SharedLoopActivity,
+    // Active chat turn and messages queued behind it
+    pub chat_turns: SharedChatTurns,
+    // Which subsystems started degraded or failed
+    pub subsystems: Arc<SubsystemStatuses>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -84,15 +111,15 @@ pub fn run() {
 
     // Log loaded model configuration for debugging
     eprintln!("[Startup] Model configuration loaded:");
-    for key in &[
-        "PRIMARY_MODEL",
-        "SECURITY_MODEL",
-        "LIGHT_TASK_MODEL",
-        "CLAUDE_CODE_MODEL",
+    for var in [
+        env_registry::PRIMARY_MODEL,
+        env_registry::SECURITY_MODEL,
+        env_registry::LIGHT_TASK_MODEL,
+        env_registry::CLAUDE_CODE_MODEL,
     ] {
-        match std::env::var(key) {
-            Ok(val) => eprintln!("[Startup]   {} = {}", key, val),
-            Err(_) => eprintln!("[Startup]   {} = <not set>", key),
+        match var.var() {
+            Ok(val) => eprintln!("[Startup]   {} = {}", var.name, val),
+            Err(_) => eprintln!("[Startup]   {} = <not set>", var.name),
         }
     }
 
@@ -113,9 +140,13 @@ pub fn run() {
                 std::fs::create_dir_all(parent).ok();
             }
 
+            // Outcome of each subsystem's initialization, for the UI and meta-agent
+            let statuses = SubsystemStatuses::global().clone();
+
             let logger = match Logger::new(log_db_path.clone()) {
                 Ok(logger) => {
                     println!("✓ Logger initialized at {:?}", log_db_path);
+                    statuses.set_ok(subsystems::LOGGER);
                     Arc::new(logger)
                 }
                 Err(e) => {
@@ -125,6 +156,11 @@ pub fn run() {
                     match Logger::new(temp_log.clone()) {
                         Ok(logger) => {
                             println!("✓ Logger initialized at temp location: {:?}", temp_log);
+                            statuses.set_degraded(
+                                subsystems::LOGGER,
+                                &format!("logging to a temp directory ({})", e),
+                                "make sure the app data directory is writable, then restart",
+                            );
                             Arc::new(logger)
                         }
                         Err(e2) => {
@@ -148,6 +184,7 @@ pub fn run() {
             let agent_runs_db = match AgentRunsDB::new(runs_db_path.clone()) {
                 Ok(db) => {
                     println!("✓ Agent runs database initialized at {:?}", runs_db_path);
+                    statuses.set_ok(subsystems::RUNS_DATABASE);
                     Arc::new(db)
                 }
                 Err(e) => {
@@ -160,6 +197,15 @@ pub fn run() {
                                 "✓ Agent runs database initialized at temp location: {:?}",
                                 temp_db
                             );
+                            statuses.set_degraded(
+                                subsystems::RUNS_DATABASE,
+                                &format!(
+                                    "run history and cost tracking use a temp database that \
+                                     may be lost ({})",
+                                    e
+                                ),
+                                "make sure the app data directory is writable, then restart",
+                            );
                             Arc::new(db)
                         }
                         Err(e2) => panic!(
@@ -170,33 +216,73 @@ pub fn run() {
                 }
             };
 
+            // Resume in the workspace that was active when the app last closed,
+            // before anything loads workspace-scoped files
+            match tauri::async_runtime::block_on(agent_runs_db.last_active_workspace()) {
+                Ok(Some(workspace_id)) => workspace::set_active_workspace(&workspace_id),
+                Ok(None) => {}
+                Err(e) => eprintln!("⚠ Warning: Failed to restore active workspace: {}", e),
+            }
+
             // Reconcile stale runs from previous session
-            // This marks any "running" agents as "crashed" since the app just started
+            // This marks any "running" agents as "crashed" since the app just started,
+            // and tells the frontend so it can offer to resume them
             let runs_db_for_reconcile = agent_runs_db.clone();
+            let reconcile_emitter: Arc<dyn events::AppEventEmitter> =
+                Arc::new(app.handle().clone());
             tauri::async_runtime::spawn(async move {
                 match runs_db_for_reconcile.reconcile_stale_runs().await {
-                    Ok(count) if count > 0 => {
+                    Ok(agent_ids) if !agent_ids.is_empty() => {
                         println!(
                             "✓ Reconciled {} stale agent runs from previous session",
-                            count
+                            agent_ids.len()
                         );
+                        // Their prompt files were never cleaned up; no agent
+                        // is running yet, so every one left is stale
+                        let mut working_dirs = HashSet::new();
+                        for agent_id in &agent_ids {
+                            if let Ok(Some(run)) = runs_db_for_reconcile.get_run(agent_id).await {
+                                working_dirs.insert(run.working_dir);
+                            }
+                        }
+                        for working_dir in working_dirs {
+                            agent_manager::prompt_files::remove_all_prompt_files(&working_dir);
+                        }
+                        for agent_id in agent_ids {
+                            if let Ok(payload) = serde_json::to_value(types::AgentStatusEvent {
+                                agent_id,
+                                status: types::AgentStatus::Error,
+                                info: None,
+                                reason: None,
+                            }) {
+                                let _ = reconcile_emitter.emit("agent:status", payload);
+                            }
+                        }
                     }
                     Ok(_) => {}
                     Err(e) => eprintln!("⚠ Warning: Failed to reconcile stale runs: {}", e),
                 }
             });
 
-            let agent_manager = Arc::new(Mutex::new(AgentManager::with_logger_and_db(
-                hook_port,
-                logger.clone(),
-                agent_runs_db.clone(),
-            )));
+            // Retry writes that previously failed (disk full, lock contention, ...)
+            agent_runs_db.start_dead_letter_retry();
+
+            // Write buffered agent outputs and orchestrator events in batches
+            agent_runs_db.start_event_flusher();
+
+            // Permanently remove trashed runs and conversations past their retention
+            agent_runs_db.start_trash_purge();
+
+            let agent_manager =
+                AgentManager::with_logger_and_db(hook_port, logger.clone(), agent_runs_db.clone());
+            let session_finalizer = agent_manager.session_finalizer.clone();
+            let agent_manager = Arc::new(Mutex::new(agent_manager));
 
             // Initialize meta-agent - infers provider from PRIMARY_MODEL
             let meta_agent = match MetaAgent::new() {
                 Ok(agent) => {
                     // Log which provider and model is being used
-                    let model = std::env::var("PRIMARY_MODEL").ok();
+                    let model = env_registry::PRIMARY_MODEL.var().ok();
                     let is_openai = model
                         .as_ref()
                         .map(|m| {
@@ -209,12 +295,12 @@ pub fn run() {
 
                     let provider_info = if is_openai {
                         format!("OpenAI ({})", model.unwrap_or_else(|| "gpt-4o".to_string()))
-                    } else if std::env::var("ANTHROPIC_API_KEY").is_ok() {
+                    } else if env_registry::ANTHROPIC_API_KEY.var().is_ok() {
                         format!(
                             "Claude ({})",
                             model.unwrap_or_else(|| "claude-sonnet-4-5-20250929".to_string())
                         )
-                    } else if std::env::var("OPENAI_API_KEY").is_ok() {
+                    } else if env_registry::OPENAI_API_KEY.var().is_ok() {
                         format!("OpenAI ({})", model.unwrap_or_else(|| "gpt-4o".to_string()))
                     } else {
                         "Unknown".to_string()
@@ -223,10 +309,16 @@ pub fn run() {
                         "✓ Meta-agent initialized successfully using {}",
                         provider_info
                     );
+                    statuses.set_ok(subsystems::META_AGENT);
                     Arc::new(Mutex::new(agent))
                 }
                 Err(e) => {
                     eprintln!("⚠ Warning: {}. Chat functionality will not work.", e);
+                    statuses.set_failed(
+                        subsystems::META_AGENT,
+                        &e.to_string(),
+                        "set ANTHROPIC_API_KEY or OPENAI_API_KEY in .env, then restart",
+                    );
                     eprintln!("  Set ANTHROPIC_API_KEY or OPENAI_API_KEY environment variable.");
                     // Create a dummy agent with no API key (will fail on first use)
                     Arc::new(Mutex::new(MetaAgent::new_with_client(
@@ -248,16 +340,23 @@ pub fn run() {
             ) {
                 Ok(monitor) => {
                     let monitor = Arc::new(monitor);
+                    session_finalizer.set_security_monitor(&monitor);
                     // Start background analysis loop inside async context
                     let monitor_for_bg = monitor.clone();
                     tauri::async_runtime::spawn(async move {
                         let _ = monitor_for_bg.start_background_analysis().await;
                     });
                     println!("✓ Security monitor initialized");
+                    statuses.set_ok(subsystems::SECURITY_MONITOR);
                     Some(monitor)
                 }
                 Err(e) => {
                     eprintln!("⚠ Warning: Failed to initialize security monitor: {}", e);
+                    statuses.set_failed(
+                        subsystems::SECURITY_MONITOR,
+                        &e,
+                        "agent activity is not being checked; see the startup log, then restart",
+                    );
                     eprintln!("  Security monitoring will be disabled.");
                     None
                 }
@@ -269,13 +368,82 @@ pub fn run() {
             let approved_scopes: Arc<Mutex<HashMap<String, i64>>> =
                 Arc::new(Mutex::new(HashMap::new()));
 
-            // Start hook server (with security monitor and elevation state)
+            // Create shared pending question state (accessible without locking meta_agent,
+            // answerable from the UI, voice and the hook server's REST API)
+            let pending_meta_question: Arc<Mutex<Option<PendingQuestion>>> =
+                Arc::new(Mutex::new(None));
+
+            // Initialize auto-pipeline manager (optional - requires API key)
+            let auto_pipeline_manager = match AutoPipelineManager::new() {
+                Ok(manager) => {
+                    println!("✓ Auto-pipeline manager initialized");
+                    statuses.set_ok(subsystems::AUTO_PIPELINE);
+                    Some(Arc::new(Mutex::new(manager)))
+                }
+                Err(e) => {
+                    eprintln!("⚠ Auto-pipeline manager unavailable (no API key): {}", e);
+                    statuses.set_failed(
+                        subsystems::AUTO_PIPELINE,
+                        &e,
+                        "set OPENAI_API_KEY or ANTHROPIC_API_KEY in .env, then restart",
+                    );
+                    None
+                }
+            };
+
+            // Bring back pipelines that were waiting on the user when the app closed
+            if let Some(manager) = auto_pipeline_manager.clone() {
+                let runs_db = agent_runs_db.clone();
+                tauri::async_runtime::spawn(async move {
+                    match runs_db.list_pipeline_human_requests().await {
+                        Ok(records) if records.is_empty() => {}
+                        Ok(records) => {
+                            let restored =
+                                manager.lock().await.restore_human_requests(records).await;
+                            println!("✓ Restored {} pipeline(s) waiting on an answer", restored);
+                        }
+                        Err(e) => eprintln!("⚠ Warning: Failed to load paused pipelines: {}", e),
+                    }
+                });
+            }
+
+            // Evict finished pipelines from memory once past their retention
+            if let Some(manager) = auto_pipeline_manager.clone() {
+                auto_pipeline::retention::start_gc(manager, agent_runs_db.clone());
+            }
+
+            // Start hook server (with security monitor, elevation, question state and pipelines)
             let agent_manager_clone = agent_manager.clone();
             let app_handle = Arc::new(app.handle().clone());
+
+            // Alert when recent API spend is far above its usual level
+            agent_runs_db.start_cost_anomaly_check(app_handle.clone());
+
+            // Release directory locks left by pipelines of an earlier run, except
+            // those of pipelines restored waiting on the user
+            // (before any pipeline can start and take a lock)
+            {
+                let restored: HashSet<String> = match tauri::async_runtime::block_on(
+                    agent_runs_db.list_pipeline_human_requests(),
+                ) {
+                    Ok(records) => records.into_iter().map(|r| r.pipeline_id).collect(),
+                    Err(e) => {
+                        eprintln!("⚠ Warning: Failed to load paused pipelines: {}", e);
+                        HashSet::new()
+                    }
+                };
+                let released = directory_locks::release_stale(&restored, app_handle.as_ref());
+                if released > 0 {
+                    println!("✓ Released {} stale directory lock(s)", released);
+                }
+            }
             let app_handle_for_hook = app_handle.clone();
             let security_monitor_for_hook = security_monitor.clone();
             let pending_elevated_for_hook = pending_elevated.clone();
             let approved_scopes_for_hook = approved_scopes.clone();
+            let pending_question_for_hook = pending_meta_question.clone();
+            let auto_pipeline_manager_for_hook = auto_pipeline_manager.clone();
+            let hook_error_emitter = app_handle.clone();
 
             tauri::async_runtime::spawn(async move {
                 if let Err(e) = hook_server::start_hook_server(
@@ -285,16 +453,28 @@ pub fn run() {
                     security_monitor_for_hook,
                     pending_elevated_for_hook,
                     approved_scopes_for_hook,
+                    pending_question_for_hook,
+                    auto_pipeline_manager_for_hook,
                 )
                 .await
                 {
                     eprintln!("Hook server error: {}", e);
+                    let statuses = SubsystemStatuses::global();
+                    statuses.set_failed(
+                        subsystems::HOOK_SERVER,
+                        &e.to_string(),
+                        &format!(
+                            "agent hooks and the REST API are off; free port {} and restart",
+                            hook_port
+                        ),
+                    );
+                    statuses.emit_degraded(hook_error_emitter.as_ref());
                 }
             });
 
             // Start periodic cleanup task for stopped agents (every 60 seconds)
+            // (their security expectations were cleared when their sessions were finalized)
             let agent_manager_for_cleanup = agent_manager.clone();
-            let security_monitor_for_cleanup = security_monitor.clone();
             tauri::async_runtime::spawn(async move {
                 let cleanup_interval = std::time::Duration::from_secs(60);
                 let max_stopped_age = std::time::Duration::from_secs(5 * 60); // 5 minutes
@@ -310,55 +490,101 @@ pub fn run() {
                             "[Cleanup] Removed {} stopped agents from memory",
                             removed_ids.len()
                         );
-
-                        // Cleanup security monitor expectations for removed agents
-                        if let Some(ref monitor) = security_monitor_for_cleanup {
-                            for agent_id in &removed_ids {
-                                monitor.remove_agent_expectations(agent_id).await;
-                            }
-                        }
                     }
                 }
             });
 
-            // Initialize auto-pipeline manager (optional - requires API key)
-            let auto_pipeline_manager = match AutoPipelineManager::new() {
-                Ok(manager) => {
-                    println!("✓ Auto-pipeline manager initialized");
-                    Some(Arc::new(Mutex::new(manager)))
-                }
-                Err(e) => {
-                    eprintln!("⚠ Auto-pipeline manager unavailable (no API key): {}", e);
-                    None
-                }
-            };
+            // Mark agents whose claude process died as crashed
+            tauri::async_runtime::spawn(agent_manager::process_health::run_health_check(
+                agent_manager.clone(),
+                app_handle.clone(),
+            ));
 
-            // Create shared pending question state (accessible without locking meta_agent)
-            let pending_meta_question: Arc<Mutex<Option<PendingQuestion>>> =
-                Arc::new(Mutex::new(None));
+            // Stop agents left waiting for input past their source's idle timeout
+            tauri::async_runtime::spawn(agent_manager::idle_timeout::run_idle_watchdog(
+                agent_manager.clone(),
+                app_handle.clone(),
+            ));
 
             // Create shared sleep state (accessible without locking meta_agent for interrupt)
             let meta_sleep_state: Arc<Mutex<SleepState>> =
                 Arc::new(Mutex::new(SleepState::default()));
 
+            // Create shared directory approval state (answerable without locking meta_agent)
+            let directory_approvals: SharedDirectoryApprovals =
+                Arc::new(DirectoryApprovals::load());
+
             // Create shared wake sender storage (agents will use this to wake meta-agent)
             let agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>> = Arc::new(Mutex::new(None));
 
             // Set the shared states on the meta agent
-            {
+            let (meta_loop_activity, meta_result_queue) = {
                 let mut ma = meta_agent.blocking_lock();
                 ma.set_pending_question(pending_meta_question.clone());
                 ma.set_sleep_state(meta_sleep_state.clone());
+                ma.set_directory_approvals(directory_approvals.clone());
                 ma.set_agent_wake_tx(agent_wake_tx.clone());
                 ma.set_conversation_db(agent_runs_db.clone());
-            }
+                ma.set_auto_pipeline_manager(auto_pipeline_manager.clone());
+                // Learn from conversations once they've gone quiet
+                ma.get_memory_worker()
+                    .start_history_scan(agent_runs_db.clone());
+                (ma.get_loop_activity(), ma.get_result_queue())
+            };
+
+            // Start the worker that answers "shall I proceed?" questions for opted-in agents
+            let (follow_up_tx, follow_up_rx) = tokio::sync::mpsc::channel(16);
+            tauri::async_runtime::spawn(agent_manager::auto_follow_up::run_auto_follow_up_worker(
+                agent_manager.clone(),
+                app_handle.clone(),
+                follow_up_rx,
+            ));
 
             // Share the wake sender with the agent manager so workers can wake meta-agent
             {
                 let mut am = agent_manager.blocking_lock();
                 am.set_agent_wake_tx(agent_wake_tx.clone());
+                am.set_follow_up_tx(follow_up_tx);
+                // Crashed agents are reported to the meta-agent
+                am.set_result_queue(meta_result_queue);
+
+                // Open new agents' security sessions before their first prompt
+                // (weak, since the monitor holds the agent manager)
+                if let Some(monitor) = &security_monitor {
+                    let monitor = Arc::downgrade(monitor);
+                    am.add_on_agent_created(move |info| {
+                        let Some(monitor) = monitor.upgrade() else {
+                            return;
+                        };
+                        let (agent_id, working_dir) = (info.id.clone(), info.working_dir.clone());
+                        let network_expected = info
+                            .permission_profile
+                            .as_ref()
+                            .is_some_and(|p| p.network_expected);
+                        tauri::async_runtime::spawn(async move {
+                            monitor
+                                .on_agent_created(&agent_id, &working_dir, network_expected)
+                                .await;
+                        });
+                    });
+                }
+                // Announce the agent once every component knows about it
+                let emitter: Arc<dyn events::AppEventEmitter> = app_handle.clone();
+                am.add_on_agent_created(move |info| {
+                    if let Ok(payload) = serde_json::to_value(info) {
+                        let _ = emitter.emit("agent:registered", payload);
+                    }
+                });
             }
 
+            let chat_turns: SharedChatTurns = Arc::new(ChatTurns::new(Some(app_handle.clone())));
+
+            // OS notifications for pipelines, questions and sudo requests waiting on the user
+            notifications::init(app.handle(), logger.clone());
+
+            // Tell the UI if the app is running degraded
+            statuses.emit_degraded(app_handle.as_ref());
+
             app.manage(AppState {
                 agent_manager,
                 meta_agent,
@@ -371,36 +597,96 @@ pub fn run() {
                 app_handle,
                 pending_meta_question,
                 meta_sleep_state,
+                directory_approvals,
                 agent_wake_tx,
+                meta_loop_activity,
+                chat_turns,
+                subsystems: statuses,
             });
 
+            #[cfg(desktop)]
+            tray::init(app.handle());
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(observer_mode::guard_commands(tauri::generate_handler![
             // Agent commands
             commands::create_agent,
             commands::send_prompt,
+            commands::get_queued_prompts,
+            commands::cancel_queued_prompt,
             commands::stop_agent,
             commands::list_agents,
             commands::get_agent_statistics,
+            commands::get_agent_outputs,
+            commands::search_agent_outputs,
+            commands::set_agent_output_buffer_size,
+            commands::list_agents_detailed,
+            commands::create_agent_group,
+            commands::list_agent_groups,
+            commands::stop_agent_group,
+            commands::stop_all_agents,
+            commands::send_prompt_to_group,
+            commands::get_group_statistics,
+            commands::get_memory_footprint,
+            commands::add_agent_note,
+            commands::get_agent_notes,
+            commands::get_agent_messages,
+            commands::get_agent_hook_events,
+            commands::get_hook_health,
+            commands::refresh_github_context,
+            commands::relabel_run,
+            commands::rename_agent,
+            commands::set_agent_conventions_injection,
+            commands::set_agent_auto_follow_up,
+            commands::set_agent_keep_worktree,
+            commands::set_auto_follow_up_kill_switch,
+            commands::get_auto_follow_up_kill_switch,
             commands::list_github_repos,
             commands::resume_crashed_run,
+            commands::resume_agent,
+            commands::handoff_agent,
+            commands::clone_agent,
             // Chat commands
             commands::send_chat_message,
+            commands::get_chat_queue_status,
             commands::get_chat_history,
             commands::clear_chat_history,
             commands::process_agent_results,
             commands::set_commander_personality,
             commands::get_commander_system_prompt,
             commands::reset_commander_personality,
+            commands::preview_effective_system_prompt,
+            commands::set_base_system_prompt_override,
+            commands::reset_base_system_prompt_override,
+            commands::get_stop_phrases,
+            commands::set_stop_phrases,
             commands::answer_meta_agent_question,
+            commands::get_pending_meta_question,
+            commands::approve_directory,
+            commands::get_pending_directory_approvals,
+            commands::browse_directory,
+            commands::get_recent_working_dirs,
+            commands::get_meta_agent_activity,
             // Conversation persistence commands
             commands::list_conversations,
             commands::load_conversation,
             commands::new_conversation,
+            commands::fork_conversation,
             commands::delete_conversation,
             commands::rename_conversation,
+            commands::summarize_conversation,
+            commands::rate_message,
+            commands::get_feedback_summary,
+            commands::get_rated_messages,
+            commands::review_last_compaction,
+            commands::pin_message,
             commands::get_current_conversation_id,
+            commands::use_mock_ai_provider,
+            // Workspace commands
+            commands::create_workspace,
+            commands::switch_workspace,
+            commands::list_workspaces,
             // Cost commands
             commands::get_cost_summary,
             commands::get_cost_by_date_range,
@@ -411,6 +697,7 @@ pub fn run() {
             commands::get_daily_costs,
             commands::get_runs_current_month_cost,
             commands::get_runs_today_cost,
+            commands::get_offload_stats,
             // Logging commands
             commands::query_logs,
             commands::get_recent_logs,
@@ -435,24 +722,52 @@ pub fn run() {
             commands::list_generated_skills,
             commands::delete_generated_skill,
             commands::get_skill_content,
+            // Generated file manifest
+            commands::list_generated_files,
             // Database commands
             commands::get_database_stats,
+            commands::flush_dead_letters,
+            commands::get_usage_trends,
             commands::get_cost_database_stats,
             commands::get_all_runs,
             commands::get_run_by_id,
             commands::query_runs,
+            commands::query_runs_page,
             commands::get_resumable_runs,
             commands::get_run_prompts,
+            commands::get_prompt_costs,
+            commands::get_agent_turns,
             commands::get_run_stats,
             commands::cleanup_old_runs,
             commands::reconcile_stale_runs,
+            commands::delete_run,
+            commands::list_trash,
+            commands::restore_from_trash,
+            commands::empty_trash,
             // Auto-pipeline commands
             commands::create_auto_pipeline,
             commands::start_auto_pipeline,
             commands::get_auto_pipeline,
+            commands::list_auto_pipelines,
+            commands::get_pipeline_health,
+            commands::answer_pipeline_human_request,
+            commands::list_directory_locks,
+            commands::force_release_lock,
+            commands::get_prompt_templates,
+            commands::set_prompt_template,
+            commands::reset_prompt_template,
+            commands::save_prompt_template,
+            commands::list_prompt_templates,
+            commands::render_prompt_template,
+            commands::delete_prompt_template,
+            commands::get_permission_profiles,
+            commands::set_permission_profile,
+            commands::delete_permission_profile,
             // Security commands
             commands::get_security_status,
+            commands::get_security_monitor_stats,
             commands::set_security_enabled,
+            commands::set_security_llm_threshold,
             commands::get_pending_security_reviews,
             commands::approve_security_action,
             commands::reject_security_action,
@@ -472,20 +787,31 @@ pub fn run() {
             commands::get_orchestrator_decisions,
             commands::get_agent_output_history,
             commands::get_pipeline_history,
+            commands::get_pipeline_agent_messages,
+            commands::list_pipeline_artifacts,
+            commands::get_artifact_content,
             commands::clear_pipeline_events,
             // Config commands
             commands::check_claude_code_installed,
+            commands::diagnose_claude_cli,
             commands::get_config_status,
             commands::open_config_directory,
             commands::create_env_placeholder,
             commands::update_config_value,
             commands::update_config_batch,
             commands::validate_api_key,
+            commands::generate_env_template,
+            commands::validate_configuration,
+            commands::set_observer_mode,
+            commands::get_observer_mode,
+            commands::get_version_info,
+            commands::get_subsystem_status,
             // Voice commands (Dictate mode)
             voice::start_voice_session,
             voice::send_voice_audio,
             voice::stop_voice_session,
             voice::get_voice_status,
+            voice::get_voice_session_health,
             // Discuss mode commands
             voice::start_discuss_session,
             voice::send_discuss_audio,
@@ -496,7 +822,23 @@ pub fn run() {
             voice::send_attention_audio,
             voice::stop_attention_session,
             voice::get_attention_status
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Don't lose events still waiting in the write buffer
+                let Some(state) = app_handle.try_state::<AppState>() else {
+                    return;
+                };
+                let runs_db = state.agent_runs_db.clone();
+                match tauri::async_runtime::block_on(runs_db.flush_event_buffer()) {
+                    Ok(count) if count > 0 => {
+                        println!("✓ Flushed {} buffered events on shutdown", count)
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("⚠ Warning: Failed to flush buffered events: {}", e),
+                }
+            }
+        });
 }
@@ -0,0 +1,235 @@
+// Memory footprint - Approximate bytes held by long-lived in-memory buffers
+//
+// Long sessions grow RSS through three holders: each agent's output buffer,
+// the meta-agent's conversation history and every orchestrator agent's
+// conversation. The holders report their approximate sizes here:
+// - agent output buffers add and release bytes as outputs come and go
+// - conversations record their current size under a key ("meta",
+//   "orchestrator:<pipeline_id>") whenever they change
+// When the total crosses MEMORY_WARN_MB (default 512) a warning is logged;
+// it re-arms once the total drops below 80% of the threshold.
+//
+// Sizes are estimates (string lengths plus fixed per-entry overhead), good
+// enough to see what is growing, not an allocator-level measurement.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::commands::env_registry::{self, EnvVar};
+
+/// Default per-agent output buffer cap in MB (AGENT_OUTPUT_BUFFER_MB)
+const DEFAULT_OUTPUT_BUFFER_MB: usize = 8;
+
+/// Default footprint warning threshold in MB (MEMORY_WARN_MB)
+const DEFAULT_WARN_MB: usize = 512;
+
+/// Key under which the meta-agent's conversation is recorded
+pub const META_CONVERSATION_KEY: &str = "meta";
+
+const MB: usize = 1024 * 1024;
+
+static AGENT_OUTPUT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static CONVERSATIONS: OnceLock<Mutex<BTreeMap<String, usize>>> = OnceLock::new();
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Bytes held by one agent's output buffer
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentBufferFootprint {
+    pub agent_id: String,
+    pub outputs: usize,
+    pub bytes: usize,
+}
+
+/// Bytes held by one conversation
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationFootprint {
+    pub key: String,
+    pub bytes: usize,
+}
+
+/// Snapshot returned by get_memory_footprint
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryFootprint {
+    pub agents: Vec<AgentBufferFootprint>,
+    pub agent_output_bytes: usize,
+    pub conversations: Vec<ConversationFootprint>,
+    pub conversation_bytes: usize,
+    pub total_bytes: usize,
+    pub output_buffer_cap_bytes: usize,
+    pub warn_threshold_bytes: usize,
+}
+
+fn env_mb(var: &EnvVar, default: usize) -> usize {
+    var.var()
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|mb| *mb > 0)
+        .unwrap_or(default)
+        * MB
+}
+
+/// Per-agent output buffer cap in bytes
+pub fn output_buffer_cap_bytes() -> usize {
+    env_mb(
+        &env_registry::AGENT_OUTPUT_BUFFER_MB,
+        DEFAULT_OUTPUT_BUFFER_MB,
+    )
+}
+
+/// Total footprint that triggers a warning, in bytes
+pub fn warn_threshold_bytes() -> usize {
+    env_mb(&env_registry::MEMORY_WARN_MB, DEFAULT_WARN_MB)
+}
+
+fn conversations() -> &'static Mutex<BTreeMap<String, usize>> {
+    CONVERSATIONS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// An output buffer grew by `bytes`
+pub fn add_agent_output(bytes: usize) {
+    AGENT_OUTPUT_BYTES.fetch_add(bytes, Ordering::Relaxed);
+    check_threshold();
+}
+
+/// An output buffer evicted or dropped `bytes`
+pub fn release_agent_output(bytes: usize) {
+    let _ = AGENT_OUTPUT_BYTES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |held| {
+        Some(held.saturating_sub(bytes))
+    });
+}
+
+/// Record the current size of a conversation
+pub fn record_conversation(key: &str, bytes: usize) {
+    conversations()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key.to_string(), bytes);
+    check_threshold();
+}
+
+/// Stop counting a conversation that was dropped
+pub fn forget_conversation(key: &str) {
+    conversations()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(key);
+}
+
+fn conversation_bytes() -> usize {
+    conversations()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .sum()
+}
+
+/// Approximate bytes held by all buffers and conversations
+pub fn total_bytes() -> usize {
+    AGENT_OUTPUT_BYTES.load(Ordering::Relaxed) + conversation_bytes()
+}
+
+/// Warn once when the total crosses the threshold
+fn check_threshold() {
+    let total = total_bytes();
+    let threshold = warn_threshold_bytes();
+    if total >= threshold {
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            eprintln!(
+                "[Memory] WARNING: in-memory footprint is ~{} MB (threshold {} MB): agent outputs {} MB, conversations {} MB",
+                total / MB,
+                threshold / MB,
+                AGENT_OUTPUT_BYTES.load(Ordering::Relaxed) / MB,
+                conversation_bytes() / MB
+            );
+        }
+    } else if total < threshold / 10 * 8 {
+        WARNED.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of the footprint, given the per-agent buffer sizes
+pub fn snapshot(mut agents: Vec<AgentBufferFootprint>) -> MemoryFootprint {
+    agents.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    let conversations: Vec<ConversationFootprint> = conversations()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(key, bytes)| ConversationFootprint {
+            key: key.clone(),
+            bytes: *bytes,
+        })
+        .collect();
+
+    let agent_output_bytes = agents.iter().map(|a| a.bytes).sum();
+    let conversation_bytes = conversations.iter().map(|c| c.bytes).sum();
+    MemoryFootprint {
+        agents,
+        agent_output_bytes,
+        conversations,
+        conversation_bytes,
+        total_bytes: agent_output_bytes + conversation_bytes,
+        output_buffer_cap_bytes: output_buffer_cap_bytes(),
+        warn_threshold_bytes: warn_threshold_bytes(),
+    }
+}
+
+/// Approximate in-memory size of a JSON value
+pub fn approx_json_bytes(value: &Value) -> usize {
+    // Rough per-node overhead of serde_json's enum and containers
+    const NODE: usize = 16;
+    NODE + match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+        Value::String(s) => s.len(),
+        Value::Array(items) => items.iter().map(approx_json_bytes).sum(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| k.len() + approx_json_bytes(v))
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_approx_json_bytes_counts_strings() {
+        let small = approx_json_bytes(&json!({"a": 1}));
+        let big = approx_json_bytes(&json!({"a": "x".repeat(10_000)}));
+        assert!(small < 100);
+        assert!(big >= 10_000 && big < 10_100);
+    }
+
+    #[test]
+    fn test_snapshot_totals() {
+        record_conversation("test:snapshot", 1_000);
+        let footprint = snapshot(vec![
+            AgentBufferFootprint {
+                agent_id: "a".to_string(),
+                outputs: 1,
+                bytes: 10,
+            },
+            AgentBufferFootprint {
+                agent_id: "b".to_string(),
+                outputs: 2,
+                bytes: 500,
+            },
+        ]);
+        forget_conversation("test:snapshot");
+
+        assert_eq!(footprint.agents[0].agent_id, "b");
+        assert_eq!(footprint.agent_output_bytes, 510);
+        assert!(footprint
+            .conversations
+            .iter()
+            .any(|c| c.key == "test:snapshot" && c.bytes == 1_000));
+        assert_eq!(
+            footprint.total_bytes,
+            footprint.agent_output_bytes + footprint.conversation_bytes
+        );
+    }
+}
@@ -1,14 +1,19 @@
 // Action logging for MetaAgent (commander action sidebar)
 
 use serde_json::Value;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
 
 use crate::types::CommanderAction;
 
 use super::helpers::{shorten_id, shorten_path};
 
 /// Emit a commander action event for the action log sidebar
-pub fn emit_action(tool_name: &str, input: &Value, result: &Value, app_handle: &AppHandle) {
+pub fn emit_action<R: Runtime>(
+    tool_name: &str,
+    input: &Value,
+    result: &Value,
+    app_handle: &AppHandle<R>,
+) {
     let description = format_action_description(tool_name, input, result);
     let agent_id = extract_agent_id(input);
     let success = result["success"].as_bool().unwrap_or(true);
@@ -41,10 +46,32 @@ pub fn format_action_description(tool_name: &str, input: &Value, result: &Value)
             let agent_id = input["agent_id"].as_str().unwrap_or("?");
             format!("Sent task to agent {}", shorten_id(agent_id))
         }
+        "RouteTaskToAgent" => match result["agent_id"].as_str() {
+            Some(agent_id) => format!("Routed task to agent {}", shorten_id(agent_id)),
+            None => "Routed task: no suitable agent, create a new one".to_string(),
+        },
         "StopWorkerAgent" => {
             let agent_id = input["agent_id"].as_str().unwrap_or("?");
             format!("Stopped agent {}", shorten_id(agent_id))
         }
+        "StopAllWorkerAgents" => format!(
+            "Stopped {} agents ({} failed)",
+            result["stopped"].as_u64().unwrap_or(0),
+            result["failed"].as_u64().unwrap_or(0)
+        ),
+        "RenameWorkerAgent" => format!(
+            "Renamed agent {} to \"{}\"",
+            shorten_id(input["agent_id"].as_str().unwrap_or("?")),
+            input["title"].as_str().unwrap_or("")
+        ),
+        "HandoffWorkerAgent" => match result["agent_id"].as_str() {
+            Some(new_agent_id) => format!(
+                "Handed off agent {} to agent {}",
+                shorten_id(input["agent_id"].as_str().unwrap_or("?")),
+                shorten_id(new_agent_id)
+            ),
+            None => "Hand-off failed".to_string(),
+        },
         "ListWorkerAgents" => {
             let count = result["agents"].as_array().map(|a| a.len()).unwrap_or(0);
             format!("Listed {} agents", count)
@@ -53,6 +80,40 @@ pub fn format_action_description(tool_name: &str, input: &Value, result: &Value)
             let agent_id = input["agent_id"].as_str().unwrap_or("?");
             format!("Retrieved output from agent {}", shorten_id(agent_id))
         }
+        "SendMessageToAgent" => {
+            let from = input["from_agent_id"].as_str().unwrap_or("?");
+            let to = input["to_agent_id"].as_str().unwrap_or("?");
+            format!(
+                "Relayed message from agent {} to agent {}",
+                shorten_id(from),
+                shorten_id(to)
+            )
+        }
+        "AddAgentNote" => {
+            let agent_id = input["agent_id"].as_str().unwrap_or("?");
+            format!("Added note to agent {}", shorten_id(agent_id))
+        }
+        "CreateAutoPipeline" => {
+            let dir = input["working_dir"].as_str().unwrap_or("unknown");
+            let short_dir = shorten_path(dir, 30);
+            if let Some(pipeline_id) = result["pipeline_id"].as_str() {
+                format!(
+                    "Created pipeline {} in {}",
+                    shorten_id(pipeline_id),
+                    short_dir
+                )
+            } else {
+                format!("Created pipeline in {}", short_dir)
+            }
+        }
+        "GetPipelineStatus" => match input["pipeline_id"].as_str() {
+            Some(pipeline_id) => format!("Checked pipeline {}", shorten_id(pipeline_id)),
+            None => "Checked pipeline status".to_string(),
+        },
+        "CancelPipeline" => {
+            let pipeline_id = input["pipeline_id"].as_str().unwrap_or("?");
+            format!("Cancelled pipeline {}", shorten_id(pipeline_id))
+        }
         "ListDirectory" => {
             let path = input["path"].as_str().unwrap_or("?");
             format!("Listed directory {}", path)
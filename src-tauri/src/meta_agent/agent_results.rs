@@ -0,0 +1,232 @@
+// Agent result formatting for the meta-agent
+//
+// A long-running worker can produce hundreds of KB of output. Instead of
+// concatenating everything, this selects the salient subset - the final result
+// block, the last few assistant messages and any error-flagged tool results -
+// and appends a counted summary of what was left out. Both the manual
+// `process_agent_results` command and the automatic result queue use it, so
+// the commander sees the same shape either way.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::output_compressor::OutputCompressor;
+use crate::types::AgentOutputEvent;
+use crate::utils::string::truncate_with_ellipsis;
+
+/// Default size limit for a formatted agent result
+pub const DEFAULT_RESULT_MAX_CHARS: usize = 20_000;
+/// Smallest limit accepted, so headers and summaries always fit
+const MIN_RESULT_MAX_CHARS: usize = 1_000;
+/// Number of trailing assistant messages to keep
+const RECENT_TEXT_MESSAGES: usize = 5;
+/// Number of trailing error results to keep
+const RECENT_ERRORS: usize = 5;
+/// Size limit for a single error result
+const MAX_ERROR_CHARS: usize = 500;
+
+/// Format an agent's outputs for the meta-agent, staying within `max_chars`
+pub fn format_agent_results(
+    outputs: &[AgentOutputEvent],
+    agent_name: &str,
+    results_only: bool,
+    max_chars: usize,
+) -> String {
+    let max_chars = max_chars.max(MIN_RESULT_MAX_CHARS);
+
+    let final_result = outputs.iter().rposition(|o| o.output_type == "result");
+    let texts: Vec<usize> = indices_of(outputs, "text");
+    let recent_texts = &texts[texts.len().saturating_sub(RECENT_TEXT_MESSAGES)..];
+    let errors: Vec<usize> = if results_only {
+        Vec::new()
+    } else {
+        indices_of(outputs, "error")
+    };
+    let recent_errors = &errors[errors.len().saturating_sub(RECENT_ERRORS)..];
+
+    let mut formatted = if results_only {
+        format!("Final results from agent in {}:\n\n", agent_name)
+    } else {
+        format!("Output summary from agent in {}:\n\n", agent_name)
+    };
+
+    if !recent_texts.is_empty() {
+        let budget = max_chars / 2 / recent_texts.len();
+        formatted.push_str(&format!(
+            "--- Last {} assistant message(s) ---\n",
+            recent_texts.len()
+        ));
+        for &i in recent_texts {
+            formatted.push_str(&format!(
+                "Assistant: {}\n\n",
+                truncate_with_ellipsis(&outputs[i].content, budget)
+            ));
+        }
+    }
+
+    if !recent_errors.is_empty() {
+        formatted.push_str(&format!(
+            "--- {} error result(s) ---\n",
+            recent_errors.len()
+        ));
+        for &i in recent_errors {
+            formatted.push_str(&format!(
+                "Error: {}\n\n",
+                truncate_with_ellipsis(&outputs[i].content, MAX_ERROR_CHARS)
+            ));
+        }
+    }
+
+    if let Some(i) = final_result {
+        formatted.push_str("--- Final Results ---\n");
+        formatted.push_str(&format_result_block(&outputs[i], max_chars / 4));
+        formatted.push('\n');
+    }
+
+    // Count everything that didn't make it in
+    let mut omitted: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, output) in outputs.iter().enumerate() {
+        let kept = Some(i) == final_result
+            || recent_texts.contains(&i)
+            || recent_errors.contains(&i)
+            || output.output_type == "system";
+        if !kept {
+            *omitted.entry(output.output_type.as_str()).or_default() += 1;
+        }
+    }
+    if !omitted.is_empty() {
+        let counts: Vec<String> = omitted
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        formatted.push_str(&format!(
+            "--- Omitted {} output(s): {} ---\n",
+            omitted.values().sum::<usize>(),
+            counts.join(", ")
+        ));
+    }
+
+    cap_to(&formatted, max_chars)
+}
+
+/// Cap free-form result text (e.g. a queued result) to `max_chars`
+pub fn cap_result_text(text: &str, max_chars: usize) -> String {
+    cap_to(text, max_chars.max(MIN_RESULT_MAX_CHARS))
+}
+
+fn indices_of(outputs: &[AgentOutputEvent], output_type: &str) -> Vec<usize> {
+    outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| o.output_type == output_type)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Render the final result block with its cost and token usage
+fn format_result_block(output: &AgentOutputEvent, budget: usize) -> String {
+    let mut block = String::new();
+    if !output.content.is_empty() {
+        block.push_str(&truncate_with_ellipsis(&output.content, budget));
+        block.push('\n');
+    }
+    if let Some(parsed) = &output.parsed_json {
+        if let Some(cost) = parsed.get("total_cost_usd").and_then(|v| v.as_f64()) {
+            block.push_str(&format!("Cost: ${:.4}\n", cost));
+        }
+        if let Some(usage) = parsed.get("usage") {
+            let input = usage.get("input_tokens").and_then(|v| v.as_u64());
+            let output = usage.get("output_tokens").and_then(|v| v.as_u64());
+            if let (Some(input), Some(output)) = (input, output) {
+                block.push_str(&format!("Tokens: {} input, {} output\n", input, output));
+            }
+        }
+    }
+    block
+}
+
+/// Final hard limit, keeping the head and tail of the text
+fn cap_to(text: &str, max_chars: usize) -> String {
+    if text.len() <= max_chars {
+        return text.to_string();
+    }
+    match OutputCompressor::new(max_chars).compress(&Value::String(text.to_string())) {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn make_output(output_type: &str, content: &str) -> AgentOutputEvent {
+        AgentOutputEvent {
+            agent_id: "test-agent".to_string(),
+            output_type: output_type.to_string(),
+            content: content.to_string(),
+            parsed_json: None,
+            metadata: None,
+            session_id: None,
+            uuid: None,
+            parent_tool_use_id: None,
+            subtype: None,
+            timestamp: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_selects_salient_outputs_and_counts_omitted() {
+        let mut outputs = Vec::new();
+        for i in 0..10 {
+            outputs.push(make_output("text", &format!("message {}", i)));
+            outputs.push(make_output("tool_use", "Using tool: Read"));
+            outputs.push(make_output("tool_result", "file contents"));
+        }
+        outputs.push(make_output("error", "permission denied"));
+        let mut result = make_output("result", "All done");
+        result.parsed_json = Some(json!({ "total_cost_usd": 0.5 }));
+        outputs.push(result);
+
+        let text = format_agent_results(&outputs, "/repo", false, DEFAULT_RESULT_MAX_CHARS);
+
+        assert!(text.contains("message 9"));
+        assert!(text.contains("message 5"));
+        assert!(!text.contains("message 4"));
+        assert!(text.contains("Error: permission denied"));
+        assert!(text.contains("All done"));
+        assert!(text.contains("Cost: $0.5000"));
+        assert!(text.contains("Omitted 25 output(s): 5 text, 10 tool_result, 10 tool_use"));
+    }
+
+    #[test]
+    fn test_results_only_skips_errors() {
+        let outputs = vec![
+            make_output("error", "boom"),
+            make_output("result", "Finished"),
+        ];
+        let text = format_agent_results(&outputs, "/repo", true, DEFAULT_RESULT_MAX_CHARS);
+        assert!(!text.contains("boom"));
+        assert!(text.contains("Finished"));
+        assert!(text.contains("Omitted 1 output(s): 1 error"));
+    }
+
+    #[test]
+    fn test_respects_max_chars() {
+        let outputs = vec![
+            make_output("text", &"x".repeat(50_000)),
+            make_output("result", &"y".repeat(50_000)),
+        ];
+        let text = format_agent_results(&outputs, "/repo", false, 5_000);
+        assert!(text.len() <= 5_000);
+        assert!(text.contains("--- Final Results ---"));
+    }
+
+    #[test]
+    fn test_cap_result_text_enforces_minimum() {
+        let capped = cap_result_text(&"z".repeat(10_000), 10);
+        assert!(capped.len() <= MIN_RESULT_MAX_CHARS);
+        assert!(capped.contains("truncated"));
+    }
+}
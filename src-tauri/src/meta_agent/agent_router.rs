@@ -0,0 +1,402 @@
+// Agent router - Picks the worker agent best suited to a task
+//
+// RouteTaskToAgent scores every live agent against a task description:
+// - working directory: the task is for the agent's directory (given
+//   explicitly, or a path or project name mentioned in the task)
+// - topic: word overlap between the task and what the agent has been doing
+//   (title, run label, latest note, recent prompts)
+// - recent activity: an agent used in the last few minutes has warm context
+// - load: busy agents and agents waiting on a question lose points
+//
+// When no agent scores well enough the router recommends creating a new one.
+// Scoring is deterministic; the light model is only asked to break a tie
+// between the top candidates.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::ai_client::{AIClient, Message};
+use crate::types::{AgentInfo, AgentStatus};
+use crate::utils::generator::extract_text_from_content_blocks;
+use crate::utils::string::truncate_with_ellipsis;
+
+/// Points for the task being in the agent's working directory
+const DIR_WEIGHT: f64 = 3.0;
+/// Points for a full topic match
+const TOPIC_WEIGHT: f64 = 2.0;
+/// Points for activity within RECENT_ACTIVITY_MS
+const RECENCY_WEIGHT: f64 = 0.5;
+/// Penalty for an agent that is processing a prompt
+const BUSY_PENALTY: f64 = 0.75;
+/// Penalty for an agent waiting on an answer to its own question
+const PENDING_INPUT_PENALTY: f64 = 1.0;
+
+/// Activity within this window counts fully towards recency
+const RECENT_ACTIVITY_MS: i64 = 10 * 60 * 1000;
+/// Recency fades to nothing over this window
+const STALE_ACTIVITY_MS: i64 = 2 * 60 * 60 * 1000;
+
+/// Minimum score for an existing agent to be worth reusing
+pub const MIN_ROUTE_SCORE: f64 = 1.0;
+/// Top candidates closer than this are a tie
+pub const TIE_MARGIN: f64 = 0.25;
+
+/// Prompts from the agent's history considered for the topic match
+pub const RECENT_PROMPTS: usize = 3;
+
+const STOP_WORDS: [&str; 24] = [
+    "a", "an", "and", "the", "to", "of", "for", "in", "on", "with", "into", "from", "by", "is",
+    "it", "this", "that", "please", "can", "you", "make", "add", "fix", "agent",
+];
+
+const TIE_BREAK_PROMPT: &str = "You route tasks to coding agents. Given a task and candidate agents, answer with only the id of the agent that should do the task.";
+
+/// What the router knows about an agent
+#[derive(Debug, Clone)]
+pub struct AgentProfile {
+    pub info: AgentInfo,
+    /// Most recent prompts sent to the agent, newest last
+    pub recent_prompts: Vec<String>,
+}
+
+/// An agent's score with the reasons behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredAgent {
+    pub agent_id: String,
+    pub score: f64,
+    pub reasons: Vec<String>,
+}
+
+/// The router's answer
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteDecision {
+    /// Send the task to this agent
+    Agent {
+        agent_id: String,
+        justification: String,
+    },
+    /// The top candidates scored within TIE_MARGIN of each other
+    Tie { agent_ids: Vec<String> },
+    /// No agent fits; create a new one
+    CreateNew { justification: String },
+}
+
+/// Score every candidate for the task, best first. Stopped and failed agents
+/// are skipped.
+pub fn rank_agents(
+    task: &str,
+    working_dir: Option<&str>,
+    profiles: &[AgentProfile],
+    now_ms: i64,
+) -> Vec<ScoredAgent> {
+    let task_words = significant_words(task);
+    let mut ranked: Vec<ScoredAgent> = profiles
+        .iter()
+        .filter(|p| !matches!(p.info.status, AgentStatus::Stopped | AgentStatus::Error))
+        .map(|p| score_agent(task, &task_words, working_dir, p, now_ms))
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    ranked
+}
+
+/// Turn a ranking into a decision
+pub fn decide(ranked: &[ScoredAgent]) -> RouteDecision {
+    let Some(best) = ranked.first().filter(|a| a.score >= MIN_ROUTE_SCORE) else {
+        let justification = match ranked.first() {
+            Some(best) => format!(
+                "No existing agent matches the task well enough (best: {} at {:.2}, needs {:.2})",
+                best.agent_id, best.score, MIN_ROUTE_SCORE
+            ),
+            None => "No worker agents are running".to_string(),
+        };
+        return RouteDecision::CreateNew { justification };
+    };
+
+    let tied: Vec<String> = ranked
+        .iter()
+        .take_while(|a| best.score - a.score < TIE_MARGIN)
+        .map(|a| a.agent_id.clone())
+        .collect();
+    if tied.len() > 1 {
+        return RouteDecision::Tie { agent_ids: tied };
+    }
+
+    RouteDecision::Agent {
+        agent_id: best.agent_id.clone(),
+        justification: justify(best),
+    }
+}
+
+/// "Score 3.85: works in /repo; topic overlap 60%"
+pub fn justify(agent: &ScoredAgent) -> String {
+    if agent.reasons.is_empty() {
+        return format!("Score {:.2}", agent.score);
+    }
+    format!("Score {:.2}: {}", agent.score, agent.reasons.join("; "))
+}
+
+/// Ask the light model to choose between tied agents.
+///
+/// Returns None if the model is unavailable or names none of the candidates.
+pub async fn break_tie(task: &str, tied: &[&AgentProfile]) -> Option<String> {
+    let client = AIClient::light_from_env().ok()?;
+
+    let candidates: Vec<String> = tied
+        .iter()
+        .map(|p| {
+            format!(
+                "- {} in {}: {}",
+                p.info.id,
+                p.info.working_dir,
+                truncate_with_ellipsis(&agent_context(p), 300)
+            )
+        })
+        .collect();
+    let request = vec![Message {
+        role: "user".to_string(),
+        content: format!(
+            "Task:\n{}\n\nCandidates:\n{}",
+            truncate_with_ellipsis(task, 2000),
+            candidates.join("\n")
+        ),
+    }];
+
+    let response = client
+        .send_message_with_system_and_tools(TIE_BREAK_PROMPT, request, vec![])
+        .await
+        .map_err(|e| eprintln!("[AgentRouter] Tie-break failed: {}", e))
+        .ok()?;
+    let answer = extract_text_from_content_blocks(&response.content);
+    tied.iter()
+        .map(|p| &p.info.id)
+        .find(|id| answer.contains(id.as_str()))
+        .cloned()
+}
+
+fn score_agent(
+    task: &str,
+    task_words: &HashSet<String>,
+    working_dir: Option<&str>,
+    profile: &AgentProfile,
+    now_ms: i64,
+) -> ScoredAgent {
+    let info = &profile.info;
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    let (dir_match, dir_reason) = dir_match(task, working_dir, &info.working_dir);
+    if dir_match > 0.0 {
+        score += DIR_WEIGHT * dir_match;
+        reasons.push(dir_reason);
+    }
+
+    let context_words = significant_words(&agent_context(profile));
+    if !task_words.is_empty() {
+        let overlap =
+            task_words.intersection(&context_words).count() as f64 / task_words.len() as f64;
+        if overlap > 0.0 {
+            score += TOPIC_WEIGHT * overlap;
+            reasons.push(format!(
+                "topic overlap {:.0}% with its recent work",
+                overlap * 100.0
+            ));
+        }
+    }
+
+    if let Some(last) = info.last_activity {
+        let idle = (now_ms - last).max(0);
+        let recency = if idle <= RECENT_ACTIVITY_MS {
+            1.0
+        } else if idle >= STALE_ACTIVITY_MS {
+            0.0
+        } else {
+            1.0 - (idle - RECENT_ACTIVITY_MS) as f64
+                / (STALE_ACTIVITY_MS - RECENT_ACTIVITY_MS) as f64
+        };
+        if recency > 0.0 {
+            score += RECENCY_WEIGHT * recency;
+            reasons.push(format!("active {} min ago", idle / 60_000));
+        }
+    }
+
+    if info.pending_input {
+        score -= PENDING_INPUT_PENALTY;
+        reasons.push("waiting for an answer to its own question".to_string());
+    } else if info.is_processing {
+        score -= BUSY_PENALTY;
+        reasons.push("busy with another prompt".to_string());
+    } else {
+        reasons.push("idle".to_string());
+    }
+
+    ScoredAgent {
+        agent_id: info.id.clone(),
+        score,
+        reasons,
+    }
+}
+
+/// How strongly the task belongs in `agent_dir` (0.0-1.0), with the reason
+fn dir_match(task: &str, working_dir: Option<&str>, agent_dir: &str) -> (f64, String) {
+    let agent_path = Path::new(agent_dir.trim_end_matches('/'));
+
+    if let Some(dir) = working_dir {
+        let dir = Path::new(dir.trim_end_matches('/'));
+        if dir == agent_path {
+            return (1.0, format!("works in {}", agent_dir));
+        }
+        if dir.starts_with(agent_path) || agent_path.starts_with(dir) {
+            return (
+                0.7,
+                format!("works in {}, next to {}", agent_dir, dir.display()),
+            );
+        }
+        return (0.0, String::new());
+    }
+
+    if task.contains(agent_path.to_string_lossy().as_ref()) {
+        return (0.8, format!("task mentions {}", agent_dir));
+    }
+    let project = agent_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if project.len() > 2 && significant_words(task).contains(&project) {
+        return (0.5, format!("task mentions the {} project", project));
+    }
+    (0.0, String::new())
+}
+
+/// Text describing what the agent has been doing
+fn agent_context(profile: &AgentProfile) -> String {
+    let info = &profile.info;
+    let mut parts: Vec<&str> = Vec::new();
+    parts.extend(info.title.as_deref());
+    parts.extend(info.label.as_deref());
+    parts.extend(info.latest_note.as_ref().map(|n| n.text.as_str()));
+    parts.extend(profile.recent_prompts.iter().map(String::as_str));
+    parts.join(" ")
+}
+
+fn significant_words(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+        .filter(|w| w.len() > 2 && !STOP_WORDS.contains(w))
+        .map(|w| w.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AgentSource;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn profile(id: &str, working_dir: &str, prompts: &[&str]) -> AgentProfile {
+        AgentProfile {
+            info: AgentInfo {
+                id: id.to_string(),
+                working_dir: working_dir.to_string(),
+                status: AgentStatus::Running,
+                session_id: None,
+                last_activity: Some(NOW - 60 * 60 * 1000),
+                is_processing: false,
+                pending_input: false,
+                github_context: None,
+                source: AgentSource::Meta,
+                pooled: None,
+                title: None,
+                complexity: None,
+                latest_note: None,
+                label: None,
+                group_ids: Vec::new(),
+                hooks_missing: false,
+                permission_profile: None,
+                model: None,
+                worktree: None,
+            },
+            recent_prompts: prompts.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_routes_by_working_dir_and_topic() {
+        let profiles = vec![
+            profile("web", "/code/webapp", &["Build the login form in React"]),
+            profile("api", "/code/billing-api", &["Add invoice endpoints"]),
+        ];
+
+        let ranked = rank_agents(
+            "Add pagination to the invoice endpoints",
+            None,
+            &profiles,
+            NOW,
+        );
+        assert_eq!(ranked[0].agent_id, "api");
+        match decide(&ranked) {
+            RouteDecision::Agent {
+                agent_id,
+                justification,
+            } => {
+                assert_eq!(agent_id, "api");
+                assert!(justification.contains("topic overlap"));
+            }
+            other => panic!("expected an agent, got {:?}", other),
+        }
+
+        let ranked = rank_agents(
+            "Fix the failing tests",
+            Some("/code/webapp"),
+            &profiles,
+            NOW,
+        );
+        assert_eq!(ranked[0].agent_id, "web");
+        assert!(ranked[0].reasons[0].contains("works in /code/webapp"));
+    }
+
+    #[test]
+    fn test_load_breaks_otherwise_equal_agents() {
+        let mut busy = profile("busy", "/code/app", &[]);
+        busy.info.is_processing = true;
+        let mut asking = profile("asking", "/code/app", &[]);
+        asking.info.pending_input = true;
+        let idle = profile("idle", "/code/app", &[]);
+        let mut stopped = profile("stopped", "/code/app", &[]);
+        stopped.info.status = AgentStatus::Stopped;
+
+        let ranked = rank_agents(
+            "Refactor the parser",
+            Some("/code/app"),
+            &[busy, asking, idle, stopped],
+            NOW,
+        );
+        let order: Vec<&str> = ranked.iter().map(|a| a.agent_id.as_str()).collect();
+        assert_eq!(order, vec!["idle", "busy", "asking"]);
+    }
+
+    #[test]
+    fn test_ties_and_create_new() {
+        let profiles = vec![
+            profile("one", "/code/app", &[]),
+            profile("two", "/code/app", &[]),
+        ];
+        let ranked = rank_agents("Write docs", Some("/code/app"), &profiles, NOW);
+        assert!(matches!(
+            decide(&ranked),
+            RouteDecision::Tie { agent_ids } if agent_ids.len() == 2
+        ));
+
+        let ranked = rank_agents("Write docs", Some("/elsewhere"), &profiles, NOW);
+        assert!(matches!(decide(&ranked), RouteDecision::CreateNew { .. }));
+        assert!(matches!(
+            decide(&[]),
+            RouteDecision::CreateNew { justification } if justification == "No worker agents are running"
+        ));
+    }
+}
@@ -0,0 +1,366 @@
+// Chat turn management for MetaAgent
+//
+// Each chat message starts a turn: one run of the meta-agent's tool loop. The
+// MetaAgent lock serializes turns, but a message sent while a turn was running
+// used to wait on that lock unseen and then get answered after replies it was
+// never shown. Turns are now explicit. While one is active, a new message is
+// either rejected with `TurnInProgress` or queued and started as soon as the
+// active turn ends, in the order received (CHAT_BUSY_MODE = queue | reject,
+// default queue). Changes are emitted as `chat-queue:updated`.
+
+use serde::Serialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::commands::env_registry;
+use crate::events::AppEventEmitter;
+use crate::utils::time::now_millis;
+
+/// Characters of a queued message shown in the queue status
+const PREVIEW_CHARS: usize = 80;
+
+/// What happens to a message sent while a turn is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyMode {
+    /// Fail with `ChatError::TurnInProgress`
+    Reject,
+    /// Run it right after the turns ahead of it
+    Queue,
+}
+
+impl BusyMode {
+    /// Read CHAT_BUSY_MODE, defaulting to queue
+    pub fn from_env() -> Self {
+        match env_registry::CHAT_BUSY_MODE
+            .var()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "reject" => Self::Reject,
+            _ => Self::Queue,
+        }
+    }
+}
+
+/// Error returned by chat commands
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChatError {
+    /// Another turn is running and busy messages are rejected
+    TurnInProgress {
+        active_turn_id: String,
+        message: String,
+    },
+    /// The turn ran and failed
+    Failed { message: String },
+}
+
+impl fmt::Display for ChatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChatError::TurnInProgress { message, .. } | ChatError::Failed { message } => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl From<String> for ChatError {
+    fn from(message: String) -> Self {
+        ChatError::Failed { message }
+    }
+}
+
+/// A message waiting for its turn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedTurnSummary {
+    pub turn_id: String,
+    pub preview: String,
+    pub queued_at: i64,
+}
+
+/// Payload of `chat-queue:updated`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatQueueStatus {
+    pub active_turn_id: Option<String>,
+    pub pending: usize,
+    pub items: Vec<QueuedTurnSummary>,
+}
+
+#[derive(Debug, Default)]
+struct TurnState {
+    active: Option<String>,
+    queue: VecDeque<QueuedTurnSummary>,
+}
+
+/// The active chat turn and the messages queued behind it
+pub struct ChatTurns {
+    state: Mutex<TurnState>,
+    turn_ended: Notify,
+    emitter: Option<Arc<dyn AppEventEmitter>>,
+}
+
+/// Chat turns shared between commands (held in AppState)
+pub type SharedChatTurns = Arc<ChatTurns>;
+
+/// Result of asking for a turn
+pub enum TurnTicket {
+    /// No turn was running; this one has started
+    Started(TurnGuard),
+    /// Queued behind the active turn; wait for it with `QueuedTurn::wait`
+    Queued(QueuedTurn),
+}
+
+impl ChatTurns {
+    pub fn new(emitter: Option<Arc<dyn AppEventEmitter>>) -> Self {
+        Self {
+            state: Mutex::new(TurnState::default()),
+            turn_ended: Notify::new(),
+            emitter,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TurnState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn status(&self) -> ChatQueueStatus {
+        let state = self.lock();
+        ChatQueueStatus {
+            active_turn_id: state.active.clone(),
+            pending: state.queue.len(),
+            items: state.queue.iter().cloned().collect(),
+        }
+    }
+
+    fn emit_updated(&self) {
+        if let Some(ref emitter) = self.emitter {
+            let _ = emitter.emit("chat-queue:updated", json!(self.status()));
+        }
+    }
+
+    /// Start a turn for `message`, or queue or reject it if one is running
+    pub fn begin(self: &Arc<Self>, message: &str, mode: BusyMode) -> Result<TurnTicket, ChatError> {
+        let turn_id = uuid::Uuid::new_v4().to_string();
+        let ticket = {
+            let mut state = self.lock();
+            if state.active.is_none() && state.queue.is_empty() {
+                state.active = Some(turn_id.clone());
+                TurnTicket::Started(TurnGuard {
+                    turns: self.clone(),
+                    turn_id,
+                })
+            } else if mode == BusyMode::Reject {
+                // Between turns the next queued one is about to start
+                let active_turn_id = state
+                    .active
+                    .clone()
+                    .or_else(|| state.queue.front().map(|q| q.turn_id.clone()))
+                    .unwrap_or_default();
+                return Err(ChatError::TurnInProgress {
+                    active_turn_id,
+                    message: "The commander is still answering the previous message".to_string(),
+                });
+            } else {
+                state.queue.push_back(QueuedTurnSummary {
+                    turn_id: turn_id.clone(),
+                    preview: message.chars().take(PREVIEW_CHARS).collect(),
+                    queued_at: now_millis(),
+                });
+                TurnTicket::Queued(QueuedTurn {
+                    turns: self.clone(),
+                    turn_id,
+                    started: false,
+                })
+            }
+        };
+        self.emit_updated();
+        Ok(ticket)
+    }
+
+    /// Start a turn, waiting behind the active one if needed
+    pub async fn acquire(
+        self: &Arc<Self>,
+        message: &str,
+        mode: BusyMode,
+    ) -> Result<TurnGuard, ChatError> {
+        match self.begin(message, mode)? {
+            TurnTicket::Started(turn) => Ok(turn),
+            TurnTicket::Queued(queued) => Ok(queued.wait().await),
+        }
+    }
+
+    /// Make `turn_id` active if it is next in line and no turn is running
+    fn try_start(&self, turn_id: &str) -> bool {
+        let mut state = self.lock();
+        let next = state.queue.front().map(|q| q.turn_id.as_str());
+        if state.active.is_some() || next != Some(turn_id) {
+            return false;
+        }
+        state.queue.pop_front();
+        state.active = Some(turn_id.to_string());
+        true
+    }
+}
+
+/// The active turn; ending it (on drop) starts the next queued one
+pub struct TurnGuard {
+    turns: Arc<ChatTurns>,
+    turn_id: String,
+}
+
+impl TurnGuard {
+    pub fn turn_id(&self) -> &str {
+        &self.turn_id
+    }
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        {
+            let mut state = self.turns.lock();
+            if state.active.as_deref() == Some(self.turn_id.as_str()) {
+                state.active = None;
+            }
+        }
+        self.turns.turn_ended.notify_waiters();
+        self.turns.emit_updated();
+    }
+}
+
+/// A queued message; dropped before its turn starts, it leaves the queue
+pub struct QueuedTurn {
+    turns: Arc<ChatTurns>,
+    turn_id: String,
+    started: bool,
+}
+
+impl QueuedTurn {
+    /// Wait until the turns ahead have ended, then start this one
+    pub async fn wait(mut self) -> TurnGuard {
+        loop {
+            // Created before checking, so an ending turn can't be missed
+            let turn_ended = self.turns.turn_ended.notified();
+            if self.turns.try_start(&self.turn_id) {
+                self.started = true;
+                self.turns.emit_updated();
+                return TurnGuard {
+                    turns: self.turns.clone(),
+                    turn_id: self.turn_id.clone(),
+                };
+            }
+            turn_ended.await;
+        }
+    }
+}
+
+impl Drop for QueuedTurn {
+    fn drop(&mut self) {
+        if self.started {
+            return;
+        }
+        self.turns
+            .lock()
+            .queue
+            .retain(|q| q.turn_id != self.turn_id);
+        // The turn behind this one may now be next
+        self.turns.turn_ended.notify_waiters();
+        self.turns.emit_updated();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(ticket: TurnTicket) -> TurnGuard {
+        match ticket {
+            TurnTicket::Started(turn) => turn,
+            TurnTicket::Queued(_) => panic!("expected the turn to start"),
+        }
+    }
+
+    fn queued(ticket: TurnTicket) -> QueuedTurn {
+        match ticket {
+            TurnTicket::Queued(queued) => queued,
+            TurnTicket::Started(_) => panic!("expected the turn to be queued"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_messages_run_in_the_order_received() {
+        let turns = Arc::new(ChatTurns::new(None));
+        let first = started(turns.begin("first", BusyMode::Queue).unwrap());
+
+        let tickets: Vec<_> = ["second", "third", "fourth"]
+            .into_iter()
+            .map(|label| (label, queued(turns.begin(label, BusyMode::Queue).unwrap())))
+            .collect();
+        assert_eq!(turns.status().pending, 3);
+        assert_eq!(
+            turns.status().active_turn_id.as_deref(),
+            Some(first.turn_id())
+        );
+
+        // Spawn waiters last-first so the order can't come from spawn order
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        for (label, ticket) in tickets.into_iter().rev() {
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _turn = ticket.wait().await;
+                order.lock().unwrap().push(label);
+                tokio::task::yield_now().await;
+            }));
+        }
+        tokio::task::yield_now().await;
+        assert!(order.lock().unwrap().is_empty());
+
+        drop(first);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["second", "third", "fourth"]);
+        assert_eq!(
+            turns.status(),
+            ChatQueueStatus {
+                active_turn_id: None,
+                pending: 0,
+                items: Vec::new(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reject_mode_reports_the_active_turn() {
+        let turns = Arc::new(ChatTurns::new(None));
+        let active = started(turns.begin("first", BusyMode::Reject).unwrap());
+
+        match turns.begin("second", BusyMode::Reject) {
+            Err(ChatError::TurnInProgress { active_turn_id, .. }) => {
+                assert_eq!(active_turn_id, active.turn_id());
+            }
+            _ => panic!("expected TurnInProgress"),
+        }
+
+        // A queued message that is abandoned leaves the queue
+        let abandoned = queued(turns.begin("third", BusyMode::Queue).unwrap());
+        assert_eq!(turns.status().items[0].preview, "third");
+        drop(abandoned);
+        assert_eq!(turns.status().pending, 0);
+
+        drop(active);
+        let next = turns.acquire("fourth", BusyMode::Reject).await.unwrap();
+        assert_eq!(
+            turns.status().active_turn_id.as_deref(),
+            Some(next.turn_id())
+        );
+    }
+}
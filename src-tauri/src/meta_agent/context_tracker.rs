@@ -7,6 +7,7 @@ use std::sync::OnceLock;
 use tiktoken_rs::CoreBPE;
 
 use super::context_config::ContextConfig;
+use crate::ai_client::Usage;
 
 /// Global tokenizer instance (cl100k_base encoding for Claude/GPT-4)
 static TOKENIZER: OnceLock<CoreBPE> = OnceLock::new();
@@ -60,6 +61,12 @@ pub struct ContextTracker {
     current_tokens: usize,
     /// Token count for system prompt (cached)
     system_prompt_tokens: usize,
+    /// Input tokens sent this session, cached or not
+    total_input_tokens: u64,
+    /// Input tokens read from the prompt cache this session
+    cache_read_tokens: u64,
+    /// Input tokens written to the prompt cache this session
+    cache_creation_tokens: u64,
 }
 
 impl ContextTracker {
@@ -69,6 +76,9 @@ impl ContextTracker {
             config,
             current_tokens: 0,
             system_prompt_tokens: 0,
+            total_input_tokens: 0,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
         }
     }
 
@@ -85,10 +95,24 @@ impl ContextTracker {
     }
 
     /// Record token usage from an API response
-    pub fn record_usage(&mut self, input_tokens: u32, output_tokens: u32) {
+    pub fn record_usage(&mut self, usage: &Usage) {
         // Use the actual input tokens from the API response as the authoritative source
-        // This is more accurate than our estimation
-        self.current_tokens = (input_tokens + output_tokens) as usize;
+        // This is more accurate than our estimation. Cached tokens still fill the
+        // context, so they count too.
+        let input_tokens = usage.total_input_tokens();
+        self.current_tokens = (input_tokens + usage.output_tokens) as usize;
+
+        self.total_input_tokens += input_tokens as u64;
+        self.cache_read_tokens += usage.cache_read_input_tokens as u64;
+        self.cache_creation_tokens += usage.cache_creation_input_tokens as u64;
+    }
+
+    /// Share of input tokens read from the prompt cache, as a percentage
+    pub fn cache_hit_percent(&self) -> f64 {
+        if self.total_input_tokens == 0 {
+            return 0.0;
+        }
+        (self.cache_read_tokens as f64 / self.total_input_tokens as f64) * 100.0
     }
 
     /// Add tokens for a message being added to history
@@ -107,6 +131,11 @@ impl ContextTracker {
         self.current_tokens = self.system_prompt_tokens + summary_tokens + remaining_history_tokens;
     }
 
+    /// Drop all history tokens, keeping only the system prompt
+    pub fn reset_history(&mut self) {
+        self.current_tokens = self.system_prompt_tokens;
+    }
+
     /// Get the current context state
     pub fn get_state(&self) -> ContextState {
         let available = self.config.available_tokens();
@@ -175,6 +204,9 @@ impl ContextTracker {
             available_tokens: self.available_tokens(),
             remaining_tokens: self.remaining_tokens(),
             state: self.get_state(),
+            cache_read_tokens: self.cache_read_tokens,
+            cache_creation_tokens: self.cache_creation_tokens,
+            cache_hit_percent: self.cache_hit_percent(),
         }
     }
 }
@@ -187,6 +219,9 @@ pub struct ContextInfo {
     pub available_tokens: usize,
     pub remaining_tokens: usize,
     pub state: ContextState,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_hit_percent: f64,
 }
 
 impl ContextInfo {
@@ -249,6 +284,43 @@ mod tests {
         assert!((percent - 50.0).abs() < 1.0);
     }
 
+    #[test]
+    fn test_reset_history_keeps_system_prompt() {
+        let mut tracker = ContextTracker::new(ContextConfig::for_claude());
+        tracker.set_system_prompt("You are a helpful assistant.");
+        let system_tokens = tracker.current_tokens();
+
+        tracker.add_message_tokens("Hello there");
+        assert!(tracker.current_tokens() > system_tokens);
+
+        tracker.reset_history();
+        assert_eq!(tracker.current_tokens(), system_tokens);
+    }
+
+    #[test]
+    fn test_record_usage_counts_cached_tokens() {
+        let mut tracker = ContextTracker::new(ContextConfig::for_claude());
+        tracker.record_usage(&Usage {
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_creation_input_tokens: 900,
+            cache_read_input_tokens: 0,
+        });
+        tracker.record_usage(&Usage {
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 900,
+        });
+
+        // Cached input still occupies the context
+        assert_eq!(tracker.current_tokens(), 1050);
+        let info = tracker.get_context_info();
+        assert_eq!(info.cache_read_tokens, 900);
+        assert_eq!(info.cache_creation_tokens, 900);
+        assert!((info.cache_hit_percent - 45.0).abs() < 0.01);
+    }
+
     #[test]
     fn test_needs_compaction() {
         let config = ContextConfig::for_claude();
@@ -4,22 +4,79 @@
 // context tracking, and conversation-related utilities including
 // context compaction for long-running sessions.
 
-use crate::agent_runs_db::MetaMessageRecord;
+use std::collections::HashMap;
+
+use crate::agent_runs_db::{MessagePin, MetaMessageRecord};
 use crate::ai_client::types::ImageSource;
-use crate::ai_client::{Message, RichContentBlock, RichMessage, RichMessageContent};
+use crate::ai_client::{Message, RichContentBlock, RichMessage, RichMessageContent, Usage};
+use crate::memory_footprint::{self, approx_json_bytes};
 use crate::types::{ChatMessage, ImageAttachment};
 
 use super::context_config::ContextConfig;
 use super::context_summarizer::ContextSummarizer;
 use super::context_tracker::{ContextInfo, ContextState, ContextTracker};
+use super::history_repair::repair_tool_pairs;
+use super::message_pins::{format_pinned, looks_like_constraint, PinnedMessage};
+
+/// Rough fixed size of a history entry (role, vectors, enum tags)
+const MESSAGE_OVERHEAD_BYTES: usize = 64;
+
+/// Approximate in-memory size of a history message and its tool blocks
+fn message_bytes(content: &str, tool_blocks: Option<&[RichContentBlock]>) -> usize {
+    let blocks: usize = tool_blocks
+        .unwrap_or_default()
+        .iter()
+        .map(|block| match block {
+            RichContentBlock::Text { text } => text.len(),
+            RichContentBlock::Image { source } => source.data.len(),
+            RichContentBlock::ToolUse { id, name, input } => {
+                id.len() + name.len() + approx_json_bytes(input)
+            }
+            RichContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                ..
+            } => tool_use_id.len() + content.len(),
+        })
+        .sum();
+    MESSAGE_OVERHEAD_BYTES + content.len() + blocks
+}
+
+/// What a compaction replaced and with what
+#[derive(Debug, Clone)]
+pub struct CompactionReport {
+    /// Summary of the replaced messages (this compaction only)
+    pub summary: String,
+    /// First and last replaced message (message_index, inclusive)
+    pub first_message_index: u32,
+    pub last_message_index: u32,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    pub emergency: bool,
+    /// Replaced messages kept verbatim
+    pub preserved_message_indexes: Vec<u32>,
+}
 
 /// Manages the conversation history for the MetaAgent
 pub struct ConversationManager {
     history: Vec<Message>,
+    /// Tool use/result blocks of each history message, index-aligned with
+    /// `history` (None for plain text messages)
+    tool_blocks: Vec<Option<Vec<RichContentBlock>>>,
+    /// Persisted message_index of each history message, index-aligned with
+    /// `history`. Indexes keep counting across compactions.
+    message_indexes: Vec<u32>,
+    next_message_index: u32,
+    /// User pin decisions by message_index (false = unpinned a detected constraint)
+    pin_overrides: HashMap<u32, bool>,
+    /// Compacted messages kept verbatim next to the summary
+    preserved: Vec<PinnedMessage>,
     context_tracker: ContextTracker,
     summarizer: ContextSummarizer,
     /// Summary of compacted context (prepended to history when needed)
     context_summary: Option<String>,
+    /// Approximate bytes held by history, tool blocks and summary
+    bytes: usize,
 }
 
 impl ConversationManager {
@@ -31,9 +88,15 @@ impl ConversationManager {
     pub fn with_config(config: ContextConfig) -> Self {
         Self {
             history: Vec::new(),
+            tool_blocks: Vec::new(),
+            message_indexes: Vec::new(),
+            next_message_index: 1,
+            pin_overrides: HashMap::new(),
+            preserved: Vec::new(),
             context_tracker: ContextTracker::new(config),
             summarizer: ContextSummarizer::new(),
             context_summary: None,
+            bytes: 0,
         }
     }
 
@@ -42,28 +105,152 @@ impl ConversationManager {
         self.context_tracker.set_system_prompt(prompt);
     }
 
-    /// Add a user message to the conversation history
-    pub fn add_user_message(&mut self, content: String) {
+    fn push_message(
+        &mut self,
+        role: &str,
+        content: String,
+        tool_blocks: Option<Vec<RichContentBlock>>,
+    ) {
         self.context_tracker.add_message_tokens(&content);
+        self.bytes += message_bytes(&content, tool_blocks.as_deref());
         self.history.push(Message {
-            role: "user".to_string(),
+            role: role.to_string(),
             content,
         });
+        self.tool_blocks.push(tool_blocks);
+        self.message_indexes.push(self.next_message_index);
+        self.next_message_index += 1;
+        self.report_footprint();
+    }
+
+    fn recount_bytes(&mut self) {
+        self.bytes = self
+            .history
+            .iter()
+            .zip(&self.tool_blocks)
+            .map(|(m, blocks)| message_bytes(&m.content, blocks.as_deref()))
+            .sum::<usize>()
+            + self.context_summary.as_ref().map_or(0, String::len)
+            + self
+                .preserved
+                .iter()
+                .map(|p| p.content.len())
+                .sum::<usize>();
+        self.report_footprint();
+    }
+
+    fn report_footprint(&self) {
+        memory_footprint::record_conversation(memory_footprint::META_CONVERSATION_KEY, self.bytes);
+    }
+
+    /// Add a user message to the conversation history
+    pub fn add_user_message(&mut self, content: String) {
+        self.push_message("user", content, None);
     }
 
     /// Add an assistant message to the conversation history
     pub fn add_assistant_message(&mut self, content: String) {
-        self.context_tracker.add_message_tokens(&content);
-        self.history.push(Message {
-            role: "assistant".to_string(),
-            content,
-        });
+        self.push_message("assistant", content, None);
+    }
+
+    /// Add a message produced by the tool loop, keeping its tool blocks
+    pub fn add_rich_message(&mut self, msg: &RichMessage) {
+        let content = rich_message_text(msg);
+        let tool_blocks = match &msg.content {
+            RichMessageContent::Blocks(blocks) if blocks.iter().any(is_tool_block) => Some(
+                blocks
+                    .iter()
+                    .map(|block| match block {
+                        // Image data isn't kept in history
+                        RichContentBlock::Image { .. } => RichContentBlock::Text {
+                            text: "[Image]".to_string(),
+                        },
+                        other => other.clone(),
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        };
+        self.push_message(&msg.role, content, tool_blocks);
+    }
+
+    /// Tool blocks of the message at `index`, serialized for persistence
+    pub fn tool_blocks_json(&self, index: usize) -> Option<String> {
+        self.tool_blocks
+            .get(index)?
+            .as_ref()
+            .and_then(|blocks| serde_json::to_string(blocks).ok())
+    }
+
+    /// Persisted message_index of the history message at `position`
+    pub fn message_index(&self, position: usize) -> Option<u32> {
+        self.message_indexes.get(position).copied()
+    }
+
+    /// Take the user's pins into account at the next compaction. Messages
+    /// already compacted are added to or dropped from the verbatim ones.
+    pub fn apply_pins(&mut self, pins: &[MessagePin]) {
+        self.pin_overrides = pins
+            .iter()
+            .map(|pin| (pin.message_index, pin.pinned))
+            .collect();
+
+        let compacted_before = self.message_indexes.first().copied().unwrap_or(u32::MAX);
+        let mut changed = false;
+        for pin in pins {
+            let kept = self
+                .preserved
+                .iter()
+                .position(|p| p.message_index == pin.message_index);
+            match (pin.pinned, kept) {
+                (false, Some(i)) => {
+                    self.preserved.remove(i);
+                    changed = true;
+                }
+                (true, None) if pin.message_index < compacted_before => {
+                    self.preserved.push(PinnedMessage {
+                        message_index: pin.message_index,
+                        content: pin.content.clone(),
+                        detected: false,
+                    });
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+        if changed {
+            self.preserved.sort_by_key(|p| p.message_index);
+            self.recount_bytes();
+        }
+    }
+
+    /// Compacted messages kept verbatim
+    #[allow(dead_code)] // Utility method for potential API exposure
+    pub fn preserved_messages(&self) -> &[PinnedMessage] {
+        &self.preserved
+    }
+
+    /// Whether a message about to be compacted is kept verbatim: Some(true)
+    /// for a detected constraint, Some(false) for a user pin
+    fn should_preserve(
+        &self,
+        message_index: u32,
+        msg: &Message,
+        tool_blocks: Option<&[RichContentBlock]>,
+    ) -> Option<bool> {
+        if msg.role != "user" || tool_blocks.is_some() {
+            return None;
+        }
+        match self.pin_overrides.get(&message_index) {
+            Some(true) => Some(false),
+            Some(false) => None,
+            None => looks_like_constraint(&msg.content).then_some(true),
+        }
     }
 
     /// Record token usage from an API response
-    pub fn record_usage(&mut self, input_tokens: u32, output_tokens: u32) {
-        self.context_tracker
-            .record_usage(input_tokens, output_tokens);
+    pub fn record_usage(&mut self, usage: &Usage) {
+        self.context_tracker.record_usage(usage);
     }
 
     /// Get context info for tool results
@@ -78,16 +265,15 @@ impl ConversationManager {
     }
 
     /// Check if compaction is needed
-    #[allow(dead_code)] // Utility method for potential API exposure
     pub fn needs_compaction(&self) -> bool {
         self.context_tracker.needs_compaction()
     }
 
     /// Compact the conversation history if needed (call at idle moments)
-    /// Returns true if compaction was performed
-    pub async fn compact_if_needed(&mut self) -> bool {
+    /// Returns what was compacted, or None if nothing was
+    pub async fn compact_if_needed(&mut self) -> Option<CompactionReport> {
         if !self.context_tracker.needs_compaction() {
-            return false;
+            return None;
         }
 
         let is_emergency = self.context_tracker.needs_emergency_compaction();
@@ -98,16 +284,38 @@ impl ConversationManager {
         };
 
         if self.history.len() <= preserve_count {
-            return false; // Nothing to compact
+            return None; // Nothing to compact
         }
 
+        let tokens_before = self.context_tracker.current_tokens();
+
         // Split history into messages to compact and messages to keep
         let split_point = self.history.len() - preserve_count;
         let messages_to_compact: Vec<Message> = self.history.drain(..split_point).collect();
+        let compacted_blocks: Vec<_> = self.tool_blocks.drain(..split_point).collect();
+        let compacted_indexes: Vec<u32> = self.message_indexes.drain(..split_point).collect();
+
+        // Pinned messages and detected constraints are kept word for word
+        let mut preserved_message_indexes = Vec::new();
+        for ((msg, blocks), &message_index) in messages_to_compact
+            .iter()
+            .zip(&compacted_blocks)
+            .zip(&compacted_indexes)
+        {
+            if let Some(detected) = self.should_preserve(message_index, msg, blocks.as_deref()) {
+                self.preserved.push(PinnedMessage {
+                    message_index,
+                    content: msg.content.clone(),
+                    detected,
+                });
+                preserved_message_indexes.push(message_index);
+            }
+        }
 
         eprintln!(
-            "[ConversationManager] Compacting {} messages (emergency: {})",
+            "[ConversationManager] Compacting {} messages, keeping {} verbatim (emergency: {})",
             messages_to_compact.len(),
+            preserved_message_indexes.len(),
             is_emergency
         );
 
@@ -134,12 +342,14 @@ impl ConversationManager {
         };
 
         // Store or append to existing summary
-        let summary_tokens = ContextTracker::count_tokens(&summary);
         if let Some(existing) = &self.context_summary {
             self.context_summary = Some(format!("{}\n\n{}", existing, summary));
         } else {
-            self.context_summary = Some(summary);
+            self.context_summary = Some(summary.clone());
         }
+        let summary_tokens = self
+            .context_summary_message()
+            .map_or(0, |text| ContextTracker::count_tokens(&text));
 
         // Calculate remaining history tokens
         let remaining_tokens: usize = self
@@ -151,13 +361,39 @@ impl ConversationManager {
         // Reset the tracker with new token counts
         self.context_tracker
             .reset_after_compaction(summary_tokens, remaining_tokens);
+        self.recount_bytes();
 
         eprintln!(
             "[ConversationManager] Compaction complete. New context usage: {:.1}%",
             self.context_tracker.usage_percent()
         );
 
-        true
+        Some(CompactionReport {
+            summary,
+            first_message_index: compacted_indexes.first().copied().unwrap_or_default(),
+            last_message_index: compacted_indexes.last().copied().unwrap_or_default(),
+            tokens_before,
+            tokens_after: self.context_tracker.current_tokens(),
+            emergency: is_emergency,
+            preserved_message_indexes,
+        })
+    }
+
+    /// Text of the message that stands in for compacted history: the
+    /// summary followed by the messages kept verbatim
+    fn context_summary_message(&self) -> Option<String> {
+        if self.context_summary.is_none() && self.preserved.is_empty() {
+            return None;
+        }
+        let mut text = format!(
+            "[PREVIOUS CONTEXT - Summary of earlier conversation:]\n\n{}",
+            self.context_summary.as_deref().unwrap_or_default()
+        );
+        if !self.preserved.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&format_pinned(&self.preserved));
+        }
+        Some(text)
     }
 
     /// Add a user message with an image attachment (stores text representation)
@@ -183,7 +419,7 @@ impl ConversationManager {
 
     /// Check if there is a context summary
     pub fn has_context_summary(&self) -> bool {
-        self.context_summary.is_some()
+        self.context_summary.is_some() || !self.preserved.is_empty()
     }
 
     /// Get the context summary if present
@@ -195,8 +431,14 @@ impl ConversationManager {
     /// Clear the conversation history and reset context tracking
     pub fn clear(&mut self) {
         self.history.clear();
+        self.tool_blocks.clear();
+        self.message_indexes.clear();
+        self.next_message_index = 1;
+        self.pin_overrides.clear();
+        self.preserved.clear();
         self.context_summary = None;
-        self.context_tracker = ContextTracker::new(self.context_tracker.config().clone());
+        self.context_tracker.reset_history();
+        self.recount_bytes();
     }
 
     /// Load conversation history from database records
@@ -209,11 +451,19 @@ impl ConversationManager {
         sorted_records.sort_by_key(|r| r.message_index);
 
         for record in sorted_records {
-            self.context_tracker.add_message_tokens(&record.content);
-            self.history.push(Message {
-                role: record.role.clone(),
-                content: record.content.clone(),
+            let tool_blocks = record.tool_calls.as_deref().and_then(|json| {
+                serde_json::from_str::<Vec<RichContentBlock>>(json)
+                    .map_err(|e| {
+                        eprintln!(
+                            "[ConversationManager] Ignoring unreadable tool blocks for message {}: {}",
+                            record.message_index, e
+                        )
+                    })
+                    .ok()
             });
+            // Keep the persisted index so later messages continue after it
+            self.next_message_index = record.message_index.max(self.next_message_index);
+            self.push_message(&record.role, record.content, tool_blocks);
         }
 
         eprintln!(
@@ -238,15 +488,26 @@ impl ConversationManager {
             .collect()
     }
 
-    /// Build rich messages for API calls that include images
-    pub fn build_rich_messages(&self) -> Vec<RichMessage> {
-        self.history
+    /// History as RichMessages, with tool blocks restored where they were kept
+    fn history_rich_messages(&self) -> Vec<RichMessage> {
+        let messages = self
+            .history
             .iter()
-            .map(|msg| RichMessage {
+            .zip(&self.tool_blocks)
+            .map(|(msg, blocks)| RichMessage {
                 role: msg.role.clone(),
-                content: RichMessageContent::Text(msg.content.clone()),
+                content: match blocks {
+                    Some(blocks) => RichMessageContent::Blocks(blocks.clone()),
+                    None => RichMessageContent::Text(msg.content.clone()),
+                },
             })
-            .collect()
+            .collect();
+        repair_tool_pairs(messages)
+    }
+
+    /// Build rich messages for API calls that include images
+    pub fn build_rich_messages(&self) -> Vec<RichMessage> {
+        self.history_rich_messages()
     }
 
     /// Get history as RichMessages with context summary prepended (for API calls)
@@ -257,13 +518,10 @@ impl ConversationManager {
         let mut result = Vec::new();
 
         // Prepend context summary if available
-        if let Some(summary) = &self.context_summary {
+        if let Some(summary) = self.context_summary_message() {
             result.push(RichMessage {
                 role: "user".to_string(),
-                content: RichMessageContent::Text(format!(
-                    "[PREVIOUS CONTEXT - Summary of earlier conversation:]\n\n{}",
-                    summary
-                )),
+                content: RichMessageContent::Text(summary),
             });
             result.push(RichMessage {
                 role: "assistant".to_string(),
@@ -273,11 +531,7 @@ impl ConversationManager {
             });
         }
 
-        // Convert each Message to RichMessage
-        result.extend(self.history.iter().map(|msg| RichMessage {
-            role: msg.role.clone(),
-            content: RichMessageContent::Text(msg.content.clone()),
-        }));
+        result.extend(self.history_rich_messages());
 
         result
     }
@@ -343,6 +597,53 @@ impl ConversationManager {
     }
 }
 
+/// Whether a block is a tool_use or tool_result
+fn is_tool_block(block: &RichContentBlock) -> bool {
+    matches!(
+        block,
+        RichContentBlock::ToolUse { .. } | RichContentBlock::ToolResult { .. }
+    )
+}
+
+/// Text form of a RichMessage, with tool blocks serialized as JSON
+pub fn rich_message_text(msg: &RichMessage) -> String {
+    match &msg.content {
+        RichMessageContent::Text(s) => s.clone(),
+        RichMessageContent::Blocks(blocks) => {
+            let parts: Vec<String> = blocks
+                .iter()
+                .map(|block| match block {
+                    RichContentBlock::Text { text } => text.clone(),
+                    RichContentBlock::ToolUse { id, name, input } => serde_json::json!({
+                        "type": "tool_use",
+                        "id": id,
+                        "name": name,
+                        "input": input
+                    })
+                    .to_string(),
+                    RichContentBlock::ToolResult {
+                        tool_use_id,
+                        content,
+                        is_error,
+                    } => {
+                        let mut result = serde_json::json!({
+                            "type": "tool_result",
+                            "tool_use_id": tool_use_id,
+                            "content": content
+                        });
+                        if let Some(err) = is_error {
+                            result["is_error"] = serde_json::json!(err);
+                        }
+                        result.to_string()
+                    }
+                    RichContentBlock::Image { .. } => "[Image]".to_string(),
+                })
+                .collect();
+            parts.join("\n")
+        }
+    }
+}
+
 impl Default for ConversationManager {
     fn default() -> Self {
         Self::new()
@@ -352,6 +653,9 @@ impl Default for ConversationManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent_runs_db::{AgentRunsDB, MetaConversationRecord};
+    use crate::ai_client::{AIClient, AIError, AIProvider, AIResponse, ContentBlock, Tool, Usage};
+    use std::sync::Arc;
 
     #[test]
     fn test_add_messages() {
@@ -407,4 +711,192 @@ mod tests {
         assert_eq!(history.len(), 1);
         assert_eq!(history[0].role, "user");
     }
+
+    fn pin(message_index: u32, pinned: bool) -> MessagePin {
+        MessagePin {
+            message_index,
+            pinned,
+            role: "user".to_string(),
+            content: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compaction_keeps_pinned_and_constraint_messages() {
+        let mut manager = ConversationManager::with_config(ContextConfig {
+            max_context_tokens: 700,
+            system_prompt_reserve: 0,
+            output_reserve: 0,
+            warning_threshold_pct: 0.5,
+            critical_threshold_pct: 0.6,
+            max_tool_output_chars: 1_000,
+            preserve_recent_messages: 2,
+        });
+        let filler = "word ".repeat(80);
+        manager.add_user_message(format!("Never push directly to main. {}", filler));
+        manager.add_assistant_message(filler.clone());
+        manager.add_user_message(format!("Check on the agents. {}", filler));
+        manager.add_assistant_message(filler.clone());
+        manager.add_user_message(filler.clone());
+        manager.add_assistant_message(filler.clone());
+        manager.apply_pins(&[pin(3, true)]);
+
+        let report = manager.compact_if_needed().await.unwrap();
+        assert_eq!(
+            (report.first_message_index, report.last_message_index),
+            (1, 4)
+        );
+        assert_eq!(report.preserved_message_indexes, vec![1, 3]);
+        assert!(report.tokens_after < report.tokens_before);
+        assert_eq!(manager.message_index(0), Some(5));
+
+        // Unpinning a compacted message drops it from the verbatim ones
+        manager.apply_pins(&[pin(1, false), pin(3, true)]);
+        let history = manager.get_history_as_rich_messages();
+        let RichMessageContent::Text(context) = &history[0].content else {
+            panic!("context summary should be text");
+        };
+        assert!(context.contains("(message 3, pinned by the user) Check on the agents."));
+        assert!(!context.contains("Never push directly to main."));
+
+        manager.add_user_message("And now?".to_string());
+        assert_eq!(manager.message_index(2), Some(7));
+    }
+
+    /// Provider that rejects histories with unpaired tool blocks, like the Anthropic API
+    struct PairCheckingProvider;
+
+    #[async_trait::async_trait]
+    impl AIProvider for PairCheckingProvider {
+        async fn send_message(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<Tool>>,
+        ) -> Result<AIResponse, AIError> {
+            unreachable!("the meta-agent sends rich messages")
+        }
+
+        async fn send_message_with_system(
+            &self,
+            _system_prompt: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<Tool>>,
+        ) -> Result<AIResponse, AIError> {
+            unreachable!("the meta-agent sends rich messages")
+        }
+
+        async fn send_rich_message(
+            &self,
+            messages: Vec<RichMessage>,
+            _tools: Option<Vec<Tool>>,
+        ) -> Result<AIResponse, AIError> {
+            let repaired = repair_tool_pairs(messages.clone());
+            if serde_json::to_value(&repaired).unwrap() != serde_json::to_value(&messages).unwrap()
+            {
+                return Err(AIError::ApiError(
+                    "tool_use ids were found without tool_result blocks".to_string(),
+                ));
+            }
+            Ok(AIResponse {
+                id: "msg_1".to_string(),
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::Text {
+                    text: "Both agents are idle.".to_string(),
+                }],
+                model: "mock".to_string(),
+                stop_reason: Some("end_turn".to_string()),
+                usage: Usage {
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    ..Default::default()
+                },
+            })
+        }
+
+        fn name(&self) -> &str {
+            "Mock"
+        }
+
+        fn model(&self) -> &str {
+            "mock"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_conversation_survives_reload() {
+        let tool_use = RichContentBlock::ToolUse {
+            id: "toolu_1".to_string(),
+            name: "ListWorkerAgents".to_string(),
+            input: serde_json::json!({}),
+        };
+        let tool_result = RichContentBlock::ToolResult {
+            tool_use_id: "toolu_1".to_string(),
+            content: "[]".to_string(),
+            is_error: None,
+        };
+
+        let mut manager = ConversationManager::new();
+        manager.add_user_message("What's running?".to_string());
+        for (role, blocks) in [("assistant", vec![tool_use]), ("user", vec![tool_result])] {
+            manager.add_rich_message(&RichMessage {
+                role: role.to_string(),
+                content: RichMessageContent::Blocks(blocks),
+            });
+        }
+        manager.add_assistant_message("Nothing is running.".to_string());
+
+        // Save the conversation the way MetaAgent persists it
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentRunsDB::new(dir.path().join("runs.db")).unwrap();
+        db.create_meta_conversation(&MetaConversationRecord {
+            id: None,
+            conversation_id: "conv-1".to_string(),
+            title: None,
+            created_at: 0,
+            updated_at: 0,
+            message_count: 0,
+            is_archived: false,
+            preview_text: None,
+            title_locked: false,
+            summary: None,
+            forked_from: None,
+            forked_at_message: None,
+            workspace_id: crate::workspace::DEFAULT_WORKSPACE_ID.to_string(),
+        })
+        .await
+        .unwrap();
+        for (i, msg) in manager.get_history().iter().enumerate() {
+            db.insert_meta_message(&MetaMessageRecord {
+                id: None,
+                conversation_id: "conv-1".to_string(),
+                message_index: i as u32 + 1,
+                role: msg.role.clone(),
+                content: msg.content.clone(),
+                image_data: None,
+                tool_calls: manager.tool_blocks_json(i),
+                timestamp: i as i64,
+                model: None,
+                personality_hash: None,
+                rating: None,
+                rating_note: None,
+                rated_at: None,
+            })
+            .await
+            .unwrap();
+        }
+
+        let mut reloaded = ConversationManager::new();
+        reloaded.load_from_records(&db.get_meta_messages("conv-1").await.unwrap());
+        reloaded.add_user_message("And now?".to_string());
+
+        let history = reloaded.get_history_as_rich_messages();
+        assert!(matches!(&history[1].content, RichMessageContent::Blocks(b) if b.len() == 1));
+
+        let client = AIClient::with_provider(Arc::new(PairCheckingProvider));
+        let response = client
+            .send_rich_message_with_tools(history, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(response.stop_reason.as_deref(), Some("end_turn"));
+    }
 }
@@ -0,0 +1,150 @@
+// Conversation Titles - generated titles and summaries for meta conversations
+//
+// Titles are regenerated in the background once a conversation has a few
+// exchanges (see MemoryWorker::queue_title_generation). Summaries are only
+// generated on request via the summarize_conversation command.
+
+use crate::ai_client::{AIClient, Message};
+use crate::utils::generator::extract_text_from_content_blocks;
+
+/// Number of user messages after which the title is regenerated
+pub const TITLE_REGENERATION_EXCHANGE: usize = 3;
+
+/// Maximum characters of conversation text sent to the light model
+const MAX_TRANSCRIPT_CHARS: usize = 12_000;
+
+/// Maximum words kept from a generated title
+const MAX_TITLE_WORDS: usize = 8;
+
+const TITLE_PROMPT: &str = r#"You write short titles for conversations between a user and a System Commander AI.
+
+The user will provide the conversation. Reply with a title of 5-8 words that describes what the conversation is about.
+
+Important:
+- Output ONLY the title
+- No quotes, no trailing punctuation, no "Title:" prefix"#;
+
+const SUMMARY_PROMPT: &str = r#"You summarize conversations between a user and a System Commander AI.
+
+The user will provide the conversation. Reply with a single paragraph (3-5 sentences) covering what the user asked for, what was done, and where things were left.
+
+Important:
+- Output ONLY the summary paragraph
+- No headings, bullet points or preamble"#;
+
+/// Generate a 5-8 word title for the conversation
+pub async fn generate_title(client: &AIClient, messages: &[Message]) -> Result<String, String> {
+    let text = complete(client, TITLE_PROMPT, messages).await?;
+    clean_title(&text).ok_or_else(|| "Model returned an empty title".to_string())
+}
+
+/// Generate a one-paragraph summary of the conversation
+pub async fn generate_summary(client: &AIClient, messages: &[Message]) -> Result<String, String> {
+    let text = complete(client, SUMMARY_PROMPT, messages).await?;
+    let summary = text.trim();
+    if summary.is_empty() {
+        return Err("Model returned an empty summary".to_string());
+    }
+    Ok(summary.to_string())
+}
+
+async fn complete(client: &AIClient, system: &str, messages: &[Message]) -> Result<String, String> {
+    let request = vec![Message {
+        role: "user".to_string(),
+        content: format_transcript(messages),
+    }];
+
+    let response = client
+        .send_message_with_system_and_tools(system, request, vec![])
+        .await
+        .map_err(|e| format!("Light model call failed: {}", e))?;
+
+    Ok(extract_text_from_content_blocks(&response.content))
+}
+
+/// Format messages as a transcript, keeping the most recent text within budget
+fn format_transcript(messages: &[Message]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut total = 0;
+
+    for message in messages.iter().rev() {
+        let part = format!("[{}]: {}", message.role, message.content);
+        total += part.len();
+        if total > MAX_TRANSCRIPT_CHARS && !parts.is_empty() {
+            break;
+        }
+        parts.push(part);
+    }
+
+    parts.reverse();
+    parts.join("\n\n")
+}
+
+/// Normalize a model-generated title: first line only, no prefix, quotes or
+/// trailing punctuation, and at most MAX_TITLE_WORDS words
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line);
+    let line = line
+        .trim()
+        .trim_matches(|c: char| c == '"' || c == '\'' || c == '*' || c == '#')
+        .trim_end_matches(['.', '!', ':', ';', ','])
+        .trim();
+
+    let title = line
+        .split_whitespace()
+        .take(MAX_TITLE_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_title_strips_decoration() {
+        assert_eq!(
+            clean_title("Title: \"Fixing the Pipeline Retry Logic.\"\n"),
+            Some("Fixing the Pipeline Retry Logic".to_string())
+        );
+    }
+
+    #[test]
+    fn test_clean_title_limits_words() {
+        let title = clean_title("one two three four five six seven eight nine ten").unwrap();
+        assert_eq!(title.split_whitespace().count(), MAX_TITLE_WORDS);
+    }
+
+    #[test]
+    fn test_clean_title_empty() {
+        assert_eq!(clean_title("  \n\"\"\n"), None);
+    }
+
+    #[test]
+    fn test_format_transcript_keeps_recent_messages() {
+        let messages = vec![
+            Message {
+                role: "user".to_string(),
+                content: "a".repeat(MAX_TRANSCRIPT_CHARS),
+            },
+            Message {
+                role: "assistant".to_string(),
+                content: "latest".to_string(),
+            },
+        ];
+
+        let transcript = format_transcript(&messages);
+        assert!(transcript.contains("[assistant]: latest"));
+        assert!(!transcript.contains("[user]"));
+    }
+}
@@ -0,0 +1,310 @@
+// Directory approval for worker agents created by the meta-agent
+//
+// CreateWorkerAgent accepts any path the model puts in its tool input. When
+// the target directory is neither the working directory of a recent run nor
+// inside a directory on the workspace allowlist, the tool blocks until the
+// user approves or denies it:
+// - meta-agent:directory-approval is emitted with a nonce
+// - the approve_directory command resolves the nonce
+// Approving with "remember" adds the directory to the allowlist, which is
+// persisted in the active workspace's config directory.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::events::AppEventEmitter;
+use crate::workspace;
+
+/// File in the config directory holding the workspace allowlist
+const ALLOWLIST_FILE: &str = "workspace_allowlist.json";
+
+/// How long CreateWorkerAgent waits for the user before treating it as a denial
+pub const DIRECTORY_APPROVAL_TIMEOUT_SECS: u64 = 300;
+
+/// The user's answer to a directory approval request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectoryDecision {
+    pub approved: bool,
+    pub remember: bool,
+}
+
+/// A directory waiting for the user's approval
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryApprovalRequest {
+    pub nonce: String,
+    pub working_dir: String,
+    pub timeout_secs: u64,
+    pub requested_at: i64,
+}
+
+struct PendingApproval {
+    request: DirectoryApprovalRequest,
+    response_tx: oneshot::Sender<DirectoryDecision>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AllowlistFile {
+    directories: Vec<String>,
+}
+
+/// Pending directory approvals and the workspace allowlist.
+///
+/// Shared between the meta-agent tool loop and the Tauri commands (which must
+/// not lock the MetaAgent while a tool is blocked waiting for the user).
+pub struct DirectoryApprovals {
+    pending: Mutex<HashMap<String, PendingApproval>>,
+    allowlist: Mutex<Vec<PathBuf>>,
+    allowlist_path: Mutex<Option<PathBuf>>,
+    timeout: Duration,
+}
+
+pub type SharedDirectoryApprovals = Arc<DirectoryApprovals>;
+
+impl Default for DirectoryApprovals {
+    fn default() -> Self {
+        Self::with_allowlist_path(None)
+    }
+}
+
+impl DirectoryApprovals {
+    /// Load the active workspace's allowlist
+    pub fn load() -> Self {
+        Self::with_allowlist_path(allowlist_file(&workspace::active_workspace_id()))
+    }
+
+    fn with_allowlist_path(allowlist_path: Option<PathBuf>) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            allowlist: Mutex::new(read_allowlist(allowlist_path.as_deref())),
+            allowlist_path: Mutex::new(allowlist_path),
+            timeout: Duration::from_secs(DIRECTORY_APPROVAL_TIMEOUT_SECS),
+        }
+    }
+
+    /// Swap in another workspace's allowlist (pending requests are kept)
+    pub async fn load_workspace(&self, workspace_id: &str) {
+        let path = allowlist_file(workspace_id);
+        let mut allowlist = self.allowlist.lock().await;
+        *allowlist = read_allowlist(path.as_deref());
+        *self.allowlist_path.lock().await = path;
+    }
+
+    /// Whether the directory is on (or inside a directory on) the allowlist
+    pub async fn is_allowlisted(&self, working_dir: &str) -> bool {
+        let dir = normalize(working_dir);
+        let allowlist = self.allowlist.lock().await;
+        allowlist.iter().any(|root| dir.starts_with(root))
+    }
+
+    /// Directories on the allowlist (empty when the allowlist is not in use)
+    pub async fn allowlisted_roots(&self) -> Vec<PathBuf> {
+        self.allowlist.lock().await.clone()
+    }
+
+    /// Add a directory to the allowlist and persist it
+    pub async fn remember(&self, working_dir: &str) -> Result<(), String> {
+        let dir = normalize(working_dir);
+        let mut allowlist = self.allowlist.lock().await;
+        if allowlist.contains(&dir) {
+            return Ok(());
+        }
+        allowlist.push(dir);
+
+        let Some(path) = self.allowlist_path.lock().await.clone() else {
+            return Ok(());
+        };
+        let file = AllowlistFile {
+            directories: allowlist
+                .iter()
+                .map(|d| d.to_string_lossy().to_string())
+                .collect(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize workspace allowlist: {}", e))?;
+        std::fs::write(path, json)
+            .map_err(|e| format!("Failed to write workspace allowlist: {}", e))
+    }
+
+    /// Ask the user to approve a directory and wait for the answer.
+    ///
+    /// Returns None if nobody answers before the timeout.
+    pub async fn request(
+        &self,
+        emitter: &dyn AppEventEmitter,
+        working_dir: &str,
+    ) -> Option<DirectoryDecision> {
+        let request = DirectoryApprovalRequest {
+            nonce: uuid::Uuid::new_v4().to_string(),
+            working_dir: working_dir.to_string(),
+            timeout_secs: self.timeout.as_secs(),
+            requested_at: chrono::Utc::now().timestamp_millis(),
+        };
+        let nonce = request.nonce.clone();
+        let (tx, rx) = oneshot::channel();
+
+        self.pending.lock().await.insert(
+            nonce.clone(),
+            PendingApproval {
+                request: request.clone(),
+                response_tx: tx,
+            },
+        );
+
+        let _ = emitter.emit(
+            "meta-agent:directory-approval",
+            serde_json::to_value(&request).unwrap_or_default(),
+        );
+
+        let decision = tokio::time::timeout(self.timeout, rx)
+            .await
+            .ok()
+            .and_then(|r| r.ok());
+
+        // Nobody answered: drop the request and let the UI dismiss it
+        if decision.is_none() {
+            self.pending.lock().await.remove(&nonce);
+        }
+
+        let _ = emitter.emit(
+            "meta-agent:directory-approval-resolved",
+            json!({
+                "nonce": nonce,
+                "working_dir": working_dir,
+                "approved": decision.is_some_and(|d| d.approved),
+                "timed_out": decision.is_none()
+            }),
+        );
+
+        if let Some(d) = decision {
+            if d.approved && d.remember {
+                if let Err(e) = self.remember(working_dir).await {
+                    eprintln!("[DirectoryApproval] {}", e);
+                }
+            }
+        }
+
+        decision
+    }
+
+    /// Deliver the user's answer to a pending request
+    pub async fn resolve(&self, nonce: &str, approved: bool, remember: bool) -> Result<(), String> {
+        let pending = self
+            .pending
+            .lock()
+            .await
+            .remove(nonce)
+            .ok_or_else(|| format!("No pending directory approval with nonce {}", nonce))?;
+
+        pending
+            .response_tx
+            .send(DirectoryDecision { approved, remember })
+            .map_err(|_| "Failed to deliver directory approval: request was cancelled".to_string())
+    }
+
+    /// Requests still waiting for an answer
+    pub async fn pending_requests(&self) -> Vec<DirectoryApprovalRequest> {
+        let pending = self.pending.lock().await;
+        let mut requests: Vec<_> = pending.values().map(|p| p.request.clone()).collect();
+        requests.sort_by_key(|r| r.requested_at);
+        requests
+    }
+}
+
+/// Allowlist file for a workspace
+fn allowlist_file(workspace_id: &str) -> Option<PathBuf> {
+    workspace::config_dir(workspace_id).map(|d| d.join(ALLOWLIST_FILE))
+}
+
+/// Read an allowlist file (empty when missing or unreadable)
+fn read_allowlist(path: Option<&Path>) -> Vec<PathBuf> {
+    path.and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|content| serde_json::from_str::<AllowlistFile>(&content).ok())
+        .map(|file| file.directories.into_iter().map(PathBuf::from).collect())
+        .unwrap_or_default()
+}
+
+/// Canonical form of a directory so symlinks and trailing slashes compare equal
+fn normalize(working_dir: &str) -> PathBuf {
+    let path = Path::new(working_dir);
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether the directory is the working directory of one of the given runs
+pub fn is_recent_dir(working_dir: &str, recent_dirs: &[String]) -> bool {
+    let dir = normalize(working_dir);
+    recent_dirs.iter().any(|recent| normalize(recent) == dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NullEmitter;
+
+    impl AppEventEmitter for NullEmitter {
+        fn emit(&self, _event: &str, _payload: serde_json::Value) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remember_allowlists_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("workspace");
+        let project = workspace.join("project");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let allowlist_path = dir.path().join(ALLOWLIST_FILE);
+        let approvals = DirectoryApprovals::with_allowlist_path(Some(allowlist_path.clone()));
+        assert!(!approvals.is_allowlisted(project.to_str().unwrap()).await);
+
+        approvals
+            .remember(workspace.to_str().unwrap())
+            .await
+            .unwrap();
+        assert!(approvals.is_allowlisted(project.to_str().unwrap()).await);
+        assert!(!approvals.is_allowlisted(dir.path().to_str().unwrap()).await);
+
+        // The allowlist survives a reload
+        let reloaded = DirectoryApprovals::with_allowlist_path(Some(allowlist_path));
+        assert!(reloaded.is_allowlisted(project.to_str().unwrap()).await);
+    }
+
+    #[tokio::test]
+    async fn test_request_waits_for_resolve() {
+        let approvals = Arc::new(DirectoryApprovals::default());
+
+        let requester = approvals.clone();
+        let handle = tokio::spawn(async move { requester.request(&NullEmitter, "/tmp").await });
+
+        let nonce = loop {
+            if let Some(request) = approvals.pending_requests().await.pop() {
+                break request.nonce;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert!(approvals.resolve("unknown", true, false).await.is_err());
+        approvals.resolve(&nonce, false, false).await.unwrap();
+
+        let decision = handle.await.unwrap().unwrap();
+        assert!(!decision.approved);
+        assert!(approvals.pending_requests().await.is_empty());
+    }
+
+    #[test]
+    fn test_is_recent_dir_ignores_trailing_slash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+        assert!(is_recent_dir(&format!("{}/", path), &[path.clone()]));
+        assert!(!is_recent_dir("/definitely/not/used", &[path]));
+    }
+}
@@ -0,0 +1,188 @@
+// Tool pairing repair for conversation history
+//
+// Providers reject a history where an assistant tool_use block isn't answered
+// by a tool_result in the next user message, or where a tool_result refers to a
+// tool_use that isn't in the previous assistant message. Conversations saved
+// before tool blocks were persisted, loops that failed between the two
+// messages, and context compaction can all leave such halves behind. They are
+// dropped here, with a warning, before the history is sent.
+//
+// Messages are never removed (an emptied message gets placeholder text), so
+// the repaired history stays index-aligned with the conversation manager.
+
+use std::collections::HashSet;
+
+use crate::ai_client::{RichContentBlock, RichMessage, RichMessageContent};
+
+/// IDs of the tool_use blocks in an assistant message
+fn tool_use_ids(msg: &RichMessage) -> HashSet<&str> {
+    match &msg.content {
+        RichMessageContent::Blocks(blocks) if msg.role == "assistant" => blocks
+            .iter()
+            .filter_map(|block| match block {
+                RichContentBlock::ToolUse { id, .. } => Some(id.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// IDs answered by the tool_result blocks in a user message
+fn tool_result_ids(msg: &RichMessage) -> HashSet<&str> {
+    match &msg.content {
+        RichMessageContent::Blocks(blocks) if msg.role == "user" => blocks
+            .iter()
+            .filter_map(|block| match block {
+                RichContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// Drop tool_use and tool_result blocks that don't have their other half
+/// in the adjacent message
+pub fn repair_tool_pairs(messages: Vec<RichMessage>) -> Vec<RichMessage> {
+    let empty = HashSet::new();
+    let uses: Vec<HashSet<&str>> = messages.iter().map(tool_use_ids).collect();
+    let results: Vec<HashSet<&str>> = messages.iter().map(tool_result_ids).collect();
+
+    let mut dropped = 0;
+    let repaired: Vec<RichMessage> = messages
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| {
+            let RichMessageContent::Blocks(blocks) = &msg.content else {
+                return msg.clone();
+            };
+            let answered = results.get(i + 1).unwrap_or(&empty);
+            let asked = i.checked_sub(1).map_or(&empty, |prev| &uses[prev]);
+
+            let kept: Vec<RichContentBlock> = blocks
+                .iter()
+                .filter(|block| match block {
+                    RichContentBlock::ToolUse { id, .. } => {
+                        msg.role == "assistant" && answered.contains(id.as_str())
+                    }
+                    RichContentBlock::ToolResult { tool_use_id, .. } => {
+                        msg.role == "user" && asked.contains(tool_use_id.as_str())
+                    }
+                    _ => true,
+                })
+                .cloned()
+                .collect();
+            dropped += blocks.len() - kept.len();
+
+            let content = if kept.is_empty() {
+                let placeholder = if msg.role == "assistant" {
+                    "[Tool call omitted]"
+                } else {
+                    "[Tool result omitted]"
+                };
+                RichMessageContent::Text(placeholder.to_string())
+            } else {
+                RichMessageContent::Blocks(kept)
+            };
+            RichMessage {
+                role: msg.role.clone(),
+                content,
+            }
+        })
+        .collect();
+
+    if dropped > 0 {
+        eprintln!(
+            "[MetaAgent] Warning: dropped {} unmatched tool_use/tool_result block(s) from history",
+            dropped
+        );
+    }
+
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool_use(id: &str) -> RichContentBlock {
+        RichContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "ListWorkerAgents".to_string(),
+            input: json!({}),
+        }
+    }
+
+    fn tool_result(id: &str) -> RichContentBlock {
+        RichContentBlock::ToolResult {
+            tool_use_id: id.to_string(),
+            content: "[]".to_string(),
+            is_error: None,
+        }
+    }
+
+    fn blocks(role: &str, blocks: Vec<RichContentBlock>) -> RichMessage {
+        RichMessage {
+            role: role.to_string(),
+            content: RichMessageContent::Blocks(blocks),
+        }
+    }
+
+    fn text(role: &str, text: &str) -> RichMessage {
+        RichMessage {
+            role: role.to_string(),
+            content: RichMessageContent::Text(text.to_string()),
+        }
+    }
+
+    fn block_count(msg: &RichMessage) -> usize {
+        match &msg.content {
+            RichMessageContent::Blocks(blocks) => blocks.len(),
+            RichMessageContent::Text(_) => 0,
+        }
+    }
+
+    #[test]
+    fn test_matched_pairs_are_kept() {
+        let history = vec![
+            text("user", "What's running?"),
+            blocks("assistant", vec![tool_use("t1"), tool_use("t2")]),
+            blocks("user", vec![tool_result("t1"), tool_result("t2")]),
+            text("assistant", "Nothing."),
+        ];
+        let repaired = repair_tool_pairs(history);
+        assert_eq!(block_count(&repaired[1]), 2);
+        assert_eq!(block_count(&repaired[2]), 2);
+    }
+
+    #[test]
+    fn test_unmatched_halves_are_dropped() {
+        let history = vec![
+            blocks(
+                "assistant",
+                vec![
+                    RichContentBlock::Text {
+                        text: "Checking".to_string(),
+                    },
+                    tool_use("t1"),
+                    tool_use("lost"),
+                ],
+            ),
+            blocks("user", vec![tool_result("t1"), tool_result("orphan")]),
+            // The loop failed before the result was recorded
+            blocks("assistant", vec![tool_use("t3")]),
+            text("user", "Any update?"),
+        ];
+        let repaired = repair_tool_pairs(history);
+
+        assert_eq!(repaired.len(), 4);
+        assert_eq!(block_count(&repaired[0]), 2);
+        assert_eq!(block_count(&repaired[1]), 1);
+        assert!(matches!(
+            &repaired[2].content,
+            RichMessageContent::Text(t) if t == "[Tool call omitted]"
+        ));
+    }
+}
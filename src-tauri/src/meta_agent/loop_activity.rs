@@ -0,0 +1,337 @@
+// Tool loop activity tracking for MetaAgent
+//
+// The tool loop records what it is doing in a small shared structure so the
+// UI can ask for the current loop state without taking the MetaAgent lock,
+// which is held for the whole duration of a loop.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::types::MetaAgentIterationEvent;
+
+/// Shared loop activity, owned by MetaAgent and shared with AppState
+pub type SharedLoopActivity = Arc<std::sync::Mutex<LoopActivity>>;
+
+/// Conditions that end a Sleep early (mirrors the race in the Sleep tool)
+const SLEEP_WAKE_CONDITIONS: [&str; 3] = ["timer", "user_message", "agent_event"];
+
+/// What the loop is doing within the current iteration
+#[derive(Debug, Clone)]
+enum LoopPhase {
+    /// Waiting on the model
+    Thinking,
+    /// Executing a tool
+    RunningTool { tool_name: String },
+    /// Inside the Sleep tool
+    Sleeping {
+        reason: String,
+        duration_ms: u64,
+        since: Instant,
+    },
+    /// Inside the AskUserQuestion tool
+    AwaitingAnswer {
+        question: String,
+        options: Option<Vec<String>>,
+    },
+}
+
+/// Mutable loop state recorded by the tool loop engine
+#[derive(Debug, Default)]
+pub struct LoopActivity {
+    /// Set while a loop is running; None means idle
+    started_at: Option<Instant>,
+    iteration: usize,
+    max_iterations: usize,
+    phase: Option<LoopPhase>,
+    /// Set by a stop phrase; the loop ends before its next model call
+    halt_requested: bool,
+}
+
+/// Serializable view of the loop state returned to the frontend
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LoopActivitySnapshot {
+    Idle,
+    Running {
+        iteration: usize,
+        max_iterations: usize,
+        elapsed_ms: u64,
+        current_tool: Option<String>,
+    },
+    Sleeping {
+        iteration: usize,
+        max_iterations: usize,
+        elapsed_ms: u64,
+        reason: String,
+        duration_ms: u64,
+        remaining_ms: u64,
+        wake_conditions: Vec<String>,
+    },
+    AwaitingAnswer {
+        iteration: usize,
+        max_iterations: usize,
+        elapsed_ms: u64,
+        question: String,
+        options: Option<Vec<String>>,
+    },
+}
+
+impl LoopActivity {
+    /// Mark the start of a tool loop
+    pub fn start(&mut self, max_iterations: usize) {
+        self.started_at = Some(Instant::now());
+        self.iteration = 0;
+        self.max_iterations = max_iterations;
+        self.phase = Some(LoopPhase::Thinking);
+        self.halt_requested = false;
+    }
+
+    /// Ask a running loop to stop; returns whether a loop was running
+    pub fn request_halt(&mut self) -> bool {
+        let running = self.started_at.is_some();
+        self.halt_requested = running;
+        running
+    }
+
+    /// Whether the loop should stop before calling the model again
+    pub fn halt_requested(&self) -> bool {
+        self.halt_requested
+    }
+
+    /// Record the iteration the loop is on (the counter resets after Sleep)
+    pub fn set_iteration(&mut self, iteration: usize) {
+        self.iteration = iteration;
+        self.phase = Some(LoopPhase::Thinking);
+    }
+
+    /// Record that a tool is about to run and build the matching iteration event
+    pub fn begin_tool(&mut self, tool_name: &str, input: &Value) -> MetaAgentIterationEvent {
+        self.phase = Some(match tool_name {
+            "Sleep" => LoopPhase::Sleeping {
+                reason: input["reason"].as_str().unwrap_or("Waiting...").to_string(),
+                duration_ms: (input["duration_minutes"].as_f64().unwrap_or(1.0) * 60_000.0) as u64,
+                since: Instant::now(),
+            },
+            "AskUserQuestion" => LoopPhase::AwaitingAnswer {
+                question: input["question"].as_str().unwrap_or_default().to_string(),
+                options: input["options"].as_array().map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                }),
+            },
+            _ => LoopPhase::RunningTool {
+                tool_name: tool_name.to_string(),
+            },
+        });
+
+        MetaAgentIterationEvent {
+            iteration: self.iteration,
+            max_iterations: self.max_iterations,
+            tool_name: Some(tool_name.to_string()),
+            elapsed_ms: self.elapsed_ms(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Record that the current tool finished
+    pub fn end_tool(&mut self) {
+        self.phase = Some(LoopPhase::Thinking);
+    }
+
+    /// Mark the loop as finished
+    pub fn finish(&mut self) {
+        *self = Self::default();
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started_at
+            .map(|t| t.elapsed().as_millis() as u64)
+            .unwrap_or(0)
+    }
+
+    /// Take a serializable snapshot of the current loop state
+    pub fn snapshot(&self) -> LoopActivitySnapshot {
+        if self.started_at.is_none() {
+            return LoopActivitySnapshot::Idle;
+        }
+        let iteration = self.iteration;
+        let max_iterations = self.max_iterations;
+        let elapsed_ms = self.elapsed_ms();
+
+        match self.phase.clone().unwrap_or(LoopPhase::Thinking) {
+            LoopPhase::Thinking => LoopActivitySnapshot::Running {
+                iteration,
+                max_iterations,
+                elapsed_ms,
+                current_tool: None,
+            },
+            LoopPhase::RunningTool { tool_name } => LoopActivitySnapshot::Running {
+                iteration,
+                max_iterations,
+                elapsed_ms,
+                current_tool: Some(tool_name),
+            },
+            LoopPhase::Sleeping {
+                reason,
+                duration_ms,
+                since,
+            } => LoopActivitySnapshot::Sleeping {
+                iteration,
+                max_iterations,
+                elapsed_ms,
+                reason,
+                duration_ms,
+                remaining_ms: duration_ms.saturating_sub(since.elapsed().as_millis() as u64),
+                wake_conditions: SLEEP_WAKE_CONDITIONS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            },
+            LoopPhase::AwaitingAnswer { question, options } => {
+                LoopActivitySnapshot::AwaitingAnswer {
+                    iteration,
+                    max_iterations,
+                    elapsed_ms,
+                    question,
+                    options,
+                }
+            }
+        }
+    }
+}
+
+/// Marks the loop as running for its lifetime and resets it to idle on drop,
+/// so early returns from API errors never leave a stale "running" state
+pub struct LoopActivityGuard {
+    activity: SharedLoopActivity,
+}
+
+impl LoopActivityGuard {
+    pub fn start(activity: &SharedLoopActivity, max_iterations: usize) -> Self {
+        activity.lock().unwrap().start(max_iterations);
+        Self {
+            activity: activity.clone(),
+        }
+    }
+}
+
+impl Drop for LoopActivityGuard {
+    fn drop(&mut self) {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_idle_by_default() {
+        let activity = LoopActivity::default();
+        assert_eq!(activity.snapshot(), LoopActivitySnapshot::Idle);
+    }
+
+    #[test]
+    fn test_running_tool_and_event() {
+        let mut activity = LoopActivity::default();
+        activity.start(40);
+        activity.set_iteration(3);
+
+        let event = activity.begin_tool("ListAgents", &json!({}));
+        assert_eq!(event.iteration, 3);
+        assert_eq!(event.max_iterations, 40);
+        assert_eq!(event.tool_name.as_deref(), Some("ListAgents"));
+
+        match activity.snapshot() {
+            LoopActivitySnapshot::Running { current_tool, .. } => {
+                assert_eq!(current_tool.as_deref(), Some("ListAgents"));
+            }
+            other => panic!("unexpected snapshot: {:?}", other),
+        }
+
+        activity.end_tool();
+        match activity.snapshot() {
+            LoopActivitySnapshot::Running { current_tool, .. } => assert!(current_tool.is_none()),
+            other => panic!("unexpected snapshot: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sleep_and_question_phases() {
+        let mut activity = LoopActivity::default();
+        activity.start(40);
+        activity.set_iteration(1);
+
+        activity.begin_tool(
+            "Sleep",
+            &json!({"duration_minutes": 2.0, "reason": "waiting on build"}),
+        );
+        match activity.snapshot() {
+            LoopActivitySnapshot::Sleeping {
+                reason,
+                duration_ms,
+                remaining_ms,
+                wake_conditions,
+                ..
+            } => {
+                assert_eq!(reason, "waiting on build");
+                assert_eq!(duration_ms, 120_000);
+                assert!(remaining_ms <= duration_ms);
+                assert_eq!(wake_conditions.len(), 3);
+            }
+            other => panic!("unexpected snapshot: {:?}", other),
+        }
+
+        activity.begin_tool(
+            "AskUserQuestion",
+            &json!({"question": "Proceed?", "options": ["yes", "no"]}),
+        );
+        match activity.snapshot() {
+            LoopActivitySnapshot::AwaitingAnswer {
+                question, options, ..
+            } => {
+                assert_eq!(question, "Proceed?");
+                assert_eq!(options, Some(vec!["yes".to_string(), "no".to_string()]));
+            }
+            other => panic!("unexpected snapshot: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_halt_only_applies_to_the_running_loop() {
+        let mut activity = LoopActivity::default();
+        assert!(!activity.request_halt());
+        assert!(!activity.halt_requested());
+
+        activity.start(40);
+        assert!(activity.request_halt());
+        assert!(activity.halt_requested());
+
+        // The next loop starts clean
+        activity.finish();
+        activity.start(40);
+        assert!(!activity.halt_requested());
+    }
+
+    #[test]
+    fn test_guard_resets_to_idle() {
+        let shared: SharedLoopActivity = Arc::new(std::sync::Mutex::new(LoopActivity::default()));
+        {
+            let _guard = LoopActivityGuard::start(&shared, 40);
+            assert_ne!(
+                shared.lock().unwrap().snapshot(),
+                LoopActivitySnapshot::Idle
+            );
+        }
+        assert_eq!(
+            shared.lock().unwrap().snapshot(),
+            LoopActivitySnapshot::Idle
+        );
+    }
+}
@@ -2,6 +2,7 @@
 //
 // Uses a light model (haiku) to manage a memory directory with multiple files.
 // Maintains a concise MEMORY.md summary that gets injected into the meta agent's context.
+// Each workspace has its own memory directory.
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -10,6 +11,7 @@ use std::path::{Path, PathBuf};
 use tiktoken_rs::cl100k_base;
 
 use crate::ai_client::{AIClient, ContentBlock, Message, Tool};
+use crate::workspace;
 
 /// Maximum tokens allowed in MEMORY.md
 const MEMORY_MD_TOKEN_LIMIT: usize = 2000;
@@ -38,10 +40,14 @@ pub struct MemoryManager {
 }
 
 impl MemoryManager {
-    /// Create a new memory manager
+    /// Create a memory manager for the active workspace
     pub fn new() -> Option<Self> {
-        let data_dir = dirs::data_local_dir()?;
-        let memory_dir = data_dir.join("claude-commander").join(MEMORY_DIR_NAME);
+        Self::for_workspace(&workspace::active_workspace_id())
+    }
+
+    /// Create a memory manager for a workspace's memory directory
+    pub fn for_workspace(workspace_id: &str) -> Option<Self> {
+        let memory_dir = workspace::data_dir(workspace_id)?.join(MEMORY_DIR_NAME);
 
         Some(Self { memory_dir })
     }
@@ -7,14 +7,62 @@
 // IMPORTANT: Must use tauri::async_runtime::spawn instead of tokio::spawn because
 // tokio::spawn can silently fail in Tauri apps (tasks start but async ops don't complete).
 // See: https://github.com/tauri-apps/tauri/discussions/11831
+//
+// Past conversations are scanned too: every HISTORY_SCAN_INTERVAL, conversations
+// that have gone quiet since a workspace's last scan are evaluated like live
+// ones, so lessons from one chat carry over to the next. Each cycle reads at most
+// MEMORY_HISTORY_SCAN_MAX_CHARS of conversation text; MEMORY_HISTORY_SCAN=false
+// turns the scan off.
+
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::agent_runs_db::{AgentRunsDB, MetaMessageRecord};
 use crate::ai_client::{AIClient, ContentBlock, Message};
+use crate::commands::env_registry;
+use crate::utils::string::truncate_with_ellipsis;
+use crate::workspace;
 
+use super::conversation_titles;
 use super::memory_manager::MemoryManager;
 
 /// Minimum messages required for evaluation (skip if too few)
 const MIN_MESSAGES_FOR_EVAL: usize = 3;
 
+/// How often closed conversations are scanned for memory
+const HISTORY_SCAN_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// A conversation counts as closed once it has been quiet this long
+const CONVERSATION_IDLE_MS: i64 = 30 * 60 * 1000;
+
+/// How far back the first scan of a workspace looks
+const FIRST_SCAN_LOOKBACK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Conversation text read per scan cycle (MEMORY_HISTORY_SCAN_MAX_CHARS)
+pub const DEFAULT_HISTORY_SCAN_MAX_CHARS: usize = 20_000;
+
+/// Whether MEMORY_HISTORY_SCAN allows the scan (default: on)
+fn history_scan_enabled() -> bool {
+    env_registry::MEMORY_HISTORY_SCAN
+        .var()
+        .map(|v| {
+            !matches!(
+                v.trim().to_lowercase().as_str(),
+                "0" | "false" | "no" | "off"
+            )
+        })
+        .unwrap_or(true)
+}
+
+fn history_scan_max_chars() -> usize {
+    env_registry::MEMORY_HISTORY_SCAN_MAX_CHARS
+        .var()
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_HISTORY_SCAN_MAX_CHARS)
+}
+
 /// Background worker for async memory updates
 ///
 /// Uses tauri::async_runtime::spawn to run memory tasks as detached async tasks.
@@ -39,10 +87,13 @@ impl MemoryWorker {
             &instruction[..instruction.len().min(50)]
         );
 
+        // The update belongs to the workspace active now, even if it is switched meanwhile
+        let workspace_id = workspace::active_workspace_id();
+
         // Use tauri::async_runtime::spawn instead of tokio::spawn
         // (tokio::spawn can silently fail in Tauri apps)
         tauri::async_runtime::spawn(async move {
-            if let Err(e) = process_update(&instruction).await {
+            if let Err(e) = process_update(&workspace_id, &instruction).await {
                 eprintln!("[MemoryWorker] Update failed: {}", e);
             } else {
                 eprintln!("[MemoryWorker] Update completed successfully");
@@ -67,6 +118,8 @@ impl MemoryWorker {
             recent_messages.len()
         );
 
+        let workspace_id = workspace::active_workspace_id();
+
         // Use tauri::async_runtime::spawn instead of tokio::spawn
         // (tokio::spawn can silently fail in Tauri apps)
         tauri::async_runtime::spawn(async move {
@@ -86,19 +139,190 @@ impl MemoryWorker {
                 }
             };
 
-            if let Err(e) = process_evaluation(recent_messages, &client).await {
+            if let Err(e) = process_evaluation(&workspace_id, recent_messages, &client).await {
                 eprintln!("[MemoryWorker] Evaluation failed: {}", e);
             } else {
                 eprintln!("[MemoryWorker] Evaluation completed successfully");
             }
         });
     }
+
+    /// Queue a title regeneration for a conversation (spawns detached task)
+    ///
+    /// The generated title is dropped if the user has renamed the conversation.
+    pub fn queue_title_generation(
+        &self,
+        db: Arc<AgentRunsDB>,
+        conversation_id: String,
+        messages: Vec<Message>,
+    ) {
+        eprintln!(
+            "[MemoryWorker] Queuing title generation for {} ({} messages)",
+            conversation_id,
+            messages.len()
+        );
+
+        tauri::async_runtime::spawn(async move {
+            let client = match AIClient::light_from_env() {
+                Ok(client) => client,
+                Err(e) => {
+                    eprintln!("[MemoryWorker] Failed to create light client: {:?}", e);
+                    return;
+                }
+            };
+
+            let title = match conversation_titles::generate_title(&client, &messages).await {
+                Ok(title) => title,
+                Err(e) => {
+                    eprintln!("[MemoryWorker] Title generation failed: {}", e);
+                    return;
+                }
+            };
+
+            match db
+                .set_meta_conversation_generated_title(&conversation_id, &title)
+                .await
+            {
+                Ok(true) => eprintln!("[MemoryWorker] Conversation retitled: {}", title),
+                Ok(false) => eprintln!("[MemoryWorker] Title locked - keeping existing title"),
+                Err(e) => eprintln!("[MemoryWorker] Failed to store title: {}", e),
+            }
+        });
+    }
+
+    /// Spawn the background task that scans closed conversations for memory
+    pub fn start_history_scan(&self, db: Arc<AgentRunsDB>) {
+        if !history_scan_enabled() {
+            eprintln!("[MemoryWorker] History scan disabled (MEMORY_HISTORY_SCAN)");
+            return;
+        }
+        let max_chars = history_scan_max_chars();
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(HISTORY_SCAN_INTERVAL).await;
+                match scan_history(&db, max_chars).await {
+                    Ok(0) => {}
+                    Ok(scanned) => {
+                        eprintln!("[MemoryWorker] History scan read {} conversations", scanned)
+                    }
+                    Err(e) => eprintln!("[MemoryWorker] History scan failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Evaluate the conversations each workspace closed since its last scan,
+/// oldest first, within `max_chars` of text. A workspace's mark advances past
+/// each conversation once it is evaluated, so a failed or capped cycle picks up
+/// where it stopped. Returns the number of conversations evaluated.
+async fn scan_history(db: &AgentRunsDB, max_chars: usize) -> Result<usize, String> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let until = now - CONVERSATION_IDLE_MS;
+    let marks = db
+        .workspace_history_scan_marks()
+        .await
+        .map_err(|e| format!("Failed to read scan marks: {}", e))?;
+
+    let mut client: Option<AIClient> = None;
+    let mut budget = max_chars;
+    let mut scanned = 0;
+    for (workspace_id, scanned_at) in marks {
+        let after = scanned_at.unwrap_or(now - FIRST_SCAN_LOOKBACK_MS);
+        let conversations = db
+            .list_meta_conversations_updated_between(&workspace_id, after, until)
+            .await
+            .map_err(|e| format!("Failed to list conversations: {}", e))?;
+
+        let mut caught_up = true;
+        for conversation in conversations {
+            let records = db
+                .get_meta_messages(&conversation.conversation_id)
+                .await
+                .map_err(|e| format!("Failed to read messages: {}", e))?;
+            // A conversation that doesn't fit waits for a fresh budget, unless
+            // it wouldn't fit in one either; then only its end is read
+            if history_chars(&records) > budget && budget < max_chars {
+                caught_up = false;
+                break;
+            }
+
+            let (messages, used) = history_messages(&records, budget);
+            if messages.len() >= MIN_MESSAGES_FOR_EVAL {
+                budget -= used;
+                if client.is_none() {
+                    client = Some(
+                        AIClient::light_from_env()
+                            .map_err(|e| format!("Failed to create light client: {:?}", e))?,
+                    );
+                }
+                if let Some(client) = &client {
+                    process_evaluation(&workspace_id, messages, client).await?;
+                }
+                scanned += 1;
+            }
+            db.set_workspace_history_scanned_at(&workspace_id, conversation.updated_at)
+                .await
+                .map_err(|e| format!("Failed to store scan mark: {}", e))?;
+        }
+
+        if !caught_up {
+            break;
+        }
+        db.set_workspace_history_scanned_at(&workspace_id, until)
+            .await
+            .map_err(|e| format!("Failed to store scan mark: {}", e))?;
+    }
+
+    Ok(scanned)
+}
+
+/// Whether a stored message is conversation text worth evaluating
+fn is_history_text(record: &MetaMessageRecord) -> bool {
+    (record.role == "user" || record.role == "assistant") && !record.content.trim().is_empty()
+}
+
+/// Characters of conversation text in a conversation's messages
+fn history_chars(records: &[MetaMessageRecord]) -> usize {
+    records
+        .iter()
+        .filter(|r| is_history_text(r))
+        .map(|r| r.content.trim().len())
+        .sum()
+}
+
+/// The latest user and assistant messages that fit in `budget` characters,
+/// oldest first, with the characters they take. The newest message is cut
+/// short if it doesn't fit on its own.
+fn history_messages(records: &[MetaMessageRecord], budget: usize) -> (Vec<Message>, usize) {
+    let mut messages = Vec::new();
+    let mut used = 0;
+    for record in records.iter().rev().filter(|r| is_history_text(r)) {
+        let content = record.content.trim();
+        let remaining = budget - used;
+        let content = if content.len() <= remaining {
+            content.to_string()
+        } else if messages.is_empty() && remaining > 0 {
+            truncate_with_ellipsis(content, remaining)
+        } else {
+            break;
+        };
+        used += content.len().min(remaining);
+        messages.push(Message {
+            role: record.role.clone(),
+            content,
+        });
+    }
+    messages.reverse();
+    (messages, used)
 }
 
 /// Process an explicit update request
-async fn process_update(instruction: &str) -> Result<(), String> {
+async fn process_update(workspace_id: &str, instruction: &str) -> Result<(), String> {
     eprintln!("[MemoryWorker] Update Step 1: Creating memory manager");
-    let manager = MemoryManager::new().ok_or("Failed to create memory manager")?;
+    let manager =
+        MemoryManager::for_workspace(workspace_id).ok_or("Failed to create memory manager")?;
 
     eprintln!("[MemoryWorker] Update Step 2: Calling update_memory");
     let result = manager.update_memory(instruction).await?;
@@ -113,11 +337,13 @@ async fn process_update(instruction: &str) -> Result<(), String> {
 
 /// Process an evaluation request - decide if memory needs updating
 async fn process_evaluation(
+    workspace_id: &str,
     recent_messages: Vec<Message>,
     client: &AIClient,
 ) -> Result<(), String> {
     eprintln!("[MemoryWorker] Step 1: Creating memory manager");
-    let manager = MemoryManager::new().ok_or("Failed to create memory manager")?;
+    let manager =
+        MemoryManager::for_workspace(workspace_id).ok_or("Failed to create memory manager")?;
 
     eprintln!("[MemoryWorker] Step 2: Reading current memory");
     let current_memory = manager.read_memory();
@@ -269,6 +495,43 @@ mod tests {
         assert!(formatted.contains("[assistant]: Hi there"));
     }
 
+    #[test]
+    fn test_history_messages_keep_latest_within_budget() {
+        let record = |message_index: u32, role: &str, content: &str| MetaMessageRecord {
+            id: None,
+            conversation_id: "conv".to_string(),
+            message_index,
+            role: role.to_string(),
+            content: content.to_string(),
+            image_data: None,
+            tool_calls: None,
+            timestamp: message_index as i64,
+            model: None,
+            personality_hash: None,
+            rating: None,
+            rating_note: None,
+            rated_at: None,
+        };
+        let records = vec![
+            record(0, "user", "0123456789"),
+            record(1, "assistant", "abcde"),
+            record(2, "tool", "tool output is skipped"),
+            record(3, "user", "  fghij  "),
+        ];
+        assert_eq!(history_chars(&records), 20);
+
+        let (messages, used) = history_messages(&records, 12);
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["abcde", "fghij"]);
+        assert_eq!(used, 10);
+
+        // A newest message longer than the budget is cut short
+        let (messages, used) = history_messages(&records, 3);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "fgh...");
+        assert_eq!(used, 3);
+    }
+
     #[test]
     fn test_build_evaluation_prompt_empty_memory() {
         let prompt = build_evaluation_prompt("");
@@ -354,8 +617,9 @@ mod tests {
     #[test]
     #[ignore] // Run with: cargo test test_openai_api_spawned_thread -- --ignored
     fn test_openai_api_spawned_thread() {
-        let api_key =
-            std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set for this test");
+        let api_key = crate::commands::env_registry::OPENAI_API_KEY
+            .var()
+            .expect("OPENAI_API_KEY must be set for this test");
 
         let handle = thread::spawn(move || {
             let rt = tokio::runtime::Builder::new_multi_thread()
@@ -473,7 +737,7 @@ mod tests {
             ];
 
             eprintln!("[Test] Calling process_evaluation directly...");
-            process_evaluation(messages, &client).await
+            process_evaluation(workspace::DEFAULT_WORKSPACE_ID, messages, &client).await
         });
 
         match result {
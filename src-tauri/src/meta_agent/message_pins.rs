@@ -0,0 +1,105 @@
+// Messages kept verbatim through context compaction
+//
+// A summary of compacted history loses detail, and the detail most often
+// lost is a standing instruction ("never push to main", "always run the
+// tests first"). User messages that read like such a constraint are kept
+// word for word next to the summary, as are messages the user pins. The user
+// can also unpin a detected message to let it be summarized.
+
+/// Longest user message checked for constraints; longer ones are task
+/// descriptions rather than standing instructions
+const MAX_CONSTRAINT_CHARS: usize = 600;
+
+/// Phrases that mark a standing instruction
+const CONSTRAINT_PHRASES: &[&str] = &[
+    "always ",
+    "never ",
+    "don't ",
+    "do not ",
+    "must ",
+    "make sure",
+    "from now on",
+    "remember that",
+    "avoid ",
+    "only use",
+    "without asking",
+];
+
+/// A message kept verbatim in place of being summarized
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinnedMessage {
+    pub message_index: u32,
+    pub content: String,
+    /// Kept because it looks like a constraint rather than pinned by the user
+    pub detected: bool,
+}
+
+/// Whether a user message looks like a standing instruction
+pub fn looks_like_constraint(content: &str) -> bool {
+    if content.chars().count() > MAX_CONSTRAINT_CHARS {
+        return false;
+    }
+    let lower = content.to_lowercase();
+    CONSTRAINT_PHRASES
+        .iter()
+        .any(|phrase| lower.contains(phrase))
+}
+
+/// The pinned section of the context summary message
+pub fn format_pinned(pins: &[PinnedMessage]) -> String {
+    let mut out =
+        String::from("[PINNED - earlier user messages, kept word for word; still in effect:]");
+    for pin in pins {
+        let source = if pin.detected {
+            ""
+        } else {
+            ", pinned by the user"
+        };
+        out.push_str(&format!(
+            "\n\n(message {}{}) {}",
+            pin.message_index, source, pin.content
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constraint_detection() {
+        assert!(looks_like_constraint("Never push directly to main."));
+        assert!(looks_like_constraint(
+            "From now on, run the tests before committing"
+        ));
+        assert!(looks_like_constraint("Don't touch the migrations"));
+        assert!(!looks_like_constraint("What's the status of agent 3?"));
+        assert!(!looks_like_constraint(&format!(
+            "Always {}",
+            "x".repeat(MAX_CONSTRAINT_CHARS)
+        )));
+    }
+
+    #[test]
+    fn test_format_pinned() {
+        let pins = [
+            PinnedMessage {
+                message_index: 3,
+                content: "Never push to main".to_string(),
+                detected: true,
+            },
+            PinnedMessage {
+                message_index: 7,
+                content: "Target Rust 1.70".to_string(),
+                detected: false,
+            },
+        ];
+        assert_eq!(
+            format_pinned(&pins),
+            "[PINNED - earlier user messages, kept word for word; still in effect:]\
+             \n\n(message 3) Never push to main\
+             \n\n(message 7, pinned by the user) Target Rust 1.70"
+        );
+    }
+}
@@ -4,45 +4,70 @@
 // worker agents through a conversational interface.
 
 mod action_logger;
+mod agent_results;
+pub mod agent_router;
+mod chat_turns;
 mod context_config;
 mod context_summarizer;
 mod context_tracker;
 mod conversation_manager;
+pub mod conversation_titles;
+pub mod directory_approval;
 pub mod helpers;
+mod history_repair;
+mod loop_activity;
 mod memory_manager;
 mod memory_worker;
+mod message_pins;
 mod output_compressor;
 mod prompt_generator;
+mod prompt_preview;
 mod result_queue;
 pub mod search_agent;
+mod search_filters;
 mod system_prompt;
 mod tool_loop_engine;
 pub mod tools;
 
+pub use agent_results::{format_agent_results, DEFAULT_RESULT_MAX_CHARS};
+pub use chat_turns::{BusyMode, ChatError, ChatQueueStatus, ChatTurns, SharedChatTurns, TurnGuard};
+pub use loop_activity::{LoopActivity, LoopActivitySnapshot, SharedLoopActivity};
 pub use prompt_generator::CommanderPersonality;
+pub use prompt_preview::{PromptSection, SystemPromptPreview, TokenEstimate};
 
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
-use crate::agent_runs_db::{AgentRunsDB, MetaConversationRecord, MetaMessageRecord};
-use crate::ai_client::{AIClient, Message, RichContentBlock, RichMessage, RichMessageContent};
+use crate::agent_runs_db::{
+    AgentRunsDB, CompactionRecord, MetaConversationRecord, MetaMessageRecord,
+};
+use crate::ai_client::types::MEMORY_SECTION_HEADING;
+use crate::ai_client::{AIClient, Message, RichMessage};
+use crate::auto_pipeline::AutoPipelineManager;
+use crate::cost_preference;
 use crate::error::{ApiError, AppError, AppResult};
 use crate::tool_registry::ToolRegistry;
 use crate::types::{
-    AgentResultStatus, ChatMessage, ChatResponse, ContextInfoEvent, ImageAttachment,
+    AgentResultStatus, AgentStatus, ChatMessage, ChatResponse, ContextInfoEvent, ImageAttachment,
     MetaAgentThinkingEvent, QueueStatus, QueuedAgentResult,
 };
 use crate::utils::string::{truncate_utf8, truncate_with_ellipsis};
+use crate::workspace;
 
 use context_config::ContextConfig;
 use conversation_manager::ConversationManager;
+use directory_approval::{DirectoryApprovals, SharedDirectoryApprovals};
 use memory_worker::MemoryWorker;
 use result_queue::ResultQueue;
-use system_prompt::build_system_prompt;
+pub use result_queue::SharedResultQueue;
+use system_prompt::{
+    build_system_prompt, clear_base_prompt_override, load_base_prompt_override,
+    save_base_prompt_override,
+};
 use tool_loop_engine::{ToolLoopConfig, ToolLoopEngine};
-use tools::{AgentWakeSender, PendingQuestion, SleepState};
+use tools::{AgentWakeSender, PendingQuestion, PipelineToolContext, SleepState};
 
 /// The MetaAgent orchestrates worker agents through a conversational interface.
 ///
@@ -52,11 +77,13 @@ pub struct MetaAgent {
     conversation: ConversationManager,
     tool_registry: ToolRegistry,
     ai_client: AIClient,
-    result_queue: ResultQueue,
+    result_queue: SharedResultQueue,
     tool_loop: ToolLoopEngine,
     tool_loop_config: ToolLoopConfig,
     // Base system prompt with max_iterations filled in
     base_system_prompt: String,
+    // User's replacement for the built-in prompt template, if any
+    base_prompt_override: Option<String>,
     // Commander personality settings
     personality: Option<CommanderPersonality>,
     cached_prompt: Option<String>,
@@ -66,11 +93,19 @@ pub struct MetaAgent {
     pending_question: Arc<Mutex<Option<PendingQuestion>>>,
     // Wake sender storage for agents to wake meta-agent from sleep
     agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
+    // Current tool loop state (readable without locking the MetaAgent)
+    loop_activity: SharedLoopActivity,
+    // Pending working directory approvals for CreateWorkerAgent and the workspace allowlist
+    directory_approvals: SharedDirectoryApprovals,
     // Conversation persistence
     conversation_db: Option<Arc<AgentRunsDB>>,
     current_conversation_id: Option<String>,
+    // User messages in the current conversation (drives title regeneration)
+    user_turns: usize,
     // Async memory worker for non-blocking updates
     memory_worker: Arc<MemoryWorker>,
+    // Auto-pipeline manager for the pipeline tools (None if unavailable)
+    auto_pipeline_manager: Option<Arc<Mutex<AutoPipelineManager>>>,
 }
 
 impl MetaAgent {
@@ -92,8 +127,12 @@ impl MetaAgent {
             };
 
         let tool_loop_config = ToolLoopConfig::default();
-        // Build base system prompt with max_iterations from config
-        let base_system_prompt = build_system_prompt(tool_loop_config.max_iterations);
+        // Build base system prompt (or the user's override) with max_iterations from config
+        let base_prompt_override = load_base_prompt_override();
+        let base_system_prompt = build_system_prompt(
+            base_prompt_override.as_deref(),
+            tool_loop_config.max_iterations,
+        );
 
         // Initialize context config based on AI provider
         let context_config = ContextConfig::for_provider(ai_client.get_provider_name());
@@ -110,19 +149,24 @@ impl MetaAgent {
             conversation,
             tool_registry: ToolRegistry::new(),
             ai_client,
-            result_queue: ResultQueue::new(),
+            result_queue: Arc::new(std::sync::Mutex::new(ResultQueue::new())),
             tool_loop: ToolLoopEngine::new(),
             tool_loop_config,
             base_system_prompt,
+            base_prompt_override,
             personality,
             cached_prompt,
             cached_prompt_hash,
             sleep_state: Arc::new(Mutex::new(SleepState::default())),
             pending_question: Arc::new(Mutex::new(None)),
             agent_wake_tx: Arc::new(Mutex::new(None)),
+            loop_activity: Arc::new(std::sync::Mutex::new(LoopActivity::default())),
+            directory_approvals: Arc::new(DirectoryApprovals::default()),
             conversation_db: None,
             current_conversation_id: None,
+            user_turns: 0,
             memory_worker,
+            auto_pipeline_manager: None,
         }
     }
 
@@ -142,22 +186,22 @@ impl MetaAgent {
 
     /// Add a result to the queue when an agent completes
     pub fn queue_agent_result(&mut self, result: QueuedAgentResult) {
-        self.result_queue.push(result);
+        self.result_queue.lock().unwrap().push(result);
     }
 
     /// Get the next pending result from the queue
     pub fn pop_next_result(&mut self) -> Option<QueuedAgentResult> {
-        self.result_queue.pop()
+        self.result_queue.lock().unwrap().pop()
     }
 
     /// Get the current queue status
     pub fn get_queue_status(&self) -> QueueStatus {
-        self.result_queue.status()
+        self.result_queue.lock().unwrap().status()
     }
 
     /// Check if there are pending results in the queue
     pub fn has_pending_results(&self) -> bool {
-        !self.result_queue.is_empty()
+        !self.result_queue.lock().unwrap().is_empty()
     }
 
     /// Process the next result in the queue
@@ -166,14 +210,49 @@ impl MetaAgent {
         agent_manager: Arc<Mutex<AgentManager>>,
         app_handle: AppHandle,
     ) -> AppResult<Option<ChatResponse>> {
-        if let Some(mut result) = self.result_queue.pop() {
+        let next = self.result_queue.lock().unwrap().pop();
+        if let Some(mut result) = next {
             result.status = AgentResultStatus::Processing;
 
-            // Format the result as a message to process
-            let message = format!(
-                "Agent in {} has completed. Here are the results:\n\n{}",
-                result.working_dir, result.output
-            );
+            // Prefer the agent's own outputs so this matches process_agent_results;
+            // fall back to the queued text (e.g. pipeline summaries) when there are none
+            let (outputs, crashed) = {
+                let manager = agent_manager.lock().await;
+                let outputs = manager
+                    .get_agent_outputs(&result.agent_id, 0, None)
+                    .await
+                    .unwrap_or_default();
+                let crashed = manager
+                    .get_agent_info(&result.agent_id)
+                    .await
+                    .is_some_and(|info| info.status == AgentStatus::Error);
+                (outputs, crashed)
+            };
+            let message = if crashed {
+                format!(
+                    "Agent {} in {} crashed and will not answer.\n\n{}",
+                    result.agent_id,
+                    result.working_dir,
+                    agent_results::cap_result_text(&result.output, DEFAULT_RESULT_MAX_CHARS)
+                )
+            } else if outputs.is_empty() {
+                format!(
+                    "Agent in {} has completed. Here are the results:\n\n{}",
+                    result.working_dir,
+                    agent_results::cap_result_text(&result.output, DEFAULT_RESULT_MAX_CHARS)
+                )
+            } else {
+                format!(
+                    "Agent in {} has completed.\n\n{}",
+                    result.working_dir,
+                    format_agent_results(
+                        &outputs,
+                        &result.working_dir,
+                        false,
+                        DEFAULT_RESULT_MAX_CHARS
+                    )
+                )
+            };
 
             // Process it through the normal message flow
             let response = self
@@ -181,7 +260,7 @@ impl MetaAgent {
                 .await?;
 
             // Emit queue updated event
-            self.result_queue.emit_updated(&app_handle);
+            self.result_queue.lock().unwrap().emit_updated(&app_handle);
 
             Ok(Some(response))
         } else {
@@ -204,7 +283,7 @@ impl MetaAgent {
     }
 
     /// Emit context usage information to the frontend
-    fn emit_context_info(&self, app_handle: &AppHandle) {
+    pub(crate) fn emit_context_info(&self, app_handle: &AppHandle) {
         let info = self.conversation.get_context_info();
         let event = ContextInfoEvent {
             usage_percent: info.usage_percent,
@@ -213,6 +292,9 @@ impl MetaAgent {
             remaining_tokens: info.remaining_tokens,
             state: info.state.description().to_string(),
             warning_message: info.warning_message(),
+            cache_read_tokens: info.cache_read_tokens,
+            cache_creation_tokens: info.cache_creation_tokens,
+            cache_hit_percent: info.cache_hit_percent,
         };
         let _ = app_handle.emit("meta-agent:context-info", event);
     }
@@ -249,9 +331,10 @@ impl MetaAgent {
 
         // Persist user message
         self.persist_message("user", &user_message, None).await;
+        self.user_turns += 1;
 
         // Check for context compaction at idle moment (after user input processed)
-        if self.conversation.compact_if_needed().await {
+        if self.compact_history(&app_handle).await {
             eprintln!("[MetaAgent] Context compacted before processing");
         }
 
@@ -270,13 +353,16 @@ impl MetaAgent {
                 &self.ai_client,
                 &system_prompt,
                 &mut history,
-                self.tool_registry.get_all_tools().to_vec(),
+                self.tool_registry.get_available_tools(),
                 agent_manager,
                 &app_handle,
                 self.sleep_state.clone(),
                 self.pending_question.clone(),
                 self.agent_wake_tx.clone(),
                 self.memory_worker.clone(),
+                self.pipeline_tool_context(),
+                self.directory_approvals.clone(),
+                self.loop_activity.clone(),
                 || self.get_queue_status(),
                 || None, // Context info will be added after we can get it
             )
@@ -296,27 +382,32 @@ impl MetaAgent {
 
         // Record token usage from the loop
         if let Ok(ref result) = loop_result {
-            self.conversation.record_usage(
-                result.total_usage.input_tokens,
-                result.total_usage.output_tokens,
-            );
+            self.conversation.record_usage(&result.total_usage);
             // Emit context info to frontend
             self.emit_context_info(&app_handle);
         }
 
-        // Persist any new assistant messages
+        // Persist the messages added by the tool loop, with their tool blocks
         let history_after = self.conversation.get_history();
-        for msg in history_after.iter().skip(history_before) {
-            self.persist_message(&msg.role, &msg.content, None).await;
+        for (i, msg) in history_after.iter().enumerate().skip(history_before) {
+            let Some(message_index) = self.conversation.message_index(i) else {
+                continue;
+            };
+            let tool_calls = self.conversation.tool_blocks_json(i);
+            self.persist_message_at(message_index, &msg.role, &msg.content, None, tool_calls)
+                .await;
         }
 
         // Check for context compaction at idle moment (after tool loop completes)
-        if self.conversation.compact_if_needed().await {
+        if self.compact_history(&app_handle).await {
             eprintln!("[MetaAgent] Context compacted after tool loop");
             // Emit updated context info after compaction
             self.emit_context_info(&app_handle);
         }
 
+        // Retitle the conversation once it has enough context
+        self.maybe_regenerate_title();
+
         // Emit thinking stopped
         let _ = self.emit_thinking(&app_handle, false);
 
@@ -378,9 +469,10 @@ impl MetaAgent {
         };
         self.persist_message("user", &content_with_image, None)
             .await;
+        self.user_turns += 1;
 
         // Check for context compaction at idle moment (after user input processed)
-        if self.conversation.compact_if_needed().await {
+        if self.compact_history(&app_handle).await {
             eprintln!("[MetaAgent] Context compacted before processing");
         }
 
@@ -395,13 +487,16 @@ impl MetaAgent {
                 &system_prompt,
                 rich_messages,
                 &mut history,
-                self.tool_registry.get_all_tools().to_vec(),
+                self.tool_registry.get_available_tools(),
                 agent_manager,
                 &app_handle,
                 self.sleep_state.clone(),
                 self.pending_question.clone(),
                 self.agent_wake_tx.clone(),
                 self.memory_worker.clone(),
+                self.pipeline_tool_context(),
+                self.directory_approvals.clone(),
+                self.loop_activity.clone(),
                 || self.get_queue_status(),
                 || None, // Context info will be added after we can get it
             )
@@ -421,27 +516,32 @@ impl MetaAgent {
 
         // Record token usage from the loop
         if let Ok(ref result) = loop_result {
-            self.conversation.record_usage(
-                result.total_usage.input_tokens,
-                result.total_usage.output_tokens,
-            );
+            self.conversation.record_usage(&result.total_usage);
             // Emit context info to frontend
             self.emit_context_info(&app_handle);
         }
 
-        // Persist any new assistant messages
+        // Persist the messages added by the tool loop, with their tool blocks
         let history_after = self.conversation.get_history();
-        for msg in history_after.iter().skip(history_before) {
-            self.persist_message(&msg.role, &msg.content, None).await;
+        for (i, msg) in history_after.iter().enumerate().skip(history_before) {
+            let Some(message_index) = self.conversation.message_index(i) else {
+                continue;
+            };
+            let tool_calls = self.conversation.tool_blocks_json(i);
+            self.persist_message_at(message_index, &msg.role, &msg.content, None, tool_calls)
+                .await;
         }
 
         // Check for context compaction at idle moment (after tool loop completes)
-        if self.conversation.compact_if_needed().await {
+        if self.compact_history(&app_handle).await {
             eprintln!("[MetaAgent] Context compacted after tool loop");
             // Emit updated context info after compaction
             self.emit_context_info(&app_handle);
         }
 
+        // Retitle the conversation once it has enough context
+        self.maybe_regenerate_title();
+
         // Emit thinking stopped
         let _ = self.emit_thinking(&app_handle, false);
 
@@ -453,59 +553,53 @@ impl MetaAgent {
         // The tool loop may have added assistant and tool result messages
         // We need to add any new messages to our conversation manager
         let current_len = self.conversation.get_history().len();
-        for msg in history.into_iter().skip(current_len) {
-            // Extract text content from RichMessage
-            let content = Self::extract_text_from_rich_message(&msg);
-            if msg.role == "assistant" {
-                self.conversation.add_assistant_message(content);
-            } else if msg.role == "user" {
-                self.conversation.add_user_message(content);
+        for msg in history.iter().skip(current_len) {
+            if msg.role == "assistant" || msg.role == "user" {
+                self.conversation.add_rich_message(msg);
             }
         }
     }
 
-    /// Extract text content from a RichMessage (for storage in simple Message format)
-    fn extract_text_from_rich_message(msg: &RichMessage) -> String {
-        match &msg.content {
-            RichMessageContent::Text(s) => s.clone(),
-            RichMessageContent::Blocks(blocks) => {
-                // Extract text from blocks, serializing tool_use/tool_result as JSON
-                let parts: Vec<String> = blocks
-                    .iter()
-                    .map(|block| match block {
-                        RichContentBlock::Text { text } => text.clone(),
-                        RichContentBlock::ToolUse { id, name, input } => {
-                            // Serialize tool_use for reference in history
-                            serde_json::json!({
-                                "type": "tool_use",
-                                "id": id,
-                                "name": name,
-                                "input": input
-                            })
-                            .to_string()
-                        }
-                        RichContentBlock::ToolResult {
-                            tool_use_id,
-                            content,
-                            is_error,
-                        } => {
-                            // Serialize tool_result for reference in history
-                            let mut result = serde_json::json!({
-                                "type": "tool_result",
-                                "tool_use_id": tool_use_id,
-                                "content": content
-                            });
-                            if let Some(err) = is_error {
-                                result["is_error"] = serde_json::json!(err);
-                            }
-                            result.to_string()
-                        }
-                        RichContentBlock::Image { .. } => "[Image]".to_string(),
-                    })
-                    .collect();
-                parts.join("\n")
+    /// Compact the history if needed, storing the compaction and telling the
+    /// frontend what was replaced. Returns true if compaction was performed.
+    async fn compact_history(&mut self, app_handle: &AppHandle) -> bool {
+        if !self.conversation.needs_compaction() {
+            return false;
+        }
+
+        // Pins may have changed since the last compaction
+        if let (Some(db), Some(conv_id)) = (&self.conversation_db, &self.current_conversation_id) {
+            match db.get_meta_message_pins(conv_id).await {
+                Ok(pins) => self.conversation.apply_pins(&pins),
+                Err(e) => eprintln!("[MetaAgent] Failed to load message pins: {}", e),
+            }
+        }
+
+        let Some(report) = self.conversation.compact_if_needed().await else {
+            return false;
+        };
+
+        let mut record = CompactionRecord {
+            id: None,
+            conversation_id: self.current_conversation_id.clone().unwrap_or_default(),
+            summary: report.summary,
+            first_message_index: report.first_message_index,
+            last_message_index: report.last_message_index,
+            tokens_before: report.tokens_before as u32,
+            tokens_after: report.tokens_after as u32,
+            emergency: report.emergency,
+            preserved_message_indexes: report.preserved_message_indexes,
+            created_at: chrono::Utc::now().timestamp_millis(),
+        };
+        if let (Some(db), Some(_)) = (&self.conversation_db, &self.current_conversation_id) {
+            match db.record_meta_compaction(&record).await {
+                Ok(id) => record.id = Some(id),
+                Err(e) => eprintln!("[MetaAgent] Failed to record compaction: {}", e),
             }
         }
+
+        let _ = app_handle.emit("meta-agent:compaction-performed", &record);
+        true
     }
 
     // =========================================================================
@@ -519,12 +613,19 @@ impl MetaAgent {
     pub fn clear_conversation_history(&mut self) {
         self.conversation.clear();
         self.current_conversation_id = None;
+        self.user_turns = 0;
     }
 
     pub fn get_ai_client(&self) -> &AIClient {
         &self.ai_client
     }
 
+    /// Replace the AI client (the mock provider, for offline development)
+    #[cfg(feature = "mock-provider")]
+    pub fn set_ai_client(&mut self, ai_client: AIClient) {
+        self.ai_client = ai_client;
+    }
+
     pub fn get_chat_messages(&self) -> Vec<ChatMessage> {
         self.conversation.to_chat_messages()
     }
@@ -554,6 +655,35 @@ impl MetaAgent {
         self.agent_wake_tx.clone()
     }
 
+    /// Get the shared tool loop activity (for sharing with AppState)
+    pub fn get_loop_activity(&self) -> SharedLoopActivity {
+        self.loop_activity.clone()
+    }
+
+    /// Get the shared result queue (for the agent manager to report crashes)
+    pub fn get_result_queue(&self) -> SharedResultQueue {
+        self.result_queue.clone()
+    }
+
+    /// Set the directory approval state (for sharing with AppState so the
+    /// approve_directory command can answer without locking the MetaAgent)
+    pub fn set_directory_approvals(&mut self, approvals: SharedDirectoryApprovals) {
+        self.directory_approvals = approvals;
+    }
+
+    /// Set the auto-pipeline manager so the commander can drive pipelines
+    pub fn set_auto_pipeline_manager(&mut self, manager: Option<Arc<Mutex<AutoPipelineManager>>>) {
+        self.auto_pipeline_manager = manager;
+    }
+
+    /// Build the context handed to the pipeline tools
+    fn pipeline_tool_context(&self) -> PipelineToolContext {
+        PipelineToolContext {
+            manager: self.auto_pipeline_manager.clone(),
+            result_queue: self.result_queue.clone(),
+        }
+    }
+
     /// Get the memory worker for async memory updates
     pub fn get_memory_worker(&self) -> Arc<MemoryWorker> {
         self.memory_worker.clone()
@@ -593,6 +723,11 @@ impl MetaAgent {
                 message_count: 0,
                 is_archived: false,
                 preview_text: None,
+                title_locked: false,
+                summary: None,
+                forked_from: None,
+                forked_at_message: None,
+                workspace_id: workspace::active_workspace_id(),
             };
 
             db.create_meta_conversation(&record)
@@ -601,6 +736,7 @@ impl MetaAgent {
         }
 
         self.current_conversation_id = Some(conversation_id.clone());
+        self.user_turns = 0;
         eprintln!("[MetaAgent] Started new conversation: {}", conversation_id);
 
         Ok(conversation_id)
@@ -632,6 +768,7 @@ impl MetaAgent {
         // Load into ConversationManager
         self.conversation.load_from_records(&messages);
         self.current_conversation_id = Some(conversation_id.to_string());
+        self.user_turns = messages.iter().filter(|m| m.role == "user").count();
 
         eprintln!(
             "[MetaAgent] Loaded conversation {} with {} messages",
@@ -642,8 +779,93 @@ impl MetaAgent {
         Ok(self.conversation.to_chat_messages())
     }
 
-    /// Persist a message to the database
+    /// Fork a conversation at a message and load the fork.
+    ///
+    /// Messages up to and including `at_message_index` (0-based position in the
+    /// conversation) are copied into a new conversation linked to the original,
+    /// which is left untouched. Returns the new conversation ID.
+    pub async fn fork_conversation(
+        &mut self,
+        conversation_id: &str,
+        at_message_index: u32,
+    ) -> Result<String, String> {
+        let db = self
+            .conversation_db
+            .clone()
+            .ok_or_else(|| "No database configured".to_string())?;
+
+        let original = db
+            .get_meta_conversation(conversation_id)
+            .await
+            .map_err(|e| format!("Failed to get conversation: {}", e))?
+            .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+
+        let mut messages = db
+            .get_meta_messages(conversation_id)
+            .await
+            .map_err(|e| format!("Failed to get messages: {}", e))?;
+        let keep = at_message_index as usize + 1;
+        if keep > messages.len() {
+            return Err(format!(
+                "Message index {} is out of range (conversation has {} messages)",
+                at_message_index,
+                messages.len()
+            ));
+        }
+        messages.truncate(keep);
+
+        let fork_id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+        let title = original.title.as_deref().unwrap_or("Untitled conversation");
+        let fork = MetaConversationRecord {
+            id: None,
+            conversation_id: fork_id.clone(),
+            title: Some(format!("{} (fork)", title)),
+            created_at: now,
+            updated_at: now,
+            message_count: messages.len() as u32,
+            is_archived: false,
+            preview_text: messages
+                .last()
+                .map(|m| truncate_utf8(&m.content, 100).to_string()),
+            title_locked: false,
+            summary: None,
+            forked_from: Some(conversation_id.to_string()),
+            forked_at_message: Some(at_message_index),
+            workspace_id: original.workspace_id.clone(),
+        };
+
+        db.fork_meta_conversation(&fork, &messages)
+            .await
+            .map_err(|e| format!("Failed to fork conversation: {}", e))?;
+
+        eprintln!(
+            "[MetaAgent] Forked conversation {} at message {} into {}",
+            conversation_id, at_message_index, fork_id
+        );
+
+        // Loading rebuilds the history and recounts context tokens from scratch
+        self.load_conversation(&fork_id).await?;
+        Ok(fork_id)
+    }
+
+    /// Persist the latest message to the database
     async fn persist_message(&self, role: &str, content: &str, image_data: Option<String>) {
+        let last = self.conversation.get_history().len().saturating_sub(1);
+        let message_index = self.conversation.message_index(last).unwrap_or_default();
+        self.persist_message_at(message_index, role, content, image_data, None)
+            .await;
+    }
+
+    /// Persist a message to the database at the given index
+    async fn persist_message_at(
+        &self,
+        message_index: u32,
+        role: &str,
+        content: &str,
+        image_data: Option<String>,
+        tool_calls: Option<String>,
+    ) {
         let Some(db) = &self.conversation_db else {
             return;
         };
@@ -651,7 +873,6 @@ impl MetaAgent {
             return;
         };
 
-        let message_index = self.conversation.get_history().len() as u32;
         let now = chrono::Utc::now().timestamp_millis();
 
         let record = MetaMessageRecord {
@@ -661,8 +882,19 @@ impl MetaAgent {
             role: role.to_string(),
             content: content.to_string(),
             image_data,
-            tool_calls: None,
+            tool_calls,
             timestamp: now,
+            // Recorded on responses so ratings can be compared across setups
+            model: (role == "assistant").then(|| self.ai_client.get_model_name().to_string()),
+            personality_hash: (role == "assistant").then(|| {
+                self.personality
+                    .as_ref()
+                    .map(|p| format!("{:016x}", p.settings_hash()))
+                    .unwrap_or_else(|| "default".to_string())
+            }),
+            rating: None,
+            rating_note: None,
+            rated_at: None,
         };
 
         if let Err(e) = db.insert_meta_message(&record).await {
@@ -688,6 +920,23 @@ impl MetaAgent {
         }
     }
 
+    /// Queue a generated title once the conversation reaches the regeneration exchange
+    fn maybe_regenerate_title(&self) {
+        if self.user_turns != conversation_titles::TITLE_REGENERATION_EXCHANGE {
+            return;
+        }
+        let (Some(db), Some(conv_id)) = (&self.conversation_db, &self.current_conversation_id)
+        else {
+            return;
+        };
+
+        self.memory_worker.queue_title_generation(
+            db.clone(),
+            conv_id.clone(),
+            self.conversation.clone_history(),
+        );
+    }
+
     /// Ensure we have a conversation (create one if needed)
     async fn ensure_conversation(&mut self) -> Result<(), String> {
         if self.current_conversation_id.is_none() {
@@ -696,6 +945,40 @@ impl MetaAgent {
         Ok(())
     }
 
+    // =========================================================================
+    // Workspaces
+    // =========================================================================
+
+    /// Switch to another workspace without restarting.
+    ///
+    /// Drops the current conversation (new conversations, memory reads and
+    /// memory updates follow the active workspace from here on) and reloads
+    /// the workspace's personality and directory allowlist.
+    pub async fn switch_workspace(&mut self, workspace_id: &str) {
+        workspace::set_active_workspace(workspace_id);
+        self.clear_conversation_history();
+
+        if let Some(cache) = prompt_generator::load_cached_prompt() {
+            self.cached_prompt = Some(cache.prompt);
+            self.cached_prompt_hash = Some(cache.settings_hash);
+            self.personality = Some(cache.personality);
+        } else {
+            self.cached_prompt = None;
+            self.cached_prompt_hash = None;
+            self.personality = None;
+        }
+        self.base_prompt_override = load_base_prompt_override();
+        self.base_system_prompt = build_system_prompt(
+            self.base_prompt_override.as_deref(),
+            self.tool_loop_config.max_iterations,
+        );
+        let system_prompt = self.get_system_prompt().to_string();
+        self.conversation.set_system_prompt(&system_prompt);
+
+        self.directory_approvals.load_workspace(workspace_id).await;
+        eprintln!("[MetaAgent] Switched to workspace '{}'", workspace_id);
+    }
+
     // =========================================================================
     // Commander Personality
     // =========================================================================
@@ -758,15 +1041,22 @@ impl MetaAgent {
     ///
     /// This builds the full system prompt including any persistent memory content.
     fn get_system_prompt_with_memory(&self) -> String {
-        let base_prompt = self.get_system_prompt();
+        format!(
+            "{}{}{}",
+            self.get_system_prompt(),
+            Self::memory_section(),
+            cost_preference::prompt_note()
+        )
+    }
 
-        // Try to get memory content
-        let memory_section = if let Some(manager) = memory_manager::MemoryManager::new() {
+    /// The persistent memory section appended to the system prompt (empty without memory)
+    fn memory_section() -> String {
+        if let Some(manager) = memory_manager::MemoryManager::new() {
             if let Some(memory_content) = manager.get_memory_for_context() {
                 format!(
                     r#"
 
-## Your Persistent Memory
+{}
 You have access to persistent memory that survives across sessions.
 
 **Tools:**
@@ -783,16 +1073,14 @@ Current memory:
 ---
 {}
 ---"#,
-                    memory_content
+                    MEMORY_SECTION_HEADING, memory_content
                 )
             } else {
                 String::new()
             }
         } else {
             String::new()
-        };
-
-        format!("{}{}", base_prompt, memory_section)
+        }
     }
 
     /// Get the current personality settings
@@ -822,6 +1110,82 @@ Current memory:
         }
     }
 
+    /// Whether the built-in prompt is replaced by the user's override
+    pub fn has_base_prompt_override(&self) -> bool {
+        self.base_prompt_override.is_some()
+    }
+
+    /// The system prompt as it will be sent next turn, with memory appended,
+    /// broken down by section with token estimates
+    pub fn preview_effective_system_prompt(&self) -> SystemPromptPreview {
+        let source = if self.cached_prompt.is_some() {
+            "personalized"
+        } else if self.base_prompt_override.is_some() {
+            "override"
+        } else {
+            "base"
+        };
+        let warnings = match &self.base_prompt_override {
+            Some(template) => prompt_preview::override_warnings(template),
+            None => Vec::new(),
+        };
+        prompt_preview::build_preview(
+            source,
+            self.get_system_prompt(),
+            &[
+                ("memory", Self::memory_section().as_str()),
+                ("cost_preference", cost_preference::prompt_note()),
+            ],
+            self.ai_client.get_provider_name(),
+            warnings,
+        )
+    }
+
+    /// Replace the built-in prompt template with the user's own. `{max_iterations}`
+    /// in the text is filled in like in the built-in template. The override is
+    /// saved for the active workspace.
+    pub async fn set_base_prompt_override(&mut self, text: String) -> Result<(), String> {
+        save_base_prompt_override(&text)?;
+        self.base_prompt_override = Some(text);
+        self.rebuild_base_prompt().await;
+        Ok(())
+    }
+
+    /// Go back to the built-in prompt template
+    pub async fn reset_base_prompt_override(&mut self) -> Result<(), String> {
+        clear_base_prompt_override()?;
+        self.base_prompt_override = None;
+        self.rebuild_base_prompt().await;
+        Ok(())
+    }
+
+    /// Rebuild the base prompt after the template changed. A personalized
+    /// prompt was generated from the old base, so it is generated again.
+    async fn rebuild_base_prompt(&mut self) {
+        self.base_system_prompt = build_system_prompt(
+            self.base_prompt_override.as_deref(),
+            self.tool_loop_config.max_iterations,
+        );
+
+        if self.cached_prompt.take().is_some() {
+            self.cached_prompt_hash = None;
+            if let Some(personality) = self.personality.clone() {
+                if let Err(e) = self.set_personality(personality).await {
+                    eprintln!(
+                        "[MetaAgent] Warning: Failed to personalize the new base prompt, using it unpersonalized: {}",
+                        e
+                    );
+                    if let Err(e) = prompt_generator::clear_cached_prompt() {
+                        eprintln!("[MetaAgent] Warning: {}", e);
+                    }
+                }
+            }
+        }
+
+        let system_prompt = self.get_system_prompt().to_string();
+        self.conversation.set_system_prompt(&system_prompt);
+    }
+
     /// Get the max iterations from the tool loop config
     pub fn get_max_iterations(&self) -> usize {
         self.tool_loop_config.max_iterations
@@ -5,7 +5,7 @@
 //! into relevant sections.
 
 use crate::ai_client::{AIClient, ContentBlock, Message};
-use crate::commands::config_loader::get_config_dir;
+use crate::workspace;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -111,9 +111,9 @@ impl CommanderPersonality {
 
 const PROMPT_CACHE_FILENAME: &str = "commander_prompt_cache.json";
 
-/// Get the path to the prompt cache file
+/// Get the path to the active workspace's prompt cache file
 fn get_cache_path() -> Option<PathBuf> {
-    get_config_dir().ok().map(|d| d.join(PROMPT_CACHE_FILENAME))
+    workspace::config_dir(&workspace::active_workspace_id()).map(|d| d.join(PROMPT_CACHE_FILENAME))
 }
 
 /// Load the cached prompt from disk if it exists
@@ -0,0 +1,147 @@
+// System prompt preview
+//
+// The system prompt the commander actually sends - the base, personalized or
+// overridden prompt followed by the persistent memory section - broken down by
+// section with token estimates, so its cost can be checked without a turn.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+use super::context_tracker::ContextTracker;
+use super::system_prompt::MAX_ITERATIONS_PLACEHOLDER;
+
+/// o200k_base encoding used by the GPT-4o family
+static OPENAI_TOKENIZER: OnceLock<CoreBPE> = OnceLock::new();
+
+fn openai_tokenizer() -> &'static CoreBPE {
+    OPENAI_TOKENIZER.get_or_init(|| {
+        tiktoken_rs::o200k_base().expect("Failed to initialize o200k_base tokenizer")
+    })
+}
+
+/// Approximate token counts per provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TokenEstimate {
+    /// cl100k_base count (Anthropic doesn't publish Claude's tokenizer)
+    pub claude: usize,
+    /// o200k_base count
+    pub openai: usize,
+}
+
+/// One part of the assembled prompt
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptSection {
+    /// "base", "personalized", "override", "memory" or "cost_preference"
+    pub name: String,
+    pub chars: usize,
+    pub tokens: TokenEstimate,
+}
+
+/// The fully assembled system prompt with its breakdown
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemPromptPreview {
+    pub prompt: String,
+    /// Where the main prompt comes from: "base", "personalized" or "override"
+    pub source: String,
+    /// Provider the commander currently talks to
+    pub provider: String,
+    pub sections: Vec<PromptSection>,
+    pub tokens: TokenEstimate,
+    pub warnings: Vec<String>,
+}
+
+pub fn estimate_tokens(text: &str) -> TokenEstimate {
+    TokenEstimate {
+        claude: ContextTracker::count_tokens(text),
+        openai: openai_tokenizer().encode_with_special_tokens(text).len(),
+    }
+}
+
+/// Assemble the preview from the main prompt and the named sections appended
+/// to it, such as memory (empty sections are left out)
+pub fn build_preview(
+    source: &str,
+    prompt: &str,
+    appended: &[(&str, &str)],
+    provider: &str,
+    warnings: Vec<String>,
+) -> SystemPromptPreview {
+    let mut sections = vec![PromptSection {
+        name: source.to_string(),
+        chars: prompt.chars().count(),
+        tokens: estimate_tokens(prompt),
+    }];
+    let mut full = prompt.to_string();
+    for (name, text) in appended.iter().filter(|(_, text)| !text.is_empty()) {
+        sections.push(PromptSection {
+            name: name.to_string(),
+            chars: text.chars().count(),
+            tokens: estimate_tokens(text),
+        });
+        full.push_str(text);
+    }
+
+    SystemPromptPreview {
+        tokens: estimate_tokens(&full),
+        prompt: full,
+        source: source.to_string(),
+        provider: provider.to_string(),
+        sections,
+        warnings,
+    }
+}
+
+/// Warnings shown while a base prompt override is in use
+pub fn override_warnings(template: &str) -> Vec<String> {
+    let mut warnings = vec![
+        "The built-in commander prompt is replaced by your override. Its tool guidance and \
+         working rules no longer apply unless your override includes them."
+            .to_string(),
+    ];
+    if !template.contains(MAX_ITERATIONS_PLACEHOLDER) {
+        warnings.push(format!(
+            "The override doesn't contain {}, so the commander isn't told its iteration limit.",
+            MAX_ITERATIONS_PLACEHOLDER
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_sections_add_up_to_prompt() {
+        let preview = build_preview(
+            "override",
+            "You are a helpful commander.",
+            &[
+                (
+                    "memory",
+                    "\n\n## Your Persistent Memory\nUser prefers Rust.",
+                ),
+                ("cost_preference", ""),
+            ],
+            "Claude",
+            Vec::new(),
+        );
+        assert_eq!(preview.sections.len(), 2);
+        assert_eq!(preview.sections[0].name, "override");
+        assert_eq!(preview.sections[1].name, "memory");
+        assert!(preview.prompt.ends_with("User prefers Rust."));
+        let chars: usize = preview.sections.iter().map(|s| s.chars).sum();
+        assert_eq!(chars, preview.prompt.chars().count());
+        assert!(preview.tokens.claude > 0 && preview.tokens.openai > 0);
+
+        let no_memory = build_preview("base", "Prompt", &[("memory", "")], "OpenAI", Vec::new());
+        assert_eq!(no_memory.sections.len(), 1);
+    }
+
+    #[test]
+    fn test_override_without_placeholder_warns() {
+        assert_eq!(override_warnings("Use {max_iterations} steps").len(), 1);
+        assert_eq!(override_warnings("Be brief").len(), 2);
+    }
+}
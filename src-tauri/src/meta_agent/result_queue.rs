@@ -1,10 +1,15 @@
 // Result queue management for MetaAgent
 
 use std::collections::VecDeque;
-use tauri::{AppHandle, Emitter};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Runtime};
 
 use crate::types::{QueueStatus, QueuedAgentResult, ResultQueueUpdatedEvent};
 
+/// Result queue shared with background tasks (e.g. pipeline watchers) that
+/// must enqueue results without locking the MetaAgent
+pub type SharedResultQueue = Arc<std::sync::Mutex<ResultQueue>>;
+
 /// Manages the queue of agent results waiting to be processed
 pub struct ResultQueue {
     queue: VecDeque<QueuedAgentResult>,
@@ -41,7 +46,7 @@ impl ResultQueue {
     }
 
     /// Emit event when queue is updated
-    pub fn emit_updated(&self, app_handle: &AppHandle) {
+    pub fn emit_updated<R: Runtime>(&self, app_handle: &AppHandle<R>) {
         let _ = app_handle.emit(
             "result-queue:updated",
             ResultQueueUpdatedEvent {
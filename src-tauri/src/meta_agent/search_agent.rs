@@ -2,6 +2,8 @@
 //
 // Uses a light model (haiku) to interpret natural language queries
 // and search across both the run history database and memory files.
+// Optional filters (see search_filters.rs) restrict every query the agent
+// runs, and its hits are returned grouped by kind.
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -9,15 +11,64 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::agent_runs_db::{AgentRunsDB, RunQueryFilters, RunStatus};
+use super::search_filters::{SearchFilters, SearchHits, SearchKind};
+use crate::agent_manager::output_search;
+use crate::agent_runs_db::{AgentRunsDB, ConversationQueryFilters, RunQueryFilters, RunStatus};
 use crate::ai_client::{AIClient, ContentBlock, Message, Tool};
-use crate::types::AgentSource;
+use crate::commands::env_registry;
+use crate::types::{AgentNote, AgentSource};
 use crate::utils::string::truncate_with_ellipsis;
+use crate::workspace::{self, active_workspace_id};
 use chrono::{Duration, Utc};
 
 /// Maximum iterations for the search agent tool loop
 const MAX_SEARCH_AGENT_ITERATIONS: usize = 5;
 
+/// Length of the excerpt shown from a matching conversation message
+const MESSAGE_EXCERPT_LEN: usize = 240;
+
+/// Whether SEARCH_CONVERSATION_MESSAGES allows searching message text (default: on)
+fn message_search_enabled() -> bool {
+    env_registry::SEARCH_CONVERSATION_MESSAGES
+        .var()
+        .map(|v| {
+            !matches!(
+                v.trim().to_lowercase().as_str(),
+                "0" | "false" | "no" | "off"
+            )
+        })
+        .unwrap_or(true)
+}
+
+/// Up to `len` bytes of `text` around the first case-insensitive match of
+/// `keyword`, with ellipses where it was cut
+fn excerpt_around(text: &str, keyword: &str, len: usize) -> String {
+    if text.len() <= len {
+        return text.to_string();
+    }
+    // Lowercasing can change byte lengths outside ASCII; only trust the match then
+    let lower = text.to_lowercase();
+    let found = if lower.len() == text.len() {
+        lower.find(&keyword.to_lowercase()).unwrap_or(0)
+    } else {
+        0
+    };
+    let mut start = found.saturating_sub(len / 3);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + len).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}{}{}",
+        if start > 0 { "..." } else { "" },
+        &text[start..end],
+        if end < text.len() { "..." } else { "" }
+    )
+}
+
 /// Result of a search operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -25,6 +76,22 @@ pub struct SearchResult {
     pub summary: String,
     pub runs_found: usize,
     pub memories_searched: usize,
+    /// Hits grouped by kind, each with a total count and one-line snippets
+    pub results: Value,
+}
+
+/// Format an agent note for search tool results
+fn format_note(note: &AgentNote) -> Value {
+    let created_at = chrono::DateTime::from_timestamp_millis(note.timestamp)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    json!({
+        "agent_id": note.agent_id,
+        "author": note.author.as_str(),
+        "text": note.text,
+        "created_at": created_at
+    })
 }
 
 /// Search Agent handles natural language queries across runs and memories
@@ -36,8 +103,7 @@ pub struct SearchAgent {
 impl SearchAgent {
     /// Create a new search agent
     pub fn new(runs_db: Arc<AgentRunsDB>) -> Option<Self> {
-        let data_dir = dirs::data_local_dir()?;
-        let memory_dir = data_dir.join("claude-commander").join("meta-memory");
+        let memory_dir = workspace::data_dir(&active_workspace_id())?.join("meta-memory");
 
         Some(Self {
             runs_db,
@@ -50,9 +116,9 @@ impl SearchAgent {
         &self.memory_dir
     }
 
-    /// Get the tools available to the search agent
-    fn get_search_tools() -> Vec<Tool> {
-        vec![
+    /// Get the tools available to the search agent for the kinds being searched
+    fn get_search_tools(filters: &SearchFilters) -> Vec<Tool> {
+        let all_tools = vec![
             Tool {
                 name: "query_runs".to_string(),
                 description: "Search run history with optional filters. Returns a list of agent runs matching the criteria.".to_string(),
@@ -107,6 +173,24 @@ impl SearchAgent {
                     "required": ["agent_id"]
                 }),
             },
+            Tool {
+                name: "search_agent_notes".to_string(),
+                description: "Search notes pinned to agent runs by the user or commander (e.g. 'exploring approach B'). Returns matching notes with their agent IDs.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "keyword": {
+                            "type": "string",
+                            "description": "The keyword or phrase to search for (case-insensitive)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum notes to return (default: 20)"
+                        }
+                    },
+                    "required": ["keyword"]
+                }),
+            },
             Tool {
                 name: "list_memory_files".to_string(),
                 description: "List all files in the memory directory with their sizes.".to_string(),
@@ -144,17 +228,156 @@ impl SearchAgent {
                     "required": ["keyword"]
                 }),
             },
-        ]
+            Tool {
+                name: "search_prompts".to_string(),
+                description: "Search every prompt sent to agents, not only the initial one. Returns matching prompts with their agent IDs and working directories.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "keyword": {
+                            "type": "string",
+                            "description": "The keyword or phrase to search for (case-insensitive)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum prompts to return (default: 20)"
+                        }
+                    },
+                    "required": ["keyword"]
+                }),
+            },
+            Tool {
+                name: "search_agent_outputs".to_string(),
+                description: "Search what agents printed (replies, tool calls and results, errors) for a file name, error message or other text. Returns matching snippets with their agent IDs, the match wrapped in **.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "keyword": {
+                            "type": "string",
+                            "description": "Text to find, at least 3 characters, e.g. 'payments.rs' (case-insensitive substring)"
+                        },
+                        "agent_id": {
+                            "type": "string",
+                            "description": "Only this agent's output"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum outputs to return (default: 20)"
+                        }
+                    },
+                    "required": ["keyword"]
+                }),
+            },
+            Tool {
+                name: "search_prompt_templates".to_string(),
+                description: "Search the user's saved prompt templates by name and content. Returns matching templates with their {{variables}} and how often each was used.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "keyword": {
+                            "type": "string",
+                            "description": "The keyword or phrase to search for (case-insensitive)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum templates to return (default: 20)"
+                        }
+                    },
+                    "required": ["keyword"]
+                }),
+            },
+            Tool {
+                name: "search_conversations".to_string(),
+                description: "Search past conversations with the commander by title and preview text.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "keyword": {
+                            "type": "string",
+                            "description": "The keyword or phrase to search for (case-insensitive)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum conversations to return (default: 20)"
+                        }
+                    },
+                    "required": ["keyword"]
+                }),
+            },
+            Tool {
+                name: "search_conversation_messages".to_string(),
+                description: "Search what was said in past conversations with the commander (the messages themselves, not just titles). Returns matching excerpts with their conversation.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "keyword": {
+                            "type": "string",
+                            "description": "The keyword or phrase to search for (case-insensitive)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum messages to return (default: 20)"
+                        }
+                    },
+                    "required": ["keyword"]
+                }),
+            },
+        ];
+
+        all_tools
+            .into_iter()
+            .filter(|tool| Self::tool_kind(&tool.name).is_some_and(|kind| filters.allows(kind)))
+            .filter(|tool| tool.name != "search_conversation_messages" || message_search_enabled())
+            .collect()
+    }
+
+    /// The kind of record a search tool returns
+    fn tool_kind(tool_name: &str) -> Option<SearchKind> {
+        match tool_name {
+            "query_runs" | "get_run_details" | "search_agent_notes" => Some(SearchKind::Runs),
+            "search_prompts" => Some(SearchKind::Prompts),
+            "search_agent_outputs" => Some(SearchKind::AgentOutputs),
+            "list_memory_files" | "read_memory_file" | "search_memory_content" => {
+                Some(SearchKind::Memory)
+            }
+            "search_conversations" | "search_conversation_messages" => {
+                Some(SearchKind::Conversations)
+            }
+            "search_prompt_templates" => Some(SearchKind::PromptTemplates),
+            _ => None,
+        }
     }
 
     /// Execute a search tool
-    async fn execute_search_tool(&self, tool_name: &str, input: &Value) -> Value {
+    async fn execute_search_tool(
+        &self,
+        tool_name: &str,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
+        // The model only sees tools for the requested kinds, but may still name others
+        if !Self::tool_kind(tool_name).is_some_and(|kind| filters.allows(kind)) {
+            return json!({
+                "error": format!("Tool {} is outside the requested search kinds", tool_name)
+            });
+        }
+
         match tool_name {
-            "query_runs" => self.tool_query_runs(input).await,
+            "query_runs" => self.tool_query_runs(input, filters, hits).await,
             "get_run_details" => self.tool_get_run_details(input).await,
+            "search_agent_notes" => self.tool_search_agent_notes(input, filters, hits).await,
+            "search_prompts" => self.tool_search_prompts(input, filters, hits).await,
+            "search_agent_outputs" => self.tool_search_agent_outputs(input, filters, hits).await,
+            "search_conversations" => self.tool_search_conversations(input, filters, hits).await,
+            "search_conversation_messages" => {
+                self.tool_search_conversation_messages(input, filters, hits)
+                    .await
+            }
+            "search_prompt_templates" => self.tool_search_prompt_templates(input, hits).await,
             "list_memory_files" => self.tool_list_memory_files(),
             "read_memory_file" => self.tool_read_memory_file(input),
-            "search_memory_content" => self.tool_search_memory_content(input),
+            "search_memory_content" => self.tool_search_memory_content(input, hits),
             _ => json!({
                 "error": format!("Unknown tool: {}", tool_name)
             }),
@@ -162,7 +385,12 @@ impl SearchAgent {
     }
 
     /// Query runs tool implementation
-    async fn tool_query_runs(&self, input: &Value) -> Value {
+    async fn tool_query_runs(
+        &self,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
         let days_back = input["days_back"].as_i64().unwrap_or(30);
         let limit = input["limit"].as_u64().unwrap_or(20) as usize;
 
@@ -179,17 +407,21 @@ impl SearchAgent {
 
         let date_from = Some(Utc::now() - Duration::days(days_back));
 
-        let filters = RunQueryFilters {
+        let mut run_filters = RunQueryFilters {
             status,
             working_dir: None, // Do partial matching in post-processing
+            working_dir_prefix: None,
             source,
+            workspace_id: Some(active_workspace_id()),
             date_from,
             date_to: None,
             limit: Some(limit + 100), // Get extra for post-filtering
             offset: None,
+            before: None,
         };
+        filters.apply_to(&mut run_filters);
 
-        let runs = match self.runs_db.query_runs(filters).await {
+        let runs = match self.runs_db.query_runs(run_filters).await {
             Ok(runs) => runs,
             Err(e) => {
                 return json!({
@@ -228,6 +460,11 @@ impl SearchAgent {
             .take(limit)
             .collect();
 
+        for run in &filtered_runs {
+            let text = run.initial_prompt.as_deref().unwrap_or(&run.working_dir);
+            hits.add(SearchKind::Runs, &run.agent_id, text);
+        }
+
         let formatted_runs: Vec<Value> = filtered_runs
             .iter()
             .map(|run| {
@@ -273,6 +510,15 @@ impl SearchAgent {
 
         match self.runs_db.get_run(agent_id).await {
             Ok(Some(run)) => {
+                let notes: Vec<Value> = self
+                    .runs_db
+                    .get_agent_notes(agent_id)
+                    .await
+                    .unwrap_or_default()
+                    .iter()
+                    .map(format_note)
+                    .collect();
+
                 let started_at = chrono::DateTime::from_timestamp_millis(run.started_at)
                     .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
                     .unwrap_or_else(|| "unknown".to_string());
@@ -296,7 +542,8 @@ impl SearchAgent {
                         "total_tool_calls": run.total_tool_calls,
                         "cost_usd": run.total_cost_usd,
                         "can_resume": run.can_resume,
-                        "session_id": run.session_id
+                        "session_id": run.session_id,
+                        "notes": notes
                     }
                 })
             }
@@ -311,6 +558,333 @@ impl SearchAgent {
         }
     }
 
+    /// Search agent notes tool implementation
+    async fn tool_search_agent_notes(
+        &self,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
+        let keyword = match input["keyword"].as_str() {
+            Some(k) => k,
+            None => return json!({ "error": "keyword is required" }),
+        };
+        let limit = input["limit"].as_u64().unwrap_or(20) as usize;
+
+        match self.runs_db.search_agent_notes(keyword, limit).await {
+            Ok(notes) => {
+                let mut in_scope = Vec::new();
+                for note in notes {
+                    if filters.in_date_range(note.timestamp)
+                        && self.run_in_scope(&note.agent_id, filters).await
+                    {
+                        hits.add(SearchKind::Runs, &note.agent_id, &note.text);
+                        in_scope.push(note);
+                    }
+                }
+                json!({
+                    "success": true,
+                    "total_found": in_scope.len(),
+                    "notes": in_scope.iter().map(format_note).collect::<Vec<_>>()
+                })
+            }
+            Err(e) => json!({
+                "success": false,
+                "error": format!("Failed to search notes: {}", e)
+            }),
+        }
+    }
+
+    /// Whether a run matches the source and working directory filters
+    async fn run_in_scope(&self, agent_id: &str, filters: &SearchFilters) -> bool {
+        if filters.source.is_none() && filters.working_dir_prefix.is_none() {
+            return true;
+        }
+        let Ok(Some(run)) = self.runs_db.get_run(agent_id).await else {
+            return false;
+        };
+        let wrong_source = filters
+            .source
+            .as_ref()
+            .is_some_and(|source| run.source != source.as_str());
+        let outside_dir = filters
+            .working_dir_prefix
+            .as_ref()
+            .is_some_and(|prefix| !run.working_dir.starts_with(prefix.as_str()));
+        !wrong_source && !outside_dir
+    }
+
+    /// Search prompts tool implementation
+    async fn tool_search_prompts(
+        &self,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
+        let keyword = match input["keyword"].as_str() {
+            Some(k) => k,
+            None => return json!({ "error": "keyword is required" }),
+        };
+        let limit = input["limit"].as_u64().unwrap_or(20) as usize;
+
+        let mut run_filters = RunQueryFilters {
+            workspace_id: Some(active_workspace_id()),
+            limit: Some(limit),
+            ..Default::default()
+        };
+        filters.apply_to(&mut run_filters);
+
+        match self.runs_db.search_prompts(keyword, run_filters).await {
+            Ok(prompts) => {
+                let formatted: Vec<Value> = prompts
+                    .iter()
+                    .map(|p| {
+                        hits.add(
+                            SearchKind::Prompts,
+                            &format!("{}@{}", p.agent_id, p.timestamp),
+                            &p.prompt,
+                        );
+                        let sent_at = chrono::DateTime::from_timestamp_millis(p.timestamp)
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "unknown".to_string());
+                        json!({
+                            "agent_id": p.agent_id,
+                            "working_dir": p.working_dir,
+                            "sent_at": sent_at,
+                            "prompt": truncate_with_ellipsis(&p.prompt, 197)
+                        })
+                    })
+                    .collect();
+                json!({
+                    "success": true,
+                    "total_found": formatted.len(),
+                    "prompts": formatted
+                })
+            }
+            Err(e) => json!({
+                "success": false,
+                "error": format!("Failed to search prompts: {}", e)
+            }),
+        }
+    }
+
+    /// Search agent outputs tool implementation
+    async fn tool_search_agent_outputs(
+        &self,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
+        let keyword = match input["keyword"].as_str().map(output_search::search_query) {
+            Some(Ok(k)) => k,
+            Some(Err(e)) => return json!({ "error": e }),
+            None => return json!({ "error": "keyword is required" }),
+        };
+        let limit = input["limit"].as_u64().unwrap_or(20) as usize;
+
+        // Get extra for scope post-filtering
+        match output_search::search_persisted_outputs(
+            &self.runs_db,
+            keyword,
+            input["agent_id"].as_str(),
+            limit + 100,
+        )
+        .await
+        {
+            Ok(matches) => {
+                let mut formatted = Vec::new();
+                for m in matches {
+                    if formatted.len() >= limit {
+                        break;
+                    }
+                    if !filters.in_date_range(m.timestamp)
+                        || !self.run_in_scope(&m.agent_id, filters).await
+                    {
+                        continue;
+                    }
+                    hits.add(SearchKind::AgentOutputs, &m.agent_id, &m.snippet);
+                    let printed_at = chrono::DateTime::from_timestamp_millis(m.timestamp)
+                        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    formatted.push(json!({
+                        "agent_id": m.agent_id,
+                        "output_type": m.output_type,
+                        "printed_at": printed_at,
+                        "snippet": m.snippet
+                    }));
+                }
+                json!({
+                    "success": true,
+                    "total_found": formatted.len(),
+                    "outputs": formatted
+                })
+            }
+            Err(e) => json!({
+                "success": false,
+                "error": e
+            }),
+        }
+    }
+
+    /// Search conversations tool implementation
+    async fn tool_search_conversations(
+        &self,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
+        let keyword = match input["keyword"].as_str() {
+            Some(k) => k,
+            None => return json!({ "error": "keyword is required" }),
+        };
+        let limit = input["limit"].as_u64().unwrap_or(20) as usize;
+
+        let query = ConversationQueryFilters {
+            include_archived: true,
+            search_text: Some(keyword.to_string()),
+            workspace_id: Some(active_workspace_id()),
+            // Get extra for date post-filtering
+            limit: Some(limit + 100),
+            offset: None,
+        };
+
+        match self.runs_db.list_meta_conversations(query).await {
+            Ok(conversations) => {
+                let formatted: Vec<Value> = conversations
+                    .iter()
+                    .filter(|c| filters.in_date_range(c.updated_at))
+                    .take(limit)
+                    .map(|c| {
+                        let title = c.title.as_deref().unwrap_or("Untitled");
+                        let preview = c.preview_text.as_deref().unwrap_or_default();
+                        hits.add(
+                            SearchKind::Conversations,
+                            &c.conversation_id,
+                            &format!("{}: {}", title, preview),
+                        );
+                        let updated_at = chrono::DateTime::from_timestamp_millis(c.updated_at)
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "unknown".to_string());
+                        json!({
+                            "conversation_id": c.conversation_id,
+                            "title": title,
+                            "preview": preview,
+                            "updated_at": updated_at,
+                            "message_count": c.message_count
+                        })
+                    })
+                    .collect();
+                json!({
+                    "success": true,
+                    "total_found": formatted.len(),
+                    "conversations": formatted
+                })
+            }
+            Err(e) => json!({
+                "success": false,
+                "error": format!("Failed to search conversations: {}", e)
+            }),
+        }
+    }
+
+    /// Search conversation messages tool implementation
+    async fn tool_search_conversation_messages(
+        &self,
+        input: &Value,
+        filters: &SearchFilters,
+        hits: &mut SearchHits,
+    ) -> Value {
+        if !message_search_enabled() {
+            return json!({ "error": "Conversation message search is disabled (SEARCH_CONVERSATION_MESSAGES)" });
+        }
+        let keyword = match input["keyword"].as_str() {
+            Some(k) => k,
+            None => return json!({ "error": "keyword is required" }),
+        };
+        let limit = input["limit"].as_u64().unwrap_or(20) as usize;
+
+        // Get extra for date post-filtering
+        match self
+            .runs_db
+            .search_meta_messages(keyword, &active_workspace_id(), limit + 100)
+            .await
+        {
+            Ok(matches) => {
+                let formatted: Vec<Value> = matches
+                    .iter()
+                    .filter(|m| filters.in_date_range(m.timestamp))
+                    .take(limit)
+                    .map(|m| {
+                        let title = m.conversation_title.as_deref().unwrap_or("Untitled");
+                        let excerpt = excerpt_around(&m.content, keyword, MESSAGE_EXCERPT_LEN);
+                        hits.add(
+                            SearchKind::Conversations,
+                            &m.conversation_id,
+                            &format!("{}: {}", title, excerpt),
+                        );
+                        let sent_at = chrono::DateTime::from_timestamp_millis(m.timestamp)
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "unknown".to_string());
+                        json!({
+                            "conversation_id": m.conversation_id,
+                            "title": title,
+                            "message_index": m.message_index,
+                            "role": m.role,
+                            "sent_at": sent_at,
+                            "excerpt": excerpt
+                        })
+                    })
+                    .collect();
+                json!({
+                    "success": true,
+                    "total_found": formatted.len(),
+                    "messages": formatted
+                })
+            }
+            Err(e) => json!({
+                "success": false,
+                "error": format!("Failed to search conversation messages: {}", e)
+            }),
+        }
+    }
+
+    /// Search prompt templates tool implementation. Templates aren't tied to
+    /// a date, source or working directory, so only the kind filter applies.
+    async fn tool_search_prompt_templates(&self, input: &Value, hits: &mut SearchHits) -> Value {
+        let keyword = match input["keyword"].as_str() {
+            Some(k) => k,
+            None => return json!({ "error": "keyword is required" }),
+        };
+        let limit = input["limit"].as_u64().unwrap_or(20) as usize;
+
+        match self.runs_db.list_prompt_templates(Some(keyword)).await {
+            Ok(templates) => {
+                let formatted: Vec<Value> = templates
+                    .iter()
+                    .take(limit)
+                    .map(|t| {
+                        hits.add(SearchKind::PromptTemplates, &t.name, &t.content);
+                        json!({
+                            "name": t.name,
+                            "variables": t.variables,
+                            "use_count": t.use_count,
+                            "content": truncate_with_ellipsis(&t.content, 297)
+                        })
+                    })
+                    .collect();
+                json!({
+                    "success": true,
+                    "total_found": formatted.len(),
+                    "templates": formatted
+                })
+            }
+            Err(e) => json!({
+                "success": false,
+                "error": format!("Failed to search prompt templates: {}", e)
+            }),
+        }
+    }
+
     /// List memory files tool implementation
     fn tool_list_memory_files(&self) -> Value {
         if !self.memory_dir.exists() {
@@ -395,7 +969,7 @@ impl SearchAgent {
     }
 
     /// Search memory content tool implementation
-    fn tool_search_memory_content(&self, input: &Value) -> Value {
+    fn tool_search_memory_content(&self, input: &Value, hits: &mut SearchHits) -> Value {
         let keyword = match input["keyword"].as_str() {
             Some(k) => k.to_lowercase(),
             None => return json!({ "error": "keyword is required" }),
@@ -412,6 +986,16 @@ impl SearchAgent {
         let mut matches = Vec::new();
         self.search_files_recursive(&self.memory_dir, "", &keyword, &mut matches);
 
+        for m in &matches {
+            if let Some(file) = m["file"].as_str() {
+                hits.add(
+                    SearchKind::Memory,
+                    file,
+                    m["excerpts"][0].as_str().unwrap_or(file),
+                );
+            }
+        }
+
         json!({
             "success": true,
             "keyword": keyword,
@@ -503,28 +1087,58 @@ impl SearchAgent {
     /// Search with a natural language query
     ///
     /// This runs a mini tool-loop with the light model to interpret the query
-    /// and search across runs and memories.
-    pub async fn search(&self, query: &str) -> Result<SearchResult, String> {
+    /// and search across runs and memories, restricted to `filters`.
+    pub async fn search(
+        &self,
+        query: &str,
+        filters: &SearchFilters,
+    ) -> Result<SearchResult, String> {
+        let tools = Self::get_search_tools(filters);
+        let mut hits = SearchHits::new(filters);
+
+        // Only kinds without a data source (voice transcripts) were requested
+        if tools.is_empty() {
+            return Ok(SearchResult {
+                success: true,
+                summary: "Nothing to search: the requested kinds have no stored records yet."
+                    .to_string(),
+                runs_found: 0,
+                memories_searched: 0,
+                results: hits.to_json(),
+            });
+        }
+
         // Create light model client
         let client = AIClient::light_from_env()
             .map_err(|e| format!("Failed to create light client: {}", e))?;
 
-        let system_prompt = r#"You are a search agent for a System Commander AI. Your job is to search and synthesize results from two data sources.
+        let base_prompt = r#"You are a search agent for a System Commander AI. Your job is to search and synthesize results from two data sources.
 
 ## Data Sources:
 1. **Run History Database** - Past agent runs with fields: agent_id, working_dir, status, source, initial_prompt, started_at, cost, can_resume
 2. **Memory Files** - Persistent notes including user preferences, project notes, past decisions
+3. **Agent Notes** - Short notes pinned to individual runs by the user or commander
+4. **Prompts** - Every prompt sent to an agent, not only the initial one
+5. **Conversations** - Past conversations with the commander
+6. **Prompt Templates** - Prompts the user saved for reuse, with {{variable}} placeholders
+7. **Agent Outputs** - Everything agents printed: replies, tool calls and results, errors
 
 ## Tools:
 - query_runs(filters): Search run history with optional filters (working_dir, status, source, keyword, days_back, resumable_only, limit)
-- get_run_details(agent_id): Get full details for a specific run
+- get_run_details(agent_id): Get full details for a specific run, including its notes
+- search_agent_notes(keyword): Search notes pinned to runs
 - list_memory_files(): List all memory files
 - read_memory_file(path): Read a specific memory file
 - search_memory_content(keyword): Search keyword across all memory files
+- search_prompts(keyword): Search all prompts sent to agents
+- search_agent_outputs(keyword, agent_id): Search agent output for a file name or error string (which agent touched payments.rs?)
+- search_conversations(keyword): Search past commander conversations by title and preview
+- search_conversation_messages(keyword): Search what was said in past commander conversations
+- search_prompt_templates(keyword): Search saved prompt templates
 
 ## Rules:
 1. Interpret the natural language query
-2. Decide which sources to search (runs, memory, or both)
+2. Decide which sources to search (runs, notes, memory, or a combination)
 3. Execute tool calls to gather information
 4. Correlate and synthesize results
 5. Return a concise, informative summary
@@ -534,13 +1148,17 @@ impl SearchAgent {
 
 Be efficient - don't call tools unnecessarily if you already have the answer."#;
 
+        let system_prompt = format!(
+            "{}\n\n## Scope:\nOnly the tools for the requested kinds are available, and every query is already restricted to: {}. Don't widen it.",
+            base_prompt,
+            filters.describe()
+        );
+
         let mut messages = vec![Message {
             role: "user".to_string(),
             content: query.to_string(),
         }];
 
-        let tools = Self::get_search_tools();
-        let mut runs_found = 0;
         let mut memories_searched = 0;
         let mut final_summary = String::new();
 
@@ -554,7 +1172,7 @@ Be efficient - don't call tools unnecessarily if you already have the answer."#;
             );
 
             let response = client
-                .send_message_with_system_and_tools(system_prompt, messages.clone(), tools.clone())
+                .send_message_with_system_and_tools(&system_prompt, messages.clone(), tools.clone())
                 .await
                 .map_err(|e| format!("Search agent API call failed: {}", e))?;
 
@@ -570,14 +1188,12 @@ Be efficient - don't call tools unnecessarily if you already have the answer."#;
                         has_tool_use = true;
                         eprintln!("[SearchAgent] Tool call: {} - {:?}", name, input);
 
-                        let result = self.execute_search_tool(name, input).await;
+                        let result = self
+                            .execute_search_tool(name, input, filters, &mut hits)
+                            .await;
 
                         // Track what was searched
-                        if name == "query_runs" {
-                            if let Some(count) = result["total_found"].as_u64() {
-                                runs_found += count as usize;
-                            }
-                        } else if name == "search_memory_content" || name == "list_memory_files" {
+                        if name == "search_memory_content" || name == "list_memory_files" {
                             memories_searched += 1;
                         }
 
@@ -617,8 +1233,9 @@ Be efficient - don't call tools unnecessarily if you already have the answer."#;
         Ok(SearchResult {
             success: true,
             summary: final_summary,
-            runs_found,
+            runs_found: hits.total(SearchKind::Runs),
             memories_searched,
+            results: hits.to_json(),
         })
     }
 }
@@ -663,4 +1280,17 @@ mod tests {
         let traversal = "../etc/passwd".trim_start_matches('/');
         assert!(traversal.contains(".."));
     }
+
+    #[test]
+    fn test_excerpt_around_keyword() {
+        assert_eq!(excerpt_around("short", "x", 20), "short");
+
+        let text = format!("{}the staging cluster{}", "a".repeat(50), "b".repeat(50));
+        let excerpt = excerpt_around(&text, "STAGING", 30);
+        assert_eq!(excerpt, "...aaaaaaaaaathe staging clusterb...");
+
+        // Non-ASCII text is cut on a character boundary
+        let excerpt = excerpt_around(&"é".repeat(20), "x", 5);
+        assert_eq!(excerpt, "éé...");
+    }
 }
@@ -0,0 +1,389 @@
+// Search filters - Scope and grouped results for the Search tool
+//
+// The Search tool takes optional filters (date range, agent source, working
+// directory prefix, record kinds, max results per kind). The search agent's
+// tools apply them to every query they run, and each hit is recorded here so
+// the commander gets results grouped by kind with a one-line snippet and a
+// total count, and can drill down with a narrower follow-up search.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::agent_runs_db::RunQueryFilters;
+use crate::types::AgentSource;
+use crate::utils::string::truncate_with_ellipsis;
+
+/// Results listed per kind unless max_per_kind is given
+const DEFAULT_MAX_PER_KIND: usize = 5;
+
+/// Upper bound for max_per_kind
+const MAX_PER_KIND_LIMIT: usize = 50;
+
+/// Snippet length in bytes
+const SNIPPET_MAX_BYTES: usize = 120;
+
+/// Kind of record a search can return
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SearchKind {
+    Runs,
+    Prompts,
+    AgentOutputs,
+    Memory,
+    Conversations,
+    PromptTemplates,
+    VoiceTranscripts,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 7] = [
+        SearchKind::Runs,
+        SearchKind::Prompts,
+        SearchKind::AgentOutputs,
+        SearchKind::Memory,
+        SearchKind::Conversations,
+        SearchKind::PromptTemplates,
+        SearchKind::VoiceTranscripts,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Runs => "runs",
+            SearchKind::Prompts => "prompts",
+            SearchKind::AgentOutputs => "agent_outputs",
+            SearchKind::Memory => "memory",
+            SearchKind::Conversations => "conversations",
+            SearchKind::PromptTemplates => "prompt_templates",
+            SearchKind::VoiceTranscripts => "voice_transcripts",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+}
+
+/// Optional scope for a search
+///
+/// Source and working directory apply to runs, prompts and agent outputs
+/// only; memory files and commander conversations aren't tied to either.
+#[derive(Debug, Clone)]
+pub struct SearchFilters {
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    pub source: Option<AgentSource>,
+    pub working_dir_prefix: Option<String>,
+    /// Kinds to search; all kinds when the input doesn't list any
+    pub kinds: Vec<SearchKind>,
+    pub max_per_kind: usize,
+}
+
+impl Default for SearchFilters {
+    fn default() -> Self {
+        Self {
+            date_from: None,
+            date_to: None,
+            source: None,
+            working_dir_prefix: None,
+            kinds: SearchKind::ALL.to_vec(),
+            max_per_kind: DEFAULT_MAX_PER_KIND,
+        }
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date or RFC 3339 timestamp. A bare date covers the
+/// whole day, so `end_of_day` selects its last millisecond.
+fn parse_date(s: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        date.and_hms_milli_opt(23, 59, 59, 999)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Some(time.and_utc())
+}
+
+fn parse_source(s: &str) -> Option<AgentSource> {
+    match s {
+        "ui" => Some(AgentSource::UI),
+        "meta" => Some(AgentSource::Meta),
+        "pipeline" => Some(AgentSource::Pipeline),
+        "pool" => Some(AgentSource::Pool),
+        "manual" => Some(AgentSource::Manual),
+        "testwizard" => Some(AgentSource::TestWizard),
+        _ => None,
+    }
+}
+
+impl SearchFilters {
+    /// Read the filters from the Search tool input
+    pub fn from_input(input: &Value) -> Result<Self, String> {
+        let mut filters = Self::default();
+
+        if let Some(since) = input["since"].as_str() {
+            filters.date_from = Some(
+                parse_date(since, false).ok_or_else(|| format!("Invalid since date: {}", since))?,
+            );
+        }
+        if let Some(until) = input["until"].as_str() {
+            filters.date_to = Some(
+                parse_date(until, true).ok_or_else(|| format!("Invalid until date: {}", until))?,
+            );
+        }
+        if let (Some(from), Some(to)) = (filters.date_from, filters.date_to) {
+            if from > to {
+                return Err("since must be before until".to_string());
+            }
+        }
+
+        if let Some(source) = input["source"].as_str() {
+            filters.source =
+                Some(parse_source(source).ok_or_else(|| format!("Unknown source: {}", source))?);
+        }
+
+        filters.working_dir_prefix = input["working_dir"]
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        if let Some(kinds) = input["kinds"].as_array() {
+            let mut parsed = Vec::new();
+            for kind in kinds {
+                let name = kind.as_str().unwrap_or_default();
+                let kind =
+                    SearchKind::parse(name).ok_or_else(|| format!("Unknown kind: {}", name))?;
+                if !parsed.contains(&kind) {
+                    parsed.push(kind);
+                }
+            }
+            if !parsed.is_empty() {
+                filters.kinds = parsed;
+            }
+        }
+
+        if let Some(max) = input["max_per_kind"].as_u64() {
+            filters.max_per_kind = (max as usize).clamp(1, MAX_PER_KIND_LIMIT);
+        }
+
+        Ok(filters)
+    }
+
+    pub fn allows(&self, kind: SearchKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    pub fn in_date_range(&self, timestamp_ms: i64) -> bool {
+        let before = self
+            .date_from
+            .is_some_and(|from| timestamp_ms < from.timestamp_millis());
+        let after = self
+            .date_to
+            .is_some_and(|to| timestamp_ms > to.timestamp_millis());
+        !before && !after
+    }
+
+    /// Narrow run query filters to this scope
+    pub fn apply_to(&self, filters: &mut RunQueryFilters) {
+        if let Some(from) = self.date_from {
+            filters.date_from = Some(filters.date_from.map_or(from, |f| f.max(from)));
+        }
+        if let Some(to) = self.date_to {
+            filters.date_to = Some(filters.date_to.map_or(to, |t| t.min(to)));
+        }
+        if self.source.is_some() {
+            filters.source = self.source.clone();
+        }
+        if self.working_dir_prefix.is_some() {
+            filters.working_dir_prefix = self.working_dir_prefix.clone();
+        }
+    }
+
+    /// Human-readable scope for the search agent's prompt
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(from) = self.date_from {
+            parts.push(format!("since {}", from.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(to) = self.date_to {
+            parts.push(format!("until {}", to.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(ref source) = self.source {
+            parts.push(format!("source = {}", source.as_str()));
+        }
+        if let Some(ref prefix) = self.working_dir_prefix {
+            parts.push(format!("working_dir starts with {}", prefix));
+        }
+        let kinds: Vec<&str> = self.kinds.iter().map(|k| k.as_str()).collect();
+        parts.push(format!("kinds: {}", kinds.join(", ")));
+        parts.join("; ")
+    }
+}
+
+/// Collapse text to a single line of at most SNIPPET_MAX_BYTES
+pub fn snippet(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_with_ellipsis(&line, SNIPPET_MAX_BYTES)
+}
+
+#[derive(Default)]
+struct KindHits {
+    seen: HashSet<String>,
+    items: Vec<Value>,
+}
+
+/// Hits recorded by the search agent's tools, grouped by kind
+pub struct SearchHits {
+    max_per_kind: usize,
+    kinds: BTreeMap<SearchKind, KindHits>,
+}
+
+impl SearchHits {
+    pub fn new(filters: &SearchFilters) -> Self {
+        Self {
+            max_per_kind: filters.max_per_kind,
+            kinds: filters
+                .kinds
+                .iter()
+                .map(|kind| (*kind, KindHits::default()))
+                .collect(),
+        }
+    }
+
+    /// Record a hit. Repeated IDs are counted once; only the first
+    /// max_per_kind hits of a kind are listed.
+    pub fn add(&mut self, kind: SearchKind, id: &str, text: &str) {
+        let Some(hits) = self.kinds.get_mut(&kind) else {
+            return;
+        };
+        if !hits.seen.insert(id.to_string()) {
+            return;
+        }
+        if hits.items.len() < self.max_per_kind {
+            hits.items
+                .push(json!({ "id": id, "snippet": snippet(text) }));
+        }
+    }
+
+    pub fn total(&self, kind: SearchKind) -> usize {
+        self.kinds.get(&kind).map_or(0, |hits| hits.seen.len())
+    }
+
+    pub fn to_json(&self) -> Value {
+        let groups: serde_json::Map<String, Value> = self
+            .kinds
+            .iter()
+            .map(|(kind, hits)| {
+                let mut group = json!({
+                    "total": hits.seen.len(),
+                    "items": hits.items,
+                });
+                if *kind == SearchKind::VoiceTranscripts {
+                    group["note"] = json!("Voice transcripts are not persisted yet");
+                }
+                (kind.as_str().to_string(), group)
+            })
+            .collect();
+        Value::Object(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_from_input() {
+        let filters = SearchFilters::from_input(&json!({
+            "query": "login bug",
+            "since": "2026-03-01",
+            "until": "2026-03-31",
+            "source": "pipeline",
+            "working_dir": " /work/app ",
+            "kinds": ["prompts", "runs", "prompts"],
+            "max_per_kind": 500
+        }))
+        .unwrap();
+
+        assert_eq!(
+            filters.date_from.unwrap().to_rfc3339(),
+            "2026-03-01T00:00:00+00:00"
+        );
+        assert!(filters.in_date_range(
+            parse_date("2026-03-31T23:00:00Z", false)
+                .unwrap()
+                .timestamp_millis()
+        ));
+        assert!(!filters.in_date_range(parse_date("2026-04-01", false).unwrap().timestamp_millis()));
+        assert_eq!(filters.source, Some(AgentSource::Pipeline));
+        assert_eq!(filters.working_dir_prefix.as_deref(), Some("/work/app"));
+        assert_eq!(filters.kinds, vec![SearchKind::Prompts, SearchKind::Runs]);
+        assert!(!filters.allows(SearchKind::Memory));
+        assert_eq!(filters.max_per_kind, MAX_PER_KIND_LIMIT);
+
+        let defaults = SearchFilters::from_input(&json!({ "query": "x" })).unwrap();
+        assert_eq!(defaults.kinds, SearchKind::ALL.to_vec());
+        assert_eq!(defaults.max_per_kind, DEFAULT_MAX_PER_KIND);
+
+        assert!(SearchFilters::from_input(&json!({ "kinds": ["files"] })).is_err());
+        assert!(SearchFilters::from_input(&json!({ "since": "last week" })).is_err());
+        assert!(SearchFilters::from_input(
+            &json!({ "since": "2026-04-01", "until": "2026-03-01" })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_apply_to_narrows_run_filters() {
+        let filters = SearchFilters::from_input(&json!({
+            "since": "2026-03-01",
+            "working_dir": "/work"
+        }))
+        .unwrap();
+
+        // The agent asked for a wider range than the caller allows
+        let mut wide = RunQueryFilters {
+            date_from: parse_date("2026-01-01", false),
+            ..Default::default()
+        };
+        filters.apply_to(&mut wide);
+        assert_eq!(wide.date_from, filters.date_from);
+        assert_eq!(wide.working_dir_prefix.as_deref(), Some("/work"));
+
+        // A narrower range from the agent is kept
+        let narrow_from = parse_date("2026-03-10", false);
+        let mut narrow = RunQueryFilters {
+            date_from: narrow_from,
+            ..Default::default()
+        };
+        filters.apply_to(&mut narrow);
+        assert_eq!(narrow.date_from, narrow_from);
+    }
+
+    #[test]
+    fn test_hits_grouped_with_totals_and_caps() {
+        let filters = SearchFilters {
+            kinds: vec![SearchKind::Runs, SearchKind::VoiceTranscripts],
+            max_per_kind: 2,
+            ..Default::default()
+        };
+        let mut hits = SearchHits::new(&filters);
+        hits.add(SearchKind::Runs, "a", "Fix the\nlogin   bug");
+        hits.add(SearchKind::Runs, "a", "duplicate");
+        hits.add(SearchKind::Runs, "b", "Refactor auth");
+        hits.add(SearchKind::Runs, "c", "Update docs");
+        // Kinds outside the filter are ignored
+        hits.add(SearchKind::Memory, "MEMORY.md", "prefers tabs");
+
+        let grouped = hits.to_json();
+        assert_eq!(grouped["runs"]["total"], 3);
+        assert_eq!(grouped["runs"]["items"].as_array().unwrap().len(), 2);
+        assert_eq!(grouped["runs"]["items"][0]["snippet"], "Fix the login bug");
+        assert_eq!(grouped["voice_transcripts"]["total"], 0);
+        assert!(grouped["voice_transcripts"]["note"].is_string());
+        assert!(grouped.get("memory").is_none());
+        assert_eq!(hits.total(SearchKind::Memory), 0);
+    }
+}
@@ -1,5 +1,10 @@
 //! System prompt for the Meta-Agent (System Commander chat interface)
 //! This defines the identity and capabilities of the AI assistant in the main chat.
+//! Advanced users can replace the built-in template with their own, saved per workspace.
+
+use std::path::PathBuf;
+
+use crate::workspace;
 
 /// Base system prompt template with placeholder for max_iterations
 const META_AGENT_SYSTEM_PROMPT_TEMPLATE: &str = r#"You are the System Commander — an AI assistant that orchestrates and manages Claude Code worker agents to complete software engineering tasks. You are the central coordinator of this agent workforce, responsible for driving tasks to completion efficiently and truthfully.
@@ -31,6 +36,7 @@ You maintain the **single source of truth** for overall progress: a master todo
   - "What work was done on project X?"
   - "Find crashed runs that can be resumed"
   - "What do I remember about the user's preferences?"
+  - "Which agent touched payments.rs?" (searches what agents printed)
 - **UpdateMemory tool**: Save notes, preferences, and project context that persist across sessions
 - Memories are automatically included in your context at session start
 - **Proactively update memory** when you learn new facts about:
@@ -98,13 +104,27 @@ In those cases, proceed sequentially.
 3. **Wait or stop** - If an existing agent is working on related work:
    - **Option A**: Wait for it to finish (use `Sleep` and check status periodically)
    - **Option B**: Stop it with `StopWorkerAgent` if redirecting the work
+   - To stop several at once (e.g. every agent of a pipeline that went wrong), use `StopAllWorkerAgents` with a `source` or `working_dir`
 
 ### Agent Status Checks
 - **ListWorkerAgents**: Primary tool for checking agent status - returns status (Running/Stopped/Processing/WaitingForInput), is_processing flag, and pending_input for all agents
 - Use `GetAgentOutput` with `filter_type: "all"` or `"most_recent"` to check recent activity
 - Use `GetAgentTodoList` to see an agent's planned/completed work and progress percentage
+- Use `RenameWorkerAgent` to give each agent a short title for its job (e.g. "auth-refactor", "test-fixer"), so agents are easy to tell apart in the agent list
+- Use `AddAgentNote` to pin what an agent is for (e.g. "exploring approach B"); the latest note appears in `ListWorkerAgents`
+- Use `SendMessageToAgent` when one agent needs something from another (e.g. a reviewer's question for the agent that wrote the code); read the reply with `GetAgentOutput`. Messages are capped, so relay only what matters
+- Use `HandoffWorkerAgent` when a long-running agent's context is nearly full (it repeats itself or loses track of its task); it is replaced by a fresh agent that continues from a summary, under a new agent ID
+- To run several agents on the same repository at once, create them with `use_worktree: true` so each edits its own git worktree and branch instead of overwriting the others' changes
 - Before creating agents for dependent work, verify prerequisites are done
 
+### Auto-Pipelines
+For larger self-contained tasks, `CreateAutoPipeline` runs a full plan -> build -> verify loop with its own orchestrator.
+- Check progress with `GetPipelineStatus`; stop it with `CancelPipeline`
+- When it finishes, its result is queued and you are woken from `Sleep` - no need to poll
+
+### Choosing an Agent
+With several agents open, call `RouteTaskToAgent` with the task before `SendPromptToWorker`. It scores agents on working directory, recent work and load, and returns the agent to use with a justification, or recommends creating a new one.
+
 ### Updating Running Agents
 You can redirect a running agent with `SendPromptToWorker`:
 - Send new instructions without stopping and recreating the agent
@@ -166,7 +186,7 @@ Use ONLY when you genuinely need user input to proceed:
 - Clarification of ambiguous requirements
 - Confirmation before risky operations
 - Choices between multiple valid approaches
-This tool BLOCKS until the user responds (5 minute timeout).
+This tool BLOCKS until the user responds (5 minute timeout by default). If the user may be away and there is a safe choice, set `default_answer` (and optionally `timeout_seconds`) so work continues; check `answered_via` in the result to see whether the user actually answered.
 
 ### Sleep (interruptible pause + iteration reset)
 Use when waiting for agent progress or giving the user time to review:
@@ -206,7 +226,98 @@ When context is compacted, you'll receive a summary of the removed conversation.
 
 You are helpful, proactive, and focused on completing the task efficiently through your agent workforce."#;
 
-/// Generate the system prompt with the configured max_iterations value
-pub fn build_system_prompt(max_iterations: usize) -> String {
-    META_AGENT_SYSTEM_PROMPT_TEMPLATE.replace("{max_iterations}", &max_iterations.to_string())
+/// Placeholder filled with the tool loop's iteration limit
+pub const MAX_ITERATIONS_PLACEHOLDER: &str = "{max_iterations}";
+
+/// Longest base prompt override accepted, in characters
+const MAX_OVERRIDE_CHARS: usize = 100_000;
+
+const OVERRIDE_FILENAME: &str = "commander_base_prompt_override.md";
+
+/// Generate the system prompt with the configured max_iterations value.
+///
+/// `template_override` replaces the built-in template; its placeholders are
+/// filled in the same way.
+pub fn build_system_prompt(template_override: Option<&str>, max_iterations: usize) -> String {
+    template_override
+        .unwrap_or(META_AGENT_SYSTEM_PROMPT_TEMPLATE)
+        .replace(MAX_ITERATIONS_PLACEHOLDER, &max_iterations.to_string())
+}
+
+// =============================================================================
+// Base Prompt Override Persistence
+// =============================================================================
+
+/// Path of the active workspace's base prompt override
+fn override_path() -> Option<PathBuf> {
+    workspace::config_dir(&workspace::active_workspace_id()).map(|d| d.join(OVERRIDE_FILENAME))
+}
+
+/// Load the base prompt override, if one is saved
+pub fn load_base_prompt_override() -> Option<String> {
+    let path = override_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) if !text.trim().is_empty() => Some(text),
+        Ok(_) => None,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("[SystemPrompt] Failed to read base prompt override: {}", e);
+            None
+        }
+    }
+}
+
+/// Check and save a base prompt override
+pub fn save_base_prompt_override(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("System prompt override cannot be empty".to_string());
+    }
+    if text.chars().count() > MAX_OVERRIDE_CHARS {
+        return Err(format!(
+            "System prompt override is too long (max {} characters)",
+            MAX_OVERRIDE_CHARS
+        ));
+    }
+
+    let path = override_path()
+        .ok_or_else(|| "Could not determine config directory for prompt override".to_string())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    std::fs::write(&path, text)
+        .map_err(|e| format!("Failed to write system prompt override: {}", e))?;
+    eprintln!(
+        "[SystemPrompt] Saved base prompt override to {:?} ({} chars)",
+        path,
+        text.len()
+    );
+    Ok(())
+}
+
+/// Remove the saved base prompt override
+pub fn clear_base_prompt_override() -> Result<(), String> {
+    if let Some(path) = override_path() {
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove system prompt override: {}", e))?;
+            eprintln!("[SystemPrompt] Cleared base prompt override");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_gets_max_iterations_substituted() {
+        let built_in = build_system_prompt(None, 40);
+        assert!(built_in.contains("maximum of 40 iterations"));
+        assert!(!built_in.contains(MAX_ITERATIONS_PLACEHOLDER));
+
+        let custom = build_system_prompt(Some("Stop after {max_iterations} steps."), 12);
+        assert_eq!(custom, "Stop after 12 steps.");
+    }
 }
@@ -4,7 +4,7 @@
 // AI responses and executes tool calls until a final response is produced.
 
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
@@ -18,12 +18,18 @@ use crate::types::{
 };
 
 use super::context_tracker::ContextInfo;
+use super::directory_approval::SharedDirectoryApprovals;
+use super::loop_activity::{LoopActivityGuard, SharedLoopActivity};
 use super::memory_worker::MemoryWorker;
 use super::output_compressor::OutputCompressor;
 use super::tools::{
-    self, AgentWakeSender, IterationContext, PendingQuestion, SleepState, ToolExecutionResult,
+    self, AgentWakeSender, IterationContext, PendingQuestion, PipelineToolContext, SleepState,
+    ToolExecutionResult,
 };
 
+/// Final reply of a loop ended by a stop phrase
+const HALTED_REPLY: &str = "Stopped by the user's stop phrase.";
+
 /// Configuration for the tool loop engine
 pub struct ToolLoopConfig {
     /// Maximum number of iterations before giving up
@@ -97,17 +103,20 @@ impl ToolLoopEngine {
 
     /// Process a single AI response, executing any tool calls
     #[allow(clippy::too_many_arguments)]
-    pub async fn process_response(
+    pub async fn process_response<R: Runtime>(
         &self,
         response: &AIResponse,
         agent_manager: Arc<Mutex<AgentManager>>,
-        app_handle: &AppHandle,
+        app_handle: &AppHandle<R>,
         sleep_state: Arc<Mutex<SleepState>>,
         pending_question: Arc<Mutex<Option<PendingQuestion>>>,
         agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
         memory_worker: Arc<MemoryWorker>,
+        pipeline_ctx: PipelineToolContext,
+        directory_approvals: SharedDirectoryApprovals,
         queue_status_fn: impl Fn() -> QueueStatus,
         iteration_ctx: IterationContext,
+        activity: &SharedLoopActivity,
     ) -> ResponseProcessingResult {
         let mut text_content = String::new();
         let mut tool_calls = Vec::new();
@@ -125,6 +134,10 @@ impl ToolLoopEngine {
                 ContentBlock::ToolUse { id, name, input } => {
                     tool_call_count += 1;
 
+                    // Record the tool in the loop activity and tell the UI it's about to run
+                    let iteration_event = activity.lock().unwrap().begin_tool(name, input);
+                    let _ = app_handle.emit("meta-agent:iteration", iteration_event);
+
                     // Execute tool - returns ToolExecutionResult
                     let tool_execution_result = tools::execute_tool(
                         name,
@@ -135,10 +148,13 @@ impl ToolLoopEngine {
                         pending_question.clone(),
                         agent_wake_tx.clone(),
                         memory_worker.clone(),
+                        pipeline_ctx.clone(),
+                        directory_approvals.clone(),
                         &queue_status_fn,
                         iteration_ctx.clone(),
                     )
                     .await;
+                    activity.lock().unwrap().end_tool();
 
                     // Get the result value for logging/events
                     let tool_result = tool_execution_result.to_value();
@@ -260,6 +276,8 @@ impl ToolLoopEngine {
             usage: ChatUsage {
                 input_tokens: usage.input_tokens,
                 output_tokens: usage.output_tokens,
+                cache_creation_input_tokens: usage.cache_creation_input_tokens,
+                cache_read_input_tokens: usage.cache_read_input_tokens,
             },
         }
     }
@@ -271,36 +289,49 @@ impl ToolLoopEngine {
     /// Sleep tool resets the iteration counter, allowing the meta-agent to work
     /// indefinitely by periodically sleeping.
     #[allow(clippy::too_many_arguments)]
-    pub async fn run_loop<F, G>(
+    pub async fn run_loop<F, G, R>(
         &self,
         ai_client: &AIClient,
         system_prompt: &str,
         conversation_history: &mut Vec<RichMessage>,
         tools: Vec<Tool>,
         agent_manager: Arc<Mutex<AgentManager>>,
-        app_handle: &AppHandle,
+        app_handle: &AppHandle<R>,
         sleep_state: Arc<Mutex<SleepState>>,
         pending_question: Arc<Mutex<Option<PendingQuestion>>>,
         agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
         memory_worker: Arc<MemoryWorker>,
+        pipeline_ctx: PipelineToolContext,
+        directory_approvals: SharedDirectoryApprovals,
+        activity: SharedLoopActivity,
         queue_status_fn: F,
         context_info_fn: G,
     ) -> AppResult<ToolLoopResult>
     where
         F: Fn() -> QueueStatus,
         G: Fn() -> Option<ContextInfo>,
+        R: Runtime,
     {
         let mut iteration = 0;
         let mut tool_call_count = 0;
         let mut final_response: Option<ChatResponse> = None;
         let max_iterations = self.config.max_iterations;
-        let mut total_usage = Usage {
-            input_tokens: 0,
-            output_tokens: 0,
-        };
+        let mut total_usage = Usage::default();
+        let _activity_guard = LoopActivityGuard::start(&activity, max_iterations);
 
         while iteration < max_iterations && tool_call_count < self.config.max_tool_calls {
+            if activity.lock().unwrap().halt_requested() {
+                eprintln!("[MetaAgent] Halted by stop phrase - ending tool loop");
+                final_response = Some(Self::build_final_response(
+                    HALTED_REPLY.to_string(),
+                    Vec::new(),
+                    &total_usage,
+                ));
+                break;
+            }
             iteration += 1;
+            activity.lock().unwrap().set_iteration(iteration);
+            crate::metrics::global().record_tool_loop_iteration();
 
             // Extract recent messages for memory evaluation (last 15 messages)
             // Convert RichMessage to Message by extracting text content
@@ -340,8 +371,7 @@ impl ToolLoopEngine {
                 .map_err(|e| AppError::Api(ApiError::Network(format!("AI API error: {}", e))))?;
 
             // Accumulate usage
-            total_usage.input_tokens += response.usage.input_tokens;
-            total_usage.output_tokens += response.usage.output_tokens;
+            total_usage.add(&response.usage);
             eprintln!(
                 "[LLM][{}][{}] Iteration {} - tokens: in={}, out={} (total: in={}, out={})",
                 ai_client.get_provider_name(),
@@ -363,8 +393,11 @@ impl ToolLoopEngine {
                     pending_question.clone(),
                     agent_wake_tx.clone(),
                     memory_worker.clone(),
+                    pipeline_ctx.clone(),
+                    directory_approvals.clone(),
                     &queue_status_fn,
                     iteration_ctx,
+                    &activity,
                 )
                 .await;
 
@@ -455,7 +488,7 @@ impl ToolLoopEngine {
     /// and the conversation_history is updated with RichMessages for subsequent calls.
     /// Sleep tool resets the iteration counter.
     #[allow(clippy::too_many_arguments)]
-    pub async fn run_loop_with_initial_rich_messages<F, G>(
+    pub async fn run_loop_with_initial_rich_messages<F, G, R>(
         &self,
         ai_client: &AIClient,
         system_prompt: &str,
@@ -463,29 +496,42 @@ impl ToolLoopEngine {
         conversation_history: &mut Vec<RichMessage>,
         tools: Vec<Tool>,
         agent_manager: Arc<Mutex<AgentManager>>,
-        app_handle: &AppHandle,
+        app_handle: &AppHandle<R>,
         sleep_state: Arc<Mutex<SleepState>>,
         pending_question: Arc<Mutex<Option<PendingQuestion>>>,
         agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
         memory_worker: Arc<MemoryWorker>,
+        pipeline_ctx: PipelineToolContext,
+        directory_approvals: SharedDirectoryApprovals,
+        activity: SharedLoopActivity,
         queue_status_fn: F,
         context_info_fn: G,
     ) -> AppResult<ToolLoopResult>
     where
         F: Fn() -> QueueStatus,
         G: Fn() -> Option<ContextInfo>,
+        R: Runtime,
     {
         let mut iteration = 0;
         let mut tool_call_count = 0;
         let mut final_response: Option<ChatResponse> = None;
         let max_iterations = self.config.max_iterations;
-        let mut total_usage = Usage {
-            input_tokens: 0,
-            output_tokens: 0,
-        };
+        let mut total_usage = Usage::default();
+        let _activity_guard = LoopActivityGuard::start(&activity, max_iterations);
 
         while iteration < max_iterations && tool_call_count < self.config.max_tool_calls {
+            if activity.lock().unwrap().halt_requested() {
+                eprintln!("[MetaAgent] Halted by stop phrase - ending tool loop");
+                final_response = Some(Self::build_final_response(
+                    HALTED_REPLY.to_string(),
+                    Vec::new(),
+                    &total_usage,
+                ));
+                break;
+            }
             iteration += 1;
+            activity.lock().unwrap().set_iteration(iteration);
+            crate::metrics::global().record_tool_loop_iteration();
 
             // Extract recent messages for memory evaluation (last 15 messages)
             // Convert RichMessage to Message by extracting text content
@@ -538,8 +584,7 @@ impl ToolLoopEngine {
             };
 
             // Accumulate usage
-            total_usage.input_tokens += response.usage.input_tokens;
-            total_usage.output_tokens += response.usage.output_tokens;
+            total_usage.add(&response.usage);
             eprintln!(
                 "[LLM][{}][{}] Iteration {} - tokens: in={}, out={} (total: in={}, out={})",
                 ai_client.get_provider_name(),
@@ -561,8 +606,11 @@ impl ToolLoopEngine {
                     pending_question.clone(),
                     agent_wake_tx.clone(),
                     memory_worker.clone(),
+                    pipeline_ctx.clone(),
+                    directory_approvals.clone(),
                     &queue_status_fn,
                     iteration_ctx,
+                    &activity,
                 )
                 .await;
 
@@ -646,4 +694,269 @@ mod tests {
         // Just verify it was created without panicking
         assert_eq!(engine.config.max_tool_output_chars, 10_000);
     }
+
+    // ========================================================================
+    // Golden tests: the loop driven by scripted responses from MockProvider
+    // ========================================================================
+
+    use crate::ai_client::providers::MockProvider;
+    use crate::meta_agent::directory_approval::DirectoryApprovals;
+    use crate::meta_agent::result_queue::ResultQueue;
+    use crate::types::{AgentWakeEvent, AgentWakeReason};
+    use serde_json::json;
+
+    /// Run the loop on "Check on the agents" until it finishes or gives up
+    async fn run_script(
+        engine: &ToolLoopEngine,
+        provider: Arc<MockProvider>,
+        agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
+    ) -> (AppResult<ToolLoopResult>, Vec<RichMessage>) {
+        let app = tauri::test::mock_app();
+        let ai_client = AIClient::with_provider(provider);
+        let mut history = vec![RichMessage {
+            role: "user".to_string(),
+            content: RichMessageContent::Text("Check on the agents".to_string()),
+        }];
+
+        let result = engine
+            .run_loop(
+                &ai_client,
+                "You are the commander.",
+                &mut history,
+                Vec::new(),
+                Arc::new(Mutex::new(AgentManager::new(0))),
+                app.handle(),
+                Arc::new(Mutex::new(SleepState::default())),
+                Arc::new(Mutex::new(None)),
+                agent_wake_tx,
+                Arc::new(MemoryWorker::start()),
+                PipelineToolContext {
+                    manager: None,
+                    result_queue: Arc::new(std::sync::Mutex::new(ResultQueue::new())),
+                },
+                Arc::new(DirectoryApprovals::default()),
+                SharedLoopActivity::default(),
+                || QueueStatus {
+                    pending: 0,
+                    items: Vec::new(),
+                },
+                || None,
+            )
+            .await;
+        (result, history)
+    }
+
+    fn tool_result_content<'a>(history: &'a [RichMessage], tool_use_id: &str) -> &'a str {
+        history
+            .iter()
+            .filter_map(|m| match &m.content {
+                RichMessageContent::Blocks(blocks) => Some(blocks),
+                RichMessageContent::Text(_) => None,
+            })
+            .flatten()
+            .find_map(|b| match b {
+                RichContentBlock::ToolResult {
+                    tool_use_id: id,
+                    content,
+                    ..
+                } if id == tool_use_id => Some(content.as_str()),
+                _ => None,
+            })
+            .expect("tool result in history")
+    }
+
+    #[tokio::test]
+    async fn test_golden_single_tool_call_then_completion() {
+        let provider = Arc::new(MockProvider::new(vec![
+            MockProvider::tool_use(
+                "toolu_1",
+                "UpdateUser",
+                json!({"message": "Looking at the agents"}),
+            ),
+            MockProvider::text("Both agents are idle."),
+        ]));
+
+        let (result, history) =
+            run_script(&ToolLoopEngine::new(), provider.clone(), Arc::default()).await;
+
+        let result = result.unwrap();
+        assert_eq!(result.response.message.content, "Both agents are idle.");
+        assert_eq!(result.total_usage.input_tokens, 20);
+        assert_eq!(
+            MockProvider::transcript(&history),
+            vec![
+                "user: \"Check on the agents\"",
+                "assistant: tool_use UpdateUser#toolu_1",
+                "user: tool_result toolu_1",
+                "assistant: \"Both agents are idle.\"",
+            ]
+        );
+        assert!(tool_result_content(&history, "toolu_1").contains("Update sent to user"));
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_golden_complete_task_ends_the_loop() {
+        let provider = Arc::new(MockProvider::new(vec![
+            MockProvider::response(vec![
+                ContentBlock::Text {
+                    text: "Wrapping up.".to_string(),
+                },
+                ContentBlock::ToolUse {
+                    id: "toolu_1".to_string(),
+                    name: "CompleteTask".to_string(),
+                    input: json!({"message": "All agents finished.", "status": "success"}),
+                },
+            ]),
+            MockProvider::text("never sent"),
+        ]));
+
+        let (result, history) =
+            run_script(&ToolLoopEngine::new(), provider.clone(), Arc::default()).await;
+
+        let response = result.unwrap().response;
+        assert_eq!(response.message.content, "All agents finished.");
+        assert_eq!(
+            response.message.tool_calls.unwrap()[0].tool_name,
+            "CompleteTask"
+        );
+        // The loop exits without sending the CompleteTask result back
+        assert_eq!(
+            MockProvider::transcript(&history),
+            vec![
+                "user: \"Check on the agents\"",
+                "assistant: \"Wrapping up.\"",
+                "assistant: tool_use CompleteTask#toolu_1",
+            ]
+        );
+        assert_eq!(provider.remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_golden_sleep_wakes_on_agent_event() {
+        let provider = Arc::new(MockProvider::new(vec![
+            MockProvider::tool_use(
+                "toolu_1",
+                "Sleep",
+                json!({"duration_minutes": 0.5, "reason": "Waiting for the build agent"}),
+            ),
+            MockProvider::text("The build agent finished."),
+        ]));
+        let agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>> = Arc::default();
+
+        // Wake the sleeper as soon as Sleep installs its wake channel
+        let wake_slot = agent_wake_tx.clone();
+        tokio::spawn(async move {
+            loop {
+                let sender = wake_slot.lock().await.clone();
+                if let Some(sender) = sender {
+                    let _ = sender
+                        .send(AgentWakeEvent {
+                            agent_id: "agent-1234abcd".to_string(),
+                            reason: AgentWakeReason::Completed,
+                        })
+                        .await;
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        });
+
+        let (result, history) = run_script(
+            &ToolLoopEngine::new(),
+            provider.clone(),
+            agent_wake_tx.clone(),
+        )
+        .await;
+
+        assert_eq!(
+            result.unwrap().response.message.content,
+            "The build agent finished."
+        );
+        assert_eq!(
+            MockProvider::transcript(&history),
+            vec![
+                "user: \"Check on the agents\"",
+                "assistant: tool_use Sleep#toolu_1",
+                "user: tool_result toolu_1",
+                "assistant: \"The build agent finished.\"",
+            ]
+        );
+        let sleep_result: serde_json::Value =
+            serde_json::from_str(tool_result_content(&history, "toolu_1")).unwrap();
+        assert_eq!(sleep_result["agent_wake"]["agent_id"], "agent-1234abcd");
+        assert_eq!(sleep_result["iteration_reset"], true);
+        assert!(agent_wake_tx.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_golden_max_iterations_exhausted() {
+        let engine = ToolLoopEngine::with_config(ToolLoopConfig {
+            max_iterations: 3,
+            ..ToolLoopConfig::default()
+        });
+        let provider = Arc::new(MockProvider::new(
+            (1..=5)
+                .map(|n| {
+                    MockProvider::tool_use(
+                        &format!("toolu_{}", n),
+                        "UpdateUser",
+                        json!({"message": format!("Still working ({})", n)}),
+                    )
+                })
+                .collect(),
+        ));
+
+        let (result, history) = run_script(&engine, provider.clone(), Arc::default()).await;
+
+        let err = result.err().expect("loop should give up");
+        assert!(err.to_string().contains("within iteration limits"));
+        assert_eq!(provider.requests().len(), 3);
+        assert_eq!(provider.remaining(), 2);
+        assert_eq!(
+            MockProvider::transcript(&history)
+                .last()
+                .map(String::as_str),
+            Some("user: tool_result toolu_3")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_golden_unknown_tool_still_gets_a_result() {
+        // Every tool_use must be answered or the API rejects the next request;
+        // MockProvider enforces that, so this run failing would mean a
+        // tool_use was left without its tool_result.
+        let provider = Arc::new(MockProvider::new(vec![
+            MockProvider::response(vec![
+                ContentBlock::ToolUse {
+                    id: "toolu_1".to_string(),
+                    name: "UpdateUser".to_string(),
+                    input: json!({"message": "Checking"}),
+                },
+                ContentBlock::ToolUse {
+                    id: "toolu_2".to_string(),
+                    name: "NoSuchTool".to_string(),
+                    input: json!({}),
+                },
+            ]),
+            MockProvider::text("That tool doesn't exist."),
+        ]));
+
+        let (result, history) =
+            run_script(&ToolLoopEngine::new(), provider.clone(), Arc::default()).await;
+
+        assert!(result.is_ok());
+        assert_eq!(
+            MockProvider::transcript(&history),
+            vec![
+                "user: \"Check on the agents\"",
+                "assistant: tool_use UpdateUser#toolu_1",
+                "assistant: tool_use NoSuchTool#toolu_2",
+                "user: tool_result toolu_1",
+                "user: tool_result toolu_2",
+                "assistant: \"That tool doesn't exist.\"",
+            ]
+        );
+        assert!(tool_result_content(&history, "toolu_2").contains("Unknown tool: NoSuchTool"));
+    }
 }
@@ -1,20 +1,36 @@
 // Agent management tools for MetaAgent
 
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
 use tokio::sync::Mutex;
 
-use crate::agent_manager::AgentManager;
+use crate::agent_manager::handoff;
+use crate::agent_manager::rate_limit;
+use crate::agent_manager::{AgentManager, AgentSpawnOptions};
+use crate::agent_runs_db::SavedPromptTemplate;
+use crate::commands::env_registry;
+use crate::cost_preference::{self, OffloadKind};
+use crate::meta_agent::agent_router::{self, AgentProfile, RouteDecision};
+use crate::meta_agent::directory_approval::{
+    is_recent_dir, SharedDirectoryApprovals, DIRECTORY_APPROVAL_TIMEOUT_SECS,
+};
 use crate::meta_agent::helpers::{error, get_optional_bool, get_optional_u64};
-use crate::types::AgentSource;
+use crate::permission_profiles;
+use crate::prompt_library::{self, PromptTemplateRef};
+use crate::types::{AgentSource, NoteAuthor, StopOutcome, ToolRestrictions};
+use crate::utils::validation::{validate_model, validate_working_dir};
+
+/// Number of recent run directories that can be used without asking the user
+const RECENT_WORKING_DIRS_LIMIT: usize = 200;
 
 /// Resolve model name from complexity level.
 /// Only applies when CLAUDE_CODE_MODEL is "auto" or unset.
 /// Returns None if a specific model is configured (letting env var take precedence).
 fn resolve_model_from_complexity(complexity: Option<&str>) -> Option<String> {
     // Check if CLAUDE_CODE_MODEL is set to a specific model (not "auto" or empty)
-    let claude_code_model = std::env::var("CLAUDE_CODE_MODEL").unwrap_or_default();
+    let claude_code_model = env_registry::CLAUDE_CODE_MODEL.var().unwrap_or_default();
     let model_is_auto = {
         let m = claude_code_model.trim().to_lowercase();
         m.is_empty() || m == "auto"
@@ -28,35 +44,91 @@ fn resolve_model_from_complexity(complexity: Option<&str>) -> Option<String> {
     // Map complexity to model using environment variables with defaults
     let complexity = complexity.unwrap_or("easy");
     let model = match complexity {
-        "simple" => {
-            std::env::var("LIGHT_TASK_MODEL").unwrap_or_else(|_| "claude-haiku-4-5".to_string())
-        }
-        "complex" => {
-            std::env::var("SECURITY_MODEL").unwrap_or_else(|_| "claude-opus-4-5".to_string())
-        }
-        _ => std::env::var("PRIMARY_MODEL").unwrap_or_else(|_| "claude-sonnet-4-5".to_string()),
+        "simple" => env_registry::LIGHT_TASK_MODEL
+            .var()
+            .unwrap_or_else(|_| "claude-haiku-4-5".to_string()),
+        "complex" => env_registry::SECURITY_MODEL
+            .var()
+            .unwrap_or_else(|_| "claude-opus-4-5".to_string()),
+        _ => env_registry::PRIMARY_MODEL
+            .var()
+            .unwrap_or_else(|_| "claude-sonnet-4-5".to_string()),
     };
 
     Some(model)
 }
 
 /// Create a new worker agent
-pub async fn create_worker_agent(
+pub async fn create_worker_agent<R: Runtime>(
     input: Value,
     agent_manager: Arc<Mutex<AgentManager>>,
-    app_handle: AppHandle,
+    app_handle: AppHandle<R>,
+    directory_approvals: SharedDirectoryApprovals,
 ) -> Value {
     let working_dir = input["working_dir"].as_str().unwrap_or("");
     if working_dir.is_empty() {
         return error("Validation failed: working_dir is required. Use the ListDirectory tool to explore the filesystem and find a valid directory, or ask the user for a working directory path.");
     }
 
-    // Check if the directory exists
-    if !std::path::Path::new(working_dir).exists() {
-        return error(format!(
-            "Validation failed: Directory '{}' does not exist. Use the ListDirectory tool to explore available directories (e.g., ListDirectory with path '~' or '/home'), or ask the user for a valid path.",
-            working_dir
-        ));
+    let working_dir = match validate_working_dir(working_dir) {
+        Ok(dir) => dir,
+        Err(e) => {
+            return error(format!(
+                "Validation failed: {}. Use the ListDirectory tool to explore available directories (e.g., ListDirectory with path '~' or '/home'), or ask the user for a valid path.",
+                e
+            ))
+        }
+    };
+
+    let requested_model = match validate_model(input["model"].as_str()) {
+        Ok(model) => model,
+        Err(e) => return error(format!("Validation failed: {}", e)),
+    };
+
+    let extra_env: HashMap<String, String> = match &input["env"] {
+        Value::Null => HashMap::new(),
+        env => match serde_json::from_value(env.clone()) {
+            Ok(env) => env,
+            Err(_) => return error("Validation failed: env must map variable names to strings"),
+        },
+    };
+
+    let tool_list = |field: &str| -> Result<Vec<String>, String> {
+        match &input[field] {
+            Value::Null => Ok(Vec::new()),
+            list => serde_json::from_value(list.clone())
+                .map_err(|_| format!("{} must be an array of tool names", field)),
+        }
+    };
+    let tools = match tool_list("allowed_tools").and_then(|allowed_tools| {
+        permission_profiles::validate_tool_restrictions(&ToolRestrictions {
+            allowed_tools,
+            disallowed_tools: tool_list("disallowed_tools")?,
+        })
+    }) {
+        Ok(tools) => tools,
+        Err(e) => return error(format!("Validation failed: {}", e)),
+    };
+
+    // Render a prompt library template before asking for anything, so a
+    // missing variable doesn't leave an idle agent behind
+    let template = match resolve_template(&input, &agent_manager).await {
+        Ok(template) => template,
+        Err(e) => return error(format!("Validation failed: {}", e)),
+    };
+    if template.is_some() && input["initial_prompt"].as_str().is_some() {
+        return error("Validation failed: give either initial_prompt or template, not both");
+    }
+
+    if let Err(denied) = ensure_directory_approved(
+        &working_dir,
+        &agent_manager,
+        &app_handle,
+        &directory_approvals,
+    )
+    .await
+    {
+        return denied;
     }
 
     let github_url = input["github_url"].as_str().map(|s| s.to_string());
@@ -64,28 +136,48 @@ pub async fn create_worker_agent(
     // Get complexity for UI display and model selection
     let complexity = input["complexity"].as_str().map(|s| s.to_string());
 
-    // Resolve model based on complexity level (only when CLAUDE_CODE_MODEL is "auto" or unset)
-    let model = resolve_model_from_complexity(complexity.as_deref());
+    // An explicit model wins; otherwise resolve it from the complexity level
+    // (only when CLAUDE_CODE_MODEL is "auto" or unset)
+    let model = requested_model.or_else(|| resolve_model_from_complexity(complexity.as_deref()));
 
     let manager = agent_manager.lock().await;
     match manager
-        .create_agent_with_model(
-            working_dir.to_string(),
+        .create_agent_with_pipeline(
+            working_dir,
             github_url,
             None,
+            Vec::new(),
             AgentSource::Meta,
             Arc::new(app_handle.clone()),
-            model,
-            complexity,
+            AgentSpawnOptions {
+                model,
+                complexity,
+                extra_env,
+                mcp_servers: input.get("mcp_servers").filter(|v| !v.is_null()).cloned(),
+                tools,
+                use_worktree: get_optional_bool(&input, "use_worktree", false),
+                ..Default::default()
+            },
         )
         .await
     {
         Ok(agent_id) => {
+            if get_optional_bool(&input, "keep_worktree", false) {
+                let _ = manager.set_agent_keep_worktree(&agent_id, true).await;
+            }
+            let worktree = manager
+                .get_agent_info(&agent_id)
+                .await
+                .and_then(|info| info.worktree);
             drop(manager);
 
             // Send initial prompt if provided
             // Note: No security_monitor for meta-agent automated prompts
-            if let Some(initial_prompt) = input["initial_prompt"].as_str() {
+            let initial_prompt = match &template {
+                Some((_, rendered)) => Some(rendered.as_str()),
+                None => input["initial_prompt"].as_str(),
+            };
+            if let Some(initial_prompt) = initial_prompt {
                 let manager = agent_manager.lock().await;
                 if let Err(e) = manager
                     .send_prompt(
@@ -102,7 +194,12 @@ pub async fn create_worker_agent(
                         "warning": format!("Agent created but initial prompt failed: {}", e)
                     });
                 }
+                let runs_db = manager.runs_db.clone();
                 drop(manager);
+
+                if let (Some((saved, _)), Some(db)) = (&template, runs_db) {
+                    prompt_library::record_use(&db, saved).await;
+                }
             }
 
             // Navigate to agent if requested
@@ -112,21 +209,94 @@ pub async fn create_worker_agent(
                     .ok();
             }
 
-            json!({
+            let mut result = json!({
                 "success": true,
                 "agent_id": agent_id,
                 "status": "created and running"
-            })
+            });
+            if let Some(worktree) = worktree {
+                result["worktree"] = json!(worktree);
+            }
+            result
         }
         Err(e) => error(format!("Failed to create agent: {}", e)),
     }
 }
 
+/// Render the prompt library template named by the `template` input, if any
+async fn resolve_template(
+    input: &Value,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+) -> Result<Option<(SavedPromptTemplate, String)>, String> {
+    if input["template"].is_null() {
+        return Ok(None);
+    }
+    let template: PromptTemplateRef = serde_json::from_value(input["template"].clone())
+        .map_err(|e| format!("template must be {{ name, variables }}: {}", e))?;
+    let runs_db = agent_manager
+        .lock()
+        .await
+        .runs_db
+        .clone()
+        .ok_or("the prompt library is not available")?;
+    prompt_library::render_saved(&runs_db, &template)
+        .await
+        .map(Some)
+}
+
+/// Ask the user before creating an agent in a directory they have not used.
+///
+/// Directories of recent runs and anything inside the workspace allowlist go
+/// through without a prompt. Returns the tool result to hand back to the model
+/// when the user refuses (or does not answer).
+async fn ensure_directory_approved<R: Runtime>(
+    working_dir: &str,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    app_handle: &AppHandle<R>,
+    directory_approvals: &SharedDirectoryApprovals,
+) -> Result<(), Value> {
+    if directory_approvals.is_allowlisted(working_dir).await {
+        return Ok(());
+    }
+
+    let runs_db = agent_manager.lock().await.runs_db.clone();
+    if let Some(db) = runs_db {
+        match db.get_recent_working_dirs(RECENT_WORKING_DIRS_LIMIT).await {
+            Ok(recent_dirs) if is_recent_dir(working_dir, &recent_dirs) => return Ok(()),
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "[CreateWorkerAgent] Failed to load recent directories: {}",
+                e
+            ),
+        }
+    }
+
+    match directory_approvals.request(app_handle, working_dir).await {
+        Some(decision) if decision.approved => Ok(()),
+        Some(_) => Err(json!({
+            "success": false,
+            "denied": true,
+            "error": format!(
+                "The user refused to let a worker agent run in '{}'. Do not retry this directory. Pick a directory the user has worked in before, or use AskUserQuestion to ask where the agent should work.",
+                working_dir
+            )
+        })),
+        None => Err(json!({
+            "success": false,
+            "denied": true,
+            "error": format!(
+                "The user did not approve the working directory '{}' within {} seconds. Use AskUserQuestion to confirm where the agent should work before trying again.",
+                working_dir, DIRECTORY_APPROVAL_TIMEOUT_SECS
+            )
+        })),
+    }
+}
+
 /// Send a prompt to an existing worker agent
-pub async fn send_prompt_to_worker(
+pub async fn send_prompt_to_worker<R: Runtime>(
     input: Value,
     agent_manager: Arc<Mutex<AgentManager>>,
-    app_handle: AppHandle,
+    app_handle: AppHandle<R>,
 ) -> Value {
     let agent_id = input["agent_id"].as_str().unwrap_or("");
     let prompt = input["prompt"].as_str().unwrap_or("");
@@ -135,12 +305,27 @@ pub async fn send_prompt_to_worker(
         return error("agent_id and prompt are required");
     }
 
+    // Prompts sent during a usage or rate limit would only fail again
+    if let Some(until) = rate_limit::limited_until() {
+        let secs = (until - chrono::Utc::now().timestamp_millis()).max(0) / 1000;
+        return error(format!(
+            "Claude agents are rate-limited for another {}s; Sleep until then and retry",
+            secs
+        ));
+    }
+
     let manager = agent_manager.lock().await;
     // Note: No security_monitor for meta-agent automated prompts
     match manager
         .send_prompt(agent_id, prompt, Some(Arc::new(app_handle)), None)
         .await
     {
+        Ok(delivery) if delivery.queued => json!({
+            "success": true,
+            "queued": true,
+            "queue_depth": delivery.queue_depth,
+            "message": "The agent is still working; the prompt is queued and sent when its turn ends"
+        }),
         Ok(_) => json!({
             "success": true,
             "message": "Prompt sent successfully"
@@ -149,6 +334,100 @@ pub async fn send_prompt_to_worker(
     }
 }
 
+/// Pick the existing agent best suited to a task, or recommend creating one
+pub async fn route_task_to_agent(input: Value, agent_manager: Arc<Mutex<AgentManager>>) -> Value {
+    let task = input["task"].as_str().unwrap_or("");
+    if task.trim().is_empty() {
+        return error("task is required");
+    }
+    let working_dir = input["working_dir"].as_str().filter(|d| !d.is_empty());
+
+    let (agents, runs_db) = {
+        let manager = agent_manager.lock().await;
+        (manager.list_agents().await, manager.runs_db.clone())
+    };
+    let mut profiles = Vec::with_capacity(agents.len());
+    for info in agents {
+        let mut recent_prompts = match &runs_db {
+            Some(db) => db
+                .get_prompts(&info.id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(prompt, _)| prompt)
+                .collect(),
+            None => Vec::new(),
+        };
+        let skip = recent_prompts
+            .len()
+            .saturating_sub(agent_router::RECENT_PROMPTS);
+        recent_prompts.drain(..skip);
+        profiles.push(AgentProfile {
+            info,
+            recent_prompts,
+        });
+    }
+
+    let ranked = agent_router::rank_agents(
+        task,
+        working_dir,
+        &profiles,
+        chrono::Utc::now().timestamp_millis(),
+    );
+    let scores: Vec<Value> = ranked
+        .iter()
+        .map(|a| json!({ "agent_id": a.agent_id, "score": (a.score * 100.0).round() / 100.0 }))
+        .collect();
+
+    let (agent_id, justification) = match agent_router::decide(&ranked) {
+        RouteDecision::Agent {
+            agent_id,
+            justification,
+        } => (agent_id, justification),
+        RouteDecision::CreateNew { justification } => {
+            return json!({
+                "success": true,
+                "recommendation": "create_new",
+                "justification": justification,
+                "scores": scores
+            });
+        }
+        RouteDecision::Tie { agent_ids } => {
+            let tied: Vec<&AgentProfile> = agent_ids
+                .iter()
+                .filter_map(|id| profiles.iter().find(|p| &p.info.id == id))
+                .collect();
+            // Ranking order decides when the light model can't
+            let (chosen, chosen_by) = match agent_router::break_tie(task, &tied).await {
+                Some(id) => (id, "the light model"),
+                None => (agent_ids[0].clone(), "score"),
+            };
+            let scored = ranked.iter().find(|a| a.agent_id == chosen);
+            let justification = format!(
+                "Tied with {} (within {:.2} points); chosen by {}. {}",
+                agent_ids
+                    .iter()
+                    .filter(|id| **id != chosen)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                agent_router::TIE_MARGIN,
+                chosen_by,
+                scored.map(agent_router::justify).unwrap_or_default()
+            );
+            (chosen, justification)
+        }
+    };
+
+    json!({
+        "success": true,
+        "recommendation": "use_existing",
+        "agent_id": agent_id,
+        "justification": justification,
+        "scores": scores
+    })
+}
+
 /// Stop a worker agent
 pub async fn stop_worker_agent(input: Value, agent_manager: Arc<Mutex<AgentManager>>) -> Value {
     let agent_id = input["agent_id"].as_str().unwrap_or("");
@@ -157,8 +436,17 @@ pub async fn stop_worker_agent(input: Value, agent_manager: Arc<Mutex<AgentManag
         return error("agent_id is required");
     }
 
-    let manager = agent_manager.lock().await;
-    match manager.stop_agent(agent_id).await {
+    // Drained after releasing the lock, which can take AGENT_STOP_TIMEOUT_SECS
+    let begun = agent_manager
+        .lock()
+        .await
+        .begin_stop(agent_id, false, None)
+        .await;
+    let result = match begun {
+        Ok(pending) => pending.finish().await,
+        Err(e) => Err(e),
+    };
+    match result {
         Ok(_) => json!({
             "success": true,
             "message": "Agent stopped successfully"
@@ -167,6 +455,83 @@ pub async fn stop_worker_agent(input: Value, agent_manager: Arc<Mutex<AgentManag
     }
 }
 
+/// Stop every worker agent, or only those from a source and/or directory
+pub async fn stop_all_worker_agents<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+) -> Value {
+    let source = match input["source"].as_str().filter(|s| !s.is_empty()) {
+        Some(name) => match AgentSource::parse(name) {
+            Some(source) => Some(source),
+            None => return error(format!("Unknown agent source: {}", name)),
+        },
+        None => None,
+    };
+    let working_dir = input["working_dir"].as_str().filter(|s| !s.is_empty());
+
+    let manager = agent_manager.lock().await;
+    let results = manager
+        .stop_all(source.as_ref(), working_dir, &app_handle)
+        .await;
+    let count = |outcome: StopOutcome| results.iter().filter(|r| r.outcome == outcome).count();
+    json!({
+        "success": count(StopOutcome::Failed) == 0,
+        "stopped": count(StopOutcome::Stopped),
+        "already_stopped": count(StopOutcome::AlreadyStopped),
+        "failed": count(StopOutcome::Failed),
+        "results": results
+    })
+}
+
+/// Give a worker agent a title saying what it is for
+pub async fn rename_worker_agent<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+) -> Value {
+    let agent_id = input["agent_id"].as_str().unwrap_or("");
+    let title = input["title"].as_str().unwrap_or("");
+
+    if agent_id.is_empty() {
+        return error("agent_id is required");
+    }
+
+    let manager = agent_manager.lock().await;
+    match manager.set_agent_title(agent_id, title, &app_handle).await {
+        Ok(info) => json!({
+            "success": true,
+            "agent_id": agent_id,
+            "title": info.title
+        }),
+        Err(e) => error(format!("Failed to rename agent: {}", e)),
+    }
+}
+
+/// Replace a worker whose context is nearly full with a fresh one
+pub async fn handoff_worker_agent<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+) -> Value {
+    let agent_id = input["agent_id"].as_str().unwrap_or("");
+
+    if agent_id.is_empty() {
+        return error("agent_id is required");
+    }
+
+    match handoff::handoff_agent(&agent_manager, agent_id, Arc::new(app_handle)).await {
+        Ok(result) => json!({
+            "success": true,
+            "message": "Agent replaced; the new agent received the hand-off summary as its first prompt",
+            "previous_agent_id": result.previous_agent_id,
+            "agent_id": result.agent_id,
+            "summary": result.summary
+        }),
+        Err(e) => error(format!("Failed to hand off agent: {}", e)),
+    }
+}
+
 /// List all worker agents
 pub async fn list_worker_agents(agent_manager: Arc<Mutex<AgentManager>>) -> Value {
     let manager = agent_manager.lock().await;
@@ -177,11 +542,74 @@ pub async fn list_worker_agents(agent_manager: Arc<Mutex<AgentManager>>) -> Valu
     })
 }
 
+/// Pin a note to an agent (e.g. which approach it is exploring)
+pub async fn add_agent_note<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+) -> Value {
+    let agent_id = input["agent_id"].as_str().unwrap_or("");
+    let text = input["text"].as_str().unwrap_or("");
+
+    if agent_id.is_empty() {
+        return error("agent_id is required");
+    }
+    if text.trim().is_empty() {
+        return error("text is required");
+    }
+
+    let manager = agent_manager.lock().await;
+    match manager
+        .add_agent_note(agent_id, NoteAuthor::Meta, text)
+        .await
+    {
+        Ok(note) => {
+            let _ = app_handle.emit("agent:note", &note);
+            json!({
+                "success": true,
+                "note": note
+            })
+        }
+        Err(e) => error(format!("Failed to add note: {}", e)),
+    }
+}
+
+/// Relay a message from one agent into another agent's prompt queue
+pub async fn send_message_to_agent<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+) -> Value {
+    let from_agent_id = input["from_agent_id"].as_str().unwrap_or("");
+    let to_agent_id = input["to_agent_id"].as_str().unwrap_or("");
+    let message = input["message"].as_str().unwrap_or("");
+
+    if from_agent_id.is_empty() || to_agent_id.is_empty() {
+        return error("from_agent_id and to_agent_id are required");
+    }
+    if message.trim().is_empty() {
+        return error("message is required");
+    }
+
+    let manager = agent_manager.lock().await;
+    match manager
+        .send_agent_message(from_agent_id, to_agent_id, message, Arc::new(app_handle))
+        .await
+    {
+        Ok(message) => json!({
+            "success": true,
+            "message": "Message delivered. Use GetAgentOutput on the target agent to read its reply.",
+            "agent_message": message
+        }),
+        Err(e) => error(format!("Failed to send message: {}", e)),
+    }
+}
+
 /// Get output from an agent
-pub async fn get_agent_output(
+pub async fn get_agent_output<R: Runtime>(
     input: Value,
     agent_manager: Arc<Mutex<AgentManager>>,
-    _app_handle: AppHandle,
+    _app_handle: AppHandle<R>,
 ) -> Value {
     let agent_id = input["agent_id"].as_str().unwrap_or("");
     let last_n = get_optional_u64(&input, "last_n", 10) as usize;
@@ -211,7 +639,7 @@ pub async fn get_agent_output(
 
     let manager = agent_manager.lock().await;
     // Fetch all outputs first (pass 0 to get everything), then filter and apply last_n
-    match manager.get_agent_outputs(agent_id, 0).await {
+    match manager.get_agent_outputs(agent_id, 0, None).await {
         Ok(outputs) => {
             // Apply filter BEFORE last_n limit (matching UI behavior)
             let filtered_outputs: Vec<_> = outputs
@@ -254,14 +682,37 @@ pub async fn get_agent_output(
                 filter_type
             };
 
-            json!({
+            let mut result = json!({
                 "success": true,
                 "agent_id": agent_id,
                 "filter_type": filter_type,
                 "output_count": limited_outputs.len(),
                 "outputs": formatted_output,
                 "summary": format!("Retrieved {} {} outputs from agent in {}", limited_outputs.len(), type_label, agent_name)
-            })
+            });
+
+            // Leave long output with the agent while it's free to summarize it
+            if cost_preference::offload_to_workers() {
+                if let Some((tail, omitted)) = cost_preference::shorten_output(
+                    &formatted_output,
+                    cost_preference::OFFLOAD_OUTPUT_CHARS,
+                ) {
+                    result["outputs"] = json!(tail);
+                    result["omitted_chars"] = json!(omitted);
+                    result["hint"] = json!(format!(
+                        "Showing the last {} characters. To save API cost, ask the agent with \
+                         SendPromptToWorker for a short summary instead of reading its full output.",
+                        cost_preference::OFFLOAD_OUTPUT_CHARS
+                    ));
+                    cost_preference::record_offload(
+                        manager.runs_db.as_ref(),
+                        OffloadKind::AgentOutput,
+                        cost_preference::estimate_tokens(omitted),
+                    )
+                    .await;
+                }
+            }
+            result
         }
         Err(e) => error(format!("Failed to get agent output: {}", e)),
     }
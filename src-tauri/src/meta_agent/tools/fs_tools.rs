@@ -3,6 +3,7 @@
 use serde_json::{json, Value};
 
 use crate::meta_agent::helpers::error;
+use crate::utils::validation::expand_home_dir;
 
 /// List contents of a directory
 pub async fn list_directory(input: Value) -> Value {
@@ -12,7 +13,10 @@ pub async fn list_directory(input: Value) -> Value {
     }
 
     // Expand ~ to home directory
-    let expanded_path = expand_home_dir(path);
+    let expanded_path = match expand_home_dir(path) {
+        Ok(p) => p.to_string_lossy().into_owned(),
+        Err(e) => return error(e),
+    };
 
     // Check if path exists
     let path_obj = std::path::Path::new(&expanded_path);
@@ -43,19 +47,6 @@ pub async fn list_directory(input: Value) -> Value {
     }
 }
 
-/// Expand ~ to home directory (cross-platform)
-fn expand_home_dir(path: &str) -> String {
-    if path.starts_with('~') {
-        if let Some(home) = dirs::home_dir() {
-            path.replacen('~', home.to_string_lossy().as_ref(), 1)
-        } else {
-            path.to_string()
-        }
-    } else {
-        path.to_string()
-    }
-}
-
 /// Collect directory entries into JSON items
 fn collect_directory_items(entries: std::fs::ReadDir) -> Vec<Value> {
     let mut items = Vec::new();
@@ -6,25 +6,127 @@
 // - AskUserQuestion: Block and wait for user input
 // - CompleteTask: Signal task completion (handled in mod.rs)
 
+use serde::Serialize;
 use serde_json::{json, Value};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
 use tokio::sync::{mpsc, oneshot, Mutex};
 
 use crate::agent_manager::AgentManager;
 use crate::ai_client::Message;
 use crate::meta_agent::helpers::error;
 use crate::meta_agent::memory_worker::MemoryWorker;
+use crate::observer_mode;
 use crate::types::AgentWakeEvent;
 
 // ============================================================================
 // Shared State Types
 // ============================================================================
 
+/// Default time to wait for an answer to AskUserQuestion
+const DEFAULT_QUESTION_TIMEOUT_SECS: u64 = 300;
+
+/// Bounds for a model-configured question timeout
+const MIN_QUESTION_TIMEOUT_SECS: u64 = 10;
+const MAX_QUESTION_TIMEOUT_SECS: u64 = 3600;
+
+/// Where an answer to AskUserQuestion came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerSource {
+    Ui,
+    Voice,
+    Rest,
+    Timeout,
+}
+
+impl AnswerSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnswerSource::Ui => "ui",
+            AnswerSource::Voice => "voice",
+            AnswerSource::Rest => "rest",
+            AnswerSource::Timeout => "timeout",
+        }
+    }
+}
+
+/// An answer delivered to a pending question
+#[derive(Debug)]
+pub struct QuestionAnswer {
+    pub answer: String,
+    pub source: AnswerSource,
+}
+
 /// State for a pending user question
 pub struct PendingQuestion {
+    pub info: PendingQuestionInfo,
+    pub response_tx: oneshot::Sender<QuestionAnswer>,
+}
+
+/// Serializable view of a pending question, shared with every answering surface
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingQuestionInfo {
     pub question_id: String,
-    pub response_tx: oneshot::Sender<String>,
+    pub question: String,
+    pub options: Option<Vec<String>>,
+    /// Answer used if nobody responds before the timeout
+    pub default_answer: Option<String>,
+    pub timeout_secs: u64,
+    pub asked_at: i64,
+}
+
+/// Deliver an answer to the pending question. Refused in observer mode.
+///
+/// When `question_id` is given it must match the pending question; surfaces
+/// that cannot know the ID (voice) pass None to answer whatever is pending.
+pub async fn answer_pending_question(
+    pending_question: &Mutex<Option<PendingQuestion>>,
+    question_id: Option<&str>,
+    answer: String,
+    source: AnswerSource,
+) -> Result<(), String> {
+    if observer_mode::enabled() {
+        return Err(observer_mode::OBSERVER_MODE_MESSAGE.to_string());
+    }
+    deliver_answer(pending_question, question_id, answer, source).await
+}
+
+/// Deliver an answer to the pending question, even in observer mode (a
+/// stop phrase releases the question this way)
+pub async fn deliver_answer(
+    pending_question: &Mutex<Option<PendingQuestion>>,
+    question_id: Option<&str>,
+    answer: String,
+    source: AnswerSource,
+) -> Result<(), String> {
+    let mut pending = pending_question.lock().await;
+    let pq = pending
+        .take()
+        .ok_or_else(|| "No pending question to answer".to_string())?;
+
+    if let Some(id) = question_id {
+        if pq.info.question_id != id {
+            // Put it back if question_id doesn't match
+            *pending = Some(pq);
+            return Err(format!(
+                "Question ID mismatch: expected current pending question, got {}",
+                id
+            ));
+        }
+    }
+
+    pq.response_tx
+        .send(QuestionAnswer { answer, source })
+        .map_err(|_| "Failed to send answer: channel closed".to_string())
+}
+
+/// Clamp the model-requested timeout to a sane range
+fn question_timeout_secs(input: &Value) -> u64 {
+    input["timeout_seconds"]
+        .as_u64()
+        .unwrap_or(DEFAULT_QUESTION_TIMEOUT_SECS)
+        .clamp(MIN_QUESTION_TIMEOUT_SECS, MAX_QUESTION_TIMEOUT_SECS)
 }
 
 /// Shared state for interruptible sleep
@@ -46,9 +148,9 @@ pub type AgentWakeSender = mpsc::Sender<AgentWakeEvent>;
 /// Sleep for a duration, interruptible by user messages or agent state changes.
 /// Returns current agent status when waking up.
 /// If sleep duration >= 1 minute, queues a memory evaluation task.
-pub async fn sleep_tool(
+pub async fn sleep_tool<R: Runtime>(
     input: Value,
-    app_handle: &AppHandle,
+    app_handle: &AppHandle<R>,
     sleep_state: Arc<Mutex<SleepState>>,
     agent_manager: Arc<Mutex<AgentManager>>,
     agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
@@ -203,7 +305,7 @@ async fn get_agents_summary(agent_manager: &Arc<Mutex<AgentManager>>) -> Value {
 // ============================================================================
 
 /// Send a non-blocking status update to the user
-pub async fn update_user(input: Value, app_handle: &AppHandle) -> Value {
+pub async fn update_user<R: Runtime>(input: Value, app_handle: &AppHandle<R>) -> Value {
     let message = match input["message"].as_str() {
         Some(m) if !m.is_empty() => m,
         _ => return error("message is required"),
@@ -233,9 +335,13 @@ pub async fn update_user(input: Value, app_handle: &AppHandle) -> Value {
 // ============================================================================
 
 /// Ask the user a question and wait for their response (blocking)
-pub async fn ask_user_question(
+///
+/// The question can be answered from the chat UI, voice or the REST API. If
+/// the model supplied a default_answer it is used when the timeout expires.
+/// The tool result always records where the answer came from.
+pub async fn ask_user_question<R: Runtime>(
     input: Value,
-    app_handle: &AppHandle,
+    app_handle: &AppHandle<R>,
     pending_question: Arc<Mutex<Option<PendingQuestion>>>,
 ) -> Value {
     let question = match input["question"].as_str() {
@@ -248,15 +354,28 @@ pub async fn ask_user_question(
             .filter_map(|v| v.as_str().map(String::from))
             .collect()
     });
+    let default_answer = input["default_answer"]
+        .as_str()
+        .filter(|a| !a.is_empty())
+        .map(String::from);
+    let timeout_secs = question_timeout_secs(&input);
+
+    let info = PendingQuestionInfo {
+        question_id: uuid::Uuid::new_v4().to_string(),
+        question: question.to_string(),
+        options,
+        default_answer,
+        timeout_secs,
+        asked_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let question_id = info.question_id.clone();
+    let (tx, rx) = oneshot::channel::<QuestionAnswer>();
 
-    let question_id = uuid::Uuid::new_v4().to_string();
-    let (tx, rx) = oneshot::channel::<String>();
-
-    // Store pending question so the answer command can find it
+    // Store pending question so every answering surface can find it
     {
         let mut pending = pending_question.lock().await;
         *pending = Some(PendingQuestion {
-            question_id: question_id.clone(),
+            info: info.clone(),
             response_tx: tx,
         });
     }
@@ -266,51 +385,136 @@ pub async fn ask_user_question(
         "meta-agent:question",
         json!({
             "question_id": question_id,
-            "question": question,
-            "options": options,
-            "timestamp": chrono::Utc::now().timestamp_millis()
+            "question": info.question,
+            "options": info.options,
+            "default_answer": info.default_answer,
+            "timeout_secs": timeout_secs,
+            "timestamp": info.asked_at
         }),
     );
 
-    // Wait for response with 5 minute timeout
-    let timeout_duration = std::time::Duration::from_secs(300);
-    match tokio::time::timeout(timeout_duration, rx).await {
-        Ok(Ok(answer)) => {
-            // Clear pending question
-            {
-                let mut pending = pending_question.lock().await;
-                *pending = None;
-            }
-            json!({
-                "success": true,
-                "answer": answer,
-                "question_id": question_id
-            })
-        }
+    let timeout_duration = std::time::Duration::from_secs(timeout_secs);
+    let outcome = tokio::time::timeout(timeout_duration, rx).await;
+
+    // Clear pending question
+    {
+        let mut pending = pending_question.lock().await;
+        *pending = None;
+    }
+
+    let answered = match outcome {
+        Ok(Ok(answer)) => answer,
         Ok(Err(_)) => {
             // Channel closed (question cancelled)
-            {
-                let mut pending = pending_question.lock().await;
-                *pending = None;
-            }
-            json!({
+            return json!({
                 "success": false,
                 "error": "Question was cancelled",
                 "question_id": question_id
-            })
+            });
         }
-        Err(_) => {
-            // Timeout
-            {
-                let mut pending = pending_question.lock().await;
-                *pending = None;
+        Err(_) => match info.default_answer {
+            Some(answer) => QuestionAnswer {
+                answer,
+                source: AnswerSource::Timeout,
+            },
+            None => {
+                return json!({
+                    "success": false,
+                    "error": format!("User did not respond within {} seconds", timeout_secs),
+                    "question_id": question_id,
+                    "timed_out": true,
+                    "answered_via": AnswerSource::Timeout.as_str()
+                });
             }
-            json!({
-                "success": false,
-                "error": "User did not respond within 5 minutes",
-                "question_id": question_id,
-                "timed_out": true
-            })
-        }
+        },
+    };
+
+    // Let every surface dismiss the question, whichever one answered it
+    let _ = app_handle.emit(
+        "meta-agent:question-answered",
+        json!({
+            "question_id": question_id,
+            "answer": answered.answer,
+            "answered_via": answered.source.as_str()
+        }),
+    );
+
+    let mut result = json!({
+        "success": true,
+        "answer": answered.answer,
+        "question_id": question_id,
+        "answered_via": answered.source.as_str()
+    });
+    if answered.source == AnswerSource::Timeout {
+        result["note"] = json!("The user did not respond; the default answer was used.");
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(question_id: &str) -> (PendingQuestion, oneshot::Receiver<QuestionAnswer>) {
+        let (tx, rx) = oneshot::channel();
+        let info = PendingQuestionInfo {
+            question_id: question_id.to_string(),
+            question: "Which database?".to_string(),
+            options: None,
+            default_answer: None,
+            timeout_secs: DEFAULT_QUESTION_TIMEOUT_SECS,
+            asked_at: 0,
+        };
+        (
+            PendingQuestion {
+                info,
+                response_tx: tx,
+            },
+            rx,
+        )
+    }
+
+    #[test]
+    fn test_question_timeout_is_clamped() {
+        assert_eq!(
+            question_timeout_secs(&json!({})),
+            DEFAULT_QUESTION_TIMEOUT_SECS
+        );
+        assert_eq!(
+            question_timeout_secs(&json!({"timeout_seconds": 1})),
+            MIN_QUESTION_TIMEOUT_SECS
+        );
+        assert_eq!(
+            question_timeout_secs(&json!({"timeout_seconds": 999_999})),
+            MAX_QUESTION_TIMEOUT_SECS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_answer_records_source() {
+        let (pq, rx) = pending("q1");
+        let state = Mutex::new(Some(pq));
+
+        answer_pending_question(&state, None, "postgres".to_string(), AnswerSource::Voice)
+            .await
+            .unwrap();
+
+        let answer = rx.await.unwrap();
+        assert_eq!(answer.answer, "postgres");
+        assert_eq!(answer.source, AnswerSource::Voice);
+        assert!(state.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_answer_rejects_wrong_question_id() {
+        let (pq, _rx) = pending("q1");
+        let state = Mutex::new(Some(pq));
+
+        let result =
+            answer_pending_question(&state, Some("q2"), "yes".to_string(), AnswerSource::Rest)
+                .await;
+        assert!(result.is_err());
+        // The pending question is kept for the right answer
+        assert!(state.lock().await.is_some());
     }
 }
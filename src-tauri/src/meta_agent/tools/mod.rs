@@ -4,23 +4,30 @@ pub mod agent_tools;
 pub mod fs_tools;
 pub mod interaction_tools;
 pub mod memory_tools;
+pub mod pipeline_tools;
 pub mod search_tools;
 pub mod todo_tools;
 
 // Re-export interaction tool types for use in MetaAgent
-pub use interaction_tools::{AgentWakeSender, PendingQuestion, SleepState};
+pub use interaction_tools::{
+    answer_pending_question, deliver_answer, AgentWakeSender, AnswerSource, PendingQuestion,
+    PendingQuestionInfo, SleepState,
+};
+pub use pipeline_tools::PipelineToolContext;
 
 // Re-export IterationContext for use in tool_loop_engine
 pub use self::IterationContext as IterCtx;
 
 use serde_json::{json, Value};
 use std::sync::Arc;
-use tauri::AppHandle;
+use tauri::{AppHandle, Runtime};
 use tokio::sync::Mutex;
 
 use crate::agent_manager::AgentManager;
 use crate::ai_client::Message;
+use crate::meta_agent::directory_approval::SharedDirectoryApprovals;
 use crate::meta_agent::memory_worker::MemoryWorker;
+use crate::observer_mode;
 use crate::utils::string::truncate_with_ellipsis;
 
 use super::action_logger::emit_action;
@@ -130,26 +137,41 @@ impl ToolExecutionResult {
 /// The iteration_ctx provides info about remaining iterations so tools can include
 /// this in their results, helping the meta-agent manage its work within limits.
 #[allow(clippy::too_many_arguments)]
-pub async fn execute_tool(
+pub async fn execute_tool<R: Runtime>(
     tool_name: &str,
     input: Value,
     agent_manager: Arc<Mutex<AgentManager>>,
-    app_handle: AppHandle,
+    app_handle: AppHandle<R>,
     sleep_state: Arc<Mutex<SleepState>>,
     pending_question: Arc<Mutex<Option<PendingQuestion>>>,
     agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
     memory_worker: Arc<MemoryWorker>,
+    pipeline_ctx: PipelineToolContext,
+    directory_approvals: SharedDirectoryApprovals,
     _queue_status_fn: impl Fn() -> crate::types::QueueStatus,
     iteration_ctx: IterationContext,
 ) -> ToolExecutionResult {
+    // The tool list is filtered too, but a turn may have started before
+    // observer mode was turned on
+    if !observer_mode::tool_allowed(tool_name) {
+        return ToolExecutionResult::Continue(json!({
+            "success": false,
+            "error": format!("{} is unavailable: {}", tool_name, observer_mode::OBSERVER_MODE_MESSAGE)
+        }));
+    }
+
     let result = match tool_name {
         // =====================================================================
         // Agent Management Tools
         // =====================================================================
         "CreateWorkerAgent" => {
-            let val =
-                agent_tools::create_worker_agent(input.clone(), agent_manager, app_handle.clone())
-                    .await;
+            let val = agent_tools::create_worker_agent(
+                input.clone(),
+                agent_manager,
+                app_handle.clone(),
+                directory_approvals,
+            )
+            .await;
             ToolExecutionResult::Continue(val)
         }
         "SendPromptToWorker" => {
@@ -161,10 +183,35 @@ pub async fn execute_tool(
             .await;
             ToolExecutionResult::Continue(val)
         }
+        "RouteTaskToAgent" => {
+            let val = agent_tools::route_task_to_agent(input.clone(), agent_manager).await;
+            ToolExecutionResult::Continue(val)
+        }
         "StopWorkerAgent" => {
             let val = agent_tools::stop_worker_agent(input.clone(), agent_manager).await;
             ToolExecutionResult::Continue(val)
         }
+        "StopAllWorkerAgents" => {
+            let val = agent_tools::stop_all_worker_agents(
+                input.clone(),
+                agent_manager,
+                app_handle.clone(),
+            )
+            .await;
+            ToolExecutionResult::Continue(val)
+        }
+        "RenameWorkerAgent" => {
+            let val =
+                agent_tools::rename_worker_agent(input.clone(), agent_manager, app_handle.clone())
+                    .await;
+            ToolExecutionResult::Continue(val)
+        }
+        "HandoffWorkerAgent" => {
+            let val =
+                agent_tools::handoff_worker_agent(input.clone(), agent_manager, app_handle.clone())
+                    .await;
+            ToolExecutionResult::Continue(val)
+        }
         "ListWorkerAgents" => {
             let val = agent_tools::list_worker_agents(agent_manager).await;
             ToolExecutionResult::Continue(val)
@@ -182,6 +229,20 @@ pub async fn execute_tool(
             let val = agent_tools::get_agent_todo_list(input.clone(), agent_manager.clone()).await;
             ToolExecutionResult::Continue(val)
         }
+        "AddAgentNote" => {
+            let val =
+                agent_tools::add_agent_note(input.clone(), agent_manager, app_handle.clone()).await;
+            ToolExecutionResult::Continue(val)
+        }
+        "SendMessageToAgent" => {
+            let val = agent_tools::send_message_to_agent(
+                input.clone(),
+                agent_manager,
+                app_handle.clone(),
+            )
+            .await;
+            ToolExecutionResult::Continue(val)
+        }
         "Search" => {
             // Get runs_db from agent_manager
             let manager = agent_manager.lock().await;
@@ -200,6 +261,35 @@ pub async fn execute_tool(
             ToolExecutionResult::Continue(val)
         }
 
+        // =====================================================================
+        // Auto-Pipeline Tools
+        // =====================================================================
+        "CreateAutoPipeline" => {
+            let val = pipeline_tools::create_auto_pipeline(
+                input.clone(),
+                agent_manager,
+                app_handle.clone(),
+                pipeline_ctx,
+                agent_wake_tx,
+            )
+            .await;
+            ToolExecutionResult::Continue(val)
+        }
+        "GetPipelineStatus" => {
+            let val = pipeline_tools::get_pipeline_status(input.clone(), pipeline_ctx).await;
+            ToolExecutionResult::Continue(val)
+        }
+        "CancelPipeline" => {
+            let val = pipeline_tools::cancel_pipeline(
+                input.clone(),
+                agent_manager,
+                app_handle.clone(),
+                pipeline_ctx,
+            )
+            .await;
+            ToolExecutionResult::Continue(val)
+        }
+
         // =====================================================================
         // Filesystem Tools
         // =====================================================================
@@ -0,0 +1,322 @@
+// Auto-pipeline tools for MetaAgent
+//
+// These tools let the commander hand a whole task to an auto-pipeline
+// (plan -> build -> verify) and keep track of it without polling:
+// - CreateAutoPipeline: Create and start a pipeline in the background
+// - GetPipelineStatus: Compact status of one or all pipelines
+// - CancelPipeline: Abort a running pipeline and stop its agents
+//
+// When a pipeline started from here finishes, a compact summary is pushed onto
+// the result queue and a wake event is sent, so a sleeping commander resumes.
+//
+// These tools are only registered for the meta-agent. The pipeline orchestrator
+// has its own tool set and must never be able to start pipelines recursively.
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::Mutex;
+
+use crate::agent_manager::AgentManager;
+use crate::auto_pipeline::{AutoPipeline, AutoPipelineManager};
+use crate::meta_agent::helpers::{error, shorten_id, validate_required};
+use crate::meta_agent::result_queue::SharedResultQueue;
+use crate::subsystems::{self, SubsystemStatuses};
+use crate::types::{AgentResultStatus, AgentWakeEvent, AgentWakeReason, QueuedAgentResult};
+use crate::utils::string::truncate_with_ellipsis;
+use crate::utils::validation::validate_working_dir;
+
+use super::AgentWakeSender;
+
+/// Names of the tools that control auto-pipelines
+pub const PIPELINE_CONTROL_TOOLS: &[&str] =
+    &["CreateAutoPipeline", "GetPipelineStatus", "CancelPipeline"];
+
+/// Shared handles the pipeline tools need from the MetaAgent
+#[derive(Clone)]
+pub struct PipelineToolContext {
+    /// None when no API key is configured for the pipeline orchestrator
+    pub manager: Option<Arc<Mutex<AutoPipelineManager>>>,
+    pub result_queue: SharedResultQueue,
+}
+
+/// Why pipelines can't be used, as recorded when the manager failed to start
+fn unavailable() -> Value {
+    error(
+        SubsystemStatuses::global()
+            .unavailable(subsystems::AUTO_PIPELINE)
+            .unwrap_or_else(|| {
+                "Auto-pipeline unavailable: No API key configured. Set OPENAI_API_KEY or ANTHROPIC_API_KEY in .env".to_string()
+            }),
+    )
+}
+
+// ============================================================================
+// Tools
+// ============================================================================
+
+/// Create an auto-pipeline and (by default) start it in the background
+pub async fn create_auto_pipeline<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+    ctx: PipelineToolContext,
+    agent_wake_tx: Arc<Mutex<Option<AgentWakeSender>>>,
+) -> Value {
+    let user_request = match validate_required(&input, "user_request") {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    let working_dir = match validate_required(&input, "working_dir") {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    let working_dir = match validate_working_dir(&working_dir) {
+        Ok(dir) => dir,
+        Err(e) => return error(e),
+    };
+    let auto_start = input["auto_start"].as_bool().unwrap_or(true);
+    let max_retries = input["max_retries"]
+        .as_u64()
+        .map(|n| n.min(u8::MAX as u64) as u8);
+
+    let Some(manager) = ctx.manager.clone() else {
+        return unavailable();
+    };
+
+    let mgr = manager.lock().await;
+    let pipeline_id = match mgr
+        .create_pipeline(user_request, working_dir.clone(), max_retries)
+        .await
+    {
+        Ok(id) => id,
+        Err(e) => return error(format!("Failed to create pipeline: {}", e)),
+    };
+
+    if !auto_start {
+        return json!({
+            "success": true,
+            "pipeline_id": pipeline_id,
+            "status": "pending",
+            "message": "Pipeline created but not started"
+        });
+    }
+
+    if let Some(pipeline) = mgr.get_pipeline(&pipeline_id).await {
+        let _ = app_handle.emit("auto_pipeline:started", pipeline);
+    }
+
+    let emitter: Arc<dyn crate::events::AppEventEmitter> = Arc::new(app_handle.clone());
+    let handle = match mgr
+        .start_pipeline(&pipeline_id, agent_manager, emitter)
+        .await
+    {
+        Ok(h) => h,
+        Err(e) => return error(format!("Failed to start pipeline: {}", e)),
+    };
+    drop(mgr);
+
+    // Watch for completion and report back through the result queue
+    let watch_id = pipeline_id.clone();
+    let watch_manager = manager.clone();
+    tokio::spawn(async move {
+        let outcome = match handle.await {
+            Ok(result) => result,
+            // Cancelled via CancelPipeline - the caller already knows
+            Err(e) if e.is_cancelled() => return,
+            Err(e) => Err(format!("Pipeline task panicked: {}", e)),
+        };
+
+        let pipeline = watch_manager.lock().await.get_pipeline(&watch_id).await;
+        let summary = pipeline
+            .as_ref()
+            .map(|p| pipeline_summary(p, false))
+            .unwrap_or_else(|| json!({ "pipeline_id": watch_id }));
+
+        let reason = match &outcome {
+            Ok(()) => AgentWakeReason::Completed,
+            Err(e) => AgentWakeReason::Error(e.clone()),
+        };
+        let output = format!(
+            "Auto-pipeline {} {}.\n{}",
+            shorten_id(&watch_id),
+            reason,
+            serde_json::to_string_pretty(&summary).unwrap_or_default()
+        );
+
+        {
+            let mut queue = ctx.result_queue.lock().unwrap();
+            queue.push(QueuedAgentResult {
+                agent_id: watch_id.clone(),
+                working_dir,
+                output,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                status: AgentResultStatus::Pending,
+            });
+            queue.emit_updated(&app_handle);
+        }
+
+        if let Some(tx) = agent_wake_tx.lock().await.as_ref() {
+            let _ = tx
+                .send(AgentWakeEvent {
+                    agent_id: watch_id,
+                    reason,
+                })
+                .await;
+        }
+    });
+
+    json!({
+        "success": true,
+        "pipeline_id": pipeline_id,
+        "status": "running",
+        "message": "Pipeline started. Its result will be queued and will wake you from Sleep when it finishes."
+    })
+}
+
+/// Get a compact status for one pipeline, or all pipelines if no id is given
+pub async fn get_pipeline_status(input: Value, ctx: PipelineToolContext) -> Value {
+    let Some(manager) = ctx.manager else {
+        return unavailable();
+    };
+    let mgr = manager.lock().await;
+
+    if let Some(pipeline_id) = input["pipeline_id"].as_str().filter(|s| !s.is_empty()) {
+        return match mgr.get_pipeline(pipeline_id).await {
+            Some(p) => {
+                let running = mgr.is_running(pipeline_id).await;
+                json!({ "success": true, "pipeline": pipeline_summary(&p, running) })
+            }
+            None => error(format!("Pipeline {} not found", pipeline_id)),
+        };
+    }
+
+    let mut pipelines = mgr.list_pipelines().await;
+    pipelines.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    let mut summaries = Vec::with_capacity(pipelines.len());
+    for p in &pipelines {
+        let running = mgr.is_running(&p.id).await;
+        summaries.push(pipeline_summary(p, running));
+    }
+
+    json!({
+        "success": true,
+        "count": summaries.len(),
+        "pipelines": summaries
+    })
+}
+
+/// Cancel a running pipeline and stop all of its agents
+pub async fn cancel_pipeline<R: Runtime>(
+    input: Value,
+    agent_manager: Arc<Mutex<AgentManager>>,
+    app_handle: AppHandle<R>,
+    ctx: PipelineToolContext,
+) -> Value {
+    let pipeline_id = match validate_required(&input, "pipeline_id") {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    let Some(manager) = ctx.manager else {
+        return unavailable();
+    };
+
+    let emitter: Arc<dyn crate::events::AppEventEmitter> = Arc::new(app_handle);
+    let mgr = manager.lock().await;
+    match mgr
+        .cancel_pipeline(&pipeline_id, &agent_manager, &emitter)
+        .await
+    {
+        Ok(()) => json!({
+            "success": true,
+            "pipeline_id": pipeline_id,
+            "message": "Pipeline cancelled"
+        }),
+        Err(e) => error(e),
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Build a compact summary of a pipeline, leaving out step outputs so it stays
+/// small enough for the commander's context
+fn pipeline_summary(pipeline: &AutoPipeline, running: bool) -> Value {
+    let steps: Vec<Value> = pipeline
+        .steps
+        .iter()
+        .map(|s| {
+            json!({
+                "role": format!("{:?}", s.role),
+                "status": format!("{:?}", s.status),
+                "agent_id": s.agent_id.as_deref().map(shorten_id),
+                "retries": format!("{}/{}", s.retry_count, s.max_retries),
+                "last_retry_error": s
+                    .retry_history
+                    .last()
+                    .map(|r| truncate_with_ellipsis(&r.error, 200)),
+            })
+        })
+        .collect();
+
+    let last_decision = pipeline.iteration_history.last().map(|r| {
+        json!({
+            "iteration": r.iteration,
+            "decision": r.decision,
+            "reasoning": truncate_with_ellipsis(&r.reasoning, 300),
+            "issue_count": r.issues.len(),
+            "verification_input": r.verification_input,
+        })
+    });
+
+    let health = &pipeline.tool_health;
+    json!({
+        "pipeline_id": pipeline.id,
+        "status": pipeline.status,
+        "running": running,
+        "working_dir": pipeline.working_dir,
+        "request": truncate_with_ellipsis(&pipeline.user_request, 200),
+        "iteration": format!("{}/{}", pipeline.current_iteration, pipeline.max_iterations),
+        "steps": steps,
+        "final_decision": pipeline.final_decision,
+        "last_decision": last_decision,
+        "tool_health": {
+            "recent_errors": format!("{}/{}", health.recent_errors, health.recent_calls),
+            "degraded": health.degraded,
+            "last_error": health.last_error,
+        },
+        "created_at": pipeline.created_at,
+        "completed_at": pipeline.completed_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_pipeline::StepOutput;
+
+    #[test]
+    fn test_pipeline_summary_omits_step_outputs() {
+        let mut pipeline = AutoPipeline::new(
+            "pipeline-1234567890".to_string(),
+            "Add a login page".to_string(),
+            "/tmp".to_string(),
+            3,
+        );
+        pipeline.steps[0].agent_id = Some("abcdef0123456789".to_string());
+        pipeline.steps[0].output = Some(StepOutput {
+            raw_text: "VERY LONG PLAN OUTPUT".repeat(100),
+            structured_data: None,
+            agent_outputs: vec![],
+        });
+
+        let summary = pipeline_summary(&pipeline, true);
+        let text = summary.to_string();
+
+        assert!(!text.contains("VERY LONG PLAN OUTPUT"));
+        assert_eq!(summary["running"], json!(true));
+        assert_eq!(summary["steps"][0]["agent_id"], json!("abcdef01"));
+        assert_eq!(summary["steps"].as_array().unwrap().len(), 3);
+    }
+}
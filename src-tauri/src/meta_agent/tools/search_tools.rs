@@ -1,12 +1,14 @@
 // Search tools for MetaAgent
 //
-// Exposes the Search tool that invokes the search agent subagent.
+// Exposes the Search tool that invokes the search agent subagent. Optional
+// filters narrow the scope; results come back grouped by kind.
 
 use serde_json::{json, Value};
 use std::sync::Arc;
 
 use crate::agent_runs_db::AgentRunsDB;
 use crate::meta_agent::search_agent::SearchAgent;
+use crate::meta_agent::search_filters::SearchFilters;
 
 /// Search across run history and memories using natural language
 pub async fn search(input: Value, runs_db: Arc<AgentRunsDB>) -> Value {
@@ -20,6 +22,16 @@ pub async fn search(input: Value, runs_db: Arc<AgentRunsDB>) -> Value {
         }
     };
 
+    let filters = match SearchFilters::from_input(&input) {
+        Ok(f) => f,
+        Err(e) => {
+            return json!({
+                "success": false,
+                "error": e
+            });
+        }
+    };
+
     // Create search agent
     let agent = match SearchAgent::new(runs_db) {
         Some(a) => a,
@@ -32,13 +44,15 @@ pub async fn search(input: Value, runs_db: Arc<AgentRunsDB>) -> Value {
     };
 
     // Execute the search
-    match agent.search(query).await {
+    match agent.search(query, &filters).await {
         Ok(result) => json!({
             "success": result.success,
             "summary": result.summary,
             "runs_found": result.runs_found,
             "memories_searched": result.memories_searched,
-            "hint": "This search queried both run history and persistent memories."
+            "scope": filters.describe(),
+            "results": result.results,
+            "hint": "Results are grouped by kind with a total count each. To see more of one kind, search again with kinds set to it and a narrower date range, source or working_dir, or a higher max_per_kind."
         }),
         Err(e) => json!({
             "success": false,
@@ -1,13 +1,13 @@
 // Todo list tools for MetaAgent
 
 use serde_json::{json, Value};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
 
 use crate::meta_agent::helpers::error;
 use crate::types::{MetaTodoItem, MetaTodoStatus, MetaTodoUpdatedEvent};
 
 /// Update the meta-agent's todo list
-pub async fn update_meta_todo_list(input: Value, app_handle: AppHandle) -> Value {
+pub async fn update_meta_todo_list<R: Runtime>(input: Value, app_handle: AppHandle<R>) -> Value {
     let todos_input = match input["todos"].as_array() {
         Some(arr) => arr,
         None => {
@@ -0,0 +1,403 @@
+// Application metrics
+//
+// A small in-process registry of counters and latency histograms, updated from
+// the modules that own the events (AI client, shared HTTP client, meta-agent
+// tool loop, hook server). The hook server's optional /metrics route renders it together with
+// gauges read at scrape time in the Prometheus text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: [f64; 9] = [0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+#[derive(Debug, Clone, Default)]
+struct LatencyHistogram {
+    /// Count per bucket (not cumulative); the extra slot is +Inf
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum_secs: f64,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        let slot = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[slot] += 1;
+        self.sum_secs += secs;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ProviderStats {
+    requests: u64,
+    errors: u64,
+    latency: LatencyHistogram,
+}
+
+/// Process-wide counters and histograms
+#[derive(Default)]
+pub struct MetricsRegistry {
+    tool_loop_iterations: AtomicU64,
+    hook_events: Mutex<BTreeMap<String, u64>>,
+    ai_requests: Mutex<BTreeMap<String, ProviderStats>>,
+    /// Requests through the shared HTTP client, by host
+    http_requests: Mutex<BTreeMap<String, ProviderStats>>,
+}
+
+/// Values read from the rest of the app when /metrics is scraped
+#[derive(Debug, Clone, Default)]
+pub struct GaugeSnapshot {
+    /// Live agents keyed by (status, source)
+    pub agents: BTreeMap<(String, String), u64>,
+    /// Pipelines keyed by status
+    pub pipelines: BTreeMap<String, u64>,
+    /// Approximate bytes held in memory, keyed by kind
+    /// ("agent_outputs", "conversations")
+    pub memory_bytes: BTreeMap<String, u64>,
+    /// None when the runs database is unavailable
+    pub cost_today_usd: Option<f64>,
+}
+
+static REGISTRY: OnceLock<MetricsRegistry> = OnceLock::new();
+
+/// The global metrics registry
+pub fn global() -> &'static MetricsRegistry {
+    REGISTRY.get_or_init(MetricsRegistry::default)
+}
+
+/// Escape a label value for the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Write the request, error and latency series for stats keyed by one label
+fn write_request_stats(
+    out: &mut String,
+    prefix: &str,
+    label: &str,
+    what: &str,
+    stats: &BTreeMap<String, ProviderStats>,
+) {
+    let name = format!("{}_requests_total", prefix);
+    write_header(out, &name, "counter", &format!("{} requests", what));
+    for (key, stats) in stats {
+        let _ = writeln!(
+            out,
+            "{}{{{}=\"{}\"}} {}",
+            name,
+            label,
+            escape_label(key),
+            stats.requests
+        );
+    }
+
+    let name = format!("{}_request_errors_total", prefix);
+    write_header(
+        out,
+        &name,
+        "counter",
+        &format!("{} requests that failed", what),
+    );
+    for (key, stats) in stats {
+        let _ = writeln!(
+            out,
+            "{}{{{}=\"{}\"}} {}",
+            name,
+            label,
+            escape_label(key),
+            stats.errors
+        );
+    }
+
+    let name = format!("{}_request_duration_seconds", prefix);
+    write_header(
+        out,
+        &name,
+        "histogram",
+        &format!("{} request latency", what),
+    );
+    for (key, stats) in stats {
+        let key = escape_label(key);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&stats.latency.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}",
+                name, label, key, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}",
+            name, label, key, stats.latency.count
+        );
+        let _ = writeln!(
+            out,
+            "{}_sum{{{}=\"{}\"}} {}",
+            name, label, key, stats.latency.sum_secs
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{{{}=\"{}\"}} {}",
+            name, label, key, stats.latency.count
+        );
+    }
+}
+
+impl MetricsRegistry {
+    /// Count one iteration of the meta-agent tool loop
+    pub fn record_tool_loop_iteration(&self) {
+        self.tool_loop_iterations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a hook event received from an agent
+    pub fn record_hook_event(&self, event: &str) {
+        let mut events = self.hook_events.lock().unwrap_or_else(|e| e.into_inner());
+        *events.entry(event.to_string()).or_default() += 1;
+    }
+
+    /// Record a completed AI request
+    pub fn record_ai_request(&self, provider: &str, latency: Duration, success: bool) {
+        let mut requests = self.ai_requests.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = requests.entry(provider.to_string()).or_default();
+        stats.requests += 1;
+        if !success {
+            stats.errors += 1;
+        }
+        stats.latency.observe(latency);
+    }
+
+    /// Record a request sent through the shared HTTP client
+    pub fn record_http_request(&self, host: &str, latency: Duration, success: bool) {
+        let mut requests = self.http_requests.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = requests.entry(host.to_string()).or_default();
+        stats.requests += 1;
+        if !success {
+            stats.errors += 1;
+        }
+        stats.latency.observe(latency);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self, gauges: &GaugeSnapshot) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "commander_agents",
+            "gauge",
+            "Live worker agents by status and source",
+        );
+        for ((status, source), count) in &gauges.agents {
+            let _ = writeln!(
+                out,
+                "commander_agents{{status=\"{}\",source=\"{}\"}} {}",
+                escape_label(status),
+                escape_label(source),
+                count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "commander_pipelines",
+            "gauge",
+            "Auto-pipelines by status",
+        );
+        for (status, count) in &gauges.pipelines {
+            let _ = writeln!(
+                out,
+                "commander_pipelines{{status=\"{}\"}} {}",
+                escape_label(status),
+                count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "commander_memory_bytes",
+            "gauge",
+            "Approximate bytes held by output buffers and conversations",
+        );
+        for (kind, bytes) in &gauges.memory_bytes {
+            let _ = writeln!(
+                out,
+                "commander_memory_bytes{{kind=\"{}\"}} {}",
+                escape_label(kind),
+                bytes
+            );
+        }
+
+        write_header(
+            &mut out,
+            "commander_meta_tool_loop_iterations_total",
+            "counter",
+            "Meta-agent tool loop iterations",
+        );
+        let _ = writeln!(
+            out,
+            "commander_meta_tool_loop_iterations_total {}",
+            self.tool_loop_iterations.load(Ordering::Relaxed)
+        );
+
+        let requests = self
+            .ai_requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        write_request_stats(
+            &mut out,
+            "commander_ai",
+            "provider",
+            "AI provider",
+            &requests,
+        );
+
+        let requests = self
+            .http_requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        write_request_stats(&mut out, "commander_http", "host", "HTTP", &requests);
+
+        let events = self
+            .hook_events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        write_header(
+            &mut out,
+            "commander_hook_events_total",
+            "counter",
+            "Hook events received from agents",
+        );
+        for (event, count) in &events {
+            let _ = writeln!(
+                out,
+                "commander_hook_events_total{{event=\"{}\"}} {}",
+                escape_label(event),
+                count
+            );
+        }
+
+        if let Some(cost) = gauges.cost_today_usd {
+            write_header(
+                &mut out,
+                "commander_cost_today_usd",
+                "gauge",
+                "Agent cost since midnight in USD",
+            );
+            let _ = writeln!(out, "commander_cost_today_usd {}", cost);
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let registry = MetricsRegistry::default();
+        registry.record_ai_request("Claude", Duration::from_millis(200), true);
+        registry.record_ai_request("Claude", Duration::from_secs(3), false);
+        registry.record_ai_request("Claude", Duration::from_secs(500), true);
+
+        let out = registry.render(&GaugeSnapshot::default());
+        assert!(out.contains("commander_ai_requests_total{provider=\"Claude\"} 3"));
+        assert!(out.contains("commander_ai_request_errors_total{provider=\"Claude\"} 1"));
+        assert!(out.contains(
+            "commander_ai_request_duration_seconds_bucket{provider=\"Claude\",le=\"0.25\"} 1"
+        ));
+        assert!(out.contains(
+            "commander_ai_request_duration_seconds_bucket{provider=\"Claude\",le=\"5\"} 2"
+        ));
+        assert!(out.contains(
+            "commander_ai_request_duration_seconds_bucket{provider=\"Claude\",le=\"120\"} 2"
+        ));
+        assert!(out.contains(
+            "commander_ai_request_duration_seconds_bucket{provider=\"Claude\",le=\"+Inf\"} 3"
+        ));
+        assert!(out.contains("commander_ai_request_duration_seconds_count{provider=\"Claude\"} 3"));
+    }
+
+    #[test]
+    fn test_http_requests_are_labelled_by_host() {
+        let registry = MetricsRegistry::default();
+        registry.record_http_request("api.anthropic.com", Duration::from_millis(400), true);
+        registry.record_http_request("api.openai.com", Duration::from_secs(2), false);
+
+        let out = registry.render(&GaugeSnapshot::default());
+        assert!(out.contains("commander_http_requests_total{host=\"api.anthropic.com\"} 1"));
+        assert!(out.contains("commander_http_request_errors_total{host=\"api.openai.com\"} 1"));
+        assert!(out.contains(
+            "commander_http_request_duration_seconds_bucket{host=\"api.anthropic.com\",le=\"0.5\"} 1"
+        ));
+        assert!(out.contains("# TYPE commander_http_request_duration_seconds histogram"));
+    }
+
+    #[test]
+    fn test_render_gauges_and_counters() {
+        let registry = MetricsRegistry::default();
+        registry.record_tool_loop_iteration();
+        registry.record_tool_loop_iteration();
+        registry.record_hook_event("PreToolUse");
+        registry.record_hook_event("Stop");
+        registry.record_hook_event("PreToolUse");
+
+        let mut gauges = GaugeSnapshot {
+            cost_today_usd: Some(1.25),
+            ..Default::default()
+        };
+        gauges
+            .agents
+            .insert(("running".to_string(), "pipeline".to_string()), 2);
+        gauges.pipelines.insert("completed".to_string(), 4);
+        gauges
+            .memory_bytes
+            .insert("agent_outputs".to_string(), 2048);
+
+        let out = registry.render(&gauges);
+        assert!(out.contains("commander_agents{status=\"running\",source=\"pipeline\"} 2"));
+        assert!(!out.contains("commander_pool"));
+        assert!(out.contains("commander_pipelines{status=\"completed\"} 4"));
+        assert!(out.contains("commander_memory_bytes{kind=\"agent_outputs\"} 2048"));
+        assert!(out.contains("commander_meta_tool_loop_iterations_total 2"));
+        assert!(out.contains("commander_hook_events_total{event=\"PreToolUse\"} 2"));
+        assert!(out.contains("commander_cost_today_usd 1.25"));
+        assert!(out.contains("# TYPE commander_ai_request_duration_seconds histogram"));
+        // Every sample line belongs to a declared metric
+        for line in out.lines().filter(|l| !l.starts_with('#')) {
+            let name = line.split(['{', ' ']).next().unwrap();
+            let base = name
+                .trim_end_matches("_bucket")
+                .trim_end_matches("_sum")
+                .trim_end_matches("_count");
+            assert!(out.contains(&format!("# TYPE {} ", base)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,438 @@
+// Notifications - OS notifications for things waiting on the user
+//
+// A pipeline paused for the user's review, a question from the commander or
+// a sudo approval request can sit unnoticed for hours while the window is in
+// the background. The router listens for the events that announce them and
+// shows an OS notification for each, unless its category is muted
+// (NOTIFY_MUTE) or it arrives during quiet hours (NOTIFY_QUIET_HOURS).
+//
+// Every dispatch, shown or not, is emitted as `notification:dispatched` with a
+// deep link (the panel and the pipeline, question or request id) the frontend
+// uses to open the right place, and recorded in the log database (component
+// "notifications") with why it was or wasn't shown.
+
+use chrono::Timelike;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Listener, Runtime};
+
+use crate::commands::env_registry;
+use crate::logger::Logger;
+use crate::utils::time::now_millis;
+
+/// Longest notification body; the rest is in the app
+const MAX_BODY_CHARS: usize = 200;
+
+/// What a notification is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// A pipeline paused for the user's review or answer
+    PipelineReview,
+    /// A question from the commander (AskUserQuestion)
+    Question,
+    /// A sudo command waiting for approval
+    ElevatedCommand,
+}
+
+impl NotificationCategory {
+    pub const ALL: [NotificationCategory; 3] = [
+        NotificationCategory::PipelineReview,
+        NotificationCategory::Question,
+        NotificationCategory::ElevatedCommand,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationCategory::PipelineReview => "pipeline_review",
+            NotificationCategory::Question => "question",
+            NotificationCategory::ElevatedCommand => "elevated_command",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL.into_iter().find(|c| c.as_str() == name)
+    }
+}
+
+/// Where the frontend goes when a notification is acted on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeepLink {
+    /// "pipeline", "question" or "elevated_command"
+    pub panel: String,
+    pub id: String,
+}
+
+/// A notification for something waiting on the user
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub deep_link: DeepLink,
+}
+
+/// Events that announce something waiting on the user
+const WATCHED_EVENTS: &[&str] = &[
+    "auto_pipeline:needs-human",
+    "meta-agent:question",
+    "elevated:request",
+];
+
+fn text(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn shorten(body: &str) -> String {
+    let body = body.trim();
+    if body.chars().count() <= MAX_BODY_CHARS {
+        return body.to_string();
+    }
+    let cut: String = body.chars().take(MAX_BODY_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// The notification for a watched event, if its payload names what is waiting
+pub fn notification_for(event: &str, payload: &Value) -> Option<Notification> {
+    let (category, title, body, panel, id) = match event {
+        "auto_pipeline:needs-human" => (
+            NotificationCategory::PipelineReview,
+            "Pipeline waiting for your review",
+            text(payload, "question"),
+            "pipeline",
+            text(payload, "pipeline_id"),
+        ),
+        "meta-agent:question" => (
+            NotificationCategory::Question,
+            "The commander has a question",
+            text(payload, "question"),
+            "question",
+            text(payload, "question_id"),
+        ),
+        "elevated:request" => {
+            let request = payload.get("request")?;
+            (
+                NotificationCategory::ElevatedCommand,
+                "Sudo command waiting for approval",
+                text(request, "command"),
+                "elevated_command",
+                text(request, "id"),
+            )
+        }
+        _ => return None,
+    };
+    if id.is_empty() {
+        return None;
+    }
+    Some(Notification {
+        category,
+        title: title.to_string(),
+        body: shorten(&body),
+        deep_link: DeepLink {
+            panel: panel.to_string(),
+            id,
+        },
+    })
+}
+
+/// Local time span without notifications, which may wrap past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Minutes after midnight
+    start: u32,
+    end: u32,
+}
+
+fn parse_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+impl QuietHours {
+    /// Parse "HH:MM-HH:MM", e.g. "22:00-07:30"
+    pub fn parse(span: &str) -> Result<Self, String> {
+        let invalid = || format!("Quiet hours must look like 22:00-07:00, got '{}'", span);
+        let (start, end) = span.split_once('-').ok_or_else(invalid)?;
+        let (start, end) = (
+            parse_time(start).ok_or_else(invalid)?,
+            parse_time(end).ok_or_else(invalid)?,
+        );
+        Ok(Self { start, end })
+    }
+
+    /// Whether a time (minutes after midnight) falls inside
+    pub fn contains(&self, minute_of_day: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute_of_day)
+        } else {
+            minute_of_day >= self.start || minute_of_day < self.end
+        }
+    }
+}
+
+/// Muted categories and quiet hours
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotificationSettings {
+    pub muted: Vec<NotificationCategory>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl NotificationSettings {
+    /// Parse NOTIFY_MUTE (comma-separated categories, or "all") and
+    /// NOTIFY_QUIET_HOURS, skipping what can't be understood
+    pub fn parse(mute: &str, quiet_hours: &str) -> Self {
+        let mut muted = Vec::new();
+        for name in mute.split(',').filter(|n| !n.trim().is_empty()) {
+            if name.trim().eq_ignore_ascii_case("all") {
+                muted = NotificationCategory::ALL.to_vec();
+                break;
+            }
+            match NotificationCategory::parse(name) {
+                Some(category) if !muted.contains(&category) => muted.push(category),
+                Some(_) => {}
+                None => eprintln!("[Notifications] Unknown category in NOTIFY_MUTE: {}", name),
+            }
+        }
+
+        let quiet_hours = match quiet_hours.trim() {
+            "" => None,
+            span => QuietHours::parse(span)
+                .map_err(|e| eprintln!("[Notifications] {}", e))
+                .ok(),
+        };
+        Self { muted, quiet_hours }
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(
+            &env_registry::NOTIFY_MUTE.var().unwrap_or_default(),
+            &env_registry::NOTIFY_QUIET_HOURS.var().unwrap_or_default(),
+        )
+    }
+
+    /// Why a notification isn't shown at a local time, if it isn't
+    pub fn suppression(
+        &self,
+        category: NotificationCategory,
+        minute_of_day: u32,
+    ) -> Option<DispatchOutcome> {
+        if self.muted.contains(&category) {
+            Some(DispatchOutcome::Muted)
+        } else if self
+            .quiet_hours
+            .is_some_and(|quiet| quiet.contains(minute_of_day))
+        {
+            Some(DispatchOutcome::QuietHours)
+        } else {
+            None
+        }
+    }
+}
+
+/// What became of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchOutcome {
+    Shown,
+    /// Its category is in NOTIFY_MUTE
+    Muted,
+    /// It arrived during NOTIFY_QUIET_HOURS
+    QuietHours,
+    /// No notifier on this system, or it failed
+    Failed,
+}
+
+/// A dispatch, emitted as `notification:dispatched` and logged
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationDispatch {
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub outcome: DispatchOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub dispatched_at: i64,
+}
+
+/// Show an OS notification, where a notifier is available
+pub fn notify_os(title: &str, body: &str) -> Result<(), String> {
+    let result = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            body.replace('"', "'"),
+            title
+        );
+        std::process::Command::new("osascript")
+            .args(["-e", &script])
+            .spawn()
+    } else if cfg!(target_os = "linux") {
+        std::process::Command::new("notify-send")
+            .args([title, body])
+            .spawn()
+    } else {
+        return Err("No OS notifier on this platform".to_string());
+    };
+    result
+        .map(|_| ())
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+/// Show a notification unless the settings suppress it
+pub fn dispatch(
+    notification: Notification,
+    settings: &NotificationSettings,
+    minute_of_day: u32,
+) -> NotificationDispatch {
+    let (outcome, error) = match settings.suppression(notification.category, minute_of_day) {
+        Some(outcome) => (outcome, None),
+        None => match notify_os(&notification.title, &notification.body) {
+            Ok(()) => (DispatchOutcome::Shown, None),
+            Err(e) => (DispatchOutcome::Failed, Some(e)),
+        },
+    };
+    NotificationDispatch {
+        notification,
+        outcome,
+        error,
+        dispatched_at: now_millis(),
+    }
+}
+
+async fn record(logger: &Logger, dispatch: &NotificationDispatch) {
+    let message = format!(
+        "{} notification for {} {}: {}",
+        dispatch.notification.category.as_str(),
+        dispatch.notification.deep_link.panel,
+        dispatch.notification.deep_link.id,
+        match (&dispatch.outcome, &dispatch.error) {
+            (_, Some(error)) => error.clone(),
+            (DispatchOutcome::Shown, None) => "shown".to_string(),
+            (DispatchOutcome::Muted, None) => "muted by NOTIFY_MUTE".to_string(),
+            (DispatchOutcome::QuietHours, None) => "held back by NOTIFY_QUIET_HOURS".to_string(),
+            (DispatchOutcome::Failed, None) => "failed".to_string(),
+        }
+    );
+    let metadata = serde_json::to_string(dispatch).ok();
+    let _ = if dispatch.outcome == DispatchOutcome::Failed {
+        logger
+            .warning("notifications", &message, None, metadata)
+            .await
+    } else {
+        logger.info("notifications", &message, None, metadata).await
+    };
+}
+
+/// Notify the user about what is waiting on them as it is announced
+pub fn init<R: Runtime>(app: &AppHandle<R>, logger: Arc<Logger>) {
+    for event in WATCHED_EVENTS {
+        let app_handle = app.clone();
+        let logger = logger.clone();
+        app.listen_any(*event, move |emitted| {
+            let payload: Value = serde_json::from_str(emitted.payload()).unwrap_or(Value::Null);
+            let Some(notification) = notification_for(event, &payload) else {
+                return;
+            };
+            let app_handle = app_handle.clone();
+            let logger = logger.clone();
+            tauri::async_runtime::spawn(async move {
+                let now = chrono::Local::now();
+                let dispatch = dispatch(
+                    notification,
+                    &NotificationSettings::from_env(),
+                    now.hour() * 60 + now.minute(),
+                );
+                record(&logger, &dispatch).await;
+                if let Ok(payload) = serde_json::to_value(&dispatch) {
+                    let _ = app_handle.emit("notification:dispatched", payload);
+                }
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_notifications_for_watched_events() {
+        let review = notification_for(
+            "auto_pipeline:needs-human",
+            &json!({ "pipeline_id": "p1", "question": "Approve the plan?" }),
+        )
+        .unwrap();
+        assert_eq!(review.category, NotificationCategory::PipelineReview);
+        assert_eq!(review.body, "Approve the plan?");
+        assert_eq!(
+            review.deep_link,
+            DeepLink {
+                panel: "pipeline".to_string(),
+                id: "p1".to_string()
+            }
+        );
+
+        let sudo = notification_for(
+            "elevated:request",
+            &json!({ "request": { "id": "r1", "command": "sudo apt install jq" } }),
+        )
+        .unwrap();
+        assert_eq!(sudo.category, NotificationCategory::ElevatedCommand);
+        assert_eq!(sudo.deep_link.id, "r1");
+
+        let long = "x".repeat(MAX_BODY_CHARS * 2);
+        let question = notification_for(
+            "meta-agent:question",
+            &json!({ "question_id": "q1", "question": long }),
+        )
+        .unwrap();
+        assert_eq!(question.body.chars().count(), MAX_BODY_CHARS);
+
+        assert!(notification_for("meta-agent:question", &json!({ "question": "?" })).is_none());
+        assert!(notification_for("agent:status", &json!({})).is_none());
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_past_midnight() {
+        let night = QuietHours::parse("22:00-07:30").unwrap();
+        assert!(night.contains(23 * 60));
+        assert!(night.contains(7 * 60 + 29));
+        assert!(!night.contains(7 * 60 + 30));
+        assert!(!night.contains(12 * 60));
+
+        let lunch = QuietHours::parse(" 12:00 - 13:00 ").unwrap();
+        assert!(lunch.contains(12 * 60 + 30));
+        assert!(!lunch.contains(13 * 60));
+
+        assert!(QuietHours::parse("22:00").is_err());
+        assert!(QuietHours::parse("25:00-07:00").is_err());
+    }
+
+    #[test]
+    fn test_mutes_and_quiet_hours_suppress() {
+        let settings = NotificationSettings::parse("question, bogus", "22:00-07:00");
+        assert_eq!(settings.muted, vec![NotificationCategory::Question]);
+        assert_eq!(
+            settings.suppression(NotificationCategory::Question, 12 * 60),
+            Some(DispatchOutcome::Muted)
+        );
+        assert_eq!(
+            settings.suppression(NotificationCategory::PipelineReview, 23 * 60),
+            Some(DispatchOutcome::QuietHours)
+        );
+        assert_eq!(
+            settings.suppression(NotificationCategory::PipelineReview, 12 * 60),
+            None
+        );
+
+        let all = NotificationSettings::parse("all", "not a span");
+        assert_eq!(all.muted, NotificationCategory::ALL.to_vec());
+        assert_eq!(all.quiet_hours, None);
+    }
+}
@@ -0,0 +1,238 @@
+// Observer mode - read-only operation for demos and screen-sharing
+//
+// While observer mode is on, every Tauri command that changes something
+// (spawning, stopping or prompting agents, answering approvals and
+// checkpoints, clearing or deleting history, changing configuration) is
+// rejected with an observer_mode error before it runs, and the meta-agent is
+// only offered its read-only tools. Read commands work as usual. The check
+// sits in the invoke handler, so a new mutating command only has to be added
+// to MUTATING_COMMANDS. Paths that change something without a command (voice
+// intents, answers to the meta-agent's questions) check enabled() themselves.
+// The mode is not persisted: it starts off on every launch. Changes are
+// emitted as observer_mode:changed.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tauri::ipc::Invoke;
+use tauri::Runtime;
+
+use crate::events::AppEventEmitter;
+use crate::utils::time::now_millis;
+
+/// Tauri commands refused while observer mode is on
+pub const MUTATING_COMMANDS: &[&str] = &[
+    // Agents
+    "create_agent",
+    "send_prompt",
+    "cancel_queued_prompt",
+    "stop_agent",
+    "create_agent_group",
+    "stop_agent_group",
+    "stop_all_agents",
+    "send_prompt_to_group",
+    "add_agent_note",
+    "relabel_run",
+    "rename_agent",
+    "set_agent_conventions_injection",
+    "set_agent_auto_follow_up",
+    "set_agent_keep_worktree",
+    "set_agent_output_buffer_size",
+    "set_auto_follow_up_kill_switch",
+    "resume_crashed_run",
+    "resume_agent",
+    "handoff_agent",
+    "clone_agent",
+    // Chat
+    "send_chat_message",
+    "clear_chat_history",
+    "process_agent_results",
+    "set_commander_personality",
+    "reset_commander_personality",
+    "set_base_system_prompt_override",
+    "reset_base_system_prompt_override",
+    "set_stop_phrases",
+    "answer_meta_agent_question",
+    "approve_directory",
+    "new_conversation",
+    "fork_conversation",
+    "delete_conversation",
+    "rename_conversation",
+    "summarize_conversation",
+    "rate_message",
+    "pin_message",
+    "use_mock_ai_provider",
+    // Workspaces
+    "create_workspace",
+    "switch_workspace",
+    // History and logs
+    "clear_cost_history",
+    "cleanup_old_logs",
+    "clear_pipeline_events",
+    "flush_dead_letters",
+    "cleanup_old_runs",
+    "reconcile_stale_runs",
+    "delete_run",
+    "restore_from_trash",
+    "empty_trash",
+    // Instructions and skills
+    "save_instruction_file",
+    "apply_instruction_suggestions",
+    "create_test_agent",
+    "stop_test_agent",
+    "enhance_instruction_from_test",
+    "generate_skill_from_instruction",
+    "delete_generated_skill",
+    // Pipelines
+    "create_auto_pipeline",
+    "start_auto_pipeline",
+    "answer_pipeline_human_request",
+    "force_release_lock",
+    "set_prompt_template",
+    "reset_prompt_template",
+    // Prompt library
+    "save_prompt_template",
+    "delete_prompt_template",
+    // Permission profiles
+    "set_permission_profile",
+    "delete_permission_profile",
+    // Security
+    "set_security_enabled",
+    "set_security_llm_threshold",
+    "approve_security_action",
+    "reject_security_action",
+    "clear_security_reviews",
+    "approve_elevated_command",
+    "deny_elevated_command",
+    // Configuration
+    "create_env_placeholder",
+    "update_config_value",
+    "update_config_batch",
+    "generate_env_template",
+];
+
+/// Meta-agent tools that change something, hidden while observer mode is on
+pub const MUTATING_TOOLS: &[&str] = &[
+    "CreateWorkerAgent",
+    "SendPromptToWorker",
+    "StopWorkerAgent",
+    "StopAllWorkerAgents",
+    "RenameWorkerAgent",
+    "HandoffWorkerAgent",
+    "AddAgentNote",
+    "SendMessageToAgent",
+    "CreateAutoPipeline",
+    "CancelPipeline",
+    "UpdateMemory",
+];
+
+pub const OBSERVER_MODE_MESSAGE: &str = "Observer mode is on; turn it off to make changes";
+
+/// When observer mode was turned on, or 0 while it is off
+static ENABLED_SINCE: AtomicI64 = AtomicI64::new(0);
+
+pub fn enabled() -> bool {
+    ENABLED_SINCE.load(Ordering::Relaxed) != 0
+}
+
+/// Observer mode state for the UI banner
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ObserverModeStatus {
+    pub enabled: bool,
+    /// When it was turned on
+    pub since: Option<i64>,
+}
+
+pub fn status() -> ObserverModeStatus {
+    let since = ENABLED_SINCE.load(Ordering::Relaxed);
+    ObserverModeStatus {
+        enabled: since != 0,
+        since: (since != 0).then_some(since),
+    }
+}
+
+/// Turn observer mode on or off and tell the frontend
+pub fn set_enabled(enabled: bool, app_handle: &dyn AppEventEmitter) -> ObserverModeStatus {
+    if enabled {
+        // Turning it on again keeps the original start
+        let _ =
+            ENABLED_SINCE.compare_exchange(0, now_millis(), Ordering::Relaxed, Ordering::Relaxed);
+    } else {
+        ENABLED_SINCE.store(0, Ordering::Relaxed);
+    }
+
+    let status = status();
+    if let Ok(value) = serde_json::to_value(&status) {
+        let _ = app_handle.emit("observer_mode:changed", value);
+    }
+    status
+}
+
+pub fn command_allowed(command: &str) -> bool {
+    !enabled() || !MUTATING_COMMANDS.contains(&command)
+}
+
+/// Whether the meta-agent may use a tool right now
+pub fn tool_allowed(tool_name: &str) -> bool {
+    !enabled() || !MUTATING_TOOLS.contains(&tool_name)
+}
+
+/// Error a refused command rejects with. It has the same shape as the
+/// tagged ChatError variants, so send_chat_message callers can match on it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ObserverModeError {
+    pub kind: &'static str,
+    pub message: &'static str,
+}
+
+impl Default for ObserverModeError {
+    fn default() -> Self {
+        Self {
+            kind: "observer_mode",
+            message: OBSERVER_MODE_MESSAGE,
+        }
+    }
+}
+
+/// Wrap the generated invoke handler so mutating commands are refused while
+/// observer mode is on
+pub fn guard_commands<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        if !command_allowed(invoke.message.command()) {
+            invoke.resolver.reject(ObserverModeError::default());
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_matches_chat_error_shape() {
+        assert_eq!(
+            serde_json::to_value(ObserverModeError::default()).unwrap(),
+            serde_json::json!({"kind": "observer_mode", "message": OBSERVER_MODE_MESSAGE})
+        );
+    }
+
+    #[test]
+    fn test_mutating_commands_and_tools_exist() {
+        let handlers = include_str!("lib.rs");
+        for name in MUTATING_COMMANDS {
+            assert!(
+                handlers.contains(&format!("commands::{},", name)),
+                "{} is not a registered command",
+                name
+            );
+        }
+
+        let registry = crate::tool_registry::ToolRegistry::new();
+        for name in MUTATING_TOOLS {
+            assert!(registry.get_tool_by_name(name).is_some(), "{}", name);
+        }
+    }
+}
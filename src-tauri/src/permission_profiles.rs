@@ -0,0 +1,343 @@
+// Permission profiles - risk policies bound to where agents come from
+//
+// Agents spawned by a pool, a pipeline or the meta-agent warrant different
+// policies, and per-call spawn options are easy to forget. A permission profile
+// is configured once and bound to agent sources. create_agent_with_pipeline
+// applies the profile bound to the agent's source unless the caller names
+// another one:
+// - permission mode and tool allow/deny lists become Claude CLI flags
+// - the elevated-command policy decides whether sudo requests reach the user
+// - the network expectation seeds the security monitor's session
+// Agents from a source with no profile keep the defaults (bypassPermissions,
+// every tool, sudo requests asked). The applied profile's name is recorded on
+// the run so audits can tell which policy was in force.
+//
+// Tool lists can also be given for a single agent. They are added to its
+// profile's, and a tool disallowed by either is never allowed. The lists the
+// agent ran with are recorded on its run.
+
+use crate::agent_runs_db::{AgentRunsDB, PermissionProfile};
+use crate::types::{AgentSource, AppliedProfile, ToolRestrictions};
+
+/// Tools a read-only agent (e.g. a pipeline's verification agent) may use:
+/// reading and searching, and Bash only for builds, tests and looking at git
+const READ_ONLY_ALLOWED_TOOLS: &[&str] = &[
+    "Read",
+    "Grep",
+    "Glob",
+    "LS",
+    "TodoWrite",
+    "Bash(cargo check:*)",
+    "Bash(cargo build:*)",
+    "Bash(cargo test:*)",
+    "Bash(cargo clippy:*)",
+    "Bash(npm test:*)",
+    "Bash(npm run build:*)",
+    "Bash(npm run test:*)",
+    "Bash(npm run lint:*)",
+    "Bash(npx tsc:*)",
+    "Bash(pytest:*)",
+    "Bash(go build:*)",
+    "Bash(go test:*)",
+    "Bash(git diff:*)",
+    "Bash(git status:*)",
+    "Bash(git log:*)",
+    "Bash(git show:*)",
+];
+
+/// Tools and commands that change files, kept from read-only agents. Under
+/// bypassPermissions the allowed list isn't enforced, so these are what stop
+/// the common ways of editing; a shell redirect still gets through.
+const READ_ONLY_DISALLOWED_TOOLS: &[&str] = &[
+    "Edit",
+    "MultiEdit",
+    "Write",
+    "NotebookEdit",
+    "Bash(rm:*)",
+    "Bash(mv:*)",
+    "Bash(cp:*)",
+    "Bash(sed -i:*)",
+    "Bash(tee:*)",
+    "Bash(git add:*)",
+    "Bash(git commit:*)",
+    "Bash(git push:*)",
+    "Bash(git checkout:*)",
+    "Bash(git switch:*)",
+    "Bash(git reset:*)",
+    "Bash(git restore:*)",
+    "Bash(git stash:*)",
+    "Bash(git clean:*)",
+    "Bash(git apply:*)",
+];
+
+/// Longest profile name accepted
+const MAX_NAME_LEN: usize = 80;
+
+/// Trimmed profile name, or why it can't be used
+pub fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Permission profile name is required".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "Permission profile name is longer than {} characters",
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(name)
+}
+
+/// Trim tool names, dropping blanks and repeats
+fn clean_tools(tools: &[String]) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tool in tools.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !cleaned.iter().any(|t| t == tool) {
+            cleaned.push(tool.to_string());
+        }
+    }
+    cleaned
+}
+
+/// Tool lists for one agent, cleaned like a profile's, with no tool both
+/// allowed and disallowed
+pub fn validate_tool_restrictions(tools: &ToolRestrictions) -> Result<ToolRestrictions, String> {
+    let allowed_tools = clean_tools(&tools.allowed_tools);
+    let disallowed_tools = clean_tools(&tools.disallowed_tools);
+    if let Some(tool) = allowed_tools.iter().find(|t| disallowed_tools.contains(t)) {
+        return Err(format!("Tool '{}' is both allowed and disallowed", tool));
+    }
+    Ok(ToolRestrictions {
+        allowed_tools,
+        disallowed_tools,
+    })
+}
+
+/// Reading, searching, builds, tests and git inspection, with the tools and
+/// common commands that change files disallowed
+pub fn read_only_tools() -> ToolRestrictions {
+    let tools = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    ToolRestrictions {
+        allowed_tools: tools(READ_ONLY_ALLOWED_TOOLS),
+        disallowed_tools: tools(READ_ONLY_DISALLOWED_TOOLS),
+    }
+}
+
+/// The tool lists an agent runs with: its profile's and its own, where
+/// disallowing a tool wins over allowing it
+pub fn effective_tools(
+    profile: Option<&PermissionProfile>,
+    tools: &ToolRestrictions,
+) -> ToolRestrictions {
+    let combined = |from_profile: Option<&Vec<String>>, own: &[String]| {
+        let mut list = from_profile.cloned().unwrap_or_default();
+        list.extend(own.iter().cloned());
+        clean_tools(&list)
+    };
+    let disallowed_tools = combined(
+        profile.map(|p| &p.disallowed_tools),
+        &tools.disallowed_tools,
+    );
+    let allowed_tools = combined(profile.map(|p| &p.allowed_tools), &tools.allowed_tools)
+        .into_iter()
+        .filter(|t| !disallowed_tools.contains(t))
+        .collect();
+    ToolRestrictions {
+        allowed_tools,
+        disallowed_tools,
+    }
+}
+
+/// A profile ready to save: trimmed name and tool lists, sources listed once,
+/// and no tool both allowed and disallowed
+pub fn validate_profile(profile: &PermissionProfile) -> Result<PermissionProfile, String> {
+    let mut sources: Vec<AgentSource> = Vec::new();
+    for source in &profile.sources {
+        if !sources.contains(source) {
+            sources.push(source.clone());
+        }
+    }
+    let allowed_tools = clean_tools(&profile.allowed_tools);
+    let disallowed_tools = clean_tools(&profile.disallowed_tools);
+    if let Some(tool) = allowed_tools.iter().find(|t| disallowed_tools.contains(t)) {
+        return Err(format!("Tool '{}' is both allowed and disallowed", tool));
+    }
+
+    Ok(PermissionProfile {
+        name: validate_name(&profile.name)?.to_string(),
+        sources,
+        allowed_tools,
+        disallowed_tools,
+        ..profile.clone()
+    })
+}
+
+/// The profile for a new agent: the one named by the caller, else the one
+/// bound to its source. None when neither exists or there is no database.
+pub async fn resolve(
+    runs_db: Option<&AgentRunsDB>,
+    source: &AgentSource,
+    requested: Option<&str>,
+) -> Result<Option<PermissionProfile>, String> {
+    let Some(runs_db) = runs_db else {
+        return match requested {
+            Some(name) => Err(format!(
+                "Cannot apply permission profile '{}' without the run database",
+                name
+            )),
+            None => Ok(None),
+        };
+    };
+
+    match requested {
+        Some(name) => {
+            let name = validate_name(name)?;
+            runs_db
+                .get_permission_profile(name)
+                .await
+                .map_err(|e| format!("Failed to load permission profile: {}", e))?
+                .map(Some)
+                .ok_or_else(|| format!("No permission profile named '{}'", name))
+        }
+        None => runs_db
+            .permission_profile_for_source(source)
+            .await
+            .map_err(|e| format!("Failed to load permission profile: {}", e)),
+    }
+}
+
+/// Claude CLI flags for a profile (the default permission mode without one)
+/// and an agent's own tool lists
+pub fn cli_args(profile: Option<&PermissionProfile>, tools: &ToolRestrictions) -> Vec<String> {
+    let mut args = vec![
+        "--permission-mode".to_string(),
+        profile
+            .map(|p| p.permission_mode)
+            .unwrap_or_default()
+            .to_str()
+            .to_string(),
+    ];
+    let tools = effective_tools(profile, tools);
+    if !tools.allowed_tools.is_empty() {
+        args.push("--allowedTools".to_string());
+        args.push(tools.allowed_tools.join(","));
+    }
+    if !tools.disallowed_tools.is_empty() {
+        args.push("--disallowedTools".to_string());
+        args.push(tools.disallowed_tools.join(","));
+    }
+    args
+}
+
+/// What the rest of the app needs to know about an agent's profile
+pub fn applied(profile: &PermissionProfile) -> AppliedProfile {
+    AppliedProfile {
+        name: profile.name.clone(),
+        elevated_commands: profile.elevated_commands,
+        network_expected: profile.network_expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_runs_db::{ElevatedCommandPolicy, PermissionMode};
+
+    fn profile() -> PermissionProfile {
+        PermissionProfile {
+            name: "  pool workers ".to_string(),
+            sources: vec![AgentSource::Pool, AgentSource::Pool, AgentSource::Meta],
+            allowed_tools: vec![" Read".to_string(), "Read".to_string(), "".to_string()],
+            disallowed_tools: vec!["WebFetch".to_string(), "Bash(curl:*)".to_string()],
+            permission_mode: PermissionMode::AcceptEdits,
+            elevated_commands: ElevatedCommandPolicy::Deny,
+            network_expected: false,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_validate_profile_cleans_lists() {
+        let valid = validate_profile(&profile()).unwrap();
+        assert_eq!(valid.name, "pool workers");
+        assert_eq!(valid.sources, vec![AgentSource::Pool, AgentSource::Meta]);
+        assert_eq!(valid.allowed_tools, vec!["Read"]);
+
+        let mut conflicting = profile();
+        conflicting.allowed_tools.push("WebFetch".to_string());
+        assert_eq!(
+            validate_profile(&conflicting).unwrap_err(),
+            "Tool 'WebFetch' is both allowed and disallowed"
+        );
+        conflicting.name = " ".to_string();
+        assert!(validate_profile(&conflicting).is_err());
+    }
+
+    #[test]
+    fn test_cli_args() {
+        let none = ToolRestrictions::default();
+        assert_eq!(
+            cli_args(None, &none),
+            vec!["--permission-mode", "bypassPermissions"]
+        );
+        let profile = validate_profile(&profile()).unwrap();
+        assert_eq!(
+            cli_args(Some(&profile), &none),
+            vec![
+                "--permission-mode",
+                "acceptEdits",
+                "--allowedTools",
+                "Read",
+                "--disallowedTools",
+                "WebFetch,Bash(curl:*)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_agent_tools_add_to_the_profile_and_disallowing_wins() {
+        let profile = validate_profile(&profile()).unwrap();
+        let own = validate_tool_restrictions(&ToolRestrictions {
+            allowed_tools: vec!["Grep".to_string(), " WebFetch".to_string()],
+            disallowed_tools: vec!["Edit".to_string(), "Read".to_string()],
+        })
+        .unwrap();
+
+        let tools = effective_tools(Some(&profile), &own);
+        assert_eq!(tools.allowed_tools, vec!["Grep"]);
+        assert_eq!(
+            tools.disallowed_tools,
+            vec!["WebFetch", "Bash(curl:*)", "Edit", "Read"]
+        );
+
+        assert_eq!(
+            validate_tool_restrictions(&ToolRestrictions {
+                allowed_tools: vec!["Edit".to_string()],
+                disallowed_tools: vec!["Edit".to_string()],
+            })
+            .unwrap_err(),
+            "Tool 'Edit' is both allowed and disallowed"
+        );
+        assert_eq!(effective_tools(None, &read_only_tools()), read_only_tools());
+    }
+
+    #[test]
+    fn test_read_only_tools_scope_bash() {
+        let tools = read_only_tools();
+        assert!(!tools.allowed_tools.iter().any(|t| t == "Bash"));
+        assert!(tools
+            .allowed_tools
+            .iter()
+            .any(|t| t == "Bash(cargo test:*)"));
+        assert!(tools.allowed_tools.iter().any(|t| t == "Bash(git diff:*)"));
+        for blocked in ["Edit", "Write", "Bash(rm:*)", "Bash(git commit:*)"] {
+            assert!(
+                tools.disallowed_tools.iter().any(|t| t == blocked),
+                "{}",
+                blocked
+            );
+        }
+        assert!(validate_tool_restrictions(&tools).is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+// Prompt library - saved worker instructions with {{variable}} placeholders
+//
+// Instructions the user sends again and again (a review checklist, a
+// refactoring recipe) are saved by name in the run database. Rendering fills
+// in the `{{variable}}` placeholders and fails with every missing and unknown
+// variable rather than sending a prompt with holes in it. send_prompt and the
+// CreateWorkerAgent tool can send a template by name; each send counts as a
+// use so templates nobody uses can be found and deleted.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::agent_runs_db::{AgentRunsDB, SavedPromptTemplate};
+
+/// Longest template name accepted
+const MAX_NAME_LEN: usize = 80;
+
+/// A template to send, as given to send_prompt and CreateWorkerAgent
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PromptTemplateRef {
+    pub name: String,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+/// Trimmed template name, or why it can't be used
+pub fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Prompt template name is required".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "Prompt template name is longer than {} characters",
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(name)
+}
+
+fn is_variable_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Find the next `{{name}}` placeholder: its start, end and trimmed name
+fn next_placeholder(text: &str) -> Option<(usize, usize, &str)> {
+    let mut from = 0;
+    while let Some(open) = text[from..].find("{{").map(|i| from + i) {
+        let inner_start = open + 2;
+        if let Some(close) = text[inner_start..].find("}}").map(|i| inner_start + i) {
+            let name = text[inner_start..close].trim();
+            if !name.is_empty() && name.chars().all(is_variable_char) {
+                return Some((open, close + 2, name));
+            }
+        }
+        from = inner_start;
+    }
+    None
+}
+
+/// Variable names (`{{name}}`, letters, digits, `_` and `-`) in order of first use
+pub fn template_variables(content: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = content;
+    while let Some((_, end, name)) = next_placeholder(rest) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &rest[end..];
+    }
+    names
+}
+
+/// Fill every placeholder in a single pass, so placeholder-like text in a
+/// value is never expanded. Every variable must be supplied and no others.
+pub fn render(content: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    let used = template_variables(content);
+    let missing: Vec<&str> = used
+        .iter()
+        .copied()
+        .filter(|name| !variables.contains_key(*name))
+        .collect();
+    let mut unknown: Vec<&str> = variables
+        .keys()
+        .map(String::as_str)
+        .filter(|name| !used.contains(name))
+        .collect();
+    unknown.sort_unstable();
+
+    if !missing.is_empty() || !unknown.is_empty() {
+        let list = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| format!("{{{{{}}}}}", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut problems = Vec::new();
+        if !missing.is_empty() {
+            problems.push(format!("missing variables: {}", list(&missing)));
+        }
+        if !unknown.is_empty() {
+            problems.push(format!("unknown variables: {}", list(&unknown)));
+        }
+        return Err(format!(
+            "Cannot render prompt template: {}",
+            problems.join("; ")
+        ));
+    }
+
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some((start, end, name)) = next_placeholder(rest) {
+        out.push_str(&rest[..start]);
+        out.push_str(&variables[name]);
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Load a saved template and render it
+pub async fn render_saved(
+    runs_db: &AgentRunsDB,
+    template: &PromptTemplateRef,
+) -> Result<(SavedPromptTemplate, String), String> {
+    let name = validate_name(&template.name)?;
+    let saved = runs_db
+        .get_prompt_template(name)
+        .await
+        .map_err(|e| format!("Failed to load prompt template: {}", e))?
+        .ok_or_else(|| format!("No prompt template named '{}'", name))?;
+    let prompt = render(&saved.content, &template.variables)?;
+    Ok((saved, prompt))
+}
+
+/// Count a prompt sent from a template. Failing to count doesn't fail the send.
+pub async fn record_use(runs_db: &AgentRunsDB, template: &SavedPromptTemplate) {
+    if let Err(e) = runs_db.record_prompt_template_use(&template.name).await {
+        eprintln!(
+            "[PromptLibrary] Failed to count use of '{}': {}",
+            template.name, e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_variables_in_order_of_first_use() {
+        let content =
+            "Review {{ path }} for {{focus}}; {{path}} again. {{not a var}} {single} {{}}";
+        assert_eq!(template_variables(content), vec!["path", "focus"]);
+    }
+
+    #[test]
+    fn test_render_fills_once_and_validates() {
+        let content = "Review {{path}} for {{focus}}.";
+        assert_eq!(
+            render(
+                content,
+                &vars(&[("path", "src/{{focus}}"), ("focus", "bugs")])
+            )
+            .unwrap(),
+            "Review src/{{focus}} for bugs."
+        );
+
+        let err = render(content, &vars(&[("path", "src"), ("scope", "x")])).unwrap_err();
+        assert_eq!(
+            err,
+            "Cannot render prompt template: missing variables: {{focus}}; unknown variables: {{scope}}"
+        );
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(validate_name("  review  ").unwrap(), "review");
+        assert!(validate_name("   ").is_err());
+        assert!(validate_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+}
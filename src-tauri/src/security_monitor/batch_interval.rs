@@ -0,0 +1,285 @@
+//! Adaptive interval for the background analysis loop.
+//!
+//! A fixed interval is too slow while an incident is unfolding and needlessly
+//! frequent when nothing is happening. After every cycle the interval is
+//! adjusted from what the cycle flushed:
+//!
+//! - a batch with a High or Critical event halves it, down to the floor
+//! - a batch of routine events moves it back toward the base interval
+//! - a run of quiet cycles (nothing flushed) doubles it, up to the ceiling
+//!
+//! A Critical pattern match doesn't wait for the timer at all: pushing one
+//! wakes the loop, which flushes the collector at once.
+
+use serde::Serialize;
+
+use super::collector::SecurityEvent;
+use super::pattern_matcher::Severity;
+
+/// Default shortest interval, reached during an incident
+pub const DEFAULT_MIN_BATCH_INTERVAL_MS: u64 = 1000;
+
+/// Default longest interval, reached when idle
+pub const DEFAULT_MAX_BATCH_INTERVAL_MS: u64 = 30_000;
+
+/// Default number of quiet cycles in a row before the interval grows
+pub const DEFAULT_QUIET_CYCLES_BEFORE_BACKOFF: u32 = 3;
+
+/// What ended the loop's wait
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlushTrigger {
+    /// The interval elapsed
+    Timer,
+    /// A Critical pattern match was pushed to the collector
+    Critical,
+}
+
+/// Current interval and flush counts of the analysis loop
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchIntervalStats {
+    /// Interval the loop waits before its next cycle
+    pub effective_interval_ms: u64,
+    pub base_interval_ms: u64,
+    pub min_interval_ms: u64,
+    pub max_interval_ms: u64,
+    pub cycles: u64,
+    /// Batches flushed when the interval elapsed
+    pub timer_flushes: u64,
+    /// Batches flushed early for a Critical pattern match
+    pub critical_flushes: u64,
+    /// Times the interval was shortened or lengthened
+    pub shrinks: u64,
+    pub grows: u64,
+    /// Cycles in a row that flushed nothing
+    pub quiet_cycles: u32,
+    pub last_flush_at: Option<i64>,
+    pub last_adjusted_at: Option<i64>,
+}
+
+/// Whether an event is High or Critical, by pattern match or risk score
+pub fn is_high_severity(event: &SecurityEvent) -> bool {
+    event
+        .risk_score
+        .is_some_and(|score| score >= Severity::High.to_score())
+        || event
+            .pattern_matches
+            .iter()
+            .flatten()
+            .any(|m| m.severity >= Severity::High)
+}
+
+/// Interval of the analysis loop, adapted to the events it flushes
+#[derive(Debug)]
+pub struct AdaptiveBatchInterval {
+    base_ms: u64,
+    min_ms: u64,
+    max_ms: u64,
+    quiet_cycles_before_backoff: u32,
+    current_ms: u64,
+    stats: BatchIntervalStats,
+}
+
+impl AdaptiveBatchInterval {
+    /// Start at `base_ms`, kept within `min_ms..=max_ms`
+    pub fn new(base_ms: u64, min_ms: u64, max_ms: u64, quiet_cycles_before_backoff: u32) -> Self {
+        let min_ms = min_ms.max(1);
+        let max_ms = max_ms.max(min_ms);
+        let base_ms = base_ms.clamp(min_ms, max_ms);
+        Self {
+            base_ms,
+            min_ms,
+            max_ms,
+            quiet_cycles_before_backoff: quiet_cycles_before_backoff.max(1),
+            current_ms: base_ms,
+            stats: BatchIntervalStats {
+                effective_interval_ms: base_ms,
+                base_interval_ms: base_ms,
+                min_interval_ms: min_ms,
+                max_interval_ms: max_ms,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// How long the loop waits before its next cycle
+    pub fn current_ms(&self) -> u64 {
+        self.current_ms
+    }
+
+    pub fn stats(&self) -> BatchIntervalStats {
+        self.stats.clone()
+    }
+
+    /// Record a cycle of the loop at `now` with the batch it flushed (if
+    /// any), and return the interval to wait before the next one
+    pub fn record_cycle(
+        &mut self,
+        batch: Option<&[SecurityEvent]>,
+        trigger: FlushTrigger,
+        now: i64,
+    ) -> u64 {
+        self.stats.cycles += 1;
+        let batch = batch.filter(|events| !events.is_empty());
+
+        let next_ms = match batch {
+            Some(events) => {
+                self.stats.last_flush_at = Some(now);
+                match trigger {
+                    FlushTrigger::Timer => self.stats.timer_flushes += 1,
+                    FlushTrigger::Critical => self.stats.critical_flushes += 1,
+                }
+                self.stats.quiet_cycles = 0;
+
+                if events.iter().any(is_high_severity) {
+                    (self.current_ms / 2).max(self.min_ms)
+                } else if self.current_ms < self.base_ms {
+                    (self.current_ms * 2).min(self.base_ms)
+                } else {
+                    self.current_ms
+                }
+            }
+            None => {
+                self.stats.quiet_cycles += 1;
+                if self.stats.quiet_cycles >= self.quiet_cycles_before_backoff {
+                    self.stats.quiet_cycles = 0;
+                    (self.current_ms * 2).min(self.max_ms)
+                } else {
+                    self.current_ms
+                }
+            }
+        };
+
+        if next_ms != self.current_ms {
+            if next_ms < self.current_ms {
+                self.stats.shrinks += 1;
+            } else {
+                self.stats.grows += 1;
+            }
+            self.stats.last_adjusted_at = Some(now);
+            self.current_ms = next_ms;
+            self.stats.effective_interval_ms = next_ms;
+        }
+        self.current_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security_monitor::pattern_matcher::{PatternMatch, ThreatCategory};
+
+    /// Clock the tests advance by hand
+    struct TestClock(i64);
+
+    impl TestClock {
+        fn advance(&mut self, ms: u64) -> i64 {
+            self.0 += ms as i64;
+            self.0
+        }
+    }
+
+    fn event(severity: Option<Severity>) -> SecurityEvent {
+        let mut event =
+            SecurityEvent::new_command_execution("agent-1", None, "cargo test", "/repo", "ui");
+        if let Some(severity) = severity {
+            event.risk_score = Some(severity.to_score());
+            event.pattern_matches = Some(vec![PatternMatch {
+                rule_id: "rule".to_string(),
+                rule_name: "Rule".to_string(),
+                category: ThreatCategory::DangerousCommand,
+                severity,
+                matched_text: "x".to_string(),
+                confidence: 0.9,
+                event_id: Some(event.id.clone()),
+            }]);
+        }
+        event
+    }
+
+    #[test]
+    fn test_high_severity_shrinks_to_the_floor_then_recovers() {
+        let mut clock = TestClock(0);
+        let mut interval = AdaptiveBatchInterval::new(5000, 1000, 30_000, 3);
+        let incident = [event(None), event(Some(Severity::High))];
+
+        let mut wait = interval.current_ms();
+        for expected in [2500, 1250, 1000, 1000] {
+            wait = interval.record_cycle(Some(&incident), FlushTrigger::Timer, clock.advance(wait));
+            assert_eq!(wait, expected);
+        }
+
+        // Routine batches move it back to the base, not past it
+        let routine = [event(None), event(Some(Severity::Low))];
+        for expected in [2000, 4000, 5000, 5000] {
+            wait = interval.record_cycle(Some(&routine), FlushTrigger::Timer, clock.advance(wait));
+            assert_eq!(wait, expected);
+        }
+
+        let stats = interval.stats();
+        assert_eq!(stats.effective_interval_ms, 5000);
+        assert_eq!((stats.shrinks, stats.grows), (3, 3));
+        assert_eq!(stats.timer_flushes, 8);
+        assert_eq!(stats.last_flush_at, Some(clock.0));
+    }
+
+    #[test]
+    fn test_quiet_cycles_grow_to_the_ceiling() {
+        let mut clock = TestClock(0);
+        let mut interval = AdaptiveBatchInterval::new(5000, 1000, 30_000, 3);
+
+        let mut waits = Vec::new();
+        for _ in 0..12 {
+            let wait = interval.current_ms();
+            waits.push(interval.record_cycle(None, FlushTrigger::Timer, clock.advance(wait)));
+        }
+        assert_eq!(
+            waits,
+            vec![
+                5000, 5000, 10_000, 10_000, 10_000, 20_000, 20_000, 20_000, 30_000, 30_000, 30_000,
+                30_000
+            ]
+        );
+
+        // An event resets the quiet streak without shrinking the interval
+        let adjusted_at = interval.stats().last_adjusted_at;
+        interval.record_cycle(
+            Some(&[event(None)]),
+            FlushTrigger::Timer,
+            clock.advance(30_000),
+        );
+        let stats = interval.stats();
+        assert_eq!(stats.quiet_cycles, 0);
+        assert_eq!(stats.effective_interval_ms, 30_000);
+        assert_eq!(stats.last_adjusted_at, adjusted_at);
+        assert_eq!(stats.timer_flushes, 1);
+    }
+
+    #[test]
+    fn test_critical_flush_is_counted_and_shrinks() {
+        let mut clock = TestClock(1_000);
+        let mut interval = AdaptiveBatchInterval::new(5000, 1000, 30_000, 3);
+
+        // Woken 300ms into the wait by a Critical match
+        let now = clock.advance(300);
+        let wait = interval.record_cycle(
+            Some(&[event(Some(Severity::Critical))]),
+            FlushTrigger::Critical,
+            now,
+        );
+        assert_eq!(wait, 2500);
+
+        let stats = interval.stats();
+        assert_eq!((stats.critical_flushes, stats.timer_flushes), (1, 0));
+        assert_eq!(stats.last_flush_at, Some(1_300));
+        assert_eq!(stats.last_adjusted_at, Some(1_300));
+    }
+
+    #[test]
+    fn test_bounds_are_sanitized() {
+        let interval = AdaptiveBatchInterval::new(50_000, 0, 10_000, 0);
+        let stats = interval.stats();
+        assert_eq!(interval.current_ms(), 10_000);
+        assert_eq!((stats.min_interval_ms, stats.max_interval_ms), (1, 10_000));
+    }
+}
@@ -0,0 +1,321 @@
+//! Cheap risk estimate that decides which batches go to LLM analysis.
+//!
+//! Chatty agents fill a batch every few seconds, and sending every batch to
+//! the LLM means near-continuous model calls. Each batch is scored from what
+//! is already known about its events:
+//!
+//! - pattern matches: the highest severity, plus a little per extra match
+//! - expectation anomalies: the highest severity, plus a little per extra one
+//! - novelty: tools, commands and directories not seen before for the agent
+//!
+//! Batches scoring at or above the threshold, or with a Critical pattern match,
+//! are analyzed by the LLM. The rest are skipped (pattern matches in them still
+//! get pattern-only analysis) and counted in `PrefilterStats`.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::collector::{SecurityEvent, SecurityEventType};
+use super::pattern_matcher::Severity;
+
+/// Default score a batch needs to be sent to the LLM
+pub const DEFAULT_LLM_RISK_THRESHOLD: f32 = 0.4;
+
+/// Added per pattern match or anomaly beyond the strongest one
+const EXTRA_SIGNAL_SCORE: f32 = 0.05;
+
+/// Cap on what extra matches and anomalies can add
+const MAX_EXTRA_SIGNAL_SCORE: f32 = 0.2;
+
+/// Added per tool, command or directory new to the agent
+const NOVELTY_SCORE: f32 = 0.1;
+
+/// Cap on what novelty can add
+const MAX_NOVELTY_SCORE: f32 = 0.3;
+
+/// Risk estimate for one batch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchRiskEstimate {
+    /// Combined score (0.0 - 1.0)
+    pub score: f32,
+    pub has_critical_pattern: bool,
+    pub pattern_matches: usize,
+    pub anomalies: usize,
+    /// Tools, commands and directories seen for the first time
+    pub novel_items: Vec<String>,
+}
+
+/// Counts of batches sent to or kept from the LLM
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrefilterStats {
+    pub batches_analyzed: u64,
+    pub batches_skipped: u64,
+    pub events_skipped: u64,
+    /// Skipped batches that had pattern matches (handled pattern-only)
+    pub skipped_with_patterns: u64,
+    /// Skipped batches that had expectation anomalies
+    pub skipped_with_anomalies: u64,
+    /// Highest score among skipped batches
+    pub max_skipped_score: f32,
+    pub last_skipped_at: Option<i64>,
+}
+
+/// What an agent has done before, for novelty
+#[derive(Debug, Default)]
+struct AgentHistory {
+    items: HashSet<String>,
+}
+
+/// Scores batches and keeps the skip statistics
+#[derive(Debug)]
+pub struct BatchPrefilter {
+    threshold: f32,
+    history: HashMap<String, AgentHistory>,
+    stats: PrefilterStats,
+}
+
+impl BatchPrefilter {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold: threshold.clamp(0.0, 1.0),
+            history: HashMap::new(),
+            stats: PrefilterStats::default(),
+        }
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Change the threshold (0.0 sends every batch, 1.0 only Critical ones)
+    pub fn set_threshold(&mut self, threshold: f32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(format!(
+                "LLM risk threshold must be between 0 and 1, got {}",
+                threshold
+            ));
+        }
+        self.threshold = threshold;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> PrefilterStats {
+        self.stats.clone()
+    }
+
+    /// Forget what an agent has done (call when the agent is stopped)
+    pub fn remove_agent(&mut self, agent_id: &str) {
+        self.history.remove(agent_id);
+    }
+
+    /// Score a batch and decide whether it goes to the LLM. The batch's
+    /// tools and directories are remembered for later novelty checks.
+    pub fn should_analyze(&mut self, events: &[SecurityEvent]) -> (bool, BatchRiskEstimate) {
+        let estimate = self.estimate(events);
+        let analyze = estimate.has_critical_pattern || estimate.score >= self.threshold;
+
+        if analyze {
+            self.stats.batches_analyzed += 1;
+        } else {
+            self.stats.batches_skipped += 1;
+            self.stats.events_skipped += events.len() as u64;
+            if estimate.pattern_matches > 0 {
+                self.stats.skipped_with_patterns += 1;
+            }
+            if estimate.anomalies > 0 {
+                self.stats.skipped_with_anomalies += 1;
+            }
+            self.stats.max_skipped_score = self.stats.max_skipped_score.max(estimate.score);
+            self.stats.last_skipped_at = Some(chrono::Utc::now().timestamp_millis());
+        }
+        (analyze, estimate)
+    }
+
+    fn estimate(&mut self, events: &[SecurityEvent]) -> BatchRiskEstimate {
+        let patterns: Vec<&Severity> = events
+            .iter()
+            .filter_map(|e| e.pattern_matches.as_ref())
+            .flatten()
+            .map(|m| &m.severity)
+            .collect();
+        let anomalies: Vec<f32> = events
+            .iter()
+            .filter_map(|e| e.anomaly_info.as_ref())
+            .filter(|a| a.is_anomaly)
+            .map(|a| a.severity.to_score())
+            .collect();
+
+        let strongest = patterns
+            .iter()
+            .map(|s| s.to_score())
+            .chain(anomalies.iter().copied())
+            .fold(0.0_f32, f32::max);
+        let signals = patterns.len() + anomalies.len();
+        let extra =
+            (signals.saturating_sub(1) as f32 * EXTRA_SIGNAL_SCORE).min(MAX_EXTRA_SIGNAL_SCORE);
+
+        let mut novel_items = Vec::new();
+        for event in events {
+            let history = self.history.entry(event.agent_id.clone()).or_default();
+            for item in novelty_items(event) {
+                if history.items.insert(item.clone()) {
+                    novel_items.push(item);
+                }
+            }
+        }
+        let novelty = (novel_items.len() as f32 * NOVELTY_SCORE).min(MAX_NOVELTY_SCORE);
+
+        BatchRiskEstimate {
+            score: (strongest + extra + novelty).min(1.0),
+            has_critical_pattern: patterns.iter().any(|s| **s == Severity::Critical),
+            pattern_matches: patterns.len(),
+            anomalies: anomalies.len(),
+            novel_items,
+        }
+    }
+}
+
+/// Tools, command programs and directories an event touches
+fn novelty_items(event: &SecurityEvent) -> Vec<String> {
+    let mut items = Vec::new();
+    match &event.event_type {
+        SecurityEventType::ToolUseRequest {
+            tool_name,
+            tool_input,
+        } => {
+            items.push(format!("tool:{}", tool_name));
+            if let Some(command) = tool_input.get("command").and_then(|v| v.as_str()) {
+                items.extend(command_item(command));
+            }
+            for key in ["file_path", "path", "notebook_path"] {
+                if let Some(path) = tool_input.get(key).and_then(|v| v.as_str()) {
+                    items.push(dir_item(path));
+                }
+            }
+        }
+        SecurityEventType::FileOperation { path, .. } => items.push(dir_item(path)),
+        SecurityEventType::CommandExecution { command } => items.extend(command_item(command)),
+        SecurityEventType::NetworkRequest { url } => {
+            let host = url
+                .split("://")
+                .nth(1)
+                .unwrap_or(url)
+                .split('/')
+                .next()
+                .unwrap_or_default();
+            items.push(format!("host:{}", host));
+        }
+        _ => {}
+    }
+    items
+}
+
+/// The program a command runs
+fn command_item(command: &str) -> Option<String> {
+    command
+        .split_whitespace()
+        .find(|word| !word.contains('='))
+        .map(|program| format!("cmd:{}", program))
+}
+
+/// The directory a path is in
+fn dir_item(path: &str) -> String {
+    let dir = Path::new(path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    format!("dir:{}", dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security_monitor::pattern_matcher::{PatternMatch, ThreatCategory};
+    use serde_json::json;
+
+    fn read(agent: &str, path: &str) -> SecurityEvent {
+        SecurityEvent::new_tool_request(
+            agent,
+            None,
+            "Read",
+            json!({ "file_path": path }),
+            "/repo",
+            "ui",
+        )
+    }
+
+    fn with_match(mut event: SecurityEvent, severity: Severity) -> SecurityEvent {
+        event.pattern_matches = Some(vec![PatternMatch {
+            rule_id: "rule".to_string(),
+            rule_name: "Rule".to_string(),
+            category: ThreatCategory::DangerousCommand,
+            severity,
+            matched_text: "x".to_string(),
+            confidence: 0.9,
+            event_id: Some(event.id.clone()),
+        }]);
+        event
+    }
+
+    #[test]
+    fn test_routine_batches_are_skipped_once_familiar() {
+        let mut prefilter = BatchPrefilter::new(DEFAULT_LLM_RISK_THRESHOLD);
+        let batch: Vec<SecurityEvent> = (0..20)
+            .map(|i| read("agent-1", &format!("/repo/src/file{}.rs", i)))
+            .collect();
+
+        // First sight of Read and /repo/src is novel but not alarming
+        let (analyze, estimate) = prefilter.should_analyze(&batch);
+        assert!(!analyze);
+        assert_eq!(estimate.novel_items.len(), 2);
+
+        let (analyze, estimate) = prefilter.should_analyze(&batch);
+        assert!(!analyze);
+        assert_eq!(estimate.score, 0.0);
+
+        let stats = prefilter.stats();
+        assert_eq!(stats.batches_skipped, 2);
+        assert_eq!(stats.events_skipped, 40);
+        assert_eq!(stats.batches_analyzed, 0);
+    }
+
+    #[test]
+    fn test_patterns_and_novelty_raise_the_score() {
+        let mut prefilter = BatchPrefilter::new(DEFAULT_LLM_RISK_THRESHOLD);
+        prefilter.should_analyze(&[read("agent-1", "/repo/src/main.rs")]);
+
+        // A Low match alone stays under the threshold
+        let (analyze, _) = prefilter.should_analyze(&[with_match(
+            read("agent-1", "/repo/src/lib.rs"),
+            Severity::Low,
+        )]);
+        assert!(!analyze);
+        assert_eq!(prefilter.stats().skipped_with_patterns, 1);
+
+        // The same match in a new place with a new tool crosses it
+        let curl = SecurityEvent::new_tool_request(
+            "agent-1",
+            None,
+            "Bash",
+            json!({ "command": "curl https://example.com" }),
+            "/repo",
+            "ui",
+        );
+        let (analyze, estimate) = prefilter.should_analyze(&[with_match(curl, Severity::Low)]);
+        assert!(analyze);
+        assert!(estimate.novel_items.contains(&"cmd:curl".to_string()));
+    }
+
+    #[test]
+    fn test_critical_pattern_always_analyzed() {
+        let mut prefilter = BatchPrefilter::new(1.0);
+        let (analyze, estimate) = prefilter.should_analyze(&[with_match(
+            read("agent-1", "/etc/passwd"),
+            Severity::Critical,
+        )]);
+        assert!(analyze);
+        assert!(estimate.has_critical_pattern);
+        assert!(prefilter.set_threshold(1.5).is_err());
+    }
+}
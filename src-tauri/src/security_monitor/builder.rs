@@ -5,13 +5,15 @@
 //! SecurityMonitor struct.
 
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
 use crate::agent_manager::AgentManager;
 use crate::ai_client::AIClient;
 use crate::events::AppEventEmitter;
 use crate::logger::Logger;
 
+use super::batch_interval::AdaptiveBatchInterval;
+use super::batch_prefilter::BatchPrefilter;
 use super::llm_analyzer::LLMAnalyzer;
 use super::pattern_matcher::PatternMatcher;
 use super::response_handler::{ResponseConfig, ResponseHandler, RetryConfig};
@@ -124,6 +126,13 @@ impl SecurityMonitorBuilder {
         let response_handler =
             Self::build_response_handler(agent_manager, logger, app_handle, response_config);
         let session_expectations = Self::build_session_expectations();
+        let prefilter = Arc::new(Mutex::new(BatchPrefilter::new(config.llm_risk_threshold)));
+        let batch_interval = Arc::new(Mutex::new(AdaptiveBatchInterval::new(
+            config.batch_interval_ms,
+            config.min_batch_interval_ms,
+            config.max_batch_interval_ms,
+            config.quiet_cycles_before_backoff,
+        )));
 
         Ok(SecurityMonitor {
             collector,
@@ -133,6 +142,9 @@ impl SecurityMonitorBuilder {
             enabled: Arc::new(Mutex::new(config.enabled)),
             config,
             session_expectations,
+            prefilter,
+            batch_interval,
+            flush_signal: Arc::new(Notify::new()),
         })
     }
 
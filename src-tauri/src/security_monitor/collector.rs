@@ -295,6 +295,11 @@ impl SecurityEventCollector {
         }
     }
 
+    /// Change how long events may wait before a batch is released
+    pub fn set_batch_interval_ms(&mut self, batch_interval_ms: u64) {
+        self.batch_interval_ms = batch_interval_ms;
+    }
+
     /// Get current event count
     pub fn len(&self) -> usize {
         self.events.len()
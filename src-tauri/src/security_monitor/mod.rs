@@ -32,6 +32,8 @@
 //! ```
 
 pub mod anomaly_detection;
+pub mod batch_interval;
+pub mod batch_prefilter;
 pub mod builder;
 pub mod collector;
 mod expectation_generator;
@@ -45,6 +47,8 @@ pub mod rules;
 pub mod session_expectations;
 
 pub use anomaly_detection::ExpectationCheckResult;
+pub use batch_interval::{AdaptiveBatchInterval, BatchIntervalStats, FlushTrigger};
+pub use batch_prefilter::{BatchPrefilter, BatchRiskEstimate, PrefilterStats};
 pub use collector::{
     SecurityEvent, SecurityEventCollector, SecurityEventMetadata, SecurityEventType,
 };
@@ -60,11 +64,12 @@ pub use session_expectations::{InitialExpectations, SessionExpectations};
 pub use builder::SecurityMonitorBuilder;
 
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tokio::time::{interval, Duration};
+use tokio::sync::{Mutex, Notify};
+use tokio::time::{sleep, Duration};
 
 use crate::agent_manager::AgentManager;
 use crate::ai_client::AIClient;
+use crate::commands::env_registry;
 use crate::events::AppEventEmitter;
 use crate::logger::Logger;
 
@@ -73,9 +78,17 @@ use crate::logger::Logger;
 pub struct SecurityConfig {
     pub enabled: bool,
     pub batch_size: usize,
+    /// Starting interval of the analysis loop, which adapts between the
+    /// min and max intervals
     pub batch_interval_ms: u64,
+    pub min_batch_interval_ms: u64,
+    pub max_batch_interval_ms: u64,
+    /// Cycles in a row with no events before the interval grows
+    pub quiet_cycles_before_backoff: u32,
     pub max_events: usize,
     pub monitoring_provider: MonitoringProvider,
+    /// Batch risk estimate needed for LLM analysis (Critical patterns always go)
+    pub llm_risk_threshold: f32,
 }
 
 impl Default for SecurityConfig {
@@ -84,8 +97,12 @@ impl Default for SecurityConfig {
             enabled: true,
             batch_size: 50,
             batch_interval_ms: 5000,
+            min_batch_interval_ms: batch_interval::DEFAULT_MIN_BATCH_INTERVAL_MS,
+            max_batch_interval_ms: batch_interval::DEFAULT_MAX_BATCH_INTERVAL_MS,
+            quiet_cycles_before_backoff: batch_interval::DEFAULT_QUIET_CYCLES_BEFORE_BACKOFF,
             max_events: 10000,
             monitoring_provider: MonitoringProvider::default(),
+            llm_risk_threshold: batch_prefilter::DEFAULT_LLM_RISK_THRESHOLD,
         }
     }
 }
@@ -108,7 +125,8 @@ impl MonitoringProvider {
             MonitoringProvider::Custom { provider, model } => {
                 match provider.to_lowercase().as_str() {
                     "claude" | "anthropic" => {
-                        let api_key = std::env::var("ANTHROPIC_API_KEY")
+                        let api_key = env_registry::ANTHROPIC_API_KEY
+                            .var()
                             .map_err(|_| "ANTHROPIC_API_KEY not set")?;
                         Ok(AIClient::new(crate::ai_client::Provider::Claude {
                             api_key,
@@ -116,7 +134,8 @@ impl MonitoringProvider {
                         }))
                     }
                     "openai" | "gpt" => {
-                        let api_key = std::env::var("OPENAI_API_KEY")
+                        let api_key = env_registry::OPENAI_API_KEY
+                            .var()
                             .map_err(|_| "OPENAI_API_KEY not set")?;
                         Ok(AIClient::new(crate::ai_client::Provider::OpenAI {
                             api_key,
@@ -151,6 +170,12 @@ pub struct SecurityMonitor {
     pub(crate) config: SecurityConfig,
     /// Session-based expectation tracking for prompt-seeded anomaly detection
     pub(crate) session_expectations: Arc<Mutex<SessionExpectations>>,
+    /// Decides which batches are worth an LLM call
+    pub(crate) prefilter: Arc<Mutex<BatchPrefilter>>,
+    /// Interval of the analysis loop
+    pub(crate) batch_interval: Arc<Mutex<AdaptiveBatchInterval>>,
+    /// Wakes the analysis loop early for a Critical pattern match
+    pub(crate) flush_signal: Arc<Notify>,
 }
 
 impl std::fmt::Debug for SecurityMonitor {
@@ -190,6 +215,28 @@ impl SecurityMonitor {
             .build()
     }
 
+    /// Called when an agent is created - opens its expectation session
+    ///
+    /// The session starts with default expectations; the first prompt
+    /// replaces them with ones seeded from the prompt. `network_expected`
+    /// comes from the agent's permission profile and outlasts the seeding.
+    pub async fn on_agent_created(
+        &self,
+        agent_id: &str,
+        working_dir: &str,
+        network_expected: bool,
+    ) {
+        if !*self.enabled.lock().await {
+            return;
+        }
+
+        self.session_expectations.lock().await.open_session(
+            agent_id,
+            working_dir,
+            network_expected,
+        );
+    }
+
     /// Called when user sends a prompt - seeds expectations for anomaly detection
     ///
     /// This analyzes the prompt using the LLM to predict expected tools, paths,
@@ -236,6 +283,9 @@ impl SecurityMonitor {
 
         // Fast path: Pattern matching (synchronous)
         let pattern_matches = self.pattern_matcher.check(&event);
+        let has_critical_match = pattern_matches
+            .iter()
+            .any(|m| m.severity == Severity::Critical);
 
         // Calculate risk score based on pattern matches
         let mut risk_score: f32 = if pattern_matches.is_empty() {
@@ -243,13 +293,7 @@ impl SecurityMonitor {
         } else {
             pattern_matches
                 .iter()
-                .map(|m| match m.severity {
-                    Severity::Critical => 1.0,
-                    Severity::High => 0.8,
-                    Severity::Medium => 0.5,
-                    Severity::Low => 0.3,
-                    Severity::Info => 0.1,
-                })
+                .map(|m| m.severity.to_score())
                 .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
                 .unwrap_or(0.0)
         };
@@ -276,23 +320,30 @@ impl SecurityMonitor {
         event.risk_score = Some(risk_score);
 
         // Add event to collector
-        let mut collector = self.collector.lock().await;
-        collector.push(event);
+        self.collector.lock().await.push(event);
+
+        // Critical matches are analyzed now rather than at the next tick
+        if has_critical_match {
+            self.flush_signal.notify_one();
+        }
     }
 
     /// Remove expectations for an agent (call when agent is stopped)
     pub async fn remove_agent_expectations(&self, agent_id: &str) {
         let mut expectations = self.session_expectations.lock().await;
         expectations.remove_session(agent_id);
+        self.prefilter.lock().await.remove_agent(agent_id);
     }
 
     /// Start the background analysis loop
     pub fn start_background_analysis(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
-            let mut interval = interval(Duration::from_millis(self.config.batch_interval_ms));
-
             loop {
-                interval.tick().await;
+                let wait = Duration::from_millis(self.batch_interval.lock().await.current_ms());
+                let trigger = tokio::select! {
+                    _ = sleep(wait) => FlushTrigger::Timer,
+                    _ = self.flush_signal.notified() => FlushTrigger::Critical,
+                };
 
                 if !*self.enabled.lock().await {
                     continue;
@@ -306,6 +357,17 @@ impl SecurityMonitor {
                     collector.get_batch_if_ready(current_time)
                 };
 
+                // Adapt the interval to what this cycle flushed
+                let next_interval_ms = self.batch_interval.lock().await.record_cycle(
+                    batch.as_deref(),
+                    trigger,
+                    current_time,
+                );
+                self.collector
+                    .lock()
+                    .await
+                    .set_batch_interval_ms(next_interval_ms);
+
                 if let Some(events) = batch {
                     if events.is_empty() {
                         continue;
@@ -318,11 +380,21 @@ impl SecurityMonitor {
                         .flatten()
                         .collect();
 
-                    // Determine if we need LLM analysis
+                    // Only batches the cheap risk estimate flags are worth an LLM call
                     let has_suspicious_patterns = !all_pattern_matches.is_empty();
-                    let large_batch = events.len() >= 10;
-                    let should_analyze_with_llm =
-                        self.llm_analyzer.is_some() && (has_suspicious_patterns || large_batch);
+                    let should_analyze_with_llm = self.llm_analyzer.is_some()
+                        && {
+                            let (analyze, estimate) =
+                                self.prefilter.lock().await.should_analyze(&events);
+                            if !analyze && has_suspicious_patterns {
+                                println!(
+                                "Security monitor: Skipped LLM analysis of {} events (risk estimate {:.2})",
+                                events.len(),
+                                estimate.score
+                            );
+                            }
+                            analyze
+                        };
 
                     if should_analyze_with_llm {
                         // Build context
@@ -482,6 +554,31 @@ impl SecurityMonitor {
         &self.config
     }
 
+    /// Batch risk estimate currently needed for LLM analysis
+    pub async fn llm_risk_threshold(&self) -> f32 {
+        self.prefilter.lock().await.threshold()
+    }
+
+    /// Change the batch risk estimate needed for LLM analysis (0.0 - 1.0)
+    pub async fn set_llm_risk_threshold(&self, threshold: f32) -> Result<(), String> {
+        self.prefilter.lock().await.set_threshold(threshold)
+    }
+
+    /// Counts of batches sent to or kept from LLM analysis
+    pub async fn prefilter_stats(&self) -> PrefilterStats {
+        self.prefilter.lock().await.stats()
+    }
+
+    /// Current interval and flush counts of the analysis loop
+    pub async fn batch_interval_stats(&self) -> BatchIntervalStats {
+        self.batch_interval.lock().await.stats()
+    }
+
+    /// Events waiting for the next batch
+    pub async fn pending_event_count(&self) -> usize {
+        self.collector.lock().await.len()
+    }
+
     /// Get response handler for external access (e.g., handling review responses)
     pub fn get_response_handler(&self) -> &ResponseHandler {
         &self.response_handler
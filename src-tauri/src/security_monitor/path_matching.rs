@@ -2,8 +2,16 @@
 //!
 //! This module provides path normalization, glob pattern matching,
 //! and path scope checking for the session expectations system.
+//!
+//! Comparisons work on normalized strings: separators become `/`, `.` and `..`
+//! are resolved lexically, verbatim (`\\?\`) prefixes are stripped, and
+//! Windows-style paths (drive letters, UNC shares) compare case-insensitively
+//! on every platform so the logic can be tested anywhere.
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::utils::validation::strip_verbatim_prefix;
 
 /// Defines the allowed path scope for an agent session
 #[derive(Clone, Debug)]
@@ -21,27 +29,21 @@ pub enum PathScope {
 impl PathScope {
     /// Check if a path is within this scope
     pub fn contains(&self, path: &str, working_dir: &str) -> bool {
-        let normalized = normalize_path(path);
+        let normalized = resolve_path(path);
 
         match self {
-            PathScope::WorkingDirOnly(wd) => {
-                let wd_normalized = normalize_path(wd);
-                normalized == wd_normalized
-            }
-            PathScope::WorkingDirAndChildren(wd) => {
-                let wd_normalized = normalize_path(wd);
-                normalized.starts_with(&wd_normalized)
-            }
+            PathScope::WorkingDirOnly(wd) => normalized == resolve_path(wd),
+            PathScope::WorkingDirAndChildren(wd) => is_within(&normalized, &resolve_path(wd)),
             PathScope::SpecificPatterns(patterns) => {
+                let wd_normalized = resolve_path(working_dir);
                 // Check if path matches any of the glob patterns
                 for pattern in patterns {
-                    if matches_glob_pattern(&normalized, pattern, working_dir) {
+                    if matches_glob_pattern(&normalized, pattern, &wd_normalized) {
                         return true;
                     }
                 }
                 // Also allow paths within working directory
-                let wd_normalized = normalize_path(working_dir);
-                normalized.starts_with(&wd_normalized)
+                is_within(&normalized, &wd_normalized)
             }
             PathScope::Unrestricted => true,
         }
@@ -75,8 +77,7 @@ impl ForbiddenPathConfig {
     pub fn is_forbidden(&self, path: &str) -> bool {
         let normalized = normalize_path(path);
         for forbidden in &self.paths {
-            let forbidden_normalized = normalize_path(forbidden);
-            if normalized.starts_with(&forbidden_normalized) || normalized == forbidden_normalized {
+            if is_within(&normalized, &normalize_path(forbidden)) {
                 return true;
             }
         }
@@ -95,18 +96,129 @@ pub fn extract_path_from_input(tool_input: &serde_json::Value) -> Option<String>
         .map(|s| s.to_string())
 }
 
+/// Whether a path starts with a drive letter (`C:`)
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'/' || bytes[2] == b'\\')
+}
+
+/// Whether a path uses Windows syntax (drive letter or UNC share)
+fn is_windows_path(path: &str) -> bool {
+    has_drive_prefix(path) || path.starts_with(r"\\")
+}
+
+/// Whether a path (or pattern) is absolute on any platform
+fn is_absolute_path(path: &str) -> bool {
+    path.starts_with('/') || path.starts_with("~/") || is_windows_path(path)
+}
+
 /// Normalize a path for consistent comparison
+///
+/// Pure string logic, no filesystem access. Separators become `/`, `.` and
+/// `..` are resolved, trailing slashes are removed (except on a root), and
+/// Windows paths are lowercased.
 pub fn normalize_path(path: &str) -> String {
-    // Expand ~ to home directory pattern
-    let expanded = if path.starts_with("~/") {
-        // Keep as pattern for matching
-        path.to_string()
+    let path = strip_verbatim_prefix(path.trim());
+    let windows = is_windows_path(&path) || cfg!(windows);
+    let path = if windows {
+        path.replace('\\', "/")
     } else {
-        path.to_string()
+        path
     };
 
-    // Remove trailing slashes for consistency
-    expanded.trim_end_matches('/').to_string()
+    // Split off the root, which `..` can never climb above
+    let (root, rest) = if has_drive_prefix(&path) {
+        (format!("{}/", &path[..2]), &path[2..])
+    } else if let Some(unc) = path.strip_prefix("//").filter(|_| windows) {
+        // UNC: the server and share are part of the root
+        let mut parts = unc.splitn(3, '/');
+        let server = parts.next().unwrap_or_default();
+        let share = parts.next().unwrap_or_default();
+        let rest = &unc[(server.len() + share.len() + 1).min(unc.len())..];
+        if share.is_empty() {
+            (format!("//{}/", server), rest)
+        } else {
+            (format!("//{}/{}/", server, share), rest)
+        }
+    } else if path.starts_with('/') {
+        ("/".to_string(), path.as_str())
+    } else {
+        (String::new(), path.as_str())
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.last().is_some_and(|last| *last != "..") {
+                    components.pop();
+                } else if root.is_empty() {
+                    components.push("..");
+                }
+            }
+            other => components.push(other),
+        }
+    }
+
+    let mut normalized = format!("{}{}", root, components.join("/"));
+    if normalized.len() > 1 && normalized.ends_with('/') && !has_drive_prefix(&normalized) {
+        // Bare UNC share
+        normalized.pop();
+    }
+    if windows {
+        normalized = normalized.to_lowercase();
+    }
+    normalized
+}
+
+/// Canonicalize the deepest existing ancestor of `path`, re-appending the rest
+fn canonicalize_existing(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(resolved) = std::fs::canonicalize(current) {
+            return Some(
+                missing
+                    .iter()
+                    .rev()
+                    .fold(resolved, |acc, part| acc.join(part)),
+            );
+        }
+        missing.push(current.file_name()?);
+        current = current.parent()?;
+    }
+}
+
+/// Normalize a path, resolving symlinks when it is absolute on this platform
+///
+/// Paths that don't exist yet (e.g. a file about to be written) are resolved
+/// through their nearest existing ancestor, so both sides of a comparison go
+/// through the same symlinks.
+pub fn resolve_path(path: &str) -> String {
+    let normalized = normalize_path(path);
+    if !Path::new(&normalized).is_absolute() {
+        return normalized;
+    }
+    match canonicalize_existing(Path::new(&normalized)) {
+        Some(resolved) => normalize_path(&resolved.to_string_lossy()),
+        None => normalized,
+    }
+}
+
+/// Whether normalized `path` is `dir` or inside it, on a component boundary
+pub fn is_within(path: &str, dir: &str) -> bool {
+    if dir.is_empty() || path == dir {
+        return true;
+    }
+    match path.strip_prefix(dir) {
+        // A root (`/`, `c:/`) already ends with the separator
+        Some(rest) => dir.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
 }
 
 /// Match a path against a glob pattern
@@ -119,11 +231,17 @@ pub fn matches_glob_pattern(path: &str, pattern: &str, working_dir: &str) -> boo
     // Simple glob matching
     // For production, consider using the `glob` or `globset` crate
 
-    let pattern_normalized = if pattern.starts_with('/') || pattern.starts_with("~/") {
-        pattern.to_string()
+    let path = normalize_path(path);
+    let path = path.as_str();
+    let pattern_normalized = if is_absolute_path(pattern) {
+        normalize_path(pattern)
     } else {
         // Relative pattern - prepend working directory
-        format!("{}/{}", working_dir.trim_end_matches('/'), pattern)
+        normalize_path(&format!(
+            "{}/{}",
+            normalize_path(working_dir).trim_end_matches('/'),
+            pattern
+        ))
     };
 
     // Handle ** (recursive)
@@ -133,7 +251,7 @@ pub fn matches_glob_pattern(path: &str, pattern: &str, working_dir: &str) -> boo
             let prefix = parts[0].trim_end_matches('/');
             let suffix = parts[1].trim_start_matches('/');
 
-            let path_starts_ok = prefix.is_empty() || path.starts_with(prefix);
+            let path_starts_ok = is_within(path, prefix);
 
             // Handle suffix with wildcards (e.g., "*.rs")
             let path_ends_ok = if suffix.is_empty() {
@@ -233,6 +351,92 @@ mod tests {
         assert_eq!(normalize_path("~/test"), "~/test");
     }
 
+    #[test]
+    fn test_normalize_windows_paths() {
+        let cases = [
+            (r"C:\Repo\src\", "c:/repo/src"),
+            ("c:/repo/src", "c:/repo/src"),
+            (r"C:\Repo\.\src\..\lib", "c:/repo/lib"),
+            (r"C:\", "c:/"),
+            ("C:", "c:/"),
+            (r"C:\..", "c:/"),
+            (r"\\?\C:\Repo", "c:/repo"),
+            (r"\\?\UNC\Server\Share\Repo", "//server/share/repo"),
+            (r"\\Server\Share\Repo\", "//server/share/repo"),
+            (r"\\Server\Share\..\..", "//server/share"),
+            ("/home/user/./project/../other", "/home/user/other"),
+            ("/", "/"),
+            ("../src", "../src"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_path(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_scope_matrix() {
+        // (path, working dir, in scope)
+        let cases = [
+            // Windows: case and separators don't matter
+            (r"C:\Repo\src\main.rs", r"C:\Repo", true),
+            ("c:/repo/src/main.rs", r"C:\Repo\", true),
+            (r"\\?\C:\Repo\src\main.rs", "c:/repo", true),
+            (r"C:\Repo", "c:/repo/", true),
+            (r"C:\Repository\x.rs", r"C:\Repo", false),
+            (r"D:\Repo\x.rs", r"C:\Repo", false),
+            (r"C:\Repo\..\Secrets\key.pem", r"C:\Repo", false),
+            // Drive root as the working dir
+            (r"C:\anything\at\all.txt", r"C:\", true),
+            (r"D:\other.txt", r"C:\", false),
+            // UNC shares
+            (r"\\Server\Share\Repo\a.txt", r"\\server\share\repo", true),
+            (
+                r"\\?\UNC\Server\Share\Repo\a.txt",
+                r"\\Server\Share\Repo",
+                true,
+            ),
+            (r"\\Server\Other\Repo\a.txt", r"\\Server\Share\Repo", false),
+            // Unix paths stay on component boundaries
+            ("/home/user/project/src/lib.rs", "/home/user/project/", true),
+            ("/home/user/project2/lib.rs", "/home/user/project", false),
+            ("/home/user/project/../other", "/home/user/project", false),
+        ];
+        for (path, wd, expected) in cases {
+            let scope = PathScope::WorkingDirAndChildren(wd.to_string());
+            assert_eq!(scope.contains(path, wd), expected, "{} in {}", path, wd);
+        }
+    }
+
+    #[test]
+    fn test_windows_glob_patterns() {
+        let wd = r"C:\Users\Dev\Project";
+        assert!(matches_glob_pattern(
+            r"C:\Users\Dev\Project\README.MD",
+            "*.md",
+            wd
+        ));
+        assert!(matches_glob_pattern(
+            r"c:\users\dev\project\src\deep\mod.rs",
+            r"src\**\*.rs",
+            wd
+        ));
+        assert!(matches_glob_pattern(r"D:\Data\x.csv", r"D:\data\*.csv", wd));
+        assert!(!matches_glob_pattern(
+            r"C:\Users\Dev\Projects\a.rs",
+            "src/**/*.rs",
+            wd
+        ));
+    }
+
+    #[test]
+    fn test_forbidden_paths_respect_boundaries() {
+        let config = ForbiddenPathConfig {
+            paths: vec![r"C:\Windows\System32\config".to_string()],
+        };
+        assert!(config.is_forbidden(r"c:\windows\system32\CONFIG\SAM"));
+        assert!(!config.is_forbidden(r"C:\Windows\System32\configs.txt"));
+    }
+
     #[test]
     fn test_extract_path() {
         let input = serde_json::json!({"file_path": "/tmp/test.txt"});
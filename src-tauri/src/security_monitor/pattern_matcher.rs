@@ -92,6 +92,19 @@ pub enum Severity {
     Critical,
 }
 
+impl Severity {
+    /// Convert severity to a numeric risk score (0.0 - 1.0)
+    pub fn to_score(&self) -> f32 {
+        match self {
+            Severity::Critical => 1.0,
+            Severity::High => 0.8,
+            Severity::Medium => 0.5,
+            Severity::Low => 0.3,
+            Severity::Info => 0.1,
+        }
+    }
+}
+
 /// Pattern specification for a detection rule
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PatternSpec {
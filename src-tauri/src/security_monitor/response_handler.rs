@@ -321,8 +321,14 @@ impl ResponseHandler {
             let agent_id = agent_id_owned.clone();
             let manager = Arc::clone(&agent_manager);
             async move {
-                let mgr = manager.lock().await;
-                mgr.stop_agent(&agent_id).await
+                // Killed outright, and outside the lock so other commands
+                // aren't held up while it is cleaned up
+                let pending = manager
+                    .lock()
+                    .await
+                    .begin_stop(&agent_id, true, None)
+                    .await?;
+                pending.finish().await.map(|_| ())
             }
         })
         .await?;
@@ -21,6 +21,8 @@ use super::path_matching::{
 pub struct SessionExpectations {
     /// Per-session expectations (agent_id -> expectations)
     sessions: HashMap<String, AgentSessionState>,
+    /// Agents whose permission profile expects network access from the start
+    network_expected: HashSet<String>,
     /// Configuration for expectation checking
     config: ExpectationConfig,
 }
@@ -131,6 +133,7 @@ impl SessionExpectations {
     pub fn new() -> Self {
         Self {
             sessions: HashMap::new(),
+            network_expected: HashSet::new(),
             config: ExpectationConfig::default(),
         }
     }
@@ -138,6 +141,7 @@ impl SessionExpectations {
     pub fn with_config(config: ExpectationConfig) -> Self {
         Self {
             sessions: HashMap::new(),
+            network_expected: HashSet::new(),
             config,
         }
     }
@@ -171,7 +175,7 @@ impl SessionExpectations {
             // Initialize from LLM expectations
             allowed_tools: initial.expected_tools.clone(),
             allowed_path_scope: PathScope::SpecificPatterns(initial.expected_path_patterns.clone()),
-            network_allowed: initial.network_likely,
+            network_allowed: initial.network_likely || self.network_expected.contains(agent_id),
             destructive_allowed: initial.destructive_likely,
             initial_expectations: initial,
 
@@ -199,7 +203,7 @@ impl SessionExpectations {
 
             allowed_tools: initial.expected_tools.clone(),
             allowed_path_scope: PathScope::WorkingDirAndChildren(working_dir.to_string()),
-            network_allowed: initial.network_likely,
+            network_allowed: initial.network_likely || self.network_expected.contains(agent_id),
             destructive_allowed: initial.destructive_likely,
             initial_expectations: initial,
 
@@ -213,6 +217,19 @@ impl SessionExpectations {
         self.sessions.insert(agent_id.to_string(), state);
     }
 
+    /// Open a session with default expectations for a new agent, so tool
+    /// calls made before its first prompt are checked too. An existing
+    /// session is left alone. With `network_expected` (from the agent's
+    /// permission profile) network access is never an anomaly.
+    pub fn open_session(&mut self, agent_id: &str, working_dir: &str, network_expected: bool) {
+        if network_expected {
+            self.network_expected.insert(agent_id.to_string());
+        }
+        if !self.sessions.contains_key(agent_id) {
+            self.seed_default(agent_id, working_dir, "");
+        }
+    }
+
     /// Check event against expectations AND update observed behavior
     pub fn check_and_update(
         &mut self,
@@ -367,6 +384,7 @@ impl SessionExpectations {
     /// Remove session when agent is stopped
     pub fn remove_session(&mut self, agent_id: &str) {
         self.sessions.remove(agent_id);
+        self.network_expected.remove(agent_id);
     }
 
     /// Get session state for an agent (for debugging/display)
@@ -428,4 +446,51 @@ mod tests {
         assert!(expectations.is_forbidden_path("~/.ssh/id_rsa"));
         assert!(!expectations.is_forbidden_path("/home/user/project/file.txt"));
     }
+
+    #[test]
+    fn test_open_session_keeps_seeded_expectations() {
+        let mut expectations = SessionExpectations::new();
+        expectations.open_session("agent", "/home/user/project", false);
+        let session = expectations.get_session("agent").unwrap();
+        assert!(session.original_prompt.is_empty());
+        assert!(session.is_path_in_scope("/home/user/project/src/main.rs"));
+
+        expectations.seed_default("agent", "/home/user/project", "Fix the tests");
+        expectations.open_session("agent", "/home/user/other", false);
+        let session = expectations.get_session("agent").unwrap();
+        assert_eq!(session.original_prompt, "Fix the tests");
+        assert_eq!(session.working_dir, "/home/user/project");
+    }
+
+    #[test]
+    fn test_profile_network_expectation_survives_seeding() {
+        let mut expectations = SessionExpectations::new();
+        expectations.open_session("pipeline", "/home/user/project", true);
+        expectations.open_session("pool", "/home/user/project", false);
+        assert!(
+            expectations
+                .get_session("pipeline")
+                .unwrap()
+                .network_allowed
+        );
+
+        expectations.seed_default("pipeline", "/home/user/project", "Update deps");
+        expectations.seed_default("pool", "/home/user/project", "Update deps");
+        assert!(
+            expectations
+                .get_session("pipeline")
+                .unwrap()
+                .network_allowed
+        );
+        assert!(!expectations.get_session("pool").unwrap().network_allowed);
+
+        expectations.remove_session("pipeline");
+        expectations.seed_default("pipeline", "/home/user/project", "Again");
+        assert!(
+            !expectations
+                .get_session("pipeline")
+                .unwrap()
+                .network_allowed
+        );
+    }
 }
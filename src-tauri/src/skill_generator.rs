@@ -1,4 +1,5 @@
 use crate::ai_client::AIClient;
+use crate::generated_files::{self, GeneratedFileKind};
 use crate::utils::generator::{
     extract_json_from_response, extract_text_from_content_blocks, sanitize_name,
 };
@@ -237,6 +238,7 @@ fn create_skill_directory(
 ) -> Result<String, String> {
     let skills_dir = Path::new(working_dir).join(".claude").join("skills");
     let skill_dir = skills_dir.join(&skill_content.skill_name);
+    let existed = skill_dir.exists();
 
     // Create skill directory
     fs::create_dir_all(&skill_dir)
@@ -291,6 +293,14 @@ fn create_skill_directory(
         }
     }
 
+    generated_files::record(
+        working_dir,
+        &skill_dir,
+        GeneratedFileKind::Skill,
+        !existed,
+        None,
+    );
+
     Ok(skill_dir.to_string_lossy().to_string())
 }
 
@@ -0,0 +1,340 @@
+// Stopword - User-defined phrases that halt all agent activity
+//
+// A stop phrase ("commander, full stop") typed in chat or spoken in a voice
+// session is caught before it reaches the meta-agent or any model. It halts
+// everything at once:
+// - the meta-agent's tool loop ends before its next model call (a Sleep or
+//   AskUserQuestion it is blocked on is released first)
+// - running auto-pipelines are cancelled, which stops their agents
+// - every other worker agent is killed, all at once
+// and the user gets a fixed confirmation instead of a model reply. Each firing
+// is recorded in the log database (component "stopword") with what it halted.
+//
+// Matching ignores case, surrounding whitespace and punctuation, so a voice
+// transcript of "Commander, full stop." matches "commander full stop". The
+// phrase list is stored in stop_phrases.json in the app config directory.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+use tokio::sync::Mutex;
+
+use crate::agent_manager::{stopping, AgentManager};
+use crate::auto_pipeline::AutoPipelineManager;
+use crate::events::AppEventEmitter;
+use crate::logger::Logger;
+use crate::meta_agent::tools::{deliver_answer, AnswerSource, PendingQuestion, SleepState};
+use crate::meta_agent::SharedLoopActivity;
+use crate::types::AgentStatus;
+use crate::AppState;
+
+/// Phrases used until the user configures their own
+const DEFAULT_STOP_PHRASES: [&str; 1] = ["commander, full stop"];
+
+const STOP_PHRASES_FILE: &str = "stop_phrases.json";
+
+/// Given to a Sleep or AskUserQuestion the meta-agent is blocked on
+const HALT_WAKE_MESSAGE: &str = "Stopped by the user's stop phrase.";
+
+static STOP_PHRASES: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StopPhrasesFile {
+    phrases: Vec<String>,
+}
+
+/// Where a stop phrase was heard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopSource {
+    Chat,
+    Voice,
+    /// The tray menu's stop entry
+    Tray,
+}
+
+impl StopSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopSource::Chat => "chat",
+            StopSource::Voice => "voice",
+            StopSource::Tray => "tray",
+        }
+    }
+}
+
+/// What a stop phrase halted, emitted as `stopword:fired`
+#[derive(Debug, Clone, Serialize)]
+pub struct HaltReport {
+    pub phrase: String,
+    pub source: StopSource,
+    pub meta_loop_halted: bool,
+    pub pipelines_cancelled: Vec<String>,
+    pub agents_stopped: Vec<String>,
+    pub timestamp: i64,
+}
+
+impl HaltReport {
+    /// Fixed reply shown instead of a model response
+    pub fn confirmation(&self) -> String {
+        format!(
+            "Full stop. Halted {} agent(s) and {} pipeline(s){}. Nothing will run until you give a new instruction.",
+            self.agents_stopped.len(),
+            self.pipelines_cancelled.len(),
+            if self.meta_loop_halted {
+                " and the commander's current task"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// Everything a stop phrase halts, cloned out of AppState so voice callbacks
+/// can hold it
+#[derive(Clone)]
+pub struct HaltTargets {
+    pub agent_manager: Arc<Mutex<AgentManager>>,
+    pub auto_pipeline_manager: Option<Arc<Mutex<AutoPipelineManager>>>,
+    pub meta_sleep_state: Arc<Mutex<SleepState>>,
+    pub pending_meta_question: Arc<Mutex<Option<PendingQuestion>>>,
+    pub meta_loop_activity: SharedLoopActivity,
+    pub logger: Arc<Logger>,
+    pub app_handle: Arc<dyn AppEventEmitter>,
+}
+
+impl HaltTargets {
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            agent_manager: state.agent_manager.clone(),
+            auto_pipeline_manager: state.auto_pipeline_manager.clone(),
+            meta_sleep_state: state.meta_sleep_state.clone(),
+            pending_meta_question: state.pending_meta_question.clone(),
+            meta_loop_activity: state.meta_loop_activity.clone(),
+            logger: state.logger.clone(),
+            app_handle: state.app_handle.clone(),
+        }
+    }
+}
+
+/// Lowercase, drop punctuation and collapse whitespace
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Trim, drop empty and duplicate phrases, keeping the user's wording
+fn clean_phrases(phrases: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for phrase in phrases {
+        let phrase = phrase.trim().to_string();
+        if normalize(&phrase).is_empty()
+            || cleaned.iter().any(|p| normalize(p) == normalize(&phrase))
+        {
+            continue;
+        }
+        cleaned.push(phrase);
+    }
+    cleaned
+}
+
+/// The configured phrase matching the whole message, if any
+fn match_phrase(text: &str, phrases: &[String]) -> Option<String> {
+    let text = normalize(text);
+    if text.is_empty() {
+        return None;
+    }
+    phrases.iter().find(|p| normalize(p) == text).cloned()
+}
+
+fn phrases_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("claude-commander").join(STOP_PHRASES_FILE))
+}
+
+fn phrases() -> &'static RwLock<Vec<String>> {
+    STOP_PHRASES.get_or_init(|| {
+        let saved = phrases_file()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str::<StopPhrasesFile>(&content).ok())
+            .map(|file| clean_phrases(file.phrases));
+        RwLock::new(
+            saved.unwrap_or_else(|| DEFAULT_STOP_PHRASES.iter().map(|p| p.to_string()).collect()),
+        )
+    })
+}
+
+/// The configured stop phrases
+pub fn stop_phrases() -> Vec<String> {
+    phrases().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Replace the stop phrases and save them; an empty list disables the feature
+pub fn set_stop_phrases(new_phrases: Vec<String>) -> Result<Vec<String>, String> {
+    let cleaned = clean_phrases(new_phrases);
+    let path = phrases_file().ok_or_else(|| "Could not find config directory".to_string())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&StopPhrasesFile {
+        phrases: cleaned.clone(),
+    })
+    .map_err(|e| format!("Failed to serialize stop phrases: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to save stop phrases: {}", e))?;
+
+    *phrases().write().unwrap_or_else(|e| e.into_inner()) = cleaned.clone();
+    Ok(cleaned)
+}
+
+/// The stop phrase the message consists of, if any
+pub fn detect(text: &str) -> Option<String> {
+    match_phrase(text, &phrases().read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Halt the meta-agent, all pipelines and all agents, then log and emit what
+/// was halted
+pub async fn halt_all(targets: &HaltTargets, phrase: String, source: StopSource) -> HaltReport {
+    eprintln!(
+        "[Stopword] Stop phrase \"{}\" heard via {} - halting all activity",
+        phrase,
+        source.as_str()
+    );
+
+    // Flag the loop before waking it so it ends instead of calling the model
+    let meta_loop_halted = targets.meta_loop_activity.lock().unwrap().request_halt();
+    if let Some(cancel_tx) = targets.meta_sleep_state.lock().await.cancel_tx.take() {
+        let _ = cancel_tx.send(HALT_WAKE_MESSAGE.to_string());
+    }
+    let answer_source = match source {
+        StopSource::Chat | StopSource::Tray => AnswerSource::Ui,
+        StopSource::Voice => AnswerSource::Voice,
+    };
+    // A halt works in observer mode too
+    let _ = deliver_answer(
+        &targets.pending_meta_question,
+        None,
+        HALT_WAKE_MESSAGE.to_string(),
+        answer_source,
+    )
+    .await;
+
+    let mut pipelines_cancelled = Vec::new();
+    if let Some(ref manager) = targets.auto_pipeline_manager {
+        let manager = manager.lock().await;
+        for pipeline in manager.list_pipelines().await {
+            if !manager.is_running(&pipeline.id).await {
+                continue;
+            }
+            match manager
+                .cancel_pipeline(&pipeline.id, &targets.agent_manager, &targets.app_handle)
+                .await
+            {
+                Ok(()) => pipelines_cancelled.push(pipeline.id),
+                Err(e) => eprintln!(
+                    "[Stopword] Failed to cancel pipeline {}: {}",
+                    pipeline.id, e
+                ),
+            }
+        }
+    }
+
+    // Agents are killed, not drained, and the manager lock is released before
+    // waiting on them
+    let (running, begun) = {
+        let manager = targets.agent_manager.lock().await;
+        let running: Vec<String> = manager
+            .list_agents()
+            .await
+            .into_iter()
+            .filter(|agent| agent.status != AgentStatus::Stopped)
+            .map(|agent| agent.id)
+            .collect();
+        let mut begun = Vec::with_capacity(running.len());
+        for agent_id in &running {
+            begun.push(manager.begin_stop(agent_id, true, None).await);
+        }
+        (running, begun)
+    };
+    let mut agents_stopped = Vec::new();
+    for (agent_id, result) in stopping::finish_stops(&running, begun).await {
+        match result {
+            Ok(_) => agents_stopped.push(agent_id),
+            Err(e) => eprintln!("[Stopword] Failed to stop agent {}: {}", agent_id, e),
+        }
+    }
+
+    let report = HaltReport {
+        phrase,
+        source,
+        meta_loop_halted,
+        pipelines_cancelled,
+        agents_stopped,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let _ = targets
+        .logger
+        .warning(
+            "stopword",
+            &format!(
+                "Stop phrase \"{}\" fired via {}: halted {} agent(s), {} pipeline(s){}",
+                report.phrase,
+                source.as_str(),
+                report.agents_stopped.len(),
+                report.pipelines_cancelled.len(),
+                if report.meta_loop_halted {
+                    " and the meta-agent loop"
+                } else {
+                    ""
+                }
+            ),
+            None,
+            serde_json::to_string(&report).ok(),
+        )
+        .await;
+    let _ = targets.app_handle.emit("stopword:fired", json!(report));
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(phrases: &[&str]) -> Vec<String> {
+        phrases.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_match_ignores_case_whitespace_and_punctuation() {
+        let configured = list(&["Commander, full stop", "abort everything"]);
+
+        assert_eq!(
+            match_phrase("  commander full stop.  ", &configured).as_deref(),
+            Some("Commander, full stop")
+        );
+        assert_eq!(
+            match_phrase("ABORT EVERYTHING!", &configured).as_deref(),
+            Some("abort everything")
+        );
+        // Only whole messages match, so mentioning the phrase doesn't halt
+        assert_eq!(
+            match_phrase("what does commander full stop do?", &configured),
+            None
+        );
+        assert_eq!(match_phrase("", &configured), None);
+        assert_eq!(match_phrase("...", &configured), None);
+    }
+
+    #[test]
+    fn test_clean_phrases_drops_blank_and_duplicates() {
+        let cleaned = clean_phrases(list(&["  Full stop  ", "", "!!", "full stop.", "halt"]));
+        assert_eq!(cleaned, list(&["Full stop", "halt"]));
+    }
+}
@@ -8,6 +8,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::ai_client::AIClient;
+use crate::generated_files::{self, GeneratedFileKind};
 use crate::utils::generator::{
     extract_json_from_response, extract_text_from_content_blocks, sanitize_name,
 };
@@ -237,8 +238,16 @@ fn create_subagent_file(
 
     // Write the file
     let agent_file = agents_dir.join(format!("{}.md", subagent_content.name));
+    let existed = agent_file.exists();
     fs::write(&agent_file, file_content)
         .map_err(|e| format!("Failed to write subagent file: {}", e))?;
+    generated_files::record(
+        working_dir,
+        &agent_file,
+        GeneratedFileKind::Subagent,
+        !existed,
+        None,
+    );
 
     Ok(agent_file.to_string_lossy().to_string())
 }
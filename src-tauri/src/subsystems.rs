@@ -0,0 +1,186 @@
+// Subsystem status - which parts of the app are running degraded or not at all
+//
+// Startup falls back instead of failing when a subsystem can't initialize: the
+// logger and runs database move to a temp directory, the meta-agent and
+// auto-pipelines run without an API key, the security monitor is skipped.
+// Each outcome is recorded here (ok, degraded or failed, with the reason and
+// how to fix it), returned by get_subsystem_status and, when anything isn't
+// ok, emitted as system:degraded. Callers about to use a subsystem check
+// `unavailable` first, so their errors name the actual cause.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::events::AppEventEmitter;
+use crate::utils::time::now_millis;
+
+pub const LOGGER: &str = "logger";
+pub const RUNS_DATABASE: &str = "runs_database";
+pub const META_AGENT: &str = "meta_agent";
+pub const SECURITY_MONITOR: &str = "security_monitor";
+pub const AUTO_PIPELINE: &str = "auto_pipeline";
+pub const HOOK_SERVER: &str = "hook_server";
+
+/// How a subsystem came up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubsystemHealth {
+    Ok,
+    /// Running, with reduced function (e.g. data kept in a temp directory)
+    Degraded,
+    /// Not running
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubsystemStatus {
+    pub name: String,
+    pub health: SubsystemHealth,
+    pub reason: Option<String>,
+    /// What the user can do about it
+    pub remediation: Option<String>,
+    pub updated_at: i64,
+}
+
+/// Latest status of each subsystem, in the order they were first recorded
+#[derive(Debug, Default)]
+pub struct SubsystemStatuses {
+    statuses: Mutex<Vec<SubsystemStatus>>,
+}
+
+static STATUSES: OnceLock<Arc<SubsystemStatuses>> = OnceLock::new();
+
+impl SubsystemStatuses {
+    /// The app's subsystem statuses
+    pub fn global() -> &'static Arc<SubsystemStatuses> {
+        STATUSES.get_or_init(Default::default)
+    }
+
+    fn set(
+        &self,
+        name: &str,
+        health: SubsystemHealth,
+        reason: Option<&str>,
+        remediation: Option<&str>,
+    ) {
+        let status = SubsystemStatus {
+            name: name.to_string(),
+            health,
+            reason: reason.map(str::to_string),
+            remediation: remediation.map(str::to_string),
+            updated_at: now_millis(),
+        };
+        let mut statuses = self.statuses.lock().unwrap_or_else(|e| e.into_inner());
+        match statuses.iter_mut().find(|s| s.name == name) {
+            Some(existing) => *existing = status,
+            None => statuses.push(status),
+        }
+    }
+
+    pub fn set_ok(&self, name: &str) {
+        self.set(name, SubsystemHealth::Ok, None, None);
+    }
+
+    pub fn set_degraded(&self, name: &str, reason: &str, remediation: &str) {
+        self.set(
+            name,
+            SubsystemHealth::Degraded,
+            Some(reason),
+            Some(remediation),
+        );
+    }
+
+    pub fn set_failed(&self, name: &str, reason: &str, remediation: &str) {
+        self.set(
+            name,
+            SubsystemHealth::Failed,
+            Some(reason),
+            Some(remediation),
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<SubsystemStatus> {
+        self.list().into_iter().find(|s| s.name == name)
+    }
+
+    pub fn list(&self) -> Vec<SubsystemStatus> {
+        self.statuses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Subsystems that are degraded or failed
+    pub fn not_ok(&self) -> Vec<SubsystemStatus> {
+        self.list()
+            .into_iter()
+            .filter(|s| s.health != SubsystemHealth::Ok)
+            .collect()
+    }
+
+    /// Why a subsystem can't be used, if it failed, e.g. "meta_agent
+    /// unavailable: no API key configured (set ANTHROPIC_API_KEY ...)"
+    pub fn unavailable(&self, name: &str) -> Option<String> {
+        let status = self.get(name)?;
+        if status.health != SubsystemHealth::Failed {
+            return None;
+        }
+        let mut message = format!(
+            "{} unavailable: {}",
+            name,
+            status.reason.as_deref().unwrap_or("failed to start")
+        );
+        if let Some(remediation) = &status.remediation {
+            message.push_str(&format!(" ({})", remediation));
+        }
+        Some(message)
+    }
+
+    /// Tell the UI about subsystems that aren't ok, if there are any
+    pub fn emit_degraded(&self, app_handle: &dyn AppEventEmitter) {
+        let not_ok = self.not_ok();
+        if not_ok.is_empty() {
+            return;
+        }
+        if let Ok(payload) = serde_json::to_value(&not_ok) {
+            let _ = app_handle.emit("system:degraded", payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statuses_are_replaced_and_explain_failures() {
+        let statuses = SubsystemStatuses::default();
+        statuses.set_ok(LOGGER);
+        statuses.set_failed(
+            META_AGENT,
+            "no API key configured",
+            "set ANTHROPIC_API_KEY or OPENAI_API_KEY in .env and restart",
+        );
+        statuses.set_degraded(
+            RUNS_DATABASE,
+            "using a temp directory",
+            "check the data directory is writable",
+        );
+
+        let names: Vec<_> = statuses.not_ok().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec![META_AGENT, RUNS_DATABASE]);
+        assert_eq!(
+            statuses.unavailable(META_AGENT).as_deref(),
+            Some(
+                "meta_agent unavailable: no API key configured \
+                 (set ANTHROPIC_API_KEY or OPENAI_API_KEY in .env and restart)"
+            )
+        );
+        // Degraded subsystems still work
+        assert!(statuses.unavailable(RUNS_DATABASE).is_none());
+
+        statuses.set_ok(META_AGENT);
+        assert!(statuses.unavailable(META_AGENT).is_none());
+        assert_eq!(statuses.list().len(), 3);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ai_client::Tool;
+use crate::observer_mode;
 use serde_json::json;
 
 pub struct ToolRegistry {
@@ -12,7 +13,7 @@ impl ToolRegistry {
         // Agent Management Tools
         tools.push(Tool {
             name: "CreateWorkerAgent".to_string(),
-            description: "Creates a new Claude Code worker agent in a specified working directory and optionally sends it an initial task. Use this when the user wants to create an agent and have it do something. IMPORTANT: If the user provides a task or instruction for the agent, you MUST include it in the initial_prompt parameter to start the agent working immediately. Without an initial_prompt, the agent will just wait idle for input. IMPORTANT: Before creating an agent, ask the user what working directory they want to use, or suggest using their home directory (e.g., /home/username/agent-workspace).".to_string(),
+            description: "Creates a new Claude Code worker agent in a specified working directory and optionally sends it an initial task. Use this when the user wants to create an agent and have it do something. IMPORTANT: If the user provides a task or instruction for the agent, you MUST include it in the initial_prompt parameter to start the agent working immediately. Without an initial_prompt, the agent will just wait idle for input. IMPORTANT: Before creating an agent, ask the user what working directory they want to use, or suggest using their home directory (e.g., /home/username/agent-workspace). Directories the user has not worked in before need their approval; if they deny it, do not retry the same directory.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -24,6 +25,22 @@ impl ToolRegistry {
                         "type": "string",
                         "description": "The first task/prompt to send to the agent immediately after creation. This should contain the user's instruction for what the agent should do. If the user said 'create an agent and make it write tests', this should be 'Write comprehensive tests for this project'."
                     },
+                    "template": {
+                        "type": "object",
+                        "description": "Send a saved prompt from the user's prompt library as the initial prompt instead of initial_prompt. Use when the user names a saved prompt; Search with kinds ['prompt_templates'] finds them and their variables. Every {{variable}} in the template must be given.",
+                        "properties": {
+                            "name": {
+                                "type": "string",
+                                "description": "Name of the saved prompt"
+                            },
+                            "variables": {
+                                "type": "object",
+                                "additionalProperties": { "type": "string" },
+                                "description": "Value for each {{variable}} in the template"
+                            }
+                        },
+                        "required": ["name"]
+                    },
                     "navigate": {
                         "type": "boolean",
                         "description": "If true, automatically switch the UI to show this agent after creation. Defaults to false."
@@ -32,6 +49,37 @@ impl ToolRegistry {
                         "type": "string",
                         "enum": ["simple", "easy", "complex"],
                         "description": "Task complexity level determining which Claude model to use. 'simple' uses Haiku (fast/cheap), 'easy' uses Sonnet (balanced, default), 'complex' uses Opus (most capable). Only applies when CLAUDE_CODE_MODEL is set to 'auto'."
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Claude model for the agent, overriding complexity and CLAUDE_CODE_MODEL: an alias (haiku, sonnet, opus, opusplan) or a full model name like claude-sonnet-4-5. Omit to use the configured model."
+                    },
+                    "env": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Extra environment variables for the agent's process, e.g. {\"DATABASE_URL\": \"...\", \"API_BASE\": \"...\"} to point it at a staging environment. Only the names are recorded with the run. API keys are dropped while CLAUDE_CODE_API_KEY_MODE is blocked."
+                    },
+                    "mcp_servers": {
+                        "type": "object",
+                        "description": "MCP servers for the agent, in claude's --mcp-config format: {\"mcpServers\": {\"<name>\": {\"command\": \"...\", \"args\": [...], \"env\": {...}}}}, or {\"type\": \"http\", \"url\": \"...\"} for a remote server. Added to the servers every agent gets from WORKER_MCP_CONFIG."
+                    },
+                    "allowed_tools": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Tools the agent may use without asking, e.g. [\"Read\", \"Grep\", \"Bash(git diff:*)\"]. Added to its permission profile's list."
+                    },
+                    "disallowed_tools": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Tools the agent may not use, e.g. [\"Edit\", \"Write\"] for a read-only reviewer. Added to its permission profile's list; a tool disallowed here is never allowed."
+                    },
+                    "use_worktree": {
+                        "type": "boolean",
+                        "description": "If true, the agent works in a git worktree of its own (.grove/worktrees/<agent_id> on branch grove/<agent_id>) instead of the working directory, so several agents can edit the same repository without overwriting each other's changes. The repository must have no uncommitted changes. Defaults to false."
+                    },
+                    "keep_worktree": {
+                        "type": "boolean",
+                        "description": "If true, the agent's worktree is left in place when it stops so its changes can be inspected; otherwise it is removed unless it has uncommitted changes (the branch is kept if it has commits). Defaults to false."
                     }
                 },
                 "required": ["working_dir"]
@@ -57,6 +105,25 @@ impl ToolRegistry {
             }),
         });
 
+        tools.push(Tool {
+            name: "RouteTaskToAgent".to_string(),
+            description: "Picks the existing worker agent best suited to a task, or recommends creating a new one. Agents are scored on working directory, how closely the task matches their recent work (title, label, notes, recent prompts), recent activity and current load (busy agents and agents waiting on their own question score lower). Returns the chosen agent_id with a justification and every agent's score. Call this before SendPromptToWorker when more than one agent is open, then send the prompt to the returned agent (or use CreateWorkerAgent when it recommends create_new).".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "task": {
+                        "type": "string",
+                        "description": "The task you want to hand off, as you would phrase the prompt"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "The directory the task is for, if known. Strongly favors agents working there."
+                    }
+                },
+                "required": ["task"]
+            }),
+        });
+
         tools.push(Tool {
             name: "StopWorkerAgent".to_string(),
             description: "Stops/terminates a running worker agent. The agent process will be killed and cannot be resumed.".to_string(),
@@ -72,6 +139,45 @@ impl ToolRegistry {
             }),
         });
 
+        tools.push(Tool {
+            name: "StopAllWorkerAgents".to_string(),
+            description: "Stops every worker agent at once, or only those from one source and/or working in one directory (e.g. all of a pipeline's agents after it went wrong). Returns the outcome for each agent: stopped, already_stopped or failed with an error.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "source": {
+                        "type": "string",
+                        "enum": ["ui", "meta", "pipeline", "pool", "manual", "testwizard"],
+                        "description": "Only stop agents created this way"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Only stop agents working in this directory or below it"
+                    }
+                },
+                "required": []
+            }),
+        });
+
+        tools.push(Tool {
+            name: "RenameWorkerAgent".to_string(),
+            description: "Gives a worker agent a short title saying what it is for (e.g. \"auth-refactor\", \"test-fixer\"). The title is shown in the agent list, in ListWorkerAgents and in run history.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "agent_id": {
+                        "type": "string",
+                        "description": "The unique ID of the agent to rename"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "The new title, shorter than 80 characters"
+                    }
+                },
+                "required": ["agent_id", "title"]
+            }),
+        });
+
         tools.push(Tool {
             name: "ListWorkerAgents".to_string(),
             description: "Lists all currently running worker agents with their status (Running/Stopped/Processing/WaitingForInput), working directories, and IDs. Use this as your PRIMARY tool to check agent status.".to_string(),
@@ -82,6 +188,21 @@ impl ToolRegistry {
             }),
         });
 
+        tools.push(Tool {
+            name: "HandoffWorkerAgent".to_string(),
+            description: "Replaces a worker agent whose context window is nearly full with a fresh agent. The old agent's task state (original request, todo list, files changed, latest reply) is summarized, the old agent is stopped, and a new agent in the same directory with the same options receives the summary as its first prompt. Use this when a long-running agent starts losing track of its task. Returns the new agent's ID.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "agent_id": {
+                        "type": "string",
+                        "description": "The unique ID of the agent to replace"
+                    }
+                },
+                "required": ["agent_id"]
+            }),
+        });
+
         tools.push(Tool {
             name: "GetAgentOutput".to_string(),
             description: "Retrieves recent output from a worker agent. Useful for checking what an agent has been doing or its current status. Can filter by output type.".to_string(),
@@ -121,21 +242,150 @@ impl ToolRegistry {
             }),
         });
 
+        tools.push(Tool {
+            name: "AddAgentNote".to_string(),
+            description: "Pins a short note to an agent run, e.g. 'exploring approach B' or 'blocked on flaky auth tests'. Notes persist with the run, the latest one is shown in ListWorkerAgents, and all notes are searchable with Search. Use this to keep track of what each agent is for during long sessions.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "agent_id": {
+                        "type": "string",
+                        "description": "The unique ID of the agent to annotate"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "The note text"
+                    }
+                },
+                "required": ["agent_id", "text"]
+            }),
+        });
+
+        tools.push(Tool {
+            name: "SendMessageToAgent".to_string(),
+            description: "Relays a message from one worker agent to another, e.g. a verification agent's clarifying question to the agent that built the code. The message is queued as the target agent's next prompt, marked as coming from the sending agent. Replies are not forwarded automatically: read the target's answer with GetAgentOutput and relay it back if needed. Messages are capped per pipeline (or per agent pair) so agents can't ping-pong forever.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "from_agent_id": {
+                        "type": "string",
+                        "description": "The agent the message comes from"
+                    },
+                    "to_agent_id": {
+                        "type": "string",
+                        "description": "The agent to deliver the message to"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "The message text"
+                    }
+                },
+                "required": ["from_agent_id", "to_agent_id", "message"]
+            }),
+        });
+
         tools.push(Tool {
             name: "Search".to_string(),
-            description: "Search across run history and persistent memories using natural language. A search agent interprets your query and searches both data sources, correlating results. Use for questions like 'what work was done on project X?', 'find crashed runs that can be resumed', 'what do I remember about the user's preferences?'.".to_string(),
+            description: "Search across run history (including agent notes, prompts and everything agents printed), past commander conversations and persistent memories using natural language. A search agent interprets your query and searches the data sources, correlating results. Optional filters restrict the scope; results come back grouped by kind with a total count and a one-line snippet each, so start broad and drill down with a narrower follow-up search. Use for questions like 'what work was done on project X?', 'find crashed runs that can be resumed', 'what do I remember about the user's preferences?', 'which agent touched payments.rs?'.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "query": {
                         "type": "string",
                         "description": "Natural language query describing what to search for. Examples: 'recent work on tauri_server', 'crashed runs from last week', 'user preferences for coding style'."
+                    },
+                    "since": {
+                        "type": "string",
+                        "description": "Only records from this date on (YYYY-MM-DD or RFC 3339). Applies to runs, prompts, notes, agent outputs and conversations."
+                    },
+                    "until": {
+                        "type": "string",
+                        "description": "Only records up to this date, inclusive (YYYY-MM-DD or RFC 3339)"
+                    },
+                    "source": {
+                        "type": "string",
+                        "enum": ["ui", "meta", "pipeline", "pool", "manual", "testwizard"],
+                        "description": "Only runs, prompts and agent outputs from agents created this way"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Only runs, prompts and agent outputs whose working directory starts with this path"
+                    },
+                    "kinds": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["runs", "prompts", "agent_outputs", "memory", "conversations", "prompt_templates", "voice_transcripts"]
+                        },
+                        "description": "Record kinds to search (default: all). Voice transcripts are not persisted yet."
+                    },
+                    "max_per_kind": {
+                        "type": "integer",
+                        "description": "Maximum results listed per kind (default: 5, max: 50). Totals are always reported."
                     }
                 },
                 "required": ["query"]
             }),
         });
 
+        // Auto-Pipeline Tools
+        tools.push(Tool {
+            name: "CreateAutoPipeline".to_string(),
+            description: "Creates an auto-pipeline that plans, builds and verifies a task with its own orchestrator, iterating until verification passes. Prefer this over a single worker for larger, self-contained tasks. The pipeline runs in the background; when it finishes its result is queued and you are woken from Sleep.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "user_request": {
+                        "type": "string",
+                        "description": "The full task description for the pipeline"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Absolute path to the repository/directory the pipeline should work in"
+                    },
+                    "auto_start": {
+                        "type": "boolean",
+                        "description": "Start the pipeline immediately (default: true)"
+                    },
+                    "max_retries": {
+                        "type": "integer",
+                        "description": "How many times a failed step (e.g. a flaky build) is retried automatically before failing (default: 1, max: 5)"
+                    }
+                },
+                "required": ["user_request", "working_dir"]
+            }),
+        });
+
+        tools.push(Tool {
+            name: "GetPipelineStatus".to_string(),
+            description: "Gets a compact status summary of an auto-pipeline (status, iteration, per-step status, latest decision). Omit pipeline_id to list all pipelines. Does not include full step outputs.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pipeline_id": {
+                        "type": "string",
+                        "description": "The pipeline ID returned by CreateAutoPipeline (optional)"
+                    }
+                },
+                "required": []
+            }),
+        });
+
+        tools.push(Tool {
+            name: "CancelPipeline".to_string(),
+            description: "Cancels a running auto-pipeline and stops all of its agents.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pipeline_id": {
+                        "type": "string",
+                        "description": "The ID of the pipeline to cancel"
+                    }
+                },
+                "required": ["pipeline_id"]
+            }),
+        });
+
         // Filesystem Tools
         tools.push(Tool {
             name: "ListDirectory".to_string(),
@@ -245,7 +495,7 @@ impl ToolRegistry {
 
         tools.push(Tool {
             name: "AskUserQuestion".to_string(),
-            description: "Ask the user a question and WAIT for their response. This is BLOCKING - you will pause until the user responds (or 5 minute timeout). Use this only when you genuinely need user input to proceed, such as clarification, confirmation, or choices between options.".to_string(),
+            description: "Ask the user a question and WAIT for their response. This is BLOCKING - you will pause until the user responds (or the timeout, 5 minutes by default). The user may answer from the desktop app, by voice or through the REST API; the result's answered_via field says which (ui/voice/rest/timeout). Use this only when you genuinely need user input to proceed, such as clarification, confirmation, or choices between options.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -257,6 +507,14 @@ impl ToolRegistry {
                         "type": "array",
                         "items": { "type": "string" },
                         "description": "Optional: Provide predefined choices for the user to select from (e.g., ['Yes', 'No', 'Skip'])"
+                    },
+                    "default_answer": {
+                        "type": "string",
+                        "description": "Optional: Answer to proceed with if the user does not respond before the timeout. Only set this when there is a safe default; without it a timeout returns an error."
+                    },
+                    "timeout_seconds": {
+                        "type": "integer",
+                        "description": "Optional: How long to wait for an answer, 10-3600 seconds (default 300)"
                     }
                 },
                 "required": ["question"]
@@ -293,6 +551,15 @@ impl ToolRegistry {
     pub fn get_all_tools(&self) -> &[Tool] {
         &self.tools
     }
+
+    /// Tools the meta-agent may use right now (no mutating tools in observer mode)
+    pub fn get_available_tools(&self) -> Vec<Tool> {
+        self.tools
+            .iter()
+            .filter(|t| observer_mode::tool_allowed(&t.name))
+            .cloned()
+            .collect()
+    }
 }
 
 impl Default for ToolRegistry {
@@ -0,0 +1,323 @@
+// Tray - compact status in the system tray and on the dock/taskbar badge
+//
+// A tray icon whose tooltip and menu show how many agents are running, how
+// many questions and approvals are waiting for the user (meta-agent
+// questions, directory and sudo approvals, paused pipelines) and today's
+// cost. The main window's badge shows the waiting count where the platform
+// has one. The status is recomputed when an event that can change it is
+// emitted rather than on a timer, and a burst of events becomes one refresh.
+//
+// The menu can stop everything (the same halt as a stop phrase) and bring up
+// the pending question. Mobile builds have no tray (the module is desktop
+// only), and on a desktop without tray support (Linux without
+// libappindicator) the icon fails to build and the app runs without it.
+
+use serde::Serialize;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Emitter, Listener, Manager, Runtime};
+
+use crate::stopword::{self, HaltTargets, StopSource};
+use crate::types::{AgentStatus, ElevatedCommandStatus};
+use crate::utils::time::now_millis;
+use crate::AppState;
+
+/// Events after which the status may have changed
+const REFRESH_EVENTS: &[&str] = &[
+    "agent:status",
+    "agent:stats",
+    "agent:input_required",
+    "agent:handoff",
+    "meta-agent:question",
+    "meta-agent:question-answered",
+    "meta-agent:directory-approval",
+    "meta-agent:directory-approval-resolved",
+    "elevated:request",
+    "elevated:status",
+    "auto_pipeline:needs-human",
+    "auto_pipeline:human-answered",
+    "auto_pipeline:completed",
+    "stopword:fired",
+];
+
+/// How long to wait after an event, so a burst of events is one refresh
+const REFRESH_DELAY: Duration = Duration::from_millis(250);
+
+const TRAY_ID: &str = "main";
+const MAIN_WINDOW: &str = "main";
+
+const MENU_OPEN_QUESTION: &str = "tray:open_question";
+const MENU_STOP_ALL: &str = "tray:stop_all";
+const MENU_SHOW: &str = "tray:show";
+const MENU_QUIT: &str = "tray:quit";
+
+/// Recorded as the "phrase" of a halt started from the tray
+const TRAY_STOP_PHRASE: &str = "Stop everything";
+
+/// What the tray shows
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TrayStatus {
+    pub running_agents: usize,
+    /// Questions and approvals waiting for the user
+    pub waiting: usize,
+    pub today_cost_usd: f64,
+}
+
+impl TrayStatus {
+    pub fn agents_line(&self) -> String {
+        match self.running_agents {
+            1 => "1 agent running".to_string(),
+            n => format!("{} agents running", n),
+        }
+    }
+
+    pub fn waiting_line(&self) -> String {
+        match self.waiting {
+            0 => "Nothing waiting for you".to_string(),
+            1 => "1 item waiting for you".to_string(),
+            n => format!("{} items waiting for you", n),
+        }
+    }
+
+    pub fn cost_line(&self) -> String {
+        format!("${:.2} today", self.today_cost_usd)
+    }
+
+    pub fn tooltip(&self) -> String {
+        format!(
+            "Claude Commander\n{}\n{}\n{}",
+            self.agents_line(),
+            self.waiting_line(),
+            self.cost_line()
+        )
+    }
+
+    /// The waiting count, or no badge when nothing is waiting
+    pub fn badge_count(&self) -> Option<i64> {
+        (self.waiting > 0).then_some(self.waiting as i64)
+    }
+}
+
+async fn collect_status(state: &AppState) -> TrayStatus {
+    let running_agents = {
+        let manager = state.agent_manager.lock().await;
+        manager
+            .list_agents()
+            .await
+            .iter()
+            .filter(|agent| !matches!(agent.status, AgentStatus::Stopped | AgentStatus::Error))
+            .count()
+    };
+
+    let now = now_millis();
+    let mut waiting = state
+        .pending_elevated
+        .lock()
+        .await
+        .values()
+        .filter(|cmd| cmd.status == ElevatedCommandStatus::Pending && cmd.expires_at > now)
+        .count();
+    waiting += state.directory_approvals.pending_requests().await.len();
+    if state.pending_meta_question.lock().await.is_some() {
+        waiting += 1;
+    }
+    waiting += state
+        .agent_runs_db
+        .list_pipeline_human_requests()
+        .await
+        .map(|requests| requests.len())
+        .unwrap_or(0);
+
+    TrayStatus {
+        running_agents,
+        waiting,
+        today_cost_usd: state.agent_runs_db.get_today_cost().await.unwrap_or(0.0),
+    }
+}
+
+/// Menu entries whose text or state follows the status
+struct StatusItems<R: Runtime> {
+    agents: MenuItem<R>,
+    waiting: MenuItem<R>,
+    cost: MenuItem<R>,
+    open_question: MenuItem<R>,
+}
+
+struct Tray<R: Runtime> {
+    app: AppHandle<R>,
+    icon: Option<(TrayIcon<R>, StatusItems<R>)>,
+    refresh_scheduled: AtomicBool,
+}
+
+impl<R: Runtime> Tray<R> {
+    fn schedule_refresh(self: &Arc<Self>) {
+        if self.refresh_scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let tray = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(REFRESH_DELAY).await;
+            tray.refresh_scheduled.store(false, Ordering::Release);
+            let Some(state) = tray.app.try_state::<AppState>() else {
+                return;
+            };
+            let status = collect_status(&state).await;
+            tray.show(&status);
+        });
+    }
+
+    fn show(&self, status: &TrayStatus) {
+        if let Some((icon, items)) = &self.icon {
+            let _ = icon.set_tooltip(Some(status.tooltip()));
+            let _ = items.agents.set_text(status.agents_line());
+            let _ = items.waiting.set_text(status.waiting_line());
+            let _ = items.cost.set_text(status.cost_line());
+            let _ = items.open_question.set_enabled(status.waiting > 0);
+        }
+        // Fails where the platform has no badge, which is fine
+        if let Some(window) = self.app.get_webview_window(MAIN_WINDOW) {
+            let _ = window.set_badge_count(status.badge_count());
+        }
+    }
+}
+
+/// Create the tray icon and keep it and the badge up to date
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    // The Linux tray library is loaded at runtime and panics when missing
+    let icon = match std::panic::catch_unwind(AssertUnwindSafe(|| build_icon(app))) {
+        Ok(Ok(icon)) => Some(icon),
+        Ok(Err(e)) => {
+            eprintln!("[Tray] Tray icon unavailable: {}", e);
+            None
+        }
+        Err(_) => {
+            eprintln!("[Tray] Tray icon unavailable: no tray support on this system");
+            None
+        }
+    };
+
+    let tray = Arc::new(Tray {
+        app: app.clone(),
+        icon,
+        refresh_scheduled: AtomicBool::new(false),
+    });
+    for event in REFRESH_EVENTS {
+        let tray = tray.clone();
+        app.listen_any(*event, move |_| tray.schedule_refresh());
+    }
+    tray.schedule_refresh();
+}
+
+fn build_icon<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<(TrayIcon<R>, StatusItems<R>)> {
+    let status = TrayStatus::default();
+    let items = StatusItems {
+        agents: MenuItem::with_id(
+            app,
+            "tray:agents",
+            status.agents_line(),
+            false,
+            None::<&str>,
+        )?,
+        waiting: MenuItem::with_id(
+            app,
+            "tray:waiting",
+            status.waiting_line(),
+            false,
+            None::<&str>,
+        )?,
+        cost: MenuItem::with_id(app, "tray:cost", status.cost_line(), false, None::<&str>)?,
+        open_question: MenuItem::with_id(
+            app,
+            MENU_OPEN_QUESTION,
+            "Open pending question",
+            false,
+            None::<&str>,
+        )?,
+    };
+    let stop_all = MenuItem::with_id(app, MENU_STOP_ALL, TRAY_STOP_PHRASE, true, None::<&str>)?;
+    let show = MenuItem::with_id(app, MENU_SHOW, "Show Claude Commander", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, MENU_QUIT, "Quit", true, None::<&str>)?;
+    let status_separator = PredefinedMenuItem::separator(app)?;
+    let window_separator = PredefinedMenuItem::separator(app)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &items.agents,
+            &items.waiting,
+            &items.cost,
+            &status_separator,
+            &items.open_question,
+            &stop_all,
+            &window_separator,
+            &show,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(status.tooltip())
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    Ok((builder.build(app)?, items))
+}
+
+fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, id: &str) {
+    match id {
+        MENU_OPEN_QUESTION => {
+            show_main_window(app);
+            let _ = app.emit("tray:open-pending-question", ());
+        }
+        MENU_STOP_ALL => {
+            let Some(state) = app.try_state::<AppState>() else {
+                return;
+            };
+            let targets = HaltTargets::from_state(&state);
+            tauri::async_runtime::spawn(async move {
+                stopword::halt_all(&targets, TRAY_STOP_PHRASE.to_string(), StopSource::Tray).await;
+            });
+        }
+        MENU_SHOW => show_main_window(app),
+        MENU_QUIT => app.exit(0),
+        _ => {}
+    }
+}
+
+fn show_main_window<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_text_and_badge() {
+        let idle = TrayStatus::default();
+        assert_eq!(
+            idle.tooltip(),
+            "Claude Commander\n0 agents running\nNothing waiting for you\n$0.00 today"
+        );
+        assert_eq!(idle.badge_count(), None);
+
+        let busy = TrayStatus {
+            running_agents: 1,
+            waiting: 2,
+            today_cost_usd: 3.456,
+        };
+        assert_eq!(busy.agents_line(), "1 agent running");
+        assert_eq!(busy.waiting_line(), "2 items waiting for you");
+        assert_eq!(busy.cost_line(), "$3.46 today");
+        assert_eq!(busy.badge_count(), Some(2));
+    }
+}
@@ -10,6 +10,8 @@ pub enum AgentStatus {
     WaitingForInput,
     Idle,
     Processing,
+    /// The last turn failed on a Claude usage or rate limit
+    RateLimited,
 }
 
 impl AgentStatus {
@@ -22,6 +24,7 @@ impl AgentStatus {
             AgentStatus::WaitingForInput => "waitingforinput",
             AgentStatus::Idle => "idle",
             AgentStatus::Processing => "processing",
+            AgentStatus::RateLimited => "ratelimited",
         }
     }
 }
@@ -55,6 +58,19 @@ impl AgentSource {
             AgentSource::TestWizard => "testwizard",
         }
     }
+
+    /// Parse a source as stored in the runs database
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ui" => Some(AgentSource::UI),
+            "meta" => Some(AgentSource::Meta),
+            "pipeline" => Some(AgentSource::Pipeline),
+            "pool" => Some(AgentSource::Pool),
+            "manual" => Some(AgentSource::Manual),
+            "testwizard" => Some(AgentSource::TestWizard),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for AgentSource {
@@ -93,6 +109,182 @@ pub struct AgentInfo {
     pub title: Option<String>, // Optional display title (e.g., pipeline stage)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub complexity: Option<String>, // Task complexity: "simple", "easy", "complex"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_note: Option<AgentNote>, // Most recent note pinned to this agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>, // Short run name derived from the initial prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_ids: Vec<String>, // Agent groups this agent belongs to
+    #[serde(default)]
+    pub hooks_missing: bool, // Tool calls seen in the output but no hook events received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_profile: Option<AppliedProfile>, // Permission profile spawned under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>, // Model passed to the claude CLI (--model)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<AgentWorktree>, // Git worktree the agent runs in, if isolated
+}
+
+/// A git worktree an agent runs in, apart from other agents in the same repo
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentWorktree {
+    /// Root of the repository the worktree was added to
+    pub repo_path: String,
+    /// The worktree's directory
+    pub path: String,
+    /// Branch checked out in the worktree
+    pub branch: String,
+}
+
+/// The permission profile an agent was spawned under, as needed after spawning
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AppliedProfile {
+    pub name: String,
+    pub elevated_commands: crate::agent_runs_db::ElevatedCommandPolicy,
+    pub network_expected: bool,
+}
+
+/// Tool lists given for one agent, combined with its permission profile's
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolRestrictions {
+    /// Tools the agent may use without asking (--allowedTools)
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Tools the agent may not use (--disallowedTools)
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+}
+
+/// Who pinned a note to an agent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteAuthor {
+    User,
+    Meta,
+}
+
+impl NoteAuthor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteAuthor::User => "user",
+            NoteAuthor::Meta => "meta",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "meta" => NoteAuthor::Meta,
+            _ => NoteAuthor::User,
+        }
+    }
+}
+
+/// A free-form note attached to an agent run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentNote {
+    pub id: Option<i64>,
+    pub agent_id: String,
+    pub author: NoteAuthor,
+    pub text: String,
+    pub timestamp: i64, // Unix timestamp in milliseconds
+}
+
+/// A message relayed from one agent to another by the commander
+/// (also the payload of the `agent:message` event)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentMessage {
+    pub id: Option<i64>,
+    /// Pipeline both agents belong to (None for standalone agents)
+    pub pipeline_id: Option<String>,
+    pub from_agent_id: String,
+    pub to_agent_id: String,
+    pub text: String,
+    pub timestamp: i64, // Unix timestamp in milliseconds
+}
+
+/// Agents operated on together: one group per pipeline, plus groups named by the user
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentGroup {
+    pub id: String,
+    pub name: String,
+    /// Pipeline the group was created for (None for user-defined groups)
+    pub pipeline_id: Option<String>,
+    pub agent_ids: Vec<String>,
+    pub created_at: i64, // Unix timestamp in milliseconds
+}
+
+/// Outcome of a group operation for one member
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GroupMemberResult {
+    pub agent_id: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What stop_all_agents did with one agent
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopOutcome {
+    Stopped,
+    AlreadyStopped,
+    Failed,
+}
+
+/// Outcome of stop_all_agents for one agent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentStopResult {
+    pub agent_id: String,
+    pub outcome: StopOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Usage summed over a group's members, live or finished
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GroupStatistics {
+    pub group_id: String,
+    pub agent_count: u32,
+    /// Members that are running or waiting for input
+    pub active_count: u32,
+    pub total_prompts: u32,
+    pub total_tool_calls: u32,
+    pub total_tokens_used: u64,
+    pub total_cost_usd: f64,
+}
+
+/// The counters an agent card shows
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentStatsSummary {
+    pub total_prompts: u32,
+    pub total_tool_calls: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_tokens_used: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+    pub last_activity: String,
+}
+
+/// How far an agent is through its todo list
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TodoProgress {
+    pub completed: u32,
+    pub total: u32,
+}
+
+/// An agent with everything its card shows, from list_agents_detailed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSummary {
+    pub info: AgentInfo,
+    pub stats: AgentStatsSummary,
+    /// Start of the agent's latest reply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_message: Option<String>,
+    /// None until the agent writes a todo list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo_progress: Option<TodoProgress>,
+    /// A security review for this agent is awaiting a decision
+    pub security_alert: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +312,18 @@ pub struct AgentOutputEvent {
     pub timestamp: Option<i64>,
 }
 
+/// An agent output that matched search_agent_outputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputSearchMatch {
+    pub agent_id: String,
+    pub output_type: String,
+    /// One line around the first match, with the match wrapped in `**`
+    pub snippet: String,
+    pub timestamp: i64,
+    /// Found in a live agent's output buffer rather than the runs database
+    pub live: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -129,6 +333,10 @@ pub struct OutputMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub byte_size: Option<usize>,
     pub is_truncated: bool,
+    /// The content arrived with ANSI escape codes (kept in the output buffer;
+    /// removed from what is stored and shown, see agent_manager::ansi)
+    #[serde(default)]
+    pub contains_ansi: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -136,6 +344,17 @@ pub struct AgentStatusEvent {
     pub agent_id: String,
     pub status: AgentStatus,
     pub info: Option<AgentInfo>,
+    /// Why the app stopped the agent, when it wasn't the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<StopReason>,
+}
+
+/// Why an agent was stopped without being asked to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Waited for input longer than its source's idle timeout
+    IdleTimeout,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -150,6 +369,9 @@ pub struct AgentActivityEvent {
     pub is_processing: bool,
     pub pending_input: bool,
     pub last_activity: i64, // Unix timestamp in milliseconds
+    /// Prompts waiting for the current turn to end
+    #[serde(default)]
+    pub queued_prompts: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -225,6 +447,24 @@ pub struct AgentStatistics {
     pub duration_ms: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub num_turns: Option<u32>,
+
+    // Stderr diagnostics
+    #[serde(default)]
+    pub stderr_lines: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_stderr_error: Option<String>,
+
+    // Time spent per tool, from PreToolUse to PostToolUse
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub tool_timings: std::collections::HashMap<String, ToolTimingStats>,
+}
+
+/// Calls of one tool and how long they took
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolTimingStats {
+    pub calls: u32,
+    pub total_ms: u64,
+    pub max_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -272,10 +512,14 @@ pub struct ChatResponse {
     pub usage: ChatUsage,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChatUsage {
     pub input_tokens: u32,
     pub output_tokens: u32,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -286,6 +530,17 @@ pub struct MetaAgentToolCallEvent {
     pub timestamp: i64,
 }
 
+/// Emitted by the tool loop just before each tool runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaAgentIterationEvent {
+    pub iteration: usize,
+    pub max_iterations: usize,
+    pub tool_name: Option<String>,
+    /// Milliseconds since the current tool loop started
+    pub elapsed_ms: u64,
+    pub timestamp: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetaAgentThinkingEvent {
     pub is_thinking: bool,
@@ -307,6 +562,12 @@ pub struct ContextInfoEvent {
     pub state: String,
     /// Optional warning message
     pub warning_message: Option<String>,
+    /// Input tokens read from the prompt cache this session
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the prompt cache this session
+    pub cache_creation_tokens: u64,
+    /// Share of input tokens read from the prompt cache (0-100)
+    pub cache_hit_percent: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Allowed file extensions for instruction files
 const ALLOWED_EXTENSIONS: &[&str] = &["md", "txt"];
@@ -37,6 +37,119 @@ pub fn is_allowed_instruction_file(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// Expand a leading `~` (alone or followed by a separator) to the home directory
+pub fn expand_home_dir(path: &str) -> Result<PathBuf, String> {
+    let rest = match path.strip_prefix('~') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', '\\']) => &rest[1..],
+        _ => return Ok(PathBuf::from(path)),
+    };
+    let home = dirs::home_dir()
+        .ok_or_else(|| format!("Cannot expand '~' in '{}': home directory is unknown", path))?;
+    Ok(home.join(rest))
+}
+
+/// Strip a verbatim prefix (`\\?\C:\x` -> `C:\x`, `\\?\UNC\srv\x` -> `\\srv\x`)
+pub fn strip_verbatim_prefix(path: &str) -> String {
+    let Some(rest) = path
+        .strip_prefix(r"\\?\")
+        .or_else(|| path.strip_prefix("//?/"))
+    else {
+        return path.to_string();
+    };
+    match rest
+        .strip_prefix(r"UNC\")
+        .or_else(|| rest.strip_prefix("UNC/"))
+    {
+        Some(share) => format!(r"\\{}", share),
+        None => rest.to_string(),
+    }
+}
+
+/// Check a working directory and return it as a normalized absolute path
+///
+/// Trims surrounding whitespace, rejects NUL and other control characters,
+/// expands `~`, resolves relative paths and symlinks, and requires the result
+/// to be an existing directory. The error names the check that failed. On
+/// Windows the result is the usual `C:\...` form, not the `\\?\C:\...` one
+/// canonicalize returns, which cmd.exe can't run in.
+pub fn validate_working_dir(working_dir: &str) -> Result<String, String> {
+    let trimmed = working_dir.trim();
+    if trimmed.is_empty() {
+        return Err("Working directory is empty".to_string());
+    }
+    if let Some(c) = trimmed.chars().find(|c| c.is_control()) {
+        let what = if c == '\0' {
+            "a NUL byte".to_string()
+        } else {
+            format!("control character U+{:04X}", c as u32)
+        };
+        return Err(format!(
+            "Working directory '{}' contains {}",
+            trimmed.escape_debug(),
+            what
+        ));
+    }
+
+    let expanded = expand_home_dir(trimmed)?;
+    let canonical = std::fs::canonicalize(&expanded).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            format!("Working directory '{}' does not exist", expanded.display())
+        } else {
+            format!(
+                "Working directory '{}' could not be resolved: {}",
+                expanded.display(),
+                e
+            )
+        }
+    })?;
+    if !canonical.is_dir() {
+        return Err(format!(
+            "Working directory '{}' is not a directory",
+            canonical.display()
+        ));
+    }
+
+    canonical
+        .into_os_string()
+        .into_string()
+        .map(|path| strip_verbatim_prefix(&path))
+        .map_err(|p| format!("Working directory '{:?}' is not valid UTF-8", p))
+}
+
+/// Model aliases the claude CLI resolves to its current models
+pub const MODEL_ALIASES: &[&str] = &["haiku", "sonnet", "opus", "opusplan"];
+
+/// Check a model requested for a worker agent's `--model`
+///
+/// Accepts the CLI's aliases and full model names (`claude-sonnet-4-5`,
+/// `claude-haiku-4-5-20251001`), optionally with the `[1m]` long-context
+/// suffix, and returns it lowercased. None or blank means the configured
+/// default. Anything else is refused here rather than failing the agent's
+/// first prompt.
+pub fn validate_model(model: Option<&str>) -> Result<Option<String>, String> {
+    let Some(model) = model.map(str::trim).filter(|m| !m.is_empty()) else {
+        return Ok(None);
+    };
+    let normalized = model.to_lowercase();
+    let name = normalized.strip_suffix("[1m]").unwrap_or(&normalized);
+    let is_full_name = name.strip_prefix("claude-").is_some_and(|rest| {
+        rest.starts_with(|c: char| c.is_ascii_alphabetic())
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    });
+    if MODEL_ALIASES.contains(&name) || is_full_name {
+        Ok(Some(normalized))
+    } else {
+        Err(format!(
+            "Unknown model '{}': use {} or a full model name like claude-sonnet-4-5",
+            model,
+            MODEL_ALIASES.join(", ")
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,4 +191,74 @@ mod tests {
         assert!(is_allowed_instruction_file(Path::new("/path/to/file.txt")));
         assert!(!is_allowed_instruction_file(Path::new("file.rs")));
     }
+
+    #[test]
+    fn test_validate_working_dir_normalizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let canonical = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(canonical.join("sub")).unwrap();
+
+        // Whitespace and `..` segments are normalized away
+        let messy = format!("  {}/sub/..  ", canonical.display());
+        assert_eq!(
+            validate_working_dir(&messy).unwrap(),
+            canonical.to_str().unwrap()
+        );
+
+        let home = dirs::home_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(validate_working_dir("~").unwrap(), home.to_str().unwrap());
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\Users\dev\app"),
+            r"C:\Users\dev\app"
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\app"),
+            r"\\server\share\app"
+        );
+        assert_eq!(strip_verbatim_prefix(r"C:\Users\dev"), r"C:\Users\dev");
+        assert_eq!(strip_verbatim_prefix("/home/dev/app"), "/home/dev/app");
+    }
+
+    #[test]
+    fn test_validate_working_dir_names_the_failed_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+        let missing = dir.path().join("missing");
+
+        let err = |path: &str| validate_working_dir(path).unwrap_err();
+        assert_eq!(err("   "), "Working directory is empty");
+        assert!(err("/tmp/a\0b").contains("contains a NUL byte"));
+        assert!(err("/tmp/a\nb").contains("contains control character U+000A"));
+        assert!(err(missing.to_str().unwrap()).ends_with("does not exist"));
+        assert!(err(file.to_str().unwrap()).ends_with("is not a directory"));
+    }
+
+    #[test]
+    fn test_validate_model() {
+        assert_eq!(validate_model(None).unwrap(), None);
+        assert_eq!(validate_model(Some("  ")).unwrap(), None);
+        assert_eq!(validate_model(Some(" Haiku ")).unwrap().unwrap(), "haiku");
+        assert_eq!(
+            validate_model(Some("claude-sonnet-4-5-20250929"))
+                .unwrap()
+                .unwrap(),
+            "claude-sonnet-4-5-20250929"
+        );
+        assert!(validate_model(Some("sonnet[1m]")).is_ok());
+
+        for invalid in [
+            "gpt-4o",
+            "claude-",
+            "claude-sonnet 4",
+            "sonnet; rm -rf /",
+            "--help",
+        ] {
+            assert!(validate_model(Some(invalid)).is_err(), "{}", invalid);
+        }
+    }
 }
@@ -0,0 +1,42 @@
+// Version info - which build wrote a record
+//
+// The app version, the git commit it was built from (embedded by build.rs)
+// and the database schema version. Every agent run and pipeline group is
+// stamped with them when it is first written, so a bad run or a corrupted
+// row can be traced back to the build that produced it.
+
+use serde::Serialize;
+
+use crate::agent_runs_db::SCHEMA_VERSION;
+
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short commit hash, or "unknown" for builds outside a git checkout
+pub const GIT_COMMIT: &str = env!("GIT_COMMIT_HASH");
+
+/// What get_version_info returns
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionInfo {
+    pub app_version: &'static str,
+    pub git_commit: &'static str,
+    pub schema_version: i64,
+    /// Cargo features this build was compiled with
+    pub features: Vec<&'static str>,
+}
+
+pub fn current() -> VersionInfo {
+    let mut features = Vec::new();
+    if cfg!(feature = "fault-injection") {
+        features.push("fault-injection");
+    }
+    if cfg!(feature = "mock-provider") {
+        features.push("mock-provider");
+    }
+
+    VersionInfo {
+        app_version: APP_VERSION,
+        git_commit: GIT_COMMIT,
+        schema_version: SCHEMA_VERSION,
+        features,
+    }
+}
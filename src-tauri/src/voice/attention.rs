@@ -10,6 +10,8 @@ use tokio::sync::{mpsc, Mutex};
 use tokio::task::JoinHandle;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+use crate::commands::env_registry;
+
 /// Attention mode session - announces task completion and allows follow-up
 /// Auto-closes after inactivity timeout
 pub struct AttentionSession {
@@ -64,7 +66,8 @@ impl AttentionSession {
             .on_timeout
             .expect("Attention mode requires on_timeout callback");
 
-        let model = std::env::var("OPENAI_REALTIME_MODEL")
+        let model = env_registry::OPENAI_REALTIME_MODEL
+            .var()
             .unwrap_or_else(|_| "gpt-4o-realtime-preview".to_string());
 
         let url = format!("wss://api.openai.com/v1/realtime?model={}", model);
@@ -4,14 +4,18 @@
 // It uses the session_registry and session_manager modules for unified session handling.
 
 use super::attention::AttentionSession;
+use super::connection::{ConnectionState, VoiceSessionHealth};
 use super::discuss::DiscussSession;
 use super::realtime::VoiceSession;
 use super::session_manager::{
     get_api_key, AttentionTimeoutEvent, ToolCallEvent, VoiceAudioEvent, VoiceCallbacks,
     VoiceResponseEvent, VoiceSettings, VoiceStatus, VoiceTranscriptEvent,
 };
-use super::session_registry::{attention_session, discuss_session, voice_session, SessionOps};
+use super::session_registry::{
+    attention_session, discuss_session, voice_session, SessionOps, SessionRegistry, SessionType,
+};
 use super::tools;
+use crate::stopword::{self, HaltTargets, StopSource};
 use crate::AppState;
 use tauri::Emitter;
 
@@ -28,14 +32,53 @@ async fn get_voice_settings(state: &tauri::State<'_, AppState>) -> VoiceSettings
     }
 }
 
+/// Halt everything when a transcript is a stop phrase, ending the voice
+/// session first so its model can't act on it. Returns whether it was one.
+fn handle_stop_phrase(
+    transcript: &str,
+    session_type: SessionType,
+    targets: &HaltTargets,
+    app_handle: &tauri::AppHandle,
+) -> bool {
+    let Some(phrase) = stopword::detect(transcript) else {
+        return false;
+    };
+    let (targets, app) = (targets.clone(), app_handle.clone());
+    tauri::async_runtime::spawn(async move {
+        let stopped = match session_type {
+            SessionType::Voice => stop_voice_session(app.clone()).await.map(|_| ()),
+            SessionType::Discuss => stop_discuss_session(app.clone()).await,
+            SessionType::Attention => stop_attention_session(app.clone()).await,
+        };
+        if let Err(e) = stopped {
+            eprintln!("[{}] Failed to stop session: {}", session_type.name(), e);
+        }
+
+        let report = stopword::halt_all(&targets, phrase, StopSource::Voice).await;
+        let _ = app.emit(
+            "toast",
+            serde_json::json!({
+                "type": "warning",
+                "message": report.confirmation(),
+                "duration": 8000,
+            }),
+        );
+    });
+    true
+}
+
 // ============================================================================
 // Voice Mode Commands (Dictate)
 // ============================================================================
 
 /// Start a voice recording session (Dictate mode).
 #[tauri::command]
-pub async fn start_voice_session(app_handle: tauri::AppHandle) -> Result<(), String> {
+pub async fn start_voice_session(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
     let api_key = get_api_key()?;
+    let stop_targets = HaltTargets::from_state(&state);
     let session_lock = voice_session();
     let mut session_guard = session_lock.lock().await;
 
@@ -48,10 +91,18 @@ pub async fn start_voice_session(app_handle: tauri::AppHandle) -> Result<(), Str
 
     // Create new session with callbacks
     let mut session = VoiceSession::new();
-    let (app_t, app_r, app_a) = (app_handle.clone(), app_handle.clone(), app_handle.clone());
+    let (app_t, app_r, app_a, app_c) = (
+        app_handle.clone(),
+        app_handle.clone(),
+        app_handle.clone(),
+        app_handle.clone(),
+    );
 
     let callbacks = VoiceCallbacks::basic(
         move |transcript| {
+            if handle_stop_phrase(&transcript, SessionType::Voice, &stop_targets, &app_t) {
+                return;
+            }
             let _ = app_t.emit("voice:transcript", VoiceTranscriptEvent { transcript });
         },
         move |delta| {
@@ -60,7 +111,21 @@ pub async fn start_voice_session(app_handle: tauri::AppHandle) -> Result<(), Str
         move |audio| {
             let _ = app_a.emit("voice:audio", VoiceAudioEvent { audio });
         },
-    );
+    )
+    .with_connection_state(move |event| {
+        let failed = event.state == ConnectionState::Failed;
+        let _ = app_c.emit("voice:connection-state", event);
+        // The session ended on its own; stop showing it as listening
+        if failed {
+            let _ = app_c.emit(
+                "voice:status",
+                VoiceStatus {
+                    is_active: false,
+                    transcript: String::new(),
+                },
+            );
+        }
+    });
 
     session.connect(&api_key, callbacks).await?;
 
@@ -111,6 +176,12 @@ pub async fn get_voice_status() -> Result<VoiceStatus, String> {
     Ok(voice_session().get_status().await)
 }
 
+/// Get the voice session's connection health (None if no session was started).
+#[tauri::command]
+pub async fn get_voice_session_health() -> Result<Option<VoiceSessionHealth>, String> {
+    Ok(SessionRegistry::global().voice_health().await)
+}
+
 // ============================================================================
 // Discuss Mode Commands
 // ============================================================================
@@ -147,11 +218,17 @@ pub async fn start_discuss_session(
 
     let meta_agent = state.meta_agent.clone();
     let agent_manager = state.agent_manager.clone();
+    let pipeline_manager = state.auto_pipeline_manager.clone();
+    let pending_question = state.pending_meta_question.clone();
+    let stop_targets = HaltTargets::from_state(&state);
 
     println!("[Discuss] Using voice: {}", voice_settings.voice);
 
     let callbacks = VoiceCallbacks::basic(
         move |transcript| {
+            if handle_stop_phrase(&transcript, SessionType::Discuss, &stop_targets, &app_t) {
+                return;
+            }
             let _ = app_t.emit("discuss:transcript", VoiceTranscriptEvent { transcript });
         },
         move |delta| {
@@ -163,7 +240,12 @@ pub async fn start_discuss_session(
     )
     .with_tool_call(move |name, call_id, args| {
         let app = app_tool.clone();
-        let (meta, mgr) = (meta_agent.clone(), agent_manager.clone());
+        let (meta, mgr, pipelines, question) = (
+            meta_agent.clone(),
+            agent_manager.clone(),
+            pipeline_manager.clone(),
+            pending_question.clone(),
+        );
         let (n, a) = (name.clone(), args.clone());
 
         let _ = app.emit(
@@ -175,7 +257,7 @@ pub async fn start_discuss_session(
             },
         );
 
-        execute_tool_blocking(n, a, meta, mgr, app)
+        execute_tool_blocking(n, a, meta, mgr, pipelines, question, app)
     })
     .with_turn_callbacks(
         move || {
@@ -273,6 +355,9 @@ pub async fn start_attention_session(
 
     let meta_agent = state.meta_agent.clone();
     let agent_manager = state.agent_manager.clone();
+    let pipeline_manager = state.auto_pipeline_manager.clone();
+    let pending_question = state.pending_meta_question.clone();
+    let stop_targets = HaltTargets::from_state(&state);
     let agent_id_timeout = agent_id.clone();
 
     println!(
@@ -282,6 +367,9 @@ pub async fn start_attention_session(
 
     let callbacks = VoiceCallbacks::basic(
         move |transcript| {
+            if handle_stop_phrase(&transcript, SessionType::Attention, &stop_targets, &app_t) {
+                return;
+            }
             let _ = app_t.emit("attention:transcript", VoiceTranscriptEvent { transcript });
         },
         move |delta| {
@@ -293,7 +381,12 @@ pub async fn start_attention_session(
     )
     .with_tool_call(move |name, call_id, args| {
         let app = app_tool.clone();
-        let (meta, mgr) = (meta_agent.clone(), agent_manager.clone());
+        let (meta, mgr, pipelines, question) = (
+            meta_agent.clone(),
+            agent_manager.clone(),
+            pipeline_manager.clone(),
+            pending_question.clone(),
+        );
         let (n, a) = (name.clone(), args.clone());
 
         let _ = app.emit(
@@ -305,7 +398,7 @@ pub async fn start_attention_session(
             },
         );
 
-        execute_tool_blocking(n, a, meta, mgr, app)
+        execute_tool_blocking(n, a, meta, mgr, pipelines, question, app)
     })
     .with_timeout(move || {
         let _ = app_timeout.emit(
@@ -384,6 +477,12 @@ fn execute_tool_blocking(
     args: String,
     meta_agent: std::sync::Arc<tokio::sync::Mutex<crate::meta_agent::MetaAgent>>,
     agent_manager: std::sync::Arc<tokio::sync::Mutex<crate::agent_manager::AgentManager>>,
+    auto_pipeline_manager: Option<
+        std::sync::Arc<tokio::sync::Mutex<crate::auto_pipeline::AutoPipelineManager>>,
+    >,
+    pending_question: std::sync::Arc<
+        tokio::sync::Mutex<Option<crate::meta_agent::tools::PendingQuestion>>,
+    >,
     app_handle: tauri::AppHandle,
 ) -> String {
     std::thread::spawn(move || {
@@ -398,8 +497,16 @@ fn execute_tool_blocking(
             }
         };
         rt.block_on(async {
-            tools::execute_tool_with_state(&name, &args, meta_agent, agent_manager, app_handle)
-                .await
+            tools::execute_tool_with_state(
+                &name,
+                &args,
+                meta_agent,
+                agent_manager,
+                auto_pipeline_manager,
+                pending_question,
+                app_handle,
+            )
+            .await
         })
     })
     .join()
@@ -0,0 +1,247 @@
+// Connection health - State, liveness and reconnect policy for realtime sessions
+//
+// The realtime WebSocket can drop (network blips, token expiry) without the
+// session noticing. The connection task pings the server periodically, treats a
+// missing pong as a dead connection, and reconnects with exponential backoff.
+// Audio captured while the socket is down is kept for a few seconds and
+// replayed once the session is re-established.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Reconnect attempts before the session is ended
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Delay before the first reconnect attempt; doubles on each further attempt
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound for the reconnect delay
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// How often the connection is pinged to measure latency and detect dead sockets
+pub const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A ping unanswered for this long means the connection is dead
+pub const PONG_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How much unsent audio is replayed after reconnecting
+const REPLAY_WINDOW: Duration = Duration::from_secs(3);
+
+/// Delay before reconnect attempt `attempt` (1-based)
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting,
+    /// Stopped by the user
+    Closed,
+    /// Gave up after MAX_RECONNECT_ATTEMPTS
+    Failed,
+}
+
+/// Payload of the `voice:connection-state` event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VoiceConnectionStateEvent {
+    pub state: ConnectionState,
+    /// Current reconnect attempt (0 when not reconnecting)
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Why the connection dropped or the session ended
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Snapshot returned by get_voice_session_health
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VoiceSessionHealth {
+    pub state: ConnectionState,
+    /// Time since the session was started
+    pub uptime_ms: u64,
+    pub reconnect_count: u32,
+    /// Round-trip time of the last answered ping
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+struct HealthState {
+    state: ConnectionState,
+    started_at: Instant,
+    reconnect_count: u32,
+    latency: Option<Duration>,
+    ping_sent_at: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Connection health shared between a session and its connection task
+pub struct ConnectionHealth {
+    inner: Mutex<HealthState>,
+}
+
+impl ConnectionHealth {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(HealthState {
+                state: ConnectionState::Connecting,
+                started_at: Instant::now(),
+                reconnect_count: 0,
+                latency: None,
+                ping_sent_at: None,
+                last_error: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HealthState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.lock().state
+    }
+
+    pub fn set_state(&self, state: ConnectionState, error: Option<String>) {
+        let mut health = self.lock();
+        health.state = state;
+        if error.is_some() {
+            health.last_error = error;
+        }
+        if state != ConnectionState::Connected {
+            health.ping_sent_at = None;
+        }
+    }
+
+    /// Record a successful reconnect
+    pub fn reconnected(&self) {
+        let mut health = self.lock();
+        health.reconnect_count += 1;
+        health.state = ConnectionState::Connected;
+    }
+
+    /// Record a ping. Returns false if the previous one went unanswered for
+    /// longer than PONG_TIMEOUT, i.e. the connection is dead.
+    pub fn ping_sent(&self) -> bool {
+        let mut health = self.lock();
+        let now = Instant::now();
+        match health.ping_sent_at {
+            Some(sent) if now.duration_since(sent) > PONG_TIMEOUT => false,
+            // Keep measuring from the oldest unanswered ping
+            Some(_) => true,
+            None => {
+                health.ping_sent_at = Some(now);
+                true
+            }
+        }
+    }
+
+    pub fn pong_received(&self) {
+        let mut health = self.lock();
+        if let Some(sent) = health.ping_sent_at.take() {
+            health.latency = Some(sent.elapsed());
+        }
+    }
+
+    pub fn snapshot(&self) -> VoiceSessionHealth {
+        let health = self.lock();
+        VoiceSessionHealth {
+            state: health.state,
+            uptime_ms: health.started_at.elapsed().as_millis() as u64,
+            reconnect_count: health.reconnect_count,
+            latency_ms: health.latency.map(|d| d.as_millis() as u64),
+            last_error: health.last_error.clone(),
+        }
+    }
+}
+
+impl Default for ConnectionHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Audio that couldn't be sent, bounded to the last REPLAY_WINDOW
+#[derive(Default)]
+pub struct AudioReplayBuffer {
+    chunks: VecDeque<(Instant, String)>,
+}
+
+impl AudioReplayBuffer {
+    pub fn push(&mut self, audio: String) {
+        self.push_at(Instant::now(), audio);
+    }
+
+    fn push_at(&mut self, at: Instant, audio: String) {
+        self.chunks.push_back((at, audio));
+        self.prune(at);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .chunks
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > REPLAY_WINDOW)
+        {
+            self.chunks.pop_front();
+        }
+    }
+
+    /// Take the chunks still within the replay window, oldest first
+    pub fn take(&mut self) -> Vec<String> {
+        self.prune(Instant::now());
+        self.chunks.drain(..).map(|(_, audio)| audio).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        assert_eq!(backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(4), Duration::from_secs(4));
+        assert_eq!(backoff_delay(10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_replay_buffer_keeps_recent_audio_only() {
+        let mut buffer = AudioReplayBuffer::default();
+        let now = Instant::now();
+        buffer.push_at(now - Duration::from_secs(10), "stale".to_string());
+        buffer.push_at(now - Duration::from_secs(1), "recent".to_string());
+        buffer.push("latest".to_string());
+
+        assert_eq!(buffer.take(), vec!["recent", "latest"]);
+        assert!(buffer.take().is_empty());
+    }
+
+    #[test]
+    fn test_health_tracks_reconnects_and_latency() {
+        let health = ConnectionHealth::new();
+        health.set_state(ConnectionState::Connected, None);
+        assert!(health.ping_sent());
+        health.pong_received();
+
+        health.set_state(
+            ConnectionState::Reconnecting,
+            Some("Connection reset".to_string()),
+        );
+        health.reconnected();
+
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.state, ConnectionState::Connected);
+        assert_eq!(snapshot.reconnect_count, 1);
+        assert!(snapshot.latency_ms.is_some());
+        assert_eq!(snapshot.last_error.as_deref(), Some("Connection reset"));
+    }
+}
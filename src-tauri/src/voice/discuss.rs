@@ -9,6 +9,8 @@ use tokio::sync::{mpsc, Mutex};
 use tokio::task::JoinHandle;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+use crate::commands::env_registry;
+
 /// Discuss mode session - bidirectional voice with tool-use
 pub struct DiscussSession {
     /// Sender for outgoing WebSocket messages
@@ -54,7 +56,8 @@ impl DiscussSession {
             .on_assistant_turn_complete
             .expect("Discuss mode requires on_assistant_turn_complete callback");
 
-        let model = std::env::var("OPENAI_REALTIME_MODEL")
+        let model = env_registry::OPENAI_REALTIME_MODEL
+            .var()
             .unwrap_or_else(|_| "gpt-4o-realtime-preview".to_string());
 
         let url = format!("wss://api.openai.com/v1/realtime?model={}", model);
@@ -1,5 +1,6 @@
 pub mod attention;
 pub mod commands;
+pub mod connection;
 pub mod discuss;
 pub mod realtime;
 pub mod session_manager;
@@ -8,6 +9,7 @@ pub mod tools;
 
 pub use attention::AttentionSession;
 pub use commands::*;
+pub use connection::{ConnectionState, VoiceConnectionStateEvent, VoiceSessionHealth};
 pub use discuss::DiscussSession;
 pub use realtime::VoiceSession;
 pub use session_manager::{
@@ -1,11 +1,23 @@
-use super::session_manager::{AudioCb, ResponseCb, TranscriptCb, VoiceCallbacks};
+use super::connection::{
+    backoff_delay, AudioReplayBuffer, ConnectionHealth, ConnectionState, VoiceConnectionStateEvent,
+    VoiceSessionHealth, MAX_RECONNECT_ATTEMPTS, PING_INTERVAL,
+};
+use super::session_manager::{
+    AudioCb, ConnectionStateCb, ResponseCb, TranscriptCb, VoiceCallbacks,
+};
 use async_openai::types::realtime::{ClientEvent, InputAudioBufferAppendEvent, ServerEvent};
+use futures_util::stream::SplitSink;
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
 use std::sync::Arc;
+use tokio::net::TcpStream;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::JoinHandle;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::commands::env_registry;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
 /// OpenAI Realtime API session
 pub struct VoiceSession {
@@ -15,10 +27,38 @@ pub struct VoiceSession {
     transcript: Arc<Mutex<String>>,
     /// Session active flag
     is_active: Arc<Mutex<bool>>,
-    /// JoinHandle for outgoing message task (for cleanup)
-    write_handle: Option<JoinHandle<()>>,
-    /// JoinHandle for incoming message task (for cleanup)
-    read_handle: Option<JoinHandle<()>>,
+    /// Connection state, reconnects and latency
+    health: Arc<ConnectionHealth>,
+    /// JoinHandle for the connection task (for cleanup)
+    connection_handle: Option<JoinHandle<()>>,
+}
+
+/// Everything the connection task needs to run and re-establish the session
+struct ConnectionContext {
+    api_key: String,
+    model: String,
+    transcript: Arc<Mutex<String>>,
+    is_active: Arc<Mutex<bool>>,
+    health: Arc<ConnectionHealth>,
+    on_transcript: TranscriptCb,
+    on_response: ResponseCb,
+    on_audio: AudioCb,
+    on_connection_state: Option<ConnectionStateCb>,
+}
+
+impl ConnectionContext {
+    /// Update the health state and notify the frontend
+    fn set_state(&self, state: ConnectionState, attempt: u32, reason: Option<String>) {
+        self.health.set_state(state, reason.clone());
+        if let Some(cb) = &self.on_connection_state {
+            (cb)(VoiceConnectionStateEvent {
+                state,
+                attempt,
+                max_attempts: MAX_RECONNECT_ATTEMPTS,
+                reason,
+            });
+        }
+    }
 }
 
 impl VoiceSession {
@@ -27,30 +67,61 @@ impl VoiceSession {
             ws_sender: None,
             transcript: Arc::new(Mutex::new(String::new())),
             is_active: Arc::new(Mutex::new(false)),
-            write_handle: None,
-            read_handle: None,
+            health: Arc::new(ConnectionHealth::new()),
+            connection_handle: None,
         }
     }
 
     /// Connect to OpenAI Realtime API
+    ///
+    /// Fails if the first connection can't be made. Later drops are handled by
+    /// the connection task, which reconnects with backoff.
     pub async fn connect(
         &mut self,
         api_key: &str,
         callbacks: VoiceCallbacks,
     ) -> Result<(), String> {
-        let on_transcript = callbacks.on_transcript;
-        let on_response = callbacks.on_response;
-        let on_audio = callbacks.on_audio;
-        let model = std::env::var("OPENAI_REALTIME_MODEL")
+        let model = env_registry::OPENAI_REALTIME_MODEL
+            .var()
             .unwrap_or_else(|_| "gpt-realtime-mini".to_string());
 
-        let url = format!("wss://api.openai.com/v1/realtime?model={}", model);
-
         println!(
             "[Voice] Connecting to OpenAI Realtime API with model: {}",
             model
         );
 
+        self.health = Arc::new(ConnectionHealth::new());
+        let ws_stream = Self::open_socket(api_key, &model).await?;
+
+        // Set up channel for sending audio
+        let (tx, rx) = mpsc::channel::<String>(100);
+        self.ws_sender = Some(tx);
+
+        // Set active flag
+        *self.is_active.lock().await = true;
+
+        let ctx = ConnectionContext {
+            api_key: api_key.to_string(),
+            model,
+            transcript: self.transcript.clone(),
+            is_active: self.is_active.clone(),
+            health: self.health.clone(),
+            on_transcript: callbacks.on_transcript,
+            on_response: callbacks.on_response,
+            on_audio: callbacks.on_audio,
+            on_connection_state: callbacks.on_connection_state,
+        };
+        ctx.set_state(ConnectionState::Connected, 0, None);
+
+        self.connection_handle = Some(tokio::spawn(Self::run_connection(ws_stream, rx, ctx)));
+
+        Ok(())
+    }
+
+    /// Open the WebSocket and send the session configuration
+    async fn open_socket(api_key: &str, model: &str) -> Result<WsStream, String> {
+        let url = format!("wss://api.openai.com/v1/realtime?model={}", model);
+
         // Build WebSocket request with auth header
         let request = http::Request::builder()
             .uri(&url)
@@ -67,14 +138,12 @@ impl VoiceSession {
             .body(())
             .map_err(|e| format!("Failed to build request: {}", e))?;
 
-        let (ws_stream, _) = connect_async(request)
+        let (mut ws_stream, _) = connect_async(request)
             .await
             .map_err(|e| format!("WebSocket connection failed: {}", e))?;
 
         println!("[Voice] Connected to OpenAI Realtime API");
 
-        let (mut ws_write, mut ws_read) = ws_stream.split();
-
         // Build session configuration manually to match OpenAI's expected format
         // (async-openai types have incorrect fields for some parts)
         let session_config = json!({
@@ -98,100 +167,203 @@ impl VoiceSession {
         let config_json = serde_json::to_string(&session_config)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
-        ws_write
+        ws_stream
             .send(Message::Text(config_json))
             .await
             .map_err(|e| format!("Failed to send config: {}", e))?;
 
         println!("[Voice] Session configured");
+        Ok(ws_stream)
+    }
 
-        // Set up channel for sending audio
-        let (tx, mut rx) = mpsc::channel::<String>(100);
-        self.ws_sender = Some(tx);
-
-        // Set active flag
-        *self.is_active.lock().await = true;
-        let is_active = self.is_active.clone();
-        let transcript = self.transcript.clone();
-
-        // Spawn task to handle outgoing messages
-        let is_active_write = is_active.clone();
-        let write_handle = tokio::spawn(async move {
-            while let Some(audio_data) = rx.recv().await {
-                if !*is_active_write.lock().await {
-                    break;
-                }
+    /// Drive the connection until the session stops or reconnecting fails
+    async fn run_connection(
+        mut ws_stream: WsStream,
+        mut rx: mpsc::Receiver<String>,
+        ctx: ConnectionContext,
+    ) {
+        let mut replay = AudioReplayBuffer::default();
+        loop {
+            let reason = Self::pump(ws_stream, &mut rx, &mut replay, &ctx).await;
+            if !*ctx.is_active.lock().await {
+                return;
+            }
+            match Self::reconnect(&mut rx, &mut replay, &ctx, reason).await {
+                Some(stream) => ws_stream = stream,
+                None => return,
+            }
+        }
+    }
 
-                // Use async-openai type for audio buffer append
-                let append_event = InputAudioBufferAppendEvent {
-                    event_id: None,
-                    audio: audio_data,
-                };
-                let client_event: ClientEvent = append_event.into();
+    /// Relay audio and server events over one socket. Returns why it stopped.
+    async fn pump(
+        ws_stream: WsStream,
+        rx: &mut mpsc::Receiver<String>,
+        replay: &mut AudioReplayBuffer,
+        ctx: &ConnectionContext,
+    ) -> String {
+        let (mut ws_write, mut ws_read) = ws_stream.split();
 
-                if let Ok(json) = serde_json::to_string(&client_event) {
-                    if ws_write.send(Message::Text(json)).await.is_err() {
-                        break;
-                    }
+        // Audio captured while the connection was down
+        let mut pending = replay.take().into_iter();
+        while let Some(audio) = pending.next() {
+            if let Err(e) = Self::send_audio_event(&mut ws_write, audio.clone()).await {
+                replay.push(audio);
+                for audio in pending {
+                    replay.push(audio);
                 }
+                return format!("Failed to replay audio: {}", e);
             }
-        });
-        self.write_handle = Some(write_handle);
-
-        // Spawn task to handle incoming messages
-        let on_transcript_clone = on_transcript.clone();
-        let on_response_clone = on_response.clone();
-        let on_audio_clone = on_audio.clone();
-        let read_handle = tokio::spawn(async move {
-            while let Some(msg) = ws_read.next().await {
-                if !*is_active.lock().await {
-                    break;
-                }
+        }
 
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        // Try parsing with async-openai first, fall back to raw JSON
-                        match serde_json::from_str::<ServerEvent>(&text) {
-                            Ok(event) => {
-                                Self::handle_server_event(
-                                    event,
-                                    &transcript,
-                                    &on_transcript_clone,
-                                    &on_response_clone,
-                                )
-                                .await;
-                            }
-                            Err(_) => {
-                                // async-openai types don't match all API responses
-                                // Handle events manually via raw JSON
-                                if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&text) {
-                                    Self::handle_raw_event(
-                                        &raw,
-                                        &transcript,
-                                        &on_transcript_clone,
-                                        &on_response_clone,
-                                        &on_audio_clone,
-                                    )
-                                    .await;
-                                }
-                            }
-                        }
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        ping.tick().await;
+
+        loop {
+            tokio::select! {
+                audio = rx.recv() => {
+                    let Some(audio) = audio else {
+                        return "Session closed".to_string();
+                    };
+                    if !*ctx.is_active.lock().await {
+                        return "Session closed".to_string();
+                    }
+                    if let Err(e) = Self::send_audio_event(&mut ws_write, audio.clone()).await {
+                        replay.push(audio);
+                        return format!("Failed to send audio: {}", e);
                     }
-                    Ok(Message::Close(_)) => {
+                }
+                msg = ws_read.next() => match msg {
+                    Some(Ok(Message::Text(text))) => Self::handle_text(&text, ctx).await,
+                    Some(Ok(Message::Pong(_))) => ctx.health.pong_received(),
+                    Some(Ok(Message::Close(frame))) => {
                         println!("[Voice] WebSocket closed");
-                        break;
+                        return match frame {
+                            Some(frame) if !frame.reason.is_empty() => {
+                                format!("Server closed the connection: {}", frame.reason)
+                            }
+                            _ => "Server closed the connection".to_string(),
+                        };
                     }
-                    Err(e) => {
+                    Some(Err(e)) => {
                         eprintln!("[Voice] WebSocket error: {}", e);
-                        break;
+                        return format!("WebSocket error: {}", e);
+                    }
+                    None => return "Connection lost".to_string(),
+                    Some(Ok(_)) => {}
+                },
+                _ = ping.tick() => {
+                    if !ctx.health.ping_sent() {
+                        return "Server stopped responding".to_string();
+                    }
+                    if let Err(e) = ws_write.send(Message::Ping(Vec::new())).await {
+                        return format!("Failed to ping server: {}", e);
                     }
-                    _ => {}
                 }
             }
-        });
-        self.read_handle = Some(read_handle);
+        }
+    }
 
-        Ok(())
+    /// Reconnect with exponential backoff, buffering audio meanwhile.
+    /// Returns None when the session stopped or every attempt failed.
+    async fn reconnect(
+        rx: &mut mpsc::Receiver<String>,
+        replay: &mut AudioReplayBuffer,
+        ctx: &ConnectionContext,
+        mut reason: String,
+    ) -> Option<WsStream> {
+        eprintln!("[Voice] Connection lost: {}", reason);
+
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            ctx.set_state(ConnectionState::Reconnecting, attempt, Some(reason.clone()));
+
+            // Keep accepting audio while waiting so the frontend never blocks
+            let delay = tokio::time::sleep(backoff_delay(attempt));
+            tokio::pin!(delay);
+            loop {
+                tokio::select! {
+                    _ = &mut delay => break,
+                    audio = rx.recv() => match audio {
+                        Some(audio) => replay.push(audio),
+                        None => return None,
+                    },
+                }
+            }
+            if !*ctx.is_active.lock().await {
+                return None;
+            }
+
+            match Self::open_socket(&ctx.api_key, &ctx.model).await {
+                Ok(stream) => {
+                    println!("[Voice] Reconnected after {} attempt(s)", attempt);
+                    ctx.health.reconnected();
+                    ctx.set_state(ConnectionState::Connected, 0, None);
+                    return Some(stream);
+                }
+                Err(e) => {
+                    eprintln!("[Voice] Reconnect attempt {} failed: {}", attempt, e);
+                    reason = e;
+                }
+            }
+        }
+
+        *ctx.is_active.lock().await = false;
+        ctx.set_state(
+            ConnectionState::Failed,
+            MAX_RECONNECT_ATTEMPTS,
+            Some(format!(
+                "Voice connection lost and could not be restored after {} attempts: {}",
+                MAX_RECONNECT_ATTEMPTS, reason
+            )),
+        );
+        None
+    }
+
+    async fn send_audio_event(
+        ws_write: &mut SplitSink<WsStream, Message>,
+        audio: String,
+    ) -> Result<(), String> {
+        // Use async-openai type for audio buffer append
+        let append_event = InputAudioBufferAppendEvent {
+            event_id: None,
+            audio,
+        };
+        let client_event: ClientEvent = append_event.into();
+        let json = serde_json::to_string(&client_event)
+            .map_err(|e| format!("Failed to serialize audio event: {}", e))?;
+        ws_write
+            .send(Message::Text(json))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn handle_text(text: &str, ctx: &ConnectionContext) {
+        // Try parsing with async-openai first, fall back to raw JSON
+        match serde_json::from_str::<ServerEvent>(text) {
+            Ok(event) => {
+                Self::handle_server_event(
+                    event,
+                    &ctx.transcript,
+                    &ctx.on_transcript,
+                    &ctx.on_response,
+                )
+                .await;
+            }
+            Err(_) => {
+                // async-openai types don't match all API responses
+                // Handle events manually via raw JSON
+                if let Ok(raw) = serde_json::from_str::<serde_json::Value>(text) {
+                    Self::handle_raw_event(
+                        &raw,
+                        &ctx.transcript,
+                        &ctx.on_transcript,
+                        &ctx.on_response,
+                        &ctx.on_audio,
+                    )
+                    .await;
+                }
+            }
+        }
     }
 
     async fn handle_server_event(
@@ -348,13 +520,11 @@ impl VoiceSession {
         *self.is_active.lock().await = false;
         self.ws_sender = None;
 
-        // Abort WebSocket handler tasks to prevent resource leaks
-        if let Some(handle) = self.write_handle.take() {
-            handle.abort();
-        }
-        if let Some(handle) = self.read_handle.take() {
+        // Abort the connection task to prevent resource leaks
+        if let Some(handle) = self.connection_handle.take() {
             handle.abort();
         }
+        self.health.set_state(ConnectionState::Closed, None);
 
         let transcript = self.transcript.lock().await.clone();
         *self.transcript.lock().await = String::new();
@@ -365,6 +535,11 @@ impl VoiceSession {
     pub async fn is_active(&self) -> bool {
         *self.is_active.lock().await
     }
+
+    /// Connection state, uptime, reconnect count and latency
+    pub fn health(&self) -> VoiceSessionHealth {
+        self.health.snapshot()
+    }
 }
 
 impl Default for VoiceSession {
@@ -375,11 +550,8 @@ impl Default for VoiceSession {
 
 impl Drop for VoiceSession {
     fn drop(&mut self) {
-        // Abort any running tasks to prevent resource leaks
-        if let Some(handle) = self.write_handle.take() {
-            handle.abort();
-        }
-        if let Some(handle) = self.read_handle.take() {
+        // Abort the connection task to prevent resource leaks
+        if let Some(handle) = self.connection_handle.take() {
             handle.abort();
         }
     }
@@ -5,6 +5,8 @@
 
 use async_trait::async_trait;
 
+use crate::commands::env_registry;
+
 /// Common trait for all voice session types.
 ///
 /// This trait captures the shared behavior across Voice, Discuss, and Attention sessions,
@@ -63,12 +65,15 @@ pub struct AttentionTimeoutEvent {
 
 use std::sync::Arc;
 
+use super::connection::VoiceConnectionStateEvent;
+
 /// Arc-wrapped callback type aliases for use in async contexts.
 pub type TranscriptCb = Arc<dyn Fn(String) + Send + Sync>;
 pub type ResponseCb = Arc<dyn Fn(String) + Send + Sync>;
 pub type AudioCb = Arc<dyn Fn(String) + Send + Sync>;
 pub type ToolCb = Arc<dyn Fn(String, String, String) -> String + Send + Sync>;
 pub type SimpleCb = Arc<dyn Fn() + Send + Sync>;
+pub type ConnectionStateCb = Arc<dyn Fn(VoiceConnectionStateEvent) + Send + Sync>;
 
 /// Unified callbacks for voice sessions.
 ///
@@ -90,6 +95,8 @@ pub struct VoiceCallbacks {
     pub on_user_turn_complete: Option<SimpleCb>,
     /// Called when assistant turn completes (discuss only)
     pub on_assistant_turn_complete: Option<SimpleCb>,
+    /// Called when the connection drops, reconnects or gives up (voice only)
+    pub on_connection_state: Option<ConnectionStateCb>,
 }
 
 impl VoiceCallbacks {
@@ -108,6 +115,7 @@ impl VoiceCallbacks {
             on_timeout: None,
             on_user_turn_complete: None,
             on_assistant_turn_complete: None,
+            on_connection_state: None,
         }
     }
 
@@ -143,6 +151,15 @@ impl VoiceCallbacks {
         self.on_assistant_turn_complete = Some(Arc::new(on_assistant_turn_complete));
         self
     }
+
+    /// Add connection state callback (for voice mode)
+    pub fn with_connection_state<C>(mut self, on_connection_state: C) -> Self
+    where
+        C: Fn(VoiceConnectionStateEvent) + Send + Sync + 'static,
+    {
+        self.on_connection_state = Some(Arc::new(on_connection_state));
+        self
+    }
 }
 
 /// Voice settings from commander personality
@@ -165,6 +182,7 @@ impl Default for VoiceSettings {
 
 /// Helper to get API key from environment.
 pub fn get_api_key() -> Result<String, String> {
-    std::env::var("OPENAI_API_KEY")
+    env_registry::OPENAI_API_KEY
+        .var()
         .map_err(|_| "OPENAI_API_KEY not set. Please set it in your .env file.".to_string())
 }
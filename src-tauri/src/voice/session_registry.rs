@@ -4,6 +4,7 @@
 // registry pattern that provides type-safe access to each session type.
 
 use super::attention::AttentionSession;
+use super::connection::VoiceSessionHealth;
 use super::discuss::DiscussSession;
 use super::realtime::VoiceSession;
 use super::session_manager::VoiceStatus;
@@ -52,6 +53,18 @@ impl SessionRegistry {
     pub fn attention(&self) -> &Arc<Mutex<Option<AttentionSession>>> {
         &self.attention
     }
+
+    /// Connection health of the voice session, if one was started.
+    ///
+    /// A session that failed to reconnect keeps reporting its failure until
+    /// it's stopped or replaced.
+    pub async fn voice_health(&self) -> Option<VoiceSessionHealth> {
+        self.voice
+            .lock()
+            .await
+            .as_ref()
+            .map(|session| session.health())
+    }
 }
 
 /// Session type discriminant for unified operations.
@@ -2,15 +2,144 @@
 //!
 //! This module handles executing tools called by the OpenAI Realtime model
 //! during Discuss mode. Routes through the real System Commander (MetaAgent).
+//!
+//! Before a message reaches the MetaAgent, a small intent layer checks whether it
+//! is a direct control command ("stop agent three", "what did I spend today",
+//! "status of the build pipeline"). Those are executed straight against the
+//! backend; everything else falls through to the normal Mission Control flow.
+//!
+//! Utterances starting with "answer" ("answer: yes, use postgres") answer the
+//! commander's pending AskUserQuestion. They are handled first because the
+//! MetaAgent stays locked while it waits for that answer.
 
 use crate::agent_manager::AgentManager;
+use crate::ai_client::{AIClient, ContentBlock, Message};
+use crate::auto_pipeline::AutoPipelineManager;
+use crate::meta_agent::tools::{answer_pending_question, AnswerSource, PendingQuestion};
 use crate::meta_agent::MetaAgent;
+use crate::observer_mode;
+use crate::types::{AgentInfo, AgentStatus};
 use crate::utils::string::truncate_with_ellipsis;
-use serde_json::Value;
-use std::sync::Arc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use tauri::AppHandle;
 use tokio::sync::Mutex;
 
+/// How long a destructive intent waits for a spoken confirmation
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+const INTENT_PROMPT: &str = r#"You classify voice commands for a coding-agent control panel.
+Return ONLY a JSON object, no prose. Use one of these shapes:
+{"intent": "list_agents"}
+{"intent": "stop_agent", "target": {"ordinal": 3}}
+{"intent": "pause_agent", "target": {"name": "frontend"}}
+{"intent": "resume_agent", "target": {"ordinal": 1}}
+{"intent": "today_cost"}
+{"intent": "pipeline_status", "query": "build"}
+{"intent": "none"}
+
+Rules:
+- "target.ordinal" is the 1-based agent number the user said ("agent three" -> 3).
+- "target.name" is a title or project name the user used to refer to an agent.
+- "pipeline_status.query" is optional text identifying the pipeline.
+- Use "none" for anything else: questions, planning, creating agents, or conversation.
+
+Utterance: "{utterance}""#;
+
+/// A control command recognized in a voice utterance
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "intent", rename_all = "snake_case")]
+pub enum VoiceIntent {
+    ListAgents,
+    StopAgent {
+        #[serde(default)]
+        target: AgentTarget,
+    },
+    PauseAgent {
+        #[serde(default)]
+        target: AgentTarget,
+    },
+    ResumeAgent {
+        #[serde(default)]
+        target: AgentTarget,
+    },
+    TodayCost,
+    PipelineStatus {
+        #[serde(default)]
+        query: Option<String>,
+    },
+    None,
+}
+
+impl VoiceIntent {
+    /// Intents that need a spoken confirmation before they run
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, VoiceIntent::StopAgent { .. })
+    }
+
+    /// Intents that change something, refused in observer mode
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            VoiceIntent::StopAgent { .. }
+                | VoiceIntent::PauseAgent { .. }
+                | VoiceIntent::ResumeAgent { .. }
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            VoiceIntent::ListAgents => "list_agents",
+            VoiceIntent::StopAgent { .. } => "stop_agent",
+            VoiceIntent::PauseAgent { .. } => "pause_agent",
+            VoiceIntent::ResumeAgent { .. } => "resume_agent",
+            VoiceIntent::TodayCost => "today_cost",
+            VoiceIntent::PipelineStatus { .. } => "pipeline_status",
+            VoiceIntent::None => "none",
+        }
+    }
+}
+
+/// How the user referred to an agent
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct AgentTarget {
+    #[serde(default)]
+    pub ordinal: Option<usize>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// The agent an intent was resolved to
+struct TargetAgent {
+    id: String,
+    label: String,
+}
+
+impl TargetAgent {
+    fn of(agent: &AgentInfo) -> Self {
+        TargetAgent {
+            id: agent.id.clone(),
+            label: agent_label(agent),
+        }
+    }
+}
+
+/// A destructive intent waiting for "yes" / "no", with the agent the
+/// question named (ordinals may point elsewhere by the time it is answered)
+struct PendingIntent {
+    intent: VoiceIntent,
+    agent: TargetAgent,
+    transcript: String,
+    created_at: Instant,
+}
+
+fn pending_intent() -> &'static std::sync::Mutex<Option<PendingIntent>> {
+    static PENDING: OnceLock<std::sync::Mutex<Option<PendingIntent>>> = OnceLock::new();
+    PENDING.get_or_init(|| std::sync::Mutex::new(None))
+}
+
 /// Execute a tool and return the result as a string
 /// This version routes through the real System Commander (MetaAgent)
 pub async fn execute_tool_with_state(
@@ -18,6 +147,8 @@ pub async fn execute_tool_with_state(
     args: &str,
     meta_agent: Arc<Mutex<MetaAgent>>,
     agent_manager: Arc<Mutex<AgentManager>>,
+    auto_pipeline_manager: Option<Arc<Mutex<AutoPipelineManager>>>,
+    pending_question: Arc<Mutex<Option<PendingQuestion>>>,
     app_handle: AppHandle,
 ) -> String {
     let parsed_args: Value = match serde_json::from_str(args) {
@@ -29,6 +160,18 @@ pub async fn execute_tool_with_state(
 
     match name {
         "talk_to_mission_control" => {
+            let message = parsed_args
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("");
+            if let Some(answer) = parse_spoken_answer(message) {
+                return answer_question_by_voice(&pending_question, answer).await;
+            }
+            if let Some(reply) =
+                try_handle_intent(message, &agent_manager, &auto_pipeline_manager).await
+            {
+                return reply;
+            }
             execute_talk_to_mission_control(&parsed_args, meta_agent, agent_manager, app_handle)
                 .await
         }
@@ -36,6 +179,406 @@ pub async fn execute_tool_with_state(
     }
 }
 
+// ============================================================================
+// Question Answers
+// ============================================================================
+
+/// Extract the answer from an utterance like "answer: yes, use postgres"
+fn parse_spoken_answer(transcript: &str) -> Option<String> {
+    let trimmed = transcript.trim();
+    let rest = ["my answer is", "answer"].iter().find_map(|prefix| {
+        trimmed
+            .get(..prefix.len())
+            .filter(|head| head.eq_ignore_ascii_case(prefix))
+            .map(|_| &trimmed[prefix.len()..])
+    })?;
+
+    // Require a separator so "answered" or "answers" don't match
+    if !rest.starts_with([':', ',', ' ', '-']) {
+        return None;
+    }
+    let answer = rest
+        .trim_start_matches([':', ',', ' ', '-'])
+        .trim()
+        .trim_end_matches('.');
+    (!answer.is_empty()).then(|| answer.to_string())
+}
+
+/// Answer the commander's pending question and return a short spoken reply
+async fn answer_question_by_voice(
+    pending_question: &Arc<Mutex<Option<PendingQuestion>>>,
+    answer: String,
+) -> String {
+    println!("[Tools] Voice answer to pending question: {}", answer);
+    match answer_pending_question(pending_question, None, answer, AnswerSource::Voice).await {
+        Ok(()) => "Got it, I passed your answer to the commander.".to_string(),
+        Err(e) => format!("I couldn't deliver that answer: {}", e),
+    }
+}
+
+// ============================================================================
+// Intent Layer
+// ============================================================================
+
+/// Try to handle an utterance as a direct control command.
+/// Returns None when it should fall through to Mission Control.
+async fn try_handle_intent(
+    transcript: &str,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    auto_pipeline_manager: &Option<Arc<Mutex<AutoPipelineManager>>>,
+) -> Option<String> {
+    if transcript.trim().is_empty() {
+        return None;
+    }
+
+    // Answer to a pending confirmation?
+    let pending = pending_intent().lock().unwrap().take();
+    if let Some(pending) = pending {
+        if pending.created_at.elapsed() < CONFIRMATION_TIMEOUT {
+            if is_affirmative(transcript) {
+                let reply = execute_confirmed(&pending, agent_manager).await;
+                log_intent(agent_manager, &pending.intent, &pending.transcript, &reply).await;
+                return Some(reply);
+            }
+            if is_negative(transcript) {
+                return Some("Okay, cancelled.".to_string());
+            }
+        }
+        // Anything else abandons the pending confirmation
+    }
+
+    let intent = extract_intent(transcript).await?;
+    if intent == VoiceIntent::None {
+        return None;
+    }
+
+    // Observer mode refuses it right away instead of after a confirmation
+    if intent.is_destructive() && observer_refusal(&intent, observer_mode::enabled()).is_none() {
+        let (description, agent) = match describe_destructive(&intent, agent_manager).await {
+            Ok(described) => described,
+            Err(e) => return Some(e),
+        };
+        *pending_intent().lock().unwrap() = Some(PendingIntent {
+            intent,
+            agent,
+            transcript: transcript.to_string(),
+            created_at: Instant::now(),
+        });
+        return Some(format!(
+            "{} Say yes to confirm or no to cancel.",
+            description
+        ));
+    }
+
+    let reply = execute_intent(&intent, agent_manager, auto_pipeline_manager).await;
+    log_intent(agent_manager, &intent, transcript, &reply).await;
+    Some(reply)
+}
+
+/// Classify an utterance with the light model. Any failure means "no intent".
+async fn extract_intent(transcript: &str) -> Option<VoiceIntent> {
+    let client = AIClient::light_from_env().ok()?;
+    let prompt = INTENT_PROMPT.replace("{utterance}", transcript);
+
+    let response = client
+        .send_message(vec![Message {
+            role: "user".to_string(),
+            content: prompt,
+        }])
+        .await
+        .map_err(|e| eprintln!("[Tools] Intent extraction failed: {}", e))
+        .ok()?;
+
+    let text = response
+        .content
+        .iter()
+        .filter_map(|block| {
+            if let ContentBlock::Text { text } = block {
+                Some(text.as_str())
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    parse_intent(&text)
+}
+
+/// Parse the model's JSON reply, tolerating surrounding prose or code fences
+fn parse_intent(text: &str) -> Option<VoiceIntent> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    serde_json::from_str(&text[start..=end]).ok()
+}
+
+fn is_affirmative(transcript: &str) -> bool {
+    const YES: &[&str] = &["yes", "yeah", "yep", "confirm", "do it", "go ahead", "sure"];
+    let t = normalize(transcript);
+    YES.iter()
+        .any(|w| t == *w || t.starts_with(&format!("{} ", w)))
+}
+
+fn is_negative(transcript: &str) -> bool {
+    const NO: &[&str] = &["no", "nope", "cancel", "don't", "stop that", "never mind"];
+    let t = normalize(transcript);
+    NO.iter()
+        .any(|w| t == *w || t.starts_with(&format!("{} ", w)))
+}
+
+fn normalize(s: &str) -> String {
+    s.trim()
+        .trim_end_matches(['.', '!', '?'])
+        .to_lowercase()
+        .replace(',', "")
+}
+
+/// Agents in the order they are read out, so ordinals stay consistent
+async fn ordered_agents(agent_manager: &Arc<Mutex<AgentManager>>) -> Vec<AgentInfo> {
+    let mut agents = agent_manager.lock().await.list_agents().await;
+    agents.sort_by(|a, b| agent_label(a).cmp(&agent_label(b)).then(a.id.cmp(&b.id)));
+    agents
+}
+
+fn agent_label(agent: &AgentInfo) -> String {
+    agent.title.clone().unwrap_or_else(|| {
+        std::path::Path::new(&agent.working_dir)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| agent.working_dir.clone())
+    })
+}
+
+/// Resolve an ordinal ("agent three") or name to a single agent
+fn resolve_target<'a>(
+    agents: &'a [AgentInfo],
+    target: &AgentTarget,
+) -> Result<&'a AgentInfo, String> {
+    if let Some(n) = target.ordinal {
+        return agents
+            .get(n.wrapping_sub(1))
+            .ok_or_else(|| format!("There is no agent {}. I see {} agents.", n, agents.len()));
+    }
+
+    let name = target
+        .name
+        .as_deref()
+        .map(str::to_lowercase)
+        .ok_or_else(|| "Which agent? Say its number or name.".to_string())?;
+    let matches: Vec<&AgentInfo> = agents
+        .iter()
+        .filter(|a| {
+            agent_label(a).to_lowercase().contains(&name)
+                || a.working_dir.to_lowercase().contains(&name)
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [agent] => Ok(agent),
+        [] => Err(format!("I couldn't find an agent called {}.", name)),
+        _ => Err(format!(
+            "{} agents match {}. Say the agent number instead.",
+            matches.len(),
+            name
+        )),
+    }
+}
+
+/// The confirmation question for a destructive intent and the agent it names
+async fn describe_destructive(
+    intent: &VoiceIntent,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+) -> Result<(String, TargetAgent), String> {
+    let VoiceIntent::StopAgent { target } = intent else {
+        return Err("I can't confirm that.".to_string());
+    };
+    let agents = ordered_agents(agent_manager).await;
+    let agent = TargetAgent::of(resolve_target(&agents, target)?);
+    Ok((format!("Stop {}?", agent.label), agent))
+}
+
+/// Check that the agent a confirmation named is still running
+fn confirmed_agent_running(agents: &[AgentInfo], agent: &TargetAgent) -> Result<(), String> {
+    match agents.iter().find(|a| a.id == agent.id) {
+        Some(a) if a.status != AgentStatus::Stopped => Ok(()),
+        _ => Err(format!(
+            "{} isn't running any more, so I didn't do anything.",
+            agent.label
+        )),
+    }
+}
+
+/// Run a confirmed intent against the agent the question named
+async fn execute_confirmed(
+    pending: &PendingIntent,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+) -> String {
+    if let Some(refusal) = observer_refusal(&pending.intent, observer_mode::enabled()) {
+        return refusal;
+    }
+    let agents = ordered_agents(agent_manager).await;
+    if let Err(e) = confirmed_agent_running(&agents, &pending.agent) {
+        return e;
+    }
+    act_on_agent(&pending.intent, &pending.agent, agent_manager).await
+}
+
+/// Stop, pause or resume an agent and return a short spoken reply
+async fn act_on_agent(
+    intent: &VoiceIntent,
+    agent: &TargetAgent,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+) -> String {
+    let (result, verb) = match intent {
+        VoiceIntent::StopAgent { .. } => {
+            // Drained after releasing the lock
+            let begun = agent_manager
+                .lock()
+                .await
+                .begin_stop(&agent.id, false, None)
+                .await;
+            let result = match begun {
+                Ok(pending) => pending.finish().await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            (result, "Stopped")
+        }
+        VoiceIntent::PauseAgent { .. } => {
+            let manager = agent_manager.lock().await;
+            (manager.set_agent_paused(&agent.id, true).await, "Paused")
+        }
+        _ => {
+            let manager = agent_manager.lock().await;
+            (manager.set_agent_paused(&agent.id, false).await, "Resumed")
+        }
+    };
+    match result {
+        Ok(()) => format!("{} {}.", verb, agent.label),
+        Err(e) => format!("Couldn't do that for {}: {}", agent.label, e),
+    }
+}
+
+/// The reply refusing an intent that would change something while observer
+/// mode is on
+fn observer_refusal(intent: &VoiceIntent, observer: bool) -> Option<String> {
+    (observer && intent.is_mutating()).then(|| observer_mode::OBSERVER_MODE_MESSAGE.to_string())
+}
+
+/// Run an intent against the backend and return a short spoken reply
+async fn execute_intent(
+    intent: &VoiceIntent,
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    auto_pipeline_manager: &Option<Arc<Mutex<AutoPipelineManager>>>,
+) -> String {
+    if let Some(refusal) = observer_refusal(intent, observer_mode::enabled()) {
+        return refusal;
+    }
+    match intent {
+        VoiceIntent::ListAgents => {
+            let agents = ordered_agents(agent_manager).await;
+            if agents.is_empty() {
+                return "No agents are running.".to_string();
+            }
+            let lines: Vec<String> = agents
+                .iter()
+                .enumerate()
+                .map(|(i, a)| {
+                    format!(
+                        "Agent {}: {}, {}.",
+                        i + 1,
+                        agent_label(a),
+                        a.status.as_str()
+                    )
+                })
+                .collect();
+            lines.join(" ")
+        }
+        VoiceIntent::StopAgent { target }
+        | VoiceIntent::PauseAgent { target }
+        | VoiceIntent::ResumeAgent { target } => {
+            let agents = ordered_agents(agent_manager).await;
+            let agent = match resolve_target(&agents, target) {
+                Ok(a) => TargetAgent::of(a),
+                Err(e) => return e,
+            };
+            act_on_agent(intent, &agent, agent_manager).await
+        }
+        VoiceIntent::TodayCost => {
+            let runs_db = agent_manager.lock().await.runs_db.clone();
+            let Some(runs_db) = runs_db else {
+                return "Cost tracking isn't available.".to_string();
+            };
+            match runs_db.get_today_cost().await {
+                Ok(cost) => format!("You've spent ${:.2} today.", cost),
+                Err(e) => format!("I couldn't read today's cost: {}", e),
+            }
+        }
+        VoiceIntent::PipelineStatus { query } => {
+            let Some(manager) = auto_pipeline_manager else {
+                return "Pipelines aren't available.".to_string();
+            };
+            let mut pipelines = manager.lock().await.list_pipelines().await;
+            if let Some(q) = query.as_deref().map(str::to_lowercase) {
+                pipelines.retain(|p| {
+                    p.user_request.to_lowercase().contains(&q)
+                        || p.working_dir.to_lowercase().contains(&q)
+                });
+            }
+            pipelines.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            match pipelines.first() {
+                Some(p) => format!(
+                    "The pipeline for \"{}\" is {}, iteration {} of {}.",
+                    truncate_with_ellipsis(&p.user_request, 60),
+                    p.status,
+                    p.current_iteration,
+                    p.max_iterations
+                ),
+                None => "I couldn't find a matching pipeline.".to_string(),
+            }
+        }
+        VoiceIntent::None => String::new(),
+    }
+}
+
+/// Record every executed intent together with the transcript that triggered it
+async fn log_intent(
+    agent_manager: &Arc<Mutex<AgentManager>>,
+    intent: &VoiceIntent,
+    transcript: &str,
+    reply: &str,
+) {
+    println!(
+        "[Tools] Voice intent {} from \"{}\": {}",
+        intent.name(),
+        transcript,
+        reply
+    );
+
+    let logger = agent_manager.lock().await.logger.clone();
+    if let Some(logger) = logger {
+        let metadata = json!({
+            "intent": intent.name(),
+            "transcript": transcript,
+            "reply": reply,
+        });
+        let _ = logger
+            .info(
+                "voice_intent",
+                &format!("Executed voice intent {}", intent.name()),
+                None,
+                Some(metadata.to_string()),
+            )
+            .await;
+    }
+}
+
+// ============================================================================
+// Mission Control
+// ============================================================================
+
 /// Talk to Mission Control - the central command system
 /// This routes messages through the real MetaAgent/System Commander
 async fn execute_talk_to_mission_control(
@@ -102,5 +645,144 @@ async fn execute_talk_to_mission_control(
 
 #[cfg(test)]
 mod tests {
-    // Tests removed - they require full state setup now
+    use super::*;
+    use crate::types::AgentSource;
+
+    fn agent(id: &str, title: &str) -> AgentInfo {
+        AgentInfo {
+            id: id.to_string(),
+            working_dir: format!("/work/{}", title),
+            status: AgentStatus::Running,
+            session_id: None,
+            last_activity: None,
+            is_processing: false,
+            pending_input: false,
+            github_context: None,
+            source: AgentSource::UI,
+            pooled: None,
+            title: Some(title.to_string()),
+            complexity: None,
+            latest_note: None,
+            label: None,
+            group_ids: Vec::new(),
+            hooks_missing: false,
+            permission_profile: None,
+            model: None,
+            worktree: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_intent_with_code_fence() {
+        let text = "```json\n{\"intent\": \"stop_agent\", \"target\": {\"ordinal\": 3}}\n```";
+        assert_eq!(
+            parse_intent(text),
+            Some(VoiceIntent::StopAgent {
+                target: AgentTarget {
+                    ordinal: Some(3),
+                    name: None
+                }
+            })
+        );
+        assert_eq!(
+            parse_intent(r#"{"intent": "none"}"#),
+            Some(VoiceIntent::None)
+        );
+        assert_eq!(parse_intent("not json"), None);
+    }
+
+    #[test]
+    fn test_resolve_target_by_ordinal_and_name() {
+        let agents = vec![agent("a", "backend"), agent("b", "frontend")];
+
+        let by_ordinal = AgentTarget {
+            ordinal: Some(2),
+            name: None,
+        };
+        assert_eq!(resolve_target(&agents, &by_ordinal).unwrap().id, "b");
+
+        let by_name = AgentTarget {
+            ordinal: None,
+            name: Some("Backend".to_string()),
+        };
+        assert_eq!(resolve_target(&agents, &by_name).unwrap().id, "a");
+
+        let missing = AgentTarget {
+            ordinal: Some(0),
+            name: None,
+        };
+        assert!(resolve_target(&agents, &missing).is_err());
+
+        let ambiguous = AgentTarget {
+            ordinal: None,
+            name: Some("end".to_string()),
+        };
+        assert!(resolve_target(&agents, &ambiguous).is_err());
+    }
+
+    #[test]
+    fn test_confirmation_acts_on_the_agent_it_named() {
+        // "Stop agent 2?" named frontend; then another agent appeared ahead of it
+        let agents = vec![agent("a", "backend"), agent("b", "frontend")];
+        let second = AgentTarget {
+            ordinal: Some(2),
+            name: None,
+        };
+        let named = TargetAgent::of(resolve_target(&agents, &second).unwrap());
+        let agents = vec![
+            agent("a", "backend"),
+            agent("c", "docs"),
+            agent("b", "frontend"),
+        ];
+        assert_eq!(named.id, "b");
+        assert!(confirmed_agent_running(&agents, &named).is_ok());
+
+        let mut stopped = agents.clone();
+        stopped[2].status = AgentStatus::Stopped;
+        let err = confirmed_agent_running(&stopped, &named).unwrap_err();
+        assert!(err.contains("frontend"), "{}", err);
+        assert!(confirmed_agent_running(&agents[..2], &named).is_err());
+    }
+
+    #[test]
+    fn test_observer_mode_refuses_voice_stop() {
+        let stop = VoiceIntent::StopAgent {
+            target: AgentTarget {
+                ordinal: Some(1),
+                name: None,
+            },
+        };
+        assert_eq!(
+            observer_refusal(&stop, true).as_deref(),
+            Some(observer_mode::OBSERVER_MODE_MESSAGE)
+        );
+        assert_eq!(observer_refusal(&stop, false), None);
+        assert_eq!(observer_refusal(&VoiceIntent::ListAgents, true), None);
+        assert_eq!(observer_refusal(&VoiceIntent::TodayCost, true), None);
+    }
+
+    #[test]
+    fn test_parse_spoken_answer() {
+        assert_eq!(
+            parse_spoken_answer("Answer: yes, use postgres."),
+            Some("yes, use postgres".to_string())
+        );
+        assert_eq!(
+            parse_spoken_answer("my answer is the second option"),
+            Some("the second option".to_string())
+        );
+        assert_eq!(parse_spoken_answer("answered all the emails"), None);
+        assert_eq!(parse_spoken_answer("answer:"), None);
+        assert_eq!(parse_spoken_answer("stop agent three"), None);
+    }
+
+    #[test]
+    fn test_confirmation_words() {
+        assert!(is_affirmative("Yes."));
+        assert!(is_affirmative("yes, do it"));
+        assert!(!is_affirmative("yesterday's build"));
+        assert!(is_negative("No"));
+        assert!(is_negative("never mind"));
+        assert!(!is_negative("notes please"));
+    }
 }
@@ -0,0 +1,107 @@
+// Workspaces - Named scopes for the meta-agent's working context
+//
+// A workspace owns its meta-agent conversations, memory directory,
+// personality (the cached personalized prompt), directory allowlist, and the
+// runs started while it is active, so cost can be rolled up per workspace.
+// Conversations and runs carry a `workspace_id` column; the files live under
+// `workspaces/<id>/` in the config and data directories. The default
+// workspace keeps the original locations so existing data stays where it is.
+//
+// Exactly one workspace is active at a time. Switching (see
+// MetaAgent::switch_workspace) takes effect without restarting the app.
+
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+use crate::commands::config_loader::get_config_dir;
+
+/// Workspace that existing data belongs to
+pub const DEFAULT_WORKSPACE_ID: &str = "default";
+
+/// Directory under the config and data directories holding named workspaces
+const WORKSPACES_DIR: &str = "workspaces";
+
+static ACTIVE_WORKSPACE: OnceLock<RwLock<String>> = OnceLock::new();
+
+fn active() -> &'static RwLock<String> {
+    ACTIVE_WORKSPACE.get_or_init(|| RwLock::new(DEFAULT_WORKSPACE_ID.to_string()))
+}
+
+/// ID of the active workspace
+pub fn active_workspace_id() -> String {
+    active().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Make a workspace the active one
+pub fn set_active_workspace(workspace_id: &str) {
+    *active().write().unwrap_or_else(|e| e.into_inner()) = workspace_id.to_string();
+}
+
+/// Config directory for a workspace (prompt cache, directory allowlist)
+pub fn config_dir(workspace_id: &str) -> Option<PathBuf> {
+    let base = get_config_dir().ok()?;
+    Some(scoped(base, workspace_id))
+}
+
+/// Data directory for a workspace (memory)
+pub fn data_dir(workspace_id: &str) -> Option<PathBuf> {
+    let base = dirs::data_local_dir()?.join("claude-commander");
+    Some(scoped(base, workspace_id))
+}
+
+fn scoped(base: PathBuf, workspace_id: &str) -> PathBuf {
+    if workspace_id == DEFAULT_WORKSPACE_ID {
+        base
+    } else {
+        base.join(WORKSPACES_DIR).join(workspace_id)
+    }
+}
+
+/// Derive a workspace ID from its name ("Acme Corp" -> "acme-corp").
+///
+/// IDs name directories, so only ASCII letters, digits and dashes are kept.
+pub fn workspace_id_from_name(name: &str) -> Result<String, String> {
+    let mut id = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    let id = id.trim_end_matches('-').to_string();
+
+    if id.is_empty() {
+        return Err(format!(
+            "Workspace name '{}' needs at least one letter or digit",
+            name.trim()
+        ));
+    }
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_id_from_name() {
+        assert_eq!(workspace_id_from_name("Acme Corp").unwrap(), "acme-corp");
+        assert_eq!(
+            workspace_id_from_name("  Client: Globex / 2026 ").unwrap(),
+            "client-globex-2026"
+        );
+        assert!(workspace_id_from_name("  ").is_err());
+        assert!(workspace_id_from_name("日本").is_err());
+    }
+
+    #[test]
+    fn test_default_workspace_keeps_original_paths() {
+        let base = PathBuf::from("/config/claude-commander");
+        assert_eq!(scoped(base.clone(), DEFAULT_WORKSPACE_ID), base);
+        assert_eq!(
+            scoped(base.clone(), "acme"),
+            base.join("workspaces").join("acme")
+        );
+    }
+}
@@ -0,0 +1,1078 @@
+// Chaos tests for the agent lifecycle
+//
+// Runs AgentManager, the hook server and the auto-pipeline against a fake
+// `claude` script (tests/fixtures/fake_claude.sh) with faults injected at the
+// points exposed by `claude_commander_lib::fault_injection`.
+//
+// Run with: cargo test --features fault-injection --test fault_injection
+
+#![cfg(all(unix, feature = "fault-injection"))]
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use claude_commander_lib::agent_manager::{AgentManager, AgentSpawnOptions};
+use claude_commander_lib::agent_runs_db::{
+    AgentRun, AgentRunsDB, ElevatedCommandPolicy, PermissionMode, PermissionProfile, RunStatus,
+    StopMode,
+};
+use claude_commander_lib::auto_pipeline::AutoPipelineManager;
+use claude_commander_lib::events::AppEventEmitter;
+use claude_commander_lib::fault_injection::{FaultInjector, FaultPoint};
+use claude_commander_lib::meta_agent::SharedResultQueue;
+use claude_commander_lib::types::{AgentSource, AgentStatus, StopOutcome, ToolRestrictions};
+
+/// Event emitter that records everything for later assertions
+#[derive(Default)]
+struct RecordingEmitter {
+    events: std::sync::Mutex<Vec<(String, Value)>>,
+}
+
+impl AppEventEmitter for RecordingEmitter {
+    fn emit(&self, event: &str, payload: Value) -> Result<(), String> {
+        self.events
+            .lock()
+            .unwrap()
+            .push((event.to_string(), payload));
+        Ok(())
+    }
+}
+
+impl RecordingEmitter {
+    fn has_event(&self, name: &str, matches: impl Fn(&Value) -> bool) -> bool {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(event, payload)| event == name && matches(payload))
+    }
+}
+
+/// Point CLAUDE_PATH at the fake CLI (once per test binary)
+fn use_fake_claude() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fake_claude.sh");
+        std::env::set_var("CLAUDE_PATH", script);
+    });
+}
+
+struct Harness {
+    _dir: tempfile::TempDir,
+    working_dir: String,
+    injector: Arc<FaultInjector>,
+    runs_db: Arc<AgentRunsDB>,
+    emitter: Arc<RecordingEmitter>,
+    manager: AgentManager,
+}
+
+impl Harness {
+    fn new() -> Self {
+        use_fake_claude();
+
+        let dir = tempfile::tempdir().unwrap();
+        let working_dir = dir.path().join("repo");
+        std::fs::create_dir(&working_dir).unwrap();
+
+        let injector = Arc::new(FaultInjector::new());
+        let runs_db = Arc::new(
+            AgentRunsDB::new(dir.path().join("agent_runs.db"))
+                .unwrap()
+                .with_fault_hook(injector.clone()),
+        );
+
+        let mut manager = AgentManager::new(0);
+        manager.runs_db = Some(runs_db.clone());
+        manager.set_fault_hook(injector.clone());
+
+        Self {
+            working_dir: working_dir.to_string_lossy().to_string(),
+            _dir: dir,
+            injector,
+            runs_db,
+            emitter: Arc::new(RecordingEmitter::default()),
+            manager,
+        }
+    }
+
+    async fn create_agent(&self) -> Result<String, String> {
+        self.manager
+            .create_agent(
+                self.working_dir.clone(),
+                None,
+                None,
+                AgentSource::Manual,
+                self.emitter.clone(),
+            )
+            .await
+    }
+
+    async fn send_prompt(&self, agent_id: &str, prompt: &str) {
+        self.manager
+            .send_prompt(agent_id, prompt, Some(self.emitter.clone()), None)
+            .await
+            .unwrap();
+    }
+
+    fn git(&self, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&self.working_dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    /// Make the working directory a git repository with one commit
+    fn init_repo(&self) {
+        self.git(&["init", "-q"]);
+        std::fs::write(Path::new(&self.working_dir).join("README.md"), "hello").unwrap();
+        self.git(&["add", "README.md"]);
+        self.git(&["commit", "-q", "-m", "init"]);
+    }
+
+    async fn run(&self, agent_id: &str) -> AgentRun {
+        self.runs_db.get_run(agent_id).await.unwrap().unwrap()
+    }
+
+    async fn agent_status(&self, agent_id: &str) -> Option<AgentStatus> {
+        self.manager
+            .get_agent_info(agent_id)
+            .await
+            .map(|info| info.status)
+    }
+
+    /// Wait until the run is recorded as crashed
+    async fn wait_for_crashed_run(&self, agent_id: &str) -> AgentRun {
+        assert!(
+            wait_until(|| async { self.run(agent_id).await.status == RunStatus::Crashed }).await,
+            "run {} never transitioned to crashed",
+            agent_id
+        );
+        self.run(agent_id).await
+    }
+
+    /// No lock may still be held (or wedged) after a fault
+    async fn assert_locks_free(&self) {
+        let timeout = Duration::from_secs(1);
+        assert!(
+            tokio::time::timeout(timeout, self.manager.agents.lock())
+                .await
+                .is_ok(),
+            "agents lock is still held"
+        );
+        assert!(
+            tokio::time::timeout(timeout, self.manager.session_to_agent.lock())
+                .await
+                .is_ok(),
+            "session map lock is still held"
+        );
+        assert!(
+            tokio::time::timeout(timeout, self.runs_db.get_stats())
+                .await
+                .is_ok_and(|stats| stats.is_ok()),
+            "database connection is unusable"
+        );
+    }
+
+    fn agent_error_emitted(&self, agent_id: &str) -> bool {
+        self.emitter.has_event("agent:status", |payload| {
+            payload["agent_id"] == agent_id && payload["status"] == "error"
+        })
+    }
+}
+
+/// Poll `check` until it holds, giving up after five seconds
+async fn wait_until<F, Fut>(mut check: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    for _ in 0..100 {
+        if check().await {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_spawn_failure_leaves_no_agent_behind() {
+    let harness = Harness::new();
+    harness.injector.arm(FaultPoint::AgentSpawn, 1);
+
+    let err = harness.create_agent().await.unwrap_err();
+    assert!(err.contains("injected fault"), "unexpected error: {}", err);
+    assert!(harness.manager.list_agents().await.is_empty());
+    harness.assert_locks_free().await;
+
+    // The fault only fires once; the next spawn goes through
+    let agent_id = harness.create_agent().await.unwrap();
+    assert_eq!(
+        harness.agent_status(&agent_id).await,
+        Some(AgentStatus::Running)
+    );
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stop_drains_before_killing() {
+    let harness = Harness::new();
+
+    // The fake CLI exits as soon as its stdin closes
+    let agent_id = harness.create_agent().await.unwrap();
+    let mode = harness
+        .manager
+        .stop_agent_with(&agent_id, false, Some(5))
+        .await
+        .unwrap();
+    assert_eq!(mode, StopMode::Graceful);
+    // Exiting during the drain is a stop, not a crash
+    assert!(
+        wait_until(|| async {
+            let run = harness.run(&agent_id).await;
+            run.status == RunStatus::Stopped && run.stop_mode == Some(StopMode::Graceful)
+        })
+        .await
+    );
+
+    let agent_id = harness.create_agent().await.unwrap();
+    let mode = harness
+        .manager
+        .stop_agent_with(&agent_id, true, None)
+        .await
+        .unwrap();
+    assert_eq!(mode, StopMode::Forced);
+    assert_eq!(
+        harness.run(&agent_id).await.stop_mode,
+        Some(StopMode::Forced)
+    );
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stop_all_reports_each_agent() {
+    let harness = Harness::new();
+    let first = harness.create_agent().await.unwrap();
+    let second = harness.create_agent().await.unwrap();
+    harness.manager.stop_agent(&first).await.unwrap();
+
+    // Filters that match nothing stop nothing
+    let emitter = &*harness.emitter;
+    assert!(harness
+        .manager
+        .stop_all(Some(&AgentSource::Pipeline), None, emitter)
+        .await
+        .is_empty());
+    assert!(harness
+        .manager
+        .stop_all(None, Some("/nonexistent"), emitter)
+        .await
+        .is_empty());
+
+    let results = harness
+        .manager
+        .stop_all(
+            Some(&AgentSource::Manual),
+            Some(&harness.working_dir),
+            emitter,
+        )
+        .await;
+    assert_eq!(results.len(), 2);
+    let outcome = |agent_id: &str| {
+        results
+            .iter()
+            .find(|r| r.agent_id == agent_id)
+            .map(|r| r.outcome)
+    };
+    assert_eq!(outcome(&first), Some(StopOutcome::AlreadyStopped));
+    assert_eq!(outcome(&second), Some(StopOutcome::Stopped));
+    assert!(harness.emitter.has_event("agent:status", |payload| {
+        payload["agent_id"] == second.as_str() && payload["status"] == "stopped"
+    }));
+    let run = harness.run(&second).await;
+    assert_eq!(run.status, RunStatus::Stopped);
+    assert_eq!(run.stop_mode, Some(StopMode::Forced));
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rename_agent_updates_info_and_run() {
+    let harness = Harness::new();
+    let agent_id = harness.create_agent().await.unwrap();
+
+    let info = harness
+        .manager
+        .set_agent_title(&agent_id, "  auth-refactor ", &*harness.emitter)
+        .await
+        .unwrap();
+    assert_eq!(info.title.as_deref(), Some("auth-refactor"));
+    assert!(harness.emitter.has_event("agent:status", |payload| {
+        payload["agent_id"] == agent_id.as_str() && payload["info"]["title"] == "auth-refactor"
+    }));
+    assert_eq!(
+        harness.run(&agent_id).await.title.as_deref(),
+        Some("auth-refactor")
+    );
+
+    assert!(harness
+        .manager
+        .set_agent_title(&agent_id, " ", &*harness.emitter)
+        .await
+        .is_err());
+    assert!(harness
+        .manager
+        .set_agent_title("missing", "test-fixer", &*harness.emitter)
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_source_profile_is_applied_and_recorded() {
+    let harness = Harness::new();
+    harness
+        .runs_db
+        .save_permission_profile(&PermissionProfile {
+            name: "locked down".to_string(),
+            sources: vec![AgentSource::Manual],
+            allowed_tools: vec!["Read".to_string()],
+            disallowed_tools: Vec::new(),
+            permission_mode: PermissionMode::Default,
+            elevated_commands: ElevatedCommandPolicy::Deny,
+            network_expected: false,
+            created_at: 0,
+            updated_at: 0,
+        })
+        .await
+        .unwrap();
+
+    let agent_id = harness.create_agent().await.unwrap();
+    let applied = harness.manager.applied_profile(&agent_id).await.unwrap();
+    assert_eq!(applied.name, "locked down");
+    assert_eq!(applied.elevated_commands, ElevatedCommandPolicy::Deny);
+    assert_eq!(
+        harness.run(&agent_id).await.permission_profile.as_deref(),
+        Some("locked down")
+    );
+
+    // Naming a profile that doesn't exist fails the spawn
+    let err = harness
+        .manager
+        .create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            Vec::new(),
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                permission_profile: Some("missing".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, "No permission profile named 'missing'");
+
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_requested_model_is_recorded() {
+    let harness = Harness::new();
+    let agent_id = harness
+        .manager
+        .create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            Vec::new(),
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                model: Some("haiku".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let info = harness.manager.get_agent_info(&agent_id).await.unwrap();
+    assert_eq!(info.model.as_deref(), Some("haiku"));
+    assert_eq!(harness.run(&agent_id).await.model.as_deref(), Some("haiku"));
+
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_extra_env_reaches_the_agent_but_api_keys_stay_blocked() {
+    let harness = Harness::new();
+    let extra_env: HashMap<String, String> = [
+        ("DATABASE_URL", "postgres://staging"),
+        ("ANTHROPIC_API_KEY", "sk-ant-injected"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    let agent_id = harness
+        .manager
+        .create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            Vec::new(),
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                extra_env,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    // Only the names are recorded, and the blocked API key not at all
+    assert_eq!(harness.run(&agent_id).await.env_keys, vec!["DATABASE_URL"]);
+
+    harness.send_prompt(&agent_id, "ENV").await;
+    let reply = "DATABASE_URL=postgres://staging ANTHROPIC_API_KEY=unset";
+    assert!(
+        wait_until(|| async {
+            harness.emitter.has_event("agent:output", |payload| {
+                payload["agent_id"] == agent_id.as_str()
+                    && payload["content"]
+                        .as_str()
+                        .is_some_and(|c| c.contains(reply))
+            })
+        })
+        .await
+    );
+
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mcp_servers_are_passed_to_the_agent() {
+    let harness = Harness::new();
+    let create = |mcp_servers: Value| {
+        harness.manager.create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            Vec::new(),
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                mcp_servers: Some(mcp_servers),
+                ..Default::default()
+            },
+        )
+    };
+
+    // A malformed config is refused before anything is spawned
+    let err = create(json!({ "mcpServers": { "internal": { "args": ["serve"] } } }))
+        .await
+        .unwrap_err();
+    assert!(err.contains("command"), "{}", err);
+    assert!(harness.manager.list_agents().await.is_empty());
+
+    let agent_id = create(json!({ "mcpServers": { "internal": { "command": "internal-mcp" } } }))
+        .await
+        .unwrap();
+    let config_path = std::env::temp_dir().join(format!("claude_mcp_{}.json", agent_id));
+    let config: Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+    assert_eq!(config["mcpServers"]["internal"]["command"], "internal-mcp");
+
+    harness.send_prompt(&agent_id, "ARGS").await;
+    let expected = format!("--mcp-config {}", config_path.display());
+    assert!(
+        wait_until(|| async {
+            harness.emitter.has_event("agent:output", |payload| {
+                payload["agent_id"] == agent_id.as_str()
+                    && payload["content"]
+                        .as_str()
+                        .is_some_and(|c| c.contains(&expected))
+            })
+        })
+        .await
+    );
+
+    // The config file goes away with the agent
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+    assert!(!config_path.exists());
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_tool_restrictions_are_passed_and_recorded() {
+    let harness = Harness::new();
+    let create = |allowed: &[&str], disallowed: &[&str]| {
+        harness.manager.create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            Vec::new(),
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                tools: ToolRestrictions {
+                    allowed_tools: allowed.iter().map(|t| t.to_string()).collect(),
+                    disallowed_tools: disallowed.iter().map(|t| t.to_string()).collect(),
+                },
+                ..Default::default()
+            },
+        )
+    };
+
+    let err = create(&["Edit"], &["Edit"]).await.unwrap_err();
+    assert_eq!(err, "Tool 'Edit' is both allowed and disallowed");
+    assert!(harness.manager.list_agents().await.is_empty());
+
+    let agent_id = create(&["Read", "Bash(git diff:*)"], &["Edit", "Write"])
+        .await
+        .unwrap();
+    let run = harness.run(&agent_id).await;
+    assert_eq!(run.allowed_tools, vec!["Read", "Bash(git diff:*)"]);
+    assert_eq!(run.disallowed_tools, vec!["Edit", "Write"]);
+
+    harness.send_prompt(&agent_id, "ARGS").await;
+    let expected = "--allowedTools Read,Bash(git diff:*) --disallowedTools Edit,Write";
+    assert!(
+        wait_until(|| async {
+            harness.emitter.has_event("agent:output", |payload| {
+                payload["agent_id"] == agent_id.as_str()
+                    && payload["content"]
+                        .as_str()
+                        .is_some_and(|c| c.contains(expected))
+            })
+        })
+        .await
+    );
+
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_clone_copies_configuration_and_context() {
+    let harness = Harness::new();
+    harness.init_repo();
+    let original = harness
+        .manager
+        .create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            vec!["review".to_string()],
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                model: Some("haiku".to_string()),
+                tools: ToolRestrictions {
+                    allowed_tools: vec!["Read".to_string()],
+                    disallowed_tools: Vec::new(),
+                },
+                use_worktree: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let original_worktree = harness
+        .manager
+        .get_agent_info(&original)
+        .await
+        .unwrap()
+        .worktree
+        .unwrap();
+    let skill = Path::new(&original_worktree.path).join(".claude/skills/review/SKILL.md");
+    std::fs::create_dir_all(skill.parent().unwrap()).unwrap();
+    std::fs::write(&skill, "# Review").unwrap();
+    harness.send_prompt(&original, "ARGS").await;
+    let replied = |agent_id: String| {
+        let emitter = harness.emitter.clone();
+        move || {
+            let agent_id = agent_id.clone();
+            let emitter = emitter.clone();
+            async move {
+                emitter.has_event("agent:output", |payload| {
+                    payload["agent_id"] == agent_id.as_str()
+                        && payload["content"]
+                            .as_str()
+                            .is_some_and(|c| c.starts_with("ARGS "))
+                })
+            }
+        }
+    };
+    assert!(wait_until(replied(original.clone())).await);
+
+    let clone = harness
+        .manager
+        .clone_agent(&original, true, harness.emitter.clone())
+        .await
+        .unwrap();
+    assert_ne!(clone, original);
+    let info = harness.manager.get_agent_info(&clone).await.unwrap();
+    assert_eq!(info.model.as_deref(), Some("haiku"));
+    // The clone works in a worktree of its own, added to the same repository
+    let clone_worktree = info.worktree.unwrap();
+    assert_ne!(clone_worktree.path, original_worktree.path);
+    assert_eq!(clone_worktree.repo_path, original_worktree.repo_path);
+    assert_eq!(info.working_dir, clone_worktree.path);
+    // The original keeps running
+    assert!(!matches!(
+        harness.agent_status(&original).await,
+        None | Some(AgentStatus::Stopped)
+    ));
+
+    let run = harness.run(&clone).await;
+    assert_eq!(run.cloned_from.as_deref(), Some(original.as_str()));
+    assert_eq!(run.allowed_tools, vec!["Read"]);
+    let prompts = harness.runs_db.get_prompts(&clone).await.unwrap();
+    assert!(prompts[0].0.starts_with("# Context from a previous agent"));
+    assert!(prompts[0].0.contains("--model haiku"));
+
+    // The context quotes the original's "ARGS" reply, so the clone answers in kind
+    assert!(wait_until(replied(clone.clone())).await);
+
+    // Stopping the clone leaves the running original's worktree and skills alone
+    harness.manager.stop_agent(&clone).await.unwrap();
+    assert!(!Path::new(&clone_worktree.path).exists());
+    assert!(skill.exists());
+    assert!(!matches!(
+        harness.agent_status(&original).await,
+        None | Some(AgentStatus::Stopped)
+    ));
+
+    harness.manager.stop_agent(&original).await.unwrap();
+    assert!(!skill.exists());
+    assert!(!Path::new(&original_worktree.path).exists());
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_worktree_agents_work_apart_and_are_cleaned_up() {
+    let harness = Harness::new();
+    let create = || {
+        harness.manager.create_agent_with_skills(
+            harness.working_dir.clone(),
+            None,
+            Vec::new(),
+            AgentSource::Manual,
+            harness.emitter.clone(),
+            AgentSpawnOptions {
+                use_worktree: true,
+                ..Default::default()
+            },
+        )
+    };
+
+    let err = create().await.unwrap_err();
+    assert!(err.contains("is not a git repository"), "{}", err);
+
+    harness.init_repo();
+
+    // Untracked files in the repository don't block a worktree
+    std::fs::write(Path::new(&harness.working_dir).join("notes.txt"), "scratch").unwrap();
+
+    let first = create().await.unwrap();
+    let second = create().await.unwrap();
+    let dirty = create().await.unwrap();
+
+    let repo = std::fs::canonicalize(&harness.working_dir).unwrap();
+    let info = harness.manager.get_agent_info(&first).await.unwrap();
+    let worktree = info.worktree.unwrap();
+    assert_eq!(Path::new(&worktree.repo_path), repo);
+    assert_eq!(
+        Path::new(&worktree.path),
+        repo.join(".grove/worktrees").join(&first)
+    );
+    assert_eq!(worktree.branch, format!("grove/{}", first));
+    assert_eq!(info.working_dir, worktree.path);
+    assert!(Path::new(&worktree.path).join("README.md").exists());
+
+    let run = harness.run(&first).await;
+    assert_eq!(run.working_dir, worktree.path);
+    assert_eq!(run.repo_path.as_deref(), Some(worktree.repo_path.as_str()));
+    assert_eq!(run.worktree_path.as_deref(), Some(worktree.path.as_str()));
+
+    // Stopping removes the worktree unless it is to be kept
+    harness
+        .manager
+        .set_agent_keep_worktree(&second, true)
+        .await
+        .unwrap();
+    harness.manager.stop_agent(&first).await.unwrap();
+    harness.manager.stop_agent(&second).await.unwrap();
+    assert!(!Path::new(&worktree.path).exists());
+    let kept = harness.manager.get_agent_info(&second).await.unwrap();
+    assert!(Path::new(&kept.worktree.unwrap().path).exists());
+    assert_eq!(harness.manager.take_worktree_notice(&first).await, None);
+
+    // Uncommitted changes keep the worktree, and the user is told where it is
+    let dirty_path = harness
+        .manager
+        .get_agent_info(&dirty)
+        .await
+        .unwrap()
+        .worktree
+        .unwrap()
+        .path;
+    std::fs::write(Path::new(&dirty_path).join("README.md"), "unsaved work").unwrap();
+    harness.manager.stop_agent(&dirty).await.unwrap();
+    assert!(Path::new(&dirty_path).join("README.md").exists());
+    let notice = harness.manager.take_worktree_notice(&dirty).await.unwrap();
+    assert!(notice.contains(&dirty_path), "{}", notice);
+    assert_eq!(harness.manager.take_worktree_notice(&dirty).await, None);
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_prompts_sent_while_processing_are_queued() {
+    let harness = Harness::new();
+    let agent_id = harness.create_agent().await.unwrap();
+    harness.send_prompt(&agent_id, "first").await;
+
+    // The first turn isn't over until the idle grace period has passed
+    let emitter: Arc<dyn AppEventEmitter> = harness.emitter.clone();
+    let second = harness
+        .manager
+        .send_prompt(&agent_id, "second", Some(emitter.clone()), None)
+        .await
+        .unwrap();
+    assert!(second.queued);
+    assert_eq!(second.queue_depth, 1);
+    let third = harness
+        .manager
+        .send_prompt(&agent_id, "third", Some(emitter.clone()), None)
+        .await
+        .unwrap();
+    assert_eq!(third.queue_depth, 2);
+    assert!(harness.emitter.has_event("agent:activity", |payload| {
+        payload["agent_id"] == agent_id.as_str() && payload["queued_prompts"] == 2
+    }));
+
+    let queued = harness.manager.get_queued_prompts(&agent_id).await.unwrap();
+    let remaining = harness
+        .manager
+        .cancel_queued_prompt(&agent_id, &queued[1].id, Some(emitter))
+        .await
+        .unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].prompt, "second");
+
+    // The queued prompt is sent, and recorded, once the first turn ends
+    assert!(
+        wait_until(|| async { harness.runs_db.get_prompts(&agent_id).await.unwrap().len() == 2 })
+            .await,
+        "queued prompt was never sent"
+    );
+    let prompts = harness.runs_db.get_prompts(&agent_id).await.unwrap();
+    let recorded: Vec<&str> = prompts.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(recorded, vec!["first", "second"]);
+    assert!(prompts[1].1 > remaining[0].queued_at);
+    assert!(harness
+        .manager
+        .get_queued_prompts(&agent_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_idle_agents_are_stopped_with_their_cost_recorded() {
+    let mut harness = Harness::new();
+    let agent_id = harness.create_agent().await.unwrap();
+    harness.send_prompt(&agent_id, "first").await;
+    assert!(
+        wait_until(|| async {
+            harness.agent_status(&agent_id).await == Some(AgentStatus::WaitingForInput)
+        })
+        .await,
+        "agent never went idle"
+    );
+
+    // Nowhere near the default timeout yet
+    let stopped = harness.manager.stop_idle_agents(&*harness.emitter).await;
+    assert!(stopped.is_empty());
+
+    harness
+        .manager
+        .set_idle_timeout(AgentSource::Manual, Some(Duration::ZERO));
+    let stopped = harness.manager.stop_idle_agents(&*harness.emitter).await;
+    assert_eq!(stopped, vec![agent_id.clone()]);
+    assert!(harness.emitter.has_event("agent:status", |payload| {
+        payload["agent_id"] == agent_id.as_str()
+            && payload["status"] == "stopped"
+            && payload["reason"] == "idle_timeout"
+    }));
+    let run = harness.run(&agent_id).await;
+    assert_eq!(run.status, RunStatus::Stopped);
+    assert!(run.total_cost_usd.is_some());
+
+    // Already stopped, so not stopped again
+    assert!(harness
+        .manager
+        .stop_idle_agents(&*harness.emitter)
+        .await
+        .is_empty());
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_process_crash_marks_run_crashed_and_resumable() {
+    let harness = Harness::new();
+    let agent_id = harness.create_agent().await.unwrap();
+
+    harness.send_prompt(&agent_id, "CRASH now").await;
+
+    let run = harness.wait_for_crashed_run(&agent_id).await;
+    assert!(run.can_resume);
+    assert!(run
+        .error_message
+        .unwrap_or_default()
+        .starts_with("Process terminated unexpectedly"));
+    assert_eq!(
+        harness.agent_status(&agent_id).await,
+        Some(AgentStatus::Error)
+    );
+    assert!(harness.agent_error_emitted(&agent_id));
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dead_process_with_open_stdout_is_marked_crashed() {
+    let mut harness = Harness::new();
+    let result_queue: SharedResultQueue = Default::default();
+    harness.manager.set_result_queue(result_queue.clone());
+    let agent_id = harness.create_agent().await.unwrap();
+
+    // A child of the process keeps stdout open, so its end is never read
+    harness.send_prompt(&agent_id, "ORPHAN now").await;
+    let mut crashed = Vec::new();
+    for _ in 0..50 {
+        crashed = harness
+            .manager
+            .check_agent_processes(&*harness.emitter)
+            .await;
+        if !crashed.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(crashed, vec![agent_id.clone()]);
+
+    assert_eq!(
+        harness.agent_status(&agent_id).await,
+        Some(AgentStatus::Error)
+    );
+    assert!(harness.agent_error_emitted(&agent_id));
+    let run = harness.run(&agent_id).await;
+    assert_eq!(run.status, RunStatus::Crashed);
+    assert!(run.can_resume);
+    let error_message = run.error_message.unwrap_or_default();
+    assert!(
+        error_message.starts_with("Process terminated unexpectedly (exit code 3)"),
+        "unexpected error: {}",
+        error_message
+    );
+    assert!(error_message.contains("leaving an orphan behind"));
+    assert_eq!(result_queue.lock().unwrap().status().pending, 1);
+
+    // Reported once
+    assert!(harness
+        .manager
+        .check_agent_processes(&*harness.emitter)
+        .await
+        .is_empty());
+    harness.assert_locks_free().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stdout_eof_mid_message_marks_run_crashed() {
+    let harness = Harness::new();
+    harness.injector.arm(FaultPoint::StdoutEof, 1);
+
+    let agent_id = harness.create_agent().await.unwrap();
+
+    let run = harness.wait_for_crashed_run(&agent_id).await;
+    assert!(run.can_resume);
+    assert_eq!(harness.injector.fired(FaultPoint::StdoutEof), 1);
+    assert!(harness.agent_error_emitted(&agent_id));
+    // The truncated message is surfaced as plain output rather than dropped
+    assert!(harness.emitter.has_event("agent:output", |payload| {
+        payload["agent_id"] == agent_id.as_str()
+            && payload["content"]
+                .as_str()
+                .is_some_and(|c| c.starts_with("{\"type\""))
+    }));
+    harness.assert_locks_free().await;
+
+    harness.manager.stop_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_db_lock_errors_are_dead_lettered_and_recovered() {
+    let harness = Harness::new();
+    let agent_id = harness.create_agent().await.unwrap();
+
+    // Every write fails while the agent crashes: the prompt record, the run
+    // update from send_prompt and the crash update all land in the queue
+    harness.injector.arm_always(FaultPoint::DbLocked);
+    harness.send_prompt(&agent_id, "CRASH now").await;
+
+    assert!(
+        wait_until(|| async {
+            harness.agent_status(&agent_id).await == Some(AgentStatus::Error)
+                && harness
+                    .runs_db
+                    .get_database_stats()
+                    .await
+                    .is_ok_and(|stats| stats.dead_letter_count >= 3)
+        })
+        .await,
+        "crash writes were not dead-lettered"
+    );
+    assert_eq!(harness.run(&agent_id).await.status, RunStatus::Running);
+    assert!(harness.agent_error_emitted(&agent_id));
+    harness.assert_locks_free().await;
+
+    // Once the lock clears, replaying the queue brings the run up to date
+    harness.injector.disarm(FaultPoint::DbLocked);
+    harness.runs_db.flush_dead_letters().await;
+
+    let run = harness.run(&agent_id).await;
+    assert_eq!(run.status, RunStatus::Crashed);
+    assert!(run.can_resume);
+    assert_eq!(run.total_prompts, 1);
+    assert_eq!(
+        harness
+            .runs_db
+            .get_database_stats()
+            .await
+            .unwrap()
+            .dead_letter_count,
+        0
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_hook_server_errors_do_not_wedge_the_manager() {
+    let harness = Harness::new();
+    let agent_id = harness.create_agent().await.unwrap();
+    let emitter = harness.emitter.clone();
+    let injector = harness.injector.clone();
+
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let agent_manager = Arc::new(Mutex::new(harness.manager));
+    tokio::spawn(claude_commander_lib::hook_server::start_hook_server(
+        agent_manager.clone(),
+        emitter,
+        port,
+        None,
+        Arc::new(Mutex::new(Default::default())),
+        Arc::new(Mutex::new(Default::default())),
+        Arc::new(Mutex::new(None)),
+        None,
+    ));
+
+    let client = reqwest::Client::new();
+    let url = format!("http://127.0.0.1:{}/hook?agent_id={}", port, agent_id);
+    let post_hook = || {
+        client
+            .post(&url)
+            .json(&json!({"session_id": "fake-session", "hook_event_name": "Stop"}))
+            .send()
+    };
+
+    assert!(
+        wait_until(|| async {
+            tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .is_ok()
+        })
+        .await,
+        "hook server did not start"
+    );
+
+    injector.arm(FaultPoint::HookServerError, 1);
+    let response = post_hook().await.unwrap();
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    // The failed request must not keep the manager locked
+    let response = post_hook().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(
+        tokio::time::timeout(Duration::from_secs(1), agent_manager.lock())
+            .await
+            .is_ok(),
+        "agent manager lock is still held"
+    );
+
+    agent_manager
+        .lock()
+        .await
+        .stop_agent(&agent_id)
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ai_provider_timeout_fails_pipeline() {
+    let harness = Harness::new();
+
+    // The orchestrator needs a provider configured; the injected timeout fires
+    // before any request is made
+    if std::env::var("ANTHROPIC_API_KEY").is_err() && std::env::var("OPENAI_API_KEY").is_err() {
+        std::env::set_var("ANTHROPIC_API_KEY", "fault-injection-test-key");
+    }
+
+    let injector = harness.injector.clone();
+    injector.arm_always(FaultPoint::AiProviderTimeout);
+
+    let pipelines = AutoPipelineManager::new()
+        .unwrap()
+        .with_fault_hook(injector.clone());
+    let pipeline_id = pipelines
+        .create_pipeline(
+            "Add a README".to_string(),
+            harness.working_dir.clone(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let emitter = harness.emitter.clone();
+    let agent_manager = Arc::new(Mutex::new(harness.manager));
+    let handle = pipelines
+        .start_pipeline(&pipeline_id, agent_manager.clone(), emitter.clone())
+        .await
+        .unwrap();
+
+    let err = handle.await.unwrap().unwrap_err();
+    assert!(err.contains("timed out"), "unexpected error: {}", err);
+    assert!(injector.fired(FaultPoint::AiProviderTimeout) >= 1);
+
+    let pipeline = pipelines.get_pipeline(&pipeline_id).await.unwrap();
+    assert_eq!(pipeline.status, "failed");
+    assert!(!pipelines.is_running(&pipeline_id).await);
+    assert!(emitter.has_event("auto_pipeline:completed", |payload| {
+        payload["pipeline_id"] == pipeline_id.as_str() && payload["status"] == "failed"
+    }));
+    assert!(agent_manager.lock().await.list_agents().await.is_empty());
+}
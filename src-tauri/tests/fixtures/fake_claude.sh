@@ -0,0 +1,40 @@
+#!/bin/sh
+# Fake Claude CLI for the fault injection tests.
+#
+# Speaks just enough stream-json for AgentManager: an init message on start,
+# then one assistant message and a result per prompt. A prompt containing
+# CRASH makes it die halfway through writing a message; ORPHAN makes it exit
+# while a child it started keeps its stdout open; ENV replies with the
+# environment variables the env tests set; ARGS replies with its arguments.
+
+session="fake-session-$$"
+args="$*"
+
+printf '{"type":"system","subtype":"init","session_id":"%s"}\n' "$session"
+
+while IFS= read -r line; do
+    case "$line" in
+        *CRASH*)
+            echo "fake claude: simulated crash" >&2
+            printf '{"type":"assistant","session_id":"%s","message":{"content":[{"type":"te' "$session"
+            exit 1
+            ;;
+        *ORPHAN*)
+            echo "fake claude: leaving an orphan behind" >&2
+            sleep 10 &
+            exit 3
+            ;;
+        *ENV*)
+            printf '{"type":"assistant","session_id":"%s","message":{"role":"assistant","content":[{"type":"text","text":"DATABASE_URL=%s ANTHROPIC_API_KEY=%s"}]}}\n' "$session" "$DATABASE_URL" "${ANTHROPIC_API_KEY:-unset}"
+            printf '{"type":"result","subtype":"success","session_id":"%s","result":"ok","total_cost_usd":0}\n' "$session"
+            ;;
+        *ARGS*)
+            printf '{"type":"assistant","session_id":"%s","message":{"role":"assistant","content":[{"type":"text","text":"ARGS %s"}]}}\n' "$session" "$args"
+            printf '{"type":"result","subtype":"success","session_id":"%s","result":"ok","total_cost_usd":0}\n' "$session"
+            ;;
+        *)
+            printf '{"type":"assistant","session_id":"%s","message":{"role":"assistant","content":[{"type":"text","text":"ok"}]}}\n' "$session"
+            printf '{"type":"result","subtype":"success","session_id":"%s","result":"ok","total_cost_usd":0}\n' "$session"
+            ;;
+    esac
+done
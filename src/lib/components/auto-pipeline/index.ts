@@ -4,6 +4,7 @@ export { default as OrchestratorStatus } from './OrchestratorStatus.svelte';
 export { default as StageRow } from './StageRow.svelte';
 export { default as FilterBar } from './FilterBar.svelte';
 export { default as OutputList } from './OutputList.svelte';
+export { default as ScratchpadHistory } from './ScratchpadHistory.svelte';
 
 // Re-export the UnifiedOutput type
 export type { UnifiedOutput } from './types';
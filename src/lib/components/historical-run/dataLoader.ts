@@ -12,7 +12,10 @@ import type {
   OrchestratorDecision,
   OrchestratorToolCallRecord,
   OrchestratorStateChangeRecord,
-  OrchestratorDecisionRecord
+  OrchestratorDecisionRecord,
+  Page,
+  AgentTurn,
+  PromptUsage
 } from "$lib/types";
 
 // ============================================================================
@@ -22,6 +25,10 @@ import type {
 export interface PromptData {
   prompt: string;
   timestamp: number;
+  usage?: PromptUsage;
+  turnIndex: number;
+  durationMs?: number;
+  outputCount: number;
 }
 
 export interface ActivityData {
@@ -94,12 +101,19 @@ export function convertDecisionRecord(record: OrchestratorDecisionRecord): Orche
 // ============================================================================
 
 /**
- * Load prompts for a given agent
+ * Load prompts for a given agent, one per turn
  */
 export async function loadPrompts(agentId: string): Promise<LoadResult<PromptData[]>> {
   try {
-    const result = await invoke<Array<[string, number]>>("get_run_prompts", { agentId });
-    const data = result.map(([prompt, timestamp]) => ({ prompt, timestamp }));
+    const result = await invoke<AgentTurn[]>("get_agent_turns", { agentId });
+    const data = result.map((turn) => ({
+      prompt: turn.prompt,
+      timestamp: turn.started_at,
+      usage: turn.usage,
+      turnIndex: turn.turn_index,
+      durationMs: turn.duration_ms,
+      outputCount: turn.outputs.length
+    }));
     return { data, error: null };
   } catch (e) {
     console.error("Failed to load run prompts:", e);
@@ -113,24 +127,24 @@ export async function loadPrompts(agentId: string): Promise<LoadResult<PromptDat
 export async function loadActivity(pipelineId: string): Promise<LoadResult<ActivityData>> {
   try {
     const [toolCallsResult, stateChangesResult, decisionsResult] = await Promise.all([
-      invoke<OrchestratorToolCallRecord[]>("get_orchestrator_tool_calls", {
+      invoke<Page<OrchestratorToolCallRecord>>("get_orchestrator_tool_calls", {
         pipelineId,
         limit: 1000
       }),
-      invoke<OrchestratorStateChangeRecord[]>("get_orchestrator_state_changes", {
+      invoke<Page<OrchestratorStateChangeRecord>>("get_orchestrator_state_changes", {
         pipelineId,
         limit: 500
       }),
-      invoke<OrchestratorDecisionRecord[]>("get_orchestrator_decisions", {
+      invoke<Page<OrchestratorDecisionRecord>>("get_orchestrator_decisions", {
         pipelineId,
         limit: 100
       }),
     ]);
 
     const data: ActivityData = {
-      toolCalls: (toolCallsResult || []).map(convertToolCallRecord),
-      stateChanges: (stateChangesResult || []).map(convertStateChangeRecord),
-      decisions: (decisionsResult || []).map(convertDecisionRecord)
+      toolCalls: (toolCallsResult?.items ?? []).map(convertToolCallRecord),
+      stateChanges: (stateChangesResult?.items ?? []).map(convertStateChangeRecord),
+      decisions: (decisionsResult?.items ?? []).map(convertDecisionRecord)
     };
 
     return { data, error: null };
@@ -10,6 +10,17 @@ export interface ClaudeCodeStatus {
   subscriptionType: string | null;
 }
 
+// Which claude CLI agents are started with (diagnose_claude_cli)
+export interface ClaudeCliDiagnosis {
+  path: string; // The CLI, or npx when none was found
+  prefix_args: string[]; // Arguments before claude's own (npx's package)
+  source: "env_var" | "known_location" | "path" | "local_node_modules" | "npx";
+  claude_path_env: string | null;
+  version: string | null;
+  error: string | null; // Why `--version` failed
+  searched: string[]; // Where the CLI was looked for, in order
+}
+
 export type ApiKeyStatus = "unchecked" | "validating" | "valid" | "invalid";
 export type ClaudeAuthStatus = "unchecked" | "ready";
 
@@ -15,6 +15,8 @@ export interface CreateSingleAgentParams {
   workingDir: string;
   githubUrl?: string;
   instructions: string[];
+  injectConventions?: boolean;
+  autoFollowUp?: boolean;
 }
 
 export interface CreateCustomPipelineParams {
@@ -47,12 +49,14 @@ export interface CreatePipelineResult {
 export async function createSingleAgent(
   params: CreateSingleAgentParams
 ): Promise<CreateAgentResult> {
-  const { workingDir, githubUrl, instructions } = params;
+  const { workingDir, githubUrl, instructions, injectConventions, autoFollowUp } = params;
 
   const agentId = await invoke<string>("create_agent", {
     workingDir,
     githubUrl: githubUrl?.trim() || null,
     selectedInstructionFiles: instructions.length > 0 ? instructions : null,
+    injectConventions: injectConventions ?? null,
+    autoFollowUp: autoFollowUp ?? null,
   });
 
   const agent: Agent = {
@@ -43,20 +43,29 @@ function throttle<T extends (...args: unknown[]) => void>(fn: T, ms: number): T
 
 import type {
   Agent,
+  AgentMessage,
+  AgentNote,
   AgentOutput,
   ToolEvent,
   AgentStatusEvent,
   AgentInputRequiredEvent,
   AgentActivityEvent,
   AgentStatsEvent,
+  ToolTimingStats,
+  AgentStderrEvent,
   MetaAgentThinkingEvent,
   MetaAgentToolCallEvent,
   MetaTodoUpdatedEvent,
   MetaAgentUserUpdateEvent,
   MetaAgentQuestionEvent,
+  MetaAgentQuestionAnsweredEvent,
+  MetaAgentDirectoryApprovalEvent,
+  MetaAgentDirectoryApprovalResolvedEvent,
   MetaAgentSleepEvent,
+  MetaAgentIterationEvent,
   ContextInfoEvent,
   AutoPipeline,
+  StepProgressEvent,
   OrchestratorToolCall,
   OrchestratorStateChange,
   OrchestratorDecision,
@@ -92,6 +101,8 @@ export interface EventHandlerCallbacks {
     toolName: string;
     timestamp: Date;
   }) => void;
+  onAgentNote?: (agentId: string, note: AgentNote) => void;
+  onAgentMessage?: (message: AgentMessage) => void;
   onAgentStats: (agentId: string, stats: {
     agentId: string;
     totalPrompts: number;
@@ -101,6 +112,9 @@ export interface EventHandlerCallbacks {
     lastActivity: string;
     totalTokensUsed?: number;
     totalCostUsd?: number;
+    stderrLines?: number;
+    lastStderrError?: string;
+    toolTimings?: Record<string, ToolTimingStats>;
   }) => void;
 
   // Meta-agent callbacks
@@ -109,7 +123,11 @@ export interface EventHandlerCallbacks {
   onMetaAgentTodos?: (event: MetaTodoUpdatedEvent) => void;
   onMetaAgentUserUpdate?: (event: MetaAgentUserUpdateEvent) => void;
   onMetaAgentQuestion?: (event: MetaAgentQuestionEvent) => void;
+  onMetaAgentQuestionAnswered?: (event: MetaAgentQuestionAnsweredEvent) => void;
+  onMetaAgentDirectoryApproval?: (event: MetaAgentDirectoryApprovalEvent) => void;
+  onMetaAgentDirectoryApprovalResolved?: (event: MetaAgentDirectoryApprovalResolvedEvent) => void;
   onMetaAgentSleep?: (event: MetaAgentSleepEvent) => void;
+  onMetaAgentIteration?: (event: MetaAgentIterationEvent) => void;
   onMetaAgentContextInfo?: (event: ContextInfoEvent) => void;
   onNavigate: (agentId: string) => void;
 
@@ -124,6 +142,7 @@ export interface EventHandlerCallbacks {
   onAutoPipelineStep: (pipelineId: string, stepNumber: number) => void;
   onAutoPipelineComplete: (pipelineId: string) => void;
   onStepStatus: (pipelineId: string, stepNumber: number, status: string) => void;
+  onStepProgress: (event: StepProgressEvent) => void;
 
   // Orchestrator callbacks
   onOrchestratorToolStart: (toolCall: OrchestratorToolCall) => void;
@@ -154,6 +173,9 @@ export interface EventHandlerCallbacks {
   // Elevated command callbacks
   onElevatedCommandRequest?: (request: PendingElevatedCommand) => void;
   onElevatedCommandStatus?: (requestId: string, status: string, error?: string) => void;
+
+  // Tray callback - the tray menu's "Open pending question" entry
+  onTrayOpenPendingQuestion?: () => void;
 }
 
 // ============================================================================
@@ -167,17 +189,36 @@ async function setupAgentOutputListener(
     agent_id: string;
     output_type: string;
     content: string;
+    content_html?: string;
   }>("agent:output", (event) => {
     const output: AgentOutput = {
       agentId: event.payload.agent_id,
       type: event.payload.output_type as AgentOutput["type"],
       content: event.payload.content,
+      contentHtml: event.payload.content_html,
       timestamp: new Date(),
     };
     onAgentOutput(event.payload.agent_id, output);
   });
 }
 
+async function setupAgentStderrListener(
+  onAgentOutput: EventHandlerCallbacks['onAgentOutput']
+): Promise<UnlistenFn> {
+  // Stderr arrives in batches; show each line as an error output
+  return listen<AgentStderrEvent>("agent:stderr", (event) => {
+    const timestamp = new Date();
+    for (const line of event.payload.lines) {
+      onAgentOutput(event.payload.agent_id, {
+        agentId: event.payload.agent_id,
+        type: "error",
+        content: line,
+        timestamp,
+      });
+    }
+  });
+}
+
 async function setupToolEventListener(
   onToolEvent: EventHandlerCallbacks['onToolEvent']
 ): Promise<UnlistenFn> {
@@ -239,6 +280,8 @@ async function setupStatusListener(
         } : undefined,
         title: info.title,
         complexity: info.complexity as Agent['complexity'],
+        latestNote: info.latest_note,
+        label: info.label,
       };
     }
 
@@ -283,6 +326,9 @@ async function setupStatsListener(
     lastActivity: string;
     totalTokensUsed?: number;
     totalCostUsd?: number;
+    stderrLines?: number;
+    lastStderrError?: string;
+    toolTimings?: Record<string, ToolTimingStats>;
   }) => void>();
 
   return listen<AgentStatsEvent>("agent:stats", (event) => {
@@ -304,6 +350,9 @@ async function setupStatsListener(
       lastActivity: event.payload.stats.last_activity,
       totalTokensUsed: event.payload.stats.total_tokens_used,
       totalCostUsd: event.payload.stats.total_cost_usd,
+      stderrLines: event.payload.stats.stderr_lines,
+      lastStderrError: event.payload.stats.last_stderr_error,
+      toolTimings: event.payload.stats.tool_timings,
     });
   });
 }
@@ -337,6 +386,22 @@ async function setupActivityDetailListener(
   });
 }
 
+async function setupAgentNoteListener(
+  onAgentNote: EventHandlerCallbacks['onAgentNote']
+): Promise<UnlistenFn> {
+  return listen<AgentNote>("agent:note", (event) => {
+    onAgentNote?.(event.payload.agent_id, event.payload);
+  });
+}
+
+async function setupAgentMessageListener(
+  onAgentMessage: EventHandlerCallbacks['onAgentMessage']
+): Promise<UnlistenFn> {
+  return listen<AgentMessage>("agent:message", (event) => {
+    onAgentMessage?.(event.payload);
+  });
+}
+
 // ============================================================================
 // Meta-Agent Event Handlers
 // ============================================================================
@@ -392,6 +457,32 @@ async function setupMetaAgentQuestionListener(
   });
 }
 
+async function setupMetaAgentQuestionAnsweredListener(
+  onMetaAgentQuestionAnswered: EventHandlerCallbacks['onMetaAgentQuestionAnswered']
+): Promise<UnlistenFn> {
+  return listen<MetaAgentQuestionAnsweredEvent>("meta-agent:question-answered", (event) => {
+    console.log("[Frontend] Meta-agent question answered via", event.payload.answered_via);
+    onMetaAgentQuestionAnswered?.(event.payload);
+  });
+}
+
+async function setupMetaAgentDirectoryApprovalListener(
+  onMetaAgentDirectoryApproval: EventHandlerCallbacks['onMetaAgentDirectoryApproval']
+): Promise<UnlistenFn> {
+  return listen<MetaAgentDirectoryApprovalEvent>("meta-agent:directory-approval", (event) => {
+    console.log("[Frontend] Meta-agent directory approval requested:", event.payload.working_dir);
+    onMetaAgentDirectoryApproval?.(event.payload);
+  });
+}
+
+async function setupMetaAgentDirectoryApprovalResolvedListener(
+  onMetaAgentDirectoryApprovalResolved: EventHandlerCallbacks['onMetaAgentDirectoryApprovalResolved']
+): Promise<UnlistenFn> {
+  return listen<MetaAgentDirectoryApprovalResolvedEvent>("meta-agent:directory-approval-resolved", (event) => {
+    onMetaAgentDirectoryApprovalResolved?.(event.payload);
+  });
+}
+
 async function setupMetaAgentSleepListener(
   onMetaAgentSleep: EventHandlerCallbacks['onMetaAgentSleep']
 ): Promise<UnlistenFn> {
@@ -404,6 +495,14 @@ async function setupMetaAgentSleepListener(
   });
 }
 
+async function setupMetaAgentIterationListener(
+  onMetaAgentIteration: EventHandlerCallbacks['onMetaAgentIteration']
+): Promise<UnlistenFn> {
+  return listen<MetaAgentIterationEvent>("meta-agent:iteration", (event) => {
+    onMetaAgentIteration?.(event.payload);
+  });
+}
+
 async function setupMetaAgentContextInfoListener(
   onMetaAgentContextInfo: EventHandlerCallbacks['onMetaAgentContextInfo']
 ): Promise<UnlistenFn> {
@@ -527,6 +626,14 @@ async function setupStepStatusListener(
   });
 }
 
+async function setupStepProgressListener(
+  onStepProgress: EventHandlerCallbacks['onStepProgress']
+): Promise<UnlistenFn> {
+  return listen<StepProgressEvent>("auto_pipeline:step-progress", (event) => {
+    onStepProgress(event.payload);
+  });
+}
+
 // ============================================================================
 // Orchestrator Event Handlers
 // ============================================================================
@@ -667,6 +774,18 @@ async function setupSecurityReviewCompletedListener(
   });
 }
 
+// ============================================================================
+// Tray Event Handler
+// ============================================================================
+
+async function setupTrayOpenPendingQuestionListener(
+  onTrayOpenPendingQuestion: EventHandlerCallbacks['onTrayOpenPendingQuestion']
+): Promise<UnlistenFn> {
+  return listen("tray:open-pending-question", () => {
+    onTrayOpenPendingQuestion?.();
+  });
+}
+
 // ============================================================================
 // Toast Event Handler
 // ============================================================================
@@ -719,14 +838,17 @@ export async function setupEventListeners(
 ): Promise<() => void> {
   // Setup all listeners in parallel
   const unlistenPromises = await Promise.all([
-    // Agent events (7)
+    // Agent events (10)
     setupAgentOutputListener(callbacks.onAgentOutput),
+    setupAgentStderrListener(callbacks.onAgentOutput),
     setupToolEventListener(callbacks.onToolEvent),
     setupStatusListener(callbacks.onAgentStatus),
     setupInputRequiredListener(callbacks.onInputRequired),
     setupActivityListener(callbacks.onAgentActivity),
     setupStatsListener(callbacks.onAgentStats),
     setupActivityDetailListener(callbacks.onAgentActivityDetail),
+    setupAgentNoteListener(callbacks.onAgentNote),
+    setupAgentMessageListener(callbacks.onAgentMessage),
 
     // Meta-agent events (8)
     setupThinkingListener(callbacks.onMetaAgentThinking),
@@ -734,7 +856,11 @@ export async function setupEventListeners(
     setupMetaAgentTodosListener(callbacks.onMetaAgentTodos),
     setupMetaAgentUserUpdateListener(callbacks.onMetaAgentUserUpdate),
     setupMetaAgentQuestionListener(callbacks.onMetaAgentQuestion),
+    setupMetaAgentQuestionAnsweredListener(callbacks.onMetaAgentQuestionAnswered),
+    setupMetaAgentDirectoryApprovalListener(callbacks.onMetaAgentDirectoryApproval),
+    setupMetaAgentDirectoryApprovalResolvedListener(callbacks.onMetaAgentDirectoryApprovalResolved),
     setupMetaAgentSleepListener(callbacks.onMetaAgentSleep),
+    setupMetaAgentIterationListener(callbacks.onMetaAgentIteration),
     setupMetaAgentContextInfoListener(callbacks.onMetaAgentContextInfo),
     setupNavigateListener(callbacks.onNavigate),
 
@@ -744,11 +870,12 @@ export async function setupEventListeners(
     setupPipelinePhaseListener(callbacks.onPipelinePhase),
     setupPhaseProgressListener(callbacks.onPhaseProgress),
 
-    // Auto-pipeline events (4)
+    // Auto-pipeline events (5)
     setupAutoPipelineStartedListener(callbacks.onAutoPipelineStarted),
     setupAutoPipelineStepListener(callbacks.onAutoPipelineStep),
     setupAutoPipelineCompleteListener(callbacks.onAutoPipelineComplete),
     setupStepStatusListener(callbacks.onStepStatus),
+    setupStepProgressListener(callbacks.onStepProgress),
 
     // Orchestrator events (4)
     setupOrchestratorToolStartListener(callbacks.onOrchestratorToolStart),
@@ -766,6 +893,9 @@ export async function setupEventListeners(
     // Toast event (1)
     setupToastListener(callbacks.onToast),
 
+    // Tray event (1)
+    setupTrayOpenPendingQuestionListener(callbacks.onTrayOpenPendingQuestion),
+
     // Elevated command events (2)
     setupElevatedRequestListener(callbacks.onElevatedCommandRequest),
     setupElevatedStatusListener(callbacks.onElevatedCommandStatus),
@@ -16,6 +16,7 @@ export {
   fetchAndUpdateAutoPipeline,
   createAutoPipelineUpdater,
   refreshAutoPipeline,
+  applyStepProgress,
 } from './pipelineHelpers';
 
 // Keyboard Shortcuts
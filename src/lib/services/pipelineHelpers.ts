@@ -6,7 +6,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
-import type { AutoPipeline } from "$lib/types";
+import type { AutoPipeline, StepProgressEvent } from "$lib/types";
 
 /**
  * Fetches an auto-pipeline from the backend by ID.
@@ -70,3 +70,25 @@ export async function refreshAutoPipeline(
     });
   }
 }
+
+/**
+ * Applies a step-progress event to the matching step in the store,
+ * without refetching the pipeline.
+ */
+export function applyStepProgress(
+  event: StepProgressEvent,
+  autoPipelinesUpdate: (updater: (m: Map<string, AutoPipeline>) => Map<string, AutoPipeline>) => void
+): void {
+  const { pipeline_id, step_number, agent_id: _agentId, ...progress } = event;
+  autoPipelinesUpdate((m) => {
+    const pipeline = m.get(pipeline_id);
+    if (!pipeline) return m;
+    m.set(pipeline_id, {
+      ...pipeline,
+      steps: pipeline.steps.map((step) =>
+        step.step_number === step_number ? { ...step, progress } : step
+      ),
+    });
+    return new Map(m);
+  });
+}
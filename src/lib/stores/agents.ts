@@ -8,7 +8,10 @@
 import { writable, derived, get } from "svelte/store";
 import type {
   Agent,
+  AgentMessage,
+  AgentNote,
   AgentOutput,
+  GitHubContext,
   ToolEvent,
   ChatMessage,
   MetaAgentToolCallEvent,
@@ -57,6 +60,8 @@ export const agentOutputs = writable<Map<string, AgentOutput[]>>(new Map());
 export const toolEvents = writable<Map<string, ToolEvent[]>>(new Map());
 export const viewedAgents = writable<Set<string>>(new Set());
 export const agentStats = writable<Map<string, AgentStatistics>>(new Map());
+// Messages relayed between agents this session, oldest first
+export const agentMessages = writable<AgentMessage[]>([]);
 
 // Cache viewedAgents to avoid get() calls in hot paths
 let cachedViewedAgents: Set<string> = new Set();
@@ -379,6 +384,40 @@ export function clearAgentOutput(agentId: string) {
 // Agent Activity & State Functions
 // ============================================================================
 
+export function updateAgentGitHubContext(agentId: string, githubContext: GitHubContext | undefined) {
+  agents.update((map) => {
+    const agent = map.get(agentId);
+    if (agent) {
+      map.set(agentId, { ...agent, githubContext });
+    }
+    return new Map(map);
+  });
+}
+
+export function updateAgentLabel(agentId: string, label: string | undefined) {
+  agents.update((map) => {
+    const agent = map.get(agentId);
+    if (agent) {
+      map.set(agentId, { ...agent, label });
+    }
+    return new Map(map);
+  });
+}
+
+export function updateAgentNote(agentId: string, note: AgentNote) {
+  agents.update((map) => {
+    const agent = map.get(agentId);
+    if (agent) {
+      map.set(agentId, { ...agent, latestNote: note });
+    }
+    return new Map(map);
+  });
+}
+
+export function addAgentMessage(message: AgentMessage) {
+  agentMessages.update((messages) => [...messages, message]);
+}
+
 export function updateAgentActivity(
   agentId: string,
   activity: {
@@ -551,6 +590,15 @@ export function setHistoricalRuns(runs: AgentRun[]) {
   historicalRuns.set(runs);
 }
 
+export function updateHistoricalRunLabel(agentId: string, label: string) {
+  historicalRuns.update((runs) =>
+    runs.map((run) => (run.agent_id === agentId ? { ...run, label } : run))
+  );
+  selectedHistoricalRun.update((run) =>
+    run?.agent_id === agentId ? { ...run, label } : run
+  );
+}
+
 export function selectHistoricalRun(run: AgentRun | null) {
   selectedHistoricalRun.set(run);
   if (run) {
@@ -3,26 +3,38 @@
  *
  * Stores state for meta-agent interaction tools:
  * - Pending questions from AskUserQuestion
+ * - Working directories CreateWorkerAgent needs approved
  * - User updates from UpdateUser
  * - Sleep status from Sleep
+ * - Tool-loop progress (iteration and tool about to run)
  */
 
 import { writable, derived } from "svelte/store";
 import type {
   MetaAgentUserUpdateEvent,
   MetaAgentQuestionEvent,
+  MetaAgentDirectoryApprovalEvent,
   MetaAgentSleepEvent,
+  MetaAgentIterationEvent,
+  MetaAgentActivity,
+  PendingMetaQuestion,
 } from "../types";
 
 // Pending question from AskUserQuestion tool
 export const pendingMetaQuestion = writable<MetaAgentQuestionEvent | null>(null);
 
+// Working directories waiting for approval before a worker is created there
+export const pendingDirectoryApprovals = writable<MetaAgentDirectoryApprovalEvent[]>([]);
+
 // Recent user updates from UpdateUser tool
 export const metaUserUpdates = writable<MetaAgentUserUpdateEvent[]>([]);
 
 // Sleep status from Sleep tool
 export const metaSleepStatus = writable<MetaAgentSleepEvent | null>(null);
 
+// Latest tool-loop progress event
+export const metaIteration = writable<MetaAgentIterationEvent | null>(null);
+
 // Derived: is the meta-agent currently waiting for user input?
 export const isWaitingForInput = derived(pendingMetaQuestion, ($question) => $question !== null);
 
@@ -38,6 +50,16 @@ export function clearMetaQuestion() {
   pendingMetaQuestion.set(null);
 }
 
+export function addDirectoryApproval(request: MetaAgentDirectoryApprovalEvent) {
+  pendingDirectoryApprovals.update((requests) =>
+    requests.some((r) => r.nonce === request.nonce) ? requests : [...requests, request]
+  );
+}
+
+export function removeDirectoryApproval(nonce: string) {
+  pendingDirectoryApprovals.update((requests) => requests.filter((r) => r.nonce !== nonce));
+}
+
 export function addMetaUserUpdate(update: MetaAgentUserUpdateEvent) {
   metaUserUpdates.update((updates) => {
     // Keep last 50 updates
@@ -61,9 +83,50 @@ export function clearMetaSleepStatus() {
   metaSleepStatus.set(null);
 }
 
+export function setMetaIteration(event: MetaAgentIterationEvent | null) {
+  metaIteration.set(event);
+}
+
+/**
+ * Fetch the current tool-loop state from the backend.
+ * Safe to call while the meta-agent is busy (doesn't wait on the loop).
+ */
+export async function fetchMetaAgentActivity(): Promise<MetaAgentActivity> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<MetaAgentActivity>("get_meta_agent_activity");
+}
+
+/**
+ * Fetch the question the meta-agent is waiting on, if any.
+ * Used to restore the question card after a reload.
+ */
+export async function fetchPendingMetaQuestion(): Promise<MetaAgentQuestionEvent | null> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  const pending = await invoke<PendingMetaQuestion | null>("get_pending_meta_question");
+  if (!pending) return null;
+  return {
+    question_id: pending.question_id,
+    question: pending.question,
+    options: pending.options,
+    default_answer: pending.default_answer,
+    timeout_secs: pending.timeout_secs,
+    timestamp: pending.asked_at,
+  };
+}
+
+/**
+ * Fetch the working directories waiting for approval.
+ * Used to restore the approval prompts after a reload.
+ */
+export async function fetchPendingDirectoryApprovals(): Promise<MetaAgentDirectoryApprovalEvent[]> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<MetaAgentDirectoryApprovalEvent[]>("get_pending_directory_approvals");
+}
+
 // Clear all interaction state (useful when chat is cleared)
 export function clearMetaInteractionState() {
   pendingMetaQuestion.set(null);
   metaUserUpdates.set([]);
   metaSleepStatus.set(null);
+  metaIteration.set(null);
 }
@@ -55,7 +55,7 @@ export const unifiedHistory = derived(
       items.push({
         type: 'agent_run',
         id: run.agent_id,
-        title: formatPath(run.working_dir),
+        title: run.label || formatPath(run.working_dir),
         preview: run.initial_prompt,
         timestamp: run.started_at,
         status: run.status,
@@ -166,6 +166,43 @@ export async function startNewConversation(): Promise<string> {
   }
 }
 
+/**
+ * Fork a conversation at a message (0-based, inclusive) and switch to the fork.
+ * The original conversation is left untouched.
+ */
+export async function forkConversation(
+  conversationId: string,
+  atMessageIndex: number
+): Promise<string> {
+  conversationsLoading.set(true);
+  conversationsError.set(null);
+
+  try {
+    const forkId = await invoke<string>("fork_conversation", {
+      conversationId,
+      atMessageIndex,
+    });
+
+    // The backend has already switched to the fork; fetch its messages
+    const messages = await invoke<ChatMessage[]>("get_chat_history");
+    metaAgentChat.set(messages);
+    currentConversationId.set(forkId);
+
+    // Refresh the conversations list
+    await loadConversations();
+
+    console.log(`[metaConversations] Forked ${conversationId} at message ${atMessageIndex}: ${forkId}`);
+    return forkId;
+  } catch (error) {
+    const message = error instanceof Error ? error.message : String(error);
+    conversationsError.set(message);
+    console.error("[metaConversations] Failed to fork conversation:", error);
+    throw error;
+  } finally {
+    conversationsLoading.set(false);
+  }
+}
+
 /**
  * Delete a conversation
  */
@@ -211,7 +248,9 @@ export async function renameConversation(
     // Update local state
     conversations.update((convs) =>
       convs.map((c) =>
-        c.conversation_id === conversationId ? { ...c, title: newTitle } : c
+        c.conversation_id === conversationId
+          ? { ...c, title: newTitle, title_locked: true }
+          : c
       )
     );
 
@@ -224,6 +263,31 @@ export async function renameConversation(
   }
 }
 
+/**
+ * Generate and store a paragraph summary of a conversation
+ */
+export async function summarizeConversation(conversationId: string): Promise<string> {
+  conversationsError.set(null);
+
+  try {
+    const summary = await invoke<string>("summarize_conversation", { conversationId });
+
+    // Update local state
+    conversations.update((convs) =>
+      convs.map((c) =>
+        c.conversation_id === conversationId ? { ...c, summary } : c
+      )
+    );
+
+    return summary;
+  } catch (error) {
+    const message = error instanceof Error ? error.message : String(error);
+    conversationsError.set(message);
+    console.error("[metaConversations] Failed to summarize conversation:", error);
+    throw error;
+  }
+}
+
 /**
  * Get the current conversation ID from the backend
  */
@@ -0,0 +1,27 @@
+import { writable } from 'svelte/store';
+import { invoke } from '@tauri-apps/api/core';
+import type { TrashItem, TrashKind, TrashPurgeResult } from '../types';
+
+// Trashed runs and meta-agent conversations, most recently deleted first
+export const trashItems = writable<TrashItem[]>([]);
+
+export async function loadTrash(kind?: TrashKind): Promise<void> {
+  try {
+    trashItems.set(await invoke<TrashItem[]>('list_trash', { kind }));
+  } catch (error) {
+    console.error('[trash] Failed to load trash:', error);
+    throw error;
+  }
+}
+
+export async function restoreFromTrash(kind: TrashKind, id: string): Promise<void> {
+  await invoke('restore_from_trash', { kind, id });
+  trashItems.update((items) => items.filter((item) => !(item.kind === kind && item.id === id)));
+}
+
+// Permanently delete trashed items; omit olderThanDays to empty the whole trash
+export async function emptyTrash(olderThanDays?: number): Promise<TrashPurgeResult> {
+  const result = await invoke<TrashPurgeResult>('empty_trash', { olderThanDays });
+  await loadTrash();
+  return result;
+}
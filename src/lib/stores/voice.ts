@@ -26,6 +26,23 @@ export interface VoiceStatus {
   transcript: string;
 }
 
+export type VoiceConnectionState = "connecting" | "connected" | "reconnecting" | "closed" | "failed";
+
+export interface VoiceConnectionStateEvent {
+  state: VoiceConnectionState;
+  attempt: number;
+  max_attempts: number;
+  reason?: string;
+}
+
+export interface VoiceSessionHealth {
+  state: VoiceConnectionState;
+  uptime_ms: number;
+  reconnect_count: number;
+  latency_ms?: number;
+  last_error?: string;
+}
+
 export type VoiceMode = "dictate" | "discuss" | "attention" | "off";
 
 export interface VoiceState {
@@ -36,6 +53,8 @@ export interface VoiceState {
   response: string;
   error: string | null;
   mode: VoiceMode;
+  // Set while the backend is re-establishing a dropped connection
+  reconnect: { attempt: number; maxAttempts: number } | null;
 }
 
 // Stores
@@ -47,6 +66,7 @@ export const voiceState = writable<VoiceState>({
   response: "",
   error: null,
   mode: "off",
+  reconnect: null,
 });
 
 // Store for controlling voice sidebar visibility from anywhere
@@ -129,6 +149,17 @@ export async function initVoiceListeners() {
       audioPlaybackCallback(audio);
     }
   });
+
+  // Listen for connection drops, reconnects and give-ups
+  await listen<VoiceConnectionStateEvent>("voice:connection-state", (event) => {
+    const { state, attempt, max_attempts, reason } = event.payload;
+    voiceState.update((s) => ({
+      ...s,
+      reconnect: state === "reconnecting" ? { attempt, maxAttempts: max_attempts } : null,
+      isRecording: state === "failed" ? false : s.isRecording,
+      error: state === "failed" ? reason ?? "Voice connection lost" : s.error,
+    }));
+  });
 }
 
 // Actions
@@ -145,6 +176,7 @@ export async function startVoiceSession(): Promise<void> {
     transcript: "",
     segments: [],
     response: "",
+    reconnect: null,
   }));
 
   try {
@@ -209,6 +241,7 @@ export function clearVoiceState(): void {
     response: "",
     error: null,
     mode: "off",
+    reconnect: null,
   });
 }
 
@@ -216,6 +249,10 @@ export async function getVoiceStatus(): Promise<VoiceStatus> {
   return await invoke<VoiceStatus>("get_voice_status");
 }
 
+export async function getVoiceSessionHealth(): Promise<VoiceSessionHealth | null> {
+  return await invoke<VoiceSessionHealth | null>("get_voice_session_health");
+}
+
 // ============================================================================
 // Discuss Mode
 // ============================================================================
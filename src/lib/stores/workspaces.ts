@@ -0,0 +1,30 @@
+import { writable } from 'svelte/store';
+import { invoke } from '@tauri-apps/api/core';
+import type { WorkspaceInfo } from '../types';
+import { loadConversations, currentConversationId } from './metaConversations';
+
+// Workspaces scoping the meta-agent's conversations, memory and personality
+export const workspaces = writable<WorkspaceInfo[]>([]);
+
+export async function loadWorkspaces(): Promise<void> {
+  try {
+    workspaces.set(await invoke<WorkspaceInfo[]>('list_workspaces'));
+  } catch (error) {
+    console.error('[workspaces] Failed to load workspaces:', error);
+    throw error;
+  }
+}
+
+// Returns the new workspace's ID (derived from its name)
+export async function createWorkspace(name: string): Promise<string> {
+  const workspaceId = await invoke<string>('create_workspace', { name });
+  await loadWorkspaces();
+  return workspaceId;
+}
+
+// The meta agent drops its current conversation, so the conversation list is reloaded too
+export async function switchWorkspace(workspaceId: string): Promise<void> {
+  await invoke('switch_workspace', { workspaceId });
+  currentConversationId.set(null);
+  await Promise.all([loadWorkspaces(), loadConversations()]);
+}
@@ -1,4 +1,4 @@
-export type AgentStatus = "running" | "stopped" | "error" | "waitingforinput" | "idle" | "processing";
+export type AgentStatus = "running" | "stopped" | "error" | "waitingforinput" | "idle" | "processing" | "ratelimited";
 
 export type AgentComplexity = "simple" | "easy" | "complex";
 
@@ -23,6 +23,76 @@ export interface Agent {
   githubContext?: GitHubContext;
   title?: string;
   complexity?: AgentComplexity;
+  latestNote?: AgentNote;
+  label?: string;
+}
+
+export interface AgentNote {
+  id?: number;
+  agent_id: string;
+  author: "user" | "meta";
+  text: string;
+  timestamp: number;
+}
+
+// A message relayed from one agent to another by the commander
+export interface AgentMessage {
+  id?: number;
+  pipeline_id?: string; // undefined for standalone agents
+  from_agent_id: string;
+  to_agent_id: string;
+  text: string;
+  timestamp: number;
+}
+
+// One group per pipeline, plus groups created with create_agent_group
+export interface AgentGroup {
+  id: string;
+  name: string;
+  pipeline_id: string | null; // null for user-defined groups
+  agent_ids: string[];
+  created_at: number;
+}
+
+export interface GroupMemberResult {
+  agent_id: string;
+  success: boolean;
+  error?: string;
+}
+
+export type StopOutcome = 'stopped' | 'already_stopped' | 'failed';
+
+export interface AgentStopResult {
+  agent_id: string;
+  outcome: StopOutcome;
+  error?: string;
+}
+
+export interface GroupStatistics {
+  group_id: string;
+  agent_count: number;
+  active_count: number; // members running or waiting for input
+  total_prompts: number;
+  total_tool_calls: number;
+  total_tokens_used: number;
+  total_cost_usd: number;
+}
+
+export interface AgentStatsSummary {
+  total_prompts: number;
+  total_tool_calls: number;
+  total_tokens_used?: number;
+  total_cost_usd?: number;
+  last_activity: string;
+}
+
+// Everything an agent card shows, from list_agents_detailed
+export interface AgentSummary {
+  info: AgentInfo;
+  stats: AgentStatsSummary;
+  last_message?: string; // start of the latest reply
+  todo_progress?: { completed: number; total: number };
+  security_alert: boolean; // a security review is awaiting a decision
 }
 
 export interface AgentOutput {
@@ -32,6 +102,7 @@ export interface AgentOutput {
   timestamp: Date;
   parsedJson?: Record<string, unknown>;
   metadata?: OutputMetadata;
+  contentHtml?: string; // ANSI colors as ansi-* classes (AGENT_OUTPUT_ANSI=html)
 }
 
 export interface OutputMetadata {
@@ -39,8 +110,11 @@ export interface OutputMetadata {
   lineCount?: number;
   byteSize?: number;
   isTruncated: boolean;
+  containsAnsi?: boolean;
 }
 
+export type OutputTypeFilter = "text" | "tool_results" | "errors";
+
 export interface ToolEvent {
   agentId: string;
   sessionId: string;
@@ -82,8 +156,67 @@ export interface AgentInfo {
     commit_sha?: string;
     last_synced?: string;
   };
-  title?: string;
+  title?: string; // Display title, e.g. pipeline stage; changed via rename_agent
   complexity?: string;
+  latest_note?: AgentNote;
+  label?: string; // Short run name derived from the initial prompt
+  group_ids?: string[]; // Agent groups this agent belongs to
+  hooks_missing?: boolean; // Tool calls seen in the output but no hook events received
+  permission_profile?: AppliedProfile; // Permission profile spawned under
+  model?: string; // Model passed to the claude CLI (--model)
+  worktree?: AgentWorktree; // Git worktree the agent runs in, if isolated
+}
+
+export interface AgentWorktree {
+  repo_path: string; // Root of the repository the worktree was added to
+  path: string;
+  branch: string; // grove/<agent_id>
+}
+
+export interface AppliedProfile {
+  name: string;
+  elevated_commands: ElevatedCommandPolicy;
+  network_expected: boolean;
+}
+
+export type HandoffStage =
+  | 'summarizing'
+  | 'stopping'
+  | 'spawning'
+  | 'prompting'
+  | 'completed'
+  | 'failed';
+
+// Payload of the agent:handoff event
+export interface HandoffEvent {
+  agent_id: string; // The agent being replaced
+  stage: HandoffStage;
+  new_agent_id?: string;
+  error?: string;
+}
+
+export interface HandoffResult {
+  previous_agent_id: string;
+  agent_id: string;
+  summary: string;
+}
+
+export interface HookEventRecord {
+  id: number | null;
+  agent_id: string | null;
+  session_id: string;
+  event_name: string;
+  tool_name: string | null;
+  matched_by: 'session' | 'agent_id' | 'unmatched';
+  latency_ms: number;
+  created_at: number;
+}
+
+export interface HookHealth {
+  events_received: number;
+  unmatched_session_events: number;
+  last_event_at: number | null;
+  last_event_age_ms: number | null;
 }
 
 export interface AgentOutputEvent {
@@ -92,6 +225,7 @@ export interface AgentOutputEvent {
   content: string;
   parsed_json?: Record<string, unknown>;
   metadata?: OutputMetadata;
+  content_html?: string;
   // Enhanced fields matching Rust struct
   session_id?: string;
   uuid?: string;
@@ -101,10 +235,20 @@ export interface AgentOutputEvent {
   timestamp?: number;
 }
 
+// Result of search_agent_outputs
+export interface OutputSearchMatch {
+  agent_id: string;
+  output_type: string;
+  snippet: string; // one line around the first match, wrapped in **
+  timestamp: number;
+  live: boolean; // found in a live agent's buffer rather than the database
+}
+
 export interface AgentStatusEvent {
   agent_id: string;
   status: AgentStatus;
   info?: AgentInfo;
+  reason?: 'idle_timeout'; // set when the app stopped the agent
 }
 
 export interface AgentInputRequiredEvent {
@@ -117,6 +261,19 @@ export interface AgentActivityEvent {
   is_processing: boolean;
   pending_input: boolean;
   last_activity: number;
+  queued_prompts: number;
+}
+
+/** What send_prompt did with a prompt sent while the agent was busy */
+export interface PromptSendResult {
+  queued: boolean;
+  queue_depth: number;
+}
+
+export interface QueuedPrompt {
+  id: string;
+  prompt: string;
+  queued_at: number;
 }
 
 export interface AgentStatistics {
@@ -128,6 +285,43 @@ export interface AgentStatistics {
   lastActivity: string;
   totalTokensUsed?: number;
   totalCostUsd?: number;
+  stderrLines?: number;
+  lastStderrError?: string;
+  toolTimings?: Record<string, ToolTimingStats>;
+}
+
+// Time spent in one tool, from PreToolUse to PostToolUse
+export interface ToolTimingStats {
+  calls: number;
+  total_ms: number;
+  max_ms: number;
+}
+
+export interface AgentRateLimitedEvent {
+  agent_id: string;
+  kind: "usage_limit" | "rate_limit" | "overloaded";
+  message: string;
+  retry_after_secs?: number;
+  resume_at: number; // When dispatching to Claude agents resumes (Unix ms)
+}
+
+export interface StopwordFiredEvent {
+  phrase: string;
+  source: "chat" | "voice";
+  meta_loop_halted: boolean;
+  pipelines_cancelled: string[];
+  agents_stopped: string[];
+  timestamp: number;
+}
+
+export interface MemoryFootprint {
+  agents: { agent_id: string; outputs: number; bytes: number }[];
+  agent_output_bytes: number;
+  conversations: { key: string; bytes: number }[];
+  conversation_bytes: number;
+  total_bytes: number;
+  output_buffer_cap_bytes: number;
+  warn_threshold_bytes: number;
 }
 
 export interface AgentStatsEvent {
@@ -141,9 +335,18 @@ export interface AgentStatsEvent {
     last_activity: string;
     total_tokens_used?: number;
     total_cost_usd?: number;
+    stderr_lines?: number;
+    last_stderr_error?: string;
+    tool_timings?: Record<string, ToolTimingStats>;
   };
 }
 
+export interface AgentStderrEvent {
+  agent_id: string;
+  lines: string[];
+  total_lines: number;
+}
+
 // Image attachment for chat messages
 export interface ImageAttachment {
   id: string;
@@ -175,14 +378,86 @@ export interface ChatResponse {
   usage: ChatUsage;
 }
 
+/** Error thrown by send_chat_message */
+export type ChatError =
+  | { kind: 'turn_in_progress'; active_turn_id: string; message: string }
+  | { kind: 'failed'; message: string }
+  | ObserverModeError;
+
+/** Rejection of any mutating command while observer mode is on */
+export interface ObserverModeError {
+  kind: 'observer_mode';
+  message: string;
+}
+
+/** Returned by set_observer_mode / get_observer_mode, payload of observer_mode:changed */
+export interface ObserverModeStatus {
+  enabled: boolean;
+  since: number | null;
+}
+
+export interface VersionInfo {
+  app_version: string;
+  git_commit: string; // "unknown" for builds outside a git checkout
+  schema_version: number;
+  features: string[];
+}
+
+export type SubsystemHealth = 'ok' | 'degraded' | 'failed';
+
+export interface SubsystemStatus {
+  name: string; // e.g. "runs_database", "meta_agent"
+  health: SubsystemHealth;
+  reason: string | null;
+  remediation: string | null;
+  updated_at: number;
+}
+
+export interface QueuedTurnSummary {
+  turnId: string;
+  preview: string;
+  queuedAt: number;
+}
+
+/** Payload of chat-queue:updated */
+export interface ChatQueueStatus {
+  activeTurnId: string | null;
+  pending: number;
+  items: QueuedTurnSummary[];
+}
+
 export interface ChatUsage {
   input_tokens: number;
   output_tokens: number;
+  cache_creation_input_tokens?: number;
+  cache_read_input_tokens?: number;
 }
 
 export interface SystemPromptInfo {
   prompt: string;
-  source: "base" | "personalized";
+  source: "base" | "personalized" | "override";
+}
+
+// Token counts per provider tokenizer (Claude is approximated with cl100k_base)
+export interface TokenEstimate {
+  claude: number;
+  openai: number;
+}
+
+export interface PromptSection {
+  name: "base" | "personalized" | "override" | "memory";
+  chars: number;
+  tokens: TokenEstimate;
+}
+
+// preview_effective_system_prompt / set_base_system_prompt_override
+export interface SystemPromptPreview {
+  prompt: string; // exactly what is sent, memory included
+  source: "base" | "personalized" | "override";
+  provider: string;
+  sections: PromptSection[];
+  tokens: TokenEstimate;
+  warnings: string[];
 }
 
 export interface MetaAgentToolCallEvent {
@@ -206,15 +481,88 @@ export interface MetaAgentQuestionEvent {
   question_id: string;
   question: string;
   options?: string[];
+  default_answer?: string;  // Used if nobody answers before the timeout
+  timeout_secs?: number;
   timestamp: number;
 }
 
+/** Where an AskUserQuestion answer came from */
+export type QuestionAnswerSource = "ui" | "voice" | "rest" | "timeout";
+
+export interface MetaAgentQuestionAnsweredEvent {
+  question_id: string;
+  answer: string;
+  answered_via: QuestionAnswerSource;
+}
+
+/** Pending question as returned by get_pending_meta_question */
+export interface PendingMetaQuestion {
+  question_id: string;
+  question: string;
+  options?: string[];
+  default_answer?: string;
+  timeout_secs: number;
+  asked_at: number;
+}
+
+/** CreateWorkerAgent is waiting for approval of an unfamiliar working directory */
+export interface MetaAgentDirectoryApprovalEvent {
+  nonce: string;
+  working_dir: string;
+  timeout_secs: number;
+  requested_at: number;
+}
+
+export interface MetaAgentDirectoryApprovalResolvedEvent {
+  nonce: string;
+  working_dir: string;
+  approved: boolean;
+  timed_out: boolean;
+}
+
 export interface MetaAgentSleepEvent {
   status: "sleeping" | "awake";
   duration_ms?: number;
   reason?: string;
 }
 
+export interface MetaAgentIterationEvent {
+  iteration: number;
+  max_iterations: number;
+  tool_name?: string | null;
+  elapsed_ms: number;
+  timestamp: number;
+}
+
+/** Current tool-loop state returned by get_meta_agent_activity */
+export type MetaAgentActivity =
+  | { state: "idle" }
+  | {
+      state: "running";
+      iteration: number;
+      max_iterations: number;
+      elapsed_ms: number;
+      current_tool?: string | null;
+    }
+  | {
+      state: "sleeping";
+      iteration: number;
+      max_iterations: number;
+      elapsed_ms: number;
+      reason: string;
+      duration_ms: number;
+      remaining_ms: number;
+      wake_conditions: string[];
+    }
+  | {
+      state: "awaiting_answer";
+      iteration: number;
+      max_iterations: number;
+      elapsed_ms: number;
+      question: string;
+      options?: string[] | null;
+    };
+
 export type ContextState = "normal" | "warning" | "critical" | "overflow";
 
 export interface ContextInfoEvent {
@@ -224,6 +572,9 @@ export interface ContextInfoEvent {
   remainingTokens: number;
   state: ContextState;
   warningMessage: string | null;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  cacheHitPercent: number;
 }
 
 // Cost Tracking Types
@@ -239,6 +590,8 @@ export interface SessionCostRecord {
   totalPrompts: number;
   totalToolCalls: number;
   modelUsage?: Record<string, ModelCostBreakdown>;
+  label?: string;
+  deleted?: boolean; // Run is in the trash; its cost still counts
 }
 
 export interface ModelCostBreakdown {
@@ -274,6 +627,39 @@ export interface DailyCost {
   sessionCount: number;
 }
 
+export interface DailyOffloads {
+  date: string;
+  task_count: number;
+  tokens_avoided: number;
+  usd_avoided: number;
+}
+
+export interface OffloadStats {
+  preference: 'subscription_first' | 'latency_first';
+  offloading: boolean;
+  limited_until: number | null;
+  usd_per_million_tokens: number;
+  daily: DailyOffloads[];
+}
+
+export interface AgentSpend {
+  agent_id: string;
+  label?: string | null;
+  spend_usd: number;
+}
+
+// Payload of the cost:anomaly event
+export interface CostAnomalyEvent {
+  window: "1h" | "24h";
+  spend_usd: number;
+  baseline_usd: number | null;
+  threshold_usd: number;
+  multiplier: number;
+  history_days: number;
+  top_agents: AgentSpend[];
+  detected_at: number;
+}
+
 export interface InstructionFileInfo {
   id: string;
   name: string;
@@ -391,9 +777,22 @@ export interface SkillScript {
   language: string;
 }
 
+// Files the commander wrote into a working directory (list_generated_files)
+export type GeneratedFileKind = "skill" | "subagent" | "claude_md" | "scratchpad" | "prompt_file";
+
+export interface GeneratedFile {
+  path: string; // relative to the working directory
+  kind: GeneratedFileKind;
+  created: boolean; // false when an existing file was modified
+  pipeline_id?: string;
+  recorded_at: number;
+}
+
 // Agent Run History Types
 export type RunStatus = "running" | "completed" | "stopped" | "crashed" | "waiting_input";
 
+export type StopMode = "graceful" | "forced";
+
 export interface AgentRun {
   id?: number;
   agent_id: string;
@@ -417,6 +816,24 @@ export interface AgentRun {
   model_usage?: string;
   can_resume: boolean;
   resume_data?: string;
+  conventions_injected?: boolean; // Repository conventions digest was prepended to the first prompt
+  label?: string; // Short run name derived from the initial prompt, or set via relabel_run
+  workspace_id?: string; // Workspace active when the run started
+  continued_from?: string | null; // Run this one took over from in a context hand-off
+  app_version?: string | null; // Build that created the run
+  git_commit?: string | null;
+  schema_version?: number | null;
+  stop_mode?: StopMode | null; // How stop_agent ended the process
+  permission_profile?: string | null; // Permission profile spawned under
+  model?: string | null; // Model passed to the claude CLI (--model)
+  title?: string | null; // Agent title at spawn, or set via rename_agent
+  env_keys?: string[]; // Names of the extra env vars given at spawn (values aren't stored)
+  allowed_tools?: string[]; // Tools usable without asking: profile's list plus those given at spawn
+  disallowed_tools?: string[]; // Tools the agent could not use: profile's list plus those given at spawn
+  repo_path?: string | null; // Repository an agent with its own worktree was started in
+  worktree_path?: string | null; // That agent's git worktree
+  tool_timings?: string | null; // JSON: tool name -> ToolTimingStats, stored on stop
+  cloned_from?: string | null; // Agent this one was cloned from (clone_agent)
 }
 
 export interface ModelCostBreakdown {
@@ -427,6 +844,35 @@ export interface ModelCostBreakdown {
   cost_usd: number;
 }
 
+export interface PromptUsage {
+  total_tokens: number;
+  cost_usd: number;
+  model_usage: Record<string, ModelCostBreakdown>;
+}
+
+export type TrashKind = 'run' | 'conversation';
+
+export interface TrashItem {
+  kind: TrashKind;
+  id: string;
+  title: string;
+  deleted_at: number;
+  cost_usd?: number; // Runs only
+}
+
+export interface TrashPurgeResult {
+  runs: number;
+  conversations: number;
+}
+
+export interface PromptCostRecord {
+  agent_id: string;
+  prompt: string;
+  timestamp: number;
+  usage?: PromptUsage; // Missing for prompts recorded before usage was tracked, or still in flight
+  working_dir?: string;
+}
+
 export interface RunStats {
   total_runs: number;
   by_status: [string, number][];
@@ -439,12 +885,108 @@ export interface AutoPipeline {
   id: string;
   user_request: string;
   working_dir: string;
-  status: 'running' | 'completed' | 'failed';
+  status: 'running' | 'completed' | 'failed' | 'cancelled' | 'awaiting_human';
   steps: AutoPipelineStep[];
   questions: string[];
   answers: string[];
   created_at: string;
   completed_at?: string;
+  prompt_templates?: PromptTemplateUsage[];
+  artifact_counts?: ArtifactCounts;
+  human_request?: HumanRequest | null; // set while the orchestrator waits on the user
+  tool_health?: PipelineHealth;
+}
+
+// Rolling error rate of the orchestrator's tool calls (get_pipeline_health, auto_pipeline:degraded)
+export interface PipelineHealth {
+  window: number;
+  threshold: number; // 0 disables the degraded check
+  recent: boolean[]; // outcomes of the last `window` calls, oldest first (true = error)
+  recent_calls: number;
+  recent_errors: number;
+  error_rate: number;
+  total_calls: number;
+  total_errors: number;
+  degraded: boolean;
+  degraded_since: string | null;
+  last_error: string | null;
+}
+
+// Question the orchestrator paused a pipeline on (auto_pipeline:needs-human)
+export interface HumanRequest {
+  pipeline_id: string;
+  question: string;
+  context: string;
+  excerpts: string[]; // latest output of the current phase's agent
+  requested_at: number;
+}
+
+export interface ArtifactCounts {
+  skills: number;
+  subagents: number;
+  claude_md: number;
+  removed: number; // artifacts whose files were cleaned up
+}
+
+export type PromptTemplateName = 'planning' | 'builder' | 'verifier';
+
+export interface PromptTemplateUsage {
+  name: PromptTemplateName;
+  hash: string; // content hash of the template
+  custom: boolean; // false for the built-in default
+}
+
+export interface PromptTemplateInfo {
+  name: PromptTemplateName;
+  content: string;
+  default_content: string;
+  is_custom: boolean;
+  hash: string;
+  required_placeholders: string[];
+  allowed_placeholders: string[];
+}
+
+// Prompt library: the user's saved prompts with {{variable}} placeholders
+export interface SavedPromptTemplate {
+  name: string;
+  content: string;
+  variables: string[];
+  use_count: number;
+  last_used_at: number | null;
+  created_at: number;
+  updated_at: number;
+}
+
+export type AgentSourceName = "ui" | "meta" | "pipeline" | "pool" | "manual" | "testwizard";
+
+// The Claude CLI's --permission-mode
+export type PermissionMode = "default" | "acceptEdits" | "plan" | "bypassPermissions";
+
+export type ElevatedCommandPolicy = "ask" | "deny";
+
+// Named policy applied to agents spawned from its sources (get_permission_profiles)
+export interface PermissionProfile {
+  name: string;
+  sources: AgentSourceName[];
+  allowed_tools: string[]; // Empty allows any tool
+  disallowed_tools: string[];
+  permission_mode: PermissionMode;
+  elevated_commands: ElevatedCommandPolicy;
+  network_expected: boolean;
+  created_at: number;
+  updated_at: number;
+}
+
+// Sends a saved prompt instead of a typed one (send_prompt's `template`)
+export interface PromptTemplateRef {
+  name: string;
+  variables?: Record<string, string>;
+}
+
+/** send_prompt's override of when a large prompt is handed over in a file */
+export interface PromptFileOptions {
+  delivery?: 'auto' | 'inline' | 'file';
+  threshold_kb?: number; // replaces PROMPT_FILE_THRESHOLD_KB for this send
 }
 
 export interface AutoPipelineStep {
@@ -459,6 +1001,37 @@ export interface AutoPipelineStep {
   };
   started_at?: string;
   completed_at?: string;
+  max_retries?: number;
+  retry_count?: number;
+  retry_history?: StepRetryRecord[];
+  progress?: StepProgress;
+}
+
+export interface StepRetryRecord {
+  attempt: number;
+  error: string;
+  failed_at: string;
+}
+
+// Step progress from the step agent's TodoWrite list
+export interface PlanTaskProgress {
+  task: string;
+  done: boolean;
+  matched_todo?: string;
+}
+
+export interface StepProgress {
+  percent: number; // 0-100
+  completed: number;
+  total: number;
+  in_progress?: string;
+  plan_tasks: PlanTaskProgress[];
+}
+
+export interface StepProgressEvent extends StepProgress {
+  pipeline_id: string;
+  step_number: number;
+  agent_id: string;
 }
 
 // Orchestrator activity types
@@ -502,11 +1075,37 @@ export interface AgentOutputRecord {
   content: string;
   metadata?: string; // JSON serialized
   timestamp: number; // Unix timestamp in milliseconds
+  turn_index?: number; // Turn of the prompt this output answers
+}
+
+// One prompt-response cycle of an agent
+export interface AgentTurn {
+  turn_index: number;
+  prompt: string;
+  started_at: number;
+  ended_at?: number; // Missing while in flight or for turns recorded before turn ends were tracked
+  duration_ms?: number;
+  outputs: AgentOutputRecord[];
+  total_tokens: number;
+  cost_usd: number;
+  usage?: PromptUsage;
 }
 
 // Database record types (from SQLite)
 // These match the Rust models.rs types returned by Tauri commands
 
+/** Position after the last row of a page, passed back as `before` */
+export interface PageCursor {
+  timestamp: number;
+  id: number;
+}
+
+/** One page of results; next_cursor is null on the last page */
+export interface Page<T> {
+  items: T[];
+  next_cursor: PageCursor | null;
+}
+
 export interface OrchestratorToolCallRecord {
   id?: number;
   pipeline_id: string;
@@ -543,10 +1142,87 @@ export interface OrchestratorDecisionRecord {
   timestamp: number;
 }
 
+export interface ScratchpadVersionRecord {
+  id?: number;
+  pipeline_id: string;
+  iteration: number;
+  step_number?: number;
+  content: string;
+  timestamp: number;
+}
+
+export interface GeneratedArtifactRecord {
+  id?: number;
+  pipeline_id?: string;
+  agent_id?: string;
+  kind: 'skill' | 'subagent' | 'claude_md';
+  name: string;
+  path: string;
+  content_hash: string;
+  size_bytes: number;
+  pipeline_state?: string;
+  created_at: number;
+  removed_at?: number; // set when cleanup removed the files
+}
+
 export interface PipelineHistoryBundle {
   tool_calls: OrchestratorToolCallRecord[];
   state_changes: OrchestratorStateChangeRecord[];
   decisions: OrchestratorDecisionRecord[];
+  scratchpad_versions?: ScratchpadVersionRecord[];
+}
+
+// OS notifications for things waiting on the user
+export type NotificationCategory = 'pipeline_review' | 'question' | 'elevated_command';
+
+/** Payload of notification:dispatched */
+export interface NotificationDispatch {
+  category: NotificationCategory;
+  title: string;
+  body: string;
+  deep_link: {
+    panel: 'pipeline' | 'question' | 'elevated_command';
+    id: string;
+  };
+  outcome: 'shown' | 'muted' | 'quiet_hours' | 'failed';
+  error?: string;
+  dispatched_at: number;
+}
+
+// Directory Locks (a running pipeline's working directory)
+export interface DirectoryLock {
+  working_dir: string;
+  pipeline_id: string;
+  acquired_at: number;
+}
+
+export type LockedAction = 'create_agent' | 'send_prompt' | 'start_pipeline';
+
+/** Payload of directory_lock:conflict */
+export interface LockConflict {
+  working_dir: string;
+  lock: DirectoryLock;
+  action: LockedAction;
+  subject_id?: string; // the agent prompted, or the pipeline started
+  blocked: boolean; // false when the work went ahead (warn mode)
+}
+
+// Filesystem Browse Types (working directory picker)
+export interface DirectoryEntry {
+  name: string;
+  path: string;
+  is_dir: boolean;
+  is_git_repo: boolean;
+  has_claude_md: boolean;
+  last_used_at?: number;
+  /** Outside the home directory and the workspace allowlist */
+  restricted: boolean;
+}
+
+export interface DirectoryListing {
+  directory: DirectoryEntry;
+  parent?: string;
+  entries: DirectoryEntry[];
 }
 
 // Security Types
@@ -678,6 +1354,18 @@ export interface ConfigUpdateResult {
   requires_restart: boolean;
 }
 
+export interface ConfigIssue {
+  severity: 'error' | 'warning';
+  variable: string;
+  message: string;
+}
+
+export interface ConfigValidation {
+  valid: boolean;
+  issues: ConfigIssue[];
+  env_files: string[];
+}
+
 export interface ApiKeyValidationResult {
   valid: boolean;
   message: string;
@@ -757,6 +1445,79 @@ export interface MetaConversation {
   message_count: number;
   is_archived: boolean;
   preview_text?: string;
+  title_locked?: boolean;  // Set by a manual rename; generated titles never replace it
+  summary?: string;        // Generated by summarize_conversation
+  forked_from?: string;       // Conversation this one was forked from
+  forked_at_message?: number; // 0-based position of the last copied message
+  workspace_id?: string;
+}
+
+export type MessageRating = 'up' | 'down';
+
+export interface MetaMessageRecord {
+  id?: number;
+  conversation_id: string;
+  message_index: number;
+  role: 'user' | 'assistant';
+  content: string;
+  image_data?: string;
+  tool_calls?: string;       // JSON array of tool blocks
+  timestamp: number;         // Unix timestamp in milliseconds
+  model?: string;            // Set on assistant messages
+  personality_hash?: string; // "default" without a commander personality
+  rating?: MessageRating;
+  rating_note?: string;
+  rated_at?: number;
+}
+
+export interface FeedbackGroup {
+  personality_hash?: string;
+  model?: string;
+  up: number;
+  down: number;
+}
+
+export interface FeedbackSummary {
+  days: number;
+  total_up: number;
+  total_down: number;
+  groups: FeedbackGroup[];
+}
+
+export interface RatedMessageContext {
+  message: MetaMessageRecord;
+  previous_user_message?: string;
+  tools_used: string[];
+}
+
+// Payload of meta-agent:compaction-performed
+export interface CompactionRecord {
+  id?: number;
+  conversation_id: string;
+  summary: string;
+  first_message_index: number;  // Replaced range, inclusive
+  last_message_index: number;
+  tokens_before: number;
+  tokens_after: number;
+  emergency: boolean;
+  preserved_message_indexes: number[];  // Kept word for word
+  created_at: number;           // Unix timestamp in milliseconds
+}
+
+export interface CompactionReview {
+  compaction: CompactionRecord;
+  replaced_messages: MetaMessageRecord[];
+}
+
+export interface WorkspaceInfo {
+  workspace_id: string;
+  name: string;
+  created_at: number;      // Unix timestamp in milliseconds
+  last_active_at: number;  // Unix timestamp in milliseconds
+  conversation_count: number;
+  run_count: number;
+  total_cost_usd: number;
+  active: boolean;
 }
 
 // ============================================================================
@@ -13,6 +13,7 @@ export type OutputType =
   | 'tool_use'
   | 'tool_result'
   | 'error'
+  | 'stderr'
   | 'result'
   | 'system'
   | 'stream_event'
@@ -38,6 +39,8 @@ export function getOutputTypeLabel(type: string): string {
       return 'Result';
     case 'error':
       return 'Error';
+    case 'stderr':
+      return 'Stderr';
     case 'result':
       return 'Completed';
     case 'system':
@@ -64,6 +67,7 @@ export function getOutputTypeLabel(type: string): string {
 export function getOutputTypeClass(type: string): string {
   switch (type) {
     case 'error':
+    case 'stderr':
       return 'output-error';
     case 'tool_use':
       return 'output-tool';
@@ -76,6 +76,7 @@ export function getAgentStatusColor(status: AgentStatus): string {
     case 'running':
       return 'var(--success)';
     case 'waitingforinput':
+    case 'ratelimited':
       return 'var(--warning)';
     case 'idle':
       return 'var(--text-secondary)';
@@ -130,6 +131,7 @@ export function getStatusColor(status: Status): string {
 
     // AgentStatus specific
     case 'waitingforinput':
+    case 'ratelimited':
       return 'var(--warning)';
     case 'idle':
       return 'var(--text-secondary)';
@@ -165,6 +167,7 @@ export function getStatusColorHex(status: Status): string {
       return '#9ca3af'; // gray-400 (slightly lighter than stopped)
     case 'waitingforinput':
     case 'waiting_input':
+    case 'ratelimited':
       return '#f59e0b'; // amber-500
     case 'processing':
       return '#f0705a'; // coral (accent)
@@ -204,6 +207,8 @@ export function getAgentStatusLabel(status: AgentStatus): string {
       return 'Running';
     case 'waitingforinput':
       return 'Needs Input';
+    case 'ratelimited':
+      return 'Rate Limited';
     case 'idle':
       return 'Idle';
     case 'processing':
@@ -255,6 +260,8 @@ export function getStatusLabel(status: Status): string {
     // AgentStatus specific
     case 'waitingforinput':
       return 'Needs Input';
+    case 'ratelimited':
+      return 'Rate Limited';
     case 'idle':
       return 'Idle';
     case 'processing':